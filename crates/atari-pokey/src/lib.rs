@@ -47,13 +47,18 @@
 
 #![allow(clippy::cast_precision_loss)]
 
+use emu_core::{BlipBuffer, Stems};
+
 // ---------------------------------------------------------------------------
 // Constants
 // ---------------------------------------------------------------------------
 
-/// Output sample rate (Hz).
+/// Default output sample rate (Hz).
 const SAMPLE_RATE: u32 = 48_000;
 
+/// DC-blocking high-pass cutoff (Hz).
+const DC_BLOCK_HZ: f32 = 37.0;
+
 /// Maximum pot counter value.
 const POT_MAX: u8 = 228;
 
@@ -244,24 +249,20 @@ pub struct Pokey {
     base_divider: u16,
 
     // -- Audio output --
-    /// Accumulator for downsampling.
-    accumulator: f32,
-
-    /// Number of CPU ticks accumulated.
-    sample_count: u32,
+    /// Band-limited output, clocked per CPU cycle.
+    blip: BlipBuffer,
 
-    /// CPU ticks per output sample (fractional).
-    ticks_per_sample: f32,
+    /// Channels left out of the mix (bit n = `CHANNELS[n]`).
+    channel_mute: u8,
 
-    /// Output sample buffer at 48 kHz.
-    buffer: Vec<f32>,
-
-    /// DC-blocking high-pass filter state.
-    hp_prev_in: f32,
-    hp_prev_out: f32,
+    /// Per-channel capture, while enabled.
+    stems: Option<Stems>,
 }
 
 impl Pokey {
+    /// Channel names, in tap and mute-mask order.
+    pub const CHANNELS: [&'static str; 4] = ["ch1", "ch2", "ch3", "ch4"];
+
     /// Create a new POKEY clocked at the given CPU frequency.
     ///
     /// For NTSC Atari systems, pass `1_789_772`. For PAL, pass `1_773_447`.
@@ -289,12 +290,9 @@ impl Pokey {
             poly17_table: build_poly_table(17, 16, 4),
             poly_counter: 0,
             base_divider: 0,
-            accumulator: 0.0,
-            sample_count: 0,
-            ticks_per_sample: cpu_freq as f32 / SAMPLE_RATE as f32,
-            buffer: Vec::with_capacity(SAMPLE_RATE as usize / 50 + 1),
-            hp_prev_in: 0.0,
-            hp_prev_out: 0.0,
+            blip: BlipBuffer::new(f64::from(cpu_freq), SAMPLE_RATE).with_high_pass(DC_BLOCK_HZ),
+            channel_mute: 0,
+            stems: None,
         }
     }
 
@@ -330,24 +328,11 @@ impl Pokey {
         // Tick channels.
         self.tick_channels(base_tick);
 
-        // Downsample to 48 kHz.
-        let sample = self.mix();
-        self.accumulator += sample;
-        self.sample_count += 1;
-
-        if self.sample_count as f32 >= self.ticks_per_sample {
-            let avg = self.accumulator / self.sample_count as f32;
-
-            // DC-blocking high-pass filter.
-            // y[n] = alpha * (y[n-1] + x[n] - x[n-1]), alpha ~= 0.9952 (~37 Hz at 48 kHz)
-            const ALPHA: f32 = 0.9952;
-            let filtered = ALPHA * (self.hp_prev_out + avg - self.hp_prev_in);
-            self.hp_prev_in = avg;
-            self.hp_prev_out = filtered;
-
-            self.buffer.push(filtered);
-            self.accumulator = 0.0;
-            self.sample_count = 0;
+        let taps = self.channel_outputs();
+        self.blip.set_level(self.mix(&taps));
+        self.blip.advance(1);
+        if let Some(stems) = &mut self.stems {
+            stems.record(&taps, 1);
         }
     }
 
@@ -468,16 +453,60 @@ impl Pokey {
         }
     }
 
-    /// Drain the audio output buffer. Returns mono f32 samples at 48 kHz,
-    /// in the range -1.0 to 1.0.
+    /// Drain the audio output buffer. Returns mono f32 samples at the
+    /// output rate, in the range -1.0 to 1.0.
     pub fn take_buffer(&mut self) -> Vec<f32> {
-        std::mem::take(&mut self.buffer)
+        self.blip.take_samples()
     }
 
     /// Number of samples currently in the audio buffer.
     #[must_use]
     pub fn buffer_len(&self) -> usize {
-        self.buffer.len()
+        self.blip.len()
+    }
+
+    /// Output sample rate.
+    #[must_use]
+    pub fn sample_rate(&self) -> u32 {
+        self.blip.sample_rate()
+    }
+
+    /// Change the output sample rate (48 kHz by default).
+    pub fn set_sample_rate(&mut self, rate: u32) {
+        self.blip.set_sample_rate(rate);
+        if let Some(stems) = &mut self.stems {
+            stems.set_sample_rate(rate);
+        }
+    }
+
+    /// Stretch the output rate for dynamic rate control.
+    pub fn set_rate_adjust(&mut self, ratio: f64) {
+        self.blip.set_rate_adjust(ratio);
+        if let Some(stems) = &mut self.stems {
+            stems.set_rate_adjust(ratio);
+        }
+    }
+
+    /// Mute channels in the mix: bit n silences `CHANNELS[n]`.
+    pub fn set_channel_mute(&mut self, mask: u8) {
+        self.channel_mute = mask & 0x0F;
+    }
+
+    /// Channels currently muted in the mix.
+    #[must_use]
+    pub fn channel_mute(&self) -> u8 {
+        self.channel_mute
+    }
+
+    /// Start or stop capturing each channel as its own stream.
+    pub fn set_stems(&mut self, enabled: bool) {
+        self.stems = enabled.then(|| Stems::new(&self.blip, &self.channel_outputs()));
+    }
+
+    /// Drain the per-channel streams, one per `CHANNELS` entry, at the
+    /// output rate. Empty while capture is off.
+    pub fn take_stems(&mut self) -> Vec<Vec<f32>> {
+        self.stems.as_mut().map_or_else(Vec::new, Stems::take)
     }
 
     /// Set a potentiometer target value (index 0-7, value 0-228).
@@ -656,10 +685,11 @@ impl Pokey {
         byte
     }
 
-    /// Mix all four channels into a single sample value.
-    fn mix(&self) -> f32 {
-        let mut total: u8 = 0;
-        for (i, ch) in self.channels.iter().enumerate() {
+    /// Each channel's contribution to the mix at this instant.
+    #[must_use]
+    pub fn channel_outputs(&self) -> [f32; 4] {
+        let mut taps = [0.0; 4];
+        for (i, (ch, tap)) in self.channels.iter().zip(&mut taps).enumerate() {
             let output = if ch.volume_only() {
                 // Volume-only mode: output = volume value directly.
                 ch.volume()
@@ -689,12 +719,20 @@ impl Pokey {
 
                 if hp_active { ch.volume() } else { 0 }
             };
-            total = total.saturating_add(output);
+            // Max possible = 60 (4 channels x 15). Normalise to 0.0..1.0.
+            // The DC-blocking filter will centre around zero.
+            *tap = f32::from(output) / 60.0;
         }
+        taps
+    }
 
-        // Max possible = 60 (4 channels x 15). Normalise to 0.0..1.0.
-        // The DC-blocking filter will centre around zero.
-        f32::from(total) / 60.0
+    /// Sum of the unmuted channels.
+    fn mix(&self, taps: &[f32; 4]) -> f32 {
+        taps.iter()
+            .enumerate()
+            .filter(|&(ch, _)| self.channel_mute & (1 << ch) == 0)
+            .map(|(_, &tap)| tap)
+            .sum()
     }
 
     /// Determine whether the polynomial counter gate is active for the
@@ -950,7 +988,7 @@ mod tests {
         // In volume-only mode, the channel outputs the volume value directly,
         // independent of frequency counter or poly counters.
         // Mix should produce a non-zero sample.
-        let sample = pokey.mix();
+        let sample = pokey.mix(&pokey.channel_outputs());
         assert!(
             sample > 0.0,
             "Volume-only mode should produce non-zero output, got {sample}"
@@ -958,7 +996,7 @@ mod tests {
 
         // Set volume to 0 — output should be 0.
         pokey.write(0x01, 0x10); // Volume-only, vol=0
-        let sample = pokey.mix();
+        let sample = pokey.mix(&pokey.channel_outputs());
         assert!(
             (sample - 0.0).abs() < f32::EPSILON,
             "Volume-only with vol=0 should produce zero output"
@@ -1058,4 +1096,25 @@ mod tests {
         let pokey = Pokey::default();
         assert_eq!(pokey.cpu_freq, 1_789_772);
     }
+
+    #[test]
+    fn muted_channel_keeps_its_stem() {
+        let mut pokey = ntsc_pokey();
+        pokey.write(0x00, 40); // AUDF1
+        pokey.write(0x01, 0xAF); // AUDC1: pure tone, volume 15
+        pokey.set_channel_mute(0b0001);
+        pokey.set_stems(true);
+        for _ in 0..(1_789_772 / 10) {
+            pokey.tick();
+        }
+
+        let mix = pokey.take_buffer();
+        let stems = pokey.take_stems();
+        assert_eq!(stems.len(), Pokey::CHANNELS.len());
+        assert!(stems.iter().all(|stem| stem.len() == mix.len()));
+        let peak = |buf: &[f32]| buf.iter().fold(0.0f32, |m, s| m.max(s.abs()));
+        assert!(peak(&mix) < 1e-6, "muted channel reached the mix");
+        assert!(peak(&stems[0]) > 0.1, "stem should carry channel 1");
+        assert!(peak(&stems[3]) < 1e-6);
+    }
 }
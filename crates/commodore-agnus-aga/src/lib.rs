@@ -4,6 +4,7 @@
 //! existing ECS/OCS bus arbitration. This crate follows the same Deref
 //! composition pattern as `commodore-agnus-ecs`.
 
+use emu_core::{SaveState, StateError, StateReader, StateWriter};
 use std::ops::{Deref, DerefMut};
 
 pub use commodore_agnus_ecs::AgnusEcs as InnerAgnusEcs;
@@ -154,6 +155,16 @@ impl AgnusAga {
     }
 }
 
+impl SaveState for AgnusAga {
+    fn save_state(&self, w: &mut StateWriter) {
+        self.inner.save_state(w);
+    }
+
+    fn load_state(&mut self, r: &mut StateReader<'_>) -> Result<(), StateError> {
+        self.inner.load_state(r)
+    }
+}
+
 impl Default for AgnusAga {
     fn default() -> Self {
         Self::new()
@@ -4,6 +4,7 @@
 //! Agnus implementation. It preserves current behavior while giving us a place
 //! to add ECS-specific DMA/register/timing deltas incrementally.
 
+use emu_core::{SaveState, StateError, StateReader, StateWriter};
 use std::ops::{Deref, DerefMut};
 
 pub use commodore_agnus_ocs::Agnus as InnerAgnusOcs;
@@ -580,6 +581,43 @@ impl AgnusEcs {
     }
 }
 
+impl SaveState for AgnusEcs {
+    fn save_state(&self, w: &mut StateWriter) {
+        self.inner.save_state(w);
+        w.write_u16(self.beamcon0);
+        w.write_u16(self.htotal);
+        w.write_u16(self.hsstop);
+        w.write_u16(self.vtotal);
+        w.write_u16(self.vsstop);
+        w.write_u16(self.hbstrt);
+        w.write_u16(self.hbstop);
+        w.write_u16(self.vbstrt);
+        w.write_u16(self.vbstop);
+        w.write_u16(self.hsstrt);
+        w.write_u16(self.vsstrt);
+        w.write_u16(self.diwhigh);
+        w.write_bool(self.diwhigh_written);
+    }
+
+    fn load_state(&mut self, r: &mut StateReader<'_>) -> Result<(), StateError> {
+        self.inner.load_state(r)?;
+        self.beamcon0 = r.read_u16()?;
+        self.htotal = r.read_u16()?;
+        self.hsstop = r.read_u16()?;
+        self.vtotal = r.read_u16()?;
+        self.vsstop = r.read_u16()?;
+        self.hbstrt = r.read_u16()?;
+        self.hbstop = r.read_u16()?;
+        self.vbstrt = r.read_u16()?;
+        self.vbstop = r.read_u16()?;
+        self.hsstrt = r.read_u16()?;
+        self.vsstrt = r.read_u16()?;
+        self.diwhigh = r.read_u16()?;
+        self.diwhigh_written = r.read_bool()?;
+        Ok(())
+    }
+}
+
 impl Default for AgnusEcs {
     fn default() -> Self {
         Self::new()
@@ -1,5 +1,7 @@
 //! Agnus - Beam counter and DMA slot allocation.
 
+use emu_core::{SaveState, StateError, StateReader, StateWriter};
+
 pub const PAL_CCKS_PER_LINE: u16 = 227;
 pub const PAL_LINES_PER_FRAME: u16 = 312;
 /// Same as PAL — both use 227 CCKs per line.
@@ -128,7 +130,7 @@ pub enum BlitterDmaOp {
 /// servicing for the current word. Replaces the pre-built VecDeque queue so
 /// that individual channel accesses can be granted in any order (with the
 /// constraint that WriteD must wait until all reads are done).
-#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq)]
 struct BlitterWordState {
     need_a: bool,
     need_b: bool,
@@ -141,6 +143,29 @@ struct BlitterWordState {
     internal_done: bool,
 }
 
+impl SaveState for BlitterWordState {
+    fn save_state(&self, w: &mut StateWriter) {
+        w.write_bool(self.need_a);
+        w.write_bool(self.need_b);
+        w.write_bool(self.need_c);
+        w.write_bool(self.need_d);
+        w.write_bool(self.reads_done);
+        w.write_bool(self.internal_only);
+        w.write_bool(self.internal_done);
+    }
+
+    fn load_state(&mut self, r: &mut StateReader<'_>) -> Result<(), StateError> {
+        self.need_a = r.read_bool()?;
+        self.need_b = r.read_bool()?;
+        self.need_c = r.read_bool()?;
+        self.need_d = r.read_bool()?;
+        self.reads_done = r.read_bool()?;
+        self.internal_only = r.read_bool()?;
+        self.internal_done = r.read_bool()?;
+        Ok(())
+    }
+}
+
 impl BlitterWordState {
     fn new_area(use_a: bool, use_b: bool, use_c: bool, use_d: bool) -> Self {
         let internal_only = !use_a && !use_b && !use_c && !use_d;
@@ -176,7 +201,7 @@ impl BlitterWordState {
     }
 }
 
-#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq)]
 struct BlitterLineRuntime {
     steps_remaining: u32,
     error: i16,
@@ -197,7 +222,50 @@ struct BlitterLineRuntime {
     have_c_word: bool,
 }
 
-#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+impl SaveState for BlitterLineRuntime {
+    fn save_state(&self, w: &mut StateWriter) {
+        w.write_u32(self.steps_remaining);
+        w.write_i16(self.error);
+        w.write_i16(self.error_add);
+        w.write_i16(self.error_sub);
+        w.write_u32(self.cpt);
+        w.write_u32(self.dpt);
+        w.write_u16(self.pixel_bit);
+        w.write_i16(self.row_mod);
+        w.write_u16(self.texture);
+        w.write_u8(self.lf);
+        w.write_bool(self.sing);
+        w.write_bool(self.texture_enabled);
+        w.write_bool(self.major_is_y);
+        w.write_bool(self.x_neg);
+        w.write_bool(self.y_neg);
+        w.write_u16(self.last_c_word);
+        w.write_bool(self.have_c_word);
+    }
+
+    fn load_state(&mut self, r: &mut StateReader<'_>) -> Result<(), StateError> {
+        self.steps_remaining = r.read_u32()?;
+        self.error = r.read_i16()?;
+        self.error_add = r.read_i16()?;
+        self.error_sub = r.read_i16()?;
+        self.cpt = r.read_u32()?;
+        self.dpt = r.read_u32()?;
+        self.pixel_bit = r.read_u16()?;
+        self.row_mod = r.read_i16()?;
+        self.texture = r.read_u16()?;
+        self.lf = r.read_u8()?;
+        self.sing = r.read_bool()?;
+        self.texture_enabled = r.read_bool()?;
+        self.major_is_y = r.read_bool()?;
+        self.x_neg = r.read_bool()?;
+        self.y_neg = r.read_bool()?;
+        self.last_c_word = r.read_u16()?;
+        self.have_c_word = r.read_bool()?;
+        Ok(())
+    }
+}
+
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq)]
 struct BlitterAreaRuntime {
     rows_remaining: u32,
     width_words: u32,
@@ -232,6 +300,77 @@ struct BlitterAreaRuntime {
     c_val: u16,
 }
 
+impl SaveState for BlitterAreaRuntime {
+    fn save_state(&self, w: &mut StateWriter) {
+        w.write_u32(self.rows_remaining);
+        w.write_u32(self.width_words);
+        w.write_u32(self.words_remaining_in_row);
+        w.write_bool(self.use_a);
+        w.write_bool(self.use_b);
+        w.write_bool(self.use_c);
+        w.write_bool(self.use_d);
+        w.write_u8(self.lf);
+        w.write_u16(self.a_shift);
+        w.write_u16(self.b_shift);
+        w.write_bool(self.desc);
+        w.write_i32(self.ptr_step);
+        w.write_i32(self.mod_dir);
+        w.write_bool(self.fill_enabled);
+        w.write_bool(self.ife);
+        w.write_bool(self.efe);
+        w.write_u16(self.fill_carry_init);
+        w.write_u16(self.fill_carry);
+        w.write_u32(self.apt);
+        w.write_u32(self.bpt);
+        w.write_u32(self.cpt);
+        w.write_u32(self.dpt);
+        w.write_i16(self.amod);
+        w.write_i16(self.bmod);
+        w.write_i16(self.cmod);
+        w.write_i16(self.dmod);
+        w.write_u16(self.a_prev);
+        w.write_u16(self.b_prev);
+        w.write_u16(self.a_raw);
+        w.write_u16(self.b_raw);
+        w.write_u16(self.c_val);
+    }
+
+    fn load_state(&mut self, r: &mut StateReader<'_>) -> Result<(), StateError> {
+        self.rows_remaining = r.read_u32()?;
+        self.width_words = r.read_u32()?;
+        self.words_remaining_in_row = r.read_u32()?;
+        self.use_a = r.read_bool()?;
+        self.use_b = r.read_bool()?;
+        self.use_c = r.read_bool()?;
+        self.use_d = r.read_bool()?;
+        self.lf = r.read_u8()?;
+        self.a_shift = r.read_u16()?;
+        self.b_shift = r.read_u16()?;
+        self.desc = r.read_bool()?;
+        self.ptr_step = r.read_i32()?;
+        self.mod_dir = r.read_i32()?;
+        self.fill_enabled = r.read_bool()?;
+        self.ife = r.read_bool()?;
+        self.efe = r.read_bool()?;
+        self.fill_carry_init = r.read_u16()?;
+        self.fill_carry = r.read_u16()?;
+        self.apt = r.read_u32()?;
+        self.bpt = r.read_u32()?;
+        self.cpt = r.read_u32()?;
+        self.dpt = r.read_u32()?;
+        self.amod = r.read_i16()?;
+        self.bmod = r.read_i16()?;
+        self.cmod = r.read_i16()?;
+        self.dmod = r.read_i16()?;
+        self.a_prev = r.read_u16()?;
+        self.b_prev = r.read_u16()?;
+        self.a_raw = r.read_u16()?;
+        self.b_raw = r.read_u16()?;
+        self.c_val = r.read_u16()?;
+        Ok(())
+    }
+}
+
 #[derive(Clone)]
 pub struct Agnus {
     pub vpos: u16,
@@ -1170,6 +1309,103 @@ impl Agnus {
     }
 }
 
+/// Region timing (`lines_per_frame`) and the chipset's bitplane limit are
+/// configuration: a state taken on the other video standard is rejected.
+impl SaveState for Agnus {
+    fn save_state(&self, w: &mut StateWriter) {
+        w.write_u16(self.lines_per_frame);
+        w.write_u16(self.vpos);
+        w.write_u16(self.hpos);
+        w.write_u16(self.dmacon);
+        w.write_u16(self.bplcon0);
+        w.write_u32_slice(&self.bpl_pt);
+        w.write_u16(self.ddfstrt);
+        w.write_u16(self.ddfstop);
+        w.write_u16(self.bltcon0);
+        w.write_u16(self.bltcon1);
+        w.write_u16(self.bltsize);
+        w.write_u16(self.bltsizv_ecs);
+        w.write_u16(self.bltsizh_ecs);
+        w.write_bool(self.blitter_busy);
+        w.write_bool(self.blitter_exec_pending);
+        w.write_u32(self.blitter_ccks_remaining);
+        w.write_option(self.blitter_word_state.as_ref());
+        w.write_option(self.blitter_line_runtime.as_ref());
+        w.write_option(self.blitter_area_runtime.as_ref());
+        w.write_u32(self.blt_apt);
+        w.write_u32(self.blt_bpt);
+        w.write_u32(self.blt_cpt);
+        w.write_u32(self.blt_dpt);
+        w.write_i16(self.blt_amod);
+        w.write_i16(self.blt_bmod);
+        w.write_i16(self.blt_cmod);
+        w.write_i16(self.blt_dmod);
+        w.write_u16(self.blt_adat);
+        w.write_u16(self.blt_bdat);
+        w.write_u16(self.blt_cdat);
+        w.write_u16(self.blt_afwm);
+        w.write_u16(self.blt_alwm);
+        w.write_u16(self.diwstrt);
+        w.write_u16(self.diwstop);
+        w.write_i16(self.bpl1mod);
+        w.write_i16(self.bpl2mod);
+        w.write_u32_slice(&self.spr_pt);
+        w.write_u16_slice(&self.spr_pt_hi_latch);
+        w.write_bool_slice(&self.spr_pt_hi_pending);
+        w.write_u32(self.dsk_pt);
+        w.write_u16(self.fmode);
+        w.write_bool(self.lof);
+    }
+
+    fn load_state(&mut self, r: &mut StateReader<'_>) -> Result<(), StateError> {
+        if r.read_u16()? != self.lines_per_frame {
+            return Err(StateError::Invalid("Agnus timing mismatch".to_string()));
+        }
+        self.vpos = r.read_u16()?;
+        self.hpos = r.read_u16()?;
+        self.dmacon = r.read_u16()?;
+        self.bplcon0 = r.read_u16()?;
+        r.read_u32_into(&mut self.bpl_pt)?;
+        self.ddfstrt = r.read_u16()?;
+        self.ddfstop = r.read_u16()?;
+        self.bltcon0 = r.read_u16()?;
+        self.bltcon1 = r.read_u16()?;
+        self.bltsize = r.read_u16()?;
+        self.bltsizv_ecs = r.read_u16()?;
+        self.bltsizh_ecs = r.read_u16()?;
+        self.blitter_busy = r.read_bool()?;
+        self.blitter_exec_pending = r.read_bool()?;
+        self.blitter_ccks_remaining = r.read_u32()?;
+        self.blitter_word_state = r.read_option_value()?;
+        self.blitter_line_runtime = r.read_option_value()?;
+        self.blitter_area_runtime = r.read_option_value()?;
+        self.blt_apt = r.read_u32()?;
+        self.blt_bpt = r.read_u32()?;
+        self.blt_cpt = r.read_u32()?;
+        self.blt_dpt = r.read_u32()?;
+        self.blt_amod = r.read_i16()?;
+        self.blt_bmod = r.read_i16()?;
+        self.blt_cmod = r.read_i16()?;
+        self.blt_dmod = r.read_i16()?;
+        self.blt_adat = r.read_u16()?;
+        self.blt_bdat = r.read_u16()?;
+        self.blt_cdat = r.read_u16()?;
+        self.blt_afwm = r.read_u16()?;
+        self.blt_alwm = r.read_u16()?;
+        self.diwstrt = r.read_u16()?;
+        self.diwstop = r.read_u16()?;
+        self.bpl1mod = r.read_i16()?;
+        self.bpl2mod = r.read_i16()?;
+        r.read_u32_into(&mut self.spr_pt)?;
+        r.read_u16_into(&mut self.spr_pt_hi_latch)?;
+        r.read_bool_into(&mut self.spr_pt_hi_pending)?;
+        self.dsk_pt = r.read_u32()?;
+        self.fmode = r.read_u16()?;
+        self.lof = r.read_bool()?;
+        Ok(())
+    }
+}
+
 impl Default for Agnus {
     fn default() -> Self {
         Self::new()
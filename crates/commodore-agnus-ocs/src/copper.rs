@@ -1,5 +1,7 @@
 //! Copper - Coprocessor for synchronized register updates.
 
+use emu_core::{SaveState, StateError, StateReader, StateWriter};
+
 #[derive(Debug, Clone, Copy, PartialEq, Eq)]
 pub enum State {
     Idle,
@@ -134,6 +136,37 @@ impl Copper {
     }
 }
 
+impl SaveState for Copper {
+    fn save_state(&self, w: &mut StateWriter) {
+        w.write_u8(self.state as u8);
+        w.write_u32(self.cop1lc);
+        w.write_u32(self.cop2lc);
+        w.write_u32(self.pc);
+        w.write_u16(self.ir1);
+        w.write_u16(self.ir2);
+        w.write_bool(self.waiting);
+        w.write_bool(self.danger);
+    }
+
+    fn load_state(&mut self, r: &mut StateReader<'_>) -> Result<(), StateError> {
+        self.state = match r.read_u8()? {
+            0 => State::Idle,
+            1 => State::Fetch1,
+            2 => State::Fetch2,
+            3 => State::Wait,
+            n => return Err(StateError::Invalid(format!("copper state {n}"))),
+        };
+        self.cop1lc = r.read_u32()?;
+        self.cop2lc = r.read_u32()?;
+        self.pc = r.read_u32()?;
+        self.ir1 = r.read_u16()?;
+        self.ir2 = r.read_u16()?;
+        self.waiting = r.read_bool()?;
+        self.danger = r.read_bool()?;
+        Ok(())
+    }
+}
+
 impl Default for Copper {
     fn default() -> Self {
         Self::new()
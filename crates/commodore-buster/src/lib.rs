@@ -6,6 +6,8 @@
 //! the OS reads the descriptor, writes a base address, and the next
 //! board becomes visible.
 
+use emu_core::{SaveState, StateError, StateReader, StateWriter};
+
 // ---------------------------------------------------------------------------
 // Autoconfig register offsets (nybble-packed, even byte addresses)
 // ---------------------------------------------------------------------------
@@ -425,6 +427,40 @@ impl Buster {
     }
 }
 
+/// Board contents and configured base addresses. The set of boards in the
+/// slots is configuration and must match the machine being restored.
+impl SaveState for Buster {
+    fn save_state(&self, w: &mut StateWriter) {
+        w.write_usize(self.slots.len());
+        for slot in &self.slots {
+            match slot {
+                ZorroIISlot::Ram(board) => {
+                    w.write_bytes(&board.ram);
+                    w.write_bool(board.base_addr.is_some());
+                    w.write_u32(board.base_addr.unwrap_or(0));
+                }
+            }
+        }
+        w.write_usize(self.current_autoconfig);
+    }
+
+    fn load_state(&mut self, r: &mut StateReader<'_>) -> Result<(), StateError> {
+        if r.read_usize()? != self.slots.len() {
+            return Err(StateError::Invalid("Zorro II boards do not match".into()));
+        }
+        for slot in &mut self.slots {
+            match slot {
+                ZorroIISlot::Ram(board) => {
+                    r.read_bytes_into(&mut board.ram)?;
+                    board.base_addr = r.read_bool()?.then_some(r.read_u32()?);
+                }
+            }
+        }
+        self.current_autoconfig = r.read_usize()?;
+        Ok(())
+    }
+}
+
 impl Default for Buster {
     fn default() -> Self {
         Self::new()
@@ -5,6 +5,7 @@
 //! All state lives in the inner OCS Denise; this crate provides the methods
 //! that interpret that state in AGA mode.
 
+use emu_core::{SaveState, StateError, StateReader, StateWriter};
 use std::ops::{Deref, DerefMut};
 
 pub use commodore_denise_ecs::DeniseEcs as InnerDeniseEcs;
@@ -219,6 +220,16 @@ impl DeniseAga {
     }
 }
 
+impl SaveState for DeniseAga {
+    fn save_state(&self, w: &mut StateWriter) {
+        self.inner.save_state(w);
+    }
+
+    fn load_state(&mut self, r: &mut StateReader<'_>) -> Result<(), StateError> {
+        self.inner.load_state(r)
+    }
+}
+
 impl Default for DeniseAga {
     fn default() -> Self {
         Self::new()
@@ -4,6 +4,7 @@
 //! ECS-specific Denise behavior (e.g. ECS display-mode extensions) can be
 //! layered in here while preserving the current OCS rendering baseline.
 
+use emu_core::{SaveState, StateError, StateReader, StateWriter};
 use std::ops::{Deref, DerefMut};
 
 pub use commodore_denise_ocs::DeniseOcs as InnerDeniseOcs;
@@ -132,6 +133,19 @@ impl DeniseEcs {
     }
 }
 
+impl SaveState for DeniseEcs {
+    fn save_state(&self, w: &mut StateWriter) {
+        self.inner.save_state(w);
+        w.write_u16(self.bplcon3);
+    }
+
+    fn load_state(&mut self, r: &mut StateReader<'_>) -> Result<(), StateError> {
+        self.inner.load_state(r)?;
+        self.bplcon3 = r.read_u16()?;
+        Ok(())
+    }
+}
+
 impl Default for DeniseEcs {
     fn default() -> Self {
         Self::new()
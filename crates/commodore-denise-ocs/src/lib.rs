@@ -3,6 +3,8 @@
 //! Denise receives bitplane data from Agnus DMA and shifts it out pixel by
 //! pixel, combining with the colour palette to produce the final framebuffer.
 
+use emu_core::{SaveState, StateError, StateReader, StateWriter};
+
 /// Raster framebuffer width: 227 CCKs × 8 superhires pixels.
 pub const RASTER_FB_WIDTH: u32 = 1816;
 /// PAL raster framebuffer height: 312 lines x 2 (interlace double-height).
@@ -1418,6 +1420,119 @@ pub fn pixel_aspect_ratio(pal: bool, hires: bool, interlaced: bool) -> f64 {
     base * h_factor * v_factor
 }
 
+/// The raster size and bitplane limit are configuration, and the shift-load
+/// debug snapshot is diagnostic only; neither is saved.
+impl SaveState for DeniseOcs {
+    fn save_state(&self, w: &mut StateWriter) {
+        w.write_u16_slice(&self.palette);
+        w.write_u32_slice(&self.palette_24);
+        w.write_u32_slice(&self.framebuffer_raster);
+        w.write_bool(self.interlace_active);
+        w.write_bool(self.lof);
+        w.write_u16_slice(&self.bpl_data);
+        w.write_u16_slice(&self.bpl_shift);
+        w.write_u8(self.shift_count);
+        w.write_bytes(&self.bpl_shift_count);
+        w.write_bytes(&self.bpl_shift_delay);
+        w.write_u16_slice(&self.bpl_prev_data);
+        w.write_u16_slice(&self.bpl_pending_data);
+        w.write_bool(self.bpl_pending_copy_odd_planes);
+        w.write_bool(self.bpl_pending_copy_even_planes);
+        w.write_bool(self.bpl_scroll_pending_line);
+        w.write_u16(self.bplcon0);
+        w.write_u16(self.bplcon1);
+        w.write_u16(self.bplcon2);
+        w.write_u16(self.bplcon4);
+        w.write_u16(self.clxcon);
+        w.write_u16(self.clxdat);
+        w.write_u16_slice(&self.spr_pos);
+        w.write_u16_slice(&self.spr_pos_display);
+        w.write_bool_slice(&self.spr_pos_dirty);
+        w.write_u16_slice(&self.spr_ctl);
+        for v in &self.spr_data {
+            w.write_u64(*v);
+        }
+        for v in &self.spr_datb {
+            w.write_u64(*v);
+        }
+        w.write_bool_slice(&self.spr_armed);
+        for v in &self.spr_shift_data {
+            w.write_u64(*v);
+        }
+        for v in &self.spr_shift_datb {
+            w.write_u64(*v);
+        }
+        w.write_bytes(&self.spr_shift_count);
+        w.write_u8(self.spr_width);
+        w.write_bytes(&self.spr_current_code);
+        w.write_bool(self.sprite_runtime_line_valid);
+        w.write_u32(self.sprite_runtime_beam_x);
+        w.write_u32(self.sprite_runtime_beam_y);
+        w.write_bool(self.deferred_shift_load_after_source_pixels.is_some());
+        w.write_u8(self.deferred_shift_load_after_source_pixels.unwrap_or(0));
+        w.write_u16(self.ham_prev_rgb);
+        w.write_u32(self.ham_prev_rgb24);
+        for fifo in &self.bpl_fifo {
+            w.write_u16_slice(fifo);
+        }
+        w.write_bytes(&self.bpl_fifo_len);
+    }
+
+    fn load_state(&mut self, r: &mut StateReader<'_>) -> Result<(), StateError> {
+        r.read_u16_into(&mut self.palette)?;
+        r.read_u32_into(&mut self.palette_24)?;
+        r.read_u32_into(&mut self.framebuffer_raster)?;
+        self.interlace_active = r.read_bool()?;
+        self.lof = r.read_bool()?;
+        r.read_u16_into(&mut self.bpl_data)?;
+        r.read_u16_into(&mut self.bpl_shift)?;
+        self.shift_count = r.read_u8()?;
+        r.read_bytes_into(&mut self.bpl_shift_count)?;
+        r.read_bytes_into(&mut self.bpl_shift_delay)?;
+        r.read_u16_into(&mut self.bpl_prev_data)?;
+        r.read_u16_into(&mut self.bpl_pending_data)?;
+        self.bpl_pending_copy_odd_planes = r.read_bool()?;
+        self.bpl_pending_copy_even_planes = r.read_bool()?;
+        self.bpl_scroll_pending_line = r.read_bool()?;
+        self.bplcon0 = r.read_u16()?;
+        self.bplcon1 = r.read_u16()?;
+        self.bplcon2 = r.read_u16()?;
+        self.bplcon4 = r.read_u16()?;
+        self.clxcon = r.read_u16()?;
+        self.clxdat = r.read_u16()?;
+        r.read_u16_into(&mut self.spr_pos)?;
+        r.read_u16_into(&mut self.spr_pos_display)?;
+        r.read_bool_into(&mut self.spr_pos_dirty)?;
+        r.read_u16_into(&mut self.spr_ctl)?;
+        for v in &mut self.spr_data {
+            *v = r.read_u64()?;
+        }
+        for v in &mut self.spr_datb {
+            *v = r.read_u64()?;
+        }
+        r.read_bool_into(&mut self.spr_armed)?;
+        for v in &mut self.spr_shift_data {
+            *v = r.read_u64()?;
+        }
+        for v in &mut self.spr_shift_datb {
+            *v = r.read_u64()?;
+        }
+        r.read_bytes_into(&mut self.spr_shift_count)?;
+        self.spr_width = r.read_u8()?;
+        r.read_bytes_into(&mut self.spr_current_code)?;
+        self.sprite_runtime_line_valid = r.read_bool()?;
+        self.sprite_runtime_beam_x = r.read_u32()?;
+        self.sprite_runtime_beam_y = r.read_u32()?;
+        self.deferred_shift_load_after_source_pixels = r.read_bool()?.then_some(r.read_u8()?);
+        self.ham_prev_rgb = r.read_u16()?;
+        self.ham_prev_rgb24 = r.read_u32()?;
+        for fifo in &mut self.bpl_fifo {
+            r.read_u16_into(fifo)?;
+        }
+        r.read_bytes_into(&mut self.bpl_fifo_len)
+    }
+}
+
 impl Default for DeniseOcs {
     fn default() -> Self {
         Self::new()
@@ -10,6 +10,8 @@
 //! The machine-level bus wrapper transfers data between the DMAC buffer
 //! and system memory using the ACR/WTC registers.
 
+use emu_core::{SaveState, StateError, StateReader, StateWriter};
+
 // ---------------------------------------------------------------------------
 // WD33C93 registers (indirect access via SASR/SCMD)
 // ---------------------------------------------------------------------------
@@ -215,6 +217,25 @@ impl ScsiTarget {
     }
 }
 
+/// The disk image is written back into the state; the sector count follows
+/// from its size.
+impl SaveState for ScsiTarget {
+    fn save_state(&self, w: &mut StateWriter) {
+        w.write_bytes(&self.disk_image);
+        w.write_u8(self.sense_key);
+        w.write_u8(self.sense_asc);
+        w.write_u8(self.sense_ascq);
+    }
+
+    fn load_state(&mut self, r: &mut StateReader<'_>) -> Result<(), StateError> {
+        r.read_bytes_into(&mut self.disk_image)?;
+        self.sense_key = r.read_u8()?;
+        self.sense_asc = r.read_u8()?;
+        self.sense_ascq = r.read_u8()?;
+        Ok(())
+    }
+}
+
 // ---------------------------------------------------------------------------
 // WD33C93 state
 // ---------------------------------------------------------------------------
@@ -670,6 +691,39 @@ impl Wd33c93 {
     }
 }
 
+/// Target presence is configuration. The debug trace flag is not saved.
+impl SaveState for Wd33c93 {
+    fn save_state(&self, w: &mut StateWriter) {
+        w.write_u8(self.selected_reg);
+        w.write_bytes(&self.regs);
+        w.write_u8(self.asr);
+        for target in &self.targets {
+            w.write_option(target.as_ref());
+        }
+        w.write_bytes(&self.dma_buffer);
+        w.write_usize(self.dma_read_pos);
+        w.write_usize(self.dma_write_pos);
+        w.write_bool(self.dma_pending);
+        w.write_bool(self.dma_direction_read);
+        w.write_bytes(&self.cdb);
+    }
+
+    fn load_state(&mut self, r: &mut StateReader<'_>) -> Result<(), StateError> {
+        self.selected_reg = r.read_u8()?;
+        r.read_bytes_into(&mut self.regs)?;
+        self.asr = r.read_u8()?;
+        for target in &mut self.targets {
+            r.read_option(target.as_mut(), "a SCSI target")?;
+        }
+        self.dma_buffer = r.read_bytes()?.to_vec();
+        self.dma_read_pos = r.read_usize()?;
+        self.dma_write_pos = r.read_usize()?;
+        self.dma_pending = r.read_bool()?;
+        self.dma_direction_read = r.read_bool()?;
+        r.read_bytes_into(&mut self.cdb)
+    }
+}
+
 // ---------------------------------------------------------------------------
 // SDMAC 390537
 // ---------------------------------------------------------------------------
@@ -994,6 +1048,29 @@ impl Dmac390537 {
     }
 }
 
+impl SaveState for Dmac390537 {
+    fn save_state(&self, w: &mut StateWriter) {
+        self.wd.save_state(w);
+        w.write_u8(self.cntr);
+        w.write_u8(self.dawr);
+        w.write_u32(self.wtc);
+        w.write_u32(self.acr);
+        w.write_u8(self.istr_latched);
+        w.write_bool(self.dma_active);
+    }
+
+    fn load_state(&mut self, r: &mut StateReader<'_>) -> Result<(), StateError> {
+        self.wd.load_state(r)?;
+        self.cntr = r.read_u8()?;
+        self.dawr = r.read_u8()?;
+        self.wtc = r.read_u32()?;
+        self.acr = r.read_u32()?;
+        self.istr_latched = r.read_u8()?;
+        self.dma_active = r.read_bool()?;
+        Ok(())
+    }
+}
+
 impl Default for Dmac390537 {
     fn default() -> Self {
         Self::new()
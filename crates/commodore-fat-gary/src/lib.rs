@@ -5,6 +5,8 @@
 //! registers at `$DE0000`, and timeout/bus-error generation for accesses
 //! to unmapped address ranges.
 
+use emu_core::{SaveState, StateError, StateReader, StateWriter};
+
 // ---------------------------------------------------------------------------
 // TOENB register bit-fields
 // ---------------------------------------------------------------------------
@@ -238,6 +240,19 @@ impl FatGary {
     }
 }
 
+impl SaveState for FatGary {
+    fn save_state(&self, w: &mut StateWriter) {
+        w.write_u8(self.toenb);
+        w.write_u8(self.timeout);
+    }
+
+    fn load_state(&mut self, r: &mut StateReader<'_>) -> Result<(), StateError> {
+        self.toenb = r.read_u8()?;
+        self.timeout = r.read_u8()?;
+        Ok(())
+    }
+}
+
 impl Default for FatGary {
     fn default() -> Self {
         Self::new()
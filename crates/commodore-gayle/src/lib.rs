@@ -12,6 +12,8 @@
 
 pub mod ne2000;
 
+use emu_core::{SaveState, StateError, StateReader, StateWriter};
+
 // ---------------------------------------------------------------------------
 // IDE ATA constants
 // ---------------------------------------------------------------------------
@@ -553,6 +555,61 @@ impl IdeDrive {
     }
 }
 
+/// The disk image is written back into the state; its geometry is configuration.
+impl SaveState for IdeDrive {
+    fn save_state(&self, w: &mut StateWriter) {
+        w.write_u8(self.error);
+        w.write_u8(self.sector_count);
+        w.write_u8(self.sector_number);
+        w.write_u8(self.cylinder_lo);
+        w.write_u8(self.cylinder_hi);
+        w.write_u8(self.dev_head);
+        w.write_u8(self.status);
+        w.write_u8(self.state as u8);
+        w.write_bytes(&self.data_buffer);
+        w.write_usize(self.data_pos);
+        w.write_usize(self.data_len);
+        w.write_u16(self.sectors_remaining);
+        w.write_u16(self.sectors_in_block);
+        w.write_u16(self.sectors_per_irq);
+        w.write_u8(self.multiple_count);
+        w.write_u8(self.logical_heads);
+        w.write_u8(self.logical_spt);
+        w.write_bytes(&self.disk_image);
+        w.write_bool(self.irq_pending);
+        w.write_bool(self.nien);
+    }
+
+    fn load_state(&mut self, r: &mut StateReader<'_>) -> Result<(), StateError> {
+        self.error = r.read_u8()?;
+        self.sector_count = r.read_u8()?;
+        self.sector_number = r.read_u8()?;
+        self.cylinder_lo = r.read_u8()?;
+        self.cylinder_hi = r.read_u8()?;
+        self.dev_head = r.read_u8()?;
+        self.status = r.read_u8()?;
+        self.state = match r.read_u8()? {
+            0 => IdeState::Idle,
+            1 => IdeState::DataIn,
+            2 => IdeState::DataOut,
+            n => return Err(StateError::Invalid(format!("IDE state {n}"))),
+        };
+        self.data_buffer = r.read_bytes()?.to_vec();
+        self.data_pos = r.read_usize()?;
+        self.data_len = r.read_usize()?;
+        self.sectors_remaining = r.read_u16()?;
+        self.sectors_in_block = r.read_u16()?;
+        self.sectors_per_irq = r.read_u16()?;
+        self.multiple_count = r.read_u8()?;
+        self.logical_heads = r.read_u8()?;
+        self.logical_spt = r.read_u8()?;
+        r.read_bytes_into(&mut self.disk_image)?;
+        self.irq_pending = r.read_bool()?;
+        self.nien = r.read_bool()?;
+        Ok(())
+    }
+}
+
 /// Set a 16-bit word in an IDENTIFY buffer at the given word index.
 fn set_word(buf: &mut [u8], word_idx: usize, val: u16) {
     let byte_idx = word_idx * 2;
@@ -607,6 +664,54 @@ pub(crate) enum PcmciaCard {
     },
 }
 
+/// The card type, CIS and write-protect switch are configuration; a state
+/// taken with a different card inserted is rejected.
+impl SaveState for PcmciaCard {
+    fn save_state(&self, w: &mut StateWriter) {
+        match self {
+            Self::Sram { common, .. } => {
+                w.write_u8(0);
+                w.write_bytes(common);
+            }
+            Self::CompactFlash {
+                drive, configured, ..
+            } => {
+                w.write_u8(1);
+                drive.save_state(w);
+                w.write_i8(*configured);
+            }
+            Self::Ne2000 {
+                nic, configured, ..
+            } => {
+                w.write_u8(2);
+                nic.save_state(w);
+                w.write_i8(*configured);
+            }
+        }
+    }
+
+    fn load_state(&mut self, r: &mut StateReader<'_>) -> Result<(), StateError> {
+        match (r.read_u8()?, self) {
+            (0, Self::Sram { common, .. }) => r.read_bytes_into(common),
+            (1, Self::CompactFlash {
+                drive, configured, ..
+            }) => {
+                drive.load_state(r)?;
+                *configured = r.read_i8()?;
+                Ok(())
+            }
+            (2, Self::Ne2000 {
+                nic, configured, ..
+            }) => {
+                nic.load_state(r)?;
+                *configured = r.read_i8()?;
+                Ok(())
+            }
+            _ => Err(StateError::Invalid("PCMCIA card does not match".into())),
+        }
+    }
+}
+
 // ---------------------------------------------------------------------------
 // CIS tuple generation
 // ---------------------------------------------------------------------------
@@ -1600,6 +1705,26 @@ fn ide_reg_index(local: u32) -> u32 {
     (stripped >> 2) & 0x07
 }
 
+impl SaveState for Gayle {
+    fn save_state(&self, w: &mut StateWriter) {
+        w.write_u8(self.gayle_cs);
+        w.write_u8(self.gayle_irq);
+        w.write_u8(self.gayle_int);
+        w.write_u8(self.gayle_cfg);
+        w.write_option(self.drive.as_ref());
+        w.write_option(self.pcmcia_card.as_ref());
+    }
+
+    fn load_state(&mut self, r: &mut StateReader<'_>) -> Result<(), StateError> {
+        self.gayle_cs = r.read_u8()?;
+        self.gayle_irq = r.read_u8()?;
+        self.gayle_int = r.read_u8()?;
+        self.gayle_cfg = r.read_u8()?;
+        r.read_option(self.drive.as_mut(), "an IDE drive")?;
+        r.read_option(self.pcmcia_card.as_mut(), "a PCMCIA card")
+    }
+}
+
 impl Default for Gayle {
     fn default() -> Self {
         Self::new()
@@ -8,6 +8,8 @@
 
 use std::collections::VecDeque;
 
+use emu_core::{SaveState, StateError, StateReader, StateWriter};
+
 // ---------------------------------------------------------------------------
 // Constants
 // ---------------------------------------------------------------------------
@@ -609,6 +611,54 @@ fn crc32(data: &[u8]) -> u32 {
     crc ^ 0xFFFF_FFFF
 }
 
+/// The packet queues are the host side of the link and are not saved, like
+/// a keyboard input queue.
+impl SaveState for Ne2000State {
+    fn save_state(&self, w: &mut StateWriter) {
+        w.write_u8(self.cmd);
+        w.write_u8(self.pstart);
+        w.write_u8(self.pstop);
+        w.write_u8(self.boundary);
+        w.write_u8(self.tpsr);
+        w.write_u16(self.tcnt);
+        w.write_u16(self.rsar);
+        w.write_u16(self.rcnt);
+        w.write_u8(self.rxcr);
+        w.write_u8(self.txcr);
+        w.write_u8(self.dcfg);
+        w.write_u8(self.imr);
+        w.write_u8(self.tsr);
+        w.write_u8(self.isr);
+        w.write_u8(self.rsr);
+        w.write_bytes(&self.phys);
+        w.write_u8(self.curpag);
+        w.write_bytes(&self.mult);
+        w.write_bytes(&self.mem);
+    }
+
+    fn load_state(&mut self, r: &mut StateReader<'_>) -> Result<(), StateError> {
+        self.cmd = r.read_u8()?;
+        self.pstart = r.read_u8()?;
+        self.pstop = r.read_u8()?;
+        self.boundary = r.read_u8()?;
+        self.tpsr = r.read_u8()?;
+        self.tcnt = r.read_u16()?;
+        self.rsar = r.read_u16()?;
+        self.rcnt = r.read_u16()?;
+        self.rxcr = r.read_u8()?;
+        self.txcr = r.read_u8()?;
+        self.dcfg = r.read_u8()?;
+        self.imr = r.read_u8()?;
+        self.tsr = r.read_u8()?;
+        self.isr = r.read_u8()?;
+        self.rsr = r.read_u8()?;
+        r.read_bytes_into(&mut self.phys)?;
+        self.curpag = r.read_u8()?;
+        r.read_bytes_into(&mut self.mult)?;
+        r.read_bytes_into(&mut self.mem)
+    }
+}
+
 // ---------------------------------------------------------------------------
 // Tests
 // ---------------------------------------------------------------------------
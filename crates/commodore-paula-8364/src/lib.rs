@@ -4,6 +4,7 @@
 //! sources to 6 CPU interrupt levels. It also handles audio channel DMA and
 //! floppy disk DMA.
 
+use emu_core::{SaveState, StateError, StateReader, StateWriter};
 use std::collections::VecDeque;
 
 const AUDIO_DMA_MASTER: u16 = 0x0200;
@@ -105,6 +106,52 @@ impl Default for AudioChannel {
     }
 }
 
+impl SaveState for AudioChannel {
+    fn save_state(&self, w: &mut StateWriter) {
+        w.write_u32(self.lc);
+        w.write_u32(self.ptr);
+        w.write_u16(self.len_words);
+        w.write_u32(self.words_remaining);
+        w.write_u16(self.per);
+        w.write_u8(self.vol);
+        w.write_u16(self.dat);
+        w.write_bool(self.current_word.is_some());
+        w.write_u16(self.current_word.unwrap_or(0));
+        w.write_bool(self.next_word.is_some());
+        w.write_u16(self.next_word.unwrap_or(0));
+        w.write_bool(self.next_byte_is_hi);
+        w.write_u16(self.period_counter);
+        w.write_i8(self.output_sample);
+        w.write_bool(self.dma_active);
+        w.write_bool(self.dma_enabled_prev);
+        w.write_u8(self.dma_requests_pending);
+        w.write_u8(self.dma_return_countdown);
+        w.write_bool(self.dma_return_word.is_some());
+        w.write_u16(self.dma_return_word.unwrap_or(0));
+    }
+
+    fn load_state(&mut self, r: &mut StateReader<'_>) -> Result<(), StateError> {
+        self.lc = r.read_u32()?;
+        self.ptr = r.read_u32()?;
+        self.len_words = r.read_u16()?;
+        self.words_remaining = r.read_u32()?;
+        self.per = r.read_u16()?;
+        self.vol = r.read_u8()?;
+        self.dat = r.read_u16()?;
+        self.current_word = r.read_bool()?.then_some(r.read_u16()?);
+        self.next_word = r.read_bool()?.then_some(r.read_u16()?);
+        self.next_byte_is_hi = r.read_bool()?;
+        self.period_counter = r.read_u16()?;
+        self.output_sample = r.read_i8()?;
+        self.dma_active = r.read_bool()?;
+        self.dma_enabled_prev = r.read_bool()?;
+        self.dma_requests_pending = r.read_u8()?;
+        self.dma_return_countdown = r.read_u8()?;
+        self.dma_return_word = r.read_bool()?.then_some(r.read_u16()?);
+        Ok(())
+    }
+}
+
 impl AudioChannel {
     fn effective_period(&self) -> u16 {
         self.per.max(MIN_AUDIO_PERIOD_CCK)
@@ -345,9 +392,14 @@ pub struct Paula8364 {
     /// Whether the PLL is in variable-rate mode (driven by IPF timing data).
     pub disk_pll_variable_rate: bool,
     audio: [AudioChannel; 4],
+    /// Audio channels left out of the mix (bit n = `AUDIO_CHANNELS[n]`).
+    channel_mute: u8,
 }
 
 impl Paula8364 {
+    /// Audio channel names, in tap and mute-mask order.
+    pub const AUDIO_CHANNELS: [&'static str; 4] = ["aud0", "aud1", "aud2", "aud3"];
+
     pub fn new() -> Self {
         Self {
             intena: 0,
@@ -371,6 +423,7 @@ impl Paula8364 {
             disk_pll_phase: 0,
             disk_pll_variable_rate: false,
             audio: [AudioChannel::default(); 4],
+            channel_mute: 0,
         }
     }
 
@@ -613,31 +666,46 @@ impl Paula8364 {
         }
     }
 
+    /// Each audio channel's contribution to its side of the mix. A channel
+    /// that only modulates another is silent.
+    pub fn audio_channel_outputs(&self) -> [f32; 4] {
+        std::array::from_fn(|index| {
+            if self.audio_channel_is_modulator(index) {
+                0.0
+            } else {
+                self.audio[index].mix_sample() * 0.5
+            }
+        })
+    }
+
+    /// Mute audio channels in the mix: bit n silences `AUDIO_CHANNELS[n]`.
+    pub fn set_audio_channel_mute(&mut self, mask: u8) {
+        self.channel_mute = mask & 0x0F;
+    }
+
+    /// Audio channels currently muted in the mix.
+    pub fn audio_channel_mute(&self) -> u8 {
+        self.channel_mute
+    }
+
     /// Mixed stereo output in the range `[-1.0, 1.0]`.
     pub fn mix_audio_stereo(&self) -> (f32, f32) {
+        self.mix_audio_channels(&self.audio_channel_outputs())
+    }
+
+    /// Stereo mix of the unmuted channels in `taps`, as returned by
+    /// `audio_channel_outputs`.
+    pub fn mix_audio_channels(&self, taps: &[f32; 4]) -> (f32, f32) {
+        let [ch0, ch1, ch2, ch3] = std::array::from_fn(|index| {
+            if self.channel_mute & (1 << index) == 0 {
+                taps[index]
+            } else {
+                0.0
+            }
+        });
         // OCS stereo routing: channels 0+3 left, 1+2 right.
-        let ch0 = if self.audio_channel_is_modulator(0) {
-            0.0
-        } else {
-            self.audio[0].mix_sample()
-        };
-        let ch1 = if self.audio_channel_is_modulator(1) {
-            0.0
-        } else {
-            self.audio[1].mix_sample()
-        };
-        let ch2 = if self.audio_channel_is_modulator(2) {
-            0.0
-        } else {
-            self.audio[2].mix_sample()
-        };
-        let ch3 = if self.audio_channel_is_modulator(3) {
-            0.0
-        } else {
-            self.audio[3].mix_sample()
-        };
-        let left = (ch0 + ch3) * 0.5;
-        let right = (ch1 + ch2) * 0.5;
+        let left = ch0 + ch3;
+        let right = ch1 + ch2;
         (left.clamp(-1.0, 1.0), right.clamp(-1.0, 1.0))
     }
 
@@ -825,6 +893,62 @@ impl Paula8364 {
     }
 }
 
+impl SaveState for Paula8364 {
+    fn save_state(&self, w: &mut StateWriter) {
+        w.write_u16(self.intena);
+        w.write_u16(self.intreq);
+        w.write_u16(self.adkcon);
+        w.write_u16(self.dsklen);
+        w.write_u16(self.dsklen_prev);
+        w.write_u16(self.dsksync);
+        w.write_u16(self.dskdatr);
+        w.write_u16(self.dskdat);
+        w.write_u8(self.dskbytr_data);
+        w.write_bool(self.dskbytr_next_data.is_some());
+        w.write_u8(self.dskbytr_next_data.unwrap_or(0));
+        w.write_u8(self.dskbytr_next_delay_cck);
+        w.write_bool(self.dskbytr_valid);
+        w.write_bool(self.dskbytr_wordequal);
+        w.write_u8(self.dskbytr_wordequal_delay_cck);
+        w.write_u16_slice(&self.dskdat_queue.iter().copied().collect::<Vec<_>>());
+        w.write_u16_slice(&self.disk_write_dma_log);
+        w.write_u16_slice(&self.disk_write_pio_log);
+        w.write_bool(self.disk_dma_pending);
+        w.write_u16(self.disk_pll_phase);
+        w.write_bool(self.disk_pll_variable_rate);
+        for v in &self.audio {
+            v.save_state(w);
+        }
+    }
+
+    fn load_state(&mut self, r: &mut StateReader<'_>) -> Result<(), StateError> {
+        self.intena = r.read_u16()?;
+        self.intreq = r.read_u16()?;
+        self.adkcon = r.read_u16()?;
+        self.dsklen = r.read_u16()?;
+        self.dsklen_prev = r.read_u16()?;
+        self.dsksync = r.read_u16()?;
+        self.dskdatr = r.read_u16()?;
+        self.dskdat = r.read_u16()?;
+        self.dskbytr_data = r.read_u8()?;
+        self.dskbytr_next_data = r.read_bool()?.then_some(r.read_u8()?);
+        self.dskbytr_next_delay_cck = r.read_u8()?;
+        self.dskbytr_valid = r.read_bool()?;
+        self.dskbytr_wordequal = r.read_bool()?;
+        self.dskbytr_wordequal_delay_cck = r.read_u8()?;
+        self.dskdat_queue = r.read_u16_vec()?.into();
+        self.disk_write_dma_log = r.read_u16_vec()?;
+        self.disk_write_pio_log = r.read_u16_vec()?;
+        self.disk_dma_pending = r.read_bool()?;
+        self.disk_pll_phase = r.read_u16()?;
+        self.disk_pll_variable_rate = r.read_bool()?;
+        for v in &mut self.audio {
+            v.load_state(r)?;
+        }
+        Ok(())
+    }
+}
+
 impl Default for Paula8364 {
     fn default() -> Self {
         Self::new()
@@ -885,6 +1009,30 @@ mod tests {
         assert!(right.abs() < 0.01, "right={right}");
     }
 
+    #[test]
+    fn muted_channel_leaves_mix_but_keeps_its_tap() {
+        let mut paula = Paula8364::new();
+        let dmacon = 0x0200 | 0x0001; // DMAEN + AUD0EN
+
+        assert!(paula.write_audio_register(0x0A0, 0x0000));
+        assert!(paula.write_audio_register(0x0A2, 0x1000));
+        assert!(paula.write_audio_register(0x0A4, 0x0001));
+        assert!(paula.write_audio_register(0x0A6, 124));
+        assert!(paula.write_audio_register(0x0A8, 64));
+
+        let read = |addr: u32| -> u8 { if addr == 0x0000_1000 { 0x7F } else { 0 } };
+        for _ in 0..124 {
+            paula.tick_audio_cck(dmacon, Some(0), read);
+        }
+        paula.set_audio_channel_mute(0b0001);
+
+        let taps = paula.audio_channel_outputs();
+        let (left, _) = paula.mix_audio_stereo();
+        assert!(taps[0] > 0.2, "tap={}", taps[0]);
+        assert!(left.abs() < 1e-6, "left={left}");
+        assert_eq!(paula.audio_channel_mute(), 0b0001);
+    }
+
     #[test]
     fn audio_dma_word_arrival_is_delayed_after_slot_service() {
         let mut paula = Paula8364::new();
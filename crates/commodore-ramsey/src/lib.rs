@@ -5,6 +5,8 @@
 //! probes during early boot, plus control bits for page mode, burst,
 //! refresh rate, and memory-size detection (wrap bit).
 
+use emu_core::{SaveState, StateError, StateReader, StateWriter};
+
 // ---------------------------------------------------------------------------
 // Config register bit-fields
 // ---------------------------------------------------------------------------
@@ -160,6 +162,19 @@ impl Ramsey {
     }
 }
 
+/// The revision register is fixed by the chip model, so only the config
+/// register is saved.
+impl SaveState for Ramsey {
+    fn save_state(&self, w: &mut StateWriter) {
+        w.write_u8(self.config);
+    }
+
+    fn load_state(&mut self, r: &mut StateReader<'_>) -> Result<(), StateError> {
+        self.config = r.read_u8()?;
+        Ok(())
+    }
+}
+
 impl Default for Ramsey {
     fn default() -> Self {
         Self::new()
@@ -9,6 +9,7 @@
 //! with an extended descriptor at `$E80100` for Zorro III boards.
 
 pub use commodore_buster::{BoardSize, ZorroIIRamBoard, ZorroIISlot};
+use emu_core::{SaveState, StateError, StateReader, StateWriter};
 
 // ---------------------------------------------------------------------------
 // Zorro III autoconfig register offsets
@@ -477,6 +478,53 @@ impl SuperBuster {
     }
 }
 
+/// Board contents, base addresses and autoconfig progress for both buses.
+/// The set of boards in the slots is configuration and must match.
+impl SaveState for SuperBuster {
+    fn save_state(&self, w: &mut StateWriter) {
+        w.write_usize(self.z3_slots.len());
+        for slot in &self.z3_slots {
+            match slot {
+                ZorroIIISlot::Ram(board) => {
+                    w.write_bytes(&board.ram);
+                    w.write_bool(board.base_addr.is_some());
+                    w.write_u32(board.base_addr.unwrap_or(0));
+                }
+            }
+        }
+        self.z2.save_state(w);
+        w.write_usize(self.z3_current);
+        w.write_u8(match self.phase {
+            AutoconfigPhase::ZorroIII => 0,
+            AutoconfigPhase::ZorroII => 1,
+            AutoconfigPhase::Complete => 2,
+        });
+    }
+
+    fn load_state(&mut self, r: &mut StateReader<'_>) -> Result<(), StateError> {
+        if r.read_usize()? != self.z3_slots.len() {
+            return Err(StateError::Invalid("Zorro III boards do not match".into()));
+        }
+        for slot in &mut self.z3_slots {
+            match slot {
+                ZorroIIISlot::Ram(board) => {
+                    r.read_bytes_into(&mut board.ram)?;
+                    board.base_addr = r.read_bool()?.then_some(r.read_u32()?);
+                }
+            }
+        }
+        self.z2.load_state(r)?;
+        self.z3_current = r.read_usize()?;
+        self.phase = match r.read_u8()? {
+            0 => AutoconfigPhase::ZorroIII,
+            1 => AutoconfigPhase::ZorroII,
+            2 => AutoconfigPhase::Complete,
+            n => return Err(StateError::Invalid(format!("autoconfig phase {n}"))),
+        };
+        Ok(())
+    }
+}
+
 impl Default for SuperBuster {
     fn default() -> Self {
         Self::new()
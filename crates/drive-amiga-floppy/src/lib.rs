@@ -6,6 +6,7 @@
 
 pub mod mfm;
 
+use emu_core::{SaveState, StateError, StateReader, StateWriter};
 use format_adf::Adf;
 use mfm::{decode_mfm_track, encode_mfm_track};
 
@@ -298,6 +299,56 @@ impl AmigaFloppyDrive {
     }
 }
 
+/// Writable images (ADF) are embedded so in-progress disk writes survive a
+/// restore. Read-only images (IPF) are configuration: the same image must
+/// already be inserted.
+impl SaveState for AmigaFloppyDrive {
+    fn save_state(&self, w: &mut StateWriter) {
+        w.write_bool(self.disk.is_some());
+        if let Some(disk) = &self.disk {
+            let data = disk.save_data();
+            w.write_bool(data.is_some());
+            if let Some(data) = data {
+                w.write_bytes(&data);
+            }
+        }
+        w.write_u32(self.cylinder);
+        w.write_u32(self.head);
+        w.write_bool(self.motor_on);
+        w.write_bool(self.motor_spinning);
+        w.write_u32(self.spin_timer);
+        w.write_bool(self.selected);
+        w.write_bool(self.disk_changed);
+        w.write_bool(self.prev_step);
+        w.write_u32(self.step_event_counter);
+        w.write_u16_slice(&self.write_mfm_capture);
+        w.write_u16_slice(&self.write_mfm_pending);
+    }
+
+    fn load_state(&mut self, r: &mut StateReader<'_>) -> Result<(), StateError> {
+        if r.read_bool()? != self.disk.is_some() {
+            return Err(StateError::Invalid("disk does not match".to_string()));
+        }
+        if self.disk.is_some() && r.read_bool()? {
+            let adf = Adf::from_bytes(r.read_bytes()?.to_vec())
+                .map_err(|e| StateError::Invalid(e.to_string()))?;
+            self.disk = Some(Box::new(AdfDiskImage::new(adf)));
+        }
+        self.cylinder = r.read_u32()?;
+        self.head = r.read_u32()?;
+        self.motor_on = r.read_bool()?;
+        self.motor_spinning = r.read_bool()?;
+        self.spin_timer = r.read_u32()?;
+        self.selected = r.read_bool()?;
+        self.disk_changed = r.read_bool()?;
+        self.prev_step = r.read_bool()?;
+        self.step_event_counter = r.read_u32()?;
+        self.write_mfm_capture = r.read_u16_vec()?;
+        self.write_mfm_pending = r.read_u16_vec()?;
+        Ok(())
+    }
+}
+
 impl Default for AmigaFloppyDrive {
     fn default() -> Self {
         Self::new()
@@ -136,6 +136,22 @@ impl AmigaEmulator {
         }
     }
 
+    /// Names of the sound channels, in channel-mute bit order.
+    pub fn audio_channels(&self) -> Vec<String> {
+        use emu_core::Machine;
+        self.amiga
+            .audio_channels()
+            .into_iter()
+            .map(String::from)
+            .collect()
+    }
+
+    /// Mute sound channels in the mix: bit `n` silences `audio_channels()[n]`.
+    pub fn set_channel_mute(&mut self, mask: u32) {
+        use emu_core::Machine;
+        self.amiga.set_channel_mute(mask);
+    }
+
     /// Reset the Amiga (warm reset — re-reads SSP and PC from ROM).
     pub fn reset(&mut self) {
         // A warm reset reads SSP from $FC0000 and PC from $FC0004 (Kickstart vectors).
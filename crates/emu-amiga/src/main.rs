@@ -19,6 +19,7 @@ use std::collections::HashMap;
 use std::collections::VecDeque;
 use std::fs::File;
 use std::io::BufWriter;
+use std::net::TcpListener;
 use std::path::PathBuf;
 use std::process;
 use std::sync::Arc;
@@ -82,12 +83,14 @@ struct CliArgs {
     disk_path: Option<PathBuf>,
     model: AmigaModel,
     headless: bool,
+    bench: bool,
     frames: u32,
     screenshot_path: Option<PathBuf>,
     audio_path: Option<PathBuf>,
     mute: bool,
     mcp: bool,
     script_path: Option<PathBuf>,
+    gdb_port: Option<u16>,
     drive_sounds: bool,
 }
 
@@ -102,6 +105,7 @@ fn print_usage() {
         "  --model <a1000|a500|a500plus|a600|a1200|a2000|a3000|a4000>  Select machine model [default: a500; chipset derives from model]"
     );
     eprintln!("  --headless     Run without a window");
+    eprintln!("  --bench        Run --frames frames flat out and report speed");
     eprintln!("  --frames <n>   Frames to run in headless mode [default: 300]");
     eprintln!("  --screenshot <file.png>  Save a framebuffer screenshot (headless)");
     eprintln!("  --audio <file.wav>  Save a WAV audio dump (headless)");
@@ -109,6 +113,7 @@ fn print_usage() {
     eprintln!("  --no-drive-sounds  Disable mechanical floppy drive sounds");
     eprintln!("  --mcp          Run as MCP JSON-RPC server (headless, stdin/stdout)");
     eprintln!("  --script <file.json>  Run a JSON script file (headless batch mode)");
+    eprintln!("  --gdb <port>   Wait for a GDB remote connection on 127.0.0.1:<port>");
     eprintln!("  -h, --help     Show this help");
 }
 
@@ -140,6 +145,7 @@ fn parse_args_from(
     let mut disk_path: Option<PathBuf> = None;
     let mut model = AmigaModel::A500;
     let mut headless = false;
+    let mut bench = false;
     let mut frames = 300;
     let mut screenshot_path: Option<PathBuf> = None;
     let mut audio_path: Option<PathBuf> = None;
@@ -147,6 +153,7 @@ fn parse_args_from(
     let mut drive_sounds = true;
     let mut mcp = false;
     let mut script_path: Option<PathBuf> = None;
+    let mut gdb_port: Option<u16> = None;
 
     let mut i = 1;
     while i < args.len() {
@@ -175,6 +182,9 @@ fn parse_args_from(
             "--headless" => {
                 headless = true;
             }
+            "--bench" => {
+                bench = true;
+            }
             "--frames" => {
                 i += 1;
                 if let Some(value) = args.get(i) {
@@ -202,6 +212,18 @@ fn parse_args_from(
                 i += 1;
                 script_path = args.get(i).map(PathBuf::from);
             }
+            "--gdb" => {
+                i += 1;
+                let Some(value) = args.get(i) else {
+                    return Err(String::from(
+                        "Missing value for --gdb (expected a TCP port)",
+                    ));
+                };
+                let port = value
+                    .parse::<u16>()
+                    .map_err(|_| format!("Invalid --gdb port: {value}"))?;
+                gdb_port = Some(port);
+            }
             "-h" | "--help" => return Ok(None),
             other => {
                 return Err(format!("Unknown argument: {other}"));
@@ -219,7 +241,7 @@ fn parse_args_from(
             .ok_or_else(|| String::from("No Kickstart ROM specified."))?
     };
 
-    if screenshot_path.is_some() || audio_path.is_some() {
+    if screenshot_path.is_some() || audio_path.is_some() || gdb_port.is_some() {
         headless = true;
     }
     Ok(Some(CliArgs {
@@ -228,12 +250,14 @@ fn parse_args_from(
         disk_path,
         model,
         headless,
+        bench,
         frames,
         screenshot_path,
         audio_path,
         mute,
         mcp,
         script_path,
+        gdb_port,
         drive_sounds,
     }))
 }
@@ -606,6 +630,37 @@ fn run_headless(cli: &CliArgs) {
     }
 }
 
+/// Time `--frames` frames with no pacing and print the benchmark report.
+fn run_bench(cli: &CliArgs) {
+    let mut amiga = make_amiga(cli);
+    print!("{}", emu_core::bench::run(&mut amiga, cli.frames));
+}
+
+/// Serve one GDB remote session; the machine only runs while `gdb` says so.
+fn run_gdb(cli: &CliArgs, port: u16) {
+    let mut amiga = make_amiga(cli);
+    let listener = match TcpListener::bind(("127.0.0.1", port)) {
+        Ok(listener) => listener,
+        Err(e) => {
+            eprintln!("Failed to listen on 127.0.0.1:{port}: {e}");
+            process::exit(1);
+        }
+    };
+    eprintln!("Waiting for gdb on 127.0.0.1:{port} (target remote :{port})");
+    let (stream, peer) = match listener.accept() {
+        Ok(connection) => connection,
+        Err(e) => {
+            eprintln!("Failed to accept gdb connection: {e}");
+            process::exit(1);
+        }
+    };
+    eprintln!("gdb connected from {peer}");
+    if let Err(e) = emu_core::gdb::serve(stream, &mut amiga) {
+        eprintln!("gdb session ended: {e}");
+        process::exit(1);
+    }
+}
+
 // ---------------------------------------------------------------------------
 // Native menus (muda)
 // ---------------------------------------------------------------------------
@@ -1322,6 +1377,16 @@ fn main() {
         return;
     }
 
+    if let Some(port) = cli.gdb_port {
+        run_gdb(&cli, port);
+        return;
+    }
+
+    if cli.bench {
+        run_bench(&cli);
+        return;
+    }
+
     if cli.headless {
         run_headless(&cli);
         return;
@@ -1498,6 +1563,19 @@ mod tests {
         assert_eq!(cli.audio_path, Some(PathBuf::from("out.wav")));
     }
 
+    #[test]
+    fn cli_parser_accepts_gdb_port() {
+        let cli = parse_cli(&["emu-amiga", "--gdb", "1234"], Some("/tmp/kick.rom"))
+            .expect("parse should succeed")
+            .expect("help was not requested");
+        assert_eq!(cli.gdb_port, Some(1234));
+        assert!(cli.headless);
+
+        let err = parse_cli(&["emu-amiga", "--gdb", "gdb"], Some("/tmp/kick.rom"))
+            .expect_err("port must be a number");
+        assert!(err.contains("--gdb"));
+    }
+
     #[test]
     fn cli_parser_derives_chipset_from_selected_model() {
         let ecs = parse_cli(
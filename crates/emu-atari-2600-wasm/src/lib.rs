@@ -6,6 +6,7 @@
 use wasm_bindgen::prelude::*;
 
 use emu_atari_2600::{Atari2600, Atari2600Config, Atari2600Region};
+use emu_core::crt::CrtFilter;
 
 /// Atari 2600 emulator for the browser.
 #[wasm_bindgen]
@@ -14,6 +15,8 @@ pub struct Atari2600Emulator {
     rgba_buf: Vec<u8>,
     w: u32,
     h: u32,
+    /// CRT filter applied to each frame.
+    filter: Option<CrtFilter>,
     /// RIOT port A (joystick directions, active-low).
     joy_input: u8,
     /// RIOT port B (console switches, active-low).
@@ -39,20 +42,33 @@ impl Atari2600Emulator {
             rgba_buf: vec![0u8; (w * h * 4) as usize],
             w,
             h,
+            filter: None,
             joy_input: 0xFF,
             switch_input: 0xFF,
             fire: false,
         })
     }
 
-    /// Framebuffer width in pixels.
+    /// Width of the RGBA buffer in pixels.
     pub fn width(&self) -> u32 {
-        self.w
+        self.output_size().0
     }
 
-    /// Framebuffer height in pixels.
+    /// Height of the RGBA buffer in pixels.
     pub fn height(&self) -> u32 {
-        self.h
+        self.output_size().1
+    }
+
+    /// Show frames as a period TV would: `none`, `scanlines`, `svideo`,
+    /// `composite` or `crt` (composite with scanlines). Scanlines double
+    /// the height, so read `width()`, `height()` and the buffer pointer
+    /// again afterwards.
+    pub fn set_filter(&mut self, name: &str) -> Result<(), JsError> {
+        self.filter = CrtFilter::from_name(name, Some(self.system.video_signal()))
+            .map_err(|e| JsError::new(&e))?;
+        let (w, h) = self.output_size();
+        self.rgba_buf.resize((w * h * 4) as usize, 0);
+        Ok(())
     }
 
     /// Run one emulation frame.
@@ -62,8 +78,17 @@ impl Atari2600Emulator {
         self.system.set_fire_button_p0(self.fire);
         self.system.run_frame();
 
+        let (w, h) = self.output_size();
         let fb = self.system.framebuffer();
-        let px_count = (self.w * self.h) as usize;
+        let filtered;
+        let fb = match &mut self.filter {
+            Some(filter) => {
+                filtered = filter.process(self.w, self.h, fb, w, h);
+                &filtered
+            }
+            None => fb,
+        };
+        let px_count = (w * h) as usize;
         for i in 0..px_count.min(fb.len()) {
             let argb = fb[i];
             let offset = i * 4;
@@ -106,6 +131,16 @@ impl Atari2600Emulator {
     }
 }
 
+impl Atari2600Emulator {
+    /// Size of the RGBA buffer: the framebuffer, or the filter's output.
+    fn output_size(&self) -> (u32, u32) {
+        match &self.filter {
+            Some(filter) => filter.output_size(self.w, self.h),
+            None => (self.w, self.h),
+        }
+    }
+}
+
 impl Atari2600Emulator {
     fn apply_key(&mut self, code: &str, pressed: bool) {
         match code {
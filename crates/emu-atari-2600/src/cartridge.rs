@@ -64,7 +64,12 @@ impl Cartridge {
     /// Also performs hotspot detection for bank switching.
     pub fn read(&mut self, addr: u16) -> u8 {
         self.check_hotspot(addr);
+        self.peek(addr)
+    }
 
+    /// Read a byte from the current bank without triggering hotspots.
+    #[must_use]
+    pub fn peek(&self, addr: u16) -> u8 {
         let offset = (addr & 0x0FFF) as usize;
         let bank_offset = self.bank * self.bank_size;
 
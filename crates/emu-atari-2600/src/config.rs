@@ -1,5 +1,7 @@
 //! Atari 2600 configuration.
 
+use emu_core::crt::{Signal, Standard};
+
 /// Video region.
 #[derive(Debug, Default, Clone, Copy, PartialEq, Eq)]
 pub enum Atari2600Region {
@@ -34,6 +36,19 @@ impl Atari2600Region {
     pub const fn cpu_hz(self) -> u32 {
         self.crystal_hz() / 3
     }
+
+    /// The TIA's composite video timing. Each pixel is one colour clock.
+    /// On NTSC that is exactly one subcarrier cycle, so lines and frames
+    /// start in phase and artifacts stand still. The PAL clock is 0.8 of
+    /// the subcarrier, so each 228-clock line moves on by 0.4 of a cycle,
+    /// and a 312-line frame by 0.8.
+    #[must_use]
+    pub const fn signal(self) -> Signal {
+        match self {
+            Self::Ntsc => Signal::rgb(Standard::Ntsc, 1.0, 0.0, &[0.0]),
+            Self::Pal => Signal::rgb(Standard::Pal, 0.8, 0.4, &[0.0, 0.8, 0.6, 0.4, 0.2]),
+        }
+    }
 }
 
 /// Atari 2600 configuration.
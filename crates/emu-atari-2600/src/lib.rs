@@ -17,18 +17,26 @@ mod cartridge;
 mod config;
 #[cfg(feature = "native")]
 pub mod controller_map;
+#[cfg(feature = "native")]
+pub mod mcp;
 
 pub use atari_tia as tia;
 pub use bus::Atari2600Bus;
 pub use config::{Atari2600Config, Atari2600Region};
 
+use std::time::Duration;
+
 use atari_tia::{Tia, TiaRegion};
+use emu_core::bench::ChipProfiler;
 use emu_core::{AudioFrame, Cpu, Machine, Observable, Tickable, Value};
 use mos_6502::Mos6502;
 
 use crate::bus::Atari2600BusInner;
 use crate::cartridge::Cartridge;
 
+/// Chips timed by the profiler, in the order the tick loop laps them.
+const CHIPS: &[&str] = &["atari-tia", "mos-6502", "mos-riot-6532"];
+
 /// Atari 2600 system.
 pub struct Atari2600 {
     cpu: Mos6502,
@@ -41,6 +49,8 @@ pub struct Atari2600 {
     region: Atari2600Region,
     /// Colour clocks per frame.
     clocks_per_frame: u64,
+    /// Per-chip tick timing, while benchmarking.
+    profiler: ChipProfiler,
 }
 
 impl Atari2600 {
@@ -82,6 +92,7 @@ impl Atari2600 {
             frame_count: 0,
             region: config.region,
             clocks_per_frame,
+            profiler: ChipProfiler::new(CHIPS),
         })
     }
 
@@ -124,6 +135,12 @@ impl Atari2600 {
         self.region
     }
 
+    /// The TIA's composite video signal, for the CRT filter.
+    #[must_use]
+    pub fn video_signal(&self) -> emu_core::crt::Signal {
+        self.region.signal()
+    }
+
     /// Reference to the CPU.
     #[must_use]
     pub fn cpu(&self) -> &Mos6502 {
@@ -183,9 +200,11 @@ impl Atari2600 {
 impl Tickable for Atari2600 {
     fn tick(&mut self) {
         self.master_clock += 1;
+        self.profiler.start();
 
         // TIA ticks every colour clock (1:1 with master clock).
         self.bus.tia.tick();
+        self.profiler.lap(0);
 
         // CPU and RIOT tick every 3rd colour clock.
         if self.master_clock.is_multiple_of(3) {
@@ -193,9 +212,11 @@ impl Tickable for Atari2600 {
             if !self.bus.tia.wsync_halt {
                 self.cpu.tick(&mut Atari2600Bus(&mut self.bus));
             }
+            self.profiler.lap(1);
 
             // RIOT timer ticks once per CPU cycle.
             self.bus.riot.tick();
+            self.profiler.lap(2);
         }
     }
 }
@@ -255,6 +276,24 @@ impl Machine for Atari2600 {
         self.frame_count()
     }
 
+    fn cpu_clock_hz(&self) -> f64 {
+        f64::from(self.region.cpu_hz())
+    }
+
+    #[allow(clippy::cast_precision_loss)]
+    fn frame_rate_hz(&self) -> f64 {
+        f64::from(self.region.crystal_hz()) / self.clocks_per_frame as f64
+    }
+
+    fn set_chip_profiling(&mut self, enabled: bool) -> bool {
+        self.profiler.set_enabled(enabled);
+        true
+    }
+
+    fn chip_times(&self) -> Vec<(&'static str, Duration)> {
+        self.profiler.times()
+    }
+
     fn reset(&mut self) {
         self.cpu_mut().reset();
     }
@@ -0,0 +1,53 @@
+//! MCP (Model Context Protocol) server for the Atari 2600 emulator.
+//!
+//! Serves the generic `MachineMcp` tools. Run with `--mcp` for MCP mode
+//! or `--script` for batch mode.
+
+use emu_core::Instruction;
+use emu_core::crt::Signal;
+use emu_core::mcp::{self, MachineMcp, McpMachine};
+
+use crate::{Atari2600, Atari2600Region};
+
+pub type McpServer = mcp::McpServer<MachineMcp<Atari2600>>;
+
+impl McpMachine for Atari2600 {
+    fn frame_rate(&self) -> u32 {
+        match self.region {
+            Atari2600Region::Ntsc => 60,
+            Atari2600Region::Pal => 50,
+        }
+    }
+
+    /// 13-bit address bus: cartridge when A12 = 1, otherwise RIOT RAM
+    /// (TIA and RIOT registers read as $FF).
+    fn disassemble(&self, address: u32) -> Instruction {
+        emu_disasm::mos6502::disassemble(
+            |addr| {
+                if addr & 0x1000 != 0 {
+                    self.bus.cart.peek(addr)
+                } else {
+                    self.peek(addr).unwrap_or(0xFF)
+                }
+            },
+            address,
+        )
+    }
+
+    /// RIOT RAM: A12 = 0, A9 = 0, A7 = 1 ($0080-$00FF and mirrors).
+    fn peek(&self, address: u16) -> Option<u8> {
+        (address & 0x1280 == 0x0080).then(|| self.bus.riot.ram()[usize::from(address & 0x7F)])
+    }
+
+    fn poke(&mut self, address: u16, value: u8) -> bool {
+        let is_ram = address & 0x1280 == 0x0080;
+        if is_ram {
+            self.bus.riot.write(address, value);
+        }
+        is_ram
+    }
+
+    fn video_signal(&self) -> Option<Signal> {
+        Some(Atari2600::video_signal(self))
+    }
+}
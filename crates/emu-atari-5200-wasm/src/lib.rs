@@ -93,6 +93,22 @@ impl Atari5200Emulator {
         self.apply_key(code, false);
     }
 
+    /// Names of the sound channels, in channel-mute bit order.
+    pub fn audio_channels(&self) -> Vec<String> {
+        use emu_core::Machine;
+        self.system
+            .audio_channels()
+            .into_iter()
+            .map(String::from)
+            .collect()
+    }
+
+    /// Mute sound channels in the mix: bit `n` silences `audio_channels()[n]`.
+    pub fn set_channel_mute(&mut self, mask: u32) {
+        use emu_core::Machine;
+        self.system.set_channel_mute(mask);
+    }
+
     /// Reset the system.
     pub fn reset(&mut self) {
         use emu_core::Cpu;
@@ -18,6 +18,8 @@ mod cartridge;
 mod config;
 #[cfg(feature = "native")]
 pub mod controller_map;
+#[cfg(feature = "native")]
+pub mod mcp;
 
 pub use atari_antic as antic;
 pub use atari_gtia as gtia;
@@ -25,9 +27,12 @@ pub use atari_pokey as pokey;
 pub use bus::Atari5200Bus;
 pub use config::{Atari5200Config, Atari5200Region};
 
+use std::time::Duration;
+
 use atari_antic::{Antic, AnticRegion, COLOUR_CLOCKS_PER_LINE};
 use atari_gtia::Gtia;
 use atari_pokey::Pokey;
+use emu_core::bench::ChipProfiler;
 use emu_core::{AudioFrame, Cpu, Machine, Observable, Tickable, Value};
 use mos_6502::Mos6502;
 
@@ -40,6 +45,9 @@ pub const POT_CENTER: u8 = 114;
 /// Joystick maximum value (fully right or fully down).
 pub const POT_MAX: u8 = 228;
 
+/// Chips timed by the profiler, in the order the tick loop laps them.
+const CHIPS: &[&str] = &["atari-antic", "atari-gtia", "mos-6502", "atari-pokey"];
+
 /// Atari 5200 system.
 pub struct Atari5200 {
     /// 6502C CPU.
@@ -58,6 +66,8 @@ pub struct Atari5200 {
     dma_budget: u8,
     /// CPU cycle counter within the current scan line (0-113).
     line_cycle: u16,
+    /// Per-chip tick timing, while benchmarking.
+    profiler: ChipProfiler,
 }
 
 impl Atari5200 {
@@ -111,6 +121,7 @@ impl Atari5200 {
             clocks_per_frame,
             dma_budget: 0,
             line_cycle: 0,
+            profiler: ChipProfiler::new(CHIPS),
         })
     }
 
@@ -223,6 +234,7 @@ impl Atari5200 {
     fn process_scan_line(&mut self) {
         // Process ANTIC line -- reads display list and screen data from RAM.
         let result = self.bus.antic.process_line(&self.bus.ram);
+        self.profiler.lap(0);
 
         // Feed player/missile data to GTIA if PM DMA occurred.
         if result.pm_dma {
@@ -243,6 +255,7 @@ impl Atari5200 {
             result.playfield_width,
             result.mode,
         );
+        self.profiler.lap(1);
 
         // Set DMA budget for this line.
         self.dma_budget = result.dma_cycles;
@@ -264,6 +277,7 @@ impl Atari5200 {
 impl Tickable for Atari5200 {
     fn tick(&mut self) {
         self.master_clock += 1;
+        self.profiler.start();
 
         // At the start of each scan line (every 228 colour clocks).
         if self.master_clock.is_multiple_of(u64::from(COLOUR_CLOCKS_PER_LINE)) {
@@ -280,9 +294,11 @@ impl Tickable for Atari5200 {
             {
                 self.cpu.tick(&mut Atari5200Bus(&mut self.bus));
             }
+            self.profiler.lap(2);
 
             // POKEY always ticks.
             self.bus.pokey.tick();
+            self.profiler.lap(3);
 
             // POKEY IRQ -> CPU IRQ.
             if self.bus.pokey.irq_pending() {
@@ -343,10 +359,61 @@ impl Machine for Atari5200 {
         self.bus.pokey.take_buffer().into_iter().map(|s| [s, s]).collect()
     }
 
+    fn audio_sample_rate(&self) -> u32 {
+        self.bus.pokey.sample_rate()
+    }
+
+    fn set_audio_sample_rate(&mut self, rate: u32) -> bool {
+        self.bus.pokey.set_sample_rate(rate);
+        true
+    }
+
+    fn set_audio_rate_adjust(&mut self, ratio: f64) {
+        self.bus.pokey.set_rate_adjust(ratio);
+    }
+
+    fn audio_channels(&self) -> Vec<&'static str> {
+        Pokey::CHANNELS.to_vec()
+    }
+
+    fn set_channel_mute(&mut self, mask: u32) {
+        self.bus.pokey.set_channel_mute(mask as u8);
+    }
+
+    fn channel_mute(&self) -> u32 {
+        u32::from(self.bus.pokey.channel_mute())
+    }
+
+    fn set_stem_capture(&mut self, enabled: bool) {
+        self.bus.pokey.set_stems(enabled);
+    }
+
+    fn take_audio_stems(&mut self) -> Vec<Vec<f32>> {
+        self.bus.pokey.take_stems()
+    }
+
     fn frame_count(&self) -> u64 {
         self.frame_count()
     }
 
+    fn cpu_clock_hz(&self) -> f64 {
+        f64::from(self.region.cpu_hz())
+    }
+
+    #[allow(clippy::cast_precision_loss)]
+    fn frame_rate_hz(&self) -> f64 {
+        f64::from(self.region.crystal_hz()) / self.clocks_per_frame as f64
+    }
+
+    fn set_chip_profiling(&mut self, enabled: bool) -> bool {
+        self.profiler.set_enabled(enabled);
+        true
+    }
+
+    fn chip_times(&self) -> Vec<(&'static str, Duration)> {
+        self.profiler.times()
+    }
+
     fn reset(&mut self) {
         self.cpu_mut().reset();
     }
@@ -0,0 +1,50 @@
+//! MCP (Model Context Protocol) server for the Atari 5200 emulator.
+//!
+//! Serves the generic `MachineMcp` tools. Run with `--mcp` for MCP mode
+//! or `--script` for batch mode.
+
+use emu_core::Instruction;
+use emu_core::mcp::{self, MachineMcp, McpMachine};
+
+use crate::{Atari5200, Atari5200Region};
+
+pub type McpServer = mcp::McpServer<MachineMcp<Atari5200>>;
+
+impl McpMachine for Atari5200 {
+    fn frame_rate(&self) -> u32 {
+        match self.region {
+            Atari5200Region::Ntsc => 60,
+            Atari5200Region::Pal => 50,
+        }
+    }
+
+    /// RAM, cartridge and BIOS; chip registers read as $FF.
+    fn disassemble(&self, address: u32) -> Instruction {
+        let bus = &self.bus;
+        emu_disasm::mos6502::disassemble(
+            |addr| match addr {
+                0x4000..=0xBFFF => bus.cart.read(addr),
+                0xF800..=0xFFFF if bus.bios.is_empty() => bus.cart.read(addr),
+                0xF800..=0xFFFF => bus
+                    .bios
+                    .get(usize::from(addr - 0xF800))
+                    .copied()
+                    .unwrap_or(0xFF),
+                _ => self.peek(addr).unwrap_or(0xFF),
+            },
+            address,
+        )
+    }
+
+    fn peek(&self, address: u16) -> Option<u8> {
+        self.bus.ram.get(usize::from(address)).copied()
+    }
+
+    fn poke(&mut self, address: u16, value: u8) -> bool {
+        self.bus
+            .ram
+            .get_mut(usize::from(address))
+            .map(|byte| *byte = value)
+            .is_some()
+    }
+}
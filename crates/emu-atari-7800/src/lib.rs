@@ -19,13 +19,18 @@ mod cartridge;
 mod config;
 #[cfg(feature = "native")]
 pub mod controller_map;
+#[cfg(feature = "native")]
+pub mod mcp;
 mod tia_audio;
 
 pub use atari_maria as maria;
 pub use bus::Atari7800Bus;
 pub use config::{Atari7800Config, Atari7800Region};
 
+use std::time::Duration;
+
 use atari_maria::{Maria, MariaRegion};
+use emu_core::bench::ChipProfiler;
 use emu_core::{AudioFrame, Cpu, Machine, Observable, Tickable, Value};
 use mos_6502::Mos6502;
 use mos_riot_6532::Riot6532;
@@ -37,6 +42,9 @@ use crate::tia_audio::TiaAudio;
 /// Colour clocks per scanline (same as 2600/5200).
 const COLOUR_CLOCKS_PER_LINE: u16 = 228;
 
+/// Chips timed by the profiler, in the order the tick loop laps them.
+const CHIPS: &[&str] = &["atari-maria", "mos-6502", "mos-riot-6532"];
+
 /// Atari 7800 system.
 pub struct Atari7800 {
     /// 6502C "SALLY" CPU.
@@ -57,6 +65,8 @@ pub struct Atari7800 {
     line_cycle: u16,
     /// Fire button state (active low on RIOT port A bit 7).
     fire_pressed: bool,
+    /// Per-chip tick timing, while benchmarking.
+    profiler: ChipProfiler,
 }
 
 impl Atari7800 {
@@ -112,6 +122,7 @@ impl Atari7800 {
             dma_budget: 0,
             line_cycle: 0,
             fire_pressed: false,
+            profiler: ChipProfiler::new(CHIPS),
         })
     }
 
@@ -223,6 +234,7 @@ impl Atari7800 {
             0x4000..=0xFFFF => cart.read_pure(addr),
             _ => 0,
         });
+        self.profiler.lap(0);
 
         self.dma_budget = dma_cycles;
         self.line_cycle = 0;
@@ -240,6 +252,7 @@ impl Atari7800 {
 impl Tickable for Atari7800 {
     fn tick(&mut self) {
         self.master_clock += 1;
+        self.profiler.start();
 
         // At the start of each scan line (every 228 colour clocks).
         if self.master_clock.is_multiple_of(u64::from(COLOUR_CLOCKS_PER_LINE)) {
@@ -256,9 +269,11 @@ impl Tickable for Atari7800 {
             {
                 self.cpu.tick(&mut Atari7800Bus(&mut self.bus));
             }
+            self.profiler.lap(1);
 
             // RIOT timer always ticks.
             self.bus.riot.tick();
+            self.profiler.lap(2);
         }
     }
 }
@@ -318,6 +333,24 @@ impl Machine for Atari7800 {
         self.frame_count()
     }
 
+    fn cpu_clock_hz(&self) -> f64 {
+        f64::from(self.region.cpu_hz())
+    }
+
+    #[allow(clippy::cast_precision_loss)]
+    fn frame_rate_hz(&self) -> f64 {
+        f64::from(self.region.crystal_hz()) / self.clocks_per_frame as f64
+    }
+
+    fn set_chip_profiling(&mut self, enabled: bool) -> bool {
+        self.profiler.set_enabled(enabled);
+        true
+    }
+
+    fn chip_times(&self) -> Vec<(&'static str, Duration)> {
+        self.profiler.times()
+    }
+
     fn reset(&mut self) {
         self.cpu_mut().reset();
     }
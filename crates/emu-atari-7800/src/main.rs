@@ -11,12 +11,14 @@ use std::sync::Arc;
 use std::time::{Duration, Instant};
 
 use emu_core::Cpu;
+use emu_core::mcp::MachineMcp;
 use emu_core::renderer::Renderer;
 use emu_atari_7800::{
     Atari7800, Atari7800Config, Atari7800Region, capture,
     controller_map::{self, Atari7800Input},
     maria,
 };
+use emu_atari_7800::mcp::McpServer;
 use muda::{Menu, MenuEvent, MenuId, MenuItem, PredefinedMenuItem, Submenu};
 use winit::application::ApplicationHandler;
 use winit::event::{ElementState, WindowEvent};
@@ -43,9 +45,12 @@ const FRAME_DURATION_PAL: Duration = Duration::from_micros(20_000);
 struct CliArgs {
     rom_path: Option<PathBuf>,
     headless: bool,
+    bench: bool,
     frames: u32,
     screenshot_path: Option<PathBuf>,
     region: Atari7800Region,
+    mcp: bool,
+    script_path: Option<PathBuf>,
 }
 
 fn print_usage() {
@@ -55,8 +60,11 @@ fn print_usage() {
     eprintln!("  --rom <file>         Atari 7800 cartridge ROM file");
     eprintln!("  --region <ntsc|pal>  Video region (default: ntsc)");
     eprintln!("  --headless           Run without a window");
+    eprintln!("  --bench              Run --frames frames flat out and report speed");
     eprintln!("  --frames <n>         Number of frames in headless mode [default: 200]");
     eprintln!("  --screenshot <file>  Save a PNG screenshot (headless)");
+    eprintln!("  --mcp                Run as MCP server (JSON-RPC over stdio)");
+    eprintln!("  --script <file>      Run a JSON script file (headless batch mode)");
 }
 
 fn print_usage_and_exit(code: i32) -> ! {
@@ -90,9 +98,12 @@ fn parse_args_from(args: &[String]) -> Result<Option<CliArgs>, String> {
     let mut cli = CliArgs {
         rom_path: None,
         headless: false,
+        bench: false,
         frames: 200,
         screenshot_path: None,
         region: Atari7800Region::Ntsc,
+        mcp: false,
+        script_path: None,
     };
 
     let mut i = 1;
@@ -104,6 +115,9 @@ fn parse_args_from(args: &[String]) -> Result<Option<CliArgs>, String> {
             "--headless" => {
                 cli.headless = true;
             }
+            "--bench" => {
+                cli.bench = true;
+            }
             "--frames" => {
                 i += 1;
                 let value = args
@@ -129,6 +143,12 @@ fn parse_args_from(args: &[String]) -> Result<Option<CliArgs>, String> {
                     _ => return Err(format!("Invalid value for --region: {value}")),
                 };
             }
+            "--mcp" => {
+                cli.mcp = true;
+            }
+            "--script" => {
+                cli.script_path = Some(next_option_value(args, &mut i, "--script")?);
+            }
             "--help" | "-h" => return Ok(None),
             other => {
                 return Err(format!("Unknown argument: {other}"));
@@ -141,6 +161,10 @@ fn parse_args_from(args: &[String]) -> Result<Option<CliArgs>, String> {
         cli.headless = true;
     }
 
+    if cli.mcp && cli.script_path.is_some() {
+        return Err("--mcp and --script are mutually exclusive".to_string());
+    }
+
     Ok(Some(cli))
 }
 
@@ -164,6 +188,12 @@ fn run_headless(cli: &CliArgs) {
     }
 }
 
+/// Time `--frames` frames with no pacing and print the benchmark report.
+fn run_bench(cli: &CliArgs) {
+    let mut system = make_system(cli);
+    print!("{}", emu_core::bench::run(&mut system, cli.frames));
+}
+
 // ---------------------------------------------------------------------------
 // Native menus (muda)
 // ---------------------------------------------------------------------------
@@ -441,6 +471,22 @@ fn make_system(cli: &CliArgs) -> Atari7800 {
     }
 }
 
+// ---------------------------------------------------------------------------
+// MCP / script mode
+// ---------------------------------------------------------------------------
+
+fn run_mcp(cli: &CliArgs) {
+    let mut server = McpServer::new(MachineMcp::new("emu-atari-7800", make_system(cli)));
+    if let Some(ref path) = cli.script_path {
+        if let Err(e) = server.run_script(path) {
+            eprintln!("Script error: {e}");
+            process::exit(1);
+        }
+    } else {
+        server.run();
+    }
+}
+
 // ---------------------------------------------------------------------------
 // Entry point
 // ---------------------------------------------------------------------------
@@ -448,6 +494,16 @@ fn make_system(cli: &CliArgs) -> Atari7800 {
 fn main() {
     let cli = parse_args();
 
+    if cli.mcp || cli.script_path.is_some() {
+        run_mcp(&cli);
+        return;
+    }
+
+    if cli.bench {
+        run_bench(&cli);
+        return;
+    }
+
     if cli.headless {
         run_headless(&cli);
         return;
@@ -522,6 +578,19 @@ mod tests {
         assert!(cli.headless);
     }
 
+    #[test]
+    fn cli_parser_reads_script_and_rejects_mcp_conflict() {
+        let cli = parse_cli(&["emu-atari-7800", "--script", "demo.json"])
+            .expect("parse should succeed")
+            .expect("help was not requested");
+        assert_eq!(cli.script_path, Some(PathBuf::from("demo.json")));
+        assert!(!cli.mcp);
+
+        let conflict = parse_cli(&["emu-atari-7800", "--mcp", "--script", "demo.json"])
+            .expect_err("mcp/script conflict should fail");
+        assert!(conflict.contains("--mcp and --script are mutually exclusive"));
+    }
+
     #[test]
     fn cli_parser_rejects_unknown_args() {
         let result = parse_cli(&["emu-atari-7800", "--bogus"]);
@@ -0,0 +1,73 @@
+//! MCP (Model Context Protocol) server for the Atari 7800 emulator.
+//!
+//! Serves the generic `MachineMcp` tools. Run with `--mcp` for MCP mode
+//! or `--script` for batch mode.
+
+use emu_core::Instruction;
+use emu_core::mcp::{self, MachineMcp, McpMachine};
+
+use crate::{Atari7800, Atari7800Region};
+
+pub type McpServer = mcp::McpServer<MachineMcp<Atari7800>>;
+
+impl McpMachine for Atari7800 {
+    fn frame_rate(&self) -> u32 {
+        match self.region {
+            Atari7800Region::Ntsc => 60,
+            Atari7800Region::Pal => 50,
+        }
+    }
+
+    /// RAM and cartridge (read without bank switching); chip registers
+    /// read as $FF.
+    fn disassemble(&self, address: u32) -> Instruction {
+        emu_disasm::mos6502::disassemble(
+            |addr| {
+                if addr >= 0x4000 {
+                    self.bus.cart.read_pure(addr)
+                } else {
+                    self.peek(addr).unwrap_or(0xFF)
+                }
+            },
+            address,
+        )
+    }
+
+    fn peek(&self, address: u16) -> Option<u8> {
+        let (ram, offset) = ram_slot(address)?;
+        Some(match ram {
+            Ram::ZeroPage => self.bus.ram_zp[offset],
+            Ram::Stack => self.bus.ram_stack[offset],
+            Ram::Main => self.bus.ram_main[offset],
+        })
+    }
+
+    fn poke(&mut self, address: u16, value: u8) -> bool {
+        let Some((ram, offset)) = ram_slot(address) else {
+            return false;
+        };
+        match ram {
+            Ram::ZeroPage => self.bus.ram_zp[offset] = value,
+            Ram::Stack => self.bus.ram_stack[offset] = value,
+            Ram::Main => self.bus.ram_main[offset] = value,
+        }
+        true
+    }
+}
+
+/// The three separate RAM blocks on the 7800 bus.
+enum Ram {
+    ZeroPage,
+    Stack,
+    Main,
+}
+
+/// Decode an address to a RAM block and offset, following the bus map.
+fn ram_slot(address: u16) -> Option<(Ram, usize)> {
+    match address {
+        0x0040..=0x00FF => Some((Ram::ZeroPage, usize::from(address - 0x40))),
+        0x0140..=0x01FF => Some((Ram::Stack, usize::from(address - 0x140))),
+        0x1800..=0x3FFF => Some((Ram::Main, usize::from((address - 0x1800) & 0x0FFF))),
+        _ => None,
+    }
+}
@@ -88,45 +88,23 @@ impl Atari800xlBusInner {
         false
     }
 
-    /// Read from the 130XE extended bank for ANTIC DMA.
+    /// Read memory as the CPU currently sees it, without side effects.
     ///
-    /// PORTB bit 5 = 0 means ANTIC sees extended bank.
-    pub fn antic_read_extended(&self, addr: u16) -> Option<u8> {
-        if !self.model.has_extended_banking() {
-            return None;
-        }
-        if !(0x4000..=0x7FFF).contains(&addr) {
-            return None;
-        }
+    /// Follows PORTB banking for RAM, extended RAM, cartridge, BASIC, OS
+    /// and self-test ROM. Chip registers at $D000-$D7FF read as $FF.
+    #[must_use]
+    pub fn peek(&self, addr: u16) -> u8 {
         let portb = self.effective_portb();
-        // Bit 5 = 0 means ANTIC sees extended bank
-        if portb & 0x20 != 0 {
-            return None; // ANTIC sees main RAM
-        }
-        let bank = ((portb >> 2) & 0x03) as usize;
-        let offset = (addr - 0x4000) as usize;
-        let idx = bank * 16384 + offset;
-        self.extended_ram.get(idx).copied()
-    }
-}
-
-/// Thin wrapper that implements `emu_core::Bus`.
-pub struct Atari800xlBus<'a>(pub &'a mut Atari800xlBusInner);
-
-impl Bus for Atari800xlBus<'_> {
-    fn read(&mut self, addr: u32) -> ReadResult {
-        let addr = addr as u16;
-        let portb = self.0.effective_portb();
-        let has_xl = self.0.model.has_xl_banking();
+        let has_xl = self.model.has_xl_banking();
         let os_rom_enabled = !has_xl || portb & 0x01 != 0;
         let basic_enabled = has_xl && portb & 0x02 == 0; // Bit 1 = 0 means BASIC on
         let self_test = has_xl && portb & 0x80 == 0; // Bit 7 = 0 means self-test on
 
-        let data = match addr {
+        match addr {
             // $0000-$3FFF: always base RAM (within ram_size)
             0x0000..=0x3FFF => {
-                if (addr as usize) < self.0.ram_size {
-                    self.0.ram[addr as usize]
+                if (addr as usize) < self.ram_size {
+                    self.ram[addr as usize]
                 } else {
                     0xFF
                 }
@@ -134,10 +112,10 @@ impl Bus for Atari800xlBus<'_> {
 
             // $4000-$7FFF: base RAM, extended bank (130XE), or unmapped
             0x4000..=0x4FFF => {
-                if let Some(val) = self.0.read_extended(addr - 0x4000, portb) {
+                if let Some(val) = self.read_extended(addr - 0x4000, portb) {
                     val
-                } else if (addr as usize) < self.0.ram_size {
-                    self.0.ram[addr as usize]
+                } else if (addr as usize) < self.ram_size {
+                    self.ram[addr as usize]
                 } else {
                     0xFF
                 }
@@ -146,16 +124,16 @@ impl Bus for Atari800xlBus<'_> {
             // $5000-$57FF: self-test ROM (XL+), extended bank, or RAM
             0x5000..=0x57FF => {
                 if self_test && os_rom_enabled {
-                    if let Some(ref os) = self.0.os_rom {
+                    if let Some(ref os) = self.os_rom {
                         let offset = (addr - 0x5000 + 0x1000) as usize;
                         os.get(offset).copied().unwrap_or(0xFF)
                     } else {
-                        self.0.ram[addr as usize]
+                        self.ram[addr as usize]
                     }
-                } else if let Some(val) = self.0.read_extended(addr - 0x4000, portb) {
+                } else if let Some(val) = self.read_extended(addr - 0x4000, portb) {
                     val
-                } else if (addr as usize) < self.0.ram_size {
-                    self.0.ram[addr as usize]
+                } else if (addr as usize) < self.ram_size {
+                    self.ram[addr as usize]
                 } else {
                     0xFF
                 }
@@ -163,10 +141,10 @@ impl Bus for Atari800xlBus<'_> {
 
             // $5800-$7FFF: extended bank (130XE) or base RAM
             0x5800..=0x7FFF => {
-                if let Some(val) = self.0.read_extended(addr - 0x4000, portb) {
+                if let Some(val) = self.read_extended(addr - 0x4000, portb) {
                     val
-                } else if (addr as usize) < self.0.ram_size {
-                    self.0.ram[addr as usize]
+                } else if (addr as usize) < self.ram_size {
+                    self.ram[addr as usize]
                 } else {
                     0xFF
                 }
@@ -174,51 +152,96 @@ impl Bus for Atari800xlBus<'_> {
 
             // $8000-$9FFF: cartridge (16KB) or RAM
             0x8000..=0x9FFF => {
-                if let Some(ref cart) = self.0.cart
+                if let Some(ref cart) = self.cart
                     && cart.covers(addr)
                 {
-                    return ReadResult::new(cart.read(addr));
+                    return cart.read(addr);
                 }
-                self.0.ram[addr as usize]
+                self.ram[addr as usize]
             }
 
             // $A000-$BFFF: cartridge, BASIC ROM, or RAM
             0xA000..=0xBFFF => {
-                if let Some(ref cart) = self.0.cart
+                if let Some(ref cart) = self.cart
                     && cart.covers(addr)
                 {
-                    return ReadResult::new(cart.read(addr));
+                    return cart.read(addr);
                 }
                 if basic_enabled
-                    && let Some(ref basic) = self.0.basic_rom
+                    && let Some(ref basic) = self.basic_rom
                 {
                     let offset = (addr - 0xA000) as usize;
-                    return ReadResult::new(
-                        basic.get(offset).copied().unwrap_or(0xFF),
-                    );
+                    return basic.get(offset).copied().unwrap_or(0xFF);
                 }
-                self.0.ram[addr as usize]
+                self.ram[addr as usize]
             }
 
             // $C000-$CFFF: OS ROM or RAM
             0xC000..=0xCFFF => {
                 if os_rom_enabled
-                    && let Some(ref os) = self.0.os_rom
+                    && let Some(ref os) = self.os_rom
                 {
                     let offset = (addr - 0xC000) as usize;
-                    return ReadResult::new(
-                        os.get(offset).copied().unwrap_or(0xFF),
-                    );
+                    return os.get(offset).copied().unwrap_or(0xFF);
                 }
-                self.0.ram[addr as usize]
+                self.ram[addr as usize]
             }
 
+            // $D000-$D7FF: chip registers and unmapped space
+            0xD000..=0xD7FF => 0xFF,
+
+            // $D800-$FFFF: OS ROM or RAM
+            0xD800..=0xFFFF => {
+                if os_rom_enabled
+                    && let Some(ref os) = self.os_rom
+                {
+                    // OS ROM file maps $C000-$FFFF continuously.
+                    let offset = (addr - 0xC000) as usize;
+                    return os.get(offset).copied().unwrap_or(0xFF);
+                }
+                self.ram[addr as usize]
+            }
+        }
+    }
+
+    /// Whether the CPU currently sees the OS ROM at $C000-$FFFF.
+    #[must_use]
+    pub fn os_rom_mapped(&self) -> bool {
+        self.os_rom.is_some() && (!self.model.has_xl_banking() || self.effective_portb() & 0x01 != 0)
+    }
+
+    /// Read from the 130XE extended bank for ANTIC DMA.
+    ///
+    /// PORTB bit 5 = 0 means ANTIC sees extended bank.
+    pub fn antic_read_extended(&self, addr: u16) -> Option<u8> {
+        if !self.model.has_extended_banking() {
+            return None;
+        }
+        if !(0x4000..=0x7FFF).contains(&addr) {
+            return None;
+        }
+        let portb = self.effective_portb();
+        // Bit 5 = 0 means ANTIC sees extended bank
+        if portb & 0x20 != 0 {
+            return None; // ANTIC sees main RAM
+        }
+        let bank = ((portb >> 2) & 0x03) as usize;
+        let offset = (addr - 0x4000) as usize;
+        let idx = bank * 16384 + offset;
+        self.extended_ram.get(idx).copied()
+    }
+}
+
+/// Thin wrapper that implements `emu_core::Bus`.
+pub struct Atari800xlBus<'a>(pub &'a mut Atari800xlBusInner);
+
+impl Bus for Atari800xlBus<'_> {
+    fn read(&mut self, addr: u32) -> ReadResult {
+        let addr = addr as u16;
+        let data = match addr {
             // $D000-$D0FF: GTIA
             0xD000..=0xD0FF => self.0.gtia.read(addr as u8),
 
-            // $D100-$D1FF: unmapped
-            0xD100..=0xD1FF => 0xFF,
-
             // $D200-$D2FF: POKEY
             0xD200..=0xD2FF => self.0.pokey.read(addr as u8),
 
@@ -228,22 +251,8 @@ impl Bus for Atari800xlBus<'_> {
             // $D400-$D4FF: ANTIC
             0xD400..=0xD4FF => self.0.antic.read(addr as u8),
 
-            // $D500-$D7FF: unmapped
-            0xD500..=0xD7FF => 0xFF,
-
-            // $D800-$FFFF: OS ROM or RAM
-            0xD800..=0xFFFF => {
-                if os_rom_enabled
-                    && let Some(ref os) = self.0.os_rom
-                {
-                    // OS ROM file maps $C000-$FFFF continuously.
-                    let offset = (addr - 0xC000) as usize;
-                    return ReadResult::new(
-                        os.get(offset).copied().unwrap_or(0xFF),
-                    );
-                }
-                self.0.ram[addr as usize]
-            }
+            // RAM, ROM, cartridge and unmapped space
+            _ => self.0.peek(addr),
         };
 
         ReadResult::new(data)
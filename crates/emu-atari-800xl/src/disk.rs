@@ -0,0 +1,283 @@
+//! ATR disk images and the SIO patch that serves them.
+//!
+//! There are no serial bus peripherals, so disk access is handled at the
+//! OS level instead: when the CPU is about to enter the OS `SIOV` entry
+//! point ($E459), the request in the device control block at $0300 is
+//! carried out directly and the call returns. Drive 1 answers from the
+//! inserted image; every other device times out at once, as if nothing
+//! were plugged in.
+//!
+//! An ATR file is a 16-byte header followed by the sectors. The first
+//! three (boot) sectors are always 128 bytes; the rest use the sector
+//! size from the header.
+
+use std::ops::Range;
+
+use crate::Atari800xl;
+
+/// OS serial I/O entry point, the same on every OS revision.
+const SIOV: u16 = 0xE459;
+/// Device control block fields.
+const DDEVIC: usize = 0x0300;
+const DUNIT: usize = 0x0301;
+const DCOMND: usize = 0x0302;
+const DSTATS: usize = 0x0303;
+const DBUFLO: usize = 0x0304;
+const DBYTLO: usize = 0x0308;
+const DAUX1: usize = 0x030A;
+/// Serial bus ID of drive 1.
+const DRIVE_1: u8 = 0x31;
+
+/// SIO completion codes.
+const STATUS_OK: u8 = 0x01;
+const STATUS_TIMEOUT: u8 = 0x8A;
+const STATUS_NAK: u8 = 0x8B;
+const STATUS_DEVICE_ERROR: u8 = 0x90;
+
+const ATR_MAGIC: [u8; 2] = [0x96, 0x02];
+const ATR_HEADER_SIZE: usize = 16;
+const BOOT_SECTORS: usize = 3;
+const BOOT_SECTOR_SIZE: usize = 128;
+
+/// An ATR disk image.
+#[derive(Debug, Clone)]
+pub struct Atr {
+    sector_size: usize,
+    /// Sector data, without the header.
+    data: Vec<u8>,
+}
+
+impl Atr {
+    /// Parse an ATR image.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the header is missing or the sector size is
+    /// not 128 or 256 bytes.
+    pub fn parse(data: &[u8]) -> Result<Self, String> {
+        if data.len() < ATR_HEADER_SIZE || data[..2] != ATR_MAGIC {
+            return Err("Not an ATR disk image".to_string());
+        }
+        let sector_size = usize::from(u16::from_le_bytes([data[4], data[5]]));
+        if !matches!(sector_size, 128 | 256) {
+            return Err(format!("Unsupported ATR sector size: {sector_size} bytes"));
+        }
+        // Image size in 16-byte paragraphs, split across two fields.
+        let paragraphs =
+            usize::from(u16::from_le_bytes([data[2], data[3]])) | usize::from(data[6]) << 16;
+        let size = (paragraphs * 16).min(data.len() - ATR_HEADER_SIZE);
+        Ok(Self {
+            sector_size,
+            data: data[ATR_HEADER_SIZE..ATR_HEADER_SIZE + size].to_vec(),
+        })
+    }
+
+    /// Size of sectors after the boot sectors.
+    #[must_use]
+    pub fn sector_size(&self) -> usize {
+        self.sector_size
+    }
+
+    /// Byte range of a sector (numbered from 1), if the image has it.
+    fn sector(&self, sector: u16) -> Option<Range<usize>> {
+        let index = usize::from(sector).checked_sub(1)?;
+        let (start, len) = if index < BOOT_SECTORS {
+            (index * BOOT_SECTOR_SIZE, BOOT_SECTOR_SIZE)
+        } else {
+            (
+                BOOT_SECTORS * BOOT_SECTOR_SIZE + (index - BOOT_SECTORS) * self.sector_size,
+                self.sector_size,
+            )
+        };
+        (start + len <= self.data.len()).then_some(start..start + len)
+    }
+}
+
+impl Atari800xl {
+    /// Insert a disk into drive 1. The OS boots from it at cold start.
+    pub fn insert_atr(&mut self, atr: Atr) {
+        self.disk = Some(atr);
+    }
+
+    /// Remove the disk from drive 1, returning it with any sectors the
+    /// program wrote.
+    pub fn eject_disk(&mut self) -> Option<Atr> {
+        self.disk.take()
+    }
+
+    /// Serve the SIO call if the CPU is about to enter `SIOV` with the OS
+    /// ROM mapped.
+    pub(crate) fn check_sio_trap(&mut self) {
+        if self.cpu.regs.pc != SIOV
+            || !self.cpu.is_instruction_complete()
+            || !self.bus.os_rom_mapped()
+        {
+            return;
+        }
+
+        // SIO returns the status in DSTATS and Y, with N set on error.
+        let status = self.sio_request();
+        self.bus.ram[DSTATS] = status;
+        self.cpu.regs.y = status;
+        self.cpu.regs.p.update_nz(status);
+        self.return_from_subroutine();
+    }
+
+    /// Carry out the request in the device control block.
+    fn sio_request(&mut self) -> u8 {
+        let ram = &mut self.bus.ram;
+        let word = |ram: &[u8], at: usize| u16::from_le_bytes([ram[at], ram[at + 1]]);
+        let device = ram[DDEVIC].wrapping_add(ram[DUNIT]).wrapping_sub(1);
+        let Some(disk) = self.disk.as_mut().filter(|_| device == DRIVE_1) else {
+            return STATUS_TIMEOUT;
+        };
+        let buffer = word(ram, DBUFLO);
+        let length = usize::from(word(ram, DBYTLO));
+        let aux = word(ram, DAUX1);
+        let address = |i: usize| usize::from(buffer.wrapping_add(i as u16));
+
+        match ram[DCOMND] {
+            // Read sector.
+            b'R' => {
+                let Some(sector) = disk.sector(aux) else {
+                    return STATUS_DEVICE_ERROR;
+                };
+                for (i, &byte) in disk.data[sector].iter().take(length).enumerate() {
+                    ram[address(i)] = byte;
+                }
+                STATUS_OK
+            }
+            // Write sector, with or without verify.
+            b'W' | b'P' => {
+                let Some(sector) = disk.sector(aux) else {
+                    return STATUS_DEVICE_ERROR;
+                };
+                for (i, byte) in disk.data[sector].iter_mut().take(length).enumerate() {
+                    *byte = ram[address(i)];
+                }
+                STATUS_OK
+            }
+            // Drive status: motor on, density, no errors.
+            b'S' => {
+                let flags = if disk.sector_size == 256 { 0x30 } else { 0x10 };
+                for (i, byte) in [flags, 0xFF, 0xE0, 0x00]
+                    .into_iter()
+                    .take(length)
+                    .enumerate()
+                {
+                    ram[address(i)] = byte;
+                }
+                STATUS_OK
+            }
+            _ => STATUS_NAK,
+        }
+    }
+
+    /// Return from the subroutine the CPU is in, as RTS would.
+    fn return_from_subroutine(&mut self) {
+        let sp = self.cpu.regs.s;
+        let lo = self.bus.ram[0x0100 | usize::from(sp.wrapping_add(1))];
+        let hi = self.bus.ram[0x0100 | usize::from(sp.wrapping_add(2))];
+        self.cpu.regs.s = sp.wrapping_add(2);
+        self.cpu
+            .force_pc(u16::from_le_bytes([lo, hi]).wrapping_add(1));
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{Atari8bitModel, Atari800xlConfig, Atari800xlRegion};
+
+    /// Single-density image whose sector `n` is filled with `n`.
+    fn image(sectors: u16) -> Vec<u8> {
+        let size = usize::from(sectors) * 128;
+        let paragraphs = (size / 16) as u16;
+        let mut data = vec![0; ATR_HEADER_SIZE];
+        data[..2].copy_from_slice(&ATR_MAGIC);
+        data[2..4].copy_from_slice(&paragraphs.to_le_bytes());
+        data[4..6].copy_from_slice(&128u16.to_le_bytes());
+        for sector in 1..=sectors {
+            data.extend(std::iter::repeat_n(sector as u8, 128));
+        }
+        data
+    }
+
+    /// A machine with a blank OS ROM, about to enter `SIOV` from $2000.
+    fn at_siov() -> Atari800xl {
+        let mut system = Atari800xl::new(&Atari800xlConfig {
+            model: Atari8bitModel::A800XL,
+            rom_data: None,
+            os_rom: Some(vec![0; 16384]),
+            basic_rom: None,
+            region: Atari800xlRegion::Ntsc,
+            basic_enabled: false,
+        })
+        .expect("machine");
+        system.cpu.regs.s = 0xFD;
+        system.bus.ram[0x01FE] = 0x02;
+        system.bus.ram[0x01FF] = 0x20;
+        system.cpu.force_pc(SIOV);
+        system
+    }
+
+    fn request(system: &mut Atari800xl, device: u8, command: u8, sector: u16) {
+        let ram = &mut system.bus.ram;
+        ram[DDEVIC] = device;
+        ram[DUNIT] = 1;
+        ram[DCOMND] = command;
+        ram[DBUFLO..DBUFLO + 2].copy_from_slice(&0x0600u16.to_le_bytes());
+        ram[DBYTLO..DBYTLO + 2].copy_from_slice(&128u16.to_le_bytes());
+        ram[DAUX1..DAUX1 + 2].copy_from_slice(&sector.to_le_bytes());
+    }
+
+    #[test]
+    fn parses_sectors_after_the_header() {
+        let atr = Atr::parse(&image(5)).expect("ATR");
+        assert_eq!(atr.sector_size(), 128);
+        assert_eq!(atr.sector(1), Some(0..128));
+        assert_eq!(atr.sector(5), Some(512..640));
+        assert_eq!(atr.sector(6), None);
+        assert_eq!(atr.sector(0), None);
+        assert!(Atr::parse(&[0; 32]).is_err());
+    }
+
+    #[test]
+    fn sio_reads_sectors_from_drive_1() {
+        let mut system = at_siov();
+        system.insert_atr(Atr::parse(&image(5)).expect("ATR"));
+        request(&mut system, DRIVE_1, b'R', 4);
+        system.check_sio_trap();
+
+        assert_eq!(system.bus.ram[DSTATS], STATUS_OK);
+        assert_eq!(system.cpu.regs.y, STATUS_OK);
+        assert!(system.bus.ram[0x0600..0x0680].iter().all(|&b| b == 4));
+        assert_eq!(system.cpu.regs.pc, 0x2003);
+        assert_eq!(system.cpu.regs.s, 0xFF);
+    }
+
+    #[test]
+    fn sio_writes_sectors_back_to_the_image() {
+        let mut system = at_siov();
+        system.insert_atr(Atr::parse(&image(5)).expect("ATR"));
+        system.bus.ram[0x0600..0x0680].fill(0xAB);
+        request(&mut system, DRIVE_1, b'W', 5);
+        system.check_sio_trap();
+        assert_eq!(system.cpu.regs.y, STATUS_OK);
+
+        let atr = system.eject_disk().expect("disk");
+        let sector = atr.sector(5).expect("sector 5");
+        assert!(atr.data[sector].iter().all(|&b| b == 0xAB));
+        let sector = atr.sector(4).expect("sector 4");
+        assert!(atr.data[sector].iter().all(|&b| b == 4));
+    }
+
+    #[test]
+    fn sio_times_out_for_missing_devices() {
+        let mut system = at_siov();
+        request(&mut system, DRIVE_1, b'R', 1);
+        system.check_sio_trap();
+        assert_eq!(system.cpu.regs.y, STATUS_TIMEOUT);
+        assert!(system.cpu.regs.p.is_set(mos_6502::flags::N));
+    }
+}
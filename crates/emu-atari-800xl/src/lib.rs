@@ -22,18 +22,27 @@ mod bus;
 pub mod capture;
 mod cartridge;
 mod config;
+mod disk;
 #[cfg(feature = "native")]
 pub mod input_map;
+#[cfg(feature = "native")]
+pub mod mcp;
+mod xex;
 
 pub use atari_antic as antic;
 pub use atari_gtia as gtia;
 pub use atari_pokey as pokey;
 pub use bus::Atari800xlBus;
 pub use config::{Atari800xlConfig, Atari800xlRegion, Atari8bitModel};
+pub use disk::Atr;
+pub use xex::{XexSegment, parse_xex};
+
+use std::time::Duration;
 
 use atari_antic::{Antic, AnticRegion, COLOUR_CLOCKS_PER_LINE};
 use atari_gtia::Gtia;
 use atari_pokey::Pokey;
+use emu_core::bench::ChipProfiler;
 use emu_core::{AudioFrame, Cpu, Machine, Observable, Tickable, Value};
 use mos_6502::Mos6502;
 use mos_pia_6520::Pia6520;
@@ -41,6 +50,9 @@ use mos_pia_6520::Pia6520;
 use crate::bus::Atari800xlBusInner;
 use crate::cartridge::Cartridge;
 
+/// Chips timed by the profiler, in the order the tick loop laps them.
+const CHIPS: &[&str] = &["atari-antic", "atari-gtia", "mos-6502", "atari-pokey"];
+
 /// Atari 800XL system.
 pub struct Atari800xl {
     /// 6502C CPU.
@@ -59,6 +71,10 @@ pub struct Atari800xl {
     dma_budget: u8,
     /// CPU cycle counter within the current scan line (0-113).
     line_cycle: u16,
+    /// Disk in drive 1, served by the SIO patch.
+    disk: Option<Atr>,
+    /// Per-chip tick timing, while benchmarking.
+    profiler: ChipProfiler,
 }
 
 impl Atari800xl {
@@ -150,6 +166,8 @@ impl Atari800xl {
             clocks_per_frame,
             dma_budget: 0,
             line_cycle: 0,
+            disk: None,
+            profiler: ChipProfiler::new(CHIPS),
         })
     }
 
@@ -293,6 +311,7 @@ impl Atari800xl {
     fn process_scan_line(&mut self) {
         // ANTIC reads from RAM directly (sees RAM underneath ROMs).
         let result = self.bus.antic.process_line(&self.bus.ram);
+        self.profiler.lap(0);
 
         // Feed player/missile data to GTIA if PM DMA occurred.
         if result.pm_dma {
@@ -312,6 +331,7 @@ impl Atari800xl {
             result.playfield_width,
             result.mode,
         );
+        self.profiler.lap(1);
 
         // Set DMA budget for this line.
         self.dma_budget = result.dma_cycles;
@@ -333,6 +353,7 @@ impl Atari800xl {
 impl Tickable for Atari800xl {
     fn tick(&mut self) {
         self.master_clock += 1;
+        self.profiler.start();
 
         // At the start of each scan line (every 228 colour clocks).
         if self.master_clock.is_multiple_of(u64::from(COLOUR_CLOCKS_PER_LINE)) {
@@ -348,10 +369,14 @@ impl Tickable for Atari800xl {
                 && !self.bus.antic.wsync_halt()
             {
                 self.cpu.tick(&mut Atari800xlBus(&mut self.bus));
+                // Check for the SIO patch after each CPU tick
+                self.check_sio_trap();
             }
+            self.profiler.lap(2);
 
             // POKEY always ticks.
             self.bus.pokey.tick();
+            self.profiler.lap(3);
 
             // POKEY IRQ or PIA IRQ -> CPU IRQ.
             if self.bus.pokey.irq_pending() || self.bus.pia.irq_pending() {
@@ -412,10 +437,61 @@ impl Machine for Atari800xl {
         self.bus.pokey.take_buffer().into_iter().map(|s| [s, s]).collect()
     }
 
+    fn audio_sample_rate(&self) -> u32 {
+        self.bus.pokey.sample_rate()
+    }
+
+    fn set_audio_sample_rate(&mut self, rate: u32) -> bool {
+        self.bus.pokey.set_sample_rate(rate);
+        true
+    }
+
+    fn set_audio_rate_adjust(&mut self, ratio: f64) {
+        self.bus.pokey.set_rate_adjust(ratio);
+    }
+
+    fn audio_channels(&self) -> Vec<&'static str> {
+        Pokey::CHANNELS.to_vec()
+    }
+
+    fn set_channel_mute(&mut self, mask: u32) {
+        self.bus.pokey.set_channel_mute(mask as u8);
+    }
+
+    fn channel_mute(&self) -> u32 {
+        u32::from(self.bus.pokey.channel_mute())
+    }
+
+    fn set_stem_capture(&mut self, enabled: bool) {
+        self.bus.pokey.set_stems(enabled);
+    }
+
+    fn take_audio_stems(&mut self) -> Vec<Vec<f32>> {
+        self.bus.pokey.take_stems()
+    }
+
     fn frame_count(&self) -> u64 {
         self.frame_count()
     }
 
+    fn cpu_clock_hz(&self) -> f64 {
+        f64::from(self.region.cpu_hz())
+    }
+
+    #[allow(clippy::cast_precision_loss)]
+    fn frame_rate_hz(&self) -> f64 {
+        f64::from(self.region.crystal_hz()) / self.clocks_per_frame as f64
+    }
+
+    fn set_chip_profiling(&mut self, enabled: bool) -> bool {
+        self.profiler.set_enabled(enabled);
+        true
+    }
+
+    fn chip_times(&self) -> Vec<(&'static str, Duration)> {
+        self.profiler.times()
+    }
+
     fn reset(&mut self) {
         self.cpu_mut().reset();
     }
@@ -11,12 +11,14 @@ use std::sync::Arc;
 use std::time::{Duration, Instant};
 
 use emu_core::Cpu;
+use emu_core::mcp::MachineMcp;
 use emu_core::renderer::Renderer;
 use emu_atari_800xl::{
-    Atari800xl, Atari800xlConfig, Atari800xlRegion, Atari8bitModel, capture,
+    Atari800xl, Atari800xlConfig, Atari800xlRegion, Atari8bitModel, Atr, capture,
     input_map::{self, Atari800xlInput},
     gtia,
 };
+use emu_atari_800xl::mcp::McpServer;
 use muda::{Menu, MenuEvent, MenuId, MenuItem, PredefinedMenuItem, Submenu};
 use winit::application::ApplicationHandler;
 use winit::event::{ElementState, WindowEvent};
@@ -35,6 +37,9 @@ const FRAME_DURATION_NTSC: Duration = Duration::from_micros(16_639);
 /// Frame duration for ~50 Hz PAL.
 const FRAME_DURATION_PAL: Duration = Duration::from_micros(20_000);
 
+/// Frames the OS gets to boot before an XEX is loaded.
+const XEX_BOOT_FRAMES: u32 = 120;
+
 // ---------------------------------------------------------------------------
 // CLI argument parsing
 // ---------------------------------------------------------------------------
@@ -44,12 +49,17 @@ struct CliArgs {
     rom_path: Option<PathBuf>,
     os_rom_path: Option<PathBuf>,
     basic_rom_path: Option<PathBuf>,
+    xex_path: Option<PathBuf>,
+    atr_path: Option<PathBuf>,
     headless: bool,
+    bench: bool,
     frames: u32,
     screenshot_path: Option<PathBuf>,
     region: Atari800xlRegion,
     model: Atari8bitModel,
     basic_enabled: bool,
+    mcp: bool,
+    script_path: Option<PathBuf>,
 }
 
 fn print_usage() {
@@ -59,13 +69,18 @@ fn print_usage() {
     eprintln!("  --rom <file>           Cartridge ROM (8KB or 16KB)");
     eprintln!("  --os-rom <file>        OS ROM (~16KB)");
     eprintln!("  --basic-rom <file>     BASIC ROM (8KB)");
+    eprintln!("  --xex <file>           Binary program, loaded once the OS has booted");
+    eprintln!("  --atr <file>           Disk image to boot from drive 1");
     eprintln!("  --model <model>        Computer model (default: 800xl)");
     eprintln!("                         Options: 400, 800, 600xl, 800xl, 65xe, 130xe");
     eprintln!("  --region <ntsc|pal>    Video region (default: ntsc)");
     eprintln!("  --basic                Enable BASIC ROM at startup");
     eprintln!("  --headless             Run without a window");
+    eprintln!("  --bench                Run --frames frames flat out and report speed");
     eprintln!("  --frames <n>           Number of frames in headless mode [default: 200]");
     eprintln!("  --screenshot <file>    Save a PNG screenshot (headless)");
+    eprintln!("  --mcp                  Run as MCP server (JSON-RPC over stdio)");
+    eprintln!("  --script <file>        Run a JSON script file (headless batch mode)");
 }
 
 fn print_usage_and_exit(code: i32) -> ! {
@@ -100,12 +115,17 @@ fn parse_args_from(args: &[String]) -> Result<Option<CliArgs>, String> {
         rom_path: None,
         os_rom_path: None,
         basic_rom_path: None,
+        xex_path: None,
+        atr_path: None,
         headless: false,
+        bench: false,
         frames: 200,
         screenshot_path: None,
         region: Atari800xlRegion::Ntsc,
         model: Atari8bitModel::A800XL,
         basic_enabled: false,
+        mcp: false,
+        script_path: None,
     };
 
     let mut i = 1;
@@ -120,12 +140,21 @@ fn parse_args_from(args: &[String]) -> Result<Option<CliArgs>, String> {
             "--basic-rom" => {
                 cli.basic_rom_path = Some(next_option_value(args, &mut i, "--basic-rom")?);
             }
+            "--xex" => {
+                cli.xex_path = Some(next_option_value(args, &mut i, "--xex")?);
+            }
+            "--atr" => {
+                cli.atr_path = Some(next_option_value(args, &mut i, "--atr")?);
+            }
             "--basic" => {
                 cli.basic_enabled = true;
             }
             "--headless" => {
                 cli.headless = true;
             }
+            "--bench" => {
+                cli.bench = true;
+            }
             "--frames" => {
                 i += 1;
                 let value = args
@@ -167,6 +196,12 @@ fn parse_args_from(args: &[String]) -> Result<Option<CliArgs>, String> {
                     _ => return Err(format!("Invalid model: {value}. Options: 400, 800, 600xl, 800xl, 65xe, 130xe")),
                 };
             }
+            "--mcp" => {
+                cli.mcp = true;
+            }
+            "--script" => {
+                cli.script_path = Some(next_option_value(args, &mut i, "--script")?);
+            }
             "--help" | "-h" => return Ok(None),
             other => {
                 return Err(format!("Unknown argument: {other}"));
@@ -179,6 +214,10 @@ fn parse_args_from(args: &[String]) -> Result<Option<CliArgs>, String> {
         cli.headless = true;
     }
 
+    if cli.mcp && cli.script_path.is_some() {
+        return Err("--mcp and --script are mutually exclusive".to_string());
+    }
+
     Ok(Some(cli))
 }
 
@@ -202,6 +241,12 @@ fn run_headless(cli: &CliArgs) {
     }
 }
 
+/// Time `--frames` frames with no pacing and print the benchmark report.
+fn run_bench(cli: &CliArgs) {
+    let mut system = make_system(cli);
+    print!("{}", emu_core::bench::run(&mut system, cli.frames));
+}
+
 // ---------------------------------------------------------------------------
 // Native menus (muda)
 // ---------------------------------------------------------------------------
@@ -505,7 +550,22 @@ fn make_system_result(cli: &CliArgs) -> Result<Atari800xl, String> {
         region: cli.region,
         basic_enabled: cli.basic_enabled,
     };
-    Atari800xl::new(&config)
+    let mut system = Atari800xl::new(&config)?;
+
+    if let Some(ref path) = cli.atr_path {
+        let data = std::fs::read(path)
+            .map_err(|e| format!("Failed to read ATR file {}: {e}", path.display()))?;
+        system.insert_atr(Atr::parse(&data)?);
+    }
+    if let Some(ref path) = cli.xex_path {
+        let data = std::fs::read(path)
+            .map_err(|e| format!("Failed to read XEX file {}: {e}", path.display()))?;
+        for _ in 0..XEX_BOOT_FRAMES {
+            system.run_frame();
+        }
+        system.load_xex(&data)?;
+    }
+    Ok(system)
 }
 
 fn make_system(cli: &CliArgs) -> Atari800xl {
@@ -520,6 +580,12 @@ fn make_system(cli: &CliArgs) -> Atari800xl {
             if let Some(ref path) = cli.basic_rom_path {
                 eprintln!("Loaded BASIC ROM: {}", path.display());
             }
+            if let Some(ref path) = cli.atr_path {
+                eprintln!("Inserted ATR: {}", path.display());
+            }
+            if let Some(ref path) = cli.xex_path {
+                eprintln!("Loaded XEX: {}", path.display());
+            }
             system
         }
         Err(e) => {
@@ -529,6 +595,22 @@ fn make_system(cli: &CliArgs) -> Atari800xl {
     }
 }
 
+// ---------------------------------------------------------------------------
+// MCP / script mode
+// ---------------------------------------------------------------------------
+
+fn run_mcp(cli: &CliArgs) {
+    let mut server = McpServer::new(MachineMcp::new("emu-atari-800xl", make_system(cli)));
+    if let Some(ref path) = cli.script_path {
+        if let Err(e) = server.run_script(path) {
+            eprintln!("Script error: {e}");
+            process::exit(1);
+        }
+    } else {
+        server.run();
+    }
+}
+
 // ---------------------------------------------------------------------------
 // Entry point
 // ---------------------------------------------------------------------------
@@ -536,6 +618,16 @@ fn make_system(cli: &CliArgs) -> Atari800xl {
 fn main() {
     let cli = parse_args();
 
+    if cli.mcp || cli.script_path.is_some() {
+        run_mcp(&cli);
+        return;
+    }
+
+    if cli.bench {
+        run_bench(&cli);
+        return;
+    }
+
     if cli.headless {
         run_headless(&cli);
         return;
@@ -609,6 +701,23 @@ mod tests {
         assert_eq!(cli.screenshot_path, Some(PathBuf::from("out.png")));
     }
 
+    #[test]
+    fn cli_parser_reads_xex_and_atr() {
+        let cli = parse_cli(&[
+            "emu-atari-800xl",
+            "--xex",
+            "game.xex",
+            "--atr",
+            "dos.atr",
+        ])
+        .expect("parse should succeed")
+        .expect("help was not requested");
+
+        assert_eq!(cli.xex_path, Some(PathBuf::from("game.xex")));
+        assert_eq!(cli.atr_path, Some(PathBuf::from("dos.atr")));
+        assert!(parse_cli(&["emu-atari-800xl", "--xex"]).is_err());
+    }
+
     #[test]
     fn cli_parser_promotes_screenshot_to_headless() {
         let cli = parse_cli(&[
@@ -624,6 +733,19 @@ mod tests {
         assert!(cli.headless);
     }
 
+    #[test]
+    fn cli_parser_reads_script_and_rejects_mcp_conflict() {
+        let cli = parse_cli(&["emu-atari-800xl", "--script", "demo.json"])
+            .expect("parse should succeed")
+            .expect("help was not requested");
+        assert_eq!(cli.script_path, Some(PathBuf::from("demo.json")));
+        assert!(!cli.mcp);
+
+        let conflict = parse_cli(&["emu-atari-800xl", "--mcp", "--script", "demo.json"])
+            .expect_err("mcp/script conflict should fail");
+        assert!(conflict.contains("--mcp and --script are mutually exclusive"));
+    }
+
     #[test]
     fn cli_parser_rejects_unknown_args() {
         let result = parse_cli(&["emu-atari-800xl", "--bogus"]);
@@ -0,0 +1,39 @@
+//! MCP (Model Context Protocol) server for the Atari 800XL emulator.
+//!
+//! Serves the generic `MachineMcp` tools. Run with `--mcp` for MCP mode
+//! or `--script` for batch mode.
+
+use emu_core::Instruction;
+use emu_core::mcp::{self, MachineMcp, McpMachine};
+
+use crate::{Atari800xl, Atari800xlRegion};
+
+pub type McpServer = mcp::McpServer<MachineMcp<Atari800xl>>;
+
+impl McpMachine for Atari800xl {
+    fn frame_rate(&self) -> u32 {
+        match self.region {
+            Atari800xlRegion::Ntsc => 60,
+            Atari800xlRegion::Pal => 50,
+        }
+    }
+
+    fn disassemble(&self, address: u32) -> Instruction {
+        emu_disasm::mos6502::disassemble(|addr| self.bus.peek(addr), address)
+    }
+
+    /// Base RAM, including RAM under the OS and BASIC ROMs.
+    fn peek(&self, address: u16) -> Option<u8> {
+        let address = usize::from(address);
+        (address < self.bus.ram_size).then(|| self.bus.ram[address])
+    }
+
+    fn poke(&mut self, address: u16, value: u8) -> bool {
+        let address = usize::from(address);
+        let is_ram = address < self.bus.ram_size;
+        if is_ram {
+            self.bus.ram[address] = value;
+        }
+        is_ram
+    }
+}
@@ -0,0 +1,190 @@
+//! Atari DOS binary load files (XEX).
+//!
+//! A file is a list of segments: a start and an inclusive end address
+//! (little-endian), then the bytes in between. The first segment is
+//! preceded by $FFFF, and later ones may be too. DOS calls the address in
+//! INITAD ($02E2) after any segment that sets it, and jumps to RUNAD
+//! ($02E0) once the whole file is in.
+
+use emu_core::{Bus, Tickable};
+
+use crate::{Atari800xl, Atari800xlBus};
+
+const RUNAD: usize = 0x02E0;
+const INITAD: usize = 0x02E2;
+/// Where INIT routines return to. The CPU never gets there otherwise.
+const RETURN_TRAP: u16 = 0xFFFF;
+/// Longest an INIT routine may run before the load gives up.
+const INIT_LIMIT_FRAMES: u64 = 300;
+
+/// One contiguous block of a binary load file.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct XexSegment {
+    pub start: u16,
+    pub data: Vec<u8>,
+}
+
+/// Split a binary load file into its segments.
+///
+/// # Errors
+///
+/// Returns an error if the file does not start with $FFFF, a segment
+/// ends before it starts, or the file is cut short.
+pub fn parse_xex(data: &[u8]) -> Result<Vec<XexSegment>, String> {
+    if !data.starts_with(&[0xFF, 0xFF]) {
+        return Err("Not an Atari binary load file".to_string());
+    }
+    let word = |at: usize| {
+        data.get(at..at + 2)
+            .map(|b| u16::from_le_bytes([b[0], b[1]]))
+            .ok_or_else(|| "Binary load file is truncated".to_string())
+    };
+
+    let mut segments = Vec::new();
+    let mut at = 0;
+    while at < data.len() {
+        if word(at)? == 0xFFFF {
+            at += 2;
+        }
+        let start = word(at)?;
+        let end = word(at + 2)?;
+        if end < start {
+            return Err(format!(
+                "Segment ${start:04X}-${end:04X} ends before it starts"
+            ));
+        }
+        let len = usize::from(end - start) + 1;
+        let bytes = data
+            .get(at + 4..at + 4 + len)
+            .ok_or_else(|| format!("Segment ${start:04X}-${end:04X} is truncated"))?;
+        segments.push(XexSegment {
+            start,
+            data: bytes.to_vec(),
+        });
+        at += 4 + len;
+    }
+    Ok(segments)
+}
+
+impl Atari800xl {
+    /// Load a binary file into memory and start it, as DOS would. Each
+    /// segment is written through the bus, INIT routines run as they are
+    /// set, and the CPU then jumps to RUNAD, or to the first segment if
+    /// the file never sets it.
+    ///
+    /// Programs that use the OS expect it to have finished booting, so
+    /// run a few seconds of frames first.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the file is malformed or an INIT routine does
+    /// not return within five seconds.
+    pub fn load_xex(&mut self, data: &[u8]) -> Result<(), String> {
+        let segments = parse_xex(data)?;
+        self.finish_instruction();
+
+        self.bus.ram[RUNAD..RUNAD + 2].fill(0);
+        for segment in &segments {
+            self.bus.ram[INITAD..INITAD + 2].fill(0);
+            for (offset, &byte) in segment.data.iter().enumerate() {
+                let addr = segment.start.wrapping_add(offset as u16);
+                Atari800xlBus(&mut self.bus).write(u32::from(addr), byte);
+            }
+            let init = u16::from_le_bytes([self.bus.ram[INITAD], self.bus.ram[INITAD + 1]]);
+            if init != 0 {
+                self.call(init)?;
+            }
+        }
+
+        let run = match u16::from_le_bytes([self.bus.ram[RUNAD], self.bus.ram[RUNAD + 1]]) {
+            0 => segments[0].start,
+            run => run,
+        };
+        self.cpu.force_pc(run);
+        Ok(())
+    }
+
+    /// Run until the CPU is between instructions (or a frame has passed,
+    /// if it has jammed).
+    fn finish_instruction(&mut self) {
+        let limit = self.master_clock + self.clocks_per_frame;
+        while !self.cpu.is_instruction_complete() && self.master_clock < limit {
+            self.tick();
+        }
+    }
+
+    /// Call the subroutine at `addr` and run the machine until it returns.
+    fn call(&mut self, addr: u16) -> Result<(), String> {
+        let sp = self.cpu.regs.s;
+        let [lo, hi] = RETURN_TRAP.wrapping_sub(1).to_le_bytes();
+        self.bus.ram[0x0100 | usize::from(sp)] = hi;
+        self.bus.ram[0x0100 | usize::from(sp.wrapping_sub(1))] = lo;
+        self.cpu.regs.s = sp.wrapping_sub(2);
+        self.cpu.force_pc(addr);
+
+        let limit = self.master_clock + INIT_LIMIT_FRAMES * self.clocks_per_frame;
+        while self.master_clock < limit {
+            self.tick();
+            if self.cpu.regs.pc == RETURN_TRAP
+                && self.cpu.regs.s == sp
+                && self.cpu.is_instruction_complete()
+            {
+                return Ok(());
+            }
+        }
+        Err(format!("INIT routine at ${addr:04X} did not return"))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{Atari8bitModel, Atari800xlConfig, Atari800xlRegion};
+
+    fn segment(out: &mut Vec<u8>, start: u16, data: &[u8]) {
+        out.extend_from_slice(&start.to_le_bytes());
+        out.extend_from_slice(&(start + data.len() as u16 - 1).to_le_bytes());
+        out.extend_from_slice(data);
+    }
+
+    #[test]
+    fn splits_segments_with_optional_markers() {
+        let mut file = vec![0xFF, 0xFF];
+        segment(&mut file, 0x2000, &[1, 2, 3]);
+        file.extend_from_slice(&[0xFF, 0xFF]);
+        segment(&mut file, 0x3000, &[4]);
+        segment(&mut file, 0x3001, &[5]);
+
+        let segments = parse_xex(&file).expect("segments");
+        assert_eq!(segments.len(), 3);
+        assert_eq!(segments[0].start, 0x2000);
+        assert_eq!(segments[0].data, [1, 2, 3]);
+        assert_eq!(segments[2].start, 0x3001);
+
+        assert!(parse_xex(&file[..file.len() - 1]).is_err());
+        assert!(parse_xex(&[0x00, 0x20]).is_err());
+    }
+
+    #[test]
+    fn load_runs_init_routines_then_jumps_to_runad() {
+        let mut file = vec![0xFF, 0xFF];
+        // INIT: LDA #$42 / STA $0700 / RTS
+        segment(&mut file, 0x0600, &[0xA9, 0x42, 0x8D, 0x00, 0x07, 0x60]);
+        segment(&mut file, 0x02E2, &[0x00, 0x06]);
+        segment(&mut file, 0x02E0, &[0x10, 0x06]);
+
+        let mut system = Atari800xl::new(&Atari800xlConfig {
+            model: Atari8bitModel::A800XL,
+            rom_data: None,
+            os_rom: None,
+            basic_rom: None,
+            region: Atari800xlRegion::Ntsc,
+            basic_enabled: false,
+        })
+        .expect("machine");
+        system.load_xex(&file).expect("load");
+
+        assert_eq!(system.bus.ram[0x0700], 0x42);
+        assert_eq!(system.cpu.regs.pc, 0x0610);
+    }
+}
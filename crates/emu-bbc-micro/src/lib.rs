@@ -14,7 +14,13 @@
 
 #![allow(clippy::cast_possible_truncation)]
 
-use emu_core::{AudioFrame, Bus, Cpu, Machine, ReadResult};
+#[cfg(feature = "native")]
+pub mod mcp;
+
+use std::time::Duration;
+
+use emu_core::bench::ChipProfiler;
+use emu_core::{AudioFrame, Bus, Cpu, Machine, Observable, ReadResult, RegisterLog, Value};
 use mos_6502::Mos6502;
 use mos_via_6522::Via6522;
 use motorola_6845::Crtc6845;
@@ -319,12 +325,17 @@ impl Bus for BbcBus {
 // BBC Micro system
 // ---------------------------------------------------------------------------
 
+/// Chips timed by the profiler, in the order the tick loop laps them.
+const CHIPS: &[&str] = &["motorola-6845", "video ula", "mos-6502", "ti-sn76489"];
+
 /// BBC Micro Model B.
 pub struct BbcMicro {
     cpu: Mos6502,
     bus: BbcBus,
     master_clock: u64,
     frame_count: u64,
+    /// Per-chip tick timing, while benchmarking.
+    profiler: ChipProfiler,
 }
 
 impl BbcMicro {
@@ -339,7 +350,13 @@ impl BbcMicro {
         let reset_hi = bus.mos_rom.get(0x3FFD).copied().unwrap_or(0);
         cpu.regs.pc = u16::from(reset_lo) | (u16::from(reset_hi) << 8);
 
-        Self { cpu, bus, master_clock: 0, frame_count: 0 }
+        Self {
+            cpu,
+            bus,
+            master_clock: 0,
+            frame_count: 0,
+            profiler: ChipProfiler::new(CHIPS),
+        }
     }
 
     /// Insert a sideways ROM (BASIC, DFS, etc.) into a bank slot.
@@ -357,20 +374,27 @@ impl BbcMicro {
         let scanlines = 312u16;
 
         for line in 0..scanlines {
+            // The profiler samples whole scanlines here.
+            self.profiler.start();
+
             // Tick CRTC for one scanline
             for _ in 0..crtc_chars_per_line {
                 self.bus.crtc.tick();
             }
+            self.profiler.lap(0);
 
             // Render if in visible area
             if line < FB_HEIGHT as u16 {
                 self.bus.render_scanline();
             }
+            self.profiler.lap(1);
 
             // CPU: 128 ticks per line at 2 MHz (64 µs × 2 MHz)
             for _ in 0..128 {
                 self.cpu.tick(&mut self.bus);
+                self.profiler.lap(2);
                 self.bus.psg.tick();
+                self.profiler.lap(3);
             }
 
             // VSYNC → System VIA CA1 interrupt
@@ -416,6 +440,28 @@ impl BbcMicro {
     }
 }
 
+impl Observable for BbcMicro {
+    fn query(&self, path: &str) -> Option<Value> {
+        if let Some(rest) = path.strip_prefix("cpu.") {
+            self.cpu.query(rest)
+        } else {
+            match path {
+                "master_clock" => Some(self.master_clock.into()),
+                "frame_count" => Some(self.frame_count.into()),
+                _ => self.cpu.query(path),
+            }
+        }
+    }
+
+    fn query_paths(&self) -> &'static [&'static str] {
+        &[
+            "cpu.<6502_paths>",
+            "master_clock",
+            "frame_count",
+        ]
+    }
+}
+
 impl Machine for BbcMicro {
     fn run_frame(&mut self) {
         self.run_frame();
@@ -437,10 +483,71 @@ impl Machine for BbcMicro {
         self.take_audio_buffer().into_iter().map(|s| [s, s]).collect()
     }
 
+    fn audio_sample_rate(&self) -> u32 {
+        self.bus.psg.sample_rate()
+    }
+
+    fn set_audio_sample_rate(&mut self, rate: u32) -> bool {
+        self.bus.psg.set_sample_rate(rate);
+        true
+    }
+
+    fn set_audio_rate_adjust(&mut self, ratio: f64) {
+        self.bus.psg.set_rate_adjust(ratio);
+    }
+
+    fn audio_channels(&self) -> Vec<&'static str> {
+        Sn76489::CHANNELS.to_vec()
+    }
+
+    fn set_channel_mute(&mut self, mask: u32) {
+        self.bus.psg.set_channel_mute(mask as u8);
+    }
+
+    fn channel_mute(&self) -> u32 {
+        u32::from(self.bus.psg.channel_mute())
+    }
+
+    fn set_stem_capture(&mut self, enabled: bool) {
+        self.bus.psg.set_stems(enabled);
+    }
+
+    fn take_audio_stems(&mut self) -> Vec<Vec<f32>> {
+        self.bus.psg.take_stems()
+    }
+
+    fn start_register_log(&mut self) -> bool {
+        self.bus.psg.start_register_log();
+        true
+    }
+
+    fn stop_register_log(&mut self) -> Option<RegisterLog> {
+        let frame = CYCLES_PER_FRAME;
+        self.bus.psg.stop_register_log().map(|log| log.with_frame_cycles(frame))
+    }
+
     fn frame_count(&self) -> u64 {
         self.frame_count()
     }
 
+    fn cpu_clock_hz(&self) -> f64 {
+        2_000_000.0
+    }
+
+    #[allow(clippy::cast_precision_loss)]
+    fn frame_rate_hz(&self) -> f64 {
+        2_000_000.0 / CYCLES_PER_FRAME as f64
+    }
+
+    fn set_chip_profiling(&mut self, enabled: bool) -> bool {
+        self.profiler.set_enabled(enabled);
+        true
+    }
+
+    fn chip_times(&self) -> Vec<(&'static str, Duration)> {
+        self.profiler.times()
+    }
+
     fn reset(&mut self) {
         self.cpu_mut().reset();
     }
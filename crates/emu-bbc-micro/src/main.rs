@@ -12,8 +12,10 @@ use std::sync::Arc;
 use std::time::{Duration, Instant};
 
 use emu_core::Cpu;
+use emu_core::mcp::MachineMcp;
 use emu_core::renderer::Renderer;
 use emu_bbc_micro::BbcMicro;
+use emu_bbc_micro::mcp::McpServer;
 use muda::{Menu, MenuEvent, MenuId, MenuItem, PredefinedMenuItem, Submenu};
 use winit::application::ApplicationHandler;
 use winit::event::{ElementState, WindowEvent};
@@ -117,8 +119,11 @@ struct CliArgs {
     mos_path: Option<PathBuf>,
     basic_path: Option<PathBuf>,
     headless: bool,
+    bench: bool,
     frames: u32,
     screenshot_path: Option<PathBuf>,
+    mcp: bool,
+    script_path: Option<PathBuf>,
 }
 
 fn print_usage() {
@@ -128,8 +133,11 @@ fn print_usage() {
     eprintln!("  --mos <file>         MOS ROM file (16 KB, required)");
     eprintln!("  --basic <file>       BASIC ROM file (16 KB, optional)");
     eprintln!("  --headless           Run without a window");
+    eprintln!("  --bench              Run --frames frames flat out and report speed");
     eprintln!("  --frames <n>         Number of frames in headless mode [default: 200]");
     eprintln!("  --screenshot <file>  Save a PNG screenshot (headless)");
+    eprintln!("  --mcp                Run as MCP server (JSON-RPC over stdio)");
+    eprintln!("  --script <file>      Run a JSON script file (headless batch mode)");
 }
 
 fn print_usage_and_exit(code: i32) -> ! {
@@ -164,8 +172,11 @@ fn parse_args_from(args: &[String]) -> Result<Option<CliArgs>, String> {
         mos_path: None,
         basic_path: None,
         headless: false,
+        bench: false,
         frames: 200,
         screenshot_path: None,
+        mcp: false,
+        script_path: None,
     };
 
     let mut i = 1;
@@ -180,6 +191,9 @@ fn parse_args_from(args: &[String]) -> Result<Option<CliArgs>, String> {
             "--headless" => {
                 cli.headless = true;
             }
+            "--bench" => {
+                cli.bench = true;
+            }
             "--frames" => {
                 i += 1;
                 let value = args
@@ -193,6 +207,12 @@ fn parse_args_from(args: &[String]) -> Result<Option<CliArgs>, String> {
             "--screenshot" => {
                 cli.screenshot_path = Some(next_option_value(args, &mut i, "--screenshot")?);
             }
+            "--mcp" => {
+                cli.mcp = true;
+            }
+            "--script" => {
+                cli.script_path = Some(next_option_value(args, &mut i, "--script")?);
+            }
             "--help" | "-h" => return Ok(None),
             other => {
                 return Err(format!("Unknown argument: {other}"));
@@ -205,6 +225,10 @@ fn parse_args_from(args: &[String]) -> Result<Option<CliArgs>, String> {
         cli.headless = true;
     }
 
+    if cli.mcp && cli.script_path.is_some() {
+        return Err("--mcp and --script are mutually exclusive".to_string());
+    }
+
     Ok(Some(cli))
 }
 
@@ -252,6 +276,12 @@ fn run_headless(cli: &CliArgs) {
     }
 }
 
+/// Time `--frames` frames with no pacing and print the benchmark report.
+fn run_bench(cli: &CliArgs) {
+    let mut system = make_system(cli);
+    print!("{}", emu_core::bench::run(&mut system, cli.frames));
+}
+
 // ---------------------------------------------------------------------------
 // Native menus (muda)
 // ---------------------------------------------------------------------------
@@ -488,6 +518,22 @@ fn make_system(cli: &CliArgs) -> BbcMicro {
     system
 }
 
+// ---------------------------------------------------------------------------
+// MCP / script mode
+// ---------------------------------------------------------------------------
+
+fn run_mcp(cli: &CliArgs) {
+    let mut server = McpServer::new(MachineMcp::new("emu-bbc-micro", make_system(cli)));
+    if let Some(ref path) = cli.script_path {
+        if let Err(e) = server.run_script(path) {
+            eprintln!("Script error: {e}");
+            process::exit(1);
+        }
+    } else {
+        server.run();
+    }
+}
+
 // ---------------------------------------------------------------------------
 // Entry point
 // ---------------------------------------------------------------------------
@@ -495,6 +541,16 @@ fn make_system(cli: &CliArgs) -> BbcMicro {
 fn main() {
     let cli = parse_args();
 
+    if cli.mcp || cli.script_path.is_some() {
+        run_mcp(&cli);
+        return;
+    }
+
+    if cli.bench {
+        run_bench(&cli);
+        return;
+    }
+
     if cli.headless {
         run_headless(&cli);
         return;
@@ -0,0 +1,52 @@
+//! MCP (Model Context Protocol) server for the BBC Micro emulator.
+//!
+//! Serves the generic `MachineMcp` tools. Run with `--mcp` for MCP mode
+//! or `--script` for batch mode.
+
+use emu_core::Instruction;
+use emu_core::mcp::{self, MachineMcp, McpMachine};
+
+use crate::BbcMicro;
+
+pub type McpServer = mcp::McpServer<MachineMcp<BbcMicro>>;
+
+impl McpMachine for BbcMicro {
+    fn frame_rate(&self) -> u32 {
+        50
+    }
+
+    /// RAM, the paged sideways ROM and the MOS ROM; FRED, JIM and SHEILA
+    /// read as $FF.
+    fn disassemble(&self, address: u32) -> Instruction {
+        let bus = &self.bus;
+        emu_disasm::mos6502::disassemble(
+            |addr| match addr {
+                0x0000..=0x7FFF => bus.ram[usize::from(addr)],
+                0x8000..=0xBFFF => bus
+                    .sideways_roms
+                    .get(usize::from(bus.rom_bank))
+                    .and_then(|rom| rom.get(usize::from(addr - 0x8000)).copied())
+                    .unwrap_or(0xFF),
+                0xFC00..=0xFEFF => 0xFF,
+                0xC000..=0xFFFF => bus
+                    .mos_rom
+                    .get(usize::from(addr - 0xC000))
+                    .copied()
+                    .unwrap_or(0xFF),
+            },
+            address,
+        )
+    }
+
+    fn peek(&self, address: u16) -> Option<u8> {
+        self.bus.ram.get(usize::from(address)).copied()
+    }
+
+    fn poke(&mut self, address: u16, value: u8) -> bool {
+        self.bus
+            .ram
+            .get_mut(usize::from(address))
+            .map(|byte| *byte = value)
+            .is_some()
+    }
+}
@@ -8,7 +8,7 @@
 
 #![allow(clippy::cast_possible_truncation)]
 
-use emu_core::{Bus, ReadResult};
+use emu_core::{Bus, ReadResult, SaveState, StateError, StateReader, StateWriter};
 
 use mos_sid_6581::Sid6581;
 
@@ -31,6 +31,10 @@ pub struct C64Bus {
     pub cia1: Cia6526,
     pub cia2: Cia6526,
     pub keyboard: KeyboardMatrix,
+    /// Joystick ports 1 and 2. Bits 0-4 = up, down, left, right, fire
+    /// (1 = pressed). Port 1 shares CIA1 port B with the keyboard rows,
+    /// port 2 shares port A with the column select.
+    pub joystick: [u8; 2],
     pub reu: Option<Reu>,
 }
 
@@ -50,6 +54,7 @@ impl C64Bus {
             cia1: Cia6526::new_with_tod(tod_divider),
             cia2: Cia6526::new_with_tod(tod_divider),
             keyboard: KeyboardMatrix::new(),
+            joystick: [0; 2],
             reu: None,
         }
     }
@@ -79,13 +84,15 @@ impl Bus for C64Bus {
                         0x0D => self.cia1.read_icr_and_clear(),
                         0x08 => self.cia1.read_tod_10ths_and_release(),
                         0x0B => self.cia1.read_tod_hours_and_latch(),
-                        _ => {
-                            if reg == 0x01 {
-                                let col_select = self.cia1.port_a_output();
-                                self.cia1.external_b = self.keyboard.scan(col_select);
-                            }
-                            self.cia1.read(reg)
+                        // Joystick switches short lines to ground, so they
+                        // pull the port low even where the CIA drives it high.
+                        0x00 => self.cia1.read(reg) & !(self.joystick[1] & 0x1F),
+                        0x01 => {
+                            let col_select = self.cia1.port_a_output() & !(self.joystick[1] & 0x1F);
+                            self.cia1.external_b = self.keyboard.scan(col_select);
+                            self.cia1.read(reg) & !(self.joystick[0] & 0x1F)
                         }
+                        _ => self.cia1.read(reg),
                     }
                 }
                 0xDD00..=0xDDFF => {
@@ -183,6 +190,30 @@ impl Bus for C64Bus {
     }
 }
 
+impl SaveState for C64Bus {
+    fn save_state(&self, w: &mut StateWriter) {
+        self.memory.save_state(w);
+        self.vic.save_state(w);
+        self.sid.save_state(w);
+        self.cia1.save_state(w);
+        self.cia2.save_state(w);
+        self.keyboard.save_state(w);
+        w.write_bytes(&self.joystick);
+        w.write_option(self.reu.as_ref());
+    }
+
+    fn load_state(&mut self, r: &mut StateReader<'_>) -> Result<(), StateError> {
+        self.memory.load_state(r)?;
+        self.vic.load_state(r)?;
+        self.sid.load_state(r)?;
+        self.cia1.load_state(r)?;
+        self.cia2.load_state(r)?;
+        self.keyboard.load_state(r)?;
+        r.read_bytes_into(&mut self.joystick)?;
+        r.read_option(self.reu.as_mut(), "an REU")
+    }
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -254,4 +285,18 @@ mod tests {
         let val = bus.read(0xDE00).data;
         assert_eq!(val, 0xFF);
     }
+
+    #[test]
+    fn joysticks_pull_cia1_ports_low() {
+        let mut bus = make_bus();
+        bus.write(0xDC02, 0xFF); // Port A: all output, as the KERNAL leaves it
+        bus.write(0xDC00, 0x7F);
+        assert_eq!(bus.read(0xDC00).data, 0x7F);
+        assert_eq!(bus.read(0xDC01).data, 0xFF);
+
+        bus.joystick[1] = 0x11; // Port 2: up + fire
+        assert_eq!(bus.read(0xDC00).data, 0x6E);
+        bus.joystick[0] = 0x08; // Port 1: right
+        assert_eq!(bus.read(0xDC01).data, 0xF7);
+    }
 }
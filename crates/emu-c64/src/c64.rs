@@ -15,15 +15,26 @@
 
 #![allow(clippy::cast_possible_truncation)]
 
-use emu_core::{AudioFrame, Bus, Cpu, Machine, Observable, Tickable, Value};
+use std::time::Duration;
+
+use emu_core::bench::ChipProfiler;
+use emu_core::breakpoint::Debuggable;
+use emu_core::crt::Signal;
+use emu_core::movie::{Movie, MovieError};
+use emu_core::trace::{self, Tracer};
+use emu_core::{
+    AudioFrame, Bus, BusAccess, Cpu, LoggingBus, Machine, NetplayInput, Observable, RegisterLog,
+    SaveState, StateError, StateReader, StateWriter, Tickable, Value,
+};
 use mos_6502::Mos6502;
+use mos_vic_ii::VicModel;
 
 use crate::bus::C64Bus;
-use crate::config::C64Config;
+use crate::config::{C64Config, C64Model};
 use crate::d64::D64;
 use crate::drive1541::Drive1541;
 use crate::iec::IecBus;
-use crate::input::{C64Key, InputQueue};
+use crate::input::{C64Key, InputEvent, InputQueue};
 use crate::memory::C64Memory;
 use crate::tape::C64TapeDeck;
 
@@ -35,6 +46,9 @@ const CYCLES_PER_FRAME: u64 = 312 * 63;
 /// We trap here when device == 1 (datasette) to deliver tape blocks directly.
 const TAPE_LOAD_ADDR: u16 = 0xF49E;
 
+/// Machine tag in save-state headers.
+const STATE_TAG: &str = "c64";
+
 /// C64 system.
 pub struct C64 {
     cpu: Mos6502,
@@ -53,8 +67,27 @@ pub struct C64 {
     drive: Option<Drive1541>,
     /// IEC serial bus connecting C64 to the drive.
     iec: IecBus,
+    /// Input movie being recorded, if any.
+    movie: Option<Movie>,
+    /// Media a movie being played loads, as (frame, slot, image).
+    movie_media: Vec<(u64, String, Vec<u8>)>,
+    /// CPU bus cycles logged for watchpoints, while logging is enabled.
+    bus_log: Option<Vec<BusAccess>>,
+    /// Instruction trace being recorded, if any.
+    tracer: Option<Tracer>,
+    /// Per-chip tick timing, while benchmarking.
+    profiler: ChipProfiler,
 }
 
+/// Chips timed by the profiler, in the order the tick loop laps them.
+const CHIPS: &[&str] = &[
+    "mos-vic-ii",
+    "mos-6502",
+    "mos-cia-6526",
+    "mos-sid-6581",
+    "1541 drive",
+];
+
 impl C64 {
     /// Create a new C64 from the given configuration.
     #[must_use]
@@ -101,6 +134,11 @@ impl C64 {
             tape: C64TapeDeck::new(),
             drive,
             iec: IecBus::new(),
+            movie: None,
+            movie_media: Vec::new(),
+            bus_log: None,
+            tracer: None,
+            profiler: ChipProfiler::new(CHIPS),
         }
     }
 
@@ -111,6 +149,12 @@ impl C64 {
     ///
     /// Returns the number of CPU cycles executed during the frame.
     pub fn run_frame(&mut self) -> u64 {
+        self.load_movie_media();
+        if let Some(movie) = &mut self.movie {
+            for event in self.input_queue.due(self.frame_count) {
+                movie.push_event(self.frame_count, event.key.name(), event.pressed);
+            }
+        }
         self.input_queue
             .process(self.frame_count, &mut self.bus.keyboard);
         self.frame_count += 1;
@@ -124,6 +168,10 @@ impl C64 {
             }
         }
 
+        if let Some(movie) = &mut self.movie {
+            movie.push_frame(self.bus.vic.framebuffer());
+        }
+
         self.master_clock - start_clock
     }
 
@@ -145,6 +193,19 @@ impl C64 {
         self.bus.vic.framebuffer_height()
     }
 
+    /// The VIC-II's composite video signal, for the CRT filter.
+    #[must_use]
+    pub fn video_signal(&self) -> Signal {
+        self.bus.vic.model().signal()
+    }
+
+    /// The 320×200 display window inside the border, as x, y, width and
+    /// height in the framebuffer.
+    #[must_use]
+    pub fn display_window(&self) -> (u32, u32, u32, u32) {
+        self.bus.vic.display_window()
+    }
+
     /// Reference to the CPU.
     #[must_use]
     pub fn cpu(&self) -> &Mos6502 {
@@ -179,6 +240,15 @@ impl C64 {
         self.frame_count
     }
 
+    /// The model this machine was built as.
+    #[must_use]
+    pub fn model(&self) -> C64Model {
+        match self.bus.vic.model() {
+            VicModel::Pal6569 => C64Model::C64Pal,
+            VicModel::Ntsc6567 => C64Model::C64Ntsc,
+        }
+    }
+
     /// Mutable reference to the timed input queue.
     pub fn input_queue(&mut self) -> &mut InputQueue {
         &mut self.input_queue
@@ -186,28 +256,68 @@ impl C64 {
 
     /// Press a key immediately.
     pub fn press_key(&mut self, key: C64Key) {
+        self.record_key(key, true);
         let (row, col) = key.matrix();
         self.bus.keyboard.set_key(row, col, true);
     }
 
     /// Release a key.
     pub fn release_key(&mut self, key: C64Key) {
+        self.record_key(key, false);
         let (row, col) = key.matrix();
         self.bus.keyboard.set_key(row, col, false);
     }
 
     /// Release all keys.
     pub fn release_all_keys(&mut self) {
+        for key in C64Key::ALL {
+            self.record_key(key, false);
+        }
         self.bus.keyboard.release_all();
     }
 
+    /// Set joystick port 1 or 2. Bits 0-4 = up, down, left, right, fire
+    /// (1 = pressed). Other ports are ignored.
+    pub fn set_joystick(&mut self, port: u8, state: u8) {
+        if let Some(joy) = self.bus.joystick.get_mut(usize::from(port).wrapping_sub(1)) {
+            *joy = state & 0x1F;
+        }
+    }
+
+    /// Log a key change to the movie being recorded.
+    ///
+    /// Keys pressed between frames take effect at the start of the next
+    /// frame, exactly like queued events stamped with the current count.
+    fn record_key(&mut self, key: C64Key, pressed: bool) {
+        if let Some(movie) = &mut self.movie {
+            movie.push_event(self.frame_count, key.name(), pressed);
+        }
+    }
+
     /// Take the SID audio output buffer (drains it).
     ///
-    /// Returns mono f32 samples in the range -1.0 to 1.0, at 48 kHz.
+    /// Returns mono f32 samples in the range -1.0 to 1.0, at the output
+    /// rate.
     pub fn take_audio_buffer(&mut self) -> Vec<f32> {
         self.bus.sid.take_buffer()
     }
 
+    /// Output sample rate of `take_audio_buffer`.
+    #[must_use]
+    pub fn audio_sample_rate(&self) -> u32 {
+        self.bus.sid.sample_rate()
+    }
+
+    /// Change the output sample rate (48 kHz by default).
+    pub fn set_audio_sample_rate(&mut self, rate: u32) {
+        self.bus.sid.set_sample_rate(rate);
+    }
+
+    /// Stretch the output rate for dynamic rate control.
+    pub fn set_audio_rate_adjust(&mut self, ratio: f64) {
+        self.bus.sid.set_rate_adjust(ratio);
+    }
+
     /// Number of audio samples pending in the SID buffer.
     #[must_use]
     pub fn audio_buffer_len(&self) -> usize {
@@ -225,6 +335,7 @@ impl C64 {
             .ok_or_else(|| "No 1541 drive (drive ROM not provided)".to_string())?;
         let d64 = D64::from_bytes(data)?;
         drive.insert_disk(d64);
+        self.record_media("disk", data);
         Ok(())
     }
 
@@ -251,7 +362,9 @@ impl C64 {
 
     /// Load a PRG file into memory.
     pub fn load_prg(&mut self, data: &[u8]) -> Result<u16, String> {
-        crate::prg::load_prg(&mut self.bus.memory, data)
+        let addr = crate::prg::load_prg(&mut self.bus.memory, data)?;
+        self.record_media("prg", data);
+        Ok(addr)
     }
 
     /// Load a CRT cartridge file.
@@ -267,6 +380,7 @@ impl C64 {
         let lo = self.bus.read(0xFFFC).data;
         let hi = self.bus.read(0xFFFD).data;
         self.cpu.regs.pc = u16::from(lo) | (u16::from(hi) << 8);
+        self.record_media("crt", data);
         Ok(name)
     }
 
@@ -278,9 +392,17 @@ impl C64 {
         let tap = crate::tap::C64TapFile::parse(data)?;
         let count = tap.blocks.len();
         self.tape.insert(tap);
+        self.record_media("tap", data);
         Ok(count)
     }
 
+    /// Log a loaded image to the movie being recorded.
+    fn record_media(&mut self, slot: &str, data: &[u8]) {
+        if let Some(movie) = &mut self.movie {
+            movie.add_media(slot, self.frame_count, data);
+        }
+    }
+
     /// Reference to the tape deck.
     #[must_use]
     pub fn tape(&self) -> &C64TapeDeck {
@@ -292,6 +414,186 @@ impl C64 {
         &mut self.tape
     }
 
+    /// Serialise the complete machine state, including the 1541 and its disk.
+    ///
+    /// The scripted input queue is host-side and not included.
+    #[must_use]
+    pub fn save_state(&self) -> Vec<u8> {
+        let mut w = StateWriter::with_header(STATE_TAG);
+        w.write_u64(self.master_clock);
+        w.write_u64(self.frame_count);
+        w.write_bool(self.cia2_nmi_prev);
+        self.cpu.save_state(&mut w);
+        self.bus.save_state(&mut w);
+        self.tape.save_state(&mut w);
+        w.write_option(self.drive.as_ref());
+        self.iec.save_state(&mut w);
+        w.into_bytes()
+    }
+
+    /// Restore a state produced by [`C64::save_state`].
+    ///
+    /// The machine must have the same model, cartridge, REU, drive and
+    /// tape. On error the machine is left partially restored and should
+    /// be reset.
+    pub fn load_state(&mut self, data: &[u8]) -> Result<(), StateError> {
+        let mut r = StateReader::with_header(data, STATE_TAG)?;
+        self.master_clock = r.read_u64()?;
+        self.frame_count = r.read_u64()?;
+        self.cia2_nmi_prev = r.read_bool()?;
+        self.cpu.load_state(&mut r)?;
+        self.bus.load_state(&mut r)?;
+        self.tape.load_state(&mut r)?;
+        r.read_option(self.drive.as_mut(), "a 1541 drive")?;
+        self.iec.load_state(&mut r)?;
+        r.finish()
+    }
+
+    /// Attach an instruction trace recorder (or detach with `None`).
+    /// Returns the one it replaces.
+    pub fn set_tracer(&mut self, tracer: Option<Tracer>) -> Option<Tracer> {
+        std::mem::replace(&mut self.tracer, tracer)
+    }
+
+    #[must_use]
+    pub fn tracer(&self) -> Option<&Tracer> {
+        self.tracer.as_ref()
+    }
+
+    pub fn tracer_mut(&mut self) -> Option<&mut Tracer> {
+        self.tracer.as_mut()
+    }
+
+    /// One CPU cycle, logging bus cycles for the debugger and the
+    /// tracer when either wants them.
+    fn tick_cpu(&mut self) {
+        let mark = self.bus_log.as_ref().map_or(0, Vec::len);
+        if let Some(log) = trace::cpu_bus_log(&mut self.bus_log, &mut self.tracer) {
+            self.cpu.tick(&mut LoggingBus::new(&mut self.bus, log));
+        } else {
+            self.cpu.tick(&mut self.bus);
+        }
+
+        if let Some(mut tracer) = self.tracer.take() {
+            let accesses = self.bus_log.as_deref().map_or(&[][..], |log| &log[mark..]);
+            tracer.observe(&*self, accesses, |pc| {
+                let memory = &self.bus.memory;
+                emu_disasm::mos6502::disassemble(|addr| memory.peek(addr), pc).bytes
+            });
+            self.tracer = Some(tracer);
+        }
+    }
+
+    /// Start recording an input movie.
+    ///
+    /// `config` must be the configuration this machine was built from; its
+    /// settings and ROM hashes go into the movie header. Recording from
+    /// frame 0 gives a power-on movie, otherwise the current state is
+    /// embedded. Live key changes and queued events are both recorded.
+    pub fn start_recording(&mut self, config: &C64Config) {
+        let mut movie = movie_header(config);
+        movie.start_frame = self.frame_count;
+        if self.frame_count != 0 {
+            movie.start_state = Some(self.save_state());
+        }
+        self.movie = Some(movie);
+    }
+
+    /// Stop recording and return the movie.
+    ///
+    /// Programs, tapes, disks and cartridges loaded while recording are
+    /// in its media list; load any needed from the start before recording.
+    pub fn stop_recording(&mut self) -> Option<Movie> {
+        self.movie.take()
+    }
+
+    /// Whether an input movie is being recorded.
+    #[must_use]
+    pub fn is_recording(&self) -> bool {
+        self.movie.is_some()
+    }
+
+    /// Set up playback of a movie.
+    ///
+    /// Checks the header against `config`, restores the start snapshot (or
+    /// requires a machine that has not run yet) and queues every event.
+    /// `media` holds (slot, file) pairs for what was loaded while recording
+    /// (`"prg"`, `"crt"`, `"tap"` or `"disk"`), one pair per file when a
+    /// slot took several; each is checked and loaded again at its recorded
+    /// frame. Run frames as normal afterwards, or use
+    /// [`Movie::verify`].
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the movie was recorded on a different setup or
+    /// with other media, names an unknown key, or its start snapshot cannot
+    /// be loaded.
+    pub fn play_movie(
+        &mut self,
+        movie: &Movie,
+        config: &C64Config,
+        media: &[(&str, &[u8])],
+    ) -> Result<(), MovieError> {
+        let header = movie_header(config);
+        movie.check_header(&header)?;
+        let loads = movie.media_loads(&header, media)?;
+        let events = movie
+            .events
+            .iter()
+            .map(|e| {
+                let key = C64Key::from_name(&e.input)
+                    .ok_or_else(|| MovieError::UnknownInput(e.input.clone()))?;
+                Ok(InputEvent {
+                    frame: e.frame,
+                    key,
+                    pressed: e.pressed,
+                })
+            })
+            .collect::<Result<Vec<_>, MovieError>>()?;
+        match &movie.start_state {
+            Some(state) => self.load_state(state)?,
+            None if self.frame_count != 0 => {
+                return Err(MovieError::NotAtStart {
+                    frame: self.frame_count,
+                });
+            }
+            None => {}
+        }
+        for event in events {
+            self.input_queue.push(event);
+        }
+        self.movie_media = loads
+            .into_iter()
+            .map(|(frame, index)| {
+                let (slot, data) = media[index];
+                (frame, slot.to_string(), data.to_vec())
+            })
+            .collect();
+        Ok(())
+    }
+
+    /// Load the movie media due at the start of this frame.
+    fn load_movie_media(&mut self) {
+        if self.movie_media.is_empty() {
+            return;
+        }
+        let frame = self.frame_count;
+        let (due, later) = std::mem::take(&mut self.movie_media)
+            .into_iter()
+            .partition::<Vec<_>, _>(|(at, _, _)| *at <= frame);
+        self.movie_media = later;
+        // Each image loaded when it was recorded, so it loads again.
+        for (_, slot, data) in due {
+            let _ = match slot.as_str() {
+                "prg" => self.load_prg(&data).map(drop),
+                "crt" => self.load_crt(&data).map(drop),
+                "tap" => self.load_tap(&data).map(drop),
+                "disk" => self.load_d64(&data),
+                _ => Ok(()),
+            };
+        }
+    }
+
     /// Check for and handle the ROM tape-loading trap.
     ///
     /// The kernal LOAD entry at $FFD5 jumps to $F49E. When the CPU reaches
@@ -381,9 +683,26 @@ impl C64 {
     }
 }
 
+/// Movie header describing a machine built from `config`.
+fn movie_header(config: &C64Config) -> Movie {
+    let mut movie = Movie::new(STATE_TAG)
+        .with_config("model", format!("{:?}", config.model))
+        .with_config("sid", format!("{:?}", config.sid_model))
+        .with_config("reu", config.reu_size.unwrap_or(0))
+        .with_config("drive", config.drive_rom.is_some())
+        .with_media("kernal", &config.kernal_rom)
+        .with_media("basic", &config.basic_rom)
+        .with_media("char", &config.char_rom);
+    if let Some(rom) = &config.drive_rom {
+        movie = movie.with_media("drive", rom);
+    }
+    movie
+}
+
 impl Tickable for C64 {
     fn tick(&mut self) {
         self.master_clock += 1;
+        self.profiler.start();
 
         // 1. VIC-II: advance beam, render 8 pixels, detect badline
         let memory = &self.bus.memory;
@@ -391,6 +710,7 @@ impl Tickable for C64 {
         let cpu_stalled = vic.tick(&|addr| memory.vic_read_by_addr(addr), &|off| {
             memory.colour_ram_read(off)
         });
+        self.profiler.lap(0);
 
         // 2. Check VIC-II raster IRQ → CPU IRQ
         if self.bus.vic.irq_active() {
@@ -399,23 +719,32 @@ impl Tickable for C64 {
 
         // 3. CPU: tick if not stalled by VIC-II badline
         if !cpu_stalled {
-            self.cpu.tick(&mut self.bus);
+            self.tick_cpu();
             // Check for tape loading trap after each CPU tick
             self.check_tape_trap();
         }
+        self.profiler.lap(1);
 
         // 4. CIA1: tick timer, check IRQ → CPU IRQ
         self.bus.cia1.tick();
 
-        // 4a. Real-time tape: tick the tape deck and feed edges to CIA1 FLAG.
-        // Motor is controlled by $01 bit 5 (active-low: 0 = motor on).
-        if self.tape.is_playing() && self.tape.has_raw_pulses() {
-            let port01 = self.bus.memory.ram[1];
+        // 4a. Datasette: PLAY or RECORD pulls the sense line ($01 bit 4)
+        // low. Motor is controlled by $01 bit 5 (active-low: 0 = motor on).
+        // Playback feeds edges to CIA1 FLAG; recording samples the write
+        // line ($01 bit 3).
+        let tape_active = self.tape.is_playing() || self.tape.is_recording();
+        self.bus.memory.set_cassette_switch(tape_active);
+        if tape_active {
+            let port01 = self.bus.memory.processor_port();
             self.tape.set_motor(port01 & 0x20 == 0);
-            if self.tape.tick() {
-                self.bus.cia1.set_flag(false); // Negative edge
-            } else {
-                self.bus.cia1.set_flag(true); // Release
+            if self.tape.is_recording() {
+                self.tape.write_tick(port01 & 0x08 != 0);
+            } else if self.tape.has_raw_pulses() {
+                if self.tape.tick() {
+                    self.bus.cia1.set_flag(false); // Negative edge
+                } else {
+                    self.bus.cia1.set_flag(true); // Release
+                }
             }
         }
 
@@ -430,9 +759,11 @@ impl Tickable for C64 {
             self.cpu.nmi();
         }
         self.cia2_nmi_prev = cia2_nmi_now;
+        self.profiler.lap(2);
 
         // 6. SID: tick oscillators, envelopes, filter, and downsample
         self.bus.sid.tick();
+        self.profiler.lap(3);
 
         // 7. IEC bus + 1541 drive: read CIA2 output, tick drive, feed back
         if let Some(ref mut drive) = self.drive {
@@ -452,6 +783,7 @@ impl Tickable for C64 {
             self.bus.cia2.external_a = (self.bus.cia2.external_a & 0x3F)
                 | if self.iec.clk() { 0x40 } else { 0x00 }
                 | if self.iec.data() { 0x80 } else { 0x00 };
+            self.profiler.lap(4);
         }
     }
 }
@@ -573,6 +905,31 @@ impl Observable for C64 {
     }
 }
 
+impl Debuggable for C64 {
+    fn instruction_boundary(&self) -> Option<u32> {
+        self.cpu
+            .is_instruction_complete()
+            .then_some(u32::from(self.cpu.regs.pc))
+    }
+
+    fn raster_position(&self) -> (u32, u32) {
+        (
+            u32::from(self.bus.vic.raster_line()),
+            u32::from(self.bus.vic.raster_cycle()),
+        )
+    }
+
+    fn set_bus_logging(&mut self, enabled: bool) {
+        self.bus_log = enabled.then(Vec::new);
+    }
+
+    fn drain_bus_accesses(&mut self, out: &mut Vec<BusAccess>) {
+        if let Some(log) = &mut self.bus_log {
+            out.append(log);
+        }
+    }
+}
+
 impl Machine for C64 {
     fn run_frame(&mut self) {
         let _ = self.run_frame();
@@ -591,16 +948,112 @@ impl Machine for C64 {
     }
 
     fn take_audio_buffer(&mut self) -> Vec<AudioFrame> {
-        self.take_audio_buffer().into_iter().map(|s| [s, s]).collect()
+        self.take_audio_buffer()
+            .into_iter()
+            .map(|s| [s, s])
+            .collect()
+    }
+
+    fn audio_sample_rate(&self) -> u32 {
+        self.audio_sample_rate()
+    }
+
+    fn set_audio_sample_rate(&mut self, rate: u32) -> bool {
+        self.set_audio_sample_rate(rate);
+        true
+    }
+
+    fn set_audio_rate_adjust(&mut self, ratio: f64) {
+        self.set_audio_rate_adjust(ratio);
+    }
+
+    fn audio_channels(&self) -> Vec<&'static str> {
+        mos_sid_6581::Sid6581::CHANNELS.to_vec()
+    }
+
+    fn set_channel_mute(&mut self, mask: u32) {
+        self.bus.sid.set_channel_mute(mask as u8);
+    }
+
+    fn channel_mute(&self) -> u32 {
+        u32::from(self.bus.sid.channel_mute())
+    }
+
+    fn set_stem_capture(&mut self, enabled: bool) {
+        self.bus.sid.set_stems(enabled);
+    }
+
+    fn take_audio_stems(&mut self) -> Vec<Vec<f32>> {
+        self.bus.sid.take_stems()
+    }
+
+    fn start_register_log(&mut self) -> bool {
+        self.bus.sid.start_register_log();
+        true
+    }
+
+    fn stop_register_log(&mut self) -> Option<RegisterLog> {
+        let model = self.bus.vic.model();
+        let frame = u64::from(model.lines_per_frame()) * u64::from(model.cycles_per_line());
+        self.bus
+            .sid
+            .stop_register_log()
+            .map(|log| log.with_frame_cycles(frame))
     }
 
     fn frame_count(&self) -> u64 {
         self.frame_count()
     }
 
+    fn cpu_clock_hz(&self) -> f64 {
+        f64::from(self.model().cpu_frequency())
+    }
+
+    fn frame_rate_hz(&self) -> f64 {
+        let model = self.model();
+        f64::from(model.cpu_frequency())
+            / (f64::from(model.lines_per_frame()) * f64::from(model.cycles_per_line()))
+    }
+
+    /// A drive motor or the datasette motor is running.
+    fn media_busy(&self) -> bool {
+        self.drive.as_ref().is_some_and(Drive1541::motor_on)
+            || ((self.tape.is_playing() || self.tape.is_recording()) && self.tape.motor_on())
+    }
+
+    fn set_chip_profiling(&mut self, enabled: bool) -> bool {
+        self.profiler.set_enabled(enabled);
+        true
+    }
+
+    fn chip_times(&self) -> Vec<(&'static str, Duration)> {
+        self.profiler.times()
+    }
+
     fn reset(&mut self) {
         self.cpu_mut().reset();
     }
+
+    fn save_state(&self) -> Result<Vec<u8>, StateError> {
+        Ok(self.save_state())
+    }
+
+    fn load_state(&mut self, data: &[u8]) -> Result<(), StateError> {
+        self.load_state(data)
+    }
+}
+
+impl NetplayInput for C64 {
+    fn netplay_buttons(&self) -> &'static [&'static str] {
+        &["Up", "Down", "Left", "Right", "Fire"]
+    }
+
+    /// Players 0 and 1 are joystick ports 1 and 2.
+    fn set_player_input(&mut self, player: usize, buttons: u16) {
+        if player < 2 {
+            self.set_joystick(player as u8 + 1, buttons as u8);
+        }
+    }
 }
 
 #[cfg(test)]
@@ -639,6 +1092,204 @@ mod tests {
         assert_eq!(c64.master_clock(), 1);
     }
 
+    #[test]
+    fn netplay_players_are_joystick_ports() {
+        let mut c64 = make_c64();
+        c64.set_player_input(0, 0x04); // Left
+        c64.set_player_input(1, 0xFF);
+        assert_eq!(c64.bus().joystick, [0x04, 0x1F]);
+
+        let state = c64.save_state();
+        c64.set_joystick(2, 0);
+        c64.load_state(&state).expect("state loads");
+        assert_eq!(c64.bus().joystick, [0x04, 0x1F]);
+    }
+
+    /// A C64 running a Kernal loop that plays a SID note and walks screen
+    /// RAM and the border colour, with a 1541 attached and a blank disk in.
+    fn make_busy_c64() -> C64 {
+        let mut kernal = vec![0xEA; 8192];
+        // LDA #$0F; STA $D418; LDA #$20; STA $D401; LDA #$F0; STA $D406;
+        // LDA #$21; STA $D404; LDX #0
+        // loop: INC $0400,X; STX $D020; INX; JMP loop
+        let code = [
+            0xA9, 0x0F, 0x8D, 0x18, 0xD4, 0xA9, 0x20, 0x8D, 0x01, 0xD4, 0xA9, 0xF0, 0x8D, 0x06,
+            0xD4, 0xA9, 0x21, 0x8D, 0x04, 0xD4, 0xA2, 0x00, 0xFE, 0x00, 0x04, 0x8E, 0x20, 0xD0,
+            0xE8, 0x4C, 0x16, 0xE0,
+        ];
+        kernal[..code.len()].copy_from_slice(&code);
+        kernal[0x1FFC] = 0x00;
+        kernal[0x1FFD] = 0xE0;
+
+        let mut drive_rom = vec![0xEA; 16384];
+        drive_rom[0x3FFC] = 0x00;
+        drive_rom[0x3FFD] = 0xC0;
+
+        let mut c64 = C64::new(&C64Config {
+            model: C64Model::C64Pal,
+            sid_model: crate::config::SidModel::Sid6581,
+            kernal_rom: kernal,
+            basic_rom: vec![0; 8192],
+            char_rom: vec![0; 4096],
+            drive_rom: Some(drive_rom),
+            reu_size: Some(128),
+        });
+        c64.load_d64(&vec![0u8; 174_848]).expect("blank D64");
+        c64
+    }
+
+    /// A configuration whose Kernal copies the keyboard column lines to the
+    /// border colour, so every key press shows up in the framebuffer.
+    fn key_border_config() -> C64Config {
+        let mut kernal = vec![0xEA; 8192];
+        // LDA #$00; STA $DC00
+        // loop: LDA $DC01; STA $D020; JMP loop
+        let code = [
+            0xA9, 0x00, 0x8D, 0x00, 0xDC, 0xAD, 0x01, 0xDC, 0x8D, 0x20, 0xD0, 0x4C, 0x05, 0xE0,
+        ];
+        kernal[..code.len()].copy_from_slice(&code);
+        kernal[0x1FFC] = 0x00;
+        kernal[0x1FFD] = 0xE0;
+        C64Config {
+            model: C64Model::C64Pal,
+            sid_model: crate::config::SidModel::Sid6581,
+            kernal_rom: kernal,
+            basic_rom: vec![0; 8192],
+            char_rom: vec![0; 4096],
+            drive_rom: None,
+            reu_size: None,
+        }
+    }
+
+    #[test]
+    fn movie_replays_live_and_queued_input() {
+        let config = key_border_config();
+        let mut c64 = C64::new(&config);
+        c64.run_frame();
+        c64.start_recording(&config);
+        for frame in 0..20 {
+            match frame {
+                3 => c64.press_key(C64Key::D),
+                6 => c64.release_key(C64Key::D),
+                8 => c64.input_queue().enqueue_key(C64Key::A, 12, 4),
+                _ => {}
+            }
+            c64.run_frame();
+        }
+        let movie = c64.stop_recording().expect("recording");
+        assert_eq!(movie.events.len(), 4);
+        assert!(movie.start_state.is_some());
+        let movie = Movie::from_bytes(&movie.to_bytes()).expect("round trip");
+
+        let mut replay = C64::new(&config);
+        replay.play_movie(&movie, &config, &[]).expect("play");
+        assert_eq!(movie.verify(&mut replay), None);
+
+        // An extra key press during playback shows up on the next frame.
+        let mut replay = C64::new(&config);
+        replay.play_movie(&movie, &config, &[]).expect("play");
+        for _ in 0..5 {
+            replay.run_frame();
+        }
+        replay.press_key(C64Key::W);
+        let divergence = movie.verify(&mut replay).expect("divergence");
+        assert_eq!(divergence.frame, 7);
+    }
+
+    #[test]
+    fn movie_from_other_setup_is_rejected() {
+        let config = key_border_config();
+        let mut c64 = C64::new(&config);
+        c64.start_recording(&config);
+        c64.run_frame();
+        let movie = c64.stop_recording().expect("recording");
+        assert!(movie.start_state.is_none());
+
+        let mut other = config.clone();
+        other.kernal_rom[0] = 0xEA;
+        assert_eq!(
+            C64::new(&other).play_movie(&movie, &other, &[]),
+            Err(MovieError::MediaMismatch {
+                slot: "kernal".into()
+            })
+        );
+
+        let mut started = C64::new(&config);
+        started.run_frame();
+        assert_eq!(
+            started.play_movie(&movie, &config, &[]),
+            Err(MovieError::NotAtStart { frame: 1 })
+        );
+    }
+
+    #[test]
+    fn movie_reloads_programs_at_their_frame() {
+        let config = key_border_config();
+        let prg: &[u8] = &[0x00, 0xC0, 0x12, 0x34];
+        let mut c64 = C64::new(&config);
+        c64.start_recording(&config);
+        for frame in 0..10 {
+            if frame == 4 {
+                c64.load_prg(prg).expect("prg");
+            }
+            c64.run_frame();
+        }
+        let movie = c64.stop_recording().expect("recording");
+
+        let mut replay = C64::new(&config);
+        assert_eq!(
+            replay.play_movie(&movie, &config, &[]),
+            Err(MovieError::MediaMismatch { slot: "prg".into() })
+        );
+        let other: &[u8] = &[0x00, 0xC0, 0x12, 0x35];
+        assert_eq!(
+            replay.play_movie(&movie, &config, &[("prg", other)]),
+            Err(MovieError::MediaMismatch { slot: "prg".into() })
+        );
+
+        replay
+            .play_movie(&movie, &config, &[("prg", prg)])
+            .expect("play");
+        for _ in 0..4 {
+            replay.run_frame();
+        }
+        assert_eq!(replay.bus.memory.peek(0xC000), 0x00);
+        assert_eq!(movie.verify(&mut replay), None);
+        assert_eq!(replay.bus.memory.peek(0xC000), 0x12);
+    }
+
+    #[test]
+    fn state_restored_mid_frame_continues_identically() {
+        let mut c64 = make_busy_c64();
+        c64.run_frame();
+        for _ in 0..7_777 {
+            c64.tick();
+        }
+        let state = Machine::save_state(&c64).expect("save");
+
+        let mut restored = make_busy_c64();
+        Machine::load_state(&mut restored, &state).expect("load");
+
+        for _ in 0..3 {
+            c64.run_frame();
+            restored.run_frame();
+        }
+        assert_eq!(c64.framebuffer(), restored.framebuffer());
+        assert_eq!(c64.cpu().regs, restored.cpu().regs);
+        assert_eq!(c64.master_clock(), restored.master_clock());
+        assert_eq!(c64.take_audio_buffer(), restored.take_audio_buffer());
+    }
+
+    #[test]
+    fn state_from_other_configuration_is_rejected() {
+        let state = make_c64().save_state();
+        let mut c64 = make_busy_c64();
+        assert!(matches!(
+            c64.load_state(&state),
+            Err(StateError::Invalid(_))
+        ));
+    }
+
     #[test]
     fn run_frame_returns_cycle_count() {
         let mut c64 = make_c64();
@@ -765,4 +1416,48 @@ mod tests {
         // Status byte at $90 should be clear
         assert_eq!(c64.bus.memory.ram_read(0x0090), 0x00);
     }
+
+    #[test]
+    fn media_busy_while_tape_motor_runs() {
+        let mut c64 = make_c64();
+        assert!(!Machine::media_busy(&c64));
+        c64.tape_mut().start_play();
+        assert!(!Machine::media_busy(&c64), "PLAY pressed, motor off");
+        c64.tape_mut().set_motor(true);
+        assert!(Machine::media_busy(&c64));
+        assert!((Machine::frame_rate_hz(&c64) - 50.125).abs() < 0.001);
+    }
+
+    #[test]
+    fn tape_records_write_line_from_cpu_port() {
+        let mut c64 = make_c64();
+        c64.tape_mut().start_record();
+        c64.tick();
+        // RECORD pressed: sense line reads low
+        assert_eq!(c64.bus.memory.peek(0x0001) & 0x10, 0x00);
+
+        // Motor on ($01 bit 5 low), toggle the write line every 176 cycles
+        let mut port = 0x17;
+        for _ in 0..8 {
+            c64.bus.memory.cpu_write(0x0001, port);
+            for _ in 0..176 {
+                c64.tick();
+            }
+            port ^= 0x08;
+        }
+        assert_eq!(c64.tape().recorded_pulses(), &[352, 352, 352]);
+        assert!(Machine::media_busy(&c64));
+
+        // Motor off: edges are not recorded
+        c64.bus.memory.cpu_write(0x0001, 0x3F);
+        for _ in 0..1000 {
+            c64.tick();
+        }
+        assert_eq!(c64.tape().recorded_pulses().len(), 3);
+
+        c64.tape_mut().stop_record();
+        let tap = c64.tape().recording_tap().to_bytes();
+        let parsed = crate::tap::C64TapFile::parse(&tap).expect("recorded TAP");
+        assert_eq!(parsed.raw_pulses, [352, 352, 352]);
+    }
 }
@@ -17,6 +17,8 @@
 
 #![allow(clippy::cast_possible_truncation)]
 
+use emu_core::{SaveState, StateError, StateReader, StateWriter};
+
 /// Cartridge hardware type.
 #[derive(Debug, Clone, Copy, PartialEq, Eq)]
 pub enum CartridgeType {
@@ -57,6 +59,33 @@ pub struct Cartridge {
     pub ef_control: u8,
 }
 
+/// Only the banking latches and on-cartridge RAM are saved. The ROM banks
+/// come from the CRT image, which must be the same one that was inserted
+/// when the state was taken.
+impl SaveState for Cartridge {
+    fn save_state(&self, w: &mut StateWriter) {
+        w.write_usize(self.roml.len());
+        w.write_usize(self.romh.len());
+        w.write_bool(self.exrom);
+        w.write_bool(self.game);
+        w.write_u8(self.bank);
+        w.write_bytes(&self.ef_ram);
+        w.write_u8(self.ef_control);
+    }
+
+    fn load_state(&mut self, r: &mut StateReader<'_>) -> Result<(), StateError> {
+        if r.read_usize()? != self.roml.len() || r.read_usize()? != self.romh.len() {
+            return Err(StateError::Invalid("cartridge does not match".into()));
+        }
+        self.exrom = r.read_bool()?;
+        self.game = r.read_bool()?;
+        self.bank = r.read_u8()?;
+        r.read_bytes_into(&mut self.ef_ram)?;
+        self.ef_control = r.read_u8()?;
+        Ok(())
+    }
+}
+
 /// CRT file signature.
 const CRT_SIGNATURE: &[u8; 16] = b"C64 CARTRIDGE   ";
 
@@ -22,7 +22,7 @@
 
 #![allow(clippy::cast_possible_truncation)]
 
-use emu_core::Cpu;
+use emu_core::{Cpu, SaveState, StateError, StateReader, StateWriter};
 use mos_6502::Mos6502;
 
 use crate::d64::D64;
@@ -461,6 +461,54 @@ impl Drive1541 {
     }
 }
 
+/// The inserted disk is saved in full: the drive writes to it, so the
+/// image on the host may no longer match what the drive has seen.
+impl SaveState for Drive1541 {
+    fn save_state(&self, w: &mut StateWriter) {
+        self.cpu.save_state(w);
+        self.bus.save_state(w);
+        w.write_bool(self.d64.is_some());
+        if let Some(d64) = &self.d64 {
+            w.write_bytes(d64.data());
+        }
+        w.write_u8(self.current_track);
+        w.write_u8(self.half_track);
+        w.write_bool(self.motor_on);
+        w.write_bool(self.led_on);
+        w.write_bytes(&self.gcr_track);
+        w.write_usize(self.gcr_position);
+        w.write_u32(self.byte_counter);
+        w.write_u8(self.prev_stepper_phase);
+        w.write_bool(self.prev_atn);
+        w.write_bool(self.prev_byte_ready);
+        w.write_bool(self.write_mode);
+        w.write_bytes(&self.write_buffer);
+    }
+
+    fn load_state(&mut self, r: &mut StateReader<'_>) -> Result<(), StateError> {
+        self.cpu.load_state(r)?;
+        self.bus.load_state(r)?;
+        self.d64 = if r.read_bool()? {
+            Some(D64::from_bytes(r.read_bytes()?).map_err(StateError::Invalid)?)
+        } else {
+            None
+        };
+        self.current_track = r.read_u8()?;
+        self.half_track = r.read_u8()?;
+        self.motor_on = r.read_bool()?;
+        self.led_on = r.read_bool()?;
+        self.gcr_track = r.read_bytes()?.to_vec();
+        self.gcr_position = r.read_usize()?;
+        self.byte_counter = r.read_u32()?;
+        self.prev_stepper_phase = r.read_u8()?;
+        self.prev_atn = r.read_bool()?;
+        self.prev_byte_ready = r.read_bool()?;
+        self.write_mode = r.read_bool()?;
+        self.write_buffer = r.read_bytes()?.to_vec();
+        Ok(())
+    }
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
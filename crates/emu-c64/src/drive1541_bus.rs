@@ -11,7 +11,7 @@
 
 #![allow(clippy::cast_possible_truncation)]
 
-use emu_core::{Bus, ReadResult};
+use emu_core::{Bus, ReadResult, SaveState, StateError, StateReader, StateWriter};
 use mos_via_6522::Via6522;
 
 /// 1541 drive bus.
@@ -47,6 +47,20 @@ impl Drive1541Bus {
     }
 }
 
+impl SaveState for Drive1541Bus {
+    fn save_state(&self, w: &mut StateWriter) {
+        w.write_bytes(&self.ram);
+        self.via1.save_state(w);
+        self.via2.save_state(w);
+    }
+
+    fn load_state(&mut self, r: &mut StateReader<'_>) -> Result<(), StateError> {
+        r.read_bytes_into(&mut self.ram)?;
+        self.via1.load_state(r)?;
+        self.via2.load_state(r)
+    }
+}
+
 impl Bus for Drive1541Bus {
     fn read(&mut self, addr: u32) -> ReadResult {
         let addr16 = addr as u16;
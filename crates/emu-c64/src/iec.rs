@@ -10,6 +10,8 @@
 //!   Input:  PA bit = 0 means line is LOW; bit = 1 means HIGH
 //!           (bit 6=CLK IN, bit 7=DATA IN)
 
+use emu_core::{SaveState, StateError, StateReader, StateWriter};
+
 /// IEC serial bus with two participants: C64 and drive.
 pub struct IecBus {
     /// ATN pull-down: [c64, drive]. true = pulling low.
@@ -86,6 +88,20 @@ impl IecBus {
     }
 }
 
+impl SaveState for IecBus {
+    fn save_state(&self, w: &mut StateWriter) {
+        w.write_bool_slice(&self.atn_pulls);
+        w.write_bool_slice(&self.clk_pulls);
+        w.write_bool_slice(&self.data_pulls);
+    }
+
+    fn load_state(&mut self, r: &mut StateReader<'_>) -> Result<(), StateError> {
+        r.read_bool_into(&mut self.atn_pulls)?;
+        r.read_bool_into(&mut self.clk_pulls)?;
+        r.read_bool_into(&mut self.data_pulls)
+    }
+}
+
 impl Default for IecBus {
     fn default() -> Self {
         Self::new()
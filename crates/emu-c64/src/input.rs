@@ -102,6 +102,94 @@ pub enum C64Key {
 }
 
 impl C64Key {
+    /// Every key, in matrix order.
+    pub const ALL: [Self; 64] = [
+        // Row 0
+        Self::Delete,
+        Self::N3,
+        Self::N5,
+        Self::N7,
+        Self::N9,
+        Self::Plus,
+        Self::Pound,
+        Self::N1,
+        // Row 1
+        Self::Return,
+        Self::W,
+        Self::R,
+        Self::Y,
+        Self::I,
+        Self::P,
+        Self::Asterisk,
+        Self::LeftArrow,
+        // Row 2
+        Self::CursorRight,
+        Self::A,
+        Self::D,
+        Self::G,
+        Self::J,
+        Self::L,
+        Self::Semicolon,
+        Self::Ctrl,
+        // Row 3
+        Self::F7,
+        Self::N4,
+        Self::N6,
+        Self::N8,
+        Self::N0,
+        Self::Minus,
+        Self::Home,
+        Self::N2,
+        // Row 4
+        Self::F1,
+        Self::Z,
+        Self::C,
+        Self::B,
+        Self::M,
+        Self::Period,
+        Self::RShift,
+        Self::Space,
+        // Row 5
+        Self::F3,
+        Self::S,
+        Self::F,
+        Self::H,
+        Self::K,
+        Self::Colon,
+        Self::Equals,
+        Self::Commodore,
+        // Row 6
+        Self::F5,
+        Self::E,
+        Self::T,
+        Self::U,
+        Self::O,
+        Self::At,
+        Self::UpArrow,
+        Self::Q,
+        // Row 7
+        Self::CursorDown,
+        Self::LShift,
+        Self::X,
+        Self::V,
+        Self::N,
+        Self::Comma,
+        Self::Slash,
+        Self::RunStop,
+    ];
+
+    /// Stable name used in input movies (the variant name).
+    #[must_use]
+    pub fn name(self) -> String {
+        format!("{self:?}")
+    }
+
+    /// Look up a key by its [`C64Key::name`].
+    #[must_use]
+    pub fn from_name(name: &str) -> Option<Self> {
+        Self::ALL.into_iter().find(|key| key.name() == name)
+    }
+
     /// Return the (row, col) pair for this key in the keyboard matrix.
     #[must_use]
     pub const fn matrix(self) -> (u8, u8) {
@@ -273,6 +361,11 @@ impl InputQueue {
         }
     }
 
+    /// Events that the next `process(frame, ..)` call will apply.
+    pub fn due(&self, frame: u64) -> impl Iterator<Item = &InputEvent> {
+        self.events.iter().take_while(move |e| e.frame <= frame)
+    }
+
     /// Number of pending events.
     #[must_use]
     pub fn len(&self) -> usize {
@@ -4,6 +4,8 @@
 //! Port A ($DC00) selects which **column** to scan (active low output).
 //! Port B ($DC01) reads which **rows** have a pressed key (active low input).
 
+use emu_core::{SaveState, StateError, StateReader, StateWriter};
+
 /// 8×8 keyboard matrix for the C64.
 ///
 /// Internally indexed by column: `cols[c]` has bit `r` set when the key
@@ -54,6 +56,16 @@ impl KeyboardMatrix {
     }
 }
 
+impl SaveState for KeyboardMatrix {
+    fn save_state(&self, w: &mut StateWriter) {
+        w.write_bytes(&self.cols);
+    }
+
+    fn load_state(&mut self, r: &mut StateReader<'_>) -> Result<(), StateError> {
+        r.read_bytes_into(&mut self.cols)
+    }
+}
+
 impl Default for KeyboardMatrix {
     fn default() -> Self {
         Self::new()
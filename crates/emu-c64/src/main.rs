@@ -15,6 +15,7 @@ use emu_c64::mcp::{C64Mcp, McpServer};
 use emu_c64::{C64, C64Config, C64Model, capture, keyboard_map};
 use emu_core::Cpu;
 use emu_core::renderer::Renderer;
+use emu_core::warp::Warp;
 use muda::{Menu, MenuEvent, MenuId, MenuItem, PredefinedMenuItem, Submenu};
 use winit::application::ApplicationHandler;
 use winit::event::{ElementState, WindowEvent};
@@ -38,6 +39,7 @@ struct CliArgs {
     d64_path: Option<PathBuf>,
     drive_rom_path: Option<PathBuf>,
     headless: bool,
+    bench: bool,
     mcp: bool,
     script_path: Option<PathBuf>,
     frames: u32,
@@ -58,6 +60,7 @@ fn parse_args() -> CliArgs {
         d64_path: None,
         drive_rom_path: None,
         headless: false,
+        bench: false,
         mcp: false,
         script_path: None,
         frames: 200,
@@ -107,6 +110,9 @@ fn parse_args() -> CliArgs {
             "--headless" => {
                 cli.headless = true;
             }
+            "--bench" => {
+                cli.bench = true;
+            }
             "--mcp" => {
                 cli.mcp = true;
             }
@@ -149,6 +155,7 @@ fn parse_args() -> CliArgs {
                 eprintln!("  --d64 <file>         Insert a D64 disk image");
                 eprintln!("  --drive-rom <file>   Load 1541 drive ROM (16384 bytes)");
                 eprintln!("  --headless           Run without a window");
+                eprintln!("  --bench              Run --frames frames flat out and report speed");
                 eprintln!("  --mcp                Run as MCP server (JSON-RPC over stdio)");
                 eprintln!("  --script <file>      Run a JSON script file (headless batch mode)");
                 eprintln!(
@@ -200,6 +207,12 @@ fn run_headless(cli: &CliArgs) {
     }
 }
 
+/// Time `--frames` frames with no pacing and print the benchmark report.
+fn run_bench(cli: &CliArgs) {
+    let mut c64 = make_c64(cli);
+    print!("{}", emu_core::bench::run(&mut c64, cli.frames));
+}
+
 // ---------------------------------------------------------------------------
 // Native menus (muda)
 // ---------------------------------------------------------------------------
@@ -281,6 +294,9 @@ struct App {
     window: Option<Arc<Window>>,
     last_frame_time: Instant,
     frame_duration: Duration,
+    /// Fast-forward: F9 toggles it, and it runs while the 1541 motor or
+    /// the datasette is running.
+    warp: Warp,
     fb_width: u32,
     fb_height: u32,
     menu_ids: MenuIds,
@@ -305,6 +321,7 @@ impl App {
             window: None,
             last_frame_time: Instant::now(),
             frame_duration,
+            warp: Warp::new(),
             fb_width,
             fb_height,
             menu_ids,
@@ -512,16 +529,28 @@ impl ApplicationHandler for App {
                         event_loop.exit();
                         return;
                     }
+                    if keycode == KeyCode::F9 {
+                        if event.state == ElementState::Pressed {
+                            let on = self.warp.toggle();
+                            eprintln!("Warp {}", if on { "on" } else { "off" });
+                        }
+                        return;
+                    }
                     self.handle_key(keycode, event.state == ElementState::Pressed);
                 }
             }
             WindowEvent::RedrawRequested => {
                 let now = Instant::now();
                 if now.duration_since(self.last_frame_time) >= self.frame_duration {
-                    self.c64.run_frame();
-                    // Drain SID audio buffer (prevent unbounded growth).
-                    // Future: feed to audio output device.
-                    let _ = self.c64.take_audio_buffer();
+                    if self.warp.is_active(&self.c64) {
+                        // The batch's audio is dropped like any other frame's.
+                        self.warp.run(&mut self.c64, self.frame_duration, |_| {});
+                    } else {
+                        self.c64.run_frame();
+                        // Drain SID audio buffer (prevent unbounded growth).
+                        // Future: feed to audio output device.
+                        let _ = self.c64.take_audio_buffer();
+                    }
 
                     if let Some(renderer) = &mut self.renderer {
                         renderer.upload_framebuffer(self.c64.framebuffer());
@@ -747,6 +776,11 @@ fn main() {
         return;
     }
 
+    if cli.bench {
+        run_bench(&cli);
+        return;
+    }
+
     if cli.headless {
         run_headless(&cli);
         return;
@@ -6,16 +6,29 @@
 
 #![allow(clippy::cast_possible_truncation)]
 
+use std::time::Duration;
+
 use base64::Engine;
 use serde_json::Value as JsonValue;
 
-use emu_core::mcp::{self, McpEmulator, ToolDefinition, ToolResult};
-use emu_core::{Cpu, Observable, Tickable};
+use emu_core::breakpoint::{Breakpoint, Breakpoints, Hit, Trigger};
+use emu_core::mcp::breakpoint::BreakpointSpace;
+use emu_core::mcp::{self, McpEmulator, Notification, RunSession, ToolDefinition, ToolResult};
+use emu_core::symbols::SymbolTable;
+use emu_core::trace::TraceLayout;
+use emu_core::{Cpu, Machine, Observable, Tickable};
 
 use crate::C64;
 use crate::config::{C64Config, C64Model};
 use crate::input::C64Key;
 
+/// Layout of `trace_start` lines: the 6502 register file.
+const TRACE_LAYOUT: TraceLayout = TraceLayout {
+    address_digits: 4,
+    opcode_bytes: 3,
+    registers: &["cpu.a", "cpu.x", "cpu.y", "cpu.s", "cpu.p"],
+};
+
 // ---------------------------------------------------------------------------
 // Public re-export: the MCP server type for main.rs
 // ---------------------------------------------------------------------------
@@ -28,23 +41,27 @@ pub type McpServer = mcp::McpServer<C64Mcp>;
 
 pub struct C64Mcp {
     c64: Option<C64>,
+    /// Configuration the C64 was booted with, for input movies.
+    config: Option<C64Config>,
+    breakpoints: Breakpoints,
+    run: RunSession,
+    symbols: SymbolTable,
 }
 
 impl C64Mcp {
     #[must_use]
     pub fn new() -> Self {
-        Self { c64: None }
+        Self {
+            c64: None,
+            config: None,
+            breakpoints: Breakpoints::new(),
+            run: RunSession::new(),
+            symbols: SymbolTable::new(),
+        }
     }
 
     fn require_c64(&mut self) -> Result<&mut C64, ToolResult> {
-        if let Some(ref mut c64) = self.c64 {
-            Ok(c64)
-        } else {
-            Err(ToolResult::Error {
-                code: -32000,
-                message: "No C64 instance. Call 'boot' first.".to_string(),
-            })
-        }
+        self.c64.as_mut().ok_or_else(no_c64)
     }
 }
 
@@ -64,7 +81,7 @@ impl McpEmulator for C64Mcp {
     }
 
     fn tool_definitions(&self) -> Vec<ToolDefinition> {
-        vec![
+        let mut tools = vec![
             ToolDefinition {
                 name: "boot",
                 description: "Boot the Commodore 64 with PAL ROMs",
@@ -124,7 +141,8 @@ impl McpEmulator for C64Mcp {
                     "type": "object",
                     "properties": {
                         "save_path": { "type": "string", "description": "If set, save PNG to this path and return metadata only" },
-                        "correct_aspect": { "type": "boolean", "description": "Scale to 4:3 display aspect ratio (default: true)" }
+                        "correct_aspect": { "type": "boolean", "description": "Scale to 4:3 display aspect ratio (default: true)" },
+                        "filter": mcp::filter_property()
                     }
                 }),
             },
@@ -150,10 +168,13 @@ impl McpEmulator for C64Mcp {
                     "type": "object",
                     "properties": {
                         "frames": { "type": "integer", "default": 50 },
-                        "save_path": { "type": "string", "description": "Save WAV to this path" }
+                        "save_path": { "type": "string", "description": "Save WAV to this path" },
+                        "stems": mcp::stems_property()
                     }
                 }),
             },
+            mcp::channel_mute_definition(),
+            mcp::register_log_definition(),
             ToolDefinition {
                 name: "query",
                 description: "Query an observable value (e.g. cpu.pc, vic.raster)",
@@ -228,6 +249,7 @@ impl McpEmulator for C64Mcp {
                     "type": "object",
                     "properties": {
                         "address": { "type": "integer" },
+                        "condition": { "type": "string", "description": "Only stop when this expression holds, e.g. \"cpu.a == 0x20\"" },
                         "max_frames": { "type": "integer", "default": 10000 }
                     },
                     "required": ["address"]
@@ -260,6 +282,8 @@ impl McpEmulator for C64Mcp {
                     "required": ["address", "length"]
                 }),
             },
+            mcp::disassemble_definition(),
+            mcp::record_gif_definition(),
             ToolDefinition {
                 name: "load_d64",
                 description: "Insert a D64 disk image",
@@ -271,6 +295,26 @@ impl McpEmulator for C64Mcp {
                     }
                 }),
             },
+            ToolDefinition {
+                name: "tape_record",
+                description: "Insert a blank tape and hold down RECORD and PLAY on the datasette. Whatever the machine saves from then on is recorded, through the KERNAL or a turbo saver",
+                input_schema: serde_json::json!({ "type": "object", "properties": {} }),
+            },
+            ToolDefinition {
+                name: "tape_stop",
+                description: "Press STOP on the datasette. The recording is kept for tape_save until the next tape_record",
+                input_schema: serde_json::json!({ "type": "object", "properties": {} }),
+            },
+            ToolDefinition {
+                name: "tape_save",
+                description: "Write the tape recording as a TAP (version 1) file",
+                input_schema: serde_json::json!({
+                    "type": "object",
+                    "properties": {
+                        "save_path": { "type": "string", "description": "Write the TAP to this path. Omit to return base64 data" }
+                    }
+                }),
+            },
             ToolDefinition {
                 name: "record_video",
                 description: "Record N frames as MP4 video with audio",
@@ -279,16 +323,23 @@ impl McpEmulator for C64Mcp {
                     "properties": {
                         "frames": { "type": "integer", "description": "Number of frames to record" },
                         "save_path": { "type": "string", "description": "Write MP4 to this path" },
-                        "correct_aspect": { "type": "boolean", "description": "Scale to 4:3 display aspect ratio (default: true)" }
+                        "correct_aspect": { "type": "boolean", "description": "Scale to 4:3 display aspect ratio (default: true)" },
+                        "filter": mcp::filter_property()
                     },
                     "required": ["frames", "save_path"]
                 }),
             },
-        ]
+        ];
+        tools.extend(mcp::breakpoint::breakpoint_definitions());
+        tools.extend(mcp::trace::trace_definitions());
+        tools.extend(mcp::run::run_definitions());
+        tools.extend(mcp::symbols::symbol_definitions());
+        tools.extend(mcp::movie::movie_definitions());
+        tools
     }
 
     fn dispatch_tool(&mut self, name: &str, arguments: &JsonValue) -> ToolResult {
-        match name {
+        let result = match name {
             "boot" => self.handle_boot(),
             "reset" => self.handle_reset(),
             "load_prg" => self.handle_load_prg(arguments),
@@ -298,6 +349,14 @@ impl McpEmulator for C64Mcp {
             "step_ticks" => self.handle_step_ticks(arguments),
             "screenshot" => self.handle_screenshot(arguments),
             "audio_capture" => self.handle_audio_capture(arguments),
+            "channel_mute" => match self.require_c64() {
+                Ok(c64) => mcp::channel_mute_result(arguments, c64),
+                Err(e) => e,
+            },
+            "register_log" => match self.require_c64() {
+                Ok(c64) => mcp::register_log_result(arguments, c64),
+                Err(e) => e,
+            },
             "query" => self.handle_query(arguments),
             "query_paths" => self.handle_query_paths(arguments),
             "boot_detected" => self.handle_boot_detected(),
@@ -307,16 +366,111 @@ impl McpEmulator for C64Mcp {
             "release_key" => self.handle_release_key(arguments),
             "type_text" => self.handle_type_text(arguments),
             "set_breakpoint" => self.handle_set_breakpoint(arguments),
+            "add_breakpoint" => self.handle_add_breakpoint(arguments),
+            "remove_breakpoint" => {
+                mcp::breakpoint::remove_breakpoint_result(arguments, &mut self.breakpoints)
+            }
+            "list_breakpoints" => {
+                mcp::breakpoint::list_breakpoints_result(&self.breakpoints, &self.symbols)
+            }
+            "clear_breakpoints" => mcp::breakpoint::clear_breakpoints_result(&mut self.breakpoints),
+            "run_until_break" => self.handle_run_until_break(arguments),
+            "trace_start" => self.handle_trace_start(arguments),
+            "trace_stop" => self.handle_trace_stop(),
+            "trace_export" => self.handle_trace_export(arguments),
             "get_screen_text" => self.handle_get_screen_text(),
             "query_memory" => self.handle_query_memory(arguments),
+            "disassemble" => self.handle_disassemble(arguments),
             "load_d64" => self.handle_load_d64(arguments),
+            "tape_record" => self.handle_tape_record(),
+            "tape_stop" => self.handle_tape_stop(),
+            "tape_save" => self.handle_tape_save(arguments),
+            "record_gif" => self.handle_record_gif(arguments),
             "record_video" => self.handle_record_video(arguments),
+            "record_movie" => self.handle_record_movie(arguments),
+            "play_movie" => self.handle_play_movie(arguments),
+            "run" => self.handle_run(arguments),
+            "pause" => self.handle_pause(),
+            "load_symbols" => self.handle_load_symbols(arguments),
+            "clear_symbols" => mcp::symbols::clear_symbols_result(&mut self.symbols),
+            "lookup_symbol" => mcp::symbols::lookup_symbol_result(arguments, &self.symbols),
             _ => ToolResult::Error {
                 code: -32601,
                 message: format!("Unknown tool: {name}"),
             },
+        };
+        let slot = match name {
+            "load_prg" | "load_bas" => Some("program"),
+            "load_d64" => Some("drive8"),
+            _ => None,
+        };
+        if let Some(slot) = slot {
+            self.run.media_tool_done(slot, name, arguments, &result);
+        }
+        result
+    }
+
+    fn run_slice(&mut self) -> Option<Duration> {
+        if !self.run.is_running() {
+            return None;
+        }
+        let Some(c64) = self.c64.as_mut() else {
+            self.run.stop();
+            return None;
+        };
+
+        // Breakpoints need the machine ticked one step at a time; without
+        // any, run_frame keeps queued input and movies in play.
+        if self.breakpoints.is_empty() {
+            c64.run_frame();
+        } else if let (Some(hit), _) = run_until_break(c64, &mut self.breakpoints, 1) {
+            let pc = format!("${:04X}", c64.cpu().regs.pc);
+            self.run.breakpoint_hit(&hit, &pc, &self.symbols);
+            return None;
+        }
+        self.run.frame_done(c64.frame_count());
+
+        if c64.cpu().is_halted() {
+            self.run
+                .cpu_halted(&format!("${:04X}", c64.cpu().regs.pc), "JAM opcode");
         }
+        if let Some(drive) = c64.drive() {
+            self.run.drive_led("8", drive.led_on());
+        }
+        self.run.next_slice()
+    }
+
+    fn take_notifications(&mut self) -> Vec<Notification> {
+        self.run.take_notifications()
+    }
+}
+
+fn no_c64() -> ToolResult {
+    ToolResult::Error {
+        code: -32000,
+        message: "No C64 instance. Call 'boot' first.".to_string(),
+    }
+}
+
+/// Run until a breakpoint stops execution or `max_frames` frames complete.
+/// Returns the hit and the number of frames completed.
+fn run_until_break(
+    c64: &mut C64,
+    breakpoints: &mut Breakpoints,
+    max_frames: u64,
+) -> (Option<Hit>, u64) {
+    let mut frames_run = 0u64;
+    if max_frames == 0 {
+        return (None, 0);
     }
+    let hit = breakpoints.run(c64, |c64| {
+        c64.tick();
+        if c64.bus_mut().vic.take_frame_complete() {
+            frames_run += 1;
+        }
+        frames_run < max_frames
+    });
+    (hit, frames_run)
 }
 
 // ---------------------------------------------------------------------------
@@ -335,6 +489,7 @@ impl C64Mcp {
             }
         };
         self.c64 = Some(C64::new(&config));
+        self.config = Some(config);
         ToolResult::Success(serde_json::json!({"status": "ok"}))
     }
 
@@ -445,6 +600,45 @@ impl C64Mcp {
         }
     }
 
+    fn handle_record_movie(&mut self, params: &JsonValue) -> ToolResult {
+        let stop = match mcp::movie::stop_param(params) {
+            Ok(s) => s,
+            Err(e) => return e,
+        };
+        let (Some(c64), Some(config)) = (self.c64.as_mut(), &self.config) else {
+            return no_c64();
+        };
+
+        if stop {
+            return mcp::movie::recording_stopped_result(params, c64.stop_recording());
+        }
+        c64.start_recording(config);
+        mcp::movie::recording_started_result(c64.frame_count())
+    }
+
+    fn handle_play_movie(&mut self, params: &JsonValue) -> ToolResult {
+        let movie = match mcp::movie::movie_param(params) {
+            Ok(m) => m,
+            Err(e) => return e,
+        };
+        let media = match mcp::movie::media_param(params) {
+            Ok(m) => m,
+            Err(e) => return e,
+        };
+        let (Some(c64), Some(config)) = (self.c64.as_mut(), &self.config) else {
+            return no_c64();
+        };
+
+        let media: Vec<(&str, &[u8])> = media
+            .iter()
+            .map(|(slot, data)| (slot.as_str(), data.as_slice()))
+            .collect();
+        if let Err(e) = c64.play_movie(&movie, config, &media) {
+            return mcp::movie::movie_error(&e);
+        }
+        mcp::movie::play_result(params, &movie, c64)
+    }
+
     fn handle_run_frames(&mut self, params: &JsonValue) -> ToolResult {
         let c64 = match self.require_c64() {
             Ok(c) => c,
@@ -529,12 +723,17 @@ impl C64Mcp {
 
         let save_path = params.get("save_path").and_then(|v| v.as_str());
         let display = parse_display_size(params, c64.framebuffer_width(), c64.framebuffer_height());
+        let filter = match mcp::parse_filter(params, Some(c64.video_signal())) {
+            Ok(filter) => filter.map(|f| f.at_frame(c64.frame_count())),
+            Err(e) => return e,
+        };
         mcp::screenshot_result(
             c64.framebuffer_width(),
             c64.framebuffer_height(),
             c64.framebuffer(),
             save_path,
             display,
+            filter,
         )
     }
 
@@ -549,13 +748,22 @@ impl C64Mcp {
             .and_then(serde_json::Value::as_u64)
             .unwrap_or(50);
 
+        let mut stems = match mcp::StemCapture::start(params, c64) {
+            Ok(s) => s,
+            Err(e) => return e,
+        };
+
         let mut all_audio: Vec<f32> = Vec::new();
         for _ in 0..frames {
             c64.run_frame();
             all_audio.extend_from_slice(&c64.take_audio_buffer());
+            if let Some(stems) = &mut stems {
+                stems.collect(c64);
+            }
         }
 
-        if let Some(save_path) = params.get("save_path").and_then(|v| v.as_str())
+        let save_path = params.get("save_path").and_then(|v| v.as_str());
+        if let Some(save_path) = save_path
             && let Err(e) = crate::capture::save_audio(&all_audio, std::path::Path::new(save_path))
         {
             return ToolResult::Error {
@@ -587,12 +795,19 @@ impl C64Mcp {
             base64::engine::general_purpose::STANDARD.encode(&wav_buf)
         };
 
-        ToolResult::Success(serde_json::json!({
+        let mut result = serde_json::json!({
             "format": "wav",
             "samples": all_audio.len(),
             "frames": frames,
             "data": b64,
-        }))
+        });
+        if let Some(stems) = stems {
+            match stems.finish(c64, save_path) {
+                Ok(stems) => result["stems"] = stems,
+                Err(e) => return e,
+            }
+        }
+        ToolResult::Success(result)
     }
 
     fn handle_query(&mut self, params: &JsonValue) -> ToolResult {
@@ -750,48 +965,132 @@ impl C64Mcp {
     }
 
     fn handle_set_breakpoint(&mut self, params: &JsonValue) -> ToolResult {
-        let c64 = match self.require_c64() {
-            Ok(c) => c,
-            Err(e) => return e,
+        let Self {
+            c64,
+            breakpoints,
+            symbols,
+            ..
+        } = self;
+        let Some(c64) = c64.as_mut() else {
+            return no_c64();
         };
 
-        let addr = match params.get("address").and_then(serde_json::Value::as_u64) {
-            Some(a) if a <= 0xFFFF => a as u16,
-            _ => {
+        let addr = match mcp::symbols::address_param(params, "address", symbols, 0xFFFF) {
+            Ok(Some(a)) => a as u16,
+            Ok(None) => {
                 return ToolResult::Error {
                     code: -32602,
                     message: "Missing or invalid 'address' (0-65535)".to_string(),
                 };
             }
+            Err(e) => return e,
         };
 
-        let max_frames = params
-            .get("max_frames")
-            .and_then(serde_json::Value::as_u64)
-            .unwrap_or(10_000);
+        let condition = match mcp::breakpoint::parse_condition(params, c64) {
+            Ok(c) => c,
+            Err(e) => return e,
+        };
+        let max_frames = mcp::breakpoint::max_frames_param(params);
 
-        let mut frames_run = 0u64;
-        let mut hit = false;
+        // Run alongside any breakpoints added with add_breakpoint, so
+        // whichever comes first stops execution.
+        let mut breakpoint = Breakpoint::new(Trigger::Execute(u32::from(addr))).temporary();
+        breakpoint.condition = condition;
+        let id = breakpoints.add(breakpoint);
+        let (hit, frames_run) = run_until_break(c64, breakpoints, max_frames);
+        breakpoints.remove(id);
 
-        'outer: for _ in 0..max_frames {
-            loop {
-                c64.tick();
-                if c64.cpu().regs.pc == addr {
-                    hit = true;
-                    break 'outer;
-                }
-                if c64.bus_mut().vic.take_frame_complete() {
-                    frames_run += 1;
-                    break;
+        ToolResult::Success(serde_json::json!({
+            "hit": hit.is_some(),
+            "breakpoint": hit.as_ref().map(|h| mcp::breakpoint::hit_to_json(h, symbols)),
+            "pc": format!("${:04X}", c64.cpu().regs.pc),
+            "frames_run": frames_run,
+        }))
+    }
+
+    fn handle_add_breakpoint(&mut self, params: &JsonValue) -> ToolResult {
+        let Some(c64) = self.c64.as_ref() else {
+            return no_c64();
+        };
+        mcp::breakpoint::add_breakpoint_result(
+            params,
+            &mut self.breakpoints,
+            c64,
+            BreakpointSpace::MEMORY_16,
+            &self.symbols,
+        )
+    }
+
+    fn handle_run_until_break(&mut self, params: &JsonValue) -> ToolResult {
+        let Some(c64) = self.c64.as_mut() else {
+            return no_c64();
+        };
+        let max_frames = mcp::breakpoint::max_frames_param(params);
+        let (hit, frames_run) = run_until_break(c64, &mut self.breakpoints, max_frames);
+        mcp::breakpoint::run_until_break_result(
+            hit.as_ref(),
+            &format!("${:04X}", c64.cpu().regs.pc),
+            frames_run,
+            &self.symbols,
+        )
+    }
+
+    fn handle_run(&mut self, params: &JsonValue) -> ToolResult {
+        let Some(c64) = self.c64.as_ref() else {
+            return no_c64();
+        };
+        let fps = Machine::frame_rate_hz(c64).round() as u32;
+        self.run.start(params, fps)
+    }
+
+    fn handle_pause(&mut self) -> ToolResult {
+        let position = self.c64.as_ref().map_or(JsonValue::Null, |c64| {
+            serde_json::json!({
+                "pc": format!("${:04X}", c64.cpu().regs.pc),
+                "frame_count": c64.frame_count(),
+            })
+        });
+        self.run.pause(position)
+    }
+
+    fn handle_trace_start(&mut self, params: &JsonValue) -> ToolResult {
+        let Some(c64) = self.c64.as_mut() else {
+            return no_c64();
+        };
+        match mcp::trace::parse_tracer(params, TRACE_LAYOUT, &self.symbols) {
+            Ok(tracer) => {
+                let result = mcp::trace::trace_start_result(&tracer);
+                // A trace being replaced still gets its last entry written.
+                if let Some(mut old) = c64.set_tracer(Some(tracer)) {
+                    let _ = old.stop();
                 }
+                result
             }
+            Err(e) => e,
         }
+    }
 
-        ToolResult::Success(serde_json::json!({
-            "hit": hit,
-            "pc": format!("${:04X}", if hit { addr } else { c64.cpu().regs.pc }),
-            "frames_run": frames_run,
-        }))
+    fn handle_load_symbols(&mut self, params: &JsonValue) -> ToolResult {
+        let result = mcp::symbols::load_symbols_result(params, &mut self.symbols);
+        // A running trace labels the rest of its entries with them too.
+        if let Some(tracer) = self.c64.as_mut().and_then(C64::tracer_mut) {
+            tracer.set_symbols(self.symbols.clone());
+        }
+        result
+    }
+
+    fn handle_trace_stop(&mut self) -> ToolResult {
+        match self.require_c64() {
+            Ok(c64) => mcp::trace::trace_stop_result(c64.tracer_mut()),
+            Err(e) => e,
+        }
+    }
+
+    fn handle_trace_export(&mut self, params: &JsonValue) -> ToolResult {
+        match self.require_c64() {
+            Ok(c64) => mcp::trace::trace_export_result(params, c64.tracer()),
+            Err(e) => e,
+        }
     }
 
     fn handle_get_screen_text(&mut self) -> ToolResult {
@@ -883,6 +1182,18 @@ impl C64Mcp {
         }))
     }
 
+    fn handle_disassemble(&mut self, params: &JsonValue) -> ToolResult {
+        let Some(c64) = self.c64.as_ref() else {
+            return no_c64();
+        };
+
+        let pc = u32::from(c64.cpu().regs.pc);
+        let memory = &c64.bus().memory;
+        mcp::disassemble_result(params, Some(pc), &self.symbols, |address| {
+            emu_disasm::mos6502::disassemble(|addr| memory.peek(addr), address)
+        })
+    }
+
     fn handle_load_d64(&mut self, params: &JsonValue) -> ToolResult {
         let c64 = match self.require_c64() {
             Ok(c) => c,
@@ -903,6 +1214,73 @@ impl C64Mcp {
         }
     }
 
+    fn handle_tape_record(&mut self) -> ToolResult {
+        let c64 = match self.require_c64() {
+            Ok(c) => c,
+            Err(e) => return e,
+        };
+
+        c64.tape_mut().start_record();
+        ToolResult::Success(serde_json::json!({ "recording": true }))
+    }
+
+    fn handle_tape_stop(&mut self) -> ToolResult {
+        let c64 = match self.require_c64() {
+            Ok(c) => c,
+            Err(e) => return e,
+        };
+
+        c64.tape_mut().stop_record();
+        let tap = c64.tape().recording_tap();
+        ToolResult::Success(serde_json::json!({
+            "recording": false,
+            "blocks": tap.blocks.len(),
+            "pulses": tap.raw_pulses.len(),
+        }))
+    }
+
+    fn handle_tape_save(&mut self, params: &JsonValue) -> ToolResult {
+        let c64 = match self.require_c64() {
+            Ok(c) => c,
+            Err(e) => return e,
+        };
+
+        let bytes = c64.tape().recording_tap().to_bytes();
+        let mut result = serde_json::json!({
+            "format": "tap",
+            "size": bytes.len(),
+        });
+        if let Some(path) = params.get("save_path").and_then(|v| v.as_str()) {
+            if let Err(e) = std::fs::write(path, &bytes) {
+                return ToolResult::Error {
+                    code: -32000,
+                    message: format!("Failed to write {path}: {e}"),
+                };
+            }
+            result["path"] = path.into();
+        } else {
+            result["data"] = base64::engine::general_purpose::STANDARD
+                .encode(&bytes)
+                .into();
+        }
+        ToolResult::Success(result)
+    }
+
+    fn handle_record_gif(&mut self, params: &JsonValue) -> ToolResult {
+        let c64 = match self.require_c64() {
+            Ok(c) => c,
+            Err(e) => return e,
+        };
+
+        let (width, height) = (c64.framebuffer_width(), c64.framebuffer_height());
+        let active = Some(c64.display_window());
+        let fps = 50; // PAL
+        mcp::record_gif_result(params, width, height, fps, active, |rec| {
+            c64.run_frame();
+            rec.add_frame(c64.framebuffer());
+        })
+    }
+
     fn handle_record_video(&mut self, params: &JsonValue) -> ToolResult {
         let c64 = match self.require_c64() {
             Ok(c) => c,
@@ -926,7 +1304,16 @@ impl C64Mcp {
             };
         };
 
+        let filter = match mcp::parse_filter(params, Some(c64.video_signal())) {
+            Ok(filter) => filter.map(|f| f.at_frame(c64.frame_count())),
+            Err(e) => return e,
+        };
         let display = parse_display_size(params, c64.framebuffer_width(), c64.framebuffer_height());
+        let display = display.or_else(|| {
+            filter
+                .as_ref()
+                .map(|f| f.output_size(c64.framebuffer_width(), c64.framebuffer_height()))
+        });
         let mut rec = match emu_core::video::VideoRecorder::new(
             c64.framebuffer_width(),
             c64.framebuffer_height(),
@@ -945,6 +1332,10 @@ impl C64Mcp {
             }
         };
 
+        if let Some(filter) = filter {
+            rec.set_filter(filter);
+        }
+
         for _ in 0..frames {
             c64.run_frame();
             let audio = c64.take_audio_buffer();
@@ -1185,7 +1576,14 @@ mod tests {
     use crate::config::SidModel;
 
     fn make_c64() -> C64 {
+        c64_with_kernal(&[])
+    }
+
+    /// C64 whose kernal starts with `code` at $E000 (the reset vector)
+    /// followed by NOPs.
+    fn c64_with_kernal(code: &[u8]) -> C64 {
         let mut kernal = vec![0xEA; 8192];
+        kernal[..code.len()].copy_from_slice(code);
         kernal[0x1FFC] = 0x00;
         kernal[0x1FFD] = 0xE0;
 
@@ -1247,6 +1645,10 @@ mod tests {
     fn query_paths_can_filter_to_vic_and_sid_surfaces() {
         let mut mcp = C64Mcp {
             c64: Some(make_c64()),
+            config: None,
+            breakpoints: Breakpoints::new(),
+            run: RunSession::new(),
+            symbols: SymbolTable::new(),
         };
 
         let vic_result = mcp.dispatch_tool(
@@ -1288,6 +1690,45 @@ mod tests {
         }
     }
 
+    #[test]
+    fn disassemble_reads_ram_and_kernal() {
+        let mut c64 = make_c64();
+        for (i, byte) in [0x20, 0xD2, 0xFF, 0xD0, 0xFE].into_iter().enumerate() {
+            c64.bus_mut().memory.ram_write(0xC000 + i as u16, byte);
+        }
+        let mut mcp = C64Mcp {
+            c64: Some(c64),
+            config: None,
+            breakpoints: Breakpoints::new(),
+            run: RunSession::new(),
+            symbols: SymbolTable::new(),
+        };
+
+        let result = mcp.dispatch_tool(
+            "disassemble",
+            &serde_json::json!({"address": 0xC000, "count": 2}),
+        );
+        match result {
+            ToolResult::Success(value) => {
+                let insns = value["instructions"].as_array().expect("instructions");
+                assert_eq!(insns[0]["text"], "JSR $FFD2");
+                assert_eq!(insns[0]["target"], 0xFFD2);
+                assert_eq!(insns[1]["text"], "BNE $C003");
+                assert_eq!(insns[1]["target"], 0xC003);
+            }
+            ToolResult::Error { message, .. } => panic!("unexpected error: {message}"),
+        }
+
+        let result = mcp.dispatch_tool(
+            "disassemble",
+            &serde_json::json!({"address": 0xE000, "count": 1}),
+        );
+        match result {
+            ToolResult::Success(value) => assert_eq!(value["instructions"][0]["text"], "NOP"),
+            ToolResult::Error { message, .. } => panic!("unexpected error: {message}"),
+        }
+    }
+
     #[test]
     fn detect_boot_reports_ready() {
         let lines = vec!["READY.".to_string()];
@@ -1311,4 +1752,328 @@ mod tests {
         assert!(!detected);
         assert!(reason.contains("no boot"));
     }
+
+    #[test]
+    fn watchpoint_with_condition_and_raster_breakpoint() {
+        // LDX #0; STX $D020; INX; JMP $E002
+        let c64 = c64_with_kernal(&[0xA2, 0x00, 0x8E, 0x20, 0xD0, 0xE8, 0x4C, 0x02, 0xE0]);
+        let mut mcp = C64Mcp {
+            c64: Some(c64),
+            config: None,
+            breakpoints: Breakpoints::new(),
+            run: RunSession::new(),
+            symbols: SymbolTable::new(),
+        };
+
+        let add = mcp.dispatch_tool(
+            "add_breakpoint",
+            &serde_json::json!({"type": "write", "address": 0xD020, "condition": "cpu.x == 3"}),
+        );
+        assert!(matches!(add, ToolResult::Success(_)));
+        match mcp.dispatch_tool("run_until_break", &serde_json::json!({})) {
+            ToolResult::Success(value) => {
+                assert_eq!(value["hit"], true);
+                assert_eq!(value["breakpoint"]["access"]["address"], 0xD020);
+                assert_eq!(value["breakpoint"]["access"]["value"], 3);
+            }
+            ToolResult::Error { message, .. } => panic!("unexpected error: {message}"),
+        }
+
+        mcp.dispatch_tool("clear_breakpoints", &JsonValue::Null);
+        mcp.dispatch_tool(
+            "add_breakpoint",
+            &serde_json::json!({"type": "raster", "line": 100, "cycle": 12}),
+        );
+        match mcp.dispatch_tool("run_until_break", &serde_json::json!({})) {
+            ToolResult::Success(value) => assert_eq!(value["breakpoint"]["type"], "raster"),
+            ToolResult::Error { message, .. } => panic!("unexpected error: {message}"),
+        }
+        let vic = &mcp.c64.as_mut().expect("c64").bus_mut().vic;
+        assert_eq!((vic.raster_line(), vic.raster_cycle()), (100, 12));
+    }
+
+    #[test]
+    fn run_stops_on_a_jam_and_reports_loaded_programs() {
+        // JAM
+        let mut mcp = C64Mcp::new();
+        mcp.c64 = Some(c64_with_kernal(&[0x02]));
+
+        let load = mcp.dispatch_tool(
+            "load_prg",
+            &serde_json::json!({"data": base64::engine::general_purpose::STANDARD.encode([0x00, 0xC0, 0x60])}),
+        );
+        assert!(matches!(load, ToolResult::Success(_)));
+        let run = mcp.dispatch_tool("run", &serde_json::json!({"realtime": false}));
+        assert!(matches!(run, ToolResult::Success(_)));
+        assert_eq!(mcp.run_slice(), None);
+
+        let sent = mcp.take_notifications();
+        let events: Vec<_> = sent.iter().map(|n| n.event).collect();
+        assert_eq!(events, ["media_changed", "frame_complete", "cpu_halted"]);
+        assert_eq!(sent[0].params["slot"], "program");
+        assert_eq!(sent[2].params["pc"], "$E001");
+    }
+
+    #[test]
+    fn run_is_paced_at_the_booted_models_frame_rate() {
+        let mut mcp = C64Mcp::new();
+        mcp.c64 = Some(c64_with_kernal(&[0x4C, 0x00, 0xE0]));
+        mcp.dispatch_tool("run", &serde_json::json!({}));
+        assert_eq!(mcp.run.frame_rate(), 50);
+
+        let mut ntsc = C64Mcp::new();
+        ntsc.c64 = Some(C64::new(&C64Config {
+            model: C64Model::C64Ntsc,
+            sid_model: SidModel::Sid6581,
+            kernal_rom: vec![0xEA; 8192],
+            basic_rom: vec![0; 8192],
+            char_rom: vec![0; 4096],
+            drive_rom: None,
+            reu_size: None,
+        }));
+        ntsc.dispatch_tool("run", &serde_json::json!({}));
+        assert_eq!(ntsc.run.frame_rate(), 60);
+    }
+
+    #[test]
+    fn vice_labels_name_breakpoints_and_disassembly() {
+        // loop: LDX #0 / STX border / INX / JMP loop+2
+        let path = std::env::temp_dir().join("emu-c64-mcp-labels.lbl");
+        std::fs::write(&path, "al C:e000 .loop\nal C:d020 .border\n").expect("write labels");
+        let mut mcp = C64Mcp::new();
+        mcp.c64 = Some(c64_with_kernal(&[0xA2, 0x00, 0x8E, 0x20, 0xD0, 0xE8, 0x4C, 0x02, 0xE0]));
+        let loaded = mcp.dispatch_tool("load_symbols", &serde_json::json!({"path": path.to_str()}));
+        let _ = std::fs::remove_file(&path);
+        assert!(matches!(loaded, ToolResult::Success(_)));
+
+        mcp.dispatch_tool(
+            "add_breakpoint",
+            &serde_json::json!({"type": "write", "address": "border"}),
+        );
+        match mcp.dispatch_tool("run_until_break", &serde_json::json!({})) {
+            ToolResult::Success(value) => assert_eq!(value["breakpoint"]["label"], "border"),
+            ToolResult::Error { message, .. } => panic!("unexpected error: {message}"),
+        }
+
+        match mcp.dispatch_tool(
+            "disassemble",
+            &serde_json::json!({"address": "loop+2", "count": 3}),
+        ) {
+            ToolResult::Success(value) => {
+                let insns = &value["instructions"];
+                assert_eq!(insns[0]["text"], "STX border");
+                assert_eq!(insns[2]["text"], "JMP $E002");
+                assert_eq!(insns[2]["target_label"], "loop+$2");
+            }
+            ToolResult::Error { message, .. } => panic!("unexpected error: {message}"),
+        }
+    }
+
+    /// A C64 whose kernal copies a keyboard row to the border colour.
+    fn border_key_config() -> C64Config {
+        // LDA #$FF; STA $DC02; LDA #$7F; STA $DC00
+        // loop: LDA $DC01; STA $D020; JMP loop
+        let code = [
+            0xA9, 0xFF, 0x8D, 0x02, 0xDC, 0xA9, 0x7F, 0x8D, 0x00, 0xDC, 0xAD, 0x01, 0xDC, 0x8D,
+            0x20, 0xD0, 0x4C, 0x0A, 0xE0,
+        ];
+        let mut kernal = vec![0xEA; 8192];
+        kernal[..code.len()].copy_from_slice(&code);
+        kernal[0x1FFC] = 0x00;
+        kernal[0x1FFD] = 0xE0;
+
+        C64Config {
+            model: C64Model::C64Pal,
+            sid_model: SidModel::Sid6581,
+            kernal_rom: kernal,
+            basic_rom: vec![0; 8192],
+            char_rom: vec![0; 4096],
+            drive_rom: None,
+            reu_size: None,
+        }
+    }
+
+    #[test]
+    fn movie_records_and_replays_through_tools() {
+        let movie_mcp = || {
+            let mut mcp = C64Mcp::new();
+            mcp.c64 = Some(C64::new(&border_key_config()));
+            mcp.config = Some(border_key_config());
+            mcp
+        };
+
+        let mut mcp = movie_mcp();
+        let started = mcp.dispatch_tool("record_movie", &serde_json::json!({"action": "start"}));
+        assert!(matches!(started, ToolResult::Success(_)));
+        mcp.dispatch_tool("run_frames", &serde_json::json!({"count": 2}));
+        mcp.dispatch_tool("press_key", &serde_json::json!({"key": "1"}));
+        mcp.dispatch_tool("run_frames", &serde_json::json!({"count": 2}));
+        let ToolResult::Success(stopped) =
+            mcp.dispatch_tool("record_movie", &serde_json::json!({"action": "stop"}))
+        else {
+            panic!("record_movie stop failed");
+        };
+        assert_eq!(stopped["frames"], 4);
+        assert_eq!(stopped["events"], 1);
+
+        let data = stopped["data"].clone();
+        let ToolResult::Success(played) =
+            movie_mcp().dispatch_tool("play_movie", &serde_json::json!({"data": data}))
+        else {
+            panic!("play_movie failed");
+        };
+        assert_eq!(played["matched"], true);
+
+        // Without the key press the third frame onwards differs
+        let bytes = base64::engine::general_purpose::STANDARD
+            .decode(data.as_str().expect("movie returned as base64"))
+            .expect("movie data decodes");
+        let mut movie = emu_core::Movie::from_bytes(&bytes).expect("movie parses");
+        movie.events.clear();
+        let data = base64::engine::general_purpose::STANDARD.encode(movie.to_bytes());
+        let ToolResult::Success(played) =
+            movie_mcp().dispatch_tool("play_movie", &serde_json::json!({"data": data}))
+        else {
+            panic!("play_movie failed");
+        };
+        assert_eq!(played["matched"], false);
+        assert_eq!(played["divergence"]["frame"], 3);
+    }
+
+    #[test]
+    fn movie_play_checks_media_loaded_while_recording() {
+        let path = std::env::temp_dir().join("emu-c64-mcp-movie.prg");
+        std::fs::write(&path, [0x00, 0xC0, 0x12, 0x34]).expect("write prg");
+        let mut mcp = C64Mcp::new();
+        mcp.c64 = Some(C64::new(&border_key_config()));
+        mcp.config = Some(border_key_config());
+        mcp.dispatch_tool("record_movie", &serde_json::json!({}));
+        mcp.dispatch_tool("run_frames", &serde_json::json!({"count": 2}));
+        mcp.dispatch_tool("load_prg", &serde_json::json!({"path": path.to_str()}));
+        mcp.dispatch_tool("run_frames", &serde_json::json!({"count": 2}));
+        let ToolResult::Success(stopped) =
+            mcp.dispatch_tool("record_movie", &serde_json::json!({"action": "stop"}))
+        else {
+            panic!("record_movie stop failed");
+        };
+        let loaded = stopped["media"]
+            .as_array()
+            .expect("media list")
+            .last()
+            .cloned();
+        assert_eq!(loaded, Some(serde_json::json!({"slot": "prg", "frame": 2})));
+
+        let mut replay = C64Mcp::new();
+        replay.c64 = Some(C64::new(&border_key_config()));
+        replay.config = Some(border_key_config());
+        let params = serde_json::json!({"data": stopped["data"]});
+        match replay.dispatch_tool("play_movie", &params) {
+            ToolResult::Error { message, .. } => assert!(message.contains("prg differs")),
+            ToolResult::Success(_) => panic!("played without the program"),
+        }
+        let params = serde_json::json!({"data": stopped["data"], "media": {"prg": path.to_str()}});
+        let played = replay.dispatch_tool("play_movie", &params);
+        let _ = std::fs::remove_file(&path);
+        let ToolResult::Success(played) = played else {
+            panic!("play_movie failed");
+        };
+        assert_eq!(played["matched"], true);
+    }
+
+    #[test]
+    fn tape_tools_record_the_write_line_and_save_a_tap() {
+        // LDA #$17 / loop: STA $01 / LDX #$28 / DEX / BNE / EOR #$08 / JMP loop
+        // keeps the motor on and toggles the write line.
+        let mut mcp = C64Mcp::new();
+        mcp.c64 = Some(c64_with_kernal(&[
+            0xA9, 0x17, 0x85, 0x01, 0xA2, 0x28, 0xCA, 0xD0, 0xFD, 0x49, 0x08, 0x4C, 0x02, 0xE0,
+        ]));
+        let ToolResult::Success(started) = mcp.dispatch_tool("tape_record", &JsonValue::Null)
+        else {
+            panic!("tape_record failed");
+        };
+        assert_eq!(started["recording"], true);
+        mcp.dispatch_tool("run_frames", &serde_json::json!({ "count": 2 }));
+
+        let ToolResult::Success(stopped) = mcp.dispatch_tool("tape_stop", &JsonValue::Null) else {
+            panic!("tape_stop failed");
+        };
+        assert_eq!(stopped["recording"], false);
+        let pulses = stopped["pulses"].as_u64().expect("pulse count");
+        assert!(pulses > 0);
+
+        let path = std::env::temp_dir().join("emu-c64-mcp-tape.tap");
+        let ToolResult::Success(saved) = mcp.dispatch_tool(
+            "tape_save",
+            &serde_json::json!({ "save_path": path.to_str() }),
+        ) else {
+            panic!("tape_save failed");
+        };
+        assert_eq!(saved["format"], "tap");
+        let bytes = std::fs::read(&path).expect("read TAP");
+        let _ = std::fs::remove_file(&path);
+        let tap = crate::tap::C64TapFile::parse(&bytes).expect("parse TAP");
+        assert_eq!(tap.raw_pulses.len() as u64, pulses);
+        // 209 cycles per half period, stored in units of 8 cycles
+        assert!(tap.raw_pulses.iter().all(|&p| p == 416));
+
+        // Without a path the TAP comes back as base64
+        let ToolResult::Success(inline) = mcp.dispatch_tool("tape_save", &JsonValue::Null) else {
+            panic!("tape_save failed");
+        };
+        let data = base64::engine::general_purpose::STANDARD
+            .decode(inline["data"].as_str().expect("data"))
+            .expect("base64");
+        assert_eq!(data, bytes);
+    }
+
+    #[test]
+    #[ignore] // Requires real C64 ROMs at roms/
+    fn kernal_save_through_tape_tools_reparses_as_tap() {
+        let mut mcp = C64Mcp::new();
+        assert!(matches!(
+            mcp.dispatch_tool("boot", &JsonValue::Null),
+            ToolResult::Success(_)
+        ));
+        mcp.dispatch_tool("run_frames", &serde_json::json!({ "count": 200 }));
+
+        mcp.dispatch_tool("tape_record", &JsonValue::Null);
+        mcp.dispatch_tool(
+            "type_text",
+            &serde_json::json!({ "text": "10 REM HELLO\nSAVE \"HELLO\"\n" }),
+        );
+        // Leader, header, data and their repeats take about 25 seconds
+        mcp.dispatch_tool("run_frames", &serde_json::json!({ "count": 3000 }));
+        let ToolResult::Success(screen) = mcp.dispatch_tool("get_screen_text", &JsonValue::Null)
+        else {
+            panic!("get_screen_text failed");
+        };
+        let text = screen.to_string();
+        assert!(text.contains("SAVING HELLO"), "screen: {text}");
+
+        let ToolResult::Success(stopped) = mcp.dispatch_tool("tape_stop", &JsonValue::Null) else {
+            panic!("tape_stop failed");
+        };
+        assert_eq!(stopped["blocks"], 1);
+
+        let path = std::env::temp_dir().join("emu-c64-mcp-kernal-save.tap");
+        mcp.dispatch_tool(
+            "tape_save",
+            &serde_json::json!({ "save_path": path.to_str() }),
+        );
+        let bytes = std::fs::read(&path).expect("read TAP");
+        let _ = std::fs::remove_file(&path);
+        let tap = crate::tap::C64TapFile::parse(&bytes).expect("parse TAP");
+        assert_eq!(tap.blocks.len(), 1);
+        let block = &tap.blocks[0];
+        assert_eq!(block.filename, "HELLO");
+        assert_eq!(block.file_type, 1);
+        assert_eq!(block.start_address, 0x0801);
+        assert_eq!(
+            block.data.len(),
+            usize::from(block.end_address - block.start_address)
+        );
+        // Link pointer, line number 10, REM token
+        assert_eq!(&block.data[2..5], &[10, 0, 0x8F]);
+    }
 }
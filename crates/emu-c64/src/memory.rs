@@ -20,6 +20,8 @@
 
 #![allow(clippy::cast_possible_truncation)]
 
+use emu_core::{SaveState, StateError, StateReader, StateWriter};
+
 use crate::cartridge::Cartridge;
 use crate::vic::Vic;
 use mos_cia_6526::Cia6526;
@@ -41,6 +43,9 @@ pub struct C64Memory {
     port_ddr: u8,
     /// 6510 port: data register ($01).
     port_data: u8,
+    /// Datasette PLAY or RECORD held down: pulls the cassette sense
+    /// input (bit 4) low.
+    cassette_switch: bool,
     /// Inserted cartridge (controls EXROM/GAME lines and provides ROM).
     pub cartridge: Option<Cartridge>,
 }
@@ -65,6 +70,7 @@ impl C64Memory {
             colour_ram: [0; 1024],
             port_ddr: 0x2F,  // Default: bits 0-3,5 output
             port_data: 0x37, // Default: all ROMs + I/O visible
+            cassette_switch: false,
             cartridge: None,
         }
     }
@@ -84,10 +90,25 @@ impl C64Memory {
         self.effective_port() & 0x01 != 0
     }
 
-    /// Effective port value: (data & ddr) | (`external_pullups` & !ddr).
-    /// Undriven inputs float high due to pull-up resistors.
+    /// Effective port value: (data & ddr) | (`external_lines` & !ddr).
+    /// Undriven inputs float high due to pull-up resistors, except the
+    /// cassette sense line while a datasette button is down.
     fn effective_port(&self) -> u8 {
-        (self.port_data & self.port_ddr) | (0x37 & !self.port_ddr)
+        let external = if self.cassette_switch { 0x27 } else { 0x37 };
+        (self.port_data & self.port_ddr) | (external & !self.port_ddr)
+    }
+
+    /// Level on the 6510 port pins, as seen by the datasette: bit 3 is
+    /// the cassette write line, bit 5 the motor control (active low).
+    #[must_use]
+    pub fn processor_port(&self) -> u8 {
+        self.effective_port()
+    }
+
+    /// Press or release the datasette PLAY/RECORD buttons, which drive
+    /// the cassette sense line (port bit 4, low while pressed).
+    pub fn set_cassette_switch(&mut self, pressed: bool) {
+        self.cassette_switch = pressed;
     }
 
     /// Is the I/O area ($D000-$DFFF) visible to the CPU?
@@ -134,11 +155,8 @@ impl C64Memory {
         match addr {
             // 6510 port
             0x0000 => self.port_ddr,
-            0x0001 => {
-                // Read: output bits from data reg, input bits from external
-                // For now, external lines float high (pull-up).
-                (self.port_data & self.port_ddr) | (0x37 & !self.port_ddr)
-            }
+            // Read: output bits from data reg, input bits from external
+            0x0001 => self.effective_port(),
 
             // $8000-$9FFF: ROML when cartridge asserts EXROM=0
             0x8000..=0x9FFF => {
@@ -327,6 +345,26 @@ impl C64Memory {
     }
 }
 
+/// ROMs are configuration; the inserted cartridge's banking state is saved
+/// but its ROM contents are not.
+impl SaveState for C64Memory {
+    fn save_state(&self, w: &mut StateWriter) {
+        w.write_bytes(&self.ram[..]);
+        w.write_bytes(&self.colour_ram);
+        w.write_u8(self.port_ddr);
+        w.write_u8(self.port_data);
+        w.write_option(self.cartridge.as_ref());
+    }
+
+    fn load_state(&mut self, r: &mut StateReader<'_>) -> Result<(), StateError> {
+        r.read_bytes_into(&mut self.ram[..])?;
+        r.read_bytes_into(&mut self.colour_ram)?;
+        self.port_ddr = r.read_u8()?;
+        self.port_data = r.read_u8()?;
+        r.read_option(self.cartridge.as_mut(), "a cartridge")
+    }
+}
+
 impl format_prg::RamAccess for C64Memory {
     fn ram_read(&self, addr: u16) -> u8 {
         self.ram_read(addr)
@@ -386,6 +424,16 @@ mod tests {
         assert_eq!(mem.cpu_read(0xD000), 0xCC);
     }
 
+    #[test]
+    fn cassette_switch_pulls_sense_line_low() {
+        let mut mem = make_memory();
+        assert_eq!(mem.cpu_read(0x0001) & 0x10, 0x10);
+        mem.set_cassette_switch(true);
+        assert_eq!(mem.cpu_read(0x0001) & 0x10, 0x00);
+        // Banking bits are unaffected
+        assert_eq!(mem.processor_port() & 0x07, 0x07);
+    }
+
     #[test]
     fn port_read_write() {
         let mut mem = make_memory();
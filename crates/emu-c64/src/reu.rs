@@ -21,6 +21,8 @@
 
 #![allow(clippy::cast_possible_truncation)]
 
+use emu_core::{SaveState, StateError, StateReader, StateWriter};
+
 /// REU (RAM Expansion Unit).
 pub struct Reu {
     /// Expansion RAM (128K, 256K, or 512K).
@@ -190,6 +192,31 @@ impl Reu {
     }
 }
 
+impl SaveState for Reu {
+    fn save_state(&self, w: &mut StateWriter) {
+        w.write_bytes(&self.ram);
+        w.write_u8(self.status);
+        w.write_u8(self.command);
+        w.write_u16(self.c64_addr);
+        w.write_u32(self.reu_addr);
+        w.write_u16(self.length);
+        w.write_u8(self.irq_mask);
+        w.write_u8(self.addr_control);
+    }
+
+    fn load_state(&mut self, r: &mut StateReader<'_>) -> Result<(), StateError> {
+        r.read_bytes_into(&mut self.ram)?;
+        self.status = r.read_u8()?;
+        self.command = r.read_u8()?;
+        self.c64_addr = r.read_u16()?;
+        self.reu_addr = r.read_u32()?;
+        self.length = r.read_u16()?;
+        self.irq_mask = r.read_u8()?;
+        self.addr_control = r.read_u8()?;
+        Ok(())
+    }
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
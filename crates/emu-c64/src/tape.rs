@@ -6,6 +6,14 @@
 //! Also supports real-time pulse playback for turbo loaders that
 //! bypass the kernal ROM and read tape signals directly via the
 //! CIA1 FLAG pin.
+//!
+//! Recording samples the cassette write line ($01 bit 3) every CPU
+//! cycle while the motor runs. Each rising edge ends one pulse, timed in
+//! cycles since the previous one, which is how a TAP file measures them.
+//! The KERNAL SAVE routine and turbo savers both drive the same line, so
+//! both record the same way.
+
+use emu_core::{SaveState, StateError, StateReader, StateWriter};
 
 use crate::tap::{C64TapBlock, C64TapFile};
 
@@ -27,6 +35,16 @@ pub struct C64TapeDeck {
     motor_on: bool,
     /// Current signal level (toggles on each pulse edge).
     signal_level: bool,
+
+    // --- Recording state ---
+    /// Whether RECORD is pressed.
+    recording: bool,
+    /// Pulse durations written so far, in CPU cycles.
+    written: Vec<u32>,
+    /// Cycles since the last rising edge, or `None` before the first.
+    write_cycles: Option<u32>,
+    /// Last sampled level of the write line.
+    write_level: bool,
 }
 
 impl C64TapeDeck {
@@ -42,6 +60,10 @@ impl C64TapeDeck {
             playing: false,
             motor_on: false,
             signal_level: true,
+            recording: false,
+            written: Vec::new(),
+            write_cycles: None,
+            write_level: false,
         }
     }
 
@@ -166,6 +188,93 @@ impl C64TapeDeck {
     pub fn has_raw_pulses(&self) -> bool {
         !self.raw_pulses.is_empty()
     }
+
+    // --- Recording ---
+
+    /// Start recording (press RECORD and PLAY on the datasette) onto a
+    /// blank tape. Stops any playback.
+    pub fn start_record(&mut self) {
+        self.playing = false;
+        self.recording = true;
+        self.written.clear();
+        self.write_cycles = None;
+    }
+
+    /// Stop recording. The pulses written so far are kept.
+    pub fn stop_record(&mut self) {
+        self.recording = false;
+    }
+
+    /// Whether RECORD is pressed.
+    #[must_use]
+    pub fn is_recording(&self) -> bool {
+        self.recording
+    }
+
+    /// Pulse durations recorded so far, in CPU cycles.
+    #[must_use]
+    pub fn recorded_pulses(&self) -> &[u32] {
+        &self.written
+    }
+
+    /// The recording as a TAP file, with any standard kernal blocks
+    /// decoded.
+    #[must_use]
+    pub fn recording_tap(&self) -> C64TapFile {
+        C64TapFile::from_pulses(self.written.clone())
+    }
+
+    /// Sample the cassette write line for one CPU cycle while recording.
+    ///
+    /// Tape only moves while the motor runs, so time with the motor off
+    /// is not counted and edges then are not recorded.
+    pub fn write_tick(&mut self, level: bool) {
+        let rising = level && !self.write_level;
+        self.write_level = level;
+        if !self.recording || !self.motor_on {
+            return;
+        }
+
+        if rising {
+            if let Some(cycles) = self.write_cycles {
+                self.written.push(cycles);
+            }
+            self.write_cycles = Some(0);
+        }
+        if let Some(cycles) = self.write_cycles.as_mut() {
+            *cycles = cycles.saturating_add(1);
+        }
+    }
+}
+
+/// The tape image itself is media, not machine state: only the deck's
+/// position and motor state are saved, and restoring requires the same
+/// tape to be inserted. A recording in progress is host-side capture and
+/// is not saved either.
+impl SaveState for C64TapeDeck {
+    fn save_state(&self, w: &mut StateWriter) {
+        w.write_bool(self.tap.is_some());
+        w.write_usize(self.raw_pulses.len());
+        w.write_usize(self.block_index);
+        w.write_usize(self.pulse_index);
+        w.write_u32(self.pulse_countdown);
+        w.write_bool(self.playing);
+        w.write_bool(self.motor_on);
+        w.write_bool(self.signal_level);
+    }
+
+    fn load_state(&mut self, r: &mut StateReader<'_>) -> Result<(), StateError> {
+        if r.read_bool()? != self.tap.is_some() || r.read_usize()? != self.raw_pulses.len() {
+            return Err(StateError::Invalid("tape does not match".into()));
+        }
+        self.block_index = r.read_usize()?;
+        self.pulse_index = r.read_usize()?;
+        self.pulse_countdown = r.read_u32()?;
+        self.playing = r.read_bool()?;
+        self.motor_on = r.read_bool()?;
+        self.signal_level = r.read_bool()?;
+        Ok(())
+    }
 }
 
 impl Default for C64TapeDeck {
@@ -284,6 +393,43 @@ mod tests {
         );
     }
 
+    /// Drive the write line with a square wave of `period` cycles.
+    fn write_wave(deck: &mut C64TapeDeck, period: u32, cycles: u32) {
+        for cycle in 0..cycles {
+            deck.write_tick(cycle % period < period / 2);
+        }
+    }
+
+    #[test]
+    fn recording_times_write_line_pulses() {
+        let mut deck = C64TapeDeck::new();
+        deck.start_record();
+        deck.set_motor(true);
+
+        write_wave(&mut deck, 352, 352 * 4);
+        write_wave(&mut deck, 512, 512 * 3);
+        deck.stop_record();
+
+        // The first rising edge only starts the clock
+        assert_eq!(deck.recorded_pulses(), &[352, 352, 352, 352, 512, 512]);
+        assert!(!deck.is_recording());
+    }
+
+    #[test]
+    fn recording_pauses_with_motor() {
+        let mut deck = C64TapeDeck::new();
+        deck.start_record();
+        deck.set_motor(true);
+        write_wave(&mut deck, 100, 200);
+        deck.set_motor(false);
+        write_wave(&mut deck, 100, 1000);
+        deck.set_motor(true);
+        write_wave(&mut deck, 100, 200);
+
+        // Edges with the motor stopped are lost and their time not counted
+        assert_eq!(deck.recorded_pulses(), &[100, 100, 100]);
+    }
+
     #[test]
     fn no_edges_when_motor_off() {
         let mut deck = C64TapeDeck::new();
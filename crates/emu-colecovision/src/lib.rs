@@ -14,13 +14,21 @@
 
 #![allow(clippy::cast_possible_truncation)]
 
-use emu_core::{AudioFrame, Bus, Cpu, Machine, ReadResult};
+#[cfg(feature = "native")]
+pub mod mcp;
+
+use std::time::Duration;
+
+use emu_core::bench::ChipProfiler;
+use emu_core::{AudioFrame, Bus, Cpu, Machine, Observable, ReadResult, RegisterLog, Value};
 use ti_sn76489::Sn76489;
 use ti_tms9918::{Tms9918, VdpRegion};
 use zilog_z80::Z80;
 
 /// VDP dots per CPU cycle (TMS9918 runs at 3× CPU clock).
 const VDP_DOTS_PER_CPU: u64 = 3;
+/// Z80 clock (NTSC colour-burst frequency).
+const CPU_CLOCK_HZ: f64 = 3_579_545.0;
 /// Crystal ticks per frame (NTSC: 342 dots × 262 lines × 3 CPU cycles per dot).
 const NTSC_TICKS_PER_FRAME: u64 = 342 * 262 * 3;
 
@@ -133,12 +141,12 @@ impl CvBus {
             joystick_mode: false,
         }
     }
-}
 
-impl Bus for CvBus {
-    fn read(&mut self, addr: u32) -> ReadResult {
-        let addr = addr as u16;
-        let data = match addr {
+    /// Read memory as the CPU sees it. Memory reads have no side effects
+    /// on this bus, so debuggers can call this freely.
+    #[must_use]
+    pub fn peek(&self, addr: u16) -> u8 {
+        match addr {
             // BIOS ROM: $0000-$1FFF
             0x0000..=0x1FFF => {
                 let idx = addr as usize;
@@ -153,8 +161,13 @@ impl Bus for CvBus {
                 let idx = (addr - 0x8000) as usize;
                 if idx < self.cart_rom.len() { self.cart_rom[idx] } else { 0xFF }
             }
-        };
-        ReadResult { data, wait: 0 }
+        }
+    }
+}
+
+impl Bus for CvBus {
+    fn read(&mut self, addr: u32) -> ReadResult {
+        ReadResult { data: self.peek(addr as u16), wait: 0 }
     }
 
     fn write(&mut self, addr: u32, data: u8) -> u8 {
@@ -210,6 +223,9 @@ impl Bus for CvBus {
     }
 }
 
+/// Chips timed by the profiler, in the order the tick loop laps them.
+const CHIPS: &[&str] = &["zilog-z80", "ti-tms9918", "ti-sn76489"];
+
 /// ColecoVision system.
 pub struct ColecoVision {
     cpu: Z80,
@@ -217,6 +233,8 @@ pub struct ColecoVision {
     master_clock: u64,
     ticks_per_frame: u64,
     frame_count: u64,
+    /// Per-chip tick timing, while benchmarking.
+    profiler: ChipProfiler,
 }
 
 impl ColecoVision {
@@ -237,6 +255,7 @@ impl ColecoVision {
             master_clock: 0,
             ticks_per_frame,
             frame_count: 0,
+            profiler: ChipProfiler::new(CHIPS),
         }
     }
 
@@ -245,13 +264,17 @@ impl ColecoVision {
         let target = self.master_clock + self.ticks_per_frame;
 
         while self.master_clock < target {
+            self.profiler.start();
             self.cpu.tick(&mut self.bus);
+            self.profiler.lap(0);
 
             for _ in 0..VDP_DOTS_PER_CPU {
                 self.bus.vdp.tick();
             }
+            self.profiler.lap(1);
 
             self.bus.psg.tick();
+            self.profiler.lap(2);
 
             // VDP interrupt → Z80 INT (directly from TMS9918A INT pin)
             if self.bus.vdp.interrupt {
@@ -270,7 +293,7 @@ impl ColecoVision {
         self.bus.vdp.framebuffer()
     }
 
-    /// Take audio samples (mono f32, 48 kHz).
+    /// Take audio samples (mono f32 at the output rate, 48 kHz by default).
     pub fn take_audio_buffer(&mut self) -> Vec<f32> {
         self.bus.psg.take_buffer()
     }
@@ -303,6 +326,31 @@ impl ColecoVision {
     }
 }
 
+impl Observable for ColecoVision {
+    fn query(&self, path: &str) -> Option<Value> {
+        if let Some(rest) = path.strip_prefix("cpu.") {
+            self.cpu.query(rest)
+        } else if path == "vdp.scanline" {
+            Some(self.bus.vdp.scanline().into())
+        } else {
+            match path {
+                "master_clock" => Some(self.master_clock.into()),
+                "frame_count" => Some(self.frame_count.into()),
+                _ => self.cpu.query(path),
+            }
+        }
+    }
+
+    fn query_paths(&self) -> &'static [&'static str] {
+        &[
+            "cpu.<z80_paths>",
+            "vdp.scanline",
+            "master_clock",
+            "frame_count",
+        ]
+    }
+}
+
 impl Machine for ColecoVision {
     fn run_frame(&mut self) {
         self.run_frame();
@@ -324,10 +372,75 @@ impl Machine for ColecoVision {
         self.take_audio_buffer().into_iter().map(|s| [s, s]).collect()
     }
 
+    fn audio_sample_rate(&self) -> u32 {
+        self.bus.psg.sample_rate()
+    }
+
+    fn set_audio_sample_rate(&mut self, rate: u32) -> bool {
+        self.bus.psg.set_sample_rate(rate);
+        true
+    }
+
+    fn set_audio_rate_adjust(&mut self, ratio: f64) {
+        self.bus.psg.set_rate_adjust(ratio);
+    }
+
+    fn audio_channels(&self) -> Vec<&'static str> {
+        Sn76489::CHANNELS.to_vec()
+    }
+
+    fn set_channel_mute(&mut self, mask: u32) {
+        self.bus.psg.set_channel_mute(mask as u8);
+    }
+
+    fn channel_mute(&self) -> u32 {
+        u32::from(self.bus.psg.channel_mute())
+    }
+
+    fn set_stem_capture(&mut self, enabled: bool) {
+        self.bus.psg.set_stems(enabled);
+    }
+
+    fn take_audio_stems(&mut self) -> Vec<Vec<f32>> {
+        self.bus.psg.take_stems()
+    }
+
+    fn start_register_log(&mut self) -> bool {
+        self.bus.psg.start_register_log();
+        true
+    }
+
+    fn stop_register_log(&mut self) -> Option<RegisterLog> {
+        let frame = self.ticks_per_frame;
+        self.bus.psg.stop_register_log().map(|log| log.with_frame_cycles(frame))
+    }
+
     fn frame_count(&self) -> u64 {
         self.frame_count()
     }
 
+    fn cpu_clock_hz(&self) -> f64 {
+        CPU_CLOCK_HZ
+    }
+
+    fn frame_rate_hz(&self) -> f64 {
+        let lines = if self.ticks_per_frame == NTSC_TICKS_PER_FRAME {
+            262.0
+        } else {
+            313.0
+        };
+        CPU_CLOCK_HZ / (228.0 * lines)
+    }
+
+    fn set_chip_profiling(&mut self, enabled: bool) -> bool {
+        self.profiler.set_enabled(enabled);
+        true
+    }
+
+    fn chip_times(&self) -> Vec<(&'static str, Duration)> {
+        self.profiler.times()
+    }
+
     fn reset(&mut self) {
         self.cpu_mut().reset();
     }
@@ -369,6 +482,13 @@ mod tests {
         assert_eq!(cv.frame_count(), 1);
     }
 
+    #[test]
+    fn observable_reports_vdp_scanline() {
+        let cv = ColecoVision::new(minimal_bios(), minimal_cart(), CvRegion::Ntsc);
+        assert_eq!(cv.query("vdp.scanline"), Some(Value::U16(0)));
+        assert!(cv.query_paths().contains(&"vdp.scanline"));
+    }
+
     #[test]
     fn ram_read_write() {
         let mut bus = CvBus::new(minimal_bios(), minimal_cart(), CvRegion::Ntsc);
@@ -12,8 +12,10 @@ use std::sync::Arc;
 use std::time::{Duration, Instant};
 
 use emu_core::Cpu;
+use emu_core::mcp::MachineMcp;
 use emu_core::renderer::Renderer;
 use emu_colecovision::{ColecoVision, CvRegion, KeypadKey};
+use emu_colecovision::mcp::McpServer;
 use muda::{Menu, MenuEvent, MenuId, MenuItem, PredefinedMenuItem, Submenu};
 use winit::application::ApplicationHandler;
 use winit::event::{ElementState, WindowEvent};
@@ -42,9 +44,12 @@ struct CliArgs {
     bios_path: Option<PathBuf>,
     rom_path: Option<PathBuf>,
     headless: bool,
+    bench: bool,
     frames: u32,
     screenshot_path: Option<PathBuf>,
     region: CvRegion,
+    mcp: bool,
+    script_path: Option<PathBuf>,
 }
 
 fn print_usage() {
@@ -55,8 +60,11 @@ fn print_usage() {
     eprintln!("  --rom <file>         Cartridge ROM file");
     eprintln!("  --region <ntsc|pal>  Video region (default: ntsc)");
     eprintln!("  --headless           Run without a window");
+    eprintln!("  --bench              Run --frames frames flat out and report speed");
     eprintln!("  --frames <n>         Number of frames in headless mode [default: 200]");
     eprintln!("  --screenshot <file>  Save a PNG screenshot (headless)");
+    eprintln!("  --mcp                Run as MCP server (JSON-RPC over stdio)");
+    eprintln!("  --script <file>      Run a JSON script file (headless batch mode)");
 }
 
 fn print_usage_and_exit(code: i32) -> ! {
@@ -91,9 +99,12 @@ fn parse_args_from(args: &[String]) -> Result<Option<CliArgs>, String> {
         bios_path: None,
         rom_path: None,
         headless: false,
+        bench: false,
         frames: 200,
         screenshot_path: None,
         region: CvRegion::Ntsc,
+        mcp: false,
+        script_path: None,
     };
 
     let mut i = 1;
@@ -108,6 +119,9 @@ fn parse_args_from(args: &[String]) -> Result<Option<CliArgs>, String> {
             "--headless" => {
                 cli.headless = true;
             }
+            "--bench" => {
+                cli.bench = true;
+            }
             "--frames" => {
                 i += 1;
                 let value = args
@@ -133,6 +147,12 @@ fn parse_args_from(args: &[String]) -> Result<Option<CliArgs>, String> {
                     _ => return Err(format!("Invalid value for --region: {value}")),
                 };
             }
+            "--mcp" => {
+                cli.mcp = true;
+            }
+            "--script" => {
+                cli.script_path = Some(next_option_value(args, &mut i, "--script")?);
+            }
             "--help" | "-h" => return Ok(None),
             other => {
                 return Err(format!("Unknown argument: {other}"));
@@ -145,6 +165,10 @@ fn parse_args_from(args: &[String]) -> Result<Option<CliArgs>, String> {
         cli.headless = true;
     }
 
+    if cli.mcp && cli.script_path.is_some() {
+        return Err("--mcp and --script are mutually exclusive".to_string());
+    }
+
     Ok(Some(cli))
 }
 
@@ -192,6 +216,12 @@ fn run_headless(cli: &CliArgs) {
     }
 }
 
+/// Time `--frames` frames with no pacing and print the benchmark report.
+fn run_bench(cli: &CliArgs) {
+    let mut system = make_system(cli);
+    print!("{}", emu_core::bench::run(&mut system, cli.frames));
+}
+
 // ---------------------------------------------------------------------------
 // Native menus (muda)
 // ---------------------------------------------------------------------------
@@ -451,6 +481,22 @@ fn make_system(cli: &CliArgs) -> ColecoVision {
     ColecoVision::new(bios_data, rom_data, cli.region)
 }
 
+// ---------------------------------------------------------------------------
+// MCP / script mode
+// ---------------------------------------------------------------------------
+
+fn run_mcp(cli: &CliArgs) {
+    let mut server = McpServer::new(MachineMcp::new("emu-colecovision", make_system(cli)));
+    if let Some(ref path) = cli.script_path {
+        if let Err(e) = server.run_script(path) {
+            eprintln!("Script error: {e}");
+            process::exit(1);
+        }
+    } else {
+        server.run();
+    }
+}
+
 // ---------------------------------------------------------------------------
 // Entry point
 // ---------------------------------------------------------------------------
@@ -458,6 +504,16 @@ fn make_system(cli: &CliArgs) -> ColecoVision {
 fn main() {
     let cli = parse_args();
 
+    if cli.mcp || cli.script_path.is_some() {
+        run_mcp(&cli);
+        return;
+    }
+
+    if cli.bench {
+        run_bench(&cli);
+        return;
+    }
+
     if cli.headless {
         run_headless(&cli);
         return;
@@ -0,0 +1,38 @@
+//! MCP (Model Context Protocol) server for the `ColecoVision` emulator.
+//!
+//! Serves the generic `MachineMcp` tools. Run with `--mcp` for MCP mode
+//! or `--script` for batch mode.
+
+use emu_core::Instruction;
+use emu_core::mcp::{self, MachineMcp, McpMachine};
+
+use crate::{ColecoVision, NTSC_TICKS_PER_FRAME};
+
+pub type McpServer = mcp::McpServer<MachineMcp<ColecoVision>>;
+
+impl McpMachine for ColecoVision {
+    fn frame_rate(&self) -> u32 {
+        if self.ticks_per_frame == NTSC_TICKS_PER_FRAME {
+            60
+        } else {
+            50
+        }
+    }
+
+    fn disassemble(&self, address: u32) -> Instruction {
+        emu_disasm::z80::disassemble(|addr| self.bus.peek(addr), address)
+    }
+
+    /// 1 KB of RAM mirrored across $6000-$7FFF.
+    fn peek(&self, address: u16) -> Option<u8> {
+        matches!(address, 0x6000..=0x7FFF).then(|| self.bus.ram[usize::from(address & 0x03FF)])
+    }
+
+    fn poke(&mut self, address: u16, value: u8) -> bool {
+        let is_ram = matches!(address, 0x6000..=0x7FFF);
+        if is_ram {
+            self.bus.ram[usize::from(address & 0x03FF)] = value;
+        }
+        is_ram
+    }
+}
@@ -0,0 +1,525 @@
+//! Animated GIF and APNG capture for short looping clips.
+//!
+//! Most systems draw from a fixed palette of 16 to 64 colours, so frames
+//! are stored as palette indices rather than RGB: the colours seen so far
+//! become one shared palette, and each frame holds a byte per pixel. A
+//! clip that stays under 256 colours is encoded losslessly; past that,
+//! new colours map to the nearest one already in the palette.
+//!
+//! Frames that repeat the previous one extend its duration instead of
+//! being stored again. When the file is written, each frame after the
+//! first covers only the rectangle that changed, with unchanged pixels
+//! inside it made transparent, so a raster bar moving over a still
+//! screen costs a few lines per frame.
+
+#![allow(clippy::cast_possible_truncation)]
+
+use std::collections::HashMap;
+use std::path::Path;
+
+/// Largest palette: one index is kept back for transparent pixels.
+const MAX_COLOURS: usize = 255;
+
+/// Output container, chosen from the file extension.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum AnimationFormat {
+    Gif,
+    Apng,
+}
+
+impl AnimationFormat {
+    /// APNG for `.png` and `.apng`, GIF for anything else.
+    #[must_use]
+    pub fn from_path(path: &Path) -> Self {
+        let ext = path
+            .extension()
+            .and_then(|e| e.to_str())
+            .map(str::to_ascii_lowercase);
+        match ext.as_deref() {
+            Some("png" | "apng") => Self::Apng,
+            _ => Self::Gif,
+        }
+    }
+
+    /// Name reported in tool results.
+    #[must_use]
+    pub fn name(self) -> &'static str {
+        match self {
+            Self::Gif => "gif",
+            Self::Apng => "apng",
+        }
+    }
+}
+
+/// Information returned after a clip is written.
+pub struct AnimationInfo {
+    /// Frames in the file, after repeats were merged.
+    pub frames: usize,
+    /// Emulated frames the clip covers.
+    pub duration_frames: u64,
+    /// Colours in the palette.
+    pub colours: usize,
+    /// Encoded width and height.
+    pub width: u32,
+    pub height: u32,
+    /// File size in bytes.
+    pub size: usize,
+}
+
+/// One stored frame: palette indices and how many emulated frames it
+/// stays on screen.
+struct Frame {
+    pixels: Vec<u8>,
+    duration: u32,
+}
+
+/// Records emulator frames into an animated GIF or APNG.
+///
+/// Feed it every emulated frame with `add_frame()`; it keeps one in
+/// `every` and crops it to the chosen area. Call `finish()` to write the
+/// file.
+pub struct AnimationRecorder {
+    src_w: u32,
+    /// Area kept from each frame: x, y, width, height.
+    crop: (u32, u32, u32, u32),
+    fps: u32,
+    every: u32,
+    /// Frames offered so far, kept or not.
+    offered: u64,
+    palette: Vec<u32>,
+    /// RGB to palette index, including colours mapped to a near match.
+    indices: HashMap<u32, u8>,
+    frames: Vec<Frame>,
+}
+
+impl AnimationRecorder {
+    /// Create a recorder for a `width` × `height` framebuffer shown at
+    /// `fps`, keeping every `every`th frame. `crop` is the area to keep
+    /// (x, y, width, height); `None` keeps the whole frame.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if `every` is zero or the crop area is empty or
+    /// falls outside the framebuffer.
+    pub fn new(
+        width: u32,
+        height: u32,
+        fps: u32,
+        every: u32,
+        crop: Option<(u32, u32, u32, u32)>,
+    ) -> Result<Self, String> {
+        if every == 0 {
+            return Err("Frame interval must be at least 1".to_string());
+        }
+        let crop = crop.unwrap_or((0, 0, width, height));
+        let (x, y, w, h) = crop;
+        if w == 0 || h == 0 || x + w > width || y + h > height {
+            return Err(format!(
+                "Crop area {w}x{h} at ({x}, {y}) does not fit the {width}x{height} frame"
+            ));
+        }
+        if w > u32::from(u16::MAX) || h > u32::from(u16::MAX) {
+            return Err(format!("Frame size {w}x{h} is too large"));
+        }
+        Ok(Self {
+            src_w: width,
+            crop,
+            fps,
+            every,
+            offered: 0,
+            palette: Vec::new(),
+            indices: HashMap::new(),
+            frames: Vec::new(),
+        })
+    }
+
+    /// Offer the next emulated frame (0x00RRGGBB pixels; alpha is
+    /// ignored). Frames between kept ones are skipped.
+    pub fn add_frame(&mut self, pixels: &[u32]) {
+        let keep = self.offered.is_multiple_of(u64::from(self.every));
+        self.offered += 1;
+        if !keep {
+            return;
+        }
+
+        let (x, y, w, h) = self.crop;
+        let mut indexed = Vec::with_capacity((w * h) as usize);
+        for row in y..y + h {
+            let start = (row * self.src_w + x) as usize;
+            for col in 0..w as usize {
+                let rgb = pixels.get(start + col).copied().unwrap_or(0) & 0x00FF_FFFF;
+                indexed.push(self.index(rgb));
+            }
+        }
+
+        match self.frames.last_mut() {
+            Some(last) if last.pixels == indexed => last.duration += self.every,
+            _ => self.frames.push(Frame {
+                pixels: indexed,
+                duration: self.every,
+            }),
+        }
+    }
+
+    /// Frames kept so far, after repeats were merged.
+    #[must_use]
+    pub fn frame_count(&self) -> usize {
+        self.frames.len()
+    }
+
+    /// Palette index for a colour, adding it while there is room.
+    fn index(&mut self, rgb: u32) -> u8 {
+        if let Some(&index) = self.indices.get(&rgb) {
+            return index;
+        }
+        let index = if self.palette.len() < MAX_COLOURS {
+            self.palette.push(rgb);
+            (self.palette.len() - 1) as u8
+        } else {
+            nearest(&self.palette, rgb)
+        };
+        self.indices.insert(rgb, index);
+        index
+    }
+
+    /// Encode the clip and write it to `path`, as APNG for `.png` and
+    /// `.apng` and GIF otherwise.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if no frames were recorded, encoding fails, or
+    /// the file cannot be written.
+    pub fn finish(&self, path: &Path) -> Result<AnimationInfo, String> {
+        let bytes = self.encode(AnimationFormat::from_path(path))?;
+        if let Some(parent) = path.parent() {
+            std::fs::create_dir_all(parent).map_err(|e| format!("Create output directory: {e}"))?;
+        }
+        std::fs::write(path, &bytes).map_err(|e| format!("Write {}: {e}", path.display()))?;
+        Ok(AnimationInfo {
+            frames: self.frames.len(),
+            duration_frames: self
+                .frames
+                .iter()
+                .map(|frame| u64::from(frame.duration))
+                .sum(),
+            colours: self.palette.len(),
+            width: self.crop.2,
+            height: self.crop.3,
+            size: bytes.len(),
+        })
+    }
+
+    /// Encode the clip in memory.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if no frames were recorded or encoding fails.
+    pub fn encode(&self, format: AnimationFormat) -> Result<Vec<u8>, String> {
+        if self.frames.is_empty() {
+            return Err("No frames recorded".to_string());
+        }
+        match format {
+            AnimationFormat::Gif => self.encode_gif(),
+            AnimationFormat::Apng => self.encode_apng(),
+        }
+    }
+
+    /// Index that marks unchanged pixels in delta frames.
+    fn transparent(&self) -> u8 {
+        self.palette.len() as u8
+    }
+
+    /// Palette as RGB triples, with the transparent entry on the end.
+    fn palette_rgb(&self) -> Vec<u8> {
+        let mut rgb: Vec<u8> = self
+            .palette
+            .iter()
+            .flat_map(|&c| [(c >> 16) as u8, (c >> 8) as u8, c as u8])
+            .collect();
+        rgb.extend_from_slice(&[0, 0, 0]);
+        rgb
+    }
+
+    /// Each frame as the rectangle that changed since the previous one
+    /// (the whole frame for the first), with its start and duration in
+    /// emulated frames.
+    fn deltas(&self) -> Vec<Delta> {
+        let (_, _, w, h) = self.crop;
+        let transparent = self.transparent();
+        let mut start = 0u64;
+        let mut deltas = Vec::with_capacity(self.frames.len());
+        for (i, frame) in self.frames.iter().enumerate() {
+            let delta = match i.checked_sub(1).map(|p| &self.frames[p].pixels) {
+                None => Delta {
+                    x: 0,
+                    y: 0,
+                    width: w,
+                    height: h,
+                    pixels: frame.pixels.clone(),
+                    start,
+                    duration: frame.duration,
+                },
+                Some(prev) => {
+                    let (x0, y0, x1, y1) = changed_area(prev, &frame.pixels, w, h);
+                    let mut pixels = Vec::with_capacity(((x1 - x0) * (y1 - y0)) as usize);
+                    for y in y0..y1 {
+                        for x in x0..x1 {
+                            let at = (y * w + x) as usize;
+                            pixels.push(if frame.pixels[at] == prev[at] {
+                                transparent
+                            } else {
+                                frame.pixels[at]
+                            });
+                        }
+                    }
+                    Delta {
+                        x: x0,
+                        y: y0,
+                        width: x1 - x0,
+                        height: y1 - y0,
+                        pixels,
+                        start,
+                        duration: frame.duration,
+                    }
+                }
+            };
+            start += u64::from(frame.duration);
+            deltas.push(delta);
+        }
+        deltas
+    }
+
+    fn encode_gif(&self) -> Result<Vec<u8>, String> {
+        let fail = |e: gif::EncodingError| format!("GIF encode error: {e}");
+        let (_, _, w, h) = self.crop;
+        let mut buf = Vec::new();
+        let mut encoder =
+            gif::Encoder::new(&mut buf, w as u16, h as u16, &self.palette_rgb()).map_err(fail)?;
+        encoder.set_repeat(gif::Repeat::Infinite).map_err(fail)?;
+
+        let transparent = self.transparent();
+        // Delays are in hundredths of a second; rounding the start and
+        // end of each frame keeps the clip's total length exact.
+        let centis = |frames: u64| (frames * 100 + u64::from(self.fps) / 2) / u64::from(self.fps);
+        for (i, delta) in self.deltas().into_iter().enumerate() {
+            let end = delta.start + u64::from(delta.duration);
+            let delay = (centis(end) - centis(delta.start)).min(u64::from(u16::MAX)) as u16;
+            let frame = gif::Frame {
+                delay,
+                dispose: gif::DisposalMethod::Keep,
+                transparent: (i > 0).then_some(transparent),
+                left: delta.x as u16,
+                top: delta.y as u16,
+                width: delta.width as u16,
+                height: delta.height as u16,
+                buffer: delta.pixels.into(),
+                ..gif::Frame::default()
+            };
+            encoder.write_frame(&frame).map_err(fail)?;
+        }
+        drop(encoder);
+        Ok(buf)
+    }
+
+    fn encode_apng(&self) -> Result<Vec<u8>, String> {
+        let fail = |e: png::EncodingError| format!("APNG encode error: {e}");
+        let (_, _, w, h) = self.crop;
+        let mut buf = Vec::new();
+        let mut encoder = png::Encoder::new(&mut buf, w, h);
+        encoder.set_color(png::ColorType::Indexed);
+        encoder.set_depth(png::BitDepth::Eight);
+        encoder.set_palette(self.palette_rgb());
+        let mut alpha = vec![0xFF; self.palette.len()];
+        alpha.push(0);
+        encoder.set_trns(alpha);
+        encoder
+            .set_animated(self.frames.len() as u32, 0)
+            .map_err(fail)?;
+        let mut writer = encoder.write_header().map_err(fail)?;
+
+        // Frame delays are exact fractions: a frame lasting n emulated
+        // frames is n/fps seconds.
+        let fps = self.fps.min(u32::from(u16::MAX)) as u16;
+        for (i, delta) in self.deltas().into_iter().enumerate() {
+            let duration = delta.duration.min(u32::from(u16::MAX)) as u16;
+            writer.set_frame_delay(duration, fps).map_err(fail)?;
+            writer.reset_frame_position().map_err(fail)?;
+            writer
+                .set_frame_dimension(delta.width, delta.height)
+                .map_err(fail)?;
+            writer.set_frame_position(delta.x, delta.y).map_err(fail)?;
+            writer
+                .set_blend_op(if i == 0 {
+                    png::BlendOp::Source
+                } else {
+                    png::BlendOp::Over
+                })
+                .map_err(fail)?;
+            writer.write_image_data(&delta.pixels).map_err(fail)?;
+        }
+        writer.finish().map_err(fail)?;
+        Ok(buf)
+    }
+}
+
+/// A frame as written: the changed rectangle and its timing.
+struct Delta {
+    x: u32,
+    y: u32,
+    width: u32,
+    height: u32,
+    pixels: Vec<u8>,
+    /// First emulated frame it is shown on, counted from the clip start.
+    start: u64,
+    duration: u32,
+}
+
+/// Bounding box (x0, y0, x1, y1), exclusive, of the pixels that differ
+/// between two frames. Merged repeats guarantee at least one does.
+fn changed_area(prev: &[u8], next: &[u8], width: u32, height: u32) -> (u32, u32, u32, u32) {
+    let (mut x0, mut y0, mut x1, mut y1) = (width, height, 0, 0);
+    for y in 0..height {
+        let row = (y * width) as usize..((y + 1) * width) as usize;
+        let (a, b) = (&prev[row.clone()], &next[row]);
+        if a == b {
+            continue;
+        }
+        let first = a.iter().zip(b).position(|(p, n)| p != n).unwrap_or(0) as u32;
+        let last = a.iter().zip(b).rposition(|(p, n)| p != n).unwrap_or(0) as u32;
+        x0 = x0.min(first);
+        x1 = x1.max(last + 1);
+        y0 = y0.min(y);
+        y1 = y + 1;
+    }
+    (x0, y0, x1, y1)
+}
+
+/// Index of the palette colour closest to `rgb`.
+fn nearest(palette: &[u32], rgb: u32) -> u8 {
+    let channels = |c: u32| [(c >> 16) & 0xFF, (c >> 8) & 0xFF, c & 0xFF].map(|v| v as i32);
+    let target = channels(rgb);
+    palette
+        .iter()
+        .enumerate()
+        .min_by_key(|&(_, &c)| {
+            channels(c)
+                .iter()
+                .zip(target)
+                .map(|(a, b)| (a - b).pow(2))
+                .sum::<i32>()
+        })
+        .map_or(0, |(i, _)| i as u8)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// A 16×8 frame of `background` with a one-line bar of `bar` at `line`.
+    fn bar_frame(line: u32, background: u32, bar: u32) -> Vec<u32> {
+        (0..16 * 8)
+            .map(|i| if i / 16 == line { bar } else { background })
+            .collect()
+    }
+
+    #[test]
+    fn repeats_merge_and_skipped_frames_stretch() {
+        let mut rec = AnimationRecorder::new(16, 8, 50, 2, None).expect("recorder");
+        for line in [0, 0, 0, 0, 1, 1, 2, 2] {
+            rec.add_frame(&bar_frame(line, 0, 0x00FF_FFFF));
+        }
+        // Kept: 0, 0, 1, 2 → the two zeros merge.
+        assert_eq!(rec.frame_count(), 3);
+        assert_eq!(rec.frames[0].duration, 4);
+        assert_eq!(rec.palette, [0x00FF_FFFF, 0]);
+
+        let deltas = rec.deltas();
+        assert_eq!((deltas[1].x, deltas[1].y), (0, 0));
+        assert_eq!((deltas[1].width, deltas[1].height), (16, 2));
+        assert_eq!(deltas[2].start, 6);
+    }
+
+    #[test]
+    fn gif_decodes_to_the_recorded_frames() {
+        let mut rec = AnimationRecorder::new(16, 8, 50, 1, Some((0, 2, 16, 4))).expect("recorder");
+        for line in 2..6 {
+            rec.add_frame(&bar_frame(line, 0x0010_1020, 0x00E0_4040));
+        }
+        let gif = rec.encode(AnimationFormat::Gif).expect("gif");
+
+        let mut options = gif::DecodeOptions::new();
+        options.set_color_output(gif::ColorOutput::RGBA);
+        let mut decoder = options.read_info(gif.as_slice()).expect("decoder");
+        assert_eq!((decoder.width(), decoder.height()), (16, 4));
+        let mut screen = vec![0u8; 16 * 4 * 4];
+        let mut frames = 0;
+        while let Some(frame) = decoder.read_next_frame().expect("frame") {
+            assert_eq!(frame.delay, 2);
+            // Composite the delta onto the screen, skipping transparency.
+            for y in 0..usize::from(frame.height) {
+                for x in 0..usize::from(frame.width) {
+                    let src = (y * usize::from(frame.width) + x) * 4;
+                    if frame.buffer[src + 3] == 0 {
+                        continue;
+                    }
+                    let dst = ((y + usize::from(frame.top)) * 16 + x + usize::from(frame.left)) * 4;
+                    screen[dst..dst + 4].copy_from_slice(&frame.buffer[src..src + 4]);
+                }
+            }
+            let bar_row = frames;
+            for y in 0..4 {
+                let expected = if y == bar_row {
+                    [0xE0, 0x40, 0x40]
+                } else {
+                    [0x10, 0x10, 0x20]
+                };
+                assert_eq!(
+                    screen[y * 64..y * 64 + 3],
+                    expected,
+                    "frame {frames} row {y}"
+                );
+            }
+            frames += 1;
+        }
+        assert_eq!(frames, 4);
+    }
+
+    #[test]
+    fn apng_frames_cover_only_the_change() {
+        let mut rec = AnimationRecorder::new(16, 8, 60, 1, None).expect("recorder");
+        rec.add_frame(&bar_frame(3, 0, 0x0000_FF00));
+        rec.add_frame(&bar_frame(3, 0, 0x0000_FF00));
+        rec.add_frame(&bar_frame(4, 0, 0x0000_FF00));
+        let apng = rec.encode(AnimationFormat::Apng).expect("apng");
+
+        let mut reader = png::Decoder::new(apng.as_slice())
+            .read_info()
+            .expect("reader");
+        let info = reader.info();
+        assert_eq!(info.animation_control.map(|a| a.num_frames), Some(2));
+        assert_eq!(info.color_type, png::ColorType::Indexed);
+
+        let mut buf = vec![0; reader.output_buffer_size()];
+        reader.next_frame(&mut buf).expect("first frame");
+        reader.next_frame(&mut buf).expect("second frame");
+        let control = reader.info().frame_control.expect("fcTL");
+        assert_eq!((control.y_offset, control.height), (3, 2));
+        assert_eq!((control.delay_num, control.delay_den), (1, 60));
+    }
+
+    #[test]
+    fn colours_past_the_palette_map_to_the_nearest() {
+        let mut rec = AnimationRecorder::new(256, 1, 50, 1, None).expect("recorder");
+        let ramp: Vec<u32> = (0..256).map(|v| v * 0x0001_0101).collect();
+        rec.add_frame(&ramp);
+        assert_eq!(rec.palette.len(), MAX_COLOURS);
+        assert_eq!(rec.frames[0].pixels[255], 254);
+
+        assert!(AnimationRecorder::new(16, 8, 50, 0, None).is_err());
+        assert!(AnimationRecorder::new(16, 8, 50, 1, Some((8, 0, 16, 8))).is_err());
+        assert_eq!(
+            AnimationFormat::from_path(Path::new("clip.APNG")),
+            AnimationFormat::Apng
+        );
+    }
+}
@@ -55,6 +55,13 @@ impl AudioQueue {
         self.primed = false;
     }
 
+    /// Queue level scaled so the target latency sits at 0.5 and twice the
+    /// target at 1.0, for [`crate::blip::dynamic_rate`].
+    #[allow(clippy::cast_precision_loss)]
+    fn fill_ratio(&self) -> f64 {
+        self.samples.len() as f64 / (self.target_samples * 2) as f64
+    }
+
     fn push_frames(&mut self, frames: &[AudioFrame]) {
         if frames.is_empty() {
             return;
@@ -107,6 +114,13 @@ pub(crate) struct AudioOutput {
 }
 
 impl AudioOutput {
+    /// Sample rate of the default output device's preferred config.
+    pub(crate) fn device_sample_rate() -> Option<u32> {
+        let device = cpal::default_host().default_output_device()?;
+        let config = device.default_output_config().ok()?;
+        Some(config.sample_rate().0)
+    }
+
     pub(crate) fn new(sample_rate: u32, frame_duration: Duration) -> Result<Self, String> {
         let host = cpal::default_host();
         let device = host
@@ -207,6 +221,10 @@ impl AudioOutput {
         };
         queue.push_frames(frames);
     }
+
+    pub(crate) fn fill_ratio(&self) -> Option<f64> {
+        self.queue.lock().ok().map(|queue| queue.fill_ratio())
+    }
 }
 
 fn samples_per_duration(sample_rate: u32, duration: Duration) -> usize {
@@ -289,6 +307,14 @@ mod tests {
         assert_eq!(data, [0.5, 0.6, 0.7, 0.8]);
     }
 
+    #[test]
+    fn fill_ratio_is_half_at_target() {
+        let mut queue = AudioQueue::with_thresholds(4, 8);
+        assert!(queue.fill_ratio().abs() < f64::EPSILON);
+        queue.push_frames(&[[0.1, 0.2], [0.3, 0.4]]);
+        assert!((queue.fill_ratio() - 0.5).abs() < f64::EPSILON);
+    }
+
     #[test]
     fn queue_reprimes_after_underrun() {
         let queue = Arc::new(Mutex::new(AudioQueue::with_thresholds(4, 8)));
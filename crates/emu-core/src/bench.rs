@@ -0,0 +1,389 @@
+//! Headless benchmarking and per-chip profiling.
+//!
+//! [`run`] times a batch of frames with no pacing, rendering or audio
+//! output, and reports emulated frames per second and the CPU clock rate
+//! achieved. Machines that carry a [`ChipProfiler`] also report how the
+//! time splits between the chip crates they tick, which shows where
+//! optimisation will pay.
+//!
+//! Reading the clock costs about as much as ticking a simple chip, so the
+//! profiler times only one master tick in [`ChipProfiler::INTERVAL`] and
+//! scales the totals up. The benchmark takes its speed figures from an
+//! unprofiled pass and runs a second, profiled pass for the breakdown.
+
+use std::fmt;
+use std::time::{Duration, Instant};
+
+use crate::Machine;
+
+/// Sampled per-chip tick timer.
+///
+/// A machine calls [`start`](Self::start) at the top of each master tick,
+/// then [`lap`](Self::lap) after each chip it ticks. On a sampled tick each
+/// lap charges the time since the previous one, less the cost of reading
+/// the clock, to that chip; on every other tick both calls return after one
+/// branch.
+#[derive(Debug, Clone)]
+pub struct ChipProfiler {
+    /// Chip crate names, indexed by the `chip` argument to `lap`.
+    names: &'static [&'static str],
+    /// Time measured for each chip on sampled ticks.
+    totals: Vec<Duration>,
+    enabled: bool,
+    /// Ticks left before the next sampled one.
+    countdown: u32,
+    /// Time of the last lap on a sampled tick; `None` between samples.
+    last: Option<Instant>,
+    /// Cost of one clock read, taken off every lap.
+    overhead: Duration,
+}
+
+impl ChipProfiler {
+    /// One tick in this many is timed. Prime, so the samples do not line
+    /// up with scanline or CPU-divider periods.
+    pub const INTERVAL: u32 = 61;
+
+    /// Create a disabled profiler for the named chips.
+    #[must_use]
+    pub fn new(names: &'static [&'static str]) -> Self {
+        Self {
+            names,
+            totals: vec![Duration::ZERO; names.len()],
+            enabled: false,
+            countdown: 0,
+            last: None,
+            overhead: Duration::ZERO,
+        }
+    }
+
+    /// Start or stop sampling. Either way the totals are cleared.
+    pub fn set_enabled(&mut self, enabled: bool) {
+        self.enabled = enabled;
+        self.totals.fill(Duration::ZERO);
+        self.countdown = 0;
+        self.last = None;
+        if enabled {
+            self.overhead = clock_read_cost();
+        }
+    }
+
+    /// Whether sampling is on.
+    #[must_use]
+    pub fn is_enabled(&self) -> bool {
+        self.enabled
+    }
+
+    /// Mark the start of a master tick.
+    #[inline]
+    pub fn start(&mut self) {
+        if !self.enabled {
+            return;
+        }
+        if self.countdown == 0 {
+            self.countdown = Self::INTERVAL - 1;
+            self.last = Some(Instant::now());
+        } else {
+            self.countdown -= 1;
+            self.last = None;
+        }
+    }
+
+    /// Charge the time since the previous lap to `chip`.
+    #[inline]
+    pub fn lap(&mut self, chip: usize) {
+        if let Some(last) = self.last {
+            let now = Instant::now();
+            self.totals[chip] += (now - last).saturating_sub(self.overhead);
+            self.last = Some(now);
+        }
+    }
+
+    /// Estimated time spent in each chip: the sampled totals scaled by
+    /// [`INTERVAL`](Self::INTERVAL).
+    #[must_use]
+    pub fn times(&self) -> Vec<(&'static str, Duration)> {
+        self.names
+            .iter()
+            .zip(&self.totals)
+            .map(|(&name, &total)| (name, total * Self::INTERVAL))
+            .collect()
+    }
+}
+
+/// Average time of one `Instant::now()`.
+fn clock_read_cost() -> Duration {
+    const READS: u32 = 1000;
+    let start = Instant::now();
+    for _ in 0..READS {
+        std::hint::black_box(Instant::now());
+    }
+    start.elapsed() / READS
+}
+
+/// Result of [`run`].
+#[derive(Debug, Clone)]
+pub struct BenchReport {
+    /// Frames run in each pass.
+    pub frames: u32,
+    /// Wall time of the unprofiled pass.
+    pub elapsed: Duration,
+    /// The machine's CPU clock, or zero if unknown.
+    pub cpu_clock_hz: f64,
+    /// The machine's frame rate, or zero if unknown.
+    pub frame_rate_hz: f64,
+    /// Estimated time in each chip during the profiled pass. Empty if the
+    /// machine is not instrumented.
+    pub chips: Vec<(&'static str, Duration)>,
+    /// Wall time of the profiled pass.
+    pub profiled_elapsed: Duration,
+}
+
+impl BenchReport {
+    /// Emulated frames per wall-clock second.
+    #[must_use]
+    pub fn fps(&self) -> f64 {
+        f64::from(self.frames) / self.elapsed.as_secs_f64().max(f64::MIN_POSITIVE)
+    }
+
+    /// Speed as a multiple of real time, or zero if the frame rate is
+    /// unknown.
+    #[must_use]
+    pub fn speed(&self) -> f64 {
+        if self.frame_rate_hz > 0.0 {
+            self.fps() / self.frame_rate_hz
+        } else {
+            0.0
+        }
+    }
+
+    /// Effective CPU clock achieved, in MHz.
+    #[must_use]
+    pub fn mhz(&self) -> f64 {
+        self.cpu_clock_hz * self.speed() / 1_000_000.0
+    }
+}
+
+impl fmt::Display for BenchReport {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        writeln!(
+            f,
+            "Frames:  {} in {:.3} s",
+            self.frames,
+            self.elapsed.as_secs_f64()
+        )?;
+        if self.frame_rate_hz > 0.0 {
+            writeln!(
+                f,
+                "Speed:   {:.1} fps, {:.2}x real time",
+                self.fps(),
+                self.speed()
+            )?;
+        } else {
+            writeln!(f, "Speed:   {:.1} fps", self.fps())?;
+        }
+        if self.cpu_clock_hz > 0.0 && self.frame_rate_hz > 0.0 {
+            writeln!(
+                f,
+                "CPU:     {:.2} MHz achieved ({:.3} MHz emulated clock)",
+                self.mhz(),
+                self.cpu_clock_hz / 1_000_000.0
+            )?;
+        }
+        if self.chips.is_empty() {
+            return writeln!(f, "Chips:   no per-chip timing for this machine");
+        }
+
+        writeln!(f, "Chips:   sampled 1 tick in {}", ChipProfiler::INTERVAL)?;
+        let mut chips = self.chips.clone();
+        chips.sort_by_key(|&(_, time)| std::cmp::Reverse(time));
+        let width = chips.iter().map(|(name, _)| name.len()).max().unwrap_or(0);
+        let attributed: Duration = chips.iter().map(|&(_, time)| time).sum();
+        // Sampling error can put the estimates a little over the wall time.
+        let wall = self
+            .profiled_elapsed
+            .max(attributed)
+            .as_secs_f64()
+            .max(f64::MIN_POSITIVE);
+        for (name, time) in &chips {
+            writeln!(
+                f,
+                "  {name:<width$}  {:5.1}%",
+                time.as_secs_f64() / wall * 100.0
+            )?;
+        }
+        let other = self.profiled_elapsed.saturating_sub(attributed);
+        writeln!(
+            f,
+            "  {:<width$}  {:5.1}%",
+            "other",
+            other.as_secs_f64() / wall * 100.0
+        )
+    }
+}
+
+/// Run `frames` frames flat out and report the speed.
+///
+/// If the machine is instrumented, a second pass with chip profiling on
+/// gives the breakdown. When the machine supports save states the second
+/// pass replays the same frames from a snapshot; otherwise it carries on
+/// from where the first stopped.
+pub fn run<M: Machine>(machine: &mut M, frames: u32) -> BenchReport {
+    let snapshot = machine.save_state().ok();
+    let elapsed = time_frames(machine, frames);
+
+    let mut chips = Vec::new();
+    let mut profiled_elapsed = Duration::ZERO;
+    if machine.set_chip_profiling(true) {
+        if let Some(state) = &snapshot
+            && machine.load_state(state).is_err()
+        {
+            // Partially restored; start the profile from a clean machine.
+            machine.reset();
+        }
+        profiled_elapsed = time_frames(machine, frames);
+        chips = machine.chip_times();
+        machine.set_chip_profiling(false);
+    }
+
+    BenchReport {
+        frames,
+        elapsed,
+        cpu_clock_hz: machine.cpu_clock_hz(),
+        frame_rate_hz: machine.frame_rate_hz(),
+        chips,
+        profiled_elapsed,
+    }
+}
+
+/// Wall time to run `frames` frames, draining audio as a frontend would.
+fn time_frames<M: Machine>(machine: &mut M, frames: u32) -> Duration {
+    let start = Instant::now();
+    for _ in 0..frames {
+        machine.run_frame();
+        let _ = machine.take_audio_buffer();
+    }
+    start.elapsed()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::AudioFrame;
+
+    /// Two chips per tick; the second spins longer than the first.
+    struct Spinner {
+        frames: u64,
+        profiler: ChipProfiler,
+        sink: u64,
+    }
+
+    impl Spinner {
+        fn new() -> Self {
+            Self {
+                frames: 0,
+                profiler: ChipProfiler::new(&["fast-chip", "slow-chip"]),
+                sink: 0,
+            }
+        }
+
+        fn spin(&mut self, n: u64) {
+            for i in 0..n {
+                self.sink = std::hint::black_box(self.sink.wrapping_mul(31).wrapping_add(i));
+            }
+        }
+    }
+
+    impl Machine for Spinner {
+        fn run_frame(&mut self) {
+            for _ in 0..1000 {
+                self.profiler.start();
+                self.spin(10);
+                self.profiler.lap(0);
+                self.spin(400);
+                self.profiler.lap(1);
+            }
+            self.frames += 1;
+        }
+
+        fn framebuffer(&self) -> &[u32] {
+            &[]
+        }
+
+        fn framebuffer_width(&self) -> u32 {
+            0
+        }
+
+        fn framebuffer_height(&self) -> u32 {
+            0
+        }
+
+        fn take_audio_buffer(&mut self) -> Vec<AudioFrame> {
+            Vec::new()
+        }
+
+        fn frame_count(&self) -> u64 {
+            self.frames
+        }
+
+        fn reset(&mut self) {
+            self.frames = 0;
+        }
+
+        fn cpu_clock_hz(&self) -> f64 {
+            1_000_000.0
+        }
+
+        fn frame_rate_hz(&self) -> f64 {
+            50.0
+        }
+
+        fn set_chip_profiling(&mut self, enabled: bool) -> bool {
+            self.profiler.set_enabled(enabled);
+            true
+        }
+
+        fn chip_times(&self) -> Vec<(&'static str, Duration)> {
+            self.profiler.times()
+        }
+    }
+
+    #[test]
+    fn disabled_profiler_records_nothing() {
+        let mut machine = Spinner::new();
+        machine.run_frame();
+        assert!(machine.chip_times().iter().all(|(_, time)| time.is_zero()));
+    }
+
+    #[test]
+    fn profiler_charges_each_chip() {
+        let mut machine = Spinner::new();
+        machine.set_chip_profiling(true);
+        for _ in 0..5 {
+            machine.run_frame();
+        }
+        let times = machine.chip_times();
+        assert_eq!(times[0].0, "fast-chip");
+        assert_eq!(times[1].0, "slow-chip");
+        assert!(times[1].1 > times[0].1, "{times:?}");
+
+        machine.set_chip_profiling(false);
+        assert!(machine.chip_times().iter().all(|(_, time)| time.is_zero()));
+    }
+
+    #[test]
+    fn report_includes_speed_and_chips() {
+        let mut machine = Spinner::new();
+        let report = run(&mut machine, 4);
+        assert_eq!(report.frames, 4);
+        assert!(report.fps() > 0.0);
+        assert!((report.mhz() - report.speed()).abs() < 1e-9 * report.speed().max(1.0));
+        assert_eq!(report.chips.len(), 2);
+        assert!(!machine.profiler.is_enabled());
+
+        let text = report.to_string();
+        assert!(text.contains("slow-chip"), "{text}");
+        assert!(text.contains("MHz achieved"), "{text}");
+        // Slowest chip first.
+        assert!(text.find("slow-chip") < text.find("fast-chip"), "{text}");
+    }
+}
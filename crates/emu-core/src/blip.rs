@@ -0,0 +1,520 @@
+//! Band-limited step synthesis.
+//!
+//! Sound chips change their output level at their native clock, often
+//! millions of times a second. Averaging that signal down to the host rate
+//! is a poor low-pass filter: square waves and noise above Nyquist fold
+//! back into the audible band as inharmonic whine. `BlipBuffer` records
+//! each level change as a band-limited step (a windowed-sinc BLEP) placed
+//! at its exact sub-sample position, then integrates the steps into output
+//! samples. The output is alias-free at any rate.
+//!
+//! A chip calls [`BlipBuffer::set_level`] whenever its mixed output may
+//! have changed and [`BlipBuffer::advance`] once per native clock. Each
+//! step reaches the output `WIDTH / 2` samples after it happens.
+//!
+//! The output rate can be changed at any time. [`BlipBuffer::set_rate_adjust`]
+//! stretches it by a small ratio, so a runner can hold its audio queue at
+//! a steady fill level when the host's audio clock drifts from the emulated
+//! frame rate (see [`dynamic_rate`]).
+//!
+//! [`Stems`] runs one extra buffer per chip channel, so each voice can be
+//! captured on its own alongside the mix.
+
+#![allow(clippy::cast_precision_loss)]
+
+use std::collections::VecDeque;
+use std::f64::consts::PI;
+use std::sync::LazyLock;
+
+use crate::{SaveState, StateError, StateReader, StateWriter};
+
+/// Output samples each step is spread across.
+const WIDTH: usize = 16;
+
+/// Sub-sample step positions resolved by the kernel.
+const PHASE_BITS: u32 = 6;
+const PHASES: usize = 1 << PHASE_BITS;
+
+/// Fractional bits of the fixed-point time position.
+const FRAC_BITS: u32 = 32;
+const ONE: u64 = 1 << FRAC_BITS;
+
+/// Kernel cutoff as a fraction of the output rate. Just under Nyquist
+/// (0.5) so the window's transition band stays mostly above it.
+const CUTOFF: f64 = 0.45;
+
+/// Quadrature points per tap when integrating the impulse response.
+const STEPS: usize = 32;
+
+type Kernel = [[f64; WIDTH]; PHASES];
+
+/// Step kernels, one per sub-sample phase. Each tap is the share of a unit
+/// step that lands on that output sample; every phase sums to exactly 1 so
+/// a step always settles at its full height.
+static KERNEL: LazyLock<Kernel> = LazyLock::new(|| {
+    let mut kernel = [[0.0; WIDTH]; PHASES];
+    for (phase, taps) in kernel.iter_mut().enumerate() {
+        let offset = phase as f64 / PHASES as f64;
+        for (i, tap) in taps.iter_mut().enumerate() {
+            let start = i as f64 - (WIDTH / 2) as f64 - offset;
+            *tap = (0..STEPS)
+                .map(|k| impulse(start + (k as f64 + 0.5) / STEPS as f64))
+                .sum::<f64>()
+                / STEPS as f64;
+        }
+        let sum: f64 = taps.iter().sum();
+        for tap in taps.iter_mut() {
+            *tap /= sum;
+        }
+    }
+    kernel
+});
+
+/// Blackman-windowed sinc low-pass impulse response, `t` in output samples.
+fn impulse(t: f64) -> f64 {
+    let half = (WIDTH / 2) as f64;
+    if t.abs() >= half {
+        return 0.0;
+    }
+    let x = 2.0 * CUTOFF * t;
+    let sinc = if x.abs() < 1e-12 {
+        1.0
+    } else {
+        (PI * x).sin() / (PI * x)
+    };
+    let w = t / half;
+    let window = 0.42 + 0.5 * (PI * w).cos() + 0.08 * (2.0 * PI * w).cos();
+    2.0 * CUTOFF * sinc * window
+}
+
+/// Output-rate adjustment that steers a host audio queue toward half full.
+///
+/// `fill` is the queue's level as a fraction of its capacity (0.0 empty,
+/// 1.0 full). The result runs from `1 + max_skew` when empty to
+/// `1 - max_skew` when full: the emulator makes slightly more samples per
+/// frame while the host drains faster than it fills, and slightly fewer
+/// while the host falls behind. Pass it to [`BlipBuffer::set_rate_adjust`].
+/// A skew of 0.005 corrects well over any real clock drift and its pitch
+/// change is inaudible.
+#[must_use]
+pub fn dynamic_rate(fill: f64, max_skew: f64) -> f64 {
+    1.0 + max_skew * (1.0 - 2.0 * fill.clamp(0.0, 1.0))
+}
+
+/// Band-limited resampler from a chip's native clock to a host sample rate.
+pub struct BlipBuffer {
+    clock_rate: f64,
+    sample_rate: u32,
+    rate_adjust: f64,
+    /// Output samples per input clock, 32.32 fixed point.
+    factor: u64,
+    /// Time past the last finished sample, 32.32 fixed point, below 1.0.
+    offset: u64,
+    /// Pending step contributions for the next `WIDTH` output samples.
+    ring: [f64; WIDTH],
+    head: usize,
+    kernel: &'static Kernel,
+    /// Level most recently passed to `set_level`.
+    level: f32,
+    /// Running sum of finished contributions: the current output level.
+    integrator: f64,
+    /// DC-blocking high-pass cutoff in Hz, or 0 for none.
+    high_pass_hz: f32,
+    hp_alpha: f32,
+    hp_prev_in: f32,
+    hp_prev_out: f32,
+    samples: VecDeque<f32>,
+}
+
+impl BlipBuffer {
+    /// Create a buffer for a source clocked at `clock_rate` Hz, producing
+    /// `sample_rate` samples per second.
+    #[must_use]
+    pub fn new(clock_rate: f64, sample_rate: u32) -> Self {
+        let mut blip = Self {
+            clock_rate,
+            sample_rate,
+            rate_adjust: 1.0,
+            factor: 0,
+            offset: 0,
+            ring: [0.0; WIDTH],
+            head: 0,
+            kernel: &KERNEL,
+            level: 0.0,
+            integrator: 0.0,
+            high_pass_hz: 0.0,
+            hp_alpha: 1.0,
+            hp_prev_in: 0.0,
+            hp_prev_out: 0.0,
+            samples: VecDeque::with_capacity(sample_rate as usize / 50 + WIDTH),
+        };
+        blip.update_rate();
+        blip
+    }
+
+    /// Add a DC-blocking high-pass filter to the output.
+    ///
+    /// Most chips' raw output is unipolar and was AC-coupled by the
+    /// machine's audio circuit. A cutoff around 37 Hz matches that.
+    #[must_use]
+    pub fn with_high_pass(mut self, cutoff_hz: f32) -> Self {
+        self.high_pass_hz = cutoff_hz;
+        self.update_rate();
+        self
+    }
+
+    /// Output samples per second, ignoring any rate adjustment.
+    #[must_use]
+    pub fn sample_rate(&self) -> u32 {
+        self.sample_rate
+    }
+
+    /// Change the output rate. Pending steps carry over, so this can be
+    /// done mid-stream without a click.
+    pub fn set_sample_rate(&mut self, sample_rate: u32) {
+        self.sample_rate = sample_rate;
+        self.update_rate();
+    }
+
+    /// Change the input clock rate (for example on a PAL/NTSC switch).
+    pub fn set_clock_rate(&mut self, clock_rate: f64) {
+        self.clock_rate = clock_rate;
+        self.update_rate();
+    }
+
+    /// Stretch the output rate by `ratio` (1.0 = exact).
+    ///
+    /// Used for dynamic rate control: a ratio of 1.002 produces 0.2% more
+    /// samples per emulated second without touching the nominal rate.
+    pub fn set_rate_adjust(&mut self, ratio: f64) {
+        self.rate_adjust = ratio;
+        self.update_rate();
+    }
+
+    fn update_rate(&mut self) {
+        let rate = f64::from(self.sample_rate) * self.rate_adjust;
+        self.factor = (rate / self.clock_rate * ONE as f64).round() as u64;
+        // One-pole RC high-pass: alpha = RC / (RC + dt).
+        let omega = 2.0 * std::f32::consts::PI * self.high_pass_hz / self.sample_rate as f32;
+        self.hp_alpha = 1.0 / (1.0 + omega);
+    }
+
+    /// Set the source's output level at the current time.
+    ///
+    /// Calling this with an unchanged level costs nothing, so chips can
+    /// call it after every clock.
+    #[allow(clippy::float_cmp)] // Exact: only skips work for an identical level.
+    pub fn set_level(&mut self, level: f32) {
+        if level == self.level {
+            return;
+        }
+        let delta = f64::from(level) - f64::from(self.level);
+        self.level = level;
+
+        let phase = (self.offset >> (FRAC_BITS - PHASE_BITS)) as usize;
+        for (i, &tap) in self.kernel[phase].iter().enumerate() {
+            self.ring[(self.head + i) % WIDTH] += delta * tap;
+        }
+    }
+
+    /// Advance time by `clocks` source clocks, finishing every output
+    /// sample that falls inside them.
+    pub fn advance(&mut self, clocks: u32) {
+        self.offset += u64::from(clocks) * self.factor;
+        while self.offset >= ONE {
+            self.offset -= ONE;
+            self.integrator += self.ring[self.head];
+            self.ring[self.head] = 0.0;
+            self.head = (self.head + 1) % WIDTH;
+
+            let mut sample = self.integrator as f32;
+            if self.high_pass_hz > 0.0 {
+                let filtered = self.hp_alpha * (self.hp_prev_out + sample - self.hp_prev_in);
+                self.hp_prev_in = sample;
+                self.hp_prev_out = filtered;
+                sample = filtered;
+            }
+            self.samples.push_back(sample);
+        }
+    }
+
+    /// Number of finished samples waiting to be taken.
+    #[must_use]
+    pub fn len(&self) -> usize {
+        self.samples.len()
+    }
+
+    /// True when no finished samples are waiting.
+    #[must_use]
+    pub fn is_empty(&self) -> bool {
+        self.samples.is_empty()
+    }
+
+    /// Remove the oldest finished sample.
+    pub fn pop_sample(&mut self) -> Option<f32> {
+        self.samples.pop_front()
+    }
+
+    /// Drain every finished sample.
+    pub fn take_samples(&mut self) -> Vec<f32> {
+        Vec::from(std::mem::take(&mut self.samples))
+    }
+
+    /// Return to silence, discarding pending steps and finished samples.
+    pub fn clear(&mut self) {
+        self.offset = 0;
+        self.ring = [0.0; WIDTH];
+        self.head = 0;
+        self.level = 0.0;
+        self.integrator = 0.0;
+        self.hp_prev_in = 0.0;
+        self.hp_prev_out = 0.0;
+        self.samples.clear();
+    }
+}
+
+impl SaveState for BlipBuffer {
+    fn save_state(&self, w: &mut StateWriter) {
+        w.write_u64(self.offset);
+        for i in 0..WIDTH {
+            w.write_f64(self.ring[(self.head + i) % WIDTH]);
+        }
+        w.write_f32(self.level);
+        w.write_f64(self.integrator);
+        w.write_f32(self.hp_prev_in);
+        w.write_f32(self.hp_prev_out);
+        let (front, back) = self.samples.as_slices();
+        w.write_usize(self.samples.len());
+        for &s in front.iter().chain(back) {
+            w.write_f32(s);
+        }
+    }
+
+    fn load_state(&mut self, r: &mut StateReader<'_>) -> Result<(), StateError> {
+        self.offset = r.read_u64()?;
+        for slot in &mut self.ring {
+            *slot = r.read_f64()?;
+        }
+        self.head = 0;
+        self.level = r.read_f32()?;
+        self.integrator = r.read_f64()?;
+        self.hp_prev_in = r.read_f32()?;
+        self.hp_prev_out = r.read_f32()?;
+        self.samples = r.read_f32_vec()?.into();
+        Ok(())
+    }
+}
+
+/// Per-channel output streams for stem capture.
+///
+/// A chip creates this from its main buffer when stems are requested, then
+/// passes its per-channel taps to [`Stems::record`] once per native clock.
+/// Every stem shares the main buffer's clock, output rate, rate adjustment
+/// and high-pass, and starts on the same sample boundary, so stem `n`
+/// lines up sample for sample with the mix.
+pub struct Stems {
+    buffers: Vec<BlipBuffer>,
+}
+
+impl Stems {
+    /// Start one stream per entry in `levels`, each already settled at
+    /// that level so capture starts without a click.
+    #[must_use]
+    pub fn new(template: &BlipBuffer, levels: &[f32]) -> Self {
+        let buffers = levels
+            .iter()
+            .map(|&level| {
+                let mut blip = BlipBuffer::new(template.clock_rate, template.sample_rate);
+                blip.high_pass_hz = template.high_pass_hz;
+                blip.set_rate_adjust(template.rate_adjust);
+                blip.offset = template.offset;
+                blip.level = level;
+                blip.integrator = f64::from(level);
+                blip.hp_prev_in = level;
+                blip
+            })
+            .collect();
+        Self { buffers }
+    }
+
+    /// Number of channels.
+    #[must_use]
+    pub fn channels(&self) -> usize {
+        self.buffers.len()
+    }
+
+    /// Set each channel's level, then advance all of them by `clocks`.
+    pub fn record(&mut self, levels: &[f32], clocks: u32) {
+        for (blip, &level) in self.buffers.iter_mut().zip(levels) {
+            blip.set_level(level);
+            blip.advance(clocks);
+        }
+    }
+
+    /// Change the output rate of every channel.
+    pub fn set_sample_rate(&mut self, sample_rate: u32) {
+        for blip in &mut self.buffers {
+            blip.set_sample_rate(sample_rate);
+        }
+    }
+
+    /// Stretch the output rate of every channel.
+    pub fn set_rate_adjust(&mut self, ratio: f64) {
+        for blip in &mut self.buffers {
+            blip.set_rate_adjust(ratio);
+        }
+    }
+
+    /// Drain every finished sample, one `Vec` per channel.
+    pub fn take(&mut self) -> Vec<Vec<f32>> {
+        self.buffers
+            .iter_mut()
+            .map(BlipBuffer::take_samples)
+            .collect()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Feed a square wave of `freq` Hz from a 1 MHz clock for one second.
+    fn square(blip: &mut BlipBuffer, freq: u32) -> Vec<f32> {
+        let half_period = 1_000_000 / (freq * 2);
+        for clock in 0..1_000_000 {
+            let high = (clock / half_period).is_multiple_of(2);
+            blip.set_level(if high { 0.5 } else { -0.5 });
+            blip.advance(1);
+        }
+        blip.take_samples()
+    }
+
+    /// Magnitude of the `freq` Hz component of `samples` at `rate`.
+    fn tone_magnitude(samples: &[f32], freq: f64, rate: f64) -> f64 {
+        let (mut re, mut im) = (0.0, 0.0);
+        for (n, &s) in samples.iter().enumerate() {
+            let angle = 2.0 * PI * freq * n as f64 / rate;
+            re += f64::from(s) * angle.cos();
+            im += f64::from(s) * angle.sin();
+        }
+        (re * re + im * im).sqrt() / samples.len() as f64
+    }
+
+    #[test]
+    fn kernel_phases_sum_to_one() {
+        for taps in KERNEL.iter() {
+            let sum: f64 = taps.iter().sum();
+            assert!((sum - 1.0).abs() < 1e-12);
+        }
+    }
+
+    #[test]
+    fn produces_the_configured_rate() {
+        for rate in [44_100, 48_000, 96_000] {
+            let mut blip = BlipBuffer::new(1_000_000.0, rate);
+            blip.advance(1_000_000);
+            let n = blip.len() as i64;
+            assert!((n - i64::from(rate)).abs() <= 1, "{rate}: {n}");
+        }
+    }
+
+    #[test]
+    fn step_settles_at_its_level() {
+        let mut blip = BlipBuffer::new(1_000_000.0, 48_000);
+        blip.set_level(0.75);
+        blip.advance(10_000);
+        let samples = blip.take_samples();
+        assert!(samples[..WIDTH / 4].iter().all(|s| s.abs() < 0.01));
+        assert!(samples[WIDTH..].iter().all(|s| (s - 0.75).abs() < 1e-6));
+    }
+
+    #[test]
+    fn square_wave_does_not_alias() {
+        // A 15 kHz square wave's third harmonic is at 45 kHz, which a plain
+        // average at 48 kHz folds down to 3 kHz.
+        let mut blip = BlipBuffer::new(1_000_000.0, 48_000);
+        let samples = square(&mut blip, 15_625);
+        let fundamental = tone_magnitude(&samples, 15_625.0, 48_000.0);
+        let alias = tone_magnitude(&samples, 1_125.0, 48_000.0);
+        assert!(fundamental > 0.2, "fundamental {fundamental}");
+        assert!(alias < fundamental * 0.01, "alias {alias}");
+    }
+
+    #[test]
+    fn high_pass_removes_dc() {
+        let mut blip = BlipBuffer::new(1_000_000.0, 48_000).with_high_pass(37.0);
+        blip.set_level(1.0);
+        blip.advance(1_000_000);
+        let samples = blip.take_samples();
+        assert!(samples.last().expect("samples").abs() < 0.001);
+    }
+
+    #[test]
+    fn rate_adjust_stretches_output() {
+        let mut blip = BlipBuffer::new(1_000_000.0, 48_000);
+        blip.set_rate_adjust(dynamic_rate(0.0, 0.005));
+        blip.advance(1_000_000);
+        assert!((48_239..=48_240).contains(&blip.len()));
+        assert!((dynamic_rate(0.5, 0.005) - 1.0).abs() < 1e-12);
+        assert!(dynamic_rate(1.0, 0.005) < 1.0);
+    }
+
+    #[test]
+    fn save_state_round_trips_mid_step() {
+        let mut a = BlipBuffer::new(1_000_000.0, 48_000);
+        a.set_level(0.5);
+        a.advance(50);
+        let mut w = StateWriter::new();
+        a.save_state(&mut w);
+        let bytes = w.into_bytes();
+
+        let mut b = BlipBuffer::new(1_000_000.0, 48_000);
+        b.load_state(&mut StateReader::new(&bytes)).expect("load");
+        for blip in [&mut a, &mut b] {
+            blip.set_level(-0.25);
+            blip.advance(1_000);
+        }
+        assert_eq!(a.take_samples(), b.take_samples());
+    }
+
+    #[test]
+    fn stems_sum_to_the_mix() {
+        let mut mix = BlipBuffer::new(1_000_000.0, 48_000).with_high_pass(37.0);
+        let levels = |clock: u32| {
+            let a = if (clock / 1_000).is_multiple_of(2) {
+                0.25
+            } else {
+                0.0
+            };
+            let b = if (clock / 377).is_multiple_of(2) {
+                0.125
+            } else {
+                0.5
+            };
+            [a, b]
+        };
+        for clock in 0..10_007 {
+            mix.set_level(levels(clock).iter().sum());
+            mix.advance(1);
+        }
+        mix.take_samples();
+
+        let mut stems = Stems::new(&mix, &levels(10_007));
+        assert_eq!(stems.channels(), 2);
+        for clock in 10_007..50_000 {
+            let taps = levels(clock);
+            mix.set_level(taps.iter().sum());
+            mix.advance(1);
+            stems.record(&taps, 1);
+        }
+        let mixed = mix.take_samples();
+        let split = stems.take();
+        assert_eq!(split[0].len(), mixed.len());
+        assert_eq!(split[1].len(), mixed.len());
+        // The mix carries high-pass history from before the stems started;
+        // it has decayed to nothing a few time constants in.
+        for (i, &m) in mixed.iter().enumerate().skip(mixed.len() / 2) {
+            assert!((split[0][i] + split[1][i] - m).abs() < 1e-3, "sample {i}");
+        }
+    }
+}
@@ -0,0 +1,566 @@
+//! Shared breakpoint engine.
+//!
+//! A [`Breakpoints`] set watches a machine while it runs and reports the
+//! first breakpoint that should stop execution. Triggers cover execution
+//! addresses, memory and I/O watchpoints, raster positions and bare
+//! conditions; any of them can carry a [`Condition`], a hit count and a
+//! temporary flag.
+//!
+//! The engine is checked after every master-clock tick. Execution and
+//! condition breakpoints are tested when the CPU reaches an instruction
+//! boundary, raster breakpoints when the beam enters the requested
+//! position, and watchpoints against the bus cycles the machine logged
+//! since the last check. Each of these is edge-triggered, so resuming from
+//! a stop does not immediately stop again at the same place.
+
+mod condition;
+
+use std::ops::RangeInclusive;
+
+use crate::{BusAccess, Observable};
+
+pub use condition::{Condition, ConditionError};
+
+/// A machine the breakpoint engine can watch.
+pub trait Debuggable: Observable {
+    /// Address of the next instruction while the CPU sits on an
+    /// instruction boundary, `None` mid-instruction.
+    fn instruction_boundary(&self) -> Option<u32>;
+
+    /// Current raster position as `(line, cycle)` in the video chip's own
+    /// units (ULA T-states, VIC-II cycles, PPU dots, Agnus colour clocks).
+    fn raster_position(&self) -> (u32, u32);
+
+    /// Start or stop logging CPU bus cycles for watchpoints.
+    fn set_bus_logging(&mut self, enabled: bool);
+
+    /// Move the bus cycles logged since the last call into `out`.
+    fn drain_bus_accesses(&mut self, out: &mut Vec<BusAccess>);
+}
+
+/// Which accesses a watchpoint reacts to.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Access {
+    Read,
+    Write,
+    ReadWrite,
+}
+
+impl Access {
+    fn matches(self, is_read: bool) -> bool {
+        match self {
+            Self::Read => is_read,
+            Self::Write => !is_read,
+            Self::ReadWrite => true,
+        }
+    }
+}
+
+/// What makes a breakpoint fire.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Trigger {
+    /// The CPU is about to execute the instruction at this address.
+    Execute(u32),
+    /// A memory access within the range.
+    Memory {
+        range: RangeInclusive<u32>,
+        access: Access,
+    },
+    /// An I/O port access within the range.
+    Io {
+        range: RangeInclusive<u32>,
+        access: Access,
+    },
+    /// The beam reaches `line` (and `cycle`, if given).
+    Raster { line: u32, cycle: Option<u32> },
+    /// The breakpoint's condition holds at an instruction boundary.
+    Condition,
+}
+
+impl Trigger {
+    /// True for memory and I/O watchpoints.
+    #[must_use]
+    pub fn is_watchpoint(&self) -> bool {
+        matches!(self, Self::Memory { .. } | Self::Io { .. })
+    }
+
+    fn matches_access(&self, access: &BusAccess) -> bool {
+        match self {
+            Self::Memory {
+                range,
+                access: want,
+            } => {
+                !access.kind.is_io()
+                    && range.contains(&access.address)
+                    && want.matches(access.kind.is_read())
+            }
+            Self::Io {
+                range,
+                access: want,
+            } => {
+                access.kind.is_io()
+                    && range.contains(&access.address)
+                    && want.matches(access.kind.is_read())
+            }
+            _ => false,
+        }
+    }
+}
+
+/// A single breakpoint.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Breakpoint {
+    pub trigger: Trigger,
+    /// Extra condition that must hold when the trigger fires.
+    pub condition: Option<Condition>,
+    /// Stop only once the breakpoint has been hit this many times.
+    pub hit_count: u32,
+    /// Remove the breakpoint after it stops execution.
+    pub temporary: bool,
+    pub enabled: bool,
+    /// Number of times the trigger fired with the condition holding.
+    pub hits: u32,
+}
+
+impl Breakpoint {
+    #[must_use]
+    pub fn new(trigger: Trigger) -> Self {
+        Self {
+            trigger,
+            condition: None,
+            hit_count: 1,
+            temporary: false,
+            enabled: true,
+            hits: 0,
+        }
+    }
+
+    #[must_use]
+    pub fn with_condition(mut self, condition: Condition) -> Self {
+        self.condition = Some(condition);
+        self
+    }
+
+    #[must_use]
+    pub fn with_hit_count(mut self, hit_count: u32) -> Self {
+        self.hit_count = hit_count.max(1);
+        self
+    }
+
+    #[must_use]
+    pub fn temporary(mut self) -> Self {
+        self.temporary = true;
+        self
+    }
+
+    /// Record a trigger firing; returns true if execution should stop.
+    fn fire(&mut self, machine: &(impl Observable + ?Sized)) -> bool {
+        if !self.enabled || !self.condition.as_ref().is_none_or(|c| c.evaluate(machine)) {
+            return false;
+        }
+        self.hits = self.hits.saturating_add(1);
+        self.hits >= self.hit_count
+    }
+}
+
+/// A breakpoint that stopped execution.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Hit {
+    pub id: u32,
+    pub trigger: Trigger,
+    /// Hits recorded so far, including this one.
+    pub hits: u32,
+    /// The bus cycle that tripped a watchpoint.
+    pub access: Option<BusAccess>,
+    /// The breakpoint was temporary and has been removed.
+    pub temporary: bool,
+}
+
+/// A set of breakpoints plus the edge-detection state for one machine.
+#[derive(Debug, Clone, Default)]
+pub struct Breakpoints {
+    entries: Vec<(u32, Breakpoint)>,
+    next_id: u32,
+    last_boundary: Option<u32>,
+    last_raster: (u32, u32),
+    accesses: Vec<BusAccess>,
+}
+
+impl Breakpoints {
+    #[must_use]
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Add a breakpoint and return its id.
+    pub fn add(&mut self, breakpoint: Breakpoint) -> u32 {
+        self.next_id += 1;
+        self.entries.push((self.next_id, breakpoint));
+        self.next_id
+    }
+
+    /// Remove a breakpoint; returns false if the id is unknown.
+    pub fn remove(&mut self, id: u32) -> bool {
+        let before = self.entries.len();
+        self.entries.retain(|(entry_id, _)| *entry_id != id);
+        self.entries.len() != before
+    }
+
+    pub fn clear(&mut self) {
+        self.entries.clear();
+    }
+
+    #[must_use]
+    pub fn get(&self, id: u32) -> Option<&Breakpoint> {
+        self.entries
+            .iter()
+            .find(|(entry_id, _)| *entry_id == id)
+            .map(|(_, bp)| bp)
+    }
+
+    pub fn get_mut(&mut self, id: u32) -> Option<&mut Breakpoint> {
+        self.entries
+            .iter_mut()
+            .find(|(entry_id, _)| *entry_id == id)
+            .map(|(_, bp)| bp)
+    }
+
+    /// Breakpoints in the order they were added.
+    pub fn iter(&self) -> impl Iterator<Item = (u32, &Breakpoint)> {
+        self.entries.iter().map(|(id, bp)| (*id, bp))
+    }
+
+    #[must_use]
+    pub fn len(&self) -> usize {
+        self.entries.len()
+    }
+
+    #[must_use]
+    pub fn is_empty(&self) -> bool {
+        self.entries.is_empty()
+    }
+
+    fn has_watchpoints(&self) -> bool {
+        self.entries
+            .iter()
+            .any(|(_, bp)| bp.enabled && bp.trigger.is_watchpoint())
+    }
+
+    /// Prepare to run: take the current position as already seen and turn
+    /// on bus logging if any watchpoint needs it.
+    pub fn arm(&mut self, machine: &mut impl Debuggable) {
+        self.last_boundary = machine.instruction_boundary();
+        self.last_raster = machine.raster_position();
+        self.accesses.clear();
+        machine.set_bus_logging(self.has_watchpoints());
+    }
+
+    /// Stop bus logging after a run.
+    pub fn disarm(&mut self, machine: &mut impl Debuggable) {
+        machine.set_bus_logging(false);
+        self.accesses.clear();
+    }
+
+    /// Check the machine after a tick. Returns the first breakpoint that
+    /// should stop execution; temporary breakpoints are removed when they
+    /// do.
+    pub fn check(&mut self, machine: &mut impl Debuggable) -> Option<Hit> {
+        let mut stop = None;
+
+        // Watchpoints first: the accesses happened during this tick.
+        machine.drain_bus_accesses(&mut self.accesses);
+        if !self.accesses.is_empty() {
+            let accesses = std::mem::take(&mut self.accesses);
+            'accesses: for access in &accesses {
+                for (id, bp) in &mut self.entries {
+                    if bp.trigger.matches_access(access) && bp.fire(&*machine) {
+                        stop = Some((*id, Some(*access)));
+                        break 'accesses;
+                    }
+                }
+            }
+            self.accesses = accesses;
+            self.accesses.clear();
+        }
+
+        let boundary = machine.instruction_boundary();
+        let new_boundary = boundary.is_some() && boundary != self.last_boundary;
+        self.last_boundary = boundary;
+
+        let raster = machine.raster_position();
+        let prev_raster = std::mem::replace(&mut self.last_raster, raster);
+
+        if stop.is_none() {
+            for (id, bp) in &mut self.entries {
+                let fired = match bp.trigger {
+                    Trigger::Execute(address) => new_boundary && boundary == Some(address),
+                    Trigger::Condition => new_boundary,
+                    Trigger::Raster { line, cycle } => {
+                        let at =
+                            |(l, c): (u32, u32)| l == line && cycle.is_none_or(|want| c == want);
+                        at(raster) && !at(prev_raster)
+                    }
+                    Trigger::Memory { .. } | Trigger::Io { .. } => false,
+                };
+                if fired && bp.fire(&*machine) {
+                    stop = Some((*id, None));
+                    break;
+                }
+            }
+        }
+
+        let (id, access) = stop?;
+        let index = self
+            .entries
+            .iter()
+            .position(|(entry_id, _)| *entry_id == id)?;
+        let bp = &self.entries[index].1;
+        let hit = Hit {
+            id,
+            trigger: bp.trigger.clone(),
+            hits: bp.hits,
+            access,
+            temporary: bp.temporary,
+        };
+        if bp.temporary {
+            self.entries.remove(index);
+        }
+        Some(hit)
+    }
+
+    /// Run until a breakpoint stops execution or `step` returns false.
+    ///
+    /// `step` advances the machine by one tick and reports whether the run
+    /// may continue (typically a frame or tick budget).
+    pub fn run<M: Debuggable>(
+        &mut self,
+        machine: &mut M,
+        mut step: impl FnMut(&mut M) -> bool,
+    ) -> Option<Hit> {
+        self.arm(machine);
+        let hit = loop {
+            let more = step(machine);
+            if let Some(hit) = self.check(machine) {
+                break Some(hit);
+            }
+            if !more {
+                break None;
+            }
+        };
+        self.disarm(machine);
+        hit
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{Access, Breakpoint, Breakpoints, Condition, Debuggable, Trigger};
+    use crate::{AccessKind, Bus, BusAccess, LoggingBus, Observable, SimpleBus, Value};
+
+    /// Toy machine: one instruction per four ticks, each instruction
+    /// reads the byte at `pc` and stores it to `$8000 + pc`.
+    struct Toy {
+        bus: SimpleBus,
+        pc: u16,
+        phase: u8,
+        line: u32,
+        cycle: u32,
+        log: Option<Vec<BusAccess>>,
+    }
+
+    impl Toy {
+        fn new() -> Self {
+            Self {
+                bus: SimpleBus::new(),
+                pc: 0,
+                phase: 0,
+                line: 0,
+                cycle: 0,
+                log: None,
+            }
+        }
+
+        fn tick(&mut self) {
+            self.cycle += 1;
+            if self.cycle == 10 {
+                self.cycle = 0;
+                self.line = (self.line + 1) % 8;
+            }
+            self.phase = (self.phase + 1) % 4;
+            if self.phase == 1 {
+                let pc = u32::from(self.pc);
+                if let Some(log) = &mut self.log {
+                    let mut bus = LoggingBus::new(&mut self.bus, log);
+                    let value = bus.read(pc).data;
+                    bus.write(0x8000 + pc, value);
+                } else {
+                    let value = self.bus.read(pc).data;
+                    self.bus.write(0x8000 + pc, value);
+                }
+            }
+            if self.phase == 0 {
+                self.pc = self.pc.wrapping_add(1);
+            }
+        }
+    }
+
+    impl Observable for Toy {
+        fn query(&self, path: &str) -> Option<Value> {
+            match path {
+                "cpu.pc" => Some(self.pc.into()),
+                "line" => Some(Value::U32(self.line)),
+                _ => None,
+            }
+        }
+
+        fn query_paths(&self) -> &'static [&'static str] {
+            &["cpu.pc", "line"]
+        }
+    }
+
+    impl Debuggable for Toy {
+        fn instruction_boundary(&self) -> Option<u32> {
+            (self.phase == 0).then_some(u32::from(self.pc))
+        }
+
+        fn raster_position(&self) -> (u32, u32) {
+            (self.line, self.cycle)
+        }
+
+        fn set_bus_logging(&mut self, enabled: bool) {
+            self.log = enabled.then(Vec::new);
+        }
+
+        fn drain_bus_accesses(&mut self, out: &mut Vec<BusAccess>) {
+            if let Some(log) = &mut self.log {
+                out.append(log);
+            }
+        }
+    }
+
+    fn run(bps: &mut Breakpoints, toy: &mut Toy, max_ticks: u32) -> Option<super::Hit> {
+        let mut ticks = 0;
+        bps.run(toy, |toy| {
+            toy.tick();
+            ticks += 1;
+            ticks < max_ticks
+        })
+    }
+
+    #[test]
+    fn execute_breakpoint_stops_on_boundary_and_resumes_past_it() {
+        let mut toy = Toy::new();
+        let mut bps = Breakpoints::new();
+        let id = bps.add(Breakpoint::new(Trigger::Execute(5)));
+
+        let hit = run(&mut bps, &mut toy, 1000).expect("hit");
+        assert_eq!(hit.id, id);
+        assert_eq!(toy.pc, 5);
+        assert_eq!(toy.instruction_boundary(), Some(5));
+
+        // Resuming from the stop must not stop again at the same place.
+        toy.pc = 0;
+        assert!(run(&mut bps, &mut toy, 8).is_none());
+        assert_eq!(bps.get(id).map(|bp| bp.hits), Some(1));
+    }
+
+    #[test]
+    fn hit_count_and_temporary_breakpoints() {
+        let mut toy = Toy::new();
+        let mut bps = Breakpoints::new();
+        let counted = bps.add(Breakpoint::new(Trigger::Execute(3)).with_hit_count(2));
+        let temp = bps.add(Breakpoint::new(Trigger::Execute(2)).temporary());
+
+        let hit = run(&mut bps, &mut toy, 1000).expect("temporary hit");
+        assert_eq!(hit.id, temp);
+        assert!(hit.temporary);
+        assert!(bps.get(temp).is_none());
+
+        // The first pass over $0003 only counts.
+        toy.pc = 0;
+        assert!(run(&mut bps, &mut toy, 20).is_none());
+        toy.pc = 0;
+        let hit = run(&mut bps, &mut toy, 1000).expect("second hit");
+        assert_eq!((hit.id, hit.hits), (counted, 2));
+    }
+
+    #[test]
+    fn memory_watchpoints_filter_by_range_and_access() {
+        let mut toy = Toy::new();
+        toy.bus.load(0x0000, &[0, 0, 0, 0x42]);
+        let mut bps = Breakpoints::new();
+        bps.add(Breakpoint::new(Trigger::Memory {
+            range: 0x0100..=0x01FF,
+            access: Access::Read,
+        }));
+        let write = bps.add(Breakpoint::new(Trigger::Memory {
+            range: 0x8003..=0x8003,
+            access: Access::Write,
+        }));
+        // I/O watchpoints never see memory cycles.
+        bps.add(Breakpoint::new(Trigger::Io {
+            range: 0..=0xFFFF,
+            access: Access::ReadWrite,
+        }));
+
+        let hit = run(&mut bps, &mut toy, 1000).expect("hit");
+        assert_eq!(hit.id, write);
+        assert_eq!(
+            hit.access,
+            Some(BusAccess {
+                kind: AccessKind::Write,
+                address: 0x8003,
+                value: 0x42,
+                wait: 0
+            })
+        );
+        assert!(toy.log.is_none(), "logging is switched off after a run");
+    }
+
+    #[test]
+    fn raster_breakpoints_fire_on_entry() {
+        let mut toy = Toy::new();
+        let mut bps = Breakpoints::new();
+        let line = bps.add(Breakpoint::new(Trigger::Raster {
+            line: 2,
+            cycle: None,
+        }));
+        let exact = bps.add(Breakpoint::new(Trigger::Raster {
+            line: 3,
+            cycle: Some(7),
+        }));
+
+        assert_eq!(run(&mut bps, &mut toy, 1000).map(|h| h.id), Some(line));
+        assert_eq!(toy.raster_position(), (2, 0));
+        assert_eq!(run(&mut bps, &mut toy, 1000).map(|h| h.id), Some(exact));
+        assert_eq!(toy.raster_position(), (3, 7));
+    }
+
+    #[test]
+    fn conditions_gate_triggers_and_stand_alone() {
+        let mut toy = Toy::new();
+        let mut bps = Breakpoints::new();
+        let cond = |s| Condition::parse(s).expect("parses");
+        bps.add(Breakpoint::new(Trigger::Execute(1)).with_condition(cond("line == 5")));
+        let id = bps.add(Breakpoint::new(Trigger::Condition).with_condition(cond("cpu.pc == 9")));
+
+        let hit = run(&mut bps, &mut toy, 1000).expect("hit");
+        assert_eq!(hit.id, id);
+        assert_eq!(toy.pc, 9);
+        assert_eq!(bps.get(1).map(|bp| bp.hits), Some(0));
+    }
+
+    #[test]
+    fn disabled_breakpoints_and_budget() {
+        let mut toy = Toy::new();
+        let mut bps = Breakpoints::new();
+        let id = bps.add(Breakpoint::new(Trigger::Execute(1)));
+        if let Some(bp) = bps.get_mut(id) {
+            bp.enabled = false;
+        }
+        assert!(run(&mut bps, &mut toy, 40).is_none());
+        assert!(bps.remove(id));
+        assert!(!bps.remove(id));
+        assert!(bps.is_empty());
+    }
+}
@@ -0,0 +1,468 @@
+//! Breakpoint condition expressions.
+//!
+//! Conditions are small integer expressions over `Observable` paths, e.g.
+//! `cpu.a == 0x20 && vic.line > 100`. Operators and precedence follow
+//! Rust: `!` and unary `-` bind tightest, then `+ -`, `&`, `^`, `|`, the
+//! comparisons, `&&` and finally `||`. Literals are decimal, `0x` hex or
+//! `$` hex. Booleans read from paths count as 0 or 1, and any non-zero
+//! result is true.
+//!
+//! A path that the machine cannot answer (or answers with a string or
+//! array) makes the whole condition false rather than an error, so a
+//! breakpoint never stops on a value it could not read.
+
+use std::fmt;
+
+use crate::{Observable, Value};
+
+/// Error from [`Condition::parse`].
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ConditionError {
+    /// Byte offset in the source where parsing failed.
+    pub position: usize,
+    pub message: String,
+}
+
+impl fmt::Display for ConditionError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{} at offset {}", self.message, self.position)
+    }
+}
+
+impl std::error::Error for ConditionError {}
+
+/// A parsed condition expression.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Condition {
+    source: String,
+    expr: Expr,
+}
+
+impl Condition {
+    /// Parse a condition expression.
+    ///
+    /// # Errors
+    ///
+    /// Returns a [`ConditionError`] for empty input, unknown characters,
+    /// malformed literals, unbalanced parentheses or trailing tokens.
+    pub fn parse(source: &str) -> Result<Self, ConditionError> {
+        let tokens = tokenize(source)?;
+        let mut parser = Parser {
+            tokens: &tokens,
+            pos: 0,
+            end: source.len(),
+        };
+        let expr = parser.parse_binary(0)?;
+        if let Some((_, offset)) = parser.tokens.get(parser.pos) {
+            return Err(ConditionError {
+                position: *offset,
+                message: "unexpected token".to_string(),
+            });
+        }
+        Ok(Self {
+            source: source.trim().to_string(),
+            expr,
+        })
+    }
+
+    /// The expression as written (trimmed).
+    #[must_use]
+    pub fn source(&self) -> &str {
+        &self.source
+    }
+
+    /// Observable paths referenced by the expression, in source order.
+    #[must_use]
+    pub fn paths(&self) -> Vec<&str> {
+        let mut paths = Vec::new();
+        self.expr.collect_paths(&mut paths);
+        paths
+    }
+
+    /// Evaluate the condition against a machine.
+    #[must_use]
+    pub fn evaluate(&self, machine: &(impl Observable + ?Sized)) -> bool {
+        self.expr.eval(machine).is_some_and(|v| v != 0)
+    }
+}
+
+impl fmt::Display for Condition {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(&self.source)
+    }
+}
+
+/// Convert an observable value to an integer operand.
+fn value_as_i64(value: &Value) -> Option<i64> {
+    match *value {
+        Value::Bool(v) => Some(i64::from(v)),
+        Value::U8(v) => Some(i64::from(v)),
+        Value::U16(v) => Some(i64::from(v)),
+        Value::U32(v) => Some(i64::from(v)),
+        Value::U64(v) => i64::try_from(v).ok(),
+        Value::I8(v) => Some(i64::from(v)),
+        Value::String(_) | Value::Array(_) | Value::Map(_) => None,
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum BinaryOp {
+    Or,
+    And,
+    Eq,
+    Ne,
+    Lt,
+    Le,
+    Gt,
+    Ge,
+    BitOr,
+    BitXor,
+    BitAnd,
+    Add,
+    Sub,
+}
+
+impl BinaryOp {
+    /// Binding strength; higher binds tighter.
+    fn precedence(self) -> u8 {
+        match self {
+            Self::Or => 1,
+            Self::And => 2,
+            Self::Eq | Self::Ne | Self::Lt | Self::Le | Self::Gt | Self::Ge => 3,
+            Self::BitOr => 4,
+            Self::BitXor => 5,
+            Self::BitAnd => 6,
+            Self::Add | Self::Sub => 7,
+        }
+    }
+
+    fn is_comparison(self) -> bool {
+        self.precedence() == 3
+    }
+
+    fn apply(self, lhs: i64, rhs: i64) -> i64 {
+        match self {
+            Self::Or => i64::from(lhs != 0 || rhs != 0),
+            Self::And => i64::from(lhs != 0 && rhs != 0),
+            Self::Eq => i64::from(lhs == rhs),
+            Self::Ne => i64::from(lhs != rhs),
+            Self::Lt => i64::from(lhs < rhs),
+            Self::Le => i64::from(lhs <= rhs),
+            Self::Gt => i64::from(lhs > rhs),
+            Self::Ge => i64::from(lhs >= rhs),
+            Self::BitOr => lhs | rhs,
+            Self::BitXor => lhs ^ rhs,
+            Self::BitAnd => lhs & rhs,
+            Self::Add => lhs.wrapping_add(rhs),
+            Self::Sub => lhs.wrapping_sub(rhs),
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+enum Expr {
+    Literal(i64),
+    Path(String),
+    Not(Box<Expr>),
+    Neg(Box<Expr>),
+    Binary(BinaryOp, Box<Expr>, Box<Expr>),
+}
+
+impl Expr {
+    fn eval(&self, machine: &(impl Observable + ?Sized)) -> Option<i64> {
+        match self {
+            Self::Literal(v) => Some(*v),
+            Self::Path(path) => machine.query(path).as_ref().and_then(value_as_i64),
+            Self::Not(inner) => Some(i64::from(inner.eval(machine)? == 0)),
+            Self::Neg(inner) => Some(inner.eval(machine)?.wrapping_neg()),
+            // Short-circuit so `a || b` still holds when `b` is unreadable.
+            Self::Binary(BinaryOp::Or, lhs, rhs) => {
+                if lhs.eval(machine)? != 0 {
+                    Some(1)
+                } else {
+                    Some(i64::from(rhs.eval(machine)? != 0))
+                }
+            }
+            Self::Binary(BinaryOp::And, lhs, rhs) => {
+                if lhs.eval(machine)? == 0 {
+                    Some(0)
+                } else {
+                    Some(i64::from(rhs.eval(machine)? != 0))
+                }
+            }
+            Self::Binary(op, lhs, rhs) => Some(op.apply(lhs.eval(machine)?, rhs.eval(machine)?)),
+        }
+    }
+
+    fn collect_paths<'a>(&'a self, out: &mut Vec<&'a str>) {
+        match self {
+            Self::Literal(_) => {}
+            Self::Path(path) => out.push(path),
+            Self::Not(inner) | Self::Neg(inner) => inner.collect_paths(out),
+            Self::Binary(_, lhs, rhs) => {
+                lhs.collect_paths(out);
+                rhs.collect_paths(out);
+            }
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+enum Token {
+    Number(i64),
+    Path(String),
+    Op(BinaryOp),
+    Not,
+    LParen,
+    RParen,
+}
+
+/// Split the source into tokens paired with their byte offsets.
+fn tokenize(source: &str) -> Result<Vec<(Token, usize)>, ConditionError> {
+    let bytes = source.as_bytes();
+    let mut tokens = Vec::new();
+    let mut i = 0;
+
+    while i < bytes.len() {
+        let c = bytes[i];
+        let start = i;
+        if c.is_ascii_whitespace() {
+            i += 1;
+            continue;
+        }
+
+        if c.is_ascii_digit() || c == b'$' {
+            let (radix, digits_start) = if c == b'$' {
+                (16, i + 1)
+            } else if bytes[i..].starts_with(b"0x") || bytes[i..].starts_with(b"0X") {
+                (16, i + 2)
+            } else {
+                (10, i)
+            };
+            i = digits_start;
+            while i < bytes.len() && (bytes[i].is_ascii_alphanumeric() || bytes[i] == b'_') {
+                i += 1;
+            }
+            let digits = source[digits_start..i].replace('_', "");
+            let value = i64::from_str_radix(&digits, radix).map_err(|_| ConditionError {
+                position: start,
+                message: format!("invalid number '{}'", &source[start..i]),
+            })?;
+            tokens.push((Token::Number(value), start));
+            continue;
+        }
+
+        if c.is_ascii_alphabetic() || c == b'_' {
+            while i < bytes.len()
+                && (bytes[i].is_ascii_alphanumeric() || bytes[i] == b'_' || bytes[i] == b'.')
+            {
+                i += 1;
+            }
+            tokens.push((Token::Path(source[start..i].to_string()), start));
+            continue;
+        }
+
+        let next = bytes.get(i + 1).copied();
+        let (token, len) = match (c, next) {
+            (b'|', Some(b'|')) => (Token::Op(BinaryOp::Or), 2),
+            (b'&', Some(b'&')) => (Token::Op(BinaryOp::And), 2),
+            (b'=', Some(b'=')) => (Token::Op(BinaryOp::Eq), 2),
+            (b'!', Some(b'=')) => (Token::Op(BinaryOp::Ne), 2),
+            (b'<', Some(b'=')) => (Token::Op(BinaryOp::Le), 2),
+            (b'>', Some(b'=')) => (Token::Op(BinaryOp::Ge), 2),
+            (b'<', _) => (Token::Op(BinaryOp::Lt), 1),
+            (b'>', _) => (Token::Op(BinaryOp::Gt), 1),
+            (b'|', _) => (Token::Op(BinaryOp::BitOr), 1),
+            (b'^', _) => (Token::Op(BinaryOp::BitXor), 1),
+            (b'&', _) => (Token::Op(BinaryOp::BitAnd), 1),
+            (b'+', _) => (Token::Op(BinaryOp::Add), 1),
+            (b'-', _) => (Token::Op(BinaryOp::Sub), 1),
+            (b'!', _) => (Token::Not, 1),
+            (b'(', _) => (Token::LParen, 1),
+            (b')', _) => (Token::RParen, 1),
+            _ => {
+                return Err(ConditionError {
+                    position: start,
+                    message: format!(
+                        "unexpected character '{}'",
+                        source[start..].chars().next().unwrap_or('?')
+                    ),
+                });
+            }
+        };
+        tokens.push((token, start));
+        i += len;
+    }
+
+    Ok(tokens)
+}
+
+/// Precedence-climbing parser over a token slice.
+struct Parser<'a> {
+    tokens: &'a [(Token, usize)],
+    pos: usize,
+    /// Offset reported for errors at end of input.
+    end: usize,
+}
+
+impl Parser<'_> {
+    fn offset(&self) -> usize {
+        self.tokens
+            .get(self.pos)
+            .map_or(self.end, |(_, offset)| *offset)
+    }
+
+    fn error(&self, message: &str) -> ConditionError {
+        ConditionError {
+            position: self.offset(),
+            message: message.to_string(),
+        }
+    }
+
+    fn parse_binary(&mut self, min_precedence: u8) -> Result<Expr, ConditionError> {
+        let mut lhs = self.parse_unary()?;
+        while let Some((Token::Op(op), _)) = self.tokens.get(self.pos) {
+            let op = *op;
+            let precedence = op.precedence();
+            if precedence < min_precedence {
+                break;
+            }
+            self.pos += 1;
+            let rhs = self.parse_binary(precedence + 1)?;
+            // Rust rejects `a == b == c`; so do we.
+            if op.is_comparison()
+                && let Some((Token::Op(next), _)) = self.tokens.get(self.pos)
+                && next.is_comparison()
+            {
+                return Err(self.error("comparison operators cannot be chained"));
+            }
+            lhs = Expr::Binary(op, Box::new(lhs), Box::new(rhs));
+        }
+        Ok(lhs)
+    }
+
+    fn parse_unary(&mut self) -> Result<Expr, ConditionError> {
+        let Some((token, _)) = self.tokens.get(self.pos) else {
+            return Err(self.error("expected an operand"));
+        };
+        match token {
+            Token::Not => {
+                self.pos += 1;
+                Ok(Expr::Not(Box::new(self.parse_unary()?)))
+            }
+            Token::Op(BinaryOp::Sub) => {
+                self.pos += 1;
+                Ok(Expr::Neg(Box::new(self.parse_unary()?)))
+            }
+            Token::Number(v) => {
+                self.pos += 1;
+                Ok(Expr::Literal(*v))
+            }
+            Token::Path(path) => {
+                self.pos += 1;
+                Ok(Expr::Path(path.clone()))
+            }
+            Token::LParen => {
+                self.pos += 1;
+                let inner = self.parse_binary(0)?;
+                if self.tokens.get(self.pos).map(|(t, _)| t) != Some(&Token::RParen) {
+                    return Err(self.error("expected ')'"));
+                }
+                self.pos += 1;
+                Ok(inner)
+            }
+            Token::Op(_) | Token::RParen => Err(self.error("expected an operand")),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::Condition;
+    use crate::{Observable, Value};
+
+    struct Regs;
+
+    impl Observable for Regs {
+        fn query(&self, path: &str) -> Option<Value> {
+            match path {
+                "cpu.a" => Some(Value::U8(0x20)),
+                "cpu.flags.z" => Some(Value::Bool(true)),
+                "vic.line" => Some(Value::U16(150)),
+                "name" => Some(Value::String("x".to_string())),
+                _ => None,
+            }
+        }
+
+        fn query_paths(&self) -> &'static [&'static str] {
+            &["cpu.a", "cpu.flags.z", "vic.line", "name"]
+        }
+    }
+
+    fn eval(source: &str) -> bool {
+        Condition::parse(source).expect("parses").evaluate(&Regs)
+    }
+
+    #[test]
+    fn evaluates_comparisons_over_paths() {
+        assert!(eval("cpu.a == 0x20 && vic.line > 100"));
+        assert!(eval("cpu.a == $20"));
+        assert!(eval("cpu.a == 32"));
+        assert!(!eval("cpu.a != 32"));
+        assert!(!eval("cpu.a == 0x20 && vic.line > 200"));
+        assert!(eval("vic.line >= 150 && vic.line <= 150"));
+    }
+
+    #[test]
+    fn follows_rust_precedence() {
+        // `&` binds tighter than `==`, `&&` tighter than `||`.
+        assert!(eval("cpu.a & 0x20 == 0x20"));
+        assert!(eval("0 && 0 || 1"));
+        assert!(!eval("0 && (0 || 1)"));
+        assert!(eval("vic.line - 50 == 100"));
+        assert!(eval("-1 < 0"));
+        assert!(eval("(cpu.a | 1) ^ 1 == 0x20"));
+    }
+
+    #[test]
+    fn booleans_and_negation() {
+        assert!(eval("cpu.flags.z"));
+        assert!(!eval("!cpu.flags.z"));
+        assert!(eval("cpu.flags.z == 1"));
+    }
+
+    #[test]
+    fn unreadable_paths_make_the_condition_false() {
+        assert!(!eval("missing == 0"));
+        assert!(!eval("name == 0"));
+        assert!(eval("cpu.a == 0x20 || missing"));
+        assert!(!eval("cpu.a == 0 && missing"));
+    }
+
+    #[test]
+    fn reports_paths_in_source_order() {
+        let cond =
+            Condition::parse("cpu.a == 0x20 && (vic.line > 100 || !cpu.flags.z)").expect("parses");
+        assert_eq!(cond.paths(), ["cpu.a", "vic.line", "cpu.flags.z"]);
+        assert_eq!(
+            cond.to_string(),
+            "cpu.a == 0x20 && (vic.line > 100 || !cpu.flags.z)"
+        );
+    }
+
+    #[test]
+    fn rejects_malformed_expressions() {
+        for (source, position) in [
+            ("", 0),
+            ("cpu.a ==", 8),
+            ("(cpu.a == 1", 11),
+            ("cpu.a == 1)", 10),
+            ("cpu.a = 1", 6),
+            ("0xZZ", 0),
+            ("1 == 1 == 1", 7),
+        ] {
+            let err = Condition::parse(source).expect_err(source);
+            assert_eq!(err.position, position, "{source}: {err}");
+        }
+    }
+}
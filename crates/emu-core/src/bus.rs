@@ -182,9 +182,102 @@ impl Observable for SimpleBus {
     }
 }
 
+/// Kind of bus cycle recorded by [`LoggingBus`].
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum AccessKind {
+    /// Memory read (including opcode fetches).
+    Read,
+    /// Memory write.
+    Write,
+    /// I/O port read.
+    IoRead,
+    /// I/O port write.
+    IoWrite,
+}
+
+impl AccessKind {
+    /// True for memory and I/O reads.
+    #[must_use]
+    pub fn is_read(self) -> bool {
+        matches!(self, Self::Read | Self::IoRead)
+    }
+
+    /// True for I/O port cycles.
+    #[must_use]
+    pub fn is_io(self) -> bool {
+        matches!(self, Self::IoRead | Self::IoWrite)
+    }
+}
+
+/// A single completed bus cycle.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct BusAccess {
+    pub kind: AccessKind,
+    pub address: u32,
+    /// Byte read or written.
+    pub value: u8,
+    /// Wait states reported by the bus for this cycle.
+    pub wait: u8,
+}
+
+/// Bus adapter that forwards every access and records it.
+///
+/// Machines wrap their bus in this while a debugger needs to see memory
+/// and I/O traffic (watchpoints, bus traces). Wrapping costs one push per
+/// access, so machines only do it while a log is attached.
+pub struct LoggingBus<'a, B> {
+    bus: &'a mut B,
+    log: &'a mut Vec<BusAccess>,
+}
+
+impl<'a, B> LoggingBus<'a, B> {
+    pub fn new(bus: &'a mut B, log: &'a mut Vec<BusAccess>) -> Self {
+        Self { bus, log }
+    }
+
+    fn record(&mut self, kind: AccessKind, address: u32, value: u8, wait: u8) {
+        self.log.push(BusAccess {
+            kind,
+            address,
+            value,
+            wait,
+        });
+    }
+}
+
+impl<B: Bus> Bus for LoggingBus<'_, B> {
+    fn read(&mut self, addr: u32) -> ReadResult {
+        let result = self.bus.read(addr);
+        self.record(AccessKind::Read, addr, result.data, result.wait);
+        result
+    }
+
+    fn write(&mut self, addr: u32, value: u8) -> u8 {
+        let wait = self.bus.write(addr, value);
+        self.record(AccessKind::Write, addr, value, wait);
+        wait
+    }
+
+    fn io_read(&mut self, addr: u32) -> ReadResult {
+        let result = self.bus.io_read(addr);
+        self.record(AccessKind::IoRead, addr, result.data, result.wait);
+        result
+    }
+
+    fn io_write(&mut self, addr: u32, value: u8) -> u8 {
+        let wait = self.bus.io_write(addr, value);
+        self.record(AccessKind::IoWrite, addr, value, wait);
+        wait
+    }
+
+    fn reset(&mut self) {
+        self.bus.reset();
+    }
+}
+
 #[cfg(test)]
 mod tests {
-    use super::{Bus, Observable, ReadResult, SimpleBus};
+    use super::{AccessKind, Bus, BusAccess, LoggingBus, Observable, ReadResult, SimpleBus};
     use crate::Value;
 
     #[test]
@@ -242,4 +335,39 @@ mod tests {
         assert_eq!(bus.io_read(0x00).wait, 0);
         assert_eq!(bus.io_write(0x00, 0x12), 0);
     }
+
+    #[test]
+    fn logging_bus_records_every_access_in_order() {
+        let mut bus = SimpleBus::new();
+        let mut log = Vec::new();
+        {
+            let mut logged = LoggingBus::new(&mut bus, &mut log);
+            logged.write(0x4000, 0x12);
+            assert_eq!(logged.read(0x4000).data, 0x12);
+            logged.io_write(0xFE, 0x07);
+            logged.io_read(0x1F);
+        }
+
+        assert_eq!(bus.peek(0x4000), 0x12);
+        let kinds: Vec<AccessKind> = log.iter().map(|a| a.kind).collect();
+        assert_eq!(
+            kinds,
+            [
+                AccessKind::Write,
+                AccessKind::Read,
+                AccessKind::IoWrite,
+                AccessKind::IoRead
+            ]
+        );
+        assert_eq!(
+            log[2],
+            BusAccess {
+                kind: AccessKind::IoWrite,
+                address: 0xFE,
+                value: 0x07,
+                wait: 0
+            }
+        );
+        assert_eq!(log[3].value, 0xFF);
+    }
 }
@@ -0,0 +1,514 @@
+//! Software composite video and CRT filter.
+//!
+//! Emulators draw perfect RGB pixels, but most of these machines reached
+//! the television through a composite or RF connection. The luma and the
+//! colour subcarrier shared one wire, so sharp luma detail leaked into
+//! the colour (artifact colours, dot crawl) and colour was smeared
+//! horizontally by its narrow bandwidth. PAL sets also averaged the colour
+//! of each line with the one above in a delay line.
+//!
+//! [`CrtFilter`] reproduces this on the CPU, after the frame is finished,
+//! so it works headless: in screenshots, recorded video and the browser
+//! builds. Each system describes its own video output with a [`Signal`]:
+//! its pixel clock relative to the colour subcarrier, how the phase moves
+//! from line to line and frame to frame, and optionally the waveform the
+//! chip actually produces for each colour (the NES PPU draws square waves,
+//! not sine waves).
+//!
+//! The signal is sampled twelve times per subcarrier cycle, encoded,
+//! then decoded as a TV would: luma through a one-cycle comb, chroma
+//! demodulated and low-passed over two cycles.
+
+#![allow(
+    clippy::cast_possible_truncation,
+    clippy::cast_precision_loss,
+    clippy::cast_sign_loss
+)]
+
+use std::collections::HashMap;
+use std::f32::consts::TAU;
+
+/// Samples per subcarrier cycle.
+pub const PHASES: usize = 12;
+
+/// One colour's signal level over a subcarrier cycle, sampled at
+/// [`PHASES`] evenly spaced phases. 0.0 is black and 1.0 is white.
+pub type Waveform = [f32; PHASES];
+
+/// Luma is averaged over one subcarrier cycle, which removes the carrier.
+const LUMA_WINDOW: usize = PHASES;
+/// Chroma is averaged over two cycles after demodulation, roughly the
+/// 1.3 MHz colour bandwidth of a domestic set.
+const CHROMA_WINDOW: usize = 2 * PHASES;
+
+/// Colour television standard.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Standard {
+    Ntsc,
+    /// The V component flips phase every line, and the set may average
+    /// each line's colour with the previous one.
+    Pal,
+}
+
+/// How a system's video chip drives the composite output.
+#[derive(Debug, Clone, Copy)]
+pub struct Signal {
+    pub standard: Standard,
+    /// Subcarrier cycles per framebuffer pixel.
+    pub cycles_per_pixel: f32,
+    /// Change in subcarrier phase from one line to the next, in cycles.
+    pub line_phase: f32,
+    /// Subcarrier phase at the start of successive frames, in cycles.
+    /// The pattern repeats.
+    pub frame_phases: &'static [f32],
+    /// Rotation applied to decoded colour, in degrees, to line the chip's
+    /// hues up with the TV's colour burst.
+    pub hue: f32,
+    /// Gain applied to decoded colour, to match the chip's chroma
+    /// amplitude to the TV's.
+    pub saturation: f32,
+    /// The waveform the chip produces for a framebuffer pixel.
+    pub waveform: fn(u32) -> Waveform,
+}
+
+impl Signal {
+    /// A chip whose colours are modelled by converting RGB pixels to
+    /// luma and a sine-wave subcarrier.
+    #[must_use]
+    pub const fn rgb(
+        standard: Standard,
+        cycles_per_pixel: f32,
+        line_phase: f32,
+        frame_phases: &'static [f32],
+    ) -> Self {
+        Self {
+            standard,
+            cycles_per_pixel,
+            line_phase,
+            frame_phases,
+            hue: 0.0,
+            saturation: 1.0,
+            waveform: rgb_waveform,
+        }
+    }
+}
+
+/// Sine-wave signal for a `0x00RRGGBB` pixel: luma plus the U and V
+/// colour difference components in quadrature.
+#[must_use]
+pub fn rgb_waveform(pixel: u32) -> Waveform {
+    let channel = |shift: u32| ((pixel >> shift) & 0xFF) as f32 / 255.0;
+    let (r, g, b) = (channel(16), channel(8), channel(0));
+    let y = 0.299 * r + 0.587 * g + 0.114 * b;
+    let u = 0.492 * (b - y);
+    let v = 0.877 * (r - y);
+    std::array::from_fn(|i| {
+        let (sin, cos) = (TAU * i as f32 / PHASES as f32).sin_cos();
+        y + u * sin + v * cos
+    })
+}
+
+/// How the machine is connected to the screen.
+#[derive(Debug, Clone, Copy)]
+pub enum Connection {
+    /// Clean RGB, as the emulator draws it.
+    Rgb,
+    /// Separate luma and chroma: colour bandwidth is limited, but luma
+    /// detail does not turn into colour.
+    SVideo(Signal),
+    /// Luma and chroma on one wire.
+    Composite(Signal),
+}
+
+/// CPU post-process that makes frames look as they did on a period TV.
+#[derive(Debug, Clone)]
+pub struct CrtFilter {
+    pub connection: Connection,
+    /// How dark the gaps between scanlines are, from 0.0 (no gaps) to
+    /// 1.0 (black).
+    pub scanlines: f32,
+    /// Blend each line's colour with the line above, as PAL sets did.
+    /// Ignored for NTSC.
+    pub delay_line: bool,
+    /// Frames processed, which sets the subcarrier phase.
+    frame: u64,
+}
+
+impl CrtFilter {
+    /// A filter for `connection` with no scanlines, and the delay line on
+    /// for PAL.
+    #[must_use]
+    pub fn new(connection: Connection) -> Self {
+        let delay_line = matches!(
+            connection,
+            Connection::SVideo(signal) | Connection::Composite(signal)
+                if signal.standard == Standard::Pal
+        );
+        Self {
+            connection,
+            scanlines: 0.0,
+            delay_line,
+            frame: 0,
+        }
+    }
+
+    /// Build a filter from its name: `none`, `scanlines`, `svideo`,
+    /// `composite` or `crt` (composite with scanlines). `None` means no
+    /// filtering.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error for unknown names, or for a signal-based filter
+    /// when the system has no `signal` description.
+    pub fn from_name(name: &str, signal: Option<Signal>) -> Result<Option<Self>, String> {
+        let needs_signal = || {
+            signal
+                .ok_or_else(|| format!("No composite signal is described for this system ({name})"))
+        };
+        let filter = match name {
+            "none" | "rgb" => return Ok(None),
+            "scanlines" => Self::new(Connection::Rgb).with_scanlines(0.5),
+            "svideo" => Self::new(Connection::SVideo(needs_signal()?)),
+            "composite" => Self::new(Connection::Composite(needs_signal()?)),
+            "crt" => Self::new(Connection::Composite(needs_signal()?)).with_scanlines(0.5),
+            _ => {
+                return Err(format!(
+                    "Unknown filter '{name}' (expected none, scanlines, svideo, composite or crt)"
+                ));
+            }
+        };
+        Ok(Some(filter))
+    }
+
+    /// Set the scanline strength.
+    #[must_use]
+    pub fn with_scanlines(mut self, strength: f32) -> Self {
+        self.scanlines = strength.clamp(0.0, 1.0);
+        self
+    }
+
+    /// Set the frame number of the next frame processed.
+    #[must_use]
+    pub fn at_frame(mut self, frame: u64) -> Self {
+        self.frame = frame;
+        self
+    }
+
+    /// Natural output size for a `width`×`height` frame: scanlines need
+    /// two output lines per source line.
+    #[must_use]
+    pub fn output_size(&self, width: u32, height: u32) -> (u32, u32) {
+        if self.scanlines > 0.0 {
+            (width, height * 2)
+        } else {
+            (width, height)
+        }
+    }
+
+    /// Filter one frame of `0x00RRGGBB` pixels and scale it to
+    /// `out_width`×`out_height`. Scanlines only show when there are at
+    /// least two output lines per source line.
+    #[must_use]
+    pub fn process(
+        &mut self,
+        width: u32,
+        height: u32,
+        pixels: &[u32],
+        out_width: u32,
+        out_height: u32,
+    ) -> Vec<u32> {
+        let frame = self.frame;
+        self.frame += 1;
+
+        let decoded;
+        let source = match self.connection {
+            Connection::Rgb => pixels,
+            Connection::SVideo(signal) | Connection::Composite(signal) => {
+                let composite = matches!(self.connection, Connection::Composite(_));
+                let delay_line = self.delay_line && signal.standard == Standard::Pal;
+                decoded = decode(&signal, composite, delay_line, frame, width, height, pixels);
+                &decoded
+            }
+        };
+        scale(source, width, height, out_width, out_height, self.scanlines)
+    }
+}
+
+/// A colour's waveform and its average, which is its luma.
+struct Colour {
+    wave: Waveform,
+    luma: f32,
+}
+
+/// Level of `wave` at `phase` cycles, interpolating between samples.
+fn level(wave: &Waveform, phase: f32) -> f32 {
+    let position = phase.rem_euclid(1.0) * PHASES as f32;
+    let index = (position as usize).min(PHASES - 1);
+    let fraction = position - index as f32;
+    wave[index] * (1.0 - fraction) + wave[(index + 1) % PHASES] * fraction
+}
+
+/// Moving average of `input` over `window` samples centred on each one,
+/// shortened at the ends of the line.
+fn box_filter(input: &[f32], window: usize, output: &mut [f32]) {
+    let mut sums = Vec::with_capacity(input.len() + 1);
+    sums.push(0.0f64);
+    for &value in input {
+        sums.push(sums[sums.len() - 1] + f64::from(value));
+    }
+    for (k, out) in output.iter_mut().enumerate() {
+        let start = k.saturating_sub(window / 2);
+        let end = (k + window - window / 2).min(input.len());
+        *out = ((sums[end] - sums[start]) / (end - start) as f64) as f32;
+    }
+}
+
+/// Encode each line as the chip would, then decode it as a TV would.
+fn decode(
+    signal: &Signal,
+    composite: bool,
+    delay_line: bool,
+    frame: u64,
+    width: u32,
+    height: u32,
+    pixels: &[u32],
+) -> Vec<u32> {
+    let (width, height) = (width as usize, height as usize);
+    let mut out = vec![0; width * height];
+    if width == 0 {
+        return out;
+    }
+    let samples_per_pixel = signal.cycles_per_pixel * PHASES as f32;
+    let samples = (width as f32 * samples_per_pixel).ceil() as usize;
+    let start_phase = match signal.frame_phases.len() {
+        0 => 0.0,
+        len => signal.frame_phases[(frame % len as u64) as usize],
+    };
+    let (hue_sin, hue_cos) = signal.hue.to_radians().sin_cos();
+    let (hue_sin, hue_cos) = (hue_sin * signal.saturation, hue_cos * signal.saturation);
+
+    let mut colours: HashMap<u32, Colour> = HashMap::new();
+    let mut luma_in = vec![0.0; samples];
+    let mut chroma_in = vec![0.0; samples];
+    let mut u_in = vec![0.0; samples];
+    let mut v_in = vec![0.0; samples];
+    let mut luma = vec![0.0; samples];
+    let mut u = vec![0.0; samples];
+    let mut v = vec![0.0; samples];
+    let mut previous: Option<(Vec<f32>, Vec<f32>)> = None;
+    let pixel_of = |k: usize| ((k as f32 / samples_per_pixel) as usize).min(width - 1);
+
+    for (y, row) in pixels.chunks(width).take(height).enumerate() {
+        let line_phase = start_phase + signal.line_phase * y as f32;
+        // PAL flips V on alternate lines, which is the same as running
+        // the subcarrier backwards from the opposite phase.
+        let flipped = signal.standard == Standard::Pal && y % 2 == 1;
+
+        for k in 0..samples {
+            let pixel = row.get(pixel_of(k)).copied().unwrap_or(0);
+            let colour = colours.entry(pixel).or_insert_with(|| {
+                let wave = (signal.waveform)(pixel);
+                let luma = wave.iter().sum::<f32>() / PHASES as f32;
+                Colour { wave, luma }
+            });
+            let mut phase = line_phase + k as f32 / PHASES as f32;
+            if flipped {
+                phase = 0.5 - phase;
+            }
+            let carrier = level(&colour.wave, phase) - colour.luma;
+            luma_in[k] = colour.luma;
+            chroma_in[k] = carrier;
+            let (sin, cos) = (TAU * phase).sin_cos();
+            let chroma = if composite {
+                colour.luma + carrier
+            } else {
+                carrier
+            };
+            u_in[k] = 2.0 * chroma * sin;
+            v_in[k] = 2.0 * chroma * cos;
+        }
+
+        if composite {
+            for (sum, &carrier) in luma_in.iter_mut().zip(&chroma_in) {
+                *sum += carrier;
+            }
+            box_filter(&luma_in, LUMA_WINDOW, &mut luma);
+        } else {
+            luma.copy_from_slice(&luma_in);
+        }
+        box_filter(&u_in, CHROMA_WINDOW, &mut u);
+        box_filter(&v_in, CHROMA_WINDOW, &mut v);
+
+        if delay_line {
+            let current = (u.clone(), v.clone());
+            if let Some((prev_u, prev_v)) = &previous {
+                for k in 0..samples {
+                    u[k] = f32::midpoint(u[k], prev_u[k]);
+                    v[k] = f32::midpoint(v[k], prev_v[k]);
+                }
+            }
+            previous = Some(current);
+        }
+
+        let mut sums = vec![[0.0f32; 4]; width];
+        for k in 0..samples {
+            let sum = &mut sums[pixel_of(k)];
+            sum[0] += luma[k];
+            sum[1] += u[k];
+            sum[2] += v[k];
+            sum[3] += 1.0;
+        }
+        for (x, [y_sum, u_sum, v_sum, count]) in sums.into_iter().enumerate() {
+            let count = count.max(1.0);
+            let (luma, u, v) = (y_sum / count, u_sum / count, v_sum / count);
+            let (u, v) = (u * hue_cos - v * hue_sin, u * hue_sin + v * hue_cos);
+            out[y * width + x] = yuv_to_rgb(luma, u, v);
+        }
+    }
+    out
+}
+
+fn yuv_to_rgb(y: f32, u: f32, v: f32) -> u32 {
+    let channel = |value: f32| (value.clamp(0.0, 1.0) * 255.0).round() as u32;
+    let r = channel(y + 1.140 * v);
+    let g = channel(y - 0.395 * u - 0.581 * v);
+    let b = channel(y + 2.032 * u);
+    0xFF00_0000 | (r << 16) | (g << 8) | b
+}
+
+/// Nearest-neighbour scale, darkening the lower half of each source line
+/// by `scanlines`.
+fn scale(src: &[u32], src_w: u32, src_h: u32, dst_w: u32, dst_h: u32, scanlines: f32) -> Vec<u32> {
+    let mut out = vec![0; (dst_w * dst_h) as usize];
+    if src_w == 0 || src_h == 0 {
+        return out;
+    }
+    let keep = 1.0 - scanlines.clamp(0.0, 1.0);
+    let darken = |pixel: u32| {
+        let channel = |shift: u32| (((pixel >> shift) & 0xFF) as f32 * keep).round() as u32;
+        0xFF00_0000 | (channel(16) << 16) | (channel(8) << 8) | channel(0)
+    };
+    for y in 0..dst_h {
+        let src_y = (y * src_h / dst_h).min(src_h - 1);
+        let gap = scanlines > 0.0 && (2 * y * src_h / dst_h) % 2 == 1;
+        let row = &mut out[(y * dst_w) as usize..((y + 1) * dst_w) as usize];
+        for (x, out) in row.iter_mut().enumerate() {
+            let src_x = (x as u32 * src_w / dst_w).min(src_w - 1);
+            let pixel = src
+                .get((src_y * src_w + src_x) as usize)
+                .copied()
+                .unwrap_or(0);
+            *out = if gap { darken(pixel) } else { pixel };
+        }
+    }
+    out
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const NTSC: Signal = Signal::rgb(Standard::Ntsc, 0.5, 0.5, &[0.0, 0.5]);
+    const PAL: Signal = Signal::rgb(Standard::Pal, 0.5, 0.25, &[0.0]);
+
+    fn channels(pixel: u32) -> [i32; 3] {
+        [16, 8, 0].map(|shift| ((pixel >> shift) & 0xFF) as i32)
+    }
+
+    fn close(a: u32, b: u32, tolerance: i32) -> bool {
+        channels(a)
+            .iter()
+            .zip(channels(b))
+            .all(|(x, y)| (x - y).abs() <= tolerance)
+    }
+
+    #[test]
+    fn flat_colours_survive_composite() {
+        for colour in [
+            0x0000_0000,
+            0x00FF_FFFF,
+            0x0088_3932,
+            0x0040_318D,
+            0x0055_A049,
+        ] {
+            let mut filter = CrtFilter::new(Connection::Composite(NTSC));
+            let out = filter.process(32, 4, &[colour; 128], 32, 4);
+            assert!(
+                close(out[2 * 32 + 16], colour, 6),
+                "{colour:06X} became {:06X}",
+                out[2 * 32 + 16]
+            );
+        }
+    }
+
+    #[test]
+    fn fine_luma_detail_becomes_colour_on_composite_only() {
+        // Alternate black and white pixels: one stripe pair per subcarrier
+        // cycle, the classic source of artifact colour.
+        let stripes: Vec<u32> = (0..64 * 4)
+            .map(|i| if i % 2 == 0 { 0x00FF_FFFF } else { 0 })
+            .collect();
+        let saturation = |pixel: u32| {
+            let [r, g, b] = channels(pixel);
+            r.max(g).max(b) - r.min(g).min(b)
+        };
+
+        let composite = CrtFilter::new(Connection::Composite(NTSC)).process(64, 4, &stripes, 64, 4);
+        let svideo = CrtFilter::new(Connection::SVideo(NTSC)).process(64, 4, &stripes, 64, 4);
+        assert!(saturation(composite[64 + 32]) > 64);
+        assert!(saturation(svideo[64 + 32]) < 8);
+    }
+
+    #[test]
+    fn pal_delay_line_blends_alternate_lines() {
+        let lines: Vec<u32> = (0..32 * 4)
+            .map(|i| {
+                if (i / 32) % 2 == 0 {
+                    0x00C0_2020
+                } else {
+                    0x0020_20C0
+                }
+            })
+            .collect();
+        let mut filter = CrtFilter::new(Connection::SVideo(PAL));
+        assert!(filter.delay_line);
+        let blended = filter.process(32, 4, &lines, 32, 4);
+        let [r, _, b] = channels(blended[2 * 32 + 16]);
+        assert!((r - b).abs() < 16, "red {r} blue {b}");
+
+        filter.delay_line = false;
+        let sharp = filter.process(32, 4, &lines, 32, 4);
+        assert!(close(sharp[2 * 32 + 16], 0x00C0_2020, 8));
+    }
+
+    #[test]
+    fn scanlines_darken_every_other_output_line() {
+        let mut filter = CrtFilter::new(Connection::Rgb).with_scanlines(0.5);
+        assert_eq!(filter.output_size(2, 2), (2, 4));
+        let out = filter.process(2, 2, &[0x00FF_FFFF; 4], 2, 4);
+        assert!(close(out[0], 0x00FF_FFFF, 0));
+        assert!(close(out[2], 0x0080_8080, 1));
+        assert!(close(out[4], 0x00FF_FFFF, 0));
+
+        // Not enough output lines for gaps: plain scaling.
+        let out = filter.process(2, 2, &[0x00FF_FFFF; 4], 2, 2);
+        assert!(out.iter().all(|&p| close(p, 0x00FF_FFFF, 0)));
+    }
+
+    #[test]
+    fn filters_by_name() {
+        assert!(CrtFilter::from_name("none", None).expect("none").is_none());
+        assert!(
+            CrtFilter::from_name("scanlines", None)
+                .expect("rgb")
+                .is_some()
+        );
+        assert!(CrtFilter::from_name("composite", None).is_err());
+        assert!(
+            CrtFilter::from_name("crt", Some(NTSC))
+                .expect("crt")
+                .is_some()
+        );
+        assert!(CrtFilter::from_name("vhs", Some(NTSC)).is_err());
+    }
+}
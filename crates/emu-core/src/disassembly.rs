@@ -0,0 +1,57 @@
+//! Decoded instructions for debugging tools.
+//!
+//! Disassemblers for each CPU family produce `Instruction`s; the MCP
+//! layer, trace recorder and front ends consume them without knowing
+//! which CPU they came from.
+
+use std::fmt;
+
+/// One decoded instruction.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Instruction {
+    /// Address of the first opcode byte.
+    pub address: u32,
+    /// Every byte the instruction occupies, in memory order.
+    pub bytes: Vec<u8>,
+    /// Mnemonic, including any size suffix (`LDA`, `LD`, `MOVE.L`).
+    pub mnemonic: String,
+    /// Operands in the CPU's conventional assembler syntax. Empty for
+    /// implied instructions.
+    pub operands: String,
+    /// Resolved destination of a branch, jump or call, when it can be
+    /// computed from the instruction alone.
+    pub target: Option<u32>,
+    /// True for undocumented or illegal opcodes.
+    pub undocumented: bool,
+}
+
+impl Instruction {
+    /// Instruction length in bytes.
+    #[must_use]
+    pub fn len(&self) -> usize {
+        self.bytes.len()
+    }
+
+    /// True if no bytes were decoded (never the case for disassembler
+    /// output).
+    #[must_use]
+    pub fn is_empty(&self) -> bool {
+        self.bytes.is_empty()
+    }
+
+    /// Address of the following instruction in memory.
+    #[must_use]
+    pub fn next_address(&self) -> u32 {
+        self.address.wrapping_add(self.bytes.len() as u32)
+    }
+}
+
+impl fmt::Display for Instruction {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        if self.operands.is_empty() {
+            write!(f, "{}", self.mnemonic)
+        } else {
+            write!(f, "{} {}", self.mnemonic, self.operands)
+        }
+    }
+}
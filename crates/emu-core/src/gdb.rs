@@ -0,0 +1,701 @@
+//! GDB remote serial protocol stub.
+//!
+//! [`serve`] speaks the remote serial protocol (RSP) to one `gdb` over a
+//! TCP stream, driving any machine that implements [`GdbTarget`]. The
+//! protocol state lives in [`GdbSession`], which answers one packet at a
+//! time and knows nothing of the transport.
+//!
+//! Breakpoints (`Z0`/`Z1`) and watchpoints (`Z2`-`Z4`) go into the shared
+//! [`Breakpoints`] engine, so memory is never patched: breakpoints work in
+//! ROM and watchpoints cost nothing when none are set. Continuing runs the
+//! machine tick by tick until a breakpoint fires or `gdb` sends an
+//! interrupt (Ctrl-C). Registers are described to `gdb` by the target's
+//! own `target.xml`.
+
+use std::io::{self, ErrorKind, Read, Write};
+use std::net::TcpStream;
+
+use crate::breakpoint::{Access, Breakpoint, Breakpoints, Debuggable, Hit, Trigger};
+
+/// Largest `m` read answered in one packet.
+const MAX_READ: usize = 0x800;
+/// Ticks between checks for an interrupt while running.
+const INTERRUPT_POLL_TICKS: u32 = 0x1_0000;
+
+/// A machine `gdb` can debug.
+pub trait GdbTarget: Debuggable {
+    /// Target description (`target.xml`) listing the registers in `g`
+    /// packet order.
+    fn target_xml(&self) -> String;
+
+    /// Register `index` in target byte order, `None` past the last one.
+    fn read_register(&self, index: usize) -> Option<Vec<u8>>;
+
+    /// Set register `index` from target-order bytes. False if there is no
+    /// such register.
+    fn write_register(&mut self, index: usize, value: &[u8]) -> bool;
+
+    /// Read memory for the debugger without disturbing I/O.
+    fn read_memory(&self, address: u32, out: &mut [u8]);
+
+    /// Write memory for the debugger. False if any of it could not be
+    /// written.
+    fn write_memory(&mut self, address: u32, data: &[u8]) -> bool;
+
+    /// Advance the machine by one master-clock tick.
+    fn tick(&mut self);
+}
+
+/// What the transport should do after a packet.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Action {
+    /// Send this reply packet.
+    Reply(String),
+    /// Send the reply, if any, and end the session.
+    Close(Option<String>),
+}
+
+/// A breakpoint or watchpoint as `gdb` knows it.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+struct Inserted {
+    /// `Z` packet type: 0 software, 1 hardware, 2 write, 3 read, 4 access.
+    kind: u8,
+    address: u32,
+    length: u32,
+    id: u32,
+}
+
+/// Protocol state for one `gdb` connection.
+#[derive(Debug, Default)]
+pub struct GdbSession {
+    breakpoints: Breakpoints,
+    inserted: Vec<Inserted>,
+    swbreak: bool,
+    hwbreak: bool,
+}
+
+fn hex(bytes: &[u8]) -> String {
+    use std::fmt::Write as _;
+    bytes.iter().fold(String::new(), |mut out, b| {
+        let _ = write!(out, "{b:02x}");
+        out
+    })
+}
+
+fn unhex(text: &str) -> Option<Vec<u8>> {
+    if !text.len().is_multiple_of(2) {
+        return None;
+    }
+    (0..text.len())
+        .step_by(2)
+        .map(|i| u8::from_str_radix(text.get(i..i + 2)?, 16).ok())
+        .collect()
+}
+
+fn parse_hex(text: &str) -> Option<u32> {
+    u32::from_str_radix(text, 16).ok()
+}
+
+impl GdbSession {
+    #[must_use]
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Answer one packet (without its `$`/`#xx` framing). `interrupted`
+    /// is polled while the machine runs and stops it when true.
+    pub fn handle<T: GdbTarget>(
+        &mut self,
+        target: &mut T,
+        packet: &str,
+        interrupted: &mut dyn FnMut() -> bool,
+    ) -> Action {
+        let reply = match packet.as_bytes().first() {
+            Some(b'?') => "S05".to_string(),
+            Some(b'g') => Self::read_registers(target),
+            Some(b'G') => Self::write_registers(target, &packet[1..]),
+            Some(b'p') => parse_hex(&packet[1..])
+                .and_then(|n| target.read_register(n as usize))
+                .map_or_else(|| "E01".to_string(), |value| hex(&value)),
+            Some(b'P') => Self::write_register(target, &packet[1..]),
+            Some(b'm') => Self::read_memory(target, &packet[1..]),
+            Some(b'M') => Self::write_memory(target, &packet[1..]),
+            Some(b'Z') => self.insert(&packet[1..]),
+            Some(b'z') => self.remove(&packet[1..]),
+            Some(b'c') => self.resume(target, false, interrupted),
+            Some(b's') => self.resume(target, true, interrupted),
+            Some(b'H') => "OK".to_string(),
+            Some(b'k') => return Action::Close(None),
+            Some(b'D') => return Action::Close(Some("OK".to_string())),
+            Some(b'q') => self.query(target, &packet[1..]),
+            _ => String::new(),
+        };
+        Action::Reply(reply)
+    }
+
+    fn query<T: GdbTarget>(&mut self, target: &T, query: &str) -> String {
+        if let Some(features) = query.strip_prefix("Supported") {
+            self.swbreak = features.contains("swbreak+");
+            self.hwbreak = features.contains("hwbreak+");
+            return format!(
+                "PacketSize={:x};qXfer:features:read+;swbreak+;hwbreak+;QStartNoAckMode+",
+                MAX_READ * 2 + 16
+            );
+        }
+        if query == "Attached" {
+            return "1".to_string();
+        }
+        if let Some(range) = query.strip_prefix("Xfer:features:read:target.xml:") {
+            let Some((offset, length)) = range.split_once(',') else {
+                return "E01".to_string();
+            };
+            let (Some(offset), Some(length)) = (parse_hex(offset), parse_hex(length)) else {
+                return "E01".to_string();
+            };
+            let xml = target.target_xml();
+            let start = (offset as usize).min(xml.len());
+            let end = start.saturating_add(length as usize).min(xml.len());
+            let more = if end < xml.len() { 'm' } else { 'l' };
+            return format!("{more}{}", &xml[start..end]);
+        }
+        String::new()
+    }
+
+    fn read_registers<T: GdbTarget>(target: &T) -> String {
+        (0..)
+            .map_while(|n| target.read_register(n))
+            .map(|value| hex(&value))
+            .collect()
+    }
+
+    fn write_registers<T: GdbTarget>(target: &mut T, data: &str) -> String {
+        let Some(bytes) = unhex(data) else {
+            return "E01".to_string();
+        };
+        let mut rest = bytes.as_slice();
+        let mut n = 0;
+        while let Some(size) = target.read_register(n).map(|r| r.len()) {
+            if rest.len() < size {
+                break;
+            }
+            target.write_register(n, &rest[..size]);
+            rest = &rest[size..];
+            n += 1;
+        }
+        "OK".to_string()
+    }
+
+    fn write_register<T: GdbTarget>(target: &mut T, args: &str) -> String {
+        let written = args.split_once('=').and_then(|(n, value)| {
+            let n = parse_hex(n)?;
+            Some(target.write_register(n as usize, &unhex(value)?))
+        });
+        if written == Some(true) {
+            "OK".to_string()
+        } else {
+            "E01".to_string()
+        }
+    }
+
+    fn read_memory<T: GdbTarget>(target: &T, args: &str) -> String {
+        let Some((address, length)) = args.split_once(',') else {
+            return "E01".to_string();
+        };
+        let (Some(address), Some(length)) = (parse_hex(address), parse_hex(length)) else {
+            return "E01".to_string();
+        };
+        let mut bytes = vec![0; (length as usize).min(MAX_READ)];
+        target.read_memory(address, &mut bytes);
+        hex(&bytes)
+    }
+
+    fn write_memory<T: GdbTarget>(target: &mut T, args: &str) -> String {
+        let parsed = args.split_once(':').and_then(|(range, data)| {
+            let (address, length) = range.split_once(',')?;
+            let data = unhex(data)?;
+            (parse_hex(length)? as usize == data.len()).then_some((parse_hex(address)?, data))
+        });
+        match parsed {
+            Some((address, data)) if target.write_memory(address, &data) => "OK".to_string(),
+            _ => "E01".to_string(),
+        }
+    }
+
+    /// `type,address,kind`, as `Z` and `z` packets carry them.
+    fn parse_point(args: &str) -> Option<(u8, u32, u32)> {
+        let mut fields = args.split(',');
+        let kind = fields.next()?.parse().ok()?;
+        let address = parse_hex(fields.next()?)?;
+        let length = fields.next().and_then(parse_hex).unwrap_or(1);
+        Some((kind, address, length))
+    }
+
+    fn insert(&mut self, args: &str) -> String {
+        let Some((kind, address, length)) = Self::parse_point(args) else {
+            return "E01".to_string();
+        };
+        if self
+            .inserted
+            .iter()
+            .any(|p| (p.kind, p.address, p.length) == (kind, address, length))
+        {
+            return "OK".to_string();
+        }
+        let range = address..=address.wrapping_add(length.max(1) - 1);
+        let trigger = match kind {
+            0 | 1 => Trigger::Execute(address),
+            2 => Trigger::Memory {
+                range,
+                access: Access::Write,
+            },
+            3 => Trigger::Memory {
+                range,
+                access: Access::Read,
+            },
+            4 => Trigger::Memory {
+                range,
+                access: Access::ReadWrite,
+            },
+            _ => return String::new(),
+        };
+        let id = self.breakpoints.add(Breakpoint::new(trigger));
+        self.inserted.push(Inserted {
+            kind,
+            address,
+            length,
+            id,
+        });
+        "OK".to_string()
+    }
+
+    fn remove(&mut self, args: &str) -> String {
+        let Some((kind, address, length)) = Self::parse_point(args) else {
+            return "E01".to_string();
+        };
+        if kind > 4 {
+            return String::new();
+        }
+        if let Some(index) = self
+            .inserted
+            .iter()
+            .position(|p| (p.kind, p.address, p.length) == (kind, address, length))
+        {
+            let point = self.inserted.remove(index);
+            self.breakpoints.remove(point.id);
+        }
+        "OK".to_string()
+    }
+
+    /// Run until a breakpoint, the end of one instruction (`step`), or an
+    /// interrupt, and describe why the machine stopped.
+    fn resume<T: GdbTarget>(
+        &mut self,
+        target: &mut T,
+        step: bool,
+        interrupted: &mut dyn FnMut() -> bool,
+    ) -> String {
+        let step_id = step.then(|| {
+            self.breakpoints
+                .add(Breakpoint::new(Trigger::Condition).temporary())
+        });
+        let mut ticks = 0u32;
+        let mut stopped_by_user = false;
+        let hit = self.breakpoints.run(target, |target| {
+            target.tick();
+            ticks = ticks.wrapping_add(1);
+            if ticks.is_multiple_of(INTERRUPT_POLL_TICKS) && interrupted() {
+                stopped_by_user = true;
+                return false;
+            }
+            true
+        });
+        if let Some(id) = step_id {
+            self.breakpoints.remove(id);
+        }
+        // Stop between instructions so the registers `gdb` reads next are
+        // consistent (a halted CPU never gets there).
+        if stopped_by_user {
+            for _ in 0..INTERRUPT_POLL_TICKS {
+                if target.instruction_boundary().is_some() {
+                    break;
+                }
+                target.tick();
+            }
+        }
+
+        match hit {
+            Some(hit) if Some(hit.id) != step_id => self.stop_reply(&hit),
+            Some(_) => "S05".to_string(),
+            None if stopped_by_user => "S02".to_string(),
+            None => "S05".to_string(),
+        }
+    }
+
+    fn stop_reply(&self, hit: &Hit) -> String {
+        let Some(point) = self.inserted.iter().find(|p| p.id == hit.id) else {
+            return "S05".to_string();
+        };
+        let address = hit.access.map_or(point.address, |access| access.address);
+        match point.kind {
+            0 if self.swbreak => "T05swbreak:;".to_string(),
+            1 if self.hwbreak => "T05hwbreak:;".to_string(),
+            2 => format!("T05watch:{address:x};"),
+            3 => format!("T05rwatch:{address:x};"),
+            4 => format!("T05awatch:{address:x};"),
+            _ => "S05".to_string(),
+        }
+    }
+}
+
+/// Packet framing over a TCP stream.
+struct Connection {
+    stream: TcpStream,
+    pending: Vec<u8>,
+    ack: bool,
+}
+
+impl Connection {
+    fn next_byte(&mut self) -> io::Result<Option<u8>> {
+        if !self.pending.is_empty() {
+            return Ok(Some(self.pending.remove(0)));
+        }
+        let mut byte = [0];
+        match self.stream.read(&mut byte) {
+            Ok(0) => Ok(None),
+            Ok(_) => Ok(Some(byte[0])),
+            Err(e) if e.kind() == ErrorKind::Interrupted => self.next_byte(),
+            Err(e) => Err(e),
+        }
+    }
+
+    /// The next packet's payload; `None` once `gdb` hangs up.
+    fn read_packet(&mut self) -> io::Result<Option<String>> {
+        loop {
+            // Acks, and interrupts that arrive while already stopped.
+            match self.next_byte()? {
+                None => return Ok(None),
+                Some(b'$') => {}
+                Some(_) => continue,
+            }
+            let mut payload = Vec::new();
+            loop {
+                match self.next_byte()? {
+                    None => return Ok(None),
+                    Some(b'#') => break,
+                    Some(b'}') => {
+                        let Some(escaped) = self.next_byte()? else {
+                            return Ok(None);
+                        };
+                        payload.push(escaped ^ 0x20);
+                    }
+                    Some(byte) => payload.push(byte),
+                }
+            }
+            let mut checksum = [0; 2];
+            for digit in &mut checksum {
+                let Some(byte) = self.next_byte()? else {
+                    return Ok(None);
+                };
+                *digit = byte;
+            }
+            let expected = std::str::from_utf8(&checksum)
+                .ok()
+                .and_then(|text| u8::from_str_radix(text, 16).ok());
+            let sum = payload.iter().fold(0u8, |acc, b| acc.wrapping_add(*b));
+            if self.ack {
+                let ok = expected == Some(sum);
+                self.stream.write_all(if ok { b"+" } else { b"-" })?;
+                if !ok {
+                    continue;
+                }
+            }
+            return Ok(Some(String::from_utf8_lossy(&payload).into_owned()));
+        }
+    }
+
+    fn send(&mut self, payload: &str) -> io::Result<()> {
+        let sum = payload.bytes().fold(0u8, u8::wrapping_add);
+        write!(self.stream, "${payload}#{sum:02x}")?;
+        self.stream.flush()
+    }
+
+    /// True if `gdb` has sent an interrupt (Ctrl-C) since the last check.
+    fn poll_interrupt(&mut self) -> bool {
+        if self.stream.set_nonblocking(true).is_err() {
+            return false;
+        }
+        let mut buf = [0; 64];
+        let mut interrupted = false;
+        while let Ok(n @ 1..) = self.stream.read(&mut buf) {
+            for &byte in &buf[..n] {
+                if byte == 0x03 {
+                    interrupted = true;
+                } else {
+                    self.pending.push(byte);
+                }
+            }
+        }
+        let _ = self.stream.set_nonblocking(false);
+        interrupted
+    }
+}
+
+/// Serve one `gdb` connection until it detaches, kills the session, or
+/// hangs up. The machine stays stopped between `gdb` commands.
+///
+/// # Errors
+///
+/// Returns any I/O error on the stream.
+pub fn serve<T: GdbTarget>(stream: TcpStream, target: &mut T) -> io::Result<()> {
+    stream.set_nodelay(true)?;
+    let mut connection = Connection {
+        stream,
+        pending: Vec::new(),
+        ack: true,
+    };
+    let mut session = GdbSession::new();
+    while let Some(packet) = connection.read_packet()? {
+        if packet == "QStartNoAckMode" {
+            connection.send("OK")?;
+            connection.ack = false;
+            continue;
+        }
+        let action = session.handle(target, &packet, &mut || connection.poll_interrupt());
+        match action {
+            Action::Reply(reply) => connection.send(&reply)?,
+            Action::Close(reply) => {
+                if let Some(reply) = reply {
+                    connection.send(&reply)?;
+                }
+                return Ok(());
+            }
+        }
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use std::io::{Read, Write};
+    use std::net::{TcpListener, TcpStream};
+
+    use super::{Action, GdbSession, GdbTarget, serve};
+    use crate::breakpoint::Debuggable;
+    use crate::{Bus, BusAccess, LoggingBus, Observable, SimpleBus, Value};
+
+    /// Toy 16-bit machine: one instruction per four ticks; each reads the
+    /// byte at `pc` into `a` and stores it to `$8000 + pc`.
+    struct Toy {
+        bus: SimpleBus,
+        a: u8,
+        pc: u16,
+        phase: u8,
+        log: Option<Vec<BusAccess>>,
+    }
+
+    impl Toy {
+        fn new() -> Self {
+            Self {
+                bus: SimpleBus::new(),
+                a: 0,
+                pc: 0,
+                phase: 0,
+                log: None,
+            }
+        }
+    }
+
+    impl Observable for Toy {
+        fn query(&self, path: &str) -> Option<Value> {
+            (path == "cpu.pc").then(|| self.pc.into())
+        }
+
+        fn query_paths(&self) -> &'static [&'static str] {
+            &["cpu.pc"]
+        }
+    }
+
+    impl Debuggable for Toy {
+        fn instruction_boundary(&self) -> Option<u32> {
+            (self.phase == 0).then_some(u32::from(self.pc))
+        }
+
+        fn raster_position(&self) -> (u32, u32) {
+            (0, 0)
+        }
+
+        fn set_bus_logging(&mut self, enabled: bool) {
+            self.log = enabled.then(Vec::new);
+        }
+
+        fn drain_bus_accesses(&mut self, out: &mut Vec<BusAccess>) {
+            if let Some(log) = &mut self.log {
+                out.append(log);
+            }
+        }
+    }
+
+    impl GdbTarget for Toy {
+        fn target_xml(&self) -> String {
+            "<target><architecture>toy</architecture></target>".to_string()
+        }
+
+        fn read_register(&self, index: usize) -> Option<Vec<u8>> {
+            match index {
+                0 => Some(vec![self.a]),
+                1 => Some(self.pc.to_le_bytes().to_vec()),
+                _ => None,
+            }
+        }
+
+        fn write_register(&mut self, index: usize, value: &[u8]) -> bool {
+            match (index, value) {
+                (0, [a]) => self.a = *a,
+                (1, [lo, hi]) => self.pc = u16::from_le_bytes([*lo, *hi]),
+                _ => return false,
+            }
+            true
+        }
+
+        fn read_memory(&self, address: u32, out: &mut [u8]) {
+            for (i, byte) in out.iter_mut().enumerate() {
+                *byte = self.bus.peek(address.wrapping_add(i as u32) as u16);
+            }
+        }
+
+        fn write_memory(&mut self, address: u32, data: &[u8]) -> bool {
+            for (i, &byte) in data.iter().enumerate() {
+                self.bus
+                    .write(address.wrapping_add(i as u32) & 0xFFFF, byte);
+            }
+            true
+        }
+
+        fn tick(&mut self) {
+            self.phase = (self.phase + 1) % 4;
+            if self.phase == 1 {
+                let pc = u32::from(self.pc);
+                if let Some(log) = &mut self.log {
+                    let mut bus = LoggingBus::new(&mut self.bus, log);
+                    self.a = bus.read(pc).data;
+                    bus.write(0x8000 + pc, self.a);
+                } else {
+                    self.a = self.bus.read(pc).data;
+                    self.bus.write(0x8000 + pc, self.a);
+                }
+            }
+            if self.phase == 0 {
+                self.pc = self.pc.wrapping_add(1);
+            }
+        }
+    }
+
+    fn reply(session: &mut GdbSession, toy: &mut Toy, packet: &str) -> String {
+        match session.handle(toy, packet, &mut || false) {
+            Action::Reply(reply) => reply,
+            Action::Close(reply) => reply.unwrap_or_default(),
+        }
+    }
+
+    #[test]
+    fn registers_memory_and_target_description() {
+        let mut toy = Toy::new();
+        let mut session = GdbSession::new();
+
+        assert_eq!(reply(&mut session, &mut toy, "P1=3412"), "OK");
+        assert_eq!(reply(&mut session, &mut toy, "g"), "003412");
+        assert_eq!(reply(&mut session, &mut toy, "G7f0010"), "OK");
+        assert_eq!((toy.a, toy.pc), (0x7F, 0x1000));
+        assert_eq!(reply(&mut session, &mut toy, "p1"), "0010");
+        assert_eq!(reply(&mut session, &mut toy, "p2"), "E01");
+
+        assert_eq!(reply(&mut session, &mut toy, "M1000,3:a9ea60"), "OK");
+        assert_eq!(reply(&mut session, &mut toy, "m1000,4"), "a9ea6000");
+        assert_eq!(reply(&mut session, &mut toy, "M1000,3:a9"), "E01");
+
+        assert!(reply(&mut session, &mut toy, "qSupported:swbreak+").contains("qXfer"));
+        assert_eq!(
+            reply(&mut session, &mut toy, "qXfer:features:read:target.xml:0,8"),
+            "m<target>"
+        );
+        assert_eq!(
+            reply(
+                &mut session,
+                &mut toy,
+                "qXfer:features:read:target.xml:28,40"
+            ),
+            "l</target>"
+        );
+        assert_eq!(reply(&mut session, &mut toy, "vMustReplyEmpty"), "");
+    }
+
+    #[test]
+    fn breakpoints_watchpoints_and_single_step() {
+        let mut toy = Toy::new();
+        let mut session = GdbSession::new();
+        reply(&mut session, &mut toy, "qSupported:swbreak+;hwbreak+");
+
+        assert_eq!(reply(&mut session, &mut toy, "s"), "S05");
+        assert_eq!(toy.pc, 1);
+
+        assert_eq!(reply(&mut session, &mut toy, "Z0,10,1"), "OK");
+        assert_eq!(reply(&mut session, &mut toy, "c"), "T05swbreak:;");
+        assert_eq!(toy.pc, 0x10);
+        assert_eq!(reply(&mut session, &mut toy, "z0,10,1"), "OK");
+
+        assert_eq!(reply(&mut session, &mut toy, "Z2,8020,2"), "OK");
+        assert_eq!(reply(&mut session, &mut toy, "c"), "T05watch:8020;");
+        assert_eq!(reply(&mut session, &mut toy, "Z3,30,1"), "OK");
+        assert_eq!(reply(&mut session, &mut toy, "c"), "T05watch:8021;");
+        assert_eq!(reply(&mut session, &mut toy, "z2,8020,2"), "OK");
+        assert_eq!(reply(&mut session, &mut toy, "c"), "T05rwatch:30;");
+
+        let mut polls = 0;
+        let action = session.handle(&mut toy, "z3,30,1", &mut || false);
+        assert_eq!(action, Action::Reply("OK".to_string()));
+        let action = session.handle(&mut toy, "c", &mut || {
+            polls += 1;
+            polls == 2
+        });
+        assert_eq!(action, Action::Reply("S02".to_string()));
+        assert_eq!(
+            session.handle(&mut toy, "D", &mut || false),
+            Action::Close(Some("OK".to_string()))
+        );
+    }
+
+    #[test]
+    fn serves_packets_over_tcp() {
+        let listener = TcpListener::bind("127.0.0.1:0").expect("bind");
+        let address = listener.local_addr().expect("address");
+        let server = std::thread::spawn(move || {
+            let (stream, _) = listener.accept().expect("accept");
+            let mut toy = Toy::new();
+            toy.pc = 0x1234;
+            serve(stream, &mut toy).expect("serve");
+        });
+
+        let mut gdb = TcpStream::connect(address).expect("connect");
+        let mut exchange = |packet: &str| {
+            let sum = packet.bytes().fold(0u8, u8::wrapping_add);
+            write!(gdb, "${packet}#{sum:02x}").expect("send");
+            let mut received = Vec::new();
+            let mut byte = [0];
+            while !received.ends_with(b"#") {
+                gdb.read_exact(&mut byte).expect("receive");
+                received.push(byte[0]);
+            }
+            let mut checksum = [0; 2];
+            gdb.read_exact(&mut checksum).expect("checksum");
+            gdb.write_all(b"+").expect("ack");
+            let text = String::from_utf8(received).expect("ascii");
+            let start = text.find('$').expect("packet start");
+            text[start + 1..text.len() - 1].to_string()
+        };
+
+        assert_eq!(exchange("QStartNoAckMode"), "OK");
+        assert_eq!(exchange("p1"), "3412");
+        assert_eq!(exchange("D"), "OK");
+        server.join().expect("server thread");
+    }
+}
@@ -3,30 +3,53 @@
 //! Everything ticks at the master crystal frequency. All component timing
 //! derives from this. No exceptions.
 
+#[cfg(feature = "mcp")]
+pub mod anim;
 #[cfg(feature = "renderer")]
 mod audio;
+pub mod bench;
+pub mod blip;
+pub mod breakpoint;
 mod bus;
 #[cfg(feature = "renderer")]
 mod capture;
 mod clock;
 mod cpu;
+pub mod crt;
+mod disassembly;
+pub mod gdb;
 mod machine;
 #[cfg(feature = "mcp")]
 pub mod mcp;
+pub mod movie;
+pub mod netplay;
 mod observable;
+pub mod reglog;
 #[cfg(feature = "renderer")]
 pub mod renderer;
+pub mod rewind;
 #[cfg(feature = "renderer")]
 pub mod runner;
+pub mod state;
+pub mod symbols;
 mod tickable;
 mod ticks;
+pub mod trace;
 #[cfg(feature = "video")]
 pub mod video;
+pub mod warp;
 
-pub use bus::{Bus, ReadResult, SimpleBus, WordBus};
+pub use blip::{BlipBuffer, Stems};
+pub use bus::{AccessKind, Bus, BusAccess, LoggingBus, ReadResult, SimpleBus, WordBus};
 pub use clock::MasterClock;
 pub use cpu::Cpu;
+pub use disassembly::Instruction;
 pub use machine::{AudioFrame, Machine};
+pub use movie::{Divergence, Movie, MovieError, MovieEvent, MovieMedia};
+pub use netplay::NetplayInput;
 pub use observable::{Observable, Value};
+pub use reglog::{LoggedChip, RegisterLog, SongInfo};
+pub use rewind::RewindBuffer;
+pub use state::{SaveState, StateError, StateReader, StateWriter};
 pub use tickable::Tickable;
 pub use ticks::Ticks;
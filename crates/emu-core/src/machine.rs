@@ -5,6 +5,10 @@
 //! This enables generic tooling: save states, recording, WASM wrappers,
 //! and windowed runners can all be written once against the trait.
 
+use std::time::Duration;
+
+use crate::{RegisterLog, StateError};
+
 /// Stereo audio frame: left and right channels.
 pub type AudioFrame = [f32; 2];
 
@@ -29,9 +33,8 @@ pub trait Machine {
 
     /// Drain the audio output buffer.
     ///
-    /// Returns stereo sample pairs (left, right) at the system's output
-    /// sample rate (typically 48 kHz). Mono systems duplicate the sample
-    /// to both channels.
+    /// Returns stereo sample pairs (left, right) at `audio_sample_rate()`.
+    /// Mono systems duplicate the sample to both channels.
     fn take_audio_buffer(&mut self) -> Vec<AudioFrame>;
 
     /// Audio sample rate for `take_audio_buffer()`.
@@ -41,9 +44,123 @@ pub trait Machine {
         48_000
     }
 
+    /// Change the output sample rate, for example to match the host's
+    /// audio device at 44.1 or 96 kHz.
+    ///
+    /// Returns `false` if the machine's audio is fixed at
+    /// `audio_sample_rate()`.
+    fn set_audio_sample_rate(&mut self, rate: u32) -> bool {
+        let _ = rate;
+        false
+    }
+
+    /// Stretch the output sample rate by a small ratio (1.0 = exact).
+    ///
+    /// Runners use this for dynamic rate control: when the host's audio
+    /// clock drifts from the emulated frame rate, nudging the ratio keeps
+    /// the audio queue from underrunning or overflowing. See
+    /// [`crate::blip::dynamic_rate`]. Machines with fixed-rate audio ignore it.
+    fn set_audio_rate_adjust(&mut self, ratio: f64) {
+        let _ = ratio;
+    }
+
+    /// Names of the sound channels this machine taps, such as `voice1` or
+    /// `pulse2`. Position `n` is bit `n` of the channel-mute mask and
+    /// stem `n` of `take_audio_stems()`. Empty if the machine has no taps.
+    fn audio_channels(&self) -> Vec<&'static str> {
+        Vec::new()
+    }
+
+    /// Silence channels in the mix: bit `n` mutes `audio_channels()[n]`.
+    ///
+    /// Muting only changes what is heard. Channels keep running and their
+    /// stems are still captured.
+    fn set_channel_mute(&mut self, mask: u32) {
+        let _ = mask;
+    }
+
+    /// Current channel-mute mask.
+    fn channel_mute(&self) -> u32 {
+        0
+    }
+
+    /// Start or stop capturing one mono stream per channel.
+    fn set_stem_capture(&mut self, enabled: bool) {
+        let _ = enabled;
+    }
+
+    /// Drain the per-channel streams, one `Vec` per `audio_channels()`
+    /// entry, at `audio_sample_rate()`. Empty while capture is off.
+    fn take_audio_stems(&mut self) -> Vec<Vec<f32>> {
+        Vec::new()
+    }
+
+    /// Start logging writes to the machine's music chip (SN76489, AY or
+    /// SID), replacing any log already running.
+    ///
+    /// Returns `false` if the machine has no chip that can be logged.
+    fn start_register_log(&mut self) -> bool {
+        false
+    }
+
+    /// Stop logging and return the log, with its frame length set to this
+    /// machine's. `None` if no log was running.
+    fn stop_register_log(&mut self) -> Option<RegisterLog> {
+        None
+    }
+
     /// Total number of completed frames since creation.
     fn frame_count(&self) -> u64;
 
+    /// Main CPU clock in Hz, for speed reports. Zero if unknown.
+    fn cpu_clock_hz(&self) -> f64 {
+        0.0
+    }
+
+    /// Frames per emulated second, for speed reports. Zero if unknown.
+    fn frame_rate_hz(&self) -> f64 {
+        0.0
+    }
+
+    /// True while the machine is waiting on slow media: a tape playing or
+    /// a disk drive motor running. Runners with auto-warp enabled run
+    /// unthrottled until it clears. See [`crate::warp`].
+    fn media_busy(&self) -> bool {
+        false
+    }
+
+    /// Start or stop sampling how long each chip's tick takes, clearing
+    /// the totals. See [`crate::bench::ChipProfiler`].
+    ///
+    /// Returns `false` if the machine is not instrumented.
+    fn set_chip_profiling(&mut self, enabled: bool) -> bool {
+        let _ = enabled;
+        false
+    }
+
+    /// Sampled time spent in each chip since profiling started, named by
+    /// the crate that emulates it.
+    fn chip_times(&self) -> Vec<(&'static str, Duration)> {
+        Vec::new()
+    }
+
     /// Reset the system (equivalent to pressing the reset button).
     fn reset(&mut self);
+
+    /// Serialise the complete machine state into a versioned snapshot.
+    ///
+    /// Snapshots can be taken at any point, including mid-frame; loading
+    /// one into an identically configured machine continues bit-identically.
+    fn save_state(&self) -> Result<Vec<u8>, StateError> {
+        Err(StateError::Unsupported)
+    }
+
+    /// Restore a snapshot produced by `save_state`.
+    ///
+    /// The machine must have the same model and read-only media (ROMs,
+    /// cartridge, tape) as the one that saved the state.
+    fn load_state(&mut self, data: &[u8]) -> Result<(), StateError> {
+        let _ = data;
+        Err(StateError::Unsupported)
+    }
 }
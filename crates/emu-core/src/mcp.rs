@@ -4,17 +4,35 @@
 //! `initialize`, `tools/list`, and `tools/call` so each emulator only
 //! needs to provide tool definitions and a dispatch function.
 //!
-//! Wire format: newline-delimited JSON-RPC 2.0 over stdin/stdout.
+//! Wire format: newline-delimited JSON-RPC 2.0 over stdin/stdout. While a
+//! continuous `run` is in progress the server also pushes JSON-RPC
+//! notifications (see [`run`]).
 
 #![allow(clippy::module_name_repetitions)]
 
 use std::io::{self, BufRead, Write};
-use std::path::Path;
+use std::path::{Path, PathBuf};
+use std::sync::mpsc::{self, Receiver, RecvTimeoutError};
+use std::time::{Duration, Instant};
 
 use serde::{Deserialize, Serialize};
 use serde_json::Value as JsonValue;
 
-use crate::Value;
+use crate::anim::{AnimationFormat, AnimationRecorder};
+use crate::crt::{CrtFilter, Signal};
+use crate::reglog::{RegisterLog, SongInfo};
+use crate::symbols::SymbolTable;
+use crate::{Machine, Value};
+
+pub mod breakpoint;
+pub mod machine;
+pub mod movie;
+pub mod run;
+pub mod symbols;
+pub mod trace;
+
+pub use machine::{MachineMcp, McpMachine};
+pub use run::{Notification, RunSession};
 
 // ---------------------------------------------------------------------------
 // Public types
@@ -49,6 +67,20 @@ pub trait McpEmulator {
 
     /// Server version shown in the `initialize` response.
     fn server_version(&self) -> &str;
+
+    /// Advance a continuous `run` by one slice (a frame).
+    ///
+    /// Returns how long to wait before the next slice is due, or `None`
+    /// when no run is in progress. Emulators without a `run` tool keep
+    /// the default.
+    fn run_slice(&mut self) -> Option<Duration> {
+        None
+    }
+
+    /// Notifications queued since the last call.
+    fn take_notifications(&mut self) -> Vec<Notification> {
+        Vec::new()
+    }
 }
 
 // ---------------------------------------------------------------------------
@@ -117,39 +149,94 @@ impl<T: McpEmulator> McpServer<T> {
     }
 
     /// Run the MCP protocol loop over stdin/stdout.
+    ///
+    /// Requests are read on a separate thread so that a continuous `run`
+    /// keeps going between them and `pause` can interrupt it.
     pub fn run(&mut self) {
-        let stdin = io::stdin();
+        let (tx, rx) = mpsc::channel();
+        std::thread::spawn(move || {
+            for line in io::stdin().lock().lines() {
+                let Ok(line) = line else {
+                    break;
+                };
+                if tx.send(line).is_err() {
+                    break;
+                }
+            }
+        });
+
         let stdout = io::stdout();
         let mut stdout = stdout.lock();
+        self.serve(&rx, &mut stdout);
+    }
 
-        for line in stdin.lock().lines() {
-            let Ok(line) = line else {
-                break;
+    /// Answer requests from `requests` until it closes, running slices of
+    /// a continuous `run` whenever one is due.
+    fn serve(&mut self, requests: &Receiver<String>, out: &mut impl Write) {
+        // When the next slice of a continuous run is due, if one is going.
+        let mut next_slice: Option<Instant> = None;
+        loop {
+            let line = match next_slice {
+                None => match requests.recv() {
+                    Ok(line) => Some(line),
+                    Err(_) => break,
+                },
+                Some(due) => {
+                    match requests.recv_timeout(due.saturating_duration_since(Instant::now())) {
+                        Ok(line) => Some(line),
+                        Err(RecvTimeoutError::Timeout) => None,
+                        Err(RecvTimeoutError::Disconnected) => break,
+                    }
+                }
             };
 
-            let line = line.trim().to_string();
-            if line.is_empty() {
-                continue;
+            if let Some(line) = line {
+                self.handle_line(&line, out);
+                // A `run` request starts the first slice straight away.
+                if next_slice.is_none() {
+                    next_slice = self.inner.run_slice().map(|wait| Instant::now() + wait);
+                }
+            } else {
+                next_slice = self.inner.run_slice().map(|wait| Instant::now() + wait);
             }
+            self.write_notifications(out);
+        }
+    }
 
-            let msg: RpcMessage = match serde_json::from_str(&line) {
-                Ok(m) => m,
-                Err(e) => {
-                    let resp =
-                        RpcResponse::error(JsonValue::Null, -32700, format!("Parse error: {e}"));
-                    write_response(&mut stdout, &resp);
-                    continue;
-                }
-            };
+    fn handle_line(&mut self, line: &str, out: &mut impl Write) {
+        let line = line.trim();
+        if line.is_empty() {
+            return;
+        }
 
-            // Notifications have no id — don't send a response.
-            if msg.id.is_none() {
-                continue;
+        let msg: RpcMessage = match serde_json::from_str(line) {
+            Ok(m) => m,
+            Err(e) => {
+                let resp = RpcResponse::error(JsonValue::Null, -32700, format!("Parse error: {e}"));
+                write_response(out, &resp);
+                return;
             }
+        };
 
-            let id = msg.id.unwrap_or(JsonValue::Null);
-            let response = self.handle(&msg.method, &msg.params, id);
-            write_response(&mut stdout, &response);
+        // Notifications have no id — don't send a response.
+        if msg.id.is_none() {
+            return;
+        }
+
+        let id = msg.id.unwrap_or(JsonValue::Null);
+        let response = self.handle(&msg.method, &msg.params, id);
+        write_response(out, &response);
+    }
+
+    fn write_notifications(&mut self, out: &mut impl Write) {
+        for notification in self.inner.take_notifications() {
+            let message = serde_json::json!({
+                "jsonrpc": "2.0",
+                "method": format!("notifications/{}", notification.event),
+                "params": notification.params,
+            });
+            let _ = writeln!(out, "{message}");
+            let _ = out.flush();
         }
     }
 
@@ -177,6 +264,13 @@ impl<T: McpEmulator> McpServer<T> {
 
             write_response(&mut stdout, &response);
 
+            // A script cannot pause, so a `run` step goes until it stops
+            // by itself (breakpoint, halted CPU or `max_frames`).
+            while self.inner.run_slice().is_some() {
+                self.write_notifications(&mut stdout);
+            }
+            self.write_notifications(&mut stdout);
+
             // Script mode: if save_path was provided, save base64 data to file.
             if let Some(save_path) = params.get("save_path").and_then(|v| v.as_str())
                 && let Some(ref result) = response.result
@@ -442,13 +536,502 @@ pub fn video_result(save_path: &str, frames: u64, fps: u32) -> ToolResult {
     }))
 }
 
+/// Definition of the `record_gif` tool, shared by every system.
+#[must_use]
+pub fn record_gif_definition() -> ToolDefinition {
+    ToolDefinition {
+        name: "record_gif",
+        description: "Run N frames and record them as a looping animated GIF (or APNG for .png/.apng paths)",
+        input_schema: serde_json::json!({
+            "type": "object",
+            "properties": {
+                "frames": { "type": "integer", "description": "Number of frames to run" },
+                "save_path": { "type": "string", "description": "Output path: .gif, or .png/.apng for APNG" },
+                "every": { "type": "integer", "description": "Keep every Nth frame (default: 1)" },
+                "crop": {
+                    "type": ["string", "array"],
+                    "items": { "type": "integer" },
+                    "description": "\"none\" (default), \"active\" for the display area without border, or [x, y, width, height]"
+                }
+            },
+            "required": ["frames", "save_path"]
+        }),
+    }
+}
+
+/// `record_gif` helper: run the requested frames through `run_frame`,
+/// which must advance the machine one frame and offer its framebuffer to
+/// the recorder, then write the clip.
+///
+/// `active_area` is the display area without border (x, y, width,
+/// height), used for `"crop": "active"`; `None` means the whole
+/// framebuffer is active.
+#[allow(clippy::cast_precision_loss)]
+pub fn record_gif_result(
+    params: &JsonValue,
+    width: u32,
+    height: u32,
+    fps: u32,
+    active_area: Option<(u32, u32, u32, u32)>,
+    mut run_frame: impl FnMut(&mut AnimationRecorder),
+) -> ToolResult {
+    let invalid = |message: &str| ToolResult::Error {
+        code: -32602,
+        message: message.to_string(),
+    };
+    let frames = match params.get("frames").and_then(JsonValue::as_u64) {
+        Some(f) if f > 0 => f,
+        _ => return invalid("Missing or invalid 'frames' (positive integer)"),
+    };
+    let Some(save_path) = params.get("save_path").and_then(JsonValue::as_str) else {
+        return invalid("Missing 'save_path' parameter");
+    };
+    let every = match params.get("every") {
+        None => 1,
+        Some(v) => match v.as_u64().and_then(|n| u32::try_from(n).ok()) {
+            Some(n) if n > 0 => n,
+            _ => return invalid("Invalid 'every' (positive integer)"),
+        },
+    };
+    let crop = match params.get("crop") {
+        None => None,
+        Some(JsonValue::String(s)) if s == "none" => None,
+        Some(JsonValue::String(s)) if s == "active" => active_area,
+        Some(JsonValue::Array(values)) => {
+            let Some([x, y, w, h]) = values
+                .iter()
+                .map(|v| v.as_u64().and_then(|n| u32::try_from(n).ok()))
+                .collect::<Option<Vec<_>>>()
+                .and_then(|v| <[u32; 4]>::try_from(v).ok())
+            else {
+                return invalid("'crop' must be [x, y, width, height]");
+            };
+            Some((x, y, w, h))
+        }
+        Some(_) => return invalid("'crop' must be \"none\", \"active\" or [x, y, width, height]"),
+    };
+
+    let mut rec = match AnimationRecorder::new(width, height, fps, every, crop) {
+        Ok(r) => r,
+        Err(message) => {
+            return ToolResult::Error {
+                code: -32602,
+                message,
+            };
+        }
+    };
+    for _ in 0..frames {
+        run_frame(&mut rec);
+    }
+
+    let path = Path::new(save_path);
+    match rec.finish(path) {
+        Ok(info) => ToolResult::Success(serde_json::json!({
+            "format": AnimationFormat::from_path(path).name(),
+            "frames": info.frames,
+            "colours": info.colours,
+            "width": info.width,
+            "height": info.height,
+            "duration": info.duration_frames as f64 / f64::from(fps),
+            "path": save_path,
+            "size": info.size,
+        })),
+        Err(e) => ToolResult::Error {
+            code: -32000,
+            message: format!("Animation recording failed: {e}"),
+        },
+    }
+}
+
+/// Encode interleaved samples as a 16-bit PCM WAV file.
+pub fn encode_wav(samples: &[f32], channels: u16, sample_rate: u32) -> Result<Vec<u8>, String> {
+    let spec = hound::WavSpec {
+        channels,
+        sample_rate,
+        bits_per_sample: 16,
+        sample_format: hound::SampleFormat::Int,
+    };
+    let mut wav = Vec::new();
+    let mut writer = hound::WavWriter::new(io::Cursor::new(&mut wav), spec)
+        .map_err(|e| format!("WAV encode failed: {e}"))?;
+    for &sample in samples {
+        writer
+            .write_sample((sample.clamp(-1.0, 1.0) * f32::from(i16::MAX)) as i16)
+            .map_err(|e| format!("WAV encode failed: {e}"))?;
+    }
+    writer
+        .finalize()
+        .map_err(|e| format!("WAV encode failed: {e}"))?;
+    Ok(wav)
+}
+
+/// JSON schema for the `stems` argument of `audio_capture`.
+#[must_use]
+pub fn stems_property() -> JsonValue {
+    serde_json::json!({
+        "type": "boolean",
+        "description": "Also capture each sound channel as a mono WAV, saved as <name>_<channel>.wav beside save_path (see channel_mute for channel names). Default: false"
+    })
+}
+
+/// Per-channel capture for `audio_capture` with `"stems": true`.
+///
+/// Call [`StemCapture::collect`] after each frame so the machine's stem
+/// buffers stay short, then [`StemCapture::finish`] to encode the WAVs.
+pub struct StemCapture {
+    channels: Vec<&'static str>,
+    samples: Vec<Vec<f32>>,
+}
+
+impl StemCapture {
+    /// Start capture on `machine` if `params` asks for stems.
+    pub fn start<M: Machine + ?Sized>(
+        params: &JsonValue,
+        machine: &mut M,
+    ) -> Result<Option<Self>, ToolResult> {
+        if !params
+            .get("stems")
+            .and_then(JsonValue::as_bool)
+            .unwrap_or(false)
+        {
+            return Ok(None);
+        }
+        let channels = machine.audio_channels();
+        if channels.is_empty() {
+            return Err(no_channel_taps());
+        }
+        machine.set_stem_capture(true);
+        Ok(Some(Self {
+            samples: vec![Vec::new(); channels.len()],
+            channels,
+        }))
+    }
+
+    /// Append the stem samples produced since the last call.
+    pub fn collect<M: Machine + ?Sized>(&mut self, machine: &mut M) {
+        for (all, new) in self.samples.iter_mut().zip(machine.take_audio_stems()) {
+            all.extend(new);
+        }
+    }
+
+    /// Stop capture and encode each channel as a mono WAV: saved beside
+    /// `save_path`, or returned as base64 without one.
+    pub fn finish<M: Machine + ?Sized>(
+        mut self,
+        machine: &mut M,
+        save_path: Option<&str>,
+    ) -> Result<JsonValue, ToolResult> {
+        use base64::Engine;
+
+        self.collect(machine);
+        machine.set_stem_capture(false);
+        let failed = |message: String| ToolResult::Error {
+            code: -32000,
+            message,
+        };
+
+        let rate = machine.audio_sample_rate();
+        let mut stems = Vec::with_capacity(self.channels.len());
+        for (channel, samples) in self.channels.iter().zip(&self.samples) {
+            let wav = encode_wav(samples, 1, rate).map_err(failed)?;
+            let mut entry = serde_json::json!({
+                "channel": channel,
+                "samples": samples.len(),
+            });
+            if let Some(save_path) = save_path {
+                let path = stem_path(Path::new(save_path), channel);
+                std::fs::write(&path, &wav)
+                    .map_err(|e| failed(format!("Failed to save {}: {e}", path.display())))?;
+                entry["path"] = path.display().to_string().into();
+                entry["size"] = wav.len().into();
+            } else {
+                entry["data"] = base64::engine::general_purpose::STANDARD
+                    .encode(&wav)
+                    .into();
+            }
+            stems.push(entry);
+        }
+        Ok(JsonValue::Array(stems))
+    }
+}
+
+/// Stem file for `channel` beside `path`: `tune.wav` → `tune_voice1.wav`.
+fn stem_path(path: &Path, channel: &str) -> PathBuf {
+    let name = path
+        .file_stem()
+        .map_or_else(|| "audio".into(), |s| s.to_string_lossy());
+    let ext = path
+        .extension()
+        .map_or_else(|| "wav".into(), |e| e.to_string_lossy());
+    path.with_file_name(format!("{name}_{channel}.{ext}"))
+}
+
+fn no_channel_taps() -> ToolResult {
+    ToolResult::Error {
+        code: -32602,
+        message: "This system has no per-channel audio taps".to_string(),
+    }
+}
+
+/// Definition of the `channel_mute` tool, shared by every system.
+#[must_use]
+pub fn channel_mute_definition() -> ToolDefinition {
+    let names = serde_json::json!({
+        "type": ["string", "array"],
+        "items": { "type": "string" }
+    });
+    ToolDefinition {
+        name: "channel_mute",
+        description: "Mute or solo sound channels in the live mix. With no arguments, list the channels and which are muted",
+        input_schema: serde_json::json!({
+            "type": "object",
+            "properties": {
+                "mute": names.clone(),
+                "solo": names
+            }
+        }),
+    }
+}
+
+/// `channel_mute` helper: `"mute"` silences exactly the named channels
+/// (`[]` unmutes everything), `"solo"` silences all the others.
+pub fn channel_mute_result<M: Machine + ?Sized>(params: &JsonValue, machine: &mut M) -> ToolResult {
+    let channels = machine.audio_channels();
+    if channels.is_empty() {
+        return no_channel_taps();
+    }
+    let mask_of = |key: &str| -> Result<Option<u32>, ToolResult> {
+        let names: Vec<&str> = match params.get(key) {
+            None => return Ok(None),
+            Some(JsonValue::String(name)) => vec![name.as_str()],
+            Some(JsonValue::Array(values)) => values.iter().filter_map(JsonValue::as_str).collect(),
+            Some(_) => {
+                return Err(ToolResult::Error {
+                    code: -32602,
+                    message: format!("'{key}' must be a channel name or a list of names"),
+                });
+            }
+        };
+        let mut mask = 0;
+        for name in names {
+            let Some(index) = channels.iter().position(|c| *c == name) else {
+                return Err(ToolResult::Error {
+                    code: -32602,
+                    message: format!(
+                        "Unknown channel '{name}' (channels: {})",
+                        channels.join(", ")
+                    ),
+                });
+            };
+            mask |= 1 << index;
+        }
+        Ok(Some(mask))
+    };
+
+    let all = (1u32 << channels.len()) - 1;
+    match (mask_of("mute"), mask_of("solo")) {
+        (Err(e), _) | (_, Err(e)) => return e,
+        (Ok(Some(_)), Ok(Some(_))) => {
+            return ToolResult::Error {
+                code: -32602,
+                message: "Give 'mute' or 'solo', not both".to_string(),
+            };
+        }
+        (Ok(Some(mute)), Ok(None)) => machine.set_channel_mute(mute),
+        (Ok(None), Ok(Some(solo))) => machine.set_channel_mute(all & !solo),
+        (Ok(None), Ok(None)) => {}
+    }
+
+    let mask = machine.channel_mute();
+    let muted: Vec<&str> = channels
+        .iter()
+        .enumerate()
+        .filter(|&(i, _)| mask & (1 << i) != 0)
+        .map(|(_, c)| *c)
+        .collect();
+    ToolResult::Success(serde_json::json!({
+        "channels": channels,
+        "muted": muted,
+    }))
+}
+
+/// Definition of the `register_log` tool, shared by every system.
+#[must_use]
+pub fn register_log_definition() -> ToolDefinition {
+    ToolDefinition {
+        name: "register_log",
+        description: "Log writes to the sound chip (SN76489, AY-3-8910 or SID) with cycle timestamps, then save them as chip music: VGM, YM5/YM6, PSID or a text SID dump. 'convert' turns a saved SID dump into any SID format",
+        input_schema: serde_json::json!({
+            "type": "object",
+            "properties": {
+                "action": { "type": "string", "enum": ["start", "stop", "convert"] },
+                "save_path": { "type": "string", "description": "Write the file here; otherwise it is returned as base64 data" },
+                "format": {
+                    "type": "string",
+                    "enum": ["vgm", "ym5", "ym6", "psid", "sid_dump"],
+                    "description": "Default: from the save_path extension (.vgm, .ym, .sid, .txt), else the chip's first format"
+                },
+                "path": { "type": "string", "description": "SID dump to read (convert)" },
+                "title": { "type": "string" },
+                "author": { "type": "string" },
+                "released": { "type": "string" }
+            },
+            "required": ["action"]
+        }),
+    }
+}
+
+/// `register_log` helper: start or stop the machine's chip log, or
+/// convert a saved SID dump.
+pub fn register_log_result<M: Machine + ?Sized>(params: &JsonValue, machine: &mut M) -> ToolResult {
+    let log = match params.get("action").and_then(JsonValue::as_str) {
+        Some("start") => {
+            if !machine.start_register_log() {
+                return ToolResult::Error {
+                    code: -32602,
+                    message: "This system has no sound chip to log".to_string(),
+                };
+            }
+            return ToolResult::Success(serde_json::json!({ "logging": true }));
+        }
+        Some("stop") => match machine.stop_register_log() {
+            Some(log) => log,
+            None => {
+                return ToolResult::Error {
+                    code: -32602,
+                    message: "No register log is running (start one first)".to_string(),
+                };
+            }
+        },
+        Some("convert") => {
+            let Some(path) = params.get("path").and_then(JsonValue::as_str) else {
+                return ToolResult::Error {
+                    code: -32602,
+                    message: "Missing 'path' to a SID dump".to_string(),
+                };
+            };
+            let parsed = std::fs::read_to_string(path)
+                .map_err(|e| format!("Failed to read {path}: {e}"))
+                .and_then(|text| RegisterLog::parse_sid_dump(&text));
+            match parsed {
+                Ok(log) => log,
+                Err(message) => {
+                    return ToolResult::Error {
+                        code: -32000,
+                        message,
+                    };
+                }
+            }
+        }
+        _ => {
+            return ToolResult::Error {
+                code: -32602,
+                message: "'action' must be start, stop or convert".to_string(),
+            };
+        }
+    };
+    register_log_export(params, &log)
+}
+
+fn register_log_export(params: &JsonValue, log: &RegisterLog) -> ToolResult {
+    use base64::Engine;
+
+    let save_path = params.get("save_path").and_then(JsonValue::as_str);
+    let from_extension = save_path
+        .and_then(|p| Path::new(p).extension())
+        .and_then(|ext| match ext.to_string_lossy().to_ascii_lowercase().as_str() {
+            "vgm" => Some("vgm"),
+            "ym" => Some("ym6"),
+            "sid" => Some("psid"),
+            "txt" => Some("sid_dump"),
+            _ => None,
+        })
+        .filter(|format| log.chip().formats().contains(format));
+    let format = params
+        .get("format")
+        .and_then(JsonValue::as_str)
+        .or(from_extension)
+        .unwrap_or(log.chip().formats()[0]);
+    let text = |key: &str| {
+        params
+            .get(key)
+            .and_then(JsonValue::as_str)
+            .unwrap_or_default()
+            .to_string()
+    };
+    let info = SongInfo {
+        title: text("title"),
+        author: text("author"),
+        released: text("released"),
+    };
+    let bytes = match log.export(format, &info) {
+        Ok(bytes) => bytes,
+        Err(message) => {
+            return ToolResult::Error {
+                code: -32602,
+                message,
+            };
+        }
+    };
+
+    let mut result = serde_json::json!({
+        "chip": log.chip().name(),
+        "format": format,
+        "writes": log.writes().len(),
+        "cycles": log.cycles(),
+        "frames": log.frames(),
+        "size": bytes.len(),
+    });
+    if let Some(path) = save_path {
+        if let Err(e) = std::fs::write(path, &bytes) {
+            return ToolResult::Error {
+                code: -32000,
+                message: format!("Failed to write {path}: {e}"),
+            };
+        }
+        result["path"] = path.into();
+    } else {
+        result["data"] = base64::engine::general_purpose::STANDARD
+            .encode(&bytes)
+            .into();
+    }
+    ToolResult::Success(result)
+}
+
+/// JSON schema for the `filter` argument of `screenshot` and
+/// `record_video`.
+#[must_use]
+pub fn filter_property() -> JsonValue {
+    serde_json::json!({
+        "type": "string",
+        "enum": ["none", "scanlines", "svideo", "composite", "crt"],
+        "description": "CRT filter: scanlines, S-Video or composite signal simulation, or crt (composite with scanlines). Default: none"
+    })
+}
+
+/// Parse the optional `filter` argument. `signal` is the system's
+/// composite signal, if it describes one.
+pub fn parse_filter(
+    params: &JsonValue,
+    signal: Option<Signal>,
+) -> Result<Option<CrtFilter>, ToolResult> {
+    let Some(name) = params.get("filter").and_then(JsonValue::as_str) else {
+        return Ok(None);
+    };
+    CrtFilter::from_name(name, signal).map_err(|message| ToolResult::Error {
+        code: -32602,
+        message,
+    })
+}
+
 /// Screenshot helper: encode framebuffer and either save to disk or return base64.
 ///
 /// If `save_path` is `Some`, writes PNG to that path and returns metadata only.
 /// Otherwise returns base64-encoded PNG data in the response.
 ///
 /// When `display_size` is `Some`, the framebuffer is scaled with
-/// nearest-neighbour interpolation before encoding.
+/// nearest-neighbour interpolation before encoding. A `filter` is applied
+/// first, and without a display size sets the output size itself.
 #[must_use]
 pub fn screenshot_result(
     width: u32,
@@ -456,9 +1039,13 @@ pub fn screenshot_result(
     pixels: &[u32],
     save_path: Option<&str>,
     display_size: Option<(u32, u32)>,
+    filter: Option<CrtFilter>,
 ) -> ToolResult {
     let (enc_w, enc_h, enc_pixels);
-    if let Some((dw, dh)) = display_size
+    if let Some(mut filter) = filter {
+        (enc_w, enc_h) = display_size.unwrap_or_else(|| filter.output_size(width, height));
+        enc_pixels = filter.process(width, height, pixels, enc_w, enc_h);
+    } else if let Some((dw, dh)) = display_size
         && (dw != width || dh != height)
     {
         enc_pixels = scale_nearest(pixels, width, height, dw, dh);
@@ -505,3 +1092,103 @@ pub fn screenshot_result(
         }))
     }
 }
+
+/// Definition of the `disassemble` tool, shared by every system.
+#[must_use]
+pub fn disassemble_definition() -> ToolDefinition {
+    ToolDefinition {
+        name: "disassemble",
+        description: "Disassemble instructions from memory without side effects",
+        input_schema: serde_json::json!({
+            "type": "object",
+            "properties": {
+                "address": { "type": ["integer", "string"], "description": "Start address or label (default: current PC)" },
+                "count": { "type": "integer", "description": "Number of instructions (1-1024)", "default": 16 }
+            }
+        }),
+    }
+}
+
+/// `disassemble` helper: decode `count` instructions from `address`.
+///
+/// `pc` is the start address used when the caller omits `address`.
+/// `decode` is a per-CPU disassembler bound to a side-effect-free read of
+/// the machine's memory. `address` may be a label from `symbols`, and
+/// labelled addresses are named in the output.
+pub fn disassemble_result(
+    params: &JsonValue,
+    pc: Option<u32>,
+    symbols: &SymbolTable,
+    mut decode: impl FnMut(u32) -> crate::Instruction,
+) -> ToolResult {
+    let address = match symbols::address_param(params, "address", symbols, u32::MAX) {
+        Ok(address) => address.or(pc),
+        Err(e) => return e,
+    };
+    let Some(address) = address else {
+        return ToolResult::Error {
+            code: -32602,
+            message: "Missing or invalid 'address'".to_string(),
+        };
+    };
+
+    let count = match params.get("count").and_then(JsonValue::as_u64) {
+        None => 16,
+        Some(c) if (1..=1024).contains(&c) => c as usize,
+        Some(_) => {
+            return ToolResult::Error {
+                code: -32602,
+                message: "Invalid 'count' (1-1024)".to_string(),
+            };
+        }
+    };
+
+    let mut instructions = Vec::with_capacity(count);
+    let mut next = address;
+    for _ in 0..count {
+        let insn = decode(next);
+        next = insn.next_address();
+        instructions.push(instruction_to_json(&insn, symbols));
+    }
+
+    ToolResult::Success(serde_json::json!({
+        "address": address,
+        "count": count,
+        "next_address": next,
+        "instructions": instructions,
+    }))
+}
+
+/// Convert a decoded instruction to JSON. `text` shows addresses that
+/// have a label in `symbols` by name; `operands` stays numeric.
+#[must_use]
+pub fn instruction_to_json(insn: &crate::Instruction, symbols: &SymbolTable) -> JsonValue {
+    let text = if insn.operands.is_empty() {
+        insn.mnemonic.clone()
+    } else {
+        format!("{} {}", insn.mnemonic, symbols.label_operands(&insn.operands))
+    };
+    serde_json::json!({
+        "address": insn.address,
+        "label": symbols.label_at(insn.address),
+        "bytes": insn.bytes,
+        "mnemonic": insn.mnemonic,
+        "operands": insn.operands,
+        "text": text,
+        "target": insn.target,
+        "target_label": insn.target.and_then(|t| symbols.describe(t)),
+        "undocumented": insn.undocumented,
+    })
+}
+
+/// Read an integer observable (such as `cpu.pc`) as a `u32`.
+#[must_use]
+pub fn observable_as_u32(value: &Value) -> Option<u32> {
+    match *value {
+        Value::U8(v) => Some(u32::from(v)),
+        Value::U16(v) => Some(u32::from(v)),
+        Value::U32(v) => Some(v),
+        Value::U64(v) => u32::try_from(v).ok(),
+        _ => None,
+    }
+}
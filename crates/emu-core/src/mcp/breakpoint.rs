@@ -0,0 +1,589 @@
+//! Breakpoint tools shared by the system MCP servers.
+//!
+//! Each server keeps a [`Breakpoints`] set next to its machine and routes
+//! `add_breakpoint`, `remove_breakpoint`, `list_breakpoints` and
+//! `clear_breakpoints` to the helpers here. `run_until_break` needs the
+//! system's own frame detection, so servers run the loop themselves and
+//! format the outcome with [`run_until_break_result`].
+//!
+//! Addresses may be given as labels from the server's [`SymbolTable`],
+//! and breakpoints are reported with the label they fall under.
+
+#![allow(clippy::cast_possible_truncation)]
+
+use serde_json::Value as JsonValue;
+
+use super::symbols::address_param;
+use super::{ToolDefinition, ToolResult};
+use crate::breakpoint::{Access, Breakpoint, Breakpoints, Condition, Hit, Trigger};
+use crate::symbols::SymbolTable;
+use crate::{AccessKind, BusAccess, Observable};
+
+/// Frames `run_until_break` and `set_breakpoint` run before giving up.
+pub const DEFAULT_MAX_FRAMES: u64 = 10_000;
+
+/// Address ranges a system's breakpoints may use.
+#[derive(Debug, Clone, Copy)]
+pub struct BreakpointSpace {
+    /// Highest CPU memory address.
+    pub memory_max: u32,
+    /// Highest I/O port, or `None` if the CPU has no separate I/O space.
+    pub io_max: Option<u32>,
+}
+
+impl BreakpointSpace {
+    /// 16-bit memory, no I/O space (6502 systems).
+    pub const MEMORY_16: Self = Self {
+        memory_max: 0xFFFF,
+        io_max: None,
+    };
+    /// 16-bit memory and 16-bit I/O ports (Z80 systems).
+    pub const Z80: Self = Self {
+        memory_max: 0xFFFF,
+        io_max: Some(0xFFFF),
+    };
+    /// 32-bit memory, no I/O space (68000 systems).
+    pub const MEMORY_32: Self = Self {
+        memory_max: u32::MAX,
+        io_max: None,
+    };
+}
+
+/// Definitions for the shared breakpoint tools.
+#[must_use]
+pub fn breakpoint_definitions() -> Vec<ToolDefinition> {
+    vec![
+        ToolDefinition {
+            name: "add_breakpoint",
+            description: "Add a breakpoint, watchpoint or raster breakpoint (stops run_until_break)",
+            input_schema: serde_json::json!({
+                "type": "object",
+                "properties": {
+                    "type": {
+                        "type": "string",
+                        "enum": ["execute", "read", "write", "access", "io_read", "io_write", "io", "raster", "condition"],
+                        "description": "execute: PC reaches address; read/write/access: memory watchpoint; io_*: I/O port watchpoint; raster: beam position; condition: condition holds at any instruction"
+                    },
+                    "address": { "type": ["integer", "string"], "description": "Address, port or label (execute, watchpoints)" },
+                    "end": { "type": ["integer", "string"], "description": "Inclusive end of a watchpoint range (default: address)" },
+                    "line": { "type": "integer", "description": "Raster line" },
+                    "cycle": { "type": "integer", "description": "Cycle within the line (default: any)" },
+                    "condition": { "type": "string", "description": "Expression over observable paths, e.g. \"cpu.a == 0x20 && vic.line > 100\"" },
+                    "hit_count": { "type": "integer", "description": "Stop on this hit and every later one", "default": 1 },
+                    "temporary": { "type": "boolean", "description": "Remove after the first stop", "default": false }
+                },
+                "required": ["type"]
+            }),
+        },
+        ToolDefinition {
+            name: "remove_breakpoint",
+            description: "Remove a breakpoint by id",
+            input_schema: serde_json::json!({
+                "type": "object",
+                "properties": { "id": { "type": "integer" } },
+                "required": ["id"]
+            }),
+        },
+        ToolDefinition {
+            name: "list_breakpoints",
+            description: "List breakpoints with their hit counts",
+            input_schema: serde_json::json!({ "type": "object", "properties": {} }),
+        },
+        ToolDefinition {
+            name: "clear_breakpoints",
+            description: "Remove all breakpoints",
+            input_schema: serde_json::json!({ "type": "object", "properties": {} }),
+        },
+        ToolDefinition {
+            name: "run_until_break",
+            description: "Run until a breakpoint stops execution (or max frames elapsed)",
+            input_schema: serde_json::json!({
+                "type": "object",
+                "properties": {
+                    "max_frames": { "type": "integer", "default": DEFAULT_MAX_FRAMES }
+                }
+            }),
+        },
+    ]
+}
+
+fn invalid(message: impl Into<String>) -> ToolResult {
+    ToolResult::Error {
+        code: -32602,
+        message: message.into(),
+    }
+}
+
+/// Read `max_frames`, defaulting to [`DEFAULT_MAX_FRAMES`].
+#[must_use]
+pub fn max_frames_param(params: &JsonValue) -> u64 {
+    params
+        .get("max_frames")
+        .and_then(JsonValue::as_u64)
+        .unwrap_or(DEFAULT_MAX_FRAMES)
+}
+
+/// Parse the optional `condition` parameter and check that every path it
+/// uses answers on `machine`.
+///
+/// # Errors
+///
+/// Returns a `-32602` tool error for a malformed expression or an unknown
+/// path.
+pub fn parse_condition(
+    params: &JsonValue,
+    machine: &(impl Observable + ?Sized),
+) -> Result<Option<Condition>, ToolResult> {
+    let source = match params.get("condition") {
+        None | Some(JsonValue::Null) => return Ok(None),
+        Some(JsonValue::String(s)) => s,
+        Some(_) => return Err(invalid("'condition' must be a string")),
+    };
+    let condition =
+        Condition::parse(source).map_err(|e| invalid(format!("Invalid condition: {e}")))?;
+    if let Some(path) = condition
+        .paths()
+        .into_iter()
+        .find(|p| machine.query(p).is_none())
+    {
+        return Err(invalid(format!("Unknown path '{path}' in condition")));
+    }
+    Ok(Some(condition))
+}
+
+fn u32_param(params: &JsonValue, key: &str, max: u32) -> Result<Option<u32>, ToolResult> {
+    match params.get(key) {
+        None | Some(JsonValue::Null) => Ok(None),
+        Some(value) => value
+            .as_u64()
+            .and_then(|v| u32::try_from(v).ok())
+            .filter(|v| *v <= max)
+            .map(Some)
+            .ok_or_else(|| invalid(format!("Invalid '{key}' (0-{max})"))),
+    }
+}
+
+/// Build a breakpoint from `add_breakpoint` parameters. `address` and
+/// `end` may name labels in `symbols`.
+///
+/// # Errors
+///
+/// Returns a `-32602` tool error for an unknown type, missing or
+/// out-of-range addresses, an unknown label, or a bad condition.
+pub fn parse_breakpoint(
+    params: &JsonValue,
+    machine: &(impl Observable + ?Sized),
+    space: BreakpointSpace,
+    symbols: &SymbolTable,
+) -> Result<Breakpoint, ToolResult> {
+    let kind = params
+        .get("type")
+        .and_then(JsonValue::as_str)
+        .ok_or_else(|| invalid("Missing 'type'"))?;
+
+    let range = |max: u32| -> Result<std::ops::RangeInclusive<u32>, ToolResult> {
+        let start = address_param(params, "address", symbols, max)?
+            .ok_or_else(|| invalid(format!("Missing 'address' (0-{max})")))?;
+        let end = address_param(params, "end", symbols, max)?.unwrap_or(start);
+        if end < start {
+            return Err(invalid("'end' is below 'address'"));
+        }
+        Ok(start..=end)
+    };
+    let io_max = || {
+        space
+            .io_max
+            .ok_or_else(|| invalid("This system has no I/O port space"))
+    };
+
+    let trigger = match kind {
+        "execute" => {
+            let address = address_param(params, "address", symbols, space.memory_max)?
+                .ok_or_else(|| invalid(format!("Missing 'address' (0-{})", space.memory_max)))?;
+            Trigger::Execute(address)
+        }
+        "read" | "write" | "access" => Trigger::Memory {
+            range: range(space.memory_max)?,
+            access: access_from_name(kind),
+        },
+        "io_read" | "io_write" | "io" => Trigger::Io {
+            range: range(io_max()?)?,
+            access: access_from_name(kind.trim_start_matches("io").trim_start_matches('_')),
+        },
+        "raster" => Trigger::Raster {
+            line: u32_param(params, "line", u32::MAX)?.ok_or_else(|| invalid("Missing 'line'"))?,
+            cycle: u32_param(params, "cycle", u32::MAX)?,
+        },
+        "condition" => Trigger::Condition,
+        other => return Err(invalid(format!("Unknown breakpoint type: {other}"))),
+    };
+
+    let condition = parse_condition(params, machine)?;
+    if trigger == Trigger::Condition && condition.is_none() {
+        return Err(invalid("Type 'condition' needs a 'condition'"));
+    }
+
+    let hit_count = match params.get("hit_count") {
+        None | Some(JsonValue::Null) => 1,
+        Some(value) => value
+            .as_u64()
+            .and_then(|v| u32::try_from(v).ok())
+            .filter(|v| *v >= 1)
+            .ok_or_else(|| invalid("Invalid 'hit_count' (1 or more)"))?,
+    };
+
+    let mut breakpoint = Breakpoint::new(trigger).with_hit_count(hit_count);
+    breakpoint.condition = condition;
+    breakpoint.temporary = params
+        .get("temporary")
+        .and_then(JsonValue::as_bool)
+        .unwrap_or(false);
+    Ok(breakpoint)
+}
+
+/// Access filter for a watchpoint type name (`read`, `write`, anything
+/// else means both).
+fn access_from_name(name: &str) -> Access {
+    match name {
+        "read" => Access::Read,
+        "write" => Access::Write,
+        _ => Access::ReadWrite,
+    }
+}
+
+fn type_name(trigger: &Trigger) -> &'static str {
+    match trigger {
+        Trigger::Execute(_) => "execute",
+        Trigger::Memory { access, .. } => match access {
+            Access::Read => "read",
+            Access::Write => "write",
+            Access::ReadWrite => "access",
+        },
+        Trigger::Io { access, .. } => match access {
+            Access::Read => "io_read",
+            Access::Write => "io_write",
+            Access::ReadWrite => "io",
+        },
+        Trigger::Raster { .. } => "raster",
+        Trigger::Condition => "condition",
+    }
+}
+
+/// JSON fields describing a trigger (`type` plus its location, and the
+/// label of a memory address).
+fn trigger_to_json(trigger: &Trigger, symbols: &SymbolTable) -> serde_json::Map<String, JsonValue> {
+    let mut map = serde_json::Map::new();
+    map.insert("type".into(), type_name(trigger).into());
+    match trigger {
+        Trigger::Execute(address) => {
+            map.insert("address".into(), (*address).into());
+            map.insert("label".into(), symbols.describe(*address).into());
+        }
+        Trigger::Memory { range, .. } => {
+            map.insert("address".into(), (*range.start()).into());
+            map.insert("end".into(), (*range.end()).into());
+            map.insert("label".into(), symbols.describe(*range.start()).into());
+        }
+        Trigger::Io { range, .. } => {
+            map.insert("address".into(), (*range.start()).into());
+            map.insert("end".into(), (*range.end()).into());
+        }
+        Trigger::Raster { line, cycle } => {
+            map.insert("line".into(), (*line).into());
+            map.insert("cycle".into(), (*cycle).into());
+        }
+        Trigger::Condition => {}
+    }
+    map
+}
+
+/// Convert a breakpoint to JSON.
+#[must_use]
+pub fn breakpoint_to_json(id: u32, breakpoint: &Breakpoint, symbols: &SymbolTable) -> JsonValue {
+    let mut map = trigger_to_json(&breakpoint.trigger, symbols);
+    map.insert("id".into(), id.into());
+    map.insert(
+        "condition".into(),
+        breakpoint
+            .condition
+            .as_ref()
+            .map(|c| c.source().to_string())
+            .into(),
+    );
+    map.insert("hit_count".into(), breakpoint.hit_count.into());
+    map.insert("hits".into(), breakpoint.hits.into());
+    map.insert("temporary".into(), breakpoint.temporary.into());
+    map.insert("enabled".into(), breakpoint.enabled.into());
+    JsonValue::Object(map)
+}
+
+fn access_to_json(access: &BusAccess) -> JsonValue {
+    let kind = match access.kind {
+        AccessKind::Read => "read",
+        AccessKind::Write => "write",
+        AccessKind::IoRead => "io_read",
+        AccessKind::IoWrite => "io_write",
+    };
+    serde_json::json!({
+        "kind": kind,
+        "address": access.address,
+        "value": access.value,
+        "wait": access.wait,
+    })
+}
+
+/// Convert a breakpoint hit to JSON.
+#[must_use]
+pub fn hit_to_json(hit: &Hit, symbols: &SymbolTable) -> JsonValue {
+    let mut map = trigger_to_json(&hit.trigger, symbols);
+    map.insert("id".into(), hit.id.into());
+    map.insert("hits".into(), hit.hits.into());
+    map.insert("temporary".into(), hit.temporary.into());
+    map.insert(
+        "access".into(),
+        hit.access.as_ref().map_or(JsonValue::Null, access_to_json),
+    );
+    JsonValue::Object(map)
+}
+
+/// `add_breakpoint` handler.
+pub fn add_breakpoint_result(
+    params: &JsonValue,
+    breakpoints: &mut Breakpoints,
+    machine: &(impl Observable + ?Sized),
+    space: BreakpointSpace,
+    symbols: &SymbolTable,
+) -> ToolResult {
+    match parse_breakpoint(params, machine, space, symbols) {
+        Ok(breakpoint) => {
+            let id = breakpoints.add(breakpoint.clone());
+            ToolResult::Success(breakpoint_to_json(id, &breakpoint, symbols))
+        }
+        Err(e) => e,
+    }
+}
+
+/// `remove_breakpoint` handler.
+pub fn remove_breakpoint_result(params: &JsonValue, breakpoints: &mut Breakpoints) -> ToolResult {
+    let Some(id) = params
+        .get("id")
+        .and_then(JsonValue::as_u64)
+        .and_then(|id| u32::try_from(id).ok())
+    else {
+        return invalid("Missing or invalid 'id'");
+    };
+    if breakpoints.remove(id) {
+        ToolResult::Success(serde_json::json!({ "removed": id }))
+    } else {
+        ToolResult::Error {
+            code: -32000,
+            message: format!("No breakpoint with id {id}"),
+        }
+    }
+}
+
+/// `list_breakpoints` handler.
+#[must_use]
+pub fn list_breakpoints_result(breakpoints: &Breakpoints, symbols: &SymbolTable) -> ToolResult {
+    let list: Vec<JsonValue> = breakpoints
+        .iter()
+        .map(|(id, bp)| breakpoint_to_json(id, bp, symbols))
+        .collect();
+    ToolResult::Success(serde_json::json!({ "breakpoints": list }))
+}
+
+/// `clear_breakpoints` handler.
+pub fn clear_breakpoints_result(breakpoints: &mut Breakpoints) -> ToolResult {
+    let cleared = breakpoints.len();
+    breakpoints.clear();
+    ToolResult::Success(serde_json::json!({ "cleared": cleared }))
+}
+
+/// `run_until_break` result. `pc` is already formatted for the system.
+#[must_use]
+pub fn run_until_break_result(
+    hit: Option<&Hit>,
+    pc: &str,
+    frames_run: u64,
+    symbols: &SymbolTable,
+) -> ToolResult {
+    ToolResult::Success(serde_json::json!({
+        "hit": hit.is_some(),
+        "breakpoint": hit.map(|h| hit_to_json(h, symbols)),
+        "pc": pc,
+        "frames_run": frames_run,
+    }))
+}
+
+#[cfg(test)]
+mod tests {
+    use serde_json::{Value as JsonValue, json};
+
+    use super::{
+        BreakpointSpace, add_breakpoint_result, list_breakpoints_result, parse_breakpoint,
+        remove_breakpoint_result,
+    };
+    use crate::breakpoint::{Access, Breakpoints, Trigger};
+    use crate::mcp::ToolResult;
+    use crate::symbols::SymbolTable;
+    use crate::{Observable, Value};
+
+    struct Cpu;
+
+    impl Observable for Cpu {
+        fn query(&self, path: &str) -> Option<Value> {
+            (path == "cpu.a").then_some(Value::U8(0))
+        }
+
+        fn query_paths(&self) -> &'static [&'static str] {
+            &["cpu.a"]
+        }
+    }
+
+    fn error_message(result: Result<impl Sized, ToolResult>) -> String {
+        match result {
+            Err(ToolResult::Error {
+                code: -32602,
+                message,
+            }) => message,
+            _ => panic!("expected a parameter error"),
+        }
+    }
+
+    #[test]
+    fn parses_watchpoints_and_raster_breakpoints() {
+        let bp = parse_breakpoint(
+            &json!({"type": "io_write", "address": 0xFE, "end": 0xFF, "hit_count": 3}),
+            &Cpu,
+            BreakpointSpace::Z80,
+            &SymbolTable::new(),
+        )
+        .ok()
+        .expect("parses");
+        assert_eq!(
+            bp.trigger,
+            Trigger::Io {
+                range: 0xFE..=0xFF,
+                access: Access::Write
+            }
+        );
+        assert_eq!(bp.hit_count, 3);
+
+        let bp = parse_breakpoint(
+            &json!({"type": "raster", "line": 100, "condition": "cpu.a == 0", "temporary": true}),
+            &Cpu,
+            BreakpointSpace::MEMORY_16,
+            &SymbolTable::new(),
+        )
+        .ok()
+        .expect("parses");
+        assert_eq!(
+            bp.trigger,
+            Trigger::Raster {
+                line: 100,
+                cycle: None
+            }
+        );
+        assert!(bp.temporary);
+        assert!(bp.condition.is_some());
+    }
+
+    #[test]
+    fn rejects_bad_parameters() {
+        let space = BreakpointSpace::MEMORY_16;
+        let symbols = SymbolTable::new();
+        let parse = |params: JsonValue| parse_breakpoint(&params, &Cpu, space, &symbols);
+        assert!(
+            error_message(parse(json!({"type": "execute", "address": 0x10000})))
+                .contains("0-65535")
+        );
+        assert!(error_message(parse(json!({"type": "io", "address": 1}))).contains("no I/O"));
+        assert!(
+            error_message(parse(json!({"type": "write", "address": 5, "end": 4})))
+                .contains("'end'")
+        );
+        assert!(error_message(parse(json!({"type": "condition"}))).contains("needs a 'condition'"));
+        assert!(
+            error_message(parse(
+                json!({"type": "condition", "condition": "cpu.b == 1"})
+            ))
+            .contains("'cpu.b'")
+        );
+        assert!(
+            error_message(parse(
+                json!({"type": "execute", "address": 0, "condition": "cpu.a =="})
+            ))
+            .contains("Invalid condition")
+        );
+        assert!(error_message(parse(json!({"type": "step"}))).contains("Unknown breakpoint type"));
+        assert!(
+            error_message(parse(json!({"type": "execute", "address": "main"})))
+                .contains("Unknown label 'main'")
+        );
+    }
+
+    #[test]
+    fn add_list_and_remove_round_trip() {
+        let mut breakpoints = Breakpoints::new();
+        let ToolResult::Success(added) = add_breakpoint_result(
+            &json!({"type": "access", "address": 0xD020}),
+            &mut breakpoints,
+            &Cpu,
+            BreakpointSpace::MEMORY_16,
+            &SymbolTable::new(),
+        ) else {
+            panic!("add failed");
+        };
+        assert_eq!(added["id"], 1);
+        assert_eq!(added["type"], "access");
+        assert_eq!(added["end"], 0xD020);
+
+        let ToolResult::Success(list) = list_breakpoints_result(&breakpoints, &SymbolTable::new())
+        else {
+            panic!("list failed");
+        };
+        assert_eq!(list["breakpoints"][0]["hits"], 0);
+
+        assert!(matches!(
+            remove_breakpoint_result(&json!({"id": 1}), &mut breakpoints),
+            ToolResult::Success(_)
+        ));
+        assert!(matches!(
+            remove_breakpoint_result(&json!({"id": 1}), &mut breakpoints),
+            ToolResult::Error { code: -32000, .. }
+        ));
+    }
+
+    #[test]
+    fn labels_name_addresses_in_and_out() {
+        let mut symbols = SymbolTable::new();
+        symbols.insert("score", 0x0340);
+        symbols.insert("update", 0xC100);
+        let mut breakpoints = Breakpoints::new();
+
+        let ToolResult::Success(added) = add_breakpoint_result(
+            &json!({"type": "write", "address": "score", "end": "score+1"}),
+            &mut breakpoints,
+            &Cpu,
+            BreakpointSpace::MEMORY_16,
+            &symbols,
+        ) else {
+            panic!("add failed");
+        };
+        assert_eq!(added["address"], 0x0340);
+        assert_eq!(added["end"], 0x0341);
+        assert_eq!(added["label"], "score");
+
+        let ToolResult::Success(added) = add_breakpoint_result(
+            &json!({"type": "execute", "address": "update+$0C"}),
+            &mut breakpoints,
+            &Cpu,
+            BreakpointSpace::MEMORY_16,
+            &symbols,
+        ) else {
+            panic!("add failed");
+        };
+        assert_eq!(added["address"], 0xC10C);
+        assert_eq!(added["label"], "update+$C");
+    }
+}
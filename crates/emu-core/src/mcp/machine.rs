@@ -0,0 +1,1030 @@
+//! Generic MCP tools for any `Machine + Observable`.
+//!
+//! [`MachineMcp`] gives a system the tools every emulator shares —
+//! running frames (or continuously, with `run` and `pause`), screenshots,
+//! audio and video capture, observable queries, memory access, disassembly
+//! with labels from `load_symbols`, and reset — without writing any
+//! handlers.
+//! Systems with nothing else to offer serve it directly:
+//!
+//! ```ignore
+//! let mut server = McpServer::new(MachineMcp::new("emu-msx", msx));
+//! server.run();
+//! ```
+//!
+//! Systems with media or input tools wrap it in their own `McpEmulator`,
+//! append their definitions to [`MachineMcp::tool_definitions`] and fall
+//! back to [`MachineMcp::dispatch`] for anything they do not handle (and
+//! forward `run_slice` and `take_notifications` to it).
+
+#![allow(clippy::cast_possible_truncation)]
+
+use std::time::Duration;
+
+use serde_json::Value as JsonValue;
+
+use super::{McpEmulator, Notification, RunSession, ToolDefinition, ToolResult};
+use crate::crt::Signal;
+use crate::symbols::SymbolTable;
+use crate::{Instruction, Machine, Observable};
+
+/// What the generic tools need from a machine beyond `Machine` and
+/// `Observable`.
+pub trait McpMachine: Machine + Observable {
+    /// Display refresh rate in frames per second, used to time recorded
+    /// video.
+    fn frame_rate(&self) -> u32;
+
+    /// Decode the instruction at `address`.
+    ///
+    /// Must read through a side-effect-free view of the CPU's address
+    /// space (RAM, ROM and cartridge), never the live bus.
+    fn disassemble(&self, address: u32) -> Instruction;
+
+    /// Read a byte of CPU-visible RAM without side effects.
+    ///
+    /// Returns `None` for addresses that are not RAM (ROM, I/O, open
+    /// bus); `query_memory` reports those as `null`.
+    fn peek(&self, address: u16) -> Option<u8> {
+        let _ = address;
+        None
+    }
+
+    /// Write a byte of CPU-visible RAM. Returns false if `address` is not
+    /// RAM.
+    fn poke(&mut self, address: u16, value: u8) -> bool {
+        let _ = (address, value);
+        false
+    }
+
+    /// The video chip's composite signal, which enables the `svideo`,
+    /// `composite` and `crt` filters.
+    fn video_signal(&self) -> Option<Signal> {
+        None
+    }
+
+    /// The display area inside the border, as x, y, width and height in
+    /// the framebuffer, for `record_gif` to crop to. `None` when the
+    /// framebuffer has no border.
+    fn active_area(&self) -> Option<(u32, u32, u32, u32)> {
+        None
+    }
+}
+
+/// MCP tool provider wrapping a single machine.
+pub struct MachineMcp<M> {
+    name: &'static str,
+    machine: M,
+    run: RunSession,
+    symbols: SymbolTable,
+}
+
+impl<M: McpMachine> MachineMcp<M> {
+    /// Serve `machine` under the given server name (the binary name, by
+    /// convention).
+    #[must_use]
+    pub fn new(name: &'static str, machine: M) -> Self {
+        Self {
+            name,
+            machine,
+            run: RunSession::new(),
+            symbols: SymbolTable::new(),
+        }
+    }
+
+    /// The wrapped machine.
+    #[must_use]
+    pub fn machine(&self) -> &M {
+        &self.machine
+    }
+
+    /// The wrapped machine, for system-specific tools.
+    pub fn machine_mut(&mut self) -> &mut M {
+        &mut self.machine
+    }
+
+    /// Labels loaded with `load_symbols`.
+    #[must_use]
+    pub fn symbols(&self) -> &SymbolTable {
+        &self.symbols
+    }
+
+    /// Labels, for systems that load them from their own media.
+    pub fn symbols_mut(&mut self) -> &mut SymbolTable {
+        &mut self.symbols
+    }
+
+    /// Swap in a new machine (after loading different media, say).
+    pub fn replace(&mut self, machine: M) -> M {
+        std::mem::replace(&mut self.machine, machine)
+    }
+
+    /// Definitions of the generic tools.
+    #[must_use]
+    pub fn tool_definitions() -> Vec<ToolDefinition> {
+        let mut tools = vec![
+            ToolDefinition {
+                name: "reset",
+                description: "Reset the machine",
+                input_schema: serde_json::json!({ "type": "object", "properties": {} }),
+            },
+            ToolDefinition {
+                name: "run_frames",
+                description: "Run the emulator for N frames",
+                input_schema: serde_json::json!({
+                    "type": "object",
+                    "properties": {
+                        "count": { "type": "integer", "description": "Number of frames to run", "default": 1 }
+                    }
+                }),
+            },
+            ToolDefinition {
+                name: "screenshot",
+                description: "Capture the current screen as PNG",
+                input_schema: serde_json::json!({
+                    "type": "object",
+                    "properties": {
+                        "save_path": { "type": "string", "description": "If set, save PNG to this path and return metadata only" },
+                        "correct_aspect": { "type": "boolean", "description": "Scale to 4:3 display aspect ratio (default: true)" },
+                        "filter": super::filter_property()
+                    }
+                }),
+            },
+            ToolDefinition {
+                name: "audio_capture",
+                description: "Run N frames and capture stereo audio as WAV",
+                input_schema: serde_json::json!({
+                    "type": "object",
+                    "properties": {
+                        "frames": { "type": "integer", "default": 50 },
+                        "save_path": { "type": "string", "description": "If set, save WAV to this path and return metadata only" },
+                        "stems": super::stems_property()
+                    }
+                }),
+            },
+            super::channel_mute_definition(),
+            super::register_log_definition(),
+            ToolDefinition {
+                name: "query",
+                description: "Query an observable value (see query_paths)",
+                input_schema: serde_json::json!({
+                    "type": "object",
+                    "properties": {
+                        "path": { "type": "string", "description": "Observable path (e.g. cpu.pc)" }
+                    },
+                    "required": ["path"]
+                }),
+            },
+            ToolDefinition {
+                name: "query_paths",
+                description: "List the observable paths, optionally filtered by prefix",
+                input_schema: serde_json::json!({
+                    "type": "object",
+                    "properties": {
+                        "prefix": { "type": "string", "description": "Only return paths starting with this prefix" }
+                    }
+                }),
+            },
+            ToolDefinition {
+                name: "query_memory",
+                description: "Read a range of RAM (non-RAM addresses read as null)",
+                input_schema: serde_json::json!({
+                    "type": "object",
+                    "properties": {
+                        "address": { "type": "integer", "description": "Start address (0-65535)" },
+                        "length": { "type": "integer", "description": "Number of bytes (1-65536)" }
+                    },
+                    "required": ["address", "length"]
+                }),
+            },
+            ToolDefinition {
+                name: "poke",
+                description: "Write a byte to RAM",
+                input_schema: serde_json::json!({
+                    "type": "object",
+                    "properties": {
+                        "address": { "type": "integer", "description": "Address (0-65535)" },
+                        "value": { "type": "integer", "description": "Byte value (0-255)" }
+                    },
+                    "required": ["address", "value"]
+                }),
+            },
+            super::disassemble_definition(),
+            super::record_gif_definition(),
+        ];
+        if cfg!(feature = "video") {
+            tools.push(ToolDefinition {
+                name: "record_video",
+                description: "Run N frames and record them as MP4 (H.264 + AAC)",
+                input_schema: serde_json::json!({
+                    "type": "object",
+                    "properties": {
+                        "frames": { "type": "integer", "description": "Number of frames to record" },
+                        "save_path": { "type": "string", "description": "Output MP4 path" },
+                        "correct_aspect": { "type": "boolean", "description": "Scale to 4:3 display aspect ratio (default: true)" },
+                        "filter": super::filter_property()
+                    },
+                    "required": ["frames", "save_path"]
+                }),
+            });
+        }
+        tools.extend(super::run::run_definitions());
+        tools.extend(super::symbols::symbol_definitions());
+        tools
+    }
+
+    /// Run a generic tool. Returns `None` if `name` is not one of them.
+    pub fn dispatch(&mut self, name: &str, params: &JsonValue) -> Option<ToolResult> {
+        Some(match name {
+            "reset" => self.handle_reset(),
+            "run_frames" => self.handle_run_frames(params),
+            "screenshot" => self.handle_screenshot(params),
+            "audio_capture" => self.handle_audio_capture(params),
+            "channel_mute" => super::channel_mute_result(params, &mut self.machine),
+            "register_log" => super::register_log_result(params, &mut self.machine),
+            "query" => self.handle_query(params),
+            "query_paths" => self.handle_query_paths(params),
+            "query_memory" => self.handle_query_memory(params),
+            "poke" => self.handle_poke(params),
+            "disassemble" => self.handle_disassemble(params),
+            "record_gif" => self.handle_record_gif(params),
+            "run" => self.run.start(params, self.machine.frame_rate()),
+            "pause" => self.run.pause(serde_json::json!({
+                "frame_count": self.machine.frame_count(),
+            })),
+            "load_symbols" => super::symbols::load_symbols_result(params, &mut self.symbols),
+            "clear_symbols" => super::symbols::clear_symbols_result(&mut self.symbols),
+            "lookup_symbol" => super::symbols::lookup_symbol_result(params, &self.symbols),
+            #[cfg(feature = "video")]
+            "record_video" => self.handle_record_video(params),
+            _ => return None,
+        })
+    }
+}
+
+impl<M: McpMachine> McpEmulator for MachineMcp<M> {
+    fn dispatch_tool(&mut self, name: &str, arguments: &JsonValue) -> ToolResult {
+        self.dispatch(name, arguments)
+            .unwrap_or_else(|| ToolResult::Error {
+                code: -32601,
+                message: format!("Unknown tool: {name}"),
+            })
+    }
+
+    fn tool_definitions(&self) -> Vec<ToolDefinition> {
+        Self::tool_definitions()
+    }
+
+    fn server_name(&self) -> &'static str {
+        self.name
+    }
+
+    fn server_version(&self) -> &'static str {
+        env!("CARGO_PKG_VERSION")
+    }
+
+    fn run_slice(&mut self) -> Option<Duration> {
+        if !self.run.is_running() {
+            return None;
+        }
+        self.machine.run_frame();
+        self.run.frame_done(self.machine.frame_count());
+        self.run.next_slice()
+    }
+
+    fn take_notifications(&mut self) -> Vec<Notification> {
+        self.run.take_notifications()
+    }
+}
+
+// ---------------------------------------------------------------------------
+// Tool handlers
+// ---------------------------------------------------------------------------
+
+impl<M: McpMachine> MachineMcp<M> {
+    fn handle_reset(&mut self) -> ToolResult {
+        self.machine.reset();
+        ToolResult::Success(serde_json::json!({"status": "ok"}))
+    }
+
+    fn handle_run_frames(&mut self, params: &JsonValue) -> ToolResult {
+        let count = params
+            .get("count")
+            .and_then(JsonValue::as_u64)
+            .or_else(|| params.get("frames").and_then(JsonValue::as_u64))
+            .unwrap_or(1);
+
+        for _ in 0..count {
+            self.machine.run_frame();
+        }
+
+        ToolResult::Success(serde_json::json!({
+            "frames": count,
+            "frame_count": self.machine.frame_count(),
+        }))
+    }
+
+    fn handle_screenshot(&mut self, params: &JsonValue) -> ToolResult {
+        let m = &self.machine;
+        let save_path = params.get("save_path").and_then(JsonValue::as_str);
+        let display = parse_display_size(params, m.framebuffer_width(), m.framebuffer_height());
+        let filter = match super::parse_filter(params, m.video_signal()) {
+            Ok(filter) => filter.map(|f| f.at_frame(m.frame_count())),
+            Err(e) => return e,
+        };
+        super::screenshot_result(
+            m.framebuffer_width(),
+            m.framebuffer_height(),
+            m.framebuffer(),
+            save_path,
+            display,
+            filter,
+        )
+    }
+
+    fn handle_audio_capture(&mut self, params: &JsonValue) -> ToolResult {
+        let frames = params
+            .get("frames")
+            .and_then(JsonValue::as_u64)
+            .unwrap_or(50);
+
+        let mut stems = match super::StemCapture::start(params, &mut self.machine) {
+            Ok(s) => s,
+            Err(e) => return e,
+        };
+
+        let mut audio = Vec::new();
+        for _ in 0..frames {
+            self.machine.run_frame();
+            audio.extend(self.machine.take_audio_buffer());
+            if let Some(stems) = &mut stems {
+                stems.collect(&mut self.machine);
+            }
+        }
+
+        let samples: Vec<f32> = audio.iter().flatten().copied().collect();
+        let wav = match super::encode_wav(&samples, 2, self.machine.audio_sample_rate()) {
+            Ok(w) => w,
+            Err(message) => {
+                return ToolResult::Error {
+                    code: -32000,
+                    message,
+                };
+            }
+        };
+
+        let save_path = params.get("save_path").and_then(JsonValue::as_str);
+        let mut result = if let Some(path) = save_path {
+            if let Err(e) = std::fs::write(path, &wav) {
+                return ToolResult::Error {
+                    code: -32000,
+                    message: format!("Failed to save audio: {e}"),
+                };
+            }
+            serde_json::json!({
+                "format": "wav",
+                "samples": audio.len(),
+                "frames": frames,
+                "path": path,
+                "size": wav.len(),
+            })
+        } else {
+            use base64::Engine;
+            serde_json::json!({
+                "format": "wav",
+                "samples": audio.len(),
+                "frames": frames,
+                "data": base64::engine::general_purpose::STANDARD.encode(&wav),
+            })
+        };
+        if let Some(stems) = stems {
+            match stems.finish(&mut self.machine, save_path) {
+                Ok(stems) => result["stems"] = stems,
+                Err(e) => return e,
+            }
+        }
+        ToolResult::Success(result)
+    }
+
+    fn handle_query(&mut self, params: &JsonValue) -> ToolResult {
+        let Some(path) = params.get("path").and_then(JsonValue::as_str) else {
+            return ToolResult::Error {
+                code: -32602,
+                message: "Missing 'path' parameter".to_string(),
+            };
+        };
+
+        match self.machine.query(path) {
+            Some(value) => {
+                let json_val = super::observable_to_json(&value);
+                ToolResult::Success(serde_json::json!({"path": path, "value": json_val}))
+            }
+            None => ToolResult::Error {
+                code: -32000,
+                message: format!("Unknown query path: {path}"),
+            },
+        }
+    }
+
+    fn handle_query_paths(&mut self, params: &JsonValue) -> ToolResult {
+        let prefix = params.get("prefix").and_then(JsonValue::as_str);
+        let paths: Vec<&str> = self
+            .machine
+            .query_paths()
+            .iter()
+            .copied()
+            .filter(|path| prefix.is_none_or(|prefix| path.starts_with(prefix)))
+            .collect();
+
+        ToolResult::Success(serde_json::json!({
+            "prefix": prefix,
+            "paths": paths,
+        }))
+    }
+
+    fn handle_query_memory(&mut self, params: &JsonValue) -> ToolResult {
+        let address = match params.get("address").and_then(JsonValue::as_u64) {
+            Some(a) if a <= 0xFFFF => a as u16,
+            _ => {
+                return ToolResult::Error {
+                    code: -32602,
+                    message: "Missing or invalid 'address' (0-65535)".to_string(),
+                };
+            }
+        };
+
+        let length = match params.get("length").and_then(JsonValue::as_u64) {
+            Some(l) if (1..=65536).contains(&l) => l as usize,
+            Some(_) => {
+                return ToolResult::Error {
+                    code: -32602,
+                    message: "Invalid 'length' (1-65536)".to_string(),
+                };
+            }
+            None => {
+                return ToolResult::Error {
+                    code: -32602,
+                    message: "Missing 'length' parameter".to_string(),
+                };
+            }
+        };
+
+        let bytes: Vec<Option<u8>> = (0..length)
+            .map(|i| self.machine.peek(address.wrapping_add(i as u16)))
+            .collect();
+
+        ToolResult::Success(serde_json::json!({
+            "address": address,
+            "length": length,
+            "data": bytes,
+        }))
+    }
+
+    fn handle_poke(&mut self, params: &JsonValue) -> ToolResult {
+        let address = match params.get("address").and_then(JsonValue::as_u64) {
+            Some(a) if a <= 0xFFFF => a as u16,
+            _ => {
+                return ToolResult::Error {
+                    code: -32602,
+                    message: "Missing or invalid 'address' (0-65535)".to_string(),
+                };
+            }
+        };
+
+        let value = match params.get("value").and_then(JsonValue::as_u64) {
+            Some(v) if v <= 0xFF => v as u8,
+            _ => {
+                return ToolResult::Error {
+                    code: -32602,
+                    message: "Missing or invalid 'value' (0-255)".to_string(),
+                };
+            }
+        };
+
+        if !self.machine.poke(address, value) {
+            return ToolResult::Error {
+                code: -32000,
+                message: format!("${address:04X} is not RAM"),
+            };
+        }
+        ToolResult::Success(serde_json::json!({"address": address, "value": value}))
+    }
+
+    fn handle_disassemble(&mut self, params: &JsonValue) -> ToolResult {
+        let m = &self.machine;
+        let pc = m
+            .query("cpu.pc")
+            .as_ref()
+            .and_then(super::observable_as_u32);
+        super::disassemble_result(params, pc, &self.symbols, |address| {
+            m.disassemble(address)
+        })
+    }
+
+    fn handle_record_gif(&mut self, params: &JsonValue) -> ToolResult {
+        let m = &mut self.machine;
+        let (width, height) = (m.framebuffer_width(), m.framebuffer_height());
+        let (fps, active) = (m.frame_rate(), m.active_area());
+        super::record_gif_result(params, width, height, fps, active, |rec| {
+            m.run_frame();
+            rec.add_frame(m.framebuffer());
+        })
+    }
+
+    #[cfg(feature = "video")]
+    fn handle_record_video(&mut self, params: &JsonValue) -> ToolResult {
+        let frames = match params.get("frames").and_then(JsonValue::as_u64) {
+            Some(f) if f > 0 => f,
+            _ => {
+                return ToolResult::Error {
+                    code: -32602,
+                    message: "Missing or invalid 'frames' (positive integer)".to_string(),
+                };
+            }
+        };
+
+        let Some(save_path) = params.get("save_path").and_then(JsonValue::as_str) else {
+            return ToolResult::Error {
+                code: -32602,
+                message: "Missing 'save_path' parameter".to_string(),
+            };
+        };
+
+        let m = &mut self.machine;
+        let filter = match super::parse_filter(params, m.video_signal()) {
+            Ok(filter) => filter.map(|f| f.at_frame(m.frame_count())),
+            Err(e) => return e,
+        };
+        let display = parse_display_size(params, m.framebuffer_width(), m.framebuffer_height());
+        let display = display.or_else(|| {
+            filter
+                .as_ref()
+                .map(|f| f.output_size(m.framebuffer_width(), m.framebuffer_height()))
+        });
+        let mut rec = match crate::video::VideoRecorder::new(
+            m.framebuffer_width(),
+            m.framebuffer_height(),
+            m.frame_rate(),
+            2, // stereo
+            m.audio_sample_rate(),
+            std::path::Path::new(save_path),
+            display,
+        ) {
+            Ok(r) => r,
+            Err(e) => {
+                return ToolResult::Error {
+                    code: -32000,
+                    message: format!("Video recorder init: {e}"),
+                };
+            }
+        };
+
+        if let Some(filter) = filter {
+            rec.set_filter(filter);
+        }
+
+        for _ in 0..frames {
+            m.run_frame();
+            let audio: Vec<f32> = m.take_audio_buffer().into_iter().flatten().collect();
+            if let Err(e) = rec.add_frame(m.framebuffer(), &audio) {
+                return ToolResult::Error {
+                    code: -32000,
+                    message: format!("Video recording failed: {e}"),
+                };
+            }
+        }
+
+        match rec.finish() {
+            Ok(info) => super::video_result(save_path, info.frames, info.fps),
+            Err(e) => ToolResult::Error {
+                code: -32000,
+                message: format!("Video finish failed: {e}"),
+            },
+        }
+    }
+}
+
+// ---------------------------------------------------------------------------
+// Helpers
+// ---------------------------------------------------------------------------
+
+/// Read `correct_aspect` (default true) and compute display size.
+fn parse_display_size(params: &JsonValue, w: u32, h: u32) -> Option<(u32, u32)> {
+    let correct = params
+        .get("correct_aspect")
+        .and_then(JsonValue::as_bool)
+        .unwrap_or(true);
+    correct.then(|| super::display_size_4_3(w, h))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{AudioFrame, LoggedChip, RegisterLog, Value};
+
+    /// A machine with 256 bytes of RAM at $0000 and one audio frame per
+    /// video frame, mixed from two tapped channels.
+    struct Counter {
+        frames: u64,
+        ram: [u8; 256],
+        pixels: [u32; 4],
+        mute: u32,
+        /// Stem samples waiting to be taken, while capture is on.
+        stems: Option<usize>,
+        /// Register log: one write per frame, 100 cycles apart.
+        log: Option<RegisterLog>,
+    }
+
+    impl Counter {
+        fn new() -> Self {
+            Self {
+                frames: 0,
+                ram: [0; 256],
+                pixels: [0; 4],
+                mute: 0,
+                stems: None,
+                log: None,
+            }
+        }
+    }
+
+    impl Machine for Counter {
+        fn run_frame(&mut self) {
+            self.frames += 1;
+            if let Some(pending) = &mut self.stems {
+                *pending += 1;
+            }
+            if let Some(log) = &mut self.log {
+                log.record(0, 0x90);
+                for _ in 0..100 {
+                    log.tick();
+                }
+            }
+        }
+
+        fn framebuffer(&self) -> &[u32] {
+            &self.pixels
+        }
+
+        fn framebuffer_width(&self) -> u32 {
+            2
+        }
+
+        fn framebuffer_height(&self) -> u32 {
+            2
+        }
+
+        fn take_audio_buffer(&mut self) -> Vec<AudioFrame> {
+            vec![[0.5, -0.5]]
+        }
+
+        fn audio_channels(&self) -> Vec<&'static str> {
+            vec!["up", "down"]
+        }
+
+        fn set_channel_mute(&mut self, mask: u32) {
+            self.mute = mask;
+        }
+
+        fn channel_mute(&self) -> u32 {
+            self.mute
+        }
+
+        fn set_stem_capture(&mut self, enabled: bool) {
+            self.stems = enabled.then_some(0);
+        }
+
+        fn take_audio_stems(&mut self) -> Vec<Vec<f32>> {
+            self.stems.as_mut().map_or_else(Vec::new, |pending| {
+                let n = std::mem::take(pending);
+                vec![vec![0.25; n], vec![-0.25; n]]
+            })
+        }
+
+        fn start_register_log(&mut self) -> bool {
+            self.log = Some(RegisterLog::new(LoggedChip::Sn76489, 3_579_545));
+            true
+        }
+
+        fn stop_register_log(&mut self) -> Option<RegisterLog> {
+            self.log.take().map(|log| log.with_frame_cycles(100))
+        }
+
+        fn frame_count(&self) -> u64 {
+            self.frames
+        }
+
+        fn reset(&mut self) {
+            self.ram = [0; 256];
+        }
+    }
+
+    impl Observable for Counter {
+        fn query(&self, path: &str) -> Option<Value> {
+            match path {
+                "frame_count" => Some(self.frames.into()),
+                "cpu.pc" => Some(0x10u16.into()),
+                "ram.0" => Some(self.ram[0].into()),
+                _ => None,
+            }
+        }
+
+        fn query_paths(&self) -> &'static [&'static str] {
+            &["frame_count", "cpu.pc", "ram.0"]
+        }
+    }
+
+    impl McpMachine for Counter {
+        fn frame_rate(&self) -> u32 {
+            50
+        }
+
+        /// Every byte is a one-byte instruction; $4C is a two-byte jump.
+        fn disassemble(&self, address: u32) -> Instruction {
+            let at = |a: u32| self.ram[(a & 0xFF) as usize];
+            let opcode = at(address);
+            let (bytes, target) = if opcode == 0x4C {
+                let dest = u32::from(at(address + 1));
+                (vec![opcode, dest as u8], Some(dest))
+            } else {
+                (vec![opcode], None)
+            };
+            Instruction {
+                address,
+                mnemonic: if target.is_some() { "JMP" } else { "DB" }.to_string(),
+                operands: match target {
+                    Some(dest) => format!("${dest:02X}"),
+                    None => format!("${opcode:02X}"),
+                },
+                bytes,
+                target,
+                undocumented: false,
+            }
+        }
+
+        fn peek(&self, address: u16) -> Option<u8> {
+            self.ram.get(usize::from(address)).copied()
+        }
+
+        fn poke(&mut self, address: u16, value: u8) -> bool {
+            self.ram
+                .get_mut(usize::from(address))
+                .map(|byte| *byte = value)
+                .is_some()
+        }
+    }
+
+    fn success(result: ToolResult) -> JsonValue {
+        match result {
+            ToolResult::Success(value) => value,
+            ToolResult::Error { message, .. } => panic!("tool failed: {message}"),
+        }
+    }
+
+    #[test]
+    fn run_frames_then_query() {
+        let mut mcp = MachineMcp::new("counter", Counter::new());
+        let result = success(mcp.dispatch_tool("run_frames", &serde_json::json!({"count": 3})));
+        assert_eq!(result["frame_count"], 3);
+
+        let result =
+            success(mcp.dispatch_tool("query", &serde_json::json!({"path": "frame_count"})));
+        assert_eq!(result["value"], 3);
+        let result =
+            success(mcp.dispatch_tool("query_paths", &serde_json::json!({"prefix": "ram"})));
+        assert_eq!(result["paths"], serde_json::json!(["ram.0"]));
+    }
+
+    #[test]
+    fn poke_and_query_memory_report_non_ram() {
+        let mut mcp = MachineMcp::new("counter", Counter::new());
+        success(mcp.dispatch_tool("poke", &serde_json::json!({"address": 255, "value": 7})));
+        let result = mcp.dispatch_tool("poke", &serde_json::json!({"address": 256, "value": 7}));
+        assert!(matches!(result, ToolResult::Error { code: -32000, .. }));
+
+        let result = success(mcp.dispatch_tool(
+            "query_memory",
+            &serde_json::json!({"address": 254, "length": 3}),
+        ));
+        assert_eq!(result["data"], serde_json::json!([0, 7, null]));
+
+        success(mcp.dispatch_tool("reset", &JsonValue::Null));
+        assert_eq!(mcp.machine().ram[255], 0);
+    }
+
+    #[test]
+    fn audio_capture_returns_stereo_wav() {
+        let mut mcp = MachineMcp::new("counter", Counter::new());
+        let result = success(mcp.dispatch_tool("audio_capture", &serde_json::json!({"frames": 4})));
+        assert_eq!(result["samples"], 4);
+
+        use base64::Engine;
+        let wav = base64::engine::general_purpose::STANDARD
+            .decode(result["data"].as_str().expect("data"))
+            .expect("base64");
+        let reader = hound::WavReader::new(std::io::Cursor::new(wav)).expect("wav");
+        assert_eq!(reader.spec().channels, 2);
+        assert_eq!(reader.len(), 8);
+    }
+
+    #[test]
+    fn audio_capture_saves_stems_beside_the_mix() {
+        let mut mcp = MachineMcp::new("counter", Counter::new());
+        let path = std::env::temp_dir().join("emu-core-stems-test.wav");
+        let result = success(mcp.dispatch_tool(
+            "audio_capture",
+            &serde_json::json!({"frames": 3, "save_path": path, "stems": true}),
+        ));
+        let stems = result["stems"].as_array().expect("stems");
+        assert_eq!(stems.len(), 2);
+        assert_eq!(stems[1]["channel"], "down");
+        assert_eq!(stems[1]["samples"], 3);
+
+        let down = std::env::temp_dir().join("emu-core-stems-test_down.wav");
+        assert_eq!(stems[1]["path"], down.display().to_string());
+        let reader = hound::WavReader::open(&down).expect("stem wav");
+        assert_eq!(reader.spec().channels, 1);
+        assert_eq!(reader.len(), 3);
+        assert!(mcp.machine().stems.is_none(), "capture should stop afterwards");
+        for file in [path, down, std::env::temp_dir().join("emu-core-stems-test_up.wav")] {
+            let _ = std::fs::remove_file(file);
+        }
+    }
+
+    #[test]
+    fn channel_mute_solos_and_lists() {
+        let mut mcp = MachineMcp::new("counter", Counter::new());
+        let result = success(mcp.dispatch_tool("channel_mute", &serde_json::json!({"solo": "down"})));
+        assert_eq!(result["channels"], serde_json::json!(["up", "down"]));
+        assert_eq!(result["muted"], serde_json::json!(["up"]));
+        assert_eq!(mcp.machine().mute, 0b01);
+
+        let result = success(mcp.dispatch_tool("channel_mute", &serde_json::json!({"mute": []})));
+        assert_eq!(result["muted"], serde_json::json!([]));
+
+        let result = mcp.dispatch_tool("channel_mute", &serde_json::json!({"mute": ["sideways"]}));
+        assert!(matches!(result, ToolResult::Error { code: -32602, .. }));
+    }
+
+    #[test]
+    fn register_log_saves_the_format_its_extension_names() {
+        let mut mcp = MachineMcp::new("counter", Counter::new());
+        let stop = serde_json::json!({"action": "stop"});
+        let result = mcp.dispatch_tool("register_log", &stop);
+        assert!(matches!(result, ToolResult::Error { code: -32602, .. }));
+
+        success(mcp.dispatch_tool("register_log", &serde_json::json!({"action": "start"})));
+        success(mcp.dispatch_tool("run_frames", &serde_json::json!({"count": 2})));
+        let path = std::env::temp_dir().join("emu-core-reglog-test.vgm");
+        let result = success(mcp.dispatch_tool(
+            "register_log",
+            &serde_json::json!({"action": "stop", "save_path": path, "title": "Two beeps"}),
+        ));
+        assert_eq!(result["chip"], "sn76489");
+        assert_eq!(result["format"], "vgm");
+        assert_eq!(result["writes"], 2);
+        assert_eq!(result["frames"], 2);
+        let vgm = std::fs::read(&path).expect("VGM written");
+        assert_eq!(&vgm[0..4], b"Vgm ");
+        let _ = std::fs::remove_file(path);
+
+        success(mcp.dispatch_tool("register_log", &serde_json::json!({"action": "start"})));
+        let result = mcp.dispatch_tool(
+            "register_log",
+            &serde_json::json!({"action": "stop", "format": "psid"}),
+        );
+        assert!(matches!(result, ToolResult::Error { code: -32602, .. }));
+    }
+
+    #[test]
+    fn screenshot_filters_need_a_signal_for_composite() {
+        let mut mcp = MachineMcp::new("counter", Counter::new());
+        let result = success(mcp.dispatch_tool(
+            "screenshot",
+            &serde_json::json!({"correct_aspect": false, "filter": "scanlines"}),
+        ));
+        assert_eq!(result["width"], 2);
+        assert_eq!(result["height"], 4);
+
+        let result = mcp.dispatch_tool("screenshot", &serde_json::json!({"filter": "composite"}));
+        assert!(matches!(result, ToolResult::Error { code: -32602, .. }));
+    }
+
+    #[test]
+    fn record_gif_merges_still_frames() {
+        let mut mcp = MachineMcp::new("counter", Counter::new());
+        let path = std::env::temp_dir().join("emu-core-record-gif-test.gif");
+        let result = success(mcp.dispatch_tool(
+            "record_gif",
+            &serde_json::json!({"frames": 10, "every": 2, "save_path": path}),
+        ));
+        assert_eq!(result["format"], "gif");
+        assert_eq!(result["frames"], 1);
+        assert_eq!(result["duration"], 0.2);
+        assert_eq!(mcp.machine().frames, 10);
+        let bytes = std::fs::read(&path).expect("gif written");
+        assert!(bytes.starts_with(b"GIF89a"));
+        let _ = std::fs::remove_file(path);
+
+        let result = mcp.dispatch_tool(
+            "record_gif",
+            &serde_json::json!({"frames": 1, "save_path": "x.gif", "crop": [1, 1, 2, 2]}),
+        );
+        assert!(matches!(result, ToolResult::Error { code: -32602, .. }));
+    }
+
+    #[test]
+    fn disassemble_defaults_to_pc_and_resolves_targets() {
+        let mut mcp = MachineMcp::new("counter", Counter::new());
+        mcp.machine_mut().ram[0x10] = 0xEA;
+        mcp.machine_mut().ram[0x11] = 0x4C;
+        mcp.machine_mut().ram[0x12] = 0x40;
+
+        let result = success(mcp.dispatch_tool("disassemble", &serde_json::json!({"count": 3})));
+        assert_eq!(result["address"], 0x10);
+        assert_eq!(result["next_address"], 0x14);
+        let insns = result["instructions"].as_array().expect("instructions");
+        assert_eq!(insns.len(), 3);
+        assert_eq!(insns[0]["text"], "DB $EA");
+        assert_eq!(insns[1]["address"], 0x11);
+        assert_eq!(insns[1]["bytes"], serde_json::json!([0x4C, 0x40]));
+        assert_eq!(insns[1]["target"], 0x40);
+        assert_eq!(insns[2]["address"], 0x13);
+        assert_eq!(insns[2]["target"], JsonValue::Null);
+
+        let result = success(mcp.dispatch_tool(
+            "disassemble",
+            &serde_json::json!({"address": 0x40, "count": 1}),
+        ));
+        assert_eq!(result["instructions"][0]["address"], 0x40);
+
+        let result = mcp.dispatch_tool("disassemble", &serde_json::json!({"count": 0}));
+        assert!(matches!(result, ToolResult::Error { code: -32602, .. }));
+    }
+
+    #[test]
+    fn disassemble_names_labelled_addresses() {
+        let mut mcp = MachineMcp::new("counter", Counter::new());
+        mcp.machine_mut().ram[0x11] = 0x4C;
+        mcp.machine_mut().ram[0x12] = 0x40;
+        mcp.symbols_mut().insert("start", 0x11);
+        mcp.symbols_mut().insert("done", 0x40);
+
+        let result = success(mcp.dispatch_tool(
+            "disassemble",
+            &serde_json::json!({"address": "start", "count": 1}),
+        ));
+        let insn = &result["instructions"][0];
+        assert_eq!(insn["label"], "start");
+        assert_eq!(insn["target_label"], "done");
+
+        let result = success(
+            mcp.dispatch_tool("lookup_symbol", &serde_json::json!({"address": 0x12})),
+        );
+        assert_eq!(result["label"], "start+$1");
+        let result = mcp.dispatch_tool("disassemble", &serde_json::json!({"address": "end"}));
+        assert!(matches!(result, ToolResult::Error { code: -32602, .. }));
+    }
+
+    #[test]
+    fn run_streams_frames_between_requests_until_paused() {
+        let (tx, rx) = std::sync::mpsc::channel();
+        for request in [
+            r#"{"jsonrpc":"2.0","id":1,"method":"tools/call","params":{"name":"run","arguments":{"realtime":false}}}"#,
+            r#"{"jsonrpc":"2.0","id":2,"method":"tools/call","params":{"name":"pause"}}"#,
+        ] {
+            tx.send(request.to_string()).expect("send");
+        }
+        drop(tx);
+
+        let mut server = crate::mcp::McpServer::new(MachineMcp::new("counter", Counter::new()));
+        let mut out = Vec::new();
+        server.serve(&rx, &mut out);
+
+        // The run starts with one frame; pause is answered before the next.
+        let lines: Vec<JsonValue> = String::from_utf8(out)
+            .expect("utf-8")
+            .lines()
+            .map(|line| serde_json::from_str(line).expect("JSON line"))
+            .collect();
+        assert_eq!(lines.len(), 3);
+        assert_eq!(lines[0]["id"], 1);
+        assert_eq!(lines[1]["method"], "notifications/frame_complete");
+        assert_eq!(lines[1]["params"]["frame_number"], 1);
+        assert_eq!(lines[2]["id"], 2);
+        let text = lines[2]["result"]["content"][0]["text"]
+            .as_str()
+            .expect("text");
+        let paused: JsonValue = serde_json::from_str(text).expect("result");
+        assert_eq!(paused["was_running"], true);
+        assert_eq!(paused["frame_count"], 1);
+    }
+
+    #[test]
+    fn unknown_tools_fall_through() {
+        let mut mcp = MachineMcp::new("counter", Counter::new());
+        assert!(mcp.dispatch("load_tape", &JsonValue::Null).is_none());
+        let result = mcp.dispatch_tool("load_tape", &JsonValue::Null);
+        assert!(matches!(result, ToolResult::Error { code: -32601, .. }));
+    }
+}
@@ -0,0 +1,203 @@
+//! Input movie tools shared by the system MCP servers.
+//!
+//! `record_movie` starts recording on the booted machine and, when
+//! stopped, returns the `E8XM` movie file. `play_movie` queues a movie's
+//! input on the machine and by default runs it to the end, comparing
+//! every frame with the recording and reporting the first one that
+//! differs. Tapes, disks and programs loaded while recording are listed
+//! in the stopped result by slot; `play_movie` takes the same files in its
+//! `media` parameter and the machine loads them at the recorded frames.
+//! Each server supplies its machine's own `start_recording`,
+//! `stop_recording` and `play_movie` calls; these helpers handle the
+//! parameters and results.
+
+use base64::Engine;
+use serde_json::Value as JsonValue;
+
+use super::{ToolDefinition, ToolResult};
+use crate::Machine;
+use crate::movie::{Movie, MovieError};
+
+/// Definitions for the shared movie tools.
+#[must_use]
+pub fn movie_definitions() -> Vec<ToolDefinition> {
+    vec![
+        ToolDefinition {
+            name: "record_movie",
+            description: "Start or stop recording an input movie. Stopping returns the movie file and the slot of each image it depends on. Load media after starting so it is recorded; play_movie needs the same files",
+            input_schema: serde_json::json!({
+                "type": "object",
+                "properties": {
+                    "action": { "type": "string", "enum": ["start", "stop"], "description": "Start or stop recording (default: start)" },
+                    "save_path": { "type": "string", "description": "Write the movie to this path when stopping (otherwise returned as base64)" }
+                }
+            }),
+        },
+        ToolDefinition {
+            name: "play_movie",
+            description: "Play an input movie on the booted machine and report the first frame that differs from the recording",
+            input_schema: serde_json::json!({
+                "type": "object",
+                "properties": {
+                    "path": { "type": "string", "description": "Movie file" },
+                    "data": { "type": "string", "description": "Base64-encoded movie file" },
+                    "media": { "type": "object", "description": "Files loaded while recording, as slot -> path, or slot -> [paths] when a slot took several (e.g. {\"tap\": [\"side_a.tap\", \"side_b.tap\"]}). Each load is matched to the file with its recorded hash and loaded at its recorded frame", "additionalProperties": { "type": ["string", "array"], "items": { "type": "string" } } },
+                    "verify": { "type": "boolean", "description": "Run to the end of the movie checking every frame (default: true). When false, only the input is queued", "default": true }
+                }
+            }),
+        },
+    ]
+}
+
+fn invalid(message: impl Into<String>) -> ToolResult {
+    ToolResult::Error {
+        code: -32602,
+        message: message.into(),
+    }
+}
+
+/// Read the `action` parameter of `record_movie`: `true` to stop.
+///
+/// # Errors
+///
+/// Returns a `-32602` tool error for anything but `start` or `stop`.
+pub fn stop_param(params: &JsonValue) -> Result<bool, ToolResult> {
+    match params.get("action").and_then(JsonValue::as_str) {
+        None | Some("start") => Ok(false),
+        Some("stop") => Ok(true),
+        Some(other) => Err(invalid(format!(
+            "Unknown action '{other}' (expected start or stop)"
+        ))),
+    }
+}
+
+/// Read a movie from the `path` or base64 `data` parameter.
+///
+/// # Errors
+///
+/// Returns a `-32602` tool error if neither is given, the file cannot be
+/// read, or it is not a movie.
+pub fn movie_param(params: &JsonValue) -> Result<Movie, ToolResult> {
+    let bytes = if let Some(b64) = params.get("data").and_then(JsonValue::as_str) {
+        base64::engine::general_purpose::STANDARD
+            .decode(b64)
+            .map_err(|e| invalid(format!("Invalid base64: {e}")))?
+    } else if let Some(path) = params.get("path").and_then(JsonValue::as_str) {
+        std::fs::read(path).map_err(|e| invalid(format!("Cannot read {path}: {e}")))?
+    } else {
+        return Err(invalid("Provide 'data' (base64) or 'path'"));
+    };
+    Movie::from_bytes(&bytes).map_err(|e| invalid(format!("Invalid movie: {e}")))
+}
+
+/// Read the `media` parameter of `play_movie`: each slot with the contents
+/// of its file, once per file when a slot lists several.
+///
+/// # Errors
+///
+/// Returns a `-32602` tool error if a path is not a string or cannot be read.
+pub fn media_param(params: &JsonValue) -> Result<Vec<(String, Vec<u8>)>, ToolResult> {
+    let Some(media) = params.get("media").and_then(JsonValue::as_object) else {
+        return Ok(Vec::new());
+    };
+    let mut files = Vec::new();
+    for (slot, paths) in media {
+        let paths = match paths {
+            JsonValue::Array(paths) => paths.iter().collect(),
+            path => vec![path],
+        };
+        for path in paths {
+            let path = path
+                .as_str()
+                .ok_or_else(|| invalid(format!("Media '{slot}' must be a path")))?;
+            let data =
+                std::fs::read(path).map_err(|e| invalid(format!("Cannot read {path}: {e}")))?;
+            files.push((slot.clone(), data));
+        }
+    }
+    Ok(files)
+}
+
+/// Error result for a movie that cannot be played on this machine.
+#[must_use]
+pub fn movie_error(e: &MovieError) -> ToolResult {
+    ToolResult::Error {
+        code: -32000,
+        message: format!("Movie playback failed: {e}"),
+    }
+}
+
+/// Result of `record_movie` with `action: start`.
+#[must_use]
+pub fn recording_started_result(frame: u64) -> ToolResult {
+    ToolResult::Success(serde_json::json!({ "recording": true, "start_frame": frame }))
+}
+
+/// Result of `record_movie` with `action: stop`: the movie written to
+/// `save_path`, or returned as base64 `data`.
+#[must_use]
+pub fn recording_stopped_result(params: &JsonValue, movie: Option<Movie>) -> ToolResult {
+    let Some(movie) = movie else {
+        return ToolResult::Error {
+            code: -32000,
+            message: "Not recording a movie".to_string(),
+        };
+    };
+    let bytes = movie.to_bytes();
+    let mut result = serde_json::json!({
+        "recording": false,
+        "start_frame": movie.start_frame,
+        "frames": movie.frame_hashes.len(),
+        "events": movie.events.len(),
+        "media": movie
+            .media
+            .iter()
+            .map(|m| serde_json::json!({ "slot": m.slot, "frame": m.frame }))
+            .collect::<Vec<_>>(),
+        "size": bytes.len(),
+    });
+    if let Some(path) = params.get("save_path").and_then(JsonValue::as_str) {
+        if let Err(e) = std::fs::write(path, &bytes) {
+            return ToolResult::Error {
+                code: -32000,
+                message: format!("Failed to write {path}: {e}"),
+            };
+        }
+        result["path"] = path.into();
+    } else {
+        result["data"] = base64::engine::general_purpose::STANDARD
+            .encode(&bytes)
+            .into();
+    }
+    ToolResult::Success(result)
+}
+
+/// Result of `play_movie` once playback has been set up on `machine`.
+///
+/// With `verify` (the default) the movie is run to its end, stopping at
+/// the first frame whose framebuffer differs from the recording.
+#[must_use]
+pub fn play_result<M: Machine>(params: &JsonValue, movie: &Movie, machine: &mut M) -> ToolResult {
+    let mut result = serde_json::json!({
+        "start_frame": movie.start_frame,
+        "end_frame": movie.end_frame(),
+        "events": movie.events.len(),
+    });
+    if params.get("verify").and_then(JsonValue::as_bool) == Some(false) {
+        result["verified"] = false.into();
+        return ToolResult::Success(result);
+    }
+
+    let divergence = movie.verify(machine);
+    result["verified"] = true.into();
+    result["frame"] = machine.frame_count().into();
+    result["matched"] = divergence.is_none().into();
+    if let Some(d) = divergence {
+        result["divergence"] = serde_json::json!({
+            "frame": d.frame,
+            "expected_hash": format!("{:016x}", d.expected),
+            "actual_hash": format!("{:016x}", d.actual),
+        });
+    }
+    ToolResult::Success(result)
+}
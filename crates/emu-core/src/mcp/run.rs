@@ -0,0 +1,346 @@
+//! Continuous `run` mode and the notifications it pushes.
+//!
+//! A system's MCP server keeps a [`RunSession`]. The `run` tool starts it
+//! and returns at once; the server then calls
+//! [`McpEmulator::run_slice`](super::McpEmulator::run_slice) between
+//! requests, and each slice runs one frame and queues whatever happened
+//! as [`Notification`]s. `pause` (or a breakpoint, or a halted CPU) ends
+//! the run.
+//!
+//! Events:
+//!
+//! - `breakpoint_hit`: an execute, raster or condition breakpoint stopped
+//!   the run.
+//! - `watchpoint_hit`: a memory or I/O watchpoint stopped the run.
+//! - `frame_complete`: sent every `frame_interval` frames.
+//! - `media_changed`: a disk, tape, cartridge or program was loaded.
+//! - `cpu_halted`: the CPU stopped for good; ends the run.
+//! - `drive_led`: a drive's activity LED turned on or off.
+
+use std::time::{Duration, Instant};
+
+use serde_json::Value as JsonValue;
+
+use super::breakpoint::hit_to_json;
+use super::{ToolDefinition, ToolResult};
+use crate::breakpoint::Hit;
+use crate::symbols::SymbolTable;
+
+/// A server-initiated event, sent as the JSON-RPC notification
+/// `notifications/<event>`.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Notification {
+    pub event: &'static str,
+    pub params: JsonValue,
+}
+
+/// Definitions for `run` and `pause`.
+#[must_use]
+pub fn run_definitions() -> Vec<ToolDefinition> {
+    vec![
+        ToolDefinition {
+            name: "run",
+            description: "Run continuously until 'pause', a breakpoint or a halted CPU; events arrive as notifications",
+            input_schema: serde_json::json!({
+                "type": "object",
+                "properties": {
+                    "realtime": { "type": "boolean", "description": "Pace frames at the system's frame rate (false: as fast as possible)", "default": true },
+                    "frame_interval": { "type": "integer", "description": "Send frame_complete every N frames (0: never)", "default": 1 },
+                    "max_frames": { "type": "integer", "description": "Stop after this many frames" }
+                }
+            }),
+        },
+        ToolDefinition {
+            name: "pause",
+            description: "Stop a continuous run",
+            input_schema: serde_json::json!({ "type": "object", "properties": {} }),
+        },
+    ]
+}
+
+/// State of one system's continuous run.
+#[derive(Debug, Default)]
+pub struct RunSession {
+    running: bool,
+    realtime: bool,
+    frame_interval: u64,
+    frame_rate: u32,
+    max_frames: Option<u64>,
+    frames_run: u64,
+    frame_duration: Duration,
+    started: Option<Instant>,
+    drive_led: Option<bool>,
+    pending: Vec<Notification>,
+}
+
+impl RunSession {
+    #[must_use]
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    #[must_use]
+    pub fn is_running(&self) -> bool {
+        self.running
+    }
+
+    /// Frames per second a realtime run is paced at.
+    #[must_use]
+    pub fn frame_rate(&self) -> u32 {
+        self.frame_rate
+    }
+
+    /// Frames run since `run` was called.
+    #[must_use]
+    pub fn frames_run(&self) -> u64 {
+        self.frames_run
+    }
+
+    /// `run` handler. `frame_rate` paces a realtime run.
+    pub fn start(&mut self, params: &JsonValue, frame_rate: u32) -> ToolResult {
+        let realtime = params
+            .get("realtime")
+            .and_then(JsonValue::as_bool)
+            .unwrap_or(true);
+        let frame_interval = params
+            .get("frame_interval")
+            .and_then(JsonValue::as_u64)
+            .unwrap_or(1);
+        let max_frames = params.get("max_frames").and_then(JsonValue::as_u64);
+
+        self.running = max_frames != Some(0);
+        self.realtime = realtime;
+        self.frame_interval = frame_interval;
+        self.frame_rate = frame_rate;
+        self.max_frames = max_frames;
+        self.frames_run = 0;
+        self.frame_duration = Duration::from_secs(1) / frame_rate.max(1);
+        self.started = Some(Instant::now());
+        ToolResult::Success(serde_json::json!({
+            "status": if self.running { "running" } else { "paused" },
+            "realtime": realtime,
+            "frame_interval": frame_interval,
+        }))
+    }
+
+    /// `pause` handler. `position` describes where the machine stopped
+    /// (PC and frame count, say) and is merged into the result.
+    pub fn pause(&mut self, position: JsonValue) -> ToolResult {
+        let was_running = std::mem::replace(&mut self.running, false);
+        let mut result = serde_json::json!({
+            "status": "paused",
+            "was_running": was_running,
+            "frames_run": self.frames_run,
+        });
+        if let (Some(out), JsonValue::Object(fields)) = (result.as_object_mut(), position) {
+            out.extend(fields);
+        }
+        ToolResult::Success(result)
+    }
+
+    /// Note a completed frame: sends `frame_complete` on the interval and
+    /// ends the run once `max_frames` have run.
+    pub fn frame_done(&mut self, frame_number: u64) {
+        self.frames_run += 1;
+        if self.frame_interval > 0 && self.frames_run.is_multiple_of(self.frame_interval) {
+            self.notify(
+                "frame_complete",
+                serde_json::json!({ "frame_number": frame_number }),
+            );
+        }
+        if self.max_frames.is_some_and(|max| self.frames_run >= max) {
+            self.running = false;
+        }
+    }
+
+    /// A breakpoint stopped the run: sends `watchpoint_hit` if a bus
+    /// access tripped it, `breakpoint_hit` otherwise.
+    pub fn breakpoint_hit(&mut self, hit: &Hit, pc: &str, symbols: &SymbolTable) {
+        let event = if hit.trigger.is_watchpoint() {
+            "watchpoint_hit"
+        } else {
+            "breakpoint_hit"
+        };
+        self.notify(
+            event,
+            serde_json::json!({
+                "breakpoint": hit_to_json(hit, symbols),
+                "pc": pc,
+                "frames_run": self.frames_run,
+            }),
+        );
+        self.running = false;
+    }
+
+    /// The CPU has stopped for good (a jam opcode, a double bus fault,
+    /// `HALT` with interrupts off): sends `cpu_halted` and ends the run.
+    pub fn cpu_halted(&mut self, pc: &str, reason: &str) {
+        self.notify(
+            "cpu_halted",
+            serde_json::json!({ "pc": pc, "reason": reason }),
+        );
+        self.running = false;
+    }
+
+    /// Report the drive LED after a frame; sends `drive_led` when it
+    /// changes (an LED first seen off is not news).
+    pub fn drive_led(&mut self, drive: &str, on: bool) {
+        let was = self.drive_led.replace(on);
+        if was != Some(on) && (was.is_some() || on) {
+            self.notify("drive_led", serde_json::json!({ "drive": drive, "on": on }));
+        }
+    }
+
+    /// Media went into or out of `slot`. Sent whether or not a run is in
+    /// progress; `detail` says what (a path, a format).
+    pub fn media_changed(&mut self, slot: &str, detail: JsonValue) {
+        let mut params = serde_json::json!({ "slot": slot });
+        if let (Some(out), JsonValue::Object(fields)) = (params.as_object_mut(), detail) {
+            out.extend(fields);
+        }
+        self.notify("media_changed", params);
+    }
+
+    /// Send `media_changed` for a media tool (`tool`, called with
+    /// `params`) that succeeded.
+    pub fn media_tool_done(
+        &mut self,
+        slot: &str,
+        tool: &str,
+        params: &JsonValue,
+        result: &ToolResult,
+    ) {
+        if matches!(result, ToolResult::Success(_)) {
+            let mut detail = serde_json::json!({ "tool": tool });
+            if let Some(path) = params.get("path").and_then(JsonValue::as_str) {
+                detail["path"] = path.into();
+            }
+            self.media_changed(slot, detail);
+        }
+    }
+
+    /// End the run without a notification (the machine went away).
+    pub fn stop(&mut self) {
+        self.running = false;
+    }
+
+    fn notify(&mut self, event: &'static str, params: JsonValue) {
+        self.pending.push(Notification { event, params });
+    }
+
+    /// Notifications queued since the last call.
+    pub fn take_notifications(&mut self) -> Vec<Notification> {
+        std::mem::take(&mut self.pending)
+    }
+
+    /// What [`McpEmulator::run_slice`](super::McpEmulator::run_slice)
+    /// returns after a frame: the wait until the next frame is due, or
+    /// `None` once the run has ended.
+    #[must_use]
+    pub fn next_slice(&self) -> Option<Duration> {
+        if !self.running {
+            return None;
+        }
+        if !self.realtime {
+            return Some(Duration::ZERO);
+        }
+        let elapsed = self.started.map_or(Duration::ZERO, |t| t.elapsed());
+        let due = self.frame_duration * u32::try_from(self.frames_run).unwrap_or(u32::MAX);
+        Some(due.saturating_sub(elapsed))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use serde_json::json;
+
+    use super::RunSession;
+    use crate::breakpoint::{Access, Hit, Trigger};
+    use crate::mcp::ToolResult;
+    use crate::symbols::SymbolTable;
+
+    fn events(run: &mut RunSession) -> Vec<&'static str> {
+        run.take_notifications().iter().map(|n| n.event).collect()
+    }
+
+    #[test]
+    fn frames_are_reported_on_the_interval_until_max_frames() {
+        let mut run = RunSession::new();
+        let result = run.start(
+            &json!({"realtime": false, "frame_interval": 2, "max_frames": 5}),
+            50,
+        );
+        assert!(matches!(result, ToolResult::Success(_)));
+        assert_eq!(run.next_slice(), Some(std::time::Duration::ZERO));
+
+        for frame in 1..=5 {
+            assert!(run.is_running());
+            run.frame_done(100 + frame);
+        }
+        assert!(!run.is_running());
+        assert_eq!(run.next_slice(), None);
+        let sent = run.take_notifications();
+        let frames: Vec<_> = sent.iter().map(|n| &n.params["frame_number"]).collect();
+        assert_eq!(frames, [&json!(102), &json!(104)]);
+
+        let ToolResult::Success(paused) = run.pause(json!({"pc": "$0000"})) else {
+            panic!("pause failed");
+        };
+        assert_eq!(paused["was_running"], false);
+        assert_eq!(paused["frames_run"], 5);
+        assert_eq!(paused["pc"], "$0000");
+    }
+
+    #[test]
+    fn breakpoints_watchpoints_and_halts_end_the_run() {
+        let mut run = RunSession::new();
+        let mut hit = Hit {
+            id: 1,
+            trigger: Trigger::Execute(0x1000),
+            hits: 1,
+            access: None,
+            temporary: false,
+        };
+
+        run.start(&json!({}), 50);
+        run.breakpoint_hit(&hit, "$1000", &SymbolTable::new());
+        assert!(!run.is_running());
+
+        hit.trigger = Trigger::Memory {
+            range: 0xD020..=0xD020,
+            access: Access::Write,
+        };
+        run.start(&json!({}), 50);
+        run.breakpoint_hit(&hit, "$C00A", &SymbolTable::new());
+
+        run.start(&json!({}), 50);
+        run.cpu_halted("$0040", "jam");
+        assert!(!run.is_running());
+        assert_eq!(
+            events(&mut run),
+            ["breakpoint_hit", "watchpoint_hit", "cpu_halted"]
+        );
+    }
+
+    #[test]
+    fn drive_led_is_sent_on_change_and_media_whenever() {
+        let mut run = RunSession::new();
+        run.drive_led("8", false);
+        run.drive_led("8", true);
+        run.drive_led("8", true);
+        run.drive_led("8", false);
+        run.media_changed("drive8", json!({"path": "game.d64"}));
+
+        let sent = run.take_notifications();
+        let leds: Vec<_> = sent
+            .iter()
+            .filter(|n| n.event == "drive_led")
+            .map(|n| n.params["on"].clone())
+            .collect();
+        assert_eq!(leds, [json!(true), json!(false)]);
+        assert_eq!(
+            sent.last().map(|n| &n.params),
+            Some(&json!({"slot": "drive8", "path": "game.d64"}))
+        );
+    }
+}
@@ -0,0 +1,221 @@
+//! Symbol tools shared by the system MCP servers.
+//!
+//! Each server keeps a [`SymbolTable`] next to its machine. `load_symbols`
+//! fills it from a label file; the breakpoint, disassembly and trace tools
+//! then accept label names as addresses and report labels alongside them.
+
+use std::path::Path;
+
+use serde_json::Value as JsonValue;
+
+use super::{ToolDefinition, ToolResult};
+use crate::symbols::{SymbolFormat, SymbolTable};
+
+/// Definitions for the shared symbol tools.
+#[must_use]
+pub fn symbol_definitions() -> Vec<ToolDefinition> {
+    vec![
+        ToolDefinition {
+            name: "load_symbols",
+            description: "Load labels from a VICE .lbl/.vs, ca65 .dbg, or sjasmplus/pasmo .sym file",
+            input_schema: serde_json::json!({
+                "type": "object",
+                "properties": {
+                    "path": { "type": "string", "description": "Symbol file" },
+                    "format": { "type": "string", "enum": ["vice", "ca65", "sym"], "description": "File format (default: from the extension)" },
+                    "replace": { "type": "boolean", "description": "Drop the labels already loaded", "default": false }
+                },
+                "required": ["path"]
+            }),
+        },
+        ToolDefinition {
+            name: "clear_symbols",
+            description: "Forget every loaded label",
+            input_schema: serde_json::json!({ "type": "object", "properties": {} }),
+        },
+        ToolDefinition {
+            name: "lookup_symbol",
+            description: "Resolve a label (or label+offset) to an address, or name an address",
+            input_schema: serde_json::json!({
+                "type": "object",
+                "properties": {
+                    "name": { "type": "string", "description": "Label, label+offset, or number" },
+                    "address": { "type": "integer", "description": "Address to name" }
+                }
+            }),
+        },
+    ]
+}
+
+fn invalid(message: impl Into<String>) -> ToolResult {
+    ToolResult::Error {
+        code: -32602,
+        message: message.into(),
+    }
+}
+
+/// Read an address parameter given as an integer or as a string
+/// [`SymbolTable::resolve`] understands. `None` if absent.
+///
+/// # Errors
+///
+/// Returns a `-32602` tool error for an unknown label or an address above
+/// `max`.
+pub fn address_param(
+    params: &JsonValue,
+    key: &str,
+    symbols: &SymbolTable,
+    max: u32,
+) -> Result<Option<u32>, ToolResult> {
+    let address = match params.get(key) {
+        None | Some(JsonValue::Null) => return Ok(None),
+        Some(JsonValue::String(text)) => symbols
+            .resolve(text)
+            .ok_or_else(|| invalid(format!("Unknown label '{text}' in '{key}'")))?,
+        Some(value) => value
+            .as_u64()
+            .and_then(|v| u32::try_from(v).ok())
+            .ok_or_else(|| invalid(format!("Invalid '{key}' (0-{max})")))?,
+    };
+    if address > max {
+        return Err(invalid(format!("Invalid '{key}' (0-{max})")));
+    }
+    Ok(Some(address))
+}
+
+/// `load_symbols` handler.
+pub fn load_symbols_result(params: &JsonValue, symbols: &mut SymbolTable) -> ToolResult {
+    let Some(path) = params.get("path").and_then(JsonValue::as_str) else {
+        return invalid("Missing 'path'");
+    };
+    let format = match params.get("format").and_then(JsonValue::as_str) {
+        Some(name) => SymbolFormat::from_name(name),
+        None => SymbolFormat::from_path(Path::new(path)),
+    };
+    let Some(format) = format else {
+        return invalid("Unknown symbol format; pass 'format' (vice, ca65 or sym)");
+    };
+    let text = match std::fs::read_to_string(path) {
+        Ok(t) => t,
+        Err(e) => {
+            return ToolResult::Error {
+                code: -32000,
+                message: format!("Cannot read {path}: {e}"),
+            };
+        }
+    };
+    let loaded = match SymbolTable::parse(format, &text) {
+        Ok(table) => table,
+        Err(e) => {
+            return ToolResult::Error {
+                code: -32000,
+                message: format!("{path}: {e}"),
+            };
+        }
+    };
+    merge_symbols(params, symbols, &loaded)
+}
+
+/// Add `loaded` to `symbols` (replacing them if the `replace` parameter
+/// is set) and report the counts.
+pub fn merge_symbols(
+    params: &JsonValue,
+    symbols: &mut SymbolTable,
+    loaded: &SymbolTable,
+) -> ToolResult {
+    if params
+        .get("replace")
+        .and_then(JsonValue::as_bool)
+        .unwrap_or(false)
+    {
+        symbols.clear();
+    }
+    symbols.extend(loaded);
+    ToolResult::Success(serde_json::json!({
+        "status": "ok",
+        "loaded": loaded.len(),
+        "total": symbols.len(),
+    }))
+}
+
+/// `clear_symbols` handler.
+pub fn clear_symbols_result(symbols: &mut SymbolTable) -> ToolResult {
+    let cleared = symbols.len();
+    symbols.clear();
+    ToolResult::Success(serde_json::json!({ "cleared": cleared }))
+}
+
+/// `lookup_symbol` handler.
+#[must_use]
+pub fn lookup_symbol_result(params: &JsonValue, symbols: &SymbolTable) -> ToolResult {
+    let address = if let Some(name) = params.get("name").and_then(JsonValue::as_str) {
+        match symbols.resolve(name) {
+            Some(address) => address,
+            None => {
+                return ToolResult::Error {
+                    code: -32000,
+                    message: format!("Unknown label '{name}'"),
+                };
+            }
+        }
+    } else if let Some(address) = params
+        .get("address")
+        .and_then(JsonValue::as_u64)
+        .and_then(|a| u32::try_from(a).ok())
+    {
+        address
+    } else {
+        return invalid("Pass 'name' or 'address'");
+    };
+    ToolResult::Success(serde_json::json!({
+        "address": address,
+        "label": symbols.describe(address),
+    }))
+}
+
+#[cfg(test)]
+mod tests {
+    use serde_json::json;
+
+    use super::{address_param, load_symbols_result, lookup_symbol_result};
+    use crate::mcp::ToolResult;
+    use crate::symbols::SymbolTable;
+
+    #[test]
+    fn loads_a_label_file_and_resolves_names() {
+        let path = std::env::temp_dir().join("emu-core-symbols-test.lbl");
+        std::fs::write(&path, "al C:c000 .init\nal C:c010 .loop\n").expect("write");
+        let mut symbols = SymbolTable::new();
+        symbols.insert("old", 0x1000);
+
+        let ToolResult::Success(loaded) = load_symbols_result(
+            &json!({"path": path.to_str(), "replace": true}),
+            &mut symbols,
+        ) else {
+            panic!("load failed");
+        };
+        let _ = std::fs::remove_file(&path);
+        assert_eq!(loaded["loaded"], 2);
+        assert_eq!(loaded["total"], 2);
+
+        let ToolResult::Success(found) = lookup_symbol_result(&json!({"name": "loop+2"}), &symbols)
+        else {
+            panic!("lookup failed");
+        };
+        assert_eq!(found["address"], 0xC012);
+        assert_eq!(found["label"], "loop+$2");
+
+        assert!(matches!(
+            address_param(&json!({"address": "init"}), "address", &symbols, 0xFFFF),
+            Ok(Some(0xC000))
+        ));
+        assert!(matches!(
+            address_param(&json!({"address": "nowhere"}), "address", &symbols, 0xFFFF),
+            Err(ToolResult::Error { code: -32602, .. })
+        ));
+        assert!(matches!(
+            load_symbols_result(&json!({"path": "game.bin"}), &mut symbols),
+            ToolResult::Error { code: -32602, .. }
+        ));
+    }
+}
@@ -0,0 +1,190 @@
+//! Trace recorder tools shared by the system MCP servers.
+//!
+//! `trace_start` builds a [`Tracer`] with the system's own [`TraceLayout`]
+//! and the server attaches it to the machine, which records every
+//! instruction it runs from then on, whichever tool drives it. The tracer
+//! takes a copy of the server's labels; servers hand it a fresh copy when
+//! `load_symbols` changes them.
+
+use std::fs::File;
+use std::io::BufWriter;
+
+use serde_json::Value as JsonValue;
+
+use super::{ToolDefinition, ToolResult};
+use crate::symbols::SymbolTable;
+use crate::trace::{TraceFormat, TraceLayout, Tracer};
+
+/// Definitions for the shared trace tools.
+#[must_use]
+pub fn trace_definitions() -> Vec<ToolDefinition> {
+    vec![
+        ToolDefinition {
+            name: "trace_start",
+            description: "Start recording every executed instruction (PC, opcode, registers, optional bus cycles)",
+            input_schema: serde_json::json!({
+                "type": "object",
+                "properties": {
+                    "bus": { "type": "boolean", "description": "Also record each instruction's bus cycles", "default": false },
+                    "capacity": { "type": "integer", "description": "Instructions kept in the ring buffer", "default": Tracer::DEFAULT_CAPACITY },
+                    "path": { "type": "string", "description": "Stream to this file instead of the ring buffer" },
+                    "format": { "type": "string", "enum": ["text", "json"], "description": "Format of the streamed file (default: text)" }
+                }
+            }),
+        },
+        ToolDefinition {
+            name: "trace_stop",
+            description: "Stop recording; the ring buffer stays available to trace_export",
+            input_schema: serde_json::json!({ "type": "object", "properties": {} }),
+        },
+        ToolDefinition {
+            name: "trace_export",
+            description: "Export the ring buffer as text or JSON Lines",
+            input_schema: serde_json::json!({
+                "type": "object",
+                "properties": {
+                    "format": { "type": "string", "enum": ["text", "json"], "default": "text" },
+                    "last": { "type": "integer", "description": "Only the newest N instructions" },
+                    "save_path": { "type": "string", "description": "Write to this file instead of returning the data" }
+                }
+            }),
+        },
+    ]
+}
+
+fn invalid(message: impl Into<String>) -> ToolResult {
+    ToolResult::Error {
+        code: -32602,
+        message: message.into(),
+    }
+}
+
+fn format_param(params: &JsonValue) -> Result<TraceFormat, ToolResult> {
+    match params.get("format") {
+        None | Some(JsonValue::Null) => Ok(TraceFormat::Text),
+        Some(value) => value
+            .as_str()
+            .and_then(TraceFormat::from_name)
+            .ok_or_else(|| invalid("Invalid 'format' (text or json)")),
+    }
+}
+
+/// Build the tracer `trace_start` asks for, labelling entries from
+/// `symbols`.
+///
+/// # Errors
+///
+/// Returns a tool error for a bad format or capacity, or if the trace
+/// file cannot be created.
+pub fn parse_tracer(
+    params: &JsonValue,
+    layout: TraceLayout,
+    symbols: &SymbolTable,
+) -> Result<Tracer, ToolResult> {
+    let format = format_param(params)?;
+    let bus = params
+        .get("bus")
+        .and_then(JsonValue::as_bool)
+        .unwrap_or(false);
+
+    let tracer = if let Some(path) = params.get("path").and_then(JsonValue::as_str) {
+        let file = File::create(path).map_err(|e| ToolResult::Error {
+            code: -32000,
+            message: format!("Cannot create {path}: {e}"),
+        })?;
+        Tracer::stream(layout, Box::new(BufWriter::new(file)), format)
+    } else {
+        let capacity = match params.get("capacity") {
+            None | Some(JsonValue::Null) => Tracer::DEFAULT_CAPACITY,
+            Some(value) => value
+                .as_u64()
+                .and_then(|c| usize::try_from(c).ok())
+                .filter(|c| *c > 0)
+                .ok_or_else(|| invalid("Invalid 'capacity' (1 or more)"))?,
+        };
+        Tracer::ring(layout, capacity)
+    };
+    Ok(tracer.with_bus_log(bus).with_symbols(symbols.clone()))
+}
+
+/// `trace_start` result for a newly attached tracer.
+#[must_use]
+pub fn trace_start_result(tracer: &Tracer) -> ToolResult {
+    ToolResult::Success(serde_json::json!({
+        "status": "ok",
+        "mode": if tracer.is_streaming() { "file" } else { "ring" },
+        "bus": tracer.logs_bus(),
+    }))
+}
+
+/// `trace_stop` handler.
+#[must_use]
+pub fn trace_stop_result(trace: Option<&mut Tracer>) -> ToolResult {
+    let Some(tracer) = trace else {
+        return ToolResult::Error {
+            code: -32000,
+            message: "No trace running. Call 'trace_start' first.".to_string(),
+        };
+    };
+    if let Err(e) = tracer.stop() {
+        return ToolResult::Error {
+            code: -32000,
+            message: format!("Trace file write failed: {e}"),
+        };
+    }
+    ToolResult::Success(serde_json::json!({
+        "status": "ok",
+        "recorded": tracer.recorded(),
+    }))
+}
+
+/// `trace_export` handler.
+pub fn trace_export_result(params: &JsonValue, trace: Option<&Tracer>) -> ToolResult {
+    let Some(tracer) = trace else {
+        return ToolResult::Error {
+            code: -32000,
+            message: "No trace recorded. Call 'trace_start' first.".to_string(),
+        };
+    };
+    if tracer.is_streaming() {
+        return ToolResult::Error {
+            code: -32000,
+            message: "Trace is streaming to a file; nothing to export".to_string(),
+        };
+    }
+    let format = match format_param(params) {
+        Ok(f) => f,
+        Err(e) => return e,
+    };
+    let last = params
+        .get("last")
+        .and_then(JsonValue::as_u64)
+        .and_then(|n| usize::try_from(n).ok());
+
+    let data = tracer.export(format, last);
+    let held = tracer.entries().count();
+    let entries = last.map_or(held, |n| n.min(held));
+    let format_name = match format {
+        TraceFormat::Text => "text",
+        TraceFormat::Json => "json",
+    };
+
+    if let Some(path) = params.get("save_path").and_then(JsonValue::as_str) {
+        if let Err(e) = std::fs::write(path, &data) {
+            return ToolResult::Error {
+                code: -32000,
+                message: format!("Cannot write {path}: {e}"),
+            };
+        }
+        return ToolResult::Success(serde_json::json!({
+            "format": format_name,
+            "entries": entries,
+            "path": path,
+        }));
+    }
+    ToolResult::Success(serde_json::json!({
+        "format": format_name,
+        "entries": entries,
+        "data": data,
+    }))
+}
@@ -0,0 +1,624 @@
+//! Deterministic input movies.
+//!
+//! A movie is everything needed to replay a session: the machine tag, the
+//! boot configuration, SHA-1 hashes of the ROMs and media involved, and
+//! every input event stamped with the frame at which it took effect. The
+//! emulators are deterministic, so feeding the same events into an
+//! identically configured machine reproduces the session exactly.
+//!
+//! Each recorded frame also carries a hash of the framebuffer. Playing a
+//! movie back with [`Movie::verify`] compares those hashes and reports the
+//! first frame that differs, which pins a determinism regression to the
+//! frame where it first became visible.
+//!
+//! A movie recorded from power-on has no start snapshot and is replayed
+//! on a freshly created machine. A movie recorded mid-session embeds a
+//! save state of the moment recording began.
+//!
+//! Tapes, disks and programs loaded while recording are stored as hashes
+//! stamped with the frame they went in. Playback is handed the same files,
+//! checks them against the hashes and loads each at its recorded frame.
+//!
+//! Inputs are stored by name (`"Return"`, `"A"`, `"KempstonFire"`) so this
+//! module knows nothing about any system's keys; each machine maps names
+//! to and from its own input enum.
+
+use std::fmt::{self, Write};
+
+use sha1::{Digest, Sha1};
+
+use crate::{Machine, StateError, StateReader, StateWriter};
+
+/// Magic bytes at the start of every movie file.
+pub const MOVIE_MAGIC: [u8; 4] = *b"E8XM";
+
+/// Current movie format version.
+///
+/// Bump when the serialised layout changes. Version 2 stamps each media
+/// entry with the frame it was loaded at.
+pub const MOVIE_VERSION: u16 = 2;
+
+/// Errors raised while loading or starting playback of a movie.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum MovieError {
+    /// The data does not start with `MOVIE_MAGIC`.
+    BadMagic,
+    /// The movie was written by a different format version.
+    UnsupportedVersion(u16),
+    /// The movie body could not be decoded, or its start snapshot could
+    /// not be loaded.
+    State(StateError),
+    /// The movie was recorded on a different machine.
+    WrongMachine { expected: String, found: String },
+    /// A boot configuration setting differs.
+    ConfigMismatch {
+        key: String,
+        expected: String,
+        found: String,
+    },
+    /// A ROM or media image differs from the one recorded.
+    MediaMismatch { slot: String },
+    /// A power-on movie was started on a machine that has already run.
+    NotAtStart { frame: u64 },
+    /// An event names an input the machine does not have.
+    UnknownInput(String),
+}
+
+impl fmt::Display for MovieError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::BadMagic => write!(f, "not a movie (bad magic)"),
+            Self::UnsupportedVersion(v) => {
+                write!(
+                    f,
+                    "unsupported movie version {v} (expected {MOVIE_VERSION})"
+                )
+            }
+            Self::State(e) => write!(f, "{e}"),
+            Self::WrongMachine { expected, found } => {
+                write!(f, "movie is for {found}, not {expected}")
+            }
+            Self::ConfigMismatch {
+                key,
+                expected,
+                found,
+            } => write!(f, "movie was recorded with {key} = {found}, not {expected}"),
+            Self::MediaMismatch { slot } => write!(f, "{slot} differs from the recorded image"),
+            Self::NotAtStart { frame } => {
+                write!(f, "power-on movie needs a fresh machine (at frame {frame})")
+            }
+            Self::UnknownInput(name) => write!(f, "unknown input \"{name}\""),
+        }
+    }
+}
+
+impl std::error::Error for MovieError {}
+
+impl From<StateError> for MovieError {
+    fn from(e: StateError) -> Self {
+        Self::State(e)
+    }
+}
+
+/// One input change.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct MovieEvent {
+    /// Frame at whose start the event takes effect, matching the frame
+    /// numbering of each system's `InputQueue`.
+    pub frame: u64,
+    /// Input name as understood by the recording machine.
+    pub input: String,
+    /// True = press, false = release.
+    pub pressed: bool,
+}
+
+/// A ROM or media image the movie depends on.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct MovieMedia {
+    /// Slot the image was attached to (`"rom"`, `"tap"`, `"disk"`...).
+    pub slot: String,
+    /// Frame at whose start the image was loaded; 0 for boot ROMs.
+    pub frame: u64,
+    /// SHA-1 of the image as lowercase hex.
+    pub hash: String,
+}
+
+/// First frame whose framebuffer differs from the recording.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Divergence {
+    /// Frame number (the machine's `frame_count()` after running it).
+    pub frame: u64,
+    /// Hash stored in the movie.
+    pub expected: u64,
+    /// Hash of the frame just produced.
+    pub actual: u64,
+}
+
+/// A recorded input session.
+#[derive(Debug, Clone, PartialEq, Eq, Default)]
+pub struct Movie {
+    /// Machine tag, the same one used in save-state headers.
+    pub machine: String,
+    /// Boot configuration as ordered (key, value) pairs.
+    pub config: Vec<(String, String)>,
+    /// Boot ROMs, then each image loaded while recording.
+    pub media: Vec<MovieMedia>,
+    /// Frame count when recording began.
+    pub start_frame: u64,
+    /// Snapshot taken when recording began, absent for power-on movies.
+    pub start_state: Option<Vec<u8>>,
+    /// Input events in the order they took effect.
+    pub events: Vec<MovieEvent>,
+    /// Framebuffer hash after each recorded frame, starting with frame
+    /// `start_frame + 1`.
+    pub frame_hashes: Vec<u64>,
+}
+
+impl Movie {
+    /// Create an empty movie for the machine with the given tag.
+    #[must_use]
+    pub fn new(machine: &str) -> Self {
+        Self {
+            machine: machine.to_string(),
+            ..Self::default()
+        }
+    }
+
+    /// Add a boot configuration setting.
+    #[must_use]
+    pub fn with_config(mut self, key: &str, value: impl fmt::Display) -> Self {
+        self.config.push((key.to_string(), value.to_string()));
+        self
+    }
+
+    /// Add a boot ROM or image, stored as its SHA-1.
+    #[must_use]
+    pub fn with_media(mut self, slot: &str, data: &[u8]) -> Self {
+        self.add_media(slot, 0, data);
+        self
+    }
+
+    /// Record an image loaded into `slot` at the start of `frame` (a tape
+    /// or program loaded while recording, say).
+    pub fn add_media(&mut self, slot: &str, frame: u64, data: &[u8]) {
+        self.media.push(MovieMedia {
+            slot: slot.to_string(),
+            frame,
+            hash: media_hash(data),
+        });
+    }
+
+    /// Match each image loaded while recording with its file in `media`
+    /// and return the frame each one goes in, with the index of its file.
+    ///
+    /// Images loaded while recording are the media entries whose slot is
+    /// not in `expected`, the header built from the playback machine's
+    /// configuration. `media` pairs a slot with a file, in whatever form
+    /// the machine hashed when recording; a slot may appear several times
+    /// (side A then side B), and each load is matched to the file with
+    /// its hash.
+    ///
+    /// # Errors
+    ///
+    /// Returns `MediaMismatch` for the first load with no matching file in
+    /// `media`.
+    pub fn media_loads<T: AsRef<[u8]>>(
+        &self,
+        expected: &Self,
+        media: &[(&str, T)],
+    ) -> Result<Vec<(u64, usize)>, MovieError> {
+        let hashes: Vec<_> = media
+            .iter()
+            .map(|(slot, data)| (*slot, media_hash(data.as_ref())))
+            .collect();
+        self.media
+            .iter()
+            .filter(|m| !expected.media.iter().any(|e| e.slot == m.slot))
+            .map(|load| {
+                let index = hashes
+                    .iter()
+                    .position(|(slot, hash)| *slot == load.slot && *hash == load.hash)
+                    .ok_or_else(|| MovieError::MediaMismatch {
+                        slot: load.slot.clone(),
+                    })?;
+                Ok((load.frame, index))
+            })
+            .collect()
+    }
+
+    /// Frame count once every recorded frame has been played.
+    #[must_use]
+    pub fn end_frame(&self) -> u64 {
+        self.start_frame + self.frame_hashes.len() as u64
+    }
+
+    /// Append an input event.
+    pub fn push_event(&mut self, frame: u64, input: impl Into<String>, pressed: bool) {
+        self.events.push(MovieEvent {
+            frame,
+            input: input.into(),
+            pressed,
+        });
+    }
+
+    /// Append the hash of a just-completed frame.
+    pub fn push_frame(&mut self, framebuffer: &[u32]) {
+        self.frame_hashes.push(frame_hash(framebuffer));
+    }
+
+    /// Check that `expected`, built from the playback machine's own
+    /// configuration, matches what this movie was recorded with.
+    ///
+    /// Every setting and media slot in `expected` must be present with the
+    /// same value. Media the movie records but `expected` omits (a program
+    /// loaded while recording) is not checked; use [`Movie::check_media`]
+    /// for those.
+    ///
+    /// # Errors
+    ///
+    /// Returns the first mismatch found.
+    pub fn check_header(&self, expected: &Self) -> Result<(), MovieError> {
+        if self.machine != expected.machine {
+            return Err(MovieError::WrongMachine {
+                expected: expected.machine.clone(),
+                found: self.machine.clone(),
+            });
+        }
+        for (key, value) in &expected.config {
+            let found = lookup(&self.config, key);
+            if found != Some(value.as_str()) {
+                return Err(MovieError::ConfigMismatch {
+                    key: key.clone(),
+                    expected: value.clone(),
+                    found: found.unwrap_or("nothing").to_string(),
+                });
+            }
+        }
+        for media in &expected.media {
+            if !self
+                .media
+                .iter()
+                .any(|m| m.slot == media.slot && m.hash == media.hash)
+            {
+                return Err(MovieError::MediaMismatch {
+                    slot: media.slot.clone(),
+                });
+            }
+        }
+        Ok(())
+    }
+
+    /// Check that `data` is an image recorded in `slot`.
+    ///
+    /// # Errors
+    ///
+    /// Returns `MediaMismatch` if no image recorded in the slot matches.
+    pub fn check_media(&self, slot: &str, data: &[u8]) -> Result<(), MovieError> {
+        let hash = media_hash(data);
+        if self.media.iter().any(|m| m.slot == slot && m.hash == hash) {
+            Ok(())
+        } else {
+            Err(MovieError::MediaMismatch {
+                slot: slot.to_string(),
+            })
+        }
+    }
+
+    /// Run the remaining recorded frames, comparing each framebuffer with
+    /// the recording.
+    ///
+    /// The machine must already be set up for playback (each system's
+    /// `play_movie`). Frames the machine has already passed are skipped.
+    /// Returns the first divergent frame, or `None` if every frame matched.
+    pub fn verify<M: Machine>(&self, machine: &mut M) -> Option<Divergence> {
+        while machine.frame_count() < self.end_frame() {
+            machine.run_frame();
+            let frame = machine.frame_count();
+            let Some(index) = frame.checked_sub(self.start_frame + 1) else {
+                continue;
+            };
+            let expected = self.frame_hashes[index as usize];
+            let actual = frame_hash(machine.framebuffer());
+            if actual != expected {
+                return Some(Divergence {
+                    frame,
+                    expected,
+                    actual,
+                });
+            }
+        }
+        None
+    }
+
+    /// Serialise the movie to its file format.
+    #[must_use]
+    pub fn to_bytes(&self) -> Vec<u8> {
+        let mut w = StateWriter::new();
+        w.write_u16(MOVIE_VERSION);
+        w.write_str(&self.machine);
+        write_pairs(&mut w, &self.config);
+        w.write_usize(self.media.len());
+        for media in &self.media {
+            w.write_str(&media.slot);
+            w.write_u64(media.frame);
+            w.write_str(&media.hash);
+        }
+        w.write_u64(self.start_frame);
+        w.write_bool(self.start_state.is_some());
+        if let Some(state) = &self.start_state {
+            w.write_bytes(state);
+        }
+        w.write_usize(self.events.len());
+        for event in &self.events {
+            w.write_u64(event.frame);
+            w.write_str(&event.input);
+            w.write_bool(event.pressed);
+        }
+        w.write_usize(self.frame_hashes.len());
+        for &hash in &self.frame_hashes {
+            w.write_u64(hash);
+        }
+
+        let mut out = MOVIE_MAGIC.to_vec();
+        out.extend_from_slice(&w.into_bytes());
+        out
+    }
+
+    /// Parse a movie file.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the magic or version is wrong or the data is
+    /// truncated.
+    pub fn from_bytes(data: &[u8]) -> Result<Self, MovieError> {
+        let body = data
+            .strip_prefix(&MOVIE_MAGIC)
+            .ok_or(MovieError::BadMagic)?;
+        let mut r = StateReader::new(body);
+        let version = r.read_u16()?;
+        if version != MOVIE_VERSION {
+            return Err(MovieError::UnsupportedVersion(version));
+        }
+        let machine = r.read_string()?;
+        let config = read_pairs(&mut r)?;
+        let mut media = Vec::new();
+        for _ in 0..r.read_usize()? {
+            media.push(MovieMedia {
+                slot: r.read_string()?,
+                frame: r.read_u64()?,
+                hash: r.read_string()?,
+            });
+        }
+        let start_frame = r.read_u64()?;
+        let start_state = if r.read_bool()? {
+            Some(r.read_bytes()?.to_vec())
+        } else {
+            None
+        };
+        let mut events = Vec::new();
+        for _ in 0..r.read_usize()? {
+            events.push(MovieEvent {
+                frame: r.read_u64()?,
+                input: r.read_string()?,
+                pressed: r.read_bool()?,
+            });
+        }
+        let mut frame_hashes = Vec::new();
+        for _ in 0..r.read_usize()? {
+            frame_hashes.push(r.read_u64()?);
+        }
+        r.finish()?;
+        Ok(Self {
+            machine,
+            config,
+            media,
+            start_frame,
+            start_state,
+            events,
+            frame_hashes,
+        })
+    }
+}
+
+/// 64-bit FNV-1a hash of a framebuffer.
+#[must_use]
+pub fn frame_hash(framebuffer: &[u32]) -> u64 {
+    framebuffer
+        .iter()
+        .flat_map(|pixel| pixel.to_le_bytes())
+        .fold(0xCBF2_9CE4_8422_2325, |hash, byte| {
+            (hash ^ u64::from(byte)).wrapping_mul(0x0000_0100_0000_01B3)
+        })
+}
+
+/// SHA-1 of a media image as lowercase hex.
+#[must_use]
+pub fn media_hash(data: &[u8]) -> String {
+    Sha1::digest(data).iter().fold(String::new(), |mut hex, b| {
+        let _ = write!(hex, "{b:02x}");
+        hex
+    })
+}
+
+fn lookup<'a>(pairs: &'a [(String, String)], key: &str) -> Option<&'a str> {
+    pairs
+        .iter()
+        .find(|(k, _)| k == key)
+        .map(|(_, v)| v.as_str())
+}
+
+fn write_pairs(w: &mut StateWriter, pairs: &[(String, String)]) {
+    w.write_usize(pairs.len());
+    for (key, value) in pairs {
+        w.write_str(key);
+        w.write_str(value);
+    }
+}
+
+fn read_pairs(r: &mut StateReader<'_>) -> Result<Vec<(String, String)>, StateError> {
+    let mut pairs = Vec::new();
+    for _ in 0..r.read_usize()? {
+        pairs.push((r.read_string()?, r.read_string()?));
+    }
+    Ok(pairs)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::AudioFrame;
+
+    /// A machine whose one-pixel framebuffer shows the frame number, unless
+    /// `glitch_at` makes it misdraw a frame.
+    struct Counter {
+        frames: u64,
+        pixel: [u32; 1],
+        glitch_at: Option<u64>,
+    }
+
+    impl Counter {
+        fn new() -> Self {
+            Self {
+                frames: 0,
+                pixel: [0],
+                glitch_at: None,
+            }
+        }
+    }
+
+    impl Machine for Counter {
+        fn run_frame(&mut self) {
+            self.frames += 1;
+            self.pixel[0] = self.frames as u32;
+            if self.glitch_at == Some(self.frames) {
+                self.pixel[0] = 0xDEAD;
+            }
+        }
+
+        fn framebuffer(&self) -> &[u32] {
+            &self.pixel
+        }
+
+        fn framebuffer_width(&self) -> u32 {
+            1
+        }
+
+        fn framebuffer_height(&self) -> u32 {
+            1
+        }
+
+        fn take_audio_buffer(&mut self) -> Vec<AudioFrame> {
+            Vec::new()
+        }
+
+        fn frame_count(&self) -> u64 {
+            self.frames
+        }
+
+        fn reset(&mut self) {}
+    }
+
+    fn record(frames: u64) -> Movie {
+        let mut machine = Counter::new();
+        let mut movie = Movie::new("counter")
+            .with_config("model", "test")
+            .with_media("rom", &[1, 2, 3]);
+        for _ in 0..frames {
+            machine.run_frame();
+            movie.push_frame(machine.framebuffer());
+        }
+        movie
+    }
+
+    #[test]
+    fn bytes_round_trip() {
+        let mut movie = record(5);
+        movie.push_event(2, "Return", true);
+        movie.push_event(4, "Return", false);
+        movie.start_state = Some(vec![9, 8, 7]);
+        movie.add_media("tape", 3, &[4, 5]);
+
+        let bytes = movie.to_bytes();
+        assert_eq!(Movie::from_bytes(&bytes), Ok(movie));
+        assert_eq!(
+            Movie::from_bytes(&bytes[..bytes.len() - 1]),
+            Err(MovieError::State(StateError::UnexpectedEof))
+        );
+        assert_eq!(Movie::from_bytes(b"E8XS"), Err(MovieError::BadMagic));
+        let mut old = bytes;
+        old[4..6].copy_from_slice(&1u16.to_le_bytes());
+        assert_eq!(
+            Movie::from_bytes(&old),
+            Err(MovieError::UnsupportedVersion(1))
+        );
+    }
+
+    #[test]
+    fn verify_reports_first_divergent_frame() {
+        let movie = record(10);
+        assert_eq!(movie.verify(&mut Counter::new()), None);
+
+        let mut machine = Counter::new();
+        machine.glitch_at = Some(7);
+        let divergence = movie.verify(&mut machine).expect("divergence");
+        assert_eq!(divergence.frame, 7);
+        assert_eq!(divergence.expected, frame_hash(&[7]));
+        assert_eq!(machine.frames, 7);
+    }
+
+    #[test]
+    fn header_check_names_the_mismatch() {
+        let movie = record(0);
+        let matching = Movie::new("counter")
+            .with_config("model", "test")
+            .with_media("rom", &[1, 2, 3]);
+        assert_eq!(movie.check_header(&matching), Ok(()));
+
+        let other_model = Movie::new("counter").with_config("model", "other");
+        assert_eq!(
+            movie.check_header(&other_model),
+            Err(MovieError::ConfigMismatch {
+                key: "model".into(),
+                expected: "other".into(),
+                found: "test".into(),
+            })
+        );
+
+        let other_rom = Movie::new("counter").with_media("rom", &[1, 2, 4]);
+        assert_eq!(
+            movie.check_header(&other_rom),
+            Err(MovieError::MediaMismatch { slot: "rom".into() })
+        );
+        assert!(movie.check_media("rom", &[1, 2, 3]).is_ok());
+        assert!(movie.check_media("tape", &[1, 2, 3]).is_err());
+    }
+
+    #[test]
+    fn loaded_media_is_matched_by_slot_and_hash() {
+        let header = Movie::new("counter").with_media("rom", &[1, 2, 3]);
+        let mut movie = record(0);
+        movie.add_media("tape", 40, &[7, 7]);
+
+        let tape: &[u8] = &[7, 7];
+        let media = [("tape", tape)];
+        let loads = movie.media_loads(&header, &media).expect("loads");
+        assert_eq!(loads, vec![(40, 0)]);
+
+        let mismatch = Err(MovieError::MediaMismatch {
+            slot: "tape".into(),
+        });
+        let other: &[u8] = &[7, 8];
+        assert_eq!(movie.media_loads(&header, &[("tape", other)]), mismatch);
+        let none: &[(&str, &[u8])] = &[];
+        assert_eq!(movie.media_loads(&header, none), mismatch);
+
+        // Side B goes in later; each load finds its own file.
+        movie.add_media("tape", 90, &[7, 8]);
+        assert_eq!(movie.media_loads(&header, &media), mismatch);
+        let both = [("tape", other), ("tape", tape)];
+        let loads = movie.media_loads(&header, &both).expect("loads");
+        assert_eq!(loads, vec![(40, 1), (90, 0)]);
+        assert!(movie.check_media("tape", &[7, 7]).is_ok());
+        assert!(movie.check_media("tape", &[7, 9]).is_err());
+    }
+}
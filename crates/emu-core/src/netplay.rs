@@ -0,0 +1,736 @@
+//! Rollback netplay over UDP.
+//!
+//! Two copies of the same machine, one on each side of the link, run the
+//! same frames with the same controller input. Each side sends its own
+//! controller state for every frame to the other. Rather than wait for the
+//! peer's input to arrive, a [`Session`] guesses it (the peer's last known
+//! input, since buttons are usually held for many frames) and runs on.
+//! It keeps a snapshot from the start of every frame whose remote input is
+//! still unconfirmed. When the real input arrives and differs from the
+//! guess, it loads the snapshot from the first wrong frame and runs forward
+//! again with the corrected input. The machines are deterministic, so both
+//! sides end up in the same state.
+//!
+//! Local input takes effect a few frames late
+//! ([`SessionConfig::input_delay`]), which hides most of the round trip,
+//! so rollbacks are rare and short. A session never runs more than
+//! [`SessionConfig::max_rollback`] frames past the last confirmed remote
+//! input; beyond that it stalls until the peer catches up.
+//!
+//! Every packet repeats all the input the peer has not yet acknowledged,
+//! so a lost datagram is covered by the next one. Nothing here knows about
+//! any system's controllers: each machine implements [`NetplayInput`] to
+//! map a per-player button mask onto its own ports.
+
+use std::collections::{BTreeMap, VecDeque};
+use std::fmt;
+use std::io::{self, ErrorKind};
+use std::net::{SocketAddr, UdpSocket};
+use std::time::{Duration, Instant};
+
+use crate::{Machine, StateError};
+
+/// Magic bytes at the start of every netplay packet.
+pub const PACKET_MAGIC: [u8; 4] = *b"E8XN";
+
+/// Current packet format version.
+pub const PROTOCOL_VERSION: u8 = 1;
+
+/// Magic, version, player, start hash, frame, ack, first input frame and
+/// input count.
+const HEADER_LEN: usize = 4 + 1 + 1 + 8 + 8 + 8 + 8 + 1;
+
+/// Most inputs one packet carries.
+const MAX_INPUTS: usize = 255;
+
+/// A machine whose controllers a netplay session can drive.
+pub trait NetplayInput: Machine {
+    /// Button names for the mask `set_player_input` takes, bit 0 first.
+    fn netplay_buttons(&self) -> &'static [&'static str];
+
+    /// Set player `player`'s (0 or 1) controller to `buttons`, one bit per
+    /// pressed button in `netplay_buttons()` order.
+    fn set_player_input(&mut self, player: usize, buttons: u16);
+}
+
+/// How a session runs.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct SessionConfig {
+    /// Which player this side controls, 0 or 1.
+    pub local_player: usize,
+    /// Frames between reading local input and applying it. The two sides
+    /// may use different delays.
+    pub input_delay: u32,
+    /// Most frames to run ahead of the peer's confirmed input. Zero waits
+    /// for the real input every frame and never rolls back.
+    pub max_rollback: u32,
+    /// How long to wait for the peer while stalled before giving up.
+    pub timeout: Duration,
+}
+
+impl SessionConfig {
+    /// Defaults for `local_player`: two frames of input delay, up to eight
+    /// frames of rollback, and a five-second timeout.
+    #[must_use]
+    pub fn new(local_player: usize) -> Self {
+        Self {
+            local_player,
+            input_delay: 2,
+            max_rollback: 8,
+            timeout: Duration::from_secs(5),
+        }
+    }
+}
+
+/// Errors raised by a netplay session.
+#[derive(Debug)]
+pub enum NetplayError {
+    /// The socket failed.
+    Io(io::Error),
+    /// The machine could not save or restore a snapshot.
+    State(StateError),
+    /// `local_player` is not 0 or 1.
+    InvalidPlayer(usize),
+    /// The peer started from a different state: other media, another
+    /// model, or a different save state.
+    StateMismatch,
+    /// The peer controls the same player as this side.
+    SamePlayer(usize),
+    /// Nothing was heard from the peer for `timeout` while stalled.
+    Timeout,
+}
+
+impl fmt::Display for NetplayError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::Io(e) => write!(f, "netplay socket: {e}"),
+            Self::State(e) => write!(f, "{e}"),
+            Self::InvalidPlayer(p) => write!(f, "player {p} is not 0 or 1"),
+            Self::StateMismatch => write!(f, "peer started from a different machine state"),
+            Self::SamePlayer(p) => write!(f, "peer is also player {p}"),
+            Self::Timeout => write!(f, "peer stopped responding"),
+        }
+    }
+}
+
+impl std::error::Error for NetplayError {}
+
+impl From<io::Error> for NetplayError {
+    fn from(e: io::Error) -> Self {
+        Self::Io(e)
+    }
+}
+
+impl From<StateError> for NetplayError {
+    fn from(e: StateError) -> Self {
+        Self::State(e)
+    }
+}
+
+/// What [`Session::advance_frame`] did.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Advance {
+    /// The frame ran, after replaying `rolled_back` earlier frames with
+    /// corrected remote input.
+    Ran { rolled_back: u32 },
+    /// Too far ahead of the peer's confirmed input: the frame did not run
+    /// and the local input was queued. Call again next frame.
+    Stalled,
+}
+
+/// One side of a two-player rollback session.
+pub struct Session {
+    socket: UdpSocket,
+    peer: SocketAddr,
+    config: SessionConfig,
+    /// Hash of the machine state the session started from.
+    start_hash: u64,
+    /// Next frame to run, counted from the start of the session.
+    frame: u64,
+    /// Local input by frame, from the oldest the peer or a rollback may
+    /// still need.
+    local: BTreeMap<u64, u16>,
+    /// Remote input received, from the oldest a rollback may still need.
+    remote: BTreeMap<u64, u16>,
+    /// Remote input is known for every frame before this one.
+    confirmed: u64,
+    /// Remote input for frame `confirmed - 1`, the basis for guesses.
+    last_confirmed_input: u16,
+    /// Remote input guessed for frames that ran before it arrived.
+    predicted: BTreeMap<u64, u16>,
+    /// Earliest frame that ran on a wrong guess.
+    mispredicted: Option<u64>,
+    /// Snapshots from the start of each frame from `snapshots_base` on.
+    snapshots: VecDeque<Vec<u8>>,
+    snapshots_base: u64,
+    /// The peer has all local input before this frame.
+    peer_ack: u64,
+    /// Latest frame the peer reported reaching.
+    peer_frame: u64,
+    last_heard: Instant,
+    rollbacks: u64,
+    resimulated: u64,
+}
+
+impl Session {
+    /// Start a session on `socket` with the peer at `peer`.
+    ///
+    /// Both sides must start from the same machine state, with the same
+    /// media and model. The session hashes a snapshot of `machine` and
+    /// refuses to run against a peer whose hash differs.
+    pub fn new<M: NetplayInput + ?Sized>(
+        socket: UdpSocket,
+        peer: SocketAddr,
+        config: SessionConfig,
+        machine: &M,
+    ) -> Result<Self, NetplayError> {
+        if config.local_player > 1 {
+            return Err(NetplayError::InvalidPlayer(config.local_player));
+        }
+        socket.set_nonblocking(true)?;
+        let start_hash = state_hash(&machine.save_state()?);
+        // Input delay frames have nothing pressed; they go out like any others.
+        let local = (0..u64::from(config.input_delay)).map(|f| (f, 0)).collect();
+        Ok(Self {
+            socket,
+            peer,
+            config,
+            start_hash,
+            frame: 0,
+            local,
+            remote: BTreeMap::new(),
+            confirmed: 0,
+            last_confirmed_input: 0,
+            predicted: BTreeMap::new(),
+            mispredicted: None,
+            snapshots: VecDeque::new(),
+            snapshots_base: 0,
+            peer_ack: 0,
+            peer_frame: 0,
+            last_heard: Instant::now(),
+            rollbacks: 0,
+            resimulated: 0,
+        })
+    }
+
+    /// The session's settings.
+    #[must_use]
+    pub fn config(&self) -> &SessionConfig {
+        &self.config
+    }
+
+    /// The peer's address.
+    #[must_use]
+    pub fn peer(&self) -> SocketAddr {
+        self.peer
+    }
+
+    /// Frames run since the session started.
+    #[must_use]
+    pub fn frame(&self) -> u64 {
+        self.frame
+    }
+
+    /// Remote input is known for every frame before this one.
+    #[must_use]
+    pub fn confirmed_frame(&self) -> u64 {
+        self.confirmed
+    }
+
+    /// How many frames this side is ahead of the peer, as of the peer's
+    /// last packet. A frontend that sees this stay above 1 should run
+    /// slightly slower so the peer can catch up.
+    #[must_use]
+    pub fn frame_advantage(&self) -> i64 {
+        self.frame as i64 - self.peer_frame as i64
+    }
+
+    /// Number of times a wrong guess forced a rollback.
+    #[must_use]
+    pub fn rollbacks(&self) -> u64 {
+        self.rollbacks
+    }
+
+    /// Total frames replayed by rollbacks.
+    #[must_use]
+    pub fn resimulated_frames(&self) -> u64 {
+        self.resimulated
+    }
+
+    /// Run the next frame with `input` as this side's controller state.
+    ///
+    /// `input` takes effect `input_delay` frames from now. Audio from frames
+    /// replayed by a rollback has already been heard and is discarded, so
+    /// drain the machine's audio after every call.
+    pub fn advance_frame<M: NetplayInput + ?Sized>(
+        &mut self,
+        machine: &mut M,
+        input: u16,
+    ) -> Result<Advance, NetplayError> {
+        self.local
+            .entry(self.frame + u64::from(self.config.input_delay))
+            .or_insert(input);
+        self.receive()?;
+        let rolled_back = self.resolve(machine)?;
+        if self.frame >= self.confirmed + u64::from(self.config.max_rollback) {
+            self.send()?;
+            if self.last_heard.elapsed() > self.config.timeout {
+                return Err(NetplayError::Timeout);
+            }
+            return Ok(Advance::Stalled);
+        }
+        self.run(machine)?;
+        self.trim();
+        self.send()?;
+        Ok(Advance::Ran { rolled_back })
+    }
+
+    /// Exchange packets and apply any corrections without running a new
+    /// frame, as when paused or waiting for the peer to finish. Returns
+    /// the number of frames replayed.
+    pub fn poll<M: NetplayInput + ?Sized>(&mut self, machine: &mut M) -> Result<u32, NetplayError> {
+        self.receive()?;
+        let rolled_back = self.resolve(machine)?;
+        self.trim();
+        self.send()?;
+        Ok(rolled_back)
+    }
+
+    /// Snapshot the machine, then run one frame with both players' input.
+    fn run<M: NetplayInput + ?Sized>(&mut self, machine: &mut M) -> Result<(), NetplayError> {
+        if self.snapshots.is_empty() {
+            self.snapshots_base = self.frame;
+        }
+        self.snapshots.push_back(machine.save_state()?);
+        let local = self.local.get(&self.frame).copied().unwrap_or(0);
+        let remote = if let Some(&input) = self.remote.get(&self.frame) {
+            input
+        } else {
+            self.predicted.insert(self.frame, self.last_confirmed_input);
+            self.last_confirmed_input
+        };
+        let player = self.config.local_player;
+        machine.set_player_input(player, local);
+        machine.set_player_input(1 - player, remote);
+        machine.run_frame();
+        self.frame += 1;
+        Ok(())
+    }
+
+    /// Roll back to the earliest wrong guess and replay up to the present.
+    fn resolve<M: NetplayInput + ?Sized>(&mut self, machine: &mut M) -> Result<u32, NetplayError> {
+        let Some(from) = self.mispredicted.take() else {
+            return Ok(0);
+        };
+        let index = (from - self.snapshots_base) as usize;
+        let Some(snapshot) = self.snapshots.get(index) else {
+            return Err(
+                StateError::Invalid(format!("no netplay snapshot for frame {from}")).into(),
+            );
+        };
+        machine.load_state(snapshot)?;
+        self.snapshots.truncate(index);
+        let end = self.frame;
+        self.frame = from;
+        while self.frame < end {
+            self.run(machine)?;
+            drop(machine.take_audio_buffer());
+        }
+        self.rollbacks += 1;
+        self.resimulated += end - from;
+        Ok((end - from) as u32)
+    }
+
+    /// Drop snapshots and input no rollback or resend can need.
+    fn trim(&mut self) {
+        let settled = self.confirmed.min(self.frame);
+        while self.snapshots_base < settled && self.snapshots.pop_front().is_some() {
+            self.snapshots_base += 1;
+        }
+        self.remote = self.remote.split_off(&settled);
+        self.local = self.local.split_off(&settled.min(self.peer_ack));
+    }
+
+    /// Read every waiting packet.
+    fn receive(&mut self) -> Result<(), NetplayError> {
+        let mut buf = [0u8; HEADER_LEN + MAX_INPUTS * 2];
+        loop {
+            match self.socket.recv_from(&mut buf) {
+                Ok((len, from)) if from == self.peer => {
+                    if let Some(packet) = Packet::decode(&buf[..len]) {
+                        self.accept(&packet)?;
+                    }
+                }
+                Ok(_) => {}
+                Err(e) if e.kind() == ErrorKind::WouldBlock => return Ok(()),
+                // An earlier send found nobody listening yet.
+                Err(e)
+                    if matches!(
+                        e.kind(),
+                        ErrorKind::ConnectionReset | ErrorKind::ConnectionRefused
+                    ) => {}
+                Err(e) => return Err(e.into()),
+            }
+        }
+    }
+
+    /// Take in the peer's input, noting the first frame guessed wrongly.
+    fn accept(&mut self, packet: &Packet) -> Result<(), NetplayError> {
+        if packet.start_hash != self.start_hash {
+            return Err(NetplayError::StateMismatch);
+        }
+        if usize::from(packet.player) == self.config.local_player {
+            return Err(NetplayError::SamePlayer(self.config.local_player));
+        }
+        self.last_heard = Instant::now();
+        self.peer_frame = self.peer_frame.max(packet.frame);
+        self.peer_ack = self.peer_ack.max(packet.ack);
+        for (frame, &input) in (packet.first..).zip(&packet.inputs) {
+            if frame >= self.confirmed {
+                self.remote.entry(frame).or_insert(input);
+            }
+        }
+        while let Some(&input) = self.remote.get(&self.confirmed) {
+            if self
+                .predicted
+                .remove(&self.confirmed)
+                .is_some_and(|guess| guess != input)
+            {
+                let first = self
+                    .mispredicted
+                    .map_or(self.confirmed, |f| f.min(self.confirmed));
+                self.mispredicted = Some(first);
+            }
+            self.last_confirmed_input = input;
+            self.confirmed += 1;
+        }
+        Ok(())
+    }
+
+    /// Send every local input the peer has not acknowledged.
+    fn send(&mut self) -> Result<(), NetplayError> {
+        let packet = Packet {
+            player: self.config.local_player as u8,
+            start_hash: self.start_hash,
+            frame: self.frame,
+            ack: self.confirmed,
+            first: self.peer_ack,
+            inputs: self
+                .local
+                .range(self.peer_ack..)
+                .take(MAX_INPUTS)
+                .map(|(_, &input)| input)
+                .collect(),
+        };
+        match self.socket.send_to(&packet.encode(), self.peer) {
+            Ok(_) => Ok(()),
+            // A dropped packet is resent with the next one.
+            Err(e)
+                if matches!(
+                    e.kind(),
+                    ErrorKind::WouldBlock
+                        | ErrorKind::ConnectionReset
+                        | ErrorKind::ConnectionRefused
+                ) =>
+            {
+                Ok(())
+            }
+            Err(e) => Err(e.into()),
+        }
+    }
+}
+
+/// One datagram: the sender's progress and a run of its input.
+#[derive(Debug, Clone, PartialEq, Eq)]
+struct Packet {
+    /// Player the sender controls.
+    player: u8,
+    /// Hash of the state the sender started from.
+    start_hash: u64,
+    /// Next frame the sender will run.
+    frame: u64,
+    /// The sender has the receiver's input for every frame before this.
+    ack: u64,
+    /// Frame of `inputs[0]`.
+    first: u64,
+    inputs: Vec<u16>,
+}
+
+impl Packet {
+    fn encode(&self) -> Vec<u8> {
+        let mut out = Vec::with_capacity(HEADER_LEN + self.inputs.len() * 2);
+        out.extend_from_slice(&PACKET_MAGIC);
+        out.push(PROTOCOL_VERSION);
+        out.push(self.player);
+        for v in [self.start_hash, self.frame, self.ack, self.first] {
+            out.extend_from_slice(&v.to_le_bytes());
+        }
+        out.push(self.inputs.len().min(MAX_INPUTS) as u8);
+        for input in self.inputs.iter().take(MAX_INPUTS) {
+            out.extend_from_slice(&input.to_le_bytes());
+        }
+        out
+    }
+
+    /// Parse a datagram, or `None` if it is not a packet this version sent.
+    fn decode(data: &[u8]) -> Option<Self> {
+        if data.len() < HEADER_LEN || data[..4] != PACKET_MAGIC || data[4] != PROTOCOL_VERSION {
+            return None;
+        }
+        let u64_at = |at: usize| data[at..at + 8].try_into().ok().map(u64::from_le_bytes);
+        let count = usize::from(data[HEADER_LEN - 1]);
+        let body = data.get(HEADER_LEN..HEADER_LEN + count * 2)?;
+        Some(Self {
+            player: data[5],
+            start_hash: u64_at(6)?,
+            frame: u64_at(14)?,
+            ack: u64_at(22)?,
+            first: u64_at(30)?,
+            inputs: body
+                .chunks_exact(2)
+                .map(|pair| u16::from_le_bytes([pair[0], pair[1]]))
+                .collect(),
+        })
+    }
+}
+
+/// 64-bit FNV-1a hash of a snapshot.
+fn state_hash(state: &[u8]) -> u64 {
+    state.iter().fold(0xCBF2_9CE4_8422_2325, |hash, &byte| {
+        (hash ^ u64::from(byte)).wrapping_mul(0x0000_0100_0000_01B3)
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{AudioFrame, StateReader, StateWriter};
+
+    /// A machine whose state folds in every frame's input, so any input
+    /// applied to the wrong frame changes the result.
+    struct Mixer {
+        acc: u64,
+        frames: u64,
+        inputs: [u16; 2],
+    }
+
+    impl Mixer {
+        fn new(seed: u64) -> Self {
+            Self {
+                acc: seed,
+                frames: 0,
+                inputs: [0; 2],
+            }
+        }
+    }
+
+    impl Machine for Mixer {
+        fn run_frame(&mut self) {
+            let mixed = u64::from(self.inputs[0]) << 16 | u64::from(self.inputs[1]);
+            self.acc = self.acc.wrapping_mul(0x0000_0100_0000_01B3) ^ mixed;
+            self.frames += 1;
+        }
+
+        fn framebuffer(&self) -> &[u32] {
+            &[]
+        }
+
+        fn framebuffer_width(&self) -> u32 {
+            0
+        }
+
+        fn framebuffer_height(&self) -> u32 {
+            0
+        }
+
+        fn take_audio_buffer(&mut self) -> Vec<AudioFrame> {
+            Vec::new()
+        }
+
+        fn frame_count(&self) -> u64 {
+            self.frames
+        }
+
+        fn reset(&mut self) {}
+
+        fn save_state(&self) -> Result<Vec<u8>, StateError> {
+            let mut w = StateWriter::with_header("mixer");
+            w.write_u64(self.acc);
+            w.write_u64(self.frames);
+            Ok(w.into_bytes())
+        }
+
+        fn load_state(&mut self, data: &[u8]) -> Result<(), StateError> {
+            let mut r = StateReader::with_header(data, "mixer")?;
+            self.acc = r.read_u64()?;
+            self.frames = r.read_u64()?;
+            r.finish()
+        }
+    }
+
+    impl NetplayInput for Mixer {
+        fn netplay_buttons(&self) -> &'static [&'static str] {
+            &["A", "B"]
+        }
+
+        fn set_player_input(&mut self, player: usize, buttons: u16) {
+            self.inputs[player] = buttons;
+        }
+    }
+
+    /// Input that changes every few frames, differently for each player.
+    fn pad(player: usize, frame: u64) -> u16 {
+        let period = [5, 3][player];
+        ((frame / period).wrapping_mul(0x9E37) >> 3) as u16 & 0xFF
+    }
+
+    fn loopback_pair(
+        a: SessionConfig,
+        b: SessionConfig,
+        machine_a: &Mixer,
+        machine_b: &Mixer,
+    ) -> (Session, Session) {
+        let socket_a = UdpSocket::bind("127.0.0.1:0").expect("bind");
+        let socket_b = UdpSocket::bind("127.0.0.1:0").expect("bind");
+        let addr_a = socket_a.local_addr().expect("addr");
+        let addr_b = socket_b.local_addr().expect("addr");
+        (
+            Session::new(socket_a, addr_b, a, machine_a).expect("session"),
+            Session::new(socket_b, addr_a, b, machine_b).expect("session"),
+        )
+    }
+
+    #[test]
+    fn loopback_sessions_match_offline_play() {
+        const FRAMES: u64 = 120;
+        let config_a = SessionConfig::new(0);
+        let config_b = SessionConfig {
+            input_delay: 1,
+            ..SessionConfig::new(1)
+        };
+        let (mut machine_a, mut machine_b) = (Mixer::new(7), Mixer::new(7));
+        let (mut a, mut b) = loopback_pair(config_a, config_b, &machine_a, &machine_b);
+
+        // A runs three frames for every two of B's, so A keeps guessing
+        // B's input before it arrives.
+        for _ in 0..10_000 {
+            for (session, machine, player, steps) in [
+                (&mut a, &mut machine_a, 0, 3),
+                (&mut b, &mut machine_b, 1, 2),
+            ] {
+                for _ in 0..steps {
+                    if session.frame() < FRAMES {
+                        let input = pad(player, session.frame());
+                        session.advance_frame(machine, input).expect("advance");
+                    } else {
+                        session.poll(machine).expect("poll");
+                    }
+                }
+            }
+            if [&a, &b]
+                .iter()
+                .all(|s| s.frame() == FRAMES && s.confirmed_frame() >= FRAMES)
+            {
+                break;
+            }
+        }
+        assert!(a.confirmed_frame() >= FRAMES && b.confirmed_frame() >= FRAMES);
+        assert!(a.rollbacks() > 0, "A never had to correct a guess");
+
+        let mut offline = Mixer::new(7);
+        let delays = [config_a.input_delay, config_b.input_delay].map(u64::from);
+        for frame in 0..FRAMES {
+            for (player, &delay) in delays.iter().enumerate() {
+                let input = frame
+                    .checked_sub(delay)
+                    .map_or(0, |read_at| pad(player, read_at));
+                offline.set_player_input(player, input);
+            }
+            offline.run_frame();
+        }
+        assert_eq!(machine_a.acc, offline.acc);
+        assert_eq!(machine_b.acc, offline.acc);
+    }
+
+    #[test]
+    fn runs_ahead_at_most_max_rollback_frames() {
+        let mut machine = Mixer::new(1);
+        let socket = UdpSocket::bind("127.0.0.1:0").expect("bind");
+        // Nobody answers at the peer address.
+        let peer = UdpSocket::bind("127.0.0.1:0").expect("bind");
+        let config = SessionConfig {
+            max_rollback: 3,
+            ..SessionConfig::new(0)
+        };
+        let mut session = Session::new(socket, peer.local_addr().expect("addr"), config, &machine)
+            .expect("session");
+        for _ in 0..3 {
+            assert_eq!(
+                session.advance_frame(&mut machine, 1).expect("advance"),
+                Advance::Ran { rolled_back: 0 }
+            );
+        }
+        assert_eq!(
+            session.advance_frame(&mut machine, 1).expect("advance"),
+            Advance::Stalled
+        );
+        assert_eq!(machine.frames, 3);
+    }
+
+    #[test]
+    fn later_packets_cover_lost_ones() {
+        let (mut machine_a, mut machine_b) = (Mixer::new(3), Mixer::new(3));
+        let (mut a, mut b) = loopback_pair(
+            SessionConfig::new(0),
+            SessionConfig::new(1),
+            &machine_a,
+            &machine_b,
+        );
+        for _ in 0..4 {
+            a.advance_frame(&mut machine_a, 5).expect("advance");
+        }
+        // Lose everything A has sent so far.
+        let mut buf = [0u8; 1024];
+        std::thread::sleep(Duration::from_millis(20));
+        while b.socket.recv_from(&mut buf).is_ok() {}
+
+        a.advance_frame(&mut machine_a, 5).expect("advance");
+        std::thread::sleep(Duration::from_millis(20));
+        b.poll(&mut machine_b).expect("poll");
+        // Frames 0-6: two frames of delay, then five of input.
+        assert_eq!(b.confirmed_frame(), 7);
+    }
+
+    #[test]
+    fn mismatched_start_states_are_refused() {
+        let (mut machine_a, mut machine_b) = (Mixer::new(1), Mixer::new(2));
+        let (mut a, mut b) = loopback_pair(
+            SessionConfig::new(0),
+            SessionConfig::new(1),
+            &machine_a,
+            &machine_b,
+        );
+        a.advance_frame(&mut machine_a, 0).expect("advance");
+        std::thread::sleep(Duration::from_millis(20));
+        assert!(matches!(
+            b.advance_frame(&mut machine_b, 0),
+            Err(NetplayError::StateMismatch)
+        ));
+    }
+
+    #[test]
+    fn packets_round_trip() {
+        let packet = Packet {
+            player: 1,
+            start_hash: 0x0123_4567_89AB_CDEF,
+            frame: 42,
+            ack: 40,
+            first: 38,
+            inputs: vec![0x00FF, 0x1234, 0],
+        };
+        let bytes = packet.encode();
+        assert_eq!(bytes.len(), HEADER_LEN + 6);
+        assert_eq!(Packet::decode(&bytes), Some(packet));
+        assert_eq!(Packet::decode(&bytes[..HEADER_LEN + 5]), None);
+    }
+}
@@ -0,0 +1,651 @@
+//! Sound-chip register logs and chip-music export.
+//!
+//! A [`RegisterLog`] records every write to a sound chip, stamped with the
+//! chip's input-clock cycle. The chip owns the log while it runs: it calls
+//! [`RegisterLog::tick`] once per input clock and [`RegisterLog::record`]
+//! for each write, and seeds the log with its current register state when
+//! logging starts, so the log plays back correctly from its first cycle.
+//!
+//! A finished log exports to the standard formats for its chip:
+//!
+//! | Chip       | Formats                         |
+//! | ---------- | ------------------------------- |
+//! | SN76489    | VGM                             |
+//! | AY-3-8910  | VGM, YM5, YM6                   |
+//! | SID        | PSID, text register dump        |
+//!
+//! VGM keeps the timing exact to 1/44100 s. YM and PSID play one register
+//! frame per video frame, so writes are grouped by [`RegisterLog::frame_cycles`].
+//! The text dump keeps every cycle and reads back with
+//! [`RegisterLog::parse_sid_dump`], so a dump can be turned into a PSID later.
+
+use std::fmt::Write;
+
+/// The chip a log was taken from. Decides which formats it exports to.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum LoggedChip {
+    /// TI SN76489 PSG. Register 0 is the data port, 1 the Game Gear
+    /// stereo register.
+    Sn76489,
+    /// GI AY-3-8910 PSG. Registers 0–13.
+    Ay38910,
+    /// MOS 6581 SID. Registers $00–$18.
+    Sid6581,
+    /// MOS 8580 SID. Registers $00–$18.
+    Sid8580,
+}
+
+impl LoggedChip {
+    /// Name used in dumps and tool responses.
+    #[must_use]
+    pub fn name(self) -> &'static str {
+        match self {
+            Self::Sn76489 => "sn76489",
+            Self::Ay38910 => "ay-3-8910",
+            Self::Sid6581 => "6581",
+            Self::Sid8580 => "8580",
+        }
+    }
+
+    /// Export formats this chip supports, default first.
+    #[must_use]
+    pub fn formats(self) -> &'static [&'static str] {
+        match self {
+            Self::Sn76489 => &["vgm"],
+            Self::Ay38910 => &["vgm", "ym6", "ym5"],
+            Self::Sid6581 | Self::Sid8580 => &["psid", "sid_dump"],
+        }
+    }
+
+    fn is_sid(self) -> bool {
+        matches!(self, Self::Sid6581 | Self::Sid8580)
+    }
+}
+
+/// One register write.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct RegisterWrite {
+    /// Chip input-clock cycles since logging started.
+    pub cycle: u64,
+    pub register: u8,
+    pub value: u8,
+}
+
+/// Song details written into the file headers that have room for them.
+#[derive(Debug, Clone, Default)]
+pub struct SongInfo {
+    pub title: String,
+    pub author: String,
+    /// Release or copyright line (PSID `released`, VGM date, YM comment).
+    pub released: String,
+}
+
+/// Register writes to one sound chip, with cycle timestamps.
+#[derive(Debug, Clone)]
+pub struct RegisterLog {
+    chip: LoggedChip,
+    clock_hz: u32,
+    frame_cycles: u64,
+    cycle: u64,
+    writes: Vec<RegisterWrite>,
+}
+
+/// VGM sample rate: every VGM timestamp is in 1/44100 s.
+const VGM_RATE: u64 = 44_100;
+
+/// PSID player load address. The player's `JMP` is absolute, so the
+/// image only runs here.
+const PSID_LOAD: u16 = 0x1000;
+
+/// Frame data must end before the I/O area at $D000.
+const PSID_END: usize = 0xD000;
+
+/// Player for PSID export: `init` points $FB/$FC at the frame data and
+/// `play` writes one frame's `(register, value)` pairs to $D400. A frame
+/// ends with $FF and the song with $FE, which `play` never steps past.
+const PSID_PLAYER: [u8; 0x2B] = [
+    0xA9, 0x00, //       init: LDA #<data (patched)
+    0x85, 0xFB, //             STA $FB
+    0xA9, 0x00, //             LDA #>data (patched)
+    0x85, 0xFC, //             STA $FC
+    0x60, //                   RTS
+    0xA0, 0x00, //       play: LDY #0
+    0xB1, 0xFB, //       next: LDA ($FB),Y
+    0xC9, 0xFE, //             CMP #$FE
+    0xF0, 0x19, //             BEQ done
+    0xC8, //                   INY
+    0xC9, 0xFF, //             CMP #$FF
+    0xF0, 0x0A, //             BEQ step
+    0xAA, //                   TAX
+    0xB1, 0xFB, //             LDA ($FB),Y
+    0xC8, //                   INY
+    0x9D, 0x00, 0xD4, //       STA $D400,X
+    0x4C, 0x0B, 0x10, //       JMP next
+    0x98, //             step: TYA
+    0x18, //                   CLC
+    0x65, 0xFB, //             ADC $FB
+    0x85, 0xFB, //             STA $FB
+    0x90, 0x02, //             BCC done
+    0xE6, 0xFC, //             INC $FC
+    0x60, //             done: RTS
+];
+
+/// Offset of `play` within [`PSID_PLAYER`].
+const PSID_PLAY_OFFSET: u16 = 0x09;
+
+/// A frame's writes must fit the player's 8-bit index.
+const PSID_MAX_PAIRS: usize = 127;
+
+/// Bits each AY register really has. YM5/YM6 use the spare bits for
+/// effects, so they must be clear.
+const AY_MASKS: [u8; 14] = [
+    0xFF, 0x0F, 0xFF, 0x0F, 0xFF, 0x0F, 0x1F, 0xFF, 0x1F, 0x1F, 0x1F, 0xFF, 0xFF, 0x0F,
+];
+
+impl RegisterLog {
+    /// Start an empty log for `chip` clocked at `clock_hz`.
+    ///
+    /// Frames default to 1/50 s; machines set their real frame length
+    /// with [`Self::with_frame_cycles`].
+    #[must_use]
+    pub fn new(chip: LoggedChip, clock_hz: u32) -> Self {
+        Self {
+            chip,
+            clock_hz,
+            frame_cycles: u64::from(clock_hz / 50).max(1),
+            cycle: 0,
+            writes: Vec::new(),
+        }
+    }
+
+    /// Set the length of one video frame in chip cycles, for the
+    /// frame-based formats.
+    #[must_use]
+    pub fn with_frame_cycles(mut self, cycles: u64) -> Self {
+        self.frame_cycles = cycles.max(1);
+        self
+    }
+
+    #[must_use]
+    pub fn chip(&self) -> LoggedChip {
+        self.chip
+    }
+
+    /// Chip input clock in Hz.
+    #[must_use]
+    pub fn clock_hz(&self) -> u32 {
+        self.clock_hz
+    }
+
+    /// One video frame in chip cycles.
+    #[must_use]
+    pub fn frame_cycles(&self) -> u64 {
+        self.frame_cycles
+    }
+
+    /// Chip cycles logged so far.
+    #[must_use]
+    pub fn cycles(&self) -> u64 {
+        self.cycle
+    }
+
+    /// Whole or part frames logged so far.
+    #[must_use]
+    pub fn frames(&self) -> u64 {
+        let last = self.writes.last().map_or(0, |w| self.frame_of(w.cycle) + 1);
+        self.cycle.div_ceil(self.frame_cycles).max(last)
+    }
+
+    #[must_use]
+    pub fn writes(&self) -> &[RegisterWrite] {
+        &self.writes
+    }
+
+    /// Advance the log by one chip input clock.
+    pub fn tick(&mut self) {
+        self.cycle += 1;
+    }
+
+    /// Log a write at the current cycle.
+    pub fn record(&mut self, register: u8, value: u8) {
+        self.writes.push(RegisterWrite {
+            cycle: self.cycle,
+            register,
+            value,
+        });
+    }
+
+    /// Export in `format`, one of [`LoggedChip::formats`].
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the chip does not support `format` or the log
+    /// does not fit the format.
+    pub fn export(&self, format: &str, info: &SongInfo) -> Result<Vec<u8>, String> {
+        if !self.chip.formats().contains(&format) {
+            return Err(format!(
+                "A {} log cannot be saved as '{format}' (use {})",
+                self.chip.name(),
+                self.chip.formats().join(", ")
+            ));
+        }
+        match format {
+            "vgm" => Ok(self.vgm(info)),
+            "ym5" => Ok(self.ym(b"YM5!", info)),
+            "ym6" => Ok(self.ym(b"YM6!", info)),
+            "psid" => self.psid(info),
+            _ => Ok(self.sid_dump().into_bytes()),
+        }
+    }
+
+    /// Write a VGM 1.51 file: exact timing, with a GD3 tag.
+    fn vgm(&self, info: &SongInfo) -> Vec<u8> {
+        let sample = |cycle: u64| {
+            let samples = u128::from(cycle) * u128::from(VGM_RATE) / u128::from(self.clock_hz);
+            u64::try_from(samples).unwrap_or(u64::MAX)
+        };
+
+        let mut out = vec![0u8; 0x80];
+        let mut at = 0;
+        for write in &self.writes {
+            let now = sample(write.cycle);
+            vgm_wait(&mut out, now - at);
+            at = now;
+            match (self.chip, write.register) {
+                (LoggedChip::Sn76489, 0) => out.extend_from_slice(&[0x50, write.value]),
+                (LoggedChip::Sn76489, _) => out.extend_from_slice(&[0x4F, write.value]),
+                _ => out.extend_from_slice(&[0xA0, write.register, write.value]),
+            }
+        }
+        let total = sample(self.cycle);
+        vgm_wait(&mut out, total - at);
+        out.push(0x66);
+
+        let gd3_at = out.len();
+        out.extend_from_slice(&gd3(info));
+
+        out[0..4].copy_from_slice(b"Vgm ");
+        let eof = len_u32(out.len()) - 4;
+        put_u32_le(&mut out, 0x04, eof);
+        put_u32_le(&mut out, 0x08, 0x151);
+        put_u32_le(&mut out, 0x14, len_u32(gd3_at) - 0x14);
+        put_u32_le(&mut out, 0x18, u32::try_from(total).unwrap_or(u32::MAX));
+        put_u32_le(
+            &mut out,
+            0x24,
+            u32::try_from(self.frame_rate()).unwrap_or(0),
+        );
+        put_u32_le(&mut out, 0x34, 0x80 - 0x34);
+        if self.chip == LoggedChip::Sn76489 {
+            put_u32_le(&mut out, 0x0C, self.clock_hz);
+            // Feedback taps and shift width of this crate's SN76489 core.
+            out[0x28..0x2A].copy_from_slice(&0x0009u16.to_le_bytes());
+            out[0x2A] = 16;
+        } else {
+            put_u32_le(&mut out, 0x74, self.clock_hz);
+            out[0x78] = 0x00; // AY-3-8910
+            out[0x79] = 0x01; // legacy output
+        }
+        out
+    }
+
+    /// Write an uncompressed, interleaved YM5 or YM6 file: one 16-byte
+    /// register frame per video frame.
+    fn ym(&self, magic: &[u8; 4], info: &SongInfo) -> Vec<u8> {
+        let frames = usize::try_from(self.frames()).unwrap_or(0);
+        let mut regs = [0u8; 14];
+        let mut data = vec![[0u8; 16]; frames];
+        let mut writes = self.writes.iter().peekable();
+        for (row, frame) in data.iter_mut().zip(0..) {
+            let mut shape = 0xFF;
+            while let Some(w) = writes.next_if(|w| self.frame_of(w.cycle) == frame) {
+                let reg = usize::from(w.register);
+                if reg < regs.len() {
+                    regs[reg] = w.value & AY_MASKS[reg];
+                    if reg == 13 {
+                        shape = regs[13];
+                    }
+                }
+            }
+            row[..14].copy_from_slice(&regs);
+            // Register 13 is only written when the song retriggers the envelope.
+            row[13] = shape;
+        }
+
+        let mut out = Vec::new();
+        out.extend_from_slice(magic);
+        out.extend_from_slice(b"LeOnArD!");
+        out.extend_from_slice(&len_u32(frames).to_be_bytes());
+        out.extend_from_slice(&1u32.to_be_bytes()); // interleaved
+        out.extend_from_slice(&0u16.to_be_bytes()); // no digidrums
+        out.extend_from_slice(&self.clock_hz.to_be_bytes());
+        out.extend_from_slice(&u16::try_from(self.frame_rate()).unwrap_or(50).to_be_bytes());
+        out.extend_from_slice(&0u32.to_be_bytes()); // loop frame
+        out.extend_from_slice(&0u16.to_be_bytes()); // no extra data
+        for text in [&info.title, &info.author, &info.released] {
+            out.extend(text.bytes().filter(|&b| b != 0));
+            out.push(0);
+        }
+        for reg in 0..16 {
+            out.extend(data.iter().map(|row| row[reg]));
+        }
+        out.extend_from_slice(b"End!");
+        out
+    }
+
+    /// Write a PSID v2 file that replays the log one frame per vertical
+    /// blank.
+    ///
+    /// Writes within a frame keep their order but lose their spacing. A
+    /// frame with more writes than the player handles (sample playback)
+    /// keeps only the last write to each register.
+    fn psid(&self, info: &SongInfo) -> Result<Vec<u8>, String> {
+        let mut data = Vec::new();
+        let mut writes = self.writes.iter().peekable();
+        for frame in 0..self.frames().max(1) {
+            let mut pairs: Vec<(u8, u8)> = Vec::new();
+            while let Some(w) = writes.next_if(|w| self.frame_of(w.cycle) == frame) {
+                pairs.push((w.register, w.value));
+            }
+            if pairs.len() > PSID_MAX_PAIRS {
+                let mut last = Vec::new();
+                for (i, &(reg, _)) in pairs.iter().enumerate() {
+                    if !pairs[i + 1..].iter().any(|&(r, _)| r == reg) {
+                        last.push(pairs[i]);
+                    }
+                }
+                pairs = last;
+            }
+            for (reg, value) in pairs {
+                data.extend_from_slice(&[reg, value]);
+            }
+            data.push(0xFF);
+        }
+        data.push(0xFE);
+
+        let data_at = usize::from(PSID_LOAD) + PSID_PLAYER.len();
+        if data_at + data.len() > PSID_END {
+            return Err(format!(
+                "Log too long for a PSID: {} bytes of frame data, room for {}",
+                data.len(),
+                PSID_END - data_at
+            ));
+        }
+
+        let mut player = PSID_PLAYER;
+        let [data_lo, data_hi] = u16::try_from(data_at).unwrap_or(0).to_le_bytes();
+        player[1] = data_lo;
+        player[5] = data_hi;
+
+        let mut flags = 0u16;
+        flags |= if self.clock_hz < 1_000_000 {
+            0x04
+        } else {
+            0x08
+        };
+        flags |= if self.chip == LoggedChip::Sid8580 {
+            0x20
+        } else {
+            0x10
+        };
+
+        let mut out = Vec::with_capacity(0x7C + 2 + player.len() + data.len());
+        out.extend_from_slice(b"PSID");
+        out.extend_from_slice(&2u16.to_be_bytes());
+        out.extend_from_slice(&0x7Cu16.to_be_bytes());
+        out.extend_from_slice(&0u16.to_be_bytes()); // load address leads the data
+        out.extend_from_slice(&PSID_LOAD.to_be_bytes());
+        out.extend_from_slice(&(PSID_LOAD + PSID_PLAY_OFFSET).to_be_bytes());
+        out.extend_from_slice(&1u16.to_be_bytes()); // songs
+        out.extend_from_slice(&1u16.to_be_bytes()); // start song
+        out.extend_from_slice(&0u32.to_be_bytes()); // vertical blank speed
+        for text in [&info.title, &info.author, &info.released] {
+            let mut field = [0u8; 32];
+            for (slot, ch) in field.iter_mut().zip(text.chars()) {
+                *slot = u8::try_from(ch).unwrap_or(b'?');
+            }
+            out.extend_from_slice(&field);
+        }
+        out.extend_from_slice(&flags.to_be_bytes());
+        out.extend_from_slice(&[0, 0, 0, 0]); // relocation, second SID
+        out.extend_from_slice(&PSID_LOAD.to_le_bytes());
+        out.extend_from_slice(&player);
+        out.extend_from_slice(&data);
+        Ok(out)
+    }
+
+    /// The log as text: a `#` header, then one `cycle register value` line
+    /// per write, with register and value in hex.
+    #[must_use]
+    pub fn sid_dump(&self) -> String {
+        let mut out = String::new();
+        let _ = writeln!(out, "# chip {}", self.chip.name());
+        let _ = writeln!(out, "# clock {}", self.clock_hz);
+        let _ = writeln!(out, "# frame {}", self.frame_cycles);
+        let _ = writeln!(out, "# cycles {}", self.cycle);
+        for w in &self.writes {
+            let _ = writeln!(out, "{} {:02X} {:02X}", w.cycle, w.register, w.value);
+        }
+        out
+    }
+
+    /// Read a log back from [`Self::sid_dump`] text.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error naming the first line that does not parse.
+    pub fn parse_sid_dump(text: &str) -> Result<Self, String> {
+        let mut log = Self::new(LoggedChip::Sid6581, 985_248);
+        let mut frame_cycles = None;
+        for (n, line) in text.lines().enumerate() {
+            let bad = || format!("Line {}: cannot parse '{line}'", n + 1);
+            let line = line.trim();
+            if line.is_empty() {
+                continue;
+            }
+            if let Some(header) = line.strip_prefix('#') {
+                let mut parts = header.split_whitespace();
+                let (key, value) = (parts.next(), parts.next().unwrap_or(""));
+                match key {
+                    Some("chip") => {
+                        log.chip = match value {
+                            "6581" => LoggedChip::Sid6581,
+                            "8580" => LoggedChip::Sid8580,
+                            _ => return Err(format!("Not a SID dump (chip '{value}')")),
+                        };
+                    }
+                    Some("clock") => log.clock_hz = value.parse().map_err(|_| bad())?,
+                    Some("frame") => frame_cycles = Some(value.parse().map_err(|_| bad())?),
+                    Some("cycles") => log.cycle = value.parse().map_err(|_| bad())?,
+                    _ => {}
+                }
+                continue;
+            }
+            let mut parts = line.split_whitespace();
+            let mut field = |radix| {
+                parts
+                    .next()
+                    .and_then(|s| u64::from_str_radix(s, radix).ok())
+                    .ok_or_else(bad)
+            };
+            let cycle = field(10)?;
+            let register = u8::try_from(field(16)?).map_err(|_| bad())?;
+            let value = u8::try_from(field(16)?).map_err(|_| bad())?;
+            if log.writes.last().is_some_and(|w| w.cycle > cycle) {
+                return Err(format!("Line {}: cycles go backwards", n + 1));
+            }
+            log.writes.push(RegisterWrite {
+                cycle,
+                register,
+                value,
+            });
+        }
+        if !log.chip.is_sid() {
+            return Err("Not a SID dump".to_string());
+        }
+        log.frame_cycles = frame_cycles.unwrap_or(u64::from(log.clock_hz / 50)).max(1);
+        log.cycle = log.cycle.max(log.writes.last().map_or(0, |w| w.cycle));
+        Ok(log)
+    }
+
+    fn frame_of(&self, cycle: u64) -> u64 {
+        cycle / self.frame_cycles
+    }
+
+    /// Frames per second, rounded.
+    fn frame_rate(&self) -> u64 {
+        (u64::from(self.clock_hz) + self.frame_cycles / 2) / self.frame_cycles
+    }
+}
+
+/// Append VGM wait commands covering `samples`.
+fn vgm_wait(out: &mut Vec<u8>, mut samples: u64) {
+    while samples > 0 {
+        let step = u16::try_from(samples).unwrap_or(u16::MAX);
+        match step {
+            735 => out.push(0x62),
+            882 => out.push(0x63),
+            1..=16 => out.push(0x6F + step.to_le_bytes()[0]),
+            _ => {
+                out.push(0x61);
+                out.extend_from_slice(&step.to_le_bytes());
+            }
+        }
+        samples -= u64::from(step);
+    }
+}
+
+/// A GD3 tag: eleven UTF-16 strings, English and Japanese pairs.
+fn gd3(info: &SongInfo) -> Vec<u8> {
+    let fields = [
+        info.title.as_str(),
+        "",
+        "",
+        "",
+        "",
+        "",
+        info.author.as_str(),
+        "",
+        info.released.as_str(),
+        "emu198x",
+        "",
+    ];
+    let mut body = Vec::new();
+    for field in fields {
+        for unit in field.encode_utf16().chain([0]) {
+            body.extend_from_slice(&unit.to_le_bytes());
+        }
+    }
+    let mut out = Vec::with_capacity(12 + body.len());
+    out.extend_from_slice(b"Gd3 ");
+    out.extend_from_slice(&0x100u32.to_le_bytes());
+    out.extend_from_slice(&len_u32(body.len()).to_le_bytes());
+    out.extend_from_slice(&body);
+    out
+}
+
+fn put_u32_le(out: &mut [u8], at: usize, value: u32) {
+    out[at..at + 4].copy_from_slice(&value.to_le_bytes());
+}
+
+fn len_u32(len: usize) -> u32 {
+    u32::try_from(len).unwrap_or(u32::MAX)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn u32_le(data: &[u8], at: usize) -> u32 {
+        u32::from_le_bytes(data[at..at + 4].try_into().expect("4 bytes"))
+    }
+
+    #[test]
+    fn vgm_waits_between_writes_in_44100ths() {
+        // 3.5 MHz SN76489: 1/100 s = 35,000 cycles = 441 samples.
+        let mut log = RegisterLog::new(LoggedChip::Sn76489, 3_500_000);
+        log.record(0, 0x9F);
+        for _ in 0..35_000 {
+            log.tick();
+        }
+        log.record(0, 0x90);
+        log.record(1, 0xF0);
+        for _ in 0..35_000 {
+            log.tick();
+        }
+
+        let vgm = log.vgm(&SongInfo::default());
+        assert_eq!(&vgm[0..4], b"Vgm ");
+        assert_eq!(u32_le(&vgm, 0x04) as usize, vgm.len() - 4);
+        assert_eq!(u32_le(&vgm, 0x0C), 3_500_000);
+        assert_eq!(u32_le(&vgm, 0x18), 882);
+        assert_eq!(
+            &vgm[0x80..0x8C],
+            &[
+                0x50, 0x9F, 0x61, 0xB9, 0x01, 0x50, 0x90, 0x4F, 0xF0, 0x61, 0xB9, 0x01
+            ]
+        );
+        assert_eq!(vgm[0x8C], 0x66);
+        assert_eq!(&vgm[0x8D..0x91], b"Gd3 ");
+        assert_eq!(u32_le(&vgm, 0x14) as usize + 0x14, 0x8D);
+    }
+
+    #[test]
+    fn ym_frames_hold_registers_and_mark_envelope_writes() {
+        let mut log = RegisterLog::new(LoggedChip::Ay38910, 1_000_000).with_frame_cycles(100);
+        log.record(0, 0x34);
+        log.record(13, 0x0E);
+        for _ in 0..150 {
+            log.tick();
+        }
+        log.record(1, 0xFF); // spare bits must not reach the file
+
+        let ym = log
+            .export("ym6", &SongInfo::default())
+            .expect("AY logs export to YM6");
+        assert_eq!(&ym[0..12], b"YM6!LeOnArD!");
+        assert_eq!(u32::from_be_bytes(ym[12..16].try_into().expect("4")), 2);
+        assert_eq!(
+            u16::from_be_bytes(ym[26..28].try_into().expect("2")),
+            10_000
+        );
+        // Empty title, author and comment, then 16 registers × 2 frames.
+        let data = &ym[34 + 3..];
+        assert_eq!(&data[0..2], &[0x34, 0x34]); // R0
+        assert_eq!(&data[2..4], &[0x00, 0x0F]); // R1
+        assert_eq!(&data[26..28], &[0x0E, 0xFF]); // R13
+        assert_eq!(&data[32..], b"End!");
+    }
+
+    #[test]
+    fn sid_dump_round_trips_and_builds_a_psid() {
+        let mut log = RegisterLog::new(LoggedChip::Sid8580, 985_248).with_frame_cycles(19_656);
+        log.record(0x18, 0x0F);
+        for _ in 0..20_000 {
+            log.tick();
+        }
+        log.record(0x04, 0x11);
+
+        let parsed = RegisterLog::parse_sid_dump(&log.sid_dump()).expect("dump parses");
+        assert_eq!(parsed.chip(), LoggedChip::Sid8580);
+        assert_eq!(parsed.writes(), log.writes());
+        assert_eq!(parsed.cycles(), 20_000);
+        assert_eq!(parsed.frame_cycles(), 19_656);
+
+        let info = SongInfo {
+            title: "Test".to_string(),
+            ..SongInfo::default()
+        };
+        let psid = parsed.export("psid", &info).expect("fits in memory");
+        assert_eq!(&psid[0..4], b"PSID");
+        assert_eq!(&psid[0x0A..0x0E], &[0x10, 0x00, 0x10, 0x09]);
+        assert_eq!(&psid[0x16..0x1A], b"Test");
+        assert_eq!(&psid[0x76..0x78], &[0x00, 0x24]); // PAL, 8580
+        let data = &psid[0x7C + 2 + PSID_PLAYER.len()..];
+        assert_eq!(data, &[0x18, 0x0F, 0xFF, 0x04, 0x11, 0xFF, 0xFE]);
+        assert_eq!(&psid[0x7C + 2 + 1..0x7C + 2 + 2], &[0x2B]);
+
+        assert!(parsed.export("vgm", &info).is_err());
+        assert!(RegisterLog::parse_sid_dump("# chip 6581\n10 zz 00\n").is_err());
+    }
+}
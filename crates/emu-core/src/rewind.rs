@@ -0,0 +1,357 @@
+//! Rewind ring buffer of delta-compressed machine snapshots.
+//!
+//! The buffer keeps the newest snapshot in full and every older snapshot as
+//! a delta against its newer neighbour. Stepping back decodes one delta;
+//! dropping the oldest entry when the ring is full costs nothing. Emulated
+//! frames change only a small fraction of RAM, so deltas are typically a
+//! few kilobytes even for machines with megabytes of state.
+//!
+//! Nothing here depends on windowing, so headless tools and the WASM
+//! wrappers can drive the same buffer as the native runner.
+
+use std::collections::VecDeque;
+
+use crate::{Machine, StateError};
+
+/// Unchanged runs shorter than this are folded into the surrounding
+/// literal, since each run costs at least two bytes of header.
+const MIN_MATCH: usize = 4;
+
+/// A ring of periodic machine snapshots for stepping backwards in time.
+#[derive(Debug, Clone)]
+pub struct RewindBuffer {
+    /// Maximum number of snapshots retained.
+    capacity: usize,
+    /// Frames between snapshots.
+    interval: u32,
+    /// Frames run since the newest snapshot was taken or restored.
+    frames_since_capture: u32,
+    /// Newest snapshot, uncompressed.
+    newest: Option<Vec<u8>>,
+    /// Older snapshots, oldest first. Each entry rebuilds its snapshot
+    /// from the one after it (or from `newest` for the last entry).
+    deltas: VecDeque<Vec<u8>>,
+}
+
+impl RewindBuffer {
+    /// Default number of snapshots (12 seconds at 50 Hz).
+    pub const DEFAULT_CAPACITY: usize = 600;
+
+    /// Create a buffer holding up to `capacity` snapshots, one taken every
+    /// `interval` frames.
+    #[must_use]
+    pub fn new(capacity: usize, interval: u32) -> Self {
+        Self {
+            capacity: capacity.max(1),
+            interval: interval.max(1),
+            frames_since_capture: 0,
+            newest: None,
+            deltas: VecDeque::new(),
+        }
+    }
+
+    /// Frames between snapshots.
+    #[must_use]
+    pub fn interval(&self) -> u32 {
+        self.interval
+    }
+
+    /// Number of snapshots held.
+    #[must_use]
+    pub fn len(&self) -> usize {
+        usize::from(self.newest.is_some()) + self.deltas.len()
+    }
+
+    /// True when no snapshots are held.
+    #[must_use]
+    pub fn is_empty(&self) -> bool {
+        self.newest.is_none()
+    }
+
+    /// Total bytes held, compressed deltas plus the newest snapshot.
+    #[must_use]
+    pub fn size_bytes(&self) -> usize {
+        self.newest.as_ref().map_or(0, Vec::len) + self.deltas.iter().map(Vec::len).sum::<usize>()
+    }
+
+    /// Discard all snapshots (after loading different media, say).
+    pub fn clear(&mut self) {
+        self.newest = None;
+        self.deltas.clear();
+        self.frames_since_capture = 0;
+    }
+
+    /// Note that a frame has completed, taking a snapshot when the
+    /// interval has elapsed. Call once after each `run_frame`.
+    ///
+    /// # Errors
+    ///
+    /// Returns the machine's error if it cannot save state.
+    pub fn record<M: Machine>(&mut self, machine: &M) -> Result<(), StateError> {
+        self.frames_since_capture += 1;
+        if self.newest.is_none() || self.frames_since_capture >= self.interval {
+            self.push(machine.save_state()?);
+        }
+        Ok(())
+    }
+
+    /// Add a snapshot as the newest entry, evicting the oldest if full.
+    pub fn push(&mut self, state: Vec<u8>) {
+        if let Some(previous) = self.newest.take() {
+            self.deltas.push_back(encode_delta(&previous, &state));
+            if self.deltas.len() >= self.capacity {
+                self.deltas.pop_front();
+            }
+        }
+        self.newest = Some(state);
+        self.frames_since_capture = 0;
+    }
+
+    /// Remove and return the newest snapshot.
+    pub fn pop(&mut self) -> Option<Vec<u8>> {
+        let newest = self.newest.take()?;
+        if let Some(delta) = self.deltas.pop_back() {
+            self.newest = Some(decode_delta(&delta, &newest));
+        }
+        self.frames_since_capture = 0;
+        Some(newest)
+    }
+
+    /// Restore the machine to the previous snapshot.
+    ///
+    /// If the machine has run on since the newest snapshot, that snapshot
+    /// is restored; otherwise the newest is discarded and the one before
+    /// it restored. Holding a rewind key calls this once per displayed
+    /// frame. Returns false when there is nothing further back.
+    ///
+    /// # Errors
+    ///
+    /// Returns the machine's error if the snapshot cannot be loaded.
+    pub fn step_back<M: Machine>(&mut self, machine: &mut M) -> Result<bool, StateError> {
+        if self.frames_since_capture == 0 {
+            if self.deltas.is_empty() {
+                return Ok(false);
+            }
+            self.pop();
+        }
+        let Some(state) = &self.newest else {
+            return Ok(false);
+        };
+        machine.load_state(state)?;
+        self.frames_since_capture = 0;
+        Ok(true)
+    }
+}
+
+impl Default for RewindBuffer {
+    fn default() -> Self {
+        Self::new(Self::DEFAULT_CAPACITY, 1)
+    }
+}
+
+/// Encode `target` as runs of bytes copied from `base` and literal bytes.
+///
+/// Layout: target length, then repeated (copy count, literal count,
+/// literal bytes) until the target is covered. Counts are LEB128.
+fn encode_delta(target: &[u8], base: &[u8]) -> Vec<u8> {
+    let matching = |at: usize| {
+        target[at..]
+            .iter()
+            .zip(base.get(at..).unwrap_or_default())
+            .take_while(|(t, b)| t == b)
+            .count()
+    };
+
+    let mut out = Vec::new();
+    write_varint(&mut out, target.len());
+    let mut pos = 0;
+    while pos < target.len() {
+        let copy = matching(pos);
+        let literal_start = pos + copy;
+        let mut literal_end = literal_start;
+        while literal_end < target.len() && matching(literal_end) < MIN_MATCH {
+            literal_end += 1;
+        }
+        write_varint(&mut out, copy);
+        write_varint(&mut out, literal_end - literal_start);
+        out.extend_from_slice(&target[literal_start..literal_end]);
+        pos = literal_end;
+    }
+    out
+}
+
+/// Rebuild the target of `encode_delta` from `base`.
+fn decode_delta(delta: &[u8], base: &[u8]) -> Vec<u8> {
+    let mut pos = 0;
+    let len = read_varint(delta, &mut pos);
+    let mut out = Vec::with_capacity(len);
+    while out.len() < len {
+        let copy = read_varint(delta, &mut pos);
+        let start = out.len();
+        out.extend_from_slice(&base[start..start + copy]);
+        let literal = read_varint(delta, &mut pos);
+        out.extend_from_slice(&delta[pos..pos + literal]);
+        pos += literal;
+    }
+    out
+}
+
+fn write_varint(out: &mut Vec<u8>, mut v: usize) {
+    while v >= 0x80 {
+        out.push((v & 0x7F) as u8 | 0x80);
+        v >>= 7;
+    }
+    out.push(v as u8);
+}
+
+fn read_varint(data: &[u8], pos: &mut usize) -> usize {
+    let mut v = 0;
+    let mut shift = 0;
+    loop {
+        let byte = data[*pos];
+        *pos += 1;
+        v |= usize::from(byte & 0x7F) << shift;
+        if byte & 0x80 == 0 {
+            return v;
+        }
+        shift += 7;
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{AudioFrame, StateReader, StateWriter};
+
+    /// A machine whose whole state is a frame counter and a block of RAM
+    /// that changes a little each frame.
+    struct Counter {
+        frames: u64,
+        ram: Vec<u8>,
+    }
+
+    impl Counter {
+        fn new() -> Self {
+            Self {
+                frames: 0,
+                ram: vec![0; 4096],
+            }
+        }
+    }
+
+    impl Machine for Counter {
+        fn run_frame(&mut self) {
+            self.frames += 1;
+            let at = (self.frames as usize * 37) % self.ram.len();
+            self.ram[at] = self.ram[at].wrapping_add(1);
+        }
+
+        fn framebuffer(&self) -> &[u32] {
+            &[]
+        }
+
+        fn framebuffer_width(&self) -> u32 {
+            0
+        }
+
+        fn framebuffer_height(&self) -> u32 {
+            0
+        }
+
+        fn take_audio_buffer(&mut self) -> Vec<AudioFrame> {
+            Vec::new()
+        }
+
+        fn frame_count(&self) -> u64 {
+            self.frames
+        }
+
+        fn reset(&mut self) {}
+
+        fn save_state(&self) -> Result<Vec<u8>, StateError> {
+            let mut w = StateWriter::with_header("counter");
+            w.write_u64(self.frames);
+            w.write_bytes(&self.ram);
+            Ok(w.into_bytes())
+        }
+
+        fn load_state(&mut self, data: &[u8]) -> Result<(), StateError> {
+            let mut r = StateReader::with_header(data, "counter")?;
+            self.frames = r.read_u64()?;
+            r.read_bytes_into(&mut self.ram)?;
+            r.finish()
+        }
+    }
+
+    #[test]
+    fn delta_round_trips_across_lengths() {
+        let base: Vec<u8> = (0..200u8).collect();
+        let mut longer = base.clone();
+        longer[10] = 0xFF;
+        longer[11] = 0xFE;
+        longer.extend_from_slice(&[1, 2, 3]);
+        let shorter = base[..150].to_vec();
+
+        for target in [&base, &longer, &shorter, &Vec::new()] {
+            assert_eq!(&decode_delta(&encode_delta(target, &base), &base), target);
+            assert_eq!(&decode_delta(&encode_delta(target, &[]), &[]), target);
+        }
+    }
+
+    #[test]
+    fn small_changes_compress_well() {
+        let base = vec![0u8; 65536];
+        let mut target = base.clone();
+        target[1000] = 1;
+        target[40000] = 2;
+        assert!(encode_delta(&target, &base).len() < 32);
+    }
+
+    #[test]
+    fn step_back_restores_each_frame_in_turn() {
+        let mut machine = Counter::new();
+        let mut rewind = RewindBuffer::new(100, 1);
+        let mut history = Vec::new();
+        for _ in 0..10 {
+            machine.run_frame();
+            rewind.record(&machine).expect("record");
+            history.push(machine.ram.clone());
+        }
+
+        for expected in history.iter().rev().skip(1) {
+            assert!(rewind.step_back(&mut machine).expect("step"));
+            assert_eq!(&machine.ram, expected);
+        }
+        assert_eq!(machine.frames, 1);
+        assert!(!rewind.step_back(&mut machine).expect("step"));
+    }
+
+    #[test]
+    fn step_back_first_returns_to_newest_snapshot_between_intervals() {
+        let mut machine = Counter::new();
+        let mut rewind = RewindBuffer::new(100, 4);
+        for _ in 0..10 {
+            machine.run_frame();
+            rewind.record(&machine).expect("record");
+        }
+        // Snapshots at frames 1, 5 and 9; the machine is at frame 10.
+        assert_eq!(rewind.len(), 3);
+        assert!(rewind.step_back(&mut machine).expect("step"));
+        assert_eq!(machine.frames, 9);
+        assert!(rewind.step_back(&mut machine).expect("step"));
+        assert_eq!(machine.frames, 5);
+    }
+
+    #[test]
+    fn full_ring_drops_oldest_snapshot() {
+        let mut machine = Counter::new();
+        let mut rewind = RewindBuffer::new(5, 1);
+        for _ in 0..20 {
+            machine.run_frame();
+            rewind.record(&machine).expect("record");
+        }
+        assert_eq!(rewind.len(), 5);
+        while rewind.step_back(&mut machine).expect("step") {}
+        assert_eq!(machine.frames, 16);
+    }
+}
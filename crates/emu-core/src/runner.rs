@@ -11,6 +11,17 @@
 //!     .with_open_handler(&["sg", "bin"], |path| { ... })
 //!     .run();
 //! ```
+//!
+//! Machines run here that implement save states can rewind: the runner
+//! records a `RewindBuffer` snapshot every frame, and holding the rewind
+//! key (default F10) steps backwards one snapshot per displayed frame.
+//! Only the SG-1000 binary uses this runner today. The Spectrum, C64, NES
+//! and Amiga have save states but drive their own main loops, so they have
+//! no rewind key yet.
+//!
+//! The warp key (default F9) toggles [`Warp`]: each displayed frame then
+//! runs as many frames as fit in `frame_duration`, or a fixed frame skip.
+//! Auto-warp switches it on while the machine's tape or disk drive runs.
 
 use std::path::PathBuf;
 use std::sync::Arc;
@@ -25,10 +36,16 @@ use winit::event_loop::{ActiveEventLoop, EventLoop};
 use winit::keyboard::{KeyCode, PhysicalKey};
 use winit::window::{Fullscreen, Window, WindowAttributes, WindowId};
 
-use crate::Machine;
 use crate::audio::AudioOutput;
+use crate::blip;
 use crate::capture::{AudioCapture, save_screenshot_argb32};
 use crate::renderer::{FilterMode, Renderer};
+use crate::warp::Warp;
+use crate::{AudioFrame, Machine, RewindBuffer, StateError};
+
+/// Largest output-rate stretch used to keep the audio queue at its target
+/// (0.5%: far more than any real clock drift, too small to hear).
+const AUDIO_MAX_SKEW: f64 = 0.005;
 
 /// Key handler function type: `(machine, keycode, pressed)`.
 pub type KeyHandler<M> = Box<dyn FnMut(&mut M, KeyCode, bool)>;
@@ -244,6 +261,10 @@ pub struct Runner<M: Machine> {
     open_handler: Option<OpenHandler<M>>,
     file_extensions: Vec<String>,
     quit_key: KeyCode,
+    rewind: Option<RewindBuffer>,
+    rewind_key: KeyCode,
+    warp: Warp,
+    warp_key: KeyCode,
 }
 
 impl<M: Machine> Runner<M> {
@@ -260,6 +281,10 @@ impl<M: Machine> Runner<M> {
             open_handler: None,
             file_extensions: Vec::new(),
             quit_key: KeyCode::Escape,
+            rewind: Some(RewindBuffer::default()),
+            rewind_key: KeyCode::F10,
+            warp: Warp::new(),
+            warp_key: KeyCode::F9,
         }
     }
 
@@ -297,12 +322,59 @@ impl<M: Machine> Runner<M> {
         self
     }
 
+    /// Keep up to `capacity` rewind snapshots, one every `interval` frames
+    /// (default: 600 snapshots, every frame).
+    #[must_use]
+    pub fn with_rewind(mut self, capacity: usize, interval: u32) -> Self {
+        self.rewind = Some(RewindBuffer::new(capacity, interval));
+        self
+    }
+
+    /// Enable or disable rewind recording (default: enabled).
+    #[must_use]
+    pub fn with_rewind_enabled(mut self, enabled: bool) -> Self {
+        if !enabled {
+            self.rewind = None;
+        } else if self.rewind.is_none() {
+            self.rewind = Some(RewindBuffer::default());
+        }
+        self
+    }
+
+    /// Set the key held to rewind (default: F10).
+    #[must_use]
+    pub fn with_rewind_key(mut self, key: KeyCode) -> Self {
+        self.rewind_key = key;
+        self
+    }
+
+    /// Set the warp settings (default: [`Warp::new`]).
+    #[must_use]
+    pub fn with_warp(mut self, warp: Warp) -> Self {
+        self.warp = warp;
+        self
+    }
+
+    /// Set the key that toggles warp (default: F9).
+    #[must_use]
+    pub fn with_warp_key(mut self, key: KeyCode) -> Self {
+        self.warp_key = key;
+        self
+    }
+
     /// Run the windowed application. Blocks until the window is closed.
-    pub fn run(self) {
+    pub fn run(mut self) {
         let ext_label = self.file_extensions.join(", ");
         let (menu, menu_ids, menu_controls) = build_menu(&self.title, &ext_label);
         let fb_width = self.machine.framebuffer_width();
         let fb_height = self.machine.framebuffer_height();
+        // Run at the device's own rate when the machine can, so the host
+        // doesn't resample a second time.
+        if self.audio_enabled
+            && let Some(rate) = AudioOutput::device_sample_rate()
+        {
+            self.machine.set_audio_sample_rate(rate);
+        }
         let audio_sample_rate = self.machine.audio_sample_rate();
         let audio = if self.audio_enabled {
             match AudioOutput::new(audio_sample_rate, self.frame_duration) {
@@ -339,6 +411,11 @@ impl<M: Machine> Runner<M> {
             open_handler: self.open_handler,
             file_extensions: self.file_extensions,
             quit_key: self.quit_key,
+            rewind: self.rewind,
+            rewind_key: self.rewind_key,
+            rewinding: false,
+            warp: self.warp,
+            warp_key: self.warp_key,
             pending_windowed_resize: false,
         };
 
@@ -382,6 +459,12 @@ struct App<M: Machine> {
     open_handler: Option<OpenHandler<M>>,
     file_extensions: Vec<String>,
     quit_key: KeyCode,
+    rewind: Option<RewindBuffer>,
+    rewind_key: KeyCode,
+    /// Rewind key is held.
+    rewinding: bool,
+    warp: Warp,
+    warp_key: KeyCode,
     pending_windowed_resize: bool,
 }
 
@@ -503,6 +586,73 @@ impl<M: Machine> App<M> {
         }
     }
 
+    /// Snapshot the frame just run into the rewind buffer.
+    fn record_rewind(&mut self) {
+        let Some(rewind) = &mut self.rewind else {
+            return;
+        };
+        if let Err(e) = rewind.record(&self.machine) {
+            if e != StateError::Unsupported {
+                eprintln!("Rewind disabled: {e}");
+            }
+            self.rewind = None;
+        }
+    }
+
+    /// Write samples to the audio capture, if one is running.
+    fn capture_audio(&mut self, samples: &[AudioFrame]) {
+        if let Some(capture) = &mut self.audio_capture
+            && let Err(e) = capture.append_frames(samples)
+        {
+            eprintln!("Audio capture error: {e}");
+            self.audio_capture = None;
+            self.set_audio_capture_menu_state(false);
+        }
+    }
+
+    /// Run one warp batch and show its last frame. Capture gets all the
+    /// audio; the speakers get the warp playback.
+    fn run_warp(&mut self) {
+        let rewind = &mut self.rewind;
+        let batch = self
+            .warp
+            .run(&mut self.machine, self.frame_duration, |machine| {
+                if let Some(buffer) = rewind
+                    && let Err(e) = buffer.record(machine)
+                {
+                    if e != StateError::Unsupported {
+                        eprintln!("Rewind disabled: {e}");
+                    }
+                    *rewind = None;
+                }
+            });
+        self.capture_audio(&batch.audio);
+        if let Some(audio) = &self.audio {
+            audio.push_frames(&self.warp.playback(&batch));
+        }
+    }
+
+    /// Step back one rewind snapshot while the rewind key is held.
+    fn step_rewind(&mut self) {
+        let Some(rewind) = &mut self.rewind else {
+            return;
+        };
+        match rewind.step_back(&mut self.machine) {
+            Ok(true) => {
+                // The restored audio belongs to a frame already played.
+                let _ = self.machine.take_audio_buffer();
+                if let Some(renderer) = &mut self.renderer {
+                    renderer.upload_framebuffer(self.machine.framebuffer());
+                }
+            }
+            Ok(false) => {}
+            Err(e) => {
+                eprintln!("Rewind error: {e}");
+                self.rewind = None;
+            }
+        }
+    }
+
     fn request_windowed_size(&mut self) {
         if self.fullscreen {
             self.pending_windowed_resize = true;
@@ -583,7 +733,11 @@ impl<M: Machine> App<M> {
         if let Some(path) = dialog.pick_file() {
             if let Some(new_machine) = handler(&path) {
                 self.machine = new_machine;
+                self.machine.set_audio_sample_rate(self.audio_sample_rate);
                 self.clear_audio();
+                if let Some(rewind) = &mut self.rewind {
+                    rewind.clear();
+                }
 
                 let new_sample_rate = self.machine.audio_sample_rate();
                 if new_sample_rate != self.audio_sample_rate {
@@ -757,6 +911,21 @@ impl<M: Machine> ApplicationHandler for App<M> {
                         event_loop.exit();
                         return;
                     }
+                    if keycode == self.warp_key {
+                        if pressed {
+                            let on = self.warp.toggle();
+                            eprintln!("Warp {}", if on { "on" } else { "off" });
+                            self.clear_audio();
+                        }
+                        return;
+                    }
+                    if keycode == self.rewind_key && self.rewind.is_some() {
+                        if pressed && !self.rewinding {
+                            self.clear_audio();
+                        }
+                        self.rewinding = pressed;
+                        return;
+                    }
                     if let Some(handler) = &mut self.key_handler {
                         handler(&mut self.machine, keycode, pressed);
                     }
@@ -764,18 +933,27 @@ impl<M: Machine> ApplicationHandler for App<M> {
             }
             WindowEvent::RedrawRequested => {
                 let now = Instant::now();
-                if now.duration_since(self.last_frame_time) >= self.frame_duration {
-                    self.machine.run_frame();
-                    let samples = self.machine.take_audio_buffer();
-                    if let Some(capture) = &mut self.audio_capture
-                        && let Err(e) = capture.append_frames(&samples)
-                    {
-                        eprintln!("Audio capture error: {e}");
-                        self.audio_capture = None;
-                        self.set_audio_capture_menu_state(false);
-                    }
-                    if let Some(audio) = &self.audio {
-                        audio.push_frames(&samples);
+                if now.duration_since(self.last_frame_time) >= self.frame_duration && self.rewinding
+                {
+                    self.step_rewind();
+                    self.last_frame_time = now;
+                } else if now.duration_since(self.last_frame_time) >= self.frame_duration {
+                    if self.warp.is_active(&self.machine) {
+                        self.run_warp();
+                    } else {
+                        self.machine.run_frame();
+                        let samples = self.machine.take_audio_buffer();
+                        self.capture_audio(&samples);
+                        if let Some(audio) = &self.audio {
+                            audio.push_frames(&samples);
+                            if let Some(fill) = audio.fill_ratio() {
+                                self.machine.set_audio_rate_adjust(blip::dynamic_rate(
+                                    fill,
+                                    AUDIO_MAX_SKEW,
+                                ));
+                            }
+                        }
+                        self.record_rewind();
                     }
 
                     if let Some(renderer) = &mut self.renderer {
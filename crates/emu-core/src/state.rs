@@ -0,0 +1,522 @@
+//! Versioned binary save states.
+//!
+//! A save state is a flat little-endian byte stream. Each component writes
+//! its fields in a fixed order through `SaveState`; the machine wraps the
+//! stream in a header carrying a magic number, the format version, and a
+//! machine tag so a C64 state can never be loaded into a Spectrum.
+//!
+//! Everything that influences future emulation is captured: CPU micro-op
+//! phase, beam positions, oscillator and envelope counters, mapper
+//! registers, partially rendered framebuffers, and writable media such as
+//! disk images. Read-only media (system ROMs, cartridge ROM, tape images)
+//! are not embedded — the same media must be inserted before loading, and
+//! only the playback position is restored.
+
+use std::fmt;
+
+/// Magic bytes at the start of every save state.
+pub const STATE_MAGIC: [u8; 4] = *b"E8XS";
+
+/// Current save-state format version.
+///
+/// Bump when any component changes its serialised layout. Older versions
+/// are rejected rather than misread.
+pub const STATE_VERSION: u16 = 2;
+
+/// Errors raised while loading a save state.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum StateError {
+    /// The machine does not implement save states.
+    Unsupported,
+    /// The data does not start with `STATE_MAGIC`.
+    BadMagic,
+    /// The state was written by a different format version.
+    UnsupportedVersion(u16),
+    /// The state belongs to a different machine.
+    WrongMachine { expected: String, found: String },
+    /// The data ended before all fields were read.
+    UnexpectedEof,
+    /// Bytes remain after the last field.
+    TrailingData(usize),
+    /// A field holds a value the component cannot accept.
+    Invalid(String),
+}
+
+impl fmt::Display for StateError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::Unsupported => write!(f, "save states are not supported by this machine"),
+            Self::BadMagic => write!(f, "not a save state (bad magic)"),
+            Self::UnsupportedVersion(v) => {
+                write!(f, "unsupported save-state version {v} (expected {STATE_VERSION})")
+            }
+            Self::WrongMachine { expected, found } => {
+                write!(f, "save state is for {found}, not {expected}")
+            }
+            Self::UnexpectedEof => write!(f, "save state is truncated"),
+            Self::TrailingData(n) => write!(f, "{n} unexpected bytes after save state"),
+            Self::Invalid(msg) => write!(f, "invalid save state: {msg}"),
+        }
+    }
+}
+
+impl std::error::Error for StateError {}
+
+/// A component whose complete internal state can be saved and restored.
+///
+/// `load_state` must read exactly the fields `save_state` wrote, in the
+/// same order. Configuration that is fixed at construction (clock rates,
+/// ROM contents, model) is not written; the caller builds an identically
+/// configured component first and then loads into it.
+pub trait SaveState {
+    /// Append this component's state to the writer.
+    fn save_state(&self, w: &mut StateWriter);
+
+    /// Restore this component's state from the reader.
+    fn load_state(&mut self, r: &mut StateReader<'_>) -> Result<(), StateError>;
+}
+
+/// Little-endian save-state serialiser.
+#[derive(Default)]
+pub struct StateWriter {
+    buf: Vec<u8>,
+}
+
+impl StateWriter {
+    /// Create an empty writer with no header.
+    #[must_use]
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Create a writer and emit the standard header for `machine`.
+    #[must_use]
+    pub fn with_header(machine: &str) -> Self {
+        let mut w = Self::new();
+        w.buf.extend_from_slice(&STATE_MAGIC);
+        w.write_u16(STATE_VERSION);
+        w.write_str(machine);
+        w
+    }
+
+    /// Finish writing and return the serialised bytes.
+    #[must_use]
+    pub fn into_bytes(self) -> Vec<u8> {
+        self.buf
+    }
+
+    pub fn write_u8(&mut self, v: u8) {
+        self.buf.push(v);
+    }
+
+    pub fn write_i8(&mut self, v: i8) {
+        self.buf.push(v as u8);
+    }
+
+    pub fn write_bool(&mut self, v: bool) {
+        self.buf.push(u8::from(v));
+    }
+
+    pub fn write_u16(&mut self, v: u16) {
+        self.buf.extend_from_slice(&v.to_le_bytes());
+    }
+
+    pub fn write_i16(&mut self, v: i16) {
+        self.buf.extend_from_slice(&v.to_le_bytes());
+    }
+
+    pub fn write_u32(&mut self, v: u32) {
+        self.buf.extend_from_slice(&v.to_le_bytes());
+    }
+
+    pub fn write_i32(&mut self, v: i32) {
+        self.buf.extend_from_slice(&v.to_le_bytes());
+    }
+
+    pub fn write_u64(&mut self, v: u64) {
+        self.buf.extend_from_slice(&v.to_le_bytes());
+    }
+
+    pub fn write_i64(&mut self, v: i64) {
+        self.buf.extend_from_slice(&v.to_le_bytes());
+    }
+
+    /// Write a `usize` as a 64-bit value so states are portable across hosts.
+    pub fn write_usize(&mut self, v: usize) {
+        self.write_u64(v as u64);
+    }
+
+    pub fn write_f32(&mut self, v: f32) {
+        self.write_u32(v.to_bits());
+    }
+
+    pub fn write_f64(&mut self, v: f64) {
+        self.write_u64(v.to_bits());
+    }
+
+    /// Write a length-prefixed byte slice.
+    pub fn write_bytes(&mut self, data: &[u8]) {
+        self.write_usize(data.len());
+        self.buf.extend_from_slice(data);
+    }
+
+    /// Write a length-prefixed UTF-8 string.
+    pub fn write_str(&mut self, s: &str) {
+        self.write_bytes(s.as_bytes());
+    }
+
+    /// Write a length-prefixed slice of `bool`.
+    pub fn write_bool_slice(&mut self, data: &[bool]) {
+        self.write_usize(data.len());
+        for &v in data {
+            self.write_bool(v);
+        }
+    }
+
+    /// Write a length-prefixed slice of `u16`.
+    pub fn write_u16_slice(&mut self, data: &[u16]) {
+        self.write_usize(data.len());
+        for &v in data {
+            self.write_u16(v);
+        }
+    }
+
+    /// Write a length-prefixed slice of `u32`.
+    pub fn write_u32_slice(&mut self, data: &[u32]) {
+        self.write_usize(data.len());
+        for &v in data {
+            self.write_u32(v);
+        }
+    }
+
+    /// Write a length-prefixed slice of `f32`.
+    pub fn write_f32_slice(&mut self, data: &[f32]) {
+        self.write_usize(data.len());
+        for &v in data {
+            self.write_f32(v);
+        }
+    }
+
+    /// Write an optional component: a presence flag, then its state.
+    pub fn write_option<T: SaveState>(&mut self, v: Option<&T>) {
+        self.write_bool(v.is_some());
+        if let Some(v) = v {
+            v.save_state(self);
+        }
+    }
+}
+
+/// Little-endian save-state deserialiser.
+pub struct StateReader<'a> {
+    data: &'a [u8],
+    pos: usize,
+}
+
+impl<'a> StateReader<'a> {
+    /// Create a reader over raw state bytes with no header.
+    #[must_use]
+    pub fn new(data: &'a [u8]) -> Self {
+        Self { data, pos: 0 }
+    }
+
+    /// Create a reader and validate the standard header against `machine`.
+    pub fn with_header(data: &'a [u8], machine: &str) -> Result<Self, StateError> {
+        if data.len() < STATE_MAGIC.len() || data[..STATE_MAGIC.len()] != STATE_MAGIC {
+            return Err(StateError::BadMagic);
+        }
+        let mut r = Self {
+            data,
+            pos: STATE_MAGIC.len(),
+        };
+        let version = r.read_u16()?;
+        if version != STATE_VERSION {
+            return Err(StateError::UnsupportedVersion(version));
+        }
+        let found = r.read_string()?;
+        if found != machine {
+            return Err(StateError::WrongMachine {
+                expected: machine.to_string(),
+                found,
+            });
+        }
+        Ok(r)
+    }
+
+    /// Check that every byte has been consumed.
+    pub fn finish(self) -> Result<(), StateError> {
+        match self.data.len() - self.pos {
+            0 => Ok(()),
+            n => Err(StateError::TrailingData(n)),
+        }
+    }
+
+    fn take(&mut self, n: usize) -> Result<&'a [u8], StateError> {
+        let end = self.pos.checked_add(n).ok_or(StateError::UnexpectedEof)?;
+        let slice = self
+            .data
+            .get(self.pos..end)
+            .ok_or(StateError::UnexpectedEof)?;
+        self.pos = end;
+        Ok(slice)
+    }
+
+    fn take_array<const N: usize>(&mut self) -> Result<[u8; N], StateError> {
+        let mut out = [0u8; N];
+        out.copy_from_slice(self.take(N)?);
+        Ok(out)
+    }
+
+    pub fn read_u8(&mut self) -> Result<u8, StateError> {
+        Ok(self.take(1)?[0])
+    }
+
+    pub fn read_i8(&mut self) -> Result<i8, StateError> {
+        Ok(self.read_u8()? as i8)
+    }
+
+    pub fn read_bool(&mut self) -> Result<bool, StateError> {
+        match self.read_u8()? {
+            0 => Ok(false),
+            1 => Ok(true),
+            v => Err(StateError::Invalid(format!("bad bool {v}"))),
+        }
+    }
+
+    pub fn read_u16(&mut self) -> Result<u16, StateError> {
+        Ok(u16::from_le_bytes(self.take_array()?))
+    }
+
+    pub fn read_i16(&mut self) -> Result<i16, StateError> {
+        Ok(i16::from_le_bytes(self.take_array()?))
+    }
+
+    pub fn read_u32(&mut self) -> Result<u32, StateError> {
+        Ok(u32::from_le_bytes(self.take_array()?))
+    }
+
+    pub fn read_i32(&mut self) -> Result<i32, StateError> {
+        Ok(i32::from_le_bytes(self.take_array()?))
+    }
+
+    pub fn read_u64(&mut self) -> Result<u64, StateError> {
+        Ok(u64::from_le_bytes(self.take_array()?))
+    }
+
+    pub fn read_i64(&mut self) -> Result<i64, StateError> {
+        Ok(i64::from_le_bytes(self.take_array()?))
+    }
+
+    pub fn read_usize(&mut self) -> Result<usize, StateError> {
+        let v = self.read_u64()?;
+        usize::try_from(v).map_err(|_| StateError::Invalid(format!("length {v} too large")))
+    }
+
+    pub fn read_f32(&mut self) -> Result<f32, StateError> {
+        Ok(f32::from_bits(self.read_u32()?))
+    }
+
+    pub fn read_f64(&mut self) -> Result<f64, StateError> {
+        Ok(f64::from_bits(self.read_u64()?))
+    }
+
+    /// Read a length prefix, rejecting lengths that exceed the remaining data.
+    fn read_len(&mut self, elem_size: usize) -> Result<usize, StateError> {
+        let len = self.read_usize()?;
+        if len.saturating_mul(elem_size) > self.data.len() - self.pos {
+            return Err(StateError::UnexpectedEof);
+        }
+        Ok(len)
+    }
+
+    /// Read a length-prefixed byte slice.
+    pub fn read_bytes(&mut self) -> Result<&'a [u8], StateError> {
+        let len = self.read_len(1)?;
+        self.take(len)
+    }
+
+    /// Read a length-prefixed byte slice into a fixed-size buffer.
+    ///
+    /// Fails if the stored length differs from `dst.len()`.
+    pub fn read_bytes_into(&mut self, dst: &mut [u8]) -> Result<(), StateError> {
+        let src = self.read_bytes()?;
+        if src.len() != dst.len() {
+            return Err(StateError::Invalid(format!(
+                "expected {} bytes, found {}",
+                dst.len(),
+                src.len()
+            )));
+        }
+        dst.copy_from_slice(src);
+        Ok(())
+    }
+
+    /// Read a length-prefixed UTF-8 string.
+    pub fn read_string(&mut self) -> Result<String, StateError> {
+        let bytes = self.read_bytes()?;
+        String::from_utf8(bytes.to_vec()).map_err(|e| StateError::Invalid(e.to_string()))
+    }
+
+    /// Read a length-prefixed `bool` slice into a fixed-size buffer.
+    pub fn read_bool_into(&mut self, dst: &mut [bool]) -> Result<(), StateError> {
+        let len = self.read_len(1)?;
+        if len != dst.len() {
+            return Err(StateError::Invalid(format!(
+                "expected {} flags, found {len}",
+                dst.len()
+            )));
+        }
+        for v in dst.iter_mut() {
+            *v = self.read_bool()?;
+        }
+        Ok(())
+    }
+
+    /// Read a length-prefixed `u16` slice.
+    pub fn read_u16_vec(&mut self) -> Result<Vec<u16>, StateError> {
+        let len = self.read_len(2)?;
+        (0..len).map(|_| self.read_u16()).collect()
+    }
+
+    /// Read a length-prefixed `u16` slice into a fixed-size buffer.
+    pub fn read_u16_into(&mut self, dst: &mut [u16]) -> Result<(), StateError> {
+        let len = self.read_len(2)?;
+        if len != dst.len() {
+            return Err(StateError::Invalid(format!(
+                "expected {} words, found {len}",
+                dst.len()
+            )));
+        }
+        for v in dst.iter_mut() {
+            *v = self.read_u16()?;
+        }
+        Ok(())
+    }
+
+    /// Read a length-prefixed `u32` slice.
+    pub fn read_u32_vec(&mut self) -> Result<Vec<u32>, StateError> {
+        let len = self.read_len(4)?;
+        (0..len).map(|_| self.read_u32()).collect()
+    }
+
+    /// Read a length-prefixed `u32` slice into a fixed-size buffer.
+    pub fn read_u32_into(&mut self, dst: &mut [u32]) -> Result<(), StateError> {
+        let len = self.read_len(4)?;
+        if len != dst.len() {
+            return Err(StateError::Invalid(format!(
+                "expected {} words, found {len}",
+                dst.len()
+            )));
+        }
+        for v in dst.iter_mut() {
+            *v = self.read_u32()?;
+        }
+        Ok(())
+    }
+
+    /// Read a length-prefixed `f32` slice.
+    pub fn read_f32_vec(&mut self) -> Result<Vec<f32>, StateError> {
+        let len = self.read_len(4)?;
+        (0..len).map(|_| self.read_f32()).collect()
+    }
+
+    /// Read an optional component written by `write_option`.
+    ///
+    /// The component's presence is part of the machine configuration, so a
+    /// mismatch between the state and `slot` is an error rather than an
+    /// implicit insert or removal.
+    pub fn read_option<T: SaveState>(
+        &mut self,
+        slot: Option<&mut T>,
+        what: &str,
+    ) -> Result<(), StateError> {
+        let present = self.read_bool()?;
+        match (present, slot) {
+            (true, Some(v)) => v.load_state(self),
+            (false, None) => Ok(()),
+            (true, None) => Err(StateError::Invalid(format!("state has {what}, machine does not"))),
+            (false, Some(_)) => Err(StateError::Invalid(format!("machine has {what}, state does not"))),
+        }
+    }
+
+    /// Read an optional value written by `write_option` whose presence is
+    /// runtime state (an in-flight operation, say) rather than configuration.
+    pub fn read_option_value<T: SaveState + Default>(&mut self) -> Result<Option<T>, StateError> {
+        if !self.read_bool()? {
+            return Ok(None);
+        }
+        let mut v = T::default();
+        v.load_state(self)?;
+        Ok(Some(v))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn primitives_round_trip() {
+        let mut w = StateWriter::new();
+        w.write_u8(0x12);
+        w.write_i8(-3);
+        w.write_bool(true);
+        w.write_u16(0xBEEF);
+        w.write_u32(0xDEAD_BEEF);
+        w.write_u64(u64::MAX - 1);
+        w.write_i32(-100_000);
+        w.write_f32(0.25);
+        w.write_bytes(&[1, 2, 3]);
+        w.write_str("c64");
+        w.write_u32_slice(&[7, 8]);
+        let bytes = w.into_bytes();
+
+        let mut r = StateReader::new(&bytes);
+        assert_eq!(r.read_u8(), Ok(0x12));
+        assert_eq!(r.read_i8(), Ok(-3));
+        assert_eq!(r.read_bool(), Ok(true));
+        assert_eq!(r.read_u16(), Ok(0xBEEF));
+        assert_eq!(r.read_u32(), Ok(0xDEAD_BEEF));
+        assert_eq!(r.read_u64(), Ok(u64::MAX - 1));
+        assert_eq!(r.read_i32(), Ok(-100_000));
+        assert_eq!(r.read_f32(), Ok(0.25));
+        assert_eq!(r.read_bytes(), Ok(&[1u8, 2, 3][..]));
+        assert_eq!(r.read_string().as_deref(), Ok("c64"));
+        assert_eq!(r.read_u32_vec(), Ok(vec![7, 8]));
+        assert_eq!(r.finish(), Ok(()));
+    }
+
+    #[test]
+    fn header_rejects_other_machines_and_versions() {
+        let bytes = StateWriter::with_header("spectrum").into_bytes();
+        assert!(StateReader::with_header(&bytes, "spectrum").is_ok());
+        assert!(matches!(
+            StateReader::with_header(&bytes, "nes"),
+            Err(StateError::WrongMachine { .. })
+        ));
+
+        let mut old = bytes.clone();
+        old[4] = 0xFF;
+        assert!(matches!(
+            StateReader::with_header(&old, "spectrum"),
+            Err(StateError::UnsupportedVersion(_))
+        ));
+        assert_eq!(
+            StateReader::with_header(b"nope", "spectrum").err(),
+            Some(StateError::BadMagic)
+        );
+    }
+
+    #[test]
+    fn truncated_and_oversized_lengths_are_errors() {
+        let mut r = StateReader::new(&[1, 2]);
+        assert_eq!(r.read_u32(), Err(StateError::UnexpectedEof));
+
+        let mut w = StateWriter::new();
+        w.write_usize(1 << 40);
+        let bytes = w.into_bytes();
+        assert_eq!(
+            StateReader::new(&bytes).read_bytes(),
+            Err(StateError::UnexpectedEof)
+        );
+    }
+}
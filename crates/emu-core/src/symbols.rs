@@ -0,0 +1,429 @@
+//! Symbol tables for debugging tools.
+//!
+//! A [`SymbolTable`] maps label names to addresses and back. It is filled
+//! from the files assemblers and other emulators write, and the MCP layer
+//! uses it to accept `"main+4"` wherever an address is expected and to
+//! show labels in breakpoints, traces and disassembly.
+//!
+//! Text formats parsed here:
+//!
+//! - [`SymbolFormat::Vice`]: VICE monitor label files (`.lbl`, `.vs`),
+//!   `al C:080d .start`. ca65's `-Ln` output uses the same syntax.
+//! - [`SymbolFormat::Ca65`]: ca65/ld65 debug info (`.dbg`), the `sym`
+//!   lines of type `lab`.
+//! - [`SymbolFormat::Sym`]: sjasmplus (`start: EQU 0x00008000`) and pasmo
+//!   (`START EQU 08000H`) symbol files.
+//!
+//! Amiga hunk executables carry their symbols relative to each hunk, so
+//! they are resolved by the Amiga machine once it knows where the hunks
+//! were loaded.
+
+use std::collections::{BTreeMap, HashMap};
+use std::fmt;
+use std::path::Path;
+
+/// How far past a label [`SymbolTable::describe`] still names an address
+/// as `label+offset`.
+pub const NEAREST_RANGE: u32 = 0x100;
+
+/// A symbol file format.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum SymbolFormat {
+    /// VICE `al` label commands.
+    Vice,
+    /// ca65/ld65 `.dbg` debug info.
+    Ca65,
+    /// sjasmplus or pasmo `EQU` listing.
+    Sym,
+}
+
+impl SymbolFormat {
+    /// Parse `"vice"`, `"ca65"` or `"sym"`.
+    #[must_use]
+    pub fn from_name(name: &str) -> Option<Self> {
+        match name {
+            "vice" => Some(Self::Vice),
+            "ca65" => Some(Self::Ca65),
+            "sym" => Some(Self::Sym),
+            _ => None,
+        }
+    }
+
+    /// Guess the format from a file extension (`.lbl`, `.vs`, `.dbg`,
+    /// `.sym`).
+    #[must_use]
+    pub fn from_path(path: &Path) -> Option<Self> {
+        let ext = path.extension()?.to_str()?.to_ascii_lowercase();
+        match ext.as_str() {
+            "lbl" | "vs" | "labels" => Some(Self::Vice),
+            "dbg" => Some(Self::Ca65),
+            "sym" => Some(Self::Sym),
+            _ => None,
+        }
+    }
+}
+
+/// A malformed line in a symbol file.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct SymbolError {
+    /// 1-based line number.
+    pub line: usize,
+    pub message: String,
+}
+
+impl fmt::Display for SymbolError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "line {}: {}", self.line, self.message)
+    }
+}
+
+impl std::error::Error for SymbolError {}
+
+/// Label names and the addresses they stand for.
+///
+/// An address may carry several names; the first one added is the one
+/// shown. Names are case-sensitive.
+#[derive(Debug, Clone, Default)]
+pub struct SymbolTable {
+    by_address: BTreeMap<u32, Vec<String>>,
+    by_name: HashMap<String, u32>,
+}
+
+impl SymbolTable {
+    #[must_use]
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Parse a symbol file.
+    ///
+    /// # Errors
+    ///
+    /// Returns the first line that cannot be parsed.
+    pub fn parse(format: SymbolFormat, text: &str) -> Result<Self, SymbolError> {
+        let mut table = Self::new();
+        for (index, line) in text.lines().enumerate() {
+            let parsed = match format {
+                SymbolFormat::Vice => parse_vice_line(line),
+                SymbolFormat::Ca65 => parse_ca65_line(line),
+                SymbolFormat::Sym => parse_sym_line(line),
+            };
+            match parsed {
+                Ok(Some((name, address))) => table.insert(name, address),
+                Ok(None) => {}
+                Err(message) => {
+                    return Err(SymbolError {
+                        line: index + 1,
+                        message,
+                    });
+                }
+            }
+        }
+        Ok(table)
+    }
+
+    /// Add `name` at `address`. A name already in the table moves.
+    pub fn insert(&mut self, name: impl Into<String>, address: u32) {
+        let name = name.into();
+        if let Some(old) = self.by_name.insert(name.clone(), address) {
+            self.remove_from_address(old, &name);
+        }
+        self.by_address.entry(address).or_default().push(name);
+    }
+
+    fn remove_from_address(&mut self, address: u32, name: &str) {
+        if let Some(names) = self.by_address.get_mut(&address) {
+            names.retain(|n| n != name);
+            if names.is_empty() {
+                self.by_address.remove(&address);
+            }
+        }
+    }
+
+    /// Add every symbol from `other`.
+    pub fn extend(&mut self, other: &Self) {
+        for (&address, names) in &other.by_address {
+            for name in names {
+                self.insert(name.clone(), address);
+            }
+        }
+    }
+
+    pub fn clear(&mut self) {
+        self.by_address.clear();
+        self.by_name.clear();
+    }
+
+    /// Number of names.
+    #[must_use]
+    pub fn len(&self) -> usize {
+        self.by_name.len()
+    }
+
+    #[must_use]
+    pub fn is_empty(&self) -> bool {
+        self.by_name.is_empty()
+    }
+
+    /// Address of `name`.
+    #[must_use]
+    pub fn address_of(&self, name: &str) -> Option<u32> {
+        self.by_name.get(name).copied()
+    }
+
+    /// The label shown for `address`, if it has one.
+    #[must_use]
+    pub fn label_at(&self, address: u32) -> Option<&str> {
+        self.by_address
+            .get(&address)
+            .and_then(|names| names.first())
+            .map(String::as_str)
+    }
+
+    /// The closest label at or below `address`, within
+    /// [`NEAREST_RANGE`], and the offset from it.
+    #[must_use]
+    pub fn nearest(&self, address: u32) -> Option<(&str, u32)> {
+        let (&base, names) = self.by_address.range(..=address).next_back()?;
+        let offset = address - base;
+        (offset < NEAREST_RANGE).then(|| (names[0].as_str(), offset))
+    }
+
+    /// `address` as `label` or `label+$N`, if a label is near enough.
+    #[must_use]
+    pub fn describe(&self, address: u32) -> Option<String> {
+        self.nearest(address).map(|(name, offset)| {
+            if offset == 0 {
+                name.to_string()
+            } else {
+                format!("{name}+${offset:X}")
+            }
+        })
+    }
+
+    /// Every symbol in address order.
+    pub fn iter(&self) -> impl Iterator<Item = (u32, &str)> {
+        self.by_address
+            .iter()
+            .flat_map(|(&address, names)| names.iter().map(move |n| (address, n.as_str())))
+    }
+
+    /// Resolve an address written as a number (`$C000`, `0xC000`,
+    /// `49152`), a label, or a label with an offset (`loop+3`,
+    /// `table-$10`).
+    #[must_use]
+    pub fn resolve(&self, text: &str) -> Option<u32> {
+        let text = text.trim();
+        if let Some(value) = parse_number(text) {
+            return Some(value);
+        }
+        if let Some(address) = self.address_of(text) {
+            return Some(address);
+        }
+        let split = text.rfind(['+', '-']).filter(|&i| i > 0)?;
+        let base = self.address_of(text[..split].trim())?;
+        let offset = parse_number(text[split + 1..].trim())?;
+        Some(if text.as_bytes()[split] == b'+' {
+            base.wrapping_add(offset)
+        } else {
+            base.wrapping_sub(offset)
+        })
+    }
+
+    /// Replace absolute addresses in disassembler operands (`$` followed
+    /// by four or more hex digits, not an immediate) with their labels.
+    #[must_use]
+    pub fn label_operands(&self, operands: &str) -> String {
+        let bytes = operands.as_bytes();
+        let mut out = String::with_capacity(operands.len());
+        let mut i = 0;
+        while i < bytes.len() {
+            if bytes[i] == b'$' && (i == 0 || bytes[i - 1] != b'#') {
+                let digits = bytes[i + 1..]
+                    .iter()
+                    .take_while(|b| b.is_ascii_hexdigit())
+                    .count();
+                let end = i + 1 + digits;
+                if digits >= 4
+                    && let Ok(address) = u32::from_str_radix(&operands[i + 1..end], 16)
+                    && let Some(label) = self.label_at(address)
+                {
+                    out.push_str(label);
+                    i = end;
+                    continue;
+                }
+            }
+            out.push(char::from(bytes[i]));
+            i += 1;
+        }
+        out
+    }
+}
+
+/// Parse `$hex`, `0xhex`, `&hex`, `hexH` or decimal.
+fn parse_number(text: &str) -> Option<u32> {
+    let hex = |digits: &str| u32::from_str_radix(digits, 16).ok();
+    if let Some(digits) = text.strip_prefix('$').or_else(|| text.strip_prefix('&')) {
+        hex(digits)
+    } else if let Some(digits) = text
+        .strip_prefix("0x")
+        .or_else(|| text.strip_prefix("0X"))
+    {
+        hex(digits)
+    } else if let Some(digits) = text.strip_suffix(['h', 'H'])
+        && digits.starts_with(|c: char| c.is_ascii_digit())
+    {
+        hex(digits)
+    } else if text.starts_with(|c: char| c.is_ascii_digit()) {
+        text.parse().ok()
+    } else {
+        None
+    }
+}
+
+type ParsedLine = Result<Option<(String, u32)>, String>;
+
+/// `al [C:]080d .name`. Other monitor commands are skipped.
+fn parse_vice_line(line: &str) -> ParsedLine {
+    let mut words = line.split_whitespace();
+    if words.next() != Some("al") {
+        return Ok(None);
+    }
+    let (Some(address), Some(name)) = (words.next(), words.next()) else {
+        return Err("expected 'al <address> <label>'".to_string());
+    };
+    let address = address.rsplit(':').next().unwrap_or(address);
+    let address =
+        u32::from_str_radix(address, 16).map_err(|_| format!("invalid address '{address}'"))?;
+    Ok(Some((name.trim_start_matches('.').to_string(), address)))
+}
+
+/// `sym id=3,name="start",...,val=0x80D,...,type=lab`. Equates and
+/// imports are skipped.
+fn parse_ca65_line(line: &str) -> ParsedLine {
+    let Some(fields) = line.strip_prefix("sym") else {
+        return Ok(None);
+    };
+    let mut name = None;
+    let mut value = None;
+    let mut kind = None;
+    for field in fields.trim().split(',') {
+        match field.split_once('=') {
+            Some(("name", v)) => name = Some(v.trim_matches('"')),
+            Some(("val", v)) => value = Some(v),
+            Some(("type", v)) => kind = Some(v),
+            _ => {}
+        }
+    }
+    if kind != Some("lab") {
+        return Ok(None);
+    }
+    let (Some(name), Some(value)) = (name, value) else {
+        return Err("label without name or val".to_string());
+    };
+    let address = parse_number(value).ok_or_else(|| format!("invalid val '{value}'"))?;
+    Ok(Some((name.to_string(), address)))
+}
+
+/// `name: EQU 0x00008000` (sjasmplus) or `NAME EQU 08000H` (pasmo).
+fn parse_sym_line(line: &str) -> ParsedLine {
+    let line = line.split(';').next().unwrap_or_default();
+    let words: Vec<&str> = line.split_whitespace().collect();
+    match words.as_slice() {
+        [] => Ok(None),
+        [name, equ, value] if equ.eq_ignore_ascii_case("equ") => {
+            let address = parse_number(value)
+                .or_else(|| value.strip_prefix('#').and_then(|v| parse_number(&format!("${v}"))))
+                .ok_or_else(|| format!("invalid value '{value}'"))?;
+            Ok(Some((name.trim_end_matches(':').to_string(), address)))
+        }
+        _ => Err("expected '<label> EQU <value>'".to_string()),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::path::Path;
+
+    use super::{SymbolFormat, SymbolTable};
+
+    #[test]
+    fn parses_vice_ca65_and_sym_files() {
+        let vice = SymbolTable::parse(
+            SymbolFormat::Vice,
+            "al C:080d .start\nal C:0820 .loop\nbreak 080d\nal 0830 done\n",
+        )
+        .expect("parses");
+        assert_eq!(vice.address_of("start"), Some(0x080D));
+        assert_eq!(vice.address_of("done"), Some(0x0830));
+        assert_eq!(vice.len(), 3);
+
+        let dbg = SymbolTable::parse(
+            SymbolFormat::Ca65,
+            "version\tmajor=2,minor=0\n\
+             sym\tid=0,name=\"main\",addrsize=absolute,scope=0,def=1,ref=4,val=0x80D,seg=0,type=lab\n\
+             sym\tid=1,name=\"SCREEN\",addrsize=absolute,scope=0,def=2,val=0x400,type=equ\n\
+             sym\tid=2,name=\"chrout\",addrsize=absolute,scope=0,def=3,type=imp,exp=5\n",
+        )
+        .expect("parses");
+        assert_eq!(dbg.address_of("main"), Some(0x080D));
+        assert_eq!(dbg.len(), 1);
+
+        let sym = SymbolTable::parse(
+            SymbolFormat::Sym,
+            "; sjasmplus\nstart: EQU 0x00008000\nmain.loop: equ $8005\nDRAW EQU 08010H\nCOUNT EQU 10\n",
+        )
+        .expect("parses");
+        assert_eq!(sym.address_of("start"), Some(0x8000));
+        assert_eq!(sym.address_of("main.loop"), Some(0x8005));
+        assert_eq!(sym.address_of("DRAW"), Some(0x8010));
+        assert_eq!(sym.address_of("COUNT"), Some(10));
+
+        let err = SymbolTable::parse(SymbolFormat::Sym, "start: EQU 0x8000\nbroken\n")
+            .expect_err("second line is malformed");
+        assert_eq!(err.line, 2);
+
+        assert_eq!(
+            SymbolFormat::from_path(Path::new("game.LBL")),
+            Some(SymbolFormat::Vice)
+        );
+        assert_eq!(SymbolFormat::from_path(Path::new("game.bin")), None);
+    }
+
+    #[test]
+    fn resolves_labels_offsets_and_numbers() {
+        let mut table = SymbolTable::new();
+        table.insert("loop", 0xC010);
+        table.insert("table", 0xC100);
+
+        assert_eq!(table.resolve("loop"), Some(0xC010));
+        assert_eq!(table.resolve("loop+3"), Some(0xC013));
+        assert_eq!(table.resolve("table - $10"), Some(0xC0F0));
+        assert_eq!(table.resolve("$D020"), Some(0xD020));
+        assert_eq!(table.resolve("0xd021"), Some(0xD021));
+        assert_eq!(table.resolve("53280"), Some(53280));
+        assert_eq!(table.resolve("missing"), None);
+
+        assert_eq!(table.describe(0xC010).as_deref(), Some("loop"));
+        assert_eq!(table.describe(0xC01A).as_deref(), Some("loop+$A"));
+        assert_eq!(table.describe(0xC00F), None);
+        assert_eq!(table.describe(0xC210), None);
+
+        table.insert("loop", 0xC020);
+        assert_eq!(table.label_at(0xC010), None);
+        assert_eq!(table.len(), 2);
+    }
+
+    #[test]
+    fn labels_absolute_operands_but_not_immediates() {
+        let mut table = SymbolTable::new();
+        table.insert("border", 0xD020);
+        table.insert("draw", 0x0000_8010);
+
+        assert_eq!(table.label_operands("$D020,X"), "border,X");
+        assert_eq!(table.label_operands("#$D020"), "#$D020");
+        assert_eq!(table.label_operands("$00008010(PC)"), "draw(PC)");
+        assert_eq!(table.label_operands("(IX+$05)"), "(IX+$05)");
+        assert_eq!(table.label_operands("$D021"), "$D021");
+    }
+}
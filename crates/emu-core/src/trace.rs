@@ -0,0 +1,714 @@
+//! Instruction and bus-cycle trace recorder.
+//!
+//! A [`Tracer`] is attached to a [`Debuggable`] machine, which shows it
+//! the machine after every CPU tick. It records one [`TraceEntry`] per
+//! executed instruction: master clock, PC, opcode bytes and a
+//! system-chosen register file, optionally followed by every bus cycle
+//! the instruction made (with the wait states the bus reported).
+//!
+//! Entries go either into a bounded ring, exported on demand, or straight
+//! to a writer as they complete. Both export formats are line-oriented:
+//! plain text meant for diffing against other emulators' traces, and JSON
+//! Lines with the same content. When the tracer has a [`SymbolTable`],
+//! each entry also names the label its PC falls under.
+
+use std::collections::VecDeque;
+use std::fmt::Write as _;
+use std::io::{self, Write};
+
+use crate::breakpoint::Debuggable;
+use crate::symbols::SymbolTable;
+use crate::{AccessKind, BusAccess, Value};
+
+/// Export format.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum TraceFormat {
+    /// One line per instruction, one indented line per bus cycle.
+    Text,
+    /// One JSON object per instruction (JSON Lines).
+    Json,
+}
+
+impl TraceFormat {
+    /// Parse `"text"` or `"json"`.
+    #[must_use]
+    pub fn from_name(name: &str) -> Option<Self> {
+        match name {
+            "text" => Some(Self::Text),
+            "json" => Some(Self::Json),
+            _ => None,
+        }
+    }
+}
+
+/// How a system's trace lines are laid out.
+#[derive(Debug, Clone, Copy)]
+pub struct TraceLayout {
+    /// Hex digits used for PC and bus addresses.
+    pub address_digits: usize,
+    /// Opcode bytes the text column is padded to.
+    pub opcode_bytes: usize,
+    /// Observable paths recorded as the register file, in column order.
+    pub registers: &'static [&'static str],
+}
+
+/// One executed instruction.
+#[derive(Debug, Clone, PartialEq)]
+pub struct TraceEntry {
+    /// Machine master clock when the instruction started.
+    pub clock: u64,
+    pub pc: u32,
+    /// Every byte of the instruction, in memory order.
+    pub opcode: Vec<u8>,
+    /// Values of [`TraceLayout::registers`] before the instruction ran.
+    pub registers: Vec<Value>,
+    /// Bus cycles the instruction made, if bus logging is on.
+    pub bus: Vec<BusAccess>,
+}
+
+enum Sink {
+    Ring {
+        entries: VecDeque<TraceEntry>,
+        capacity: usize,
+    },
+    Stream {
+        writer: Box<dyn Write + Send>,
+        format: TraceFormat,
+        error: Option<io::Error>,
+    },
+}
+
+/// Records executed instructions into a ring buffer or a stream.
+pub struct Tracer {
+    layout: TraceLayout,
+    log_bus: bool,
+    recording: bool,
+    sink: Sink,
+    symbols: SymbolTable,
+    /// Instruction in progress; completes at the next boundary.
+    current: Option<TraceEntry>,
+    last_boundary: Option<u32>,
+    /// Bus cycles logged through [`Tracer::bus_log`] this tick.
+    tick_accesses: Vec<BusAccess>,
+    recorded: u64,
+}
+
+impl Tracer {
+    /// Default number of entries a ring keeps.
+    pub const DEFAULT_CAPACITY: usize = 100_000;
+
+    /// Record into a ring keeping the newest `capacity` instructions.
+    #[must_use]
+    pub fn ring(layout: TraceLayout, capacity: usize) -> Self {
+        Self::with_sink(
+            layout,
+            Sink::Ring {
+                entries: VecDeque::new(),
+                capacity: capacity.max(1),
+            },
+        )
+    }
+
+    /// Write every instruction to `writer` as it completes.
+    #[must_use]
+    pub fn stream(layout: TraceLayout, writer: Box<dyn Write + Send>, format: TraceFormat) -> Self {
+        Self::with_sink(
+            layout,
+            Sink::Stream {
+                writer,
+                format,
+                error: None,
+            },
+        )
+    }
+
+    fn with_sink(layout: TraceLayout, sink: Sink) -> Self {
+        Self {
+            layout,
+            log_bus: false,
+            recording: true,
+            sink,
+            symbols: SymbolTable::new(),
+            current: None,
+            last_boundary: None,
+            tick_accesses: Vec::new(),
+            recorded: 0,
+        }
+    }
+
+    /// Also record each instruction's bus cycles.
+    #[must_use]
+    pub fn with_bus_log(mut self, enabled: bool) -> Self {
+        self.log_bus = enabled;
+        self
+    }
+
+    /// Label entries from `symbols`.
+    #[must_use]
+    pub fn with_symbols(mut self, symbols: SymbolTable) -> Self {
+        self.symbols = symbols;
+        self
+    }
+
+    /// Replace the labels used from now on (and by [`export`](Self::export)).
+    pub fn set_symbols(&mut self, symbols: SymbolTable) {
+        self.symbols = symbols;
+    }
+
+    #[must_use]
+    pub fn logs_bus(&self) -> bool {
+        self.log_bus
+    }
+
+    #[must_use]
+    pub fn is_recording(&self) -> bool {
+        self.recording
+    }
+
+    /// True if entries are written out rather than kept in a ring.
+    #[must_use]
+    pub fn is_streaming(&self) -> bool {
+        matches!(self.sink, Sink::Stream { .. })
+    }
+
+    /// Instructions recorded so far, including any the ring dropped.
+    #[must_use]
+    pub fn recorded(&self) -> u64 {
+        self.recorded
+    }
+
+    /// The newest `capacity` entries, oldest first: completed ones from
+    /// the ring followed by the instruction still in progress. Empty when
+    /// streaming.
+    pub fn entries(&self) -> impl Iterator<Item = &TraceEntry> {
+        let (ring, skip) = match &self.sink {
+            Sink::Ring { entries, capacity } => {
+                let held = entries.len() + usize::from(self.current.is_some());
+                (Some(entries.iter()), held.saturating_sub(*capacity))
+            }
+            Sink::Stream { .. } => (None, 0),
+        };
+        ring.into_iter()
+            .flatten()
+            .chain(self.current.iter().filter(|_| !self.is_streaming()))
+            .skip(skip)
+    }
+
+    /// Log for the CPU's bus cycles this tick, when the trace wants them
+    /// and no debugger log is attached already.
+    pub fn bus_log(&mut self) -> Option<&mut Vec<BusAccess>> {
+        (self.log_bus && self.recording).then_some(&mut self.tick_accesses)
+    }
+
+    /// Note the machine state after a CPU tick.
+    ///
+    /// `accesses` are that tick's bus cycles taken from a debugger log,
+    /// if one is attached; cycles logged through [`bus_log`](Self::bus_log)
+    /// are picked up as well. `opcode` returns the bytes of the
+    /// instruction at an address without side effects.
+    pub fn observe<M: Debuggable + ?Sized>(
+        &mut self,
+        machine: &M,
+        accesses: &[BusAccess],
+        opcode: impl FnOnce(u32) -> Vec<u8>,
+    ) {
+        if !self.recording {
+            return;
+        }
+        // Cycles made before the boundary belong to the previous
+        // instruction (or precede the trace and are dropped).
+        if self.log_bus
+            && let Some(current) = &mut self.current
+        {
+            current.bus.append(&mut self.tick_accesses);
+            current.bus.extend_from_slice(accesses);
+        }
+        self.tick_accesses.clear();
+
+        let boundary = machine.instruction_boundary();
+        let new_boundary = boundary.is_some() && boundary != self.last_boundary;
+        self.last_boundary = boundary;
+        let Some(pc) = boundary.filter(|_| new_boundary) else {
+            return;
+        };
+
+        let entry = TraceEntry {
+            clock: machine
+                .query("master_clock")
+                .and_then(|v| value_as_u64(&v))
+                .unwrap_or(0),
+            pc,
+            opcode: opcode(pc),
+            registers: self
+                .layout
+                .registers
+                .iter()
+                .map(|path| machine.query(path).unwrap_or(Value::Bool(false)))
+                .collect(),
+            bus: Vec::new(),
+        };
+        if let Some(done) = self.current.replace(entry) {
+            self.push(done);
+        }
+    }
+
+    /// Stop recording: completes the instruction in progress and flushes a
+    /// stream. Returns the first write error a stream hit, if any.
+    pub fn stop(&mut self) -> io::Result<()> {
+        if !self.recording {
+            return Ok(());
+        }
+        self.recording = false;
+        if let Some(done) = self.current.take() {
+            self.push(done);
+        }
+        match &mut self.sink {
+            Sink::Ring { .. } => Ok(()),
+            Sink::Stream { writer, error, .. } => match error.take() {
+                Some(e) => Err(e),
+                None => writer.flush(),
+            },
+        }
+    }
+
+    fn push(&mut self, entry: TraceEntry) {
+        self.recorded += 1;
+        match &mut self.sink {
+            Sink::Ring { entries, capacity } => {
+                if entries.len() == *capacity {
+                    entries.pop_front();
+                }
+                entries.push_back(entry);
+            }
+            Sink::Stream {
+                writer,
+                format,
+                error,
+            } => {
+                if error.is_some() {
+                    return;
+                }
+                let mut line = String::new();
+                format_entry(&self.layout, &self.symbols, &entry, *format, &mut line);
+                if let Err(e) = writer.write_all(line.as_bytes()) {
+                    *error = Some(e);
+                }
+            }
+        }
+    }
+
+    /// Export the last `last` entries held in the ring (all of them if
+    /// `None`).
+    #[must_use]
+    pub fn export(&self, format: TraceFormat, last: Option<usize>) -> String {
+        let count = self.entries().count();
+        let skip = last.map_or(0, |n| count.saturating_sub(n));
+        let mut out = String::new();
+        for entry in self.entries().skip(skip) {
+            format_entry(&self.layout, &self.symbols, entry, format, &mut out);
+        }
+        out
+    }
+}
+
+/// Where a machine logs its CPU's bus cycles for one tick: the debugger's
+/// log if one is attached, else the tracer's, else nowhere.
+pub fn cpu_bus_log<'a>(
+    bus_log: &'a mut Option<Vec<BusAccess>>,
+    tracer: &'a mut Option<Tracer>,
+) -> Option<&'a mut Vec<BusAccess>> {
+    match bus_log {
+        Some(log) => Some(log),
+        None => tracer.as_mut().and_then(Tracer::bus_log),
+    }
+}
+
+fn value_as_u64(value: &Value) -> Option<u64> {
+    match *value {
+        Value::Bool(v) => Some(u64::from(v)),
+        Value::U8(v) => Some(u64::from(v)),
+        Value::U16(v) => Some(u64::from(v)),
+        Value::U32(v) => Some(u64::from(v)),
+        Value::U64(v) => Some(v),
+        _ => None,
+    }
+}
+
+/// Register column label: the path without its `cpu.` prefix, upper case.
+fn register_label(path: &str) -> String {
+    path.strip_prefix("cpu.")
+        .unwrap_or(path)
+        .to_ascii_uppercase()
+}
+
+fn access_code(kind: AccessKind) -> &'static str {
+    match kind {
+        AccessKind::Read => "R",
+        AccessKind::Write => "W",
+        AccessKind::IoRead => "IR",
+        AccessKind::IoWrite => "IW",
+    }
+}
+
+fn access_name(kind: AccessKind) -> &'static str {
+    match kind {
+        AccessKind::Read => "read",
+        AccessKind::Write => "write",
+        AccessKind::IoRead => "io_read",
+        AccessKind::IoWrite => "io_write",
+    }
+}
+
+/// Append one entry (with its trailing newline) to `out`.
+fn format_entry(
+    layout: &TraceLayout,
+    symbols: &SymbolTable,
+    entry: &TraceEntry,
+    format: TraceFormat,
+    out: &mut String,
+) {
+    let label = symbols.describe(entry.pc);
+    match format {
+        TraceFormat::Text => format_text(layout, entry, label.as_deref(), out),
+        TraceFormat::Json => format_json(layout, entry, label.as_deref(), out),
+    }
+}
+
+fn format_text(layout: &TraceLayout, entry: &TraceEntry, label: Option<&str>, out: &mut String) {
+    let digits = layout.address_digits;
+    let mut opcode = String::new();
+    for (i, byte) in entry.opcode.iter().enumerate() {
+        if i > 0 {
+            opcode.push(' ');
+        }
+        let _ = write!(opcode, "{byte:02X}");
+    }
+    let width = (layout.opcode_bytes * 3).saturating_sub(1);
+    let _ = write!(
+        out,
+        "{:>12}  {:0digits$X}  {opcode:<width$}",
+        entry.clock, entry.pc
+    );
+    for (path, value) in layout.registers.iter().zip(&entry.registers) {
+        let _ = write!(out, " {}=", register_label(path));
+        match value {
+            Value::Bool(v) => out.push(if *v { '1' } else { '0' }),
+            Value::U8(v) => {
+                let _ = write!(out, "{v:02X}");
+            }
+            Value::U16(v) => {
+                let _ = write!(out, "{v:04X}");
+            }
+            Value::U32(v) => {
+                let _ = write!(out, "{v:08X}");
+            }
+            other => {
+                let _ = write!(out, "{other}");
+            }
+        }
+    }
+    if let Some(label) = label {
+        let _ = write!(out, "  ; {label}");
+    }
+    out.push('\n');
+    for access in &entry.bus {
+        let _ = write!(
+            out,
+            "{:>12}  {:<2} {:0digits$X} {:02X}",
+            "",
+            access_code(access.kind),
+            access.address,
+            access.value
+        );
+        if access.wait > 0 {
+            let _ = write!(out, " +{}", access.wait);
+        }
+        out.push('\n');
+    }
+}
+
+fn format_json(layout: &TraceLayout, entry: &TraceEntry, label: Option<&str>, out: &mut String) {
+    let _ = write!(out, "{{\"clock\":{},\"pc\":{},", entry.clock, entry.pc);
+    if let Some(label) = label {
+        out.push_str("\"label\":");
+        json_string(label, out);
+        out.push(',');
+    }
+    out.push_str("\"opcode\":[");
+    for (i, byte) in entry.opcode.iter().enumerate() {
+        if i > 0 {
+            out.push(',');
+        }
+        let _ = write!(out, "{byte}");
+    }
+    out.push_str("],\"registers\":{");
+    for (i, (path, value)) in layout.registers.iter().zip(&entry.registers).enumerate() {
+        if i > 0 {
+            out.push(',');
+        }
+        json_string(path, out);
+        out.push(':');
+        match value {
+            Value::Bool(v) => {
+                let _ = write!(out, "{v}");
+            }
+            Value::I8(v) => {
+                let _ = write!(out, "{v}");
+            }
+            other => match value_as_u64(other) {
+                Some(v) => {
+                    let _ = write!(out, "{v}");
+                }
+                None => json_string(&other.to_string(), out),
+            },
+        }
+    }
+    out.push_str("},\"bus\":[");
+    for (i, access) in entry.bus.iter().enumerate() {
+        if i > 0 {
+            out.push(',');
+        }
+        let _ = write!(
+            out,
+            "{{\"kind\":\"{}\",\"address\":{},\"value\":{},\"wait\":{}}}",
+            access_name(access.kind),
+            access.address,
+            access.value,
+            access.wait
+        );
+    }
+    out.push_str("]}\n");
+}
+
+fn json_string(s: &str, out: &mut String) {
+    out.push('"');
+    for c in s.chars() {
+        match c {
+            '"' => out.push_str("\\\""),
+            '\\' => out.push_str("\\\\"),
+            c if u32::from(c) < 0x20 => {
+                let _ = write!(out, "\\u{:04x}", u32::from(c));
+            }
+            c => out.push(c),
+        }
+    }
+    out.push('"');
+}
+
+#[cfg(test)]
+mod tests {
+    use std::io::Write;
+    use std::sync::{Arc, Mutex};
+
+    use super::{TraceFormat, TraceLayout, Tracer, cpu_bus_log};
+    use crate::breakpoint::{Access, Breakpoint, Breakpoints, Debuggable, Trigger};
+    use crate::symbols::SymbolTable;
+    use crate::{Bus, BusAccess, LoggingBus, Observable, SimpleBus, Value};
+
+    const LAYOUT: TraceLayout = TraceLayout {
+        address_digits: 4,
+        opcode_bytes: 2,
+        registers: &["cpu.a", "cpu.flag"],
+    };
+
+    /// Toy CPU: one instruction per two ticks; each loads the byte at `pc`
+    /// into A and skips the following operand byte.
+    struct Toy {
+        bus: SimpleBus,
+        clock: u64,
+        pc: u16,
+        a: u8,
+        bus_log: Option<Vec<BusAccess>>,
+        tracer: Option<Tracer>,
+    }
+
+    impl Toy {
+        fn new(tracer: Tracer) -> Self {
+            let mut bus = SimpleBus::new();
+            bus.load(0, &[0x10, 0xAA, 0x20, 0xBB, 0x30, 0xCC, 0x40, 0xDD]);
+            Self {
+                bus,
+                clock: 0,
+                pc: 0,
+                a: 0,
+                bus_log: None,
+                tracer: Some(tracer),
+            }
+        }
+
+        /// Same hook-up as the real machines' CPU tick.
+        fn tick(&mut self) {
+            self.clock += 1;
+            let mark = self.bus_log.as_ref().map_or(0, Vec::len);
+            if self.clock % 2 == 1 {
+                let pc = u32::from(self.pc);
+                self.a = if let Some(log) = cpu_bus_log(&mut self.bus_log, &mut self.tracer) {
+                    LoggingBus::new(&mut self.bus, log).read(pc).data
+                } else {
+                    self.bus.read(pc).data
+                };
+            } else {
+                self.pc = self.pc.wrapping_add(2);
+            }
+            if let Some(mut tracer) = self.tracer.take() {
+                let accesses = self.bus_log.as_deref().map_or(&[][..], |log| &log[mark..]);
+                tracer.observe(&*self, accesses, |pc| self.opcode(pc));
+                self.tracer = Some(tracer);
+            }
+        }
+
+        fn run(&mut self, ticks: u32) {
+            for _ in 0..ticks {
+                self.tick();
+            }
+        }
+
+        fn opcode(&self, pc: u32) -> Vec<u8> {
+            let pc = u16::try_from(pc).expect("16-bit address");
+            vec![self.bus.peek(pc), self.bus.peek(pc + 1)]
+        }
+
+        fn tracer(&mut self) -> &mut Tracer {
+            self.tracer.as_mut().expect("tracer attached")
+        }
+    }
+
+    impl Observable for Toy {
+        fn query(&self, path: &str) -> Option<Value> {
+            match path {
+                "cpu.a" => Some(self.a.into()),
+                "cpu.flag" => Some((self.a >= 0x20).into()),
+                "master_clock" => Some(self.clock.into()),
+                _ => None,
+            }
+        }
+
+        fn query_paths(&self) -> &'static [&'static str] {
+            &["cpu.a", "cpu.flag", "master_clock"]
+        }
+    }
+
+    impl Debuggable for Toy {
+        fn instruction_boundary(&self) -> Option<u32> {
+            self.clock.is_multiple_of(2).then_some(u32::from(self.pc))
+        }
+
+        fn raster_position(&self) -> (u32, u32) {
+            (0, 0)
+        }
+
+        fn set_bus_logging(&mut self, enabled: bool) {
+            self.bus_log = enabled.then(Vec::new);
+        }
+
+        fn drain_bus_accesses(&mut self, out: &mut Vec<BusAccess>) {
+            if let Some(log) = &mut self.bus_log {
+                out.append(log);
+            }
+        }
+    }
+
+    #[test]
+    fn ring_records_instructions_and_bus_cycles_as_text() {
+        let mut toy = Toy::new(Tracer::ring(LAYOUT, 10).with_bus_log(true));
+        toy.run(6);
+
+        let tracer = toy.tracer();
+        assert_eq!(tracer.recorded(), 2);
+        assert_eq!(
+            tracer.export(TraceFormat::Text, None),
+            concat!(
+                "           2  0002  20 BB A=10 FLAG=0\n",
+                "              R  0002 20\n",
+                "           4  0004  30 CC A=20 FLAG=1\n",
+                "              R  0004 30\n",
+                "           6  0006  40 DD A=30 FLAG=1\n",
+            )
+        );
+        assert_eq!(tracer.export(TraceFormat::Text, Some(1)).lines().count(), 1);
+    }
+
+    #[test]
+    fn ring_drops_the_oldest_entries_and_json_matches() {
+        let mut toy = Toy::new(Tracer::ring(LAYOUT, 1));
+        toy.run(6);
+        toy.tracer().stop().expect("ring never fails");
+
+        assert_eq!(toy.tracer().recorded(), 3);
+        assert_eq!(
+            toy.tracer().export(TraceFormat::Json, None),
+            "{\"clock\":6,\"pc\":6,\"opcode\":[64,221],\"registers\":{\"cpu.a\":48,\"cpu.flag\":true},\"bus\":[]}\n"
+        );
+
+        // A stopped tracer ignores further ticks.
+        toy.run(4);
+        assert_eq!(toy.tracer().recorded(), 3);
+    }
+
+    #[test]
+    fn entries_name_the_label_their_pc_falls_under() {
+        let mut symbols = SymbolTable::new();
+        symbols.insert("start", 2);
+        let mut toy = Toy::new(Tracer::ring(LAYOUT, 10).with_symbols(symbols));
+        toy.run(4);
+
+        let tracer = toy.tracer();
+        assert_eq!(
+            tracer.export(TraceFormat::Text, None),
+            concat!(
+                "           2  0002  20 BB A=10 FLAG=0  ; start\n",
+                "           4  0004  30 CC A=20 FLAG=1  ; start+$2\n",
+            )
+        );
+        assert!(
+            tracer
+                .export(TraceFormat::Json, Some(1))
+                .starts_with("{\"clock\":4,\"pc\":4,\"label\":\"start+$2\",\"opcode\":[48,204]")
+        );
+    }
+
+    #[test]
+    fn stream_writes_completed_entries_and_shares_the_bus_with_watchpoints() {
+        #[derive(Clone, Default)]
+        struct Shared(Arc<Mutex<Vec<u8>>>);
+
+        impl Write for Shared {
+            fn write(&mut self, buf: &[u8]) -> std::io::Result<usize> {
+                self.0.lock().expect("lock").write(buf)
+            }
+
+            fn flush(&mut self) -> std::io::Result<()> {
+                Ok(())
+            }
+        }
+
+        let out = Shared::default();
+        let mut toy = Toy::new(
+            Tracer::stream(LAYOUT, Box::new(out.clone()), TraceFormat::Text).with_bus_log(true),
+        );
+        let mut breakpoints = Breakpoints::new();
+        breakpoints.add(Breakpoint::new(Trigger::Memory {
+            range: 4..=4,
+            access: Access::Read,
+        }));
+
+        let hit = breakpoints.run(&mut toy, |toy| {
+            toy.tick();
+            true
+        });
+        assert_eq!(hit.and_then(|h| h.access).map(|a| a.value), Some(0x30));
+        assert_eq!(toy.tracer().entries().count(), 0);
+
+        toy.tracer().stop().expect("flush");
+        let text = String::from_utf8(out.0.lock().expect("lock").clone()).expect("utf8");
+        assert_eq!(
+            text,
+            concat!(
+                "           2  0002  20 BB A=10 FLAG=0\n",
+                "              R  0002 20\n",
+                "           4  0004  30 CC A=20 FLAG=1\n",
+                "              R  0004 30\n",
+            )
+        );
+    }
+}
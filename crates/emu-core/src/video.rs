@@ -20,6 +20,8 @@ use openh264::encoder::{
 };
 use openh264::formats::{RgbaSliceU8, YUVBuffer};
 
+use crate::crt::CrtFilter;
+
 /// Information returned after recording completes.
 pub struct VideoInfo {
     /// Total frames recorded.
@@ -61,6 +63,8 @@ pub struct VideoRecorder {
     aac_output: Vec<u8>,
     /// Reusable buffer for scaled pixels (empty when no scaling needed).
     scale_buf: Vec<u32>,
+    /// CRT filter applied to each frame before encoding.
+    filter: Option<CrtFilter>,
 }
 
 impl VideoRecorder {
@@ -150,9 +154,17 @@ impl VideoRecorder {
             } else {
                 Vec::new()
             },
+            filter: None,
         })
     }
 
+    /// Filter every frame through `filter`, which also scales it to the
+    /// encoded size. For scanlines to show, create the recorder with a
+    /// display size of at least [`CrtFilter::output_size`].
+    pub fn set_filter(&mut self, filter: CrtFilter) {
+        self.filter = Some(filter);
+    }
+
     /// Add one video frame (RGBA `u32` pixels, packed `0x00RRGGBB`) and its
     /// corresponding audio samples (`f32` in -1..1, mono or interleaved stereo).
     pub fn add_frame(&mut self, pixels: &[u32], audio: &[f32]) -> Result<(), String> {
@@ -183,8 +195,13 @@ impl VideoRecorder {
         let h = self.height as usize;
         let expected = w * h;
 
-        // Scale from source dimensions to encode dimensions if needed.
-        let src_pixels = if !self.scale_buf.is_empty() {
+        // Filter, or scale from source dimensions to encode dimensions if
+        // needed.
+        let src_pixels = if let Some(filter) = &mut self.filter {
+            self.scale_buf =
+                filter.process(self.src_w, self.src_h, pixels, self.width, self.height);
+            &self.scale_buf
+        } else if !self.scale_buf.is_empty() {
             scale_nearest_into(
                 pixels,
                 self.src_w,
@@ -0,0 +1,292 @@
+//! Warp (fast-forward) mode.
+//!
+//! A paced runner shows one frame per host frame. In warp it runs a batch
+//! of frames back to back for each host frame instead, and shows only the
+//! last. Either the user toggles warp, or auto-warp switches it on while
+//! [`Machine::media_busy`] reports a tape or disk drive running, so loads
+//! finish in seconds.
+//!
+//! A batch is either a fixed number of frames (a frame skip) or as many
+//! as fit in the host frame's time budget. Audio from a batch is muted,
+//! or squeezed into one frame's worth of samples so it plays back at a
+//! higher pitch, like a tape on fast-forward.
+//!
+//! Nothing here depends on windowing, so each runner drives the same
+//! logic.
+
+use std::time::{Duration, Instant};
+
+use crate::{AudioFrame, Machine};
+
+/// What to do with the audio from frames run in warp.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum WarpAudio {
+    /// Play nothing while warping.
+    #[default]
+    Mute,
+    /// Play each batch's audio in the time of one frame, raising its pitch.
+    Pitch,
+}
+
+/// Warp settings and state.
+#[derive(Debug, Clone)]
+pub struct Warp {
+    /// Warp switched on by the user.
+    enabled: bool,
+    /// Warp while the machine reports slow media running.
+    auto: bool,
+    /// Frames skipped per frame shown; `None` fills the time budget.
+    frame_skip: Option<u32>,
+    audio: WarpAudio,
+}
+
+impl Default for Warp {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl Warp {
+    /// Warp off, auto-warp on, no fixed frame skip, audio muted.
+    #[must_use]
+    pub fn new() -> Self {
+        Self {
+            enabled: false,
+            auto: true,
+            frame_skip: None,
+            audio: WarpAudio::Mute,
+        }
+    }
+
+    /// Enable or disable auto-warp (default: enabled).
+    #[must_use]
+    pub fn with_auto(mut self, auto: bool) -> Self {
+        self.auto = auto;
+        self
+    }
+
+    /// Run `skip` hidden frames for every frame shown, rather than as many
+    /// as fit in the time budget (default: `None`).
+    #[must_use]
+    pub fn with_frame_skip(mut self, skip: Option<u32>) -> Self {
+        self.frame_skip = skip;
+        self
+    }
+
+    /// Set the warp audio handling (default: muted).
+    #[must_use]
+    pub fn with_audio(mut self, audio: WarpAudio) -> Self {
+        self.audio = audio;
+        self
+    }
+
+    /// Switch manual warp on or off. Returns the new setting.
+    pub fn toggle(&mut self) -> bool {
+        self.enabled = !self.enabled;
+        self.enabled
+    }
+
+    /// Switch manual warp on or off.
+    pub fn set_enabled(&mut self, enabled: bool) {
+        self.enabled = enabled;
+    }
+
+    /// Whether manual warp is on.
+    #[must_use]
+    pub fn is_enabled(&self) -> bool {
+        self.enabled
+    }
+
+    /// Whether auto-warp is on.
+    #[must_use]
+    pub fn is_auto(&self) -> bool {
+        self.auto
+    }
+
+    /// Whether `machine` should be warping now.
+    #[must_use]
+    pub fn is_active<M: Machine + ?Sized>(&self, machine: &M) -> bool {
+        self.enabled || (self.auto && machine.media_busy())
+    }
+
+    /// Run one batch of frames.
+    ///
+    /// Runs `frame_skip + 1` frames, or with no frame skip, frames until
+    /// `budget` has passed. Always runs at least one frame, and stops early
+    /// if warp switches off (auto-warp ends when the media stops).
+    /// `each_frame` runs after every frame, for rewind snapshots and the
+    /// like; the batch holds all the audio, for capture and
+    /// [`playback`](Self::playback).
+    pub fn run<M: Machine>(
+        &self,
+        machine: &mut M,
+        budget: Duration,
+        mut each_frame: impl FnMut(&mut M),
+    ) -> WarpBatch {
+        let start = Instant::now();
+        let mut batch = WarpBatch {
+            frames: 0,
+            audio: Vec::new(),
+        };
+        loop {
+            machine.run_frame();
+            batch.audio.extend(machine.take_audio_buffer());
+            each_frame(machine);
+            batch.frames += 1;
+
+            let full = match self.frame_skip {
+                Some(skip) => batch.frames > skip,
+                None => start.elapsed() >= budget,
+            };
+            if full || !self.is_active(machine) {
+                return batch;
+            }
+        }
+    }
+
+    /// The audio to play for `batch`.
+    #[must_use]
+    #[allow(clippy::cast_precision_loss)]
+    pub fn playback(&self, batch: &WarpBatch) -> Vec<AudioFrame> {
+        match self.audio {
+            WarpAudio::Mute => Vec::new(),
+            WarpAudio::Pitch => {
+                // Average runs of `frames` samples: one frame's worth of
+                // audio, the waveform sped up by the warp factor.
+                let step = batch.frames.max(1) as usize;
+                batch
+                    .audio
+                    .chunks(step)
+                    .map(|run| {
+                        let n = run.len() as f32;
+                        let (l, r) = run
+                            .iter()
+                            .fold((0.0, 0.0), |(l, r), s| (l + s[0], r + s[1]));
+                        [l / n, r / n]
+                    })
+                    .collect()
+            }
+        }
+    }
+}
+
+/// Frames and audio from one [`Warp::run`].
+#[derive(Debug, Clone, Default)]
+pub struct WarpBatch {
+    /// Frames run.
+    pub frames: u32,
+    /// All the audio those frames produced, at the machine's sample rate.
+    pub audio: Vec<AudioFrame>,
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Emits four samples per frame; the "tape" runs for a set number of
+    /// frames.
+    struct Deck {
+        frames: u64,
+        tape_frames_left: u32,
+    }
+
+    impl Machine for Deck {
+        fn run_frame(&mut self) {
+            self.frames += 1;
+            self.tape_frames_left = self.tape_frames_left.saturating_sub(1);
+        }
+
+        fn framebuffer(&self) -> &[u32] {
+            &[]
+        }
+
+        fn framebuffer_width(&self) -> u32 {
+            0
+        }
+
+        fn framebuffer_height(&self) -> u32 {
+            0
+        }
+
+        fn take_audio_buffer(&mut self) -> Vec<AudioFrame> {
+            vec![[0.5, -0.5]; 4]
+        }
+
+        fn frame_count(&self) -> u64 {
+            self.frames
+        }
+
+        fn reset(&mut self) {
+            self.frames = 0;
+        }
+
+        fn media_busy(&self) -> bool {
+            self.tape_frames_left > 0
+        }
+    }
+
+    fn deck(tape_frames: u32) -> Deck {
+        Deck {
+            frames: 0,
+            tape_frames_left: tape_frames,
+        }
+    }
+
+    #[test]
+    fn auto_warp_follows_media() {
+        let warp = Warp::new();
+        assert!(warp.is_active(&deck(10)));
+        assert!(!warp.is_active(&deck(0)));
+        assert!(!warp.with_auto(false).is_active(&deck(10)));
+    }
+
+    #[test]
+    fn manual_toggle() {
+        let mut warp = Warp::new().with_auto(false);
+        assert!(warp.toggle());
+        assert!(warp.is_active(&deck(0)));
+        assert!(!warp.toggle());
+        assert!(!warp.is_active(&deck(0)));
+    }
+
+    #[test]
+    fn frame_skip_sets_batch_size() {
+        let mut warp = Warp::new().with_frame_skip(Some(3));
+        warp.set_enabled(true);
+        let mut machine = deck(0);
+        let mut seen = 0;
+        let batch = warp.run(&mut machine, Duration::ZERO, |_| seen += 1);
+        assert_eq!(batch.frames, 4);
+        assert_eq!(seen, 4);
+        assert_eq!(batch.audio.len(), 16);
+        assert_eq!(machine.frames, 4);
+    }
+
+    #[test]
+    fn auto_warp_stops_mid_batch_when_media_stops() {
+        let warp = Warp::new().with_frame_skip(Some(100));
+        let mut machine = deck(5);
+        let batch = warp.run(&mut machine, Duration::ZERO, |_| {});
+        assert_eq!(batch.frames, 5);
+        assert!(!warp.is_active(&machine));
+    }
+
+    #[test]
+    fn time_budget_runs_at_least_one_frame() {
+        let mut warp = Warp::new();
+        warp.set_enabled(true);
+        let batch = warp.run(&mut deck(0), Duration::ZERO, |_| {});
+        assert_eq!(batch.frames, 1);
+    }
+
+    #[test]
+    fn pitch_squeezes_batch_into_one_frame() {
+        let batch = WarpBatch {
+            frames: 2,
+            audio: vec![[1.0, -1.0], [3.0, -3.0], [5.0, -5.0], [7.0, -7.0]],
+        };
+        let pitched = Warp::new().with_audio(WarpAudio::Pitch).playback(&batch);
+        assert_eq!(pitched, vec![[2.0, -2.0], [6.0, -6.0]]);
+        assert!(Warp::new().playback(&batch).is_empty());
+    }
+}
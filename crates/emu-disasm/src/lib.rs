@@ -0,0 +1,38 @@
+//! Disassemblers for the CPU families in the workspace.
+//!
+//! Each module decodes one instruction at a time from a side-effect-free
+//! read function and returns an [`emu_core::Instruction`]. Reads never go
+//! through a system bus, so disassembling can't trigger I/O or disturb
+//! emulation state.
+//!
+//! - [`mos6502`]: NMOS 6502 including every illegal opcode the
+//!   `mos-6502` core executes.
+//! - [`z80`]: Z80 including CB/ED/DD/FD/DDCB/FDCB prefixes and the
+//!   undocumented operations.
+//! - [`m68k`]: 68000 through 68060, with FPU and MMU instructions gated
+//!   by `CpuCapabilities`.
+
+pub mod m68k;
+pub mod mos6502;
+pub mod z80;
+
+pub use emu_core::Instruction;
+
+/// Disassemble `count` consecutive instructions starting at `address`.
+///
+/// `decode` is one of the per-CPU `disassemble` functions with its read
+/// function (and model, for the 68000) already bound.
+pub fn disassemble_range(
+    address: u32,
+    count: usize,
+    mut decode: impl FnMut(u32) -> Instruction,
+) -> Vec<Instruction> {
+    let mut out = Vec::with_capacity(count);
+    let mut pc = address;
+    for _ in 0..count {
+        let insn = decode(pc);
+        pc = insn.next_address();
+        out.push(insn);
+    }
+    out
+}
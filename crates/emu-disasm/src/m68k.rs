@@ -0,0 +1,1514 @@
+//! 68000-family disassembler.
+//!
+//! Decodes the integer instruction set of every `CpuModel`, from the
+//! 68000 through the 68060. Instructions a model lacks — 68010 MOVEC and
+//! RTD, 68020 bit fields and 32-bit multiply/divide, 68040 MOVE16 and
+//! cache control — decode as `DC.W` on the models without them. FPU
+//! (coprocessor 1) and MMU (PMMU and 68040-style) instructions are gated
+//! by `CpuCapabilities::fpu` and `CpuCapabilities::mmu`.
+//!
+//! Output uses Motorola syntax. PC-relative operands are shown with the
+//! resolved address (`$00FC00D2(PC)`), and branch, jump and call targets
+//! are reported in `Instruction::target`.
+
+use emu_core::Instruction;
+use motorola_68000::{CpuCapabilities, CpuModel, TimingClass};
+
+/// Operand size.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Size {
+    Byte,
+    Word,
+    Long,
+}
+
+impl Size {
+    /// Decode the common two-bit size field (00 = B, 01 = W, 10 = L).
+    fn from_bits(bits: u16) -> Option<Self> {
+        match bits & 3 {
+            0 => Some(Self::Byte),
+            1 => Some(Self::Word),
+            2 => Some(Self::Long),
+            _ => None,
+        }
+    }
+
+    const fn suffix(self) -> &'static str {
+        match self {
+            Self::Byte => ".B",
+            Self::Word => ".W",
+            Self::Long => ".L",
+        }
+    }
+}
+
+/// Integer condition codes, in encoding order.
+const CONDITIONS: [&str; 16] = [
+    "T", "F", "HI", "LS", "CC", "CS", "NE", "EQ", "VC", "VS", "PL", "MI", "GE", "LT", "GT", "LE",
+];
+
+/// FPU conditional predicates, in encoding order.
+const FPU_CONDITIONS: [&str; 32] = [
+    "F", "EQ", "OGT", "OGE", "OLT", "OLE", "OGL", "OR", "UN", "UEQ", "UGT", "UGE", "ULT", "ULE",
+    "NE", "T", "SF", "SEQ", "GT", "GE", "LT", "LE", "GL", "GLE", "NGLE", "NGL", "NLE", "NLT",
+    "NGE", "NGT", "SNE", "ST",
+];
+
+/// FPU data formats for the source specifier field, with their size in
+/// bytes as an immediate.
+const FPU_FORMATS: [(&str, u32); 8] = [
+    (".L", 4),
+    (".S", 4),
+    (".X", 12),
+    (".P", 12),
+    (".W", 2),
+    (".D", 8),
+    (".B", 2),
+    (".P", 12),
+];
+
+/// Decoding state for one instruction.
+struct Decoder<F> {
+    read: F,
+    start: u32,
+    bytes: Vec<u8>,
+    model: CpuModel,
+    caps: CpuCapabilities,
+    target: Option<u32>,
+}
+
+impl<F: Fn(u32) -> u8> Decoder<F> {
+    /// Address of the next unread byte.
+    fn pos(&self) -> u32 {
+        self.start.wrapping_add(self.bytes.len() as u32)
+    }
+
+    fn word(&mut self) -> u16 {
+        let at = self.pos();
+        let hi = (self.read)(at);
+        let lo = (self.read)(at.wrapping_add(1));
+        self.bytes.extend([hi, lo]);
+        u16::from_be_bytes([hi, lo])
+    }
+
+    fn long(&mut self) -> u32 {
+        let hi = self.word();
+        let lo = self.word();
+        (u32::from(hi) << 16) | u32::from(lo)
+    }
+
+    /// 68010 and later.
+    fn is_010(&self) -> bool {
+        self.caps.movec
+    }
+
+    /// 68020 and later.
+    fn is_020(&self) -> bool {
+        self.model.timing_class() != TimingClass::M68000
+    }
+
+    /// 68040 and later.
+    fn is_040(&self) -> bool {
+        matches!(
+            self.model.timing_class(),
+            TimingClass::M68040 | TimingClass::M68060
+        )
+    }
+
+    fn immediate(&mut self, size: Size) -> String {
+        match size {
+            Size::Byte => format!("#${:02X}", self.word() & 0xFF),
+            Size::Word => format!("#${:04X}", self.word()),
+            Size::Long => format!("#${:08X}", self.long()),
+        }
+    }
+
+    /// Effective address operand. Returns `None` for invalid modes.
+    fn ea(&mut self, mode: u16, reg: u16, size: Size) -> Option<String> {
+        Some(match mode {
+            0 => format!("D{reg}"),
+            1 => format!("A{reg}"),
+            2 => format!("(A{reg})"),
+            3 => format!("(A{reg})+"),
+            4 => format!("-(A{reg})"),
+            5 => format!("{}(A{reg})", signed_hex(i32::from(self.word() as i16))),
+            6 => self.indexed(&format!("A{reg}"), None)?,
+            _ => match reg {
+                0 => format!("${:04X}.W", self.word()),
+                1 => format!("${:08X}", self.long()),
+                2 => {
+                    let base = self.pos();
+                    let dest = base.wrapping_add(self.word() as i16 as u32);
+                    format!("${dest:08X}(PC)")
+                }
+                3 => {
+                    let base = self.pos();
+                    self.indexed("PC", Some(base))?
+                }
+                4 => self.immediate(size),
+                _ => return None,
+            },
+        })
+    }
+
+    /// Effective address from the low six bits of an opcode.
+    fn ea_field(&mut self, op: u16, size: Size) -> Option<String> {
+        self.ea((op >> 3) & 7, op & 7, size)
+    }
+
+    /// Address a control-flow effective address resolves to, when it
+    /// doesn't depend on registers.
+    fn static_target(&self, mode: u16, reg: u16, ext: &[u8]) -> Option<u32> {
+        let word = |i: usize| u16::from_be_bytes([ext[i], ext[i + 1]]);
+        match (mode, reg) {
+            (7, 0) => Some(word(0) as i16 as u32),
+            (7, 1) => Some((u32::from(word(0)) << 16) | u32::from(word(2))),
+            (7, 2) => Some(
+                self.start
+                    .wrapping_add(2)
+                    .wrapping_add(word(0) as i16 as u32),
+            ),
+            _ => None,
+        }
+    }
+
+    /// Index register text (`D3.W`, `A0.L*4`) from an extension word.
+    fn index_register(&self, ext: u16) -> String {
+        let kind = if ext & 0x8000 == 0 { 'D' } else { 'A' };
+        let size = if ext & 0x0800 == 0 { 'W' } else { 'L' };
+        let scale = 1 << ((ext >> 9) & 3);
+        if scale == 1 || !self.caps.scaled_index {
+            format!("{kind}{}.{size}", (ext >> 12) & 7)
+        } else {
+            format!("{kind}{}.{size}*{scale}", (ext >> 12) & 7)
+        }
+    }
+
+    /// Indexed modes: the brief format on every model, the full format
+    /// (base/outer displacements, memory indirection) on the 68020+.
+    /// `pc_base` is the extension word address for PC-relative modes.
+    fn indexed(&mut self, base: &str, pc_base: Option<u32>) -> Option<String> {
+        let ext = self.word();
+        let index = self.index_register(ext);
+
+        if ext & 0x0100 == 0 || !self.caps.scaled_index {
+            let disp = i32::from(ext as u8 as i8);
+            return Some(match pc_base {
+                Some(pc) => format!("${:08X}(PC,{index})", pc.wrapping_add(disp as u32)),
+                None => format!("{}({base},{index})", signed_hex(disp)),
+            });
+        }
+
+        let base_suppressed = ext & 0x0080 != 0;
+        let index_suppressed = ext & 0x0040 != 0;
+        let bd = match (ext >> 4) & 3 {
+            1 => None,
+            2 => Some(i32::from(self.word() as i16)),
+            3 => Some(self.long() as i32),
+            _ => return None,
+        };
+        let iis = ext & 7;
+        let od = match iis & 3 {
+            2 => Some(i32::from(self.word() as i16)),
+            3 => Some(self.long() as i32),
+            _ => None,
+        };
+
+        let mut inner: Vec<String> = Vec::new();
+        if let Some(bd) = bd {
+            inner.push(signed_hex(bd));
+        }
+        if !base_suppressed {
+            inner.push(base.to_string());
+        }
+        let index = (!index_suppressed).then_some(index);
+
+        let text = match (index_suppressed, iis) {
+            (_, 0) => {
+                inner.extend(index);
+                format!("({})", inner.join(","))
+            }
+            (false, 1..=3) => {
+                inner.extend(index);
+                outer(&format!("[{}]", inner.join(",")), None, od)
+            }
+            (false, 5..=7) => outer(&format!("[{}]", inner.join(",")), index, od),
+            (true, 1..=3) => outer(&format!("[{}]", inner.join(",")), None, od),
+            _ => return None,
+        };
+        Some(text)
+    }
+
+    /// Decode one instruction, returning mnemonic and operands.
+    fn decode(&mut self) -> Option<(String, String)> {
+        let op = self.word();
+        match op >> 12 {
+            0x0 => self.line_0(op),
+            0x1 => self.move_(op, Size::Byte),
+            0x2 => self.move_(op, Size::Long),
+            0x3 => self.move_(op, Size::Word),
+            0x4 => self.line_4(op),
+            0x5 => self.line_5(op),
+            0x6 => Some(self.branch(op)),
+            0x7 => {
+                if op & 0x0100 != 0 {
+                    return None;
+                }
+                Some(ins(
+                    "MOVEQ",
+                    format!("#{},D{}", op as u8 as i8, (op >> 9) & 7),
+                ))
+            }
+            0x8 => self.line_8(op),
+            0x9 | 0xD => self.add_sub(op),
+            0xB => self.line_b(op),
+            0xC => self.line_c(op),
+            0xE => self.line_e(op),
+            0xF => self.line_f(op),
+            // Line A is unimplemented on every model.
+            _ => None,
+        }
+    }
+
+    fn line_0(&mut self, op: u16) -> Option<(String, String)> {
+        let mode = (op >> 3) & 7;
+        let reg = op & 7;
+        let dreg = (op >> 9) & 7;
+
+        if op & 0x0138 == 0x0108 {
+            let disp = signed_hex(i32::from(self.word() as i16));
+            let size = if op & 0x0040 == 0 { ".W" } else { ".L" };
+            let mem = format!("{disp}(A{reg})");
+            return Some(if op & 0x0080 == 0 {
+                ins(&format!("MOVEP{size}"), format!("{mem},D{dreg}"))
+            } else {
+                ins(&format!("MOVEP{size}"), format!("D{dreg},{mem}"))
+            });
+        }
+
+        if op & 0x0100 != 0 {
+            let name = ["BTST", "BCHG", "BCLR", "BSET"][usize::from((op >> 6) & 3)];
+            let ea = self.ea_field(op, Size::Byte)?;
+            return Some(ins(name, format!("D{dreg},{ea}")));
+        }
+
+        let size_bits = (op >> 6) & 3;
+        match dreg {
+            4 => {
+                let name = ["BTST", "BCHG", "BCLR", "BSET"][usize::from(size_bits)];
+                let bit = self.word() & 0xFF;
+                let ea = self.ea_field(op, Size::Byte)?;
+                Some(ins(name, format!("#{bit},{ea}")))
+            }
+            0..=2 if size_bits == 3 => {
+                if !self.is_020() {
+                    return None;
+                }
+                let size = Size::from_bits(dreg)?;
+                let ext = self.word();
+                let name = if ext & 0x0800 == 0 { "CMP2" } else { "CHK2" };
+                let ea = self.ea_field(op, size)?;
+                Some(ins(
+                    &format!("{name}{}", size.suffix()),
+                    format!("{ea},{}", general_register(ext >> 12)),
+                ))
+            }
+            3 if size_bits == 3 => {
+                if !matches!(self.model, CpuModel::M68020 | CpuModel::M68EC020) {
+                    return None;
+                }
+                if mode <= 1 {
+                    Some(ins("RTM", general_register(op & 0xF)))
+                } else {
+                    let args = self.word() & 0xFF;
+                    let ea = self.ea_field(op, Size::Byte)?;
+                    Some(ins("CALLM", format!("#{args},{ea}")))
+                }
+            }
+            5..=7 if size_bits == 3 => {
+                if !self.caps.cas {
+                    return None;
+                }
+                let size = match dreg & 3 {
+                    1 => Size::Byte,
+                    2 => Size::Word,
+                    _ => Size::Long,
+                };
+                if op & 0x3F == 0x3C {
+                    if size == Size::Byte {
+                        return None;
+                    }
+                    let e1 = self.word();
+                    let e2 = self.word();
+                    return Some(ins(
+                        &format!("CAS2{}", size.suffix()),
+                        format!(
+                            "D{}:D{},D{}:D{},({}):({})",
+                            e1 & 7,
+                            e2 & 7,
+                            (e1 >> 6) & 7,
+                            (e2 >> 6) & 7,
+                            general_register(e1 >> 12),
+                            general_register(e2 >> 12)
+                        ),
+                    ));
+                }
+                let ext = self.word();
+                let ea = self.ea_field(op, size)?;
+                Some(ins(
+                    &format!("CAS{}", size.suffix()),
+                    format!("D{},D{},{ea}", ext & 7, (ext >> 6) & 7),
+                ))
+            }
+            7 => {
+                if !self.is_010() {
+                    return None;
+                }
+                let size = Size::from_bits(size_bits)?;
+                let ext = self.word();
+                let rn = general_register(ext >> 12);
+                let ea = self.ea_field(op, size)?;
+                let name = format!("MOVES{}", size.suffix());
+                Some(if ext & 0x0800 == 0 {
+                    ins(&name, format!("{ea},{rn}"))
+                } else {
+                    ins(&name, format!("{rn},{ea}"))
+                })
+            }
+            _ => {
+                let name = match dreg {
+                    0 => "ORI",
+                    1 => "ANDI",
+                    2 => "SUBI",
+                    3 => "ADDI",
+                    5 => "EORI",
+                    6 => "CMPI",
+                    _ => return None,
+                };
+                let size = Size::from_bits(size_bits)?;
+                if op & 0x3F == 0x3C && matches!(dreg, 0 | 1 | 5) {
+                    return match size {
+                        Size::Byte => Some(ins(name, format!("{},CCR", self.immediate(size)))),
+                        Size::Word => Some(ins(name, format!("{},SR", self.immediate(size)))),
+                        Size::Long => None,
+                    };
+                }
+                let imm = self.immediate(size);
+                let ea = self.ea_field(op, size)?;
+                Some(ins(
+                    &format!("{name}{}", size.suffix()),
+                    format!("{imm},{ea}"),
+                ))
+            }
+        }
+    }
+
+    fn move_(&mut self, op: u16, size: Size) -> Option<(String, String)> {
+        let src = self.ea_field(op, size)?;
+        let dst_mode = (op >> 6) & 7;
+        let dst_reg = (op >> 9) & 7;
+        if dst_mode == 1 {
+            if size == Size::Byte {
+                return None;
+            }
+            return Some(ins(
+                &format!("MOVEA{}", size.suffix()),
+                format!("{src},A{dst_reg}"),
+            ));
+        }
+        let dst = self.ea(dst_mode, dst_reg, size)?;
+        Some(ins(
+            &format!("MOVE{}", size.suffix()),
+            format!("{src},{dst}"),
+        ))
+    }
+
+    fn line_4(&mut self, op: u16) -> Option<(String, String)> {
+        let mode = (op >> 3) & 7;
+        let reg = op & 7;
+        let areg = (op >> 9) & 7;
+
+        if op & 0xFFF8 == 0x49C0 {
+            return self.caps.extb_l.then(|| ins("EXTB.L", format!("D{reg}")));
+        }
+        match op & 0x01C0 {
+            0x01C0 => {
+                let ea = self.ea_field(op, Size::Long)?;
+                return Some(ins("LEA", format!("{ea},A{areg}")));
+            }
+            0x0180 => {
+                let ea = self.ea_field(op, Size::Word)?;
+                return Some(ins("CHK.W", format!("{ea},D{areg}")));
+            }
+            0x0100 => {
+                if !self.is_020() {
+                    return None;
+                }
+                let ea = self.ea_field(op, Size::Long)?;
+                return Some(ins("CHK.L", format!("{ea},D{areg}")));
+            }
+            _ => {}
+        }
+
+        let size_bits = (op >> 6) & 3;
+        match (op >> 8) & 0xF {
+            0x0 | 0x2 | 0x4 | 0x6 if size_bits < 3 => {
+                let name = ["NEGX", "CLR", "NEG", "NOT"][usize::from((op >> 9) & 3)];
+                let size = Size::from_bits(size_bits)?;
+                let ea = self.ea_field(op, size)?;
+                Some(ins(&format!("{name}{}", size.suffix()), ea))
+            }
+            0x0 => Some(ins(
+                "MOVE",
+                format!("SR,{}", self.ea_field(op, Size::Word)?),
+            )),
+            0x2 => {
+                if !self.is_010() {
+                    return None;
+                }
+                Some(ins(
+                    "MOVE",
+                    format!("CCR,{}", self.ea_field(op, Size::Word)?),
+                ))
+            }
+            0x4 => Some(ins(
+                "MOVE",
+                format!("{},CCR", self.ea_field(op, Size::Word)?),
+            )),
+            0x6 => Some(ins(
+                "MOVE",
+                format!("{},SR", self.ea_field(op, Size::Word)?),
+            )),
+            0x8 => match size_bits {
+                0 if mode == 1 => {
+                    if !self.is_020() {
+                        return None;
+                    }
+                    Some(ins("LINK.L", format!("A{reg},#${:08X}", self.long())))
+                }
+                0 => Some(ins("NBCD", self.ea_field(op, Size::Byte)?)),
+                1 if mode == 0 => Some(ins("SWAP", format!("D{reg}"))),
+                1 if mode == 1 => self.is_010().then(|| ins("BKPT", format!("#{reg}"))),
+                1 => Some(ins("PEA", self.ea_field(op, Size::Long)?)),
+                _ if mode == 0 => {
+                    let size = if size_bits == 2 { "EXT.W" } else { "EXT.L" };
+                    Some(ins(size, format!("D{reg}")))
+                }
+                _ => {
+                    let mask = self.word();
+                    let size = if size_bits == 2 { "MOVEM.W" } else { "MOVEM.L" };
+                    let ea = self.ea_field(op, Size::Long)?;
+                    Some(ins(
+                        size,
+                        format!("{},{ea}", register_list(mask, mode == 4)),
+                    ))
+                }
+            },
+            0xA => {
+                if op == 0x4AFC {
+                    return Some(ins("ILLEGAL", String::new()));
+                }
+                if size_bits == 3 {
+                    return Some(ins("TAS", self.ea_field(op, Size::Byte)?));
+                }
+                let size = Size::from_bits(size_bits)?;
+                Some(ins(
+                    &format!("TST{}", size.suffix()),
+                    self.ea_field(op, size)?,
+                ))
+            }
+            0xC => match size_bits {
+                0 | 1 => {
+                    if !self.caps.mull_divl {
+                        return None;
+                    }
+                    let ext = self.word();
+                    let signed = ext & 0x0800 != 0;
+                    let quad = ext & 0x0400 != 0;
+                    let dl = (ext >> 12) & 7;
+                    let dh = ext & 7;
+                    let ea = self.ea_field(op, Size::Long)?;
+                    if size_bits == 0 {
+                        let name = if signed { "MULS.L" } else { "MULU.L" };
+                        let dst = if quad {
+                            format!("D{dh}:D{dl}")
+                        } else {
+                            format!("D{dl}")
+                        };
+                        Some(ins(name, format!("{ea},{dst}")))
+                    } else {
+                        let (name, dst) = match (quad, dh == dl) {
+                            (true, _) => ("DIV", format!("D{dh}:D{dl}")),
+                            (false, true) => ("DIV", format!("D{dl}")),
+                            (false, false) => ("DIVL", format!("D{dh}:D{dl}")),
+                        };
+                        let sign = if signed { "S" } else { "U" };
+                        let name = if name == "DIV" {
+                            format!("DIV{sign}.L")
+                        } else {
+                            format!("DIV{sign}L.L")
+                        };
+                        Some(ins(&name, format!("{ea},{dst}")))
+                    }
+                }
+                _ => {
+                    let mask = self.word();
+                    let size = if size_bits == 2 { "MOVEM.W" } else { "MOVEM.L" };
+                    let ea = self.ea_field(op, Size::Long)?;
+                    Some(ins(size, format!("{ea},{}", register_list(mask, false))))
+                }
+            },
+            0xE => self.line_4e(op),
+            _ => None,
+        }
+    }
+
+    fn line_4e(&mut self, op: u16) -> Option<(String, String)> {
+        let reg = op & 7;
+        match op & 0xFFC0 {
+            0x4E80 | 0x4EC0 => {
+                let name = if op & 0x0040 == 0 { "JSR" } else { "JMP" };
+                let before = self.bytes.len();
+                let ea = self.ea_field(op, Size::Long)?;
+                let ext = self.bytes[before..].to_vec();
+                self.target = self.static_target((op >> 3) & 7, reg, &ext);
+                return Some(ins(name, ea));
+            }
+            0x4E40 => {}
+            _ => return None,
+        }
+        Some(match op {
+            0x4E40..=0x4E4F => ins("TRAP", format!("#{}", op & 0xF)),
+            0x4E50..=0x4E57 => ins(
+                "LINK",
+                format!("A{reg},#{}", signed_hex(i32::from(self.word() as i16))),
+            ),
+            0x4E58..=0x4E5F => ins("UNLK", format!("A{reg}")),
+            0x4E60..=0x4E67 => ins("MOVE", format!("A{reg},USP")),
+            0x4E68..=0x4E6F => ins("MOVE", format!("USP,A{reg}")),
+            0x4E70 => ins("RESET", String::new()),
+            0x4E71 => ins("NOP", String::new()),
+            0x4E72 => ins("STOP", format!("#${:04X}", self.word())),
+            0x4E73 => ins("RTE", String::new()),
+            0x4E74 if self.is_010() => ins(
+                "RTD",
+                format!("#{}", signed_hex(i32::from(self.word() as i16))),
+            ),
+            0x4E75 => ins("RTS", String::new()),
+            0x4E76 => ins("TRAPV", String::new()),
+            0x4E77 => ins("RTR", String::new()),
+            0x4E7A | 0x4E7B if self.caps.movec => {
+                let ext = self.word();
+                let rn = general_register(ext >> 12);
+                let rc = control_register(ext & 0x0FFF)?;
+                if op == 0x4E7A {
+                    ins("MOVEC", format!("{rc},{rn}"))
+                } else {
+                    ins("MOVEC", format!("{rn},{rc}"))
+                }
+            }
+            _ => return None,
+        })
+    }
+
+    fn line_5(&mut self, op: u16) -> Option<(String, String)> {
+        let mode = (op >> 3) & 7;
+        let reg = op & 7;
+        let cond = CONDITIONS[usize::from((op >> 8) & 0xF)];
+
+        let Some(size) = Size::from_bits(op >> 6) else {
+            if mode == 1 {
+                let base = self.pos();
+                let dest = base.wrapping_add(self.word() as i16 as u32);
+                self.target = Some(dest);
+                let name = if cond == "F" {
+                    "DBRA".to_string()
+                } else {
+                    format!("DB{cond}")
+                };
+                return Some(ins(&name, format!("D{reg},${dest:08X}")));
+            }
+            if mode == 7 && (2..=4).contains(&reg) {
+                if !self.is_020() {
+                    return None;
+                }
+                return Some(match reg {
+                    2 => ins(&format!("TRAP{cond}.W"), format!("#${:04X}", self.word())),
+                    3 => ins(&format!("TRAP{cond}.L"), format!("#${:08X}", self.long())),
+                    _ => ins(&format!("TRAP{cond}"), String::new()),
+                });
+            }
+            return Some(ins(&format!("S{cond}"), self.ea_field(op, Size::Byte)?));
+        };
+
+        let data = match (op >> 9) & 7 {
+            0 => 8,
+            n => n,
+        };
+        let name = if op & 0x0100 == 0 { "ADDQ" } else { "SUBQ" };
+        let ea = self.ea_field(op, size)?;
+        Some(ins(
+            &format!("{name}{}", size.suffix()),
+            format!("#{data},{ea}"),
+        ))
+    }
+
+    fn branch(&mut self, op: u16) -> (String, String) {
+        let base = self.pos();
+        let (disp, suffix) = match op & 0xFF {
+            0 => (self.word() as i16 as u32, ".W"),
+            0xFF if self.is_020() => (self.long(), ".L"),
+            d => (d as u8 as i8 as u32, ".S"),
+        };
+        let dest = base.wrapping_add(disp);
+        self.target = Some(dest);
+        let name = match (op >> 8) & 0xF {
+            0 => "BRA".to_string(),
+            1 => "BSR".to_string(),
+            c => format!("B{}", CONDITIONS[usize::from(c)]),
+        };
+        ins(&format!("{name}{suffix}"), format!("${dest:08X}"))
+    }
+
+    fn line_8(&mut self, op: u16) -> Option<(String, String)> {
+        let dreg = (op >> 9) & 7;
+        let reg = op & 7;
+        match op & 0x01F0 {
+            0x0100 => return Some(bcd_pair("SBCD", op)),
+            0x0140 | 0x0180 if self.is_020() => {
+                let name = if op & 0x01F0 == 0x0140 {
+                    "PACK"
+                } else {
+                    "UNPK"
+                };
+                let adj = self.word();
+                let regs = if op & 0x0008 == 0 {
+                    format!("D{reg},D{dreg}")
+                } else {
+                    format!("-(A{reg}),-(A{dreg})")
+                };
+                return Some(ins(name, format!("{regs},#${adj:04X}")));
+            }
+            _ => {}
+        }
+        match (op >> 6) & 7 {
+            3 => Some(ins(
+                "DIVU.W",
+                format!("{},D{dreg}", self.ea_field(op, Size::Word)?),
+            )),
+            7 => Some(ins(
+                "DIVS.W",
+                format!("{},D{dreg}", self.ea_field(op, Size::Word)?),
+            )),
+            _ => self.logic("OR", op),
+        }
+    }
+
+    /// OR/AND with `<ea>,Dn` and `Dn,<ea>` forms.
+    fn logic(&mut self, name: &str, op: u16) -> Option<(String, String)> {
+        let dreg = (op >> 9) & 7;
+        let size = Size::from_bits(op >> 6)?;
+        let ea = self.ea_field(op, size)?;
+        let name = format!("{name}{}", size.suffix());
+        Some(if op & 0x0100 == 0 {
+            ins(&name, format!("{ea},D{dreg}"))
+        } else {
+            ins(&name, format!("D{dreg},{ea}"))
+        })
+    }
+
+    fn add_sub(&mut self, op: u16) -> Option<(String, String)> {
+        let base = if op >> 12 == 0xD { "ADD" } else { "SUB" };
+        let reg = (op >> 9) & 7;
+        let opmode = (op >> 6) & 7;
+        if opmode == 3 || opmode == 7 {
+            let size = if opmode == 3 { Size::Word } else { Size::Long };
+            let ea = self.ea_field(op, size)?;
+            return Some(ins(
+                &format!("{base}A{}", size.suffix()),
+                format!("{ea},A{reg}"),
+            ));
+        }
+        if op & 0x0130 == 0x0100 {
+            let size = Size::from_bits(op >> 6)?;
+            let src = op & 7;
+            let operands = if op & 0x0008 == 0 {
+                format!("D{src},D{reg}")
+            } else {
+                format!("-(A{src}),-(A{reg})")
+            };
+            return Some(ins(&format!("{base}X{}", size.suffix()), operands));
+        }
+        self.logic(base, op)
+    }
+
+    fn line_b(&mut self, op: u16) -> Option<(String, String)> {
+        let reg = (op >> 9) & 7;
+        let opmode = (op >> 6) & 7;
+        match opmode {
+            3 | 7 => {
+                let size = if opmode == 3 { Size::Word } else { Size::Long };
+                let ea = self.ea_field(op, size)?;
+                Some(ins(
+                    &format!("CMPA{}", size.suffix()),
+                    format!("{ea},A{reg}"),
+                ))
+            }
+            0..=2 => {
+                let size = Size::from_bits(opmode)?;
+                let ea = self.ea_field(op, size)?;
+                Some(ins(
+                    &format!("CMP{}", size.suffix()),
+                    format!("{ea},D{reg}"),
+                ))
+            }
+            _ => {
+                let size = Size::from_bits(opmode)?;
+                if (op >> 3) & 7 == 1 {
+                    return Some(ins(
+                        &format!("CMPM{}", size.suffix()),
+                        format!("(A{})+,(A{reg})+", op & 7),
+                    ));
+                }
+                let ea = self.ea_field(op, size)?;
+                Some(ins(
+                    &format!("EOR{}", size.suffix()),
+                    format!("D{reg},{ea}"),
+                ))
+            }
+        }
+    }
+
+    fn line_c(&mut self, op: u16) -> Option<(String, String)> {
+        let rx = (op >> 9) & 7;
+        let ry = op & 7;
+        match op & 0x01F8 {
+            0x0100 | 0x0108 => return Some(bcd_pair("ABCD", op)),
+            0x0140 => return Some(ins("EXG", format!("D{rx},D{ry}"))),
+            0x0148 => return Some(ins("EXG", format!("A{rx},A{ry}"))),
+            0x0188 => return Some(ins("EXG", format!("D{rx},A{ry}"))),
+            _ => {}
+        }
+        match (op >> 6) & 7 {
+            3 => Some(ins(
+                "MULU.W",
+                format!("{},D{rx}", self.ea_field(op, Size::Word)?),
+            )),
+            7 => Some(ins(
+                "MULS.W",
+                format!("{},D{rx}", self.ea_field(op, Size::Word)?),
+            )),
+            _ => self.logic("AND", op),
+        }
+    }
+
+    fn line_e(&mut self, op: u16) -> Option<(String, String)> {
+        const SHIFTS: [&str; 4] = ["AS", "LS", "ROX", "RO"];
+        let dir = if op & 0x0100 == 0 { 'R' } else { 'L' };
+
+        let Some(size) = Size::from_bits(op >> 6) else {
+            if op & 0x0800 != 0 {
+                return self.bitfield(op);
+            }
+            let name = SHIFTS[usize::from((op >> 9) & 3)];
+            let ea = self.ea_field(op, Size::Word)?;
+            return Some(ins(&format!("{name}{dir}.W"), ea));
+        };
+
+        let name = SHIFTS[usize::from((op >> 3) & 3)];
+        let count = (op >> 9) & 7;
+        let count = if op & 0x0020 != 0 {
+            format!("D{count}")
+        } else if count == 0 {
+            "#8".to_string()
+        } else {
+            format!("#{count}")
+        };
+        Some(ins(
+            &format!("{name}{dir}{}", size.suffix()),
+            format!("{count},D{}", op & 7),
+        ))
+    }
+
+    fn bitfield(&mut self, op: u16) -> Option<(String, String)> {
+        if !self.caps.bitfield {
+            return None;
+        }
+        const NAMES: [&str; 8] = [
+            "BFTST", "BFEXTU", "BFCHG", "BFEXTS", "BFCLR", "BFFFO", "BFSET", "BFINS",
+        ];
+        let kind = usize::from((op >> 8) & 7);
+        let ext = self.word();
+        let offset = if ext & 0x0800 != 0 {
+            format!("D{}", (ext >> 6) & 7)
+        } else {
+            format!("{}", (ext >> 6) & 0x1F)
+        };
+        let width = if ext & 0x0020 != 0 {
+            format!("D{}", ext & 7)
+        } else {
+            match ext & 0x1F {
+                0 => "32".to_string(),
+                w => w.to_string(),
+            }
+        };
+        let ea = format!("{}{{{offset}:{width}}}", self.ea_field(op, Size::Long)?);
+        let dn = format!("D{}", (ext >> 12) & 7);
+        Some(match kind {
+            1 | 3 | 5 => ins(NAMES[kind], format!("{ea},{dn}")),
+            7 => ins(NAMES[kind], format!("{dn},{ea}")),
+            _ => ins(NAMES[kind], ea),
+        })
+    }
+
+    fn line_f(&mut self, op: u16) -> Option<(String, String)> {
+        let cpid = (op >> 9) & 7;
+        if cpid == 1 && self.caps.fpu {
+            return self.fpu(op);
+        }
+        if cpid == 0 && self.caps.mmu && !self.is_040() && op & 0x01C0 == 0 {
+            return self.pmmu(op);
+        }
+        if !self.is_040() {
+            return None;
+        }
+
+        let reg = op & 7;
+        match op & 0xFF00 {
+            0xF400 => {
+                let cache = ["NC", "DC", "IC", "BC"][usize::from((op >> 6) & 3)];
+                let name = if op & 0x0020 == 0 { "CINV" } else { "CPUSH" };
+                match (op >> 3) & 3 {
+                    1 => Some(ins(&format!("{name}L"), format!("{cache},(A{reg})"))),
+                    2 => Some(ins(&format!("{name}P"), format!("{cache},(A{reg})"))),
+                    3 => Some(ins(&format!("{name}A"), cache.to_string())),
+                    _ => None,
+                }
+            }
+            0xF500 if self.caps.mmu => match op & 0x00F8 {
+                0x00 => Some(ins("PFLUSHN", format!("(A{reg})"))),
+                0x08 => Some(ins("PFLUSH", format!("(A{reg})"))),
+                0x10 => Some(ins("PFLUSHAN", String::new())),
+                0x18 => Some(ins("PFLUSHA", String::new())),
+                0x48 => Some(ins("PTESTW", format!("(A{reg})"))),
+                0x68 => Some(ins("PTESTR", format!("(A{reg})"))),
+                _ => None,
+            },
+            0xF600 => match op & 0x00F8 {
+                0x20 => {
+                    let ext = self.word();
+                    Some(ins("MOVE16", format!("(A{reg})+,(A{})+", (ext >> 12) & 7)))
+                }
+                0x00..=0x18 => {
+                    let abs = format!("${:08X}", self.long());
+                    Some(ins(
+                        "MOVE16",
+                        match (op >> 3) & 3 {
+                            0 => format!("(A{reg})+,{abs}"),
+                            1 => format!("{abs},(A{reg})+"),
+                            2 => format!("(A{reg}),{abs}"),
+                            _ => format!("{abs},(A{reg})"),
+                        },
+                    ))
+                }
+                _ => None,
+            },
+            _ => None,
+        }
+    }
+
+    /// Coprocessor 1: the 68881/68882 or on-chip FPU.
+    fn fpu(&mut self, op: u16) -> Option<(String, String)> {
+        let mode = (op >> 3) & 7;
+        let reg = op & 7;
+        match (op >> 6) & 7 {
+            0 => self.fpu_general(op),
+            1 => {
+                let ext = self.word();
+                let cond = FPU_CONDITIONS.get(usize::from(ext & 0x3F))?;
+                if mode == 1 {
+                    let base = self.pos();
+                    let dest = base.wrapping_add(self.word() as i16 as u32);
+                    self.target = Some(dest);
+                    return Some(ins(&format!("FDB{cond}"), format!("D{reg},${dest:08X}")));
+                }
+                if mode == 7 && (2..=4).contains(&reg) {
+                    return Some(match reg {
+                        2 => ins(&format!("FTRAP{cond}.W"), format!("#${:04X}", self.word())),
+                        3 => ins(&format!("FTRAP{cond}.L"), format!("#${:08X}", self.long())),
+                        _ => ins(&format!("FTRAP{cond}"), String::new()),
+                    });
+                }
+                Some(ins(&format!("FS{cond}.B"), self.ea_field(op, Size::Byte)?))
+            }
+            2 | 3 => {
+                let base = self.pos();
+                let (disp, suffix) = if op & 0x0040 == 0 {
+                    (self.word() as i16 as u32, ".W")
+                } else {
+                    (self.long(), ".L")
+                };
+                if op == 0xF280 && disp == 0 {
+                    return Some(ins("FNOP", String::new()));
+                }
+                let cond = FPU_CONDITIONS.get(usize::from(op & 0x3F))?;
+                let dest = base.wrapping_add(disp);
+                self.target = Some(dest);
+                Some(ins(&format!("FB{cond}{suffix}"), format!("${dest:08X}")))
+            }
+            4 => Some(ins("FSAVE", self.ea_field(op, Size::Long)?)),
+            5 => Some(ins("FRESTORE", self.ea_field(op, Size::Long)?)),
+            _ => None,
+        }
+    }
+
+    fn fpu_general(&mut self, op: u16) -> Option<(String, String)> {
+        let ext = self.word();
+        let mode = (op >> 3) & 7;
+        let reg = op & 7;
+        let src_spec = (ext >> 10) & 7;
+        let dst = (ext >> 7) & 7;
+
+        match ext >> 13 {
+            0 | 2 => {
+                if ext >> 13 == 2 && src_spec == 7 {
+                    return Some(ins("FMOVECR", format!("#${:02X},FP{dst}", ext & 0x7F)));
+                }
+                let opmode = ext & 0x7F;
+                let name = fpu_operation(opmode)?;
+                let (suffix, src) = if ext >> 13 == 0 {
+                    if op & 0x3F != 0 {
+                        return None;
+                    }
+                    (".X", format!("FP{src_spec}"))
+                } else {
+                    let (suffix, bytes) = FPU_FORMATS[usize::from(src_spec)];
+                    let src = if mode == 7 && reg == 4 {
+                        self.fpu_immediate(bytes)
+                    } else {
+                        self.ea(mode, reg, Size::Long)?
+                    };
+                    (suffix, src)
+                };
+                let name = format!("{name}{suffix}");
+                Some(match opmode {
+                    0x30..=0x37 => ins(&name, format!("{src},FP{}:FP{dst}", ext & 7)),
+                    0x3A => ins(&name, src),
+                    _ => ins(&name, format!("{src},FP{dst}")),
+                })
+            }
+            3 => {
+                let (suffix, _) = FPU_FORMATS[usize::from(src_spec)];
+                let ea = self.ea(mode, reg, Size::Long)?;
+                let k = match src_spec {
+                    3 => format!("{{#{}}}", (ext & 0x7F) as i8),
+                    7 => format!("{{D{}}}", (ext >> 4) & 7),
+                    _ => String::new(),
+                };
+                Some(ins(&format!("FMOVE{suffix}"), format!("FP{dst},{ea}{k}")))
+            }
+            4 | 5 => {
+                let list: Vec<&str> = [(4, "FPCR"), (2, "FPSR"), (1, "FPIAR")]
+                    .into_iter()
+                    .filter(|&(bit, _)| src_spec & bit != 0)
+                    .map(|(_, name)| name)
+                    .collect();
+                let name = if list.len() == 1 {
+                    "FMOVE.L"
+                } else {
+                    "FMOVEM.L"
+                };
+                let regs = list.join("/");
+                let ea = if mode == 7 && reg == 4 {
+                    self.fpu_immediate(4 * list.len() as u32)
+                } else {
+                    self.ea(mode, reg, Size::Long)?
+                };
+                Some(if ext >> 13 == 4 {
+                    ins(name, format!("{ea},{regs}"))
+                } else {
+                    ins(name, format!("{regs},{ea}"))
+                })
+            }
+            _ => {
+                let dynamic = ext & 0x0800 != 0;
+                let predecrement = ext & 0x1000 == 0;
+                let list = if dynamic {
+                    format!("D{}", (ext >> 4) & 7)
+                } else {
+                    fpu_register_list(ext as u8, predecrement)
+                };
+                let ea = self.ea(mode, reg, Size::Long)?;
+                Some(if ext >> 13 == 6 {
+                    ins("FMOVEM.X", format!("{ea},{list}"))
+                } else {
+                    ins("FMOVEM.X", format!("{list},{ea}"))
+                })
+            }
+        }
+    }
+
+    /// Raw immediate of `bytes` bytes for FPU source operands.
+    fn fpu_immediate(&mut self, bytes: u32) -> String {
+        use std::fmt::Write;
+        let mut text = String::from("#$");
+        for _ in 0..bytes / 2 {
+            let word = self.word();
+            let _ = write!(text, "{word:04X}");
+        }
+        text
+    }
+
+    /// Coprocessor 0 general instructions: the 68030 on-chip MMU or a
+    /// 68851 beside a 68020.
+    fn pmmu(&mut self, op: u16) -> Option<(String, String)> {
+        let ext = self.word();
+        let rw = ext & 0x0200 != 0;
+        match ext >> 13 {
+            0 | 2 | 3 => {
+                let preg = match (ext >> 13, (ext >> 10) & 7) {
+                    (0, 2) => ("TT0", Size::Long),
+                    (0, 3) => ("TT1", Size::Long),
+                    (2, 0) => ("TC", Size::Long),
+                    (2, 2) => ("SRP", Size::Long),
+                    (2, 3) => ("CRP", Size::Long),
+                    (3, 0) => ("MMUSR", Size::Word),
+                    _ => return None,
+                };
+                let wide = matches!(preg.0, "SRP" | "CRP");
+                let suffix = if wide { ".D" } else { preg.1.suffix() };
+                let name = if ext & 0x0100 != 0 && ext >> 13 != 3 {
+                    format!("PMOVEFD{suffix}")
+                } else {
+                    format!("PMOVE{suffix}")
+                };
+                let ea = self.ea_field(op, preg.1)?;
+                Some(if rw {
+                    ins(&name, format!("{},{ea}", preg.0))
+                } else {
+                    ins(&name, format!("{ea},{}", preg.0))
+                })
+            }
+            1 => {
+                if ext & 0xFDE0 == 0x2000 {
+                    let fc = function_code(ext)?;
+                    let ea = self.ea_field(op, Size::Long)?;
+                    let name = if rw { "PLOADR" } else { "PLOADW" };
+                    return Some(ins(name, format!("{fc},{ea}")));
+                }
+                let mask = (ext >> 5) & 7;
+                match (ext >> 10) & 7 {
+                    1 => Some(ins("PFLUSHA", String::new())),
+                    4 => Some(ins("PFLUSH", format!("{},#{mask}", function_code(ext)?))),
+                    6 => {
+                        let fc = function_code(ext)?;
+                        let ea = self.ea_field(op, Size::Long)?;
+                        Some(ins("PFLUSH", format!("{fc},#{mask},{ea}")))
+                    }
+                    _ => None,
+                }
+            }
+            4 => {
+                let fc = function_code(ext)?;
+                let level = (ext >> 10) & 7;
+                let ea = self.ea_field(op, Size::Long)?;
+                let name = if rw { "PTESTR" } else { "PTESTW" };
+                let an = if ext & 0x0100 != 0 {
+                    format!(",A{}", (ext >> 5) & 7)
+                } else {
+                    String::new()
+                };
+                Some(ins(name, format!("{fc},{ea},#{level}{an}")))
+            }
+            _ => None,
+        }
+    }
+}
+
+fn ins(mnemonic: &str, operands: String) -> (String, String) {
+    (mnemonic.to_string(), operands)
+}
+
+/// `$1F` / `-$1F`.
+fn signed_hex(value: i32) -> String {
+    if value < 0 {
+        format!("-${:X}", value.unsigned_abs())
+    } else {
+        format!("${value:X}")
+    }
+}
+
+/// Memory-indirect operand with optional post-index and outer
+/// displacement.
+fn outer(indirect: &str, index: Option<String>, od: Option<i32>) -> String {
+    let mut parts = vec![indirect.to_string()];
+    parts.extend(index);
+    parts.extend(od.map(signed_hex));
+    format!("({})", parts.join(","))
+}
+
+/// D0-D7 / A0-A7 from a four-bit register field.
+fn general_register(field: u16) -> String {
+    let kind = if field & 8 == 0 { 'D' } else { 'A' };
+    format!("{kind}{}", field & 7)
+}
+
+/// ABCD/SBCD register and predecrement forms.
+fn bcd_pair(name: &str, op: u16) -> (String, String) {
+    let rx = (op >> 9) & 7;
+    let ry = op & 7;
+    if op & 0x0008 == 0 {
+        ins(name, format!("D{ry},D{rx}"))
+    } else {
+        ins(name, format!("-(A{ry}),-(A{rx})"))
+    }
+}
+
+/// MOVEM register list. Predecrement masks are bit-reversed (bit 0 is
+/// A7).
+fn register_list(mask: u16, predecrement: bool) -> String {
+    let mask = if predecrement {
+        mask.reverse_bits()
+    } else {
+        mask
+    };
+    let mut groups = Vec::new();
+    for (kind, bits) in [('D', mask as u8), ('A', (mask >> 8) as u8)] {
+        let mut i = 0;
+        while i < 8 {
+            if bits & (1 << i) == 0 {
+                i += 1;
+                continue;
+            }
+            let first = i;
+            while i < 8 && bits & (1 << i) != 0 {
+                i += 1;
+            }
+            let last = i - 1;
+            groups.push(if first == last {
+                format!("{kind}{first}")
+            } else {
+                format!("{kind}{first}-{kind}{last}")
+            });
+        }
+    }
+    groups.join("/")
+}
+
+/// FMOVEM data register list. Control/postincrement masks have FP0 in
+/// bit 7; predecrement masks have FP0 in bit 0.
+fn fpu_register_list(mask: u8, predecrement: bool) -> String {
+    let mask = if predecrement {
+        mask
+    } else {
+        mask.reverse_bits()
+    };
+    (0..8)
+        .filter(|i| mask & (1 << i) != 0)
+        .map(|i| format!("FP{i}"))
+        .collect::<Vec<_>>()
+        .join("/")
+}
+
+/// MOVEC control register names.
+fn control_register(code: u16) -> Option<&'static str> {
+    Some(match code {
+        0x000 => "SFC",
+        0x001 => "DFC",
+        0x002 => "CACR",
+        0x003 => "TC",
+        0x004 => "ITT0",
+        0x005 => "ITT1",
+        0x006 => "DTT0",
+        0x007 => "DTT1",
+        0x008 => "BUSCR",
+        0x800 => "USP",
+        0x801 => "VBR",
+        0x802 => "CAAR",
+        0x803 => "MSP",
+        0x804 => "ISP",
+        0x805 => "MMUSR",
+        0x806 => "URP",
+        0x807 => "SRP",
+        0x808 => "PCR",
+        _ => return None,
+    })
+}
+
+/// PMMU function-code operand from the low five bits of an extension
+/// word.
+fn function_code(ext: u16) -> Option<String> {
+    Some(match ext & 0x1F {
+        0 => "SFC".to_string(),
+        1 => "DFC".to_string(),
+        fc if fc & 0x18 == 0x08 => format!("D{}", fc & 7),
+        fc if fc & 0x10 != 0 => format!("#{}", fc & 0xF),
+        _ => return None,
+    })
+}
+
+/// FPU arithmetic operation names by opmode.
+fn fpu_operation(opmode: u16) -> Option<&'static str> {
+    Some(match opmode {
+        0x00 => "FMOVE",
+        0x01 => "FINT",
+        0x02 => "FSINH",
+        0x03 => "FINTRZ",
+        0x04 => "FSQRT",
+        0x06 => "FLOGNP1",
+        0x08 => "FETOXM1",
+        0x09 => "FTANH",
+        0x0A => "FATAN",
+        0x0C => "FASIN",
+        0x0D => "FATANH",
+        0x0E => "FSIN",
+        0x0F => "FTAN",
+        0x10 => "FETOX",
+        0x11 => "FTWOTOX",
+        0x12 => "FTENTOX",
+        0x14 => "FLOGN",
+        0x15 => "FLOG10",
+        0x16 => "FLOG2",
+        0x18 => "FABS",
+        0x19 => "FCOSH",
+        0x1A => "FNEG",
+        0x1C => "FACOS",
+        0x1D => "FCOS",
+        0x1E => "FGETEXP",
+        0x1F => "FGETMAN",
+        0x20 => "FDIV",
+        0x21 => "FMOD",
+        0x22 => "FADD",
+        0x23 => "FMUL",
+        0x24 => "FSGLDIV",
+        0x25 => "FREM",
+        0x26 => "FSCALE",
+        0x27 => "FSGLMUL",
+        0x28 => "FSUB",
+        0x30..=0x37 => "FSINCOS",
+        0x38 => "FCMP",
+        0x3A => "FTST",
+        0x40 => "FSMOVE",
+        0x41 => "FSSQRT",
+        0x44 => "FDMOVE",
+        0x45 => "FDSQRT",
+        0x58 => "FSABS",
+        0x5A => "FSNEG",
+        0x5C => "FDABS",
+        0x5E => "FDNEG",
+        0x60 => "FSDIV",
+        0x62 => "FSADD",
+        0x63 => "FSMUL",
+        0x64 => "FDDIV",
+        0x66 => "FDADD",
+        0x67 => "FDMUL",
+        0x68 => "FSSUB",
+        0x6C => "FDSUB",
+        _ => return None,
+    })
+}
+
+/// Disassemble the instruction at `address` as `model` would decode it.
+///
+/// `read` must not have side effects. Opcodes the model doesn't
+/// implement come back as a two-byte `DC.W`.
+pub fn disassemble(read: impl Fn(u32) -> u8, address: u32, model: CpuModel) -> Instruction {
+    let mut dec = Decoder {
+        read,
+        start: address,
+        bytes: Vec::with_capacity(10),
+        model,
+        caps: model.capabilities(),
+        target: None,
+    };
+
+    if let Some((mnemonic, operands)) = dec.decode() {
+        return Instruction {
+            address,
+            bytes: dec.bytes,
+            mnemonic,
+            operands,
+            target: dec.target,
+            undocumented: false,
+        };
+    }
+
+    dec.bytes.truncate(2);
+    let word = u16::from_be_bytes([dec.bytes[0], dec.bytes[1]]);
+    Instruction {
+        address,
+        bytes: dec.bytes,
+        mnemonic: "DC.W".to_string(),
+        operands: format!("${word:04X}"),
+        target: None,
+        undocumented: false,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn dis_model(words: &[u16], model: CpuModel) -> Instruction {
+        let bytes: Vec<u8> = words.iter().flat_map(|w| w.to_be_bytes()).collect();
+        let base = 0x00FC_0000u32;
+        disassemble(
+            |a| {
+                bytes
+                    .get(a.wrapping_sub(base) as usize)
+                    .copied()
+                    .unwrap_or(0)
+            },
+            base,
+            model,
+        )
+    }
+
+    fn dis(words: &[u16]) -> String {
+        dis_model(words, CpuModel::M68000).to_string()
+    }
+
+    #[test]
+    fn moves_and_addressing_modes() {
+        assert_eq!(dis(&[0x2001]), "MOVE.L D1,D0");
+        assert_eq!(dis(&[0x32D8]), "MOVE.W (A0)+,(A1)+");
+        assert_eq!(dis(&[0x206E, 0xFFFC]), "MOVEA.L -$4(A6),A0");
+        assert_eq!(
+            dis(&[0x13FC, 0x0041, 0x00DF, 0xF180]),
+            "MOVE.B #$41,$00DFF180"
+        );
+        assert_eq!(dis(&[0x3038, 0x0004]), "MOVE.W $0004.W,D0");
+        assert_eq!(dis(&[0x2030, 0x1804]), "MOVE.L $4(A0,D1.L),D0");
+        assert_eq!(dis(&[0x41FA, 0x0010]), "LEA $00FC0012(PC),A0");
+        assert_eq!(dis(&[0x70FF]), "MOVEQ #-1,D0");
+    }
+
+    #[test]
+    fn arithmetic_and_logic() {
+        assert_eq!(dis(&[0xD081]), "ADD.L D1,D0");
+        assert_eq!(dis(&[0xD3C0]), "ADDA.L D0,A1");
+        assert_eq!(dis(&[0x5380]), "SUBQ.L #1,D0");
+        assert_eq!(dis(&[0x0C40, 0x1234]), "CMPI.W #$1234,D0");
+        assert_eq!(dis(&[0x027C, 0xF8FF]), "ANDI #$F8FF,SR");
+        assert_eq!(dis(&[0xC1C1]), "MULS.W D1,D0");
+        assert_eq!(dis(&[0xC342]), "EXG D1,D2");
+        assert_eq!(dis(&[0xE548]), "LSL.W #2,D0");
+        assert_eq!(dis(&[0xE2A1]), "ASR.L D1,D1");
+        assert_eq!(dis(&[0x0839, 0x0006, 0x00BF, 0xE001]), "BTST #6,$00BFE001");
+    }
+
+    #[test]
+    fn control_flow_targets() {
+        let insn = dis_model(&[0x66FE], CpuModel::M68000);
+        assert_eq!(insn.to_string(), "BNE.S $00FC0000");
+        assert_eq!(insn.target, Some(0x00FC_0000));
+
+        let insn = dis_model(&[0x6100, 0x0010], CpuModel::M68000);
+        assert_eq!(insn.to_string(), "BSR.W $00FC0012");
+
+        let insn = dis_model(&[0x51C8, 0xFFFE], CpuModel::M68000);
+        assert_eq!(insn.to_string(), "DBRA D0,$00FC0000");
+        assert_eq!(insn.target, Some(0x00FC_0000));
+
+        let insn = dis_model(&[0x4EB9, 0x00FC, 0x1234], CpuModel::M68000);
+        assert_eq!(insn.to_string(), "JSR $00FC1234");
+        assert_eq!(insn.target, Some(0x00FC_1234));
+        assert_eq!(insn.len(), 6);
+
+        assert_eq!(dis_model(&[0x4ED0], CpuModel::M68000).target, None);
+        assert_eq!(dis_model(&[0x4E75], CpuModel::M68000).to_string(), "RTS");
+    }
+
+    #[test]
+    fn movem_register_lists() {
+        assert_eq!(dis(&[0x48E7, 0xFFFE]), "MOVEM.L D0-D7/A0-A6,-(A7)");
+        assert_eq!(dis(&[0x4CDF, 0x7FFF]), "MOVEM.L (A7)+,D0-D7/A0-A6");
+        assert_eq!(dis(&[0x48E7, 0xC080]), "MOVEM.L D0-D1/A0,-(A7)");
+    }
+
+    #[test]
+    fn model_gating() {
+        // MOVEC VBR,D0 is 68010+.
+        assert_eq!(dis(&[0x4E7A, 0x0801]), "DC.W $4E7A");
+        assert_eq!(
+            dis_model(&[0x4E7A, 0x0801], CpuModel::M68010).to_string(),
+            "MOVEC VBR,D0"
+        );
+
+        // Bit fields and 32-bit multiply are 68020+.
+        assert_eq!(dis(&[0xE9C0, 0x1088]), "DC.W $E9C0");
+        assert_eq!(
+            dis_model(&[0xE9C0, 0x1088], CpuModel::M68020).to_string(),
+            "BFEXTU D0{2:8},D1"
+        );
+        assert_eq!(
+            dis_model(&[0x4C00, 0x1C02], CpuModel::M68EC020).to_string(),
+            "MULS.L D0,D2:D1"
+        );
+
+        // Scaled index and full-format extension words.
+        assert_eq!(
+            dis_model(&[0x2030, 0x1C04], CpuModel::M68020).to_string(),
+            "MOVE.L $4(A0,D1.L*4),D0"
+        );
+        assert_eq!(
+            dis_model(&[0x2030, 0x1D21, 0x0010], CpuModel::M68030).to_string(),
+            "MOVE.L ([$10,A0,D1.L*4]),D0"
+        );
+    }
+
+    #[test]
+    fn fpu_needs_fpu_capability() {
+        // FADD.X FP1,FP2
+        let fadd = [0xF200, 0x0522];
+        assert_eq!(dis_model(&fadd, CpuModel::M68EC040).mnemonic, "DC.W");
+        assert_eq!(
+            dis_model(&fadd, CpuModel::M68040).to_string(),
+            "FADD.X FP1,FP2"
+        );
+        assert_eq!(
+            dis_model(&[0xF228, 0x5400, 0x0008], CpuModel::M68030).to_string(),
+            "FMOVE.D $8(A0),FP0"
+        );
+        assert_eq!(
+            dis_model(&[0xF227, 0xE0FF], CpuModel::M68040).to_string(),
+            "FMOVEM.X FP0/FP1/FP2/FP3/FP4/FP5/FP6/FP7,-(A7)"
+        );
+        let fbeq = dis_model(&[0xF281, 0x0010], CpuModel::M68060);
+        assert_eq!(fbeq.to_string(), "FBEQ.W $00FC0012");
+        assert_eq!(fbeq.target, Some(0x00FC_0012));
+        assert_eq!(
+            dis_model(&[0xF280, 0x0000], CpuModel::M68060).to_string(),
+            "FNOP"
+        );
+    }
+
+    #[test]
+    fn mmu_needs_mmu_capability() {
+        // PMOVE TC,(A0)
+        let pmove = [0xF010, 0x4200];
+        assert_eq!(dis_model(&pmove, CpuModel::M68EC030).mnemonic, "DC.W");
+        assert_eq!(
+            dis_model(&pmove, CpuModel::M68030).to_string(),
+            "PMOVE.L TC,(A0)"
+        );
+        assert_eq!(
+            dis_model(&[0xF518], CpuModel::M68LC040).to_string(),
+            "PFLUSHA"
+        );
+        assert_eq!(dis_model(&[0xF518], CpuModel::M68EC040).mnemonic, "DC.W");
+        assert_eq!(
+            dis_model(&[0xF4F8], CpuModel::M68EC040).to_string(),
+            "CPUSHA BC"
+        );
+        assert_eq!(dis_model(&[0xF4F8], CpuModel::M68030).mnemonic, "DC.W");
+    }
+
+    #[test]
+    fn unknown_opcodes_are_data() {
+        let insn = dis_model(&[0xA000], CpuModel::M68000);
+        assert_eq!(insn.to_string(), "DC.W $A000");
+        assert_eq!(insn.len(), 2);
+        assert_eq!(dis(&[0x4AFC]), "ILLEGAL");
+    }
+}
@@ -0,0 +1,224 @@
+//! NMOS 6502 disassembler.
+//!
+//! Covers all 256 opcodes, using the same names for the illegal ones as
+//! the `mos-6502` core (SLO, RLA, SRE, RRA, SAX, LAX, DCP, ISC, ANC,
+//! ALR, ARR, AXS, XAA, LAS, SHA, TAS, SHY, SHX, JAM).
+
+use emu_core::Instruction;
+
+/// Addressing modes.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Mode {
+    Implied,
+    Accumulator,
+    Immediate,
+    ZeroPage,
+    ZeroPageX,
+    ZeroPageY,
+    Absolute,
+    AbsoluteX,
+    AbsoluteY,
+    Indirect,
+    IndexedIndirect,
+    IndirectIndexed,
+    Relative,
+}
+
+impl Mode {
+    /// Operand bytes after the opcode.
+    const fn operand_len(self) -> u16 {
+        match self {
+            Self::Implied | Self::Accumulator => 0,
+            Self::Immediate
+            | Self::ZeroPage
+            | Self::ZeroPageX
+            | Self::ZeroPageY
+            | Self::IndexedIndirect
+            | Self::IndirectIndexed
+            | Self::Relative => 1,
+            Self::Absolute | Self::AbsoluteX | Self::AbsoluteY | Self::Indirect => 2,
+        }
+    }
+}
+
+use Mode::{
+    Absolute as ABS, AbsoluteX as ABX, AbsoluteY as ABY, Accumulator as ACC, Immediate as IMM,
+    Implied as IMP, IndexedIndirect as IZX, Indirect as IND, IndirectIndexed as IZY,
+    Relative as REL, ZeroPage as ZP, ZeroPageX as ZPX, ZeroPageY as ZPY,
+};
+
+/// Mnemonic and addressing mode for every opcode, row by high nibble.
+#[rustfmt::skip]
+const OPCODES: [(&str, Mode); 256] = [
+    // 0x
+    ("BRK", IMP), ("ORA", IZX), ("JAM", IMP), ("SLO", IZX), ("NOP", ZP),  ("ORA", ZP),  ("ASL", ZP),  ("SLO", ZP),
+    ("PHP", IMP), ("ORA", IMM), ("ASL", ACC), ("ANC", IMM), ("NOP", ABS), ("ORA", ABS), ("ASL", ABS), ("SLO", ABS),
+    // 1x
+    ("BPL", REL), ("ORA", IZY), ("JAM", IMP), ("SLO", IZY), ("NOP", ZPX), ("ORA", ZPX), ("ASL", ZPX), ("SLO", ZPX),
+    ("CLC", IMP), ("ORA", ABY), ("NOP", IMP), ("SLO", ABY), ("NOP", ABX), ("ORA", ABX), ("ASL", ABX), ("SLO", ABX),
+    // 2x
+    ("JSR", ABS), ("AND", IZX), ("JAM", IMP), ("RLA", IZX), ("BIT", ZP),  ("AND", ZP),  ("ROL", ZP),  ("RLA", ZP),
+    ("PLP", IMP), ("AND", IMM), ("ROL", ACC), ("ANC", IMM), ("BIT", ABS), ("AND", ABS), ("ROL", ABS), ("RLA", ABS),
+    // 3x
+    ("BMI", REL), ("AND", IZY), ("JAM", IMP), ("RLA", IZY), ("NOP", ZPX), ("AND", ZPX), ("ROL", ZPX), ("RLA", ZPX),
+    ("SEC", IMP), ("AND", ABY), ("NOP", IMP), ("RLA", ABY), ("NOP", ABX), ("AND", ABX), ("ROL", ABX), ("RLA", ABX),
+    // 4x
+    ("RTI", IMP), ("EOR", IZX), ("JAM", IMP), ("SRE", IZX), ("NOP", ZP),  ("EOR", ZP),  ("LSR", ZP),  ("SRE", ZP),
+    ("PHA", IMP), ("EOR", IMM), ("LSR", ACC), ("ALR", IMM), ("JMP", ABS), ("EOR", ABS), ("LSR", ABS), ("SRE", ABS),
+    // 5x
+    ("BVC", REL), ("EOR", IZY), ("JAM", IMP), ("SRE", IZY), ("NOP", ZPX), ("EOR", ZPX), ("LSR", ZPX), ("SRE", ZPX),
+    ("CLI", IMP), ("EOR", ABY), ("NOP", IMP), ("SRE", ABY), ("NOP", ABX), ("EOR", ABX), ("LSR", ABX), ("SRE", ABX),
+    // 6x
+    ("RTS", IMP), ("ADC", IZX), ("JAM", IMP), ("RRA", IZX), ("NOP", ZP),  ("ADC", ZP),  ("ROR", ZP),  ("RRA", ZP),
+    ("PLA", IMP), ("ADC", IMM), ("ROR", ACC), ("ARR", IMM), ("JMP", IND), ("ADC", ABS), ("ROR", ABS), ("RRA", ABS),
+    // 7x
+    ("BVS", REL), ("ADC", IZY), ("JAM", IMP), ("RRA", IZY), ("NOP", ZPX), ("ADC", ZPX), ("ROR", ZPX), ("RRA", ZPX),
+    ("SEI", IMP), ("ADC", ABY), ("NOP", IMP), ("RRA", ABY), ("NOP", ABX), ("ADC", ABX), ("ROR", ABX), ("RRA", ABX),
+    // 8x
+    ("NOP", IMM), ("STA", IZX), ("NOP", IMM), ("SAX", IZX), ("STY", ZP),  ("STA", ZP),  ("STX", ZP),  ("SAX", ZP),
+    ("DEY", IMP), ("NOP", IMM), ("TXA", IMP), ("XAA", IMM), ("STY", ABS), ("STA", ABS), ("STX", ABS), ("SAX", ABS),
+    // 9x
+    ("BCC", REL), ("STA", IZY), ("JAM", IMP), ("SHA", IZY), ("STY", ZPX), ("STA", ZPX), ("STX", ZPY), ("SAX", ZPY),
+    ("TYA", IMP), ("STA", ABY), ("TXS", IMP), ("TAS", ABY), ("SHY", ABX), ("STA", ABX), ("SHX", ABY), ("SHA", ABY),
+    // Ax
+    ("LDY", IMM), ("LDA", IZX), ("LDX", IMM), ("LAX", IZX), ("LDY", ZP),  ("LDA", ZP),  ("LDX", ZP),  ("LAX", ZP),
+    ("TAY", IMP), ("LDA", IMM), ("TAX", IMP), ("LAX", IMM), ("LDY", ABS), ("LDA", ABS), ("LDX", ABS), ("LAX", ABS),
+    // Bx
+    ("BCS", REL), ("LDA", IZY), ("JAM", IMP), ("LAX", IZY), ("LDY", ZPX), ("LDA", ZPX), ("LDX", ZPY), ("LAX", ZPY),
+    ("CLV", IMP), ("LDA", ABY), ("TSX", IMP), ("LAS", ABY), ("LDY", ABX), ("LDA", ABX), ("LDX", ABY), ("LAX", ABY),
+    // Cx
+    ("CPY", IMM), ("CMP", IZX), ("NOP", IMM), ("DCP", IZX), ("CPY", ZP),  ("CMP", ZP),  ("DEC", ZP),  ("DCP", ZP),
+    ("INY", IMP), ("CMP", IMM), ("DEX", IMP), ("AXS", IMM), ("CPY", ABS), ("CMP", ABS), ("DEC", ABS), ("DCP", ABS),
+    // Dx
+    ("BNE", REL), ("CMP", IZY), ("JAM", IMP), ("DCP", IZY), ("NOP", ZPX), ("CMP", ZPX), ("DEC", ZPX), ("DCP", ZPX),
+    ("CLD", IMP), ("CMP", ABY), ("NOP", IMP), ("DCP", ABY), ("NOP", ABX), ("CMP", ABX), ("DEC", ABX), ("DCP", ABX),
+    // Ex
+    ("CPX", IMM), ("SBC", IZX), ("NOP", IMM), ("ISC", IZX), ("CPX", ZP),  ("SBC", ZP),  ("INC", ZP),  ("ISC", ZP),
+    ("INX", IMP), ("SBC", IMM), ("NOP", IMP), ("SBC", IMM), ("CPX", ABS), ("SBC", ABS), ("INC", ABS), ("ISC", ABS),
+    // Fx
+    ("BEQ", REL), ("SBC", IZY), ("JAM", IMP), ("ISC", IZY), ("NOP", ZPX), ("SBC", ZPX), ("INC", ZPX), ("ISC", ZPX),
+    ("SED", IMP), ("SBC", ABY), ("NOP", IMP), ("ISC", ABY), ("NOP", ABX), ("SBC", ABX), ("INC", ABX), ("ISC", ABX),
+];
+
+/// True for the 105 opcodes outside the documented instruction set.
+fn is_undocumented(opcode: u8, mnemonic: &str) -> bool {
+    // Every opcode with both low bits set is illegal, including $EB SBC.
+    opcode & 0x03 == 0x03
+        || matches!(mnemonic, "JAM" | "SHY" | "SHX")
+        || (mnemonic == "NOP" && opcode != 0xEA)
+}
+
+/// Disassemble the instruction at `address`.
+///
+/// `read` must not have side effects. Operand bytes wrap at $FFFF like
+/// the CPU's program counter.
+pub fn disassemble(read: impl Fn(u16) -> u8, address: u32) -> Instruction {
+    let pc = address as u16;
+    let opcode = read(pc);
+    let (mnemonic, mode) = OPCODES[usize::from(opcode)];
+
+    let mut bytes = vec![opcode];
+    for i in 1..=mode.operand_len() {
+        bytes.push(read(pc.wrapping_add(i)));
+    }
+    let byte = bytes.get(1).copied().unwrap_or(0);
+    let word = u16::from_le_bytes([byte, bytes.get(2).copied().unwrap_or(0)]);
+
+    let mut target = None;
+    let operands = match mode {
+        Mode::Implied => String::new(),
+        Mode::Accumulator => "A".to_string(),
+        Mode::Immediate => format!("#${byte:02X}"),
+        Mode::ZeroPage => format!("${byte:02X}"),
+        Mode::ZeroPageX => format!("${byte:02X},X"),
+        Mode::ZeroPageY => format!("${byte:02X},Y"),
+        Mode::Absolute => {
+            if matches!(mnemonic, "JMP" | "JSR") {
+                target = Some(u32::from(word));
+            }
+            format!("${word:04X}")
+        }
+        Mode::AbsoluteX => format!("${word:04X},X"),
+        Mode::AbsoluteY => format!("${word:04X},Y"),
+        Mode::Indirect => format!("(${word:04X})"),
+        Mode::IndexedIndirect => format!("(${byte:02X},X)"),
+        Mode::IndirectIndexed => format!("(${byte:02X}),Y"),
+        Mode::Relative => {
+            let dest = pc.wrapping_add(2).wrapping_add(byte as i8 as u16);
+            target = Some(u32::from(dest));
+            format!("${dest:04X}")
+        }
+    };
+
+    Instruction {
+        address: u32::from(pc),
+        bytes,
+        mnemonic: mnemonic.to_string(),
+        operands,
+        target,
+        undocumented: is_undocumented(opcode, mnemonic),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn dis(code: &[u8], address: u16) -> Instruction {
+        let mut mem = vec![0u8; 0x10000];
+        for (i, &b) in code.iter().enumerate() {
+            mem[usize::from(address) + i] = b;
+        }
+        disassemble(|a| mem[usize::from(a)], u32::from(address))
+    }
+
+    #[test]
+    fn documented_modes() {
+        assert_eq!(dis(&[0xA9, 0x42], 0).to_string(), "LDA #$42");
+        assert_eq!(dis(&[0xBD, 0x00, 0xC0], 0).to_string(), "LDA $C000,X");
+        assert_eq!(dis(&[0xB1, 0xFB], 0).to_string(), "LDA ($FB),Y");
+        assert_eq!(dis(&[0x0A], 0).to_string(), "ASL A");
+        assert_eq!(dis(&[0x6C, 0xFC, 0xFF], 0).to_string(), "JMP ($FFFC)");
+        assert!(!dis(&[0xEA], 0).undocumented);
+    }
+
+    #[test]
+    fn branches_and_jumps_resolve_targets() {
+        let insn = dis(&[0xD0, 0xFE], 0x1000);
+        assert_eq!(insn.target, Some(0x1000));
+        assert_eq!(insn.operands, "$1000");
+
+        let insn = dis(&[0x20, 0xD2, 0xFF], 0x0801);
+        assert_eq!(insn.target, Some(0xFFD2));
+        assert_eq!(insn.len(), 3);
+        assert_eq!(dis(&[0x6C, 0x00, 0x03], 0).target, None);
+    }
+
+    #[test]
+    fn illegal_opcodes_are_named_and_flagged() {
+        let insn = dis(&[0xA7, 0x10], 0);
+        assert_eq!(insn.to_string(), "LAX $10");
+        assert!(insn.undocumented);
+        assert_eq!(dis(&[0xCB, 0x01], 0).mnemonic, "AXS");
+        assert!(dis(&[0xEB, 0x01], 0).undocumented);
+        assert_eq!(dis(&[0x1C, 0x00, 0x10], 0).len(), 3);
+        let jam = dis(&[0x02], 0);
+        assert_eq!(jam.mnemonic, "JAM");
+        assert!(jam.undocumented);
+
+        let illegal = (0..=255u8)
+            .filter(|&op| dis(&[op, 0, 0], 0).undocumented)
+            .count();
+        assert_eq!(illegal, 105);
+    }
+
+    #[test]
+    fn operands_wrap_at_top_of_memory() {
+        let mut mem = vec![0u8; 0x10000];
+        mem[0xFFFF] = 0xAD;
+        mem[0x0000] = 0x34;
+        mem[0x0001] = 0x12;
+        let insn = disassemble(|a| mem[usize::from(a)], 0xFFFF);
+        assert_eq!(insn.operands, "$1234");
+    }
+}
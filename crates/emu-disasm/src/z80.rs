@@ -0,0 +1,540 @@
+//! Z80 disassembler.
+//!
+//! Decodes the unprefixed, CB, ED, DD/FD and DDCB/FDCB opcode tables with
+//! the usual x/y/z/p/q field split. Undocumented operations the core
+//! executes are decoded and flagged: SLL, IXH/IXL/IYH/IYL access, the
+//! DDCB register-copy forms, `IN (C)`, `OUT (C),0` and the ED mirrors of
+//! NEG, RETN and IM. A DD/FD prefix in front of an opcode that doesn't
+//! use HL is reported as a one-byte NOP, which is what the CPU does with
+//! it.
+
+use emu_core::Instruction;
+
+const R: [&str; 8] = ["B", "C", "D", "E", "H", "L", "(HL)", "A"];
+const RP: [&str; 4] = ["BC", "DE", "HL", "SP"];
+const RP2: [&str; 4] = ["BC", "DE", "HL", "AF"];
+const CC: [&str; 8] = ["NZ", "Z", "NC", "C", "PO", "PE", "P", "M"];
+const ALU: [(&str, bool); 8] = [
+    ("ADD", true),
+    ("ADC", true),
+    ("SUB", false),
+    ("SBC", true),
+    ("AND", false),
+    ("XOR", false),
+    ("OR", false),
+    ("CP", false),
+];
+const ROT: [&str; 8] = ["RLC", "RRC", "RL", "RR", "SLA", "SRA", "SLL", "SRL"];
+const BLOCK: [[&str; 4]; 4] = [
+    ["LDI", "CPI", "INI", "OUTI"],
+    ["LDD", "CPD", "IND", "OUTD"],
+    ["LDIR", "CPIR", "INIR", "OTIR"],
+    ["LDDR", "CPDR", "INDR", "OTDR"],
+];
+
+/// Index register selected by a DD or FD prefix.
+#[derive(Clone, Copy, PartialEq, Eq)]
+enum Index {
+    Hl,
+    Ix,
+    Iy,
+}
+
+impl Index {
+    const fn name(self) -> &'static str {
+        match self {
+            Self::Hl => "HL",
+            Self::Ix => "IX",
+            Self::Iy => "IY",
+        }
+    }
+}
+
+/// Decoding state for one instruction.
+struct Decoder<F> {
+    read: F,
+    start: u16,
+    bytes: Vec<u8>,
+    index: Index,
+    /// Displacement byte of an `(IX+d)` operand, fetched on first use.
+    displacement: Option<i8>,
+    /// Set when an HL operand was rewritten to the index register.
+    used_index: bool,
+    undocumented: bool,
+    target: Option<u32>,
+}
+
+impl<F: Fn(u16) -> u8> Decoder<F> {
+    fn fetch(&mut self) -> u8 {
+        let byte = (self.read)(self.start.wrapping_add(self.bytes.len() as u16));
+        self.bytes.push(byte);
+        byte
+    }
+
+    fn fetch_word(&mut self) -> u16 {
+        let lo = self.fetch();
+        let hi = self.fetch();
+        u16::from_le_bytes([lo, hi])
+    }
+
+    fn imm8(&mut self) -> String {
+        format!("${:02X}", self.fetch())
+    }
+
+    fn imm16(&mut self) -> String {
+        format!("${:04X}", self.fetch_word())
+    }
+
+    /// Relative jump operand; the displacement is the last byte fetched.
+    fn relative(&mut self) -> String {
+        let d = self.fetch() as i8;
+        let dest = self
+            .start
+            .wrapping_add(self.bytes.len() as u16)
+            .wrapping_add(d as u16);
+        self.target = Some(u32::from(dest));
+        format!("${dest:04X}")
+    }
+
+    fn absolute_target(&mut self) -> String {
+        let nn = self.fetch_word();
+        self.target = Some(u32::from(nn));
+        format!("${nn:04X}")
+    }
+
+    /// `(IX+d)` / `(IY+d)` text for a displacement.
+    fn indexed(&self, d: i8) -> String {
+        let reg = self.index.name();
+        if d < 0 {
+            format!("({reg}-${:02X})", d.unsigned_abs())
+        } else {
+            format!("({reg}+${d:02X})")
+        }
+    }
+
+    /// Register operand `r[i]`. When `other_is_memory` is set, H and L
+    /// keep their names even under a prefix (`LD H,(IX+d)`).
+    fn r(&mut self, i: u8, other_is_memory: bool) -> String {
+        match (self.index, i) {
+            (Index::Hl, _) => R[usize::from(i)].to_string(),
+            (_, 6) => {
+                self.used_index = true;
+                let d = if let Some(d) = self.displacement {
+                    d
+                } else {
+                    let d = self.fetch() as i8;
+                    self.displacement = Some(d);
+                    d
+                };
+                self.indexed(d)
+            }
+            (_, 4 | 5) if !other_is_memory => {
+                self.used_index = true;
+                self.undocumented = true;
+                let half = if i == 4 { "H" } else { "L" };
+                format!("{}{half}", self.index.name())
+            }
+            _ => R[usize::from(i)].to_string(),
+        }
+    }
+
+    /// Register pair `rp[p]` or `rp2[p]`, with HL replaced by the index
+    /// register.
+    fn rp(&mut self, p: u8, table: &[&str; 4]) -> String {
+        if p == 2 && self.index != Index::Hl {
+            self.used_index = true;
+            self.index.name().to_string()
+        } else {
+            table[usize::from(p)].to_string()
+        }
+    }
+
+    fn hl(&mut self) -> String {
+        self.rp(2, &RP)
+    }
+
+    fn finish(self, mnemonic: &str, operands: String) -> Instruction {
+        Instruction {
+            address: u32::from(self.start),
+            bytes: self.bytes,
+            mnemonic: mnemonic.to_string(),
+            operands,
+            target: self.target,
+            undocumented: self.undocumented,
+        }
+    }
+
+    /// Decode an unprefixed (or DD/FD-prefixed) opcode.
+    fn main(&mut self, op: u8) -> (String, String) {
+        let (x, y, z) = (op >> 6, (op >> 3) & 7, op & 7);
+        let (p, q) = (y >> 1, y & 1);
+        let s = |m: &str, o: String| (m.to_string(), o);
+
+        match x {
+            0 => match z {
+                0 => match y {
+                    0 => s("NOP", String::new()),
+                    1 => s("EX", "AF,AF'".into()),
+                    2 => s("DJNZ", self.relative()),
+                    3 => s("JR", self.relative()),
+                    _ => {
+                        let rel = self.relative();
+                        s("JR", format!("{},{rel}", CC[usize::from(y - 4)]))
+                    }
+                },
+                1 => {
+                    if q == 0 {
+                        let rp = self.rp(p, &RP);
+                        s("LD", format!("{rp},{}", self.imm16()))
+                    } else {
+                        let hl = self.hl();
+                        s("ADD", format!("{hl},{}", self.rp(p, &RP)))
+                    }
+                }
+                2 => match (q, p) {
+                    (0, 0) => s("LD", "(BC),A".into()),
+                    (0, 1) => s("LD", "(DE),A".into()),
+                    (0, 2) => {
+                        let nn = self.imm16();
+                        s("LD", format!("({nn}),{}", self.hl()))
+                    }
+                    (0, _) => s("LD", format!("({}),A", self.imm16())),
+                    (_, 0) => s("LD", "A,(BC)".into()),
+                    (_, 1) => s("LD", "A,(DE)".into()),
+                    (_, 2) => {
+                        let hl = self.hl();
+                        s("LD", format!("{hl},({})", self.imm16()))
+                    }
+                    _ => s("LD", format!("A,({})", self.imm16())),
+                },
+                3 => s(if q == 0 { "INC" } else { "DEC" }, self.rp(p, &RP)),
+                4 => s("INC", self.r(y, false)),
+                5 => s("DEC", self.r(y, false)),
+                6 => {
+                    let r = self.r(y, false);
+                    s("LD", format!("{r},{}", self.imm8()))
+                }
+                _ => s(
+                    ["RLCA", "RRCA", "RLA", "RRA", "DAA", "CPL", "SCF", "CCF"][usize::from(y)],
+                    String::new(),
+                ),
+            },
+            1 => {
+                if y == 6 && z == 6 {
+                    s("HALT", String::new())
+                } else {
+                    let dst = self.r(y, z == 6);
+                    let src = self.r(z, y == 6);
+                    s("LD", format!("{dst},{src}"))
+                }
+            }
+            2 => self.alu(y, z),
+            _ => match z {
+                0 => s("RET", CC[usize::from(y)].into()),
+                1 => match (q, p) {
+                    (0, _) => s("POP", self.rp(p, &RP2)),
+                    (_, 0) => s("RET", String::new()),
+                    (_, 1) => s("EXX", String::new()),
+                    (_, 2) => s("JP", format!("({})", self.hl())),
+                    _ => s("LD", format!("SP,{}", self.hl())),
+                },
+                2 => {
+                    let nn = self.absolute_target();
+                    s("JP", format!("{},{nn}", CC[usize::from(y)]))
+                }
+                3 => match y {
+                    0 => s("JP", self.absolute_target()),
+                    2 => s("OUT", format!("({}),A", self.imm8())),
+                    3 => s("IN", format!("A,({})", self.imm8())),
+                    4 => s("EX", format!("(SP),{}", self.hl())),
+                    5 => s("EX", "DE,HL".into()),
+                    6 => s("DI", String::new()),
+                    7 => s("EI", String::new()),
+                    // CB is handled by the caller.
+                    _ => unreachable!("CB prefix"),
+                },
+                4 => {
+                    let nn = self.absolute_target();
+                    s("CALL", format!("{},{nn}", CC[usize::from(y)]))
+                }
+                5 => match (q, p) {
+                    (0, _) => s("PUSH", self.rp(p, &RP2)),
+                    (_, 0) => s("CALL", self.absolute_target()),
+                    // DD, ED and FD are handled by the caller.
+                    _ => unreachable!("prefix"),
+                },
+                6 => {
+                    let (m, a) = ALU[usize::from(y)];
+                    let n = self.imm8();
+                    s(m, if a { format!("A,{n}") } else { n })
+                }
+                _ => {
+                    let dest = u16::from(y) * 8;
+                    self.target = Some(u32::from(dest));
+                    s("RST", format!("${dest:02X}"))
+                }
+            },
+        }
+    }
+
+    fn alu(&mut self, y: u8, z: u8) -> (String, String) {
+        let (m, a) = ALU[usize::from(y)];
+        let r = self.r(z, false);
+        (m.to_string(), if a { format!("A,{r}") } else { r })
+    }
+
+    /// Decode the opcode after a CB prefix (unindexed).
+    fn cb(&mut self) -> (String, String) {
+        let op = self.fetch();
+        let (x, y, z) = (op >> 6, (op >> 3) & 7, op & 7);
+        let r = R[usize::from(z)];
+        match x {
+            0 => {
+                self.undocumented |= y == 6;
+                (ROT[usize::from(y)].to_string(), r.to_string())
+            }
+            1 => ("BIT".into(), format!("{y},{r}")),
+            2 => ("RES".into(), format!("{y},{r}")),
+            _ => ("SET".into(), format!("{y},{r}")),
+        }
+    }
+
+    /// Decode DDCB/FDCB: displacement first, then the opcode.
+    fn indexed_cb(&mut self) -> (String, String) {
+        let d = self.fetch() as i8;
+        let op = self.fetch();
+        let (x, y, z) = (op >> 6, (op >> 3) & 7, op & 7);
+        let mem = self.indexed(d);
+        // Every form but z = 6 also copies the result into r[z]; BIT just
+        // ignores z.
+        let copy = if z == 6 || x == 1 {
+            String::new()
+        } else {
+            format!(",{}", R[usize::from(z)])
+        };
+        self.undocumented |= z != 6 || (x == 0 && y == 6);
+        match x {
+            0 => (ROT[usize::from(y)].to_string(), format!("{mem}{copy}")),
+            1 => ("BIT".into(), format!("{y},{mem}")),
+            2 => ("RES".into(), format!("{y},{mem}{copy}")),
+            _ => ("SET".into(), format!("{y},{mem}{copy}")),
+        }
+    }
+
+    /// Decode the opcode after an ED prefix.
+    fn ed(&mut self) -> (String, String) {
+        let op = self.fetch();
+        let (x, y, z) = (op >> 6, (op >> 3) & 7, op & 7);
+        let (p, q) = (y >> 1, y & 1);
+        let s = |m: &str, o: String| (m.to_string(), o);
+
+        match (x, z) {
+            (1, 0) => {
+                if y == 6 {
+                    self.undocumented = true;
+                    s("IN", "(C)".into())
+                } else {
+                    s("IN", format!("{},(C)", R[usize::from(y)]))
+                }
+            }
+            (1, 1) => {
+                if y == 6 {
+                    self.undocumented = true;
+                    s("OUT", "(C),0".into())
+                } else {
+                    s("OUT", format!("(C),{}", R[usize::from(y)]))
+                }
+            }
+            (1, 2) => s(
+                if q == 0 { "SBC" } else { "ADC" },
+                format!("HL,{}", RP[usize::from(p)]),
+            ),
+            (1, 3) => {
+                // ED 63 / ED 6B duplicate the unprefixed HL forms.
+                self.undocumented |= p == 2;
+                let nn = self.imm16();
+                let rp = RP[usize::from(p)];
+                if q == 0 {
+                    s("LD", format!("({nn}),{rp}"))
+                } else {
+                    s("LD", format!("{rp},({nn})"))
+                }
+            }
+            (1, 4) => {
+                self.undocumented |= y != 0;
+                s("NEG", String::new())
+            }
+            (1, 5) => {
+                self.undocumented |= y > 1;
+                s(if y == 1 { "RETI" } else { "RETN" }, String::new())
+            }
+            (1, 6) => {
+                self.undocumented |= !matches!(y, 0 | 2 | 3);
+                s("IM", ["0", "0", "1", "2"][usize::from(y & 3)].into())
+            }
+            (1, 7) => match y {
+                0 => s("LD", "I,A".into()),
+                1 => s("LD", "R,A".into()),
+                2 => s("LD", "A,I".into()),
+                3 => s("LD", "A,R".into()),
+                4 => s("RRD", String::new()),
+                5 => s("RLD", String::new()),
+                _ => {
+                    self.undocumented = true;
+                    s("NOP", String::new())
+                }
+            },
+            (2, 0..=3) if y >= 4 => s(BLOCK[usize::from(y - 4)][usize::from(z)], String::new()),
+            _ => {
+                // Every other ED opcode executes as a two-byte NOP.
+                self.undocumented = true;
+                s("NOP", String::new())
+            }
+        }
+    }
+}
+
+/// Disassemble the instruction at `address`.
+///
+/// `read` must not have side effects. Addresses wrap at $FFFF.
+pub fn disassemble(read: impl Fn(u16) -> u8, address: u32) -> Instruction {
+    let mut dec = Decoder {
+        read,
+        start: address as u16,
+        bytes: Vec::with_capacity(4),
+        index: Index::Hl,
+        displacement: None,
+        used_index: false,
+        undocumented: false,
+        target: None,
+    };
+
+    let op = dec.fetch();
+    let (mnemonic, operands) = match op {
+        0xCB => dec.cb(),
+        0xED => dec.ed(),
+        0xDD | 0xFD => {
+            dec.index = if op == 0xDD { Index::Ix } else { Index::Iy };
+            let next = dec.fetch();
+            match next {
+                0xCB => dec.indexed_cb(),
+                0xDD | 0xED | 0xFD => (String::new(), String::new()),
+                // EX DE,HL and EXX never take the index register.
+                0xEB | 0xD9 => (String::new(), String::new()),
+                _ => dec.main(next),
+            }
+        }
+        _ => dec.main(op),
+    };
+
+    // A prefix the following opcode ignores behaves as a lone NOP.
+    if dec.index != Index::Hl && !dec.used_index && dec.bytes.get(1) != Some(&0xCB) {
+        dec.bytes.truncate(1);
+        dec.target = None;
+        dec.undocumented = true;
+        return dec.finish("NOP", String::new());
+    }
+
+    dec.finish(&mnemonic, operands)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn dis(code: &[u8]) -> Instruction {
+        dis_at(code, 0x8000)
+    }
+
+    fn dis_at(code: &[u8], address: u16) -> Instruction {
+        let mut mem = vec![0u8; 0x10000];
+        for (i, &b) in code.iter().enumerate() {
+            mem[usize::from(address.wrapping_add(i as u16))] = b;
+        }
+        disassemble(|a| mem[usize::from(a)], u32::from(address))
+    }
+
+    #[test]
+    fn unprefixed() {
+        assert_eq!(dis(&[0x3E, 0x7F]).to_string(), "LD A,$7F");
+        assert_eq!(dis(&[0x21, 0x00, 0x40]).to_string(), "LD HL,$4000");
+        assert_eq!(dis(&[0x22, 0x34, 0x12]).to_string(), "LD ($1234),HL");
+        assert_eq!(dis(&[0x7E]).to_string(), "LD A,(HL)");
+        assert_eq!(dis(&[0x76]).to_string(), "HALT");
+        assert_eq!(dis(&[0x96]).to_string(), "SUB (HL)");
+        assert_eq!(dis(&[0x8F]).to_string(), "ADC A,A");
+        assert_eq!(dis(&[0xDB, 0xFE]).to_string(), "IN A,($FE)");
+        assert_eq!(dis(&[0x08]).to_string(), "EX AF,AF'");
+    }
+
+    #[test]
+    fn control_flow_targets() {
+        let insn = dis_at(&[0x10, 0xFE], 0x8000);
+        assert_eq!(insn.to_string(), "DJNZ $8000");
+        assert_eq!(insn.target, Some(0x8000));
+        assert_eq!(dis(&[0x20, 0x05]).target, Some(0x8007));
+        assert_eq!(dis(&[0xCD, 0x09, 0x00]).target, Some(0x0009));
+        assert_eq!(dis(&[0xC2, 0x00, 0x60]).to_string(), "JP NZ,$6000");
+        assert_eq!(dis(&[0xFF]).target, Some(0x38));
+        assert_eq!(dis(&[0xE9]).target, None);
+    }
+
+    #[test]
+    fn cb_and_ed() {
+        assert_eq!(dis(&[0xCB, 0x7E]).to_string(), "BIT 7,(HL)");
+        assert_eq!(dis(&[0xCB, 0x11]).to_string(), "RL C");
+        let sll = dis(&[0xCB, 0x37]);
+        assert_eq!(sll.to_string(), "SLL A");
+        assert!(sll.undocumented);
+
+        assert_eq!(dis(&[0xED, 0xB0]).to_string(), "LDIR");
+        assert_eq!(dis(&[0xED, 0x78]).to_string(), "IN A,(C)");
+        assert_eq!(dis(&[0xED, 0x43, 0x00, 0x50]).to_string(), "LD ($5000),BC");
+        assert_eq!(dis(&[0xED, 0x5E]).to_string(), "IM 2");
+        assert!(!dis(&[0xED, 0x44]).undocumented);
+        assert!(dis(&[0xED, 0x4C]).undocumented);
+        assert!(dis(&[0xED, 0x70]).undocumented);
+        let nop = dis(&[0xED, 0x00]);
+        assert_eq!((nop.mnemonic.as_str(), nop.len()), ("NOP", 2));
+    }
+
+    #[test]
+    fn index_prefixes() {
+        assert_eq!(dis(&[0xDD, 0x21, 0x00, 0x5C]).to_string(), "LD IX,$5C00");
+        assert_eq!(dis(&[0xDD, 0x7E, 0x05]).to_string(), "LD A,(IX+$05)");
+        assert_eq!(dis(&[0xFD, 0x75, 0xFE]).to_string(), "LD (IY-$02),L");
+        assert_eq!(
+            dis(&[0xFD, 0x36, 0x01, 0x99]).to_string(),
+            "LD (IY+$01),$99"
+        );
+        assert_eq!(dis(&[0xDD, 0xE9]).to_string(), "JP (IX)");
+        assert_eq!(dis(&[0xDD, 0x29]).to_string(), "ADD IX,IX");
+
+        let half = dis(&[0xDD, 0x7C]);
+        assert_eq!(half.to_string(), "LD A,IXH");
+        assert!(half.undocumented);
+        assert!(!dis(&[0xDD, 0x66, 0x00]).undocumented);
+    }
+
+    #[test]
+    fn ignored_prefix_is_a_lone_nop() {
+        let insn = dis(&[0xDD, 0x3E, 0x01]);
+        assert_eq!((insn.mnemonic.as_str(), insn.len()), ("NOP", 1));
+        assert!(insn.undocumented);
+        assert_eq!(dis(&[0xFD, 0xEB]).len(), 1);
+        assert_eq!(dis(&[0xDD, 0xDD, 0x21, 0, 0]).len(), 1);
+    }
+
+    #[test]
+    fn indexed_cb() {
+        let insn = dis(&[0xDD, 0xCB, 0x03, 0x46]);
+        assert_eq!(insn.to_string(), "BIT 0,(IX+$03)");
+        assert_eq!(insn.len(), 4);
+        assert!(!insn.undocumented);
+        assert_eq!(dis(&[0xFD, 0xCB, 0xFF, 0xCE]).to_string(), "SET 1,(IY-$01)");
+
+        let copy = dis(&[0xDD, 0xCB, 0x00, 0x00]);
+        assert_eq!(copy.to_string(), "RLC (IX+$00),B");
+        assert!(copy.undocumented);
+    }
+}
@@ -20,7 +20,13 @@
 
 #![allow(clippy::cast_possible_truncation)]
 
-use emu_core::{AudioFrame, Bus, Cpu, Machine, ReadResult};
+#[cfg(feature = "native")]
+pub mod mcp;
+
+use std::time::Duration;
+
+use emu_core::bench::ChipProfiler;
+use emu_core::{AudioFrame, Bus, Cpu, Machine, Observable, ReadResult, RegisterLog, Value};
 use gi_ay_3_8910::Ay3_8910;
 use intel_8255::Ppi8255;
 use ti_tms9918::{Tms9918, VdpRegion};
@@ -28,6 +34,8 @@ use zilog_z80::Z80;
 
 /// VDP dots per CPU cycle.
 const VDP_DOTS_PER_CPU: u64 = 3;
+/// Z80 clock (NTSC colour-burst frequency).
+const CPU_CLOCK_HZ: f64 = 3_579_545.0;
 /// NTSC ticks per frame.
 const NTSC_TICKS_PER_FRAME: u64 = 342 * 262 * 3;
 
@@ -251,6 +259,24 @@ impl MsxBus {
         }
     }
 
+    /// Read memory as the CPU sees it through the current slot selection.
+    /// Memory reads have no side effects, so debuggers can call this
+    /// freely.
+    #[must_use]
+    pub fn peek(&self, addr: u16) -> u8 {
+        // Sub-slot register at $FFFF: return inverted value when slot 3 is
+        // expanded and currently selected for page 3.
+        if addr == 0xFFFF && self.slot3_expanded {
+            let slot = self.resolve_slot(0xFFFF);
+            if slot == 3 {
+                return !self.sub_slot_reg;
+            }
+        }
+
+        let slot = self.resolve_slot(addr);
+        self.read_slot(slot, addr)
+    }
+
     /// Write to a specific slot at the given address.
     fn write_slot(&mut self, slot: u8, addr: u16, value: u8) {
         match slot {
@@ -267,19 +293,7 @@ impl MsxBus {
 
 impl Bus for MsxBus {
     fn read(&mut self, addr: u32) -> ReadResult {
-        let addr = addr as u16;
-
-        // Sub-slot register at $FFFF: return inverted value when slot 3 is
-        // expanded and currently selected for page 3.
-        if addr == 0xFFFF && self.slot3_expanded {
-            let slot = self.resolve_slot(0xFFFF);
-            if slot == 3 {
-                return ReadResult::new(!self.sub_slot_reg);
-            }
-        }
-
-        let slot = self.resolve_slot(addr);
-        ReadResult::new(self.read_slot(slot, addr))
+        ReadResult::new(self.peek(addr as u16))
     }
 
     fn write(&mut self, addr: u32, value: u8) -> u8 {
@@ -349,6 +363,9 @@ impl Bus for MsxBus {
 // MSX system
 // ---------------------------------------------------------------------------
 
+/// Chips timed by the profiler, in the order the tick loop laps them.
+const CHIPS: &[&str] = &["zilog-z80", "ti-tms9918", "gi-ay-3-8910"];
+
 /// MSX1 system.
 pub struct Msx {
     cpu: Z80,
@@ -356,6 +373,8 @@ pub struct Msx {
     master_clock: u64,
     ticks_per_frame: u64,
     frame_count: u64,
+    /// Per-chip tick timing, while benchmarking.
+    profiler: ChipProfiler,
 }
 
 impl Msx {
@@ -375,6 +394,7 @@ impl Msx {
             master_clock: 0,
             ticks_per_frame,
             frame_count: 0,
+            profiler: ChipProfiler::new(CHIPS),
         }
     }
 
@@ -393,16 +413,20 @@ impl Msx {
         let target = self.master_clock + self.ticks_per_frame;
 
         while self.master_clock < target {
+            self.profiler.start();
             self.cpu.tick(&mut self.bus);
+            self.profiler.lap(0);
 
             for _ in 0..VDP_DOTS_PER_CPU {
                 self.bus.vdp.tick();
             }
+            self.profiler.lap(1);
 
             // PSG runs at CPU/2 — tick every other cycle
             if self.master_clock & 1 == 0 {
                 self.bus.psg.tick();
             }
+            self.profiler.lap(2);
 
             // VDP interrupt → Z80 INT
             if self.bus.vdp.interrupt {
@@ -421,7 +445,7 @@ impl Msx {
         self.bus.vdp.framebuffer()
     }
 
-    /// Take audio samples from the PSG (stereo [L, R] pairs, 48 kHz).
+    /// Take audio samples from the PSG (stereo [L, R] pairs at the output rate).
     pub fn take_audio_buffer(&mut self) -> Vec<[f32; 2]> {
         self.bus.psg.take_buffer()
     }
@@ -467,6 +491,31 @@ impl Msx {
 // Tests
 // ---------------------------------------------------------------------------
 
+impl Observable for Msx {
+    fn query(&self, path: &str) -> Option<Value> {
+        if let Some(rest) = path.strip_prefix("cpu.") {
+            self.cpu.query(rest)
+        } else if path == "vdp.scanline" {
+            Some(self.bus.vdp.scanline().into())
+        } else {
+            match path {
+                "master_clock" => Some(self.master_clock.into()),
+                "frame_count" => Some(self.frame_count.into()),
+                _ => self.cpu.query(path),
+            }
+        }
+    }
+
+    fn query_paths(&self) -> &'static [&'static str] {
+        &[
+            "cpu.<z80_paths>",
+            "vdp.scanline",
+            "master_clock",
+            "frame_count",
+        ]
+    }
+}
+
 impl Machine for Msx {
     fn run_frame(&mut self) {
         self.run_frame();
@@ -488,10 +537,76 @@ impl Machine for Msx {
         self.take_audio_buffer()
     }
 
+    fn audio_sample_rate(&self) -> u32 {
+        self.bus.psg.sample_rate()
+    }
+
+    fn set_audio_sample_rate(&mut self, rate: u32) -> bool {
+        self.bus.psg.set_sample_rate(rate);
+        true
+    }
+
+    fn set_audio_rate_adjust(&mut self, ratio: f64) {
+        self.bus.psg.set_rate_adjust(ratio);
+    }
+
+    fn audio_channels(&self) -> Vec<&'static str> {
+        Ay3_8910::CHANNELS.to_vec()
+    }
+
+    fn set_channel_mute(&mut self, mask: u32) {
+        self.bus.psg.set_channel_mute(mask as u8);
+    }
+
+    fn channel_mute(&self) -> u32 {
+        u32::from(self.bus.psg.channel_mute())
+    }
+
+    fn set_stem_capture(&mut self, enabled: bool) {
+        self.bus.psg.set_stems(enabled);
+    }
+
+    fn take_audio_stems(&mut self) -> Vec<Vec<f32>> {
+        self.bus.psg.take_stems()
+    }
+
+    fn start_register_log(&mut self) -> bool {
+        self.bus.psg.start_register_log();
+        true
+    }
+
+    fn stop_register_log(&mut self) -> Option<RegisterLog> {
+        // The PSG ticks on every other CPU cycle.
+        let frame = self.ticks_per_frame / 2;
+        self.bus.psg.stop_register_log().map(|log| log.with_frame_cycles(frame))
+    }
+
     fn frame_count(&self) -> u64 {
         self.frame_count()
     }
 
+    fn cpu_clock_hz(&self) -> f64 {
+        CPU_CLOCK_HZ
+    }
+
+    fn frame_rate_hz(&self) -> f64 {
+        let lines = if self.ticks_per_frame == NTSC_TICKS_PER_FRAME {
+            262.0
+        } else {
+            313.0
+        };
+        CPU_CLOCK_HZ / (228.0 * lines)
+    }
+
+    fn set_chip_profiling(&mut self, enabled: bool) -> bool {
+        self.profiler.set_enabled(enabled);
+        true
+    }
+
+    fn chip_times(&self) -> Vec<(&'static str, Duration)> {
+        self.profiler.times()
+    }
+
     fn reset(&mut self) {
         self.cpu_mut().reset();
     }
@@ -517,6 +632,31 @@ mod tests {
         assert_eq!(msx.frame_count(), 1);
     }
 
+    #[test]
+    fn observable_reports_cpu_and_frame_count() {
+        let mut msx = Msx::new(minimal_bios(), MsxRegion::Ntsc);
+        msx.run_frame();
+        assert_eq!(msx.query("frame_count"), Some(Value::U64(1)));
+        assert!(msx.query("cpu.pc").is_some());
+        assert_eq!(msx.query("nonsense"), None);
+    }
+
+    #[cfg(feature = "native")]
+    #[test]
+    fn mcp_peek_only_sees_ram_in_mapped_pages() {
+        use emu_core::mcp::McpMachine;
+
+        let mut msx = Msx::new(minimal_bios(), MsxRegion::Ntsc);
+        msx.bus.ram[0xC000] = 0xAB;
+        assert_eq!(msx.peek(0xC000), None);
+        assert!(!msx.poke(0xC000, 0x12));
+
+        msx.bus.ppi.write(0, 0xC0); // Slot 3 for page 3
+        assert_eq!(msx.peek(0xC000), Some(0xAB));
+        assert!(msx.poke(0xC000, 0x12));
+        assert_eq!(msx.bus.ram[0xC000], 0x12);
+    }
+
     #[test]
     fn slot_0_reads_bios() {
         let mut bus = MsxBus::new(minimal_bios(), MsxRegion::Ntsc);
@@ -0,0 +1,38 @@
+//! MCP (Model Context Protocol) server for the MSX emulator.
+//!
+//! Serves the generic `MachineMcp` tools. Run with `--mcp` for MCP mode
+//! or `--script` for batch mode.
+
+use emu_core::Instruction;
+use emu_core::mcp::{self, MachineMcp, McpMachine};
+
+use crate::{Msx, NTSC_TICKS_PER_FRAME};
+
+pub type McpServer = mcp::McpServer<MachineMcp<Msx>>;
+
+impl McpMachine for Msx {
+    fn frame_rate(&self) -> u32 {
+        if self.ticks_per_frame == NTSC_TICKS_PER_FRAME {
+            60
+        } else {
+            50
+        }
+    }
+
+    fn disassemble(&self, address: u32) -> Instruction {
+        emu_disasm::z80::disassemble(|addr| self.bus.peek(addr), address)
+    }
+
+    /// RAM lives in slot 3, so only pages currently mapped to it are RAM.
+    fn peek(&self, address: u16) -> Option<u8> {
+        (self.bus.resolve_slot(address) == 3).then(|| self.bus.ram[usize::from(address)])
+    }
+
+    fn poke(&mut self, address: u16, value: u8) -> bool {
+        let is_ram = self.bus.resolve_slot(address) == 3;
+        if is_ram {
+            self.bus.ram[usize::from(address)] = value;
+        }
+        is_ram
+    }
+}
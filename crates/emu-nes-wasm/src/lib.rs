@@ -5,6 +5,7 @@
 
 use wasm_bindgen::prelude::*;
 
+use emu_core::crt::CrtFilter;
 use emu_nes::{Nes, NesButton, NesConfig, NesRegion};
 
 /// NES emulator for the browser.
@@ -15,6 +16,8 @@ pub struct NesEmulator {
     audio_buf: Vec<f32>,
     w: u32,
     h: u32,
+    /// CRT filter applied to each frame.
+    filter: Option<CrtFilter>,
 }
 
 #[wasm_bindgen]
@@ -35,25 +38,47 @@ impl NesEmulator {
             audio_buf: Vec::with_capacity(960),
             w,
             h,
+            filter: None,
         })
     }
 
-    /// Framebuffer width in pixels.
+    /// Width of the RGBA buffer in pixels.
     pub fn width(&self) -> u32 {
-        self.w
+        self.output_size().0
     }
 
-    /// Framebuffer height in pixels.
+    /// Height of the RGBA buffer in pixels.
     pub fn height(&self) -> u32 {
-        self.h
+        self.output_size().1
+    }
+
+    /// Show frames as a period TV would: `none`, `scanlines`, `svideo`,
+    /// `composite` or `crt` (composite with scanlines). Scanlines double
+    /// the height, so read `width()`, `height()` and the buffer pointer
+    /// again afterwards.
+    pub fn set_filter(&mut self, name: &str) -> Result<(), JsError> {
+        self.filter =
+            CrtFilter::from_name(name, self.system.video_signal()).map_err(|e| JsError::new(&e))?;
+        let (w, h) = self.output_size();
+        self.rgba_buf.resize((w * h * 4) as usize, 0);
+        Ok(())
     }
 
     /// Run one emulation frame.
     pub fn run_frame(&mut self) {
         self.system.run_frame();
 
+        let (w, h) = self.output_size();
         let fb = self.system.framebuffer();
-        let px_count = (self.w * self.h) as usize;
+        let filtered;
+        let fb = match &mut self.filter {
+            Some(filter) => {
+                filtered = filter.process(self.w, self.h, fb, w, h);
+                &filtered
+            }
+            None => fb,
+        };
+        let px_count = (w * h) as usize;
         for i in 0..px_count.min(fb.len()) {
             let argb = fb[i];
             let offset = i * 4;
@@ -97,6 +122,22 @@ impl NesEmulator {
         }
     }
 
+    /// Names of the sound channels, in channel-mute bit order.
+    pub fn audio_channels(&self) -> Vec<String> {
+        use emu_core::Machine;
+        self.system
+            .audio_channels()
+            .into_iter()
+            .map(String::from)
+            .collect()
+    }
+
+    /// Mute sound channels in the mix: bit `n` silences `audio_channels()[n]`.
+    pub fn set_channel_mute(&mut self, mask: u32) {
+        use emu_core::Machine;
+        self.system.set_channel_mute(mask);
+    }
+
     /// Reset the NES.
     pub fn reset(&mut self) {
         use emu_core::Cpu;
@@ -104,6 +145,16 @@ impl NesEmulator {
     }
 }
 
+impl NesEmulator {
+    /// Size of the RGBA buffer: the framebuffer, or the filter's output.
+    fn output_size(&self) -> (u32, u32) {
+        match &self.filter {
+            Some(filter) => filter.output_size(self.w, self.h),
+            None => (self.w, self.h),
+        }
+    }
+}
+
 /// Map DOM `KeyboardEvent.code` to NES button.
 fn map_key(code: &str) -> Option<NesButton> {
     Some(match code {
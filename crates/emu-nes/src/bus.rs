@@ -7,7 +7,7 @@
 
 #![allow(clippy::cast_possible_truncation)]
 
-use emu_core::{Bus, ReadResult};
+use emu_core::{Bus, ReadResult, SaveState, StateError, StateReader, StateWriter};
 
 use ricoh_apu_2a03::Apu;
 use ricoh_ppu_2c02::Ppu;
@@ -81,6 +81,18 @@ impl NesBus {
     pub fn peek_ram(&self, addr: u16) -> u8 {
         self.ram[(addr & 0x07FF) as usize]
     }
+
+    /// Peek a byte of CPU address space without side effects (for
+    /// disassembly). Covers RAM and cartridge space; PPU, APU and
+    /// controller registers read as $FF.
+    #[must_use]
+    pub fn peek(&self, addr: u16) -> u8 {
+        match addr {
+            0x0000..=0x1FFF => self.peek_ram(addr),
+            0x2000..=0x401F => 0xFF,
+            0x4020..=0xFFFF => self.cartridge.cpu_read(addr),
+        }
+    }
 }
 
 impl Bus for NesBus {
@@ -179,6 +191,46 @@ impl Bus for NesBus {
     }
 }
 
+impl SaveState for NesBus {
+    fn save_state(&self, w: &mut StateWriter) {
+        w.write_bytes(&self.ram);
+        self.ppu.save_state(w);
+        self.apu.save_state(w);
+        self.cartridge.save_state(w);
+        self.controller1.save_state(w);
+        self.controller2.save_state(w);
+        w.write_option(self.zapper.as_ref());
+        self.controller3.save_state(w);
+        self.controller4.save_state(w);
+        w.write_bool(self.four_score);
+        w.write_u8(self.four_score_idx_1);
+        w.write_u8(self.four_score_idx_2);
+        w.write_bool(self.oam_dma_page.is_some());
+        w.write_u8(self.oam_dma_page.unwrap_or(0));
+        w.write_bool(self.last_cycle_was_write);
+    }
+
+    fn load_state(&mut self, r: &mut StateReader<'_>) -> Result<(), StateError> {
+        r.read_bytes_into(&mut self.ram)?;
+        self.ppu.load_state(r)?;
+        self.apu.load_state(r)?;
+        self.cartridge.load_state(r)?;
+        self.controller1.load_state(r)?;
+        self.controller2.load_state(r)?;
+        r.read_option(self.zapper.as_mut(), "a Zapper")?;
+        self.controller3.load_state(r)?;
+        self.controller4.load_state(r)?;
+        self.four_score = r.read_bool()?;
+        self.four_score_idx_1 = r.read_u8()?;
+        self.four_score_idx_2 = r.read_u8()?;
+        let dma_pending = r.read_bool()?;
+        let page = r.read_u8()?;
+        self.oam_dma_page = dma_pending.then_some(page);
+        self.last_cycle_was_write = r.read_bool()?;
+        Ok(())
+    }
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -5,6 +5,8 @@
 //! Writing bit 0 = 0 latches the current button state.
 //! Each read returns one bit and shifts the register right.
 
+use emu_core::{SaveState, StateError, StateReader, StateWriter};
+
 /// NES button indices (bit positions).
 pub mod button {
     pub const A: u8 = 0;
@@ -50,6 +52,14 @@ impl Controller {
         }
     }
 
+    /// Set every button at once (bit per button, 1 = pressed).
+    pub fn set_buttons(&mut self, buttons: u8) {
+        self.buttons = buttons;
+        if self.strobe {
+            self.shift_register = self.buttons;
+        }
+    }
+
     /// Read $4016/$4017: return bit 0 of shift register, shift right.
     pub fn read(&mut self) -> u8 {
         if self.strobe {
@@ -86,6 +96,21 @@ impl Default for Controller {
     }
 }
 
+impl SaveState for Controller {
+    fn save_state(&self, w: &mut StateWriter) {
+        w.write_u8(self.buttons);
+        w.write_u8(self.shift_register);
+        w.write_bool(self.strobe);
+    }
+
+    fn load_state(&mut self, r: &mut StateReader<'_>) -> Result<(), StateError> {
+        self.buttons = r.read_u8()?;
+        self.shift_register = r.read_u8()?;
+        self.strobe = r.read_bool()?;
+        Ok(())
+    }
+}
+
 /// NES Zapper light gun state.
 ///
 /// Connects to port 2 ($4017). Bit 3 = light sense (0 = light detected,
@@ -146,6 +171,23 @@ impl Default for Zapper {
     }
 }
 
+impl SaveState for Zapper {
+    fn save_state(&self, w: &mut StateWriter) {
+        w.write_bool(self.trigger);
+        w.write_u16(self.aim_x);
+        w.write_u16(self.aim_y);
+        w.write_bool(self.light_detected);
+    }
+
+    fn load_state(&mut self, r: &mut StateReader<'_>) -> Result<(), StateError> {
+        self.trigger = r.read_bool()?;
+        self.aim_x = r.read_u16()?;
+        self.aim_y = r.read_u16()?;
+        self.light_detected = r.read_bool()?;
+        Ok(())
+    }
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
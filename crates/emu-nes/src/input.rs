@@ -22,6 +22,30 @@ pub enum NesButton {
 }
 
 impl NesButton {
+    /// Every button, in controller shift order.
+    pub const ALL: [Self; 8] = [
+        Self::A,
+        Self::B,
+        Self::Select,
+        Self::Start,
+        Self::Up,
+        Self::Down,
+        Self::Left,
+        Self::Right,
+    ];
+
+    /// Stable name used in input movies (the variant name).
+    #[must_use]
+    pub fn name(self) -> String {
+        format!("{self:?}")
+    }
+
+    /// Look up a button by its [`NesButton::name`].
+    #[must_use]
+    pub fn from_name(name: &str) -> Option<Self> {
+        Self::ALL.into_iter().find(|button| button.name() == name)
+    }
+
     /// Return the bit position for this button.
     #[must_use]
     pub const fn bit(self) -> u8 {
@@ -99,6 +123,11 @@ impl InputQueue {
         }
     }
 
+    /// Events that the next `process(frame, ..)` call will apply.
+    pub fn due(&self, frame: u64) -> impl Iterator<Item = &InputEvent> {
+        self.events.iter().take_while(move |e| e.frame <= frame)
+    }
+
     /// Number of pending events.
     #[must_use]
     pub fn len(&self) -> usize {
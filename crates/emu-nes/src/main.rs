@@ -40,6 +40,7 @@ const FRAME_DURATION: Duration = Duration::from_micros(16_639);
 struct CliArgs {
     rom_path: Option<PathBuf>,
     headless: bool,
+    bench: bool,
     mcp: bool,
     script_path: Option<PathBuf>,
     frames: u32,
@@ -55,6 +56,7 @@ fn print_usage() {
     eprintln!("  --rom <file>         iNES ROM file (.nes)");
     eprintln!("  --region <ntsc|pal>  Video region (default: ntsc)");
     eprintln!("  --headless           Run without a window");
+    eprintln!("  --bench              Run --frames frames flat out and report speed");
     eprintln!("  --mcp                Run as MCP server (JSON-RPC over stdio)");
     eprintln!("  --script <file>      Run a JSON script file (headless batch mode)");
     eprintln!("  --frames <n>         Number of frames in headless mode [default: 200]");
@@ -93,6 +95,7 @@ fn parse_args_from(args: &[String]) -> Result<Option<CliArgs>, String> {
     let mut cli = CliArgs {
         rom_path: None,
         headless: false,
+        bench: false,
         mcp: false,
         script_path: None,
         frames: 200,
@@ -110,6 +113,9 @@ fn parse_args_from(args: &[String]) -> Result<Option<CliArgs>, String> {
             "--headless" => {
                 cli.headless = true;
             }
+            "--bench" => {
+                cli.bench = true;
+            }
             "--mcp" => {
                 cli.mcp = true;
             }
@@ -191,6 +197,12 @@ fn run_headless(cli: &CliArgs) {
     }
 }
 
+/// Time `--frames` frames with no pacing and print the benchmark report.
+fn run_bench(cli: &CliArgs) {
+    let mut nes = make_nes(cli);
+    print!("{}", emu_core::bench::run(&mut nes, cli.frames));
+}
+
 // ---------------------------------------------------------------------------
 // Native menus (muda)
 // ---------------------------------------------------------------------------
@@ -509,6 +521,11 @@ fn main() {
         return;
     }
 
+    if cli.bench {
+        run_bench(&cli);
+        return;
+    }
+
     if cli.headless {
         run_headless(&cli);
         return;
@@ -697,6 +714,7 @@ mod tests {
         let cli = CliArgs {
             rom_path: None,
             headless: true,
+            bench: false,
             mcp: false,
             script_path: None,
             frames: 1,
@@ -724,6 +742,7 @@ mod tests {
         let missing_cli = CliArgs {
             rom_path: Some(missing_path.clone()),
             headless: true,
+            bench: false,
             mcp: false,
             script_path: None,
             frames: 1,
@@ -742,6 +761,7 @@ mod tests {
         let invalid_cli = CliArgs {
             rom_path: Some(invalid_rom.path().to_path_buf()),
             headless: true,
+            bench: false,
             mcp: false,
             script_path: None,
             frames: 1,
@@ -762,6 +782,7 @@ mod tests {
         let cli = CliArgs {
             rom_path: Some(rom.path().to_path_buf()),
             headless: true,
+            bench: false,
             mcp: false,
             script_path: None,
             frames: 1,
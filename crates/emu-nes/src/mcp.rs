@@ -11,17 +11,29 @@
 #![allow(clippy::too_many_lines, clippy::match_same_arms)]
 
 use std::path::PathBuf;
+use std::time::Duration;
 
 use base64::Engine;
 use serde_json::Value as JsonValue;
 
-use emu_core::mcp::{self, McpEmulator, ToolDefinition, ToolResult};
+use emu_core::breakpoint::{Breakpoint, Breakpoints, Hit, Trigger};
+use emu_core::mcp::breakpoint::BreakpointSpace;
+use emu_core::mcp::{self, McpEmulator, Notification, RunSession, ToolDefinition, ToolResult};
+use emu_core::symbols::SymbolTable;
+use emu_core::trace::TraceLayout;
 use emu_core::{Cpu, Observable, Tickable};
 
 use crate::Nes;
 use crate::config::{NesConfig, NesRegion};
 use crate::input::NesButton;
 
+/// Layout of `trace_start` lines: the 2A03 register file.
+const TRACE_LAYOUT: TraceLayout = TraceLayout {
+    address_digits: 4,
+    opcode_bytes: 3,
+    registers: &["cpu.a", "cpu.x", "cpu.y", "cpu.s", "cpu.p"],
+};
+
 // ---------------------------------------------------------------------------
 // Public re-export: the MCP server type for main.rs
 // ---------------------------------------------------------------------------
@@ -34,7 +46,12 @@ pub type McpServer = mcp::McpServer<NesMcp>;
 
 pub struct NesMcp {
     nes: Option<Nes>,
+    /// Configuration the NES was booted with, for input movies.
+    config: Option<NesConfig>,
     rom_path: Option<PathBuf>,
+    breakpoints: Breakpoints,
+    run: RunSession,
+    symbols: SymbolTable,
 }
 
 impl NesMcp {
@@ -42,7 +59,11 @@ impl NesMcp {
     pub fn new() -> Self {
         Self {
             nes: None,
+            config: None,
             rom_path: None,
+            breakpoints: Breakpoints::new(),
+            run: RunSession::new(),
+            symbols: SymbolTable::new(),
         }
     }
 
@@ -52,14 +73,7 @@ impl NesMcp {
     }
 
     fn require_nes(&mut self) -> Result<&mut Nes, ToolResult> {
-        if let Some(ref mut nes) = self.nes {
-            Ok(nes)
-        } else {
-            Err(ToolResult::Error {
-                code: -32000,
-                message: "No NES instance. Call 'boot' first.".to_string(),
-            })
-        }
+        self.nes.as_mut().ok_or_else(no_nes)
     }
 }
 
@@ -86,7 +100,7 @@ impl McpEmulator for NesMcp {
     }
 
     fn tool_definitions(&self) -> Vec<ToolDefinition> {
-        vec![
+        let mut tools = vec![
             ToolDefinition {
                 name: "boot",
                 description: "Boot the NES with a ROM (from data, path, or CLI --rom)",
@@ -151,7 +165,8 @@ impl McpEmulator for NesMcp {
                     "type": "object",
                     "properties": {
                         "save_path": { "type": "string", "description": "If set, save PNG to this path and return metadata only" },
-                        "correct_aspect": { "type": "boolean", "description": "Scale to 4:3 display aspect ratio (default: true)" }
+                        "correct_aspect": { "type": "boolean", "description": "Scale to 4:3 display aspect ratio (default: true)" },
+                        "filter": mcp::filter_property()
                     }
                 }),
             },
@@ -237,6 +252,7 @@ impl McpEmulator for NesMcp {
                     "type": "object",
                     "properties": {
                         "address": { "type": "integer" },
+                        "condition": { "type": "string", "description": "Only stop when this expression holds, e.g. \"cpu.a == 0x20\"" },
                         "max_frames": { "type": "integer", "default": 10000 }
                     },
                     "required": ["address"]
@@ -254,6 +270,8 @@ impl McpEmulator for NesMcp {
                     "required": ["address", "length"]
                 }),
             },
+            mcp::disassemble_definition(),
+            mcp::record_gif_definition(),
             ToolDefinition {
                 name: "enable_zapper",
                 description: "Enable the Zapper light gun on port 2",
@@ -296,6 +314,19 @@ impl McpEmulator for NesMcp {
                     }
                 }),
             },
+            ToolDefinition {
+                name: "audio_capture",
+                description: "Run N frames and capture mono audio as WAV",
+                input_schema: serde_json::json!({
+                    "type": "object",
+                    "properties": {
+                        "frames": { "type": "integer", "default": 60 },
+                        "save_path": { "type": "string", "description": "Save WAV to this path" },
+                        "stems": mcp::stems_property()
+                    }
+                }),
+            },
+            mcp::channel_mute_definition(),
             ToolDefinition {
                 name: "record_video",
                 description: "Record N frames as MP4 video with audio",
@@ -304,16 +335,23 @@ impl McpEmulator for NesMcp {
                     "properties": {
                         "frames": { "type": "integer", "description": "Number of frames to record" },
                         "save_path": { "type": "string", "description": "Write MP4 to this path" },
-                        "correct_aspect": { "type": "boolean", "description": "Scale to 4:3 display aspect ratio (default: true)" }
+                        "correct_aspect": { "type": "boolean", "description": "Scale to 4:3 display aspect ratio (default: true)" },
+                        "filter": mcp::filter_property()
                     },
                     "required": ["frames", "save_path"]
                 }),
             },
-        ]
+        ];
+        tools.extend(mcp::breakpoint::breakpoint_definitions());
+        tools.extend(mcp::trace::trace_definitions());
+        tools.extend(mcp::run::run_definitions());
+        tools.extend(mcp::symbols::symbol_definitions());
+        tools.extend(mcp::movie::movie_definitions());
+        tools
     }
 
     fn dispatch_tool(&mut self, name: &str, arguments: &JsonValue) -> ToolResult {
-        match name {
+        let result = match name {
             "boot" => self.handle_boot(arguments),
             "reset" => self.handle_reset(),
             "load_rom" => self.handle_load_rom(arguments),
@@ -328,19 +366,110 @@ impl McpEmulator for NesMcp {
             "release_button" => self.handle_release_button(arguments),
             "input_sequence" => self.handle_input_sequence(arguments),
             "set_breakpoint" => self.handle_set_breakpoint(arguments),
+            "add_breakpoint" => self.handle_add_breakpoint(arguments),
+            "remove_breakpoint" => {
+                mcp::breakpoint::remove_breakpoint_result(arguments, &mut self.breakpoints)
+            }
+            "list_breakpoints" => {
+                mcp::breakpoint::list_breakpoints_result(&self.breakpoints, &self.symbols)
+            }
+            "clear_breakpoints" => mcp::breakpoint::clear_breakpoints_result(&mut self.breakpoints),
+            "run_until_break" => self.handle_run_until_break(arguments),
+            "trace_start" => self.handle_trace_start(arguments),
+            "trace_stop" => self.handle_trace_stop(),
+            "trace_export" => self.handle_trace_export(arguments),
             "query_memory" => self.handle_query_memory(arguments),
+            "disassemble" => self.handle_disassemble(arguments),
             "enable_zapper" => self.handle_enable_zapper(),
             "zapper_aim" => self.handle_zapper_aim(arguments),
             "zapper_trigger" => self.handle_zapper_trigger(arguments),
             "save_battery" => self.handle_save_battery(),
             "load_battery" => self.handle_load_battery(arguments),
+            "record_gif" => self.handle_record_gif(arguments),
             "record_video" => self.handle_record_video(arguments),
+            "record_movie" => self.handle_record_movie(arguments),
+            "play_movie" => self.handle_play_movie(arguments),
+            "audio_capture" => self.handle_audio_capture(arguments),
+            "channel_mute" => match self.require_nes() {
+                Ok(nes) => mcp::channel_mute_result(arguments, nes),
+                Err(e) => e,
+            },
+            "run" => self.handle_run(arguments),
+            "pause" => self.handle_pause(),
+            "load_symbols" => self.handle_load_symbols(arguments),
+            "clear_symbols" => mcp::symbols::clear_symbols_result(&mut self.symbols),
+            "lookup_symbol" => mcp::symbols::lookup_symbol_result(arguments, &self.symbols),
             _ => ToolResult::Error {
                 code: -32601,
                 message: format!("Unknown tool: {name}"),
             },
+        };
+        if matches!(name, "boot" | "load_rom") {
+            self.run
+                .media_tool_done("cartridge", name, arguments, &result);
         }
+        result
+    }
+
+    fn run_slice(&mut self) -> Option<Duration> {
+        if !self.run.is_running() {
+            return None;
+        }
+        let Some(nes) = self.nes.as_mut() else {
+            self.run.stop();
+            return None;
+        };
+
+        // Breakpoints need the machine ticked one step at a time; without
+        // any, run_frame keeps queued input and movies in play.
+        if self.breakpoints.is_empty() {
+            nes.run_frame();
+        } else if let (Some(hit), _) = run_until_break(nes, &mut self.breakpoints, 1) {
+            let pc = format!("${:04X}", nes.cpu().regs.pc);
+            self.run.breakpoint_hit(&hit, &pc, &self.symbols);
+            return None;
+        }
+        self.run.frame_done(nes.frame_count());
+
+        if nes.cpu().is_halted() {
+            self.run
+                .cpu_halted(&format!("${:04X}", nes.cpu().regs.pc), "JAM opcode");
+        }
+        self.run.next_slice()
+    }
+
+    fn take_notifications(&mut self) -> Vec<Notification> {
+        self.run.take_notifications()
+    }
+}
+
+fn no_nes() -> ToolResult {
+    ToolResult::Error {
+        code: -32000,
+        message: "No NES instance. Call 'boot' first.".to_string(),
+    }
+}
+
+/// Run until a breakpoint stops execution or `max_frames` frames complete.
+/// Returns the hit and the number of frames completed.
+fn run_until_break(
+    nes: &mut Nes,
+    breakpoints: &mut Breakpoints,
+    max_frames: u64,
+) -> (Option<Hit>, u64) {
+    // The NES has no frame-complete flag to poll; count frames by ticks.
+    let ticks_per_frame = 341 * 262 * 4;
+    let max_ticks = max_frames * ticks_per_frame;
+    let mut ticks_run = 0u64;
+    if max_ticks == 0 {
+        return (None, 0);
     }
+    let hit = breakpoints.run(nes, |nes| {
+        nes.tick();
+        ticks_run += 1;
+        ticks_run < max_ticks
+    });
+    (hit, ticks_run / ticks_per_frame)
 }
 
 // ---------------------------------------------------------------------------
@@ -388,6 +517,7 @@ impl NesMcp {
         match Nes::new(&config) {
             Ok(nes) => {
                 self.nes = Some(nes);
+                self.config = Some(config);
                 ToolResult::Success(serde_json::json!({"status": "ok"}))
             }
             Err(e) => ToolResult::Error {
@@ -419,6 +549,7 @@ impl NesMcp {
         match Nes::new(&config) {
             Ok(nes) => {
                 self.nes = Some(nes);
+                self.config = Some(config);
                 ToolResult::Success(serde_json::json!({"status": "ok"}))
             }
             Err(e) => ToolResult::Error {
@@ -428,6 +559,45 @@ impl NesMcp {
         }
     }
 
+    fn handle_record_movie(&mut self, params: &JsonValue) -> ToolResult {
+        let stop = match mcp::movie::stop_param(params) {
+            Ok(s) => s,
+            Err(e) => return e,
+        };
+        let (Some(nes), Some(config)) = (self.nes.as_mut(), &self.config) else {
+            return no_nes();
+        };
+
+        if stop {
+            return mcp::movie::recording_stopped_result(params, nes.stop_recording());
+        }
+        nes.start_recording(config);
+        mcp::movie::recording_started_result(nes.frame_count())
+    }
+
+    fn handle_play_movie(&mut self, params: &JsonValue) -> ToolResult {
+        let movie = match mcp::movie::movie_param(params) {
+            Ok(m) => m,
+            Err(e) => return e,
+        };
+        let media = match mcp::movie::media_param(params) {
+            Ok(m) => m,
+            Err(e) => return e,
+        };
+        let (Some(nes), Some(config)) = (self.nes.as_mut(), &self.config) else {
+            return no_nes();
+        };
+
+        let media: Vec<(&str, &[u8])> = media
+            .iter()
+            .map(|(slot, data)| (slot.as_str(), data.as_slice()))
+            .collect();
+        if let Err(e) = nes.play_movie(&movie, config, &media) {
+            return mcp::movie::movie_error(&e);
+        }
+        mcp::movie::play_result(params, &movie, nes)
+    }
+
     fn handle_run_frames(&mut self, params: &JsonValue) -> ToolResult {
         let nes = match self.require_nes() {
             Ok(n) => n,
@@ -509,12 +679,17 @@ impl NesMcp {
 
         let save_path = params.get("save_path").and_then(|v| v.as_str());
         let display = parse_display_size(params, nes.framebuffer_width(), nes.framebuffer_height());
+        let filter = match mcp::parse_filter(params, nes.video_signal()) {
+            Ok(filter) => filter.map(|f| f.at_frame(nes.frame_count())),
+            Err(e) => return e,
+        };
         mcp::screenshot_result(
             nes.framebuffer_width(),
             nes.framebuffer_height(),
             nes.framebuffer(),
             save_path,
             display,
+            filter,
         )
     }
 
@@ -709,49 +884,135 @@ impl NesMcp {
     }
 
     fn handle_set_breakpoint(&mut self, params: &JsonValue) -> ToolResult {
-        let nes = match self.require_nes() {
-            Ok(n) => n,
-            Err(e) => return e,
+        let Self {
+            nes,
+            breakpoints,
+            symbols,
+            ..
+        } = self;
+        let Some(nes) = nes.as_mut() else {
+            return no_nes();
         };
 
-        let addr = match params.get("address").and_then(|v| v.as_u64()) {
-            Some(a) if a <= 0xFFFF => a as u16,
-            _ => {
+        let addr = match mcp::symbols::address_param(params, "address", symbols, 0xFFFF) {
+            Ok(Some(a)) => a as u16,
+            Ok(None) => {
                 return ToolResult::Error {
                     code: -32602,
                     message: "Missing or invalid 'address' (0-65535)".to_string(),
                 };
             }
+            Err(e) => return e,
         };
 
-        let max_frames = params
-            .get("max_frames")
-            .and_then(|v| v.as_u64())
-            .unwrap_or(10_000);
+        let condition = match mcp::breakpoint::parse_condition(params, nes) {
+            Ok(c) => c,
+            Err(e) => return e,
+        };
+        let max_frames = mcp::breakpoint::max_frames_param(params);
+
+        // Run alongside any breakpoints added with add_breakpoint, so
+        // whichever comes first stops execution.
+        let mut breakpoint = Breakpoint::new(Trigger::Execute(u32::from(addr))).temporary();
+        breakpoint.condition = condition;
+        let id = breakpoints.add(breakpoint);
+        let (hit, frames_run) = run_until_break(nes, breakpoints, max_frames);
+        breakpoints.remove(id);
 
-        let ticks_per_frame = 341 * 262 * 4;
-        let mut ticks_run = 0u64;
-        let mut hit = false;
+        ToolResult::Success(serde_json::json!({
+            "hit": hit.is_some(),
+            "breakpoint": hit.as_ref().map(|h| mcp::breakpoint::hit_to_json(h, symbols)),
+            "pc": format!("${:04X}", nes.cpu().regs.pc),
+            "frames_run": frames_run,
+        }))
+    }
 
-        let max_ticks = max_frames * ticks_per_frame;
+    fn handle_add_breakpoint(&mut self, params: &JsonValue) -> ToolResult {
+        let Some(nes) = self.nes.as_ref() else {
+            return no_nes();
+        };
+        mcp::breakpoint::add_breakpoint_result(
+            params,
+            &mut self.breakpoints,
+            nes,
+            BreakpointSpace::MEMORY_16,
+            &self.symbols,
+        )
+    }
 
-        while ticks_run < max_ticks {
-            nes.tick();
-            ticks_run += 1;
+    fn handle_run_until_break(&mut self, params: &JsonValue) -> ToolResult {
+        let Some(nes) = self.nes.as_mut() else {
+            return no_nes();
+        };
+        let max_frames = mcp::breakpoint::max_frames_param(params);
+        let (hit, frames_run) = run_until_break(nes, &mut self.breakpoints, max_frames);
+        mcp::breakpoint::run_until_break_result(
+            hit.as_ref(),
+            &format!("${:04X}", nes.cpu().regs.pc),
+            frames_run,
+            &self.symbols,
+        )
+    }
 
-            if nes.cpu().regs.pc == addr && nes.cpu().is_instruction_complete() {
-                hit = true;
-                break;
+    fn handle_run(&mut self, params: &JsonValue) -> ToolResult {
+        let Some(nes) = self.nes.as_ref() else {
+            return no_nes();
+        };
+        let fps = match nes.region() {
+            NesRegion::Ntsc => 60,
+            NesRegion::Pal => 50,
+        };
+        self.run.start(params, fps)
+    }
+
+    fn handle_pause(&mut self) -> ToolResult {
+        let position = self.nes.as_ref().map_or(JsonValue::Null, |nes| {
+            serde_json::json!({
+                "pc": format!("${:04X}", nes.cpu().regs.pc),
+                "frame_count": nes.frame_count(),
+            })
+        });
+        self.run.pause(position)
+    }
+
+    fn handle_trace_start(&mut self, params: &JsonValue) -> ToolResult {
+        let Some(nes) = self.nes.as_mut() else {
+            return no_nes();
+        };
+        match mcp::trace::parse_tracer(params, TRACE_LAYOUT, &self.symbols) {
+            Ok(tracer) => {
+                let result = mcp::trace::trace_start_result(&tracer);
+                // A trace being replaced still gets its last entry written.
+                if let Some(mut old) = nes.set_tracer(Some(tracer)) {
+                    let _ = old.stop();
+                }
+                result
             }
+            Err(e) => e,
         }
+    }
 
-        let frames_run = ticks_run / ticks_per_frame;
+    fn handle_load_symbols(&mut self, params: &JsonValue) -> ToolResult {
+        let result = mcp::symbols::load_symbols_result(params, &mut self.symbols);
+        // A running trace labels the rest of its entries with them too.
+        if let Some(tracer) = self.nes.as_mut().and_then(Nes::tracer_mut) {
+            tracer.set_symbols(self.symbols.clone());
+        }
+        result
+    }
 
-        ToolResult::Success(serde_json::json!({
-            "hit": hit,
-            "pc": format!("${:04X}", if hit { addr } else { nes.cpu().regs.pc }),
-            "frames_run": frames_run,
-        }))
+    fn handle_trace_stop(&mut self) -> ToolResult {
+        match self.require_nes() {
+            Ok(nes) => mcp::trace::trace_stop_result(nes.tracer_mut()),
+            Err(e) => e,
+        }
+    }
+
+    fn handle_trace_export(&mut self, params: &JsonValue) -> ToolResult {
+        match self.require_nes() {
+            Ok(nes) => mcp::trace::trace_export_result(params, nes.tracer()),
+            Err(e) => e,
+        }
     }
 
     fn handle_query_memory(&mut self, params: &JsonValue) -> ToolResult {
@@ -797,6 +1058,18 @@ impl NesMcp {
         }))
     }
 
+    fn handle_disassemble(&mut self, params: &JsonValue) -> ToolResult {
+        let Some(nes) = self.nes.as_ref() else {
+            return no_nes();
+        };
+
+        let pc = u32::from(nes.cpu().regs.pc);
+        let bus = nes.bus();
+        mcp::disassemble_result(params, Some(pc), &self.symbols, |address| {
+            emu_disasm::mos6502::disassemble(|addr| bus.peek(addr), address)
+        })
+    }
+
     fn handle_enable_zapper(&mut self) -> ToolResult {
         let nes = match self.require_nes() {
             Ok(n) => n,
@@ -872,6 +1145,80 @@ impl NesMcp {
         }
     }
 
+    fn handle_record_gif(&mut self, params: &JsonValue) -> ToolResult {
+        let nes = match self.require_nes() {
+            Ok(n) => n,
+            Err(e) => return e,
+        };
+
+        let fps = match nes.region() {
+            NesRegion::Ntsc => 60,
+            NesRegion::Pal => 50,
+        };
+        let (width, height) = (nes.framebuffer_width(), nes.framebuffer_height());
+        let active = Some(nes.display_window());
+        mcp::record_gif_result(params, width, height, fps, active, |rec| {
+            nes.run_frame();
+            rec.add_frame(nes.framebuffer());
+        })
+    }
+
+    fn handle_audio_capture(&mut self, params: &JsonValue) -> ToolResult {
+        let nes = match self.require_nes() {
+            Ok(n) => n,
+            Err(e) => return e,
+        };
+
+        let frames = params.get("frames").and_then(|v| v.as_u64()).unwrap_or(60);
+
+        let mut stems = match mcp::StemCapture::start(params, nes) {
+            Ok(s) => s,
+            Err(e) => return e,
+        };
+
+        let mut all_audio: Vec<f32> = Vec::new();
+        for _ in 0..frames {
+            nes.run_frame();
+            all_audio.extend_from_slice(&nes.take_audio_buffer());
+            if let Some(stems) = &mut stems {
+                stems.collect(nes);
+            }
+        }
+
+        let wav = match mcp::encode_wav(&all_audio, 1, nes.audio_sample_rate()) {
+            Ok(wav) => wav,
+            Err(e) => {
+                return ToolResult::Error {
+                    code: -32000,
+                    message: e,
+                };
+            }
+        };
+        let save_path = params.get("save_path").and_then(|v| v.as_str());
+        if let Some(save_path) = save_path
+            && let Err(e) = std::fs::write(save_path, &wav)
+        {
+            return ToolResult::Error {
+                code: -32000,
+                message: format!("Failed to save audio: {e}"),
+            };
+        }
+
+        let mut result = serde_json::json!({
+            "format": "wav",
+            "samples": all_audio.len(),
+            "frames": frames,
+            "data": base64::engine::general_purpose::STANDARD.encode(&wav),
+        });
+        if let Some(stems) = stems {
+            match stems.finish(nes, save_path) {
+                Ok(stems) => result["stems"] = stems,
+                Err(e) => return e,
+            }
+        }
+        ToolResult::Success(result)
+    }
+
     fn handle_record_video(&mut self, params: &JsonValue) -> ToolResult {
         let nes = match self.require_nes() {
             Ok(n) => n,
@@ -900,7 +1247,16 @@ impl NesMcp {
             NesRegion::Pal => 50,
         };
 
+        let filter = match mcp::parse_filter(params, nes.video_signal()) {
+            Ok(filter) => filter.map(|f| f.at_frame(nes.frame_count())),
+            Err(e) => return e,
+        };
         let display = parse_display_size(params, nes.framebuffer_width(), nes.framebuffer_height());
+        let display = display.or_else(|| {
+            filter
+                .as_ref()
+                .map(|f| f.output_size(nes.framebuffer_width(), nes.framebuffer_height()))
+        });
         let mut rec = match emu_core::video::VideoRecorder::new(
             nes.framebuffer_width(),
             nes.framebuffer_height(),
@@ -919,6 +1275,10 @@ impl NesMcp {
             }
         };
 
+        if let Some(filter) = filter {
+            rec.set_filter(filter);
+        }
+
         for _ in 0..frames {
             nes.run_frame();
             let audio = nes.take_audio_buffer();
@@ -998,6 +1358,10 @@ mod tests {
     use super::*;
 
     fn make_nes() -> Nes {
+        Nes::new(&make_config()).expect("minimal iNES ROM should load")
+    }
+
+    fn make_config() -> NesConfig {
         let mut rom_data = vec![0u8; 16 + 16_384];
         rom_data[0..4].copy_from_slice(b"NES\x1A");
         rom_data[4] = 1;
@@ -1005,11 +1369,10 @@ mod tests {
         rom_data[16] = 0xEA;
         rom_data[16 + 0x3FFC] = 0x00;
         rom_data[16 + 0x3FFD] = 0x80;
-        Nes::new(&NesConfig {
+        NesConfig {
             rom_data,
             region: NesRegion::Ntsc,
-        })
-        .expect("minimal iNES ROM should load")
+        }
     }
 
     #[test]
@@ -1096,11 +1459,41 @@ mod tests {
         assert!(matches!(result, ToolResult::Error { .. }));
     }
 
+    #[test]
+    fn disassemble_reads_prg_rom() {
+        let mut mcp = NesMcp {
+            nes: Some(make_nes()),
+            config: None,
+            rom_path: None,
+            breakpoints: Breakpoints::new(),
+            run: RunSession::new(),
+            symbols: SymbolTable::new(),
+        };
+
+        let result = mcp.dispatch_tool(
+            "disassemble",
+            &serde_json::json!({"address": 0x8000, "count": 2}),
+        );
+        match result {
+            ToolResult::Success(value) => {
+                let insns = value["instructions"].as_array().expect("instructions");
+                assert_eq!(insns[0]["text"], "NOP");
+                assert_eq!(insns[1]["address"], 0x8001);
+                assert_eq!(insns[1]["text"], "BRK");
+            }
+            ToolResult::Error { message, .. } => panic!("unexpected error: {message}"),
+        }
+    }
+
     #[test]
     fn query_paths_can_filter_to_ppu_and_apu_surfaces() {
         let mut mcp = NesMcp {
             nes: Some(make_nes()),
+            config: None,
             rom_path: None,
+            breakpoints: Breakpoints::new(),
+            run: RunSession::new(),
+            symbols: SymbolTable::new(),
         };
 
         let ppu_result = mcp.dispatch_tool(
@@ -1153,4 +1546,151 @@ mod tests {
             ToolResult::Error { message, .. } => panic!("unexpected error: {message}"),
         }
     }
+
+    #[test]
+    fn run_pushes_breakpoint_hit_and_pause_reports_the_position() {
+        let mut mcp = NesMcp::new();
+        mcp.nes = Some(make_nes());
+        mcp.dispatch_tool(
+            "add_breakpoint",
+            &serde_json::json!({"type": "execute", "address": 0x8001}),
+        );
+        let run = mcp.dispatch_tool("run", &serde_json::json!({"realtime": false}));
+        assert!(matches!(run, ToolResult::Success(_)));
+        assert_eq!(mcp.run_slice(), None);
+
+        let sent = mcp.take_notifications();
+        assert_eq!(sent.len(), 1);
+        assert_eq!(sent[0].event, "breakpoint_hit");
+        assert_eq!(sent[0].params["pc"], "$8001");
+        match mcp.dispatch_tool("pause", &JsonValue::Null) {
+            ToolResult::Success(value) => {
+                assert_eq!(value["was_running"], false);
+                assert_eq!(value["pc"], "$8001");
+            }
+            ToolResult::Error { message, .. } => panic!("unexpected error: {message}"),
+        }
+    }
+
+    #[test]
+    fn breakpoints_watchpoints_and_raster_positions() {
+        let mut mcp = NesMcp {
+            nes: Some(make_nes()),
+            config: None,
+            rom_path: None,
+            breakpoints: Breakpoints::new(),
+            run: RunSession::new(),
+            symbols: SymbolTable::new(),
+        };
+        let run =
+            |mcp: &mut NesMcp| match mcp.dispatch_tool("run_until_break", &serde_json::json!({})) {
+                ToolResult::Success(value) => value,
+                ToolResult::Error { message, .. } => panic!("unexpected error: {message}"),
+            };
+
+        // The ROM is NOP then BRK, so the first stack push follows $8001.
+        mcp.dispatch_tool(
+            "add_breakpoint",
+            &serde_json::json!({"type": "execute", "address": 0x8001}),
+        );
+        let value = run(&mut mcp);
+        assert_eq!(value["pc"], "$8001");
+        assert_eq!(value["breakpoint"]["type"], "execute");
+
+        mcp.dispatch_tool(
+            "add_breakpoint",
+            &serde_json::json!({"type": "write", "address": 0x0100, "end": 0x01FF}),
+        );
+        let value = run(&mut mcp);
+        assert_eq!(value["breakpoint"]["access"]["kind"], "write");
+        let address = value["breakpoint"]["access"]["address"]
+            .as_u64()
+            .expect("address");
+        assert!((0x0100..=0x01FF).contains(&address));
+
+        mcp.dispatch_tool("clear_breakpoints", &JsonValue::Null);
+        mcp.dispatch_tool(
+            "add_breakpoint",
+            &serde_json::json!({"type": "raster", "line": 241, "cycle": 1}),
+        );
+        run(&mut mcp);
+        let ppu = &mcp.nes.as_ref().expect("nes").bus().ppu;
+        assert_eq!((ppu.scanline(), ppu.dot()), (241, 1));
+    }
+
+    #[test]
+    fn ca65_debug_labels_reach_breakpoint_notifications() {
+        let path = std::env::temp_dir().join("emu-nes-mcp-labels.dbg");
+        std::fs::write(
+            &path,
+            "version\tmajor=2,minor=0\n\
+             sym\tid=0,name=\"reset\",addrsize=absolute,scope=0,def=1,val=0x8000,seg=0,type=lab\n\
+             sym\tid=1,name=\"crash\",addrsize=absolute,scope=0,def=2,val=0x8001,seg=0,type=lab\n",
+        )
+        .expect("write debug info");
+        let mut mcp = NesMcp::new();
+        mcp.nes = Some(make_nes());
+        let loaded = mcp.dispatch_tool("load_symbols", &serde_json::json!({"path": path.to_str()}));
+        let _ = std::fs::remove_file(&path);
+        assert!(matches!(loaded, ToolResult::Success(_)));
+
+        mcp.dispatch_tool(
+            "add_breakpoint",
+            &serde_json::json!({"type": "execute", "address": "crash"}),
+        );
+        mcp.dispatch_tool("run", &serde_json::json!({"realtime": false}));
+        assert_eq!(mcp.run_slice(), None);
+        let sent = mcp.take_notifications();
+        assert_eq!(sent[0].params["breakpoint"]["address"], 0x8001);
+        assert_eq!(sent[0].params["breakpoint"]["label"], "crash");
+    }
+
+    #[test]
+    fn movie_records_and_replays_through_tools() {
+        let movie_mcp = || {
+            let mut mcp = NesMcp::new();
+            mcp.nes = Some(make_nes());
+            mcp.config = Some(make_config());
+            mcp
+        };
+
+        let mut mcp = movie_mcp();
+        let ToolResult::Success(started) = mcp.dispatch_tool("record_movie", &JsonValue::Null)
+        else {
+            panic!("record_movie start failed");
+        };
+        assert_eq!(started["recording"], true);
+        mcp.dispatch_tool("run_frames", &serde_json::json!({"count": 2}));
+        mcp.dispatch_tool("press_button", &serde_json::json!({"button": "start"}));
+        mcp.dispatch_tool("run_frames", &serde_json::json!({"count": 2}));
+        let ToolResult::Success(stopped) =
+            mcp.dispatch_tool("record_movie", &serde_json::json!({"action": "stop"}))
+        else {
+            panic!("record_movie stop failed");
+        };
+        assert_eq!(stopped["frames"], 4);
+        assert_eq!(stopped["events"], 1);
+
+        let bytes = base64::engine::general_purpose::STANDARD
+            .decode(stopped["data"].as_str().expect("movie returned as base64"))
+            .expect("movie data decodes");
+        let mut movie = emu_core::Movie::from_bytes(&bytes).expect("movie parses");
+        let ToolResult::Success(played) =
+            movie_mcp().dispatch_tool("play_movie", &serde_json::json!({"data": stopped["data"]}))
+        else {
+            panic!("play_movie failed");
+        };
+        assert_eq!(played["matched"], true);
+
+        movie.frame_hashes[1] ^= 1;
+        let data = base64::engine::general_purpose::STANDARD.encode(movie.to_bytes());
+        let ToolResult::Success(played) =
+            movie_mcp().dispatch_tool("play_movie", &serde_json::json!({"data": data}))
+        else {
+            panic!("play_movie failed");
+        };
+        assert_eq!(played["matched"], false);
+        assert_eq!(played["divergence"]["frame"], 2);
+        assert_eq!(played["frame"], 2);
+    }
 }
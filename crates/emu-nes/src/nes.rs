@@ -11,19 +11,40 @@
 
 #![allow(clippy::cast_possible_truncation)]
 
-use emu_core::{AudioFrame, Bus, Cpu, Machine, Observable, Tickable, Value};
+use std::time::Duration;
+
+use emu_core::bench::ChipProfiler;
+use emu_core::breakpoint::Debuggable;
+use emu_core::crt::Signal;
+use emu_core::movie::{Movie, MovieError};
+use emu_core::trace::{self, Tracer};
+use emu_core::{
+    AudioFrame, Bus, BusAccess, Cpu, LoggingBus, Machine, NetplayInput, Observable, SaveState,
+    StateError, StateReader, StateWriter, Tickable, Value,
+};
 use mos_6502::Mos6502;
 
 use crate::bus::NesBus;
 use crate::cartridge::{self, Mapper};
 use crate::config::{NesConfig, NesRegion};
 use crate::controller::Controller;
-use crate::input::{InputQueue, NesButton};
+use crate::input::{InputEvent, InputQueue, NesButton};
 use crate::ppu;
 
 // Crystal divisors are region-dependent — see NesRegion::ppu_divisor() and
 // NesRegion::cpu_divisor(). NTSC: ÷4/÷12, PAL: ÷5/÷16.
 
+/// Machine tag in save-state headers.
+const STATE_TAG: &str = "nes";
+
+/// Chips timed by the profiler, in the order the tick loop laps them.
+const CHIPS: &[&str] = &[
+    "ricoh-ppu-2c02",
+    "mos-6502",
+    "nes-cartridge",
+    "ricoh-apu-2a03",
+];
+
 /// NES system.
 pub struct Nes {
     cpu: Mos6502,
@@ -47,6 +68,16 @@ pub struct Nes {
     region: NesRegion,
     /// Whether the cartridge has battery-backed save RAM.
     has_battery: bool,
+    /// Input movie being recorded, if any.
+    movie: Option<Movie>,
+    /// Media a movie being played loads, as (frame, slot, image).
+    movie_media: Vec<(u64, String, Vec<u8>)>,
+    /// CPU bus cycles logged for watchpoints, while logging is enabled.
+    bus_log: Option<Vec<BusAccess>>,
+    /// Instruction trace being recorded, if any.
+    tracer: Option<Tracer>,
+    /// Per-chip tick timing, while benchmarking.
+    profiler: ChipProfiler,
 }
 
 impl Nes {
@@ -90,6 +121,11 @@ impl Nes {
             dmc_dma_cycles: 0,
             region,
             has_battery: false,
+            movie: None,
+            movie_media: Vec::new(),
+            bus_log: None,
+            tracer: None,
+            profiler: ChipProfiler::new(CHIPS),
         }
     }
 
@@ -100,6 +136,12 @@ impl Nes {
     ///
     /// Returns the number of crystal ticks executed.
     pub fn run_frame(&mut self) -> u64 {
+        self.load_movie_media();
+        if let Some(movie) = &mut self.movie {
+            for event in self.input_queue.due(self.frame_count) {
+                movie.push_event(self.frame_count, event.button.name(), event.pressed);
+            }
+        }
         self.input_queue
             .process(self.frame_count, &mut self.bus.controller1);
         self.frame_count += 1;
@@ -116,6 +158,10 @@ impl Nes {
             z.update_light_sense(self.bus.ppu.framebuffer(), ppu::FB_WIDTH);
         }
 
+        if let Some(movie) = &mut self.movie {
+            movie.push_frame(self.bus.ppu.framebuffer());
+        }
+
         self.master_clock - start_clock
     }
 
@@ -143,6 +189,24 @@ impl Nes {
         self.region
     }
 
+    /// The PPU's composite video signal, for the CRT filter. Only the
+    /// NTSC 2C02 is described.
+    #[must_use]
+    pub fn video_signal(&self) -> Option<Signal> {
+        (self.region == NesRegion::Ntsc).then_some(ppu::composite::NTSC_SIGNAL)
+    }
+
+    /// The part of the picture a TV shows, as x, y, width and height in
+    /// the framebuffer. NTSC sets lose the top and bottom eight lines to
+    /// overscan; PAL sets show all 240.
+    #[must_use]
+    pub fn display_window(&self) -> (u32, u32, u32, u32) {
+        match self.region {
+            NesRegion::Ntsc => (0, 8, ppu::FB_WIDTH, ppu::FB_HEIGHT - 16),
+            NesRegion::Pal => (0, 0, ppu::FB_WIDTH, ppu::FB_HEIGHT),
+        }
+    }
+
     /// Reference to the CPU.
     #[must_use]
     pub fn cpu(&self) -> &Mos6502 {
@@ -184,14 +248,23 @@ impl Nes {
 
     /// Press a button on controller 1 immediately.
     pub fn press_button(&mut self, button: NesButton) {
+        self.record_button(button, true);
         self.bus.controller1.set_button(button.bit(), true);
     }
 
     /// Release a button on controller 1.
     pub fn release_button(&mut self, button: NesButton) {
+        self.record_button(button, false);
         self.bus.controller1.set_button(button.bit(), false);
     }
 
+    /// Log a controller 1 change to the movie being recorded.
+    fn record_button(&mut self, button: NesButton, pressed: bool) {
+        if let Some(movie) = &mut self.movie {
+            movie.push_event(self.frame_count, button.name(), pressed);
+        }
+    }
+
     /// Press a button on controller 2 immediately.
     pub fn press_button_p2(&mut self, button: NesButton) {
         self.bus.controller2.set_button(button.bit(), true);
@@ -204,6 +277,9 @@ impl Nes {
 
     /// Release all buttons on both controllers.
     pub fn release_all_buttons(&mut self) {
+        for button in NesButton::ALL {
+            self.record_button(button, false);
+        }
         for bit in 0..8 {
             self.bus.controller1.set_button(bit, false);
             self.bus.controller2.set_button(bit, false);
@@ -285,9 +361,184 @@ impl Nes {
             return Err("Mapper does not support PRG RAM".to_string());
         }
         self.bus.cartridge.set_prg_ram(data);
+        if let Some(movie) = &mut self.movie {
+            movie.add_media("battery", self.frame_count, data);
+        }
         Ok(())
     }
 
+    /// Serialise the complete machine state, including cartridge RAM.
+    ///
+    /// The scripted input queue is host-side and not included.
+    #[must_use]
+    pub fn save_state(&self) -> Vec<u8> {
+        let mut w = StateWriter::with_header(STATE_TAG);
+        w.write_u64(self.master_clock);
+        w.write_u64(self.frame_count);
+        w.write_u16(self.dma_cycles_remaining);
+        w.write_u16(self.dma_addr);
+        w.write_u8(self.dma_read_data);
+        w.write_bool(self.dma_odd_cycle);
+        w.write_u8(self.dmc_dma_cycles);
+        self.cpu.save_state(&mut w);
+        self.bus.save_state(&mut w);
+        w.into_bytes()
+    }
+
+    /// Restore a state produced by [`Nes::save_state`].
+    ///
+    /// The machine must be the same region with the same cartridge inserted.
+    /// On error the machine is left partially restored and should be reset.
+    pub fn load_state(&mut self, data: &[u8]) -> Result<(), StateError> {
+        let mut r = StateReader::with_header(data, STATE_TAG)?;
+        self.master_clock = r.read_u64()?;
+        self.frame_count = r.read_u64()?;
+        self.dma_cycles_remaining = r.read_u16()?;
+        self.dma_addr = r.read_u16()?;
+        self.dma_read_data = r.read_u8()?;
+        self.dma_odd_cycle = r.read_bool()?;
+        self.dmc_dma_cycles = r.read_u8()?;
+        self.cpu.load_state(&mut r)?;
+        self.bus.load_state(&mut r)?;
+        r.finish()
+    }
+
+    /// Attach an instruction trace recorder (or detach with `None`).
+    /// Returns the one it replaces.
+    pub fn set_tracer(&mut self, tracer: Option<Tracer>) -> Option<Tracer> {
+        std::mem::replace(&mut self.tracer, tracer)
+    }
+
+    #[must_use]
+    pub fn tracer(&self) -> Option<&Tracer> {
+        self.tracer.as_ref()
+    }
+
+    pub fn tracer_mut(&mut self) -> Option<&mut Tracer> {
+        self.tracer.as_mut()
+    }
+
+    /// One CPU cycle, logging bus cycles for the debugger and the
+    /// tracer when either wants them.
+    fn tick_cpu(&mut self) {
+        let mark = self.bus_log.as_ref().map_or(0, Vec::len);
+        if let Some(log) = trace::cpu_bus_log(&mut self.bus_log, &mut self.tracer) {
+            self.cpu.tick(&mut LoggingBus::new(&mut self.bus, log));
+        } else {
+            self.cpu.tick(&mut self.bus);
+        }
+
+        if let Some(mut tracer) = self.tracer.take() {
+            let accesses = self.bus_log.as_deref().map_or(&[][..], |log| &log[mark..]);
+            tracer.observe(&*self, accesses, |pc| {
+                emu_disasm::mos6502::disassemble(|addr| self.bus.peek(addr), pc).bytes
+            });
+            self.tracer = Some(tracer);
+        }
+    }
+
+    /// Start recording an input movie.
+    ///
+    /// `config` must be the configuration this machine was built from.
+    /// Recording from frame 0 gives a power-on movie, otherwise the current
+    /// state is embedded. Controller 1 is recorded, both live presses and
+    /// queued events; the other ports and the Zapper are not.
+    pub fn start_recording(&mut self, config: &NesConfig) {
+        let mut movie = movie_header(config);
+        movie.start_frame = self.frame_count;
+        if self.frame_count != 0 {
+            movie.start_state = Some(self.save_state());
+        }
+        self.movie = Some(movie);
+    }
+
+    /// Stop recording and return the movie.
+    pub fn stop_recording(&mut self) -> Option<Movie> {
+        self.movie.take()
+    }
+
+    /// Whether an input movie is being recorded.
+    #[must_use]
+    pub fn is_recording(&self) -> bool {
+        self.movie.is_some()
+    }
+
+    /// Set up playback of a movie.
+    ///
+    /// Checks the header against `config`, restores the start snapshot (or
+    /// requires a machine that has not run yet) and queues every event.
+    /// `media` holds (slot, file) pairs for the save RAM loaded while
+    /// recording (slot `"battery"`); each is checked and loaded again at
+    /// its recorded frame. Run frames as normal afterwards, or use
+    /// [`Movie::verify`].
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the movie was recorded with another cartridge,
+    /// region or save RAM, names an unknown button, or its snapshot cannot
+    /// be loaded.
+    pub fn play_movie(
+        &mut self,
+        movie: &Movie,
+        config: &NesConfig,
+        media: &[(&str, &[u8])],
+    ) -> Result<(), MovieError> {
+        let header = movie_header(config);
+        movie.check_header(&header)?;
+        let loads = movie.media_loads(&header, media)?;
+        let events = movie
+            .events
+            .iter()
+            .map(|e| {
+                let button = NesButton::from_name(&e.input)
+                    .ok_or_else(|| MovieError::UnknownInput(e.input.clone()))?;
+                Ok(InputEvent {
+                    frame: e.frame,
+                    button,
+                    pressed: e.pressed,
+                })
+            })
+            .collect::<Result<Vec<_>, MovieError>>()?;
+        match &movie.start_state {
+            Some(state) => self.load_state(state)?,
+            None if self.frame_count != 0 => {
+                return Err(MovieError::NotAtStart {
+                    frame: self.frame_count,
+                });
+            }
+            None => {}
+        }
+        for event in events {
+            self.input_queue.push(event);
+        }
+        self.movie_media = loads
+            .into_iter()
+            .map(|(frame, index)| {
+                let (slot, data) = media[index];
+                (frame, slot.to_string(), data.to_vec())
+            })
+            .collect();
+        Ok(())
+    }
+
+    /// Load the movie media due at the start of this frame.
+    fn load_movie_media(&mut self) {
+        if self.movie_media.is_empty() {
+            return;
+        }
+        let frame = self.frame_count;
+        let (due, later) = std::mem::take(&mut self.movie_media)
+            .into_iter()
+            .partition::<Vec<_>, _>(|(at, _, _)| *at <= frame);
+        self.movie_media = later;
+        for (_, slot, data) in due {
+            // The only slot is save RAM, which loaded when it was recorded.
+            if slot == "battery" {
+                let _ = self.load_battery(&data);
+            }
+        }
+    }
+
     /// Get controller 1 reference.
     #[must_use]
     pub fn controller1(&self) -> &Controller {
@@ -296,11 +547,28 @@ impl Nes {
 
     /// Take the APU audio output buffer (drains it).
     ///
-    /// Returns mono f32 samples in the range -1.0 to 1.0, at 48 kHz.
+    /// Returns mono f32 samples in the range -1.0 to 1.0, at the output
+    /// rate.
     pub fn take_audio_buffer(&mut self) -> Vec<f32> {
         self.bus.apu.take_buffer()
     }
 
+    /// Output sample rate of `take_audio_buffer`.
+    #[must_use]
+    pub fn audio_sample_rate(&self) -> u32 {
+        self.bus.apu.sample_rate()
+    }
+
+    /// Change the output sample rate (48 kHz by default).
+    pub fn set_audio_sample_rate(&mut self, rate: u32) {
+        self.bus.apu.set_sample_rate(rate);
+    }
+
+    /// Stretch the output rate for dynamic rate control.
+    pub fn set_audio_rate_adjust(&mut self, ratio: f64) {
+        self.bus.apu.set_rate_adjust(ratio);
+    }
+
     /// Number of audio samples pending in the APU buffer.
     #[must_use]
     pub fn audio_buffer_len(&self) -> usize {
@@ -334,9 +602,17 @@ impl Nes {
     }
 }
 
+/// Movie header describing a machine built from `config`.
+fn movie_header(config: &NesConfig) -> Movie {
+    Movie::new(STATE_TAG)
+        .with_config("region", format!("{:?}", config.region))
+        .with_media("cartridge", &config.rom_data)
+}
+
 impl Tickable for Nes {
     fn tick(&mut self) {
         self.master_clock += 1;
+        self.profiler.start();
 
         // PPU: every N crystal ticks (NTSC=4, PAL=5)
         if self.master_clock.is_multiple_of(self.region.ppu_divisor()) {
@@ -348,6 +624,7 @@ impl Tickable for Nes {
             if self.bus.ppu.take_nmi() {
                 self.cpu.nmi();
             }
+            self.profiler.lap(0);
         }
 
         // CPU: every N crystal ticks (NTSC=12, PAL=16)
@@ -399,15 +676,18 @@ impl Tickable for Nes {
             } else if self.dma_cycles_remaining > 0 {
                 self.tick_dma();
             } else {
-                self.cpu.tick(&mut self.bus);
+                self.tick_cpu();
             }
+            self.profiler.lap(1);
 
             // Expansion audio from cartridge mapper (Sunsoft 5B, VRC6, etc.)
             self.bus.cartridge.tick_audio();
             self.bus.apu.expansion_audio = self.bus.cartridge.audio_output();
+            self.profiler.lap(2);
 
             // APU ticks at CPU rate
             self.bus.apu.tick();
+            self.profiler.lap(3);
 
             // APU / mapper IRQ → CPU (level-sensitive)
             if self.bus.apu.irq_pending() || self.bus.cartridge.irq_pending() {
@@ -484,6 +764,31 @@ impl Observable for Nes {
     }
 }
 
+impl Debuggable for Nes {
+    fn instruction_boundary(&self) -> Option<u32> {
+        self.cpu
+            .is_instruction_complete()
+            .then_some(u32::from(self.cpu.regs.pc))
+    }
+
+    fn raster_position(&self) -> (u32, u32) {
+        (
+            u32::from(self.bus.ppu.scanline()),
+            u32::from(self.bus.ppu.dot()),
+        )
+    }
+
+    fn set_bus_logging(&mut self, enabled: bool) {
+        self.bus_log = enabled.then(Vec::new);
+    }
+
+    fn drain_bus_accesses(&mut self, out: &mut Vec<BusAccess>) {
+        if let Some(log) = &mut self.bus_log {
+            out.append(log);
+        }
+    }
+}
+
 impl Machine for Nes {
     fn run_frame(&mut self) {
         let _ = self.run_frame();
@@ -502,16 +807,94 @@ impl Machine for Nes {
     }
 
     fn take_audio_buffer(&mut self) -> Vec<AudioFrame> {
-        self.take_audio_buffer().into_iter().map(|s| [s, s]).collect()
+        self.take_audio_buffer()
+            .into_iter()
+            .map(|s| [s, s])
+            .collect()
+    }
+
+    fn audio_sample_rate(&self) -> u32 {
+        self.audio_sample_rate()
+    }
+
+    fn set_audio_sample_rate(&mut self, rate: u32) -> bool {
+        self.set_audio_sample_rate(rate);
+        true
+    }
+
+    fn set_audio_rate_adjust(&mut self, ratio: f64) {
+        self.set_audio_rate_adjust(ratio);
+    }
+
+    fn audio_channels(&self) -> Vec<&'static str> {
+        ricoh_apu_2a03::Apu::CHANNELS.to_vec()
+    }
+
+    fn set_channel_mute(&mut self, mask: u32) {
+        self.bus.apu.set_channel_mute(mask as u8);
+    }
+
+    fn channel_mute(&self) -> u32 {
+        u32::from(self.bus.apu.channel_mute())
+    }
+
+    fn set_stem_capture(&mut self, enabled: bool) {
+        self.bus.apu.set_stems(enabled);
+    }
+
+    fn take_audio_stems(&mut self) -> Vec<Vec<f32>> {
+        self.bus.apu.take_stems()
     }
 
     fn frame_count(&self) -> u64 {
         self.frame_count()
     }
 
+    fn cpu_clock_hz(&self) -> f64 {
+        f64::from(self.region.cpu_hz())
+    }
+
+    #[allow(clippy::cast_precision_loss)]
+    fn frame_rate_hz(&self) -> f64 {
+        self.region.crystal_hz() as f64 / self.ticks_per_frame as f64
+    }
+
+    fn set_chip_profiling(&mut self, enabled: bool) -> bool {
+        self.profiler.set_enabled(enabled);
+        true
+    }
+
+    fn chip_times(&self) -> Vec<(&'static str, Duration)> {
+        self.profiler.times()
+    }
+
     fn reset(&mut self) {
         self.cpu_mut().reset();
     }
+
+    fn save_state(&self) -> Result<Vec<u8>, StateError> {
+        Ok(self.save_state())
+    }
+
+    fn load_state(&mut self, data: &[u8]) -> Result<(), StateError> {
+        self.load_state(data)
+    }
+}
+
+impl NetplayInput for Nes {
+    fn netplay_buttons(&self) -> &'static [&'static str] {
+        &["A", "B", "Select", "Start", "Up", "Down", "Left", "Right"]
+    }
+
+    /// Players 0 and 1 are controllers 1 and 2.
+    fn set_player_input(&mut self, player: usize, buttons: u16) {
+        let buttons = buttons as u8;
+        match player {
+            0 => self.bus.controller1.set_buttons(buttons),
+            1 => self.bus.controller2.set_buttons(buttons),
+            _ => {}
+        }
+    }
 }
 
 #[cfg(test)]
@@ -547,6 +930,164 @@ mod tests {
         assert_eq!(ticks, 341 * 262 * 4);
     }
 
+    /// An iNES image whose program copies the A button to the backdrop
+    /// colour, so every press shows up in the framebuffer.
+    fn button_backdrop_config() -> NesConfig {
+        let mut rom = vec![0u8; 16 + 32768 + 8192];
+        rom[0..4].copy_from_slice(b"NES\x1A");
+        rom[4] = 2;
+        rom[5] = 1;
+        // loop: LDA #1; STA $4016; LDA #0; STA $4016; LDA $4016; AND #1; TAX
+        //       LDA #$3F; STA $2006; LDA #0; STA $2006; STX $2007; JMP loop
+        let code = [
+            0xA9, 0x01, 0x8D, 0x16, 0x40, 0xA9, 0x00, 0x8D, 0x16, 0x40, 0xAD, 0x16, 0x40, 0x29,
+            0x01, 0xAA, 0xA9, 0x3F, 0x8D, 0x06, 0x20, 0xA9, 0x00, 0x8D, 0x06, 0x20, 0x8E, 0x07,
+            0x20, 0x4C, 0x00, 0x80,
+        ];
+        rom[16..16 + code.len()].copy_from_slice(&code);
+        rom[16 + 0x7FFC] = 0x00;
+        rom[16 + 0x7FFD] = 0x80;
+        NesConfig {
+            rom_data: rom,
+            region: NesRegion::Ntsc,
+        }
+    }
+
+    #[test]
+    fn movie_replays_controller_input() {
+        let config = button_backdrop_config();
+        let mut nes = Nes::new(&config).expect("rom");
+        nes.start_recording(&config);
+        for frame in 0..20 {
+            match frame {
+                2 => nes.press_button(NesButton::A),
+                5 => nes.release_all_buttons(),
+                6 => nes.input_queue().enqueue_button(NesButton::A, 10, 3),
+                _ => {}
+            }
+            nes.run_frame();
+        }
+        let movie = nes.stop_recording().expect("recording");
+        assert!(movie.start_state.is_none());
+        assert_eq!(movie.frame_hashes.len(), 20);
+
+        let mut replay = Nes::new(&config).expect("rom");
+        replay.play_movie(&movie, &config, &[]).expect("play");
+        assert_eq!(movie.verify(&mut replay), None);
+
+        // Dropping the live press makes frame 3 the first to differ.
+        let mut edited = movie.clone();
+        edited.events.remove(0);
+        let mut replay = Nes::new(&config).expect("rom");
+        replay.play_movie(&edited, &config, &[]).expect("play");
+        assert_eq!(edited.verify(&mut replay).map(|d| d.frame), Some(3));
+    }
+
+    #[test]
+    fn movie_save_ram_must_match() {
+        let config = button_backdrop_config();
+        let mut nes = Nes::new(&config).expect("rom");
+        nes.start_recording(&config);
+        nes.run_frame();
+        let mut movie = nes.stop_recording().expect("recording");
+        movie.add_media("battery", 1, &[0xAA; 8]);
+
+        let mismatch = Err(MovieError::MediaMismatch {
+            slot: "battery".into(),
+        });
+        let mut replay = Nes::new(&config).expect("rom");
+        assert_eq!(replay.play_movie(&movie, &config, &[]), mismatch);
+        let other: &[u8] = &[0x55; 8];
+        assert_eq!(
+            replay.play_movie(&movie, &config, &[("battery", other)]),
+            mismatch
+        );
+        let save: &[u8] = &[0xAA; 8];
+        assert_eq!(
+            replay.play_movie(&movie, &config, &[("battery", save)]),
+            Ok(())
+        );
+    }
+
+    /// Reads both controllers in a loop and folds them into $02.
+    fn two_pad_config() -> NesConfig {
+        let mut config = button_backdrop_config();
+        // loop: LDA #1; STA $4016; LDA #0; STA $4016; LDX #8
+        // read: LDA $4016; LSR A; ROL $00; LDA $4017; LSR A; ROL $01; DEX; BNE read
+        //       LDA $02; ASL A; ADC $00; EOR $01; STA $02; JMP loop
+        let code = [
+            0xA9, 0x01, 0x8D, 0x16, 0x40, 0xA9, 0x00, 0x8D, 0x16, 0x40, 0xA2, 0x08, 0xAD, 0x16,
+            0x40, 0x4A, 0x26, 0x00, 0xAD, 0x17, 0x40, 0x4A, 0x26, 0x01, 0xCA, 0xD0, 0xF1, 0xA5,
+            0x02, 0x0A, 0x65, 0x00, 0x45, 0x01, 0x85, 0x02, 0x4C, 0x00, 0x80,
+        ];
+        config.rom_data[16..16 + code.len()].copy_from_slice(&code);
+        config
+    }
+
+    #[test]
+    fn netplay_over_loopback_matches_offline_play() {
+        use emu_core::netplay::{Session, SessionConfig};
+        use std::net::UdpSocket;
+
+        const FRAMES: u64 = 30;
+        fn pad(player: usize, frame: u64) -> u16 {
+            [0x01, 0x90, 0x41, 0x00, 0x22][(frame as usize / (3 + player)) % 5] << player
+        }
+
+        let config = two_pad_config();
+        let mut nes = [
+            Nes::new(&config).expect("rom"),
+            Nes::new(&config).expect("rom"),
+        ];
+        let sockets = [0, 1].map(|_| UdpSocket::bind("127.0.0.1:0").expect("bind"));
+        let addrs = [0, 1].map(|i| sockets[i].local_addr().expect("addr"));
+        let mut sessions = Vec::new();
+        for (player, socket) in sockets.into_iter().enumerate() {
+            let session_config = SessionConfig::new(player);
+            sessions.push(
+                Session::new(socket, addrs[1 - player], session_config, &nes[player])
+                    .expect("session"),
+            );
+        }
+
+        for _ in 0..10_000 {
+            for (player, steps) in [(0, 2), (1, 1)] {
+                let (session, machine) = (&mut sessions[player], &mut nes[player]);
+                for _ in 0..steps {
+                    if session.frame() < FRAMES {
+                        let input = pad(player, session.frame());
+                        session.advance_frame(machine, input).expect("advance");
+                    } else {
+                        session.poll(machine).expect("poll");
+                    }
+                    drop(machine.take_audio_buffer());
+                }
+            }
+            if sessions
+                .iter()
+                .all(|s| s.frame() == FRAMES && s.confirmed_frame() >= FRAMES)
+            {
+                break;
+            }
+        }
+        assert!(sessions[0].rollbacks() > 0);
+
+        let mut offline = Nes::new(&config).expect("rom");
+        for frame in 0..FRAMES {
+            for player in 0..2 {
+                let input = frame
+                    .checked_sub(2)
+                    .map_or(0, |read_at| pad(player, read_at));
+                offline.set_player_input(player, input);
+            }
+            offline.run_frame();
+            drop(offline.take_audio_buffer());
+        }
+        assert_ne!(offline.bus().ram[2], 0);
+        assert!(offline.save_state() == nes[0].save_state());
+        assert!(offline.save_state() == nes[1].save_state());
+    }
+
     fn make_pal_nes() -> Nes {
         let mut prg = vec![0xEA; 32768];
         prg[0x7FFC] = 0x00;
@@ -556,6 +1097,57 @@ mod tests {
         Nes::from_mapper(mapper, NesRegion::Pal)
     }
 
+    /// NROM with CHR RAM running a loop that enables rendering, plays a
+    /// pulse tone, and streams bytes into CHR RAM and work RAM.
+    fn make_busy_nes(region: NesRegion) -> Nes {
+        let mut prg = vec![0xEA; 32768];
+        // LDA #$1E; STA $2001; LDA #$0F; STA $4015; LDA #$BF; STA $4000;
+        // LDA #$40; STA $4002; LDA #$08; STA $4003; LDX #0
+        // loop: INX; STX $2007; INC $0300,X; JMP loop
+        let code = [
+            0xA9, 0x1E, 0x8D, 0x01, 0x20, 0xA9, 0x0F, 0x8D, 0x15, 0x40, 0xA9, 0xBF, 0x8D, 0x00,
+            0x40, 0xA9, 0x40, 0x8D, 0x02, 0x40, 0xA9, 0x08, 0x8D, 0x03, 0x40, 0xA2, 0x00, 0xE8,
+            0x8E, 0x07, 0x20, 0xFE, 0x00, 0x03, 0x4C, 0x1B, 0x80,
+        ];
+        prg[..code.len()].copy_from_slice(&code);
+        prg[0x7FFC] = 0x00;
+        prg[0x7FFD] = 0x80;
+        let mapper = Box::new(Nrom::new(prg, Vec::new(), Mirroring::Vertical));
+        Nes::from_mapper(mapper, region)
+    }
+
+    #[test]
+    fn state_restored_mid_frame_continues_identically() {
+        let mut nes = make_busy_nes(NesRegion::Ntsc);
+        nes.run_frame();
+        for _ in 0..54_321 {
+            nes.tick();
+        }
+        let state = Machine::save_state(&nes).expect("save");
+
+        let mut restored = make_busy_nes(NesRegion::Ntsc);
+        Machine::load_state(&mut restored, &state).expect("load");
+
+        for _ in 0..3 {
+            nes.run_frame();
+            restored.run_frame();
+        }
+        assert_eq!(nes.framebuffer(), restored.framebuffer());
+        assert_eq!(nes.cpu().regs, restored.cpu().regs);
+        assert_eq!(nes.master_clock(), restored.master_clock());
+        assert_eq!(nes.take_audio_buffer(), restored.take_audio_buffer());
+    }
+
+    #[test]
+    fn state_for_other_region_is_rejected() {
+        let state = make_busy_nes(NesRegion::Ntsc).save_state();
+        let mut pal = make_busy_nes(NesRegion::Pal);
+        assert!(matches!(
+            pal.load_state(&state),
+            Err(StateError::Invalid(_))
+        ));
+    }
+
     #[test]
     fn pal_frame_tick_count() {
         let mut nes = make_pal_nes();
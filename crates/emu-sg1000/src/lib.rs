@@ -11,7 +11,16 @@
 
 #![allow(clippy::cast_possible_truncation)]
 
-use emu_core::{AudioFrame, Bus, Cpu, Machine, ReadResult};
+#[cfg(feature = "native")]
+pub mod mcp;
+
+use std::time::Duration;
+
+use emu_core::bench::ChipProfiler;
+use emu_core::{
+    AudioFrame, Bus, Cpu, Machine, Observable, ReadResult, RegisterLog, SaveState, StateError,
+    StateReader, StateWriter, Value,
+};
 use ti_sn76489::Sn76489;
 use ti_tms9918::{Tms9918, VdpRegion};
 use zilog_z80::Z80;
@@ -30,6 +39,12 @@ const PAL_TICKS_PER_FRAME: u64 = CPU_TSTATES_PER_SCANLINE * PAL_SCANLINES_PER_FR
 const NTSC_PSG_CLOCK_HZ: u32 = 3_579_545;
 const PAL_PSG_CLOCK_HZ: u32 = 3_546_893;
 
+/// Machine name in save-state headers.
+const STATE_TAG: &str = "sg1000";
+
+/// Chips timed by the profiler, in the order the tick loop laps them.
+const CHIPS: &[&str] = &["zilog-z80", "ti-tms9918", "ti-sn76489"];
+
 /// SG-1000 system region.
 #[derive(Debug, Clone, Copy, PartialEq, Eq)]
 pub enum Sg1000Region {
@@ -114,12 +129,12 @@ impl Sg1000Bus {
             pause_pressed: false,
         }
     }
-}
 
-impl Bus for Sg1000Bus {
-    fn read(&mut self, addr: u32) -> ReadResult {
-        let addr = addr as u16;
-        let data = match addr {
+    /// Read memory as the CPU sees it. Memory reads have no side effects
+    /// on this bus, so debuggers can call this freely.
+    #[must_use]
+    pub fn peek(&self, addr: u16) -> u8 {
+        match addr {
             // Cartridge ROM: $0000-$BFFF
             0x0000..=0xBFFF => {
                 let idx = addr as usize;
@@ -131,8 +146,13 @@ impl Bus for Sg1000Bus {
             }
             // RAM: $C000-$FFFF (1 KB mirrored)
             0xC000..=0xFFFF => self.ram[(addr & 0x03FF) as usize],
-        };
-        ReadResult { data, wait: 0 }
+        }
+    }
+}
+
+impl Bus for Sg1000Bus {
+    fn read(&mut self, addr: u32) -> ReadResult {
+        ReadResult { data: self.peek(addr as u16), wait: 0 }
     }
 
     fn write(&mut self, addr: u32, data: u8) -> u8 {
@@ -173,6 +193,24 @@ impl Bus for Sg1000Bus {
     }
 }
 
+/// Controller state is host input, so only the pause latch is saved.
+impl SaveState for Sg1000Bus {
+    fn save_state(&self, w: &mut StateWriter) {
+        w.write_bytes(&self.ram);
+        self.vdp.save_state(w);
+        self.psg.save_state(w);
+        w.write_bool(self.pause_pressed);
+    }
+
+    fn load_state(&mut self, r: &mut StateReader<'_>) -> Result<(), StateError> {
+        r.read_bytes_into(&mut self.ram)?;
+        self.vdp.load_state(r)?;
+        self.psg.load_state(r)?;
+        self.pause_pressed = r.read_bool()?;
+        Ok(())
+    }
+}
+
 /// SG-1000 system.
 pub struct Sg1000 {
     cpu: Z80,
@@ -181,6 +219,8 @@ pub struct Sg1000 {
     ticks_per_frame: u64,
     vdp_phase: u8,
     frame_count: u64,
+    /// Per-chip tick timing, while benchmarking.
+    profiler: ChipProfiler,
 }
 
 impl Sg1000 {
@@ -203,6 +243,7 @@ impl Sg1000 {
             ticks_per_frame,
             vdp_phase: 0,
             frame_count: 0,
+            profiler: ChipProfiler::new(CHIPS),
         }
     }
 
@@ -211,8 +252,11 @@ impl Sg1000 {
         let target = self.master_clock + self.ticks_per_frame;
 
         while self.master_clock < target {
+            self.profiler.start();
+
             // One Z80 T-state.
             self.cpu.tick(&mut self.bus);
+            self.profiler.lap(0);
 
             // The TMS9918 dot clock runs at 3/2 the Z80 T-state rate.
             self.vdp_phase = self
@@ -222,9 +266,11 @@ impl Sg1000 {
                 self.bus.vdp.tick();
                 self.vdp_phase -= VDP_DOT_PHASE_DENOMINATOR;
             }
+            self.profiler.lap(1);
 
             // PSG input clock matches the Z80 clock on SG-1000 hardware.
             self.bus.psg.tick();
+            self.profiler.lap(2);
 
             // VDP interrupt → Z80 INT
             if self.bus.vdp.interrupt {
@@ -249,7 +295,7 @@ impl Sg1000 {
         self.bus.vdp.framebuffer()
     }
 
-    /// Take audio samples (mono f32, 48 kHz).
+    /// Take audio samples (mono f32 at the output rate, 48 kHz by default).
     pub fn take_audio_buffer(&mut self) -> Vec<f32> {
         self.bus.psg.take_buffer()
     }
@@ -280,6 +326,59 @@ impl Sg1000 {
     pub fn cpu_mut(&mut self) -> &mut Z80 {
         &mut self.cpu
     }
+
+    /// Serialise the complete machine state.
+    ///
+    /// The cartridge ROM is not included.
+    #[must_use]
+    pub fn save_state(&self) -> Vec<u8> {
+        let mut w = StateWriter::with_header(STATE_TAG);
+        w.write_u64(self.master_clock);
+        w.write_u64(self.frame_count);
+        w.write_u8(self.vdp_phase);
+        self.cpu.save_state(&mut w);
+        self.bus.save_state(&mut w);
+        w.into_bytes()
+    }
+
+    /// Restore a state produced by [`Sg1000::save_state`].
+    ///
+    /// The machine must be the same region with the same cartridge. On
+    /// error the machine is left partially restored and should be reset.
+    pub fn load_state(&mut self, data: &[u8]) -> Result<(), StateError> {
+        let mut r = StateReader::with_header(data, STATE_TAG)?;
+        self.master_clock = r.read_u64()?;
+        self.frame_count = r.read_u64()?;
+        self.vdp_phase = r.read_u8()?;
+        self.cpu.load_state(&mut r)?;
+        self.bus.load_state(&mut r)?;
+        r.finish()
+    }
+}
+
+impl Observable for Sg1000 {
+    fn query(&self, path: &str) -> Option<Value> {
+        if let Some(rest) = path.strip_prefix("cpu.") {
+            self.cpu.query(rest)
+        } else if path == "vdp.scanline" {
+            Some(self.bus.vdp.scanline().into())
+        } else {
+            match path {
+                "master_clock" => Some(self.master_clock.into()),
+                "frame_count" => Some(self.frame_count.into()),
+                _ => self.cpu.query(path),
+            }
+        }
+    }
+
+    fn query_paths(&self) -> &'static [&'static str] {
+        &[
+            "cpu.<z80_paths>",
+            "vdp.scanline",
+            "master_clock",
+            "frame_count",
+        ]
+    }
 }
 
 impl Machine for Sg1000 {
@@ -306,13 +405,87 @@ impl Machine for Sg1000 {
             .collect()
     }
 
+    fn audio_sample_rate(&self) -> u32 {
+        self.bus.psg.sample_rate()
+    }
+
+    fn set_audio_sample_rate(&mut self, rate: u32) -> bool {
+        self.bus.psg.set_sample_rate(rate);
+        true
+    }
+
+    fn set_audio_rate_adjust(&mut self, ratio: f64) {
+        self.bus.psg.set_rate_adjust(ratio);
+    }
+
+    fn audio_channels(&self) -> Vec<&'static str> {
+        Sn76489::CHANNELS.to_vec()
+    }
+
+    fn set_channel_mute(&mut self, mask: u32) {
+        self.bus.psg.set_channel_mute(mask as u8);
+    }
+
+    fn channel_mute(&self) -> u32 {
+        u32::from(self.bus.psg.channel_mute())
+    }
+
+    fn set_stem_capture(&mut self, enabled: bool) {
+        self.bus.psg.set_stems(enabled);
+    }
+
+    fn take_audio_stems(&mut self) -> Vec<Vec<f32>> {
+        self.bus.psg.take_stems()
+    }
+
+    fn start_register_log(&mut self) -> bool {
+        self.bus.psg.start_register_log();
+        true
+    }
+
+    fn stop_register_log(&mut self) -> Option<RegisterLog> {
+        let frame = self.ticks_per_frame;
+        self.bus.psg.stop_register_log().map(|log| log.with_frame_cycles(frame))
+    }
+
     fn frame_count(&self) -> u64 {
         self.frame_count()
     }
 
+    /// The Z80 shares the PSG's clock.
+    fn cpu_clock_hz(&self) -> f64 {
+        if self.ticks_per_frame == NTSC_TICKS_PER_FRAME {
+            f64::from(NTSC_PSG_CLOCK_HZ)
+        } else {
+            f64::from(PAL_PSG_CLOCK_HZ)
+        }
+    }
+
+    #[allow(clippy::cast_precision_loss)]
+    fn frame_rate_hz(&self) -> f64 {
+        self.cpu_clock_hz() / self.ticks_per_frame as f64
+    }
+
+    fn set_chip_profiling(&mut self, enabled: bool) -> bool {
+        self.profiler.set_enabled(enabled);
+        true
+    }
+
+    fn chip_times(&self) -> Vec<(&'static str, Duration)> {
+        self.profiler.times()
+    }
+
     fn reset(&mut self) {
         self.cpu_mut().reset();
     }
+
+    fn save_state(&self) -> Result<Vec<u8>, StateError> {
+        Ok(self.save_state())
+    }
+
+    fn load_state(&mut self, data: &[u8]) -> Result<(), StateError> {
+        self.load_state(data)
+    }
 }
 
 #[cfg(test)]
@@ -363,6 +536,55 @@ mod tests {
         );
     }
 
+    /// DI; loop: LD A,($C000); INC A; LD ($C000),A; OUT ($7F),A; JR loop
+    fn counting_rom() -> Vec<u8> {
+        let mut rom = minimal_rom();
+        rom[..12].copy_from_slice(&[
+            0xF3, 0x3A, 0x00, 0xC0, 0x3C, 0x32, 0x00, 0xC0, 0xD3, 0x7F, 0x18, 0xF5,
+        ]);
+        rom
+    }
+
+    #[test]
+    fn save_state_round_trip_continues_identically() {
+        let mut sg = Sg1000::new(counting_rom(), Sg1000Region::Ntsc);
+        sg.run_frame();
+        sg.take_audio_buffer();
+        let state = sg.save_state();
+
+        let mut copy = Sg1000::new(counting_rom(), Sg1000Region::Ntsc);
+        copy.load_state(&state).expect("state loads");
+        sg.run_frame();
+        copy.run_frame();
+        assert_eq!(copy.bus.ram, sg.bus.ram);
+        assert_eq!(copy.take_audio_buffer(), sg.take_audio_buffer());
+        assert_eq!(copy.save_state(), sg.save_state());
+
+        let mut pal = Sg1000::new(counting_rom(), Sg1000Region::Pal);
+        assert!(pal.load_state(&state).is_err());
+    }
+
+    #[test]
+    fn rewind_steps_back_to_earlier_frames() {
+        let mut sg = Sg1000::new(counting_rom(), Sg1000Region::Ntsc);
+        let mut rewind = emu_core::RewindBuffer::new(16, 1);
+        let mut counters = Vec::new();
+        for _ in 0..5 {
+            sg.run_frame();
+            rewind.record(&sg).expect("SG-1000 saves state");
+            counters.push((sg.frame_count(), sg.bus.ram[0]));
+        }
+
+        // Hold rewind after one more frame: each step goes one frame back
+        sg.run_frame();
+        rewind.record(&sg).expect("SG-1000 saves state");
+        for &(frame, counter) in counters.iter().rev() {
+            assert!(rewind.step_back(&mut sg).expect("snapshot loads"));
+            assert_eq!((sg.frame_count(), sg.bus.ram[0]), (frame, counter));
+        }
+        assert!(!rewind.step_back(&mut sg).expect("nothing further back"));
+    }
+
     #[test]
     fn ram_read_write() {
         let mut bus = Sg1000Bus::new(minimal_rom(), Sg1000Region::Ntsc);
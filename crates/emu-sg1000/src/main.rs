@@ -7,7 +7,9 @@ use std::path::{Path, PathBuf};
 use std::process;
 use std::time::Duration;
 
+use emu_core::mcp::MachineMcp;
 use emu_core::runner::Runner;
+use emu_sg1000::mcp::McpServer;
 use emu_sg1000::{Sg1000, Sg1000Region};
 use winit::keyboard::KeyCode;
 
@@ -21,10 +23,13 @@ const FB_HEIGHT: u32 = 192;
 struct CliArgs {
     rom_path: Option<PathBuf>,
     headless: bool,
+    bench: bool,
     frames: u32,
     screenshot_path: Option<PathBuf>,
     mute: bool,
     region: Sg1000Region,
+    mcp: bool,
+    script_path: Option<PathBuf>,
 }
 
 fn parse_args() -> CliArgs {
@@ -32,10 +37,13 @@ fn parse_args() -> CliArgs {
     let mut cli = CliArgs {
         rom_path: None,
         headless: false,
+        bench: false,
         frames: 200,
         screenshot_path: None,
         mute: false,
         region: Sg1000Region::Ntsc,
+        mcp: false,
+        script_path: None,
     };
     let mut i = 1;
     while i < args.len() {
@@ -45,6 +53,7 @@ fn parse_args() -> CliArgs {
                 cli.rom_path = args.get(i).map(PathBuf::from);
             }
             "--headless" => cli.headless = true,
+            "--bench" => cli.bench = true,
             "--frames" => {
                 i += 1;
                 cli.frames = args.get(i).and_then(|s| s.parse().ok()).unwrap_or(200);
@@ -54,6 +63,11 @@ fn parse_args() -> CliArgs {
                 cli.screenshot_path = args.get(i).map(PathBuf::from);
             }
             "--mute" => cli.mute = true,
+            "--mcp" => cli.mcp = true,
+            "--script" => {
+                i += 1;
+                cli.script_path = args.get(i).map(PathBuf::from);
+            }
             "--region" => {
                 i += 1;
                 cli.region = match args.get(i).map(|s| s.as_str()) {
@@ -68,9 +82,12 @@ fn parse_args() -> CliArgs {
                 eprintln!("  --rom <file>         SG-1000 cartridge ROM (.sg, .bin)");
                 eprintln!("  --region <ntsc|pal>  Video region [default: ntsc]");
                 eprintln!("  --headless           Run without a window");
+                eprintln!("  --bench              Run --frames frames flat out and report speed");
                 eprintln!("  --frames <n>         Frames in headless mode [default: 200]");
                 eprintln!("  --screenshot <file>  Save PNG screenshot (headless)");
                 eprintln!("  --mute               Disable host audio playback (windowed)");
+                eprintln!("  --mcp                Run as MCP server (JSON-RPC over stdio)");
+                eprintln!("  --script <file>      Run a JSON script file (headless batch mode)");
                 process::exit(0);
             }
             other if other.starts_with('-') => {
@@ -87,6 +104,10 @@ fn parse_args() -> CliArgs {
     if cli.screenshot_path.is_some() {
         cli.headless = true;
     }
+    if cli.mcp && cli.script_path.is_some() {
+        eprintln!("--mcp and --script are mutually exclusive");
+        process::exit(1);
+    }
     cli
 }
 
@@ -134,15 +155,41 @@ fn load_rom(path: &PathBuf, region: Sg1000Region) -> Sg1000 {
     Sg1000::new(rom_data, region)
 }
 
+fn run_mcp(cli: &CliArgs) {
+    let system = if let Some(ref path) = cli.rom_path {
+        load_rom(path, cli.region)
+    } else {
+        Sg1000::new(stub_rom(), cli.region)
+    };
+    let mut server = McpServer::new(MachineMcp::new("emu-sg1000", system));
+    if let Some(ref path) = cli.script_path {
+        if let Err(e) = server.run_script(path) {
+            eprintln!("Script error: {e}");
+            process::exit(1);
+        }
+    } else {
+        server.run();
+    }
+}
+
 fn main() {
     let cli = parse_args();
 
-    if cli.headless {
+    if cli.mcp || cli.script_path.is_some() {
+        run_mcp(&cli);
+        return;
+    }
+
+    if cli.headless || cli.bench {
         let rom_path = cli.rom_path.as_ref().unwrap_or_else(|| {
             eprintln!("No ROM file specified. Use --rom <file>");
             process::exit(1);
         });
         let mut system = load_rom(rom_path, cli.region);
+        if cli.bench {
+            print!("{}", emu_core::bench::run(&mut system, cli.frames));
+            return;
+        }
         for _ in 0..cli.frames {
             system.run_frame();
         }
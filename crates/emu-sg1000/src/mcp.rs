@@ -0,0 +1,38 @@
+//! MCP (Model Context Protocol) server for the SG-1000 emulator.
+//!
+//! Serves the generic `MachineMcp` tools. Run with `--mcp` for MCP mode
+//! or `--script` for batch mode.
+
+use emu_core::Instruction;
+use emu_core::mcp::{self, MachineMcp, McpMachine};
+
+use crate::{NTSC_TICKS_PER_FRAME, Sg1000};
+
+pub type McpServer = mcp::McpServer<MachineMcp<Sg1000>>;
+
+impl McpMachine for Sg1000 {
+    fn frame_rate(&self) -> u32 {
+        if self.ticks_per_frame == NTSC_TICKS_PER_FRAME {
+            60
+        } else {
+            50
+        }
+    }
+
+    fn disassemble(&self, address: u32) -> Instruction {
+        emu_disasm::z80::disassemble(|addr| self.bus.peek(addr), address)
+    }
+
+    /// 1 KB of RAM mirrored across $C000-$FFFF.
+    fn peek(&self, address: u16) -> Option<u8> {
+        (address >= 0xC000).then(|| self.bus.ram[usize::from(address & 0x03FF)])
+    }
+
+    fn poke(&mut self, address: u16, value: u8) -> bool {
+        let is_ram = address >= 0xC000;
+        if is_ram {
+            self.bus.ram[usize::from(address & 0x03FF)] = value;
+        }
+        is_ram
+    }
+}
@@ -12,11 +12,26 @@
 
 #![allow(clippy::cast_possible_truncation)]
 
-use emu_core::{AudioFrame, Bus, Cpu, Machine, ReadResult};
+#[cfg(feature = "native")]
+pub mod mcp;
+
+use std::time::Duration;
+
+use emu_core::bench::ChipProfiler;
+use emu_core::{
+    AudioFrame, Bus, Cpu, Machine, NetplayInput, Observable, ReadResult, RegisterLog, SaveState,
+    StateError, StateReader, StateWriter, Value,
+};
 use sega_vdp::{SegaVdp, VdpRegion, VdpVariant};
 use ti_sn76489::Sn76489;
 use zilog_z80::Z80;
 
+/// Machine name in save-state headers.
+const STATE_TAG: &str = "sms";
+
+/// Chips timed by the profiler, in the order the tick loop laps them.
+const CHIPS: &[&str] = &["zilog-z80", "sega-vdp", "ti-sn76489"];
+
 /// System variant.
 #[derive(Debug, Clone, Copy, PartialEq, Eq)]
 pub enum SmsVariant {
@@ -83,12 +98,12 @@ impl SmsBus {
         let addr = bank as usize * 16384 + offset;
         self.cart_rom.get(addr).copied().unwrap_or(0xFF)
     }
-}
 
-impl Bus for SmsBus {
-    fn read(&mut self, addr: u32) -> ReadResult {
-        let addr = addr as u16;
-        let data = match addr {
+    /// Read memory as the CPU sees it. Memory reads have no side effects
+    /// on this bus, so debuggers can call this freely.
+    #[must_use]
+    pub fn peek(&self, addr: u16) -> u8 {
+        match addr {
             // Slot 0: first 1KB always visible, rest banked
             0x0000..=0x03FF => {
                 self.cart_rom.get(addr as usize).copied().unwrap_or(0xFF)
@@ -113,8 +128,13 @@ impl Bus for SmsBus {
             0xC000..=0xFFFF => {
                 self.ram[(addr & 0x1FFF) as usize]
             }
-        };
-        ReadResult::new(data)
+        }
+    }
+}
+
+impl Bus for SmsBus {
+    fn read(&mut self, addr: u32) -> ReadResult {
+        ReadResult::new(self.peek(addr as u16))
     }
 
     fn write(&mut self, addr: u32, value: u8) -> u8 {
@@ -176,6 +196,31 @@ impl Bus for SmsBus {
     }
 }
 
+impl SaveState for SmsBus {
+    fn save_state(&self, w: &mut StateWriter) {
+        w.write_bytes(&self.ram);
+        self.vdp.save_state(w);
+        self.psg.save_state(w);
+        w.write_bytes(&self.mapper_regs);
+        w.write_u8(self.port_dc);
+        w.write_u8(self.port_dd);
+        w.write_u8(self.gg_start);
+        w.write_bool(self.pause_pressed);
+    }
+
+    fn load_state(&mut self, r: &mut StateReader<'_>) -> Result<(), StateError> {
+        r.read_bytes_into(&mut self.ram)?;
+        self.vdp.load_state(r)?;
+        self.psg.load_state(r)?;
+        r.read_bytes_into(&mut self.mapper_regs)?;
+        self.port_dc = r.read_u8()?;
+        self.port_dd = r.read_u8()?;
+        self.gg_start = r.read_u8()?;
+        self.pause_pressed = r.read_bool()?;
+        Ok(())
+    }
+}
+
 /// SMS / Game Gear system.
 pub struct Sms {
     cpu: Z80,
@@ -184,6 +229,8 @@ pub struct Sms {
     ticks_per_frame: u64,
     frame_count: u64,
     variant: SmsVariant,
+    /// Per-chip tick timing, while benchmarking.
+    profiler: ChipProfiler,
 }
 
 impl Sms {
@@ -196,18 +243,30 @@ impl Sms {
         let bus = SmsBus::new(cart_rom, variant);
         let cpu = Z80::new();
 
-        Self { cpu, bus, master_clock: 0, ticks_per_frame, frame_count: 0, variant }
+        Self {
+            cpu,
+            bus,
+            master_clock: 0,
+            ticks_per_frame,
+            frame_count: 0,
+            variant,
+            profiler: ChipProfiler::new(CHIPS),
+        }
     }
 
     pub fn run_frame(&mut self) {
         let target = self.master_clock + self.ticks_per_frame;
         while self.master_clock < target {
+            self.profiler.start();
             self.cpu.tick(&mut self.bus);
+            self.profiler.lap(0);
 
             for _ in 0..3 {
                 self.bus.vdp.tick_scanline(); // Should be tick() for dot accuracy
             }
+            self.profiler.lap(1);
             self.bus.psg.tick();
+            self.profiler.lap(2);
 
             if self.bus.vdp.interrupt {
                 self.cpu.interrupt();
@@ -236,6 +295,54 @@ impl Sms {
 
     /// Trigger a pause button press (NMI).
     pub fn press_pause(&mut self) { self.bus.pause_pressed = true; }
+
+    /// Serialise the complete machine state.
+    ///
+    /// The cartridge ROM is not included.
+    #[must_use]
+    pub fn save_state(&self) -> Vec<u8> {
+        let mut w = StateWriter::with_header(STATE_TAG);
+        w.write_u64(self.master_clock);
+        w.write_u64(self.frame_count);
+        self.cpu.save_state(&mut w);
+        self.bus.save_state(&mut w);
+        w.into_bytes()
+    }
+
+    /// Restore a state produced by [`Sms::save_state`].
+    ///
+    /// The machine must be the same variant with the same cartridge. On
+    /// error the machine is left partially restored and should be reset.
+    pub fn load_state(&mut self, data: &[u8]) -> Result<(), StateError> {
+        let mut r = StateReader::with_header(data, STATE_TAG)?;
+        self.master_clock = r.read_u64()?;
+        self.frame_count = r.read_u64()?;
+        self.cpu.load_state(&mut r)?;
+        self.bus.load_state(&mut r)?;
+        r.finish()
+    }
+}
+
+impl Observable for Sms {
+    fn query(&self, path: &str) -> Option<Value> {
+        if let Some(rest) = path.strip_prefix("cpu.") {
+            self.cpu.query(rest)
+        } else {
+            match path {
+                "master_clock" => Some(self.master_clock.into()),
+                "frame_count" => Some(self.frame_count.into()),
+                _ => self.cpu.query(path),
+            }
+        }
+    }
+
+    fn query_paths(&self) -> &'static [&'static str] {
+        &[
+            "cpu.<z80_paths>",
+            "master_clock",
+            "frame_count",
+        ]
+    }
 }
 
 impl Machine for Sms {
@@ -259,13 +366,109 @@ impl Machine for Sms {
         self.take_audio_buffer().into_iter().map(|s| [s, s]).collect()
     }
 
+    fn audio_sample_rate(&self) -> u32 {
+        self.bus.psg.sample_rate()
+    }
+
+    fn set_audio_sample_rate(&mut self, rate: u32) -> bool {
+        self.bus.psg.set_sample_rate(rate);
+        true
+    }
+
+    fn set_audio_rate_adjust(&mut self, ratio: f64) {
+        self.bus.psg.set_rate_adjust(ratio);
+    }
+
+    fn audio_channels(&self) -> Vec<&'static str> {
+        Sn76489::CHANNELS.to_vec()
+    }
+
+    fn set_channel_mute(&mut self, mask: u32) {
+        self.bus.psg.set_channel_mute(mask as u8);
+    }
+
+    fn channel_mute(&self) -> u32 {
+        u32::from(self.bus.psg.channel_mute())
+    }
+
+    fn set_stem_capture(&mut self, enabled: bool) {
+        self.bus.psg.set_stems(enabled);
+    }
+
+    fn take_audio_stems(&mut self) -> Vec<Vec<f32>> {
+        self.bus.psg.take_stems()
+    }
+
+    fn start_register_log(&mut self) -> bool {
+        self.bus.psg.start_register_log();
+        true
+    }
+
+    fn stop_register_log(&mut self) -> Option<RegisterLog> {
+        let frame = self.ticks_per_frame;
+        self.bus.psg.stop_register_log().map(|log| log.with_frame_cycles(frame))
+    }
+
     fn frame_count(&self) -> u64 {
         self.frame_count()
     }
 
+    fn cpu_clock_hz(&self) -> f64 {
+        match self.variant {
+            SmsVariant::SmsNtsc | SmsVariant::GameGear => 3_579_545.0,
+            SmsVariant::SmsPal => 3_546_893.0,
+        }
+    }
+
+    fn frame_rate_hz(&self) -> f64 {
+        // 228 Z80 cycles per line; 262 lines NTSC, 313 PAL.
+        let lines = match self.variant {
+            SmsVariant::SmsNtsc | SmsVariant::GameGear => 262.0,
+            SmsVariant::SmsPal => 313.0,
+        };
+        self.cpu_clock_hz() / (228.0 * lines)
+    }
+
+    fn set_chip_profiling(&mut self, enabled: bool) -> bool {
+        self.profiler.set_enabled(enabled);
+        true
+    }
+
+    fn chip_times(&self) -> Vec<(&'static str, Duration)> {
+        self.profiler.times()
+    }
+
     fn reset(&mut self) {
         self.cpu_mut().reset();
     }
+
+    fn save_state(&self) -> Result<Vec<u8>, StateError> {
+        Ok(self.save_state())
+    }
+
+    fn load_state(&mut self, data: &[u8]) -> Result<(), StateError> {
+        self.load_state(data)
+    }
+}
+
+impl NetplayInput for Sms {
+    fn netplay_buttons(&self) -> &'static [&'static str] {
+        &["Up", "Down", "Left", "Right", "Button1", "Button2"]
+    }
+
+    /// Player 0 is port $DC bits 0-5. Player 1 is split: up and down are
+    /// $DC bits 6-7, the rest $DD bits 0-3.
+    fn set_player_input(&mut self, player: usize, buttons: u16) {
+        let lines = !(buttons as u8) & 0x3F;
+        match player {
+            0 => self.bus.port_dc = (self.bus.port_dc & 0xC0) | lines,
+            1 => {
+                self.bus.port_dc = (self.bus.port_dc & 0x3F) | (lines << 6);
+                self.bus.port_dd = (self.bus.port_dd & 0xF0) | (lines >> 2);
+            }
+            _ => {}
+        }
+    }
 }
 
 #[cfg(test)]
@@ -287,6 +490,72 @@ mod tests {
         assert_eq!(sms.frame_count(), 1);
     }
 
+    #[cfg(feature = "native")]
+    #[test]
+    fn mcp_poke_reaches_mirrored_ram() {
+        use emu_core::mcp::McpMachine;
+
+        let mut sms = Sms::new(minimal_rom(), SmsVariant::SmsPal);
+        assert_eq!(sms.frame_rate(), 50);
+        assert!(sms.poke(0xE001, 0x5A));
+        assert_eq!(sms.peek(0xC001), Some(0x5A));
+        assert_eq!(sms.peek(0x8000), None);
+        assert!(!sms.poke(0x0000, 0x5A));
+    }
+
+    #[test]
+    fn register_log_times_psg_writes_in_cpu_cycles() {
+        let mut rom = minimal_rom();
+        // DI; LD A,$9F; OUT ($7F),A; JR $
+        rom[..7].copy_from_slice(&[0xF3, 0x3E, 0x9F, 0xD3, 0x7F, 0x18, 0xFE]);
+        let mut sms = Sms::new(rom, SmsVariant::SmsNtsc);
+        assert!(sms.start_register_log());
+        sms.run_frame();
+        let log = sms.stop_register_log().expect("log was running");
+
+        assert_eq!(log.frame_cycles(), sms.ticks_per_frame);
+        assert_eq!(log.cycles(), sms.ticks_per_frame);
+        let out = log.writes().last().expect("OUT was logged");
+        assert_eq!(out.value, 0x9F);
+        // DI (4) + LD A,n (7) + OUT (n),A (11): the write lands in the last T-states.
+        assert!((15..22).contains(&out.cycle), "OUT logged at cycle {}", out.cycle);
+        assert!(sms.stop_register_log().is_none());
+    }
+
+    #[test]
+    fn save_state_round_trip_continues_identically() {
+        let mut rom = minimal_rom();
+        // DI; loop: LD A,($C000); INC A; LD ($C000),A; OUT ($7F),A; JR loop
+        rom[..12].copy_from_slice(&[
+            0xF3, 0x3A, 0x00, 0xC0, 0x3C, 0x32, 0x00, 0xC0, 0xD3, 0x7F, 0x18, 0xF5,
+        ]);
+        let mut sms = Sms::new(rom.clone(), SmsVariant::SmsNtsc);
+        sms.run_frame();
+        let state = sms.save_state();
+
+        let mut copy = Sms::new(rom, SmsVariant::SmsNtsc);
+        copy.load_state(&state).expect("state loads");
+        sms.run_frame();
+        copy.run_frame();
+        assert_eq!(copy.bus.ram, sms.bus.ram);
+        assert_eq!(copy.take_audio_buffer(), sms.take_audio_buffer());
+        assert_eq!(copy.save_state(), sms.save_state());
+
+        let mut pal = Sms::new(minimal_rom(), SmsVariant::SmsPal);
+        assert!(pal.load_state(&state).is_err());
+    }
+
+    #[test]
+    fn netplay_input_drives_both_pads() {
+        let mut sms = Sms::new(minimal_rom(), SmsVariant::SmsNtsc);
+        sms.set_player_input(0, 0b01_0001); // Up + Button1
+        assert_eq!((sms.bus.port_dc, sms.bus.port_dd), (0xEE, 0xFF));
+        sms.set_player_input(1, 0b10_1010); // Down + Right + Button2
+        assert_eq!((sms.bus.port_dc, sms.bus.port_dd), (0x6E, 0xF5));
+        sms.set_player_input(0, 0);
+        assert_eq!(sms.bus.port_dc, 0x7F);
+    }
+
     #[test]
     fn ram_read_write() {
         let mut bus = SmsBus::new(minimal_rom(), SmsVariant::SmsNtsc);
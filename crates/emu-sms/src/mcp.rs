@@ -0,0 +1,39 @@
+//! MCP (Model Context Protocol) server for the Master System / Game Gear emulator.
+//!
+//! Serves the generic `MachineMcp` tools. Run with `--mcp` for MCP mode
+//! or `--script` for batch mode.
+
+use emu_core::Instruction;
+use emu_core::mcp::{self, MachineMcp, McpMachine};
+
+use crate::{Sms, SmsVariant};
+
+pub type McpServer = mcp::McpServer<MachineMcp<Sms>>;
+
+impl McpMachine for Sms {
+    fn frame_rate(&self) -> u32 {
+        match self.variant {
+            SmsVariant::SmsPal => 50,
+            SmsVariant::SmsNtsc | SmsVariant::GameGear => 60,
+        }
+    }
+
+    fn disassemble(&self, address: u32) -> Instruction {
+        emu_disasm::z80::disassemble(|addr| self.bus.peek(addr), address)
+    }
+
+    /// 8 KB of RAM mirrored across $C000-$FFFF. Writes to $FFFC-$FFFF
+    /// also reach the mapper registers on real hardware; `poke` only
+    /// changes RAM.
+    fn peek(&self, address: u16) -> Option<u8> {
+        (address >= 0xC000).then(|| self.bus.ram[usize::from(address & 0x1FFF)])
+    }
+
+    fn poke(&mut self, address: u16, value: u8) -> bool {
+        let is_ram = address >= 0xC000;
+        if is_ram {
+            self.bus.ram[usize::from(address & 0x1FFF)] = value;
+        }
+        is_ram
+    }
+}
@@ -6,62 +6,113 @@
 //!
 //! # Sampling
 //!
-//! The beeper level is sampled at the CPU rate (3.5 MHz) and downsampled to
-//! the output rate (typically 48 kHz) by accumulating samples and averaging.
+//! Each level change is placed on a band-limited buffer at its exact
+//! T-state, which resamples the square wave to the output rate (typically
+//! 48 kHz) without aliasing.
+//!
+//! A muted beeper holds the speaker at rest in the mix, while stem capture
+//! still records what it plays.
 
-#![allow(clippy::cast_precision_loss)] // Intentional: u32→f32 precision loss is acceptable for audio.
+use emu_core::{BlipBuffer, SaveState, StateError, StateReader, StateWriter, Stems};
 
 /// 1-bit beeper state.
 pub struct BeeperState {
     /// Current beeper level (0 or 1).
     level: u8,
-    /// Accumulated sample value for downsampling.
-    accumulator: f32,
-    /// Number of CPU ticks accumulated.
-    sample_count: u32,
-    /// CPU ticks per output sample (e.g., 3,500,000 / 48,000 ≈ 72.9).
-    ticks_per_sample: f32,
-    /// Output audio buffer (mono f32 samples, -1.0 to 1.0).
-    buffer: Vec<f32>,
+    /// Band-limited output (mono f32 samples, -1.0 to 1.0).
+    blip: BlipBuffer,
+    /// Left out of the mix.
+    muted: bool,
+    /// Unmuted capture, while enabled.
+    stems: Option<Stems>,
 }
 
 impl BeeperState {
     /// Create a new beeper with the given output sample rate.
     #[must_use]
     pub fn new(cpu_frequency: u32, output_sample_rate: u32) -> Self {
+        let mut blip = BlipBuffer::new(f64::from(cpu_frequency), output_sample_rate);
+        blip.set_level(-1.0);
         Self {
             level: 0,
-            accumulator: 0.0,
-            sample_count: 0,
-            ticks_per_sample: cpu_frequency as f32 / output_sample_rate as f32,
-            buffer: Vec::with_capacity(output_sample_rate as usize / 50 + 1), // ~1 frame
+            blip,
+            muted: false,
+            stems: None,
         }
     }
 
     /// Set the beeper level (bit 4 of port $FE: 0 or 1).
     pub fn set_level(&mut self, level: u8) {
         self.level = level & 1;
+        self.update_mix();
+    }
+
+    /// Speaker position for the current level, in the -1.0/+1.0 range.
+    fn output(&self) -> f32 {
+        if self.level != 0 { 1.0 } else { -1.0 }
+    }
+
+    fn update_mix(&mut self) {
+        let level = if self.muted { -1.0 } else { self.output() };
+        self.blip.set_level(level);
     }
 
-    /// Sample the beeper at CPU rate. Call once per CPU T-state.
+    /// Advance the beeper by one CPU T-state.
     pub fn sample(&mut self) {
-        // Convert 0/1 to -1.0/+1.0 range
-        let sample = if self.level != 0 { 1.0 } else { -1.0 };
-        self.accumulator += sample;
-        self.sample_count += 1;
-
-        if self.sample_count as f32 >= self.ticks_per_sample {
-            // Average the accumulated samples and emit one output sample
-            let avg = self.accumulator / self.sample_count as f32;
-            self.buffer.push(avg);
-            self.accumulator = 0.0;
-            self.sample_count = 0;
+        self.blip.advance(1);
+        let output = self.output();
+        if let Some(stems) = &mut self.stems {
+            stems.record(&[output], 1);
         }
     }
 
+    /// Leave the beeper out of the mix (the speaker stays at rest).
+    pub fn set_muted(&mut self, muted: bool) {
+        self.muted = muted;
+        self.update_mix();
+    }
+
+    /// Whether the beeper is left out of the mix.
+    #[must_use]
+    pub fn muted(&self) -> bool {
+        self.muted
+    }
+
+    /// Start or stop capturing the beeper as a stem, regardless of muting.
+    pub fn set_stems(&mut self, enabled: bool) {
+        self.stems = enabled.then(|| Stems::new(&self.blip, &[self.output()]));
+    }
+
+    /// Drain the captured stem (one stream). Empty while capture is off.
+    pub fn take_stems(&mut self) -> Vec<Vec<f32>> {
+        self.stems.as_mut().map_or_else(Vec::new, Stems::take)
+    }
+
     /// Take the audio buffer (drains it).
     pub fn take_buffer(&mut self) -> Vec<f32> {
-        std::mem::take(&mut self.buffer)
+        self.blip.take_samples()
+    }
+
+    /// Output sample rate.
+    #[must_use]
+    pub fn sample_rate(&self) -> u32 {
+        self.blip.sample_rate()
+    }
+
+    /// Change the output sample rate.
+    pub fn set_sample_rate(&mut self, rate: u32) {
+        self.blip.set_sample_rate(rate);
+        if let Some(stems) = &mut self.stems {
+            stems.set_sample_rate(rate);
+        }
+    }
+
+    /// Stretch the output rate for dynamic rate control.
+    pub fn set_rate_adjust(&mut self, ratio: f64) {
+        self.blip.set_rate_adjust(ratio);
+        if let Some(stems) = &mut self.stems {
+            stems.set_rate_adjust(ratio);
+        }
     }
 
     /// Current beeper level (0 or 1).
@@ -73,7 +124,20 @@ impl BeeperState {
     /// Number of samples in the output buffer.
     #[must_use]
     pub fn buffer_len(&self) -> usize {
-        self.buffer.len()
+        self.blip.len()
+    }
+}
+
+impl SaveState for BeeperState {
+    fn save_state(&self, w: &mut StateWriter) {
+        w.write_u8(self.level);
+        self.blip.save_state(w);
+    }
+
+    fn load_state(&mut self, r: &mut StateReader<'_>) -> Result<(), StateError> {
+        self.level = r.read_u8()?;
+        self.blip.load_state(r)?;
+        Ok(())
     }
 }
 
@@ -102,9 +166,9 @@ mod tests {
     fn level_toggle_produces_waveform() {
         let mut beeper = BeeperState::new(3_500_000, 48_000);
 
-        // Toggle every 50 ticks for a few hundred ticks
-        for i in 0..500 {
-            if i % 50 == 0 {
+        // Toggle every 500 ticks (3.5 kHz) for a few milliseconds
+        for i in 0..5000 {
+            if i % 500 == 0 {
                 beeper.set_level(u8::from(beeper.level() == 0));
             }
             beeper.sample();
@@ -128,4 +192,29 @@ mod tests {
         assert!(!buf.is_empty());
         assert_eq!(beeper.buffer_len(), 0);
     }
+
+    #[test]
+    fn muted_beeper_keeps_its_stem() {
+        let mut beeper = BeeperState::new(3_500_000, 48_000);
+        beeper.set_muted(true);
+        beeper.set_stems(true);
+        for i in 0..35_000 {
+            if i % 500 == 0 {
+                beeper.set_level(u8::from(beeper.level() == 0));
+            }
+            beeper.sample();
+        }
+
+        let mix = beeper.take_buffer();
+        let stems = beeper.take_stems();
+        assert_eq!(stems.len(), 1);
+        assert_eq!(stems[0].len(), mix.len());
+        // Past the initial step down to the rest position.
+        let settled = &mix[16..];
+        assert!(
+            settled.iter().all(|&s| (s + 1.0).abs() < 1e-6),
+            "speaker should rest"
+        );
+        assert!(stems[0].iter().any(|&s| s > 0.5));
+    }
 }
@@ -13,7 +13,7 @@
 
 #![allow(clippy::cast_possible_truncation)]
 
-use emu_core::{Bus, ReadResult};
+use emu_core::{Bus, ReadResult, SaveState, StateError, StateReader, StateWriter};
 use gi_ay_3_8910::Ay3_8910;
 use nec_upd765::Upd765;
 use sinclair_ula::Ula;
@@ -70,6 +70,36 @@ impl SpectrumBus {
     }
 }
 
+impl SaveState for SpectrumBus {
+    fn save_state(&self, w: &mut StateWriter) {
+        self.memory.save_state(w);
+        self.ula.save_state(w);
+        self.keyboard.save_state(w);
+        self.beeper.save_state(w);
+        w.write_u8(self.last_fe_write);
+        w.write_u8(self.kempston);
+        w.write_option(self.ay.as_ref());
+        w.write_option(self.fdc.as_ref());
+        w.write_bool(self.tape_ear.is_some());
+        w.write_bool(self.tape_ear.unwrap_or(false));
+    }
+
+    fn load_state(&mut self, r: &mut StateReader<'_>) -> Result<(), StateError> {
+        self.memory.load_state(r)?;
+        self.ula.load_state(r)?;
+        self.keyboard.load_state(r)?;
+        self.beeper.load_state(r)?;
+        self.last_fe_write = r.read_u8()?;
+        self.kempston = r.read_u8()?;
+        r.read_option(self.ay.as_mut(), "an AY")?;
+        r.read_option(self.fdc.as_mut(), "an FDC")?;
+        let has_ear = r.read_bool()?;
+        let ear = r.read_bool()?;
+        self.tape_ear = has_ear.then_some(ear);
+        Ok(())
+    }
+}
+
 impl Bus for SpectrumBus {
     fn read(&mut self, addr: u32) -> ReadResult {
         let addr16 = addr as u16;
@@ -72,6 +72,67 @@ pub enum SpectrumKey {
 }
 
 impl SpectrumKey {
+    /// Every key in matrix order, followed by the Kempston directions.
+    pub const ALL: [Self; 45] = [
+        Self::CapsShift,
+        Self::Z,
+        Self::X,
+        Self::C,
+        Self::V,
+        Self::A,
+        Self::S,
+        Self::D,
+        Self::F,
+        Self::G,
+        Self::Q,
+        Self::W,
+        Self::E,
+        Self::R,
+        Self::T,
+        Self::N1,
+        Self::N2,
+        Self::N3,
+        Self::N4,
+        Self::N5,
+        Self::N0,
+        Self::N9,
+        Self::N8,
+        Self::N7,
+        Self::N6,
+        Self::P,
+        Self::O,
+        Self::I,
+        Self::U,
+        Self::Y,
+        Self::Enter,
+        Self::L,
+        Self::K,
+        Self::J,
+        Self::H,
+        Self::Space,
+        Self::SymShift,
+        Self::M,
+        Self::N,
+        Self::B,
+        Self::KempstonRight,
+        Self::KempstonLeft,
+        Self::KempstonDown,
+        Self::KempstonUp,
+        Self::KempstonFire,
+    ];
+
+    /// Stable name used in input movies (the variant name).
+    #[must_use]
+    pub fn name(self) -> String {
+        format!("{self:?}")
+    }
+
+    /// Look up a key by its [`SpectrumKey::name`].
+    #[must_use]
+    pub fn from_name(name: &str) -> Option<Self> {
+        Self::ALL.into_iter().find(|key| key.name() == name)
+    }
+
     /// Return the Kempston joystick bit index, or None for keyboard keys.
     #[must_use]
     pub const fn kempston_bit(self) -> Option<u8> {
@@ -253,6 +314,11 @@ impl InputQueue {
         }
     }
 
+    /// Events that the next `process(frame, ..)` call will apply.
+    pub fn due(&self, frame: u64) -> impl Iterator<Item = &InputEvent> {
+        self.events.iter().take_while(move |e| e.frame <= frame)
+    }
+
     /// Number of pending events.
     #[must_use]
     pub fn len(&self) -> usize {
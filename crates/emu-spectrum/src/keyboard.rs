@@ -20,6 +20,8 @@
 //!
 //! A pressed key reads as 0 (active low). Bits 5-7 always read as 1.
 
+use emu_core::{SaveState, StateError, StateReader, StateWriter};
+
 /// Keyboard state: 8 half-rows of 5 keys each.
 ///
 /// Each half-row byte uses bits 0-4 for keys (1 = pressed, for internal
@@ -105,6 +107,16 @@ impl KeyboardState {
     }
 }
 
+impl SaveState for KeyboardState {
+    fn save_state(&self, w: &mut StateWriter) {
+        w.write_bytes(&self.rows);
+    }
+
+    fn load_state(&mut self, r: &mut StateReader<'_>) -> Result<(), StateError> {
+        r.read_bytes_into(&mut self.rows)
+    }
+}
+
 impl Default for KeyboardState {
     fn default() -> Self {
         Self::new()
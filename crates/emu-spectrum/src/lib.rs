@@ -10,6 +10,7 @@ mod bus;
 #[cfg(feature = "native")]
 pub mod capture;
 mod config;
+pub use format_csw as csw;
 pub mod input;
 mod keyboard;
 #[cfg(feature = "native")]
@@ -17,10 +18,13 @@ pub mod keyboard_map;
 #[cfg(feature = "native")]
 pub mod mcp;
 mod memory;
+pub use format_pzx as pzx;
 pub use format_sna as sna;
 mod spectrum;
 pub use format_spectrum_tap as tap;
+pub use format_szx as szx;
 pub mod tape;
+pub mod tape_recorder;
 pub use format_tzx as tzx;
 pub mod tzx_signal;
 pub use format_z80 as z80;
@@ -28,14 +32,18 @@ pub use format_z80 as z80;
 pub use beeper::BeeperState;
 pub use bus::SpectrumBus;
 pub use config::{SpectrumConfig, SpectrumModel};
+pub use csw::CswFile;
 pub use input::{InputQueue, SpectrumKey};
 pub use keyboard::KeyboardState;
 pub use memory::{Memory48K, Memory128K, MemoryPlus3, SpectrumMemory};
+pub use pzx::PzxFile;
 pub use sinclair_ula::Ula;
-pub use sna::load_sna;
+pub use sna::{load_sna, save_sna};
 pub use spectrum::Spectrum;
+pub use szx::{SzxSnapshot, load_szx, save_szx};
 pub use tap::TapFile;
 pub use tape::TapeDeck;
+pub use tape_recorder::{RecordMode, TapeRecorder};
 pub use tzx::TzxFile;
 pub use tzx_signal::TzxSignal;
-pub use z80::load_z80;
+pub use z80::{load_z80, save_z80};
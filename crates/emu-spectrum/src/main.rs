@@ -12,11 +12,12 @@ use std::time::{Duration, Instant};
 
 use emu_core::Cpu;
 use emu_core::renderer::Renderer;
+use emu_core::warp::Warp;
 use emu_spectrum::keyboard_map::MappedKey;
 use emu_spectrum::mcp::{McpServer, SpectrumMcp};
 use emu_spectrum::{
-    Spectrum, SpectrumConfig, SpectrumModel, TapFile, TzxFile, capture, keyboard_map, load_sna,
-    load_z80,
+    CswFile, PzxFile, Spectrum, SpectrumConfig, SpectrumModel, TapFile, TzxFile, capture,
+    keyboard_map, load_sna, load_szx, load_z80,
 };
 use muda::{Menu, MenuEvent, MenuId, MenuItem, PredefinedMenuItem, Submenu};
 use winit::application::ApplicationHandler;
@@ -50,11 +51,15 @@ struct CliArgs {
     rom_path: Option<PathBuf>,
     sna_path: Option<PathBuf>,
     z80_path: Option<PathBuf>,
+    szx_path: Option<PathBuf>,
     tap_path: Option<PathBuf>,
     bas_path: Option<PathBuf>,
     tzx_path: Option<PathBuf>,
+    pzx_path: Option<PathBuf>,
+    csw_path: Option<PathBuf>,
     dsk_path: Option<PathBuf>,
     headless: bool,
+    bench: bool,
     mcp: bool,
     script_path: Option<PathBuf>,
     frames: u32,
@@ -72,11 +77,15 @@ fn parse_args() -> CliArgs {
         rom_path: None,
         sna_path: None,
         z80_path: None,
+        szx_path: None,
         tap_path: None,
         bas_path: None,
         tzx_path: None,
+        pzx_path: None,
+        csw_path: None,
         dsk_path: None,
         headless: false,
+        bench: false,
         mcp: false,
         script_path: None,
         frames: 200,
@@ -108,6 +117,10 @@ fn parse_args() -> CliArgs {
                 i += 1;
                 cli.z80_path = args.get(i).map(PathBuf::from);
             }
+            "--szx" => {
+                i += 1;
+                cli.szx_path = args.get(i).map(PathBuf::from);
+            }
             "--tap" => {
                 i += 1;
                 cli.tap_path = args.get(i).map(PathBuf::from);
@@ -120,6 +133,14 @@ fn parse_args() -> CliArgs {
                 i += 1;
                 cli.tzx_path = args.get(i).map(PathBuf::from);
             }
+            "--pzx" => {
+                i += 1;
+                cli.pzx_path = args.get(i).map(PathBuf::from);
+            }
+            "--csw" => {
+                i += 1;
+                cli.csw_path = args.get(i).map(PathBuf::from);
+            }
             "--dsk" => {
                 i += 1;
                 cli.dsk_path = args.get(i).map(PathBuf::from);
@@ -127,6 +148,9 @@ fn parse_args() -> CliArgs {
             "--headless" => {
                 cli.headless = true;
             }
+            "--bench" => {
+                cli.bench = true;
+            }
             "--mcp" => {
                 cli.mcp = true;
             }
@@ -172,10 +196,14 @@ fn parse_args() -> CliArgs {
                 eprintln!("  --rom <file>         ROM file (overrides built-in ROM)");
                 eprintln!("  --sna <file>         Load a SNA snapshot (48K or 128K)");
                 eprintln!("  --z80 <file>         Load a .Z80 snapshot (v1/v2/v3)");
+                eprintln!("  --szx <file>         Load an SZX (zx-state) snapshot");
                 eprintln!("  --tap <file>         Insert a TAP file into the tape deck");
                 eprintln!("  --tzx <file>         Insert a TZX file (real-time tape signal)");
+                eprintln!("  --pzx <file>         Insert a PZX file (real-time tape signal)");
+                eprintln!("  --csw <file>         Insert a CSW recording (real-time tape signal)");
                 eprintln!("  --dsk <file>         Insert a DSK disk image (+3 only)");
                 eprintln!("  --headless           Run without a window");
+                eprintln!("  --bench              Run --frames frames flat out and report speed");
                 eprintln!("  --mcp                Run as MCP server (JSON-RPC over stdio)");
                 eprintln!("  --script <file>      Run a JSON script file (headless batch mode)");
                 eprintln!(
@@ -241,6 +269,12 @@ fn run_headless(cli: &CliArgs) {
     }
 }
 
+/// Time `--frames` frames with no pacing and print the benchmark report.
+fn run_bench(cli: &CliArgs) {
+    let mut spectrum = make_spectrum(cli);
+    print!("{}", emu_core::bench::run(&mut spectrum, cli.frames));
+}
+
 // ---------------------------------------------------------------------------
 // Native menus (muda)
 // ---------------------------------------------------------------------------
@@ -319,6 +353,8 @@ struct App {
     renderer: Option<Renderer>,
     window: Option<Arc<Window>>,
     last_frame_time: Instant,
+    /// Fast-forward: F9 toggles it, and it runs while a tape plays.
+    warp: Warp,
     title: String,
     menu_ids: MenuIds,
     _menu: Menu,
@@ -331,6 +367,7 @@ impl App {
             renderer: None,
             window: None,
             last_frame_time: Instant::now(),
+            warp: Warp::new(),
             title,
             menu_ids,
             _menu: menu,
@@ -463,6 +500,13 @@ impl ApplicationHandler for App {
                         event_loop.exit();
                         return;
                     }
+                    if keycode == KeyCode::F9 {
+                        if event.state == ElementState::Pressed {
+                            let on = self.warp.toggle();
+                            eprintln!("Warp {}", if on { "on" } else { "off" });
+                        }
+                        return;
+                    }
                     self.handle_key(keycode, event.state == ElementState::Pressed);
                 }
             }
@@ -470,8 +514,12 @@ impl ApplicationHandler for App {
                 // Throttle to ~50 Hz.
                 let now = Instant::now();
                 if now.duration_since(self.last_frame_time) >= FRAME_DURATION {
-                    self.spectrum.run_frame();
-                    let _ = self.spectrum.take_audio_buffer();
+                    if self.warp.is_active(&self.spectrum) {
+                        self.warp.run(&mut self.spectrum, FRAME_DURATION, |_| {});
+                    } else {
+                        self.spectrum.run_frame();
+                        let _ = self.spectrum.take_audio_buffer();
+                    }
 
                     if let Some(renderer) = &mut self.renderer {
                         renderer.upload_framebuffer(self.spectrum.framebuffer());
@@ -576,6 +624,22 @@ fn make_spectrum(cli: &CliArgs) -> Spectrum {
         eprintln!("Loaded Z80: {}", path.display());
     }
 
+    // Load SZX snapshot if provided.
+    if let Some(ref path) = cli.szx_path {
+        let data = match std::fs::read(path) {
+            Ok(d) => d,
+            Err(e) => {
+                eprintln!("Failed to read SZX file {}: {e}", path.display());
+                process::exit(1);
+            }
+        };
+        if let Err(e) = load_szx(&mut spectrum, &data) {
+            eprintln!("Failed to load SZX: {e}");
+            process::exit(1);
+        }
+        eprintln!("Loaded SZX: {}", path.display());
+    }
+
     // Insert TAP file if provided.
     if let Some(ref path) = cli.tap_path {
         let data = match std::fs::read(path) {
@@ -661,6 +725,57 @@ fn make_spectrum(cli: &CliArgs) -> Spectrum {
         }
     }
 
+    // Insert PZX file if provided.
+    if let Some(ref path) = cli.pzx_path {
+        let data = match std::fs::read(path) {
+            Ok(d) => d,
+            Err(e) => {
+                eprintln!("Failed to read PZX file {}: {e}", path.display());
+                process::exit(1);
+            }
+        };
+        match PzxFile::parse(&data) {
+            Ok(pzx) => {
+                eprintln!(
+                    "Inserted PZX: {} ({} blocks)",
+                    path.display(),
+                    pzx.blocks.len()
+                );
+                spectrum.insert_pzx(pzx);
+            }
+            Err(e) => {
+                eprintln!("Failed to parse PZX file: {e}");
+                process::exit(1);
+            }
+        }
+    }
+
+    // Insert CSW recording if provided.
+    if let Some(ref path) = cli.csw_path {
+        let data = match std::fs::read(path) {
+            Ok(d) => d,
+            Err(e) => {
+                eprintln!("Failed to read CSW file {}: {e}", path.display());
+                process::exit(1);
+            }
+        };
+        match CswFile::parse(&data) {
+            Ok(csw) => {
+                eprintln!(
+                    "Inserted CSW: {} ({} pulses at {} Hz)",
+                    path.display(),
+                    csw.pulses.len(),
+                    csw.sample_rate
+                );
+                spectrum.insert_csw(csw);
+            }
+            Err(e) => {
+                eprintln!("Failed to parse CSW file: {e}");
+                process::exit(1);
+            }
+        }
+    }
+
     // Insert DSK if provided.
     if let Some(ref path) = cli.dsk_path {
         let data = match std::fs::read(path) {
@@ -708,6 +823,11 @@ fn main() {
         return;
     }
 
+    if cli.bench {
+        run_bench(&cli);
+        return;
+    }
+
     if cli.headless {
         run_headless(&cli);
         return;
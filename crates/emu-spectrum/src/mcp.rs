@@ -6,23 +6,42 @@
 
 #![allow(clippy::cast_possible_truncation)]
 
+use std::time::Duration;
+
 use base64::Engine;
 use serde_json::Value as JsonValue;
 
-use emu_core::mcp::{self, McpEmulator, ToolDefinition, ToolResult};
+use emu_core::breakpoint::{Breakpoint, Breakpoints, Hit, Trigger};
+use emu_core::mcp::breakpoint::BreakpointSpace;
+use emu_core::mcp::{self, McpEmulator, Notification, RunSession, ToolDefinition, ToolResult};
+use emu_core::symbols::SymbolTable;
+use emu_core::trace::TraceLayout;
 use emu_core::{Cpu, Observable, Tickable};
 
 use crate::Spectrum;
 use crate::config::{SpectrumConfig, SpectrumModel};
+use crate::csw::CswFile;
 use crate::input::SpectrumKey;
-use crate::sna::load_sna;
+use crate::pzx::PzxFile;
+use crate::sna::{load_sna, save_sna};
+use crate::szx::{SzxChunk, SzxSnapshot, save_szx};
 use crate::tap::{TapBlock, TapFile};
+use crate::tape_recorder::RecordMode;
 use crate::tzx::TzxFile;
-use crate::z80::load_z80;
+use crate::z80::{load_z80, save_z80};
 
 /// Embedded 48K ROM.
 const ROM_48K: &[u8] = include_bytes!("../../../roms/48.rom");
 
+/// Layout of `trace_start` lines: Z80 register pairs plus IX, IY and SP.
+const TRACE_LAYOUT: TraceLayout = TraceLayout {
+    address_digits: 4,
+    opcode_bytes: 4,
+    registers: &[
+        "cpu.af", "cpu.bc", "cpu.de", "cpu.hl", "cpu.ix", "cpu.iy", "cpu.sp",
+    ],
+};
+
 // ---------------------------------------------------------------------------
 // Public re-export: the MCP server type for main.rs
 // ---------------------------------------------------------------------------
@@ -35,23 +54,30 @@ pub type McpServer = mcp::McpServer<SpectrumMcp>;
 
 pub struct SpectrumMcp {
     spectrum: Option<Spectrum>,
+    /// Configuration the Spectrum was booted with, for input movies.
+    config: Option<SpectrumConfig>,
+    breakpoints: Breakpoints,
+    run: RunSession,
+    symbols: SymbolTable,
+    /// The SZX last loaded; `save_szx` writes its unmodelled chunks back.
+    szx: Option<SzxSnapshot>,
 }
 
 impl SpectrumMcp {
     #[must_use]
     pub fn new() -> Self {
-        Self { spectrum: None }
+        Self {
+            spectrum: None,
+            config: None,
+            breakpoints: Breakpoints::new(),
+            run: RunSession::new(),
+            symbols: SymbolTable::new(),
+            szx: None,
+        }
     }
 
     fn require_spectrum(&mut self) -> Result<&mut Spectrum, ToolResult> {
-        if let Some(ref mut spectrum) = self.spectrum {
-            Ok(spectrum)
-        } else {
-            Err(ToolResult::Error {
-                code: -32000,
-                message: "No Spectrum instance. Call 'boot' first.".to_string(),
-            })
-        }
+        self.spectrum.as_mut().ok_or_else(no_spectrum)
     }
 }
 
@@ -71,7 +97,7 @@ impl McpEmulator for SpectrumMcp {
     }
 
     fn tool_definitions(&self) -> Vec<ToolDefinition> {
-        vec![
+        let mut tools = vec![
             ToolDefinition {
                 name: "boot",
                 description: "Boot the ZX Spectrum with the specified model",
@@ -114,6 +140,47 @@ impl McpEmulator for SpectrumMcp {
                     }
                 }),
             },
+            ToolDefinition {
+                name: "load_szx",
+                description: "Load an SZX (zx-state) snapshot. Chunks the emulator does not model are kept for save_szx",
+                input_schema: serde_json::json!({
+                    "type": "object",
+                    "properties": {
+                        "path": { "type": "string", "description": "Path to .szx file" },
+                        "data": { "type": "string", "description": "Base64-encoded SZX data" }
+                    }
+                }),
+            },
+            ToolDefinition {
+                name: "save_sna",
+                description: "Save the machine as a 48K or 128K SNA snapshot. 128K SNA cannot hold bank 2 or 5 paged at $C000; use save_z80 for that",
+                input_schema: serde_json::json!({
+                    "type": "object",
+                    "properties": {
+                        "save_path": { "type": "string", "description": "Write the .sna to this path. Omit to return base64 data" }
+                    }
+                }),
+            },
+            ToolDefinition {
+                name: "save_z80",
+                description: "Save the machine as a compressed v3 .Z80 snapshot, including AY registers, paging, $1FFD and the frame T-state",
+                input_schema: serde_json::json!({
+                    "type": "object",
+                    "properties": {
+                        "save_path": { "type": "string", "description": "Write the .z80 to this path. Omit to return base64 data" }
+                    }
+                }),
+            },
+            ToolDefinition {
+                name: "save_szx",
+                description: "Save the machine as an SZX snapshot. Unmodelled chunks from the last load_szx are written back unchanged",
+                input_schema: serde_json::json!({
+                    "type": "object",
+                    "properties": {
+                        "save_path": { "type": "string", "description": "Write the .szx to this path. Omit to return base64 data" }
+                    }
+                }),
+            },
             ToolDefinition {
                 name: "load_tap",
                 description: "Insert a TAP file into the tape deck",
@@ -152,7 +219,33 @@ impl McpEmulator for SpectrumMcp {
                     "type": "object",
                     "properties": {
                         "path": { "type": "string", "description": "Path to .tzx file" },
-                        "data": { "type": "string", "description": "Base64-encoded TZX data" }
+                        "data": { "type": "string", "description": "Base64-encoded TZX data" },
+                        "select": {
+                            "type": "integer",
+                            "description": "Entry to take at select blocks (default: 0, the first)"
+                        }
+                    }
+                }),
+            },
+            ToolDefinition {
+                name: "load_pzx",
+                description: "Insert a PZX file (real-time tape signal)",
+                input_schema: serde_json::json!({
+                    "type": "object",
+                    "properties": {
+                        "path": { "type": "string", "description": "Path to .pzx file" },
+                        "data": { "type": "string", "description": "Base64-encoded PZX data" }
+                    }
+                }),
+            },
+            ToolDefinition {
+                name: "load_csw",
+                description: "Insert a CSW recording, RLE or Z-RLE (real-time tape signal)",
+                input_schema: serde_json::json!({
+                    "type": "object",
+                    "properties": {
+                        "path": { "type": "string", "description": "Path to .csw file" },
+                        "data": { "type": "string", "description": "Base64-encoded CSW data" }
                     }
                 }),
             },
@@ -169,12 +262,41 @@ impl McpEmulator for SpectrumMcp {
             },
             ToolDefinition {
                 name: "tape_status",
-                description: "Query the current tape deck status",
+                description: "Query the current tape deck and recorder status",
                 input_schema: serde_json::json!({
                     "type": "object",
                     "properties": {}
                 }),
             },
+            ToolDefinition {
+                name: "tape_record_start",
+                description: "Start capturing what the machine saves, discarding any earlier capture. 'trap' catches the ROM's SA-BYTES routine: SAVE is instant and each block is kept whole. 'signal' samples the MIC output in real time and encodes TZX blocks, which also catches turbo and custom savers",
+                input_schema: serde_json::json!({
+                    "type": "object",
+                    "properties": {
+                        "mode": { "type": "string", "description": "trap or signal (default: trap)" }
+                    }
+                }),
+            },
+            ToolDefinition {
+                name: "tape_record_stop",
+                description: "Stop capturing. The capture is kept for save_tape until the next tape_record_start",
+                input_schema: serde_json::json!({
+                    "type": "object",
+                    "properties": {}
+                }),
+            },
+            ToolDefinition {
+                name: "save_tape",
+                description: "Write the tape capture as TAP or TZX. A signal capture saves as TAP only if every block it holds is at ROM speed",
+                input_schema: serde_json::json!({
+                    "type": "object",
+                    "properties": {
+                        "format": { "type": "string", "description": "tap or tzx (default: tap for a trap capture, tzx for a signal capture)" },
+                        "save_path": { "type": "string", "description": "Write the tape to this path. Omit to return base64 data" }
+                    }
+                }),
+            },
             ToolDefinition {
                 name: "run_frames",
                 description: "Run the emulator for N frames (50fps PAL)",
@@ -192,7 +314,8 @@ impl McpEmulator for SpectrumMcp {
                     "type": "object",
                     "properties": {
                         "save_path": { "type": "string", "description": "If set, save PNG to this path and return metadata only" },
-                        "correct_aspect": { "type": "boolean", "description": "Scale to 4:3 display aspect ratio (default: true)" }
+                        "correct_aspect": { "type": "boolean", "description": "Scale to 4:3 display aspect ratio (default: true)" },
+                        "filter": mcp::filter_property()
                     }
                 }),
             },
@@ -218,10 +341,13 @@ impl McpEmulator for SpectrumMcp {
                     "type": "object",
                     "properties": {
                         "frames": { "type": "integer", "default": 50 },
-                        "save_path": { "type": "string", "description": "Save WAV to this path" }
+                        "save_path": { "type": "string", "description": "Save WAV to this path" },
+                        "stems": mcp::stems_property()
                     }
                 }),
             },
+            mcp::channel_mute_definition(),
+            mcp::register_log_definition(),
             ToolDefinition {
                 name: "query",
                 description: "Query an observable value (e.g. cpu.pc, ula.border_colour)",
@@ -296,6 +422,7 @@ impl McpEmulator for SpectrumMcp {
                     "type": "object",
                     "properties": {
                         "address": { "type": "integer" },
+                        "condition": { "type": "string", "description": "Only stop when this expression holds, e.g. \"cpu.a == 0x20\"" },
                         "max_frames": { "type": "integer", "default": 10000 }
                     },
                     "required": ["address"]
@@ -318,6 +445,8 @@ impl McpEmulator for SpectrumMcp {
                     "required": ["address", "length"]
                 }),
             },
+            mcp::disassemble_definition(),
+            mcp::record_gif_definition(),
             ToolDefinition {
                 name: "record_video",
                 description: "Record N frames as MP4 video with audio",
@@ -326,30 +455,54 @@ impl McpEmulator for SpectrumMcp {
                     "properties": {
                         "frames": { "type": "integer", "description": "Number of frames to record" },
                         "save_path": { "type": "string", "description": "Write MP4 to this path" },
-                        "correct_aspect": { "type": "boolean", "description": "Scale to 4:3 display aspect ratio (default: true)" }
+                        "correct_aspect": { "type": "boolean", "description": "Scale to 4:3 display aspect ratio (default: true)" },
+                        "filter": mcp::filter_property()
                     },
                     "required": ["frames", "save_path"]
                 }),
             },
-        ]
+        ];
+        tools.extend(mcp::breakpoint::breakpoint_definitions());
+        tools.extend(mcp::trace::trace_definitions());
+        tools.extend(mcp::run::run_definitions());
+        tools.extend(mcp::symbols::symbol_definitions());
+        tools.extend(mcp::movie::movie_definitions());
+        tools
     }
 
     fn dispatch_tool(&mut self, name: &str, arguments: &JsonValue) -> ToolResult {
-        match name {
+        let result = match name {
             "boot" => self.handle_boot(arguments),
             "reset" => self.handle_reset(),
             "load_sna" => self.handle_load_sna(arguments),
             "load_z80" => self.handle_load_z80(arguments),
+            "load_szx" => self.handle_load_szx(arguments),
+            "save_sna" => self.handle_save_sna(arguments),
+            "save_z80" => self.handle_save_z80(arguments),
+            "save_szx" => self.handle_save_szx(arguments),
             "load_tap" => self.handle_load_tap(arguments),
             "load_bas" => self.handle_load_bas(arguments),
             "load_tzx" => self.handle_load_tzx(arguments),
+            "load_pzx" => self.handle_load_pzx(arguments),
+            "load_csw" => self.handle_load_csw(arguments),
             "load_dsk" => self.handle_load_dsk(arguments),
             "tape_status" => self.handle_tape_status(),
+            "tape_record_start" => self.handle_tape_record_start(arguments),
+            "tape_record_stop" => self.handle_tape_record_stop(),
+            "save_tape" => self.handle_save_tape(arguments),
             "run_frames" => self.handle_run_frames(arguments),
             "step_instruction" => self.handle_step_instruction(),
             "step_ticks" => self.handle_step_ticks(arguments),
             "screenshot" => self.handle_screenshot(arguments),
             "audio_capture" => self.handle_audio_capture(arguments),
+            "channel_mute" => match self.require_spectrum() {
+                Ok(spec) => mcp::channel_mute_result(arguments, spec),
+                Err(e) => e,
+            },
+            "register_log" => match self.require_spectrum() {
+                Ok(spec) => mcp::register_log_result(arguments, spec),
+                Err(e) => e,
+            },
             "query" => self.handle_query(arguments),
             "query_paths" => self.handle_query_paths(arguments),
             "poke" => self.handle_poke(arguments),
@@ -357,17 +510,114 @@ impl McpEmulator for SpectrumMcp {
             "release_key" => self.handle_release_key(arguments),
             "type_text" => self.handle_type_text(arguments),
             "set_breakpoint" => self.handle_set_breakpoint(arguments),
+            "add_breakpoint" => self.handle_add_breakpoint(arguments),
+            "remove_breakpoint" => {
+                mcp::breakpoint::remove_breakpoint_result(arguments, &mut self.breakpoints)
+            }
+            "list_breakpoints" => {
+                mcp::breakpoint::list_breakpoints_result(&self.breakpoints, &self.symbols)
+            }
+            "clear_breakpoints" => mcp::breakpoint::clear_breakpoints_result(&mut self.breakpoints),
+            "run_until_break" => self.handle_run_until_break(arguments),
+            "trace_start" => self.handle_trace_start(arguments),
+            "trace_stop" => self.handle_trace_stop(),
+            "trace_export" => self.handle_trace_export(arguments),
             "get_screen_text" => self.handle_get_screen_text(),
             "query_memory" => self.handle_query_memory(arguments),
+            "disassemble" => self.handle_disassemble(arguments),
+            "record_gif" => self.handle_record_gif(arguments),
             "record_video" => self.handle_record_video(arguments),
+            "record_movie" => self.handle_record_movie(arguments),
+            "play_movie" => self.handle_play_movie(arguments),
+            "run" => self.handle_run(arguments),
+            "pause" => self.handle_pause(),
+            "load_symbols" => self.handle_load_symbols(arguments),
+            "clear_symbols" => mcp::symbols::clear_symbols_result(&mut self.symbols),
+            "lookup_symbol" => mcp::symbols::lookup_symbol_result(arguments, &self.symbols),
             _ => ToolResult::Error {
                 code: -32601,
                 message: format!("Unknown tool: {name}"),
             },
+        };
+        let slot = match name {
+            "load_sna" | "load_z80" | "load_szx" => Some("snapshot"),
+            "load_tap" | "load_tzx" | "load_pzx" | "load_csw" => Some("tape"),
+            "load_bas" => Some("program"),
+            "load_dsk" => Some("disk"),
+            _ => None,
+        };
+        if let Some(slot) = slot {
+            self.run.media_tool_done(slot, name, arguments, &result);
+        }
+        result
+    }
+
+    fn run_slice(&mut self) -> Option<Duration> {
+        if !self.run.is_running() {
+            return None;
+        }
+        let Some(spec) = self.spectrum.as_mut() else {
+            self.run.stop();
+            return None;
+        };
+
+        // Breakpoints need the machine ticked one step at a time; without
+        // any, run_frame keeps queued input and movies in play.
+        if self.breakpoints.is_empty() {
+            spec.run_frame();
+        } else if let (Some(hit), _) = run_until_break(spec, &mut self.breakpoints, 1) {
+            let pc = format!("${:04X}", spec.cpu().regs.pc);
+            self.run.breakpoint_hit(&hit, &pc, &self.symbols);
+            return None;
+        }
+        self.run.frame_done(spec.frame_count());
+
+        // HALT only ends with an interrupt; with interrupts off (bar an
+        // NMI) it never does.
+        let regs = &spec.cpu().regs;
+        if regs.halted && !regs.iff1 {
+            self.run.cpu_halted(
+                &format!("${:04X}", regs.pc),
+                "HALT with interrupts disabled",
+            );
         }
+        self.run.drive_led("a", spec.bus().memory.disk_motor());
+        self.run.next_slice()
+    }
+
+    fn take_notifications(&mut self) -> Vec<Notification> {
+        self.run.take_notifications()
+    }
+}
+
+fn no_spectrum() -> ToolResult {
+    ToolResult::Error {
+        code: -32000,
+        message: "No Spectrum instance. Call 'boot' first.".to_string(),
     }
 }
 
+/// Run until a breakpoint stops execution or `max_frames` frames complete.
+/// Returns the hit and the number of frames completed.
+fn run_until_break(
+    spec: &mut Spectrum,
+    breakpoints: &mut Breakpoints,
+    max_frames: u64,
+) -> (Option<Hit>, u64) {
+    let mut frames_run = 0u64;
+    if max_frames == 0 {
+        return (None, 0);
+    }
+    let hit = breakpoints.run(spec, |spec| {
+        spec.tick();
+        if spec.bus_mut().ula.take_frame_complete() {
+            frames_run += 1;
+        }
+        frames_run < max_frames
+    });
+    (hit, frames_run)
+}
+
 // ---------------------------------------------------------------------------
 // Tool handlers
 // ---------------------------------------------------------------------------
@@ -428,6 +678,8 @@ impl SpectrumMcp {
 
         let config = SpectrumConfig { model, rom };
         self.spectrum = Some(Spectrum::new(&config));
+        self.config = Some(config);
+        self.szx = None;
         ToolResult::Success(serde_json::json!({"status": "ok", "model": model_label}))
     }
 
@@ -452,7 +704,10 @@ impl SpectrumMcp {
         };
 
         match load_sna(spec, &data) {
-            Ok(()) => ToolResult::Success(serde_json::json!({"status": "ok"})),
+            Ok(()) => {
+                self.szx = None;
+                ToolResult::Success(serde_json::json!({"status": "ok"}))
+            }
             Err(e) => ToolResult::Error {
                 code: -32000,
                 message: format!("SNA load failed: {e}"),
@@ -472,7 +727,10 @@ impl SpectrumMcp {
         };
 
         match load_z80(spec, &data) {
-            Ok(()) => ToolResult::Success(serde_json::json!({"status": "ok"})),
+            Ok(()) => {
+                self.szx = None;
+                ToolResult::Success(serde_json::json!({"status": "ok"}))
+            }
             Err(e) => ToolResult::Error {
                 code: -32000,
                 message: format!("Z80 load failed: {e}"),
@@ -480,6 +738,75 @@ impl SpectrumMcp {
         }
     }
 
+    fn handle_load_szx(&mut self, params: &JsonValue) -> ToolResult {
+        let spec = match self.require_spectrum() {
+            Ok(s) => s,
+            Err(e) => return e,
+        };
+
+        let data = match load_binary_param(params) {
+            Ok(d) => d,
+            Err(e) => return e,
+        };
+
+        let loaded = SzxSnapshot::parse(&data).and_then(|szx| szx.apply(spec).map(|()| szx));
+        match loaded {
+            Ok(szx) => {
+                let kept: Vec<String> = szx.unmodelled_chunks().map(SzxChunk::name).collect();
+                self.szx = Some(szx);
+                ToolResult::Success(serde_json::json!({"status": "ok", "kept_chunks": kept}))
+            }
+            Err(e) => ToolResult::Error {
+                code: -32000,
+                message: format!("SZX load failed: {e}"),
+            },
+        }
+    }
+
+    fn handle_save_sna(&mut self, params: &JsonValue) -> ToolResult {
+        let spec = match self.require_spectrum() {
+            Ok(s) => s,
+            Err(e) => return e,
+        };
+
+        spec.run_to_instruction_boundary();
+        match save_sna(spec) {
+            Ok(bytes) => snapshot_result(params, "sna", &bytes),
+            Err(e) => ToolResult::Error {
+                code: -32000,
+                message: format!("SNA save failed: {e}"),
+            },
+        }
+    }
+
+    fn handle_save_z80(&mut self, params: &JsonValue) -> ToolResult {
+        let spec = match self.require_spectrum() {
+            Ok(s) => s,
+            Err(e) => return e,
+        };
+
+        spec.run_to_instruction_boundary();
+        snapshot_result(params, "z80", &save_z80(spec))
+    }
+
+    fn handle_save_szx(&mut self, params: &JsonValue) -> ToolResult {
+        let mut szx = self.szx.clone();
+        let spec = match self.require_spectrum() {
+            Ok(s) => s,
+            Err(e) => return e,
+        };
+
+        spec.run_to_instruction_boundary();
+        let bytes = match &mut szx {
+            Some(szx) => {
+                szx.update(spec);
+                szx.to_bytes()
+            }
+            None => save_szx(spec),
+        };
+        snapshot_result(params, "szx", &bytes)
+    }
+
     fn handle_load_tap(&mut self, params: &JsonValue) -> ToolResult {
         let spec = match self.require_spectrum() {
             Ok(s) => s,
@@ -662,6 +989,13 @@ impl SpectrumMcp {
             Ok(tzx) => {
                 let blocks = tzx.blocks.len();
                 spec.insert_tzx(tzx);
+                if let Some(choice) = params
+                    .get("select")
+                    .and_then(serde_json::Value::as_u64)
+                    .and_then(|n| usize::try_from(n).ok())
+                {
+                    spec.select_tape_entry(choice);
+                }
                 ToolResult::Success(
                     serde_json::json!({"status": "ok", "blocks": blocks, "format": "tzx"}),
                 )
@@ -673,6 +1007,62 @@ impl SpectrumMcp {
         }
     }
 
+    fn handle_load_pzx(&mut self, params: &JsonValue) -> ToolResult {
+        let spec = match self.require_spectrum() {
+            Ok(s) => s,
+            Err(e) => return e,
+        };
+
+        let data = match load_binary_param(params) {
+            Ok(d) => d,
+            Err(e) => return e,
+        };
+
+        match PzxFile::parse(&data) {
+            Ok(pzx) => {
+                let blocks = pzx.blocks.len();
+                spec.insert_pzx(pzx);
+                ToolResult::Success(
+                    serde_json::json!({"status": "ok", "blocks": blocks, "format": "pzx"}),
+                )
+            }
+            Err(e) => ToolResult::Error {
+                code: -32000,
+                message: format!("PZX parse failed: {e}"),
+            },
+        }
+    }
+
+    fn handle_load_csw(&mut self, params: &JsonValue) -> ToolResult {
+        let spec = match self.require_spectrum() {
+            Ok(s) => s,
+            Err(e) => return e,
+        };
+
+        let data = match load_binary_param(params) {
+            Ok(d) => d,
+            Err(e) => return e,
+        };
+
+        match CswFile::parse(&data) {
+            Ok(csw) => {
+                let pulses = csw.pulses.len();
+                let sample_rate = csw.sample_rate;
+                spec.insert_csw(csw);
+                ToolResult::Success(serde_json::json!({
+                    "status": "ok",
+                    "pulses": pulses,
+                    "sample_rate": sample_rate,
+                    "format": "csw",
+                }))
+            }
+            Err(e) => ToolResult::Error {
+                code: -32000,
+                message: format!("CSW parse failed: {e}"),
+            },
+        }
+    }
+
     fn handle_load_dsk(&mut self, params: &JsonValue) -> ToolResult {
         let spec = match self.require_spectrum() {
             Ok(s) => s,
@@ -703,6 +1093,7 @@ impl SpectrumMcp {
         let tap_block = spec.tape().block_index();
         let tap_blocks = spec.tape().block_count();
         let tzx_playing = spec.is_tzx_playing();
+        let recorder = spec.tape_recorder();
 
         ToolResult::Success(serde_json::json!({
             "tap": {
@@ -713,9 +1104,121 @@ impl SpectrumMcp {
             "tzx": {
                 "playing": tzx_playing,
             },
+            "recorder": {
+                "recording": recorder.is_recording(),
+                "mode": recorder.mode().name(),
+                "blocks": recorder.block_count(),
+                "pulses": recorder.pulse_count(),
+            },
+        }))
+    }
+
+    fn handle_tape_record_start(&mut self, params: &JsonValue) -> ToolResult {
+        let spec = match self.require_spectrum() {
+            Ok(s) => s,
+            Err(e) => return e,
+        };
+
+        let mode = match params.get("mode").and_then(|v| v.as_str()) {
+            None | Some("trap") => RecordMode::Trap,
+            Some("signal") => RecordMode::Signal,
+            Some(other) => {
+                return ToolResult::Error {
+                    code: -32602,
+                    message: format!("Unknown record mode '{other}' (expected trap or signal)"),
+                };
+            }
+        };
+        spec.start_tape_recording(mode);
+        ToolResult::Success(serde_json::json!({ "recording": true, "mode": mode.name() }))
+    }
+
+    fn handle_tape_record_stop(&mut self) -> ToolResult {
+        let spec = match self.require_spectrum() {
+            Ok(s) => s,
+            Err(e) => return e,
+        };
+
+        spec.stop_tape_recording();
+        let recorder = spec.tape_recorder();
+        ToolResult::Success(serde_json::json!({
+            "recording": false,
+            "mode": recorder.mode().name(),
+            "blocks": recorder.block_count(),
+            "pulses": recorder.pulse_count(),
         }))
     }
 
+    fn handle_save_tape(&mut self, params: &JsonValue) -> ToolResult {
+        let spec = match self.require_spectrum() {
+            Ok(s) => s,
+            Err(e) => return e,
+        };
+
+        let recorder = spec.tape_recorder();
+        let default = match recorder.mode() {
+            RecordMode::Trap => "tap",
+            RecordMode::Signal => "tzx",
+        };
+        let format = params
+            .get("format")
+            .and_then(|v| v.as_str())
+            .unwrap_or(default);
+        match format {
+            "tap" => match recorder.to_tap() {
+                Ok(tap) => snapshot_result(params, "tap", &tap.to_bytes()),
+                Err(e) => ToolResult::Error {
+                    code: -32000,
+                    message: format!("TAP save failed: {e}"),
+                },
+            },
+            "tzx" => snapshot_result(params, "tzx", &recorder.to_tzx().to_bytes()),
+            other => ToolResult::Error {
+                code: -32602,
+                message: format!("Unknown tape format '{other}' (expected tap or tzx)"),
+            },
+        }
+    }
+
+    fn handle_record_movie(&mut self, params: &JsonValue) -> ToolResult {
+        let stop = match mcp::movie::stop_param(params) {
+            Ok(s) => s,
+            Err(e) => return e,
+        };
+        let (Some(spec), Some(config)) = (self.spectrum.as_mut(), &self.config) else {
+            return no_spectrum();
+        };
+
+        if stop {
+            return mcp::movie::recording_stopped_result(params, spec.stop_recording());
+        }
+        spec.start_recording(config);
+        mcp::movie::recording_started_result(spec.frame_count())
+    }
+
+    fn handle_play_movie(&mut self, params: &JsonValue) -> ToolResult {
+        let movie = match mcp::movie::movie_param(params) {
+            Ok(m) => m,
+            Err(e) => return e,
+        };
+        let media = match mcp::movie::media_param(params) {
+            Ok(m) => m,
+            Err(e) => return e,
+        };
+        let (Some(spec), Some(config)) = (self.spectrum.as_mut(), &self.config) else {
+            return no_spectrum();
+        };
+
+        let media: Vec<(&str, &[u8])> = media
+            .iter()
+            .map(|(slot, data)| (slot.as_str(), data.as_slice()))
+            .collect();
+        if let Err(e) = spec.play_movie(&movie, config, &media) {
+            return mcp::movie::movie_error(&e);
+        }
+        mcp::movie::play_result(params, &movie, spec)
+    }
+
     fn handle_run_frames(&mut self, params: &JsonValue) -> ToolResult {
         let spec = match self.require_spectrum() {
             Ok(s) => s,
@@ -797,12 +1300,17 @@ impl SpectrumMcp {
         let save_path = params.get("save_path").and_then(|v| v.as_str());
         let display =
             parse_display_size(params, spec.framebuffer_width(), spec.framebuffer_height());
+        let filter = match mcp::parse_filter(params, None) {
+            Ok(filter) => filter,
+            Err(e) => return e,
+        };
         mcp::screenshot_result(
             spec.framebuffer_width(),
             spec.framebuffer_height(),
             spec.framebuffer(),
             save_path,
             display,
+            filter,
         )
     }
 
@@ -817,13 +1325,22 @@ impl SpectrumMcp {
             .and_then(serde_json::Value::as_u64)
             .unwrap_or(50);
 
+        let mut stems = match mcp::StemCapture::start(params, spec) {
+            Ok(s) => s,
+            Err(e) => return e,
+        };
+
         let mut all_audio: Vec<[f32; 2]> = Vec::new();
         for _ in 0..frames {
             spec.run_frame();
             all_audio.extend_from_slice(&spec.take_audio_buffer());
+            if let Some(stems) = &mut stems {
+                stems.collect(spec);
+            }
         }
 
-        if let Some(save_path) = params.get("save_path").and_then(|v| v.as_str())
+        let save_path = params.get("save_path").and_then(|v| v.as_str());
+        if let Some(save_path) = save_path
             && let Err(e) = crate::capture::save_audio(&all_audio, std::path::Path::new(save_path))
         {
             return ToolResult::Error {
@@ -857,12 +1374,19 @@ impl SpectrumMcp {
             base64::engine::general_purpose::STANDARD.encode(&wav_buf)
         };
 
-        ToolResult::Success(serde_json::json!({
+        let mut result = serde_json::json!({
             "format": "wav",
             "samples": all_audio.len(),
             "frames": frames,
             "data": b64,
-        }))
+        });
+        if let Some(stems) = stems {
+            match stems.finish(spec, save_path) {
+                Ok(stems) => result["stems"] = stems,
+                Err(e) => return e,
+            }
+        }
+        ToolResult::Success(result)
     }
 
     fn handle_query(&mut self, params: &JsonValue) -> ToolResult {
@@ -1020,48 +1544,130 @@ impl SpectrumMcp {
     }
 
     fn handle_set_breakpoint(&mut self, params: &JsonValue) -> ToolResult {
-        let spec = match self.require_spectrum() {
-            Ok(s) => s,
-            Err(e) => return e,
+        let Self {
+            spectrum,
+            breakpoints,
+            symbols,
+            ..
+        } = self;
+        let Some(spec) = spectrum.as_mut() else {
+            return no_spectrum();
         };
 
-        let addr = match params.get("address").and_then(serde_json::Value::as_u64) {
-            Some(a) if a <= 0xFFFF => a as u16,
-            _ => {
+        let addr = match mcp::symbols::address_param(params, "address", symbols, 0xFFFF) {
+            Ok(Some(a)) => a as u16,
+            Ok(None) => {
                 return ToolResult::Error {
                     code: -32602,
                     message: "Missing or invalid 'address' (0-65535)".to_string(),
                 };
             }
+            Err(e) => return e,
+        };
+        let condition = match mcp::breakpoint::parse_condition(params, spec) {
+            Ok(c) => c,
+            Err(e) => return e,
         };
+        let max_frames = mcp::breakpoint::max_frames_param(params);
 
-        let max_frames = params
-            .get("max_frames")
-            .and_then(serde_json::Value::as_u64)
-            .unwrap_or(10_000);
+        // Run alongside any breakpoints added with add_breakpoint, so
+        // whichever comes first stops execution.
+        let mut breakpoint = Breakpoint::new(Trigger::Execute(u32::from(addr))).temporary();
+        breakpoint.condition = condition;
+        let id = breakpoints.add(breakpoint);
+        let (hit, frames_run) = run_until_break(spec, breakpoints, max_frames);
+        breakpoints.remove(id);
 
-        let mut frames_run = 0u64;
-        let mut hit = false;
+        ToolResult::Success(serde_json::json!({
+            "hit": hit.is_some(),
+            "breakpoint": hit.as_ref().map(|h| mcp::breakpoint::hit_to_json(h, symbols)),
+            "pc": format!("${:04X}", spec.cpu().regs.pc),
+            "frames_run": frames_run,
+        }))
+    }
 
-        'outer: for _ in 0..max_frames {
-            loop {
-                spec.tick();
-                if spec.cpu().regs.pc == addr {
-                    hit = true;
-                    break 'outer;
-                }
-                if spec.bus_mut().ula.take_frame_complete() {
-                    frames_run += 1;
-                    break;
+    fn handle_add_breakpoint(&mut self, params: &JsonValue) -> ToolResult {
+        let Some(spec) = self.spectrum.as_ref() else {
+            return no_spectrum();
+        };
+        mcp::breakpoint::add_breakpoint_result(
+            params,
+            &mut self.breakpoints,
+            spec,
+            BreakpointSpace::Z80,
+            &self.symbols,
+        )
+    }
+
+    fn handle_run_until_break(&mut self, params: &JsonValue) -> ToolResult {
+        let Some(spec) = self.spectrum.as_mut() else {
+            return no_spectrum();
+        };
+        let max_frames = mcp::breakpoint::max_frames_param(params);
+        let (hit, frames_run) = run_until_break(spec, &mut self.breakpoints, max_frames);
+        mcp::breakpoint::run_until_break_result(
+            hit.as_ref(),
+            &format!("${:04X}", spec.cpu().regs.pc),
+            frames_run,
+            &self.symbols,
+        )
+    }
+
+    fn handle_run(&mut self, params: &JsonValue) -> ToolResult {
+        if self.spectrum.is_none() {
+            return no_spectrum();
+        }
+        self.run.start(params, 50)
+    }
+
+    fn handle_pause(&mut self) -> ToolResult {
+        let position = self.spectrum.as_ref().map_or(JsonValue::Null, |spec| {
+            serde_json::json!({
+                "pc": format!("${:04X}", spec.cpu().regs.pc),
+                "frame_count": spec.frame_count(),
+            })
+        });
+        self.run.pause(position)
+    }
+
+    fn handle_trace_start(&mut self, params: &JsonValue) -> ToolResult {
+        let Some(spec) = self.spectrum.as_mut() else {
+            return no_spectrum();
+        };
+        match mcp::trace::parse_tracer(params, TRACE_LAYOUT, &self.symbols) {
+            Ok(tracer) => {
+                let result = mcp::trace::trace_start_result(&tracer);
+                // A trace being replaced still gets its last entry written.
+                if let Some(mut old) = spec.set_tracer(Some(tracer)) {
+                    let _ = old.stop();
                 }
+                result
             }
+            Err(e) => e,
         }
+    }
 
-        ToolResult::Success(serde_json::json!({
-            "hit": hit,
-            "pc": format!("${:04X}", if hit { addr } else { spec.cpu().regs.pc }),
-            "frames_run": frames_run,
-        }))
+    fn handle_load_symbols(&mut self, params: &JsonValue) -> ToolResult {
+        let result = mcp::symbols::load_symbols_result(params, &mut self.symbols);
+        // A running trace labels the rest of its entries with them too.
+        if let Some(tracer) = self.spectrum.as_mut().and_then(Spectrum::tracer_mut) {
+            tracer.set_symbols(self.symbols.clone());
+        }
+        result
+    }
+
+    fn handle_trace_stop(&mut self) -> ToolResult {
+        match self.require_spectrum() {
+            Ok(spec) => mcp::trace::trace_stop_result(spec.tracer_mut()),
+            Err(e) => e,
+        }
+    }
+
+    fn handle_trace_export(&mut self, params: &JsonValue) -> ToolResult {
+        match self.require_spectrum() {
+            Ok(spec) => mcp::trace::trace_export_result(params, spec.tracer()),
+            Err(e) => e,
+        }
     }
 
     fn handle_get_screen_text(&mut self) -> ToolResult {
@@ -1130,6 +1736,33 @@ impl SpectrumMcp {
         }))
     }
 
+    fn handle_disassemble(&mut self, params: &JsonValue) -> ToolResult {
+        let Some(spec) = self.spectrum.as_ref() else {
+            return no_spectrum();
+        };
+
+        let pc = u32::from(spec.cpu().regs.pc);
+        let memory = &spec.bus().memory;
+        mcp::disassemble_result(params, Some(pc), &self.symbols, |address| {
+            emu_disasm::z80::disassemble(|addr| memory.peek(addr), address)
+        })
+    }
+
+    fn handle_record_gif(&mut self, params: &JsonValue) -> ToolResult {
+        let spec = match self.require_spectrum() {
+            Ok(s) => s,
+            Err(e) => return e,
+        };
+
+        let (width, height) = (spec.framebuffer_width(), spec.framebuffer_height());
+        let active = Some(spec.display_window());
+        let fps = 50; // PAL
+        mcp::record_gif_result(params, width, height, fps, active, |rec| {
+            spec.run_frame();
+            rec.add_frame(spec.framebuffer());
+        })
+    }
+
     fn handle_record_video(&mut self, params: &JsonValue) -> ToolResult {
         let spec = match self.require_spectrum() {
             Ok(s) => s,
@@ -1153,8 +1786,17 @@ impl SpectrumMcp {
             };
         };
 
+        let filter = match mcp::parse_filter(params, None) {
+            Ok(filter) => filter,
+            Err(e) => return e,
+        };
         let display =
             parse_display_size(params, spec.framebuffer_width(), spec.framebuffer_height());
+        let display = display.or_else(|| {
+            filter
+                .as_ref()
+                .map(|f| f.output_size(spec.framebuffer_width(), spec.framebuffer_height()))
+        });
         let mut rec = match emu_core::video::VideoRecorder::new(
             spec.framebuffer_width(),
             spec.framebuffer_height(),
@@ -1173,6 +1815,10 @@ impl SpectrumMcp {
             }
         };
 
+        if let Some(filter) = filter {
+            rec.set_filter(filter);
+        }
+
         for _ in 0..frames {
             spec.run_frame();
             // Spectrum returns stereo as Vec<[f32; 2]> — flatten to interleaved.
@@ -1235,6 +1881,28 @@ fn load_binary_param(params: &JsonValue) -> Result<Vec<u8>, ToolResult> {
     }
 }
 
+/// Write a saved snapshot to `save_path`, or return it as base64 `data`.
+fn snapshot_result(params: &JsonValue, format: &str, bytes: &[u8]) -> ToolResult {
+    let mut result = serde_json::json!({
+        "format": format,
+        "size": bytes.len(),
+    });
+    if let Some(path) = params.get("save_path").and_then(|v| v.as_str()) {
+        if let Err(e) = std::fs::write(path, bytes) {
+            return ToolResult::Error {
+                code: -32000,
+                message: format!("Failed to write {path}: {e}"),
+            };
+        }
+        result["path"] = path.into();
+    } else {
+        result["data"] = base64::engine::general_purpose::STANDARD
+            .encode(bytes)
+            .into();
+    }
+    ToolResult::Success(result)
+}
+
 /// Parse a key name string into a `SpectrumKey`.
 fn parse_key_name(name: &str) -> Option<SpectrumKey> {
     match name.to_lowercase().as_str() {
@@ -1383,6 +2051,138 @@ mod tests {
         assert!(mcp.spectrum.is_some());
     }
 
+    #[test]
+    fn save_z80_output_loads_back() {
+        let mut mcp = SpectrumMcp::new();
+        mcp.spectrum = Some(make_spectrum());
+        mcp.dispatch_tool("run_frames", &serde_json::json!({"count": 2}));
+
+        let ToolResult::Success(saved) = mcp.dispatch_tool("save_z80", &JsonValue::Null) else {
+            panic!("save_z80 failed");
+        };
+        assert_eq!(saved["format"], "z80");
+        let data = saved["data"].as_str().unwrap().to_string();
+
+        let result = mcp.dispatch_tool("load_z80", &serde_json::json!({ "data": data }));
+        assert!(matches!(result, ToolResult::Success(_)));
+    }
+
+    #[test]
+    fn save_szx_keeps_unmodelled_chunks() {
+        let mut mcp = SpectrumMcp::new();
+        mcp.spectrum = Some(make_spectrum());
+        let ToolResult::Success(saved) = mcp.dispatch_tool("save_szx", &JsonValue::Null) else {
+            panic!("save_szx failed");
+        };
+        let bytes = base64::engine::general_purpose::STANDARD
+            .decode(saved["data"].as_str().unwrap())
+            .unwrap();
+        let mut szx = SzxSnapshot::parse(&bytes).unwrap();
+        let tape = SzxChunk {
+            id: *b"TAPE",
+            data: vec![0, 1, 2, 3],
+        };
+        szx.chunks.push(tape.clone());
+        let data = base64::engine::general_purpose::STANDARD.encode(szx.to_bytes());
+
+        let ToolResult::Success(loaded) =
+            mcp.dispatch_tool("load_szx", &serde_json::json!({ "data": data }))
+        else {
+            panic!("load_szx failed");
+        };
+        assert_eq!(loaded["kept_chunks"], serde_json::json!(["TAPE"]));
+
+        let ToolResult::Success(resaved) = mcp.dispatch_tool("save_szx", &JsonValue::Null) else {
+            panic!("save_szx failed");
+        };
+        let bytes = base64::engine::general_purpose::STANDARD
+            .decode(resaved["data"].as_str().unwrap())
+            .unwrap();
+        let resaved = SzxSnapshot::parse(&bytes).unwrap();
+        assert_eq!(resaved.unmodelled_chunks().collect::<Vec<_>>(), [&tape]);
+    }
+
+    #[test]
+    fn load_pzx_and_csw_start_the_tape() {
+        let mut mcp = SpectrumMcp::new();
+        mcp.spectrum = Some(make_spectrum());
+
+        let mut pzx = b"PZXT\x02\0\0\0\x01\0PULS\x04\0\0\0".to_vec();
+        pzx.extend_from_slice(&[0x83, 0x80, 0x78, 0x08]); // 3 x 2168
+        let data = base64::engine::general_purpose::STANDARD.encode(&pzx);
+        let ToolResult::Success(loaded) =
+            mcp.dispatch_tool("load_pzx", &serde_json::json!({ "data": data }))
+        else {
+            panic!("load_pzx failed");
+        };
+        assert_eq!(loaded["blocks"], 1);
+        let ToolResult::Success(status) = mcp.dispatch_tool("tape_status", &JsonValue::Null) else {
+            panic!("tape_status failed");
+        };
+        assert_eq!(status["tzx"]["playing"], true);
+
+        let mut csw = b"Compressed Square Wave\x1A\x01\x01".to_vec();
+        csw.extend_from_slice(&[0x44, 0xAC, 1, 0, 0, 0, 0]);
+        csw.extend_from_slice(&[20, 20, 20]);
+        let data = base64::engine::general_purpose::STANDARD.encode(&csw);
+        let ToolResult::Success(loaded) =
+            mcp.dispatch_tool("load_csw", &serde_json::json!({ "data": data }))
+        else {
+            panic!("load_csw failed");
+        };
+        assert_eq!(loaded["pulses"], 3);
+        assert_eq!(loaded["sample_rate"], 44100);
+
+        let bad = base64::engine::general_purpose::STANDARD.encode(b"not a tape");
+        let result = mcp.dispatch_tool("load_csw", &serde_json::json!({ "data": bad }));
+        assert!(matches!(result, ToolResult::Error { .. }));
+    }
+
+    #[test]
+    fn tape_recording_saves_tap_and_tzx() {
+        let mut mcp = SpectrumMcp::new();
+        mcp.spectrum = Some(make_spectrum());
+
+        let reel = serde_json::json!({ "mode": "reel" });
+        let result = mcp.dispatch_tool("tape_record_start", &reel);
+        assert!(matches!(result, ToolResult::Error { .. }));
+
+        let signal = serde_json::json!({ "mode": "signal" });
+        let ToolResult::Success(started) = mcp.dispatch_tool("tape_record_start", &signal) else {
+            panic!("tape_record_start failed");
+        };
+        assert_eq!(started["mode"], "signal");
+        let ToolResult::Success(status) = mcp.dispatch_tool("tape_status", &JsonValue::Null) else {
+            panic!("tape_status failed");
+        };
+        assert_eq!(status["recorder"]["recording"], true);
+
+        let ToolResult::Success(stopped) = mcp.dispatch_tool("tape_record_stop", &JsonValue::Null)
+        else {
+            panic!("tape_record_stop failed");
+        };
+        assert_eq!(stopped["recording"], false);
+
+        // A signal capture defaults to TZX.
+        let ToolResult::Success(saved) = mcp.dispatch_tool("save_tape", &JsonValue::Null) else {
+            panic!("save_tape failed");
+        };
+        assert_eq!(saved["format"], "tzx");
+        let bytes = base64::engine::general_purpose::STANDARD
+            .decode(saved["data"].as_str().expect("data"))
+            .expect("base64");
+        assert!(TzxFile::parse(&bytes).expect("tzx").blocks.is_empty());
+
+        mcp.dispatch_tool("tape_record_start", &JsonValue::Null);
+        let ToolResult::Success(saved) = mcp.dispatch_tool("save_tape", &JsonValue::Null) else {
+            panic!("save_tape failed");
+        };
+        assert_eq!(saved["format"], "tap");
+        assert_eq!(saved["size"], 0);
+        let result = mcp.dispatch_tool("save_tape", &serde_json::json!({ "format": "wav" }));
+        assert!(matches!(result, ToolResult::Error { .. }));
+    }
+
     #[test]
     fn run_frames_without_boot_returns_error() {
         let mut mcp = SpectrumMcp::new();
@@ -1401,6 +2201,11 @@ mod tests {
     fn query_paths_can_filter_to_ula_and_cpu_surfaces() {
         let mut mcp = SpectrumMcp {
             spectrum: Some(make_spectrum()),
+            config: None,
+            breakpoints: Breakpoints::new(),
+            run: RunSession::new(),
+            symbols: SymbolTable::new(),
+            szx: None,
         };
 
         let ula_result = mcp.dispatch_tool(
@@ -1488,6 +2293,36 @@ mod tests {
         }
     }
 
+    #[test]
+    fn disassemble_resolves_jump_targets() {
+        let mut mcp = SpectrumMcp::new();
+        mcp.dispatch_tool("boot", &JsonValue::Null);
+        for (i, byte) in [0xDD, 0x21, 0x34, 0x12, 0xC3, 0x00, 0x80]
+            .iter()
+            .enumerate()
+        {
+            mcp.dispatch_tool(
+                "poke",
+                &serde_json::json!({"address": 0x8000 + i, "value": byte}),
+            );
+        }
+
+        let result = mcp.dispatch_tool(
+            "disassemble",
+            &serde_json::json!({"address": 0x8000, "count": 2}),
+        );
+        match result {
+            ToolResult::Success(val) => {
+                let insns = val["instructions"].as_array().unwrap();
+                assert_eq!(insns[0]["text"], "LD IX,$1234");
+                assert_eq!(insns[1]["address"], 0x8004);
+                assert_eq!(insns[1]["bytes"], serde_json::json!([0xC3, 0x00, 0x80]));
+                assert_eq!(insns[1]["target"], 0x8000);
+            }
+            ToolResult::Error { message, .. } => panic!("Expected success, got error: {message}"),
+        }
+    }
+
     #[test]
     fn poke_memory() {
         let mut mcp = SpectrumMcp::new();
@@ -1593,4 +2428,287 @@ mod tests {
             ToolResult::Error { message, .. } => panic!("Expected success, got error: {message}"),
         }
     }
+
+    /// Spectrum whose ROM runs a loop that writes the border port and
+    /// $8000 with an incrementing A.
+    fn breakpoint_mcp() -> SpectrumMcp {
+        let mut rom = vec![0u8; 0x4000];
+        rom[..11].copy_from_slice(&[
+            0x3E, 0x20, // LD A,$20
+            0xD3, 0xFE, // OUT ($FE),A
+            0x32, 0x00, 0x80, // LD ($8000),A
+            0x3C, // INC A
+            0xC3, 0x04, 0x00, // JP $0004
+        ]);
+        SpectrumMcp {
+            spectrum: Some(Spectrum::new(&SpectrumConfig {
+                model: SpectrumModel::Spectrum48K,
+                rom,
+            })),
+            config: None,
+            breakpoints: Breakpoints::new(),
+            run: RunSession::new(),
+            symbols: SymbolTable::new(),
+            szx: None,
+        }
+    }
+
+    fn success(result: ToolResult) -> JsonValue {
+        match result {
+            ToolResult::Success(val) => val,
+            ToolResult::Error { message, .. } => panic!("Expected success, got error: {message}"),
+        }
+    }
+
+    #[test]
+    fn watchpoints_report_the_access() {
+        let mut mcp = breakpoint_mcp();
+        success(mcp.dispatch_tool(
+            "add_breakpoint",
+            &serde_json::json!({"type": "io_write", "address": 0x20FE}),
+        ));
+        let result = success(mcp.dispatch_tool("run_until_break", &serde_json::json!({})));
+        assert_eq!(result["hit"], true);
+        assert_eq!(result["breakpoint"]["access"]["kind"], "io_write");
+        assert_eq!(result["breakpoint"]["access"]["value"], 0x20);
+
+        success(mcp.dispatch_tool("clear_breakpoints", &JsonValue::Null));
+        success(mcp.dispatch_tool(
+            "add_breakpoint",
+            &serde_json::json!({"type": "write", "address": 0x8000, "condition": "cpu.a == 0x22"}),
+        ));
+        let result = success(mcp.dispatch_tool("run_until_break", &serde_json::json!({})));
+        assert_eq!(result["breakpoint"]["access"]["value"], 0x22);
+        assert_eq!(result["breakpoint"]["hits"], 1);
+    }
+
+    #[test]
+    fn run_pushes_frames_then_stops_on_a_watchpoint() {
+        let mut mcp = breakpoint_mcp();
+        let started = success(mcp.dispatch_tool(
+            "run",
+            &serde_json::json!({"realtime": false, "frame_interval": 1}),
+        ));
+        assert_eq!(started["status"], "running");
+        assert_eq!(mcp.run_slice(), Some(Duration::ZERO));
+        assert_eq!(mcp.run_slice(), Some(Duration::ZERO));
+
+        // A watchpoint added mid-run stops it during the next slice.
+        success(mcp.dispatch_tool(
+            "add_breakpoint",
+            &serde_json::json!({"type": "write", "address": 0x8000}),
+        ));
+        assert_eq!(mcp.run_slice(), None);
+        assert_eq!(mcp.run_slice(), None);
+
+        let sent = mcp.take_notifications();
+        let events: Vec<_> = sent.iter().map(|n| n.event).collect();
+        assert_eq!(
+            events,
+            ["frame_complete", "frame_complete", "watchpoint_hit"]
+        );
+        assert_eq!(sent[2].params["breakpoint"]["access"]["address"], 0x8000);
+
+        let paused = success(mcp.dispatch_tool("pause", &JsonValue::Null));
+        assert_eq!(paused["was_running"], false);
+        assert_eq!(paused["frames_run"], 2);
+    }
+
+    #[test]
+    fn run_reports_a_halt_with_interrupts_disabled() {
+        let mut rom = vec![0u8; 0x4000];
+        rom[..2].copy_from_slice(&[0xF3, 0x76]); // DI; HALT
+        let mut mcp = SpectrumMcp::new();
+        mcp.spectrum = Some(Spectrum::new(&SpectrumConfig {
+            model: SpectrumModel::Spectrum48K,
+            rom,
+        }));
+
+        success(mcp.dispatch_tool("run", &serde_json::json!({"realtime": false})));
+        assert_eq!(mcp.run_slice(), None);
+        let sent = mcp.take_notifications();
+        assert_eq!(sent.last().map(|n| n.event), Some("cpu_halted"));
+    }
+
+    #[test]
+    fn hit_counts_and_raster_breakpoints() {
+        let mut mcp = breakpoint_mcp();
+        success(mcp.dispatch_tool(
+            "add_breakpoint",
+            &serde_json::json!({"type": "execute", "address": 7, "hit_count": 3}),
+        ));
+        let result = success(mcp.dispatch_tool("run_until_break", &serde_json::json!({})));
+        assert_eq!(result["pc"], "$0007");
+        assert_eq!(result["breakpoint"]["hits"], 3);
+        let spec = mcp.spectrum.as_ref().expect("spectrum");
+        assert_eq!(spec.cpu().regs.a, 0x22);
+
+        success(mcp.dispatch_tool("clear_breakpoints", &JsonValue::Null));
+        success(mcp.dispatch_tool(
+            "add_breakpoint",
+            &serde_json::json!({"type": "raster", "line": 100, "temporary": true}),
+        ));
+        let result = success(mcp.dispatch_tool("run_until_break", &serde_json::json!({})));
+        assert_eq!(result["breakpoint"]["type"], "raster");
+        assert_eq!(
+            mcp.spectrum.as_ref().expect("spectrum").bus().ula.line(),
+            100
+        );
+        let list = success(mcp.dispatch_tool("list_breakpoints", &JsonValue::Null));
+        assert_eq!(list["breakpoints"], serde_json::json!([]));
+    }
+
+    #[test]
+    fn set_breakpoint_accepts_a_condition_and_leaves_no_breakpoint() {
+        let mut mcp = breakpoint_mcp();
+        let result = success(mcp.dispatch_tool(
+            "set_breakpoint",
+            &serde_json::json!({"address": 4, "condition": "cpu.a == 0x21"}),
+        ));
+        assert_eq!(result["hit"], true);
+        assert_eq!(result["pc"], "$0004");
+        assert_eq!(mcp.spectrum.as_ref().expect("spectrum").cpu().regs.a, 0x21);
+        let list = success(mcp.dispatch_tool("list_breakpoints", &JsonValue::Null));
+        assert_eq!(list["breakpoints"], serde_json::json!([]));
+
+        let result = mcp.dispatch_tool(
+            "set_breakpoint",
+            &serde_json::json!({"address": 4, "condition": "cpu.q == 1"}),
+        );
+        assert!(matches!(result, ToolResult::Error { code: -32602, .. }));
+    }
+
+    #[test]
+    fn trace_records_instructions_and_port_writes() {
+        let mut mcp = breakpoint_mcp();
+        success(mcp.dispatch_tool("trace_start", &serde_json::json!({"bus": true})));
+        success(mcp.dispatch_tool("set_breakpoint", &serde_json::json!({"address": 4})));
+
+        // The CPU already sat on the $0000 boundary when tracing began, so
+        // the trace opens with the OUT at $0002.
+        let text = success(mcp.dispatch_tool("trace_export", &JsonValue::Null));
+        assert_eq!(text["entries"], 2);
+        let data = text["data"].as_str().expect("data");
+        assert!(data.contains("  0002  D3 FE       AF=2000 "), "{data}");
+        assert!(data.contains("IW 20FE 20"), "{data}");
+
+        let json = success(mcp.dispatch_tool(
+            "trace_export",
+            &serde_json::json!({"format": "json", "last": 1}),
+        ));
+        let entry: JsonValue =
+            serde_json::from_str(json["data"].as_str().expect("data")).expect("one JSON line");
+        assert_eq!(entry["pc"], 4);
+        assert_eq!(
+            entry["registers"]["cpu.af"].as_u64().map(|af| af >> 8),
+            Some(0x20)
+        );
+
+        let stopped = success(mcp.dispatch_tool("trace_stop", &JsonValue::Null));
+        assert_eq!(stopped["recorded"], 2);
+    }
+
+    #[test]
+    fn labels_from_a_sym_file_work_in_breakpoints_traces_and_disassembly() {
+        let path = std::env::temp_dir().join("emu-spectrum-mcp-labels.sym");
+        std::fs::write(&path, "border: EQU 0x0002\nloop: EQU 0x0004\nscore: EQU 0x8000\n")
+            .expect("write sym file");
+        let mut mcp = breakpoint_mcp();
+        let loaded = success(mcp.dispatch_tool(
+            "load_symbols",
+            &serde_json::json!({"path": path.to_str()}),
+        ));
+        let _ = std::fs::remove_file(&path);
+        assert_eq!(loaded["loaded"], 3);
+
+        success(mcp.dispatch_tool("trace_start", &JsonValue::Null));
+        let result = success(mcp.dispatch_tool(
+            "set_breakpoint",
+            &serde_json::json!({"address": "loop"}),
+        ));
+        assert_eq!(result["breakpoint"]["label"], "loop");
+        let text = success(mcp.dispatch_tool("trace_export", &JsonValue::Null));
+        assert!(
+            text["data"].as_str().expect("data").contains("; border"),
+            "{text}"
+        );
+
+        let listing = success(mcp.dispatch_tool(
+            "disassemble",
+            &serde_json::json!({"address": "loop", "count": 3}),
+        ));
+        let insns = &listing["instructions"];
+        assert_eq!(insns[0]["label"], "loop");
+        assert_eq!(insns[0]["text"], "LD (score),A");
+        assert_eq!(insns[2]["text"], "JP loop");
+    }
+
+    /// A 48K whose border follows the bottom-right keyboard row.
+    fn border_key_config() -> SpectrumConfig {
+        let mut rom = vec![0u8; 0x4000];
+        // DI; loop: LD A,$7F; IN A,($FE); OUT ($FE),A; JR loop
+        rom[..9].copy_from_slice(&[0xF3, 0x3E, 0x7F, 0xDB, 0xFE, 0xD3, 0xFE, 0x18, 0xF8]);
+        SpectrumConfig {
+            model: SpectrumModel::Spectrum48K,
+            rom,
+        }
+    }
+
+    fn movie_mcp() -> SpectrumMcp {
+        let mut mcp = SpectrumMcp::new();
+        mcp.spectrum = Some(Spectrum::new(&border_key_config()));
+        mcp.config = Some(border_key_config());
+        mcp
+    }
+
+    #[test]
+    fn movie_records_and_replays_through_tools() {
+        let mut mcp = movie_mcp();
+        let ToolResult::Success(started) = mcp.dispatch_tool("record_movie", &JsonValue::Null)
+        else {
+            panic!("record_movie start failed");
+        };
+        assert_eq!(started["start_frame"], 0);
+        mcp.dispatch_tool("run_frames", &serde_json::json!({"count": 3}));
+        mcp.dispatch_tool("press_key", &serde_json::json!({"key": "space"}));
+        mcp.dispatch_tool("run_frames", &serde_json::json!({"count": 3}));
+        let stop = serde_json::json!({"action": "stop"});
+        let ToolResult::Success(stopped) = mcp.dispatch_tool("record_movie", &stop) else {
+            panic!("record_movie stop failed");
+        };
+        assert_eq!(stopped["frames"], 6);
+        assert_eq!(stopped["events"], 1);
+        let data = stopped["data"].clone();
+
+        let ToolResult::Success(played) =
+            movie_mcp().dispatch_tool("play_movie", &serde_json::json!({"data": data}))
+        else {
+            panic!("play_movie failed");
+        };
+        assert_eq!(played["matched"], true);
+        assert_eq!(played["frame"], 6);
+
+        // Tamper with the fifth frame: playback stops there and reports it
+        let bytes = base64::engine::general_purpose::STANDARD
+            .decode(data.as_str().expect("movie returned as base64"))
+            .expect("movie data decodes");
+        let mut movie = emu_core::Movie::from_bytes(&bytes).expect("movie parses");
+        movie.frame_hashes[4] ^= 1;
+        let data = base64::engine::general_purpose::STANDARD.encode(movie.to_bytes());
+        let ToolResult::Success(played) =
+            movie_mcp().dispatch_tool("play_movie", &serde_json::json!({"data": data}))
+        else {
+            panic!("play_movie failed");
+        };
+        assert_eq!(played["matched"], false);
+        assert_eq!(played["divergence"]["frame"], 5);
+
+        // A power-on movie needs a machine that has not run yet
+        let result = mcp.dispatch_tool("play_movie", &serde_json::json!({"data": data}));
+        assert!(matches!(result, ToolResult::Error { .. }));
+        assert!(matches!(
+            mcp.dispatch_tool("record_movie", &stop),
+            ToolResult::Error { .. }
+        ));
+    }
 }
@@ -6,12 +6,16 @@
 #![allow(clippy::cast_possible_truncation)] // Intentional: u16 addresses index into arrays.
 #![allow(clippy::large_stack_arrays)] // Intentional: 48K RAM is the full usable address space.
 
+use emu_core::{SaveState, StateError, StateReader, StateWriter};
+
 /// Memory interface for all Spectrum variants.
 ///
 /// Implementations handle ROM/RAM layout, banking (128K+), and contention
 /// page identification. The ULA uses `peek()` to read VRAM without triggering
 /// side effects or contention.
-pub trait SpectrumMemory {
+///
+/// Save states capture RAM and banking latches; ROM is configuration.
+pub trait SpectrumMemory: SaveState {
     /// Read a byte from the given address (may have side effects in banked models).
     fn read(&self, addr: u16) -> u8;
 
@@ -50,6 +54,28 @@ pub trait SpectrumMemory {
     fn screen_bank(&self) -> u8 {
         5
     }
+
+    /// The 16K of RAM bank `bank` (0-7), whatever is paged in. The 48K's
+    /// RAM is banks 5, 2 and 0 ($4000, $8000, $C000); other banks are empty.
+    fn ram_bank(&self, bank: u8) -> &[u8];
+
+    /// The $7FFD register. Always 0 on 48K.
+    fn bank_register(&self) -> u8 {
+        0
+    }
+
+    /// The $1FFD register. Always 0 on non-+3 models.
+    fn plus3_register(&self) -> u8 {
+        0
+    }
+
+    /// Set $7FFD from a snapshot, even if paging is locked. Bit 5 locks
+    /// it again. No-op on 48K.
+    fn restore_bank_register(&mut self, _value: u8) {}
+
+    /// Set $1FFD from a snapshot, even if paging is locked. No-op on
+    /// non-+3 models.
+    fn restore_plus3_register(&mut self, _value: u8) {}
 }
 
 /// 48K Spectrum memory: 16K ROM + 48K RAM.
@@ -123,6 +149,15 @@ impl SpectrumMemory for Memory48K {
         // $4000-$7FFF is contended (the ULA shares this bus)
         (0x4000..0x8000).contains(&addr)
     }
+
+    fn ram_bank(&self, bank: u8) -> &[u8] {
+        match bank {
+            5 => &self.ram[..0x4000],
+            2 => &self.ram[0x4000..0x8000],
+            0 => &self.ram[0x8000..],
+            _ => &[],
+        }
+    }
 }
 
 /// 128K Spectrum memory: 2×16K ROM + 8×16K RAM with bank switching.
@@ -144,6 +179,16 @@ pub struct Memory128K {
     locked: bool,
 }
 
+impl SaveState for Memory48K {
+    fn save_state(&self, w: &mut StateWriter) {
+        w.write_bytes(&self.ram);
+    }
+
+    fn load_state(&mut self, r: &mut StateReader<'_>) -> Result<(), StateError> {
+        r.read_bytes_into(&mut self.ram)
+    }
+}
+
 impl Memory128K {
     /// Create a new 128K memory with the given 32K ROM data.
     ///
@@ -253,6 +298,19 @@ impl SpectrumMemory for Memory128K {
     fn screen_bank(&self) -> u8 {
         if self.bank_reg & 0x08 != 0 { 7 } else { 5 }
     }
+
+    fn ram_bank(&self, bank: u8) -> &[u8] {
+        &self.ram[bank as usize & 7][..]
+    }
+
+    fn bank_register(&self) -> u8 {
+        self.bank_reg
+    }
+
+    fn restore_bank_register(&mut self, value: u8) {
+        self.bank_reg = value;
+        self.locked = value & 0x20 != 0;
+    }
 }
 
 #[cfg(test)]
@@ -401,6 +459,22 @@ mod tests {
         assert_eq!(mem.read(0xC000), 0x33, "Bank should still be 3 (locked)");
     }
 
+    #[test]
+    fn memory_128k_restore_ignores_and_reapplies_lock() {
+        let mut mem = Memory128K::new(&make_128k_rom());
+        mem.write_bank_register(0x23);
+        mem.write(0xC000, 0x33);
+
+        mem.restore_bank_register(0x24);
+        mem.write(0xC000, 0x44);
+        assert_eq!(mem.bank_register(), 0x24);
+        assert_eq!(mem.ram_bank(3)[0], 0x33);
+        assert_eq!(mem.ram_bank(4)[0], 0x44);
+
+        mem.write_bank_register(0x00);
+        assert_eq!(mem.bank_register(), 0x24, "restored value locks again");
+    }
+
     #[test]
     fn memory_128k_contended_pages() {
         let mut mem = Memory128K::new(&make_128k_rom());
@@ -500,6 +574,25 @@ const SPECIAL_CONFIGS: [[usize; 4]; 4] = [
     [4, 7, 6, 3], // Config 3
 ];
 
+impl SaveState for Memory128K {
+    fn save_state(&self, w: &mut StateWriter) {
+        for bank in &self.ram {
+            w.write_bytes(&bank[..]);
+        }
+        w.write_u8(self.bank_reg);
+        w.write_bool(self.locked);
+    }
+
+    fn load_state(&mut self, r: &mut StateReader<'_>) -> Result<(), StateError> {
+        for bank in &mut self.ram {
+            r.read_bytes_into(&mut bank[..])?;
+        }
+        self.bank_reg = r.read_u8()?;
+        self.locked = r.read_bool()?;
+        Ok(())
+    }
+}
+
 impl MemoryPlus3 {
     /// Create a new +3 memory with the given 64K ROM data.
     ///
@@ -567,6 +660,27 @@ impl MemoryPlus3 {
     }
 }
 
+impl SaveState for MemoryPlus3 {
+    fn save_state(&self, w: &mut StateWriter) {
+        for bank in &self.ram {
+            w.write_bytes(&bank[..]);
+        }
+        w.write_u8(self.bank_7ffd);
+        w.write_u8(self.bank_1ffd);
+        w.write_bool(self.locked);
+    }
+
+    fn load_state(&mut self, r: &mut StateReader<'_>) -> Result<(), StateError> {
+        for bank in &mut self.ram {
+            r.read_bytes_into(&mut bank[..])?;
+        }
+        self.bank_7ffd = r.read_u8()?;
+        self.bank_1ffd = r.read_u8()?;
+        self.locked = r.read_bool()?;
+        Ok(())
+    }
+}
+
 impl SpectrumMemory for MemoryPlus3 {
     fn read(&self, addr: u16) -> u8 {
         let a = addr as usize;
@@ -653,6 +767,27 @@ impl SpectrumMemory for MemoryPlus3 {
     fn screen_bank(&self) -> u8 {
         if self.bank_7ffd & 0x08 != 0 { 7 } else { 5 }
     }
+
+    fn ram_bank(&self, bank: u8) -> &[u8] {
+        &self.ram[bank as usize & 7][..]
+    }
+
+    fn bank_register(&self) -> u8 {
+        self.bank_7ffd
+    }
+
+    fn plus3_register(&self) -> u8 {
+        self.bank_1ffd
+    }
+
+    fn restore_bank_register(&mut self, value: u8) {
+        self.bank_7ffd = value;
+        self.locked = value & 0x20 != 0;
+    }
+
+    fn restore_plus3_register(&mut self, value: u8) {
+        self.bank_1ffd = value;
+    }
 }
 
 #[cfg(test)]
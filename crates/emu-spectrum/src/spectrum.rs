@@ -14,19 +14,31 @@
 
 #![allow(clippy::cast_possible_truncation)]
 
-use emu_core::{AudioFrame, Cpu, Machine, Observable, Tickable, Value};
+use std::time::Duration;
+
+use emu_core::bench::ChipProfiler;
+use emu_core::breakpoint::Debuggable;
+use emu_core::movie::{Movie, MovieError};
+use emu_core::trace::{self, Tracer};
+use emu_core::{
+    AudioFrame, BusAccess, Cpu, LoggingBus, Machine, Observable, RegisterLog, SaveState,
+    StateError, StateReader, StateWriter, Tickable, Value,
+};
 use sinclair_ula::Ula;
 use zilog_z80::Z80;
 
 use crate::beeper::BeeperState;
 use crate::bus::SpectrumBus;
 use crate::config::{SpectrumConfig, SpectrumModel};
-use crate::input::{InputQueue, SpectrumKey};
+use crate::csw::CswFile;
+use crate::input::{InputEvent, InputQueue, SpectrumKey};
 use crate::memory::{Memory48K, Memory128K, MemoryPlus3, SpectrumMemory};
+use crate::pzx::PzxFile;
 use crate::tap::TapFile;
 use crate::tape::TapeDeck;
-use crate::tzx::TzxFile;
-use crate::tzx_signal::TzxSignal;
+use crate::tape_recorder::{RecordMode, TapeRecorder};
+use crate::tzx::{TzxBlock, TzxFile};
+use crate::tzx_signal::{self, TzxSignal};
 
 /// CPU clock divider (crystal ticks per CPU T-state).
 /// 4 = 3.5 MHz (normal speed for all Sinclair models).
@@ -42,9 +54,31 @@ const AUDIO_SAMPLE_RATE: u32 = 48_000;
 /// CPU frequency in Hz (3.5 MHz).
 const CPU_FREQUENCY: u32 = 3_500_000;
 
+/// Sound channels: the beeper, then the 128K's AY channels when fitted.
+const AUDIO_CHANNELS: [&str; 4] = ["beeper", "ay_a", "ay_b", "ay_c"];
+
+/// Machine tag in save-state headers.
+const STATE_TAG: &str = "spectrum";
+
+/// Chips timed by the profiler, in the order the tick loop laps them.
+const CHIPS: &[&str] = &[
+    "sinclair-ula",
+    "tape signal",
+    "zilog-z80",
+    "beeper",
+    "gi-ay-3-8910",
+];
+
 /// ROM address of the LD-BYTES routine (tape loading entry point).
 const LD_BYTES_ADDR: u16 = 0x0556;
 
+/// ROM address of the SA-BYTES routine (tape saving entry point).
+const SA_BYTES_ADDR: u16 = 0x04C2;
+
+/// SA-BYTES opens with `LD HL,SA/LD-RET`. Checked so the save trap only
+/// fires in the 48K BASIC ROM, not whatever else is paged in.
+const SA_BYTES_CODE: [u8; 3] = [0x21, 0x3F, 0x05];
+
 /// ZX Spectrum system.
 pub struct Spectrum {
     cpu: Z80,
@@ -59,12 +93,24 @@ pub struct Spectrum {
     input_queue: InputQueue,
     /// Virtual tape deck for TAP loading (ROM trap / instant load).
     tape: TapeDeck,
+    /// Tape recorder capturing what the machine saves.
+    recorder: TapeRecorder,
     /// AY clock toggle (ticks every other CPU T-state).
     ay_toggle: bool,
     /// Spectrum model (stored for TZX 48K detection).
     model: SpectrumModel,
     /// TZX signal generator for real-time tape loading.
     tzx_signal: Option<TzxSignal>,
+    /// Input movie being recorded, if any.
+    movie: Option<Movie>,
+    /// Media a movie being played loads, as (frame, slot, image).
+    movie_media: Vec<(u64, String, MovieImage)>,
+    /// CPU bus cycles logged for watchpoints, while logging is enabled.
+    bus_log: Option<Vec<BusAccess>>,
+    /// Instruction trace being recorded, if any.
+    tracer: Option<Tracer>,
+    /// Per-chip tick timing, while benchmarking.
+    profiler: ChipProfiler,
 }
 
 impl Spectrum {
@@ -122,9 +168,15 @@ impl Spectrum {
             frame_count: 0,
             input_queue: InputQueue::new(),
             tape: TapeDeck::new(),
+            recorder: TapeRecorder::new(),
             ay_toggle: false,
             model: config.model,
             tzx_signal: None,
+            movie: None,
+            movie_media: Vec::new(),
+            bus_log: None,
+            tracer: None,
+            profiler: ChipProfiler::new(CHIPS),
         }
     }
 
@@ -135,6 +187,12 @@ impl Spectrum {
     ///
     /// Returns the number of CPU T-states executed during the frame.
     pub fn run_frame(&mut self) -> u64 {
+        self.load_movie_media();
+        if let Some(movie) = &mut self.movie {
+            for event in self.input_queue.due(self.frame_count) {
+                movie.push_event(self.frame_count, event.key.name(), event.pressed);
+            }
+        }
         self.input_queue.process(
             self.frame_count,
             &mut self.bus.keyboard,
@@ -151,6 +209,10 @@ impl Spectrum {
             }
         }
 
+        if let Some(movie) = &mut self.movie {
+            movie.push_frame(self.bus.ula.framebuffer());
+        }
+
         (self.cpu.total_ticks() - start_ticks).get()
     }
 
@@ -172,6 +234,13 @@ impl Spectrum {
         self.bus.ula.framebuffer_height()
     }
 
+    /// The 256×192 screen inside the border, as x, y, width and height
+    /// in the framebuffer.
+    #[must_use]
+    pub fn display_window(&self) -> (u32, u32, u32, u32) {
+        self.bus.ula.display_window()
+    }
+
     /// Take the mixed audio buffer (beeper + AY if present). Drains both.
     ///
     /// Returns stereo samples as `[left, right]` pairs. The beeper is mono
@@ -193,6 +262,28 @@ impl Spectrum {
         }
     }
 
+    /// Output sample rate of `take_audio_buffer`.
+    #[must_use]
+    pub fn audio_sample_rate(&self) -> u32 {
+        self.bus.beeper.sample_rate()
+    }
+
+    /// Change the output sample rate (48 kHz by default).
+    pub fn set_audio_sample_rate(&mut self, rate: u32) {
+        self.bus.beeper.set_sample_rate(rate);
+        if let Some(ay) = &mut self.bus.ay {
+            ay.set_sample_rate(rate);
+        }
+    }
+
+    /// Stretch the output rate for dynamic rate control.
+    pub fn set_audio_rate_adjust(&mut self, ratio: f64) {
+        self.bus.beeper.set_rate_adjust(ratio);
+        if let Some(ay) = &mut self.bus.ay {
+            ay.set_rate_adjust(ratio);
+        }
+    }
+
     /// Reference to the CPU.
     #[must_use]
     pub fn cpu(&self) -> &Z80 {
@@ -234,6 +325,7 @@ impl Spectrum {
 
     /// Press a key immediately (stays pressed until released).
     pub fn press_key(&mut self, key: SpectrumKey) {
+        self.record_key(key, true);
         if let Some(bit) = key.kempston_bit() {
             self.bus.kempston |= 1 << bit;
         } else {
@@ -244,6 +336,7 @@ impl Spectrum {
 
     /// Release a key.
     pub fn release_key(&mut self, key: SpectrumKey) {
+        self.record_key(key, false);
         if let Some(bit) = key.kempston_bit() {
             self.bus.kempston &= !(1 << bit);
         } else {
@@ -254,12 +347,25 @@ impl Spectrum {
 
     /// Release all keys.
     pub fn release_all_keys(&mut self) {
+        for key in SpectrumKey::ALL {
+            self.record_key(key, false);
+        }
         self.bus.keyboard.release_all();
         self.bus.kempston = 0;
     }
 
+    /// Log a key change to the movie being recorded.
+    fn record_key(&mut self, key: SpectrumKey, pressed: bool) {
+        if let Some(movie) = &mut self.movie {
+            movie.push_event(self.frame_count, key.name(), pressed);
+        }
+    }
+
     /// Insert a TAP file into the tape deck.
     pub fn insert_tap(&mut self, tap: TapFile) {
+        if let Some(movie) = &mut self.movie {
+            movie.add_media("tap", self.frame_count, &tap.to_bytes());
+        }
         self.tape.insert(tap);
     }
 
@@ -279,14 +385,58 @@ impl Spectrum {
         &self.tape
     }
 
+    /// Start capturing what the machine saves, discarding any earlier
+    /// capture. See [`RecordMode`] for the two ways to capture.
+    pub fn start_tape_recording(&mut self, mode: RecordMode) {
+        self.recorder
+            .start(mode, self.bus.last_fe_write & 0x08 != 0);
+    }
+
+    /// Stop capturing. The capture stays available until the next start.
+    pub fn stop_tape_recording(&mut self) {
+        self.recorder.stop();
+    }
+
+    /// Reference to the tape recorder.
+    #[must_use]
+    pub fn tape_recorder(&self) -> &TapeRecorder {
+        &self.recorder
+    }
+
     /// Insert a TZX file and start playback.
     pub fn insert_tzx(&mut self, tzx: TzxFile) {
+        self.insert_tape_blocks("tzx", tzx.blocks);
+    }
+
+    /// Insert a PZX file and start playback through the TZX signal.
+    pub fn insert_pzx(&mut self, pzx: PzxFile) {
+        self.insert_tape_blocks("pzx", tzx_signal::pzx_blocks(pzx));
+    }
+
+    /// Insert a CSW recording and start playback through the TZX signal.
+    pub fn insert_csw(&mut self, csw: CswFile) {
+        self.insert_tape_blocks("csw", tzx_signal::csw_blocks(csw));
+    }
+
+    /// Play `blocks` through the TZX signal. `slot` names the file type
+    /// for the movie being recorded.
+    fn insert_tape_blocks(&mut self, slot: &str, blocks: Vec<TzxBlock>) {
+        if let Some(movie) = &mut self.movie {
+            movie.add_media(slot, self.frame_count, &tape_bytes(&blocks));
+        }
         let is_48k = self.model == SpectrumModel::Spectrum48K;
-        let mut signal = TzxSignal::new(tzx.blocks, is_48k, CPU_FREQUENCY);
+        let mut signal = TzxSignal::new(blocks, is_48k, CPU_FREQUENCY);
         signal.play();
         self.tzx_signal = Some(signal);
     }
 
+    /// Choose the entry the inserted tape follows at TZX select blocks.
+    pub fn select_tape_entry(&mut self, choice: usize) {
+        if let Some(signal) = &mut self.tzx_signal {
+            signal.set_select_choice(choice);
+        }
+    }
+
     /// Eject the TZX tape and restore MIC loopback.
     pub fn eject_tzx(&mut self) {
         self.tzx_signal = None;
@@ -310,6 +460,9 @@ impl Spectrum {
         let image = nec_upd765::dsk::parse_dsk(data)?;
         let fdc = self.bus.fdc.as_mut().ok_or("No FDC (not a +3 model)")?;
         fdc.insert_disk(0, image);
+        if let Some(movie) = &mut self.movie {
+            movie.add_media("disk", self.frame_count, data);
+        }
         Ok(())
     }
 
@@ -326,6 +479,211 @@ impl Spectrum {
         self.model
     }
 
+    /// Serialise the complete machine state.
+    ///
+    /// The scripted input queue is host-side and not included.
+    #[must_use]
+    pub fn save_state(&self) -> Vec<u8> {
+        let mut w = StateWriter::with_header(STATE_TAG);
+        w.write_str(&format!("{:?}", self.model));
+        w.write_u64(self.master_clock);
+        w.write_u64(self.cpu_divider);
+        w.write_u64(self.frame_count);
+        w.write_bool(self.ay_toggle);
+        self.cpu.save_state(&mut w);
+        self.bus.save_state(&mut w);
+        self.tape.save_state(&mut w);
+        w.write_option(self.tzx_signal.as_ref());
+        w.into_bytes()
+    }
+
+    /// Restore a state produced by [`Spectrum::save_state`].
+    ///
+    /// The machine must be the same model with the same tape inserted. On
+    /// error the machine is left partially restored and should be reset.
+    pub fn load_state(&mut self, data: &[u8]) -> Result<(), StateError> {
+        let mut r = StateReader::with_header(data, STATE_TAG)?;
+        let model = r.read_string()?;
+        if model != format!("{:?}", self.model) {
+            return Err(StateError::Invalid(format!(
+                "state is for {model}, machine is {:?}",
+                self.model
+            )));
+        }
+        self.master_clock = r.read_u64()?;
+        self.cpu_divider = r.read_u64()?;
+        self.frame_count = r.read_u64()?;
+        self.ay_toggle = r.read_bool()?;
+        self.cpu.load_state(&mut r)?;
+        self.bus.load_state(&mut r)?;
+        self.tape.load_state(&mut r)?;
+        r.read_option(self.tzx_signal.as_mut(), "a TZX tape")?;
+        r.finish()
+    }
+
+    /// Start recording an input movie.
+    ///
+    /// `config` must be the configuration this machine was built from.
+    /// Recording from frame 0 gives a power-on movie, otherwise the current
+    /// state is embedded. Live key changes, Kempston input and queued
+    /// events are all recorded.
+    pub fn start_recording(&mut self, config: &SpectrumConfig) {
+        let mut movie = movie_header(config);
+        movie.start_frame = self.frame_count;
+        if self.frame_count != 0 {
+            movie.start_state = Some(self.save_state());
+        }
+        self.movie = Some(movie);
+    }
+
+    /// Stop recording and return the movie.
+    ///
+    /// Tapes and disks inserted while recording are in its media list;
+    /// insert any needed from the start before recording.
+    pub fn stop_recording(&mut self) -> Option<Movie> {
+        self.movie.take()
+    }
+
+    /// Whether an input movie is being recorded.
+    #[must_use]
+    pub fn is_recording(&self) -> bool {
+        self.movie.is_some()
+    }
+
+    /// Set up playback of a movie.
+    ///
+    /// Checks the header against `config`, restores the start snapshot (or
+    /// requires a machine that has not run yet) and queues every event.
+    /// `media` holds (slot, file) pairs for what was inserted while
+    /// recording (`"tap"`, `"tzx"`, `"pzx"`, `"csw"` or `"disk"`), one pair
+    /// per file when a slot took several; each is checked and inserted
+    /// again at its recorded frame. Run frames as
+    /// normal afterwards, or use [`Movie::verify`].
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the movie was recorded with another model, ROM
+    /// or media, names an unknown key, or its snapshot cannot be loaded.
+    pub fn play_movie(
+        &mut self,
+        movie: &Movie,
+        config: &SpectrumConfig,
+        media: &[(&str, &[u8])],
+    ) -> Result<(), MovieError> {
+        let header = movie_header(config);
+        movie.check_header(&header)?;
+        // Tapes are hashed as recorded: parsed, then written back out.
+        let images: Vec<_> = media
+            .iter()
+            .filter_map(|&(slot, data)| Some((slot, MovieImage::parse(slot, data)?)))
+            .collect();
+        let hashed: Vec<_> = images
+            .iter()
+            .map(|(slot, image)| (*slot, image.to_bytes()))
+            .collect();
+        let loads = movie.media_loads(&header, &hashed)?;
+        let events = movie
+            .events
+            .iter()
+            .map(|e| {
+                let key = SpectrumKey::from_name(&e.input)
+                    .ok_or_else(|| MovieError::UnknownInput(e.input.clone()))?;
+                Ok(InputEvent {
+                    frame: e.frame,
+                    key,
+                    pressed: e.pressed,
+                })
+            })
+            .collect::<Result<Vec<_>, MovieError>>()?;
+        match &movie.start_state {
+            Some(state) => self.load_state(state)?,
+            None if self.frame_count != 0 => {
+                return Err(MovieError::NotAtStart {
+                    frame: self.frame_count,
+                });
+            }
+            None => {}
+        }
+        for event in events {
+            self.input_queue.push(event);
+        }
+        self.movie_media = loads
+            .into_iter()
+            .map(|(frame, index)| {
+                let (slot, image) = &images[index];
+                (frame, (*slot).to_string(), image.clone())
+            })
+            .collect();
+        Ok(())
+    }
+
+    /// Insert the movie media due at the start of this frame.
+    fn load_movie_media(&mut self) {
+        if self.movie_media.is_empty() {
+            return;
+        }
+        let frame = self.frame_count;
+        let (due, later) = std::mem::take(&mut self.movie_media)
+            .into_iter()
+            .partition::<Vec<_>, _>(|(at, _, _)| *at <= frame);
+        self.movie_media = later;
+        for (_, slot, image) in due {
+            match image {
+                MovieImage::Tap(tap) => self.insert_tap(tap),
+                MovieImage::Tape(blocks) => self.insert_tape_blocks(&slot, blocks),
+                // The disk loaded when it was recorded, so it loads again.
+                MovieImage::Disk(data) => {
+                    let _ = self.load_dsk(&data);
+                }
+            }
+        }
+    }
+
+    /// Run to the start of the next instruction.
+    ///
+    /// Snapshot formats hold registers between instructions, so call this
+    /// before [`save_sna`](crate::save_sna) or [`save_z80`](crate::save_z80).
+    pub fn run_to_instruction_boundary(&mut self) {
+        while !self.cpu.is_starting_fetch() {
+            self.tick();
+        }
+    }
+
+    /// Attach an instruction trace recorder (or detach with `None`).
+    /// Returns the one it replaces.
+    pub fn set_tracer(&mut self, tracer: Option<Tracer>) -> Option<Tracer> {
+        std::mem::replace(&mut self.tracer, tracer)
+    }
+
+    #[must_use]
+    pub fn tracer(&self) -> Option<&Tracer> {
+        self.tracer.as_ref()
+    }
+
+    pub fn tracer_mut(&mut self) -> Option<&mut Tracer> {
+        self.tracer.as_mut()
+    }
+
+    /// One CPU T-state, logging bus cycles for the debugger and the
+    /// tracer when either wants them.
+    fn tick_cpu(&mut self) {
+        let mark = self.bus_log.as_ref().map_or(0, Vec::len);
+        if let Some(log) = trace::cpu_bus_log(&mut self.bus_log, &mut self.tracer) {
+            self.cpu.tick(&mut LoggingBus::new(&mut self.bus, log));
+        } else {
+            self.cpu.tick(&mut self.bus);
+        }
+
+        if let Some(mut tracer) = self.tracer.take() {
+            let accesses = self.bus_log.as_deref().map_or(&[][..], |log| &log[mark..]);
+            tracer.observe(&*self, accesses, |pc| {
+                let memory = &self.bus.memory;
+                emu_disasm::z80::disassemble(|addr| memory.peek(addr), pc).bytes
+            });
+            self.tracer = Some(tracer);
+        }
+    }
+
     /// Check for and handle the ROM tape-loading trap.
     ///
     /// The Spectrum ROM's `LD-BYTES` routine at $0556 is the standard entry
@@ -386,6 +744,35 @@ impl Spectrum {
         self.pop_ret();
     }
 
+    /// ROM trap for SA-BYTES, while recording with [`RecordMode::Trap`].
+    ///
+    /// Register conventions on entry to SA-BYTES:
+    ///   A  = flag byte ($00 for header, $FF for data)
+    ///   DE = number of bytes to save
+    ///   IX = source address in memory
+    ///
+    /// The block goes to the recorder and we return to the caller at once,
+    /// skipping the signal, the border stripes and the BREAK check.
+    fn check_save_trap(&mut self) {
+        if self.cpu.regs.pc != SA_BYTES_ADDR
+            || !self.recorder.is_trapping()
+            || !self.cpu.is_starting_fetch()
+        {
+            return;
+        }
+        if (0..3).any(|i| self.bus.memory.peek(SA_BYTES_ADDR + i) != SA_BYTES_CODE[usize::from(i)])
+        {
+            return;
+        }
+
+        let source = self.cpu.regs.ix;
+        let data = (0..self.cpu.regs.de())
+            .map(|i| self.bus.memory.read(source.wrapping_add(i)))
+            .collect();
+        self.recorder.push_block(self.cpu.regs.a, data);
+        self.pop_ret();
+    }
+
     /// Pop the return address from the stack and redirect the CPU to it.
     fn pop_ret(&mut self) {
         let sp = self.cpu.regs.sp;
@@ -441,7 +828,8 @@ impl format_sna::SnapshotTarget for Spectrum {
     }
 
     fn write_bank_register(&mut self, val: u8) {
-        self.bus.memory.write_bank_register(val);
+        // A snapshot may have paging locked; it still needs every bank.
+        self.bus.memory.restore_bank_register(val);
     }
 
     fn set_ay_register(&mut self, reg: u8, val: u8) {
@@ -456,16 +844,156 @@ impl format_sna::SnapshotTarget for Spectrum {
             ay.select_register(reg);
         }
     }
+
+    fn write_plus3_register(&mut self, val: u8) {
+        self.bus.memory.restore_plus3_register(val);
+    }
+
+    fn set_frame_tstate(&mut self, tstate: u32) {
+        self.bus.ula.set_frame_tstate(tstate);
+    }
+}
+
+impl format_sna::SnapshotSource for Spectrum {
+    fn model(&self) -> format_sna::SnapshotModel {
+        use format_sna::SnapshotModel;
+        match self.model {
+            SpectrumModel::Spectrum128K => SnapshotModel::Spectrum128K,
+            SpectrumModel::SpectrumPlus2 => SnapshotModel::SpectrumPlus2,
+            SpectrumModel::SpectrumPlus2A => SnapshotModel::SpectrumPlus2A,
+            SpectrumModel::SpectrumPlus3 => SnapshotModel::SpectrumPlus3,
+            // Only the Sinclair models can be built.
+            _ => SnapshotModel::Spectrum48K,
+        }
+    }
+
+    fn registers(&self) -> format_sna::Z80Registers {
+        let r = &self.cpu.regs;
+        format_sna::Z80Registers {
+            a: r.a,
+            f: r.f,
+            b: r.b,
+            c: r.c,
+            d: r.d,
+            e: r.e,
+            h: r.h,
+            l: r.l,
+            a_alt: r.a_alt,
+            f_alt: r.f_alt,
+            b_alt: r.b_alt,
+            c_alt: r.c_alt,
+            d_alt: r.d_alt,
+            e_alt: r.e_alt,
+            h_alt: r.h_alt,
+            l_alt: r.l_alt,
+            ix: r.ix,
+            iy: r.iy,
+            sp: r.sp,
+            // The CPU steps PC past a HALT; snapshots point at it.
+            pc: if r.halted { r.pc.wrapping_sub(1) } else { r.pc },
+            i: r.i,
+            r: r.r,
+            im: r.im,
+            iff1: r.iff1,
+            iff2: r.iff2,
+            halted: r.halted,
+        }
+    }
+
+    fn ram_bank(&self, bank: u8) -> &[u8] {
+        self.bus.memory.ram_bank(bank)
+    }
+
+    fn border(&self) -> u8 {
+        self.bus.ula.border_colour()
+    }
+
+    fn bank_register(&self) -> u8 {
+        self.bus.memory.bank_register()
+    }
+
+    fn plus3_register(&self) -> u8 {
+        self.bus.memory.plus3_register()
+    }
+
+    fn ay_registers(&self) -> Option<([u8; 16], u8)> {
+        self.bus
+            .ay
+            .as_ref()
+            .map(|ay| (ay.registers(), ay.selected_register()))
+    }
+
+    fn frame_tstate(&self) -> u32 {
+        self.bus.ula.frame_tstate()
+    }
+}
+
+/// Movie header describing a machine built from `config`.
+fn movie_header(config: &SpectrumConfig) -> Movie {
+    Movie::new(STATE_TAG)
+        .with_config("model", format!("{:?}", config.model))
+        .with_media("rom", &config.rom)
+}
+
+/// The bytes a movie hashes for a tape played through the TZX signal.
+///
+/// PZX and CSW have no writer, so every such tape is hashed as the TZX
+/// blocks it plays.
+fn tape_bytes(blocks: &[TzxBlock]) -> Vec<u8> {
+    TzxFile {
+        major: 1,
+        minor: 20,
+        blocks: blocks.to_vec(),
+    }
+    .to_bytes()
+}
+
+/// A tape or disk a movie inserts, parsed from its file.
+#[derive(Clone)]
+enum MovieImage {
+    Tap(TapFile),
+    Tape(Vec<TzxBlock>),
+    Disk(Vec<u8>),
+}
+
+impl MovieImage {
+    /// Parse the file for a movie media slot, or `None` if it does not
+    /// parse or the slot is unknown.
+    fn parse(slot: &str, data: &[u8]) -> Option<Self> {
+        match slot {
+            "tap" => TapFile::parse(data).ok().map(Self::Tap),
+            "tzx" => TzxFile::parse(data).ok().map(|tzx| Self::Tape(tzx.blocks)),
+            "pzx" => PzxFile::parse(data)
+                .ok()
+                .map(|pzx| Self::Tape(tzx_signal::pzx_blocks(pzx))),
+            "csw" => CswFile::parse(data)
+                .ok()
+                .map(|csw| Self::Tape(tzx_signal::csw_blocks(csw))),
+            "disk" => Some(Self::Disk(data.to_vec())),
+            _ => None,
+        }
+    }
+
+    /// The bytes hashed when the image was inserted while recording.
+    fn to_bytes(&self) -> Vec<u8> {
+        match self {
+            Self::Tap(tap) => tap.to_bytes(),
+            Self::Tape(blocks) => tape_bytes(blocks),
+            Self::Disk(data) => data.clone(),
+        }
+    }
 }
 
 impl Tickable for Spectrum {
     fn tick(&mut self) {
         self.master_clock += 1;
+        self.profiler.start();
 
         // Video ticks at 7 MHz (every 2 crystal ticks)
         if self.master_clock.is_multiple_of(VIDEO_DIVIDER) {
             let mem = &*self.bus.memory;
             self.bus.ula.tick(|addr| mem.vram_peek(addr));
+            self.profiler.lap(0);
         }
 
         // CPU ticks at 3.5 MHz (every 4 crystal ticks)
@@ -478,20 +1006,25 @@ impl Tickable for Spectrum {
                     self.bus.tape_ear = None;
                 }
             }
+            self.recorder.sample(self.bus.last_fe_write & 0x08 != 0);
+            self.profiler.lap(1);
 
             // Check INT from ULA
             if self.bus.ula.int_active() {
                 self.cpu.interrupt();
             }
-            self.cpu.tick(&mut self.bus);
+            self.tick_cpu();
             // ROM trap: only when no TZX signal is driving the EAR bit.
             // TZX loading uses the ROM's own LD-BYTES via real signal timing,
             // so the trap must not short-circuit it.
             if self.bus.tape_ear.is_none() {
                 self.check_tape_trap();
             }
+            self.check_save_trap();
+            self.profiler.lap(2);
             // Sample audio at CPU rate
             self.bus.beeper.sample();
+            self.profiler.lap(3);
 
             // AY clocks at half CPU rate (1.7734 MHz)
             self.ay_toggle = !self.ay_toggle;
@@ -499,6 +1032,7 @@ impl Tickable for Spectrum {
                 && let Some(ay) = &mut self.bus.ay
             {
                 ay.tick();
+                self.profiler.lap(4);
             }
         }
     }
@@ -554,6 +1088,31 @@ impl Observable for Spectrum {
     }
 }
 
+impl Debuggable for Spectrum {
+    fn instruction_boundary(&self) -> Option<u32> {
+        self.cpu
+            .is_starting_fetch()
+            .then_some(u32::from(self.cpu.regs.pc))
+    }
+
+    fn raster_position(&self) -> (u32, u32) {
+        (
+            u32::from(self.bus.ula.line()),
+            u32::from(self.bus.ula.line_tstate()),
+        )
+    }
+
+    fn set_bus_logging(&mut self, enabled: bool) {
+        self.bus_log = enabled.then(Vec::new);
+    }
+
+    fn drain_bus_accesses(&mut self, out: &mut Vec<BusAccess>) {
+        if let Some(log) = &mut self.bus_log {
+            out.append(log);
+        }
+    }
+}
+
 impl Machine for Spectrum {
     fn run_frame(&mut self) {
         let _ = self.run_frame();
@@ -575,13 +1134,114 @@ impl Machine for Spectrum {
         self.take_audio_buffer()
     }
 
+    fn audio_sample_rate(&self) -> u32 {
+        self.audio_sample_rate()
+    }
+
+    fn set_audio_sample_rate(&mut self, rate: u32) -> bool {
+        self.set_audio_sample_rate(rate);
+        true
+    }
+
+    fn set_audio_rate_adjust(&mut self, ratio: f64) {
+        self.set_audio_rate_adjust(ratio);
+    }
+
+    fn audio_channels(&self) -> Vec<&'static str> {
+        let count = if self.bus.ay.is_some() { 4 } else { 1 };
+        AUDIO_CHANNELS[..count].to_vec()
+    }
+
+    fn set_channel_mute(&mut self, mask: u32) {
+        self.bus.beeper.set_muted(mask & 1 != 0);
+        if let Some(ay) = &mut self.bus.ay {
+            ay.set_channel_mute((mask >> 1) as u8);
+        }
+    }
+
+    fn channel_mute(&self) -> u32 {
+        let ay = self
+            .bus
+            .ay
+            .as_ref()
+            .map_or(0, |ay| u32::from(ay.channel_mute()) << 1);
+        u32::from(self.bus.beeper.muted()) | ay
+    }
+
+    fn set_stem_capture(&mut self, enabled: bool) {
+        self.bus.beeper.set_stems(enabled);
+        if let Some(ay) = &mut self.bus.ay {
+            ay.set_stems(enabled);
+        }
+    }
+
+    fn take_audio_stems(&mut self) -> Vec<Vec<f32>> {
+        let mut stems = self.bus.beeper.take_stems();
+        if let Some(ay) = &mut self.bus.ay {
+            stems.extend(ay.take_stems());
+            // `take_audio_buffer` mixes beeper and AY at half level each.
+            for s in stems.iter_mut().flatten() {
+                *s *= 0.5;
+            }
+        }
+        stems
+    }
+
+    fn start_register_log(&mut self) -> bool {
+        let Some(ay) = &mut self.bus.ay else {
+            return false;
+        };
+        ay.start_register_log();
+        true
+    }
+
+    fn stop_register_log(&mut self) -> Option<RegisterLog> {
+        // The AY ticks on every other T-state.
+        let ula = &self.bus.ula;
+        let frame = u64::from(ula.tstates_per_line()) * u64::from(ula.lines_per_frame()) / 2;
+        let log = self.bus.ay.as_mut()?.stop_register_log()?;
+        Some(log.with_frame_cycles(frame))
+    }
+
     fn frame_count(&self) -> u64 {
         self.frame_count()
     }
 
+    fn cpu_clock_hz(&self) -> f64 {
+        f64::from(CPU_FREQUENCY)
+    }
+
+    fn frame_rate_hz(&self) -> f64 {
+        let ula = &self.bus.ula;
+        f64::from(CPU_FREQUENCY)
+            / (f64::from(ula.tstates_per_line()) * f64::from(ula.lines_per_frame()))
+    }
+
+    /// A TZX tape is playing.
+    fn media_busy(&self) -> bool {
+        self.is_tzx_playing()
+    }
+
+    fn set_chip_profiling(&mut self, enabled: bool) -> bool {
+        self.profiler.set_enabled(enabled);
+        true
+    }
+
+    fn chip_times(&self) -> Vec<(&'static str, Duration)> {
+        self.profiler.times()
+    }
+
     fn reset(&mut self) {
         self.cpu_mut().reset();
     }
+
+    fn save_state(&self) -> Result<Vec<u8>, StateError> {
+        Ok(self.save_state())
+    }
+
+    fn load_state(&mut self, data: &[u8]) -> Result<(), StateError> {
+        self.load_state(data)
+    }
 }
 
 #[cfg(test)]
@@ -652,6 +1312,224 @@ mod tests {
         assert_eq!(spec.query("memory.0x8000"), Some(Value::U8(0xAB)));
     }
 
+    /// A 48K whose ROM copies the keyboard lines to the border, so every
+    /// key press shows up in the framebuffer.
+    fn key_border_config() -> SpectrumConfig {
+        let mut rom = vec![0u8; 0x4000];
+        // loop: XOR A; IN A,($FE); OUT ($FE),A; JR loop
+        rom[..7].copy_from_slice(&[0xAF, 0xDB, 0xFE, 0xD3, 0xFE, 0x18, 0xF9]);
+        SpectrumConfig {
+            model: SpectrumModel::Spectrum48K,
+            rom,
+        }
+    }
+
+    #[test]
+    fn movie_replays_key_input() {
+        let config = key_border_config();
+        let mut spec = Spectrum::new(&config);
+        spec.start_recording(&config);
+        for frame in 0..12 {
+            match frame {
+                2 => spec.press_key(SpectrumKey::Z),
+                4 => spec.release_all_keys(),
+                5 => spec.input_queue().enqueue_key(SpectrumKey::X, 8, 2),
+                _ => {}
+            }
+            spec.run_frame();
+        }
+        let movie = spec.stop_recording().expect("recording");
+
+        let mut replay = Spectrum::new(&config);
+        replay.play_movie(&movie, &config, &[]).expect("play");
+        assert_eq!(movie.verify(&mut replay), None);
+
+        let mut other = key_border_config();
+        other.model = SpectrumModel::Spectrum128K;
+        other.rom = vec![0; 0x8000];
+        assert!(matches!(
+            Spectrum::new(&other).play_movie(&movie, &other, &[]),
+            Err(MovieError::ConfigMismatch { .. })
+        ));
+    }
+
+    #[test]
+    fn movie_inserts_tapes_at_their_frame() {
+        let config = key_border_config();
+        let tap = TapFile {
+            blocks: vec![format_spectrum_tap::TapBlock::data(vec![1, 2, 3])],
+        }
+        .to_bytes();
+        let mut spec = Spectrum::new(&config);
+        spec.start_recording(&config);
+        for frame in 0..6 {
+            if frame == 3 {
+                spec.insert_tap(TapFile::parse(&tap).expect("tap"));
+            }
+            spec.run_frame();
+        }
+        let movie = spec.stop_recording().expect("recording");
+
+        let mut replay = Spectrum::new(&config);
+        assert_eq!(
+            replay.play_movie(&movie, &config, &[]),
+            Err(MovieError::MediaMismatch { slot: "tap".into() })
+        );
+        replay
+            .play_movie(&movie, &config, &[("tap", &tap)])
+            .expect("play");
+        for _ in 0..3 {
+            replay.run_frame();
+        }
+        assert!(!replay.tape().is_loaded());
+        assert_eq!(movie.verify(&mut replay), None);
+        assert!(replay.tape().is_loaded());
+    }
+
+    #[test]
+    fn movie_swaps_tapes_mid_recording() {
+        let config = key_border_config();
+        let side = |blocks: usize| {
+            TapFile {
+                blocks: (0..blocks)
+                    .map(|i| format_spectrum_tap::TapBlock::data(vec![i as u8; 3]))
+                    .collect(),
+            }
+            .to_bytes()
+        };
+        let (side_a, side_b) = (side(1), side(2));
+        let mut spec = Spectrum::new(&config);
+        spec.start_recording(&config);
+        for frame in 0..8 {
+            if frame == 2 {
+                spec.insert_tap(TapFile::parse(&side_a).expect("tap"));
+            }
+            if frame == 5 {
+                spec.insert_tap(TapFile::parse(&side_b).expect("tap"));
+            }
+            spec.run_frame();
+        }
+        let movie = spec.stop_recording().expect("recording");
+
+        let mut replay = Spectrum::new(&config);
+        assert_eq!(
+            replay.play_movie(&movie, &config, &[("tap", &side_a)]),
+            Err(MovieError::MediaMismatch { slot: "tap".into() })
+        );
+        replay
+            .play_movie(&movie, &config, &[("tap", &side_b), ("tap", &side_a)])
+            .expect("play");
+        for _ in 0..4 {
+            replay.run_frame();
+        }
+        assert_eq!(replay.tape().block_count(), 1);
+        assert_eq!(movie.verify(&mut replay), None);
+        assert_eq!(replay.tape().block_count(), 2);
+    }
+
+    /// Run a few thousand crystal ticks of a busy loop that writes to
+    /// screen memory, so the state captures a mid-frame, mid-instruction CPU.
+    fn make_busy_spectrum(model: SpectrumModel, rom_len: usize) -> Spectrum {
+        let mut rom = vec![0u8; rom_len];
+        // LD HL,$4000; loop: INC (HL); INC HL; OUT ($FE),A; INC A; JR loop
+        rom[..10].copy_from_slice(&[0x21, 0x00, 0x40, 0x34, 0x23, 0xD3, 0xFE, 0x3C, 0x18, 0xF9]);
+        Spectrum::new(&SpectrumConfig { model, rom })
+    }
+
+    #[test]
+    fn state_restored_mid_frame_continues_identically() {
+        for (model, rom_len) in [
+            (SpectrumModel::Spectrum48K, 0x4000),
+            (SpectrumModel::Spectrum128K, 0x8000),
+            (SpectrumModel::SpectrumPlus3, 0x10000),
+        ] {
+            let mut spec = make_busy_spectrum(model, rom_len);
+            spec.run_frame();
+            for _ in 0..123_457 {
+                spec.tick();
+            }
+            let state = Machine::save_state(&spec).expect("save");
+
+            let mut restored = make_busy_spectrum(model, rom_len);
+            Machine::load_state(&mut restored, &state).expect("load");
+
+            for _ in 0..3 {
+                spec.run_frame();
+                restored.run_frame();
+            }
+            assert_eq!(spec.framebuffer(), restored.framebuffer(), "{model:?}");
+            assert_eq!(spec.cpu().regs, restored.cpu().regs, "{model:?}");
+            assert_eq!(spec.master_clock(), restored.master_clock());
+            assert_eq!(spec.take_audio_buffer(), restored.take_audio_buffer());
+        }
+    }
+
+    #[test]
+    fn state_for_other_model_is_rejected() {
+        let spec = make_busy_spectrum(SpectrumModel::Spectrum48K, 0x4000);
+        let state = spec.save_state();
+        let mut other = make_busy_spectrum(SpectrumModel::Spectrum128K, 0x8000);
+        assert!(matches!(
+            other.load_state(&state),
+            Err(StateError::Invalid(_))
+        ));
+    }
+
+    #[test]
+    fn z80_snapshot_round_trips_running_machine() {
+        use format_sna::{SnapshotSource, SnapshotTarget};
+
+        for (model, rom_len) in [
+            (SpectrumModel::Spectrum128K, 0x8000),
+            (SpectrumModel::SpectrumPlus3, 0x10000),
+        ] {
+            let mut spec = make_busy_spectrum(model, rom_len);
+            // Page bank 3 at $C000 with paging locked, as 48 BASIC does.
+            spec.bus.memory.restore_bank_register(0x23);
+            spec.set_ay_register(7, 0x38);
+            spec.select_ay_register(7);
+            spec.run_frame();
+            for _ in 0..12_345 {
+                spec.tick();
+            }
+            spec.run_to_instruction_boundary();
+            let data = crate::save_z80(&spec);
+
+            let mut restored = make_busy_spectrum(model, rom_len);
+            crate::load_z80(&mut restored, &data).expect("load");
+            let (a, b) = (spec.registers(), restored.registers());
+            assert_eq!(
+                (a.pc, a.sp, a.a, a.f, a.h, a.l),
+                (b.pc, b.sp, b.a, b.f, b.h, b.l)
+            );
+            assert_eq!(restored.bank_register(), 0x23, "{model:?}");
+            assert_eq!(restored.ay_registers(), spec.ay_registers());
+            assert_eq!(restored.border(), spec.border());
+            assert_eq!(restored.frame_tstate(), spec.frame_tstate());
+            for bank in 0..8 {
+                assert_eq!(restored.ram_bank(bank), spec.ram_bank(bank), "bank {bank}");
+            }
+        }
+    }
+
+    #[test]
+    fn sna_snapshot_round_trips_48k_machine() {
+        use format_sna::SnapshotSource;
+
+        let mut spec = make_busy_spectrum(SpectrumModel::Spectrum48K, 0x4000);
+        spec.cpu.regs.sp = 0xFF00;
+        spec.run_frame();
+        spec.run_to_instruction_boundary();
+        let data = crate::save_sna(&spec).expect("save");
+        assert_eq!(data.len(), 49179);
+
+        let mut restored = make_busy_spectrum(SpectrumModel::Spectrum48K, 0x4000);
+        crate::load_sna(&mut restored, &data).expect("load");
+        assert_eq!(restored.registers().pc, spec.registers().pc);
+        assert_eq!(restored.registers().sp, 0xFF00);
+        assert_eq!(restored.ram_bank(5), spec.ram_bank(5));
+    }
+
     /// Load the real 48K ROM (skips if not available).
     fn make_spectrum_real_rom() -> Option<Spectrum> {
         let rom_path = concat!(env!("CARGO_MANIFEST_DIR"), "/../../roms/48.rom");
@@ -742,4 +1620,138 @@ mod tests {
             spec.tape.block_index()
         );
     }
+
+    /// Type a one-line program and `SAVE "x"`, then record in `mode` while
+    /// answering the prompt and running `frames` frames. Recording starts
+    /// after the typing so key clicks on MIC are not captured.
+    fn save_with_real_rom(mode: RecordMode, frames: u32) -> Option<Spectrum> {
+        let mut spec = make_spectrum_real_rom()?;
+        for _ in 0..200 {
+            spec.run_frame();
+        }
+        let frame = spec.frame_count();
+        let typed = spec.input_queue().enqueue_text("10e\ns\"x\"\n", frame);
+        while spec.frame_count() < typed + 25 {
+            spec.run_frame();
+        }
+        spec.start_tape_recording(mode);
+        spec.input_queue().enqueue_text("\n", typed + 25);
+        for _ in 0..frames {
+            spec.run_frame();
+        }
+        spec.stop_tape_recording();
+        Some(spec)
+    }
+
+    #[test]
+    #[ignore] // Requires real ROM
+    fn save_trap_and_signal_capture_the_same_blocks() {
+        let Some(trapped) = save_with_real_rom(RecordMode::Trap, 150) else {
+            eprintln!("Skipping: 48K ROM not found");
+            return;
+        };
+        let tap = trapped.tape_recorder().to_tap().expect("trap capture");
+        assert_eq!(tap.blocks.len(), 2);
+        assert_eq!(tap.blocks[0].flag, 0x00);
+        assert_eq!(&tap.blocks[0].data[1..11], b"x         ");
+        assert_eq!(tap.blocks[1].flag, 0xFF);
+
+        let Some(sampled) = save_with_real_rom(RecordMode::Signal, 600) else {
+            return;
+        };
+        let tzx = sampled.tape_recorder().to_tzx();
+        assert!(
+            tzx.blocks
+                .iter()
+                .all(|b| matches!(b, TzxBlock::StandardSpeed { .. })),
+            "{:?}",
+            tzx.blocks
+                .iter()
+                .map(std::mem::discriminant)
+                .collect::<Vec<_>>()
+        );
+        assert_eq!(
+            sampled
+                .tape_recorder()
+                .to_tap()
+                .expect("signal capture")
+                .to_bytes(),
+            tap.to_bytes()
+        );
+    }
+
+    #[test]
+    fn save_trap_takes_block_and_returns() {
+        let mut rom = vec![0u8; 0x4000];
+        rom[SA_BYTES_ADDR as usize..][..3].copy_from_slice(&SA_BYTES_CODE);
+        let mut spec = Spectrum::new(&SpectrumConfig {
+            model: SpectrumModel::Spectrum48K,
+            rom,
+        });
+        // CALL SA-BYTES; HALT
+        for (i, byte) in [0xCD, 0xC2, 0x04, 0x76].into_iter().enumerate() {
+            spec.bus.memory.write(0x8000 + i as u16, byte);
+        }
+        for (i, byte) in [0x11, 0x22, 0x33].into_iter().enumerate() {
+            spec.bus.memory.write(0x9000 + i as u16, byte);
+        }
+        spec.cpu.regs.sp = 0xFF00;
+        spec.cpu.regs.a = 0xFF;
+        spec.cpu.regs.set_de(3);
+        spec.cpu.regs.ix = 0x9000;
+        spec.cpu.force_pc(0x8000);
+
+        spec.start_tape_recording(RecordMode::Trap);
+        for _ in 0..200 {
+            spec.tick();
+        }
+        assert_eq!(spec.cpu.regs.sp, 0xFF00);
+        assert_eq!(spec.cpu.regs.pc, 0x8004, "halted after the CALL");
+        let tap = spec.tape_recorder().to_tap().expect("tap");
+        assert_eq!(tap.blocks.len(), 1);
+        assert_eq!(
+            (tap.blocks[0].flag, &tap.blocks[0].data[..]),
+            (0xFF, &[0x11, 0x22, 0x33][..])
+        );
+    }
+
+    #[test]
+    fn signal_recording_samples_mic() {
+        let mut spec = make_spectrum();
+        // LD A,$08; OUT ($FE),A; XOR A; OUT ($FE),A; HALT
+        for (i, byte) in [0x3E, 0x08, 0xD3, 0xFE, 0xAF, 0xD3, 0xFE, 0x76]
+            .into_iter()
+            .enumerate()
+        {
+            spec.bus.memory.write(0x8000 + i as u16, byte);
+        }
+        spec.cpu.force_pc(0x8000);
+        spec.start_tape_recording(RecordMode::Signal);
+        for _ in 0..400 {
+            spec.tick();
+        }
+        spec.stop_tape_recording();
+
+        let recorder = spec.tape_recorder();
+        assert_eq!(recorder.pulse_count(), 2, "high pulse, then low to the end");
+        assert_eq!(recorder.block_count(), 0);
+        assert_eq!(recorder.to_tzx().blocks.len(), 1);
+    }
+
+    #[test]
+    fn media_busy_until_tape_ends() {
+        let mut spec = make_spectrum();
+        assert!(!Machine::media_busy(&spec));
+        spec.insert_tzx(TzxFile {
+            major: 1,
+            minor: 20,
+            blocks: vec![crate::tzx::TzxBlock::PureTone {
+                pulse_len: 100,
+                count: 10,
+            }],
+        });
+        assert!(Machine::media_busy(&spec));
+        spec.run_frame();
+        assert!(!Machine::media_busy(&spec));
+    }
 }
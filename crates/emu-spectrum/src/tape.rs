@@ -3,6 +3,8 @@
 //! Manages the currently loaded TAP file and tracks which block to
 //! deliver next when the ROM tape loading routine is trapped.
 
+use emu_core::{SaveState, StateError, StateReader, StateWriter};
+
 use crate::tap::{TapBlock, TapFile};
 
 /// Virtual tape deck: holds a TAP file and a block cursor.
@@ -69,6 +71,28 @@ impl TapeDeck {
     }
 }
 
+/// Only the cursor is saved. The tape itself is read-only media: the same
+/// TAP must be inserted before loading, which the block count guards.
+impl SaveState for TapeDeck {
+    fn save_state(&self, w: &mut StateWriter) {
+        w.write_bool(self.is_loaded());
+        w.write_usize(self.block_count());
+        w.write_usize(self.block_index);
+    }
+
+    fn load_state(&mut self, r: &mut StateReader<'_>) -> Result<(), StateError> {
+        let loaded = r.read_bool()?;
+        let count = r.read_usize()?;
+        if loaded != self.is_loaded() || count != self.block_count() {
+            return Err(StateError::Invalid(
+                "inserted tape does not match the saved tape".into(),
+            ));
+        }
+        self.block_index = r.read_usize()?;
+        Ok(())
+    }
+}
+
 impl Default for TapeDeck {
     fn default() -> Self {
         Self::new()
@@ -0,0 +1,582 @@
+//! Tape recorder — captures what the machine saves.
+//!
+//! Two capture paths, chosen when recording starts:
+//!
+//! - **ROM trap** ([`RecordMode::Trap`]): the Spectrum traps the ROM's
+//!   SA-BYTES routine and hands each block to
+//!   [`push_block`](TapeRecorder::push_block). Saving is instant and the
+//!   result is a TAP file, but only the ROM's own saver is caught.
+//! - **Signal** ([`RecordMode::Signal`]): the MIC bit is sampled every
+//!   T-state and the time between edges kept. [`to_tzx`](TapeRecorder::to_tzx)
+//!   splits the pulses into blocks at silences of 10 ms or more and
+//!   encodes each one as standard or turbo speed data when it finds a
+//!   pilot tone, two sync pulses and two-pulse bits, pure data for bits
+//!   with no pilot, and a direct recording for anything else. This catches
+//!   custom and turbo savers, at real speed.
+
+#![allow(clippy::cast_possible_truncation)]
+
+use crate::tap::{TapBlock, TapFile};
+use crate::tzx::{TzxBlock, TzxFile};
+use crate::tzx_signal::{ONE_PULSE, PILOT_PULSE, SYNC1_PULSE, SYNC2_PULSE, ZERO_PULSE};
+
+/// T-states per millisecond at the 3.5 MHz clock TZX timings assume.
+const TSTATES_PER_MS: u32 = 3500;
+
+/// A pulse at least this long (10 ms) is a gap between blocks.
+const GAP: u32 = 10 * TSTATES_PER_MS;
+
+/// Longest pause written after a block. Idle time before recording stops
+/// would otherwise become minutes of silence.
+const MAX_PAUSE_MS: u32 = 5000;
+
+/// Fewest pulses of one length taken as a pilot tone.
+const MIN_PILOT: usize = 256;
+
+/// Direct recording sample length: 79 T-states is about 44.3 kHz.
+const SAMPLE_TSTATES: u16 = 79;
+
+/// Pause the ROM leaves after each block, used for trapped saves.
+const ROM_PAUSE_MS: u16 = 1000;
+
+/// How a recording captures what the machine saves.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum RecordMode {
+    /// Trap SA-BYTES and keep each block whole.
+    Trap,
+    /// Sample the MIC output every T-state.
+    Signal,
+}
+
+impl RecordMode {
+    /// Lower-case name, as used by the MCP tools.
+    #[must_use]
+    pub fn name(self) -> &'static str {
+        match self {
+            Self::Trap => "trap",
+            Self::Signal => "signal",
+        }
+    }
+}
+
+/// Virtual tape recorder: the capture in progress, or the last one.
+pub struct TapeRecorder {
+    /// Mode of the current or last capture.
+    mode: RecordMode,
+    recording: bool,
+    /// Blocks caught by the ROM trap.
+    tap: TapFile,
+    /// Completed MIC pulse lengths in T-states, from the first edge.
+    pulses: Vec<u32>,
+    /// Level of the first pulse.
+    first_level: bool,
+    /// Current MIC level.
+    level: bool,
+    /// T-states since the last edge.
+    run: u32,
+    /// Whether an edge has been seen. Silence before the first is dropped.
+    started: bool,
+}
+
+impl TapeRecorder {
+    /// Create an idle recorder with nothing captured.
+    #[must_use]
+    pub fn new() -> Self {
+        Self {
+            mode: RecordMode::Trap,
+            recording: false,
+            tap: TapFile::new(),
+            pulses: Vec::new(),
+            first_level: false,
+            level: false,
+            run: 0,
+            started: false,
+        }
+    }
+
+    /// Start a capture, discarding the last one. `mic` is the MIC level
+    /// now, so the first sample is not taken as an edge.
+    pub fn start(&mut self, mode: RecordMode, mic: bool) {
+        *self = Self::new();
+        self.mode = mode;
+        self.recording = true;
+        self.level = mic;
+    }
+
+    /// Stop capturing. What was captured stays until the next start.
+    pub fn stop(&mut self) {
+        self.recording = false;
+    }
+
+    /// Whether a capture is in progress.
+    #[must_use]
+    pub fn is_recording(&self) -> bool {
+        self.recording
+    }
+
+    /// Mode of the current or last capture.
+    #[must_use]
+    pub fn mode(&self) -> RecordMode {
+        self.mode
+    }
+
+    /// Whether the SA-BYTES trap should fire.
+    #[must_use]
+    pub fn is_trapping(&self) -> bool {
+        self.recording && self.mode == RecordMode::Trap
+    }
+
+    /// Append a block caught by the ROM trap.
+    pub fn push_block(&mut self, flag: u8, data: Vec<u8>) {
+        self.tap.blocks.push(TapBlock::new(flag, data));
+    }
+
+    /// Take one T-state's MIC level, while recording the signal.
+    #[inline]
+    pub fn sample(&mut self, mic: bool) {
+        if !self.recording || self.mode != RecordMode::Signal {
+            return;
+        }
+        if mic != self.level {
+            if self.started {
+                self.pulses.push(self.run);
+            } else {
+                self.started = true;
+                self.first_level = mic;
+            }
+            self.level = mic;
+            self.run = 0;
+        }
+        self.run = self.run.saturating_add(1);
+    }
+
+    /// Blocks caught by the ROM trap.
+    #[must_use]
+    pub fn block_count(&self) -> usize {
+        self.tap.blocks.len()
+    }
+
+    /// Signal pulses captured so far, including the one in progress.
+    #[must_use]
+    pub fn pulse_count(&self) -> usize {
+        self.pulses.len() + usize::from(self.started)
+    }
+
+    /// The capture as a TZX file. Trapped blocks become standard speed
+    /// blocks; a signal capture is encoded as described in the module
+    /// documentation.
+    #[must_use]
+    pub fn to_tzx(&self) -> TzxFile {
+        let blocks = match self.mode {
+            RecordMode::Trap => self
+                .tap
+                .blocks
+                .iter()
+                .map(|block| TzxBlock::StandardSpeed {
+                    pause_ms: ROM_PAUSE_MS,
+                    // TAP bytes less the length word: flag, data, checksum.
+                    data: block.to_bytes()[2..].to_vec(),
+                })
+                .collect(),
+            RecordMode::Signal => {
+                let mut pulses = self.pulses.clone();
+                if self.started {
+                    pulses.push(self.run);
+                }
+                encode_pulses(&pulses, self.first_level)
+            }
+        };
+        TzxFile {
+            major: 1,
+            minor: 20,
+            blocks,
+        }
+    }
+
+    /// The capture as a TAP file.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if a signal capture holds anything but standard
+    /// speed blocks, which TAP cannot store.
+    pub fn to_tap(&self) -> Result<TapFile, String> {
+        if self.mode == RecordMode::Trap {
+            return Ok(self.tap.clone());
+        }
+        let mut tap = TapFile::new();
+        for block in self.to_tzx().blocks {
+            match block {
+                TzxBlock::StandardSpeed { data, .. } if data.len() >= 2 => {
+                    tap.blocks
+                        .push(TapBlock::new(data[0], data[1..data.len() - 1].to_vec()));
+                }
+                TzxBlock::Pause { .. } => {}
+                _ => {
+                    return Err(
+                        "the recording is not all ROM-speed blocks; save it as TZX".to_string()
+                    );
+                }
+            }
+        }
+        Ok(tap)
+    }
+}
+
+impl Default for TapeRecorder {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+// ---------------------------------------------------------------------------
+// Signal encoding
+// ---------------------------------------------------------------------------
+
+/// Whether `value` is within 1/8 of `target`.
+fn near(value: u32, target: u32) -> bool {
+    value.abs_diff(target) * 8 <= target
+}
+
+fn mean(pulses: &[u32]) -> u32 {
+    let sum: u64 = pulses.iter().map(|&p| u64::from(p)).sum();
+    let n = pulses.len().max(1) as u64;
+    ((sum + n / 2) / n) as u32
+}
+
+/// Split the pulses at gaps and encode each run between them.
+fn encode_pulses(pulses: &[u32], first_level: bool) -> Vec<TzxBlock> {
+    let mut blocks = Vec::new();
+    let mut start = 0;
+    for (i, &pulse) in pulses.iter().enumerate() {
+        if pulse < GAP {
+            continue;
+        }
+        let pause_ms = (pulse / TSTATES_PER_MS).min(MAX_PAUSE_MS) as u16;
+        if start < i {
+            let level = first_level ^ (start % 2 == 1);
+            blocks.push(encode_block(&pulses[start..i], level, pause_ms));
+        } else if !blocks.is_empty() {
+            blocks.push(TzxBlock::Pause {
+                duration_ms: pause_ms,
+            });
+        }
+        start = i + 1;
+    }
+    if start < pulses.len() {
+        let level = first_level ^ (start % 2 == 1);
+        blocks.push(encode_block(&pulses[start..], level, 0));
+    }
+    blocks
+}
+
+/// Encode one run of pulses with no gap inside. `level` is the MIC level
+/// of its first pulse.
+fn encode_block(pulses: &[u32], level: bool, pause_ms: u16) -> TzxBlock {
+    if let Some(block) = pilot_block(pulses, pause_ms) {
+        return block;
+    }
+    if let Some(bits) = DataBits::decode(pulses) {
+        return TzxBlock::PureData {
+            zero_pulse: bits.zero,
+            one_pulse: bits.one,
+            used_bits: bits.used_bits,
+            pause_ms,
+            data: bits.data,
+        };
+    }
+    direct_recording(pulses, level, pause_ms)
+}
+
+/// A pilot tone, two sync pulses and data bits: a standard speed block if
+/// every timing is the ROM's, otherwise turbo speed. The first pulse is
+/// taken as pilot whatever its length, since a saver's setup code often
+/// stretches or shortens it.
+fn pilot_block(pulses: &[u32], pause_ms: u16) -> Option<TzxBlock> {
+    let reference = *pulses.get(1)?;
+    let pilot_count = 1 + pulses[1..]
+        .iter()
+        .take_while(|&&p| near(p, reference))
+        .count();
+    if pilot_count < MIN_PILOT || pilot_count > usize::from(u16::MAX) {
+        return None;
+    }
+    let pilot = mean(&pulses[1..pilot_count]);
+    let sync1 = *pulses.get(pilot_count)?;
+    let sync2 = *pulses.get(pilot_count + 1)?;
+    let bits = DataBits::decode(&pulses[pilot_count + 2..])?;
+
+    let rom_timing = near(pilot, u32::from(PILOT_PULSE))
+        && near(sync1, u32::from(SYNC1_PULSE))
+        && near(sync2, u32::from(SYNC2_PULSE))
+        && near(u32::from(bits.zero), u32::from(ZERO_PULSE))
+        && near(u32::from(bits.one), u32::from(ONE_PULSE));
+    if rom_timing && bits.used_bits == 8 && u16::try_from(bits.data.len()).is_ok() {
+        return Some(TzxBlock::StandardSpeed {
+            pause_ms,
+            data: bits.data,
+        });
+    }
+    Some(TzxBlock::TurboSpeed {
+        pilot_pulse: pilot as u16,
+        sync1: sync1 as u16,
+        sync2: sync2 as u16,
+        zero_pulse: bits.zero,
+        one_pulse: bits.one,
+        pilot_count: pilot_count as u16,
+        used_bits: bits.used_bits,
+        pause_ms,
+        data: bits.data,
+    })
+}
+
+/// Bits sent as pairs of equal pulses, one length for 0 and a longer one
+/// for 1.
+struct DataBits {
+    zero: u16,
+    one: u16,
+    used_bits: u8,
+    data: Vec<u8>,
+}
+
+impl DataBits {
+    /// Decode pulse pairs, or `None` if the pulses do not fall into two
+    /// lengths or a pair is mismatched. An odd last pulse is taken to be
+    /// the first half of a bit whose second half ran into the gap.
+    fn decode(pulses: &[u32]) -> Option<Self> {
+        if pulses.len() < 2 {
+            return None;
+        }
+        let min = *pulses.iter().min()?;
+        let max = *pulses.iter().max()?;
+        let (zero, one) = if near(max, min) {
+            // One length only: call them zeros, with ones twice as long.
+            let zero = mean(pulses);
+            (zero, (zero * 2).min(u32::from(u16::MAX)))
+        } else {
+            let split = min.midpoint(max);
+            let short: Vec<u32> = pulses.iter().copied().filter(|&p| p <= split).collect();
+            let long: Vec<u32> = pulses.iter().copied().filter(|&p| p > split).collect();
+            (mean(&short), mean(&long))
+        };
+
+        let bit = |pulse: u32| {
+            if near(pulse, zero) {
+                Some(false)
+            } else if near(pulse, one) {
+                Some(true)
+            } else {
+                None
+            }
+        };
+        let mut data = Vec::with_capacity(pulses.len().div_ceil(16));
+        let mut count = 0usize;
+        for pair in pulses.chunks(2) {
+            let value = bit(pair[0])?;
+            if pair.len() == 2 && bit(pair[1])? != value {
+                return None;
+            }
+            if count.is_multiple_of(8) {
+                data.push(0);
+            }
+            if value {
+                data[count / 8] |= 0x80 >> (count % 8);
+            }
+            count += 1;
+        }
+        let used_bits = match count % 8 {
+            0 => 8,
+            n => n as u8,
+        };
+        Some(Self {
+            zero: zero as u16,
+            one: one as u16,
+            used_bits,
+            data,
+        })
+    }
+}
+
+/// Sample the pulses at [`SAMPLE_TSTATES`], one bit per sample, 1 = high.
+fn direct_recording(pulses: &[u32], mut level: bool, pause_ms: u16) -> TzxBlock {
+    let rate = u64::from(SAMPLE_TSTATES);
+    let mut data = Vec::new();
+    let mut elapsed = 0u64;
+    let mut samples = 0u64;
+    for &pulse in pulses {
+        elapsed += u64::from(pulse);
+        // Round each edge to the nearest sample.
+        let end = (elapsed + rate / 2) / rate;
+        while samples < end {
+            if samples.is_multiple_of(8) {
+                data.push(0);
+            }
+            if level {
+                data[(samples / 8) as usize] |= 0x80 >> (samples % 8);
+            }
+            samples += 1;
+        }
+        level = !level;
+    }
+    let used_bits = match samples % 8 {
+        0 => 8,
+        n => n as u8,
+    };
+    TzxBlock::DirectRecording {
+        tstates_per_sample: SAMPLE_TSTATES,
+        pause_ms,
+        used_bits,
+        data,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Record `pulses` (T-states each) through the sampler, starting low.
+    fn record(pulses: &[u32]) -> TapeRecorder {
+        let mut recorder = TapeRecorder::new();
+        recorder.start(RecordMode::Signal, false);
+        let mut level = false;
+        for &pulse in pulses {
+            level = !level;
+            for _ in 0..pulse {
+                recorder.sample(level);
+            }
+        }
+        recorder.stop();
+        recorder
+    }
+
+    /// Pulses for `bytes` sent with `zero`/`one` length bits, MSB first.
+    fn bit_pulses(bytes: &[u8], zero: u32, one: u32) -> Vec<u32> {
+        let mut pulses = Vec::new();
+        for &byte in bytes {
+            for bit in 0..8 {
+                let len = if byte & (0x80 >> bit) != 0 { one } else { zero };
+                pulses.extend([len, len]);
+            }
+        }
+        pulses
+    }
+
+    #[test]
+    fn trapped_blocks_become_tap_and_standard_tzx() {
+        let mut recorder = TapeRecorder::new();
+        recorder.start(RecordMode::Trap, false);
+        assert!(recorder.is_trapping());
+        recorder.push_block(0x00, vec![3, 1, 2]);
+        recorder.push_block(0xFF, vec![0xAA]);
+        recorder.stop();
+        assert!(!recorder.is_trapping());
+
+        let tap = recorder.to_tap().expect("tap");
+        assert_eq!(tap.blocks.len(), 2);
+        assert_eq!(tap.blocks[1].data, [0xAA]);
+
+        let tzx = recorder.to_tzx();
+        assert_eq!(
+            tzx.blocks[1],
+            TzxBlock::StandardSpeed {
+                pause_ms: ROM_PAUSE_MS,
+                data: vec![0xFF, 0xAA, 0x55],
+            }
+        );
+    }
+
+    #[test]
+    fn rom_timed_signal_becomes_standard_speed() {
+        let payload = [0xFF, 0x12, 0x80, 0xED];
+        let mut pulses = vec![2168; 3223];
+        pulses[0] = 1000;
+        pulses.extend([667, 735]);
+        let mut data = bit_pulses(&payload, 855, 1710);
+        // The last half-pulse runs into the silence after the block.
+        let last = data.pop().expect("pulse");
+        pulses.extend(data);
+        pulses.push(last + 3_500_000);
+        pulses.extend(vec![2168; 300]);
+
+        let recorder = record(&pulses);
+        let tzx = recorder.to_tzx();
+        assert_eq!(
+            tzx.blocks[0],
+            TzxBlock::StandardSpeed {
+                pause_ms: 1000,
+                data: payload.to_vec(),
+            }
+        );
+        // A tone with no sync after it is just one-length pulse pairs.
+        assert!(matches!(tzx.blocks[1], TzxBlock::PureData { .. }));
+        assert!(recorder.to_tap().is_err());
+    }
+
+    #[test]
+    fn turbo_signal_keeps_its_timing() {
+        let mut pulses = vec![1500; 1000];
+        pulses.extend([400, 500]);
+        pulses.extend(bit_pulses(&[0x5A, 0xC0], 300, 600));
+        pulses.truncate(pulses.len() - 12); // 10 bits
+        pulses.push(50_000);
+
+        let tzx = record(&pulses).to_tzx();
+        assert_eq!(
+            tzx.blocks,
+            [TzxBlock::TurboSpeed {
+                pilot_pulse: 1500,
+                sync1: 400,
+                sync2: 500,
+                zero_pulse: 300,
+                one_pulse: 600,
+                pilot_count: 1000,
+                used_bits: 2,
+                pause_ms: 14,
+                data: vec![0x5A, 0xC0],
+            }]
+        );
+    }
+
+    #[test]
+    fn bits_without_pilot_become_pure_data() {
+        let pulses = bit_pulses(&[0x0F], 500, 1000);
+        let tzx = record(&pulses).to_tzx();
+        assert_eq!(
+            tzx.blocks,
+            [TzxBlock::PureData {
+                zero_pulse: 500,
+                one_pulse: 1000,
+                used_bits: 8,
+                pause_ms: 0,
+                data: vec![0x0F],
+            }]
+        );
+    }
+
+    #[test]
+    fn irregular_signal_becomes_direct_recording() {
+        let tzx = record(&[158, 79, 395]).to_tzx();
+        assert_eq!(
+            tzx.blocks,
+            [TzxBlock::DirectRecording {
+                tstates_per_sample: SAMPLE_TSTATES,
+                pause_ms: 0,
+                used_bits: 8,
+                data: vec![0b1101_1111],
+            }]
+        );
+    }
+
+    #[test]
+    fn silence_before_the_first_edge_is_dropped() {
+        let mut recorder = TapeRecorder::new();
+        recorder.start(RecordMode::Signal, true);
+        for _ in 0..1000 {
+            recorder.sample(true);
+        }
+        assert_eq!(recorder.pulse_count(), 0);
+        recorder.sample(false);
+        recorder.sample(true);
+        assert_eq!(recorder.pulse_count(), 2);
+        assert_eq!(recorder.to_tzx().blocks.len(), 1);
+    }
+}
@@ -7,23 +7,35 @@
 //!
 //! Each data bit consists of **two** equal-length pulses (one complete square
 //! wave cycle). Bits are transmitted MSB first within each byte.
+//!
+//! Direct recordings, CSW recordings and generalized data blocks are
+//! expanded into runs of alternating levels when they start. PZX and
+//! standalone CSW tapes play through the same generator, converted to
+//! equivalent blocks by [`pzx_blocks`] and [`csw_blocks`].
 
 #![allow(clippy::cast_possible_truncation)]
 
-use crate::tzx::TzxBlock;
+use emu_core::{SaveState, StateError, StateReader, StateWriter};
+
+use crate::csw::CswFile;
+use crate::pzx::{PzxBlock, PzxFile};
+use crate::tzx::{GdbSymbol, TzxBlock};
 
 // ---------------------------------------------------------------------------
 // Standard ROM timing constants (T-states)
 // ---------------------------------------------------------------------------
 
-const PILOT_PULSE: u16 = 2168;
-const SYNC1_PULSE: u16 = 667;
-const SYNC2_PULSE: u16 = 735;
-const ZERO_PULSE: u16 = 855;
-const ONE_PULSE: u16 = 1710;
+pub(crate) const PILOT_PULSE: u16 = 2168;
+pub(crate) const SYNC1_PULSE: u16 = 667;
+pub(crate) const SYNC2_PULSE: u16 = 735;
+pub(crate) const ZERO_PULSE: u16 = 855;
+pub(crate) const ONE_PULSE: u16 = 1710;
 const HEADER_PILOT_COUNT: u16 = 8063;
 const DATA_PILOT_COUNT: u16 = 3223;
 
+/// PZX durations are T-states of the 48K's 3.5 MHz clock.
+const PZX_CLOCK: u32 = 3_500_000;
+
 // ---------------------------------------------------------------------------
 // Signal phase
 // ---------------------------------------------------------------------------
@@ -54,12 +66,155 @@ enum SignalPhase {
     Tone { pulse_len: u16, remaining: u16 },
     /// Arbitrary pulse sequence.
     PulseSeq { pulses: Vec<u16>, idx: usize },
+    /// Runs of levels from a direct recording, CSW or generalized data
+    /// block. `entry_level` is the level the block started from, needed
+    /// to rebuild the runs.
+    Levels { idx: usize, entry_level: bool },
     /// Silence for a duration (EAR forced low).
     Pause { remaining: u32 },
     /// Tape stopped — waiting for `play()`.
     Stopped,
 }
 
+// ---------------------------------------------------------------------------
+// Level runs
+// ---------------------------------------------------------------------------
+
+/// A block expanded into runs of T-states at one level. Adjacent runs
+/// always differ, so only the first level is kept.
+#[derive(Debug, Clone, Default)]
+struct LevelRuns {
+    first_level: bool,
+    lengths: Vec<u32>,
+    /// Level after the last run.
+    end_level: bool,
+    pause_ms: u16,
+}
+
+impl LevelRuns {
+    /// Expand `block`, entered with the signal at `entry_level`. Returns
+    /// `None` for blocks not played as level runs.
+    fn build(block: &TzxBlock, entry_level: bool, cpu_freq: u32) -> Option<Self> {
+        let mut runs = Self {
+            end_level: entry_level,
+            ..Self::default()
+        };
+        match block {
+            TzxBlock::DirectRecording {
+                tstates_per_sample,
+                pause_ms,
+                used_bits,
+                data,
+            } => {
+                runs.pause_ms = *pause_ms;
+                let used = if *used_bits == 0 || *used_bits > 8 {
+                    8
+                } else {
+                    *used_bits
+                };
+                for (i, &byte) in data.iter().enumerate() {
+                    let bits = if i == data.len() - 1 { used } else { 8 };
+                    for bit in 0..bits {
+                        runs.push(byte & (0x80 >> bit) != 0, u32::from(*tstates_per_sample));
+                    }
+                }
+                // The level holds after the last sample.
+                if let Some(last) = runs.lengths.len().checked_sub(1) {
+                    runs.end_level = runs.level(last);
+                }
+            }
+            TzxBlock::CswRecording {
+                pause_ms,
+                sample_rate,
+                pulses,
+            } => {
+                runs.pause_ms = *pause_ms;
+                // Round the running total rather than each pulse, so long
+                // recordings keep time.
+                let rate = u64::from((*sample_rate).max(1));
+                let mut level = entry_level;
+                let mut samples = 0u64;
+                let mut start = 0u64;
+                for &pulse in pulses {
+                    samples += u64::from(pulse);
+                    let end = samples * u64::from(cpu_freq) / rate;
+                    runs.push(level, u32::try_from(end - start).unwrap_or(u32::MAX));
+                    start = end;
+                    level = !level;
+                }
+                runs.end_level = level;
+            }
+            TzxBlock::GeneralizedData {
+                pause_ms,
+                pilot_symbols,
+                pilot_stream,
+                data_symbols,
+                data_stream,
+            } => {
+                runs.pause_ms = *pause_ms;
+                let mut level = entry_level;
+                for &(symbol, repeats) in pilot_stream {
+                    if let Some(symbol) = pilot_symbols.get(usize::from(symbol)) {
+                        for _ in 0..repeats {
+                            level = runs.push_symbol(symbol, level);
+                        }
+                    }
+                }
+                for &symbol in data_stream {
+                    if let Some(symbol) = data_symbols.get(usize::from(symbol)) {
+                        level = runs.push_symbol(symbol, level);
+                    }
+                }
+                runs.end_level = level;
+            }
+            _ => return None,
+        }
+        Some(runs)
+    }
+
+    /// Level of run `idx`.
+    fn level(&self, idx: usize) -> bool {
+        self.first_level ^ (idx % 2 == 1)
+    }
+
+    /// Append `len` T-states at `level`, extending the last run if it is at
+    /// the same level.
+    fn push(&mut self, level: bool, len: u32) {
+        if len == 0 {
+            return;
+        }
+        match self.lengths.len().checked_sub(1) {
+            None => {
+                self.first_level = level;
+                self.lengths.push(len);
+            }
+            Some(last) if self.level(last) == level => {
+                self.lengths[last] = self.lengths[last].saturating_add(len);
+            }
+            Some(_) => self.lengths.push(len),
+        }
+    }
+
+    /// Append a generalized data symbol. `level` is the level an edge
+    /// would give the next pulse; returns the same after the symbol.
+    fn push_symbol(&mut self, symbol: &GdbSymbol, level: bool) -> bool {
+        if symbol.pulses.is_empty() {
+            return level;
+        }
+        let mut level = match symbol.polarity {
+            0 => level,
+            1 => !level,
+            2 => false,
+            _ => true,
+        };
+        for &pulse in &symbol.pulses {
+            self.push(level, u32::from(pulse));
+            level = !level;
+        }
+        level
+    }
+}
+
 // ---------------------------------------------------------------------------
 // TzxSignal
 // ---------------------------------------------------------------------------
@@ -72,9 +227,16 @@ pub struct TzxSignal {
     pulse_remaining: u32,
     phase: SignalPhase,
     loop_stack: Vec<(usize, u16)>,
+    /// Call sequence in progress: the call block and which of its
+    /// offsets is playing.
+    call_state: Option<(usize, usize)>,
+    /// Entry taken at select blocks.
+    select_choice: usize,
     playing: bool,
     is_48k: bool,
     cpu_freq: u32,
+    /// Runs for a `Levels` phase; rebuilt from the block, not saved.
+    runs: LevelRuns,
 }
 
 impl TzxSignal {
@@ -88,12 +250,20 @@ impl TzxSignal {
             pulse_remaining: 0,
             phase: SignalPhase::Idle,
             loop_stack: Vec::new(),
+            call_state: None,
+            select_choice: 0,
             playing: false,
             is_48k,
             cpu_freq,
+            runs: LevelRuns::default(),
         }
     }
 
+    /// Choose the entry to follow at select blocks (default: the first).
+    pub fn set_select_choice(&mut self, choice: usize) {
+        self.select_choice = choice;
+    }
+
     /// Start playback.
     pub fn play(&mut self) {
         self.playing = true;
@@ -322,6 +492,21 @@ impl TzxSignal {
                     };
                 }
             }
+            SignalPhase::Levels { idx, entry_level } => {
+                let next = idx + 1;
+                if let Some(&len) = self.runs.lengths.get(next) {
+                    // This tick is the first of the run.
+                    self.level = self.runs.level(next);
+                    self.pulse_remaining = len - 1;
+                    self.phase = SignalPhase::Levels {
+                        idx: next,
+                        entry_level,
+                    };
+                } else {
+                    self.level = self.runs.end_level;
+                    self.finish_data_block(self.runs.pause_ms);
+                }
+            }
             SignalPhase::Pause { remaining } => {
                 // During pause, level is forced low
                 self.level = false;
@@ -431,6 +616,36 @@ impl TzxSignal {
         }
     }
 
+    /// Start playing `block` as level runs.
+    fn start_levels(&mut self, block: &TzxBlock) {
+        let entry_level = self.level;
+        self.runs = LevelRuns::build(block, entry_level, self.cpu_freq).unwrap_or_default();
+        if let Some(&len) = self.runs.lengths.first() {
+            self.level = self.runs.first_level;
+            self.pulse_remaining = len - 1;
+            self.phase = SignalPhase::Levels {
+                idx: 0,
+                entry_level,
+            };
+        } else {
+            self.level = self.runs.end_level;
+            self.finish_data_block(self.runs.pause_ms);
+        }
+    }
+
+    /// Continue from the block `offset` blocks on from `from`. Offsets
+    /// outside the tape, and zero (which would hang), are ignored.
+    fn jump_from(&mut self, from: usize, offset: i16) {
+        if offset == 0 {
+            return;
+        }
+        if let Some(target) = from.checked_add_signed(isize::from(offset))
+            && target <= self.blocks.len()
+        {
+            self.block_index = target;
+        }
+    }
+
     /// Set up the next block for playback.
     fn advance_block(&mut self) {
         if self.block_index >= self.blocks.len() {
@@ -504,6 +719,11 @@ impl TzxSignal {
             } => {
                 self.start_data_phase(zero_pulse, one_pulse, used_bits, pause_ms, data);
             }
+            TzxBlock::DirectRecording { .. }
+            | TzxBlock::CswRecording { .. }
+            | TzxBlock::GeneralizedData { .. } => {
+                self.start_levels(&block);
+            }
             TzxBlock::Pause { duration_ms: 0 } => {
                 self.phase = SignalPhase::Stopped;
                 self.playing = false;
@@ -528,6 +748,40 @@ impl TzxSignal {
                 }
                 self.phase = SignalPhase::Idle;
             }
+            TzxBlock::JumpTo { offset } => {
+                self.jump_from(self.block_index - 1, offset);
+                self.phase = SignalPhase::Idle;
+            }
+            TzxBlock::CallSequence { offsets } => {
+                let call = self.block_index - 1;
+                if let Some(&offset) = offsets.first() {
+                    self.call_state = Some((call, 0));
+                    self.jump_from(call, offset);
+                }
+                self.phase = SignalPhase::Idle;
+            }
+            TzxBlock::ReturnFromSequence => {
+                if let Some((call, done)) = self.call_state.take() {
+                    let next = match self.blocks.get(call) {
+                        Some(TzxBlock::CallSequence { offsets }) => offsets.get(done + 1).copied(),
+                        _ => None,
+                    };
+                    if let Some(offset) = next {
+                        self.call_state = Some((call, done + 1));
+                        self.jump_from(call, offset);
+                    } else {
+                        // Sequence done — carry on after the call block.
+                        self.block_index = call + 1;
+                    }
+                }
+                self.phase = SignalPhase::Idle;
+            }
+            TzxBlock::Select { entries } => {
+                if let Some(&(offset, _)) = entries.get(self.select_choice) {
+                    self.jump_from(self.block_index - 1, offset);
+                }
+                self.phase = SignalPhase::Idle;
+            }
             TzxBlock::StopIf48K => {
                 if self.is_48k {
                     self.phase = SignalPhase::Stopped;
@@ -557,10 +811,269 @@ fn ms_to_tstates(ms: u16, cpu_freq: u32) -> u32 {
     u32::from(ms) * cpu_freq / 1000
 }
 
+// ---------------------------------------------------------------------------
+// Other tape formats
+// ---------------------------------------------------------------------------
+
+/// TZX blocks that play a standalone CSW recording.
+#[must_use]
+pub fn csw_blocks(csw: CswFile) -> Vec<TzxBlock> {
+    vec![
+        TzxBlock::SetSignalLevel {
+            level: csw.initial_level,
+        },
+        TzxBlock::CswRecording {
+            pause_ms: 0,
+            sample_rate: csw.sample_rate,
+            pulses: csw.pulses,
+        },
+    ]
+}
+
+/// TZX blocks that play a PZX tape.
+///
+/// Pulse and pause blocks become CSW recordings clocked at 3.5 MHz, and
+/// data blocks become generalized data with one symbol for each bit value
+/// and a third for the tail pulse.
+#[must_use]
+pub fn pzx_blocks(pzx: PzxFile) -> Vec<TzxBlock> {
+    let mut blocks = Vec::with_capacity(pzx.blocks.len() * 2);
+    for block in pzx.blocks {
+        match block {
+            PzxBlock::Pulses { pulses } => {
+                blocks.push(TzxBlock::SetSignalLevel { level: false });
+                blocks.push(TzxBlock::CswRecording {
+                    pause_ms: 0,
+                    sample_rate: PZX_CLOCK,
+                    pulses,
+                });
+            }
+            PzxBlock::Data {
+                initial_level,
+                bit_count,
+                tail,
+                zero_pulses,
+                one_pulses,
+                data,
+            } => {
+                let mut data_stream: Vec<u8> = (0..bit_count as usize)
+                    .map(|bit| (data[bit / 8] >> (7 - bit % 8)) & 1)
+                    .collect();
+                if tail > 0 {
+                    data_stream.push(2);
+                }
+                let symbol = |pulses| GdbSymbol {
+                    polarity: 0,
+                    pulses,
+                };
+                blocks.push(TzxBlock::SetSignalLevel {
+                    level: initial_level,
+                });
+                blocks.push(TzxBlock::GeneralizedData {
+                    pause_ms: 0,
+                    pilot_symbols: Vec::new(),
+                    pilot_stream: Vec::new(),
+                    data_symbols: vec![symbol(zero_pulses), symbol(one_pulses), symbol(vec![tail])],
+                    data_stream,
+                });
+            }
+            PzxBlock::Pause {
+                initial_level,
+                duration,
+            } => {
+                blocks.push(TzxBlock::SetSignalLevel {
+                    level: initial_level,
+                });
+                blocks.push(TzxBlock::CswRecording {
+                    pause_ms: 0,
+                    sample_rate: PZX_CLOCK,
+                    pulses: vec![duration],
+                });
+            }
+            PzxBlock::Browse { text } => blocks.push(TzxBlock::TextDescription { text }),
+            PzxBlock::Stop { only_48k: true } => blocks.push(TzxBlock::StopIf48K),
+            PzxBlock::Stop { only_48k: false } => blocks.push(TzxBlock::Pause { duration_ms: 0 }),
+            PzxBlock::Unknown { .. } => {}
+        }
+    }
+    blocks
+}
+
 // ---------------------------------------------------------------------------
 // Tests
 // ---------------------------------------------------------------------------
 
+// ---------------------------------------------------------------------------
+// Save state
+// ---------------------------------------------------------------------------
+
+impl SaveState for SignalPhase {
+    fn save_state(&self, w: &mut StateWriter) {
+        match self {
+            Self::Idle => w.write_u8(0),
+            Self::Pilot {
+                pulse_len,
+                remaining,
+            } => {
+                w.write_u8(1);
+                w.write_u16(*pulse_len);
+                w.write_u16(*remaining);
+            }
+            Self::Sync1 { sync2_len } => {
+                w.write_u8(2);
+                w.write_u16(*sync2_len);
+            }
+            Self::Sync2 => w.write_u8(3),
+            Self::Data {
+                zero_pulse,
+                one_pulse,
+                data,
+                byte_idx,
+                bit_idx,
+                used_bits_last,
+                second_half,
+                pause_ms,
+            } => {
+                w.write_u8(4);
+                w.write_u16(*zero_pulse);
+                w.write_u16(*one_pulse);
+                w.write_bytes(data);
+                w.write_usize(*byte_idx);
+                w.write_u8(*bit_idx);
+                w.write_u8(*used_bits_last);
+                w.write_bool(*second_half);
+                w.write_u16(*pause_ms);
+            }
+            Self::Tone {
+                pulse_len,
+                remaining,
+            } => {
+                w.write_u8(5);
+                w.write_u16(*pulse_len);
+                w.write_u16(*remaining);
+            }
+            Self::PulseSeq { pulses, idx } => {
+                w.write_u8(6);
+                w.write_u16_slice(pulses);
+                w.write_usize(*idx);
+            }
+            Self::Pause { remaining } => {
+                w.write_u8(7);
+                w.write_u32(*remaining);
+            }
+            Self::Stopped => w.write_u8(8),
+            Self::Levels { idx, entry_level } => {
+                w.write_u8(9);
+                w.write_usize(*idx);
+                w.write_bool(*entry_level);
+            }
+        }
+    }
+
+    fn load_state(&mut self, r: &mut StateReader<'_>) -> Result<(), StateError> {
+        *self = match r.read_u8()? {
+            0 => Self::Idle,
+            1 => Self::Pilot {
+                pulse_len: r.read_u16()?,
+                remaining: r.read_u16()?,
+            },
+            2 => Self::Sync1 {
+                sync2_len: r.read_u16()?,
+            },
+            3 => Self::Sync2,
+            4 => Self::Data {
+                zero_pulse: r.read_u16()?,
+                one_pulse: r.read_u16()?,
+                data: r.read_bytes()?.to_vec(),
+                byte_idx: r.read_usize()?,
+                bit_idx: r.read_u8()?,
+                used_bits_last: r.read_u8()?,
+                second_half: r.read_bool()?,
+                pause_ms: r.read_u16()?,
+            },
+            5 => Self::Tone {
+                pulse_len: r.read_u16()?,
+                remaining: r.read_u16()?,
+            },
+            6 => Self::PulseSeq {
+                pulses: r.read_u16_vec()?,
+                idx: r.read_usize()?,
+            },
+            7 => Self::Pause {
+                remaining: r.read_u32()?,
+            },
+            8 => Self::Stopped,
+            9 => Self::Levels {
+                idx: r.read_usize()?,
+                entry_level: r.read_bool()?,
+            },
+            v => return Err(StateError::Invalid(format!("bad TZX signal phase {v}"))),
+        };
+        Ok(())
+    }
+}
+
+/// The blocks themselves are read-only media and are not saved; the same
+/// TZX must be inserted before loading. Level runs are rebuilt from the
+/// current block.
+impl SaveState for TzxSignal {
+    fn save_state(&self, w: &mut StateWriter) {
+        w.write_usize(self.blocks.len());
+        w.write_usize(self.block_index);
+        w.write_bool(self.level);
+        w.write_u32(self.pulse_remaining);
+        self.phase.save_state(w);
+        w.write_usize(self.loop_stack.len());
+        for &(index, count) in &self.loop_stack {
+            w.write_usize(index);
+            w.write_u16(count);
+        }
+        w.write_bool(self.call_state.is_some());
+        let (call, done) = self.call_state.unwrap_or_default();
+        w.write_usize(call);
+        w.write_usize(done);
+        w.write_usize(self.select_choice);
+        w.write_bool(self.playing);
+    }
+
+    fn load_state(&mut self, r: &mut StateReader<'_>) -> Result<(), StateError> {
+        if r.read_usize()? != self.blocks.len() {
+            return Err(StateError::Invalid(
+                "inserted TZX does not match the saved tape".into(),
+            ));
+        }
+        self.block_index = r.read_usize()?;
+        self.level = r.read_bool()?;
+        self.pulse_remaining = r.read_u32()?;
+        self.phase.load_state(r)?;
+        let depth = r.read_usize()?;
+        self.loop_stack.clear();
+        for _ in 0..depth {
+            let index = r.read_usize()?;
+            let count = r.read_u16()?;
+            self.loop_stack.push((index, count));
+        }
+        let in_call = r.read_bool()?;
+        let call = r.read_usize()?;
+        let done = r.read_usize()?;
+        self.call_state = in_call.then_some((call, done));
+        self.select_choice = r.read_usize()?;
+        self.playing = r.read_bool()?;
+
+        self.runs = LevelRuns::default();
+        if let SignalPhase::Levels { idx, entry_level } = self.phase {
+            self.runs = self
+                .block_index
+                .checked_sub(1)
+                .and_then(|i| self.blocks.get(i))
+                .and_then(|block| LevelRuns::build(block, entry_level, self.cpu_freq))
+                .filter(|runs| idx < runs.lengths.len())
+                .ok_or_else(|| StateError::Invalid("TZX level run out of range".into()))?;
+        }
+        Ok(())
+    }
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -867,6 +1380,226 @@ mod tests {
         assert!(!sig.is_playing(), "Should stop on 48K");
     }
 
+    /// Signal playing `blocks` from the start.
+    fn playing(blocks: Vec<TzxBlock>) -> TzxSignal {
+        let mut sig = TzxSignal::new(blocks, true, CPU_3_5MHZ);
+        sig.play();
+        sig
+    }
+
+    /// Level history made of (level, T-states) runs.
+    fn history(runs: &[(bool, usize)]) -> Vec<bool> {
+        runs.iter()
+            .flat_map(|&(level, len)| std::iter::repeat_n(level, len))
+            .collect()
+    }
+
+    #[test]
+    fn direct_recording_plays_sample_levels() {
+        // Samples 1 1 0 0 1 (five used bits), 10 T-states each.
+        let mut sig = playing(vec![TzxBlock::DirectRecording {
+            tstates_per_sample: 10,
+            pause_ms: 0,
+            used_bits: 5,
+            data: vec![0b1100_1000],
+        }]);
+        let levels = run_tstates(&mut sig, 51);
+        assert_eq!(
+            levels,
+            history(&[(true, 20), (false, 20), (true, 11)]),
+            "the level holds after the last sample"
+        );
+        assert!(sig.is_finished());
+    }
+
+    #[test]
+    fn csw_recording_scales_samples_to_tstates() {
+        // 1.75 MHz samples are two T-states each. The zero-length pulse
+        // merges its neighbours.
+        let mut sig = playing(vec![TzxBlock::CswRecording {
+            pause_ms: 0,
+            sample_rate: 1_750_000,
+            pulses: vec![5, 3, 0, 2],
+        }]);
+        let levels = run_tstates(&mut sig, 21);
+        assert_eq!(levels, history(&[(false, 10), (true, 10), (false, 1)]));
+    }
+
+    #[test]
+    fn generalized_data_follows_symbol_polarity() {
+        let mut sig = playing(vec![TzxBlock::GeneralizedData {
+            pause_ms: 0,
+            pilot_symbols: vec![GdbSymbol {
+                polarity: 0,
+                pulses: vec![4],
+            }],
+            pilot_stream: vec![(0, 3)],
+            data_symbols: vec![
+                GdbSymbol {
+                    polarity: 0,
+                    pulses: vec![2, 2],
+                },
+                GdbSymbol {
+                    polarity: 3,
+                    pulses: vec![3],
+                },
+            ],
+            data_stream: vec![1, 0],
+        }]);
+        let levels = run_tstates(&mut sig, 20);
+        assert_eq!(
+            levels,
+            history(&[
+                (false, 4),
+                (true, 4),
+                (false, 4),
+                (true, 3), // forced high
+                (false, 2),
+                (true, 2),
+                (false, 1),
+            ])
+        );
+    }
+
+    #[test]
+    fn generalized_data_pause_forces_low() {
+        let mut sig = playing(vec![TzxBlock::GeneralizedData {
+            pause_ms: 1,
+            pilot_symbols: vec![],
+            pilot_stream: vec![],
+            data_symbols: vec![GdbSymbol {
+                polarity: 3,
+                pulses: vec![5],
+            }],
+            data_stream: vec![0],
+        }]);
+        let levels = run_tstates(&mut sig, 6 + 3500);
+        assert!(levels[..5].iter().all(|&level| level));
+        assert!(levels[5..].iter().all(|&level| !level));
+        let _ = run_tstates(&mut sig, 2);
+        assert!(sig.is_finished());
+    }
+
+    /// Transitions from the call/jump/select tape with `choice` selected.
+    fn flow_control_transitions(choice: usize) -> u32 {
+        let tone = |count| TzxBlock::PureTone {
+            pulse_len: 3,
+            count,
+        };
+        let mut sig = playing(vec![
+            TzxBlock::CallSequence {
+                offsets: vec![2, 4],
+            },
+            TzxBlock::JumpTo { offset: 5 },
+            tone(1),
+            TzxBlock::ReturnFromSequence,
+            tone(2),
+            TzxBlock::ReturnFromSequence,
+            TzxBlock::Select {
+                entries: vec![(2, "short".to_string()), (1, "long".to_string())],
+            },
+            tone(4),
+            tone(8),
+        ]);
+        sig.set_select_choice(choice);
+        let levels = run_tstates(&mut sig, 200);
+        assert!(sig.is_finished());
+        count_transitions(&levels)
+    }
+
+    #[test]
+    fn call_jump_and_select_blocks() {
+        // Both calls, the jump past them, then the selected entry.
+        assert_eq!(flow_control_transitions(0), 1 + 2 + 8);
+        assert_eq!(flow_control_transitions(1), 1 + 2 + 4 + 8);
+    }
+
+    #[test]
+    fn save_state_resumes_level_runs() {
+        let blocks = vec![TzxBlock::CswRecording {
+            pause_ms: 0,
+            sample_rate: CPU_3_5MHZ,
+            pulses: (1..40).collect(),
+        }];
+        let mut sig = playing(blocks.clone());
+        let _ = run_tstates(&mut sig, 300);
+        let mut w = StateWriter::new();
+        sig.save_state(&mut w);
+        let state = w.into_bytes();
+
+        let mut restored = TzxSignal::new(blocks, true, CPU_3_5MHZ);
+        let mut r = StateReader::new(&state);
+        restored.load_state(&mut r).expect("load");
+        r.finish().expect("whole state read");
+        assert_eq!(run_tstates(&mut restored, 600), run_tstates(&mut sig, 600));
+    }
+
+    #[test]
+    fn csw_file_plays_from_initial_level() {
+        let csw = CswFile {
+            major: 2,
+            minor: 0,
+            sample_rate: CPU_3_5MHZ,
+            compression: crate::csw::CswCompression::Rle,
+            initial_level: true,
+            pulses: vec![3, 4],
+        };
+        let mut sig = playing(csw_blocks(csw));
+        let levels = run_tstates(&mut sig, 9);
+        assert_eq!(levels, history(&[(true, 4), (false, 4), (true, 1)]));
+    }
+
+    #[test]
+    fn pzx_pulses_and_data_play() {
+        let pzx = PzxFile {
+            major: 1,
+            minor: 0,
+            title: String::new(),
+            info: Vec::new(),
+            blocks: vec![
+                // Zero-length first pulse: starts high.
+                PzxBlock::Pulses {
+                    pulses: vec![0, 6, 6],
+                },
+                PzxBlock::Data {
+                    initial_level: true,
+                    bit_count: 2,
+                    tail: 3,
+                    zero_pulses: vec![2, 2],
+                    one_pulses: vec![4, 4],
+                    data: vec![0b1000_0000],
+                },
+                PzxBlock::Browse {
+                    text: "here".to_string(),
+                },
+                PzxBlock::Stop { only_48k: false },
+            ],
+        };
+        let blocks = pzx_blocks(pzx);
+        assert!(matches!(blocks[4], TzxBlock::TextDescription { .. }));
+        assert!(matches!(blocks[5], TzxBlock::Pause { duration_ms: 0 }));
+
+        let mut sig = playing(blocks);
+        let levels = run_tstates(&mut sig, 31);
+        assert_eq!(
+            levels,
+            history(&[
+                (false, 1), // level set low
+                (true, 6),
+                (false, 6),
+                (true, 2), // end of pulses, then level set high
+                (true, 4), // bit 1
+                (false, 4),
+                (true, 2), // bit 0
+                (false, 2),
+                (true, 3), // tail
+                (false, 1),
+            ])
+        );
+        let _ = run_tstates(&mut sig, 3);
+        assert!(!sig.is_playing(), "STOP block stops the tape");
+    }
+
     #[test]
     fn stop_if_48k_continues_on_128k() {
         let mut sig = TzxSignal::new(vec![TzxBlock::StopIf48K], false, CPU_3_5MHZ);
@@ -0,0 +1,435 @@
+//! Golden-frame baselines.
+//!
+//! A baseline records, for every ROM (keyed by its SHA-1), how the run
+//! ended and the framebuffer and audio hashes at each checkpoint frame.
+//! The PNG of each checkpoint frame is kept next to the baseline file in a
+//! `<name>.frames/` directory so later runs can show what changed.
+//!
+//! Comparing a run against a baseline sorts every ROM into one bucket:
+//!
+//! - **new crash**: the emulator panicked on a ROM that used to run.
+//! - **regression**: a ROM that used to pass no longer does, or any
+//!   checkpoint hash changed. The baseline is the reference, so a change is
+//!   a regression until someone looks at the PNGs and re-baselines.
+//! - **progression**: a ROM that used to crash, hang, error or show a blank
+//!   screen now passes.
+//!
+//! Audio hashes are cumulative: each covers every sample from power-on to
+//! the checkpoint.
+
+use std::collections::BTreeMap;
+use std::fs::File;
+use std::io::{BufReader, BufWriter};
+use std::path::{Path, PathBuf};
+
+use serde::{Deserialize, Serialize};
+
+use crate::{System, TestResult, TestStatus};
+
+const BASELINE_VERSION: u32 = 1;
+
+/// Framebuffer and audio hashes at one checkpoint frame.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct FrameHashes {
+    pub frame: u32,
+    pub video: String,
+    pub audio: String,
+}
+
+/// What a baseline remembers about one ROM.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct BaselineEntry {
+    pub path: String,
+    pub system: Option<System>,
+    pub status: TestStatus,
+    pub checkpoints: Vec<FrameHashes>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct Baseline {
+    pub version: u32,
+    pub roms: BTreeMap<String, BaselineEntry>,
+}
+
+/// Where the PNG of a checkpoint frame lives under `dir`.
+pub fn checkpoint_png(dir: &Path, system: System, sha1: &str, frame: u32) -> PathBuf {
+    dir.join(system.name()).join(format!("{sha1}-f{frame}.png"))
+}
+
+/// Directory holding a baseline's checkpoint PNGs.
+pub fn frames_dir(baseline_path: &Path) -> PathBuf {
+    baseline_path.with_extension("frames")
+}
+
+impl Baseline {
+    /// Baseline of a run. ROMs that were skipped or could not be read are
+    /// left out.
+    pub fn from_results(results: &[TestResult]) -> Self {
+        let roms = results
+            .iter()
+            .filter(|r| !r.sha1.is_empty() && !matches!(r.status, TestStatus::Skipped))
+            .map(|r| {
+                let entry = BaselineEntry {
+                    path: r.path.clone(),
+                    system: r.system,
+                    status: r.status,
+                    checkpoints: r.checkpoints.clone(),
+                };
+                (r.sha1.clone(), entry)
+            })
+            .collect();
+        Self {
+            version: BASELINE_VERSION,
+            roms,
+        }
+    }
+
+    /// Load a baseline file.
+    pub fn load(path: &Path) -> Result<Self, String> {
+        let file = File::open(path)
+            .map_err(|e| format!("Cannot open baseline {}: {e}", path.display()))?;
+        let baseline: Self = serde_json::from_reader(BufReader::new(file))
+            .map_err(|e| format!("Invalid baseline {}: {e}", path.display()))?;
+        if baseline.version != BASELINE_VERSION {
+            return Err(format!(
+                "Unsupported baseline version {} in {}",
+                baseline.version,
+                path.display()
+            ));
+        }
+        Ok(baseline)
+    }
+
+    /// Write the baseline to `path` and copy the run's checkpoint PNGs from
+    /// `screenshots_dir` into its frames directory.
+    pub fn save(&self, path: &Path, screenshots_dir: &Path) -> Result<(), String> {
+        let json = serde_json::to_string_pretty(self)
+            .map_err(|e| format!("JSON serialization error: {e}"))?;
+        std::fs::write(path, json)
+            .map_err(|e| format!("Cannot write baseline {}: {e}", path.display()))?;
+
+        let frames = frames_dir(path);
+        for (sha1, entry) in &self.roms {
+            let Some(system) = entry.system else {
+                continue;
+            };
+            for checkpoint in &entry.checkpoints {
+                let from = checkpoint_png(screenshots_dir, system, sha1, checkpoint.frame);
+                let to = checkpoint_png(&frames, system, sha1, checkpoint.frame);
+                if let Some(dir) = to.parent() {
+                    std::fs::create_dir_all(dir)
+                        .map_err(|e| format!("Cannot create {}: {e}", dir.display()))?;
+                }
+                // A frame that was never captured just has no picture.
+                let _ = std::fs::copy(&from, &to);
+            }
+        }
+        Ok(())
+    }
+}
+
+/// One checkpoint whose hashes moved.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct FrameChange {
+    pub frame: u32,
+    pub video_changed: bool,
+    pub audio_changed: bool,
+    /// Baseline, current and difference side by side, if both frames
+    /// were available.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub side_by_side: Option<String>,
+}
+
+/// A ROM whose result differs from the baseline.
+#[derive(Debug, Clone, Serialize)]
+pub struct RomChange {
+    pub path: String,
+    pub sha1: String,
+    pub system: Option<System>,
+    pub was: TestStatus,
+    pub now: TestStatus,
+    pub message: String,
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    pub frames: Vec<FrameChange>,
+}
+
+#[derive(Debug, Clone, Default, Serialize)]
+pub struct DiffReport {
+    pub regressions: Vec<RomChange>,
+    pub progressions: Vec<RomChange>,
+    pub new_crashes: Vec<RomChange>,
+    /// ROMs in this run that the baseline has never seen.
+    pub new_roms: Vec<String>,
+    /// Baseline ROMs that were not part of this run.
+    pub missing_roms: Vec<String>,
+    pub unchanged: usize,
+}
+
+impl DiffReport {
+    /// True if the run should fail a pre-merge check.
+    pub fn has_failures(&self) -> bool {
+        !self.regressions.is_empty() || !self.new_crashes.is_empty()
+    }
+}
+
+/// Checkpoints whose video or audio hash differs, by frame.
+fn changed_frames(was: &[FrameHashes], now: &[FrameHashes]) -> Vec<FrameChange> {
+    was.iter()
+        .filter_map(|old| {
+            let new = now.iter().find(|n| n.frame == old.frame);
+            let video_changed = new.is_none_or(|n| n.video != old.video);
+            let audio_changed = new.is_none_or(|n| n.audio != old.audio);
+            (video_changed || audio_changed).then_some(FrameChange {
+                frame: old.frame,
+                video_changed,
+                audio_changed,
+                side_by_side: None,
+            })
+        })
+        .collect()
+}
+
+/// Compare a run against `baseline`. Skipped ROMs count as not run.
+pub fn compare(baseline: &Baseline, results: &[TestResult]) -> DiffReport {
+    let mut report = DiffReport::default();
+    let mut seen = std::collections::HashSet::new();
+
+    for result in results {
+        if result.sha1.is_empty() || matches!(result.status, TestStatus::Skipped) {
+            continue;
+        }
+        seen.insert(result.sha1.as_str());
+        let Some(entry) = baseline.roms.get(&result.sha1) else {
+            report.new_roms.push(result.path.clone());
+            continue;
+        };
+
+        let change = |frames| RomChange {
+            path: result.path.clone(),
+            sha1: result.sha1.clone(),
+            system: result.system.or(entry.system),
+            was: entry.status,
+            now: result.status,
+            message: result.message.clone(),
+            frames,
+        };
+        let was_passing = matches!(entry.status, TestStatus::Passed);
+        let now_passing = matches!(result.status, TestStatus::Passed);
+
+        if matches!(result.status, TestStatus::Crashed)
+            && !matches!(entry.status, TestStatus::Crashed)
+        {
+            report.new_crashes.push(change(Vec::new()));
+        } else if was_passing && !now_passing {
+            report.regressions.push(change(Vec::new()));
+        } else if !was_passing && now_passing {
+            report.progressions.push(change(Vec::new()));
+        } else {
+            let frames = changed_frames(&entry.checkpoints, &result.checkpoints);
+            if frames.is_empty() {
+                report.unchanged += 1;
+            } else {
+                report.regressions.push(change(frames));
+            }
+        }
+    }
+
+    report.missing_roms = baseline
+        .roms
+        .iter()
+        .filter(|(sha1, _)| !seen.contains(sha1.as_str()))
+        .map(|(_, entry)| entry.path.clone())
+        .collect();
+    report
+}
+
+/// Write baseline/current/difference PNGs for every changed video
+/// checkpoint in `report` to `diff_dir`, filling in `side_by_side`.
+pub fn write_side_by_sides(
+    report: &mut DiffReport,
+    baseline_path: &Path,
+    screenshots_dir: &Path,
+    diff_dir: &Path,
+) {
+    let frames = frames_dir(baseline_path);
+    for change in &mut report.regressions {
+        let Some(system) = change.system else {
+            continue;
+        };
+        for frame in change.frames.iter_mut().filter(|f| f.video_changed) {
+            let was = checkpoint_png(&frames, system, &change.sha1, frame.frame);
+            let now = checkpoint_png(screenshots_dir, system, &change.sha1, frame.frame);
+            let out = checkpoint_png(diff_dir, system, &change.sha1, frame.frame);
+            if side_by_side(&was, &now, &out).is_ok() {
+                frame.side_by_side = Some(out.display().to_string());
+            }
+        }
+    }
+}
+
+/// An RGBA image.
+struct Image {
+    width: usize,
+    height: usize,
+    rgba: Vec<u8>,
+}
+
+impl Image {
+    fn pixel(&self, x: usize, y: usize) -> Option<[u8; 4]> {
+        (x < self.width && y < self.height).then(|| {
+            let at = (y * self.width + x) * 4;
+            [
+                self.rgba[at],
+                self.rgba[at + 1],
+                self.rgba[at + 2],
+                self.rgba[at + 3],
+            ]
+        })
+    }
+}
+
+fn read_png(path: &Path) -> Result<Image, String> {
+    let file = File::open(path).map_err(|e| format!("Cannot open {}: {e}", path.display()))?;
+    let mut reader = png::Decoder::new(BufReader::new(file))
+        .read_info()
+        .map_err(|e| format!("Invalid PNG {}: {e}", path.display()))?;
+    let mut rgba = vec![0; reader.output_buffer_size()];
+    let info = reader
+        .next_frame(&mut rgba)
+        .map_err(|e| format!("Invalid PNG {}: {e}", path.display()))?;
+    if info.color_type != png::ColorType::Rgba || info.bit_depth != png::BitDepth::Eight {
+        return Err(format!("{} is not 8-bit RGBA", path.display()));
+    }
+    rgba.truncate(info.buffer_size());
+    Ok(Image {
+        width: info.width as usize,
+        height: info.height as usize,
+        rgba,
+    })
+}
+
+/// Baseline, current and a difference panel (changed pixels in red over
+/// the dimmed current frame), left to right.
+fn side_by_side(was: &Path, now: &Path, out: &Path) -> Result<(), String> {
+    let was = read_png(was)?;
+    let now = read_png(now)?;
+    let panel = was.width.max(now.width);
+    let width = panel * 3;
+    let height = was.height.max(now.height);
+
+    let mut rgba = vec![0; width * height * 4];
+    for y in 0..height {
+        for x in 0..panel {
+            let old = was.pixel(x, y);
+            let new = now.pixel(x, y);
+            let diff = match (old, new) {
+                (Some(o), Some(n)) if o == n => [n[0] / 3, n[1] / 3, n[2] / 3, 0xFF],
+                _ => [0xFF, 0, 0, 0xFF],
+            };
+            for (i, px) in [old, new, Some(diff)].into_iter().enumerate() {
+                let at = (y * width + i * panel + x) * 4;
+                rgba[at..at + 4].copy_from_slice(&px.unwrap_or([0, 0, 0, 0xFF]));
+            }
+        }
+    }
+
+    if let Some(dir) = out.parent() {
+        std::fs::create_dir_all(dir)
+            .map_err(|e| format!("Cannot create {}: {e}", dir.display()))?;
+    }
+    let file = File::create(out).map_err(|e| format!("Cannot create {}: {e}", out.display()))?;
+    let mut encoder = png::Encoder::new(BufWriter::new(file), width as u32, height as u32);
+    encoder.set_color(png::ColorType::Rgba);
+    encoder.set_depth(png::BitDepth::Eight);
+    encoder
+        .write_header()
+        .and_then(|mut writer| writer.write_image_data(&rgba))
+        .map_err(|e| format!("Cannot write {}: {e}", out.display()))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{Baseline, FrameHashes, compare};
+    use crate::{System, TestResult, TestStatus};
+
+    fn result(sha1: &str, status: TestStatus, video: &str) -> TestResult {
+        TestResult {
+            path: format!("roms/{sha1}.nes"),
+            system: Some(System::Nes),
+            sha1: sha1.to_string(),
+            file_size: 0,
+            status,
+            message: String::new(),
+            frames_run: 60,
+            elapsed_ms: 0,
+            screenshot_path: None,
+            has_display_output: false,
+            unique_colors: 0,
+            checkpoints: vec![FrameHashes {
+                frame: 60,
+                video: video.to_string(),
+                audio: "quiet".to_string(),
+            }],
+        }
+    }
+
+    #[test]
+    fn runs_are_sorted_into_regressions_progressions_and_crashes() {
+        let baseline = Baseline::from_results(&[
+            result("same", TestStatus::Passed, "a"),
+            result("moved", TestStatus::Passed, "a"),
+            result("fixed", TestStatus::Failed, "a"),
+            result("broke", TestStatus::Passed, "a"),
+            result("gone", TestStatus::Passed, "a"),
+        ]);
+        let run = [
+            result("same", TestStatus::Passed, "a"),
+            result("moved", TestStatus::Passed, "b"),
+            result("fixed", TestStatus::Passed, "b"),
+            result("broke", TestStatus::Crashed, ""),
+            result("fresh", TestStatus::Passed, "a"),
+            result("skip", TestStatus::Skipped, ""),
+        ];
+
+        let report = compare(&baseline, &run);
+        let shas = |changes: &[super::RomChange]| -> Vec<String> {
+            changes.iter().map(|c| c.sha1.clone()).collect()
+        };
+        assert_eq!(report.unchanged, 1);
+        assert_eq!(shas(&report.regressions), ["moved"]);
+        assert_eq!(report.regressions[0].frames[0].frame, 60);
+        assert!(report.regressions[0].frames[0].video_changed);
+        assert!(!report.regressions[0].frames[0].audio_changed);
+        assert_eq!(shas(&report.progressions), ["fixed"]);
+        assert_eq!(shas(&report.new_crashes), ["broke"]);
+        assert_eq!(report.new_roms, ["roms/fresh.nes"]);
+        assert_eq!(report.missing_roms, ["roms/gone.nes"]);
+        assert!(report.has_failures());
+    }
+
+    #[test]
+    fn side_by_side_marks_changed_pixels() {
+        let dir = std::env::temp_dir().join("emu-test-harness-side-by-side");
+        let write = |name: &str, pixels: &[u8]| {
+            let path = dir.join(name);
+            std::fs::create_dir_all(&dir).expect("dir");
+            let file = std::fs::File::create(&path).expect("create");
+            let mut encoder = png::Encoder::new(file, 2, 1);
+            encoder.set_color(png::ColorType::Rgba);
+            encoder.set_depth(png::BitDepth::Eight);
+            let mut writer = encoder.write_header().expect("header");
+            writer.write_image_data(pixels).expect("data");
+            path
+        };
+        let was = write("was.png", &[30, 30, 30, 255, 0, 0, 0, 255]);
+        let now = write("now.png", &[30, 30, 30, 255, 9, 9, 9, 255]);
+        let out = dir.join("out.png");
+        super::side_by_side(&was, &now, &out).expect("side by side");
+
+        let image = super::read_png(&out).expect("read back");
+        assert_eq!((image.width, image.height), (6, 1));
+        assert_eq!(image.pixel(3, 0), Some([9, 9, 9, 255]));
+        assert_eq!(image.pixel(4, 0), Some([10, 10, 10, 255]));
+        assert_eq!(image.pixel(5, 0), Some([255, 0, 0, 255]));
+    }
+}
@@ -1,9 +1,14 @@
 //! Bulk ROM testing harness for Emu198x.
 //!
-//! Scans a directory tree of TOSEC/No-Intro/GoodTools ROM sets, identifies
-//! each ROM's target system by extension and header inspection, runs it
-//! headlessly for a configurable number of frames, captures a screenshot,
-//! and produces a JSON report with per-ROM status, timing, and hashes.
+//! Scans a directory tree of TOSEC/No-Intro/GoodTools ROM sets, including
+//! the insides of `.zip` and `.7z` archives, identifies each ROM's target
+//! system by extension and header inspection, runs it headlessly for a
+//! configurable number of frames, captures a screenshot, and produces a
+//! JSON report with per-ROM status, timing, and hashes.
+//!
+//! A run stops early if the CPU jams (or a Z80 halts with interrupts off),
+//! if neither the picture nor the sound changes for `--hang-seconds`, or
+//! when the system's wall-clock timeout runs out.
 //!
 //! Usage:
 //!   emu-test-harness <rom-dir> [OPTIONS]
@@ -15,192 +20,52 @@
 //!   --parallel <n>      Number of parallel workers [default: CPU count]
 //!   --system <name>     Only test a specific system (spectrum, nes, c64, etc.)
 //!   --roms-dir <dir>    Directory containing system ROMs (BIOS files)
+//!   --checkpoints <f,…> Frames to hash video and audio at [default: last]
+//!   --baseline <file>   Compare against a golden-frame baseline
+//!   --update-baseline <file>  Write this run as the new baseline
+//!   --diff-report <file> Baseline diff report [default: baseline-diff.json]
+//!   --diff-dir <dir>    Side-by-side PNGs of changed frames [default: diffs/]
+//!   --timeout [<system>=]<s>  Wall-clock limit per ROM [default: 60]
+//!   --hang-seconds <s>  Emulated seconds without change that count as a
+//!                       hang, 0 to disable [default: 10]
+//!
+//! See [`baseline`] for how runs are compared.
+
+mod baseline;
+mod source;
 
+use std::collections::HashMap;
 use std::io::BufWriter;
 use std::path::{Path, PathBuf};
 use std::process;
 use std::time::{Duration, Instant};
 
-use emu_core::Machine;
+use emu_core::{Machine, Observable, Value};
+use emu198x::identify::{System, a78_header, car_header, identify_system};
 use rayon::prelude::*;
-use serde::Serialize;
+use serde::{Deserialize, Serialize};
 use sha1::{Digest, Sha1};
 
-// ---------------------------------------------------------------------------
-// ROM identification
-// ---------------------------------------------------------------------------
-
-#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
-#[serde(rename_all = "lowercase")]
-enum System {
-    Spectrum,
-    C64,
-    Nes,
-    Amiga,
-    Atari2600,
-    Sg1000,
-    ColecoVision,
-    Msx,
-    Sms,
-    GameGear,
-    BbcMicro,
-}
-
-impl System {
-    fn name(self) -> &'static str {
-        match self {
-            Self::Spectrum => "spectrum",
-            Self::C64 => "c64",
-            Self::Nes => "nes",
-            Self::Amiga => "amiga",
-            Self::Atari2600 => "atari2600",
-            Self::Sg1000 => "sg1000",
-            Self::ColecoVision => "colecovision",
-            Self::Msx => "msx",
-            Self::Sms => "sms",
-            Self::GameGear => "gamegear",
-            Self::BbcMicro => "bbc",
-        }
-    }
-}
-
-fn identify_system(path: &Path, data: &[u8]) -> Option<System> {
-    // 1. Check directory path for TOSEC/No-Intro system names.
-    //    This is the most reliable signal — collection directories are
-    //    organised by system.
-    if let Some(system) = identify_from_path(path) {
-        return Some(system);
-    }
-
-    // 2. Unambiguous file extensions (these only belong to one system).
-    let ext = path
-        .extension()
-        .and_then(|e| e.to_str())
-        .map(|e| e.to_ascii_lowercase())
-        .unwrap_or_default();
-
-    match ext.as_str() {
-        // Spectrum
-        "z80" | "sna" | "tap" | "tzx" => return Some(System::Spectrum),
-        // NES (check iNES magic)
-        "nes" if data.len() >= 4 && &data[0..4] == b"NES\x1a" => return Some(System::Nes),
-        "nes" => return Some(System::Nes),
-        // C64
-        "d64" | "t64" | "crt" => return Some(System::C64),
-        // Amiga
-        "adf" if data.len() == 901_120 => return Some(System::Amiga),
-        // Atari 2600 (unambiguous extension)
-        "a26" => return Some(System::Atari2600),
-        // SG-1000
-        "sg" | "sc" => return Some(System::Sg1000),
-        // ColecoVision
-        "col" => return Some(System::ColecoVision),
-        // SMS / Game Gear
-        "sms" => return Some(System::Sms),
-        "gg" => return Some(System::GameGear),
-        // BBC Micro disk images
-        "ssd" | "dsd" => return Some(System::BbcMicro),
-        _ => {}
-    }
-
-    // 3. Ambiguous extensions (.bin, .rom, .prg) — use header heuristics.
-    match ext.as_str() {
-        "rom" if data.len() >= 2 && data[0] == 0x41 && data[1] == 0x42 => Some(System::Msx),
-        "bin" if data.len() >= 0x8000 && looks_like_sms(data) => Some(System::Sms),
-        // Only classify .bin as Atari 2600 if the size exactly matches
-        // common cartridge sizes AND the reset vector points to ROM space
-        "bin" if is_likely_2600(data) => Some(System::Atari2600),
-        _ => None,
-    }
-}
-
-/// Identify the system from directory names in the path.
-///
-/// TOSEC collections use paths like:
-///   `sinclair/spectrum/Games/[Z80]/game.z80`
-///   `commodore/c64/Games/Arcade/game.d64`
-///   `nintendo/nes/test-suites/game.nes`
-///   `sega/master-system/Games/game.sms`
-fn identify_from_path(path: &Path) -> Option<System> {
-    let path_str = path.to_string_lossy().to_ascii_lowercase();
-
-    // Check path components for system keywords (most specific first)
-    let checks: &[(&[&str], System)] = &[
-        // Spectrum
-        (&["spectrum", "zx spectrum", "zx-spectrum", "sinclair"], System::Spectrum),
-        // NES
-        (&["nes", "famicom", "nintendo entertainment"], System::Nes),
-        // C64
-        (&["/c64/", "/c64 ", "commodore 64", "commodore-64", "/c64dtv/"], System::C64),
-        // Amiga
-        (&["amiga"], System::Amiga),
-        // Atari 2600
-        (&["2600", "atari-2600", "atari 2600", "vcs"], System::Atari2600),
-        // SG-1000
-        (&["sg-1000", "sg1000"], System::Sg1000),
-        // ColecoVision
-        (&["coleco"], System::ColecoVision),
-        // MSX
-        (&["/msx/", "/msx1/", "/msx2/"], System::Msx),
-        // SMS
-        (&["master system", "master-system", "sms"], System::Sms),
-        // Game Gear
-        (&["game gear", "game-gear", "gamegear"], System::GameGear),
-        // BBC Micro
-        (&["bbc", "acorn"], System::BbcMicro),
-    ];
-
-    for (keywords, system) in checks {
-        for keyword in *keywords {
-            if path_str.contains(keyword) {
-                return Some(*system);
-            }
-        }
-    }
-
-    None
-}
-
-fn is_likely_2600(data: &[u8]) -> bool {
-    // Atari 2600 ROMs are exactly 2K, 4K, 8K, 16K, or 32K.
-    // Also check that the reset vector ($FFFC-$FFFD) points to valid ROM space.
-    if !matches!(data.len(), 2048 | 4096 | 8192 | 16384 | 32768) {
-        return false;
-    }
-    let len = data.len();
-    // Reset vector is at the end of the ROM image
-    if len >= 4 {
-        let reset_lo = data[len - 4] as u16;
-        let reset_hi = data[len - 3] as u16;
-        let reset = reset_hi << 8 | reset_lo;
-        // Valid 2600 reset vectors point to $F000-$FFFF range
-        reset >= 0xF000
-    } else {
-        false
-    }
-}
-
-fn looks_like_sms(data: &[u8]) -> bool {
-    // SMS ROMs have "TMR SEGA" at $7FF0
-    if data.len() >= 0x8000 {
-        let header = &data[0x7FF0..0x7FF8];
-        header == b"TMR SEGA"
-    } else {
-        false
-    }
-}
+use crate::baseline::{Baseline, FrameHashes};
+use crate::source::RomSource;
 
 // ---------------------------------------------------------------------------
 // Test execution
 // ---------------------------------------------------------------------------
 
-#[derive(Debug, Clone, Serialize)]
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
 #[serde(rename_all = "lowercase")]
 enum TestStatus {
     Passed,
     Failed,
     Skipped,
     Error,
+    /// The emulator panicked.
+    Crashed,
+    /// The CPU stopped, or nothing changed for the hang threshold.
+    Hung,
+    /// The run was still going when the system's timeout ran out.
+    Timeout,
 }
 
 #[derive(Debug, Clone, Serialize)]
@@ -218,17 +83,42 @@ struct TestResult {
     has_display_output: bool,
     /// Number of unique colors in the final framebuffer.
     unique_colors: usize,
+    /// Video and audio hashes at each checkpoint frame.
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    checkpoints: Vec<FrameHashes>,
 }
 
 struct TestConfig {
     frames: u32,
+    /// Wall-clock limit per ROM, unless the system has its own.
+    timeout: Duration,
+    system_timeouts: HashMap<System, Duration>,
+    /// Emulated seconds without a video or audio change that count as a
+    /// hang; 0 disables the check.
+    hang_seconds: u32,
+    /// Frames to hash at, ascending; the last one is `frames`.
+    checkpoints: Vec<u32>,
+    /// Save a PNG of every checkpoint frame (for baselines).
+    save_checkpoint_frames: bool,
     screenshots_dir: PathBuf,
     roms_dir: Option<PathBuf>,
     system_filter: Option<String>,
 }
 
-fn run_test(path: &Path, config: &TestConfig) -> TestResult {
-    let data = match std::fs::read(path) {
+impl TestConfig {
+    fn timeout(&self, system: System) -> Duration {
+        self.system_timeouts.get(&system).copied().unwrap_or(self.timeout)
+    }
+}
+
+/// A machine the harness can run and inspect.
+trait Target: Machine + Observable {}
+
+impl<T: Machine + Observable> Target for T {}
+
+fn run_test(source: &RomSource, config: &TestConfig) -> TestResult {
+    let path = source.path();
+    let data = match source.read() {
         Ok(d) => d,
         Err(e) => {
             return TestResult {
@@ -237,19 +127,20 @@ fn run_test(path: &Path, config: &TestConfig) -> TestResult {
                 sha1: String::new(),
                 file_size: 0,
                 status: TestStatus::Error,
-                message: format!("Read error: {e}"),
+                message: e,
                 frames_run: 0,
                 elapsed_ms: 0,
                 screenshot_path: None,
                 has_display_output: false,
                 unique_colors: 0,
+                checkpoints: Vec::new(),
             };
         }
     };
 
     let sha1 = hex_sha1(&data);
     let file_size = data.len();
-    let system = identify_system(path, &data);
+    let system = identify_system(&path, &data);
 
     // Filter by system if requested
     if let Some(ref filter) = config.system_filter {
@@ -267,6 +158,7 @@ fn run_test(path: &Path, config: &TestConfig) -> TestResult {
                     screenshot_path: None,
                     has_display_output: false,
                     unique_colors: 0,
+                    checkpoints: Vec::new(),
                 };
             }
         }
@@ -285,12 +177,13 @@ fn run_test(path: &Path, config: &TestConfig) -> TestResult {
             screenshot_path: None,
             has_display_output: false,
             unique_colors: 0,
+            checkpoints: Vec::new(),
         };
     };
 
     // Try to create the machine
-    let machine_result = create_machine(system, &data, config);
-    let mut machine: Box<dyn Machine> = match machine_result {
+    let machine_result = create_machine(system, &path, &data, config);
+    let mut machine: Box<dyn Target> = match machine_result {
         Ok(m) => m,
         Err(msg) => {
             return TestResult {
@@ -305,15 +198,82 @@ fn run_test(path: &Path, config: &TestConfig) -> TestResult {
                 screenshot_path: None,
                 has_display_output: false,
                 unique_colors: 0,
+                checkpoints: Vec::new(),
             };
         }
     };
 
-    // Run frames
+    // Run frames, hashing at each checkpoint and watching for hangs
     let start = Instant::now();
-    for _ in 0..config.frames {
+    let deadline = start + config.timeout(system);
+    let hang_frames = config.hang_seconds * system.frame_rate();
+    let mut audio = Sha1::new();
+    let mut checkpoints = Vec::with_capacity(config.checkpoints.len());
+    let mut last_picture = machine.framebuffer().to_vec();
+    let mut last_sample = [0; 2];
+    let mut still_frames = 0;
+    let mut frames_run = 0;
+    let mut stopped = None;
+    for frame in 1..=config.frames {
         machine.run_frame();
-        let _ = machine.take_audio_buffer();
+        frames_run = frame;
+        let mut sound_changed = false;
+        for sample in machine.take_audio_buffer() {
+            let bits = sample.map(f32::to_bits);
+            sound_changed |= bits != last_sample;
+            last_sample = bits;
+            let [left, right] = sample;
+            audio.update(left.to_le_bytes());
+            audio.update(right.to_le_bytes());
+        }
+        if config.checkpoints.contains(&frame) {
+            checkpoints.push(FrameHashes {
+                frame,
+                video: hex_framebuffer_sha1(machine.as_ref()),
+                audio: hex_digest(&audio.clone().finalize()),
+            });
+            if config.save_checkpoint_frames {
+                let png = baseline::checkpoint_png(&config.screenshots_dir, system, &sha1, frame);
+                let _ = save_png(
+                    machine.framebuffer(),
+                    machine.framebuffer_width(),
+                    machine.framebuffer_height(),
+                    &png,
+                );
+            }
+        }
+
+        if cpu_halted(machine.as_ref()) {
+            stopped = Some((TestStatus::Hung, format!("CPU halted at frame {frame}")));
+            break;
+        }
+        if machine.framebuffer() == last_picture.as_slice() && !sound_changed {
+            still_frames += 1;
+        } else {
+            last_picture.clear();
+            last_picture.extend_from_slice(machine.framebuffer());
+            still_frames = 0;
+        }
+        if hang_frames > 0 && still_frames >= hang_frames {
+            stopped = Some((
+                TestStatus::Hung,
+                format!(
+                    "No video or audio change for {} s at frame {frame}",
+                    config.hang_seconds
+                ),
+            ));
+            break;
+        }
+        if Instant::now() >= deadline {
+            stopped = Some((
+                TestStatus::Timeout,
+                format!(
+                    "Timed out after {} s at frame {frame}",
+                    config.timeout(system).as_secs()
+                ),
+            ));
+            break;
+        }
     }
     let elapsed = start.elapsed();
 
@@ -332,16 +292,18 @@ fn run_test(path: &Path, config: &TestConfig) -> TestResult {
         &config.screenshots_dir,
     );
 
-    let status = if has_display_output {
-        TestStatus::Passed
-    } else {
-        TestStatus::Failed
-    };
-
-    let message = if has_display_output {
-        format!("{unique_colors} unique colors after {} frames", config.frames)
+    let (status, message) = if let Some(stopped) = stopped {
+        stopped
+    } else if has_display_output {
+        (
+            TestStatus::Passed,
+            format!("{unique_colors} unique colors after {} frames", config.frames),
+        )
     } else {
-        format!("Blank display after {} frames", config.frames)
+        (
+            TestStatus::Failed,
+            format!("Blank display after {} frames", config.frames),
+        )
     };
 
     TestResult {
@@ -351,19 +313,24 @@ fn run_test(path: &Path, config: &TestConfig) -> TestResult {
         file_size,
         status,
         message,
-        frames_run: config.frames as u64,
+        frames_run: u64::from(frames_run),
         elapsed_ms: elapsed.as_millis() as u64,
         screenshot_path,
         has_display_output,
         unique_colors,
+        checkpoints,
     }
 }
 
+/// Frames the Atari 8-bit OS gets to boot before an XEX is loaded.
+const XEX_BOOT_FRAMES: u32 = 120;
+
 fn create_machine(
     system: System,
+    path: &Path,
     data: &[u8],
     config: &TestConfig,
-) -> Result<Box<dyn Machine>, String> {
+) -> Result<Box<dyn Target>, String> {
     match system {
         System::Spectrum => {
             // Try loading as a snapshot first, otherwise as tape
@@ -392,6 +359,16 @@ fn create_machine(
                 spec.insert_tzx(tzx);
                 return Ok(Box::new(spec));
             }
+            // Try as PZX
+            if let Ok(pzx) = emu_spectrum::PzxFile::parse(data) {
+                spec.insert_pzx(pzx);
+                return Ok(Box::new(spec));
+            }
+            // Try as CSW
+            if let Ok(csw) = emu_spectrum::CswFile::parse(data) {
+                spec.insert_csw(csw);
+                return Ok(Box::new(spec));
+            }
             Ok(Box::new(spec))
         }
         System::Nes => {
@@ -400,7 +377,7 @@ fn create_machine(
                 region: emu_nes::NesRegion::Ntsc,
             };
             emu_nes::Nes::new(&cfg)
-                .map(|nes| Box::new(nes) as Box<dyn Machine>)
+                .map(|nes| Box::new(nes) as Box<dyn Target>)
                 .map_err(|e| format!("NES load error: {e}"))
         }
         System::Sg1000 => {
@@ -427,16 +404,38 @@ fn create_machine(
                 region: emu_atari_2600::Atari2600Region::Ntsc,
             };
             emu_atari_2600::Atari2600::new(&cfg)
-                .map(|sys| Box::new(sys) as Box<dyn Machine>)
+                .map(|sys| Box::new(sys) as Box<dyn Target>)
                 .map_err(|e| format!("Atari 2600 load error: {e}"))
         }
+        System::Atari5200 => {
+            // The BIOS is optional, though few carts get far without it
+            let cfg = emu_atari_5200::Atari5200Config {
+                rom_data: car_header(data).map_or(data, |(_, rom)| rom).to_vec(),
+                bios_data: std::fs::read(system_rom_path(config, "5200.rom")).ok(),
+                region: emu_atari_5200::Atari5200Region::Ntsc,
+            };
+            emu_atari_5200::Atari5200::new(&cfg)
+                .map(|sys| Box::new(sys) as Box<dyn Target>)
+                .map_err(|e| format!("Atari 5200 load error: {e}"))
+        }
+        System::Atari7800 => {
+            let (rom, pal) = a78_header(data).unwrap_or((data, false));
+            let cfg = emu_atari_7800::Atari7800Config {
+                rom_data: rom.to_vec(),
+                region: if pal {
+                    emu_atari_7800::Atari7800Region::Pal
+                } else {
+                    emu_atari_7800::Atari7800Region::Ntsc
+                },
+            };
+            emu_atari_7800::Atari7800::new(&cfg)
+                .map(|sys| Box::new(sys) as Box<dyn Target>)
+                .map_err(|e| format!("Atari 7800 load error: {e}"))
+        }
+        System::Atari800xl => create_atari_800xl(path, data, config),
         System::ColecoVision => {
             // Needs BIOS
-            let bios_path = config
-                .roms_dir
-                .as_ref()
-                .map(|d| d.join("coleco.rom"))
-                .unwrap_or_else(|| PathBuf::from("roms/coleco.rom"));
+            let bios_path = system_rom_path(config, "coleco.rom");
             let bios = std::fs::read(&bios_path)
                 .map_err(|e| format!("ColecoVision BIOS not found at {}: {e}", bios_path.display()))?;
             Ok(Box::new(emu_colecovision::ColecoVision::new(
@@ -447,11 +446,7 @@ fn create_machine(
         }
         System::Msx => {
             // Needs BIOS
-            let bios_path = config
-                .roms_dir
-                .as_ref()
-                .map(|d| d.join("msx.rom"))
-                .unwrap_or_else(|| PathBuf::from("roms/msx.rom"));
+            let bios_path = system_rom_path(config, "msx.rom");
             let bios = std::fs::read(&bios_path)
                 .map_err(|e| format!("MSX BIOS not found at {}: {e}", bios_path.display()))?;
             let mut msx = emu_msx::Msx::new(bios, emu_msx::MsxRegion::Ntsc);
@@ -459,11 +454,7 @@ fn create_machine(
             Ok(Box::new(msx))
         }
         System::BbcMicro => {
-            let mos_path = config
-                .roms_dir
-                .as_ref()
-                .map(|d| d.join("bbc-mos.rom"))
-                .unwrap_or_else(|| PathBuf::from("roms/bbc-mos.rom"));
+            let mos_path = system_rom_path(config, "bbc-mos.rom");
             let mos = std::fs::read(&mos_path)
                 .map_err(|e| format!("BBC MOS ROM not found at {}: {e}", mos_path.display()))?;
             Ok(Box::new(emu_bbc_micro::BbcMicro::new(mos)))
@@ -477,6 +468,58 @@ fn create_machine(
     }
 }
 
+/// An Atari 8-bit computer running a cartridge, an XEX or an ATR disk.
+fn create_atari_800xl(
+    path: &Path,
+    data: &[u8],
+    config: &TestConfig,
+) -> Result<Box<dyn Target>, String> {
+    let os_path = system_rom_path(config, "atarixl.rom");
+    let os_rom = std::fs::read(&os_path).ok();
+    let ext = path
+        .extension()
+        .and_then(|e| e.to_str())
+        .map(str::to_ascii_lowercase)
+        .unwrap_or_default();
+    let is_cart = !matches!(ext.as_str(), "xex" | "atr");
+    if !is_cart && os_rom.is_none() {
+        return Err(format!(
+            "Atari OS ROM not found at {} (needed for .{ext})",
+            os_path.display()
+        ));
+    }
+
+    let cfg = emu_atari_800xl::Atari800xlConfig {
+        model: emu_atari_800xl::Atari8bitModel::A800XL,
+        rom_data: is_cart.then(|| car_header(data).map_or(data, |(_, rom)| rom).to_vec()),
+        os_rom,
+        basic_rom: None,
+        region: emu_atari_800xl::Atari800xlRegion::Ntsc,
+        basic_enabled: false,
+    };
+    let mut sys = emu_atari_800xl::Atari800xl::new(&cfg)
+        .map_err(|e| format!("Atari 800XL load error: {e}"))?;
+    match ext.as_str() {
+        "atr" => sys.insert_atr(emu_atari_800xl::Atr::parse(data)?),
+        "xex" => {
+            for _ in 0..XEX_BOOT_FRAMES {
+                sys.run_frame();
+            }
+            sys.load_xex(data)?;
+        }
+        _ => {}
+    }
+    Ok(Box::new(sys))
+}
+
+/// Where a BIOS or OS ROM lives: `--roms-dir`, or `roms/`.
+fn system_rom_path(config: &TestConfig, name: &str) -> PathBuf {
+    config
+        .roms_dir
+        .as_ref()
+        .map_or_else(|| PathBuf::from("roms").join(name), |d| d.join(name))
+}
+
 // ---------------------------------------------------------------------------
 // Helpers
 // ---------------------------------------------------------------------------
@@ -484,8 +527,33 @@ fn create_machine(
 fn hex_sha1(data: &[u8]) -> String {
     let mut hasher = Sha1::new();
     hasher.update(data);
-    let result = hasher.finalize();
-    result.iter().map(|b| format!("{b:02x}")).collect()
+    hex_digest(&hasher.finalize())
+}
+
+fn hex_digest(digest: &[u8]) -> String {
+    use std::fmt::Write as _;
+    digest.iter().fold(String::new(), |mut out, b| {
+        let _ = write!(out, "{b:02x}");
+        out
+    })
+}
+
+/// SHA-1 of the framebuffer's size and pixels.
+fn hex_framebuffer_sha1(machine: &dyn Target) -> String {
+    let mut hasher = Sha1::new();
+    hasher.update(machine.framebuffer_width().to_le_bytes());
+    hasher.update(machine.framebuffer_height().to_le_bytes());
+    for pixel in machine.framebuffer() {
+        hasher.update(pixel.to_le_bytes());
+    }
+    hex_digest(&hasher.finalize())
+}
+
+/// True if the CPU can never run again: a 6502 has jammed, or a Z80 has
+/// halted with interrupts off.
+fn cpu_halted(machine: &dyn Target) -> bool {
+    let flag = |path| matches!(machine.query(path), Some(Value::Bool(true)));
+    flag("cpu.halted") && !flag("cpu.iff1")
 }
 
 fn count_unique_colors(fb: &[u32]) -> usize {
@@ -507,18 +575,22 @@ fn save_test_screenshot(
     system: System,
     output_dir: &Path,
 ) -> Option<String> {
-    let dir = output_dir.join(system.name());
-    std::fs::create_dir_all(&dir).ok()?;
-
-    let filename = format!("{sha1}.png");
-    let path = dir.join(&filename);
+    let path = output_dir.join(system.name()).join(format!("{sha1}.png"));
+    save_png(fb, width, height, &path).ok()?;
+    Some(path.display().to_string())
+}
 
-    let file = std::fs::File::create(&path).ok()?;
+fn save_png(fb: &[u32], width: u32, height: u32, path: &Path) -> Result<(), String> {
+    let fail = |e: &dyn std::fmt::Display| format!("Cannot write {}: {e}", path.display());
+    if let Some(dir) = path.parent() {
+        std::fs::create_dir_all(dir).map_err(|e| fail(&e))?;
+    }
+    let file = std::fs::File::create(path).map_err(|e| fail(&e))?;
     let writer = BufWriter::new(file);
     let mut encoder = png::Encoder::new(writer, width, height);
     encoder.set_color(png::ColorType::Rgba);
     encoder.set_depth(png::BitDepth::Eight);
-    let mut png_writer = encoder.write_header().ok()?;
+    let mut png_writer = encoder.write_header().map_err(|e| fail(&e))?;
 
     let mut rgba = Vec::with_capacity((width * height * 4) as usize);
     for &argb in fb {
@@ -527,23 +599,21 @@ fn save_test_screenshot(
         rgba.push((argb & 0xFF) as u8);
         rgba.push(0xFF);
     }
-    png_writer.write_image_data(&rgba).ok()?;
-
-    Some(path.display().to_string())
+    png_writer.write_image_data(&rgba).map_err(|e| fail(&e))
 }
 
 // ---------------------------------------------------------------------------
 // Directory scanning
 // ---------------------------------------------------------------------------
 
-fn scan_roms(dir: &Path) -> Vec<PathBuf> {
+fn scan_roms(dir: &Path) -> Vec<RomSource> {
     let mut files = Vec::new();
     scan_roms_recursive(dir, &mut files);
     files.sort();
     files
 }
 
-fn scan_roms_recursive(dir: &Path, files: &mut Vec<PathBuf>) {
+fn scan_roms_recursive(dir: &Path, files: &mut Vec<RomSource>) {
     let entries = match std::fs::read_dir(dir) {
         Ok(e) => e,
         Err(_) => return,
@@ -554,34 +624,49 @@ fn scan_roms_recursive(dir: &Path, files: &mut Vec<PathBuf>) {
         if path.is_dir() {
             scan_roms_recursive(&path, files);
         } else if path.is_file() {
-            let ext = path
-                .extension()
-                .and_then(|e| e.to_str())
-                .map(|e| e.to_ascii_lowercase())
-                .unwrap_or_default();
-
-            let known = matches!(
-                ext.as_str(),
-                "z80" | "sna" | "tap" | "tzx"
-                    | "nes"
-                    | "prg" | "d64" | "t64" | "crt"
-                    | "adf"
-                    | "a26"
-                    | "sg" | "sc"
-                    | "col"
-                    | "rom"
-                    | "sms" | "gg"
-                    | "ssd" | "dsd"
-                    | "bin"
-            );
-
-            if known {
-                files.push(path);
+            if source::is_archive(&path) {
+                for member in source::archive_members(&path) {
+                    if has_rom_extension(Path::new(&member)) {
+                        files.push(RomSource {
+                            file: path.clone(),
+                            member: Some(member),
+                        });
+                    }
+                }
+            } else if has_rom_extension(&path) {
+                files.push(RomSource {
+                    file: path,
+                    member: None,
+                });
             }
         }
     }
 }
 
+fn has_rom_extension(path: &Path) -> bool {
+    let ext = path
+        .extension()
+        .and_then(|e| e.to_str())
+        .map(str::to_ascii_lowercase)
+        .unwrap_or_default();
+
+    matches!(
+        ext.as_str(),
+        "z80" | "sna" | "tap" | "tzx" | "pzx" | "csw"
+            | "nes"
+            | "prg" | "d64" | "t64" | "crt"
+            | "adf"
+            | "a26" | "a52" | "a78"
+            | "car" | "xex" | "atr"
+            | "sg" | "sc"
+            | "col"
+            | "rom"
+            | "sms" | "gg"
+            | "ssd" | "dsd"
+            | "bin"
+    )
+}
+
 // ---------------------------------------------------------------------------
 // CLI and main
 // ---------------------------------------------------------------------------
@@ -594,6 +679,14 @@ struct CliArgs {
     parallel: usize,
     system_filter: Option<String>,
     roms_dir: Option<PathBuf>,
+    checkpoints: Vec<u32>,
+    baseline: Option<PathBuf>,
+    update_baseline: Option<PathBuf>,
+    diff_report: PathBuf,
+    diff_dir: PathBuf,
+    timeout: Duration,
+    system_timeouts: HashMap<System, Duration>,
+    hang_seconds: u32,
 }
 
 fn parse_args() -> CliArgs {
@@ -606,6 +699,14 @@ fn parse_args() -> CliArgs {
         parallel: num_cpus(),
         system_filter: None,
         roms_dir: None,
+        checkpoints: Vec::new(),
+        baseline: None,
+        update_baseline: None,
+        diff_report: PathBuf::from("baseline-diff.json"),
+        diff_dir: PathBuf::from("diffs"),
+        timeout: Duration::from_mins(1),
+        system_timeouts: HashMap::new(),
+        hang_seconds: 10,
     };
 
     if args.len() < 2 {
@@ -618,6 +719,13 @@ fn parse_args() -> CliArgs {
         eprintln!("  --parallel <n>      Workers [default: CPU count]");
         eprintln!("  --system <name>     Filter by system");
         eprintln!("  --roms-dir <dir>    BIOS ROM directory [default: roms/]");
+        eprintln!("  --checkpoints <f,…> Frames to hash video/audio at [default: last]");
+        eprintln!("  --baseline <file>   Compare against a golden-frame baseline");
+        eprintln!("  --update-baseline <file>  Save this run as the baseline");
+        eprintln!("  --diff-report <file> Diff report [default: baseline-diff.json]");
+        eprintln!("  --diff-dir <dir>    Side-by-side PNGs [default: diffs/]");
+        eprintln!("  --timeout [<system>=]<s>  Wall-clock limit per ROM [default: 60]");
+        eprintln!("  --hang-seconds <s>  Unchanged seconds that count as a hang [default: 10]");
         process::exit(1);
     }
 
@@ -654,14 +762,87 @@ fn parse_args() -> CliArgs {
                 i += 1;
                 cli.roms_dir = args.get(i).map(PathBuf::from);
             }
+            "--checkpoints" => {
+                i += 1;
+                if let Some(s) = args.get(i) {
+                    cli.checkpoints = s
+                        .split(',')
+                        .filter_map(|f| f.trim().parse().ok())
+                        .filter(|&f| f > 0)
+                        .collect();
+                }
+            }
+            "--baseline" => {
+                i += 1;
+                cli.baseline = args.get(i).map(PathBuf::from);
+            }
+            "--update-baseline" => {
+                i += 1;
+                cli.update_baseline = args.get(i).map(PathBuf::from);
+            }
+            "--diff-report" => {
+                i += 1;
+                if let Some(s) = args.get(i) {
+                    cli.diff_report = PathBuf::from(s);
+                }
+            }
+            "--diff-dir" => {
+                i += 1;
+                if let Some(s) = args.get(i) {
+                    cli.diff_dir = PathBuf::from(s);
+                }
+            }
+            "--timeout" => {
+                i += 1;
+                if let Some(s) = args.get(i) {
+                    parse_timeout(s, &mut cli);
+                }
+            }
+            "--hang-seconds" => {
+                i += 1;
+                cli.hang_seconds = args.get(i).and_then(|s| s.parse().ok()).unwrap_or(10);
+            }
             _ => {}
         }
         i += 1;
     }
 
+    // Run long enough to reach every checkpoint; the last frame is always
+    // one.
+    cli.checkpoints.sort_unstable();
+    cli.checkpoints.dedup();
+    if let Some(&last) = cli.checkpoints.last() {
+        cli.frames = cli.frames.max(last);
+    }
+    if cli.checkpoints.last() != Some(&cli.frames) {
+        cli.checkpoints.push(cli.frames);
+    }
+
     cli
 }
 
+/// Apply `--timeout <secs>` or `--timeout <system>=<secs>`.
+fn parse_timeout(arg: &str, cli: &mut CliArgs) {
+    let (system, secs) = match arg.split_once('=') {
+        Some((name, secs)) => (Some(name), secs),
+        None => (None, arg),
+    };
+    let Ok(secs) = secs.parse() else {
+        eprintln!("Ignoring --timeout {arg}: not a number of seconds");
+        return;
+    };
+    let timeout = Duration::from_secs(secs);
+    match system {
+        None => cli.timeout = timeout,
+        Some(name) => match System::from_name(name) {
+            Some(system) => {
+                cli.system_timeouts.insert(system, timeout);
+            }
+            None => eprintln!("Ignoring --timeout {arg}: unknown system {name}"),
+        },
+    }
+}
+
 fn num_cpus() -> usize {
     std::thread::available_parallelism()
         .map(|n| n.get())
@@ -685,6 +866,11 @@ fn main() {
 
     let config = TestConfig {
         frames: cli.frames,
+        timeout: cli.timeout,
+        system_timeouts: cli.system_timeouts.clone(),
+        hang_seconds: cli.hang_seconds,
+        checkpoints: cli.checkpoints.clone(),
+        save_checkpoint_frames: cli.baseline.is_some() || cli.update_baseline.is_some(),
         screenshots_dir: cli.screenshots_dir.clone(),
         roms_dir: cli.roms_dir.clone(),
         system_filter: cli.system_filter.clone(),
@@ -706,31 +892,40 @@ fn main() {
     let results: Vec<TestResult> = files
         .par_iter()
         .enumerate()
-        .map(|(i, path)| {
+        .map(|(i, source)| {
             // Catch panics so one bad ROM doesn't kill the whole run.
             let result = match std::panic::catch_unwind(std::panic::AssertUnwindSafe(|| {
-                run_test(path, &config)
+                run_test(source, &config)
             })) {
                 Ok(r) => r,
-                Err(_) => TestResult {
-                    path: path.display().to_string(),
-                    system: None,
-                    sha1: String::new(),
-                    file_size: 0,
-                    status: TestStatus::Error,
-                    message: "Panic during execution".to_string(),
-                    frames_run: 0,
-                    elapsed_ms: 0,
-                    screenshot_path: None,
-                    has_display_output: false,
-                    unique_colors: 0,
-                },
+                Err(_) => {
+                    // Identify the ROM again so a baseline can match the crash.
+                    let path = source.path();
+                    let data = source.read().ok();
+                    TestResult {
+                        path: path.display().to_string(),
+                        system: data.as_ref().and_then(|d| identify_system(&path, d)),
+                        sha1: data.as_deref().map(hex_sha1).unwrap_or_default(),
+                        file_size: data.as_ref().map_or(0, Vec::len),
+                        status: TestStatus::Crashed,
+                        message: "Panic during execution".to_string(),
+                        frames_run: 0,
+                        elapsed_ms: 0,
+                        screenshot_path: None,
+                        has_display_output: false,
+                        unique_colors: 0,
+                        checkpoints: Vec::new(),
+                    }
+                }
             };
             let status_char = match result.status {
                 TestStatus::Passed => '.',
                 TestStatus::Failed => 'F',
                 TestStatus::Skipped => 's',
                 TestStatus::Error => 'E',
+                TestStatus::Crashed => 'C',
+                TestStatus::Hung => 'H',
+                TestStatus::Timeout => 'T',
             };
             eprint!("{status_char}");
             if (i + 1) % 80 == 0 {
@@ -748,9 +943,14 @@ fn main() {
     let failed = results.iter().filter(|r| matches!(r.status, TestStatus::Failed)).count();
     let skipped = results.iter().filter(|r| matches!(r.status, TestStatus::Skipped)).count();
     let errors = results.iter().filter(|r| matches!(r.status, TestStatus::Error)).count();
+    let crashed = results.iter().filter(|r| matches!(r.status, TestStatus::Crashed)).count();
+    let hung = results.iter().filter(|r| matches!(r.status, TestStatus::Hung)).count();
+    let timed_out = results.iter().filter(|r| matches!(r.status, TestStatus::Timeout)).count();
 
     eprintln!();
-    eprintln!("Results: {passed} passed, {failed} failed, {skipped} skipped, {errors} errors");
+    eprintln!(
+        "Results: {passed} passed, {failed} failed, {skipped} skipped, {errors} errors, {crashed} crashed, {hung} hung, {timed_out} timed out"
+    );
     eprintln!("Total time: {:.1}s", elapsed.as_secs_f64());
 
     // Per-system breakdown
@@ -790,4 +990,66 @@ fn main() {
         "Screenshots in {}",
         cli.screenshots_dir.display()
     );
+
+    let mut baseline_failed = false;
+    if let Some(path) = &cli.baseline {
+        baseline_failed = compare_with_baseline(&cli, path, &results);
+    }
+    if let Some(path) = &cli.update_baseline {
+        if let Err(e) = Baseline::from_results(&results).save(path, &cli.screenshots_dir) {
+            eprintln!("{e}");
+            process::exit(1);
+        }
+        eprintln!("Baseline written to {}", path.display());
+    }
+    if baseline_failed {
+        process::exit(1);
+    }
+}
+
+/// Compare the run against the baseline at `path`, write the diff report
+/// and side-by-side PNGs, and return true on regressions or new crashes.
+fn compare_with_baseline(cli: &CliArgs, path: &Path, results: &[TestResult]) -> bool {
+    let golden = Baseline::load(path).unwrap_or_else(|e| {
+        eprintln!("{e}");
+        process::exit(1);
+    });
+    let mut diff = baseline::compare(&golden, results);
+    baseline::write_side_by_sides(&mut diff, path, &cli.screenshots_dir, &cli.diff_dir);
+
+    eprintln!();
+    eprintln!(
+        "Baseline {}: {} regressions, {} progressions, {} new crashes, {} unchanged, {} new, {} missing",
+        path.display(),
+        diff.regressions.len(),
+        diff.progressions.len(),
+        diff.new_crashes.len(),
+        diff.unchanged,
+        diff.new_roms.len(),
+        diff.missing_roms.len()
+    );
+    for (label, changes) in [
+        ("CRASH", &diff.new_crashes),
+        ("REGRESSION", &diff.regressions),
+        ("PROGRESSION", &diff.progressions),
+    ] {
+        for change in changes {
+            eprintln!("  {label:11} {}", change.path);
+        }
+    }
+
+    match serde_json::to_string_pretty(&diff) {
+        Ok(json) => {
+            if let Err(e) = std::fs::write(&cli.diff_report, json) {
+                eprintln!("Failed to write {}: {e}", cli.diff_report.display());
+                process::exit(1);
+            }
+        }
+        Err(e) => {
+            eprintln!("JSON serialization error: {e}");
+            process::exit(1);
+        }
+    }
+    eprintln!("Diff report written to {}", cli.diff_report.display());
+    diff.has_failures()
 }
@@ -0,0 +1,200 @@
+//! Where a ROM's bytes come from: a plain file or a member of a `.zip` or
+//! `.7z` archive.
+//!
+//! Archive members are reported as if the archive were a directory
+//! (`Game (USA).zip/Game (USA).a78`), so identification by path and
+//! extension works the same for both.
+
+use std::fs::File;
+use std::io::Read;
+use std::path::{Path, PathBuf};
+
+/// A ROM on disk or inside an archive.
+#[derive(Debug, Clone, PartialEq, Eq, PartialOrd, Ord)]
+pub struct RomSource {
+    /// File on disk.
+    pub file: PathBuf,
+    /// Member name, for ROMs inside an archive.
+    pub member: Option<String>,
+}
+
+impl RomSource {
+    /// Path used to identify and report the ROM.
+    pub fn path(&self) -> PathBuf {
+        match &self.member {
+            Some(member) => self.file.join(member),
+            None => self.file.clone(),
+        }
+    }
+
+    /// Read the ROM's bytes, unpacking it if it is an archive member.
+    pub fn read(&self) -> Result<Vec<u8>, String> {
+        let Some(member) = &self.member else {
+            return std::fs::read(&self.file).map_err(|e| format!("Read error: {e}"));
+        };
+        let fail = |e: &dyn std::fmt::Display| {
+            format!("Cannot unpack {member} from {}: {e}", self.file.display())
+        };
+        if is_7z(&self.file) {
+            let mut reader =
+                sevenz_rust::SevenZReader::open(&self.file, sevenz_rust::Password::empty())
+                    .map_err(|e| fail(&e))?;
+            let mut data = None;
+            reader
+                .for_each_entries(|entry, contents| {
+                    if data.is_some() {
+                        return Ok(false);
+                    }
+                    if entry.name() == member {
+                        let mut bytes = Vec::new();
+                        contents.read_to_end(&mut bytes)?;
+                        data = Some(bytes);
+                        return Ok(false);
+                    }
+                    // Solid archives need every earlier member read past.
+                    std::io::copy(contents, &mut std::io::sink())?;
+                    Ok(true)
+                })
+                .map_err(|e| fail(&e))?;
+            data.ok_or_else(|| fail(&"not found"))
+        } else {
+            let file = File::open(&self.file).map_err(|e| fail(&e))?;
+            let mut archive = zip::ZipArchive::new(file).map_err(|e| fail(&e))?;
+            let mut entry = archive.by_name(member).map_err(|e| fail(&e))?;
+            let mut bytes = Vec::new();
+            entry.read_to_end(&mut bytes).map_err(|e| fail(&e))?;
+            Ok(bytes)
+        }
+    }
+}
+
+fn extension(path: &Path) -> String {
+    path.extension()
+        .and_then(|e| e.to_str())
+        .map(str::to_ascii_lowercase)
+        .unwrap_or_default()
+}
+
+fn is_7z(path: &Path) -> bool {
+    extension(path) == "7z"
+}
+
+/// True for `.zip` and `.7z` files.
+pub fn is_archive(path: &Path) -> bool {
+    matches!(extension(path).as_str(), "zip" | "7z")
+}
+
+/// Names of the files inside an archive. Unreadable archives list
+/// nothing.
+pub fn archive_members(path: &Path) -> Vec<String> {
+    if is_7z(path) {
+        let Ok(archive) = sevenz_rust::Archive::open(path) else {
+            return Vec::new();
+        };
+        archive
+            .files
+            .iter()
+            .filter(|entry| !entry.is_directory())
+            .map(|entry| entry.name().to_string())
+            .collect()
+    } else {
+        let Some(mut archive) = File::open(path)
+            .ok()
+            .and_then(|file| zip::ZipArchive::new(file).ok())
+        else {
+            return Vec::new();
+        };
+        (0..archive.len())
+            .filter_map(|i| {
+                let entry = archive.by_index(i).ok()?;
+                entry.is_file().then(|| entry.name().to_string())
+            })
+            .collect()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::io::Write;
+
+    use emu198x::identify::{System, identify_system};
+
+    use super::*;
+
+    #[test]
+    fn zip_members_read_like_files() {
+        let dir = std::env::temp_dir().join(format!("emu-harness-zip-{}", std::process::id()));
+        std::fs::create_dir_all(&dir).expect("temp dir");
+        let zip_path = dir.join("Game (USA).zip");
+
+        let mut writer = zip::ZipWriter::new(File::create(&zip_path).expect("create"));
+        let options = zip::write::SimpleFileOptions::default();
+        writer.add_directory("docs/", options).expect("dir");
+        writer.start_file("Game (USA).a52", options).expect("file");
+        writer.write_all(&[0xA5; 4096]).expect("write");
+        writer.finish().expect("finish");
+
+        assert!(is_archive(&zip_path));
+        assert_eq!(archive_members(&zip_path), ["Game (USA).a52"]);
+
+        let source = RomSource {
+            file: zip_path.clone(),
+            member: Some("Game (USA).a52".to_string()),
+        };
+        assert_eq!(source.path(), zip_path.join("Game (USA).a52"));
+        assert_eq!(source.read().expect("member"), [0xA5; 4096]);
+
+        let missing = RomSource {
+            file: zip_path,
+            member: Some("other.a52".to_string()),
+        };
+        assert!(missing.read().is_err());
+        let _ = std::fs::remove_dir_all(dir);
+    }
+
+    #[test]
+    fn sevenz_members_read_and_identify() {
+        let dir = std::env::temp_dir().join(format!("emu-archive-7z-{}", std::process::id()));
+        std::fs::create_dir_all(&dir).expect("temp dir");
+        let archive_path = dir.join("Game (USA).7z");
+
+        let mut writer = sevenz_rust::SevenZWriter::create(&archive_path).expect("create");
+        let mut docs = sevenz_rust::SevenZArchiveEntry::new();
+        docs.name = "docs".to_string();
+        docs.is_directory = true;
+        writer.push_archive_entry::<&[u8]>(docs, None).expect("dir");
+        for (name, fill) in [("readme.txt", 0x20), ("Game (USA).a52", 0xA5)] {
+            let mut entry = sevenz_rust::SevenZArchiveEntry::new();
+            entry.name = name.to_string();
+            entry.has_stream = true;
+            writer
+                .push_archive_entry(entry, Some(&[fill; 4096][..]))
+                .expect("file");
+        }
+        writer.finish().expect("finish");
+
+        assert!(is_archive(&archive_path));
+        assert_eq!(
+            archive_members(&archive_path),
+            ["readme.txt", "Game (USA).a52"]
+        );
+
+        let source = RomSource {
+            file: archive_path.clone(),
+            member: Some("Game (USA).a52".to_string()),
+        };
+        let data = source.read().expect("member");
+        assert_eq!(data, [0xA5; 4096]);
+        assert_eq!(
+            identify_system(&source.path(), &data),
+            Some(System::Atari5200)
+        );
+
+        let missing = RomSource {
+            file: archive_path,
+            member: Some("other.a52".to_string()),
+        };
+        assert!(missing.read().is_err());
+        let _ = std::fs::remove_dir_all(dir);
+    }
+}
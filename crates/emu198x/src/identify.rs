@@ -0,0 +1,342 @@
+//! Media identification.
+//!
+//! Works out which system a ROM, disk, tape or snapshot file is for, from
+//! its header, its path and its extension. The test harness uses it to
+//! sort ROM collections and the `emu198x` front end uses it to pick the
+//! emulator to run.
+
+use std::path::Path;
+
+use serde::{Deserialize, Serialize};
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum System {
+    Spectrum,
+    C64,
+    Nes,
+    Amiga,
+    Atari2600,
+    Atari5200,
+    Atari7800,
+    Atari800xl,
+    Sg1000,
+    ColecoVision,
+    Msx,
+    Sms,
+    GameGear,
+    BbcMicro,
+}
+
+impl System {
+    /// Every system, in a stable order.
+    pub const ALL: [Self; 14] = [
+        Self::Spectrum,
+        Self::C64,
+        Self::Nes,
+        Self::Amiga,
+        Self::Atari2600,
+        Self::Atari5200,
+        Self::Atari7800,
+        Self::Atari800xl,
+        Self::Sg1000,
+        Self::ColecoVision,
+        Self::Msx,
+        Self::Sms,
+        Self::GameGear,
+        Self::BbcMicro,
+    ];
+
+    /// Look a system up by its [`name`](Self::name).
+    #[must_use]
+    pub fn from_name(name: &str) -> Option<Self> {
+        Self::ALL.into_iter().find(|system| system.name() == name)
+    }
+
+    /// Short lowercase name, as used by `--system` and in reports.
+    #[must_use]
+    pub fn name(self) -> &'static str {
+        match self {
+            Self::Spectrum => "spectrum",
+            Self::C64 => "c64",
+            Self::Nes => "nes",
+            Self::Amiga => "amiga",
+            Self::Atari2600 => "atari2600",
+            Self::Atari5200 => "atari5200",
+            Self::Atari7800 => "atari7800",
+            Self::Atari800xl => "atari800xl",
+            Self::Sg1000 => "sg1000",
+            Self::ColecoVision => "colecovision",
+            Self::Msx => "msx",
+            Self::Sms => "sms",
+            Self::GameGear => "gamegear",
+            Self::BbcMicro => "bbc",
+        }
+    }
+
+    /// Nominal frames per second of the default model.
+    #[must_use]
+    pub fn frame_rate(self) -> u32 {
+        match self {
+            Self::Spectrum | Self::C64 | Self::Amiga | Self::BbcMicro => 50,
+            _ => 60,
+        }
+    }
+}
+
+/// Identify the system a media file belongs to.
+///
+/// Headers that name a system win, then system names in the path (ROM
+/// collections are sorted into per-system directories), then file
+/// extensions, then header heuristics for ambiguous `.bin` and `.rom`
+/// files. `None` if nothing matches.
+#[must_use]
+pub fn identify_system(path: &Path, data: &[u8]) -> Option<System> {
+    // 1. Headers that name their system outrank everything else.
+    if let Some(system) = identify_from_header(data) {
+        return Some(system);
+    }
+
+    // 2. Check directory path for TOSEC/No-Intro system names.
+    //    This is the most reliable signal — collection directories are
+    //    organised by system.
+    if let Some(system) = identify_from_path(path) {
+        return Some(system);
+    }
+
+    // 3. Unambiguous file extensions (these only belong to one system).
+    let ext = path
+        .extension()
+        .and_then(|e| e.to_str())
+        .map(str::to_ascii_lowercase)
+        .unwrap_or_default();
+
+    match ext.as_str() {
+        // Spectrum
+        "z80" | "sna" | "szx" | "tap" | "tzx" | "pzx" | "csw" => return Some(System::Spectrum),
+        // NES (check iNES magic)
+        "nes" if data.len() >= 4 && &data[0..4] == b"NES\x1a" => return Some(System::Nes),
+        "nes" => return Some(System::Nes),
+        // C64
+        "d64" | "t64" | "crt" => return Some(System::C64),
+        // Amiga
+        "adf" if data.len() == 901_120 => return Some(System::Amiga),
+        // Atari 2600 (unambiguous extension)
+        "a26" => return Some(System::Atari2600),
+        // Atari 5200 and 7800 carts without a header
+        "a52" => return Some(System::Atari5200),
+        "a78" => return Some(System::Atari7800),
+        // Atari 8-bit computers: cartridges, binary loads, disks
+        "car" | "xex" | "atr" => return Some(System::Atari800xl),
+        // SG-1000
+        "sg" | "sc" => return Some(System::Sg1000),
+        // ColecoVision
+        "col" => return Some(System::ColecoVision),
+        // SMS / Game Gear
+        "sms" => return Some(System::Sms),
+        "gg" => return Some(System::GameGear),
+        // BBC Micro disk images
+        "ssd" | "dsd" => return Some(System::BbcMicro),
+        _ => {}
+    }
+
+    // 4. Ambiguous extensions (.bin, .rom, .prg) — use header heuristics.
+    match ext.as_str() {
+        "rom" if data.len() >= 2 && data[0] == 0x41 && data[1] == 0x42 => Some(System::Msx),
+        "bin" if data.len() >= 0x8000 && looks_like_sms(data) => Some(System::Sms),
+        // Only classify .bin as Atari 2600 if the size exactly matches
+        // common cartridge sizes AND the reset vector points to ROM space
+        "bin" if is_likely_2600(data) => Some(System::Atari2600),
+        _ => None,
+    }
+}
+
+/// Identify the system from directory names in the path.
+///
+/// TOSEC collections use paths like:
+///   `sinclair/spectrum/Games/[Z80]/game.z80`
+///   `commodore/c64/Games/Arcade/game.d64`
+///   `nintendo/nes/test-suites/game.nes`
+///   `sega/master-system/Games/game.sms`
+fn identify_from_path(path: &Path) -> Option<System> {
+    let path_str = path.to_string_lossy().to_ascii_lowercase();
+
+    // Check path components for system keywords (most specific first)
+    let checks: &[(&[&str], System)] = &[
+        // Spectrum
+        (
+            &["spectrum", "zx spectrum", "zx-spectrum", "sinclair"],
+            System::Spectrum,
+        ),
+        // NES
+        (&["nes", "famicom", "nintendo entertainment"], System::Nes),
+        // C64
+        (
+            &["/c64/", "/c64 ", "commodore 64", "commodore-64", "/c64dtv/"],
+            System::C64,
+        ),
+        // Amiga
+        (&["amiga"], System::Amiga),
+        // Atari 5200 / 7800 / 8-bit computers
+        (&["5200"], System::Atari5200),
+        (&["7800"], System::Atari7800),
+        (
+            &[
+                "800xl",
+                "atari 8-bit",
+                "atari 8bit",
+                "atari-8bit",
+                "atari 800",
+                "atari-800",
+                "xl-xe",
+                "xl xe",
+            ],
+            System::Atari800xl,
+        ),
+        // Atari 2600
+        (
+            &["2600", "atari-2600", "atari 2600", "vcs"],
+            System::Atari2600,
+        ),
+        // SG-1000
+        (&["sg-1000", "sg1000"], System::Sg1000),
+        // ColecoVision
+        (&["coleco"], System::ColecoVision),
+        // MSX
+        (&["/msx/", "/msx1/", "/msx2/"], System::Msx),
+        // SMS
+        (&["master system", "master-system", "sms"], System::Sms),
+        // Game Gear
+        (&["game gear", "game-gear", "gamegear"], System::GameGear),
+        // BBC Micro
+        (&["bbc", "acorn"], System::BbcMicro),
+    ];
+
+    for (keywords, system) in checks {
+        for keyword in *keywords {
+            if path_str.contains(keyword) {
+                return Some(*system);
+            }
+        }
+    }
+
+    None
+}
+
+/// Identify files whose header names the system: A78 and CART images.
+fn identify_from_header(data: &[u8]) -> Option<System> {
+    if a78_header(data).is_some() {
+        return Some(System::Atari7800);
+    }
+    let (kind, _) = car_header(data)?;
+    // CART types 4, 6, 7, 16, 19 and 20 are the 5200's layouts.
+    Some(if matches!(kind, 4 | 6 | 7 | 16 | 19 | 20) {
+        System::Atari5200
+    } else {
+        System::Atari800xl
+    })
+}
+
+/// An A78 file: the ROM after the 128-byte header, and whether the header
+/// marks it PAL.
+#[must_use]
+pub fn a78_header(data: &[u8]) -> Option<(&[u8], bool)> {
+    (data.len() > 128 && &data[1..10] == b"ATARI7800").then(|| (&data[128..], data[57] & 1 != 0))
+}
+
+/// A CART file: its cartridge type and the ROM after the 16-byte header.
+#[must_use]
+pub fn car_header(data: &[u8]) -> Option<(u32, &[u8])> {
+    (data.len() > 16 && data.starts_with(b"CART")).then(|| {
+        let kind = u32::from_be_bytes([data[4], data[5], data[6], data[7]]);
+        (kind, &data[16..])
+    })
+}
+
+fn is_likely_2600(data: &[u8]) -> bool {
+    // Atari 2600 ROMs are exactly 2K, 4K, 8K, 16K, or 32K.
+    // Also check that the reset vector ($FFFC-$FFFD) points to valid ROM space.
+    if !matches!(data.len(), 2048 | 4096 | 8192 | 16384 | 32768) {
+        return false;
+    }
+    let len = data.len();
+    // Reset vector is at the end of the ROM image
+    if len >= 4 {
+        let reset_lo = data[len - 4] as u16;
+        let reset_hi = data[len - 3] as u16;
+        let reset = reset_hi << 8 | reset_lo;
+        // Valid 2600 reset vectors point to $F000-$FFFF range
+        reset >= 0xF000
+    } else {
+        false
+    }
+}
+
+fn looks_like_sms(data: &[u8]) -> bool {
+    // SMS ROMs have "TMR SEGA" at $7FF0
+    if data.len() >= 0x8000 {
+        let header = &data[0x7FF0..0x7FF8];
+        header == b"TMR SEGA"
+    } else {
+        false
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn atari_headers_outrank_paths_and_extensions() {
+        let mut a78 = vec![0; 128 + 16384];
+        a78[0] = 3;
+        a78[1..10].copy_from_slice(b"ATARI7800");
+        a78[57] = 1;
+        let path = Path::new("roms/atari-2600/game.bin");
+        assert_eq!(identify_system(path, &a78), Some(System::Atari7800));
+        assert_eq!(
+            a78_header(&a78).map(|(rom, pal)| (rom.len(), pal)),
+            Some((16384, true))
+        );
+
+        let mut car = b"CART".to_vec();
+        car.extend_from_slice(&[0, 0, 0, 19, 0, 0, 0, 0, 0, 0, 0, 0]);
+        car.extend_from_slice(&[0; 8192]);
+        assert_eq!(
+            identify_system(Path::new("x.car"), &car),
+            Some(System::Atari5200)
+        );
+        car[7] = 1;
+        assert_eq!(
+            identify_system(Path::new("x.car"), &car),
+            Some(System::Atari800xl)
+        );
+        assert_eq!(
+            car_header(&car).map(|(kind, rom)| (kind, rom.len())),
+            Some((1, 8192))
+        );
+    }
+
+    #[test]
+    fn atari_extensions_and_archive_paths_identify() {
+        let data = [0; 16];
+        for (name, system) in [
+            ("game.a52", System::Atari5200),
+            ("game.a78", System::Atari7800),
+            ("game.xex", System::Atari800xl),
+            ("disk.atr", System::Atari800xl),
+            (
+                "Atari 5200/Game (USA).zip/Game (USA).bin",
+                System::Atari5200,
+            ),
+        ] {
+            assert_eq!(
+                identify_system(Path::new(name), &data),
+                Some(system),
+                "{name}"
+            );
+        }
+        assert_eq!(System::from_name("atari800xl"), Some(System::Atari800xl));
+        assert_eq!(System::from_name("atari"), None);
+    }
+}
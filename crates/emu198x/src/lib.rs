@@ -0,0 +1,7 @@
+//! Shared pieces of the `emu198x` front end.
+//!
+//! [`identify`] works out which system a media file is for. The `emu198x`
+//! binary uses it to dispatch to the right emulator, and the test harness
+//! uses it to sort ROM collections.
+
+pub mod identify;
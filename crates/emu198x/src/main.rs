@@ -0,0 +1,758 @@
+//! Unified command-line front end for Emu198x.
+//!
+//! Identifies the system a media file is for (see [`emu198x::identify`]),
+//! translates the common options below into that system's own flags, and
+//! runs its emulator binary. The system binaries must sit next to this one,
+//! as they do after `cargo build`, or be on `PATH`. Anything after `--` is
+//! passed to the system binary unchanged.
+//!
+//! Usage:
+//!   emu198x [OPTIONS] [<media>] [-- <system options>...]
+//!
+//! Options:
+//!   --system <name>     Skip identification (spectrum, c64, nes, amiga, ...)
+//!   --region <ntsc|pal> Video region
+//!   --model <name>      Machine model (Spectrum, C64, Amiga, Atari 800XL)
+//!   --headless          Run without a window
+//!   --bench             Run --frames frames flat out and report speed
+//!   --frames <n>        Frames to run headless
+//!   --screenshot-at <frame>[:<file.png>]
+//!                       Run headless for <frame> frames, then save a
+//!                       screenshot [default file: <media>-<frame>.png]
+//!   --record <dir>      Record frames to a directory (headless)
+//!   --mcp               Run as MCP server (JSON-RPC over stdio)
+//!   --script <file>     Run a JSON script file (headless batch mode)
+//!   --roms-dir <dir>    Directory holding BIOS and OS ROMs [default: roms/]
+//!   --dry-run           Print the command instead of running it
+
+use std::ffi::OsStr;
+use std::path::{Path, PathBuf};
+use std::process::{self, Command};
+
+use emu198x::identify::{System, identify_system};
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Region {
+    Ntsc,
+    Pal,
+}
+
+impl Region {
+    fn name(self) -> &'static str {
+        match self {
+            Self::Ntsc => "ntsc",
+            Self::Pal => "pal",
+        }
+    }
+}
+
+#[derive(Debug)]
+struct CliArgs {
+    media: Option<PathBuf>,
+    system: Option<System>,
+    region: Option<Region>,
+    model: Option<String>,
+    headless: bool,
+    bench: bool,
+    frames: Option<u32>,
+    /// Frame to stop at and the PNG to write there.
+    screenshot_at: Option<(u32, PathBuf)>,
+    record_dir: Option<PathBuf>,
+    mcp: bool,
+    script_path: Option<PathBuf>,
+    roms_dir: Option<PathBuf>,
+    dry_run: bool,
+    /// Arguments after `--`, for the system binary.
+    passthrough: Vec<String>,
+}
+
+fn print_usage() {
+    eprintln!("Usage: emu198x [OPTIONS] [<media>] [-- <system options>...]");
+    eprintln!();
+    eprintln!("Identifies the system from the media file and runs its emulator.");
+    eprintln!("Arguments after -- go to the system binary unchanged.");
+    eprintln!();
+    eprintln!("Options:");
+    eprintln!("  --system <name>      Skip identification (spectrum, c64, nes, amiga, ...)");
+    eprintln!("  --region <ntsc|pal>  Video region");
+    eprintln!("  --model <name>       Machine model (Spectrum, C64, Amiga, Atari 800XL)");
+    eprintln!("  --headless           Run without a window");
+    eprintln!("  --bench              Run --frames frames flat out and report speed");
+    eprintln!("  --frames <n>         Number of frames in headless mode");
+    eprintln!("  --screenshot-at <frame>[:<file.png>]");
+    eprintln!("                       Run headless to <frame>, then save a PNG screenshot");
+    eprintln!("                       [default file: <media>-<frame>.png]");
+    eprintln!("  --record <dir>       Record frames to directory (headless)");
+    eprintln!("  --mcp                Run as MCP server (JSON-RPC over stdio)");
+    eprintln!("  --script <file>      Run a JSON script file (headless batch mode)");
+    eprintln!("  --roms-dir <dir>     Directory holding BIOS and OS ROMs [default: roms/]");
+    eprintln!("  --dry-run            Print the system command instead of running it");
+    eprintln!();
+    let names: Vec<_> = System::ALL.iter().map(|system| system.name()).collect();
+    eprintln!("Systems: {}", names.join(", "));
+}
+
+fn print_usage_and_exit(code: i32) -> ! {
+    print_usage();
+    process::exit(code);
+}
+
+fn parse_args() -> CliArgs {
+    let args: Vec<String> = std::env::args().collect();
+
+    match parse_args_from(&args) {
+        Ok(Some(cli)) => cli,
+        Ok(None) => print_usage_and_exit(0),
+        Err(e) => {
+            eprintln!("{e}");
+            print_usage_and_exit(1);
+        }
+    }
+}
+
+fn next_option_value<'a>(
+    args: &'a [String],
+    index: &mut usize,
+    flag: &str,
+) -> Result<&'a str, String> {
+    *index += 1;
+    args.get(*index)
+        .map(String::as_str)
+        .filter(|value| !value.starts_with("--"))
+        .ok_or_else(|| format!("{flag} requires a value"))
+}
+
+fn parse_frames(value: &str, flag: &str) -> Result<u32, String> {
+    value
+        .parse()
+        .map_err(|_| format!("{flag}: invalid frame count '{value}'"))
+}
+
+fn parse_args_from(args: &[String]) -> Result<Option<CliArgs>, String> {
+    let mut cli = CliArgs {
+        media: None,
+        system: None,
+        region: None,
+        model: None,
+        headless: false,
+        bench: false,
+        frames: None,
+        screenshot_at: None,
+        record_dir: None,
+        mcp: false,
+        script_path: None,
+        roms_dir: None,
+        dry_run: false,
+        passthrough: Vec::new(),
+    };
+    // The default screenshot name needs the media path, which may come later.
+    let mut screenshot_at: Option<(u32, Option<PathBuf>)> = None;
+
+    let mut i = 1;
+    while i < args.len() {
+        match args[i].as_str() {
+            "--" => {
+                cli.passthrough = args[i + 1..].to_vec();
+                break;
+            }
+            "--system" => {
+                let name = next_option_value(args, &mut i, "--system")?;
+                let system = System::from_name(name).ok_or_else(|| {
+                    let names: Vec<_> = System::ALL.iter().map(|system| system.name()).collect();
+                    format!(
+                        "Unknown system: {name} (expected one of: {})",
+                        names.join(", ")
+                    )
+                })?;
+                cli.system = Some(system);
+            }
+            "--region" => {
+                cli.region = Some(match next_option_value(args, &mut i, "--region")? {
+                    "ntsc" => Region::Ntsc,
+                    "pal" => Region::Pal,
+                    other => return Err(format!("Unknown region: {other} (expected ntsc or pal)")),
+                });
+            }
+            "--model" => {
+                cli.model = Some(next_option_value(args, &mut i, "--model")?.to_string());
+            }
+            "--headless" => {
+                cli.headless = true;
+            }
+            "--bench" => {
+                cli.bench = true;
+            }
+            "--frames" => {
+                let value = next_option_value(args, &mut i, "--frames")?;
+                cli.frames = Some(parse_frames(value, "--frames")?);
+            }
+            "--screenshot-at" => {
+                let value = next_option_value(args, &mut i, "--screenshot-at")?;
+                let (frame, file) = match value.split_once(':') {
+                    Some((frame, file)) => (frame, Some(PathBuf::from(file))),
+                    None => (value, None),
+                };
+                screenshot_at = Some((parse_frames(frame, "--screenshot-at")?, file));
+            }
+            "--record" => {
+                cli.record_dir = Some(PathBuf::from(next_option_value(args, &mut i, "--record")?));
+            }
+            "--mcp" => {
+                cli.mcp = true;
+            }
+            "--script" => {
+                cli.script_path = Some(PathBuf::from(next_option_value(args, &mut i, "--script")?));
+            }
+            "--roms-dir" => {
+                cli.roms_dir = Some(PathBuf::from(next_option_value(
+                    args,
+                    &mut i,
+                    "--roms-dir",
+                )?));
+            }
+            "--dry-run" => {
+                cli.dry_run = true;
+            }
+            "--help" | "-h" => return Ok(None),
+            other if other.starts_with('-') => {
+                return Err(format!("Unknown option: {other}"));
+            }
+            path => {
+                if let Some(media) = &cli.media {
+                    return Err(format!(
+                        "Only one media file can be given (got {} and {path})",
+                        media.display()
+                    ));
+                }
+                cli.media = Some(PathBuf::from(path));
+            }
+        }
+        i += 1;
+    }
+
+    if cli.mcp && cli.script_path.is_some() {
+        return Err("--mcp and --script cannot be used together".to_string());
+    }
+    if let Some((frame, file)) = screenshot_at {
+        if cli.frames.is_some_and(|frames| frames != frame) {
+            return Err("--screenshot-at sets the frame count; drop --frames".to_string());
+        }
+        let file = file.unwrap_or_else(|| {
+            let stem = cli
+                .media
+                .as_deref()
+                .and_then(Path::file_stem)
+                .map_or_else(|| "screenshot".into(), |stem| stem.to_string_lossy());
+            PathBuf::from(format!("{stem}-{frame}.png"))
+        });
+        cli.screenshot_at = Some((frame, file));
+    }
+    if cli.media.is_none() && cli.system.is_none() {
+        return Err("No media file given; pass one, or name a system with --system".to_string());
+    }
+
+    Ok(Some(cli))
+}
+
+/// The system to run: `--system`, or whatever the media file looks like.
+fn resolve_system(cli: &CliArgs) -> Result<System, String> {
+    if let Some(system) = cli.system {
+        return Ok(system);
+    }
+    let path = cli
+        .media
+        .as_deref()
+        .ok_or("No media file given; pass one, or name a system with --system")?;
+    let data =
+        std::fs::read(path).map_err(|e| format!("Failed to read {}: {e}", path.display()))?;
+    identify_system(path, &data).ok_or_else(|| {
+        format!(
+            "Could not identify the system for {}; name it with --system",
+            path.display()
+        )
+    })
+}
+
+/// The emulator binary that runs `system`.
+fn binary_name(system: System) -> &'static str {
+    match system {
+        System::Spectrum => "emu-spectrum",
+        System::C64 => "emu-c64",
+        System::Nes => "emu-nes",
+        System::Amiga => "emu-amiga",
+        System::Atari2600 => "emu-atari-2600",
+        System::Atari5200 => "emu-atari-5200",
+        System::Atari7800 => "emu-atari-7800",
+        System::Atari800xl => "emu-atari-800xl",
+        System::Sg1000 => "emu-sg1000",
+        System::ColecoVision => "emu-colecovision",
+        System::Msx => "emu-msx",
+        System::Sms | System::GameGear => "emu-sms",
+        System::BbcMicro => "emu-bbc-micro",
+    }
+}
+
+/// The system binary next to this executable, or its bare name for a
+/// `PATH` lookup.
+fn binary_path(system: System) -> PathBuf {
+    let file = format!("{}{}", binary_name(system), std::env::consts::EXE_SUFFIX);
+    std::env::current_exe()
+        .ok()
+        .map(|exe| exe.with_file_name(&file))
+        .filter(|path| path.is_file())
+        .unwrap_or_else(|| PathBuf::from(binary_name(system)))
+}
+
+/// The system binary's flag for a media file with extension `ext`.
+fn media_flag(system: System, ext: &str) -> Result<&'static str, String> {
+    let unsupported = |expected: &str| {
+        Err(format!(
+            "{} cannot load .{ext} files (expected {expected})",
+            binary_name(system)
+        ))
+    };
+    Ok(match (system, ext) {
+        (System::Spectrum, "sna") => "--sna",
+        (System::Spectrum, "z80") => "--z80",
+        (System::Spectrum, "szx") => "--szx",
+        (System::Spectrum, "tap") => "--tap",
+        (System::Spectrum, "tzx") => "--tzx",
+        (System::Spectrum, "pzx") => "--pzx",
+        (System::Spectrum, "csw") => "--csw",
+        (System::Spectrum, "dsk") => "--dsk",
+        (System::Spectrum, "bas") => "--bas",
+        (System::Spectrum, _) => {
+            return unsupported(".sna, .z80, .szx, .tap, .tzx, .pzx, .csw, .dsk or .bas");
+        }
+        (System::C64, "d64") => "--d64",
+        (System::C64, "prg") => "--prg",
+        (System::C64, "bas") => "--bas",
+        (System::C64, _) => return unsupported(".d64, .prg or .bas"),
+        (System::Atari800xl, "xex") => "--xex",
+        (System::Atari800xl, "atr") => "--atr",
+        (System::BbcMicro, _) => return unsupported("no media; it boots to BASIC"),
+        (System::Amiga, _) => "--disk",
+        (System::Msx, _) => "--cart",
+        _ => "--rom",
+    })
+}
+
+/// BIOS or OS ROM a system binary needs, as its flag and the file name in
+/// the ROMs directory.
+fn firmware(system: System) -> Option<(&'static str, &'static str)> {
+    match system {
+        System::ColecoVision => Some(("--bios", "coleco.rom")),
+        System::Msx => Some(("--bios", "msx.rom")),
+        System::BbcMicro => Some(("--mos", "bbc-mos.rom")),
+        System::Atari5200 => Some(("--bios", "5200.rom")),
+        System::Atari800xl => Some(("--os-rom", "atarixl.rom")),
+        System::Amiga => Some(("--rom", "kick.rom")),
+        _ => None,
+    }
+}
+
+/// How a region maps onto the system binary's flags.
+fn region_args(system: System, region: Region) -> Result<Vec<String>, String> {
+    let args = match (system, region) {
+        // The C64 binary picks PAL or NTSC with its model.
+        (System::C64, _) => vec!["--model", region.name()],
+        (System::Sms, Region::Ntsc) => vec!["--variant", "sms-ntsc"],
+        (System::Sms, Region::Pal) => vec!["--variant", "sms-pal"],
+        (System::GameGear, Region::Ntsc)
+        | (System::Spectrum | System::Amiga | System::BbcMicro, Region::Pal) => Vec::new(),
+        (System::GameGear, Region::Pal) => {
+            return Err("gamegear has no PAL variant".to_string());
+        }
+        (System::Spectrum | System::Amiga | System::BbcMicro, Region::Ntsc) => {
+            return Err(format!("{} runs PAL only", system.name()));
+        }
+        _ => vec!["--region", region.name()],
+    };
+    Ok(args.into_iter().map(String::from).collect())
+}
+
+/// A flag and its value, as arguments.
+fn option(flag: &str, value: impl AsRef<OsStr>) -> [String; 2] {
+    [
+        flag.to_string(),
+        value.as_ref().to_string_lossy().into_owned(),
+    ]
+}
+
+/// Translate the common options into the system binary's arguments.
+fn system_args(system: System, cli: &CliArgs, roms_dir: &Path) -> Result<Vec<String>, String> {
+    let mut args = Vec::new();
+    if system == System::GameGear {
+        args.extend(option("--variant", "gg"));
+    }
+    if let Some(media) = &cli.media {
+        let ext = media
+            .extension()
+            .and_then(|e| e.to_str())
+            .map(str::to_ascii_lowercase)
+            .unwrap_or_default();
+        args.extend(option(media_flag(system, &ext)?, media));
+    }
+
+    if let Some(model) = &cli.model {
+        if system == System::C64 && cli.region.is_some() {
+            return Err(
+                "c64 takes its region from --model; use one of --region or --model".to_string(),
+            );
+        }
+        if !matches!(
+            system,
+            System::Spectrum | System::C64 | System::Amiga | System::Atari800xl
+        ) {
+            return Err(format!("--model is not supported for {}", system.name()));
+        }
+        args.extend(option("--model", model));
+    }
+    if let Some(region) = cli.region {
+        args.extend(region_args(system, region)?);
+    }
+
+    // The user's own firmware flag, or the Amiga's environment variable,
+    // takes precedence over the ROMs directory.
+    if let Some((flag, file)) = firmware(system) {
+        let path = roms_dir.join(file);
+        let overridden = cli.passthrough.iter().any(|arg| arg == flag)
+            || (system == System::Amiga && std::env::var_os("AMIGA_KS13_ROM").is_some());
+        if !overridden && path.is_file() {
+            args.extend(option(flag, &path));
+        }
+    }
+
+    if cli.headless || cli.screenshot_at.is_some() {
+        args.push("--headless".to_string());
+    }
+    if cli.bench {
+        args.push("--bench".to_string());
+    }
+    if let Some(frames) = cli
+        .screenshot_at
+        .as_ref()
+        .map(|&(frame, _)| frame)
+        .or(cli.frames)
+    {
+        args.extend(option("--frames", frames.to_string()));
+    }
+    if let Some((_, file)) = &cli.screenshot_at {
+        args.extend(option("--screenshot", file));
+    }
+    if let Some(dir) = &cli.record_dir {
+        if !matches!(system, System::Spectrum | System::C64 | System::Nes) {
+            return Err(format!(
+                "--record is not supported for {} (only spectrum, c64 and nes)",
+                system.name()
+            ));
+        }
+        args.extend(option("--record", dir));
+    }
+    if cli.mcp {
+        args.push("--mcp".to_string());
+    }
+    if let Some(script) = &cli.script_path {
+        args.extend(option("--script", script));
+    }
+
+    args.extend(cli.passthrough.iter().cloned());
+    Ok(args)
+}
+
+/// Find the roms/ directory relative to the executable or current directory.
+fn find_roms_dir() -> PathBuf {
+    // Try relative to the executable
+    if let Ok(exe) = std::env::current_exe() {
+        // Walk up from target/debug or target/release to workspace root
+        let mut dir = exe.parent().map(Path::to_path_buf);
+        for _ in 0..5 {
+            if let Some(ref d) = dir {
+                let roms = d.join("roms");
+                if roms.is_dir() {
+                    return roms;
+                }
+                dir = d.parent().map(Path::to_path_buf);
+            }
+        }
+    }
+    // Fallback: roms/ relative to cwd
+    PathBuf::from("roms")
+}
+
+/// Quote an argument for display if the shell would split it.
+fn shell_quote(arg: &str) -> String {
+    if !arg.is_empty() && !arg.contains(|c: char| c.is_whitespace() || "'\"\\$`".contains(c)) {
+        arg.to_string()
+    } else {
+        format!("'{}'", arg.replace('\'', r"'\''"))
+    }
+}
+
+fn main() {
+    let cli = parse_args();
+
+    let system = resolve_system(&cli).unwrap_or_else(|e| {
+        eprintln!("{e}");
+        process::exit(1);
+    });
+    let roms_dir = cli.roms_dir.clone().unwrap_or_else(find_roms_dir);
+    let args = system_args(system, &cli, &roms_dir).unwrap_or_else(|e| {
+        eprintln!("{e}");
+        process::exit(1);
+    });
+    let binary = binary_path(system);
+
+    if cli.dry_run {
+        let words: Vec<_> = std::iter::once(binary.to_string_lossy().into_owned())
+            .chain(args)
+            .map(|arg| shell_quote(&arg))
+            .collect();
+        println!("{}", words.join(" "));
+        return;
+    }
+
+    match Command::new(&binary).args(&args).status() {
+        Ok(status) => process::exit(status.code().unwrap_or(1)),
+        Err(e) => {
+            eprintln!("Failed to run {}: {e}", binary.display());
+            eprintln!(
+                "Build it with: cargo build --release -p {}",
+                binary_name(system)
+            );
+            process::exit(1);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn parse_cli(args: &[&str]) -> Result<Option<CliArgs>, String> {
+        let args = std::iter::once("emu198x")
+            .chain(args.iter().copied())
+            .map(String::from)
+            .collect::<Vec<_>>();
+        parse_args_from(&args)
+    }
+
+    fn cli(args: &[&str]) -> CliArgs {
+        parse_cli(args)
+            .expect("parse should succeed")
+            .expect("help was not requested")
+    }
+
+    fn translate(system: System, args: &[&str]) -> Result<Vec<String>, String> {
+        system_args(system, &cli(args), Path::new("no-such-roms-dir"))
+    }
+
+    #[test]
+    fn parser_reads_common_options_and_passthrough() {
+        let cli = cli(&[
+            "game.tzx", "--region", "pal", "--model", "128k", "--mcp", "--", "--type", "RUN\\n",
+        ]);
+        assert_eq!(cli.media, Some(PathBuf::from("game.tzx")));
+        assert_eq!(cli.region, Some(Region::Pal));
+        assert_eq!(cli.model.as_deref(), Some("128k"));
+        assert!(cli.mcp);
+        assert_eq!(cli.passthrough, ["--type", "RUN\\n"]);
+
+        assert!(parse_cli(&["--help"]).expect("help").is_none());
+        assert!(parse_cli(&["game.tzx", "--bogus"]).is_err());
+        assert!(parse_cli(&["game.tzx", "--region", "secam"]).is_err());
+        assert!(parse_cli(&["--system", "vectrex"]).is_err());
+        assert!(
+            parse_cli(&["--headless"]).is_err(),
+            "needs media or --system"
+        );
+        assert!(parse_cli(&["--system", "c64", "--mcp", "--script", "x.json"]).is_err());
+    }
+
+    #[test]
+    fn screenshot_at_names_the_file_after_the_media() {
+        let cli = cli(&["--screenshot-at", "300", "games/Manic Miner.tzx"]);
+        assert_eq!(
+            cli.screenshot_at,
+            Some((300, PathBuf::from("Manic Miner-300.png")))
+        );
+
+        let cli = self::cli(&["game.nes", "--screenshot-at", "60:shots/title.png"]);
+        assert_eq!(
+            cli.screenshot_at,
+            Some((60, PathBuf::from("shots/title.png")))
+        );
+
+        assert!(parse_cli(&["game.nes", "--screenshot-at", "60", "--frames", "100"]).is_err());
+        assert!(parse_cli(&["game.nes", "--screenshot-at", "x"]).is_err());
+    }
+
+    #[test]
+    fn media_goes_to_the_system_flag() {
+        assert_eq!(
+            translate(System::Spectrum, &["game.tzx", "--screenshot-at", "500"]).expect("spectrum"),
+            [
+                "--tzx",
+                "game.tzx",
+                "--headless",
+                "--frames",
+                "500",
+                "--screenshot",
+                "game-500.png"
+            ]
+        );
+        assert_eq!(
+            translate(System::C64, &["disk.d64"]).expect("c64"),
+            ["--d64", "disk.d64"]
+        );
+        assert_eq!(
+            translate(System::Msx, &["game.rom"]).expect("msx"),
+            ["--cart", "game.rom"]
+        );
+        assert_eq!(
+            translate(System::Amiga, &["wb.adf"]).expect("amiga"),
+            ["--disk", "wb.adf"]
+        );
+        assert_eq!(
+            translate(System::GameGear, &["sonic.gg"]).expect("gg"),
+            ["--variant", "gg", "--rom", "sonic.gg"]
+        );
+        assert_eq!(
+            translate(
+                System::Nes,
+                &["smb.nes", "--headless", "--frames", "10", "--", "--bench"]
+            )
+            .expect("nes"),
+            [
+                "--rom",
+                "smb.nes",
+                "--headless",
+                "--frames",
+                "10",
+                "--bench"
+            ]
+        );
+        assert!(translate(System::C64, &["game.t64"]).is_err());
+        assert!(translate(System::BbcMicro, &["elite.ssd"]).is_err());
+    }
+
+    #[test]
+    fn atari_800xl_programs_and_disks_reach_their_flags() {
+        let xex = [0xFF, 0xFF, 0x00, 0x20, 0x00, 0x20, 0x60];
+        let path = Path::new("game.xex");
+        assert_eq!(identify_system(path, &xex), Some(System::Atari800xl));
+        assert_eq!(
+            translate(System::Atari800xl, &["game.xex"]).expect("xex"),
+            ["--xex", "game.xex"]
+        );
+        assert_eq!(
+            translate(System::Atari800xl, &["dos.atr"]).expect("atr"),
+            ["--atr", "dos.atr"]
+        );
+        assert_eq!(
+            translate(System::Atari800xl, &["star.car"]).expect("car"),
+            ["--rom", "star.car"]
+        );
+    }
+
+    #[test]
+    fn region_and_model_map_per_system() {
+        assert_eq!(
+            translate(System::C64, &["--system", "c64", "--region", "ntsc"]).expect("c64"),
+            ["--model", "ntsc"]
+        );
+        assert_eq!(
+            translate(System::Sms, &["--system", "sms", "--region", "pal"]).expect("sms"),
+            ["--variant", "sms-pal"]
+        );
+        assert_eq!(
+            translate(
+                System::Atari7800,
+                &["--system", "atari7800", "--region", "pal"]
+            )
+            .expect("7800"),
+            ["--region", "pal"]
+        );
+        assert!(
+            translate(
+                System::Spectrum,
+                &["--system", "spectrum", "--region", "pal"]
+            )
+            .expect("spectrum")
+            .is_empty()
+        );
+        assert!(
+            translate(
+                System::Spectrum,
+                &["--system", "spectrum", "--region", "ntsc"]
+            )
+            .is_err()
+        );
+        assert!(
+            translate(
+                System::GameGear,
+                &["--system", "gamegear", "--region", "pal"]
+            )
+            .is_err()
+        );
+
+        assert_eq!(
+            translate(System::Amiga, &["--system", "amiga", "--model", "a1200"]).expect("amiga"),
+            ["--model", "a1200"]
+        );
+        assert!(translate(System::Nes, &["--system", "nes", "--model", "famicom"]).is_err());
+        assert!(
+            translate(
+                System::C64,
+                &["--system", "c64", "--model", "pal", "--region", "pal"]
+            )
+            .is_err()
+        );
+    }
+
+    #[test]
+    fn record_is_limited_to_systems_that_support_it() {
+        assert_eq!(
+            translate(System::C64, &["--system", "c64", "--record", "out"]).expect("c64"),
+            ["--record", "out"]
+        );
+        assert!(
+            translate(
+                System::Atari2600,
+                &["--system", "atari2600", "--record", "out"]
+            )
+            .is_err()
+        );
+    }
+
+    #[test]
+    fn firmware_comes_from_the_roms_dir_unless_given() {
+        let roms = std::env::temp_dir().join("emu198x-roms-test");
+        std::fs::create_dir_all(&roms).expect("create roms dir");
+        let bios = roms.join("coleco.rom");
+        std::fs::write(&bios, [0; 8192]).expect("write bios");
+
+        let args = system_args(System::ColecoVision, &cli(&["game.col"]), &roms).expect("coleco");
+        assert_eq!(
+            args,
+            ["--rom", "game.col", "--bios", &bios.to_string_lossy()]
+        );
+        let args = system_args(
+            System::ColecoVision,
+            &cli(&["game.col", "--", "--bios", "mine.rom"]),
+            &roms,
+        )
+        .expect("coleco");
+        assert_eq!(args, ["--rom", "game.col", "--bios", "mine.rom"]);
+
+        std::fs::remove_dir_all(&roms).expect("remove roms dir");
+    }
+
+    #[test]
+    fn every_system_has_a_binary() {
+        for system in System::ALL {
+            assert!(binary_name(system).starts_with("emu-"), "{}", system.name());
+        }
+        assert_eq!(binary_name(System::GameGear), binary_name(System::Sms));
+    }
+}
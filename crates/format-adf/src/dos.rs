@@ -0,0 +1,226 @@
+//! Reading files from an AmigaDOS (OFS or FFS) volume.
+//!
+//! Block numbers are linear sector indices: block `n` is `n * 512` bytes
+//! into the image, and the root block sits in the middle of the disk.
+//! Every header block keeps its fields at fixed offsets from the end, so
+//! only the 512-byte layout is handled here.
+
+use crate::{Adf, AdfError, SECTOR_SIZE};
+
+const BLOCK_LONGS: usize = SECTOR_SIZE as usize / 4;
+const HASH_TABLE_SIZE: usize = BLOCK_LONGS - 56;
+/// First hash slot (and first data block pointer) in a header block.
+const TABLE_START: usize = 6;
+const T_HEADER: u32 = 2;
+const T_LIST: u32 = 16;
+const ST_ROOT: u32 = 1;
+const ST_USERDIR: u32 = 2;
+const ST_FILE: u32 = (-3_i32) as u32;
+/// Payload bytes in an OFS data block, after its 24-byte header.
+const OFS_DATA_SIZE: usize = SECTOR_SIZE as usize - 24;
+
+impl Adf {
+    /// True if the boot block marks an AmigaDOS volume.
+    #[must_use]
+    pub fn is_dos(&self) -> bool {
+        self.data.starts_with(b"DOS")
+    }
+
+    fn block(&self, block: u32) -> Result<&[u8], AdfError> {
+        let start = block as usize * SECTOR_SIZE as usize;
+        self.data
+            .get(start..start + SECTOR_SIZE as usize)
+            .ok_or(AdfError::BadBlock(block))
+    }
+
+    fn long(&self, block: u32, index: usize) -> Result<u32, AdfError> {
+        let bytes = &self.block(block)?[index * 4..index * 4 + 4];
+        Ok(u32::from_be_bytes([bytes[0], bytes[1], bytes[2], bytes[3]]))
+    }
+
+    /// Index of a field counted from the end of a block (`1` is the last
+    /// long).
+    fn tail(from_end: usize) -> usize {
+        BLOCK_LONGS - from_end
+    }
+
+    fn block_name(&self, block: u32) -> Result<&[u8], AdfError> {
+        let data = self.block(block)?;
+        let at = SECTOR_SIZE as usize - 80;
+        let len = usize::from(data[at]).min(30);
+        Ok(&data[at + 1..at + 1 + len])
+    }
+
+    /// Read a file by its path from the volume root (`c/dir`,
+    /// `s/startup-sequence`). Names match case-insensitively, as
+    /// AmigaDOS does.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the disk is not an AmigaDOS volume, the path
+    /// does not name a file, or a block it needs is damaged.
+    pub fn read_file(&self, path: &str) -> Result<Vec<u8>, AdfError> {
+        if !self.is_dos() {
+            return Err(AdfError::NotDos);
+        }
+        let ffs = self.data[3] & 1 != 0;
+        let total_blocks = (self.data.len() / SECTOR_SIZE as usize) as u32;
+        let mut header = total_blocks / 2;
+
+        let not_found = || AdfError::FileNotFound(path.to_string());
+        for name in path.split('/').filter(|n| !n.is_empty()) {
+            header = self.find_entry(header, name)?.ok_or_else(not_found)?;
+        }
+        if self.long(header, Self::tail(1))? != ST_FILE {
+            return Err(not_found());
+        }
+
+        let size = self.long(header, Self::tail(47))? as usize;
+        let mut out = Vec::with_capacity(size);
+        let mut table = header;
+        // Data block pointers fill each table from its end backwards;
+        // extension blocks carry on where the header leaves off.
+        while out.len() < size {
+            if table == 0 {
+                return Err(AdfError::BadBlock(header));
+            }
+            let count = self.long(table, 2)? as usize;
+            for slot in 0..count.min(HASH_TABLE_SIZE) {
+                let block = self.long(table, TABLE_START + HASH_TABLE_SIZE - 1 - slot)?;
+                let data = self.block(block)?;
+                let payload = if ffs {
+                    data
+                } else {
+                    let used = u32::from_be_bytes([data[12], data[13], data[14], data[15]]);
+                    &data[24..24 + (used as usize).min(OFS_DATA_SIZE)]
+                };
+                let take = payload.len().min(size - out.len());
+                out.extend_from_slice(&payload[..take]);
+            }
+            table = self.long(table, Self::tail(2))?;
+            if table != 0 && self.long(table, 0)? != T_LIST {
+                return Err(AdfError::BadBlock(table));
+            }
+        }
+        Ok(out)
+    }
+
+    /// Find `name` in the directory whose header is `dir`.
+    fn find_entry(&self, dir: u32, name: &str) -> Result<Option<u32>, AdfError> {
+        if self.long(dir, 0)? != T_HEADER {
+            return Err(AdfError::BadBlock(dir));
+        }
+        if !matches!(self.long(dir, Self::tail(1))?, ST_ROOT | ST_USERDIR) {
+            return Ok(None);
+        }
+        let mut entry = self.long(dir, TABLE_START + name_hash(name.as_bytes()))?;
+        // A chain longer than the disk means a loop.
+        for _ in 0..self.data.len() / SECTOR_SIZE as usize {
+            if entry == 0 {
+                return Ok(None);
+            }
+            if self
+                .block_name(entry)?
+                .eq_ignore_ascii_case(name.as_bytes())
+            {
+                return Ok(Some(entry));
+            }
+            entry = self.long(entry, Self::tail(4))?;
+        }
+        Err(AdfError::BadBlock(dir))
+    }
+}
+
+/// Directory hash slot of a name (the non-international hash).
+fn name_hash(name: &[u8]) -> usize {
+    let mut hash = name.len() as u32;
+    for &c in name {
+        hash = (hash * 13 + u32::from(c.to_ascii_uppercase())) & 0x7FF;
+    }
+    hash as usize % HASH_TABLE_SIZE
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{BLOCK_LONGS, HASH_TABLE_SIZE, ST_FILE, T_HEADER, TABLE_START, name_hash};
+    use crate::{ADF_SIZE_DD, Adf, AdfError, SECTOR_SIZE};
+
+    const ROOT: u32 = 880;
+
+    fn put_long(image: &mut [u8], block: u32, index: usize, value: u32) {
+        let at = block as usize * SECTOR_SIZE as usize + index * 4;
+        image[at..at + 4].copy_from_slice(&value.to_be_bytes());
+    }
+
+    fn put_name(image: &mut [u8], block: u32, name: &str) {
+        let at = block as usize * SECTOR_SIZE as usize + SECTOR_SIZE as usize - 80;
+        image[at] = name.len() as u8;
+        image[at + 1..at + 1 + name.len()].copy_from_slice(name.as_bytes());
+    }
+
+    /// Blank volume holding one root file, its data in blocks 2 upwards
+    /// (with OFS block headers unless `ffs`).
+    fn volume(ffs: bool, name: &str, contents: &[u8]) -> Adf {
+        let mut image = vec![0; ADF_SIZE_DD];
+        image[..4].copy_from_slice(if ffs { b"DOS\x01" } else { b"DOS\x00" });
+        put_long(&mut image, ROOT, 0, T_HEADER);
+        put_long(&mut image, ROOT, 3, HASH_TABLE_SIZE as u32);
+        put_long(&mut image, ROOT, BLOCK_LONGS - 1, 1);
+
+        let header = 881;
+        put_long(
+            &mut image,
+            ROOT,
+            TABLE_START + name_hash(name.as_bytes()),
+            header,
+        );
+        put_long(&mut image, header, 0, T_HEADER);
+        put_long(&mut image, header, 1, header);
+        put_long(&mut image, header, BLOCK_LONGS - 1, ST_FILE);
+        put_long(&mut image, header, BLOCK_LONGS - 3, ROOT);
+        put_long(&mut image, header, BLOCK_LONGS - 47, contents.len() as u32);
+        put_name(&mut image, header, name);
+
+        let per_block = if ffs { 512 } else { 488 };
+        let chunks: Vec<&[u8]> = contents.chunks(per_block).collect();
+        put_long(&mut image, header, 2, chunks.len() as u32);
+        for (i, chunk) in chunks.iter().enumerate() {
+            let block = 2 + i as u32;
+            put_long(
+                &mut image,
+                header,
+                TABLE_START + HASH_TABLE_SIZE - 1 - i,
+                block,
+            );
+            let mut at = block as usize * SECTOR_SIZE as usize;
+            if !ffs {
+                put_long(&mut image, block, 0, 8);
+                put_long(&mut image, block, 3, chunk.len() as u32);
+                at += 24;
+            }
+            image[at..at + chunk.len()].copy_from_slice(chunk);
+        }
+        Adf::from_bytes(image).expect("DD image")
+    }
+
+    #[test]
+    fn reads_files_from_ofs_and_ffs_volumes() {
+        let contents: Vec<u8> = (0..1200).map(|i| (i % 251) as u8).collect();
+        for ffs in [false, true] {
+            let adf = volume(ffs, "Exodus", &contents);
+            assert!(adf.is_dos());
+            assert_eq!(adf.read_file("exodus").expect("file"), contents);
+            assert_eq!(adf.read_file("/EXODUS").expect("file"), contents);
+            assert!(matches!(
+                adf.read_file("exodus2"),
+                Err(AdfError::FileNotFound(_))
+            ));
+        }
+    }
+
+    #[test]
+    fn non_dos_disks_have_no_files() {
+        let adf = Adf::from_bytes(vec![0; ADF_SIZE_DD]).expect("DD image");
+        assert!(matches!(adf.read_file("c/dir"), Err(AdfError::NotDos)));
+    }
+}
@@ -2,9 +2,12 @@
 //!
 //! ADF is a raw sector dump: 80 cylinders x 2 heads x 11 sectors x 512 bytes
 //! = 901,120 bytes for double-density disks. HD disks double the sector count.
+//! Files on AmigaDOS volumes can be read by path with [`Adf::read_file`].
 
 use std::fmt;
 
+mod dos;
+
 pub const SECTOR_SIZE: u32 = 512;
 pub const SECTORS_PER_TRACK_DD: u32 = 11;
 pub const SECTORS_PER_TRACK_HD: u32 = 22;
@@ -16,6 +19,12 @@ pub const ADF_SIZE_HD: usize = (CYLINDERS * HEADS * SECTORS_PER_TRACK_HD * SECTO
 #[derive(Debug)]
 pub enum AdfError {
     InvalidSize(usize),
+    /// The boot block has no `DOS` signature.
+    NotDos,
+    /// No file at the given path.
+    FileNotFound(String),
+    /// A filesystem block is missing or inconsistent.
+    BadBlock(u32),
 }
 
 impl fmt::Display for AdfError {
@@ -26,6 +35,9 @@ impl fmt::Display for AdfError {
                 "invalid ADF size: {} bytes (expected {} for DD or {} for HD)",
                 size, ADF_SIZE_DD, ADF_SIZE_HD,
             ),
+            Self::NotDos => write!(f, "not an AmigaDOS disk"),
+            Self::FileNotFound(path) => write!(f, "file not found: {path}"),
+            Self::BadBlock(block) => write!(f, "damaged filesystem block {block}"),
         }
     }
 }
@@ -1,4 +1,4 @@
-//! C64 TAP file format parser.
+//! C64 TAP file format parser and writer.
 //!
 //! The C64 TAP format stores raw pulse timing data from the datasette.
 //! Each byte encodes a pulse duration; the sequence of short and long
@@ -16,23 +16,35 @@
 //! Version 0: each byte = duration × 8 CPU cycles (0 = overflow, treated as 256×8).
 //! Version 1: byte 0 is a sentinel followed by 3 bytes of exact LE cycle count.
 //!
+//! The writer always produces version 1, so pulses longer than 255×8
+//! cycles (pauses, motor stops) keep their exact length.
+//!
 //! # Data encoding (standard C64 tape format)
 //!
-//! The C64 uses short (~352 cycles) and long (~512 cycles) pulses to encode
-//! bits. A short-long pair = 0, a long-short pair = 1. Bytes are transmitted
-//! LSB first, each followed by a parity bit (odd parity). Blocks start with
-//! a leader of repeated bytes, then a countdown sequence, then data.
+//! The kernal writes short (~384 cycles), medium (~528) and long (~688)
+//! pulses. Each byte starts with a long-medium marker, then 8 data bits
+//! LSB first and an odd parity bit; a short-medium pair is 0 and a
+//! medium-short pair is 1. A block is a leader of short pulses, a
+//! countdown ($89..$81, or $09..$01 for the repeat), the payload and an
+//! XOR checksum, closed by a long-short marker. Every block is written
+//! twice.
 
 #![allow(clippy::cast_possible_truncation)]
 
 /// TAP file header signature.
 const TAP_SIGNATURE: &[u8; 12] = b"C64-TAPE-RAW";
 
+/// Size of the TAP header; pulse data follows it.
+const HEADER_SIZE: usize = 20;
+
+/// Longest pulse a version 1 long-pulse entry can hold.
+const MAX_LONG_PULSE: u32 = 0x00FF_FFFF;
+
 /// Pulse duration thresholds (in CPU cycles at 985,248 Hz PAL).
 /// These match the standard C64 kernal tape encoding.
 const SHORT_THRESHOLD: u32 = 416; // Below this = short pulse
 const LONG_THRESHOLD: u32 = 560; // Above this = long pulse
-// Between SHORT_THRESHOLD and LONG_THRESHOLD = medium (sync/leader)
+// Between SHORT_THRESHOLD and LONG_THRESHOLD = medium pulse
 
 /// A decoded C64 tape block.
 #[derive(Debug, Clone)]
@@ -58,12 +70,12 @@ pub struct C64TapFile {
     pub raw_pulses: Vec<u32>,
 }
 
-/// Classify a pulse duration into short (0), long (1), or medium (sync).
+/// Classify a pulse duration into short, medium or long.
 #[derive(Debug, Clone, Copy, PartialEq, Eq)]
 enum Pulse {
-    Short,  // 0 bit component
-    Long,   // 1 bit component
-    Medium, // Leader/sync
+    Short,  // Leader, first half of a 0, second half of a 1
+    Medium, // Second half of a 0 or a byte marker, first half of a 1
+    Long,   // Byte and end-of-data markers
 }
 
 fn classify_pulse(cycles: u32) -> Pulse {
@@ -106,45 +118,13 @@ fn extract_pulses(data: &[u8], version: u8) -> Vec<u32> {
     pulses
 }
 
-/// Decode pulse pairs into a bit stream.
-///
-/// The C64 tape format uses pulse pairs: short-medium = 0, long-medium = 1
-/// (or vice versa depending on the encoding variant). The standard kernal
-/// encoding uses:
-/// - Short pulse followed by medium pulse = bit 0
-/// - Long pulse followed by medium pulse = bit 1
-///
-/// Returns a vector of bits and the number of pulses consumed.
-fn decode_bits(pulses: &[Pulse]) -> Vec<u8> {
-    let mut bits = Vec::new();
-    let mut i = 0;
-
-    while i + 1 < pulses.len() {
-        match (pulses[i], pulses[i + 1]) {
-            (Pulse::Short, Pulse::Medium) => {
-                bits.push(0);
-                i += 2;
-            }
-            (Pulse::Medium, Pulse::Short) => {
-                bits.push(0);
-                i += 2;
-            }
-            (Pulse::Long, Pulse::Medium) => {
-                bits.push(1);
-                i += 2;
-            }
-            (Pulse::Medium, Pulse::Long) => {
-                bits.push(1);
-                i += 2;
-            }
-            _ => {
-                // Skip unrecognized pulse
-                i += 1;
-            }
-        }
+/// Decode one bit from a pulse pair: short-medium is 0, medium-short is 1.
+fn decode_bit(first: Pulse, second: Pulse) -> Option<u8> {
+    match (first, second) {
+        (Pulse::Short, Pulse::Medium) => Some(0),
+        (Pulse::Medium, Pulse::Short) => Some(1),
+        _ => None,
     }
-
-    bits
 }
 
 /// Decode 8 data bits + 1 parity bit into a byte.
@@ -164,50 +144,75 @@ fn decode_byte(bits: &[u8]) -> Option<(u8, usize)> {
     Some((byte, 9))
 }
 
-/// Decode a sequence of bytes from a bit stream.
-fn decode_bytes(bits: &[u8], count: usize) -> (Vec<u8>, usize) {
-    let mut bytes = Vec::with_capacity(count);
-    let mut offset = 0;
+/// Split a pulse stream into records: runs of marked bytes ended by an
+/// end-of-data marker, a leader or a pulse that fits no byte.
+fn decode_records(pulses: &[Pulse]) -> Vec<Vec<u8>> {
+    let mut records = Vec::new();
+    let mut current = Vec::new();
+    let mut i = 0;
 
-    for _ in 0..count {
-        if let Some((byte, consumed)) = decode_byte(&bits[offset..]) {
-            bytes.push(byte);
-            offset += consumed;
-        } else {
-            break;
+    while i + 1 < pulses.len() {
+        match (pulses[i], pulses[i + 1]) {
+            (Pulse::Long, Pulse::Medium) => {
+                // Byte marker, then 9 bit pairs
+                let bits: Option<Vec<u8>> = pulses.get(i + 2..i + 20).and_then(|pairs| {
+                    pairs
+                        .chunks_exact(2)
+                        .map(|pair| decode_bit(pair[0], pair[1]))
+                        .collect()
+                });
+                if let Some((byte, _)) = bits.as_deref().and_then(decode_byte) {
+                    current.push(byte);
+                    i += 20;
+                    continue;
+                }
+            }
+            (Pulse::Long, Pulse::Short) => {
+                // End-of-data marker
+                if !current.is_empty() {
+                    records.push(std::mem::take(&mut current));
+                }
+                i += 2;
+                continue;
+            }
+            _ => {}
+        }
+        if !current.is_empty() {
+            records.push(std::mem::take(&mut current));
         }
+        i += 1;
+    }
+    if !current.is_empty() {
+        records.push(current);
     }
 
-    (bytes, offset)
+    records
 }
 
-/// Find the start of a data block by skipping leader and sync.
-///
-/// The C64 tape leader consists of repeated short pulses (many hundreds),
-/// followed by a sync pattern. After sync comes the actual data.
-///
-/// Returns the index of the first data bit after sync, or None.
-fn find_data_start(bits: &[u8], offset: usize) -> Option<usize> {
-    // Look for a countdown sequence: $89, $88, $87, ..., $81
-    // The countdown uses 9 bits per byte (8 data + 1 parity)
-    let mut i = offset;
-
-    // Skip until we find the header marker byte $89
-    while i + 9 <= bits.len() {
-        if let Some((byte, _)) = decode_byte(&bits[i..])
-            && byte == 0x89
-        {
-            // Found the start of countdown — skip all 9 countdown bytes
-            // ($89 through $81 = 9 bytes × 9 bits = 81 bits)
-            let skip = 9 * 9;
-            if i + skip <= bits.len() {
-                return Some(i + skip);
-            }
-            return None;
-        }
-        i += 1;
+/// Check a record's countdown and checksum. Returns whether it is the
+/// repeat copy, and its payload.
+fn block_payload(record: &[u8]) -> Option<(bool, &[u8])> {
+    if record.len() < 10 {
+        return None;
     }
-    None
+    let (countdown, rest) = record.split_at(9);
+    let repeat = match countdown[0] {
+        0x89 => false,
+        0x09 => true,
+        _ => return None,
+    };
+    if countdown
+        .iter()
+        .zip((1..=countdown[0]).rev())
+        .any(|(&byte, expected)| byte != expected)
+    {
+        return None;
+    }
+    let (payload, checksum) = rest.split_at(rest.len() - 1);
+    if payload.iter().fold(0, |sum, &byte| sum ^ byte) != checksum[0] {
+        return None;
+    }
+    Some((repeat, payload))
 }
 
 impl C64TapFile {
@@ -217,7 +222,7 @@ impl C64TapFile {
     ///
     /// Returns an error if the header is invalid or the file is truncated.
     pub fn parse(data: &[u8]) -> Result<Self, String> {
-        if data.len() < 20 {
+        if data.len() < HEADER_SIZE {
             return Err("TAP file too short for header".to_string());
         }
 
@@ -248,120 +253,105 @@ impl C64TapFile {
         let pulse_data = &data[20..pulse_data_end];
 
         // Extract pulse durations
-        let raw_pulses = extract_pulses(pulse_data, version);
+        Ok(Self::from_pulses(extract_pulses(pulse_data, version)))
+    }
 
+    /// Build a tape from pulse durations in CPU cycles, decoding any
+    /// standard kernal blocks they contain.
+    #[must_use]
+    pub fn from_pulses(raw_pulses: Vec<u32>) -> Self {
         // Classify pulses
         let pulses: Vec<Pulse> = raw_pulses.iter().map(|&c| classify_pulse(c)).collect();
 
-        // Decode pulse pairs to bits
-        let bits = decode_bits(&pulses);
-
-        // Find and decode blocks
-        let blocks = Self::extract_blocks(&bits);
+        // Decode marked bytes into records, then records into blocks
+        let blocks = Self::extract_blocks(&decode_records(&pulses));
 
-        Ok(Self { blocks, raw_pulses })
+        Self { blocks, raw_pulses }
     }
 
-    /// Extract blocks from a decoded bit stream.
+    /// Serialise the raw pulses as a version 1 TAP file.
     ///
-    /// The C64 kernal records each block twice on tape. We extract both
-    /// copies but only keep unique blocks (by matching file type and start
-    /// address, taking the first copy).
-    fn extract_blocks(bits: &[u8]) -> Vec<C64TapBlock> {
-        let mut blocks = Vec::new();
-        let mut offset = 0;
-
-        // Scan for blocks
-        loop {
-            // Find next data start (after leader/sync/countdown)
-            let Some(data_start) = find_data_start(bits, offset) else {
-                break;
-            };
+    /// Pulses that fit are stored as one byte of cycles / 8, rounded.
+    /// Longer or shorter ones use the exact long-pulse form, clamped to
+    /// its 24-bit limit.
+    #[must_use]
+    pub fn to_bytes(&self) -> Vec<u8> {
+        let mut pulse_data = Vec::with_capacity(self.raw_pulses.len());
+        for &cycles in &self.raw_pulses {
+            match u8::try_from(cycles.saturating_add(4) / 8) {
+                Ok(byte) if byte != 0 => pulse_data.push(byte),
+                _ => {
+                    let exact = cycles.min(MAX_LONG_PULSE).to_le_bytes();
+                    pulse_data.extend_from_slice(&[0, exact[0], exact[1], exact[2]]);
+                }
+            }
+        }
 
-            // Try to decode a tape header (192 bytes)
-            // First byte after countdown is the block type marker
-            let remaining = &bits[data_start..];
-            let (header_bytes, consumed) = decode_bytes(remaining, 192);
+        let mut out = Vec::with_capacity(HEADER_SIZE + pulse_data.len());
+        out.extend_from_slice(TAP_SIGNATURE);
+        out.extend_from_slice(&[1, 0, 0, 0]);
+        out.extend_from_slice(&(pulse_data.len() as u32).to_le_bytes());
+        out.extend_from_slice(&pulse_data);
+        out
+    }
 
-            if header_bytes.len() < 192 {
-                offset = data_start + consumed.max(1);
-                continue;
+    /// Extract program blocks from decoded records.
+    ///
+    /// Each kernal block is on tape twice. The first copy is used, and the
+    /// repeat only when the first fails its checksum and so is missing. A header is paired
+    /// with the data block that follows it.
+    fn extract_blocks(records: &[Vec<u8>]) -> Vec<C64TapBlock> {
+        let mut payloads = Vec::new();
+        let mut unrepeated = None;
+        for record in records {
+            match block_payload(record) {
+                Some((false, payload)) => {
+                    payloads.push(payload);
+                    unrepeated = Some(payload);
+                }
+                Some((true, payload)) if unrepeated.take() != Some(payload) => {
+                    payloads.push(payload);
+                }
+                Some(_) | None => {}
             }
+        }
 
-            // Parse the 192-byte header:
-            // Byte 0: file type (1=BASIC, 3=ML, 4=SEQ, 5=end-of-tape marker)
-            let file_type = header_bytes[0];
+        let mut blocks = Vec::new();
+        let mut i = 0;
+        while i < payloads.len() {
+            let header = payloads[i];
+            i += 1;
 
-            // Bytes 1-2: start address (lo/hi)
-            let start_address = u16::from(header_bytes[1]) | (u16::from(header_bytes[2]) << 8);
+            // Byte 0: file type (1=BASIC, 3=ML, 4=SEQ, 5=end-of-tape marker)
+            if header.len() != 192 || !matches!(header[0], 1 | 3) {
+                continue;
+            }
+            let file_type = header[0];
 
-            // Bytes 3-4: end address (lo/hi)
-            let end_address = u16::from(header_bytes[3]) | (u16::from(header_bytes[4]) << 8);
+            // Bytes 1-4: start and end address (lo/hi)
+            let start_address = u16::from_le_bytes([header[1], header[2]]);
+            let end_address = u16::from_le_bytes([header[3], header[4]]);
 
             // Bytes 5-20: filename (16 characters, padded with spaces/$A0)
-            let name_bytes = &header_bytes[5..21];
-            let filename = name_bytes
+            let filename = header[5..21]
                 .iter()
                 .map(|&b| if b == 0xA0 || b == 0 { ' ' } else { b as char })
                 .collect::<String>()
                 .trim()
                 .to_string();
 
-            // Move past the header block (192 bytes × 9 bits + gap)
-            offset = data_start + consumed;
-
-            // Skip the repeated copy of the header
-            if let Some(repeat_start) = find_data_start(bits, offset) {
-                // Skip the repeat header (192 × 9 bits)
-                let skip_bits = 192 * 9;
-                offset = (repeat_start + skip_bits).min(bits.len());
-            }
-
-            // End-of-tape marker — stop scanning
-            if file_type == 5 {
+            let data_len = usize::from(end_address.saturating_sub(start_address));
+            let Some(&data) = payloads.get(i).filter(|data| data.len() == data_len) else {
                 continue;
-            }
-
-            // Now find the data block
-            let data_len = if end_address > start_address {
-                (end_address - start_address) as usize
-            } else {
-                0
-            };
-
-            if data_len == 0 {
-                continue;
-            }
-
-            // Find data block start
-            let Some(payload_start) = find_data_start(bits, offset) else {
-                break;
             };
-
-            // First byte of data block is another type marker; skip it
-            let remaining = &bits[payload_start..];
-
-            // Decode type marker + data bytes
-            let (mut payload_bytes, payload_consumed) = decode_bytes(remaining, data_len + 1);
-            offset = payload_start + payload_consumed;
-
-            // Skip the repeated data block
-            if let Some(repeat_start) = find_data_start(bits, offset) {
-                let skip_bits = (data_len + 1) * 9;
-                offset = (repeat_start + skip_bits).min(bits.len());
-            }
-
-            // Remove the type marker byte from the front
-            if !payload_bytes.is_empty() {
-                payload_bytes.remove(0);
-            }
+            i += 1;
 
             blocks.push(C64TapBlock {
                 file_type,
                 start_address,
                 end_address,
                 filename,
-                data: payload_bytes,
+                data: data.to_vec(),
             });
         }
 
@@ -441,6 +431,21 @@ mod tests {
         assert_eq!(pulses, vec![352, 0x160, 512]); // 0x160 = 352
     }
 
+    #[test]
+    fn writer_round_trips_v1() {
+        let tap = C64TapFile::from_pulses(vec![352, 510, 672, 2048, 100_000, 0x0100_0000]);
+        let bytes = tap.to_bytes();
+        assert_eq!(&bytes[0..12], TAP_SIGNATURE);
+        assert_eq!(bytes[12], 1);
+
+        let parsed = C64TapFile::parse(&bytes).expect("written TAP should parse");
+        // Short pulses round to 8 cycles; long ones are exact up to 24 bits.
+        assert_eq!(
+            parsed.raw_pulses,
+            vec![352, 512, 672, 2048, 100_000, MAX_LONG_PULSE]
+        );
+    }
+
     #[test]
     fn decode_byte_lsb_first() {
         // Byte 0x55 = 01010101 binary, LSB first: 1,0,1,0,1,0,1,0
@@ -475,4 +480,71 @@ mod tests {
         data[16] = 100;
         assert!(C64TapFile::parse(&data).is_err());
     }
+
+    /// Pulses for one kernal block: leader, countdown from `first`, the
+    /// payload and its checksum, then the end-of-data marker.
+    fn kernal_block(first: u8, payload: &[u8]) -> Vec<u32> {
+        const S: u32 = 384;
+        const M: u32 = 528;
+        const L: u32 = 688;
+        let mut bytes: Vec<u8> = (first - 8..=first).rev().collect();
+        bytes.extend_from_slice(payload);
+        bytes.push(payload.iter().fold(0, |sum, &b| sum ^ b));
+
+        let mut pulses = vec![S; 100];
+        for byte in bytes {
+            pulses.extend_from_slice(&[L, M]);
+            let parity = u8::from(byte.count_ones() % 2 == 0);
+            for bit in (0..8).map(|n| (byte >> n) & 1).chain([parity]) {
+                pulses.extend_from_slice(if bit == 1 { &[M, S] } else { &[S, M] });
+            }
+        }
+        pulses.extend_from_slice(&[L, S]);
+        pulses
+    }
+
+    /// Pulses for a kernal SAVE of `data` to `start` as `name`.
+    fn kernal_save(name: &str, start: u16, data: &[u8]) -> [Vec<u32>; 4] {
+        let end = start + data.len() as u16;
+        let mut header = vec![0x20; 192];
+        header[0] = 1;
+        header[1..3].copy_from_slice(&start.to_le_bytes());
+        header[3..5].copy_from_slice(&end.to_le_bytes());
+        header[5..5 + name.len()].copy_from_slice(name.as_bytes());
+        [
+            kernal_block(0x89, &header),
+            kernal_block(0x09, &header),
+            kernal_block(0x89, data),
+            kernal_block(0x09, data),
+        ]
+    }
+
+    #[test]
+    fn kernal_save_decodes_to_one_block() {
+        let program = [0x0B, 0x08, 0x0A, 0x00, 0x8F, 0x00, 0x00, 0x00];
+        let pulses = kernal_save("HELLO", 0x0801, &program).concat();
+        let tap = C64TapFile::parse(&C64TapFile::from_pulses(pulses).to_bytes())
+            .expect("written TAP should parse");
+
+        assert_eq!(tap.blocks.len(), 1);
+        let block = &tap.blocks[0];
+        assert_eq!(block.file_type, 1);
+        assert_eq!(block.filename, "HELLO");
+        assert_eq!(block.start_address, 0x0801);
+        assert_eq!(block.end_address, 0x0809);
+        assert_eq!(block.data, program);
+    }
+
+    #[test]
+    fn repeat_copy_stands_in_for_a_bad_first_copy() {
+        let program = [0x12, 0x34, 0x56];
+        let mut parts = kernal_save("DATA", 0xC000, &program);
+        // Flip bit 0 of the first data byte: skip the leader and countdown
+        let bit = 100 + 9 * 20 + 2;
+        parts[2].swap(bit, bit + 1);
+
+        let tap = C64TapFile::from_pulses(parts.concat());
+        assert_eq!(tap.blocks.len(), 1);
+        assert_eq!(tap.blocks[0].data, program);
+    }
 }
@@ -0,0 +1,273 @@
+//! CSW (compressed square wave) tape image parser.
+//!
+//! CSW records a tape as the lengths of its pulses, in samples at a fixed
+//! rate, so it keeps any loader's timing. Each pulse is one half-wave;
+//! the level flips at the end of each.
+//!
+//! # Format
+//!
+//! A 22-byte signature (`"Compressed Square Wave"` + 0x1A), a version, a
+//! header, then the pulse data. Version 1 has a 16-bit sample rate and RLE
+//! only. Version 2 has a 32-bit rate, the pulse count, an encoder name and
+//! an optional header extension, and adds Z-RLE.
+//!
+//! **RLE:** one byte per pulse. A zero byte is followed by the length as a
+//! 32-bit little-endian value, for pulses over 255 samples.
+//!
+//! **Z-RLE:** the RLE stream, zlib-compressed.
+//!
+//! Reference: <https://ramsoft.bbk.org.omegahg.com/csw.html>
+
+#![allow(clippy::cast_possible_truncation)]
+
+use std::io::Read;
+
+use flate2::read::ZlibDecoder;
+
+/// CSW signature, including the 0x1A terminator.
+const MAGIC: &[u8; 23] = b"Compressed Square Wave\x1A";
+
+/// Offset of the pulse data in a version 1 file.
+const V1_DATA_OFFSET: usize = 0x20;
+
+/// Size of the fixed part of a version 2 header.
+const V2_HEADER_SIZE: usize = 0x34;
+
+/// How the pulse data is packed.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum CswCompression {
+    /// Run-length bytes.
+    Rle,
+    /// Run-length bytes, zlib-compressed.
+    ZRle,
+}
+
+impl CswCompression {
+    /// Decode the compression type byte (1 = RLE, 2 = Z-RLE).
+    ///
+    /// # Errors
+    ///
+    /// Returns an error for any other value.
+    pub fn from_byte(byte: u8) -> Result<Self, String> {
+        match byte {
+            1 => Ok(Self::Rle),
+            2 => Ok(Self::ZRle),
+            n => Err(format!("Unknown CSW compression type {n}")),
+        }
+    }
+}
+
+/// A parsed CSW file.
+#[derive(Debug, Clone)]
+pub struct CswFile {
+    pub major: u8,
+    pub minor: u8,
+    /// Samples per second.
+    pub sample_rate: u32,
+    pub compression: CswCompression,
+    /// Level of the first pulse: `true` for high.
+    pub initial_level: bool,
+    /// Pulse lengths in samples.
+    pub pulses: Vec<u32>,
+}
+
+impl CswFile {
+    /// Parse a CSW file from raw bytes.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the signature or header is invalid, or the
+    /// pulse data does not decode.
+    pub fn parse(data: &[u8]) -> Result<Self, String> {
+        if data.len() < V1_DATA_OFFSET || &data[..MAGIC.len()] != MAGIC {
+            return Err("Invalid CSW signature (expected \"Compressed Square Wave\")".to_string());
+        }
+        let major = data[0x17];
+        let minor = data[0x18];
+
+        let (sample_rate, compression, flags, start) = match major {
+            1 => (
+                u32::from(read_u16_le(data, 0x19)),
+                CswCompression::from_byte(data[0x1B])?,
+                data[0x1C],
+                V1_DATA_OFFSET,
+            ),
+            2 => {
+                if data.len() < V2_HEADER_SIZE {
+                    return Err("CSW v2 header truncated".to_string());
+                }
+                let extension = data[0x23] as usize;
+                (
+                    read_u32_le(data, 0x19),
+                    CswCompression::from_byte(data[0x21])?,
+                    data[0x22],
+                    V2_HEADER_SIZE + extension,
+                )
+            }
+            v => return Err(format!("Unsupported CSW version {v}.{minor}")),
+        };
+        if sample_rate == 0 {
+            return Err("CSW sample rate is zero".to_string());
+        }
+        if start > data.len() {
+            return Err("CSW header extension runs past the end of the file".to_string());
+        }
+
+        let pulses = decode(&data[start..], compression)?;
+        Ok(Self {
+            major,
+            minor,
+            sample_rate,
+            compression,
+            initial_level: flags & 0x01 != 0,
+            pulses,
+        })
+    }
+
+    /// Total length of the recording in samples.
+    #[must_use]
+    pub fn total_samples(&self) -> u64 {
+        self.pulses.iter().map(|&p| u64::from(p)).sum()
+    }
+}
+
+/// Decode pulse data packed with `compression`.
+///
+/// # Errors
+///
+/// Returns an error if the Z-RLE stream does not inflate or an RLE escape
+/// is cut short.
+pub fn decode(data: &[u8], compression: CswCompression) -> Result<Vec<u32>, String> {
+    match compression {
+        CswCompression::Rle => decode_rle(data),
+        CswCompression::ZRle => {
+            let mut rle = Vec::new();
+            ZlibDecoder::new(data)
+                .read_to_end(&mut rle)
+                .map_err(|e| format!("CSW Z-RLE data does not inflate: {e}"))?;
+            decode_rle(&rle)
+        }
+    }
+}
+
+/// Decode RLE pulse bytes.
+///
+/// # Errors
+///
+/// Returns an error if a long-pulse escape is cut short.
+pub fn decode_rle(data: &[u8]) -> Result<Vec<u32>, String> {
+    let mut pulses = Vec::with_capacity(data.len());
+    let mut pos = 0;
+    while pos < data.len() {
+        let byte = data[pos];
+        pos += 1;
+        if byte != 0 {
+            pulses.push(u32::from(byte));
+            continue;
+        }
+        if pos + 4 > data.len() {
+            return Err(format!("CSW long pulse at offset {} is truncated", pos - 1));
+        }
+        pulses.push(read_u32_le(data, pos));
+        pos += 4;
+    }
+    Ok(pulses)
+}
+
+/// Encode pulse lengths as RLE bytes, the inverse of [`decode_rle`].
+#[must_use]
+pub fn encode_rle(pulses: &[u32]) -> Vec<u8> {
+    let mut out = Vec::with_capacity(pulses.len());
+    for &pulse in pulses {
+        match u8::try_from(pulse) {
+            Ok(byte) if byte != 0 => out.push(byte),
+            _ => {
+                out.push(0);
+                out.extend_from_slice(&pulse.to_le_bytes());
+            }
+        }
+    }
+    out
+}
+
+fn read_u16_le(data: &[u8], pos: usize) -> u16 {
+    u16::from_le_bytes([data[pos], data[pos + 1]])
+}
+
+fn read_u32_le(data: &[u8], pos: usize) -> u32 {
+    u32::from_le_bytes([data[pos], data[pos + 1], data[pos + 2], data[pos + 3]])
+}
+
+#[cfg(test)]
+mod tests {
+    use std::io::Write;
+
+    use flate2::Compression;
+    use flate2::write::ZlibEncoder;
+
+    use super::*;
+
+    /// RLE bytes for pulses 10, 20, 300.
+    const RLE: [u8; 7] = [10, 20, 0, 0x2C, 0x01, 0x00, 0x00];
+
+    fn v1(data: &[u8]) -> Vec<u8> {
+        let mut file = MAGIC.to_vec();
+        file.extend_from_slice(&[1, 1]);
+        file.extend_from_slice(&22050u16.to_le_bytes());
+        file.extend_from_slice(&[1, 0x01, 0, 0, 0]);
+        file.extend_from_slice(data);
+        file
+    }
+
+    fn v2(compression: u8, extension: &[u8], data: &[u8]) -> Vec<u8> {
+        let mut file = MAGIC.to_vec();
+        file.extend_from_slice(&[2, 0]);
+        file.extend_from_slice(&44100u32.to_le_bytes());
+        file.extend_from_slice(&3u32.to_le_bytes());
+        file.extend_from_slice(&[compression, 0x00, extension.len() as u8]);
+        file.extend_from_slice(b"test encoder\0\0\0\0");
+        file.extend_from_slice(extension);
+        file.extend_from_slice(data);
+        file
+    }
+
+    #[test]
+    fn rle_long_pulse_escape() {
+        assert_eq!(decode_rle(&RLE).expect("decode"), [10, 20, 300]);
+        assert!(decode_rle(&[5, 0, 1, 2]).is_err());
+        assert_eq!(encode_rle(&[10, 20, 300]), RLE);
+    }
+
+    #[test]
+    fn parse_v1() {
+        let csw = CswFile::parse(&v1(&RLE)).expect("v1");
+        assert_eq!(csw.sample_rate, 22050);
+        assert_eq!(csw.compression, CswCompression::Rle);
+        assert!(csw.initial_level);
+        assert_eq!(csw.pulses, [10, 20, 300]);
+        assert_eq!(csw.total_samples(), 330);
+    }
+
+    #[test]
+    fn parse_v2_z_rle_with_extension() {
+        let mut encoder = ZlibEncoder::new(Vec::new(), Compression::default());
+        encoder.write_all(&RLE).expect("compress");
+        let packed = encoder.finish().expect("compress");
+
+        let csw = CswFile::parse(&v2(2, &[0xEE; 3], &packed)).expect("v2");
+        assert_eq!(csw.sample_rate, 44100);
+        assert_eq!(csw.compression, CswCompression::ZRle);
+        assert!(!csw.initial_level);
+        assert_eq!(csw.pulses, [10, 20, 300]);
+    }
+
+    #[test]
+    fn rejects_bad_files() {
+        assert!(CswFile::parse(b"Compressed Square Wav").is_err());
+        assert!(CswFile::parse(&v2(3, &[], &RLE)).is_err());
+        assert!(CswFile::parse(&v2(2, &[], &RLE)).is_err());
+        let mut bad_version = v1(&RLE);
+        bad_version[0x17] = 3;
+        assert!(CswFile::parse(&bad_version).is_err());
+    }
+}
@@ -0,0 +1,430 @@
+//! Amiga hunk executable parser.
+//!
+//! An executable is a `HUNK_HEADER` giving the size of each hunk, then
+//! one block per hunk (`HUNK_CODE`, `HUNK_DATA` or `HUNK_BSS`) followed by
+//! its relocation tables, optional `HUNK_SYMBOL` and `HUNK_DEBUG` blocks,
+//! and `HUNK_END`. Every field is a big-endian longword.
+//!
+//! `LoadSeg` places each hunk wherever `AllocMem` finds room, so symbol
+//! values are offsets into their hunk. [`HunkFile::locate`] finds a
+//! loaded hunk in a memory dump by its contents, skipping the longwords
+//! relocation patched.
+
+#![allow(clippy::cast_possible_truncation)]
+
+pub const HUNK_UNIT: u32 = 0x3E7;
+pub const HUNK_NAME: u32 = 0x3E8;
+pub const HUNK_CODE: u32 = 0x3E9;
+pub const HUNK_DATA: u32 = 0x3EA;
+pub const HUNK_BSS: u32 = 0x3EB;
+pub const HUNK_RELOC32: u32 = 0x3EC;
+pub const HUNK_RELOC16: u32 = 0x3ED;
+pub const HUNK_RELOC8: u32 = 0x3EE;
+pub const HUNK_EXT: u32 = 0x3EF;
+pub const HUNK_SYMBOL: u32 = 0x3F0;
+pub const HUNK_DEBUG: u32 = 0x3F1;
+pub const HUNK_END: u32 = 0x3F2;
+pub const HUNK_HEADER: u32 = 0x3F3;
+pub const HUNK_OVERLAY: u32 = 0x3F5;
+pub const HUNK_BREAK: u32 = 0x3F6;
+/// `HUNK_DREL32`, which `LoadSeg` reads as `HUNK_RELOC32SHORT` in
+/// executables.
+pub const HUNK_DREL32: u32 = 0x3F7;
+pub const HUNK_DREL16: u32 = 0x3F8;
+pub const HUNK_DREL8: u32 = 0x3F9;
+pub const HUNK_RELOC32SHORT: u32 = 0x3FC;
+
+/// Memory-attribute bits in the top of hunk ids and sizes.
+const FLAG_MASK: u32 = 0xC000_0000;
+/// `EXT_COMMON`: a reference carrying a common block size.
+const EXT_COMMON: u32 = 130;
+
+/// Kind of memory a hunk occupies.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum HunkKind {
+    Code,
+    Data,
+    Bss,
+}
+
+/// One loadable hunk.
+#[derive(Debug, Clone)]
+pub struct Hunk {
+    pub kind: HunkKind,
+    /// Bytes allocated for the hunk (from the header).
+    pub memory_size: u32,
+    /// Initialised contents; empty for BSS. The rest of `memory_size` is
+    /// zeroed.
+    pub data: Vec<u8>,
+    /// Offsets of the longwords `HUNK_RELOC32` patches with hunk
+    /// addresses.
+    pub relocs: Vec<u32>,
+    /// `HUNK_SYMBOL` entries: name and offset into the hunk.
+    pub symbols: Vec<(String, u32)>,
+}
+
+/// A parsed hunk executable.
+#[derive(Debug, Clone)]
+pub struct HunkFile {
+    pub hunks: Vec<Hunk>,
+}
+
+struct Reader<'a> {
+    data: &'a [u8],
+    pos: usize,
+}
+
+impl Reader<'_> {
+    fn at_end(&self) -> bool {
+        self.pos + 4 > self.data.len()
+    }
+
+    fn long(&mut self) -> Result<u32, String> {
+        let bytes = self
+            .data
+            .get(self.pos..self.pos + 4)
+            .ok_or_else(|| format!("Hunk file truncated at offset {}", self.pos))?;
+        self.pos += 4;
+        Ok(u32::from_be_bytes([bytes[0], bytes[1], bytes[2], bytes[3]]))
+    }
+
+    fn word(&mut self) -> Result<u16, String> {
+        let bytes = self
+            .data
+            .get(self.pos..self.pos + 2)
+            .ok_or_else(|| format!("Hunk file truncated at offset {}", self.pos))?;
+        self.pos += 2;
+        Ok(u16::from_be_bytes([bytes[0], bytes[1]]))
+    }
+
+    fn bytes(&mut self, len: usize) -> Result<&[u8], String> {
+        let bytes = self
+            .data
+            .get(self.pos..self.pos + len)
+            .ok_or_else(|| format!("Hunk file truncated at offset {}", self.pos))?;
+        self.pos += len;
+        Ok(bytes)
+    }
+
+    fn skip_longs(&mut self, count: u32) -> Result<(), String> {
+        self.bytes(count as usize * 4).map(|_| ())
+    }
+
+    /// A name `longs` longwords long, NUL padding dropped.
+    fn name(&mut self, longs: u32) -> Result<String, String> {
+        let raw = self.bytes(longs as usize * 4)?;
+        let end = raw.iter().position(|&b| b == 0).unwrap_or(raw.len());
+        Ok(String::from_utf8_lossy(&raw[..end]).into_owned())
+    }
+
+    /// `HUNK_RELOC32`-style table: (count, target, offsets...) groups
+    /// ending with a zero count.
+    fn reloc_table(&mut self, mut each: impl FnMut(u32)) -> Result<(), String> {
+        loop {
+            let count = self.long()?;
+            if count == 0 {
+                return Ok(());
+            }
+            self.long()?;
+            for _ in 0..count {
+                each(self.long()?);
+            }
+        }
+    }
+
+    /// `HUNK_RELOC32SHORT`: the same table in words, padded to a longword.
+    fn short_reloc_table(&mut self, mut each: impl FnMut(u32)) -> Result<(), String> {
+        let start = self.pos;
+        loop {
+            let count = self.word()?;
+            if count == 0 {
+                break;
+            }
+            self.word()?;
+            for _ in 0..count {
+                each(u32::from(self.word()?));
+            }
+        }
+        if !(self.pos - start).is_multiple_of(4) {
+            self.word()?;
+        }
+        Ok(())
+    }
+}
+
+impl HunkFile {
+    /// True if `data` starts with a `HUNK_HEADER`.
+    #[must_use]
+    pub fn is_hunk(data: &[u8]) -> bool {
+        data.len() >= 4 && u32::from_be_bytes([data[0], data[1], data[2], data[3]]) == HUNK_HEADER
+    }
+
+    /// Parse an executable.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the data is not a hunk executable, is
+    /// truncated, holds a block type this parser does not know, or uses
+    /// overlays.
+    pub fn parse(data: &[u8]) -> Result<Self, String> {
+        if !Self::is_hunk(data) {
+            return Err("Not a hunk executable (no HUNK_HEADER)".to_string());
+        }
+        let mut r = Reader { data, pos: 4 };
+
+        // Resident library names, unused by executables.
+        loop {
+            let longs = r.long()?;
+            if longs == 0 {
+                break;
+            }
+            r.skip_longs(longs)?;
+        }
+        let _table_size = r.long()?;
+        let first = r.long()?;
+        let last = r.long()?;
+        if last < first {
+            return Err(format!("Bad hunk range {first}-{last}"));
+        }
+        let count = last - first + 1;
+        let mut sizes = Vec::with_capacity(count.min(1024) as usize);
+        for _ in 0..count {
+            let size = r.long()?;
+            if size & FLAG_MASK == FLAG_MASK {
+                r.long()?;
+            }
+            sizes.push((size & !FLAG_MASK) * 4);
+        }
+
+        let mut hunks: Vec<Hunk> = Vec::with_capacity(sizes.len());
+        let mut current: Option<Hunk> = None;
+        while !r.at_end() && hunks.len() < sizes.len() {
+            let id = r.long()? & !FLAG_MASK;
+            match id {
+                HUNK_CODE | HUNK_DATA | HUNK_BSS => {
+                    if let Some(hunk) = current.take() {
+                        hunks.push(hunk);
+                    }
+                    let Some(&memory_size) = sizes.get(hunks.len()) else {
+                        break;
+                    };
+                    let longs = r.long()? & !FLAG_MASK;
+                    let (kind, data) = if id == HUNK_BSS {
+                        (HunkKind::Bss, Vec::new())
+                    } else {
+                        let data = r.bytes(longs as usize * 4)?.to_vec();
+                        let kind = if id == HUNK_CODE {
+                            HunkKind::Code
+                        } else {
+                            HunkKind::Data
+                        };
+                        (kind, data)
+                    };
+                    current = Some(Hunk {
+                        kind,
+                        memory_size,
+                        data,
+                        relocs: Vec::new(),
+                        symbols: Vec::new(),
+                    });
+                }
+                HUNK_RELOC32 | HUNK_RELOC32SHORT | HUNK_DREL32 => {
+                    let mut offsets = Vec::new();
+                    if id == HUNK_RELOC32 {
+                        r.reloc_table(|offset| offsets.push(offset))?;
+                    } else {
+                        r.short_reloc_table(|offset| offsets.push(offset))?;
+                    }
+                    if let Some(hunk) = current.as_mut() {
+                        hunk.relocs.extend(offsets);
+                    }
+                }
+                HUNK_RELOC16 | HUNK_RELOC8 | HUNK_DREL16 | HUNK_DREL8 => {
+                    r.reloc_table(|_| {})?;
+                }
+                HUNK_SYMBOL => loop {
+                    let longs = r.long()? & 0x00FF_FFFF;
+                    if longs == 0 {
+                        break;
+                    }
+                    let name = r.name(longs)?;
+                    let offset = r.long()?;
+                    if let Some(hunk) = current.as_mut() {
+                        hunk.symbols.push((name, offset));
+                    }
+                },
+                HUNK_EXT => loop {
+                    let header = r.long()?;
+                    if header == 0 {
+                        break;
+                    }
+                    let kind = header >> 24;
+                    r.skip_longs(header & 0x00FF_FFFF)?;
+                    if kind < 128 {
+                        r.long()?;
+                    } else {
+                        if kind == EXT_COMMON {
+                            r.long()?;
+                        }
+                        let refs = r.long()?;
+                        r.skip_longs(refs)?;
+                    }
+                },
+                HUNK_DEBUG | HUNK_NAME | HUNK_UNIT => {
+                    let longs = r.long()?;
+                    r.skip_longs(longs)?;
+                }
+                HUNK_END => {
+                    if let Some(hunk) = current.take() {
+                        hunks.push(hunk);
+                    }
+                }
+                HUNK_OVERLAY | HUNK_BREAK => {
+                    return Err("Overlaid executables are not supported".to_string());
+                }
+                other => {
+                    return Err(format!(
+                        "Unknown hunk block ${other:08X} at offset {}",
+                        r.pos - 4
+                    ));
+                }
+            }
+        }
+        if let Some(hunk) = current.take() {
+            hunks.push(hunk);
+        }
+        Ok(Self { hunks })
+    }
+
+    /// True if `memory` starts with hunk `index` as `LoadSeg` would have
+    /// left it: its data, with relocated longwords taken as read.
+    #[must_use]
+    pub fn matches(&self, index: usize, memory: &[u8]) -> bool {
+        let Some(hunk) = self.hunks.get(index) else {
+            return false;
+        };
+        if memory.len() < hunk.data.len() {
+            return false;
+        }
+        let mut masked = vec![false; hunk.data.len()];
+        for &offset in &hunk.relocs {
+            let start = offset as usize;
+            for flag in masked.iter_mut().skip(start).take(4) {
+                *flag = true;
+            }
+        }
+        hunk.data
+            .iter()
+            .zip(memory)
+            .zip(&masked)
+            .all(|((want, got), skip)| *skip || want == got)
+    }
+
+    /// Offset in `memory` where hunk `index` was loaded, searching
+    /// longword-aligned offsets. `None` for BSS hunks, hunks with too
+    /// little fixed content to recognise, or hunks not present.
+    #[must_use]
+    pub fn locate(&self, index: usize, memory: &[u8]) -> Option<usize> {
+        let hunk = self.hunks.get(index)?;
+        let fixed = hunk.data.len().saturating_sub(hunk.relocs.len() * 4);
+        if fixed < 8 || memory.len() < hunk.data.len() || hunk.data.iter().all(|&b| b == 0) {
+            return None;
+        }
+        // Anchor on the first longword relocation leaves alone.
+        let anchor = (0..hunk.data.len() / 4).map(|i| i * 4).find(|&at| {
+            hunk.relocs
+                .iter()
+                .all(|&r| (r as usize + 4 <= at) || (r as usize >= at + 4))
+        })?;
+        let key = &hunk.data[anchor..anchor + 4];
+        (0..=memory.len().saturating_sub(hunk.data.len()))
+            .step_by(4)
+            .find(|&at| {
+                memory[at + anchor..at + anchor + 4] == *key && self.matches(index, &memory[at..])
+            })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{
+        HUNK_BSS, HUNK_CODE, HUNK_DATA, HUNK_END, HUNK_HEADER, HUNK_RELOC32, HUNK_RELOC32SHORT,
+        HUNK_SYMBOL, HunkFile, HunkKind,
+    };
+
+    fn longs(values: &[u32]) -> Vec<u8> {
+        values.iter().flat_map(|v| v.to_be_bytes()).collect()
+    }
+
+    fn symbol(name: &str, offset: u32) -> Vec<u8> {
+        let mut raw = name.as_bytes().to_vec();
+        raw.resize(name.len().div_ceil(4) * 4, 0);
+        let mut out = longs(&[(raw.len() / 4) as u32]);
+        out.extend(raw);
+        out.extend(longs(&[offset]));
+        out
+    }
+
+    /// Code hunk (with a RELOC32 at offset 4 and two symbols), a data
+    /// hunk with a short reloc table, and a chip-memory BSS hunk.
+    fn executable() -> Vec<u8> {
+        let mut exe = longs(&[HUNK_HEADER, 0, 3, 0, 2, 3, 2, 0x4000_0010]);
+        exe.extend(longs(&[
+            HUNK_CODE,
+            3,
+            0x4EB9_0000,
+            0x0000_4E75,
+            0x1234_5678,
+        ]));
+        exe.extend(longs(&[HUNK_RELOC32, 1, 1, 4, 0]));
+        exe.extend(longs(&[HUNK_SYMBOL]));
+        exe.extend(symbol("start", 0));
+        exe.extend(symbol("return_here", 6));
+        exe.extend(longs(&[0, HUNK_END]));
+        exe.extend(longs(&[HUNK_DATA, 2, 0xCAFE_BABE, 0]));
+        exe.extend(longs(&[HUNK_RELOC32SHORT, 0x0001_0000, 0x0004_0000]));
+        exe.extend(longs(&[HUNK_END, HUNK_BSS | 0x4000_0000, 16, HUNK_END]));
+        exe
+    }
+
+    #[test]
+    fn parses_hunks_relocs_and_symbols() {
+        let file = HunkFile::parse(&executable()).expect("valid executable");
+        assert_eq!(file.hunks.len(), 3);
+
+        let code = &file.hunks[0];
+        assert_eq!(code.kind, HunkKind::Code);
+        assert_eq!(code.memory_size, 12);
+        assert_eq!(code.relocs, [4]);
+        assert_eq!(
+            code.symbols,
+            [("start".to_string(), 0), ("return_here".to_string(), 6)]
+        );
+
+        assert_eq!(file.hunks[1].kind, HunkKind::Data);
+        assert_eq!(file.hunks[1].relocs, [4]);
+        assert_eq!(file.hunks[2].kind, HunkKind::Bss);
+        assert_eq!(file.hunks[2].memory_size, 64);
+        assert!(file.hunks[2].data.is_empty());
+
+        assert!(!HunkFile::is_hunk(b"\0\0\x03\xE9"));
+        assert!(HunkFile::parse(&executable()[..40]).is_err());
+    }
+
+    #[test]
+    fn locates_loaded_hunks_despite_relocation() {
+        let file = HunkFile::parse(&executable()).expect("valid executable");
+        let mut memory = vec![0u8; 0x400];
+        let code_at = 0x108;
+        memory[code_at..code_at + 12].copy_from_slice(&file.hunks[0].data);
+        // LoadSeg patched the JSR target with the data hunk's address.
+        memory[code_at + 4..code_at + 8].copy_from_slice(&0x0000_0200_u32.to_be_bytes());
+        // A copy with the same bytes but different fixed content.
+        memory[0x40..0x4C].copy_from_slice(&file.hunks[0].data);
+        memory[0x4A] = 0xFF;
+
+        assert_eq!(file.locate(0, &memory), Some(code_at));
+        assert!(file.matches(0, &memory[code_at..]));
+        assert_eq!(file.locate(2, &memory), None);
+    }
+}
@@ -0,0 +1,364 @@
+//! PZX tape image parser.
+//!
+//! PZX describes a tape as pulses measured in T-states of the 3.5 MHz
+//! 48K Spectrum. It is simpler than TZX: there is one block for raw
+//! pulses, one for data bits with their own pulse patterns, and a few
+//! for pauses and control.
+//!
+//! # Format
+//!
+//! A sequence of blocks, each a 4-character tag, a 32-bit little-endian
+//! size and the body. The first block must be `PZXT`, the header.
+//!
+//! - `PULS`: pulse lengths. The level is low at the start and flips at
+//!   the end of each pulse, so a zero-length first pulse starts high.
+//! - `DATA`: bits, each sent as the pulse sequence for 0 or 1, then an
+//!   optional tail pulse.
+//! - `PAUS`: a single pulse of silence at a given level.
+//! - `BRWS`: a browse point, with a description.
+//! - `STOP`: stop the tape, always or on 48K machines only.
+//!
+//! Reference: <http://zxds.raxoft.cz/docs/pzx.txt>
+
+#![allow(clippy::cast_possible_truncation)]
+
+/// Tag of the header block, which must come first.
+const HEADER_TAG: &[u8; 4] = b"PZXT";
+
+/// A parsed PZX file.
+#[derive(Debug, Clone)]
+pub struct PzxFile {
+    pub major: u8,
+    pub minor: u8,
+    /// Title from the header, if present.
+    pub title: String,
+    /// Further (key, value) info pairs from the header.
+    pub info: Vec<(String, String)>,
+    pub blocks: Vec<PzxBlock>,
+}
+
+/// A single PZX block.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum PzxBlock {
+    /// `PULS`: pulse lengths in T-states, repeats expanded. The first
+    /// pulse is low.
+    Pulses { pulses: Vec<u32> },
+    /// `DATA`: data bits sent MSB first.
+    Data {
+        /// Level of the first pulse: `true` for high.
+        initial_level: bool,
+        /// Number of bits; the last byte may be partly used.
+        bit_count: u32,
+        /// Pulse after the last bit, or 0 for none.
+        tail: u16,
+        /// Pulse sequence for a 0 bit.
+        zero_pulses: Vec<u16>,
+        /// Pulse sequence for a 1 bit.
+        one_pulses: Vec<u16>,
+        data: Vec<u8>,
+    },
+    /// `PAUS`: a pause of `duration` T-states.
+    Pause { initial_level: bool, duration: u32 },
+    /// `BRWS`: browse point.
+    Browse { text: String },
+    /// `STOP`: stop the tape, or only on a 48K machine.
+    Stop { only_48k: bool },
+    /// Unknown block (skipped).
+    Unknown { tag: [u8; 4] },
+}
+
+impl PzxFile {
+    /// Parse a PZX file from raw bytes.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the file does not start with a `PZXT` header
+    /// or a block is malformed.
+    pub fn parse(data: &[u8]) -> Result<Self, String> {
+        if data.len() < 8 || &data[0..4] != HEADER_TAG {
+            return Err("Invalid PZX header (expected \"PZXT\" block)".to_string());
+        }
+
+        let mut file = Self {
+            major: 0,
+            minor: 0,
+            title: String::new(),
+            info: Vec::new(),
+            blocks: Vec::new(),
+        };
+        let mut pos = 0;
+
+        while pos < data.len() {
+            need(data, pos, 8, "block header")?;
+            let tag = [data[pos], data[pos + 1], data[pos + 2], data[pos + 3]];
+            let size = read_u32_le(data, pos + 4) as usize;
+            pos += 8;
+
+            need(data, pos, size, &format!("{} block", tag_name(&tag)))?;
+            let body = &data[pos..pos + size];
+            pos += size;
+
+            match &tag {
+                b"PZXT" => file.parse_header(body)?,
+                b"PULS" => file.blocks.push(parse_pulses(body)?),
+                b"DATA" => file.blocks.push(parse_data(body)?),
+                b"PAUS" => {
+                    need(body, 0, 4, "PAUS block")?;
+                    let word = read_u32_le(body, 0);
+                    file.blocks.push(PzxBlock::Pause {
+                        initial_level: word & 0x8000_0000 != 0,
+                        duration: word & 0x7FFF_FFFF,
+                    });
+                }
+                b"BRWS" => file.blocks.push(PzxBlock::Browse {
+                    text: String::from_utf8_lossy(body)
+                        .trim_end_matches('\0')
+                        .to_string(),
+                }),
+                b"STOP" => {
+                    need(body, 0, 2, "STOP block")?;
+                    file.blocks.push(PzxBlock::Stop {
+                        only_48k: read_u16_le(body, 0) == 1,
+                    });
+                }
+                _ => file.blocks.push(PzxBlock::Unknown { tag }),
+            }
+        }
+
+        Ok(file)
+    }
+
+    /// `PZXT`: version, then NUL-separated title and info pairs.
+    fn parse_header(&mut self, body: &[u8]) -> Result<(), String> {
+        need(body, 0, 2, "PZXT block")?;
+        self.major = body[0];
+        self.minor = body[1];
+        if self.major != 1 {
+            return Err(format!(
+                "Unsupported PZX version {}.{}",
+                self.major, self.minor
+            ));
+        }
+
+        let text = &body[2..];
+        let text = text.strip_suffix(&[0]).unwrap_or(text);
+        if text.is_empty() {
+            return Ok(());
+        }
+        let mut strings = text
+            .split(|&b| b == 0)
+            .map(|s| String::from_utf8_lossy(s).to_string());
+        self.title = strings.next().unwrap_or_default();
+        while let Some(key) = strings.next() {
+            self.info.push((key, strings.next().unwrap_or_default()));
+        }
+        Ok(())
+    }
+}
+
+// ---------------------------------------------------------------------------
+// Helpers
+// ---------------------------------------------------------------------------
+
+fn need(data: &[u8], pos: usize, n: usize, ctx: &str) -> Result<(), String> {
+    if pos + n > data.len() {
+        Err(format!(
+            "Truncated PZX {ctx} at offset {pos}: need {n} bytes, {} remain",
+            data.len() - pos
+        ))
+    } else {
+        Ok(())
+    }
+}
+
+fn read_u16_le(data: &[u8], pos: usize) -> u16 {
+    u16::from_le_bytes([data[pos], data[pos + 1]])
+}
+
+fn read_u32_le(data: &[u8], pos: usize) -> u32 {
+    u32::from_le_bytes([data[pos], data[pos + 1], data[pos + 2], data[pos + 3]])
+}
+
+fn tag_name(tag: &[u8; 4]) -> String {
+    String::from_utf8_lossy(tag).to_string()
+}
+
+// ---------------------------------------------------------------------------
+// Block parsers
+// ---------------------------------------------------------------------------
+
+/// `PULS`: each entry is an optional repeat count (a word above 0x8000),
+/// then a duration of one word, or two if its top bit is set.
+fn parse_pulses(body: &[u8]) -> Result<PzxBlock, String> {
+    let mut pulses = Vec::new();
+    let mut pos = 0;
+    while pos < body.len() {
+        need(body, pos, 2, "PULS entry")?;
+        let mut count = 1;
+        let mut duration = u32::from(read_u16_le(body, pos));
+        pos += 2;
+        if duration > 0x8000 {
+            count = duration & 0x7FFF;
+            need(body, pos, 2, "PULS entry")?;
+            duration = u32::from(read_u16_le(body, pos));
+            pos += 2;
+        }
+        if duration >= 0x8000 {
+            need(body, pos, 2, "PULS entry")?;
+            duration = ((duration & 0x7FFF) << 16) | u32::from(read_u16_le(body, pos));
+            pos += 2;
+        }
+        pulses.extend(std::iter::repeat_n(duration, count as usize));
+    }
+    Ok(PzxBlock::Pulses { pulses })
+}
+
+/// `DATA`: bit count and level, tail, the two pulse sequences, then the
+/// bits.
+fn parse_data(body: &[u8]) -> Result<PzxBlock, String> {
+    need(body, 0, 8, "DATA header")?;
+    let word = read_u32_le(body, 0);
+    let bit_count = word & 0x7FFF_FFFF;
+    let tail = read_u16_le(body, 4);
+    let zero_count = body[6] as usize;
+    let one_count = body[7] as usize;
+    let mut pos = 8;
+
+    need(
+        body,
+        pos,
+        (zero_count + one_count) * 2,
+        "DATA pulse sequences",
+    )?;
+    let zero_pulses = (0..zero_count)
+        .map(|i| read_u16_le(body, pos + i * 2))
+        .collect();
+    pos += zero_count * 2;
+    let one_pulses = (0..one_count)
+        .map(|i| read_u16_le(body, pos + i * 2))
+        .collect();
+    pos += one_count * 2;
+
+    let data_len = (bit_count as usize).div_ceil(8);
+    need(body, pos, data_len, "DATA bits")?;
+
+    Ok(PzxBlock::Data {
+        initial_level: word & 0x8000_0000 != 0,
+        bit_count,
+        tail,
+        zero_pulses,
+        one_pulses,
+        data: body[pos..pos + data_len].to_vec(),
+    })
+}
+
+// ---------------------------------------------------------------------------
+// Tests
+// ---------------------------------------------------------------------------
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn block(tag: &[u8; 4], body: &[u8]) -> Vec<u8> {
+        let mut out = tag.to_vec();
+        out.extend_from_slice(&(body.len() as u32).to_le_bytes());
+        out.extend_from_slice(body);
+        out
+    }
+
+    fn header() -> Vec<u8> {
+        block(b"PZXT", b"\x01\x00Game\0Author\0Someone\0")
+    }
+
+    #[test]
+    fn parse_header_info() {
+        let pzx = PzxFile::parse(&header()).expect("header");
+        assert_eq!((pzx.major, pzx.minor), (1, 0));
+        assert_eq!(pzx.title, "Game");
+        assert_eq!(pzx.info, [("Author".to_string(), "Someone".to_string())]);
+        assert!(pzx.blocks.is_empty());
+    }
+
+    #[test]
+    fn rejects_missing_header_and_bad_version() {
+        assert!(PzxFile::parse(&block(b"PULS", &[])).is_err());
+        assert!(PzxFile::parse(&block(b"PZXT", &[2, 0])).is_err());
+    }
+
+    #[test]
+    fn parse_pulse_encodings() {
+        let mut data = header();
+        let mut body = Vec::new();
+        body.extend_from_slice(&0u16.to_le_bytes()); // zero-length pulse
+        body.extend_from_slice(&(0x8000u16 | 3).to_le_bytes()); // repeat 3 times
+        body.extend_from_slice(&2168u16.to_le_bytes());
+        body.extend_from_slice(&0x8001u16.to_le_bytes()); // once, long pulse
+        body.extend_from_slice(&0x8001u16.to_le_bytes());
+        body.extend_from_slice(&0x0002u16.to_le_bytes());
+        data.extend(block(b"PULS", &body));
+
+        let pzx = PzxFile::parse(&data).expect("pulses");
+        assert_eq!(
+            pzx.blocks[0],
+            PzxBlock::Pulses {
+                pulses: vec![0, 2168, 2168, 2168, 0x1_0002]
+            }
+        );
+    }
+
+    #[test]
+    fn parse_data_pause_browse_stop() {
+        let mut data = header();
+        let mut body = Vec::new();
+        body.extend_from_slice(&(0x8000_0000u32 | 0x0A).to_le_bytes());
+        body.extend_from_slice(&945u16.to_le_bytes());
+        body.extend_from_slice(&[2, 2]);
+        for pulse in [855u16, 855, 1710, 1710] {
+            body.extend_from_slice(&pulse.to_le_bytes());
+        }
+        body.extend_from_slice(&[0xFF, 0xC0]);
+        data.extend(block(b"DATA", &body));
+        data.extend(block(b"PAUS", &3_500_000u32.to_le_bytes()));
+        data.extend(block(b"BRWS", b"Level 2\0"));
+        data.extend(block(b"STOP", &1u16.to_le_bytes()));
+        data.extend(block(b"XTRA", &[1, 2, 3]));
+
+        let pzx = PzxFile::parse(&data).expect("blocks");
+        assert_eq!(
+            pzx.blocks,
+            [
+                PzxBlock::Data {
+                    initial_level: true,
+                    bit_count: 10,
+                    tail: 945,
+                    zero_pulses: vec![855, 855],
+                    one_pulses: vec![1710, 1710],
+                    data: vec![0xFF, 0xC0],
+                },
+                PzxBlock::Pause {
+                    initial_level: false,
+                    duration: 3_500_000,
+                },
+                PzxBlock::Browse {
+                    text: "Level 2".to_string(),
+                },
+                PzxBlock::Stop { only_48k: true },
+                PzxBlock::Unknown { tag: *b"XTRA" },
+            ]
+        );
+    }
+
+    #[test]
+    fn truncated_block_errors() {
+        let mut data = header();
+        data.extend_from_slice(b"DATA");
+        data.extend_from_slice(&100u32.to_le_bytes());
+        data.extend_from_slice(&[0; 10]);
+        assert!(PzxFile::parse(&data).is_err());
+
+        let mut short_bits = header();
+        short_bits.extend(block(b"DATA", &[16, 0, 0, 0, 0, 0, 0, 0, 0xFF]));
+        assert!(PzxFile::parse(&short_bits).is_err());
+    }
+}
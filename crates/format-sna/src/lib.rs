@@ -1,4 +1,4 @@
-//! SNA snapshot parser and loader for 48K and 128K ZX Spectrum.
+//! SNA snapshot parser, loader and writer for 48K and 128K ZX Spectrum.
 //!
 //! **48K format** (49,179 bytes): 27-byte header + 49,152 bytes of RAM.
 //! PC is stored on the stack (SP points to it in RAM), so after loading
@@ -8,6 +8,9 @@
 //! (banks 5, 2, and the currently paged bank in that order) + 4-byte
 //! extension (PC, port $7FFD, TR-DOS flag) + 5 × 16,384 bytes of the
 //! remaining RAM banks in ascending order.
+//!
+//! SNA has no room for the AY registers, port $1FFD or the frame
+//! position; [`save_sna`] drops them. `.Z80` and SZX keep them.
 
 #![allow(clippy::cast_possible_truncation)]
 
@@ -51,6 +54,39 @@ pub struct Z80Registers {
     pub im: u8,
     pub iff1: bool,
     pub iff2: bool,
+    /// Stopped in a HALT instruction; PC then addresses the HALT. Only SZX
+    /// records this; other formats resume by executing the HALT again.
+    pub halted: bool,
+}
+
+/// The model a snapshot is saved from, as far as snapshot formats care.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum SnapshotModel {
+    Spectrum48K,
+    Spectrum128K,
+    SpectrumPlus2,
+    SpectrumPlus2A,
+    SpectrumPlus3,
+}
+
+impl SnapshotModel {
+    /// Whether the model has 128K of banked RAM and port $7FFD.
+    #[must_use]
+    pub fn is_128k(self) -> bool {
+        self != Self::Spectrum48K
+    }
+
+    /// Whether the model has port $1FFD (+2A and +3).
+    #[must_use]
+    pub fn has_plus3_register(self) -> bool {
+        matches!(self, Self::SpectrumPlus2A | Self::SpectrumPlus3)
+    }
+
+    /// T-states per frame on the real machine.
+    #[must_use]
+    pub fn frame_tstates(self) -> u32 {
+        if self.is_128k() { 70_908 } else { 69_888 }
+    }
 }
 
 /// Target machine for loading snapshots.
@@ -81,6 +117,51 @@ pub trait SnapshotTarget {
     /// Select the active AY register.
     /// No-op on machines without an AY chip.
     fn select_ay_register(&mut self, _reg: u8) {}
+
+    /// Write the +2A/+3 paging register (port $1FFD).
+    /// No-op on other machines.
+    fn write_plus3_register(&mut self, _val: u8) {}
+
+    /// Set the T-states since the start of the frame (the interrupt).
+    /// No-op if the machine does not track it.
+    fn set_frame_tstate(&mut self, _tstate: u32) {}
+}
+
+/// Source machine for saving snapshots: the inverse of [`SnapshotTarget`].
+pub trait SnapshotSource {
+    /// The model being saved.
+    fn model(&self) -> SnapshotModel;
+
+    /// All CPU registers, including PC.
+    fn registers(&self) -> Z80Registers;
+
+    /// The 16K contents of RAM bank `bank` (0-7), regardless of paging.
+    /// A 48K machine has banks 5, 2 and 0, at $4000, $8000 and $C000.
+    fn ram_bank(&self, bank: u8) -> &[u8];
+
+    /// The border colour (0-7).
+    fn border(&self) -> u8;
+
+    /// The 128K bank register (port $7FFD). Ignored on 48K machines.
+    fn bank_register(&self) -> u8 {
+        0
+    }
+
+    /// The +2A/+3 paging register (port $1FFD). Ignored on other machines.
+    fn plus3_register(&self) -> u8 {
+        0
+    }
+
+    /// The 16 AY registers and the selected register, or `None` without
+    /// an AY chip.
+    fn ay_registers(&self) -> Option<([u8; 16], u8)> {
+        None
+    }
+
+    /// T-states since the start of the frame (the interrupt).
+    fn frame_tstate(&self) -> u32 {
+        0
+    }
 }
 
 /// A parsed SNA snapshot.
@@ -263,6 +344,77 @@ pub fn load_sna(target: &mut impl SnapshotTarget, data: &[u8]) -> Result<(), Str
     snapshot.apply(target)
 }
 
+/// Save a snapshot as SNA: 48K for a 48K machine, 128K otherwise.
+///
+/// A 48K SNA keeps PC on the stack, so two bytes below SP are overwritten
+/// in the saved RAM (the machine itself is untouched), as a real interrupt
+/// would.
+///
+/// # Errors
+///
+/// Returns an error if a 48K machine's stack pointer is in ROM, or if a
+/// 128K machine has bank 2 or 5 paged in at $C000 (the 128K layout cannot
+/// hold a bank twice).
+pub fn save_sna(source: &impl SnapshotSource) -> Result<Vec<u8>, String> {
+    let model = source.model();
+    let mut regs = source.registers();
+
+    let mut ram = Vec::with_capacity(RAM_SIZE);
+    let paged_bank = source.bank_register() & 0x07;
+    let slots = if model.is_128k() {
+        if paged_bank == 2 || paged_bank == 5 {
+            return Err(format!(
+                "SNA 128K cannot hold bank {paged_bank} paged at $C000; save as .Z80 instead"
+            ));
+        }
+        [5, 2, paged_bank]
+    } else {
+        [5, 2, 0]
+    };
+    for bank in slots {
+        ram.extend_from_slice(source.ram_bank(bank));
+    }
+
+    if !model.is_128k() {
+        // Push PC, as the loader pops it.
+        let sp = regs.sp.wrapping_sub(2);
+        if sp < 0x4000 || sp == 0xFFFF {
+            return Err(format!(
+                "SNA stack pointer ${:04X} leaves no room to push PC into RAM",
+                regs.sp
+            ));
+        }
+        let offset = usize::from(sp - 0x4000);
+        ram[offset] = regs.pc as u8;
+        ram[offset + 1] = (regs.pc >> 8) as u8;
+        regs.sp = sp;
+    }
+
+    let mut data = Vec::with_capacity(SNA_128K_SIZE);
+    data.extend_from_slice(&[
+        regs.i, regs.l_alt, regs.h_alt, regs.e_alt, regs.d_alt, regs.c_alt, regs.b_alt, regs.f_alt,
+        regs.a_alt, regs.l, regs.h, regs.e, regs.d, regs.c, regs.b,
+    ]);
+    data.extend_from_slice(&regs.iy.to_le_bytes());
+    data.extend_from_slice(&regs.ix.to_le_bytes());
+    data.push(if regs.iff2 { 0x04 } else { 0 });
+    data.extend_from_slice(&[regs.r, regs.f, regs.a]);
+    data.extend_from_slice(&regs.sp.to_le_bytes());
+    data.extend_from_slice(&[regs.im, source.border() & 0x07]);
+    data.extend_from_slice(&ram);
+
+    if model.is_128k() {
+        data.extend_from_slice(&regs.pc.to_le_bytes());
+        data.extend_from_slice(&[source.bank_register(), 0]);
+        for bank in 0..8 {
+            if !slots.contains(&bank) {
+                data.extend_from_slice(source.ram_bank(bank));
+            }
+        }
+    }
+    Ok(data)
+}
+
 #[cfg(test)]
 #[allow(clippy::unwrap_used)]
 mod tests {
@@ -460,4 +612,113 @@ mod tests {
         assert!(result.is_err());
         assert!(result.unwrap_err().contains("not representable"));
     }
+
+    /// Snapshot source with each RAM bank filled with its own number.
+    struct TestSource {
+        model: SnapshotModel,
+        regs: Z80Registers,
+        banks: Vec<Vec<u8>>,
+        bank_reg: u8,
+    }
+
+    impl TestSource {
+        fn new(model: SnapshotModel) -> Self {
+            Self {
+                model,
+                regs: Z80Registers::default(),
+                banks: (0..8).map(|bank| vec![bank; 0x4000]).collect(),
+                bank_reg: 0,
+            }
+        }
+    }
+
+    impl SnapshotSource for TestSource {
+        fn model(&self) -> SnapshotModel {
+            self.model
+        }
+        fn registers(&self) -> Z80Registers {
+            self.regs.clone()
+        }
+        fn ram_bank(&self, bank: u8) -> &[u8] {
+            &self.banks[bank as usize]
+        }
+        fn border(&self) -> u8 {
+            5
+        }
+        fn bank_register(&self) -> u8 {
+            self.bank_reg
+        }
+    }
+
+    #[test]
+    fn save_sna_48k_round_trips_through_load() {
+        let mut source = TestSource::new(SnapshotModel::Spectrum48K);
+        source.regs.a = 0x12;
+        source.regs.h_alt = 0x34;
+        source.regs.ix = 0x5678;
+        source.regs.sp = 0x8000;
+        source.regs.pc = 0xBEEF;
+        source.regs.im = 2;
+        source.regs.iff1 = true;
+        source.regs.iff2 = true;
+
+        let sna = save_sna(&source).expect("save should succeed");
+        assert_eq!(sna.len(), SNA_48K_SIZE);
+
+        let mut target = TestTarget::new();
+        load_sna(&mut target, &sna).expect("load should succeed");
+        assert_eq!(target.regs.pc, 0xBEEF);
+        assert_eq!(target.regs.sp, 0x8000);
+        assert_eq!(target.regs.a, 0x12);
+        assert_eq!(target.regs.h_alt, 0x34);
+        assert_eq!(target.regs.ix, 0x5678);
+        assert_eq!(target.regs.im, 2);
+        assert!(target.regs.iff1);
+        assert_eq!(target.border, 5);
+        assert_eq!(target.ram[0x4000], 5);
+        assert_eq!(target.ram[0x8000], 2);
+        assert_eq!(target.ram[0xFFFF], 0);
+        // PC was pushed below SP.
+        assert_eq!(target.ram[0x7FFE], 0xEF);
+        assert_eq!(target.ram[0x7FFF], 0xBE);
+    }
+
+    #[test]
+    fn save_sna_48k_rejects_stack_in_rom() {
+        let mut source = TestSource::new(SnapshotModel::Spectrum48K);
+        source.regs.sp = 0x4001;
+        assert!(save_sna(&source).is_err());
+        source.regs.sp = 0x0000;
+        assert!(save_sna(&source).is_ok(), "SP=0 pushes to $FFFE");
+    }
+
+    #[test]
+    fn save_sna_128k_writes_banks_in_file_order() {
+        let mut source = TestSource::new(SnapshotModel::Spectrum128K);
+        source.regs.pc = 0x1234;
+        source.bank_reg = 0x13;
+
+        let sna = save_sna(&source).expect("save should succeed");
+        assert_eq!(sna.len(), SNA_128K_SIZE);
+        assert_eq!(sna[HEADER_SIZE], 5);
+        assert_eq!(sna[HEADER_SIZE + 0x4000], 2);
+        assert_eq!(sna[HEADER_SIZE + 0x8000], 3);
+        let ext = HEADER_SIZE + RAM_SIZE;
+        assert_eq!(&sna[ext..ext + 4], &[0x34, 0x12, 0x13, 0]);
+        let rest: Vec<u8> = (0..5).map(|i| sna[ext + 4 + i * 0x4000]).collect();
+        assert_eq!(rest, [0, 1, 4, 6, 7]);
+
+        let mut target = TestTarget::new();
+        load_sna(&mut target, &sna).expect("load should succeed");
+        assert_eq!(target.regs.pc, 0x1234);
+        assert_eq!(target.bank_history.last(), Some(&0x13));
+    }
+
+    #[test]
+    fn save_sna_128k_rejects_fixed_bank_at_c000() {
+        let mut source = TestSource::new(SnapshotModel::Spectrum128K);
+        source.bank_reg = 0x05;
+        let result = save_sna(&source);
+        assert!(result.unwrap_err().contains(".Z80"));
+    }
 }
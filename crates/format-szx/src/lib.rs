@@ -0,0 +1,786 @@
+//! SZX (zx-state) snapshot reader and writer for ZX Spectrum.
+//!
+//! Reference: <https://www.spectaculator.com/docs/zx-state/intro.shtml>
+//!
+//! An SZX file is an 8-byte header (`ZXST`, version, machine ID, flags)
+//! followed by chunks. Each chunk is a four-character ID, a 32-bit
+//! little-endian length and a body. The emulator models four of them:
+//!
+//! - `Z80R`: CPU registers and the T-state within the frame
+//! - `SPCR`: border, ports $7FFD and $1FFD, last write to $FE
+//! - `AY\0\0`: AY registers and the selected register
+//! - `RAMP`: one 16K RAM page, optionally zlib-compressed
+//!
+//! Everything else (the +3 FDC, Kempston, joysticks, tape position and
+//! so on) is kept byte for byte. [`SzxSnapshot::update`] refreshes the
+//! modelled chunks from a machine and leaves the rest alone, so those
+//! chunks survive a load and save.
+
+#![allow(clippy::cast_possible_truncation)]
+
+use std::io::{Read, Write};
+
+use flate2::Compression;
+use flate2::read::ZlibDecoder;
+use flate2::write::ZlibEncoder;
+
+pub use format_sna::{SnapshotModel, SnapshotSource, SnapshotTarget, Z80Registers};
+
+/// File signature.
+const MAGIC: &[u8; 4] = b"ZXST";
+
+/// Header size in bytes.
+const HEADER_SIZE: usize = 8;
+
+/// Chunk header size: ID and length.
+const CHUNK_HEADER_SIZE: usize = 8;
+
+/// Version written by [`save_szx`].
+const MAJOR_VERSION: u8 = 1;
+const MINOR_VERSION: u8 = 4;
+
+/// Chunk IDs the emulator models.
+pub const ID_Z80R: [u8; 4] = *b"Z80R";
+pub const ID_SPCR: [u8; 4] = *b"SPCR";
+pub const ID_AY: [u8; 4] = *b"AY\0\0";
+pub const ID_RAMP: [u8; 4] = *b"RAMP";
+
+/// Chunks that [`SzxSnapshot::update`] replaces.
+const MODELLED: [[u8; 4]; 4] = [ID_Z80R, ID_SPCR, ID_AY, ID_RAMP];
+
+/// `Z80R` body size; versions before 1.4 leave out MEMPTR.
+const Z80R_LEN: usize = 37;
+const Z80R_MIN_LEN: usize = 35;
+
+/// `SPCR` body size.
+const SPCR_LEN: usize = 8;
+
+/// `AY` body size.
+const AY_LEN: usize = 18;
+
+/// RAM page size.
+const PAGE_SIZE: usize = 0x4000;
+
+/// `Z80R` flag: the CPU is in a HALT instruction.
+const Z80R_HALTED: u8 = 0x02;
+
+/// `AY` flag: the AY is the 128K one (rather than a Fuller Box).
+const AY_128: u8 = 0x02;
+
+/// `RAMP` flag: the page is zlib-compressed.
+const RAMP_COMPRESSED: u16 = 0x0001;
+
+/// Machine names by SZX machine ID, for error messages.
+const MACHINE_NAMES: [&str; 17] = [
+    "16K",
+    "48K",
+    "128K",
+    "+2",
+    "+2A",
+    "+3",
+    "+3e",
+    "Pentagon 128",
+    "TC2048",
+    "TC2068",
+    "Scorpion",
+    "SE",
+    "TS2068",
+    "Pentagon 512",
+    "Pentagon 1024",
+    "NTSC 48K",
+    "128Ke",
+];
+
+/// One chunk: its four-character ID and body.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct SzxChunk {
+    pub id: [u8; 4],
+    pub data: Vec<u8>,
+}
+
+impl SzxChunk {
+    /// The ID as text, without trailing NULs (`"AY"` for `AY\0\0`).
+    #[must_use]
+    pub fn name(&self) -> String {
+        String::from_utf8_lossy(&self.id)
+            .trim_end_matches('\0')
+            .to_string()
+    }
+}
+
+/// A parsed SZX snapshot.
+///
+/// Chunks are kept as read, so [`to_bytes`](Self::to_bytes) gives back
+/// the original file; [`apply`](Self::apply) decodes the modelled ones.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct SzxSnapshot {
+    pub major_version: u8,
+    pub minor_version: u8,
+    /// SZX machine ID (1 = 48K, 2 = 128K, 3 = +2, 4 = +2A, 5 = +3, ...).
+    pub machine_id: u8,
+    /// Header flags; bit 0 selects the alternate (late) ULA timings.
+    pub flags: u8,
+    /// Chunks in file order.
+    pub chunks: Vec<SzxChunk>,
+}
+
+impl SzxSnapshot {
+    /// Parse an SZX snapshot from raw bytes.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the signature is wrong or a chunk runs past
+    /// the end of the data.
+    pub fn parse(data: &[u8]) -> Result<Self, String> {
+        if data.len() < HEADER_SIZE || &data[..4] != MAGIC {
+            return Err("Not an SZX snapshot (missing ZXST signature)".to_string());
+        }
+
+        let mut chunks = Vec::new();
+        let mut pos = HEADER_SIZE;
+        while pos < data.len() {
+            if data.len() - pos < CHUNK_HEADER_SIZE {
+                return Err(format!("SZX chunk header truncated at offset {pos}"));
+            }
+            let id: [u8; 4] = [data[pos], data[pos + 1], data[pos + 2], data[pos + 3]];
+            let len =
+                u32::from_le_bytes([data[pos + 4], data[pos + 5], data[pos + 6], data[pos + 7]])
+                    as usize;
+            let start = pos + CHUNK_HEADER_SIZE;
+            if data.len() - start < len {
+                return Err(format!(
+                    "SZX chunk {} at offset {pos} needs {len} bytes, {} left",
+                    String::from_utf8_lossy(&id),
+                    data.len() - start
+                ));
+            }
+            chunks.push(SzxChunk {
+                id,
+                data: data[start..start + len].to_vec(),
+            });
+            pos = start + len;
+        }
+
+        Ok(Self {
+            major_version: data[4],
+            minor_version: data[5],
+            machine_id: data[6],
+            flags: data[7],
+            chunks,
+        })
+    }
+
+    /// Serialize the snapshot, chunks in order.
+    #[must_use]
+    pub fn to_bytes(&self) -> Vec<u8> {
+        let size = HEADER_SIZE
+            + self
+                .chunks
+                .iter()
+                .map(|chunk| CHUNK_HEADER_SIZE + chunk.data.len())
+                .sum::<usize>();
+        let mut out = Vec::with_capacity(size);
+        out.extend_from_slice(MAGIC);
+        out.extend_from_slice(&[
+            self.major_version,
+            self.minor_version,
+            self.machine_id,
+            self.flags,
+        ]);
+        for chunk in &self.chunks {
+            out.extend_from_slice(&chunk.id);
+            out.extend_from_slice(&(chunk.data.len() as u32).to_le_bytes());
+            out.extend_from_slice(&chunk.data);
+        }
+        out
+    }
+
+    /// Build a snapshot of `source` holding only the modelled chunks.
+    #[must_use]
+    pub fn capture(source: &impl SnapshotSource) -> Self {
+        let model = source.model();
+        let mut chunks = vec![
+            z80r_chunk(&source.registers(), source.frame_tstate(), model),
+            spcr_chunk(source),
+        ];
+        if let Some((regs, selected)) = source.ay_registers() {
+            let mut data = Vec::with_capacity(AY_LEN);
+            data.push(if model.is_128k() { AY_128 } else { 0 });
+            data.push(selected);
+            data.extend_from_slice(&regs);
+            chunks.push(SzxChunk { id: ID_AY, data });
+        }
+        for &page in pages(model) {
+            chunks.push(ramp_chunk(page, source.ram_bank(page)));
+        }
+
+        Self {
+            major_version: MAJOR_VERSION,
+            minor_version: MINOR_VERSION,
+            machine_id: machine_id(model),
+            flags: 0,
+            chunks,
+        }
+    }
+
+    /// Replace the modelled chunks with the state of `source`, keeping
+    /// every other chunk and its order. The fresh chunks go first.
+    pub fn update(&mut self, source: &impl SnapshotSource) {
+        let fresh = Self::capture(source);
+        self.major_version = fresh.major_version;
+        self.minor_version = fresh.minor_version;
+        self.machine_id = fresh.machine_id;
+        self.chunks.retain(|chunk| !MODELLED.contains(&chunk.id));
+        self.chunks.splice(0..0, fresh.chunks);
+    }
+
+    /// The first chunk with the given ID.
+    #[must_use]
+    pub fn chunk(&self, id: [u8; 4]) -> Option<&SzxChunk> {
+        self.chunks.iter().find(|chunk| chunk.id == id)
+    }
+
+    /// Chunks the emulator does not model.
+    pub fn unmodelled_chunks(&self) -> impl Iterator<Item = &SzxChunk> {
+        self.chunks
+            .iter()
+            .filter(|chunk| !MODELLED.contains(&chunk.id))
+    }
+
+    /// The model the snapshot was saved from.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error for machines the emulator does not build.
+    pub fn model(&self) -> Result<SnapshotModel, String> {
+        match self.machine_id {
+            1 => Ok(SnapshotModel::Spectrum48K),
+            2 => Ok(SnapshotModel::Spectrum128K),
+            3 => Ok(SnapshotModel::SpectrumPlus2),
+            4 => Ok(SnapshotModel::SpectrumPlus2A),
+            5 => Ok(SnapshotModel::SpectrumPlus3),
+            id => Err(format!(
+                "SZX machine {} is not supported",
+                MACHINE_NAMES
+                    .get(id as usize)
+                    .map_or_else(|| format!("ID {id}"), ToString::to_string)
+            )),
+        }
+    }
+
+    /// Apply the modelled chunks to a target machine.
+    ///
+    /// RAM pages load with normal paging, then the paging registers are
+    /// restored. Unmodelled chunks are ignored.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the machine is unsupported, the `Z80R` chunk
+    /// is missing, or a chunk is malformed.
+    pub fn apply(&self, target: &mut impl SnapshotTarget) -> Result<(), String> {
+        let model = self.model()?;
+        let z80r = self
+            .chunk(ID_Z80R)
+            .ok_or_else(|| "SZX snapshot has no Z80R chunk".to_string())?;
+        let (regs, tstate) = decode_z80r(&z80r.data)?;
+        let spcr = match self.chunk(ID_SPCR) {
+            Some(chunk) if chunk.data.len() < SPCR_LEN => {
+                return Err(format!(
+                    "SZX SPCR chunk is {} bytes, expected {SPCR_LEN}",
+                    chunk.data.len()
+                ));
+            }
+            Some(chunk) => [chunk.data[0], chunk.data[1], chunk.data[2]],
+            None => [7, 0, 0],
+        };
+        let [border, port_7ffd, port_1ffd] = spcr;
+
+        target.set_registers(&regs);
+
+        if model.has_plus3_register() {
+            target.write_plus3_register(0);
+        }
+        for chunk in self.chunks.iter().filter(|chunk| chunk.id == ID_RAMP) {
+            let (page, ram) = decode_ramp(&chunk.data)?;
+            load_page(target, model, port_7ffd, page, &ram)?;
+        }
+        if model.is_128k() {
+            target.write_bank_register(port_7ffd);
+        }
+        if model.has_plus3_register() {
+            target.write_plus3_register(port_1ffd);
+        }
+        target.set_border(border & 0x07);
+
+        if let Some(chunk) = self.chunk(ID_AY) {
+            if chunk.data.len() < AY_LEN {
+                return Err(format!(
+                    "SZX AY chunk is {} bytes, expected {AY_LEN}",
+                    chunk.data.len()
+                ));
+            }
+            for (reg, &val) in chunk.data[2..AY_LEN].iter().enumerate() {
+                target.set_ay_register(reg as u8, val);
+            }
+            target.select_ay_register(chunk.data[1] & 0x0F);
+        }
+
+        target.set_frame_tstate(tstate);
+        Ok(())
+    }
+}
+
+/// Parse an SZX snapshot and apply it to `target`.
+///
+/// # Errors
+///
+/// Returns an error if the file is malformed or the machine unsupported.
+pub fn load_szx(target: &mut impl SnapshotTarget, data: &[u8]) -> Result<(), String> {
+    SzxSnapshot::parse(data)?.apply(target)
+}
+
+/// Save `source` as an SZX file with compressed RAM pages.
+#[must_use]
+pub fn save_szx(source: &impl SnapshotSource) -> Vec<u8> {
+    SzxSnapshot::capture(source).to_bytes()
+}
+
+/// SZX machine ID for a model.
+fn machine_id(model: SnapshotModel) -> u8 {
+    match model {
+        SnapshotModel::Spectrum48K => 1,
+        SnapshotModel::Spectrum128K => 2,
+        SnapshotModel::SpectrumPlus2 => 3,
+        SnapshotModel::SpectrumPlus2A => 4,
+        SnapshotModel::SpectrumPlus3 => 5,
+    }
+}
+
+/// RAM pages saved for a model.
+fn pages(model: SnapshotModel) -> &'static [u8] {
+    if model.is_128k() {
+        &[0, 1, 2, 3, 4, 5, 6, 7]
+    } else {
+        &[5, 2, 0]
+    }
+}
+
+fn z80r_chunk(regs: &Z80Registers, tstate: u32, model: SnapshotModel) -> SzxChunk {
+    let mut data = Vec::with_capacity(Z80R_LEN);
+    for (hi, lo) in [
+        (regs.a, regs.f),
+        (regs.b, regs.c),
+        (regs.d, regs.e),
+        (regs.h, regs.l),
+        (regs.a_alt, regs.f_alt),
+        (regs.b_alt, regs.c_alt),
+        (regs.d_alt, regs.e_alt),
+        (regs.h_alt, regs.l_alt),
+    ] {
+        data.extend_from_slice(&[lo, hi]);
+    }
+    for word in [regs.ix, regs.iy, regs.sp, regs.pc] {
+        data.extend_from_slice(&word.to_le_bytes());
+    }
+    data.extend_from_slice(&[
+        regs.i,
+        regs.r,
+        u8::from(regs.iff1),
+        u8::from(regs.iff2),
+        regs.im,
+    ]);
+    data.extend_from_slice(&(tstate % model.frame_tstates()).to_le_bytes());
+    // Length of the interrupt signal in T-states.
+    data.push(if model.is_128k() { 36 } else { 32 });
+    data.push(if regs.halted { Z80R_HALTED } else { 0 });
+    // MEMPTR is not part of the register set snapshots exchange.
+    data.extend_from_slice(&[0, 0]);
+    SzxChunk { id: ID_Z80R, data }
+}
+
+/// Decode a `Z80R` body into registers and the frame T-state.
+fn decode_z80r(data: &[u8]) -> Result<(Z80Registers, u32), String> {
+    if data.len() < Z80R_MIN_LEN {
+        return Err(format!(
+            "SZX Z80R chunk is {} bytes, expected {Z80R_LEN}",
+            data.len()
+        ));
+    }
+    let word = |offset: usize| u16::from_le_bytes([data[offset], data[offset + 1]]);
+    let regs = Z80Registers {
+        f: data[0],
+        a: data[1],
+        c: data[2],
+        b: data[3],
+        e: data[4],
+        d: data[5],
+        l: data[6],
+        h: data[7],
+        f_alt: data[8],
+        a_alt: data[9],
+        c_alt: data[10],
+        b_alt: data[11],
+        e_alt: data[12],
+        d_alt: data[13],
+        l_alt: data[14],
+        h_alt: data[15],
+        ix: word(16),
+        iy: word(18),
+        sp: word(20),
+        pc: word(22),
+        i: data[24],
+        r: data[25],
+        iff1: data[26] != 0,
+        iff2: data[27] != 0,
+        im: data[28] & 0x03,
+        halted: data[34] & Z80R_HALTED != 0,
+    };
+    let tstate = u32::from_le_bytes([data[29], data[30], data[31], data[32]]);
+    Ok((regs, tstate))
+}
+
+fn spcr_chunk(source: &impl SnapshotSource) -> SzxChunk {
+    let border = source.border() & 0x07;
+    let mut data = vec![0; SPCR_LEN];
+    data[0] = border;
+    data[1] = source.bank_register();
+    data[2] = source.plus3_register();
+    data[3] = border;
+    SzxChunk { id: ID_SPCR, data }
+}
+
+/// Build a `RAMP` chunk, compressed unless that makes it larger.
+fn ramp_chunk(page: u8, ram: &[u8]) -> SzxChunk {
+    let mut encoder = ZlibEncoder::new(Vec::new(), Compression::default());
+    let packed = encoder
+        .write_all(ram)
+        .and_then(|()| encoder.finish())
+        .ok()
+        .filter(|packed| packed.len() < ram.len());
+
+    let (flags, body) = match &packed {
+        Some(packed) => (RAMP_COMPRESSED, packed.as_slice()),
+        None => (0, ram),
+    };
+    let mut data = Vec::with_capacity(3 + body.len());
+    data.extend_from_slice(&flags.to_le_bytes());
+    data.push(page);
+    data.extend_from_slice(body);
+    SzxChunk { id: ID_RAMP, data }
+}
+
+/// Decode a `RAMP` body into its page number and 16K of RAM.
+fn decode_ramp(data: &[u8]) -> Result<(u8, Vec<u8>), String> {
+    if data.len() < 3 {
+        return Err(format!("SZX RAMP chunk is only {} bytes", data.len()));
+    }
+    let flags = u16::from_le_bytes([data[0], data[1]]);
+    let page = data[2];
+    let body = &data[3..];
+    let ram = if flags & RAMP_COMPRESSED != 0 {
+        let mut ram = Vec::with_capacity(PAGE_SIZE);
+        ZlibDecoder::new(body)
+            .read_to_end(&mut ram)
+            .map_err(|e| format!("SZX RAM page {page} decompression failed: {e}"))?;
+        ram
+    } else {
+        body.to_vec()
+    };
+    if ram.len() != PAGE_SIZE {
+        return Err(format!(
+            "SZX RAM page {page} is {} bytes, expected {PAGE_SIZE}",
+            ram.len()
+        ));
+    }
+    Ok((page, ram))
+}
+
+/// Write one RAM page through the target's memory map.
+fn load_page(
+    target: &mut impl SnapshotTarget,
+    model: SnapshotModel,
+    port_7ffd: u8,
+    page: u8,
+    ram: &[u8],
+) -> Result<(), String> {
+    if model.is_128k() {
+        if page > 7 {
+            return Err(format!("SZX RAM page {page} does not exist on 128K"));
+        }
+        target.write_bank_register((port_7ffd & 0xF8) | page);
+        for (i, &byte) in ram.iter().enumerate() {
+            target.write_ram(0xC000u16 + i as u16, byte);
+        }
+        return Ok(());
+    }
+
+    let base: u16 = match page {
+        5 => 0x4000,
+        2 => 0x8000,
+        0 => 0xC000,
+        _ => return Err(format!("SZX RAM page {page} does not exist on 48K")),
+    };
+    for (i, &byte) in ram.iter().enumerate() {
+        target.write_ram(base + i as u16, byte);
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Snapshot target with 128K banking at $C000.
+    struct TestTarget {
+        ram: [u8; 65536],
+        banks: Vec<Vec<u8>>,
+        border: u8,
+        regs: Z80Registers,
+        bank_reg: u8,
+        ay_regs: [u8; 16],
+        ay_selected: u8,
+        plus3_reg: Option<u8>,
+        tstate: Option<u32>,
+    }
+
+    impl TestTarget {
+        fn new() -> Self {
+            Self {
+                ram: [0; 65536],
+                banks: vec![vec![0; PAGE_SIZE]; 8],
+                border: 0,
+                regs: Z80Registers::default(),
+                bank_reg: 0,
+                ay_regs: [0; 16],
+                ay_selected: 0,
+                plus3_reg: None,
+                tstate: None,
+            }
+        }
+    }
+
+    impl SnapshotTarget for TestTarget {
+        fn set_registers(&mut self, regs: &Z80Registers) {
+            self.regs = regs.clone();
+        }
+        fn write_ram(&mut self, addr: u16, val: u8) {
+            self.ram[addr as usize] = val;
+            if addr >= 0xC000 {
+                self.banks[(self.bank_reg & 0x07) as usize][addr as usize - 0xC000] = val;
+            }
+        }
+        fn read_ram(&self, addr: u16) -> u8 {
+            self.ram[addr as usize]
+        }
+        fn set_border(&mut self, colour: u8) {
+            self.border = colour;
+        }
+        fn write_bank_register(&mut self, val: u8) {
+            self.bank_reg = val;
+        }
+        fn set_ay_register(&mut self, reg: u8, val: u8) {
+            self.ay_regs[reg as usize] = val;
+        }
+        fn select_ay_register(&mut self, reg: u8) {
+            self.ay_selected = reg;
+        }
+        fn write_plus3_register(&mut self, val: u8) {
+            self.plus3_reg = Some(val);
+        }
+        fn set_frame_tstate(&mut self, tstate: u32) {
+            self.tstate = Some(tstate);
+        }
+    }
+
+    /// Snapshot source with each RAM bank filled with a pattern of its
+    /// own number.
+    struct TestSource {
+        model: SnapshotModel,
+        regs: Z80Registers,
+        banks: Vec<Vec<u8>>,
+    }
+
+    impl TestSource {
+        fn new(model: SnapshotModel) -> Self {
+            Self {
+                model,
+                regs: Z80Registers {
+                    a: 0x12,
+                    f: 0x34,
+                    h: 0x56,
+                    l: 0x78,
+                    f_alt: 0x9A,
+                    ix: 0xBCDE,
+                    sp: 0xFF00,
+                    pc: 0x8000,
+                    r: 0x85,
+                    im: 2,
+                    iff1: true,
+                    iff2: true,
+                    halted: true,
+                    ..Z80Registers::default()
+                },
+                banks: (0..8)
+                    .map(|bank| (0..PAGE_SIZE).map(|i| (i % 7) as u8 * bank).collect())
+                    .collect(),
+            }
+        }
+    }
+
+    impl SnapshotSource for TestSource {
+        fn model(&self) -> SnapshotModel {
+            self.model
+        }
+        fn registers(&self) -> Z80Registers {
+            self.regs.clone()
+        }
+        fn ram_bank(&self, bank: u8) -> &[u8] {
+            &self.banks[bank as usize]
+        }
+        fn border(&self) -> u8 {
+            3
+        }
+        fn bank_register(&self) -> u8 {
+            0x1B
+        }
+        fn plus3_register(&self) -> u8 {
+            0x04
+        }
+        fn ay_registers(&self) -> Option<([u8; 16], u8)> {
+            self.model
+                .is_128k()
+                .then(|| (std::array::from_fn(|reg| reg as u8 + 0x40), 14))
+        }
+        fn frame_tstate(&self) -> u32 {
+            12_345
+        }
+    }
+
+    #[test]
+    fn plus3_round_trip() {
+        let source = TestSource::new(SnapshotModel::SpectrumPlus3);
+        let data = save_szx(&source);
+        assert_eq!(&data[..8], b"ZXST\x01\x04\x05\x00");
+
+        let mut target = TestTarget::new();
+        load_szx(&mut target, &data).expect("load");
+        assert_eq!(target.regs.a, 0x12);
+        assert_eq!(target.regs.f, 0x34);
+        assert_eq!(target.regs.f_alt, 0x9A);
+        assert_eq!(target.regs.ix, 0xBCDE);
+        assert_eq!(target.regs.pc, 0x8000);
+        assert_eq!(target.regs.r, 0x85);
+        assert_eq!(target.regs.im, 2);
+        assert!(target.regs.iff1 && target.regs.halted);
+        assert_eq!(target.bank_reg, 0x1B);
+        assert_eq!(target.plus3_reg, Some(0x04));
+        assert_eq!(target.border, 3);
+        assert_eq!(target.ay_regs[15], 0x4F);
+        assert_eq!(target.ay_selected, 14);
+        assert_eq!(target.tstate, Some(12_345));
+        assert_eq!(target.banks, source.banks);
+    }
+
+    #[test]
+    fn spectrum_48k_round_trip() {
+        let source = TestSource::new(SnapshotModel::Spectrum48K);
+        let snapshot = SzxSnapshot::capture(&source);
+        assert!(snapshot.chunk(ID_AY).is_none());
+        let pages: Vec<u8> = snapshot
+            .chunks
+            .iter()
+            .filter(|chunk| chunk.id == ID_RAMP)
+            .map(|chunk| chunk.data[2])
+            .collect();
+        assert_eq!(pages, [5, 2, 0]);
+
+        let mut target = TestTarget::new();
+        snapshot.apply(&mut target).expect("load");
+        assert_eq!(&target.ram[0x4000..0x8000], source.banks[5].as_slice());
+        assert_eq!(&target.ram[0x8000..0xC000], source.banks[2].as_slice());
+        assert_eq!(&target.ram[0xC000..], source.banks[0].as_slice());
+        assert_eq!(target.plus3_reg, None);
+    }
+
+    #[test]
+    fn unknown_chunks_survive_parse_and_update() {
+        let source = TestSource::new(SnapshotModel::Spectrum128K);
+        let mut snapshot = SzxSnapshot::capture(&source);
+        let tape = SzxChunk {
+            id: *b"TAPE",
+            data: vec![1, 2, 3, 4, 5],
+        };
+        let kempston = SzxChunk {
+            id: *b"JOY\0",
+            data: vec![0xAA; 6],
+        };
+        snapshot.chunks.insert(1, tape.clone());
+        snapshot.chunks.push(kempston.clone());
+
+        let bytes = snapshot.to_bytes();
+        let parsed = SzxSnapshot::parse(&bytes).expect("parse");
+        assert_eq!(parsed, snapshot);
+        assert_eq!(parsed.to_bytes(), bytes);
+
+        let mut updated = parsed;
+        let mut plus3 = TestSource::new(SnapshotModel::SpectrumPlus3);
+        plus3.regs.pc = 0x1234;
+        updated.update(&plus3);
+        assert_eq!(updated.machine_id, 5);
+        let unmodelled: Vec<&SzxChunk> = updated.unmodelled_chunks().collect();
+        assert_eq!(unmodelled, [&tape, &kempston]);
+        assert_eq!(updated.chunks.iter().filter(|c| c.id == ID_Z80R).count(), 1);
+
+        let mut target = TestTarget::new();
+        updated.apply(&mut target).expect("load");
+        assert_eq!(target.regs.pc, 0x1234);
+    }
+
+    #[test]
+    fn uncompressed_pages_load() {
+        let mut ramp = vec![0, 0, 5];
+        ramp.extend(std::iter::repeat_n(0x5A, PAGE_SIZE));
+        let snapshot = SzxSnapshot {
+            major_version: 1,
+            minor_version: 3,
+            machine_id: 1,
+            flags: 0,
+            chunks: vec![
+                SzxChunk {
+                    id: ID_Z80R,
+                    // A v1.3 Z80R, without MEMPTR.
+                    data: vec![0; Z80R_MIN_LEN],
+                },
+                SzxChunk {
+                    id: ID_RAMP,
+                    data: ramp,
+                },
+            ],
+        };
+        let mut target = TestTarget::new();
+        snapshot.apply(&mut target).expect("load");
+        assert!(target.ram[0x4000..0x8000].iter().all(|&b| b == 0x5A));
+        assert_eq!(target.border, 7);
+    }
+
+    #[test]
+    fn rejects_bad_files() {
+        assert!(SzxSnapshot::parse(b"ZXSX\x01\x04\x01\x00").is_err());
+
+        let mut truncated = b"ZXST\x01\x04\x01\x00Z80R".to_vec();
+        truncated.extend_from_slice(&100u32.to_le_bytes());
+        truncated.extend_from_slice(&[0; 10]);
+        assert!(SzxSnapshot::parse(&truncated).is_err());
+
+        let mut pentagon = SzxSnapshot::capture(&TestSource::new(SnapshotModel::Spectrum128K));
+        pentagon.machine_id = 7;
+        let err = pentagon
+            .apply(&mut TestTarget::new())
+            .expect_err("unsupported machine");
+        assert!(err.contains("Pentagon 128"), "{err}");
+
+        let mut no_cpu = SzxSnapshot::capture(&TestSource::new(SnapshotModel::Spectrum48K));
+        no_cpu.chunks.retain(|chunk| chunk.id != ID_Z80R);
+        assert!(no_cpu.apply(&mut TestTarget::new()).is_err());
+    }
+}
@@ -1,4 +1,4 @@
-//! TZX file format parser.
+//! TZX file format parser and writer.
 //!
 //! TZX encodes tape signals as T-state-accurate pulse sequences. Unlike TAP
 //! (which relies on a ROM trap for instant loading), TZX drives the EAR bit
@@ -15,6 +15,8 @@
 
 #![allow(clippy::cast_possible_truncation)]
 
+use format_csw::CswCompression;
+
 /// A parsed TZX file.
 #[derive(Debug, Clone)]
 pub struct TzxFile {
@@ -24,7 +26,7 @@ pub struct TzxFile {
 }
 
 /// A single TZX block.
-#[derive(Debug, Clone)]
+#[derive(Debug, Clone, PartialEq, Eq)]
 pub enum TzxBlock {
     /// Block $10: Standard speed data (ROM timing).
     StandardSpeed { pause_ms: u16, data: Vec<u8> },
@@ -52,16 +54,47 @@ pub enum TzxBlock {
         pause_ms: u16,
         data: Vec<u8>,
     },
+    /// Block $15: Direct recording (one EAR level per sample, MSB first).
+    DirectRecording {
+        tstates_per_sample: u16,
+        pause_ms: u16,
+        used_bits: u8,
+        data: Vec<u8>,
+    },
+    /// Block $18: CSW recording (pulse lengths in samples).
+    CswRecording {
+        pause_ms: u16,
+        sample_rate: u32,
+        pulses: Vec<u32>,
+    },
+    /// Block $19: Generalized data (pilot and data built from symbol tables).
+    GeneralizedData {
+        pause_ms: u16,
+        pilot_symbols: Vec<GdbSymbol>,
+        /// (symbol, repetitions) pairs.
+        pilot_stream: Vec<(u8, u16)>,
+        data_symbols: Vec<GdbSymbol>,
+        /// One symbol index per entry, unpacked from the bit stream.
+        data_stream: Vec<u8>,
+    },
     /// Block $20: Pause / stop the tape.
     Pause { duration_ms: u16 },
     /// Block $21: Group start.
     GroupStart { name: String },
     /// Block $22: Group end.
     GroupEnd,
+    /// Block $23: Jump to a block, relative to this one.
+    JumpTo { offset: i16 },
     /// Block $24: Loop start.
     LoopStart { repetitions: u16 },
     /// Block $25: Loop end.
     LoopEnd,
+    /// Block $26: Call sequence (block offsets relative to this one).
+    CallSequence { offsets: Vec<i16> },
+    /// Block $27: Return from sequence.
+    ReturnFromSequence,
+    /// Block $28: Select block — (relative offset, description) per entry.
+    Select { entries: Vec<(i16, String)> },
     /// Block $2A: Stop the tape if in 48K mode.
     StopIf48K,
     /// Block $2B: Set signal level.
@@ -74,6 +107,16 @@ pub enum TzxBlock {
     Unknown { block_id: u8 },
 }
 
+/// A symbol in a generalized data block.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct GdbSymbol {
+    /// How the first pulse starts: 0 = opposite to the current level (an
+    /// edge), 1 = the current level, 2 = low, 3 = high.
+    pub polarity: u8,
+    /// Pulse lengths in T-states, up to the first zero.
+    pub pulses: Vec<u16>,
+}
+
 /// TZX header magic: "`ZXTape`!" + 0x1A.
 const MAGIC: &[u8; 8] = b"ZXTape!\x1A";
 
@@ -107,11 +150,18 @@ impl TzxFile {
                 0x12 => parse_pure_tone(data, &mut pos)?,
                 0x13 => parse_pulse_sequence(data, &mut pos)?,
                 0x14 => parse_pure_data(data, &mut pos)?,
+                0x15 => parse_direct_recording(data, &mut pos)?,
+                0x18 => parse_csw_recording(data, &mut pos)?,
+                0x19 => parse_generalized_data(data, &mut pos)?,
                 0x20 => parse_pause(data, &mut pos)?,
                 0x21 => parse_group_start(data, &mut pos)?,
                 0x22 => TzxBlock::GroupEnd,
+                0x23 => parse_jump_to(data, &mut pos)?,
                 0x24 => parse_loop_start(data, &mut pos)?,
                 0x25 => TzxBlock::LoopEnd,
+                0x26 => parse_call_sequence(data, &mut pos)?,
+                0x27 => TzxBlock::ReturnFromSequence,
+                0x28 => parse_select(data, &mut pos)?,
                 0x2A => parse_stop_if_48k(data, &mut pos)?,
                 0x2B => parse_set_signal_level(data, &mut pos)?,
                 0x30 => parse_text_description(data, &mut pos)?,
@@ -128,6 +178,22 @@ impl TzxFile {
             blocks,
         })
     }
+
+    /// Serialise to TZX bytes.
+    ///
+    /// Unknown blocks kept no body when parsed, so they are left out; any
+    /// jump or call offsets across them will be off by one per block.
+    /// Lengths wider than a block's length field are truncated.
+    #[must_use]
+    pub fn to_bytes(&self) -> Vec<u8> {
+        let mut out = MAGIC.to_vec();
+        out.push(self.major);
+        out.push(self.minor);
+        for block in &self.blocks {
+            write_block(block, &mut out);
+        }
+        out
+    }
 }
 
 // ---------------------------------------------------------------------------
@@ -149,10 +215,33 @@ fn read_u16_le(data: &[u8], pos: usize) -> u16 {
     u16::from(data[pos]) | (u16::from(data[pos + 1]) << 8)
 }
 
+fn read_i16_le(data: &[u8], pos: usize) -> i16 {
+    read_u16_le(data, pos) as i16
+}
+
 fn read_u24_le(data: &[u8], pos: usize) -> u32 {
     u32::from(data[pos]) | (u32::from(data[pos + 1]) << 8) | (u32::from(data[pos + 2]) << 16)
 }
 
+fn put_u16(out: &mut Vec<u8>, value: u16) {
+    out.extend_from_slice(&value.to_le_bytes());
+}
+
+fn put_u24(out: &mut Vec<u8>, value: usize) {
+    out.extend_from_slice(&(value as u32).to_le_bytes()[..3]);
+}
+
+fn put_u32(out: &mut Vec<u8>, value: usize) {
+    out.extend_from_slice(&(value as u32).to_le_bytes());
+}
+
+/// A string with a one-byte length prefix, cut to 255 bytes.
+fn put_text(out: &mut Vec<u8>, text: &str) {
+    let bytes = &text.as_bytes()[..text.len().min(0xFF)];
+    out.push(bytes.len() as u8);
+    out.extend_from_slice(bytes);
+}
+
 fn read_u32_le(data: &[u8], pos: usize) -> u32 {
     u32::from(data[pos])
         | (u32::from(data[pos + 1]) << 8)
@@ -260,6 +349,165 @@ fn parse_pure_data(data: &[u8], pos: &mut usize) -> Result<TzxBlock, String> {
     })
 }
 
+/// Block $15: Direct recording.
+fn parse_direct_recording(data: &[u8], pos: &mut usize) -> Result<TzxBlock, String> {
+    need(data, *pos, 8, "Direct Recording header")?;
+    let tstates_per_sample = read_u16_le(data, *pos);
+    let pause_ms = read_u16_le(data, *pos + 2);
+    let used_bits = data[*pos + 4];
+    let data_len = read_u24_le(data, *pos + 5) as usize;
+    *pos += 8;
+
+    need(data, *pos, data_len, "Direct Recording data")?;
+    let block_data = data[*pos..*pos + data_len].to_vec();
+    *pos += data_len;
+
+    Ok(TzxBlock::DirectRecording {
+        tstates_per_sample,
+        pause_ms,
+        used_bits,
+        data: block_data,
+    })
+}
+
+/// Block $18: CSW recording.
+fn parse_csw_recording(data: &[u8], pos: &mut usize) -> Result<TzxBlock, String> {
+    need(data, *pos, 4, "CSW Recording length")?;
+    let block_len = read_u32_le(data, *pos) as usize;
+    *pos += 4;
+
+    need(data, *pos, block_len, "CSW Recording data")?;
+    let block_end = *pos + block_len;
+    if block_len < 10 {
+        return Err("CSW Recording block too short".to_string());
+    }
+
+    let pause_ms = read_u16_le(data, *pos);
+    let sample_rate = read_u24_le(data, *pos + 2);
+    let compression = CswCompression::from_byte(data[*pos + 5])?;
+    // Bytes 6-9 hold the decompressed pulse count, which the data implies.
+    let pulses = format_csw::decode(&data[*pos + 10..block_end], compression)?;
+    *pos = block_end;
+
+    if sample_rate == 0 {
+        return Err("CSW Recording sample rate is zero".to_string());
+    }
+
+    Ok(TzxBlock::CswRecording {
+        pause_ms,
+        sample_rate,
+        pulses,
+    })
+}
+
+/// Block $19: Generalized data.
+fn parse_generalized_data(data: &[u8], pos: &mut usize) -> Result<TzxBlock, String> {
+    need(data, *pos, 4, "Generalized Data length")?;
+    let block_len = read_u32_le(data, *pos) as usize;
+    *pos += 4;
+
+    need(data, *pos, block_len, "Generalized Data block")?;
+    let block_end = *pos + block_len;
+    // Bound every read within the block by its declared length.
+    let block = &data[..block_end];
+    need(block, *pos, 14, "Generalized Data header")?;
+
+    let pause_ms = read_u16_le(block, *pos);
+    let pilot_total = read_u32_le(block, *pos + 2) as usize;
+    let pilot_pulses = block[*pos + 6] as usize;
+    let pilot_alphabet = gdb_alphabet_size(block[*pos + 7]);
+    let data_total = read_u32_le(block, *pos + 8) as usize;
+    let data_pulses = block[*pos + 12] as usize;
+    let data_alphabet = gdb_alphabet_size(block[*pos + 13]);
+    let mut p = *pos + 14;
+
+    let mut pilot_symbols = Vec::new();
+    let mut pilot_stream = Vec::new();
+    if pilot_total > 0 {
+        pilot_symbols = parse_gdb_symbols(block, &mut p, pilot_alphabet, pilot_pulses)?;
+        need(block, p, pilot_total * 3, "Generalized Data pilot stream")?;
+        pilot_stream.reserve(pilot_total);
+        for i in 0..pilot_total {
+            let symbol = block[p + i * 3];
+            if usize::from(symbol) >= pilot_alphabet {
+                return Err(format!(
+                    "Generalized Data pilot symbol {symbol} out of range"
+                ));
+            }
+            pilot_stream.push((symbol, read_u16_le(block, p + i * 3 + 1)));
+        }
+        p += pilot_total * 3;
+    }
+
+    let mut data_symbols = Vec::new();
+    let mut data_stream = Vec::new();
+    if data_total > 0 {
+        data_symbols = parse_gdb_symbols(block, &mut p, data_alphabet, data_pulses)?;
+        // Each symbol takes ceil(log2(alphabet size)) bits, MSB first.
+        let bits = (usize::BITS - (data_alphabet - 1).leading_zeros()) as usize;
+        need(
+            block,
+            p,
+            (data_total * bits).div_ceil(8),
+            "Generalized Data data stream",
+        )?;
+        data_stream.reserve(data_total);
+        for i in 0..data_total {
+            let mut symbol = 0usize;
+            for bit in i * bits..(i + 1) * bits {
+                let byte = block[p + bit / 8];
+                symbol = (symbol << 1) | usize::from((byte >> (7 - bit % 8)) & 1);
+            }
+            if symbol >= data_alphabet {
+                return Err(format!(
+                    "Generalized Data data symbol {symbol} out of range"
+                ));
+            }
+            data_stream.push(symbol as u8);
+        }
+    }
+
+    *pos = block_end;
+
+    Ok(TzxBlock::GeneralizedData {
+        pause_ms,
+        pilot_symbols,
+        pilot_stream,
+        data_symbols,
+        data_stream,
+    })
+}
+
+/// Alphabet size from its byte in a generalized data header (0 means 256).
+fn gdb_alphabet_size(byte: u8) -> usize {
+    if byte == 0 { 256 } else { usize::from(byte) }
+}
+
+/// Symbol definitions: a flags byte then `max_pulses` pulse lengths each.
+fn parse_gdb_symbols(
+    data: &[u8],
+    pos: &mut usize,
+    count: usize,
+    max_pulses: usize,
+) -> Result<Vec<GdbSymbol>, String> {
+    let size = 1 + max_pulses * 2;
+    need(data, *pos, count * size, "Generalized Data symbol table")?;
+    let symbols = (0..count)
+        .map(|i| {
+            let def = *pos + i * size;
+            GdbSymbol {
+                polarity: data[def] & 0x03,
+                pulses: (0..max_pulses)
+                    .map(|j| read_u16_le(data, def + 1 + j * 2))
+                    .take_while(|&pulse| pulse != 0)
+                    .collect(),
+            }
+        })
+        .collect();
+    *pos += count * size;
+    Ok(symbols)
+}
+
 /// Block $20: Pause / stop the tape.
 fn parse_pause(data: &[u8], pos: &mut usize) -> Result<TzxBlock, String> {
     need(data, *pos, 2, "Pause")?;
@@ -281,6 +529,14 @@ fn parse_group_start(data: &[u8], pos: &mut usize) -> Result<TzxBlock, String> {
     Ok(TzxBlock::GroupStart { name })
 }
 
+/// Block $23: Jump to block.
+fn parse_jump_to(data: &[u8], pos: &mut usize) -> Result<TzxBlock, String> {
+    need(data, *pos, 2, "Jump To")?;
+    let offset = read_i16_le(data, *pos);
+    *pos += 2;
+    Ok(TzxBlock::JumpTo { offset })
+}
+
 /// Block $24: Loop start.
 fn parse_loop_start(data: &[u8], pos: &mut usize) -> Result<TzxBlock, String> {
     need(data, *pos, 2, "Loop Start")?;
@@ -289,6 +545,58 @@ fn parse_loop_start(data: &[u8], pos: &mut usize) -> Result<TzxBlock, String> {
     Ok(TzxBlock::LoopStart { repetitions })
 }
 
+/// Block $26: Call sequence.
+fn parse_call_sequence(data: &[u8], pos: &mut usize) -> Result<TzxBlock, String> {
+    need(data, *pos, 2, "Call Sequence count")?;
+    let count = read_u16_le(data, *pos) as usize;
+    *pos += 2;
+
+    need(data, *pos, count * 2, "Call Sequence offsets")?;
+    let offsets = (0..count)
+        .map(|i| read_i16_le(data, *pos + i * 2))
+        .collect();
+    *pos += count * 2;
+
+    Ok(TzxBlock::CallSequence { offsets })
+}
+
+/// Block $28: Select block.
+fn parse_select(data: &[u8], pos: &mut usize) -> Result<TzxBlock, String> {
+    need(data, *pos, 2, "Select Block length")?;
+    let block_len = read_u16_le(data, *pos) as usize;
+    *pos += 2;
+
+    need(data, *pos, block_len, "Select Block data")?;
+    let block_end = *pos + block_len;
+
+    if block_len < 1 {
+        return Err("Select Block too short".to_string());
+    }
+
+    let num_entries = data[*pos] as usize;
+    *pos += 1;
+
+    let mut entries = Vec::with_capacity(num_entries);
+    for _ in 0..num_entries {
+        if *pos + 3 > block_end {
+            break;
+        }
+        let offset = read_i16_le(data, *pos);
+        let text_len = data[*pos + 2] as usize;
+        *pos += 3;
+
+        let text_end = (*pos + text_len).min(block_end);
+        let text = String::from_utf8_lossy(&data[*pos..text_end]).to_string();
+        *pos = text_end;
+
+        entries.push((offset, text));
+    }
+
+    *pos = block_end;
+
+    Ok(TzxBlock::Select { entries })
+}
+
 /// Block $2A: Stop the tape if in 48K mode.
 fn parse_stop_if_48k(data: &[u8], pos: &mut usize) -> Result<TzxBlock, String> {
     need(data, *pos, 4, "Stop If 48K")?;
@@ -361,36 +669,6 @@ fn parse_archive_info(data: &[u8], pos: &mut usize) -> Result<TzxBlock, String>
 fn skip_unknown_block(block_id: u8, data: &[u8], pos: &mut usize) -> Result<TzxBlock, String> {
     // Blocks with known length layout
     let skip_len = match block_id {
-        // $15: Direct recording — 8-byte header + 3-byte data length
-        0x15 => {
-            need(data, *pos, 8, "Direct Recording header")?;
-            let data_len = read_u24_le(data, *pos + 5) as usize;
-            8 + data_len
-        }
-        // $18: CSW recording — 4-byte block length
-        // $19: Generalized data — 4-byte block length
-        0x18 | 0x19 => {
-            need(data, *pos, 4, "block length")?;
-            read_u32_le(data, *pos) as usize + 4
-        }
-        // $23: Call sequence — 2-byte count * 2 + 2
-        0x23 => {
-            need(data, *pos, 2, "Call Sequence count")?;
-            let count = read_u16_le(data, *pos) as usize;
-            2 + count * 2
-        }
-        // $26: Return from sequence — no data
-        0x26 => 0,
-        // $27: Select block — 2-byte length prefix
-        0x27 => {
-            need(data, *pos, 2, "Select Block length")?;
-            read_u16_le(data, *pos) as usize + 2
-        }
-        // $28: Jump to block — 2-byte length prefix
-        0x28 => {
-            need(data, *pos, 2, "Jump To length")?;
-            read_u16_le(data, *pos) as usize + 2
-        }
         // $33: Hardware type — 1-byte count * 3 + 1
         0x33 => {
             need(data, *pos, 1, "Hardware Type count")?;
@@ -430,6 +708,239 @@ fn skip_unknown_block(block_id: u8, data: &[u8], pos: &mut usize) -> Result<TzxB
     Ok(TzxBlock::Unknown { block_id })
 }
 
+// ---------------------------------------------------------------------------
+// Block writer
+// ---------------------------------------------------------------------------
+
+/// Append one block, ID byte first, in the layout its parser reads.
+fn write_block(block: &TzxBlock, out: &mut Vec<u8>) {
+    match block {
+        TzxBlock::StandardSpeed { pause_ms, data } => {
+            out.push(0x10);
+            put_u16(out, *pause_ms);
+            put_u16(out, data.len() as u16);
+            out.extend_from_slice(data);
+        }
+        TzxBlock::TurboSpeed {
+            pilot_pulse,
+            sync1,
+            sync2,
+            zero_pulse,
+            one_pulse,
+            pilot_count,
+            used_bits,
+            pause_ms,
+            data,
+        } => {
+            out.push(0x11);
+            for value in [
+                *pilot_pulse,
+                *sync1,
+                *sync2,
+                *zero_pulse,
+                *one_pulse,
+                *pilot_count,
+            ] {
+                put_u16(out, value);
+            }
+            out.push(*used_bits);
+            put_u16(out, *pause_ms);
+            put_u24(out, data.len());
+            out.extend_from_slice(data);
+        }
+        TzxBlock::PureTone { pulse_len, count } => {
+            out.push(0x12);
+            put_u16(out, *pulse_len);
+            put_u16(out, *count);
+        }
+        TzxBlock::PulseSequence { pulses } => {
+            out.push(0x13);
+            let pulses = &pulses[..pulses.len().min(0xFF)];
+            out.push(pulses.len() as u8);
+            for &pulse in pulses {
+                put_u16(out, pulse);
+            }
+        }
+        TzxBlock::PureData {
+            zero_pulse,
+            one_pulse,
+            used_bits,
+            pause_ms,
+            data,
+        } => {
+            out.push(0x14);
+            put_u16(out, *zero_pulse);
+            put_u16(out, *one_pulse);
+            out.push(*used_bits);
+            put_u16(out, *pause_ms);
+            put_u24(out, data.len());
+            out.extend_from_slice(data);
+        }
+        TzxBlock::DirectRecording {
+            tstates_per_sample,
+            pause_ms,
+            used_bits,
+            data,
+        } => {
+            out.push(0x15);
+            put_u16(out, *tstates_per_sample);
+            put_u16(out, *pause_ms);
+            out.push(*used_bits);
+            put_u24(out, data.len());
+            out.extend_from_slice(data);
+        }
+        TzxBlock::CswRecording {
+            pause_ms,
+            sample_rate,
+            pulses,
+        } => {
+            let rle = format_csw::encode_rle(pulses);
+            out.push(0x18);
+            put_u32(out, 10 + rle.len());
+            put_u16(out, *pause_ms);
+            put_u24(out, *sample_rate as usize);
+            out.push(1); // RLE
+            put_u32(out, pulses.len());
+            out.extend_from_slice(&rle);
+        }
+        TzxBlock::GeneralizedData {
+            pause_ms,
+            pilot_symbols,
+            pilot_stream,
+            data_symbols,
+            data_stream,
+        } => write_generalized_data(
+            out,
+            *pause_ms,
+            pilot_symbols,
+            pilot_stream,
+            data_symbols,
+            data_stream,
+        ),
+        TzxBlock::Pause { duration_ms } => {
+            out.push(0x20);
+            put_u16(out, *duration_ms);
+        }
+        TzxBlock::GroupStart { name } => {
+            out.push(0x21);
+            put_text(out, name);
+        }
+        TzxBlock::GroupEnd => out.push(0x22),
+        TzxBlock::JumpTo { offset } => {
+            out.push(0x23);
+            out.extend_from_slice(&offset.to_le_bytes());
+        }
+        TzxBlock::LoopStart { repetitions } => {
+            out.push(0x24);
+            put_u16(out, *repetitions);
+        }
+        TzxBlock::LoopEnd => out.push(0x25),
+        TzxBlock::CallSequence { offsets } => {
+            out.push(0x26);
+            put_u16(out, offsets.len() as u16);
+            for offset in offsets {
+                out.extend_from_slice(&offset.to_le_bytes());
+            }
+        }
+        TzxBlock::ReturnFromSequence => out.push(0x27),
+        TzxBlock::Select { entries } => {
+            let mut body = vec![entries.len() as u8];
+            for (offset, text) in entries {
+                body.extend_from_slice(&offset.to_le_bytes());
+                put_text(&mut body, text);
+            }
+            out.push(0x28);
+            put_u16(out, body.len() as u16);
+            out.extend_from_slice(&body);
+        }
+        TzxBlock::StopIf48K => {
+            out.push(0x2A);
+            put_u32(out, 0);
+        }
+        TzxBlock::SetSignalLevel { level } => {
+            out.push(0x2B);
+            put_u32(out, 1);
+            out.push(u8::from(*level));
+        }
+        TzxBlock::TextDescription { text } => {
+            out.push(0x30);
+            put_text(out, text);
+        }
+        TzxBlock::ArchiveInfo { entries } => {
+            let mut body = vec![entries.len() as u8];
+            for (id, text) in entries {
+                body.push(*id);
+                put_text(&mut body, text);
+            }
+            out.push(0x32);
+            put_u16(out, body.len() as u16);
+            out.extend_from_slice(&body);
+        }
+        TzxBlock::Unknown { .. } => {}
+    }
+}
+
+/// Block $19: symbol tables padded to the longest symbol, the pilot as
+/// (symbol, repetitions) triples, and the data packed MSB first.
+fn write_generalized_data(
+    out: &mut Vec<u8>,
+    pause_ms: u16,
+    pilot_symbols: &[GdbSymbol],
+    pilot_stream: &[(u8, u16)],
+    data_symbols: &[GdbSymbol],
+    data_stream: &[u8],
+) {
+    let max_pulses =
+        |symbols: &[GdbSymbol]| symbols.iter().map(|s| s.pulses.len()).max().unwrap_or(0);
+    let pilot_pulses = max_pulses(pilot_symbols);
+    let data_pulses = max_pulses(data_symbols);
+
+    let mut body = Vec::new();
+    put_u16(&mut body, pause_ms);
+    put_u32(&mut body, pilot_stream.len());
+    body.push(pilot_pulses as u8);
+    body.push(pilot_symbols.len() as u8);
+    put_u32(&mut body, data_stream.len());
+    body.push(data_pulses as u8);
+    body.push(data_symbols.len() as u8);
+
+    if !pilot_stream.is_empty() {
+        write_gdb_symbols(&mut body, pilot_symbols, pilot_pulses);
+        for &(symbol, repetitions) in pilot_stream {
+            body.push(symbol);
+            put_u16(&mut body, repetitions);
+        }
+    }
+    if !data_stream.is_empty() {
+        write_gdb_symbols(&mut body, data_symbols, data_pulses);
+        let alphabet = gdb_alphabet_size(data_symbols.len() as u8);
+        let bits = (usize::BITS - (alphabet - 1).leading_zeros()) as usize;
+        let mut packed = vec![0u8; (data_stream.len() * bits).div_ceil(8)];
+        for (i, &symbol) in data_stream.iter().enumerate() {
+            for b in 0..bits {
+                if (symbol >> (bits - 1 - b)) & 1 != 0 {
+                    let bit = i * bits + b;
+                    packed[bit / 8] |= 0x80 >> (bit % 8);
+                }
+            }
+        }
+        body.extend_from_slice(&packed);
+    }
+
+    out.push(0x19);
+    put_u32(out, body.len());
+    out.extend_from_slice(&body);
+}
+
+fn write_gdb_symbols(out: &mut Vec<u8>, symbols: &[GdbSymbol], max_pulses: usize) {
+    for symbol in symbols {
+        out.push(symbol.polarity);
+        for i in 0..max_pulses {
+            put_u16(out, symbol.pulses.get(i).copied().unwrap_or(0));
+        }
+    }
+}
+
 // ---------------------------------------------------------------------------
 // Tests
 // ---------------------------------------------------------------------------
@@ -606,6 +1117,173 @@ mod tests {
         }
     }
 
+    #[test]
+    fn parse_direct_recording_block() {
+        let mut data = tzx_header();
+        data.push(0x15);
+        data.extend_from_slice(&79u16.to_le_bytes()); // T-states per sample
+        data.extend_from_slice(&250u16.to_le_bytes()); // pause_ms
+        data.push(5); // used_bits
+        data.extend_from_slice(&[2, 0, 0]);
+        data.extend_from_slice(&[0xF0, 0xA8]);
+
+        let tzx = TzxFile::parse(&data).expect("direct recording");
+        match &tzx.blocks[0] {
+            TzxBlock::DirectRecording {
+                tstates_per_sample,
+                pause_ms,
+                used_bits,
+                data: block_data,
+            } => {
+                assert_eq!(*tstates_per_sample, 79);
+                assert_eq!(*pause_ms, 250);
+                assert_eq!(*used_bits, 5);
+                assert_eq!(block_data, &[0xF0, 0xA8]);
+            }
+            _ => panic!("Expected DirectRecording"),
+        }
+    }
+
+    #[test]
+    fn parse_csw_recording_block() {
+        let rle = [10u8, 0, 0x2C, 0x01, 0x00, 0x00, 7];
+        let mut data = tzx_header();
+        data.push(0x18);
+        data.extend_from_slice(&(10 + rle.len() as u32).to_le_bytes());
+        data.extend_from_slice(&100u16.to_le_bytes()); // pause_ms
+        data.extend_from_slice(&[0x44, 0xAC, 0x00]); // 44100 Hz
+        data.push(1); // RLE
+        data.extend_from_slice(&3u32.to_le_bytes());
+        data.extend_from_slice(&rle);
+        data.push(0x22); // following block still lines up
+
+        let tzx = TzxFile::parse(&data).expect("CSW recording");
+        assert_eq!(tzx.blocks.len(), 2);
+        match &tzx.blocks[0] {
+            TzxBlock::CswRecording {
+                pause_ms,
+                sample_rate,
+                pulses,
+            } => {
+                assert_eq!(*pause_ms, 100);
+                assert_eq!(*sample_rate, 44100);
+                assert_eq!(pulses, &[10, 300, 7]);
+            }
+            _ => panic!("Expected CswRecording"),
+        }
+        assert!(matches!(tzx.blocks[1], TzxBlock::GroupEnd));
+    }
+
+    #[test]
+    fn parse_generalized_data_block() {
+        let mut body = Vec::new();
+        body.extend_from_slice(&0u16.to_le_bytes()); // pause_ms
+        body.extend_from_slice(&2u32.to_le_bytes()); // TOTP
+        body.push(2); // NPP
+        body.push(2); // ASP
+        body.extend_from_slice(&5u32.to_le_bytes()); // TOTD
+        body.push(2); // NPD
+        body.push(3); // ASD: 2 bits per symbol
+        // Pilot symbols: one pulse of 2168, then a 667/735 sync pair.
+        body.extend_from_slice(&[0x00, 0x78, 0x08, 0x00, 0x00]);
+        body.extend_from_slice(&[0x00, 0x9B, 0x02, 0xDF, 0x02]);
+        // Pilot stream: symbol 0 x 3000, symbol 1 x 1.
+        body.extend_from_slice(&[0, 0xB8, 0x0B, 1, 1, 0]);
+        // Data symbols: 855, 1710 (forced high), 500+500.
+        body.extend_from_slice(&[0x00, 0x57, 0x03, 0x00, 0x00]);
+        body.extend_from_slice(&[0x03, 0xAE, 0x06, 0x00, 0x00]);
+        body.extend_from_slice(&[0x00, 0xF4, 0x01, 0xF4, 0x01]);
+        // Symbols 2, 0, 1, 1, 2 packed two bits each, MSB first.
+        body.extend_from_slice(&[0b1000_0101, 0b1000_0000]);
+
+        let mut data = tzx_header();
+        data.push(0x19);
+        data.extend_from_slice(&(body.len() as u32).to_le_bytes());
+        data.extend_from_slice(&body);
+
+        let tzx = TzxFile::parse(&data).expect("generalized data");
+        match &tzx.blocks[0] {
+            TzxBlock::GeneralizedData {
+                pause_ms,
+                pilot_symbols,
+                pilot_stream,
+                data_symbols,
+                data_stream,
+            } => {
+                assert_eq!(*pause_ms, 0);
+                assert_eq!(pilot_symbols[0].pulses, [2168]);
+                assert_eq!(pilot_symbols[1].pulses, [667, 735]);
+                assert_eq!(pilot_stream, &[(0, 3000), (1, 1)]);
+                assert_eq!(data_symbols.len(), 3);
+                assert_eq!(data_symbols[1].polarity, 3);
+                assert_eq!(data_symbols[2].pulses, [500, 500]);
+                assert_eq!(data_stream, &[2, 0, 1, 1, 2]);
+            }
+            _ => panic!("Expected GeneralizedData"),
+        }
+    }
+
+    #[test]
+    fn generalized_data_symbol_out_of_range() {
+        let mut body = Vec::new();
+        body.extend_from_slice(&0u16.to_le_bytes());
+        body.extend_from_slice(&0u32.to_le_bytes()); // no pilot
+        body.extend_from_slice(&[0, 0]);
+        body.extend_from_slice(&1u32.to_le_bytes());
+        body.extend_from_slice(&[1, 3]); // 3 symbols, 1 pulse each
+        body.extend_from_slice(&[0, 1, 0, 0, 2, 0, 0, 3, 0]);
+        body.push(0b1100_0000); // symbol 3
+
+        let mut data = tzx_header();
+        data.push(0x19);
+        data.extend_from_slice(&(body.len() as u32).to_le_bytes());
+        data.extend_from_slice(&body);
+        assert!(TzxFile::parse(&data).is_err());
+    }
+
+    #[test]
+    fn parse_flow_control_blocks() {
+        let mut data = tzx_header();
+        // $23: jump back 2
+        data.push(0x23);
+        data.extend_from_slice(&(-2i16).to_le_bytes());
+        // $26: call blocks +3 and +5
+        data.push(0x26);
+        data.extend_from_slice(&2u16.to_le_bytes());
+        data.extend_from_slice(&3i16.to_le_bytes());
+        data.extend_from_slice(&5i16.to_le_bytes());
+        // $27: return
+        data.push(0x27);
+        // $28: select between two entries
+        data.push(0x28);
+        data.extend_from_slice(&11u16.to_le_bytes());
+        data.push(2);
+        data.extend_from_slice(&1i16.to_le_bytes());
+        data.push(3);
+        data.extend_from_slice(b"48K");
+        data.extend_from_slice(&4i16.to_le_bytes());
+        data.push(1);
+        data.extend_from_slice(b"+");
+        data.push(0x22);
+
+        let tzx = TzxFile::parse(&data).expect("flow control");
+        assert_eq!(tzx.blocks.len(), 5);
+        assert!(matches!(tzx.blocks[0], TzxBlock::JumpTo { offset: -2 }));
+        match &tzx.blocks[1] {
+            TzxBlock::CallSequence { offsets } => assert_eq!(offsets, &[3, 5]),
+            _ => panic!("Expected CallSequence"),
+        }
+        assert!(matches!(tzx.blocks[2], TzxBlock::ReturnFromSequence));
+        match &tzx.blocks[3] {
+            TzxBlock::Select { entries } => {
+                assert_eq!(entries[0], (1, "48K".to_string()));
+                assert_eq!(entries[1], (4, "+".to_string()));
+            }
+            _ => panic!("Expected Select"),
+        }
+        assert!(matches!(tzx.blocks[4], TzxBlock::GroupEnd));
+    }
+
     #[test]
     fn parse_pause_block() {
         let mut data = tzx_header();
@@ -768,4 +1446,107 @@ mod tests {
         data.push(0x10); // Standard speed, but no data following
         assert!(TzxFile::parse(&data).is_err());
     }
+
+    #[test]
+    fn every_block_round_trips() {
+        let symbol = |polarity, pulses: &[u16]| GdbSymbol {
+            polarity,
+            pulses: pulses.to_vec(),
+        };
+        let blocks = vec![
+            TzxBlock::StandardSpeed {
+                pause_ms: 1000,
+                data: vec![0x00, 0x03, 0x03],
+            },
+            TzxBlock::TurboSpeed {
+                pilot_pulse: 2000,
+                sync1: 600,
+                sync2: 700,
+                zero_pulse: 500,
+                one_pulse: 1000,
+                pilot_count: 4000,
+                used_bits: 6,
+                pause_ms: 250,
+                data: vec![0xA5, 0xFC],
+            },
+            TzxBlock::PureTone {
+                pulse_len: 1200,
+                count: 30,
+            },
+            TzxBlock::PulseSequence {
+                pulses: vec![100, 200, 300],
+            },
+            TzxBlock::PureData {
+                zero_pulse: 400,
+                one_pulse: 800,
+                used_bits: 3,
+                pause_ms: 0,
+                data: vec![0xE0],
+            },
+            TzxBlock::DirectRecording {
+                tstates_per_sample: 79,
+                pause_ms: 10,
+                used_bits: 8,
+                data: vec![0xF0, 0x0F],
+            },
+            TzxBlock::CswRecording {
+                pause_ms: 5,
+                sample_rate: 44100,
+                pulses: vec![12, 300, 7],
+            },
+            TzxBlock::GeneralizedData {
+                pause_ms: 100,
+                pilot_symbols: vec![symbol(0, &[2168]), symbol(0, &[667, 735])],
+                pilot_stream: vec![(0, 3223), (1, 1)],
+                data_symbols: vec![
+                    symbol(0, &[855, 855]),
+                    symbol(0, &[1710, 1710]),
+                    symbol(3, &[300]),
+                ],
+                data_stream: vec![0, 1, 2, 1, 0],
+            },
+            TzxBlock::Pause { duration_ms: 2000 },
+            TzxBlock::GroupStart {
+                name: "Side A".into(),
+            },
+            TzxBlock::GroupEnd,
+            TzxBlock::JumpTo { offset: -2 },
+            TzxBlock::LoopStart { repetitions: 3 },
+            TzxBlock::LoopEnd,
+            TzxBlock::CallSequence {
+                offsets: vec![1, -3],
+            },
+            TzxBlock::ReturnFromSequence,
+            TzxBlock::Select {
+                entries: vec![(1, "Easy".into()), (2, "Hard".into())],
+            },
+            TzxBlock::StopIf48K,
+            TzxBlock::SetSignalLevel { level: true },
+            TzxBlock::TextDescription {
+                text: "Saved by the emulator".into(),
+            },
+            TzxBlock::ArchiveInfo {
+                entries: vec![(0x00, "Title".into()), (0x02, "Author".into())],
+            },
+        ];
+        let tzx = TzxFile {
+            major: 1,
+            minor: 20,
+            blocks,
+        };
+        let parsed = TzxFile::parse(&tzx.to_bytes()).expect("round trip");
+        assert_eq!((parsed.major, parsed.minor), (1, 20));
+        assert_eq!(parsed.blocks, tzx.blocks);
+    }
+
+    #[test]
+    fn writer_drops_unknown_blocks() {
+        let tzx = TzxFile {
+            major: 1,
+            minor: 20,
+            blocks: vec![TzxBlock::Unknown { block_id: 0x5A }, TzxBlock::GroupEnd],
+        };
+        let bytes = tzx.to_bytes();
+        assert_eq!(&bytes[10..], [0x22]);
+    }
 }
@@ -1,4 +1,4 @@
-//! .Z80 snapshot loader for ZX Spectrum (v1, v2, v3 formats).
+//! .Z80 snapshot loader (v1, v2, v3) and writer (v3) for ZX Spectrum.
 //!
 //! Reference: <https://worldofspectrum.net/documentation/z80format.htm>
 //!
@@ -7,14 +7,21 @@
 //!
 //! **Version 2/3** (offset 6–7 PC = 0): 30-byte base header + extended
 //! header + page-based memory blocks. Supports 48K and 128K.
+//!
+//! [`save_z80`] writes version 3 with compressed pages, including the AY
+//! registers, the frame T-state counter and, on the +2A/+3, port $1FFD.
 
 #![allow(clippy::cast_possible_truncation)]
 
-pub use format_sna::{SnapshotTarget, Z80Registers};
+pub use format_sna::{SnapshotModel, SnapshotSource, SnapshotTarget, Z80Registers};
 
 /// Minimum size for a v1 header.
 const V1_HEADER_SIZE: usize = 30;
 
+/// v3 extended header length, without and with the $1FFD byte.
+const V3_EXT_LEN: u16 = 54;
+const V3_EXT_LEN_PLUS3: u16 = 55;
+
 /// Snapshot model detected from the hardware mode byte.
 #[derive(Debug, Clone, Copy, PartialEq, Eq)]
 pub enum Z80SnapshotModel {
@@ -174,6 +181,15 @@ fn load_v2v3(target: &mut impl SnapshotTarget, data: &[u8], _version: u8) -> Res
 
     let is_128k = is_128k_hardware(hw_mode, ext_len);
 
+    // v3 frame position, stored as a countdown within one of four quarters.
+    if ext_header_end >= 58 {
+        let quarter = if is_128k { 70_908 / 4 } else { 69_888 / 4 };
+        let low = u32::from(data[55]) | (u32::from(data[56]) << 8);
+        let high = u32::from(data[57]);
+        let tstate = (((high + 1) % 4) + 1) * quarter;
+        target.set_frame_tstate(tstate.saturating_sub(low + 1));
+    }
+
     if is_128k {
         target.write_bank_register(port_7ffd);
     }
@@ -218,6 +234,11 @@ fn load_v2v3(target: &mut impl SnapshotTarget, data: &[u8], _version: u8) -> Res
         }
     }
 
+    // Last, as special paging would move the banks the pages load into.
+    if ext_len == V3_EXT_LEN_PLUS3 && matches!(hw_mode, 7 | 13) {
+        target.write_plus3_register(data[86]);
+    }
+
     Ok(())
 }
 
@@ -279,6 +300,139 @@ fn load_128k_page(target: &mut impl SnapshotTarget, page: u8, ram: &[u8], port_7
     }
 }
 
+/// Save a snapshot as a version 3 .Z80 file with compressed pages.
+#[must_use]
+pub fn save_z80(source: &impl SnapshotSource) -> Vec<u8> {
+    let model = source.model();
+    let regs = source.registers();
+    let ext_len = if model.has_plus3_register() {
+        V3_EXT_LEN_PLUS3
+    } else {
+        V3_EXT_LEN
+    };
+
+    let mut data = vec![0u8; 32 + ext_len as usize];
+    data[0] = regs.a;
+    data[1] = regs.f;
+    data[2] = regs.c;
+    data[3] = regs.b;
+    data[4] = regs.l;
+    data[5] = regs.h;
+    // Bytes 6-7: PC = 0 marks v2/v3; the real PC is in the extended header.
+    data[8..10].copy_from_slice(&regs.sp.to_le_bytes());
+    data[10] = regs.i;
+    data[11] = regs.r & 0x7F;
+    data[12] = (regs.r >> 7) | ((source.border() & 0x07) << 1);
+    data[13] = regs.e;
+    data[14] = regs.d;
+    data[15] = regs.c_alt;
+    data[16] = regs.b_alt;
+    data[17] = regs.e_alt;
+    data[18] = regs.d_alt;
+    data[19] = regs.l_alt;
+    data[20] = regs.h_alt;
+    data[21] = regs.a_alt;
+    data[22] = regs.f_alt;
+    data[23..25].copy_from_slice(&regs.iy.to_le_bytes());
+    data[25..27].copy_from_slice(&regs.ix.to_le_bytes());
+    data[27] = u8::from(regs.iff1);
+    data[28] = u8::from(regs.iff2);
+    data[29] = regs.im & 0x03;
+
+    data[30..32].copy_from_slice(&ext_len.to_le_bytes());
+    data[32..34].copy_from_slice(&regs.pc.to_le_bytes());
+    data[34] = match model {
+        SnapshotModel::Spectrum48K => 0,
+        SnapshotModel::Spectrum128K => 4,
+        SnapshotModel::SpectrumPlus3 => 7,
+        SnapshotModel::SpectrumPlus2 => 12,
+        SnapshotModel::SpectrumPlus2A => 13,
+    };
+    if model.is_128k() {
+        data[35] = source.bank_register();
+    }
+    // R register and LDIR emulation on, as every emulator writes it.
+    data[37] = 0x03;
+    if let Some((ay_regs, selected)) = source.ay_registers() {
+        data[38] = selected;
+        data[39..55].copy_from_slice(&ay_regs);
+    }
+
+    let quarter = model.frame_tstates() / 4;
+    let tstate = source.frame_tstate() % model.frame_tstates();
+    let low = (quarter - 1 - tstate % quarter) as u16;
+    data[55..57].copy_from_slice(&low.to_le_bytes());
+    data[57] = ((tstate / quarter + 3) % 4) as u8;
+
+    // Bytes 61-62: $0000-$3FFF is ROM, unless +3 special paging maps RAM.
+    let plus3 = source.plus3_register();
+    if !(model.has_plus3_register() && plus3 & 0x01 != 0) {
+        data[61] = 0xFF;
+        data[62] = 0xFF;
+    }
+    if model.has_plus3_register() {
+        data[86] = plus3;
+    }
+
+    let pages: &[(u8, u8)] = if model.is_128k() {
+        &[
+            (3, 0),
+            (4, 1),
+            (5, 2),
+            (6, 3),
+            (7, 4),
+            (8, 5),
+            (9, 6),
+            (10, 7),
+        ]
+    } else {
+        &[(8, 5), (4, 2), (5, 0)]
+    };
+    for &(page, bank) in pages {
+        let ram = source.ram_bank(bank);
+        let packed = compress_z80(ram);
+        if packed.len() < 0x4000 {
+            data.extend_from_slice(&(packed.len() as u16).to_le_bytes());
+            data.push(page);
+            data.extend_from_slice(&packed);
+        } else {
+            data.extend_from_slice(&[0xFF, 0xFF, page]);
+            data.extend_from_slice(ram);
+        }
+    }
+    data
+}
+
+/// Compress data with the Z80-format RLE.
+///
+/// Runs of five or more equal bytes, and runs of two or more `ED`s,
+/// become `ED ED count byte`. A lone `ED` is always followed by a literal
+/// byte, so it cannot merge with a run that starts after it.
+fn compress_z80(src: &[u8]) -> Vec<u8> {
+    let mut out = Vec::with_capacity(src.len());
+    let mut i = 0;
+    while i < src.len() {
+        let byte = src[i];
+        let run = src[i..]
+            .iter()
+            .take(255)
+            .take_while(|&&b| b == byte)
+            .count();
+        if run >= 5 || (byte == 0xED && run >= 2) {
+            out.extend_from_slice(&[0xED, 0xED, run as u8, byte]);
+            i += run;
+        } else {
+            out.push(byte);
+            i += 1;
+            if byte == 0xED && i < src.len() {
+                out.push(src[i]);
+                i += 1;
+            }
+        }
+    }
+    out
+}
+
 /// Decompress Z80-format RLE data.
 ///
 /// Escape sequence: `ED ED xx yy` = repeat byte `yy` × `xx` times.
@@ -319,6 +473,8 @@ mod tests {
         bank_history: Vec<u8>,
         ay_regs: [u8; 16],
         ay_selected: u8,
+        plus3_reg: Option<u8>,
+        tstate: Option<u32>,
     }
 
     impl TestTarget {
@@ -331,6 +487,8 @@ mod tests {
                 bank_history: Vec::new(),
                 ay_regs: [0; 16],
                 ay_selected: 0,
+                plus3_reg: None,
+                tstate: None,
             }
         }
     }
@@ -358,6 +516,64 @@ mod tests {
         fn select_ay_register(&mut self, reg: u8) {
             self.ay_selected = reg;
         }
+        fn write_plus3_register(&mut self, val: u8) {
+            self.plus3_reg = Some(val);
+        }
+        fn set_frame_tstate(&mut self, tstate: u32) {
+            self.tstate = Some(tstate);
+        }
+    }
+
+    /// Snapshot source with each RAM bank filled with its own number.
+    struct TestSource {
+        model: SnapshotModel,
+        regs: Z80Registers,
+        banks: Vec<Vec<u8>>,
+        bank_reg: u8,
+        plus3_reg: u8,
+        tstate: u32,
+    }
+
+    impl TestSource {
+        fn new(model: SnapshotModel) -> Self {
+            Self {
+                model,
+                regs: Z80Registers::default(),
+                banks: (0..8).map(|bank| vec![bank; 0x4000]).collect(),
+                bank_reg: 0,
+                plus3_reg: 0,
+                tstate: 0,
+            }
+        }
+    }
+
+    impl SnapshotSource for TestSource {
+        fn model(&self) -> SnapshotModel {
+            self.model
+        }
+        fn registers(&self) -> Z80Registers {
+            self.regs.clone()
+        }
+        fn ram_bank(&self, bank: u8) -> &[u8] {
+            &self.banks[bank as usize]
+        }
+        fn border(&self) -> u8 {
+            6
+        }
+        fn bank_register(&self) -> u8 {
+            self.bank_reg
+        }
+        fn plus3_register(&self) -> u8 {
+            self.plus3_reg
+        }
+        fn ay_registers(&self) -> Option<([u8; 16], u8)> {
+            self.model
+                .is_128k()
+                .then(|| (std::array::from_fn(|reg| reg as u8 * 2), 11))
+        }
+        fn frame_tstate(&self) -> u32 {
+            self.tstate
+        }
     }
 
     fn make_v1_uncompressed(pc: u16) -> Vec<u8> {
@@ -696,4 +912,92 @@ mod tests {
                 .contains("uncompressed block at page 8 truncated")
         );
     }
+
+    #[test]
+    fn compress_z80_round_trips_awkward_runs() {
+        let mut src = vec![0xED, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00];
+        src.extend_from_slice(&[0xED, 0xED, 0x11, 0xED]);
+        src.extend(std::iter::repeat_n(0x42, 300));
+        src.extend_from_slice(&[1, 2, 3, 3, 3, 3, 0xED]);
+
+        let packed = compress_z80(&src);
+        assert!(packed.len() < src.len());
+        assert_eq!(
+            &packed[..2],
+            &[0xED, 0x00],
+            "lone ED keeps its next byte literal"
+        );
+        let mut unpacked = vec![0u8; src.len()];
+        decompress_z80(&packed, &mut unpacked);
+        assert_eq!(unpacked, src);
+    }
+
+    #[test]
+    fn save_z80_48k_round_trips_through_load() {
+        let mut source = TestSource::new(SnapshotModel::Spectrum48K);
+        source.regs.a = 0x12;
+        source.regs.f_alt = 0x34;
+        source.regs.iy = 0x5C3A;
+        source.regs.sp = 0xFF40;
+        source.regs.pc = 0x8123;
+        source.regs.r = 0x95;
+        source.regs.im = 1;
+        source.regs.iff1 = true;
+        source.regs.iff2 = true;
+        source.tstate = 12_345;
+
+        let data = save_z80(&source);
+        assert_eq!(detect_version(&data), 3);
+        assert!(data.len() < 2000, "uniform banks compress well");
+
+        let mut target = TestTarget::new();
+        load_z80(&mut target, &data).expect("load should succeed");
+        assert_eq!(target.regs.pc, 0x8123);
+        assert_eq!(target.regs.sp, 0xFF40);
+        assert_eq!(target.regs.a, 0x12);
+        assert_eq!(target.regs.f_alt, 0x34);
+        assert_eq!(target.regs.iy, 0x5C3A);
+        assert_eq!(target.regs.r, 0x95);
+        assert_eq!(target.regs.im, 1);
+        assert!(target.regs.iff1 && target.regs.iff2);
+        assert_eq!(target.border, 6);
+        assert_eq!(target.tstate, Some(12_345));
+        assert_eq!(target.ram[0x4000], 5);
+        assert_eq!(target.ram[0xBFFF], 2);
+        assert_eq!(target.ram[0xC000], 0);
+        assert!(target.bank_history.is_empty());
+        assert_eq!(target.plus3_reg, None);
+    }
+
+    #[test]
+    fn save_z80_plus3_keeps_paging_ay_and_frame_position() {
+        let mut source = TestSource::new(SnapshotModel::SpectrumPlus3);
+        source.regs.pc = 0x0038;
+        source.bank_reg = 0x16;
+        source.plus3_reg = 0x04;
+        source.tstate = 70_000;
+
+        let data = save_z80(&source);
+        assert_eq!(data[30], 55);
+        assert_eq!(data[34], 7);
+        assert_eq!(data[86], 0x04);
+        assert_eq!(&data[61..63], &[0xFF, 0xFF]);
+
+        let mut target = TestTarget::new();
+        load_z80(&mut target, &data).expect("load should succeed");
+        assert_eq!(target.regs.pc, 0x0038);
+        assert_eq!(target.bank_reg, 0x16);
+        assert_eq!(target.plus3_reg, Some(0x04));
+        assert_eq!(target.tstate, Some(70_000));
+        assert_eq!(target.ay_selected, 11);
+        assert_eq!(target.ay_regs[7], 14);
+        assert_eq!(target.ram[0x4000], 5);
+        assert_eq!(target.ram[0x8000], 2);
+        // The last bank paged through $C000 during the load was bank 7.
+        assert_eq!(target.ram[0xC000], 7);
+
+        source.model = SnapshotModel::Spectrum128K;
+        let data = save_z80(&source);
+        assert_eq!((data[30], data[34]), (54, 4));
+    }
 }
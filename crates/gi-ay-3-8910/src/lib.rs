@@ -1,8 +1,11 @@
 //! General Instrument AY-3-8910 Programmable Sound Generator emulator.
 //!
 //! Three square-wave tone generators, a shared noise generator, a shared
-//! envelope generator, and a per-channel mixer. Output is downsampled to
-//! the configured sample rate (typically 48 kHz).
+//! envelope generator, and a per-channel mixer. Output is band-limited to
+//! the configured sample rate (typically 48 kHz). Each channel's output is
+//! also available as a tap, for muting or capturing it on its own.
+//! Writes to R0–R13 can be logged with input-clock timestamps and exported
+//! as VGM or YM (see [`emu_core::reglog`]).
 //!
 //! # Register map (16 registers, active 0–13)
 //!
@@ -28,6 +31,10 @@
 #![allow(clippy::cast_possible_truncation)]
 #![allow(clippy::cast_precision_loss)]
 
+use emu_core::{
+    BlipBuffer, LoggedChip, RegisterLog, SaveState, StateError, StateReader, StateWriter, Stems,
+};
+
 /// Logarithmic volume table for the AY-3-8910 DAC.
 /// 16 levels, normalised to 0.0–1.0.
 const VOLUME_TABLE: [f32; 16] = [
@@ -218,17 +225,27 @@ pub struct Ay3_8910 {
     /// Internal clock divider counter.
     clock_counter: u32,
 
-    // Downsampling state
-    accumulator: (f32, f32),
-    sample_count: u32,
-    ticks_per_sample: f32,
-    buffer: Vec<[f32; 2]>,
+    /// Band-limited output, left and right, clocked per input cycle.
+    blip: [BlipBuffer; 2],
 
     /// Stereo panning mode.
     stereo_mode: StereoMode,
+
+    /// Channels left out of the mix (bit n = `CHANNELS[n]`).
+    channel_mute: u8,
+    /// Per-channel capture, while enabled.
+    stems: Option<Stems>,
+
+    /// Input clock in Hz, for register-log timestamps.
+    clock_hz: u32,
+    /// Register-write log, while enabled.
+    log: Option<RegisterLog>,
 }
 
 impl Ay3_8910 {
+    /// Channel names, in tap and mute-mask order.
+    pub const CHANNELS: [&'static str; 3] = ["a", "b", "c"];
+
     /// Create a new AY-3-8910.
     ///
     /// `clock_freq` is the chip input clock in Hz (e.g. 1,773,400 for
@@ -247,11 +264,15 @@ impl Ay3_8910 {
             noise: NoiseGenerator::new(),
             envelope: EnvelopeGenerator::new(),
             clock_counter: 0,
-            accumulator: (0.0, 0.0),
-            sample_count: 0,
-            ticks_per_sample: clock_freq as f32 / sample_rate as f32,
-            buffer: Vec::with_capacity(sample_rate as usize / 50 + 1),
+            blip: [
+                BlipBuffer::new(f64::from(clock_freq), sample_rate),
+                BlipBuffer::new(f64::from(clock_freq), sample_rate),
+            ],
             stereo_mode: StereoMode::Mono,
+            channel_mute: 0,
+            stems: None,
+            clock_hz: clock_freq,
+            log: None,
         }
     }
 
@@ -269,6 +290,11 @@ impl Ay3_8910 {
     pub fn write_data(&mut self, value: u8) {
         let reg = self.selected_reg as usize;
         self.regs[reg] = value;
+        if let Some(log) = &mut self.log
+            && reg < 14
+        {
+            log.record(self.selected_reg, value);
+        }
 
         match reg {
             // Tone periods
@@ -307,8 +333,23 @@ impl Ay3_8910 {
         self.regs[self.selected_reg as usize]
     }
 
+    /// All 16 registers, as last written.
+    #[must_use]
+    pub fn registers(&self) -> [u8; 16] {
+        self.regs
+    }
+
+    /// The selected register index.
+    #[must_use]
+    pub fn selected_register(&self) -> u8 {
+        self.selected_reg
+    }
+
     /// Advance the chip by one input clock cycle.
     pub fn tick(&mut self) {
+        if let Some(log) = &mut self.log {
+            log.tick();
+        }
         self.clock_counter += 1;
 
         // Tone and noise generators clock at input / 8
@@ -325,35 +366,25 @@ impl Ay3_8910 {
         }
 
         // Generate sample
-        let (left, right) = self.mix();
-        self.accumulator.0 += left;
-        self.accumulator.1 += right;
-        self.sample_count += 1;
-
-        if self.sample_count as f32 >= self.ticks_per_sample {
-            let n = self.sample_count as f32;
-            self.buffer
-                .push([self.accumulator.0 / n, self.accumulator.1 / n]);
-            self.accumulator = (0.0, 0.0);
-            self.sample_count = 0;
+        let taps = self.channel_outputs();
+        let (left, right) = self.mix(&taps);
+        self.blip[0].set_level(left);
+        self.blip[1].set_level(right);
+        for blip in &mut self.blip {
+            blip.advance(1);
+        }
+        if let Some(stems) = &mut self.stems {
+            stems.record(&taps, 1);
         }
     }
 
-    /// Mix all three channels through the mixer to produce a stereo sample pair.
-    fn mix(&self) -> (f32, f32) {
+    /// Each channel's output at this instant, as it is heard in mono.
+    #[must_use]
+    pub fn channel_outputs(&self) -> [f32; 3] {
         let mixer = self.regs[7];
-        let mut left = 0.0f32;
-        let mut right = 0.0f32;
+        let mut taps = [0.0; 3];
 
-        // Per-channel panning weights: (left_weight, right_weight)
-        // Panned channels get 1.0 on their side, centre channels get 0.5 each.
-        let pan: [(f32, f32); 3] = match self.stereo_mode {
-            StereoMode::Mono => [(0.5, 0.5), (0.5, 0.5), (0.5, 0.5)],
-            StereoMode::Acb => [(1.0, 0.0), (0.5, 0.5), (0.0, 1.0)], // A=left, C=right, B=centre
-            StereoMode::Abc => [(1.0, 0.0), (0.5, 0.5), (0.0, 1.0)], // A=left, B=centre, C=right
-        };
-
-        for (ch, &(pan_l, pan_r)) in pan.iter().enumerate() {
+        for (ch, tap) in taps.iter_mut().enumerate() {
             let tone_disabled = mixer & (1 << ch) != 0;
             let noise_disabled = mixer & (1 << (ch + 3)) != 0;
 
@@ -374,27 +405,166 @@ impl Ay3_8910 {
                 0.0
             };
 
-            // Centre each channel around 0.
+            // Centre each channel around 0, then normalise. Mono: max
+            // excursion = 3 × 0.5 × 0.5 = 0.75. Stereo (hard-panned): max
+            // excursion = 1 × 0.5 × 1.0 + 1 × 0.5 × 0.5 = 0.75. Use 0.75 for
+            // consistent headroom across all modes.
             let centred = channel_sample - amplitude * 0.5;
-            left += centred * pan_l;
-            right += centred * pan_r;
+            *tap = centred * 0.5 / 0.75;
         }
+        taps
+    }
 
-        // Normalise. Mono: max excursion = 3 × 0.5 × 0.5 = 0.75.
-        // Stereo (hard-panned): max excursion = 1 × 0.5 × 1.0 + 1 × 0.5 × 0.5 = 0.75.
-        // Use 0.75 for consistent headroom across all modes.
-        (left / 0.75, right / 0.75)
+    /// Pan the unmuted channels to produce a stereo sample pair.
+    fn mix(&self, taps: &[f32; 3]) -> (f32, f32) {
+        let mut left = 0.0f32;
+        let mut right = 0.0f32;
+
+        // Per-channel panning weights: (left_weight, right_weight)
+        // Panned channels get 1.0 on their side, centre channels get 0.5 each.
+        let pan: [(f32, f32); 3] = match self.stereo_mode {
+            StereoMode::Mono => [(0.5, 0.5), (0.5, 0.5), (0.5, 0.5)],
+            StereoMode::Acb => [(1.0, 0.0), (0.5, 0.5), (0.0, 1.0)], // A=left, C=right, B=centre
+            StereoMode::Abc => [(1.0, 0.0), (0.5, 0.5), (0.0, 1.0)], // A=left, B=centre, C=right
+        };
+
+        for (ch, (&tap, &(pan_l, pan_r))) in taps.iter().zip(&pan).enumerate() {
+            if self.channel_mute & (1 << ch) == 0 {
+                // Taps are the mono (0.5) share; scale to each side's weight.
+                left += tap * pan_l * 2.0;
+                right += tap * pan_r * 2.0;
+            }
+        }
+        (left, right)
     }
 
     /// Take the audio output buffer (drains it). Each sample is `[left, right]`.
     pub fn take_buffer(&mut self) -> Vec<[f32; 2]> {
-        std::mem::take(&mut self.buffer)
+        let left = self.blip[0].take_samples();
+        let right = self.blip[1].take_samples();
+        left.into_iter().zip(right).map(|(l, r)| [l, r]).collect()
     }
 
     /// Number of samples in the output buffer.
     #[must_use]
     pub fn buffer_len(&self) -> usize {
-        self.buffer.len()
+        self.blip[0].len()
+    }
+
+    /// Output sample rate.
+    #[must_use]
+    pub fn sample_rate(&self) -> u32 {
+        self.blip[0].sample_rate()
+    }
+
+    /// Change the output sample rate.
+    pub fn set_sample_rate(&mut self, rate: u32) {
+        for blip in &mut self.blip {
+            blip.set_sample_rate(rate);
+        }
+        if let Some(stems) = &mut self.stems {
+            stems.set_sample_rate(rate);
+        }
+    }
+
+    /// Stretch the output rate for dynamic rate control.
+    pub fn set_rate_adjust(&mut self, ratio: f64) {
+        for blip in &mut self.blip {
+            blip.set_rate_adjust(ratio);
+        }
+        if let Some(stems) = &mut self.stems {
+            stems.set_rate_adjust(ratio);
+        }
+    }
+
+    /// Mute channels in the mix: bit n silences `CHANNELS[n]`.
+    pub fn set_channel_mute(&mut self, mask: u8) {
+        self.channel_mute = mask & 0x07;
+    }
+
+    /// Channels currently muted in the mix.
+    #[must_use]
+    pub fn channel_mute(&self) -> u8 {
+        self.channel_mute
+    }
+
+    /// Start or stop capturing each channel as its own mono stream.
+    pub fn set_stems(&mut self, enabled: bool) {
+        self.stems = enabled.then(|| Stems::new(&self.blip[0], &self.channel_outputs()));
+    }
+
+    /// Drain the per-channel streams, one per `CHANNELS` entry, at the
+    /// output rate. Empty while capture is off.
+    pub fn take_stems(&mut self) -> Vec<Vec<f32>> {
+        self.stems.as_mut().map_or_else(Vec::new, Stems::take)
+    }
+
+    /// Start logging writes to R0–R13. The log opens with the current
+    /// value of each, so it plays back from the same state.
+    pub fn start_register_log(&mut self) {
+        let mut log = RegisterLog::new(LoggedChip::Ay38910, self.clock_hz);
+        for (reg, &value) in (0..14).zip(&self.regs) {
+            log.record(reg, value);
+        }
+        self.log = Some(log);
+    }
+
+    /// Stop logging and return the log, if one was running.
+    pub fn stop_register_log(&mut self) -> Option<RegisterLog> {
+        self.log.take()
+    }
+}
+
+impl SaveState for Ay3_8910 {
+    fn save_state(&self, w: &mut StateWriter) {
+        w.write_bytes(&self.regs);
+        w.write_u8(self.selected_reg);
+        for t in &self.tone {
+            w.write_u16(t.period);
+            w.write_u16(t.counter);
+            w.write_bool(t.output);
+        }
+        w.write_u8(self.noise.period);
+        w.write_u8(self.noise.counter);
+        w.write_u32(self.noise.lfsr);
+        w.write_bool(self.noise.output);
+        let e = &self.envelope;
+        w.write_u16(e.period);
+        w.write_u16(e.counter);
+        w.write_u8(e.step);
+        w.write_bool(e.holding);
+        w.write_bool(e.attack);
+        w.write_u8(e.shape);
+        w.write_u32(self.clock_counter);
+        for blip in &self.blip {
+            blip.save_state(w);
+        }
+    }
+
+    fn load_state(&mut self, r: &mut StateReader<'_>) -> Result<(), StateError> {
+        r.read_bytes_into(&mut self.regs)?;
+        self.selected_reg = r.read_u8()?;
+        for t in &mut self.tone {
+            t.period = r.read_u16()?;
+            t.counter = r.read_u16()?;
+            t.output = r.read_bool()?;
+        }
+        self.noise.period = r.read_u8()?;
+        self.noise.counter = r.read_u8()?;
+        self.noise.lfsr = r.read_u32()?;
+        self.noise.output = r.read_bool()?;
+        let e = &mut self.envelope;
+        e.period = r.read_u16()?;
+        e.counter = r.read_u16()?;
+        e.step = r.read_u8()?;
+        e.holding = r.read_bool()?;
+        e.attack = r.read_bool()?;
+        e.shape = r.read_u8()?;
+        self.clock_counter = r.read_u32()?;
+        for blip in &mut self.blip {
+            blip.load_state(r)?;
+        }
+        Ok(())
     }
 }
 
@@ -492,7 +662,7 @@ mod tests {
         ay.write_data(0b0011_1000);
 
         ay.select_register(0);
-        ay.write_data(1);
+        ay.write_data(100);
 
         ay.select_register(11);
         ay.write_data(100);
@@ -591,6 +761,55 @@ mod tests {
         assert!(!buf.is_empty());
     }
 
+    #[test]
+    fn ultrasonic_tone_does_not_alias() {
+        // Period 1 puts tone A at ~110 kHz, far above Nyquist.
+        let mut ay = Ay3_8910::new(AY_CLOCK, SAMPLE_RATE);
+        ay.select_register(7);
+        ay.write_data(0b0011_1110);
+        ay.select_register(0);
+        ay.write_data(1);
+        ay.select_register(8);
+        ay.write_data(0x0F);
+
+        for _ in 0..AY_CLOCK / 10 {
+            ay.tick();
+        }
+
+        let buf = ay.take_buffer();
+        let l = left(&buf);
+        let peak = l[l.len() / 2..].iter().fold(0.0f32, |m, s| m.max(s.abs()));
+        assert!(peak < 0.01, "ultrasonic tone leaked through: {peak}");
+    }
+
+    #[test]
+    fn solo_channel_matches_its_stem() {
+        let mut ay = Ay3_8910::new(AY_CLOCK, SAMPLE_RATE);
+        ay.select_register(7);
+        ay.write_data(0b0011_1100); // Tones A and B on, noise off
+        for (reg, value) in [(0, 100), (2, 150), (8, 0x0F), (9, 0x0F)] {
+            ay.select_register(reg);
+            ay.write_data(value);
+        }
+        ay.set_channel_mute(0b101); // Solo B
+        ay.set_stems(true);
+        for _ in 0..AY_CLOCK / 10 {
+            ay.tick();
+        }
+
+        let l = left(&ay.take_buffer());
+        let stems = ay.take_stems();
+        assert_eq!(stems.len(), Ay3_8910::CHANNELS.len());
+        assert_eq!(stems[1].len(), l.len());
+        // The mix steps up from silence on the first tick; the stem starts
+        // settled, so compare once that step has passed.
+        for (i, (&mix, &stem)) in l.iter().zip(&stems[1]).enumerate().skip(16) {
+            assert!((mix - stem).abs() < 1e-5, "sample {i}: {mix} vs {stem}");
+        }
+        let peak = stems[0].iter().fold(0.0f32, |m, s| m.max(s.abs()));
+        assert!(peak > 0.1, "muted channel A should still have a stem");
+    }
+
     #[test]
     fn take_buffer_drains() {
         let mut ay = Ay3_8910::new(AY_CLOCK, SAMPLE_RATE);
@@ -668,4 +887,26 @@ mod tests {
             "Left channel should be silent (tone C panned right), got {l_energy}"
         );
     }
+
+    #[test]
+    fn register_log_seeds_state_and_skips_io_ports() {
+        let mut ay = Ay3_8910::new(1_773_400, 48_000);
+        ay.select_register(7);
+        ay.write_data(0x38);
+
+        ay.start_register_log();
+        ay.tick();
+        ay.select_register(8);
+        ay.write_data(0x0F);
+        ay.select_register(14);
+        ay.write_data(0xFF); // I/O port, not sound
+        let log = ay.stop_register_log().expect("log was running");
+
+        let writes = log.writes();
+        assert_eq!(writes.len(), 15);
+        assert_eq!((writes[7].register, writes[7].value), (7, 0x38));
+        assert_eq!(writes[14].cycle, 1);
+        assert_eq!((writes[14].register, writes[14].value), (8, 0x0F));
+        assert_eq!(log.clock_hz(), 1_773_400);
+    }
 }
@@ -0,0 +1,423 @@
+//! GDB remote target for the Amiga's 68000-family CPU.
+//!
+//! The register set follows the CPU model: the 18 core registers every
+//! 68000 has, the FPU registers when [`CpuCapabilities::fpu`] says there
+//! is one, and the control and MMU registers `MOVEC` and `PMOVE` reach
+//! under a feature of their own. Registers are numbered in that order and
+//! `target.xml` gives each its number, so `p`/`P` and the `g` packet agree
+//! with `gdb`.
+//!
+//! Memory access covers chip, slow and fast RAM and the Kickstart ROM, as
+//! the CPU sees them with the current overlay. Reads elsewhere return 0
+//! and never touch chip registers or CIAs; writes outside RAM fail.
+
+use std::fmt::Write as _;
+
+use emu_core::gdb::GdbTarget;
+use motorola_68000::cpu::Cpu68000;
+use motorola_68000::fpu::{FpFormat, bytes_to_f64, f64_to_bytes};
+use motorola_68000::model::{CpuCapabilities, TimingClass};
+use motorola_68000::registers::FpReg;
+
+use crate::Amiga;
+
+const CORE_FEATURE: &str = "org.gnu.gdb.m68k.core";
+const FPU_FEATURE: &str = "org.gnu.gdb.coldfire.fp";
+const CONTROL_FEATURE: &str = "org.emu198x.m68k.control";
+
+/// One register as `gdb` sees it.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Reg {
+    D(usize),
+    A(usize),
+    Sr,
+    Pc,
+    Fp(usize),
+    Fpcr,
+    Fpsr,
+    Fpiar,
+    Usp,
+    Ssp,
+    Vbr,
+    Sfc,
+    Dfc,
+    Cacr,
+    Caar,
+    Msp,
+    Tc,
+    /// 68030 64-bit root pointers.
+    Srp64,
+    Crp64,
+    /// 68030 transparent translation registers (stored as ITT0/ITT1).
+    Tt0,
+    Tt1,
+    /// 68040 32-bit root pointers.
+    Srp,
+    Urp,
+    Itt0,
+    Itt1,
+    Dtt0,
+    Dtt1,
+    Mmusr,
+}
+
+impl Reg {
+    fn name(self) -> String {
+        let fixed = match self {
+            Self::D(n) => return format!("d{n}"),
+            Self::A(n @ 0..=5) => return format!("a{n}"),
+            Self::Fp(n) => return format!("fp{n}"),
+            Self::A(6) => "fp",
+            Self::A(_) => "sp",
+            Self::Sr => "ps",
+            Self::Pc => "pc",
+            Self::Fpcr => "fpcontrol",
+            Self::Fpsr => "fpstatus",
+            Self::Fpiar => "fpiaddr",
+            Self::Usp => "usp",
+            Self::Ssp => "ssp",
+            Self::Vbr => "vbr",
+            Self::Sfc => "sfc",
+            Self::Dfc => "dfc",
+            Self::Cacr => "cacr",
+            Self::Caar => "caar",
+            Self::Msp => "msp",
+            Self::Tc => "tc",
+            Self::Srp64 | Self::Srp => "srp",
+            Self::Crp64 => "crp",
+            Self::Urp => "urp",
+            Self::Tt0 => "tt0",
+            Self::Tt1 => "tt1",
+            Self::Itt0 => "itt0",
+            Self::Itt1 => "itt1",
+            Self::Dtt0 => "dtt0",
+            Self::Dtt1 => "dtt1",
+            Self::Mmusr => "mmusr",
+        };
+        fixed.to_string()
+    }
+
+    fn bits(self) -> u32 {
+        match self {
+            Self::Fp(_) => 96,
+            Self::Srp64 | Self::Crp64 => 64,
+            _ => 32,
+        }
+    }
+
+    /// `type` attribute in the target description.
+    fn gdb_type(self) -> &'static str {
+        match self {
+            Self::A(_) | Self::Usp | Self::Ssp | Self::Msp | Self::Vbr => "data_ptr",
+            Self::Pc | Self::Fpiar => "code_ptr",
+            Self::Fp(_) => "float",
+            _ => "int",
+        }
+    }
+}
+
+/// The registers of `cpu`, grouped by target description feature, in
+/// register-number order.
+fn layout(cpu: &Cpu68000) -> Vec<(&'static str, Vec<Reg>)> {
+    let caps: CpuCapabilities = cpu.capabilities();
+    let mut features = Vec::new();
+
+    let mut core: Vec<Reg> = (0..8).map(Reg::D).collect();
+    core.extend((0..8).map(Reg::A));
+    core.extend([Reg::Sr, Reg::Pc]);
+    features.push((CORE_FEATURE, core));
+
+    if caps.fpu {
+        let mut fpu: Vec<Reg> = (0..8).map(Reg::Fp).collect();
+        fpu.extend([Reg::Fpcr, Reg::Fpsr, Reg::Fpiar]);
+        features.push((FPU_FEATURE, fpu));
+    }
+
+    let class = cpu.model.timing_class();
+    let mut control = vec![Reg::Usp, Reg::Ssp];
+    if caps.vbr {
+        control.push(Reg::Vbr);
+    }
+    if caps.movec {
+        control.extend([Reg::Sfc, Reg::Dfc]);
+    }
+    if caps.cacr {
+        control.extend([Reg::Cacr, Reg::Msp]);
+        if class == TimingClass::M68020 {
+            control.push(Reg::Caar);
+        }
+    }
+    if caps.mmu {
+        if class == TimingClass::M68020 {
+            control.extend([Reg::Tc, Reg::Srp64, Reg::Crp64, Reg::Tt0, Reg::Tt1]);
+        } else {
+            control.extend([
+                Reg::Tc,
+                Reg::Srp,
+                Reg::Urp,
+                Reg::Itt0,
+                Reg::Itt1,
+                Reg::Dtt0,
+                Reg::Dtt1,
+            ]);
+        }
+        control.push(Reg::Mmusr);
+    }
+    features.push((CONTROL_FEATURE, control));
+    features
+}
+
+fn register(cpu: &Cpu68000, index: usize) -> Option<Reg> {
+    layout(cpu)
+        .into_iter()
+        .flat_map(|(_, regs)| regs)
+        .nth(index)
+}
+
+fn long(value: &[u8]) -> Option<u32> {
+    Some(u32::from_be_bytes(value.try_into().ok()?))
+}
+
+fn quad(value: &[u8]) -> Option<(u32, u32)> {
+    let (upper, lower) = value.split_at_checked(4)?;
+    Some((long(upper)?, long(lower)?))
+}
+
+fn is_ram(amiga: &Amiga, address: u32) -> bool {
+    let memory = &amiga.memory;
+    let fast_end = memory
+        .fast_ram_base
+        .wrapping_add(memory.fast_ram.len() as u32);
+    let fast = (memory.fast_ram_base..fast_end).contains(&address);
+    let low = address & 0xFF_FFFF;
+    fast || low < 0x20_0000
+        || ((0xC0_0000..0xE0_0000).contains(&low) && !memory.slow_ram.is_empty())
+}
+
+impl GdbTarget for Amiga {
+    fn target_xml(&self) -> String {
+        let mut xml = String::from(
+            "<?xml version=\"1.0\"?>\n\
+             <!DOCTYPE target SYSTEM \"gdb-target.dtd\">\n\
+             <target version=\"1.0\">\n\
+             <architecture>m68k</architecture>\n",
+        );
+        let mut regnum = 0;
+        for (feature, regs) in layout(&self.cpu) {
+            let _ = writeln!(xml, "<feature name=\"{feature}\">");
+            for reg in regs {
+                let _ = writeln!(
+                    xml,
+                    "<reg name=\"{}\" bitsize=\"{}\" type=\"{}\" regnum=\"{regnum}\"/>",
+                    reg.name(),
+                    reg.bits(),
+                    reg.gdb_type()
+                );
+                regnum += 1;
+            }
+            xml.push_str("</feature>\n");
+        }
+        xml.push_str("</target>\n");
+        xml
+    }
+
+    fn read_register(&self, index: usize) -> Option<Vec<u8>> {
+        let regs = &self.cpu.regs;
+        let value = match register(&self.cpu, index)? {
+            Reg::D(n) => regs.d[n],
+            Reg::A(n) => regs.a(n),
+            Reg::Sr => u32::from(regs.sr),
+            // Between instructions `regs.pc` has already moved past the
+            // prefetched opcode.
+            Reg::Pc => self.cpu.instruction_boundary().unwrap_or(regs.pc),
+            Reg::Fp(n) => return Some(f64_to_bytes(regs.fp[n].0, FpFormat::Extended)),
+            Reg::Fpcr => regs.fpcr,
+            Reg::Fpsr => regs.fpsr,
+            Reg::Fpiar => regs.fpiar,
+            Reg::Usp => regs.usp,
+            Reg::Ssp => regs.ssp,
+            Reg::Vbr => regs.vbr,
+            Reg::Sfc => u32::from(regs.sfc),
+            Reg::Dfc => u32::from(regs.dfc),
+            Reg::Cacr => regs.cacr,
+            Reg::Caar => regs.caar,
+            Reg::Msp => regs.msp,
+            Reg::Tc => regs.tc,
+            Reg::Srp64 => return Some([regs.srp_upper, regs.srp].map(u32::to_be_bytes).concat()),
+            Reg::Crp64 => return Some([regs.crp_upper, regs.urp].map(u32::to_be_bytes).concat()),
+            Reg::Srp => regs.srp,
+            Reg::Urp => regs.urp,
+            Reg::Itt0 | Reg::Tt0 => regs.itt0,
+            Reg::Itt1 | Reg::Tt1 => regs.itt1,
+            Reg::Dtt0 => regs.dtt0,
+            Reg::Dtt1 => regs.dtt1,
+            Reg::Mmusr => regs.mmusr,
+        };
+        Some(value.to_be_bytes().to_vec())
+    }
+
+    fn write_register(&mut self, index: usize, value: &[u8]) -> bool {
+        let Some(reg) = register(&self.cpu, index) else {
+            return false;
+        };
+        if let Reg::Fp(n) = reg {
+            if value.len() != 12 {
+                return false;
+            }
+            self.cpu.regs.fp[n] = FpReg(bytes_to_f64(value, FpFormat::Extended));
+            return true;
+        }
+        if let Reg::Srp64 | Reg::Crp64 = reg {
+            let Some((upper, lower)) = quad(value) else {
+                return false;
+            };
+            let regs = &mut self.cpu.regs;
+            if reg == Reg::Srp64 {
+                (regs.srp_upper, regs.srp) = (upper, lower);
+            } else {
+                (regs.crp_upper, regs.urp) = (upper, lower);
+            }
+            return true;
+        }
+        let Some(value) = long(value) else {
+            return false;
+        };
+        if reg == Reg::Pc {
+            let opcode = u16::from_be_bytes([
+                self.memory.read_byte_32(value),
+                self.memory.read_byte_32(value.wrapping_add(1)),
+            ]);
+            self.cpu.jump_to(value, opcode);
+            return true;
+        }
+        let sr_mask = self.cpu.sr_mask();
+        let regs = &mut self.cpu.regs;
+        match reg {
+            Reg::D(n) => regs.d[n] = value,
+            Reg::A(n) => regs.set_a(n, value),
+            Reg::Sr => regs.sr = value as u16 & sr_mask,
+            Reg::Fpcr => regs.fpcr = value,
+            Reg::Fpsr => regs.fpsr = value,
+            Reg::Fpiar => regs.fpiar = value,
+            Reg::Usp => regs.usp = value,
+            Reg::Ssp => regs.ssp = value,
+            Reg::Vbr => regs.vbr = value,
+            Reg::Sfc => regs.sfc = value as u8 & 7,
+            Reg::Dfc => regs.dfc = value as u8 & 7,
+            Reg::Cacr => regs.cacr = value,
+            Reg::Caar => regs.caar = value,
+            Reg::Msp => regs.msp = value,
+            Reg::Tc => regs.tc = value,
+            Reg::Srp => regs.srp = value,
+            Reg::Urp => regs.urp = value,
+            Reg::Itt0 | Reg::Tt0 => regs.itt0 = value,
+            Reg::Itt1 | Reg::Tt1 => regs.itt1 = value,
+            Reg::Dtt0 => regs.dtt0 = value,
+            Reg::Dtt1 => regs.dtt1 = value,
+            Reg::Mmusr => regs.mmusr = value,
+            Reg::Pc | Reg::Fp(_) | Reg::Srp64 | Reg::Crp64 => unreachable!(),
+        }
+        true
+    }
+
+    fn read_memory(&self, address: u32, out: &mut [u8]) {
+        for (i, byte) in out.iter_mut().enumerate() {
+            *byte = self.memory.read_byte_32(address.wrapping_add(i as u32));
+        }
+    }
+
+    fn write_memory(&mut self, address: u32, data: &[u8]) -> bool {
+        let addresses = (0..data.len() as u32).map(|i| address.wrapping_add(i));
+        if !addresses.clone().all(|a| is_ram(self, a)) {
+            return false;
+        }
+        for (a, &byte) in addresses.zip(data) {
+            self.memory.write_byte_32(a, byte);
+        }
+        true
+    }
+
+    fn tick(&mut self) {
+        Amiga::tick(self);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use emu_core::gdb::{Action, GdbSession, GdbTarget};
+    use motorola_68000::fpu::{FpFormat, f64_to_bytes};
+
+    use crate::{Amiga, AmigaChipset, AmigaConfig, AmigaModel, AmigaRegion};
+
+    fn reply(session: &mut GdbSession, amiga: &mut Amiga, packet: &str) -> String {
+        match session.handle(amiga, packet, &mut || false) {
+            Action::Reply(reply) => reply,
+            Action::Close(reply) => reply.unwrap_or_default(),
+        }
+    }
+
+    fn hex(bytes: &[u8]) -> String {
+        bytes.iter().map(|b| format!("{b:02x}")).collect()
+    }
+
+    #[test]
+    fn registers_follow_the_cpu_model() {
+        let a500 = Amiga::new(vec![0; 256 * 1024]);
+        let xml = a500.target_xml();
+        assert!(xml.contains("org.gnu.gdb.m68k.core"));
+        assert!(!xml.contains("fp0"));
+        assert!(xml.contains("<reg name=\"ssp\" bitsize=\"32\" type=\"data_ptr\" regnum=\"19\"/>"));
+        assert_eq!(a500.read_register(20), None);
+
+        let mut a3000 = Amiga::new_with_config(AmigaConfig {
+            model: AmigaModel::A3000,
+            chipset: AmigaChipset::Ecs,
+            region: AmigaRegion::Pal,
+            kickstart: vec![0; 512 * 1024],
+            slow_ram_size: 0,
+            ide_disk: None,
+            scsi_disk: None,
+            pcmcia_card: None,
+        });
+        let xml = a3000.target_xml();
+        assert!(xml.contains("<reg name=\"fp0\" bitsize=\"96\" type=\"float\" regnum=\"18\"/>"));
+        assert!(xml.contains("<reg name=\"crp\" bitsize=\"64\""));
+        assert!(xml.contains("<reg name=\"mmusr\""));
+
+        let mut session = GdbSession::new();
+        let one_and_a_half = hex(&f64_to_bytes(1.5, FpFormat::Extended));
+        let packet = format!("P13={one_and_a_half}");
+        assert_eq!(reply(&mut session, &mut a3000, &packet), "OK");
+        assert_eq!(a3000.cpu.regs.fp[1].0, 1.5);
+        assert_eq!(reply(&mut session, &mut a3000, "p13"), one_and_a_half);
+        assert_eq!(reply(&mut session, &mut a3000, "P3=12345678"), "OK");
+        assert_eq!(a3000.cpu.regs.d[3], 0x1234_5678);
+    }
+
+    #[test]
+    fn steps_and_stops_at_breakpoints_in_ram() {
+        let mut amiga = Amiga::new(vec![0; 256 * 1024]);
+        amiga.memory.overlay = false;
+        let mut session = GdbSession::new();
+        reply(&mut session, &mut amiga, "qSupported:swbreak+");
+
+        // moveq #5,d0; addq.l #1,d0; bra.s *
+        assert_eq!(
+            reply(&mut session, &mut amiga, "M1000,6:7005528060fe"),
+            "OK"
+        );
+        assert_eq!(reply(&mut session, &mut amiga, "m1000,2"), "7005");
+        assert_eq!(reply(&mut session, &mut amiga, "Mf80000,2:4e71"), "E01");
+        assert_eq!(reply(&mut session, &mut amiga, "P11=00001000"), "OK");
+        assert_eq!(reply(&mut session, &mut amiga, "p11"), "00001000");
+
+        assert_eq!(reply(&mut session, &mut amiga, "s"), "S05");
+        assert_eq!(reply(&mut session, &mut amiga, "p11"), "00001002");
+        assert_eq!(reply(&mut session, &mut amiga, "p0"), "00000005");
+
+        assert_eq!(reply(&mut session, &mut amiga, "Z0,1004,2"), "OK");
+        assert_eq!(reply(&mut session, &mut amiga, "c"), "T05swbreak:;");
+        assert_eq!(reply(&mut session, &mut amiga, "p11"), "00001004");
+        assert_eq!(reply(&mut session, &mut amiga, "p0"), "00000006");
+    }
+}
@@ -6,6 +6,7 @@
 
 pub mod bus;
 pub mod config;
+mod gdb;
 #[cfg(feature = "native")]
 pub mod mcp;
 pub mod memory;
@@ -48,8 +49,16 @@ pub use commodore_ramsey;
 pub use drive_amiga_floppy;
 pub use format_adf;
 pub use mos_cia_8520;
-use emu_core::{AudioFrame, Machine};
-use motorola_68000::bus::{BusStatus, FunctionCode, M68kBus};
+use std::time::Duration;
+
+use emu_core::bench::ChipProfiler;
+use emu_core::breakpoint::Debuggable;
+use emu_core::trace::{self, Tracer};
+use emu_core::{
+    AudioFrame, BlipBuffer, BusAccess, Machine, SaveState, StateError, StateReader, StateWriter,
+    Stems,
+};
+use motorola_68000::bus::{BusStatus, FunctionCode, LoggingM68kBus, M68kBus};
 pub use peripheral_amiga_keyboard;
 
 /// Standard Amiga PAL Master Crystal Frequency (Hz)
@@ -67,10 +76,24 @@ pub const TICKS_PER_ECLOCK: u64 = 40;
 pub const PAL_FRAME_TICKS: u64 = (commodore_agnus_ocs::PAL_CCKS_PER_LINE as u64)
     * (commodore_agnus_ocs::PAL_LINES_PER_FRAME as u64)
     * TICKS_PER_CCK;
-/// Paula audio sample rate exposed to host runners.
+/// Default Paula audio sample rate exposed to host runners.
 pub const AUDIO_SAMPLE_RATE: u32 = 48_000;
+/// Rate of the embedded drive sound recordings.
+const DRIVE_SOUND_RATE: u32 = 48_000;
+/// Machine tag in save-state headers.
+const STATE_TAG: &str = "amiga";
 const PAL_CCK_HZ: u64 = PAL_CRYSTAL_HZ / TICKS_PER_CCK;
 
+/// Chips timed by the profiler, in the order the tick loop laps them.
+const CHIPS: &[&str] = &[
+    "commodore-denise",
+    "commodore-agnus",
+    "commodore-paula-8364",
+    "blitter",
+    "motorola-680x0",
+    "mos-cia-8520",
+];
+
 /// CPU clock mode. Models that derive their clock from the system
 /// crystal use `CrystalDerived`; models with an independent CPU
 /// oscillator (A3000, A4000) use `Independent`.
@@ -87,7 +110,7 @@ enum CpuClockMode {
     },
 }
 
-#[derive(Debug, Clone)]
+#[derive(Debug, Default, Clone)]
 struct DiskDmaRuntime {
     data: Vec<u8>,
     byte_index: usize,
@@ -97,6 +120,27 @@ struct DiskDmaRuntime {
     wordsync_waiting: bool,
 }
 
+impl SaveState for DiskDmaRuntime {
+    fn save_state(&self, w: &mut StateWriter) {
+        w.write_bytes(&self.data);
+        w.write_usize(self.byte_index);
+        w.write_u32(self.words_remaining);
+        w.write_bool(self.is_write);
+        w.write_bool(self.wordsync_enabled);
+        w.write_bool(self.wordsync_waiting);
+    }
+
+    fn load_state(&mut self, r: &mut StateReader<'_>) -> Result<(), StateError> {
+        self.data = r.read_bytes()?.to_vec();
+        self.byte_index = r.read_usize()?;
+        self.words_remaining = r.read_u32()?;
+        self.is_write = r.read_bool()?;
+        self.wordsync_enabled = r.read_bool()?;
+        self.wordsync_waiting = r.read_bool()?;
+        Ok(())
+    }
+}
+
 /// Coarse ECS sync-window state in the emulator's current beam units.
 ///
 /// This is intended for debug/test visibility while fuller ECS sync generation
@@ -158,6 +202,11 @@ pub struct DriveSoundGenerator {
     // Event tracking
     prev_step_counter: u32,
 
+    // Resampling from the recordings' 48 kHz to the output rate
+    sample_rate: u32,
+    resample_phase: u32,
+    current: f32,
+
     pub enabled: bool,
 }
 
@@ -191,7 +240,7 @@ mod drive_samples {
 }
 
 impl DriveSoundGenerator {
-    fn new(_sample_rate: u32) -> Self {
+    fn new(sample_rate: u32) -> Self {
         Self {
             click_pos: 0,
             click_playing: false,
@@ -199,10 +248,18 @@ impl DriveSoundGenerator {
             motor_envelope: 0.0,
             motor_target: 0.0,
             prev_step_counter: 0,
+            sample_rate,
+            resample_phase: 0,
+            current: 0.0,
             enabled: true,
         }
     }
 
+    fn set_sample_rate(&mut self, sample_rate: u32) {
+        self.sample_rate = sample_rate;
+        self.resample_phase = 0;
+    }
+
     /// Read drive state and fire sound events. Call once per audio sample.
     fn update_state(&mut self, motor_spinning: bool, step_counter: u32) {
         self.motor_target = if motor_spinning { 1.0 } else { 0.0 };
@@ -215,8 +272,19 @@ impl DriveSoundGenerator {
         }
     }
 
-    /// Generate one mono sample. Returns 0.0 when disabled.
+    /// Generate one mono sample at the output rate. The recordings are
+    /// mechanical noise, so holding the nearest 48 kHz sample is enough.
     fn generate_sample(&mut self) -> f32 {
+        self.resample_phase += DRIVE_SOUND_RATE;
+        while self.resample_phase >= self.sample_rate {
+            self.resample_phase -= self.sample_rate;
+            self.current = self.next_recorded_sample();
+        }
+        self.current
+    }
+
+    /// Step the recordings by one 48 kHz sample. Returns 0.0 when disabled.
+    fn next_recorded_sample(&mut self) -> f32 {
         if !self.enabled {
             return 0.0;
         }
@@ -258,6 +326,32 @@ impl DriveSoundGenerator {
     }
 }
 
+/// Playback positions only; `enabled` is a host preference and is kept.
+impl SaveState for DriveSoundGenerator {
+    fn save_state(&self, w: &mut StateWriter) {
+        w.write_usize(self.click_pos);
+        w.write_bool(self.click_playing);
+        w.write_usize(self.motor_pos);
+        w.write_f32(self.motor_envelope);
+        w.write_f32(self.motor_target);
+        w.write_u32(self.prev_step_counter);
+        w.write_u32(self.resample_phase);
+        w.write_f32(self.current);
+    }
+
+    fn load_state(&mut self, r: &mut StateReader<'_>) -> Result<(), StateError> {
+        self.click_pos = r.read_usize()?;
+        self.click_playing = r.read_bool()?;
+        self.motor_pos = r.read_usize()?;
+        self.motor_envelope = r.read_f32()?;
+        self.motor_target = r.read_f32()?;
+        self.prev_step_counter = r.read_u32()?;
+        self.resample_phase = r.read_u32()?;
+        self.current = r.read_f32()?;
+        Ok(())
+    }
+}
+
 /// Coarse ECS beam output pin state derived from the latched sync/blank model.
 ///
 /// This is debug/test-facing and intentionally approximate while fuller ECS
@@ -311,6 +405,57 @@ pub struct BeamDebugSnapshot {
     pub fb_coords: Option<(u32, u32)>,
 }
 
+/// The previous CCK's snapshot feeds sync edge detection for the CIA TOD
+/// inputs, so it is part of the machine state.
+impl SaveState for BeamDebugSnapshot {
+    fn save_state(&self, w: &mut StateWriter) {
+        w.write_u16(self.vpos);
+        w.write_u16(self.hpos_cck);
+        w.write_bool(self.sync.hsync);
+        w.write_bool(self.sync.vsync);
+        w.write_bool(self.composite_sync.active);
+        w.write_bool(self.composite_sync.redirected);
+        w.write_u8(match self.composite_sync.mode {
+            BeamCompositeSyncMode::HardwiredHvOr => 0,
+            BeamCompositeSyncMode::VariableXorSync => 1,
+        });
+        w.write_bool(self.hblank);
+        w.write_bool(self.vblank);
+        w.write_bool(self.pins.hsync_high);
+        w.write_bool(self.pins.vsync_high);
+        w.write_bool(self.pins.csync_high);
+        w.write_bool(self.pins.blank_active);
+        w.write_bool(self.fb_coords.is_some());
+        let (x, y) = self.fb_coords.unwrap_or_default();
+        w.write_u32(x);
+        w.write_u32(y);
+    }
+
+    fn load_state(&mut self, r: &mut StateReader<'_>) -> Result<(), StateError> {
+        self.vpos = r.read_u16()?;
+        self.hpos_cck = r.read_u16()?;
+        self.sync.hsync = r.read_bool()?;
+        self.sync.vsync = r.read_bool()?;
+        self.composite_sync.active = r.read_bool()?;
+        self.composite_sync.redirected = r.read_bool()?;
+        self.composite_sync.mode = match r.read_u8()? {
+            0 => BeamCompositeSyncMode::HardwiredHvOr,
+            1 => BeamCompositeSyncMode::VariableXorSync,
+            n => return Err(StateError::Invalid(format!("composite sync mode {n}"))),
+        };
+        self.hblank = r.read_bool()?;
+        self.vblank = r.read_bool()?;
+        self.pins.hsync_high = r.read_bool()?;
+        self.pins.vsync_high = r.read_bool()?;
+        self.pins.csync_high = r.read_bool()?;
+        self.pins.blank_active = r.read_bool()?;
+        let has_coords = r.read_bool()?;
+        let coords = (r.read_u32()?, r.read_u32()?);
+        self.fb_coords = has_coords.then_some(coords);
+        Ok(())
+    }
+}
+
 #[derive(Debug, Default, Clone, Copy, PartialEq, Eq)]
 pub struct BeamPixelOutputDebug {
     pub vpos: u16,
@@ -462,7 +607,11 @@ pub struct Amiga {
     pub vertb_count: u64,
     /// Debug counter: number of CIA-A TOD pulses.
     pub cia_a_tod_pulse_count: u64,
-    audio_sample_phase: u64,
+    /// Band-limited Paula output (left, right), clocked per CCK.
+    audio_blip: [BlipBuffer; 2],
+    /// Per-channel Paula capture, while enabled. Taken before the output
+    /// low-pass filter.
+    audio_stems: Option<Stems>,
     audio_buffer: Vec<f32>,
     /// RC low-pass filter state (left, right) for hardware output stage.
     audio_lpf_left: f32,
@@ -523,6 +672,12 @@ pub struct Amiga {
     /// Indexed: 0=sec_lo, 1=sec_hi, 2=min_lo, 3=min_hi, 4=hr_lo, 5=hr_hi,
     /// 6=day_lo, 7=day_hi, 8=month_lo, 9=month_hi, 10=year_lo, 11=year_hi.
     pub rtc_time: [u8; 12],
+    /// CPU bus cycles logged for watchpoints, while logging is enabled.
+    bus_log: Option<Vec<BusAccess>>,
+    /// Instruction trace being recorded, if any.
+    tracer: Option<Tracer>,
+    /// Per-chip tick timing, while benchmarking.
+    profiler: ChipProfiler,
 }
 
 impl Amiga {
@@ -750,16 +905,15 @@ impl Amiga {
             },
             vertb_count: 0,
             cia_a_tod_pulse_count: 0,
-            audio_sample_phase: 0,
+            audio_blip: [
+                BlipBuffer::new(PAL_CCK_HZ as f64, AUDIO_SAMPLE_RATE),
+                BlipBuffer::new(PAL_CCK_HZ as f64, AUDIO_SAMPLE_RATE),
+            ],
+            audio_stems: None,
             audio_buffer: Vec::with_capacity((AUDIO_SAMPLE_RATE as usize / 50) * 4),
             audio_lpf_left: 0.0,
             audio_lpf_right: 0.0,
-            // RC low-pass at ~4500 Hz, matching the Amiga's hardware output filter.
-            // alpha = omega / (1 + omega), omega = 2π × 4500 / 48000 ≈ 0.589
-            audio_lpf_alpha: {
-                let omega = 2.0 * std::f32::consts::PI * 4500.0 / AUDIO_SAMPLE_RATE as f32;
-                omega / (1.0 + omega)
-            },
+            audio_lpf_alpha: audio_lpf_alpha(AUDIO_SAMPLE_RATE),
             disk_dma_runtime: None,
             sprite_dma_phase: [0; 8],
             beam_debug_snapshot: BeamDebugSnapshot::default(),
@@ -782,6 +936,9 @@ impl Amiga {
             rtc_control: [0; 3],
             rtc_time_latched: false,
             rtc_time: [0; 12],
+            bus_log: None,
+            tracer: None,
+            profiler: ChipProfiler::new(CHIPS),
         }
     }
 
@@ -842,6 +999,9 @@ impl Amiga {
         self.master_clock = 0;
         self.vertb_count = 0;
         self.cia_a_tod_pulse_count = 0;
+        for blip in &mut self.audio_blip {
+            blip.clear();
+        }
         self.audio_buffer.clear();
         self.audio_lpf_left = 0.0;
         self.audio_lpf_right = 0.0;
@@ -949,6 +1109,7 @@ impl Amiga {
 
     pub fn tick(&mut self) {
         self.master_clock += 1;
+        self.profiler.start();
 
         if self.master_clock.is_multiple_of(TICKS_PER_CCK) {
             let vpos = self.agnus.vpos;
@@ -1142,6 +1303,8 @@ impl Amiga {
                 }
             }
 
+            self.profiler.lap(0);
+
             // --- DMA slots ---
             let bus_plan = self.agnus.cck_bus_plan();
             let audio_dma_slot = bus_plan.audio_dma_service_channel;
@@ -1273,6 +1436,8 @@ impl Amiga {
                 }
             }
 
+            self.profiler.lap(1);
+
             self.beam_pixel_outputs_debug = BeamPixelOutputDebug {
                 vpos,
                 hpos_cck: hpos,
@@ -1289,6 +1454,7 @@ impl Amiga {
                 |addr| self.memory.read_chip_byte(addr),
             );
             self.paula.tick_disk_cck();
+            self.profiler.lap(2);
 
             // Coarse blitter scheduler: preserve BUSY across CCKs so Agnus bus
             // arbitration (including nasty-mode CPU steals) affects machine
@@ -1326,10 +1492,22 @@ impl Amiga {
                 self.request_blitter_interrupt(source);
             }
 
-            self.audio_sample_phase += u64::from(AUDIO_SAMPLE_RATE);
-            while self.audio_sample_phase >= PAL_CCK_HZ {
-                self.audio_sample_phase -= PAL_CCK_HZ;
-                let (left, right) = self.paula.mix_audio_stereo();
+            self.profiler.lap(3);
+
+            let taps = self.paula.audio_channel_outputs();
+            let (left, right) = self.paula.mix_audio_channels(&taps);
+            self.audio_blip[0].set_level(left);
+            self.audio_blip[1].set_level(right);
+            for blip in &mut self.audio_blip {
+                blip.advance(1);
+            }
+            if let Some(stems) = &mut self.audio_stems {
+                stems.record(&taps, 1);
+            }
+            while let (Some(left), Some(right)) = (
+                self.audio_blip[0].pop_sample(),
+                self.audio_blip[1].pop_sample(),
+            ) {
                 // Apply one-pole RC low-pass filter (~4.5 kHz cutoff)
                 // to match the Amiga's hardware output stage. Paula only.
                 let a = self.audio_lpf_alpha;
@@ -1346,6 +1524,7 @@ impl Amiga {
                 self.audio_buffer.push(self.audio_lpf_left + drive);
                 self.audio_buffer.push(self.audio_lpf_right + drive);
             }
+            self.profiler.lap(2);
 
             self.agnus.tick_cck();
 
@@ -1362,11 +1541,13 @@ impl Amiga {
             {
                 service_dmac_dma(dmac, &mut self.memory);
             }
+            self.profiler.lap(1);
         }
 
         // Tick the CPU. Crystal-derived clocks scale the master clock to
         // match the model's frequency. Independent clocks use a Bresenham
         // accumulator to advance the CPU at the correct rate.
+        let bus_log_mark = self.bus_log.as_ref().map_or(0, Vec::len);
         match &mut self.cpu_clock_mode {
             CpuClockMode::CrystalDerived { divisor } => {
                 let cpu_clock = self.master_clock * (TICKS_PER_CPU / *divisor);
@@ -1405,7 +1586,12 @@ impl Amiga {
                     rtc_time: &mut self.rtc_time,
                     rtc_time_latched: &mut self.rtc_time_latched,
                 };
-                self.cpu.tick(&mut bus, cpu_clock);
+                if let Some(log) = trace::cpu_bus_log(&mut self.bus_log, &mut self.tracer) {
+                    self.cpu
+                        .tick(&mut LoggingM68kBus::new(&mut bus, log), cpu_clock);
+                } else {
+                    self.cpu.tick(&mut bus, cpu_clock);
+                }
             }
             CpuClockMode::Independent {
                 freq_hz,
@@ -1459,10 +1645,21 @@ impl Amiga {
                     // Scale clock to CPU bus-cycle domain: the 68000
                     // tick() gates on clock % 4 == 0, so multiply by 4
                     // so every Bresenham step maps to one bus cycle.
-                    self.cpu.tick(&mut bus, *clock * TICKS_PER_CPU);
+                    if let Some(log) = trace::cpu_bus_log(&mut self.bus_log, &mut self.tracer) {
+                        self.cpu.tick(
+                            &mut LoggingM68kBus::new(&mut bus, log),
+                            *clock * TICKS_PER_CPU,
+                        );
+                    } else {
+                        self.cpu.tick(&mut bus, *clock * TICKS_PER_CPU);
+                    }
                 }
             }
         }
+        if self.tracer.is_some() {
+            self.trace_cpu_tick(bus_log_mark);
+        }
+        self.profiler.lap(4);
 
         let motherboard_external_irq = self.motherboard_external_irq_pending();
         if motherboard_external_irq && !self.motherboard_external_irq_prev {
@@ -1516,6 +1713,7 @@ impl Amiga {
             if let Some(byte) = self.keyboard.tick() {
                 self.cia_a.receive_serial_byte(byte);
             }
+            self.profiler.lap(5);
         }
 
         // Serial port countdowns run at CCK rate (≈3.58 MHz).
@@ -1630,11 +1828,60 @@ impl Amiga {
         }
     }
 
+    /// Master crystal frequency for the region.
+    fn master_hz(&self) -> u64 {
+        if self.region == AmigaRegion::Pal {
+            PAL_CRYSTAL_HZ
+        } else {
+            NTSC_CRYSTAL_HZ
+        }
+    }
+
     /// Drain interleaved stereo audio samples (`f32`, `L,R,...`).
     pub fn take_audio_buffer(&mut self) -> Vec<f32> {
         std::mem::take(&mut self.audio_buffer)
     }
 
+    /// Output sample rate of `take_audio_buffer`.
+    #[must_use]
+    pub fn audio_sample_rate(&self) -> u32 {
+        self.audio_blip[0].sample_rate()
+    }
+
+    /// Change the output sample rate (48 kHz by default).
+    pub fn set_audio_sample_rate(&mut self, rate: u32) {
+        for blip in &mut self.audio_blip {
+            blip.set_sample_rate(rate);
+        }
+        self.audio_lpf_alpha = audio_lpf_alpha(rate);
+        self.drive_sounds.set_sample_rate(rate);
+        if let Some(stems) = &mut self.audio_stems {
+            stems.set_sample_rate(rate);
+        }
+    }
+
+    /// Stretch the output rate for dynamic rate control.
+    pub fn set_audio_rate_adjust(&mut self, ratio: f64) {
+        for blip in &mut self.audio_blip {
+            blip.set_rate_adjust(ratio);
+        }
+        if let Some(stems) = &mut self.audio_stems {
+            stems.set_rate_adjust(ratio);
+        }
+    }
+
+    /// Start or stop capturing each Paula channel as its own stream.
+    pub fn set_stem_capture(&mut self, enabled: bool) {
+        self.audio_stems = enabled
+            .then(|| Stems::new(&self.audio_blip[0], &self.paula.audio_channel_outputs()));
+    }
+
+    /// Drain the per-channel streams, one per `Paula8364::AUDIO_CHANNELS`
+    /// entry. Empty while capture is off.
+    pub fn take_audio_stems(&mut self) -> Vec<Vec<f32>> {
+        self.audio_stems.as_mut().map_or_else(Vec::new, Stems::take)
+    }
+
     /// Current state of the power and drive activity LEDs.
     pub fn indicator_state(&self) -> IndicatorState {
         let pra = self.cia_a.port_a_output();
@@ -1670,6 +1917,187 @@ impl Amiga {
         self.floppy.save_adf()
     }
 
+    /// Serialise the complete machine state, including any writable disk
+    /// images (ADF, IDE, SCSI, PCMCIA SRAM).
+    ///
+    /// Debug snapshots and the host-side serial receive queue are not
+    /// included.
+    #[must_use]
+    pub fn save_state(&self) -> Vec<u8> {
+        let mut w = StateWriter::with_header(STATE_TAG);
+        w.write_u8(self.model as u8);
+        w.write_u8(self.chipset as u8);
+        w.write_u8(self.region as u8);
+        w.write_u64(self.master_clock);
+        if let CpuClockMode::Independent { phase, clock, .. } = self.cpu_clock_mode {
+            w.write_u64(phase);
+            w.write_u64(clock);
+        }
+        self.cpu.save_state(&mut w);
+        self.agnus.save_state(&mut w);
+        self.memory.save_state(&mut w);
+        self.denise.save_state(&mut w);
+        self.copper.save_state(&mut w);
+        self.cia_a.save_state(&mut w);
+        self.cia_b.save_state(&mut w);
+        self.paula.save_state(&mut w);
+        self.floppy.save_state(&mut w);
+        self.keyboard.save_state(&mut w);
+        w.write_u8(self.mouse_x);
+        w.write_u8(self.mouse_y);
+        w.write_u16(self.joy1dat);
+        w.write_u8(self.input_buttons);
+        w.write_bool(self.cia_a_cra_sp_prev);
+        w.write_bool(self.motherboard_external_irq_prev);
+        w.write_option(self.gayle.as_ref());
+        w.write_option(self.dmac.as_ref());
+        w.write_option(self.ramsey.as_ref());
+        w.write_option(self.fat_gary.as_ref());
+        w.write_option(self.buster.as_ref());
+        w.write_option(self.super_buster.as_ref());
+        w.write_u64(self.vertb_count);
+        w.write_u64(self.cia_a_tod_pulse_count);
+        for blip in &self.audio_blip {
+            blip.save_state(&mut w);
+        }
+        w.write_f32_slice(&self.audio_buffer);
+        w.write_f32(self.audio_lpf_left);
+        w.write_f32(self.audio_lpf_right);
+        w.write_option(self.disk_dma_runtime.as_ref());
+        w.write_bytes(&self.sprite_dma_phase);
+        self.beam_debug_snapshot.save_state(&mut w);
+        write_pending_write(&mut w, self.bplcon0_denise_pending);
+        write_pending_write(&mut w, self.ddfstrt_pending);
+        write_pending_write(&mut w, self.ddfstop_pending);
+        w.write_usize(self.color_pending.len());
+        for &(index, value, countdown, bplcon3) in &self.color_pending {
+            w.write_usize(index);
+            w.write_u16(value);
+            w.write_u8(countdown);
+            w.write_u16(bplcon3);
+        }
+        w.write_bool(self.bpl_dma_vactive_latch);
+        self.drive_sounds.save_state(&mut w);
+        w.write_u16(self.serper);
+        w.write_u16(self.serdatr);
+        w.write_u32(self.serial_shift_countdown);
+        w.write_u32(self.serial_rx_countdown);
+        w.write_u16(self.serial_rx_shift_byte);
+        w.write_bytes(&self.rtc_control);
+        w.write_bool(self.rtc_time_latched);
+        w.write_bytes(&self.rtc_time);
+        w.into_bytes()
+    }
+
+    /// Restore a state produced by [`Amiga::save_state`].
+    ///
+    /// The machine must have the same model, chipset, region, Kickstart
+    /// and expansion hardware. On error the machine is left partially
+    /// restored and should be reset.
+    pub fn load_state(&mut self, data: &[u8]) -> Result<(), StateError> {
+        let mut r = StateReader::with_header(data, STATE_TAG)?;
+        if r.read_u8()? != self.model as u8
+            || r.read_u8()? != self.chipset as u8
+            || r.read_u8()? != self.region as u8
+        {
+            return Err(StateError::Invalid("Amiga configuration mismatch".into()));
+        }
+        self.master_clock = r.read_u64()?;
+        if let CpuClockMode::Independent { phase, clock, .. } = &mut self.cpu_clock_mode {
+            *phase = r.read_u64()?;
+            *clock = r.read_u64()?;
+        }
+        self.cpu.load_state(&mut r)?;
+        self.agnus.load_state(&mut r)?;
+        self.memory.load_state(&mut r)?;
+        self.denise.load_state(&mut r)?;
+        self.copper.load_state(&mut r)?;
+        self.cia_a.load_state(&mut r)?;
+        self.cia_b.load_state(&mut r)?;
+        self.paula.load_state(&mut r)?;
+        self.floppy.load_state(&mut r)?;
+        self.keyboard.load_state(&mut r)?;
+        self.mouse_x = r.read_u8()?;
+        self.mouse_y = r.read_u8()?;
+        self.joy1dat = r.read_u16()?;
+        self.input_buttons = r.read_u8()?;
+        self.cia_a_cra_sp_prev = r.read_bool()?;
+        self.motherboard_external_irq_prev = r.read_bool()?;
+        r.read_option(self.gayle.as_mut(), "a Gayle")?;
+        r.read_option(self.dmac.as_mut(), "an SDMAC")?;
+        r.read_option(self.ramsey.as_mut(), "a Ramsey")?;
+        r.read_option(self.fat_gary.as_mut(), "a Fat Gary")?;
+        r.read_option(self.buster.as_mut(), "a Buster")?;
+        r.read_option(self.super_buster.as_mut(), "a Super Buster")?;
+        self.vertb_count = r.read_u64()?;
+        self.cia_a_tod_pulse_count = r.read_u64()?;
+        for blip in &mut self.audio_blip {
+            blip.load_state(&mut r)?;
+        }
+        self.audio_buffer = r.read_f32_vec()?;
+        self.audio_lpf_left = r.read_f32()?;
+        self.audio_lpf_right = r.read_f32()?;
+        self.disk_dma_runtime = r.read_option_value()?;
+        r.read_bytes_into(&mut self.sprite_dma_phase)?;
+        self.beam_debug_snapshot.load_state(&mut r)?;
+        self.bplcon0_denise_pending = read_pending_write(&mut r)?;
+        self.ddfstrt_pending = read_pending_write(&mut r)?;
+        self.ddfstop_pending = read_pending_write(&mut r)?;
+        let color_pending_len = r.read_usize()?;
+        self.color_pending.clear();
+        for _ in 0..color_pending_len {
+            let index = r.read_usize()?;
+            if index >= 32 {
+                return Err(StateError::Invalid("color register out of range".into()));
+            }
+            self.color_pending
+                .push((index, r.read_u16()?, r.read_u8()?, r.read_u16()?));
+        }
+        self.bpl_dma_vactive_latch = r.read_bool()?;
+        self.drive_sounds.load_state(&mut r)?;
+        self.serper = r.read_u16()?;
+        self.serdatr = r.read_u16()?;
+        self.serial_shift_countdown = r.read_u32()?;
+        self.serial_rx_countdown = r.read_u32()?;
+        self.serial_rx_shift_byte = r.read_u16()?;
+        r.read_bytes_into(&mut self.rtc_control)?;
+        self.rtc_time_latched = r.read_bool()?;
+        r.read_bytes_into(&mut self.rtc_time)?;
+        r.finish()
+    }
+
+    /// Attach an instruction trace recorder (or detach with `None`).
+    /// Returns the one it replaces.
+    pub fn set_tracer(&mut self, tracer: Option<Tracer>) -> Option<Tracer> {
+        std::mem::replace(&mut self.tracer, tracer)
+    }
+
+    #[must_use]
+    pub fn tracer(&self) -> Option<&Tracer> {
+        self.tracer.as_ref()
+    }
+
+    pub fn tracer_mut(&mut self) -> Option<&mut Tracer> {
+        self.tracer.as_mut()
+    }
+
+    /// Show the tracer the machine after this tick's CPU step. `mark` is
+    /// the debugger log length before the step.
+    fn trace_cpu_tick(&mut self, mark: usize) {
+        if let Some(mut tracer) = self.tracer.take() {
+            let accesses = self.bus_log.as_deref().map_or(&[][..], |log| &log[mark..]);
+            tracer.observe(&*self, accesses, |pc| {
+                emu_disasm::m68k::disassemble(
+                    |addr| self.memory.read_byte_32(addr),
+                    pc,
+                    self.cpu.model,
+                )
+                .bytes
+            });
+            self.tracer = Some(tracer);
+        }
+    }
+
     /// Queue an Amiga keyboard event (raw Amiga keycode).
     pub fn key_event(&mut self, keycode: u8, pressed: bool) {
         self.keyboard.key_event(keycode, pressed);
@@ -2636,6 +3064,27 @@ impl emu_core::Observable for Amiga {
     }
 }
 
+impl Debuggable for Amiga {
+    fn instruction_boundary(&self) -> Option<u32> {
+        self.cpu.instruction_boundary()
+    }
+
+    fn raster_position(&self) -> (u32, u32) {
+        let agnus = self.agnus.as_inner();
+        (u32::from(agnus.vpos), u32::from(agnus.hpos))
+    }
+
+    fn set_bus_logging(&mut self, enabled: bool) {
+        self.bus_log = enabled.then(Vec::new);
+    }
+
+    fn drain_bus_accesses(&mut self, out: &mut Vec<BusAccess>) {
+        if let Some(log) = &mut self.bus_log {
+            out.append(log);
+        }
+    }
+}
+
 pub struct AmigaBusWrapper<'a> {
     pub model: AmigaModel,
     pub chipset: AmigaChipset,
@@ -2679,6 +3128,13 @@ pub struct AmigaBusWrapper<'a> {
 /// buffer at once (instantaneous) because there are no cycle-stealing
 /// effects to model on the A3000's 32-bit bus. The ACR (address counter)
 /// and WTC (word transfer count) registers track progress.
+/// Coefficient of the ~4.5 kHz RC low-pass matching the Amiga's hardware
+/// output filter: alpha = omega / (1 + omega), omega = 2π × 4500 / rate.
+fn audio_lpf_alpha(sample_rate: u32) -> f32 {
+    let omega = 2.0 * std::f32::consts::PI * 4500.0 / sample_rate as f32;
+    omega / (1.0 + omega)
+}
+
 fn service_dmac_dma(dmac: &mut Dmac390537, memory: &mut Memory) {
     let remaining = dmac.dma_bytes_remaining();
     if remaining == 0 {
@@ -4038,6 +4494,21 @@ fn execute_blit_line(agnus: &mut Agnus, memory: &mut Memory) -> BlitterInterrupt
     BlitterInterruptSource::LineCore
 }
 
+/// Write a pending register write (value, CCK countdown).
+fn write_pending_write(w: &mut StateWriter, pending: Option<(u16, u8)>) {
+    w.write_bool(pending.is_some());
+    let (value, countdown) = pending.unwrap_or_default();
+    w.write_u16(value);
+    w.write_u8(countdown);
+}
+
+/// Read a pending register write written by `write_pending_write`.
+fn read_pending_write(r: &mut StateReader<'_>) -> Result<Option<(u16, u8)>, StateError> {
+    let present = r.read_bool()?;
+    let pending = (r.read_u16()?, r.read_u8()?);
+    Ok(present.then_some(pending))
+}
+
 impl Machine for Amiga {
     fn run_frame(&mut self) {
         self.run_frame();
@@ -4062,13 +4533,74 @@ impl Machine for Amiga {
             .collect()
     }
 
+    fn audio_sample_rate(&self) -> u32 {
+        self.audio_sample_rate()
+    }
+
+    fn set_audio_sample_rate(&mut self, rate: u32) -> bool {
+        self.set_audio_sample_rate(rate);
+        true
+    }
+
+    fn set_audio_rate_adjust(&mut self, ratio: f64) {
+        self.set_audio_rate_adjust(ratio);
+    }
+
+    fn audio_channels(&self) -> Vec<&'static str> {
+        Paula8364::AUDIO_CHANNELS.to_vec()
+    }
+
+    fn set_channel_mute(&mut self, mask: u32) {
+        self.paula.set_audio_channel_mute(mask as u8);
+    }
+
+    fn channel_mute(&self) -> u32 {
+        u32::from(self.paula.audio_channel_mute())
+    }
+
+    fn set_stem_capture(&mut self, enabled: bool) {
+        self.set_stem_capture(enabled);
+    }
+
+    fn take_audio_stems(&mut self) -> Vec<Vec<f32>> {
+        self.take_audio_stems()
+    }
+
     fn frame_count(&self) -> u64 {
         self.vertb_count
     }
 
+    fn cpu_clock_hz(&self) -> f64 {
+        match self.cpu_clock_mode {
+            CpuClockMode::CrystalDerived { divisor } => (self.master_hz() / divisor) as f64,
+            CpuClockMode::Independent { freq_hz, .. } => freq_hz as f64,
+        }
+    }
+
+    fn frame_rate_hz(&self) -> f64 {
+        self.master_hz() as f64 / PAL_FRAME_TICKS as f64
+    }
+
+    fn set_chip_profiling(&mut self, enabled: bool) -> bool {
+        self.profiler.set_enabled(enabled);
+        true
+    }
+
+    fn chip_times(&self) -> Vec<(&'static str, Duration)> {
+        self.profiler.times()
+    }
+
     fn reset(&mut self) {
         self.soft_reset();
     }
+
+    fn save_state(&self) -> Result<Vec<u8>, StateError> {
+        Ok(self.save_state())
+    }
+
+    fn load_state(&mut self, data: &[u8]) -> Result<(), StateError> {
+        self.load_state(data)
+    }
 }
 
 #[cfg(test)]
@@ -4078,6 +4610,7 @@ mod tests {
         BeamCompositeSyncDebug, BeamCompositeSyncMode, BeamDebugSnapshot, BeamEdgeFlags,
         BeamPinState, BeamSyncState, BlitterInterruptSource, TICKS_PER_CCK,
     };
+    use emu_core::{Machine, StateError};
     use motorola_68000::bus::{BusStatus, FunctionCode, M68kBus};
 
     fn dummy_kickstart() -> Vec<u8> {
@@ -6012,4 +6545,89 @@ mod tests {
         }
         assert_eq!(amiga.serdatr & 0x00FF, 0x55);
     }
+
+    /// An A500 whose Kickstart loop walks COLOR00 while bitplane and audio
+    /// DMA run, with a blank disk in DF0:.
+    fn make_busy_amiga() -> Amiga {
+        let mut rom = vec![0u8; 256 * 1024];
+        // SSP = $0007FFF0, PC = $F80008.
+        rom[..8].copy_from_slice(&[0x00, 0x07, 0xFF, 0xF0, 0x00, 0xF8, 0x00, 0x08]);
+        // loop: MOVE.W D0,$DFF180; ADDQ.W #1,D0; BRA.S loop
+        rom[8..18].copy_from_slice(&[0x33, 0xC0, 0x00, 0xDF, 0xF1, 0x80, 0x52, 0x40, 0x60, 0xF6]);
+
+        let mut amiga = Amiga::new(rom);
+        for (i, byte) in amiga.memory.chip_ram[0x1_0000..0x2_0000]
+            .iter_mut()
+            .enumerate()
+        {
+            *byte = (i as u8).wrapping_mul(37);
+        }
+        for (i, byte) in amiga.memory.chip_ram[0x2_0000..0x2_0080]
+            .iter_mut()
+            .enumerate()
+        {
+            *byte = (i as u8).wrapping_mul(5);
+        }
+        amiga.write_custom_reg(0x08E, 0x2C81); // DIWSTRT
+        amiga.write_custom_reg(0x090, 0x2CC1); // DIWSTOP
+        amiga.write_custom_reg(0x092, 0x0038); // DDFSTRT
+        amiga.write_custom_reg(0x094, 0x00D0); // DDFSTOP
+        amiga.write_custom_reg(0x0E0, 0x0001); // BPL1PTH
+        amiga.write_custom_reg(0x0E2, 0x0000); // BPL1PTL
+        amiga.write_custom_reg(0x100, 0x1200); // BPLCON0: 1 plane, colour
+        amiga.write_custom_reg(0x182, 0x0F00); // COLOR01
+        amiga.write_custom_reg(0x0A0, 0x0002); // AUD0LCH
+        amiga.write_custom_reg(0x0A2, 0x0000); // AUD0LCL
+        amiga.write_custom_reg(0x0A4, 64); // AUD0LEN
+        amiga.write_custom_reg(0x0A6, 200); // AUD0PER
+        amiga.write_custom_reg(0x0A8, 64); // AUD0VOL
+        amiga.write_custom_reg(0x096, 0x8301); // DMACON: DMAEN | BPLEN | AUD0EN
+        let adf = format_adf::Adf::from_bytes(vec![0; format_adf::ADF_SIZE_DD]).expect("blank ADF");
+        amiga.insert_disk(adf);
+        amiga
+    }
+
+    #[test]
+    fn state_restored_mid_frame_continues_identically() {
+        let mut amiga = make_busy_amiga();
+        amiga.run_frame();
+        for _ in 0..123_457 {
+            amiga.tick();
+        }
+        let state = Machine::save_state(&amiga).expect("save");
+
+        let mut restored = make_busy_amiga();
+        Machine::load_state(&mut restored, &state).expect("load");
+
+        for _ in 0..2 {
+            amiga.run_frame();
+            restored.run_frame();
+        }
+        assert_eq!(
+            amiga.denise.framebuffer_raster,
+            restored.denise.framebuffer_raster
+        );
+        assert_eq!(amiga.cpu.regs, restored.cpu.regs);
+        assert_eq!(amiga.master_clock, restored.master_clock);
+        assert_eq!(amiga.take_audio_buffer(), restored.take_audio_buffer());
+    }
+
+    #[test]
+    fn state_from_other_chipset_is_rejected() {
+        let state = make_busy_amiga().save_state();
+        let mut ecs = Amiga::new_with_config(AmigaConfig {
+            model: AmigaModel::A500,
+            chipset: AmigaChipset::Ecs,
+            region: AmigaRegion::Pal,
+            kickstart: dummy_kickstart(),
+            slow_ram_size: 0,
+            ide_disk: None,
+            scsi_disk: None,
+            pcmcia_card: None,
+        });
+        assert!(matches!(
+            ecs.load_state(&state),
+            Err(StateError::Invalid(_))
+        ));
+    }
 }
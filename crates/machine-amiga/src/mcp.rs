@@ -6,16 +6,34 @@
 
 #![allow(clippy::cast_possible_truncation)]
 
+use std::time::Duration;
+
 use base64::Engine;
 use serde_json::Value as JsonValue;
 
-use emu_core::Observable;
-use emu_core::mcp::{self, McpEmulator, ToolDefinition, ToolResult};
+use emu_core::breakpoint::{Breakpoint, Breakpoints, Debuggable, Hit, Trigger};
+use emu_core::mcp::breakpoint::BreakpointSpace;
+use emu_core::mcp::{self, McpEmulator, Notification, RunSession, ToolDefinition, ToolResult};
+use emu_core::symbols::SymbolTable;
+use emu_core::trace::TraceLayout;
+use emu_core::{Machine, Observable};
+use format_hunk::{HunkFile, HunkKind};
 
 use crate::config::{AmigaChipset, AmigaConfig, AmigaModel, AmigaRegion};
 use crate::format_adf::Adf;
+use crate::memory::Memory;
 use crate::{Amiga, PAL_FRAME_TICKS};
 
+/// Layout of `trace_start` lines: 68000 data and address registers plus SR.
+const TRACE_LAYOUT: TraceLayout = TraceLayout {
+    address_digits: 8,
+    opcode_bytes: 10,
+    registers: &[
+        "cpu.d0", "cpu.d1", "cpu.d2", "cpu.d3", "cpu.d4", "cpu.d5", "cpu.d6", "cpu.d7", "cpu.a0",
+        "cpu.a1", "cpu.a2", "cpu.a3", "cpu.a4", "cpu.a5", "cpu.a6", "cpu.a7", "cpu.sr",
+    ],
+};
+
 // ---------------------------------------------------------------------------
 // Public re-export: the MCP server type for main.rs
 // ---------------------------------------------------------------------------
@@ -28,23 +46,24 @@ pub type McpServer = mcp::McpServer<AmigaMcp>;
 
 pub struct AmigaMcp {
     amiga: Option<Amiga>,
+    breakpoints: Breakpoints,
+    run: RunSession,
+    symbols: SymbolTable,
 }
 
 impl AmigaMcp {
     #[must_use]
     pub fn new() -> Self {
-        Self { amiga: None }
+        Self {
+            amiga: None,
+            breakpoints: Breakpoints::new(),
+            run: RunSession::new(),
+            symbols: SymbolTable::new(),
+        }
     }
 
     fn require_amiga(&mut self) -> Result<&mut Amiga, ToolResult> {
-        if let Some(ref mut amiga) = self.amiga {
-            Ok(amiga)
-        } else {
-            Err(ToolResult::Error {
-                code: -32000,
-                message: "No Amiga instance. Call 'boot' first.".to_string(),
-            })
-        }
+        self.amiga.as_mut().ok_or_else(no_amiga)
     }
 }
 
@@ -64,7 +83,7 @@ impl McpEmulator for AmigaMcp {
     }
 
     fn tool_definitions(&self) -> Vec<ToolDefinition> {
-        vec![
+        let mut tools = vec![
             ToolDefinition {
                 name: "boot",
                 description: "Boot the Amiga with a Kickstart ROM",
@@ -119,7 +138,8 @@ impl McpEmulator for AmigaMcp {
                     "type": "object",
                     "properties": {
                         "save_path": { "type": "string", "description": "If set, save PNG to this path and return metadata only" },
-                        "correct_aspect": { "type": "boolean", "description": "Scale to 4:3 display aspect ratio (default: true)" }
+                        "correct_aspect": { "type": "boolean", "description": "Scale to 4:3 display aspect ratio (default: true)" },
+                        "filter": mcp::filter_property()
                     }
                 }),
             },
@@ -130,10 +150,12 @@ impl McpEmulator for AmigaMcp {
                     "type": "object",
                     "properties": {
                         "frames": { "type": "integer", "default": 50 },
-                        "save_path": { "type": "string", "description": "Save WAV to this path" }
+                        "save_path": { "type": "string", "description": "Save WAV to this path" },
+                        "stems": mcp::stems_property()
                     }
                 }),
             },
+            mcp::channel_mute_definition(),
             ToolDefinition {
                 name: "query",
                 description: "Query an observable value (e.g. cpu.pc, agnus.beamcon0, denise.palette.0)",
@@ -167,6 +189,8 @@ impl McpEmulator for AmigaMcp {
                     "required": ["address", "length"]
                 }),
             },
+            mcp::disassemble_definition(),
+            mcp::record_gif_definition(),
             ToolDefinition {
                 name: "poke",
                 description: "Write a byte to memory",
@@ -186,6 +210,7 @@ impl McpEmulator for AmigaMcp {
                     "type": "object",
                     "properties": {
                         "address": { "type": "integer", "description": "24-bit address" },
+                        "condition": { "type": "string", "description": "Only stop when this expression holds, e.g. \"cpu.a == 0x20\"" },
                         "max_frames": { "type": "integer", "default": 10000 }
                     },
                     "required": ["address"]
@@ -232,16 +257,36 @@ impl McpEmulator for AmigaMcp {
                     "properties": {
                         "frames": { "type": "integer", "description": "Number of frames to record" },
                         "save_path": { "type": "string", "description": "Write MP4 to this path" },
-                        "correct_aspect": { "type": "boolean", "description": "Scale to 4:3 display aspect ratio (default: true)" }
+                        "correct_aspect": { "type": "boolean", "description": "Scale to 4:3 display aspect ratio (default: true)" },
+                        "filter": mcp::filter_property()
                     },
                     "required": ["frames", "save_path"]
                 }),
             },
-        ]
+        ];
+        tools.extend(mcp::breakpoint::breakpoint_definitions());
+        tools.extend(mcp::trace::trace_definitions());
+        tools.extend(mcp::run::run_definitions());
+        tools.extend(mcp::symbols::symbol_definitions());
+        tools.push(ToolDefinition {
+            name: "load_hunk_symbols",
+            description: "Load HUNK_SYMBOL labels from an executable, at the addresses its hunks were loaded to",
+            input_schema: serde_json::json!({
+                "type": "object",
+                "properties": {
+                    "path": { "type": "string", "description": "Executable on the host" },
+                    "file": { "type": "string", "description": "Executable on an AmigaDOS disk, e.g. 'c/mygame'" },
+                    "adf": { "type": "string", "description": "ADF holding 'file' (default: the disk in df0)" },
+                    "bases": { "type": "array", "items": { "type": "integer" }, "description": "Hunk load addresses (default: found in memory)" },
+                    "replace": { "type": "boolean", "description": "Drop the labels already loaded", "default": false }
+                }
+            }),
+        });
+        tools
     }
 
     fn dispatch_tool(&mut self, name: &str, arguments: &JsonValue) -> ToolResult {
-        match name {
+        let result = match name {
             "boot" => self.handle_boot(arguments),
             "reset" => self.handle_reset(),
             "run_frames" => self.handle_run_frames(arguments),
@@ -249,23 +294,118 @@ impl McpEmulator for AmigaMcp {
             "step_ticks" => self.handle_step_ticks(arguments),
             "screenshot" => self.handle_screenshot(arguments),
             "audio_capture" => self.handle_audio_capture(arguments),
+            "channel_mute" => match self.require_amiga() {
+                Ok(amiga) => mcp::channel_mute_result(arguments, amiga),
+                Err(e) => e,
+            },
             "query" => self.handle_query(arguments),
             "query_paths" => self.handle_query_paths(arguments),
             "query_memory" => self.handle_query_memory(arguments),
+            "disassemble" => self.handle_disassemble(arguments),
             "poke" => self.handle_poke(arguments),
             "set_breakpoint" => self.handle_set_breakpoint(arguments),
+            "add_breakpoint" => self.handle_add_breakpoint(arguments),
+            "remove_breakpoint" => {
+                mcp::breakpoint::remove_breakpoint_result(arguments, &mut self.breakpoints)
+            }
+            "list_breakpoints" => {
+                mcp::breakpoint::list_breakpoints_result(&self.breakpoints, &self.symbols)
+            }
+            "clear_breakpoints" => mcp::breakpoint::clear_breakpoints_result(&mut self.breakpoints),
+            "run_until_break" => self.handle_run_until_break(arguments),
+            "trace_start" => self.handle_trace_start(arguments),
+            "trace_stop" => self.handle_trace_stop(),
+            "trace_export" => self.handle_trace_export(arguments),
             "insert_disk" => self.handle_insert_disk(arguments),
             "press_key" => self.handle_press_key(arguments),
             "release_key" => self.handle_release_key(arguments),
+            "record_gif" => self.handle_record_gif(arguments),
             "record_video" => self.handle_record_video(arguments),
+            "run" => self.handle_run(arguments),
+            "pause" => self.handle_pause(),
+            "load_symbols" => self.handle_load_symbols(arguments),
+            "load_hunk_symbols" => self.handle_load_hunk_symbols(arguments),
+            "clear_symbols" => mcp::symbols::clear_symbols_result(&mut self.symbols),
+            "lookup_symbol" => mcp::symbols::lookup_symbol_result(arguments, &self.symbols),
             _ => ToolResult::Error {
                 code: -32601,
                 message: format!("Unknown tool: {name}"),
             },
+        };
+        if name == "insert_disk" {
+            self.run.media_tool_done("df0", name, arguments, &result);
         }
+        result
+    }
+
+    fn run_slice(&mut self) -> Option<Duration> {
+        if !self.run.is_running() {
+            return None;
+        }
+        let Some(amiga) = self.amiga.as_mut() else {
+            self.run.stop();
+            return None;
+        };
+
+        // Breakpoints need the machine ticked one step at a time; without
+        // any, run_frame keeps queued input in play.
+        if self.breakpoints.is_empty() {
+            amiga.run_frame();
+        } else if let (Some(hit), _) = run_until_break(amiga, &mut self.breakpoints, 1) {
+            self.run
+                .breakpoint_hit(&hit, &current_pc(amiga), &self.symbols);
+            return None;
+        }
+        self.run.frame_done(amiga.frame_count());
+
+        if amiga.cpu.is_halted() {
+            self.run.cpu_halted(&current_pc(amiga), "double bus fault");
+        }
+        // The drive LED is lit while the motor runs.
+        self.run.drive_led("df0", amiga.floppy.motor_on());
+        self.run.next_slice()
+    }
+
+    fn take_notifications(&mut self) -> Vec<Notification> {
+        self.run.take_notifications()
     }
 }
 
+fn no_amiga() -> ToolResult {
+    ToolResult::Error {
+        code: -32000,
+        message: "No Amiga instance. Call 'boot' first.".to_string(),
+    }
+}
+
+/// Run until a breakpoint stops execution or `max_frames` frames complete.
+/// Returns the hit and the number of frames completed.
+fn run_until_break(
+    amiga: &mut Amiga,
+    breakpoints: &mut Breakpoints,
+    max_frames: u64,
+) -> (Option<Hit>, u64) {
+    let max_ticks = max_frames * PAL_FRAME_TICKS;
+    let mut ticks_run = 0u64;
+    if max_ticks == 0 {
+        return (None, 0);
+    }
+    let hit = breakpoints.run(amiga, |amiga| {
+        amiga.tick();
+        ticks_run += 1;
+        ticks_run < max_ticks
+    });
+    (hit, ticks_run / PAL_FRAME_TICKS)
+}
+
+/// The next instruction's address, or the PC mid-instruction.
+fn current_pc(amiga: &Amiga) -> String {
+    format!(
+        "${:08X}",
+        amiga.instruction_boundary().unwrap_or(amiga.cpu.regs.pc)
+    )
+}
+
 // ---------------------------------------------------------------------------
 // Tool handlers
 // ---------------------------------------------------------------------------
@@ -437,12 +577,17 @@ impl AmigaMcp {
 
         let save_path = params.get("save_path").and_then(|v| v.as_str());
         let display = parse_display_size(params, viewport.width, viewport.height);
+        let filter = match mcp::parse_filter(params, None) {
+            Ok(filter) => filter,
+            Err(e) => return e,
+        };
         mcp::screenshot_result(
             viewport.width,
             viewport.height,
             &viewport.pixels,
             save_path,
             display,
+            filter,
         )
     }
 
@@ -454,13 +599,22 @@ impl AmigaMcp {
 
         let frames = params.get("frames").and_then(|v| v.as_u64()).unwrap_or(50);
 
+        let mut stems = match mcp::StemCapture::start(params, amiga) {
+            Ok(s) => s,
+            Err(e) => return e,
+        };
+
         let mut all_audio: Vec<f32> = Vec::new();
         for _ in 0..frames {
             amiga.run_frame();
             all_audio.extend_from_slice(&amiga.take_audio_buffer());
+            if let Some(stems) = &mut stems {
+                stems.collect(amiga);
+            }
         }
 
-        if let Some(save_path) = params.get("save_path").and_then(|v| v.as_str()) {
+        let save_path = params.get("save_path").and_then(|v| v.as_str());
+        if let Some(save_path) = save_path {
             // Encode as WAV and save directly
             let wav_bytes = match encode_wav_stereo(&all_audio) {
                 Ok(b) => b,
@@ -494,12 +648,19 @@ impl AmigaMcp {
             base64::engine::general_purpose::STANDARD.encode(&wav_bytes)
         };
 
-        ToolResult::Success(serde_json::json!({
+        let mut result = serde_json::json!({
             "format": "wav",
             "samples": all_audio.len() / 2, // stereo pairs
             "frames": frames,
             "data": b64,
-        }))
+        });
+        if let Some(stems) = stems {
+            match stems.finish(amiga, save_path) {
+                Ok(stems) => result["stems"] = stems,
+                Err(e) => return e,
+            }
+        }
+        ToolResult::Success(result)
     }
 
     fn handle_query(&mut self, params: &JsonValue) -> ToolResult {
@@ -598,6 +759,19 @@ impl AmigaMcp {
         }))
     }
 
+    /// Decodes for the configured CPU model, so FPU, MMU and 020+
+    /// instructions only appear on machines that have them.
+    fn handle_disassemble(&mut self, params: &JsonValue) -> ToolResult {
+        let Some(amiga) = self.amiga.as_ref() else {
+            return no_amiga();
+        };
+        let model = amiga.cpu.model;
+        let memory = &amiga.memory;
+        mcp::disassemble_result(params, Some(amiga.cpu.regs.pc), &self.symbols, |address| {
+            emu_disasm::m68k::disassemble(|addr| memory.read_byte_32(addr), address, model)
+        })
+    }
+
     fn handle_poke(&mut self, params: &JsonValue) -> ToolResult {
         let amiga = match self.require_amiga() {
             Ok(a) => a,
@@ -629,47 +803,216 @@ impl AmigaMcp {
     }
 
     fn handle_set_breakpoint(&mut self, params: &JsonValue) -> ToolResult {
-        let amiga = match self.require_amiga() {
-            Ok(a) => a,
-            Err(e) => return e,
+        let Self {
+            amiga,
+            breakpoints,
+            symbols,
+            ..
+        } = self;
+        let Some(amiga) = amiga.as_mut() else {
+            return no_amiga();
         };
 
-        let addr = match params.get("address").and_then(|v| v.as_u64()) {
-            Some(a) if a <= 0x00FF_FFFF => a as u32,
-            _ => {
+        let addr = match mcp::symbols::address_param(params, "address", symbols, 0x00FF_FFFF) {
+            Ok(Some(a)) => a,
+            Ok(None) => {
                 return ToolResult::Error {
                     code: -32602,
                     message: "Missing or invalid 'address' (0-16777215, 24-bit)".to_string(),
                 };
             }
+            Err(e) => return e,
         };
 
-        let max_frames = params
-            .get("max_frames")
-            .and_then(|v| v.as_u64())
-            .unwrap_or(10_000);
+        let condition = match mcp::breakpoint::parse_condition(params, amiga) {
+            Ok(c) => c,
+            Err(e) => return e,
+        };
+        let max_frames = mcp::breakpoint::max_frames_param(params);
 
-        let max_ticks = max_frames * PAL_FRAME_TICKS;
-        let mut ticks_run = 0u64;
-        let mut hit = false;
+        // Run alongside any breakpoints added with add_breakpoint, so
+        // whichever comes first stops execution.
+        let mut breakpoint = Breakpoint::new(Trigger::Execute(addr)).temporary();
+        breakpoint.condition = condition;
+        let id = breakpoints.add(breakpoint);
+        let (hit, frames_run) = run_until_break(amiga, breakpoints, max_frames);
+        breakpoints.remove(id);
 
-        while ticks_run < max_ticks {
-            amiga.tick();
-            ticks_run += 1;
+        ToolResult::Success(serde_json::json!({
+            "hit": hit.is_some(),
+            "breakpoint": hit.as_ref().map(|h| mcp::breakpoint::hit_to_json(h, symbols)),
+            "pc": format!("${:08X}", amiga.instruction_boundary().unwrap_or(amiga.cpu.regs.pc)),
+            "frames_run": frames_run,
+        }))
+    }
 
-            if amiga.cpu.regs.pc == addr && amiga.cpu.is_idle() {
-                hit = true;
-                break;
+    fn handle_add_breakpoint(&mut self, params: &JsonValue) -> ToolResult {
+        let Some(amiga) = self.amiga.as_ref() else {
+            return no_amiga();
+        };
+        mcp::breakpoint::add_breakpoint_result(
+            params,
+            &mut self.breakpoints,
+            amiga,
+            BreakpointSpace::MEMORY_32,
+            &self.symbols,
+        )
+    }
+
+    fn handle_run_until_break(&mut self, params: &JsonValue) -> ToolResult {
+        let Some(amiga) = self.amiga.as_mut() else {
+            return no_amiga();
+        };
+        let max_frames = mcp::breakpoint::max_frames_param(params);
+        let (hit, frames_run) = run_until_break(amiga, &mut self.breakpoints, max_frames);
+        mcp::breakpoint::run_until_break_result(
+            hit.as_ref(),
+            &current_pc(amiga),
+            frames_run,
+            &self.symbols,
+        )
+    }
+
+    fn handle_run(&mut self, params: &JsonValue) -> ToolResult {
+        let Some(amiga) = self.amiga.as_ref() else {
+            return no_amiga();
+        };
+        let fps = if matches!(amiga.region, AmigaRegion::Pal) {
+            50
+        } else {
+            60
+        };
+        self.run.start(params, fps)
+    }
+
+    fn handle_pause(&mut self) -> ToolResult {
+        let position = self.amiga.as_ref().map_or(JsonValue::Null, |amiga| {
+            serde_json::json!({
+                "pc": current_pc(amiga),
+                "frame_count": amiga.frame_count(),
+            })
+        });
+        self.run.pause(position)
+    }
+
+    fn handle_trace_start(&mut self, params: &JsonValue) -> ToolResult {
+        let Some(amiga) = self.amiga.as_mut() else {
+            return no_amiga();
+        };
+        match mcp::trace::parse_tracer(params, TRACE_LAYOUT, &self.symbols) {
+            Ok(tracer) => {
+                let result = mcp::trace::trace_start_result(&tracer);
+                // A trace being replaced still gets its last entry written.
+                if let Some(mut old) = amiga.set_tracer(Some(tracer)) {
+                    let _ = old.stop();
+                }
+                result
             }
+            Err(e) => e,
         }
+    }
 
-        let frames_run = ticks_run / PAL_FRAME_TICKS;
+    fn handle_load_symbols(&mut self, params: &JsonValue) -> ToolResult {
+        let result = mcp::symbols::load_symbols_result(params, &mut self.symbols);
+        self.refresh_trace_symbols();
+        result
+    }
 
-        ToolResult::Success(serde_json::json!({
-            "hit": hit,
-            "pc": format!("${:08X}", if hit { addr } else { amiga.cpu.regs.pc }),
-            "frames_run": frames_run,
-        }))
+    /// A running trace labels the rest of its entries with the new
+    /// symbols too.
+    fn refresh_trace_symbols(&mut self) {
+        if let Some(tracer) = self.amiga.as_mut().and_then(Amiga::tracer_mut) {
+            tracer.set_symbols(self.symbols.clone());
+        }
+    }
+
+    /// Symbols from an executable's `HUNK_SYMBOL` blocks are offsets into
+    /// their hunk, so the hunks have to be found first: hunk 0 by its
+    /// contents in RAM, the rest by following the segment list `LoadSeg`
+    /// built from it (each segment's BPTR to the next sits just before
+    /// its data). `bases` overrides the search.
+    fn handle_load_hunk_symbols(&mut self, params: &JsonValue) -> ToolResult {
+        let Some(amiga) = self.amiga.as_ref() else {
+            return no_amiga();
+        };
+        let exe = match read_executable(params, amiga) {
+            Ok(data) => data,
+            Err(e) => return e,
+        };
+        let file = match HunkFile::parse(&exe) {
+            Ok(f) => f,
+            Err(e) => {
+                return ToolResult::Error {
+                    code: -32000,
+                    message: e,
+                };
+            }
+        };
+
+        let bases = match params.get("bases").and_then(JsonValue::as_array) {
+            Some(list) => {
+                let parsed: Option<Vec<u32>> = list
+                    .iter()
+                    .map(|v| v.as_u64().and_then(|b| u32::try_from(b).ok()))
+                    .collect();
+                let Some(parsed) = parsed else {
+                    return ToolResult::Error {
+                        code: -32602,
+                        message: "Invalid 'bases' (32-bit addresses)".to_string(),
+                    };
+                };
+                parsed.into_iter().map(Some).collect()
+            }
+            None => locate_hunks(&file, &amiga.memory),
+        };
+        if bases.first().copied().flatten().is_none() {
+            return ToolResult::Error {
+                code: -32000,
+                message: "Executable not found in memory; run it first or pass 'bases'".to_string(),
+            };
+        }
+
+        let mut loaded = SymbolTable::new();
+        let mut hunks = Vec::new();
+        for (index, hunk) in file.hunks.iter().enumerate() {
+            let base = bases.get(index).copied().flatten();
+            if let Some(base) = base {
+                for (name, offset) in &hunk.symbols {
+                    loaded.insert(name, base.wrapping_add(*offset));
+                }
+            }
+            hunks.push(serde_json::json!({
+                "kind": match hunk.kind {
+                    HunkKind::Code => "code",
+                    HunkKind::Data => "data",
+                    HunkKind::Bss => "bss",
+                },
+                "size": hunk.memory_size,
+                "address": base,
+                "symbols": hunk.symbols.len(),
+            }));
+        }
+
+        let mut result = mcp::symbols::merge_symbols(params, &mut self.symbols, &loaded);
+        if let ToolResult::Success(value) = &mut result {
+            value["hunks"] = hunks.into();
+        }
+        self.refresh_trace_symbols();
+        result
+    }
+
+    fn handle_trace_stop(&mut self) -> ToolResult {
+        match self.require_amiga() {
+            Ok(amiga) => mcp::trace::trace_stop_result(amiga.tracer_mut()),
+            Err(e) => e,
+        }
+    }
+
+    fn handle_trace_export(&mut self, params: &JsonValue) -> ToolResult {
+        match self.require_amiga() {
+            Ok(amiga) => mcp::trace::trace_export_result(params, amiga.tracer()),
+            Err(e) => e,
+        }
     }
 
     fn handle_insert_disk(&mut self, params: &JsonValue) -> ToolResult {
@@ -765,6 +1108,29 @@ impl AmigaMcp {
         }
     }
 
+    fn handle_record_gif(&mut self, params: &JsonValue) -> ToolResult {
+        let amiga = match self.require_amiga() {
+            Ok(a) => a,
+            Err(e) => return e,
+        };
+
+        let pal = matches!(amiga.region, AmigaRegion::Pal);
+        let fps = if pal { 50 } else { 60 };
+        // The standard viewport is already cropped to the display area.
+        let viewport = |amiga: &Amiga| {
+            amiga.denise.as_inner().extract_viewport(
+                crate::commodore_denise_ocs::ViewportPreset::Standard,
+                pal,
+                true,
+            )
+        };
+        let first = viewport(amiga);
+        mcp::record_gif_result(params, first.width, first.height, fps, None, |rec| {
+            amiga.run_frame();
+            rec.add_frame(&viewport(amiga).pixels);
+        })
+    }
+
     fn handle_record_video(&mut self, params: &JsonValue) -> ToolResult {
         let amiga = match self.require_amiga() {
             Ok(a) => a,
@@ -798,7 +1164,16 @@ impl AmigaMcp {
             true,
         );
 
+        let filter = match mcp::parse_filter(params, None) {
+            Ok(filter) => filter,
+            Err(e) => return e,
+        };
         let display = parse_display_size(params, viewport.width, viewport.height);
+        let display = display.or_else(|| {
+            filter
+                .as_ref()
+                .map(|f| f.output_size(viewport.width, viewport.height))
+        });
         let mut rec = match emu_core::video::VideoRecorder::new(
             viewport.width,
             viewport.height,
@@ -817,6 +1192,10 @@ impl AmigaMcp {
             }
         };
 
+        if let Some(filter) = filter {
+            rec.set_filter(filter);
+        }
+
         for _ in 0..frames {
             amiga.run_frame();
             let viewport = amiga.denise.as_inner().extract_viewport(
@@ -884,6 +1263,78 @@ fn parse_display_size(params: &JsonValue, w: u32, h: u32) -> Option<(u32, u32)>
     }
 }
 
+/// The executable `load_hunk_symbols` names: a host `path`, or a `file`
+/// on the `adf` image or the disk in df0.
+fn read_executable(params: &JsonValue, amiga: &Amiga) -> Result<Vec<u8>, ToolResult> {
+    let failed = |message: String| ToolResult::Error {
+        code: -32000,
+        message,
+    };
+    if let Some(path) = params.get("path").and_then(JsonValue::as_str) {
+        return std::fs::read(path).map_err(|e| failed(format!("Cannot read {path}: {e}")));
+    }
+    let Some(file) = params.get("file").and_then(JsonValue::as_str) else {
+        return Err(ToolResult::Error {
+            code: -32602,
+            message: "Provide 'path' or 'file'".to_string(),
+        });
+    };
+    let image = match params.get("adf").and_then(JsonValue::as_str) {
+        Some(adf) => std::fs::read(adf).map_err(|e| failed(format!("Cannot read {adf}: {e}")))?,
+        None => amiga
+            .floppy
+            .save_adf()
+            .ok_or_else(|| failed("No ADF disk in df0".to_string()))?,
+    };
+    let adf = Adf::from_bytes(image).map_err(|e| failed(format!("ADF load failed: {e}")))?;
+    adf.read_file(file)
+        .map_err(|e| failed(format!("{file}: {e}")))
+}
+
+/// Where `LoadSeg` put each hunk of `file`, if it can be told.
+fn locate_hunks(file: &HunkFile, memory: &Memory) -> Vec<Option<u32>> {
+    let regions = [
+        (0, &memory.chip_ram),
+        (0x00C0_0000, &memory.slow_ram),
+        (memory.fast_ram_base, &memory.fast_ram),
+    ];
+    let find = |index: usize| {
+        regions
+            .iter()
+            .find_map(|(base, ram)| file.locate(index, ram).map(|offset| base + offset as u32))
+    };
+    let read_long = |address: u32| {
+        (0..4).fold(0u32, |acc, i| {
+            acc << 8 | u32::from(memory.read_byte_32(address.wrapping_add(i)))
+        })
+    };
+    let matches_at = |index: usize, address: u32| {
+        let hunk = &file.hunks[index];
+        if hunk.kind == HunkKind::Bss {
+            return true;
+        }
+        let bytes: Vec<u8> = (0..hunk.data.len() as u32)
+            .map(|i| memory.read_byte_32(address.wrapping_add(i)))
+            .collect();
+        file.matches(index, &bytes)
+    };
+
+    let mut bases: Vec<Option<u32>> = Vec::with_capacity(file.hunks.len());
+    for index in 0..file.hunks.len() {
+        let next_in_list = match bases.last() {
+            Some(Some(previous)) => {
+                let bptr = read_long(previous.wrapping_sub(4));
+                (bptr != 0)
+                    .then(|| (bptr << 2).wrapping_add(4))
+                    .filter(|&address| matches_at(index, address))
+            }
+            _ => None,
+        };
+        bases.push(next_in_list.or_else(|| find(index)));
+    }
+    bases
+}
+
 /// Load binary data from a `data` (base64) or `path` parameter.
 fn load_binary_param(params: &JsonValue) -> Result<Vec<u8>, ToolResult> {
     if let Some(b64) = params.get("data").and_then(|v| v.as_str()) {
@@ -1100,10 +1551,41 @@ mod tests {
         assert!(matches!(result, ToolResult::Error { .. }));
     }
 
+    #[test]
+    fn disassemble_reads_kickstart_for_the_cpu_model() {
+        let mut kickstart = vec![0; 256 * 1024];
+        kickstart[..6].copy_from_slice(&[0x4E, 0x71, 0x60, 0xFE, 0xF2, 0x00]);
+        let mut mcp = AmigaMcp {
+            amiga: Some(Amiga::new(kickstart)),
+            breakpoints: Breakpoints::new(),
+            run: RunSession::new(),
+            symbols: SymbolTable::new(),
+        };
+
+        let result = mcp.dispatch_tool(
+            "disassemble",
+            &serde_json::json!({"address": 0x00F8_0000, "count": 3}),
+        );
+        match result {
+            ToolResult::Success(value) => {
+                let insns = value["instructions"].as_array().expect("instructions");
+                assert_eq!(insns[0]["text"], "NOP");
+                assert_eq!(insns[1]["text"], "BRA.S $00F80002");
+                assert_eq!(insns[1]["target"], 0x00F8_0002);
+                // No FPU on the A500's 68000: line F is not decoded.
+                assert_eq!(insns[2]["mnemonic"], "DC.W");
+            }
+            ToolResult::Error { message, .. } => panic!("unexpected error: {message}"),
+        }
+    }
+
     #[test]
     fn query_paths_can_filter_to_agnus_and_denise_surfaces() {
         let mut mcp = AmigaMcp {
             amiga: Some(Amiga::new(vec![0; 256 * 1024])),
+            breakpoints: Breakpoints::new(),
+            run: RunSession::new(),
+            symbols: SymbolTable::new(),
         };
 
         let agnus_result = mcp.dispatch_tool(
@@ -1183,7 +1665,12 @@ mod tests {
         });
         amiga.write_custom_reg(0x1DC, 0x559F);
 
-        let mut mcp = AmigaMcp { amiga: Some(amiga) };
+        let mut mcp = AmigaMcp {
+            amiga: Some(amiga),
+            breakpoints: Breakpoints::new(),
+            run: RunSession::new(),
+            symbols: SymbolTable::new(),
+        };
         let result = mcp.dispatch_tool(
             "query",
             &serde_json::json!({
@@ -1206,7 +1693,12 @@ mod tests {
     #[test]
     fn query_paths_can_filter_to_cpu_surface() {
         let amiga = Amiga::new(vec![0; 256 * 1024]);
-        let mut mcp = AmigaMcp { amiga: Some(amiga) };
+        let mut mcp = AmigaMcp {
+            amiga: Some(amiga),
+            breakpoints: Breakpoints::new(),
+            run: RunSession::new(),
+            symbols: SymbolTable::new(),
+        };
         let result = mcp.dispatch_tool(
             "query_paths",
             &serde_json::json!({
@@ -1237,6 +1729,9 @@ mod tests {
     fn query_paths_only_lists_support_chips_present_on_the_active_model() {
         let mut a500_mcp = AmigaMcp {
             amiga: Some(Amiga::new(vec![0; 256 * 1024])),
+            breakpoints: Breakpoints::new(),
+            run: RunSession::new(),
+            symbols: SymbolTable::new(),
         };
         let a500_result = a500_mcp.dispatch_tool(
             "query_paths",
@@ -1266,6 +1761,9 @@ mod tests {
                 scsi_disk: None,
                 pcmcia_card: None,
             })),
+            breakpoints: Breakpoints::new(),
+            run: RunSession::new(),
+            symbols: SymbolTable::new(),
         };
         let a3000_result = a3000_mcp.dispatch_tool(
             "query_paths",
@@ -1342,4 +1840,186 @@ mod tests {
         assert_eq!(parse_key_name("lamiga"), Some(0x66));
         assert_eq!(parse_key_name("unknown"), None);
     }
+
+    #[test]
+    fn breakpoints_watch_custom_chip_writes_and_raster_lines() {
+        let mut kickstart = vec![0; 256 * 1024];
+        kickstart[..18].copy_from_slice(&[
+            0x00, 0x00, 0x04, 0x00, // initial SSP
+            0x00, 0xF8, 0x00, 0x08, // initial PC
+            0x33, 0xFC, 0x0F, 0x00, 0x00, 0xDF, 0xF1, 0x80, // MOVE.W #$0F00,$DFF180
+            0x60, 0xF6, // BRA.S $F80008
+        ]);
+        let mut mcp = AmigaMcp {
+            amiga: Some(Amiga::new(kickstart)),
+            breakpoints: Breakpoints::new(),
+            run: RunSession::new(),
+            symbols: SymbolTable::new(),
+        };
+        let run = |mcp: &mut AmigaMcp, params: serde_json::Value| {
+            mcp.dispatch_tool("clear_breakpoints", &JsonValue::Null);
+            mcp.dispatch_tool("add_breakpoint", &params);
+            match mcp.dispatch_tool("run_until_break", &serde_json::json!({"max_frames": 10})) {
+                ToolResult::Success(value) => value,
+                ToolResult::Error { message, .. } => panic!("unexpected error: {message}"),
+            }
+        };
+
+        let value = run(
+            &mut mcp,
+            serde_json::json!({"type": "execute", "address": 0x00F8_0010}),
+        );
+        assert_eq!(value["pc"], "$00F80010");
+
+        let value = run(
+            &mut mcp,
+            serde_json::json!({"type": "write", "address": 0x00DF_F180, "end": 0x00DF_F181}),
+        );
+        assert_eq!(value["breakpoint"]["access"]["address"], 0x00DF_F180);
+        assert_eq!(value["breakpoint"]["access"]["value"], 0x0F);
+
+        let value = run(&mut mcp, serde_json::json!({"type": "raster", "line": 50}));
+        assert_eq!(value["hit"], true);
+        assert_eq!(mcp.amiga.as_ref().expect("amiga").agnus.as_inner().vpos, 50);
+    }
+
+    #[test]
+    fn trace_ring_keeps_the_newest_instructions_with_their_bus_cycles() {
+        let mut kickstart = vec![0; 256 * 1024];
+        kickstart[..18].copy_from_slice(&[
+            0x00, 0x00, 0x04, 0x00, // initial SSP
+            0x00, 0xF8, 0x00, 0x08, // initial PC
+            0x33, 0xFC, 0x0F, 0x00, 0x00, 0xDF, 0xF1, 0x80, // MOVE.W #$0F00,$DFF180
+            0x60, 0xF6, // BRA.S $F80008
+        ]);
+        let mut mcp = AmigaMcp {
+            amiga: Some(Amiga::new(kickstart)),
+            breakpoints: Breakpoints::new(),
+            run: RunSession::new(),
+            symbols: SymbolTable::new(),
+        };
+        let start = mcp.dispatch_tool(
+            "trace_start",
+            &serde_json::json!({"bus": true, "capacity": 4}),
+        );
+        assert!(matches!(start, ToolResult::Success(_)));
+        mcp.dispatch_tool("run_frames", &serde_json::json!({"count": 1}));
+
+        let ToolResult::Success(value) = mcp.dispatch_tool("trace_export", &JsonValue::Null) else {
+            panic!("export failed");
+        };
+        // The loop alternates MOVE.W and BRA.S, and each MOVE carries
+        // its colour register write.
+        assert_eq!(value["entries"], 4);
+        let data = value["data"].as_str().expect("data");
+        let pcs: Vec<&str> = data
+            .lines()
+            .filter(|line| !line.starts_with("              "))
+            .map(|line| &line[14..22])
+            .collect();
+        assert_eq!(pcs, ["00F80008", "00F80010", "00F80008", "00F80010"]);
+        assert!(data.contains("  33 FC 0F 00 00 DF F1 80 "));
+        assert!(data.contains("W  00DFF180 0F"));
+        assert!(data.contains(" A7=00000400 SR=2700"));
+    }
+
+    #[test]
+    fn run_pushes_frames_until_a_watchpoint_hits() {
+        let mut kickstart = vec![0; 256 * 1024];
+        kickstart[..18].copy_from_slice(&[
+            0x00, 0x00, 0x04, 0x00, // initial SSP
+            0x00, 0xF8, 0x00, 0x08, // initial PC
+            0x33, 0xFC, 0x0F, 0x00, 0x00, 0xDF, 0xF1, 0x80, // MOVE.W #$0F00,$DFF180
+            0x60, 0xF6, // BRA.S $F80008
+        ]);
+        let mut mcp = AmigaMcp::new();
+        mcp.amiga = Some(Amiga::new(kickstart));
+
+        let run = mcp.dispatch_tool("run", &serde_json::json!({"realtime": false}));
+        assert!(matches!(run, ToolResult::Success(_)));
+        assert_eq!(mcp.run_slice(), Some(Duration::ZERO));
+        mcp.dispatch_tool(
+            "add_breakpoint",
+            &serde_json::json!({"type": "write", "address": 0x00DF_F180}),
+        );
+        assert_eq!(mcp.run_slice(), None);
+
+        let sent = mcp.take_notifications();
+        let events: Vec<_> = sent.iter().map(|n| n.event).collect();
+        assert_eq!(events, ["frame_complete", "watchpoint_hit"]);
+        assert_eq!(sent[1].params["pc"], "$00F80010");
+    }
+
+    #[test]
+    fn hunk_symbols_land_where_loadseg_put_each_hunk() {
+        fn longs(values: &[u32]) -> Vec<u8> {
+            values.iter().flat_map(|v| v.to_be_bytes()).collect()
+        }
+        let code = longs(&[0x4E71_4E71, 0x60FE_4E75]);
+        let data = longs(&[0x1234_5678, 0x9ABC_DEF0]);
+        let mut exe = longs(&[0x3F3, 0, 2, 0, 1, 2, 2, 0x3E9, 2]);
+        exe.extend(&code);
+        exe.extend(longs(&[0x3F0, 1, u32::from_be_bytes(*b"main"), 0]));
+        exe.extend(longs(&[
+            1,
+            u32::from_be_bytes(*b"loop"),
+            4,
+            0,
+            0x3F2,
+            0x3EA,
+            2,
+        ]));
+        exe.extend(&data);
+        exe.extend(longs(&[0x3F0, 2]));
+        exe.extend(b"score\0\0\0");
+        exe.extend(longs(&[0, 0, 0x3F2]));
+        let path = std::env::temp_dir().join("machine-amiga-hunk-symbols-test");
+        std::fs::write(&path, &exe).expect("write");
+
+        // Two segments as LoadSeg links them: size, BPTR to the next
+        // segment's link, then the hunk's data.
+        let mut amiga = Amiga::new(vec![0; 256 * 1024]);
+        amiga.memory.overlay = false;
+        let ram = &mut amiga.memory.chip_ram;
+        ram[0x2_0004..0x2_0008].copy_from_slice(&(0x3_0004_u32 >> 2).to_be_bytes());
+        ram[0x2_0008..0x2_0010].copy_from_slice(&code);
+        ram[0x3_0008..0x3_0010].copy_from_slice(&data);
+        let mut mcp = AmigaMcp {
+            amiga: Some(amiga),
+            breakpoints: Breakpoints::new(),
+            run: RunSession::new(),
+            symbols: SymbolTable::new(),
+        };
+
+        let result = mcp.dispatch_tool(
+            "load_hunk_symbols",
+            &serde_json::json!({"path": path.to_str()}),
+        );
+        let _ = std::fs::remove_file(&path);
+        let value = match result {
+            ToolResult::Success(value) => value,
+            ToolResult::Error { message, .. } => panic!("load failed: {message}"),
+        };
+        assert_eq!(value["loaded"], 3);
+        assert_eq!(value["hunks"][0]["address"], 0x2_0008);
+        assert_eq!(value["hunks"][1]["address"], 0x3_0008);
+        assert_eq!(value["hunks"][1]["kind"], "data");
+
+        let ToolResult::Success(found) =
+            mcp.dispatch_tool("lookup_symbol", &serde_json::json!({"name": "score"}))
+        else {
+            panic!("lookup failed");
+        };
+        assert_eq!(found["address"], 0x3_0008);
+
+        let ToolResult::Success(listing) = mcp.dispatch_tool(
+            "disassemble",
+            &serde_json::json!({"address": "loop", "count": 1}),
+        ) else {
+            panic!("disassemble failed");
+        };
+        let insn = &listing["instructions"][0];
+        assert_eq!(insn["label"], "loop");
+        assert_eq!(insn["text"], "BRA.S loop");
+    }
 }
@@ -1,5 +1,7 @@
 //! Memory management for the Amiga Rock.
 
+use emu_core::{SaveState, StateError, StateReader, StateWriter};
+
 pub const CHIP_RAM_BASE: u32 = 0x000000;
 pub const CIA_A_BASE: u32 = 0xBFE001;
 pub const CIA_B_BASE: u32 = 0xBFD000;
@@ -127,6 +129,25 @@ impl Memory {
     }
 }
 
+/// RAM contents and the overlay latch. The Kickstart image and the RAM
+/// sizes are configuration.
+impl SaveState for Memory {
+    fn save_state(&self, w: &mut StateWriter) {
+        w.write_bytes(&self.chip_ram);
+        w.write_bytes(&self.slow_ram);
+        w.write_bytes(&self.fast_ram);
+        w.write_bool(self.overlay);
+    }
+
+    fn load_state(&mut self, r: &mut StateReader<'_>) -> Result<(), StateError> {
+        r.read_bytes_into(&mut self.chip_ram)?;
+        r.read_bytes_into(&mut self.slow_ram)?;
+        r.read_bytes_into(&mut self.fast_ram)?;
+        self.overlay = r.read_bool()?;
+        Ok(())
+    }
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
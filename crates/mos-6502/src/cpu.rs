@@ -3,7 +3,9 @@
 //! Cycle-accurate emulation where each `tick()` performs exactly one
 //! bus access. Instructions are broken down into their component cycles.
 
-use emu_core::{Bus, Cpu, Observable, ReadResult, Value};
+use emu_core::{
+    Bus, Cpu, Observable, ReadResult, SaveState, StateError, StateReader, StateWriter, Value,
+};
 
 use crate::flags::{C, D, I, N, V, Z};
 use crate::{Registers, Status};
@@ -2589,6 +2591,58 @@ impl Cpu for Mos6502 {
     }
 }
 
+impl SaveState for Mos6502 {
+    fn save_state(&self, w: &mut StateWriter) {
+        w.write_u8(self.regs.a);
+        w.write_u8(self.regs.x);
+        w.write_u8(self.regs.y);
+        w.write_u8(self.regs.s);
+        w.write_u16(self.regs.pc);
+        w.write_u8(self.regs.p.0);
+        w.write_u8(match self.state {
+            State::FetchOpcode => 0,
+            State::Execute => 1,
+            State::Stopped => 2,
+        });
+        w.write_u8(self.opcode);
+        w.write_u8(self.cycle);
+        w.write_u16(self.addr);
+        w.write_u8(self.data);
+        w.write_u8(self.pointer);
+        w.write_bool(self.nmi_pending);
+        w.write_bool(self.irq_pending);
+        w.write_bool(self.irq_delay);
+        w.write_u8(self.wait_states);
+        w.write_u64(self.total_cycles);
+    }
+
+    fn load_state(&mut self, r: &mut StateReader<'_>) -> Result<(), StateError> {
+        self.regs.a = r.read_u8()?;
+        self.regs.x = r.read_u8()?;
+        self.regs.y = r.read_u8()?;
+        self.regs.s = r.read_u8()?;
+        self.regs.pc = r.read_u16()?;
+        self.regs.p = Status(r.read_u8()?);
+        self.state = match r.read_u8()? {
+            0 => State::FetchOpcode,
+            1 => State::Execute,
+            2 => State::Stopped,
+            v => return Err(StateError::Invalid(format!("bad 6502 state {v}"))),
+        };
+        self.opcode = r.read_u8()?;
+        self.cycle = r.read_u8()?;
+        self.addr = r.read_u16()?;
+        self.data = r.read_u8()?;
+        self.pointer = r.read_u8()?;
+        self.nmi_pending = r.read_bool()?;
+        self.irq_pending = r.read_bool()?;
+        self.irq_delay = r.read_bool()?;
+        self.wait_states = r.read_u8()?;
+        self.total_cycles = r.read_u64()?;
+        Ok(())
+    }
+}
+
 impl Observable for Mos6502 {
     fn query(&self, path: &str) -> Option<Value> {
         match path {
@@ -27,6 +27,8 @@
 
 #![allow(clippy::cast_possible_truncation)]
 
+use emu_core::{SaveState, StateError, StateReader, StateWriter};
+
 /// MOS 6526 Complex Interface Adapter.
 pub struct Cia6526 {
     /// Port A output register.
@@ -523,6 +525,77 @@ fn bcd_increment(val: u8) -> u8 {
     }
 }
 
+/// The TOD divider is configuration (set by the machine's video standard)
+/// and is not part of the state.
+impl SaveState for Cia6526 {
+    fn save_state(&self, w: &mut StateWriter) {
+        w.write_u8(self.port_a);
+        w.write_u8(self.port_b);
+        w.write_u8(self.ddr_a);
+        w.write_u8(self.ddr_b);
+        w.write_u8(self.external_a);
+        w.write_u8(self.external_b);
+        w.write_u16(self.timer_a);
+        w.write_u16(self.timer_a_latch);
+        w.write_bool(self.timer_a_running);
+        w.write_bool(self.timer_a_oneshot);
+        w.write_bool(self.timer_a_force_load);
+        w.write_u16(self.timer_b);
+        w.write_u16(self.timer_b_latch);
+        w.write_bool(self.timer_b_running);
+        w.write_bool(self.timer_b_oneshot);
+        w.write_bool(self.timer_b_force_load);
+        w.write_bool(self.timer_b_count_ta_underflow);
+        w.write_u8(self.icr_status);
+        w.write_u8(self.icr_mask);
+        w.write_u8(self.cra);
+        w.write_u8(self.crb);
+        w.write_bytes(&self.tod);
+        w.write_bytes(&self.tod_latch);
+        w.write_bool(self.tod_latched);
+        w.write_bool(self.tod_halted);
+        w.write_u32(self.tod_counter);
+        w.write_bool(self.prev_flag);
+        w.write_u8(self.shift_register);
+        w.write_u8(self.shift_count);
+        w.write_bool(self.sp_output);
+    }
+
+    fn load_state(&mut self, r: &mut StateReader<'_>) -> Result<(), StateError> {
+        self.port_a = r.read_u8()?;
+        self.port_b = r.read_u8()?;
+        self.ddr_a = r.read_u8()?;
+        self.ddr_b = r.read_u8()?;
+        self.external_a = r.read_u8()?;
+        self.external_b = r.read_u8()?;
+        self.timer_a = r.read_u16()?;
+        self.timer_a_latch = r.read_u16()?;
+        self.timer_a_running = r.read_bool()?;
+        self.timer_a_oneshot = r.read_bool()?;
+        self.timer_a_force_load = r.read_bool()?;
+        self.timer_b = r.read_u16()?;
+        self.timer_b_latch = r.read_u16()?;
+        self.timer_b_running = r.read_bool()?;
+        self.timer_b_oneshot = r.read_bool()?;
+        self.timer_b_force_load = r.read_bool()?;
+        self.timer_b_count_ta_underflow = r.read_bool()?;
+        self.icr_status = r.read_u8()?;
+        self.icr_mask = r.read_u8()?;
+        self.cra = r.read_u8()?;
+        self.crb = r.read_u8()?;
+        r.read_bytes_into(&mut self.tod)?;
+        r.read_bytes_into(&mut self.tod_latch)?;
+        self.tod_latched = r.read_bool()?;
+        self.tod_halted = r.read_bool()?;
+        self.tod_counter = r.read_u32()?;
+        self.prev_flag = r.read_bool()?;
+        self.shift_register = r.read_u8()?;
+        self.shift_count = r.read_u8()?;
+        self.sp_output = r.read_bool()?;
+        Ok(())
+    }
+}
+
 impl Default for Cia6526 {
     fn default() -> Self {
         Self::new()
@@ -5,6 +5,8 @@
 //! countdown timers, a 24-bit time-of-day counter, a serial shift register,
 //! and an interrupt controller.
 
+use emu_core::{SaveState, StateError, StateReader, StateWriter};
+
 /// MOS 8520 Complex Interface Adapter.
 pub struct Cia8520 {
     _label: &'static str,
@@ -447,6 +449,75 @@ impl Cia8520 {
     }
 }
 
+impl SaveState for Cia8520 {
+    fn save_state(&self, w: &mut StateWriter) {
+        w.write_u8(self.port_a);
+        w.write_u8(self.port_b);
+        w.write_u8(self.ddr_a);
+        w.write_u8(self.ddr_b);
+        w.write_u8(self.external_a);
+        w.write_u8(self.external_b);
+        w.write_u16(self.timer_a);
+        w.write_u16(self.timer_a_latch);
+        w.write_bool(self.timer_a_running);
+        w.write_bool(self.timer_a_oneshot);
+        w.write_bool(self.timer_a_force_load);
+        w.write_u16(self.timer_b);
+        w.write_u16(self.timer_b_latch);
+        w.write_bool(self.timer_b_running);
+        w.write_bool(self.timer_b_oneshot);
+        w.write_bool(self.timer_b_force_load);
+        w.write_u8(self.icr_status);
+        w.write_u8(self.icr_mask);
+        w.write_u8(self.cra);
+        w.write_u8(self.crb);
+        w.write_u8(self.sdr);
+        w.write_u32(self.tod_counter);
+        w.write_u32(self.tod_alarm);
+        w.write_u32(self.tod_latch);
+        w.write_bool(self.tod_latched);
+        w.write_u8(self.timer_a_read_hi_latch);
+        w.write_bool(self.timer_a_read_hi_latched);
+        w.write_u8(self.timer_b_read_hi_latch);
+        w.write_bool(self.timer_b_read_hi_latched);
+        w.write_bool(self.tod_halted);
+    }
+
+    fn load_state(&mut self, r: &mut StateReader<'_>) -> Result<(), StateError> {
+        self.port_a = r.read_u8()?;
+        self.port_b = r.read_u8()?;
+        self.ddr_a = r.read_u8()?;
+        self.ddr_b = r.read_u8()?;
+        self.external_a = r.read_u8()?;
+        self.external_b = r.read_u8()?;
+        self.timer_a = r.read_u16()?;
+        self.timer_a_latch = r.read_u16()?;
+        self.timer_a_running = r.read_bool()?;
+        self.timer_a_oneshot = r.read_bool()?;
+        self.timer_a_force_load = r.read_bool()?;
+        self.timer_b = r.read_u16()?;
+        self.timer_b_latch = r.read_u16()?;
+        self.timer_b_running = r.read_bool()?;
+        self.timer_b_oneshot = r.read_bool()?;
+        self.timer_b_force_load = r.read_bool()?;
+        self.icr_status = r.read_u8()?;
+        self.icr_mask = r.read_u8()?;
+        self.cra = r.read_u8()?;
+        self.crb = r.read_u8()?;
+        self.sdr = r.read_u8()?;
+        self.tod_counter = r.read_u32()?;
+        self.tod_alarm = r.read_u32()?;
+        self.tod_latch = r.read_u32()?;
+        self.tod_latched = r.read_bool()?;
+        self.timer_a_read_hi_latch = r.read_u8()?;
+        self.timer_a_read_hi_latched = r.read_bool()?;
+        self.timer_b_read_hi_latch = r.read_u8()?;
+        self.timer_b_read_hi_latched = r.read_bool()?;
+        self.tod_halted = r.read_bool()?;
+        Ok(())
+    }
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
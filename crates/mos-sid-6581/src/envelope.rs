@@ -7,6 +7,8 @@
 
 #![allow(clippy::cast_possible_truncation)]
 
+use emu_core::{SaveState, StateError, StateReader, StateWriter};
+
 /// Attack rate counter periods (CPU ticks per step).
 /// Index 0 = 2ms, index 15 = 8s. Values from the SID datasheet.
 const ATTACK_RATES: [u16; 16] = [
@@ -177,6 +179,41 @@ impl Envelope {
     }
 }
 
+impl SaveState for Envelope {
+    fn save_state(&self, w: &mut StateWriter) {
+        w.write_u8(self.level);
+        w.write_u8(self.phase as u8);
+        w.write_u16(self.rate_counter);
+        w.write_u8(self.exp_counter);
+        w.write_u8(self.exp_period);
+        w.write_u8(self.attack);
+        w.write_u8(self.decay);
+        w.write_u8(self.sustain);
+        w.write_u8(self.release);
+        w.write_bool(self.prev_gate);
+    }
+
+    fn load_state(&mut self, r: &mut StateReader<'_>) -> Result<(), StateError> {
+        self.level = r.read_u8()?;
+        self.phase = match r.read_u8()? {
+            0 => Phase::Attack,
+            1 => Phase::Decay,
+            2 => Phase::Sustain,
+            3 => Phase::Release,
+            n => return Err(StateError::Invalid(format!("envelope phase {n}"))),
+        };
+        self.rate_counter = r.read_u16()?;
+        self.exp_counter = r.read_u8()?;
+        self.exp_period = r.read_u8()?;
+        self.attack = r.read_u8()?;
+        self.decay = r.read_u8()?;
+        self.sustain = r.read_u8()?;
+        self.release = r.read_u8()?;
+        self.prev_gate = r.read_bool()?;
+        Ok(())
+    }
+}
+
 impl Default for Envelope {
     fn default() -> Self {
         Self::new()
@@ -16,6 +16,8 @@
 
 #![allow(clippy::cast_precision_loss)]
 
+use emu_core::{SaveState, StateError, StateReader, StateWriter};
+
 use crate::SidModel;
 
 /// 6581 filter coefficient lookup table (32 entries).
@@ -153,6 +155,31 @@ impl Filter {
     }
 }
 
+impl SaveState for Filter {
+    fn save_state(&self, w: &mut StateWriter) {
+        w.write_f32(self.lp);
+        w.write_f32(self.bp);
+        w.write_f32(self.hp);
+        w.write_u16(self.cutoff);
+        w.write_u8(self.resonance);
+        w.write_u8(self.mode);
+        w.write_u8(self.routing);
+        w.write_bool(self.ext_in);
+    }
+
+    fn load_state(&mut self, r: &mut StateReader<'_>) -> Result<(), StateError> {
+        self.lp = r.read_f32()?;
+        self.bp = r.read_f32()?;
+        self.hp = r.read_f32()?;
+        self.cutoff = r.read_u16()?;
+        self.resonance = r.read_u8()?;
+        self.mode = r.read_u8()?;
+        self.routing = r.read_u8()?;
+        self.ext_in = r.read_bool()?;
+        Ok(())
+    }
+}
+
 impl Default for Filter {
     fn default() -> Self {
         Self::new(SidModel::Mos6581)
@@ -3,7 +3,15 @@
 //! The SID has three voices, each with a 24-bit phase-accumulator oscillator,
 //! four waveform generators, an ADSR envelope, and a shared multi-mode
 //! state-variable filter. All components tick at the C64 CPU rate (985,248 Hz
-//! PAL) and the output is downsampled to 48 kHz.
+//! PAL) and the output is band-limited to the host sample rate.
+//!
+//! Each voice and the filter output can be muted in the mix or captured on
+//! its own as a stem. Voice taps are taken before the filter, so a voice
+//! routed through it is still heard in its own stem; the `filter` tap
+//! carries what comes out of the filter.
+//!
+//! Writes to $00–$18 can be logged with cycle timestamps and exported as a
+//! PSID or a text register dump (see [`emu_core::reglog`]).
 //!
 //! # Register map (29 registers, $D400–$D41C)
 //!
@@ -34,6 +42,10 @@ mod envelope;
 mod filter;
 mod voice;
 
+use emu_core::{
+    BlipBuffer, LoggedChip, RegisterLog, SaveState, StateError, StateReader, StateWriter, Stems,
+};
+
 pub use envelope::{Envelope, Phase};
 pub use filter::Filter;
 pub use voice::Voice;
@@ -71,18 +83,25 @@ pub struct Sid6581 {
     /// Paddle Y ADC value ($D41A / POTY). Range 0–255, default $80 (centre).
     pub poty: u8,
 
-    // Downsampling state
-    /// Accumulated mixed output for downsampling.
-    accumulator: f32,
-    /// Number of CPU ticks accumulated.
-    sample_count: u32,
-    /// CPU ticks per output sample (e.g., 985,248 / 48,000 ≈ 20.5).
-    ticks_per_sample: f32,
-    /// Output audio buffer (mono f32, -1.0 to 1.0).
-    buffer: Vec<f32>,
+    /// Band-limited output (mono f32, -1.0 to 1.0), clocked per CPU cycle.
+    blip: BlipBuffer,
+    /// Per-channel output from the last tick, in `CHANNELS` order.
+    taps: [f32; 4],
+    /// Channels left out of the mix (bit n = `CHANNELS[n]`).
+    channel_mute: u8,
+    /// Per-channel capture, while enabled.
+    stems: Option<Stems>,
+
+    /// CPU clock in Hz, for register-log timestamps.
+    clock_hz: u32,
+    /// Register-write log, while enabled.
+    log: Option<RegisterLog>,
 }
 
 impl Sid6581 {
+    /// Channel names, in tap and mute-mask order.
+    pub const CHANNELS: [&'static str; 4] = ["voice1", "voice2", "voice3", "filter"];
+
     /// Create a new SID chip (defaults to 6581 model).
     ///
     /// `cpu_frequency` is the master clock rate in Hz (985,248 for PAL C64).
@@ -104,10 +123,12 @@ impl Sid6581 {
             voice3_off: false,
             potx: 0x80,
             poty: 0x80,
-            accumulator: 0.0,
-            sample_count: 0,
-            ticks_per_sample: cpu_frequency as f32 / output_sample_rate as f32,
-            buffer: Vec::with_capacity(output_sample_rate as usize / 50 + 1),
+            blip: BlipBuffer::new(f64::from(cpu_frequency), output_sample_rate),
+            taps: [0.0; 4],
+            channel_mute: 0,
+            stems: None,
+            clock_hz: cpu_frequency,
+            log: None,
         }
     }
 
@@ -135,6 +156,11 @@ impl Sid6581 {
     /// Write a SID register (addr 0x00–0x1F).
     pub fn write(&mut self, addr: u8, value: u8) {
         let reg = addr & 0x1F;
+        if let Some(log) = &mut self.log
+            && reg <= 0x18
+        {
+            log.record(reg, value);
+        }
         match reg {
             // Voice 1 (0x00–0x06)
             0x00 => {
@@ -246,6 +272,9 @@ impl Sid6581 {
     /// clocks all three envelopes, mixes through the filter, applies
     /// master volume, and accumulates for downsampling.
     pub fn tick(&mut self) {
+        if let Some(log) = &mut self.log {
+            log.tick();
+        }
         // 1. Capture previous MSB states for sync detection
         let prev_msb = [
             self.voices[0].msb(),
@@ -292,6 +321,9 @@ impl Sid6581 {
         let mut filtered_sum: f32 = 0.0;
         let mut direct_sum: f32 = 0.0;
         let model = self.model;
+        // Master volume and normalisation to -1.0..1.0
+        // (3 voices × 2048 max amplitude = 6144).
+        let gain = f32::from(self.volume) / 15.0 / 6144.0;
 
         for (i, (voice, (env, &ring_msb))) in self
             .voices
@@ -308,6 +340,12 @@ impl Sid6581 {
 
             // Voice 3 mute: exclude from audio mix but keep running
             if i == 2 && self.voice3_off {
+                self.taps[i] = 0.0;
+                continue;
+            }
+
+            self.taps[i] = amplitude * gain;
+            if self.channel_mute & (1 << i) != 0 {
                 continue;
             }
 
@@ -321,34 +359,162 @@ impl Sid6581 {
 
         // 7. Process filter
         let filter_output = self.filter.clock(filtered_sum);
+        self.taps[3] = filter_output * gain;
+        let filter_output = if self.channel_mute & 0x08 == 0 {
+            filter_output
+        } else {
+            0.0
+        };
 
-        // 8. Mix and apply master volume
-        let mixed = (filter_output + direct_sum) * f32::from(self.volume) / 15.0;
-
-        // Normalise to -1.0..1.0 range (3 voices × 2048 max amplitude = 6144)
-        let normalised = mixed / 6144.0;
-
-        // 9. Downsample: accumulate and emit when threshold reached
-        self.accumulator += normalised;
-        self.sample_count += 1;
+        // 8. Mix, apply master volume and normalise
+        let normalised = (filter_output + direct_sum) * gain;
 
-        if self.sample_count as f32 >= self.ticks_per_sample {
-            let avg = self.accumulator / self.sample_count as f32;
-            self.buffer.push(avg);
-            self.accumulator = 0.0;
-            self.sample_count = 0;
+        // 9. Resample to the output rate
+        self.blip.set_level(normalised);
+        self.blip.advance(1);
+        if let Some(stems) = &mut self.stems {
+            stems.record(&self.taps, 1);
         }
     }
 
     /// Take the audio output buffer (drains it).
     pub fn take_buffer(&mut self) -> Vec<f32> {
-        std::mem::take(&mut self.buffer)
+        self.blip.take_samples()
     }
 
     /// Number of samples in the output buffer.
     #[must_use]
     pub fn buffer_len(&self) -> usize {
-        self.buffer.len()
+        self.blip.len()
+    }
+
+    /// Output sample rate.
+    #[must_use]
+    pub fn sample_rate(&self) -> u32 {
+        self.blip.sample_rate()
+    }
+
+    /// Change the output sample rate.
+    pub fn set_sample_rate(&mut self, rate: u32) {
+        self.blip.set_sample_rate(rate);
+        if let Some(stems) = &mut self.stems {
+            stems.set_sample_rate(rate);
+        }
+    }
+
+    /// Stretch the output rate for dynamic rate control.
+    pub fn set_rate_adjust(&mut self, ratio: f64) {
+        self.blip.set_rate_adjust(ratio);
+        if let Some(stems) = &mut self.stems {
+            stems.set_rate_adjust(ratio);
+        }
+    }
+
+    /// Each channel's output from the last tick, in `CHANNELS` order.
+    #[must_use]
+    pub fn channel_outputs(&self) -> [f32; 4] {
+        self.taps
+    }
+
+    /// Mute channels in the mix: bit n silences `CHANNELS[n]`. A muted
+    /// voice is also kept out of the filter.
+    pub fn set_channel_mute(&mut self, mask: u8) {
+        self.channel_mute = mask & 0x0F;
+    }
+
+    /// Channels currently muted in the mix.
+    #[must_use]
+    pub fn channel_mute(&self) -> u8 {
+        self.channel_mute
+    }
+
+    /// Start or stop capturing each channel as its own stream.
+    pub fn set_stems(&mut self, enabled: bool) {
+        self.stems = enabled.then(|| Stems::new(&self.blip, &self.taps));
+    }
+
+    /// Drain the per-channel streams, one per `CHANNELS` entry, at the
+    /// output rate. Empty while capture is off.
+    pub fn take_stems(&mut self) -> Vec<Vec<f32>> {
+        self.stems.as_mut().map_or_else(Vec::new, Stems::take)
+    }
+
+    /// The write-only registers $00–$18, rebuilt from the chip state.
+    #[must_use]
+    pub fn registers(&self) -> [u8; 0x19] {
+        let mut regs = [0u8; 0x19];
+        for (i, (voice, env)) in self.voices.iter().zip(&self.envelopes).enumerate() {
+            let base = i * 7;
+            regs[base..base + 2].copy_from_slice(&voice.frequency.to_le_bytes());
+            regs[base + 2..base + 4].copy_from_slice(&voice.pulse_width.to_le_bytes());
+            regs[base + 4] = voice.control;
+            regs[base + 5] = env.attack << 4 | env.decay;
+            regs[base + 6] = env.sustain << 4 | env.release;
+        }
+        regs[0x15] = (self.filter.cutoff & 0x07) as u8;
+        regs[0x16] = (self.filter.cutoff >> 3) as u8;
+        regs[0x17] =
+            self.filter.resonance << 4 | u8::from(self.filter.ext_in) << 3 | self.filter.routing;
+        regs[0x18] = u8::from(self.voice3_off) << 7 | self.filter.mode | self.volume;
+        regs
+    }
+
+    /// Start logging writes to $00–$18. The log opens with the current
+    /// value of each, so it plays back from the same state.
+    pub fn start_register_log(&mut self) {
+        let chip = match self.model {
+            SidModel::Mos6581 => LoggedChip::Sid6581,
+            SidModel::Mos8580 => LoggedChip::Sid8580,
+        };
+        let mut log = RegisterLog::new(chip, self.clock_hz);
+        for (reg, value) in (0..).zip(self.registers()) {
+            log.record(reg, value);
+        }
+        self.log = Some(log);
+    }
+
+    /// Stop logging and return the log, if one was running.
+    pub fn stop_register_log(&mut self) -> Option<RegisterLog> {
+        self.log.take()
+    }
+}
+
+/// The chip model is configuration: restoring a state taken on the other
+/// revision is rejected rather than silently switching filter curves.
+impl SaveState for Sid6581 {
+    fn save_state(&self, w: &mut StateWriter) {
+        w.write_bool(self.model == SidModel::Mos8580);
+        for voice in &self.voices {
+            voice.save_state(w);
+        }
+        for envelope in &self.envelopes {
+            envelope.save_state(w);
+        }
+        self.filter.save_state(w);
+        w.write_u8(self.volume);
+        w.write_bool(self.voice3_off);
+        w.write_u8(self.potx);
+        w.write_u8(self.poty);
+        self.blip.save_state(w);
+    }
+
+    fn load_state(&mut self, r: &mut StateReader<'_>) -> Result<(), StateError> {
+        if r.read_bool()? != (self.model == SidModel::Mos8580) {
+            return Err(StateError::Invalid("SID model mismatch".into()));
+        }
+        for voice in &mut self.voices {
+            voice.load_state(r)?;
+        }
+        for envelope in &mut self.envelopes {
+            envelope.load_state(r)?;
+        }
+        self.filter.load_state(r)?;
+        self.volume = r.read_u8()?;
+        self.voice3_off = r.read_bool()?;
+        self.potx = r.read_u8()?;
+        self.poty = r.read_u8()?;
+        self.blip.load_state(r)?;
+        Ok(())
     }
 }
 
@@ -403,6 +569,36 @@ mod tests {
         assert!(has_negative, "Expected negative samples in sawtooth");
     }
 
+    #[test]
+    fn filtered_voice_has_its_own_stem() {
+        let mut sid = Sid6581::new(985_248, 48_000);
+        sid.write(0x00, 0x37); // Voice 1 ~440 Hz sawtooth, gate on
+        sid.write(0x01, 0x1D);
+        sid.write(0x06, 0xF0);
+        sid.write(0x04, 0x21);
+        sid.write(0x16, 0x40); // Cutoff
+        sid.write(0x17, 0x01); // Voice 1 through the filter
+        sid.write(0x18, 0x1F); // Low-pass, volume 15
+        sid.set_channel_mute(0b1000); // Filter output muted
+        sid.set_stems(true);
+        for _ in 0..40_000 {
+            sid.tick();
+        }
+
+        let mix = sid.take_buffer();
+        let stems = sid.take_stems();
+        assert_eq!(stems.len(), Sid6581::CHANNELS.len());
+        assert!(stems.iter().all(|stem| stem.len() == mix.len()));
+        let peak = |buf: &[f32]| buf.iter().fold(0.0f32, |m, s| m.max(s.abs()));
+        assert!(peak(&mix) < 1e-6, "muted filter output reached the mix");
+        assert!(
+            peak(&stems[0]) > 0.05,
+            "voice 1 stem is taken before the filter"
+        );
+        assert!(peak(&stems[3]) > 0.01, "filter stem should carry voice 1");
+        assert!(peak(&stems[1]) < 1e-6);
+    }
+
     #[test]
     fn adsr_attack_reaches_max() {
         let mut sid = Sid6581::new(985_248, 48_000);
@@ -555,4 +751,38 @@ mod tests {
         assert!(!buf.is_empty());
         assert_eq!(sid.buffer_len(), 0, "Buffer should be empty after take");
     }
+
+    #[test]
+    fn register_log_replays_to_the_same_registers() {
+        let mut sid = Sid6581::new(985_248, 48_000);
+        for (reg, value) in [(0x00, 0x34), (0x01, 0x12), (0x03, 0x08), (0x04, 0x41)] {
+            sid.write(reg, value);
+        }
+        for (reg, value) in [
+            (0x13, 0x5A),
+            (0x15, 0x05),
+            (0x16, 0x9C),
+            (0x17, 0xF3),
+            (0x18, 0x9F),
+        ] {
+            sid.write(reg, value);
+        }
+
+        sid.start_register_log();
+        sid.tick();
+        sid.write(0x0B, 0x21);
+        sid.write(0x1B, 0xFF); // read-only, not logged
+        let log = sid.stop_register_log().expect("log was running");
+        assert_eq!(log.writes().len(), 0x19 + 1);
+        let last = log.writes()[0x19];
+        assert_eq!((last.cycle, last.register, last.value), (1, 0x0B, 0x21));
+
+        let mut replay = Sid6581::new(985_248, 48_000);
+        for w in log.writes() {
+            replay.write(w.register, w.value);
+        }
+        assert_eq!(replay.registers(), sid.registers());
+        assert_eq!(sid.registers()[0x17], 0xF3);
+        assert_eq!(sid.registers()[0x03], 0x08);
+    }
 }
@@ -13,6 +13,8 @@
 
 #![allow(clippy::cast_possible_truncation)]
 
+use emu_core::{SaveState, StateError, StateReader, StateWriter};
+
 use crate::SidModel;
 
 /// Noise LFSR seed value (matches real 6581 power-on state).
@@ -208,6 +210,27 @@ impl Voice {
     }
 }
 
+impl SaveState for Voice {
+    fn save_state(&self, w: &mut StateWriter) {
+        w.write_u32(self.accumulator);
+        w.write_u16(self.frequency);
+        w.write_u16(self.pulse_width);
+        w.write_u8(self.control);
+        w.write_u32(self.noise_lfsr);
+        w.write_bool(self.prev_msb);
+    }
+
+    fn load_state(&mut self, r: &mut StateReader<'_>) -> Result<(), StateError> {
+        self.accumulator = r.read_u32()?;
+        self.frequency = r.read_u16()?;
+        self.pulse_width = r.read_u16()?;
+        self.control = r.read_u8()?;
+        self.noise_lfsr = r.read_u32()?;
+        self.prev_msb = r.read_bool()?;
+        Ok(())
+    }
+}
+
 impl Default for Voice {
     fn default() -> Self {
         Self::new()
@@ -28,6 +28,8 @@
 
 #![allow(clippy::cast_possible_truncation)]
 
+use emu_core::{SaveState, StateError, StateReader, StateWriter};
+
 /// MOS 6522 Versatile Interface Adapter.
 pub struct Via6522 {
     /// Port A output register.
@@ -457,6 +459,61 @@ impl Via6522 {
     }
 }
 
+impl SaveState for Via6522 {
+    fn save_state(&self, w: &mut StateWriter) {
+        w.write_u8(self.port_a);
+        w.write_u8(self.port_b);
+        w.write_u8(self.ddr_a);
+        w.write_u8(self.ddr_b);
+        w.write_u8(self.external_a);
+        w.write_u8(self.external_b);
+        w.write_u16(self.timer1_counter);
+        w.write_u16(self.timer1_latch);
+        w.write_bool(self.timer1_fired);
+        w.write_bool(self.timer1_running);
+        w.write_u16(self.timer2_counter);
+        w.write_u8(self.timer2_latch_lo);
+        w.write_bool(self.timer2_fired);
+        w.write_bool(self.timer2_running);
+        w.write_u8(self.shift_register);
+        w.write_u8(self.shift_count);
+        w.write_u8(self.acr);
+        w.write_u8(self.pcr);
+        w.write_u8(self.ifr);
+        w.write_u8(self.ier);
+        w.write_bool(self.ca1_prev);
+        w.write_bool(self.cb1_prev);
+        w.write_bool(self.pb7_output);
+    }
+
+    fn load_state(&mut self, r: &mut StateReader<'_>) -> Result<(), StateError> {
+        self.port_a = r.read_u8()?;
+        self.port_b = r.read_u8()?;
+        self.ddr_a = r.read_u8()?;
+        self.ddr_b = r.read_u8()?;
+        self.external_a = r.read_u8()?;
+        self.external_b = r.read_u8()?;
+        self.timer1_counter = r.read_u16()?;
+        self.timer1_latch = r.read_u16()?;
+        self.timer1_fired = r.read_bool()?;
+        self.timer1_running = r.read_bool()?;
+        self.timer2_counter = r.read_u16()?;
+        self.timer2_latch_lo = r.read_u8()?;
+        self.timer2_fired = r.read_bool()?;
+        self.timer2_running = r.read_bool()?;
+        self.shift_register = r.read_u8()?;
+        self.shift_count = r.read_u8()?;
+        self.acr = r.read_u8()?;
+        self.pcr = r.read_u8()?;
+        self.ifr = r.read_u8()?;
+        self.ier = r.read_u8()?;
+        self.ca1_prev = r.read_bool()?;
+        self.cb1_prev = r.read_bool()?;
+        self.pb7_output = r.read_bool()?;
+        Ok(())
+    }
+}
+
 impl Default for Via6522 {
     fn default() -> Self {
         Self::new()
@@ -17,6 +17,8 @@
 
 pub mod palette;
 
+use emu_core::crt::{Signal, Standard};
+use emu_core::{SaveState, StateError, StateReader, StateWriter};
 use palette::PALETTE;
 
 // --- PAL defaults (used for the public constants) ---
@@ -103,6 +105,21 @@ impl VicModel {
             Self::Ntsc6567 => 65,
         }
     }
+
+    /// Composite video timing. Luma and chroma are generated separately
+    /// and summed at the output, so colours are encoded from the palette.
+    ///
+    /// There are 8 pixels per CPU cycle. A PAL cycle is 4.5 subcarrier
+    /// cycles, so a 63-cycle line ends half a cycle out of phase; an NTSC
+    /// cycle is 3.5, so a 65-cycle line does too, and the odd 263-line
+    /// frame flips it again.
+    #[must_use]
+    pub const fn signal(self) -> Signal {
+        match self {
+            Self::Pal6569 => Signal::rgb(Standard::Pal, 4.5 / 8.0, 0.5, &[0.0]),
+            Self::Ntsc6567 => Signal::rgb(Standard::Ntsc, 3.5 / 8.0, 0.5, &[0.0, 0.5]),
+        }
+    }
 }
 
 /// 8 pixels of rendered cell data, returned by each render method.
@@ -195,6 +212,7 @@ pub struct Vic {
     xscroll_latch: u8,
 
     // --- Model-dependent timing ---
+    model: VicModel,
     /// Total raster lines per frame (312 PAL / 263 NTSC).
     lines_per_frame: u16,
     /// CPU cycles per raster line (63 PAL / 65 NTSC).
@@ -247,6 +265,7 @@ impl Vic {
             xscroll_carry_pixels: [0; 8],
             xscroll_carry_fg: 0,
             xscroll_latch: 0,
+            model,
             lines_per_frame: model.lines_per_frame(),
             cycles_per_line: model.cycles_per_line(),
             first_visible_line: first_vis,
@@ -256,6 +275,21 @@ impl Vic {
         }
     }
 
+    /// Chip model.
+    #[must_use]
+    pub fn model(&self) -> VicModel {
+        self.model
+    }
+
+    /// The 40×25 display window inside the border, as x, y, width and
+    /// height in the framebuffer. Rows run from line $33 to $FA.
+    #[must_use]
+    pub fn display_window(&self) -> (u32, u32, u32, u32) {
+        let x = u32::from(DISPLAY_START_CYCLE - FIRST_VISIBLE_CYCLE) * 8;
+        let y = u32::from(0x33 - self.first_visible_line);
+        (x, y, 320, 200)
+    }
+
     /// Tick the VIC-II for one CPU cycle.
     ///
     /// Renders 8 pixels, advances the beam, detects badlines.
@@ -1132,6 +1166,79 @@ impl Vic {
     }
 }
 
+/// Model timing (lines, cycles, visible window) is configuration: a state
+/// taken on the other video standard is rejected.
+impl SaveState for Vic {
+    fn save_state(&self, w: &mut StateWriter) {
+        w.write_u16(self.lines_per_frame);
+        w.write_bytes(&self.regs);
+        w.write_u16(self.raster_line);
+        w.write_u8(self.raster_cycle);
+        w.write_u16(self.raster_compare);
+        w.write_u8(self.irq_status);
+        w.write_u8(self.irq_enable);
+        w.write_bool(self.is_badline);
+        w.write_bool(self.den_latch);
+        w.write_bool(self.frame_complete);
+        w.write_u32_slice(&self.framebuffer);
+        w.write_bytes(&self.screen_row);
+        w.write_bytes(&self.colour_row);
+        w.write_u8(self.char_row);
+        w.write_u8(self.vic_bank);
+        w.write_bytes(&self.sprite_data.concat());
+        w.write_bool_slice(&self.sprite_active);
+        w.write_bool_slice(&self.sprite_dma_active);
+        w.write_u8(self.sprite_sprite_collision);
+        w.write_u8(self.sprite_bg_collision);
+        w.write_bool(self.sprite_sprite_irq_latched);
+        w.write_bool(self.sprite_bg_irq_latched);
+        w.write_u16(self.text_row);
+        w.write_u32_slice(&self.xscroll_carry_pixels);
+        w.write_u8(self.xscroll_carry_fg);
+        w.write_u8(self.xscroll_latch);
+        w.write_bool(self.lp_triggered);
+        w.write_u8(self.last_bus_data);
+    }
+
+    fn load_state(&mut self, r: &mut StateReader<'_>) -> Result<(), StateError> {
+        if r.read_u16()? != self.lines_per_frame {
+            return Err(StateError::Invalid("VIC-II model mismatch".into()));
+        }
+        r.read_bytes_into(&mut self.regs)?;
+        self.raster_line = r.read_u16()?;
+        self.raster_cycle = r.read_u8()?;
+        self.raster_compare = r.read_u16()?;
+        self.irq_status = r.read_u8()?;
+        self.irq_enable = r.read_u8()?;
+        self.is_badline = r.read_bool()?;
+        self.den_latch = r.read_bool()?;
+        self.frame_complete = r.read_bool()?;
+        r.read_u32_into(&mut self.framebuffer)?;
+        r.read_bytes_into(&mut self.screen_row)?;
+        r.read_bytes_into(&mut self.colour_row)?;
+        self.char_row = r.read_u8()?;
+        self.vic_bank = r.read_u8()?;
+        let mut sprite_data = [0u8; 24];
+        r.read_bytes_into(&mut sprite_data)?;
+        for (dst, src) in self.sprite_data.iter_mut().zip(sprite_data.chunks_exact(3)) {
+            dst.copy_from_slice(src);
+        }
+        r.read_bool_into(&mut self.sprite_active)?;
+        r.read_bool_into(&mut self.sprite_dma_active)?;
+        self.sprite_sprite_collision = r.read_u8()?;
+        self.sprite_bg_collision = r.read_u8()?;
+        self.sprite_sprite_irq_latched = r.read_bool()?;
+        self.sprite_bg_irq_latched = r.read_bool()?;
+        self.text_row = r.read_u16()?;
+        r.read_u32_into(&mut self.xscroll_carry_pixels)?;
+        self.xscroll_carry_fg = r.read_u8()?;
+        self.xscroll_latch = r.read_u8()?;
+        self.lp_triggered = r.read_bool()?;
+        self.last_bus_data = r.read_u8()?;
+        Ok(())
+    }
+}
+
 impl Default for Vic {
     fn default() -> Self {
         Self::new(VicModel::Pal6569)
@@ -1262,6 +1369,28 @@ mod tests {
         );
     }
 
+    #[test]
+    fn display_window_is_inside_the_border() {
+        let (mut vic, memory) = make_vic_and_memory();
+        vic.write(0x20, 0x06); // Border colour = blue
+        vic.write(0x11, 0x1B); // Display on, 25 rows
+        vic.write(0x16, 0x08); // 40 columns
+        let total_cycles = u32::from(LINES_PER_FRAME) * u32::from(CYCLES_PER_LINE);
+        for _ in 0..total_cycles * 2 {
+            tick_vic(&mut vic, &memory);
+        }
+
+        let (x, y, w, h) = vic.display_window();
+        let border = PALETTE[6];
+        let pixel = |px: u32, py: u32| vic.framebuffer()[(py * FB_WIDTH + px) as usize];
+        assert_ne!(pixel(x, y), border);
+        assert_ne!(pixel(x + w - 1, y + h - 1), border);
+        assert_eq!(pixel(x - 1, y), border);
+        assert_eq!(pixel(x, y - 1), border);
+        assert_eq!(pixel(x + w, y + h - 1), border);
+        assert_eq!(pixel(x + w - 1, y + h), border);
+    }
+
     #[test]
     fn register_read_write() {
         let mut vic = Vic::new(VicModel::Pal6569);
@@ -1,5 +1,7 @@
 //! Reactive M68k Bus Trait.
 
+use emu_core::{AccessKind, BusAccess};
+
 /// Function code values from the 68000's FC0-FC2 pins.
 #[derive(Debug, Clone, Copy, PartialEq, Eq)]
 pub enum FunctionCode {
@@ -57,3 +59,95 @@ pub trait M68kBus {
     /// Assert the RESET line on the bus.
     fn reset(&mut self);
 }
+
+/// Bus adapter that forwards every cycle and logs the completed ones.
+///
+/// Word cycles are logged as two byte accesses (high byte first) so
+/// watchpoints see the same byte addresses as on 8-bit CPUs. Interrupt
+/// acknowledge cycles are not logged. `wait` counts the polls that
+/// returned [`BusStatus::Wait`] before the cycle completed.
+pub struct LoggingM68kBus<'a, B> {
+    bus: &'a mut B,
+    log: &'a mut Vec<BusAccess>,
+    waits: u8,
+}
+
+impl<'a, B> LoggingM68kBus<'a, B> {
+    pub fn new(bus: &'a mut B, log: &'a mut Vec<BusAccess>) -> Self {
+        Self { bus, log, waits: 0 }
+    }
+}
+
+impl<B: M68kBus> M68kBus for LoggingM68kBus<'_, B> {
+    fn poll_cycle(
+        &mut self,
+        addr: u32,
+        fc: FunctionCode,
+        is_read: bool,
+        is_word: bool,
+        data: Option<u16>,
+    ) -> BusStatus {
+        let status = self.bus.poll_cycle(addr, fc, is_read, is_word, data);
+        match status {
+            BusStatus::Wait => {
+                self.waits = self.waits.saturating_add(1);
+                return status;
+            }
+            BusStatus::Error => {
+                self.waits = 0;
+                return status;
+            }
+            BusStatus::Ready(_) if fc == FunctionCode::InterruptAck => {
+                self.waits = 0;
+                return status;
+            }
+            BusStatus::Ready(_) => {}
+        }
+
+        let value = match (is_read, status) {
+            (true, BusStatus::Ready(read)) => read,
+            _ => data.unwrap_or(0),
+        };
+        let kind = if is_read {
+            AccessKind::Read
+        } else {
+            AccessKind::Write
+        };
+        let wait = std::mem::take(&mut self.waits);
+        if is_word {
+            let base = addr & !1;
+            self.log.push(BusAccess {
+                kind,
+                address: base,
+                value: (value >> 8) as u8,
+                wait,
+            });
+            self.log.push(BusAccess {
+                kind,
+                address: base | 1,
+                value: value as u8,
+                wait: 0,
+            });
+        } else {
+            self.log.push(BusAccess {
+                kind,
+                address: addr,
+                value: value as u8,
+                wait,
+            });
+        }
+        status
+    }
+
+    fn poll_ipl(&mut self) -> u8 {
+        self.bus.poll_ipl()
+    }
+
+    fn poll_interrupt_ack(&mut self, level: u8) -> BusStatus {
+        self.bus.poll_interrupt_ack(level)
+    }
+
+    fn reset(&mut self) {
+        self.bus.reset();
+    }
+}
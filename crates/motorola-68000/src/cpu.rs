@@ -399,6 +399,9 @@ pub struct Cpu68000 {
     pub next_fetch_addr: u32,
     /// PC value at the start of the current instruction (opcode address).
     pub instr_start_pc: u32,
+    /// True from the tick that promoted an instruction into IR until the
+    /// next tick, so debuggers see every instruction start.
+    pub instr_started: bool,
 
     // --- Instruction execution state ---
     /// Computed effective address for memory operations.
@@ -548,6 +551,7 @@ impl Cpu68000 {
             addr: 0,
             data: 0,
             instr_start_pc: 0,
+            instr_started: false,
             in_followup: false,
             followup_tag: 0,
             src_mode: None,
@@ -830,6 +834,25 @@ impl Cpu68000 {
         self.state = State::Idle;
     }
 
+    /// Move execution to `pc` between instructions, as a debugger does
+    /// when it sets the program counter.
+    ///
+    /// `opcode` is the word at `pc`. It goes straight into IRC, so the CPU
+    /// sits at an instruction boundary and the next tick starts the
+    /// instruction there.
+    pub fn jump_to(&mut self, pc: u32, opcode: u16) {
+        self.irc = opcode;
+        self.irc_addr = pc;
+        self.next_fetch_addr = pc.wrapping_add(2);
+        self.regs.pc = self.next_fetch_addr;
+        self.micro_ops.clear();
+        self.micro_ops.push(MicroOp::PromoteIRC);
+        self.instr_started = false;
+        self.in_followup = false;
+        self.followup_tag = 0;
+        self.state = State::Idle;
+    }
+
     /// Consume the current IRC value and queue a FetchIRC to replace it.
     ///
     /// Used when the instruction needs an extension word (immediate data,
@@ -859,6 +882,31 @@ impl Cpu68000 {
         matches!(self.state, State::Idle)
     }
 
+    /// Address of the next instruction when the CPU sits between
+    /// instructions, `None` otherwise.
+    ///
+    /// The CPU is between instructions when it is idle and either has no
+    /// micro-ops queued or only the `PromoteIRC` that branches and jumps
+    /// leave behind after refilling the prefetch queue. That address is
+    /// `irc_addr`: IRC holds the next opcode, while `regs.pc` already
+    /// points past it.
+    ///
+    /// Most instructions are promoted in the same tick the previous one
+    /// finishes, so the queue never drains between them. For the rest of
+    /// that tick the new instruction has not run yet, and its address
+    /// (`instr_start_pc`) is reported instead.
+    #[must_use]
+    pub fn instruction_boundary(&self) -> Option<u32> {
+        if self.instr_started {
+            return Some(self.instr_start_pc);
+        }
+        let between = match self.micro_ops.front() {
+            None => true,
+            Some(op) => matches!(op, MicroOp::PromoteIRC),
+        };
+        (matches!(self.state, State::Idle) && between).then_some(self.irc_addr)
+    }
+
     /// Advance the CPU by one crystal clock cycle.
     ///
     /// The 68000 only acts on 4-clock boundaries. Non-aligned ticks
@@ -874,6 +922,7 @@ impl Cpu68000 {
         if !crystal_clock.is_multiple_of(4) {
             return;
         }
+        self.instr_started = false;
 
         // --- Idle: drain instant ops, check interrupts, start bus cycles ---
         if matches!(self.state, State::Idle) {
@@ -1160,6 +1209,7 @@ impl Cpu68000 {
     /// decode the new opcode.
     fn promote_pipeline(&mut self) {
         self.instr_start_pc = self.irc_addr;
+        self.instr_started = true;
         self.ir = self.irc;
         // Standard 68000: PC points past the opcode word
         self.regs.pc = self.instr_start_pc.wrapping_add(2);
@@ -2003,6 +2053,9 @@ impl Cpu68000 {
     }
 }
 
+// Save-state serialisation
+mod state;
+
 impl emu_core::Observable for Cpu68000 {
     fn query(&self, path: &str) -> Option<emu_core::Value> {
         use emu_core::Value;
@@ -2271,6 +2324,30 @@ mod tests {
         );
     }
 
+    #[test]
+    fn every_instruction_start_is_a_boundary() {
+        let mut cpu = Cpu68000::new();
+        let mut bus = InterruptLoopTestBus::new();
+        let mut clock = 0u64;
+
+        // $0120: MOVEQ #$42,D0 ; BRA.S *
+        cpu.jump_to(0x0120, 0x7042);
+        assert_eq!(cpu.instruction_boundary(), Some(0x0120));
+
+        let mut starts = Vec::new();
+        let mut last = cpu.instruction_boundary();
+        for _ in 0..40 {
+            tick_cpu(&mut cpu, &mut bus, &mut clock, 1);
+            let boundary = cpu.instruction_boundary();
+            if boundary.is_some() && boundary != last {
+                starts.push(boundary);
+            }
+            last = boundary;
+        }
+        assert_eq!(starts[..3], [Some(0x0122), Some(0x0122), Some(0x0122)]);
+        assert_eq!(cpu.regs.d[0], 0x42);
+    }
+
     #[test]
     fn observable_registers() {
         use emu_core::Observable;
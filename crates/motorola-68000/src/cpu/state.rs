@@ -0,0 +1,486 @@
+//! Save-state serialisation for the 68000.
+
+use emu_core::{SaveState, StateError, StateReader, StateWriter};
+
+use crate::addressing::AddrMode;
+use crate::alu::Size;
+use crate::bus::FunctionCode;
+use crate::microcode::MicroOp;
+use crate::mmu::{PendingBusCycle, TableWalkContext};
+use crate::registers::{FpReg, Registers};
+
+use super::{AluOp, BitOp, Cpu68000, DCache, ICache, State};
+
+impl SaveState for Registers {
+    fn save_state(&self, w: &mut StateWriter) {
+        w.write_u32_slice(&self.d);
+        w.write_u32_slice(&self.a);
+        for v in [self.usp, self.ssp, self.msp, self.caar, self.pc] {
+            w.write_u32(v);
+        }
+        w.write_u16(self.sr);
+        w.write_u32(self.vbr);
+        w.write_u8(self.sfc);
+        w.write_u8(self.dfc);
+        for v in [
+            self.cacr,
+            self.tc,
+            self.itt0,
+            self.itt1,
+            self.dtt0,
+            self.dtt1,
+            self.srp,
+            self.srp_upper,
+            self.urp,
+            self.crp_upper,
+            self.mmusr,
+            self.buscr,
+            self.pcr,
+        ] {
+            w.write_u32(v);
+        }
+        for fp in &self.fp {
+            w.write_f64(fp.0);
+        }
+        w.write_u32(self.fpcr);
+        w.write_u32(self.fpsr);
+        w.write_u32(self.fpiar);
+    }
+
+    fn load_state(&mut self, r: &mut StateReader<'_>) -> Result<(), StateError> {
+        r.read_u32_into(&mut self.d)?;
+        r.read_u32_into(&mut self.a)?;
+        for v in [
+            &mut self.usp,
+            &mut self.ssp,
+            &mut self.msp,
+            &mut self.caar,
+            &mut self.pc,
+        ] {
+            *v = r.read_u32()?;
+        }
+        self.sr = r.read_u16()?;
+        self.vbr = r.read_u32()?;
+        self.sfc = r.read_u8()?;
+        self.dfc = r.read_u8()?;
+        for v in [
+            &mut self.cacr,
+            &mut self.tc,
+            &mut self.itt0,
+            &mut self.itt1,
+            &mut self.dtt0,
+            &mut self.dtt1,
+            &mut self.srp,
+            &mut self.srp_upper,
+            &mut self.urp,
+            &mut self.crp_upper,
+            &mut self.mmusr,
+            &mut self.buscr,
+            &mut self.pcr,
+        ] {
+            *v = r.read_u32()?;
+        }
+        for fp in &mut self.fp {
+            *fp = FpReg(r.read_f64()?);
+        }
+        self.fpcr = r.read_u32()?;
+        self.fpsr = r.read_u32()?;
+        self.fpiar = r.read_u32()?;
+        Ok(())
+    }
+}
+
+fn write_micro_op(w: &mut StateWriter, op: MicroOp) {
+    w.write_u8(op.code());
+    w.write_u8(if let MicroOp::Internal(n) = op { n } else { 0 });
+}
+
+fn read_micro_op(r: &mut StateReader<'_>) -> Result<MicroOp, StateError> {
+    let code = r.read_u8()?;
+    let arg = r.read_u8()?;
+    MicroOp::from_code(code, arg)
+        .ok_or_else(|| StateError::Invalid(format!("bad 68000 micro-op {code}")))
+}
+
+fn read_function_code(r: &mut StateReader<'_>) -> Result<FunctionCode, StateError> {
+    Ok(match r.read_u8()? {
+        1 => FunctionCode::UserData,
+        2 => FunctionCode::UserProgram,
+        5 => FunctionCode::SupervisorData,
+        6 => FunctionCode::SupervisorProgram,
+        7 => FunctionCode::InterruptAck,
+        n => return Err(StateError::Invalid(format!("function code {n}"))),
+    })
+}
+
+fn write_data(w: &mut StateWriter, data: Option<u16>) {
+    w.write_bool(data.is_some());
+    w.write_u16(data.unwrap_or(0));
+}
+
+fn read_data(r: &mut StateReader<'_>) -> Result<Option<u16>, StateError> {
+    Ok(r.read_bool()?.then_some(r.read_u16()?))
+}
+
+fn write_addr_mode(w: &mut StateWriter, mode: Option<AddrMode>) {
+    let (code, reg) = match mode {
+        None => (0, 0),
+        Some(AddrMode::DataReg(n)) => (1, n),
+        Some(AddrMode::AddrReg(n)) => (2, n),
+        Some(AddrMode::AddrInd(n)) => (3, n),
+        Some(AddrMode::AddrIndPostInc(n)) => (4, n),
+        Some(AddrMode::AddrIndPreDec(n)) => (5, n),
+        Some(AddrMode::AddrIndDisp(n)) => (6, n),
+        Some(AddrMode::AddrIndIndex(n)) => (7, n),
+        Some(AddrMode::AbsShort) => (8, 0),
+        Some(AddrMode::AbsLong) => (9, 0),
+        Some(AddrMode::PcDisp) => (10, 0),
+        Some(AddrMode::PcIndex) => (11, 0),
+        Some(AddrMode::Immediate) => (12, 0),
+    };
+    w.write_u8(code);
+    w.write_u8(reg);
+}
+
+fn read_addr_mode(r: &mut StateReader<'_>) -> Result<Option<AddrMode>, StateError> {
+    let code = r.read_u8()?;
+    let reg = r.read_u8()?;
+    Ok(Some(match code {
+        0 => return Ok(None),
+        1 => AddrMode::DataReg(reg),
+        2 => AddrMode::AddrReg(reg),
+        3 => AddrMode::AddrInd(reg),
+        4 => AddrMode::AddrIndPostInc(reg),
+        5 => AddrMode::AddrIndPreDec(reg),
+        6 => AddrMode::AddrIndDisp(reg),
+        7 => AddrMode::AddrIndIndex(reg),
+        8 => AddrMode::AbsShort,
+        9 => AddrMode::AbsLong,
+        10 => AddrMode::PcDisp,
+        11 => AddrMode::PcIndex,
+        12 => AddrMode::Immediate,
+        n => return Err(StateError::Invalid(format!("addressing mode {n}"))),
+    }))
+}
+
+fn write_size(w: &mut StateWriter, size: Size) {
+    w.write_u8(size as u8);
+}
+
+fn read_size(r: &mut StateReader<'_>) -> Result<Size, StateError> {
+    Ok(match r.read_u8()? {
+        0 => Size::Byte,
+        1 => Size::Word,
+        2 => Size::Long,
+        n => return Err(StateError::Invalid(format!("operand size {n}"))),
+    })
+}
+
+fn write_walk(w: &mut StateWriter, walk: &TableWalkContext) {
+    let pending = &walk.pending;
+    write_micro_op(w, pending.op);
+    w.write_u32(pending.logical_addr);
+    w.write_u8(pending.fc.bits());
+    w.write_bool(pending.is_read);
+    w.write_bool(pending.is_word);
+    write_data(w, pending.data);
+    w.write_u8(walk.level);
+    w.write_u32(walk.next_descriptor_addr);
+    w.write_u32(walk.page_mask);
+    w.write_u32(walk.page_offset);
+    w.write_bytes(&walk.index_fields);
+    w.write_u8(walk.remaining_shift);
+    w.write_bool(walk.is_040);
+    w.write_u8(walk.page_shift_040);
+    w.write_bool(walk.write_protect);
+    w.write_bool(walk.cache_inhibit);
+}
+
+fn read_walk(r: &mut StateReader<'_>) -> Result<TableWalkContext, StateError> {
+    let pending = PendingBusCycle {
+        op: read_micro_op(r)?,
+        logical_addr: r.read_u32()?,
+        fc: read_function_code(r)?,
+        is_read: r.read_bool()?,
+        is_word: r.read_bool()?,
+        data: read_data(r)?,
+    };
+    let level = r.read_u8()?;
+    let next_descriptor_addr = r.read_u32()?;
+    let page_mask = r.read_u32()?;
+    let page_offset = r.read_u32()?;
+    let mut index_fields = [0; 4];
+    r.read_bytes_into(&mut index_fields)?;
+    Ok(TableWalkContext {
+        pending,
+        level,
+        next_descriptor_addr,
+        page_mask,
+        page_offset,
+        index_fields,
+        remaining_shift: r.read_u8()?,
+        is_040: r.read_bool()?,
+        page_shift_040: r.read_u8()?,
+        write_protect: r.read_bool()?,
+        cache_inhibit: r.read_bool()?,
+    })
+}
+
+impl SaveState for State {
+    fn save_state(&self, w: &mut StateWriter) {
+        match self {
+            Self::Idle => w.write_u8(0),
+            Self::Internal { cycles } => {
+                w.write_u8(1);
+                w.write_u8(*cycles);
+            }
+            Self::BusCycle {
+                op,
+                addr,
+                fc,
+                is_read,
+                is_word,
+                data,
+                cycle_count,
+            } => {
+                w.write_u8(2);
+                write_micro_op(w, *op);
+                w.write_u32(*addr);
+                w.write_u8(fc.bits());
+                w.write_bool(*is_read);
+                w.write_bool(*is_word);
+                write_data(w, *data);
+                w.write_u8(*cycle_count);
+            }
+            Self::TableWalk {
+                walk_addr,
+                walk_cycle_count,
+                walk_reading_lo,
+                descriptor_hi,
+                walk,
+            } => {
+                w.write_u8(3);
+                w.write_u32(*walk_addr);
+                w.write_u8(*walk_cycle_count);
+                w.write_bool(*walk_reading_lo);
+                w.write_u16(*descriptor_hi);
+                write_walk(w, walk);
+            }
+            Self::Halted => w.write_u8(4),
+            Self::Stopped => w.write_u8(5),
+        }
+    }
+
+    fn load_state(&mut self, r: &mut StateReader<'_>) -> Result<(), StateError> {
+        *self = match r.read_u8()? {
+            0 => Self::Idle,
+            1 => Self::Internal {
+                cycles: r.read_u8()?,
+            },
+            2 => Self::BusCycle {
+                op: read_micro_op(r)?,
+                addr: r.read_u32()?,
+                fc: read_function_code(r)?,
+                is_read: r.read_bool()?,
+                is_word: r.read_bool()?,
+                data: read_data(r)?,
+                cycle_count: r.read_u8()?,
+            },
+            3 => Self::TableWalk {
+                walk_addr: r.read_u32()?,
+                walk_cycle_count: r.read_u8()?,
+                walk_reading_lo: r.read_bool()?,
+                descriptor_hi: r.read_u16()?,
+                walk: read_walk(r)?,
+            },
+            4 => Self::Halted,
+            5 => Self::Stopped,
+            n => return Err(StateError::Invalid(format!("68000 state {n}"))),
+        };
+        Ok(())
+    }
+}
+
+impl SaveState for ICache {
+    fn save_state(&self, w: &mut StateWriter) {
+        for line in &self.lines {
+            w.write_u32(line.tag);
+            w.write_u16_slice(&line.words);
+            w.write_bool_slice(&line.valid);
+        }
+    }
+
+    fn load_state(&mut self, r: &mut StateReader<'_>) -> Result<(), StateError> {
+        for line in &mut self.lines {
+            line.tag = r.read_u32()?;
+            r.read_u16_into(&mut line.words)?;
+            r.read_bool_into(&mut line.valid)?;
+        }
+        Ok(())
+    }
+}
+
+impl SaveState for DCache {
+    fn save_state(&self, w: &mut StateWriter) {
+        for line in &self.lines {
+            w.write_u32(line.tag);
+            w.write_u16_slice(&line.words);
+            w.write_bool_slice(&line.valid);
+        }
+    }
+
+    fn load_state(&mut self, r: &mut StateReader<'_>) -> Result<(), StateError> {
+        for line in &mut self.lines {
+            line.tag = r.read_u32()?;
+            r.read_u16_into(&mut line.words)?;
+            r.read_bool_into(&mut line.valid)?;
+        }
+        Ok(())
+    }
+}
+
+/// The CPU model is configuration: a state taken on a different model is
+/// rejected. Debug logging is a host setting and is not saved.
+impl SaveState for Cpu68000 {
+    fn save_state(&self, w: &mut StateWriter) {
+        w.write_u8(self.model as u8);
+        self.regs.save_state(w);
+        self.state.save_state(w);
+        self.micro_ops.save_state(w);
+        w.write_u16(self.ir);
+        w.write_u16(self.irc);
+        w.write_u32(self.irc_addr);
+        w.write_u32(self.next_fetch_addr);
+        w.write_u32(self.instr_start_pc);
+        w.write_u32(self.addr);
+        w.write_u32(self.data);
+        w.write_bool(self.in_followup);
+        w.write_u8(self.followup_tag);
+        write_addr_mode(w, self.src_mode);
+        write_addr_mode(w, self.dst_mode);
+        write_size(w, self.size);
+        w.write_u8(self.ea_reg);
+        w.write_u32(self.ea_pc);
+        w.write_u8(self.alu_op as u8);
+        w.write_u8(self.bit_op as u8);
+        w.write_u8(self.target_ipl);
+        w.write_u16(self.movem_mask);
+        w.write_u8(self.movem_idx);
+        w.write_bool(self.movem_is_write);
+        w.write_u8(self.movem_an_reg);
+        w.write_u16_slice(&self.move16_buf);
+        w.write_u8(self.move16_idx);
+        w.write_u32(self.move16_dst_addr);
+        w.write_f64(self.fpu_source);
+        w.write_bool(self.exc_vector.is_some());
+        w.write_u8(self.exc_vector.unwrap_or(0));
+        w.write_u32(self.src_val);
+        w.write_u32(self.dst_val);
+        w.write_u32(self.ae_fault_addr);
+        w.write_u16(self.ae_access_info);
+        w.write_u16(self.ae_saved_sr);
+        w.write_bool(self.ae_in_progress);
+        w.write_bool(self.ae_from_fetch_irc);
+        w.write_bool(self.dbcc_dn_undo.is_some());
+        let (reg, value) = self.dbcc_dn_undo.unwrap_or_default();
+        w.write_u8(reg);
+        w.write_u16(value);
+        w.write_u16(self.ae_frame_ir);
+        w.write_bool(self.pre_move_sr.is_some());
+        w.write_u16(self.pre_move_sr.unwrap_or(0));
+        w.write_bool(self.pre_move_vc.is_some());
+        w.write_u16(self.pre_move_vc.unwrap_or(0));
+        w.write_bool(self.program_space_access);
+        w.write_bool(self.ae_undo_reg.is_some());
+        let (reg, amount, postinc, dst) = self.ae_undo_reg.unwrap_or_default();
+        w.write_u8(reg);
+        w.write_u32(amount);
+        w.write_bool(postinc);
+        w.write_bool(dst);
+        w.write_bool(self.sp_undo.is_some());
+        let (supervisor, sp) = self.sp_undo.unwrap_or_default();
+        w.write_bool(supervisor);
+        w.write_u32(sp);
+        w.write_u8(self.be_extra_count);
+        w.write_u16(self.be_format_word);
+        w.write_u8(self.group0_vector);
+        self.icache.save_state(w);
+        self.dcache.save_state(w);
+        self.mmu.save_state(w);
+    }
+
+    fn load_state(&mut self, r: &mut StateReader<'_>) -> Result<(), StateError> {
+        if r.read_u8()? != self.model as u8 {
+            return Err(StateError::Invalid("CPU model mismatch".into()));
+        }
+        self.regs.load_state(r)?;
+        self.state.load_state(r)?;
+        self.micro_ops.load_state(r)?;
+        self.ir = r.read_u16()?;
+        self.irc = r.read_u16()?;
+        self.irc_addr = r.read_u32()?;
+        self.next_fetch_addr = r.read_u32()?;
+        self.instr_start_pc = r.read_u32()?;
+        self.addr = r.read_u32()?;
+        self.data = r.read_u32()?;
+        self.in_followup = r.read_bool()?;
+        self.followup_tag = r.read_u8()?;
+        self.src_mode = read_addr_mode(r)?;
+        self.dst_mode = read_addr_mode(r)?;
+        self.size = read_size(r)?;
+        self.ea_reg = r.read_u8()?;
+        self.ea_pc = r.read_u32()?;
+        self.alu_op = match r.read_u8()? {
+            0 => AluOp::Add,
+            1 => AluOp::Sub,
+            2 => AluOp::Cmp,
+            3 => AluOp::And,
+            4 => AluOp::Or,
+            5 => AluOp::Eor,
+            n => return Err(StateError::Invalid(format!("ALU op {n}"))),
+        };
+        self.bit_op = match r.read_u8()? {
+            0 => BitOp::Btst,
+            1 => BitOp::Bset,
+            2 => BitOp::Bclr,
+            3 => BitOp::Bchg,
+            n => return Err(StateError::Invalid(format!("bit op {n}"))),
+        };
+        self.target_ipl = r.read_u8()?;
+        self.movem_mask = r.read_u16()?;
+        self.movem_idx = r.read_u8()?;
+        self.movem_is_write = r.read_bool()?;
+        self.movem_an_reg = r.read_u8()?;
+        r.read_u16_into(&mut self.move16_buf)?;
+        self.move16_idx = r.read_u8()?;
+        self.move16_dst_addr = r.read_u32()?;
+        self.fpu_source = r.read_f64()?;
+        self.exc_vector = r.read_bool()?.then_some(r.read_u8()?);
+        self.src_val = r.read_u32()?;
+        self.dst_val = r.read_u32()?;
+        self.ae_fault_addr = r.read_u32()?;
+        self.ae_access_info = r.read_u16()?;
+        self.ae_saved_sr = r.read_u16()?;
+        self.ae_in_progress = r.read_bool()?;
+        self.ae_from_fetch_irc = r.read_bool()?;
+        let present = r.read_bool()?;
+        let undo = (r.read_u8()?, r.read_u16()?);
+        self.dbcc_dn_undo = present.then_some(undo);
+        self.ae_frame_ir = r.read_u16()?;
+        self.pre_move_sr = r.read_bool()?.then_some(r.read_u16()?);
+        self.pre_move_vc = r.read_bool()?.then_some(r.read_u16()?);
+        self.program_space_access = r.read_bool()?;
+        let present = r.read_bool()?;
+        let undo = (r.read_u8()?, r.read_u32()?, r.read_bool()?, r.read_bool()?);
+        self.ae_undo_reg = present.then_some(undo);
+        let present = r.read_bool()?;
+        let undo = (r.read_bool()?, r.read_u32()?);
+        self.sp_undo = present.then_some(undo);
+        self.be_extra_count = r.read_u8()?;
+        self.be_format_word = r.read_u16()?;
+        self.group0_vector = r.read_u8()?;
+        self.icache.load_state(r)?;
+        self.dcache.load_state(r)?;
+        self.mmu.load_state(r)
+    }
+}
@@ -9,6 +9,8 @@
 //! same tick, bus ops enter the `BusCycle` state, internal delays enter
 //! the `Internal` state.
 
+use emu_core::{SaveState, StateError, StateReader, StateWriter};
+
 const QUEUE_CAPACITY: usize = 32;
 
 /// A single micro-operation in the CPU pipeline.
@@ -81,6 +83,64 @@ impl MicroOp {
     pub fn is_bus(self) -> bool {
         !self.is_instant() && !matches!(self, Self::Internal(_))
     }
+
+    /// Stable numeric code for save states. `Internal(n)` carries `n`
+    /// separately.
+    #[must_use]
+    pub const fn code(self) -> u8 {
+        match self {
+            Self::FetchIRC => 0,
+            Self::ReadByte => 1,
+            Self::ReadWord => 2,
+            Self::ReadWordNoData => 3,
+            Self::ReadLongHi => 4,
+            Self::ReadLongLo => 5,
+            Self::WriteByte => 6,
+            Self::WriteWord => 7,
+            Self::WriteLongHi => 8,
+            Self::WriteLongLo => 9,
+            Self::PushWord => 10,
+            Self::PushLongHi => 11,
+            Self::PushLongLo => 12,
+            Self::PopWord => 13,
+            Self::PopLongHi => 14,
+            Self::PopLongLo => 15,
+            Self::InterruptAck => 16,
+            Self::Internal(_) => 17,
+            Self::AssertReset => 18,
+            Self::Execute => 19,
+            Self::PromoteIRC => 20,
+        }
+    }
+
+    /// Inverse of [`code`](Self::code).
+    #[must_use]
+    pub fn from_code(code: u8, arg: u8) -> Option<Self> {
+        Some(match code {
+            0 => Self::FetchIRC,
+            1 => Self::ReadByte,
+            2 => Self::ReadWord,
+            3 => Self::ReadWordNoData,
+            4 => Self::ReadLongHi,
+            5 => Self::ReadLongLo,
+            6 => Self::WriteByte,
+            7 => Self::WriteWord,
+            8 => Self::WriteLongHi,
+            9 => Self::WriteLongLo,
+            10 => Self::PushWord,
+            11 => Self::PushLongHi,
+            12 => Self::PushLongLo,
+            13 => Self::PopWord,
+            14 => Self::PopLongHi,
+            15 => Self::PopLongLo,
+            16 => Self::InterruptAck,
+            17 => Self::Internal(arg),
+            18 => Self::AssertReset,
+            19 => Self::Execute,
+            20 => Self::PromoteIRC,
+            _ => return None,
+        })
+    }
 }
 
 /// Fixed-capacity circular queue of micro-operations.
@@ -174,3 +234,29 @@ impl MicroOpQueue {
         out
     }
 }
+
+impl SaveState for MicroOpQueue {
+    fn save_state(&self, w: &mut StateWriter) {
+        for op in &self.ops {
+            w.write_u8(op.code());
+            w.write_u8(if let MicroOp::Internal(n) = op { *n } else { 0 });
+        }
+        w.write_u8(self.head);
+        w.write_u8(self.len);
+    }
+
+    fn load_state(&mut self, r: &mut StateReader<'_>) -> Result<(), StateError> {
+        for op in &mut self.ops {
+            let code = r.read_u8()?;
+            let arg = r.read_u8()?;
+            *op = MicroOp::from_code(code, arg)
+                .ok_or_else(|| StateError::Invalid(format!("bad 68000 micro-op {code}")))?;
+        }
+        self.head = r.read_u8()?;
+        self.len = r.read_u8()?;
+        if self.head as usize >= QUEUE_CAPACITY || self.len as usize > QUEUE_CAPACITY {
+            return Err(StateError::Invalid("68000 micro-op queue out of range".into()));
+        }
+        Ok(())
+    }
+}
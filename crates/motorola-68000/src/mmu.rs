@@ -7,6 +7,8 @@
 //! 4-way set-associative ATCs (instruction + data), four TT registers
 //! (ITT0/ITT1 for instruction, DTT0/DTT1 for data).
 
+use emu_core::{SaveState, StateError, StateReader, StateWriter};
+
 use crate::model::{CpuModel, TimingClass};
 
 // ---------------------------------------------------------------------------
@@ -331,6 +333,31 @@ impl AtcEntry {
     };
 }
 
+impl SaveState for AtcEntry {
+    fn save_state(&self, w: &mut StateWriter) {
+        w.write_u32(self.logical_page);
+        w.write_u32(self.physical_page);
+        w.write_u8(self.fc);
+        w.write_bool(self.valid);
+        w.write_bool(self.write_protect);
+        w.write_bool(self.cache_inhibit);
+        w.write_bool(self.modified);
+        w.write_bool(self.global);
+    }
+
+    fn load_state(&mut self, r: &mut StateReader<'_>) -> Result<(), StateError> {
+        self.logical_page = r.read_u32()?;
+        self.physical_page = r.read_u32()?;
+        self.fc = r.read_u8()?;
+        self.valid = r.read_bool()?;
+        self.write_protect = r.read_bool()?;
+        self.cache_inhibit = r.read_bool()?;
+        self.modified = r.read_bool()?;
+        self.global = r.read_bool()?;
+        Ok(())
+    }
+}
+
 /// 68030 ATC: 22-entry fully associative with FIFO replacement.
 #[derive(Clone)]
 pub struct Atc030 {
@@ -338,6 +365,26 @@ pub struct Atc030 {
     next_slot: usize,
 }
 
+impl SaveState for Atc030 {
+    fn save_state(&self, w: &mut StateWriter) {
+        for v in &self.entries {
+            v.save_state(w);
+        }
+        w.write_usize(self.next_slot);
+    }
+
+    fn load_state(&mut self, r: &mut StateReader<'_>) -> Result<(), StateError> {
+        for v in &mut self.entries {
+            v.load_state(r)?;
+        }
+        self.next_slot = r.read_usize()?;
+        if self.next_slot >= ATC_030_SIZE {
+            return Err(StateError::Invalid("ATC slot out of range".into()));
+        }
+        Ok(())
+    }
+}
+
 impl Default for Atc030 {
     fn default() -> Self {
         Self::new()
@@ -417,6 +464,30 @@ pub struct Atc040Bank {
     next_way: [u8; ATC_040_SETS],
 }
 
+impl SaveState for Atc040Bank {
+    fn save_state(&self, w: &mut StateWriter) {
+        for set in &self.entries {
+            for e in set {
+                e.save_state(w);
+            }
+        }
+        w.write_bytes(&self.next_way);
+    }
+
+    fn load_state(&mut self, r: &mut StateReader<'_>) -> Result<(), StateError> {
+        for set in &mut self.entries {
+            for e in set {
+                e.load_state(r)?;
+            }
+        }
+        r.read_bytes_into(&mut self.next_way)?;
+        if self.next_way.iter().any(|&way| way as usize >= ATC_040_WAYS) {
+            return Err(StateError::Invalid("ATC way out of range".into()));
+        }
+        Ok(())
+    }
+}
+
 impl Default for Atc040Bank {
     fn default() -> Self {
         Self::new()
@@ -501,6 +572,18 @@ pub struct Atc040 {
     pub data: Atc040Bank,
 }
 
+impl SaveState for Atc040 {
+    fn save_state(&self, w: &mut StateWriter) {
+        self.instruction.save_state(w);
+        self.data.save_state(w);
+    }
+
+    fn load_state(&mut self, r: &mut StateReader<'_>) -> Result<(), StateError> {
+        self.instruction.load_state(r)?;
+        self.data.load_state(r)
+    }
+}
+
 impl Default for Atc040 {
     fn default() -> Self {
         Self::new()
@@ -672,6 +755,26 @@ impl Mmu {
     }
 }
 
+/// The MMU mode follows the CPU model, which is configuration; only the
+/// ATC contents are saved.
+impl SaveState for Mmu {
+    fn save_state(&self, w: &mut StateWriter) {
+        match &self.atc {
+            AtcStorage::None => {}
+            AtcStorage::M030(atc) => atc.save_state(w),
+            AtcStorage::M040(atc) => atc.save_state(w),
+        }
+    }
+
+    fn load_state(&mut self, r: &mut StateReader<'_>) -> Result<(), StateError> {
+        match &mut self.atc {
+            AtcStorage::None => Ok(()),
+            AtcStorage::M030(atc) => atc.load_state(r),
+            AtcStorage::M040(atc) => atc.load_state(r),
+        }
+    }
+}
+
 // ---------------------------------------------------------------------------
 // Bus integration — translation fast path and table walk context
 // ---------------------------------------------------------------------------
@@ -1,7 +1,8 @@
 //! NEC uPD765 floppy disk controller.
 //!
-//! Standalone IC emulation with no dependencies, following the project's
-//! chip-level library pattern (like `mos-via-6522` and `mos-sid-6581`).
+//! Standalone IC emulation following the project's chip-level library
+//! pattern (like `mos-via-6522` and `mos-sid-6581`). The only dependency is
+//! `emu-core`, for the save-state traits.
 //!
 //! The uPD765 is used in the ZX Spectrum +3, Amstrad CPC, and IBM PC.
 //! This implementation covers the command set needed for +3DOS.
@@ -22,6 +23,8 @@
 pub mod commands;
 pub mod dsk;
 
+use emu_core::{SaveState, StateError, StateReader, StateWriter};
+
 pub use dsk::DskImage;
 
 /// FDC state machine phase.
@@ -290,6 +293,79 @@ impl Upd765 {
     }
 }
 
+impl SaveState for Upd765 {
+    fn save_state(&self, w: &mut StateWriter) {
+        w.write_u8(match self.phase {
+            FdcPhase::Idle => 0,
+            FdcPhase::Command => 1,
+            FdcPhase::Execution => 2,
+            FdcPhase::Result => 3,
+        });
+        w.write_bytes(&self.command_buf);
+        w.write_usize(self.command_len);
+        w.write_bytes(&self.result_buf);
+        w.write_usize(self.result_index);
+        w.write_usize(self.data_len);
+        w.write_u8(self.st0);
+        w.write_u8(self.st1);
+        w.write_u8(self.st2);
+        w.write_bytes(&self.pcn);
+        w.write_bool(self.interrupt_pending);
+        // Disks are writable media, so their contents travel with the state.
+        for disk in &self.disk {
+            w.write_bool(disk.is_some());
+            if let Some(disk) = disk {
+                w.write_bytes(&disk.to_bytes());
+            }
+        }
+        w.write_bytes(&self.write_buf);
+        w.write_usize(self.write_expected);
+        w.write_bool(self.write_params.is_some());
+        let (drive, head, track, r, n) = self.write_params.unwrap_or_default();
+        w.write_usize(drive);
+        for v in [head, track, r, n] {
+            w.write_u8(v);
+        }
+    }
+
+    fn load_state(&mut self, r: &mut StateReader<'_>) -> Result<(), StateError> {
+        self.phase = match r.read_u8()? {
+            0 => FdcPhase::Idle,
+            1 => FdcPhase::Command,
+            2 => FdcPhase::Execution,
+            3 => FdcPhase::Result,
+            v => return Err(StateError::Invalid(format!("bad FDC phase {v}"))),
+        };
+        self.command_buf = r.read_bytes()?.to_vec();
+        self.command_len = r.read_usize()?;
+        self.result_buf = r.read_bytes()?.to_vec();
+        self.result_index = r.read_usize()?;
+        self.data_len = r.read_usize()?;
+        self.st0 = r.read_u8()?;
+        self.st1 = r.read_u8()?;
+        self.st2 = r.read_u8()?;
+        r.read_bytes_into(&mut self.pcn)?;
+        self.interrupt_pending = r.read_bool()?;
+        for disk in &mut self.disk {
+            *disk = if r.read_bool()? {
+                Some(dsk::parse_dsk(r.read_bytes()?).map_err(StateError::Invalid)?)
+            } else {
+                None
+            };
+        }
+        self.write_buf = r.read_bytes()?.to_vec();
+        self.write_expected = r.read_usize()?;
+        let has_params = r.read_bool()?;
+        let drive = r.read_usize()?;
+        let head = r.read_u8()?;
+        let track = r.read_u8()?;
+        let sector = r.read_u8()?;
+        let n = r.read_u8()?;
+        self.write_params = has_params.then_some((drive, head, track, sector, n));
+        Ok(())
+    }
+}
+
 impl Default for Upd765 {
     fn default() -> Self {
         Self::new()
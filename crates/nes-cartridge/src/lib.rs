@@ -23,6 +23,7 @@
 
 #![allow(clippy::cast_possible_truncation)]
 
+use emu_core::{SaveState, StateError, StateReader, StateWriter};
 pub use ricoh_ppu_2c02::Mirroring;
 
 /// Parsed iNES file header.
@@ -40,7 +41,11 @@ pub struct CartridgeHeader {
 ///
 /// `chr_read` takes `&mut self` because some mappers (MMC2, MMC4) update
 /// internal latches when the PPU reads from pattern table addresses.
-pub trait Mapper {
+///
+/// Every mapper is also `SaveState`: banking registers, IRQ counters,
+/// expansion audio and any cartridge RAM (PRG RAM, CHR RAM) are saved, while
+/// PRG and CHR ROM come from the cartridge image and are not.
+pub trait Mapper: SaveState {
     fn cpu_read(&self, addr: u16) -> u8;
     fn cpu_write(&mut self, addr: u16, value: u8);
     fn chr_read(&mut self, addr: u16) -> u8;
@@ -137,6 +142,22 @@ impl Mapper for Nrom {
     }
 }
 
+impl SaveState for Nrom {
+    fn save_state(&self, w: &mut StateWriter) {
+        if self.chr_is_ram {
+            w.write_bytes(&self.chr);
+        }
+        self.mirroring.save_state(w);
+    }
+
+    fn load_state(&mut self, r: &mut StateReader<'_>) -> Result<(), StateError> {
+        if self.chr_is_ram {
+            r.read_bytes_into(&mut self.chr)?;
+        }
+        self.mirroring.load_state(r)
+    }
+}
+
 /// MMC1 (Mapper 1, SxROM): serial shift register bank switching.
 ///
 /// - 5-bit shift register loaded one bit at a time via writes to $8000-$FFFF
@@ -350,6 +371,35 @@ impl Mapper for Mmc1 {
     }
 }
 
+impl SaveState for Mmc1 {
+    fn save_state(&self, w: &mut StateWriter) {
+        if self.chr_is_ram {
+            w.write_bytes(&self.chr);
+        }
+        w.write_bytes(&self.prg_ram);
+        w.write_u8(self.shift_register);
+        w.write_u8(self.shift_count);
+        w.write_u8(self.control);
+        w.write_u8(self.chr_bank_0);
+        w.write_u8(self.chr_bank_1);
+        w.write_u8(self.prg_bank);
+    }
+
+    fn load_state(&mut self, r: &mut StateReader<'_>) -> Result<(), StateError> {
+        if self.chr_is_ram {
+            r.read_bytes_into(&mut self.chr)?;
+        }
+        r.read_bytes_into(&mut self.prg_ram)?;
+        self.shift_register = r.read_u8()?;
+        self.shift_count = r.read_u8()?;
+        self.control = r.read_u8()?;
+        self.chr_bank_0 = r.read_u8()?;
+        self.chr_bank_1 = r.read_u8()?;
+        self.prg_bank = r.read_u8()?;
+        Ok(())
+    }
+}
+
 /// `UxROM` (Mapper 2): simple 16K PRG bank switching.
 ///
 /// One of the most common NES mappers, used by Mega Man, Castlevania,
@@ -429,6 +479,25 @@ impl Mapper for UxRom {
     }
 }
 
+impl SaveState for UxRom {
+    fn save_state(&self, w: &mut StateWriter) {
+        if self.chr_is_ram {
+            w.write_bytes(&self.chr);
+        }
+        self.mirroring.save_state(w);
+        w.write_u8(self.prg_bank);
+    }
+
+    fn load_state(&mut self, r: &mut StateReader<'_>) -> Result<(), StateError> {
+        if self.chr_is_ram {
+            r.read_bytes_into(&mut self.chr)?;
+        }
+        self.mirroring.load_state(r)?;
+        self.prg_bank = r.read_u8()?;
+        Ok(())
+    }
+}
+
 /// CNROM (Mapper 3): simple 8K CHR bank switching.
 ///
 /// Used by many early NES games including Gradius, Paperboy, and
@@ -486,6 +555,19 @@ impl Mapper for CnRom {
     }
 }
 
+impl SaveState for CnRom {
+    fn save_state(&self, w: &mut StateWriter) {
+        self.mirroring.save_state(w);
+        w.write_u8(self.chr_bank);
+    }
+
+    fn load_state(&mut self, r: &mut StateReader<'_>) -> Result<(), StateError> {
+        self.mirroring.load_state(r)?;
+        self.chr_bank = r.read_u8()?;
+        Ok(())
+    }
+}
+
 /// `AxROM` (Mapper 7): 32K PRG bank switching with single-screen mirroring.
 ///
 /// Used by Battletoads, Marble Madness, and Wizards & Warriors.
@@ -553,6 +635,20 @@ impl Mapper for AxRom {
     }
 }
 
+impl SaveState for AxRom {
+    fn save_state(&self, w: &mut StateWriter) {
+        w.write_bytes(&self.chr_ram);
+        w.write_u8(self.bank);
+        self.mirroring.save_state(w);
+    }
+
+    fn load_state(&mut self, r: &mut StateReader<'_>) -> Result<(), StateError> {
+        r.read_bytes_into(&mut self.chr_ram)?;
+        self.bank = r.read_u8()?;
+        self.mirroring.load_state(r)
+    }
+}
+
 /// MMC3 (Mapper 4, TxROM): the second-most common NES mapper.
 ///
 /// Used by SMB3, Kirby's Adventure, Mega Man 3-6, and Batman.
@@ -843,6 +939,45 @@ impl Mapper for Mmc3 {
     }
 }
 
+impl SaveState for Mmc3 {
+    fn save_state(&self, w: &mut StateWriter) {
+        if self.chr_is_ram {
+            w.write_bytes(&self.chr);
+        }
+        w.write_bytes(&self.prg_ram);
+        w.write_u8(self.bank_select);
+        w.write_bytes(&self.registers);
+        self.mirroring.save_state(w);
+        w.write_bool(self.prg_ram_enable);
+        w.write_bool(self.prg_ram_write_protect);
+        w.write_u8(self.irq_latch);
+        w.write_u8(self.irq_counter);
+        w.write_bool(self.irq_reload_flag);
+        w.write_bool(self.irq_enabled);
+        w.write_bool(self.irq_pending);
+        w.write_bool(self.last_a12);
+    }
+
+    fn load_state(&mut self, r: &mut StateReader<'_>) -> Result<(), StateError> {
+        if self.chr_is_ram {
+            r.read_bytes_into(&mut self.chr)?;
+        }
+        r.read_bytes_into(&mut self.prg_ram)?;
+        self.bank_select = r.read_u8()?;
+        r.read_bytes_into(&mut self.registers)?;
+        self.mirroring.load_state(r)?;
+        self.prg_ram_enable = r.read_bool()?;
+        self.prg_ram_write_protect = r.read_bool()?;
+        self.irq_latch = r.read_u8()?;
+        self.irq_counter = r.read_u8()?;
+        self.irq_reload_flag = r.read_bool()?;
+        self.irq_enabled = r.read_bool()?;
+        self.irq_pending = r.read_bool()?;
+        self.last_a12 = r.read_bool()?;
+        Ok(())
+    }
+}
+
 /// MMC2 (Mapper 9, PxROM): CHR latch-based bank switching.
 ///
 /// Used by Punch-Out!! The mapper selects between two CHR banks for each
@@ -983,6 +1118,31 @@ impl Mapper for Mmc2 {
     }
 }
 
+impl SaveState for Mmc2 {
+    fn save_state(&self, w: &mut StateWriter) {
+        w.write_u8(self.prg_bank);
+        w.write_u8(self.chr_fd_0);
+        w.write_u8(self.chr_fe_0);
+        w.write_u8(self.chr_fd_1);
+        w.write_u8(self.chr_fe_1);
+        w.write_bool(self.latch_0_fe);
+        w.write_bool(self.latch_1_fe);
+        w.write_bool(self.horizontal_mirror);
+    }
+
+    fn load_state(&mut self, r: &mut StateReader<'_>) -> Result<(), StateError> {
+        self.prg_bank = r.read_u8()?;
+        self.chr_fd_0 = r.read_u8()?;
+        self.chr_fe_0 = r.read_u8()?;
+        self.chr_fd_1 = r.read_u8()?;
+        self.chr_fe_1 = r.read_u8()?;
+        self.latch_0_fe = r.read_bool()?;
+        self.latch_1_fe = r.read_bool()?;
+        self.horizontal_mirror = r.read_bool()?;
+        Ok(())
+    }
+}
+
 /// Color Dreams (Mapper 11): Simple PRG + CHR bank switching.
 ///
 /// Used by unlicensed Color Dreams games (Crystal Mines, Bible Adventures).
@@ -1042,6 +1202,21 @@ impl Mapper for ColorDreams {
     }
 }
 
+impl SaveState for ColorDreams {
+    fn save_state(&self, w: &mut StateWriter) {
+        self.mirroring.save_state(w);
+        w.write_u8(self.prg_bank);
+        w.write_u8(self.chr_bank);
+    }
+
+    fn load_state(&mut self, r: &mut StateReader<'_>) -> Result<(), StateError> {
+        self.mirroring.load_state(r)?;
+        self.prg_bank = r.read_u8()?;
+        self.chr_bank = r.read_u8()?;
+        Ok(())
+    }
+}
+
 /// `GxROM` (Mapper 66): Simple PRG + CHR bank switching.
 ///
 /// Used by Super Mario Bros / Duck Hunt multicart, Dragon Power.
@@ -1101,6 +1276,21 @@ impl Mapper for GxRom {
     }
 }
 
+impl SaveState for GxRom {
+    fn save_state(&self, w: &mut StateWriter) {
+        self.mirroring.save_state(w);
+        w.write_u8(self.prg_bank);
+        w.write_u8(self.chr_bank);
+    }
+
+    fn load_state(&mut self, r: &mut StateReader<'_>) -> Result<(), StateError> {
+        self.mirroring.load_state(r)?;
+        self.prg_bank = r.read_u8()?;
+        self.chr_bank = r.read_u8()?;
+        Ok(())
+    }
+}
+
 /// MMC4 (Mapper 10, FxROM): CHR latch-based bank switching.
 ///
 /// Used by Fire Emblem and Fire Emblem Gaiden. Nearly identical to MMC2
@@ -1213,6 +1403,31 @@ impl Mapper for Mmc4 {
     }
 }
 
+impl SaveState for Mmc4 {
+    fn save_state(&self, w: &mut StateWriter) {
+        w.write_u8(self.prg_bank);
+        w.write_u8(self.chr_fd_0);
+        w.write_u8(self.chr_fe_0);
+        w.write_u8(self.chr_fd_1);
+        w.write_u8(self.chr_fe_1);
+        w.write_bool(self.latch_0_fe);
+        w.write_bool(self.latch_1_fe);
+        w.write_bool(self.horizontal_mirror);
+    }
+
+    fn load_state(&mut self, r: &mut StateReader<'_>) -> Result<(), StateError> {
+        self.prg_bank = r.read_u8()?;
+        self.chr_fd_0 = r.read_u8()?;
+        self.chr_fe_0 = r.read_u8()?;
+        self.chr_fd_1 = r.read_u8()?;
+        self.chr_fe_1 = r.read_u8()?;
+        self.latch_0_fe = r.read_bool()?;
+        self.latch_1_fe = r.read_bool()?;
+        self.horizontal_mirror = r.read_bool()?;
+        Ok(())
+    }
+}
+
 /// `BxROM` (Mapper 34): Simple 32K PRG bank switching.
 ///
 /// Used by Deadly Towers, Impossible Mission II.
@@ -1269,6 +1484,21 @@ impl Mapper for BxRom {
     }
 }
 
+impl SaveState for BxRom {
+    fn save_state(&self, w: &mut StateWriter) {
+        w.write_bytes(&self.chr_ram);
+        self.mirroring.save_state(w);
+        w.write_u8(self.prg_bank);
+    }
+
+    fn load_state(&mut self, r: &mut StateReader<'_>) -> Result<(), StateError> {
+        r.read_bytes_into(&mut self.chr_ram)?;
+        self.mirroring.load_state(r)?;
+        self.prg_bank = r.read_u8()?;
+        Ok(())
+    }
+}
+
 /// Camerica (Mapper 71, Codemasters): 16K PRG bank switching.
 ///
 /// Used by Micro Machines, Fire Hawk, Bee 52, and other Codemasters games.
@@ -1345,6 +1575,21 @@ impl Mapper for Camerica {
     }
 }
 
+impl SaveState for Camerica {
+    fn save_state(&self, w: &mut StateWriter) {
+        w.write_bytes(&self.chr_ram);
+        self.mirroring.save_state(w);
+        w.write_u8(self.prg_bank);
+    }
+
+    fn load_state(&mut self, r: &mut StateReader<'_>) -> Result<(), StateError> {
+        r.read_bytes_into(&mut self.chr_ram)?;
+        self.mirroring.load_state(r)?;
+        self.prg_bank = r.read_u8()?;
+        Ok(())
+    }
+}
+
 /// Mapper 87: simple CHR bank swap via $6000-$7FFF.
 ///
 /// Used by ~10 games (City Connection, Ninja Jajamaru-kun). Writes to
@@ -1405,6 +1650,19 @@ impl Mapper for Mapper87 {
     }
 }
 
+impl SaveState for Mapper87 {
+    fn save_state(&self, w: &mut StateWriter) {
+        self.mirroring.save_state(w);
+        w.write_u8(self.chr_bank);
+    }
+
+    fn load_state(&mut self, r: &mut StateReader<'_>) -> Result<(), StateError> {
+        self.mirroring.load_state(r)?;
+        self.chr_bank = r.read_u8()?;
+        Ok(())
+    }
+}
+
 /// Mapper 206 (Namco 118 / MIMIC-1): simplified MMC3.
 ///
 /// Used by ~30-50 games (Mappy-Land, Dragon Spirit, Gauntlet).
@@ -1535,6 +1793,26 @@ impl Mapper for Mapper206 {
     }
 }
 
+impl SaveState for Mapper206 {
+    fn save_state(&self, w: &mut StateWriter) {
+        if self.chr_is_ram {
+            w.write_bytes(&self.chr);
+        }
+        self.mirroring.save_state(w);
+        w.write_u8(self.bank_select);
+        w.write_bytes(&self.registers);
+    }
+
+    fn load_state(&mut self, r: &mut StateReader<'_>) -> Result<(), StateError> {
+        if self.chr_is_ram {
+            r.read_bytes_into(&mut self.chr)?;
+        }
+        self.mirroring.load_state(r)?;
+        self.bank_select = r.read_u8()?;
+        r.read_bytes_into(&mut self.registers)
+    }
+}
+
 /// NINA-003/006 (Mapper 79, AVE): PRG and CHR switching via $4100-$5FFF.
 ///
 /// Used by unlicensed AVE games (Krazy Kreatures, Tiles of Fate, Deathbots).
@@ -1595,6 +1873,21 @@ impl Mapper for Nina003 {
     }
 }
 
+impl SaveState for Nina003 {
+    fn save_state(&self, w: &mut StateWriter) {
+        self.mirroring.save_state(w);
+        w.write_u8(self.prg_bank);
+        w.write_u8(self.chr_bank);
+    }
+
+    fn load_state(&mut self, r: &mut StateReader<'_>) -> Result<(), StateError> {
+        self.mirroring.load_state(r)?;
+        self.prg_bank = r.read_u8()?;
+        self.chr_bank = r.read_u8()?;
+        Ok(())
+    }
+}
+
 /// Taito TC0190 (Mapper 33): PRG and CHR bank switching.
 ///
 /// Used by Don Doko Don, Akira, Insector X, Power Blazer.
@@ -1703,6 +1996,22 @@ impl Mapper for TaitoTc0190 {
     }
 }
 
+impl SaveState for TaitoTc0190 {
+    fn save_state(&self, w: &mut StateWriter) {
+        self.mirroring.save_state(w);
+        w.write_u8(self.prg_bank_0);
+        w.write_u8(self.prg_bank_1);
+        w.write_bytes(&self.chr_banks);
+    }
+
+    fn load_state(&mut self, r: &mut StateReader<'_>) -> Result<(), StateError> {
+        self.mirroring.load_state(r)?;
+        self.prg_bank_0 = r.read_u8()?;
+        self.prg_bank_1 = r.read_u8()?;
+        r.read_bytes_into(&mut self.chr_banks)
+    }
+}
+
 /// Irem G-101 (Mapper 32): 8K PRG bank switching with mode select.
 ///
 /// Used by Image Fight, Major League, Kaiketsu Yanchamaru 2, Ai Senshi Nicol.
@@ -1823,6 +2132,30 @@ impl Mapper for IremG101 {
     }
 }
 
+impl SaveState for IremG101 {
+    fn save_state(&self, w: &mut StateWriter) {
+        if self.chr_is_ram {
+            w.write_bytes(&self.chr_rom);
+        }
+        self.mirroring.save_state(w);
+        w.write_u8(self.prg_bank_0);
+        w.write_u8(self.prg_bank_1);
+        w.write_bool(self.prg_mode);
+        w.write_bytes(&self.chr_banks);
+    }
+
+    fn load_state(&mut self, r: &mut StateReader<'_>) -> Result<(), StateError> {
+        if self.chr_is_ram {
+            r.read_bytes_into(&mut self.chr_rom)?;
+        }
+        self.mirroring.load_state(r)?;
+        self.prg_bank_0 = r.read_u8()?;
+        self.prg_bank_1 = r.read_u8()?;
+        self.prg_mode = r.read_bool()?;
+        r.read_bytes_into(&mut self.chr_banks)
+    }
+}
+
 /// Irem H3001 (Mapper 65): 8K PRG switching with IRQ timer.
 ///
 /// Used by Spartan X 2, Daiku no Gen San 2.
@@ -1943,6 +2276,29 @@ impl Mapper for IremH3001 {
     }
 }
 
+impl SaveState for IremH3001 {
+    fn save_state(&self, w: &mut StateWriter) {
+        self.mirroring.save_state(w);
+        w.write_bytes(&self.prg_banks);
+        w.write_bytes(&self.chr_banks);
+        w.write_bool(self.irq_enabled);
+        w.write_u16(self.irq_counter);
+        w.write_u16(self.irq_latch);
+        w.write_bool(self.irq_pending);
+    }
+
+    fn load_state(&mut self, r: &mut StateReader<'_>) -> Result<(), StateError> {
+        self.mirroring.load_state(r)?;
+        r.read_bytes_into(&mut self.prg_banks)?;
+        r.read_bytes_into(&mut self.chr_banks)?;
+        self.irq_enabled = r.read_bool()?;
+        self.irq_counter = r.read_u16()?;
+        self.irq_latch = r.read_u16()?;
+        self.irq_pending = r.read_bool()?;
+        Ok(())
+    }
+}
+
 /// Konami VRC2/VRC4 (Mappers 21, 22, 23, 25): fine-grained PRG/CHR switching.
 ///
 /// Used by Gradius II, Crisis Force, Parodius Da!, Tiny Toon Adventures (JP),
@@ -2158,6 +2514,53 @@ impl Mapper for Vrc2Vrc4 {
     }
 }
 
+impl SaveState for Vrc2Vrc4 {
+    fn save_state(&self, w: &mut StateWriter) {
+        if self.chr_is_ram {
+            w.write_bytes(&self.chr_rom);
+        }
+        self.mirroring.save_state(w);
+        w.write_u8(self.prg_bank_0);
+        w.write_u8(self.prg_bank_1);
+        w.write_bool(self.prg_mode);
+        w.write_bytes(&self.chr_banks_lo);
+        w.write_bytes(&self.chr_banks_hi);
+        w.write_u8(self.addr_shift_lo);
+        w.write_u8(self.addr_shift_hi);
+        w.write_bool(self.vrc2_mode);
+        w.write_u8(self.irq_latch);
+        w.write_u8(self.irq_counter);
+        w.write_i16(self.irq_prescaler);
+        w.write_bool(self.irq_enabled);
+        w.write_bool(self.irq_enabled_after_ack);
+        w.write_bool(self.irq_mode_cycle);
+        w.write_bool(self.irq_pending);
+    }
+
+    fn load_state(&mut self, r: &mut StateReader<'_>) -> Result<(), StateError> {
+        if self.chr_is_ram {
+            r.read_bytes_into(&mut self.chr_rom)?;
+        }
+        self.mirroring.load_state(r)?;
+        self.prg_bank_0 = r.read_u8()?;
+        self.prg_bank_1 = r.read_u8()?;
+        self.prg_mode = r.read_bool()?;
+        r.read_bytes_into(&mut self.chr_banks_lo)?;
+        r.read_bytes_into(&mut self.chr_banks_hi)?;
+        self.addr_shift_lo = r.read_u8()?;
+        self.addr_shift_hi = r.read_u8()?;
+        self.vrc2_mode = r.read_bool()?;
+        self.irq_latch = r.read_u8()?;
+        self.irq_counter = r.read_u8()?;
+        self.irq_prescaler = r.read_i16()?;
+        self.irq_enabled = r.read_bool()?;
+        self.irq_enabled_after_ack = r.read_bool()?;
+        self.irq_mode_cycle = r.read_bool()?;
+        self.irq_pending = r.read_bool()?;
+        Ok(())
+    }
+}
+
 /// Sunsoft 5B expansion audio channel (AY-3-8910 style square wave).
 struct Sunsoft5bChannel {
     period: u16,
@@ -2196,6 +2599,25 @@ impl Sunsoft5bChannel {
     }
 }
 
+impl SaveState for Sunsoft5bChannel {
+    fn save_state(&self, w: &mut StateWriter) {
+        w.write_u16(self.period);
+        w.write_u8(self.volume);
+        w.write_u16(self.counter);
+        w.write_bool(self.output);
+        w.write_bool(self.tone_disable);
+    }
+
+    fn load_state(&mut self, r: &mut StateReader<'_>) -> Result<(), StateError> {
+        self.period = r.read_u16()?;
+        self.volume = r.read_u8()?;
+        self.counter = r.read_u16()?;
+        self.output = r.read_bool()?;
+        self.tone_disable = r.read_bool()?;
+        Ok(())
+    }
+}
+
 /// Sunsoft FME-7 (Mapper 69): versatile bank switching with IRQ timer.
 ///
 /// Used by Gimmick!, Batman: Return of the Joker, Hebereke (Ufouria),
@@ -2459,6 +2881,53 @@ impl Mapper for SunsoftFme7 {
     }
 }
 
+impl SaveState for SunsoftFme7 {
+    fn save_state(&self, w: &mut StateWriter) {
+        if self.chr_is_ram {
+            w.write_bytes(&self.chr_rom);
+        }
+        w.write_bytes(&self.prg_ram);
+        self.mirroring.save_state(w);
+        w.write_u8(self.command);
+        w.write_bytes(&self.chr_banks);
+        w.write_bytes(&self.prg_banks);
+        w.write_bool(self.prg_ram_enabled);
+        w.write_bool(self.prg_ram_selected);
+        w.write_bool(self.irq_enabled);
+        w.write_bool(self.irq_counter_enabled);
+        w.write_u16(self.irq_counter);
+        w.write_bool(self.irq_pending);
+        for v in &self.audio_channels {
+            v.save_state(w);
+        }
+        w.write_u8(self.audio_command);
+        w.write_u8(self.audio_divider);
+    }
+
+    fn load_state(&mut self, r: &mut StateReader<'_>) -> Result<(), StateError> {
+        if self.chr_is_ram {
+            r.read_bytes_into(&mut self.chr_rom)?;
+        }
+        r.read_bytes_into(&mut self.prg_ram)?;
+        self.mirroring.load_state(r)?;
+        self.command = r.read_u8()?;
+        r.read_bytes_into(&mut self.chr_banks)?;
+        r.read_bytes_into(&mut self.prg_banks)?;
+        self.prg_ram_enabled = r.read_bool()?;
+        self.prg_ram_selected = r.read_bool()?;
+        self.irq_enabled = r.read_bool()?;
+        self.irq_counter_enabled = r.read_bool()?;
+        self.irq_counter = r.read_u16()?;
+        self.irq_pending = r.read_bool()?;
+        for v in &mut self.audio_channels {
+            v.load_state(r)?;
+        }
+        self.audio_command = r.read_u8()?;
+        self.audio_divider = r.read_u8()?;
+        Ok(())
+    }
+}
+
 /// Bandai FCG / LZ93D50 (Mapper 16, 159): 16K PRG + 1K CHR + IRQ counter.
 ///
 /// Used by Dragon Ball Z series, SD Gundam Gaiden, Famicom Jump.
@@ -2578,6 +3047,29 @@ impl Mapper for BandaiFcg {
     }
 }
 
+impl SaveState for BandaiFcg {
+    fn save_state(&self, w: &mut StateWriter) {
+        self.mirroring.save_state(w);
+        w.write_u8(self.prg_bank);
+        w.write_bytes(&self.chr_banks);
+        w.write_bool(self.irq_enabled);
+        w.write_u16(self.irq_counter);
+        w.write_u16(self.irq_latch);
+        w.write_bool(self.irq_pending);
+    }
+
+    fn load_state(&mut self, r: &mut StateReader<'_>) -> Result<(), StateError> {
+        self.mirroring.load_state(r)?;
+        self.prg_bank = r.read_u8()?;
+        r.read_bytes_into(&mut self.chr_banks)?;
+        self.irq_enabled = r.read_bool()?;
+        self.irq_counter = r.read_u16()?;
+        self.irq_latch = r.read_u16()?;
+        self.irq_pending = r.read_bool()?;
+        Ok(())
+    }
+}
+
 /// Jaleco SS88006 (Mapper 18): 8K PRG + 1K CHR with configurable IRQ timer.
 ///
 /// Used by Magic John (Totally Rad), Pizza Pop!, Ninja Jajamaru: Ginga
@@ -2771,6 +3263,43 @@ impl Mapper for JalecoSs88006 {
     }
 }
 
+impl SaveState for JalecoSs88006 {
+    fn save_state(&self, w: &mut StateWriter) {
+        if self.chr_is_ram {
+            w.write_bytes(&self.chr_rom);
+        }
+        w.write_bytes(&self.prg_ram);
+        w.write_bool(self.prg_ram_enabled);
+        self.mirroring.save_state(w);
+        w.write_bytes(&self.prg_banks);
+        w.write_bytes(&self.chr_banks_lo);
+        w.write_bytes(&self.chr_banks_hi);
+        w.write_u16(self.irq_counter);
+        w.write_u16(self.irq_latch);
+        w.write_bool(self.irq_enabled);
+        w.write_bool(self.irq_pending);
+        w.write_u16(self.irq_mask);
+    }
+
+    fn load_state(&mut self, r: &mut StateReader<'_>) -> Result<(), StateError> {
+        if self.chr_is_ram {
+            r.read_bytes_into(&mut self.chr_rom)?;
+        }
+        r.read_bytes_into(&mut self.prg_ram)?;
+        self.prg_ram_enabled = r.read_bool()?;
+        self.mirroring.load_state(r)?;
+        r.read_bytes_into(&mut self.prg_banks)?;
+        r.read_bytes_into(&mut self.chr_banks_lo)?;
+        r.read_bytes_into(&mut self.chr_banks_hi)?;
+        self.irq_counter = r.read_u16()?;
+        self.irq_latch = r.read_u16()?;
+        self.irq_enabled = r.read_bool()?;
+        self.irq_pending = r.read_bool()?;
+        self.irq_mask = r.read_u16()?;
+        Ok(())
+    }
+}
+
 /// Namco 163 (Mapper 19): PRG/CHR banking with wavetable expansion audio.
 ///
 /// Used by Megami Tensei II, Rolling Thunder, Star Wars (Namco), Final Lap,
@@ -3087,6 +3616,43 @@ impl Mapper for Namco163 {
     }
 }
 
+impl SaveState for Namco163 {
+    fn save_state(&self, w: &mut StateWriter) {
+        w.write_bytes(&self.prg_ram);
+        self.mirroring.save_state(w);
+        w.write_bytes(&self.prg_banks);
+        w.write_bytes(&self.chr_banks);
+        w.write_bytes(&self.sound_ram);
+        w.write_u8(self.sound_addr);
+        w.write_bool(self.sound_auto_increment);
+        w.write_bool(self.sound_disable);
+        w.write_u16(self.irq_counter);
+        w.write_bool(self.irq_enabled);
+        w.write_bool(self.irq_pending);
+        w.write_u8(self.audio_timer);
+        w.write_u8(self.audio_channel_index);
+        w.write_f32(self.audio_output_level);
+    }
+
+    fn load_state(&mut self, r: &mut StateReader<'_>) -> Result<(), StateError> {
+        r.read_bytes_into(&mut self.prg_ram)?;
+        self.mirroring.load_state(r)?;
+        r.read_bytes_into(&mut self.prg_banks)?;
+        r.read_bytes_into(&mut self.chr_banks)?;
+        r.read_bytes_into(&mut self.sound_ram)?;
+        self.sound_addr = r.read_u8()?;
+        self.sound_auto_increment = r.read_bool()?;
+        self.sound_disable = r.read_bool()?;
+        self.irq_counter = r.read_u16()?;
+        self.irq_enabled = r.read_bool()?;
+        self.irq_pending = r.read_bool()?;
+        self.audio_timer = r.read_u8()?;
+        self.audio_channel_index = r.read_u8()?;
+        self.audio_output_level = r.read_f32()?;
+        Ok(())
+    }
+}
+
 /// Bandai 74*161/02 (Mapper 70): 16K PRG + 8K CHR.
 ///
 /// Used by Kamen Rider Club, Space Shadow. Simple banking with bus conflicts.
@@ -3139,6 +3705,21 @@ impl Mapper for Bandai74161 {
     fn mirroring(&self) -> Mirroring { self.mirroring }
 }
 
+impl SaveState for Bandai74161 {
+    fn save_state(&self, w: &mut StateWriter) {
+        self.mirroring.save_state(w);
+        w.write_u8(self.prg_bank);
+        w.write_u8(self.chr_bank);
+    }
+
+    fn load_state(&mut self, r: &mut StateReader<'_>) -> Result<(), StateError> {
+        self.mirroring.load_state(r)?;
+        self.prg_bank = r.read_u8()?;
+        self.chr_bank = r.read_u8()?;
+        Ok(())
+    }
+}
+
 /// Jaleco JF-17 (Mapper 72): acknowledge-then-write PRG/CHR switching.
 ///
 /// Used by Pinball Quest, Moero!! Pro Tennis.
@@ -3201,6 +3782,25 @@ impl Mapper for JalecoJf17 {
     fn mirroring(&self) -> Mirroring { self.mirroring }
 }
 
+impl SaveState for JalecoJf17 {
+    fn save_state(&self, w: &mut StateWriter) {
+        self.mirroring.save_state(w);
+        w.write_u8(self.prg_bank);
+        w.write_u8(self.chr_bank);
+        w.write_u8(self.prg_latch);
+        w.write_u8(self.chr_latch);
+    }
+
+    fn load_state(&mut self, r: &mut StateReader<'_>) -> Result<(), StateError> {
+        self.mirroring.load_state(r)?;
+        self.prg_bank = r.read_u8()?;
+        self.chr_bank = r.read_u8()?;
+        self.prg_latch = r.read_u8()?;
+        self.chr_latch = r.read_u8()?;
+        Ok(())
+    }
+}
+
 /// Irem 74161 (Mapper 78): 16K PRG + 8K CHR + mirroring control.
 ///
 /// Used by Holy Diver, Cosmo Carrier.
@@ -3266,6 +3866,23 @@ impl Mapper for Irem74161 {
     fn mirroring(&self) -> Mirroring { self.mirroring }
 }
 
+impl SaveState for Irem74161 {
+    fn save_state(&self, w: &mut StateWriter) {
+        self.mirroring.save_state(w);
+        w.write_u8(self.prg_bank);
+        w.write_u8(self.chr_bank);
+        w.write_bool(self.single_screen_mode);
+    }
+
+    fn load_state(&mut self, r: &mut StateReader<'_>) -> Result<(), StateError> {
+        self.mirroring.load_state(r)?;
+        self.prg_bank = r.read_u8()?;
+        self.chr_bank = r.read_u8()?;
+        self.single_screen_mode = r.read_bool()?;
+        Ok(())
+    }
+}
+
 /// Sunsoft-2 (Mapper 93): PRG-only switching.
 ///
 /// Used by Fantasy Zone, Shanghai.
@@ -3307,6 +3924,21 @@ impl Mapper for Sunsoft2 {
     fn mirroring(&self) -> Mirroring { self.mirroring }
 }
 
+impl SaveState for Sunsoft2 {
+    fn save_state(&self, w: &mut StateWriter) {
+        w.write_bytes(&self.chr_ram);
+        self.mirroring.save_state(w);
+        w.write_u8(self.prg_bank);
+    }
+
+    fn load_state(&mut self, r: &mut StateReader<'_>) -> Result<(), StateError> {
+        r.read_bytes_into(&mut self.chr_ram)?;
+        self.mirroring.load_state(r)?;
+        self.prg_bank = r.read_u8()?;
+        Ok(())
+    }
+}
+
 /// Jaleco JF-11/14 (Mapper 140): 32K PRG + 8K CHR via $6000-$7FFF.
 ///
 /// Used by Bio Senshi Dan, Murder on Mississippi.
@@ -3350,6 +3982,21 @@ impl Mapper for JalecoJf11 {
     fn mirroring(&self) -> Mirroring { self.mirroring }
 }
 
+impl SaveState for JalecoJf11 {
+    fn save_state(&self, w: &mut StateWriter) {
+        self.mirroring.save_state(w);
+        w.write_u8(self.prg_bank);
+        w.write_u8(self.chr_bank);
+    }
+
+    fn load_state(&mut self, r: &mut StateReader<'_>) -> Result<(), StateError> {
+        self.mirroring.load_state(r)?;
+        self.prg_bank = r.read_u8()?;
+        self.chr_bank = r.read_u8()?;
+        Ok(())
+    }
+}
+
 /// Bandai 74*161/02 with single-screen mirroring (Mapper 152).
 ///
 /// Used by Arkanoid II, Saint Seiya.
@@ -3406,6 +4053,21 @@ impl Mapper for Bandai74161Ss {
     fn mirroring(&self) -> Mirroring { self.mirroring }
 }
 
+impl SaveState for Bandai74161Ss {
+    fn save_state(&self, w: &mut StateWriter) {
+        self.mirroring.save_state(w);
+        w.write_u8(self.prg_bank);
+        w.write_u8(self.chr_bank);
+    }
+
+    fn load_state(&mut self, r: &mut StateReader<'_>) -> Result<(), StateError> {
+        self.mirroring.load_state(r)?;
+        self.prg_bank = r.read_u8()?;
+        self.chr_bank = r.read_u8()?;
+        Ok(())
+    }
+}
+
 /// Sunsoft-1 (Mapper 184): CHR-only switching via $6000-$7FFF.
 ///
 /// Used by Atlantis no Nazo, Wing of Madoola.
@@ -3455,6 +4117,21 @@ impl Mapper for Sunsoft1 {
     fn mirroring(&self) -> Mirroring { self.mirroring }
 }
 
+impl SaveState for Sunsoft1 {
+    fn save_state(&self, w: &mut StateWriter) {
+        self.mirroring.save_state(w);
+        w.write_u8(self.chr_bank_lo);
+        w.write_u8(self.chr_bank_hi);
+    }
+
+    fn load_state(&mut self, r: &mut StateReader<'_>) -> Result<(), StateError> {
+        self.mirroring.load_state(r)?;
+        self.chr_bank_lo = r.read_u8()?;
+        self.chr_bank_hi = r.read_u8()?;
+        Ok(())
+    }
+}
+
 /// CNROM with copy protection (Mapper 185).
 ///
 /// Used by Spy vs. Spy, Mighty Bomb Jack, B-Wings.
@@ -3495,6 +4172,19 @@ impl Mapper for CnromProtected {
     fn mirroring(&self) -> Mirroring { self.mirroring }
 }
 
+impl SaveState for CnromProtected {
+    fn save_state(&self, w: &mut StateWriter) {
+        self.mirroring.save_state(w);
+        w.write_bool(self.chr_enabled);
+    }
+
+    fn load_state(&mut self, r: &mut StateReader<'_>) -> Result<(), StateError> {
+        self.mirroring.load_state(r)?;
+        self.chr_enabled = r.read_bool()?;
+        Ok(())
+    }
+}
+
 /// Taito TC0690 (Mapper 48): like mapper 33 but with scanline IRQ.
 ///
 /// Used by Don Doko Don 2, Flintstones: Rescue of Dino & Hoppy.
@@ -3597,6 +4287,35 @@ impl Mapper for TaitoTc0690 {
     fn irq_pending(&self) -> bool { self.irq_pending }
 }
 
+impl SaveState for TaitoTc0690 {
+    fn save_state(&self, w: &mut StateWriter) {
+        self.mirroring.save_state(w);
+        w.write_u8(self.prg_bank_0);
+        w.write_u8(self.prg_bank_1);
+        w.write_bytes(&self.chr_banks);
+        w.write_u8(self.irq_latch);
+        w.write_u8(self.irq_counter);
+        w.write_bool(self.irq_reload_flag);
+        w.write_bool(self.irq_enabled);
+        w.write_bool(self.irq_pending);
+        w.write_bool(self.last_a12);
+    }
+
+    fn load_state(&mut self, r: &mut StateReader<'_>) -> Result<(), StateError> {
+        self.mirroring.load_state(r)?;
+        self.prg_bank_0 = r.read_u8()?;
+        self.prg_bank_1 = r.read_u8()?;
+        r.read_bytes_into(&mut self.chr_banks)?;
+        self.irq_latch = r.read_u8()?;
+        self.irq_counter = r.read_u8()?;
+        self.irq_reload_flag = r.read_bool()?;
+        self.irq_enabled = r.read_bool()?;
+        self.irq_pending = r.read_bool()?;
+        self.last_a12 = r.read_bool()?;
+        Ok(())
+    }
+}
+
 /// Sunsoft-3 (Mapper 67): 2K CHR banking with IRQ timer.
 ///
 /// Used by Fantasy Zone II.
@@ -3681,6 +4400,29 @@ impl Mapper for Sunsoft3 {
     fn irq_pending(&self) -> bool { self.irq_pending }
 }
 
+impl SaveState for Sunsoft3 {
+    fn save_state(&self, w: &mut StateWriter) {
+        self.mirroring.save_state(w);
+        w.write_u8(self.prg_bank);
+        w.write_bytes(&self.chr_banks);
+        w.write_u16(self.irq_counter);
+        w.write_bool(self.irq_enabled);
+        w.write_bool(self.irq_pending);
+        w.write_bool(self.irq_toggle);
+    }
+
+    fn load_state(&mut self, r: &mut StateReader<'_>) -> Result<(), StateError> {
+        self.mirroring.load_state(r)?;
+        self.prg_bank = r.read_u8()?;
+        r.read_bytes_into(&mut self.chr_banks)?;
+        self.irq_counter = r.read_u16()?;
+        self.irq_enabled = r.read_bool()?;
+        self.irq_pending = r.read_bool()?;
+        self.irq_toggle = r.read_bool()?;
+        Ok(())
+    }
+}
+
 /// Sunsoft-4 (Mapper 68): 2K CHR + nametable ROM banking.
 ///
 /// Used by After Burner (JP+US), Nantettatte!! Baseball.
@@ -3767,6 +4509,29 @@ impl Mapper for Sunsoft4 {
     }
 }
 
+impl SaveState for Sunsoft4 {
+    fn save_state(&self, w: &mut StateWriter) {
+        w.write_bytes(&self.prg_ram);
+        self.mirroring.save_state(w);
+        w.write_u8(self.prg_bank);
+        w.write_bytes(&self.chr_banks);
+        w.write_bytes(&self.nt_banks);
+        w.write_bool(self.nt_rom_mode);
+        w.write_bool(self.prg_ram_enabled);
+    }
+
+    fn load_state(&mut self, r: &mut StateReader<'_>) -> Result<(), StateError> {
+        r.read_bytes_into(&mut self.prg_ram)?;
+        self.mirroring.load_state(r)?;
+        self.prg_bank = r.read_u8()?;
+        r.read_bytes_into(&mut self.chr_banks)?;
+        r.read_bytes_into(&mut self.nt_banks)?;
+        self.nt_rom_mode = r.read_bool()?;
+        self.prg_ram_enabled = r.read_bool()?;
+        Ok(())
+    }
+}
+
 /// VRC1 (Mapper 75): Konami 8K PRG + 4K CHR banking.
 ///
 /// Used by Ganbare Goemon, Tetsuwan Atom.
@@ -3835,6 +4600,24 @@ impl Mapper for Vrc1 {
     fn mirroring(&self) -> Mirroring { self.mirroring }
 }
 
+impl SaveState for Vrc1 {
+    fn save_state(&self, w: &mut StateWriter) {
+        self.mirroring.save_state(w);
+        w.write_bytes(&self.prg_banks);
+        w.write_u8(self.chr_bank_lo);
+        w.write_u8(self.chr_bank_hi);
+        w.write_bytes(&self.chr_hi_bits);
+    }
+
+    fn load_state(&mut self, r: &mut StateReader<'_>) -> Result<(), StateError> {
+        self.mirroring.load_state(r)?;
+        r.read_bytes_into(&mut self.prg_banks)?;
+        self.chr_bank_lo = r.read_u8()?;
+        self.chr_bank_hi = r.read_u8()?;
+        r.read_bytes_into(&mut self.chr_hi_bits)
+    }
+}
+
 /// Taito X1-005 (Mapper 80): 8K PRG + mixed CHR banking via $7EF0-$7EFF.
 ///
 /// Used by various Taito JP titles.
@@ -3918,6 +4701,25 @@ impl Mapper for TaitoX1005 {
     fn mirroring(&self) -> Mirroring { self.mirroring }
 }
 
+impl SaveState for TaitoX1005 {
+    fn save_state(&self, w: &mut StateWriter) {
+        self.mirroring.save_state(w);
+        w.write_bytes(&self.prg_banks);
+        w.write_bytes(&self.chr_banks);
+        w.write_bytes(&self.ram);
+        w.write_bool(self.ram_enabled);
+    }
+
+    fn load_state(&mut self, r: &mut StateReader<'_>) -> Result<(), StateError> {
+        self.mirroring.load_state(r)?;
+        r.read_bytes_into(&mut self.prg_banks)?;
+        r.read_bytes_into(&mut self.chr_banks)?;
+        r.read_bytes_into(&mut self.ram)?;
+        self.ram_enabled = r.read_bool()?;
+        Ok(())
+    }
+}
+
 /// Taito X1-017 (Mapper 82): variant of X1-005 with CHR A12 inversion.
 ///
 /// Used by Kyuukyoku Harikiri Stadium series.
@@ -3998,6 +4800,27 @@ impl Mapper for TaitoX1017 {
     fn mirroring(&self) -> Mirroring { self.mirroring }
 }
 
+impl SaveState for TaitoX1017 {
+    fn save_state(&self, w: &mut StateWriter) {
+        self.mirroring.save_state(w);
+        w.write_bytes(&self.prg_banks);
+        w.write_bytes(&self.chr_banks);
+        w.write_bool(self.chr_invert);
+        w.write_bytes(&self.ram);
+        w.write_bool(self.ram_enabled);
+    }
+
+    fn load_state(&mut self, r: &mut StateReader<'_>) -> Result<(), StateError> {
+        self.mirroring.load_state(r)?;
+        r.read_bytes_into(&mut self.prg_banks)?;
+        r.read_bytes_into(&mut self.chr_banks)?;
+        self.chr_invert = r.read_bool()?;
+        r.read_bytes_into(&mut self.ram)?;
+        self.ram_enabled = r.read_bool()?;
+        Ok(())
+    }
+}
+
 /// Namco 3446 (Mapper 88): like mapper 206 but with CHR A16 split.
 ///
 /// Used by Dragon Spirit, Quinty.
@@ -4066,6 +4889,21 @@ impl Mapper for Namco3446 {
     fn mirroring(&self) -> Mirroring { self.mirroring }
 }
 
+impl SaveState for Namco3446 {
+    fn save_state(&self, w: &mut StateWriter) {
+        self.mirroring.save_state(w);
+        w.write_bytes(&self.registers);
+        w.write_u8(self.bank_select);
+    }
+
+    fn load_state(&mut self, r: &mut StateReader<'_>) -> Result<(), StateError> {
+        self.mirroring.load_state(r)?;
+        r.read_bytes_into(&mut self.registers)?;
+        self.bank_select = r.read_u8()?;
+        Ok(())
+    }
+}
+
 /// Namco 175/340 (Mapper 210): simpler Namco banking without audio/IRQ.
 ///
 /// Used by Splatterhouse, Wagyan Land 2/3, Famista series.
@@ -4136,6 +4974,20 @@ impl Mapper for Namco175 {
     fn mirroring(&self) -> Mirroring { self.mirroring }
 }
 
+impl SaveState for Namco175 {
+    fn save_state(&self, w: &mut StateWriter) {
+        self.mirroring.save_state(w);
+        w.write_bytes(&self.prg_banks);
+        w.write_bytes(&self.chr_banks);
+    }
+
+    fn load_state(&mut self, r: &mut StateReader<'_>) -> Result<(), StateError> {
+        self.mirroring.load_state(r)?;
+        r.read_bytes_into(&mut self.prg_banks)?;
+        r.read_bytes_into(&mut self.chr_banks)
+    }
+}
+
 /// VRC6 expansion audio pulse channel.
 struct Vrc6Pulse {
     duty: u8,
@@ -4166,6 +5018,29 @@ impl Vrc6Pulse {
     }
 }
 
+impl SaveState for Vrc6Pulse {
+    fn save_state(&self, w: &mut StateWriter) {
+        w.write_u8(self.duty);
+        w.write_u8(self.volume);
+        w.write_bool(self.mode);
+        w.write_bool(self.enabled);
+        w.write_u16(self.period);
+        w.write_u16(self.counter);
+        w.write_u8(self.step);
+    }
+
+    fn load_state(&mut self, r: &mut StateReader<'_>) -> Result<(), StateError> {
+        self.duty = r.read_u8()?;
+        self.volume = r.read_u8()?;
+        self.mode = r.read_bool()?;
+        self.enabled = r.read_bool()?;
+        self.period = r.read_u16()?;
+        self.counter = r.read_u16()?;
+        self.step = r.read_u8()?;
+        Ok(())
+    }
+}
+
 /// VRC6 expansion audio sawtooth channel.
 struct Vrc6Sawtooth {
     rate: u8,
@@ -4201,6 +5076,27 @@ impl Vrc6Sawtooth {
     }
 }
 
+impl SaveState for Vrc6Sawtooth {
+    fn save_state(&self, w: &mut StateWriter) {
+        w.write_u8(self.rate);
+        w.write_bool(self.enabled);
+        w.write_u16(self.period);
+        w.write_u16(self.counter);
+        w.write_u8(self.step);
+        w.write_u8(self.accumulator);
+    }
+
+    fn load_state(&mut self, r: &mut StateReader<'_>) -> Result<(), StateError> {
+        self.rate = r.read_u8()?;
+        self.enabled = r.read_bool()?;
+        self.period = r.read_u16()?;
+        self.counter = r.read_u16()?;
+        self.step = r.read_u8()?;
+        self.accumulator = r.read_u8()?;
+        Ok(())
+    }
+}
+
 /// Konami VRC6 (Mapper 24/26): 8K/16K PRG + 1K CHR + IRQ + expansion audio.
 ///
 /// Used by Castlevania III (JP: Akumajou Densetsu), Esper Dream 2, Madara.
@@ -4383,6 +5279,57 @@ impl Mapper for Vrc6 {
     }
 }
 
+impl SaveState for Vrc6 {
+    fn save_state(&self, w: &mut StateWriter) {
+        if self.chr_is_ram {
+            w.write_bytes(&self.chr_rom);
+        }
+        w.write_bytes(&self.prg_ram);
+        self.mirroring.save_state(w);
+        w.write_u8(self.prg_16k);
+        w.write_u8(self.prg_8k);
+        w.write_bytes(&self.chr_banks);
+        w.write_bool(self.prg_ram_enabled);
+        w.write_bool(self.addr_swap);
+        w.write_u8(self.irq_latch);
+        w.write_u8(self.irq_counter);
+        w.write_i16(self.irq_prescaler);
+        w.write_bool(self.irq_enabled);
+        w.write_bool(self.irq_enabled_after_ack);
+        w.write_bool(self.irq_mode_cycle);
+        w.write_bool(self.irq_pending);
+        self.pulse1.save_state(w);
+        self.pulse2.save_state(w);
+        self.sawtooth.save_state(w);
+        w.write_bool(self.audio_halt);
+    }
+
+    fn load_state(&mut self, r: &mut StateReader<'_>) -> Result<(), StateError> {
+        if self.chr_is_ram {
+            r.read_bytes_into(&mut self.chr_rom)?;
+        }
+        r.read_bytes_into(&mut self.prg_ram)?;
+        self.mirroring.load_state(r)?;
+        self.prg_16k = r.read_u8()?;
+        self.prg_8k = r.read_u8()?;
+        r.read_bytes_into(&mut self.chr_banks)?;
+        self.prg_ram_enabled = r.read_bool()?;
+        self.addr_swap = r.read_bool()?;
+        self.irq_latch = r.read_u8()?;
+        self.irq_counter = r.read_u8()?;
+        self.irq_prescaler = r.read_i16()?;
+        self.irq_enabled = r.read_bool()?;
+        self.irq_enabled_after_ack = r.read_bool()?;
+        self.irq_mode_cycle = r.read_bool()?;
+        self.irq_pending = r.read_bool()?;
+        self.pulse1.load_state(r)?;
+        self.pulse2.load_state(r)?;
+        self.sawtooth.load_state(r)?;
+        self.audio_halt = r.read_bool()?;
+        Ok(())
+    }
+}
+
 /// TxSROM (Mapper 118): MMC3 variant where CHR bank bit 7 controls nametable mirroring.
 ///
 /// Used by Ys III, NES Play Action Football, Goal! Two, Armadillo.
@@ -4518,6 +5465,39 @@ impl Mapper for TxSrom {
     }
 }
 
+impl SaveState for TxSrom {
+    fn save_state(&self, w: &mut StateWriter) {
+        if self.chr_is_ram {
+            w.write_bytes(&self.chr);
+        }
+        w.write_bytes(&self.prg_ram);
+        w.write_u8(self.bank_select);
+        w.write_bytes(&self.registers);
+        w.write_u8(self.irq_latch);
+        w.write_u8(self.irq_counter);
+        w.write_bool(self.irq_reload_flag);
+        w.write_bool(self.irq_enabled);
+        w.write_bool(self.irq_pending);
+        w.write_bool(self.last_a12);
+    }
+
+    fn load_state(&mut self, r: &mut StateReader<'_>) -> Result<(), StateError> {
+        if self.chr_is_ram {
+            r.read_bytes_into(&mut self.chr)?;
+        }
+        r.read_bytes_into(&mut self.prg_ram)?;
+        self.bank_select = r.read_u8()?;
+        r.read_bytes_into(&mut self.registers)?;
+        self.irq_latch = r.read_u8()?;
+        self.irq_counter = r.read_u8()?;
+        self.irq_reload_flag = r.read_bool()?;
+        self.irq_enabled = r.read_bool()?;
+        self.irq_pending = r.read_bool()?;
+        self.last_a12 = r.read_bool()?;
+        Ok(())
+    }
+}
+
 /// TQROM (Mapper 119): MMC3 with both CHR ROM and CHR RAM, selected by bit 6.
 ///
 /// Used by High Speed, Pin-Bot.
@@ -4649,6 +5629,37 @@ impl Mapper for Tqrom {
     fn irq_pending(&self) -> bool { self.irq_pending }
 }
 
+impl SaveState for Tqrom {
+    fn save_state(&self, w: &mut StateWriter) {
+        w.write_bytes(&self.chr_ram);
+        w.write_bytes(&self.prg_ram);
+        w.write_u8(self.bank_select);
+        w.write_bytes(&self.registers);
+        self.mirroring.save_state(w);
+        w.write_u8(self.irq_latch);
+        w.write_u8(self.irq_counter);
+        w.write_bool(self.irq_reload_flag);
+        w.write_bool(self.irq_enabled);
+        w.write_bool(self.irq_pending);
+        w.write_bool(self.last_a12);
+    }
+
+    fn load_state(&mut self, r: &mut StateReader<'_>) -> Result<(), StateError> {
+        r.read_bytes_into(&mut self.chr_ram)?;
+        r.read_bytes_into(&mut self.prg_ram)?;
+        self.bank_select = r.read_u8()?;
+        r.read_bytes_into(&mut self.registers)?;
+        self.mirroring.load_state(r)?;
+        self.irq_latch = r.read_u8()?;
+        self.irq_counter = r.read_u8()?;
+        self.irq_reload_flag = r.read_bool()?;
+        self.irq_enabled = r.read_bool()?;
+        self.irq_pending = r.read_bool()?;
+        self.last_a12 = r.read_bool()?;
+        Ok(())
+    }
+}
+
 /// Konami VRC7 (Mapper 85): 8K PRG + 1K CHR + IRQ + OPLL FM audio.
 ///
 /// Used by Lagrange Point, Tiny Toon Adventures 2 (JP).
@@ -4789,6 +5800,51 @@ impl Mapper for Vrc7 {
     }
 }
 
+impl SaveState for Vrc7 {
+    fn save_state(&self, w: &mut StateWriter) {
+        if self.chr_is_ram {
+            w.write_bytes(&self.chr_rom);
+        }
+        w.write_bytes(&self.prg_ram);
+        self.mirroring.save_state(w);
+        w.write_bytes(&self.prg_banks);
+        w.write_bytes(&self.chr_banks);
+        w.write_bool(self.prg_ram_enabled);
+        w.write_bool(self.audio_silenced);
+        w.write_u8(self.opll_addr);
+        w.write_bytes(&self.opll_regs);
+        w.write_u8(self.irq_latch);
+        w.write_u8(self.irq_counter);
+        w.write_i16(self.irq_prescaler);
+        w.write_bool(self.irq_enabled);
+        w.write_bool(self.irq_enabled_after_ack);
+        w.write_bool(self.irq_mode_cycle);
+        w.write_bool(self.irq_pending);
+    }
+
+    fn load_state(&mut self, r: &mut StateReader<'_>) -> Result<(), StateError> {
+        if self.chr_is_ram {
+            r.read_bytes_into(&mut self.chr_rom)?;
+        }
+        r.read_bytes_into(&mut self.prg_ram)?;
+        self.mirroring.load_state(r)?;
+        r.read_bytes_into(&mut self.prg_banks)?;
+        r.read_bytes_into(&mut self.chr_banks)?;
+        self.prg_ram_enabled = r.read_bool()?;
+        self.audio_silenced = r.read_bool()?;
+        self.opll_addr = r.read_u8()?;
+        r.read_bytes_into(&mut self.opll_regs)?;
+        self.irq_latch = r.read_u8()?;
+        self.irq_counter = r.read_u8()?;
+        self.irq_prescaler = r.read_i16()?;
+        self.irq_enabled = r.read_bool()?;
+        self.irq_enabled_after_ack = r.read_bool()?;
+        self.irq_mode_cycle = r.read_bool()?;
+        self.irq_pending = r.read_bool()?;
+        Ok(())
+    }
+}
+
 /// MMC5 (Mapper 5, ExROM): the most complex NES mapper.
 ///
 /// Used by Castlevania III, Just Breed, Metal Slader Glory, L'Empereur,
@@ -5076,6 +6132,58 @@ impl Mapper for Mmc5 {
     }
 }
 
+impl SaveState for Mmc5 {
+    fn save_state(&self, w: &mut StateWriter) {
+        w.write_bytes(&self.prg_ram);
+        w.write_u8(self.prg_mode);
+        w.write_bytes(&self.prg_banks);
+        w.write_u8(self.prg_ram_protect_1);
+        w.write_u8(self.prg_ram_protect_2);
+        w.write_u8(self.chr_mode);
+        w.write_u16_slice(&self.chr_banks);
+        w.write_u8(self.chr_upper);
+        w.write_bool(self.last_chr_write_sprite);
+        w.write_u8(self.nametable_mapping);
+        w.write_u8(self.fill_tile);
+        w.write_u8(self.fill_attr);
+        w.write_u8(self.exram_mode);
+        w.write_bytes(&self.exram);
+        w.write_u8(self.multiplicand);
+        w.write_u8(self.multiplier);
+        w.write_u8(self.irq_scanline);
+        w.write_bool(self.irq_enabled);
+        w.write_bool(self.irq_pending);
+        w.write_bool(self.in_frame);
+        w.write_u8(self.scanline_counter);
+        self.mirroring.save_state(w);
+    }
+
+    fn load_state(&mut self, r: &mut StateReader<'_>) -> Result<(), StateError> {
+        r.read_bytes_into(&mut self.prg_ram)?;
+        self.prg_mode = r.read_u8()?;
+        r.read_bytes_into(&mut self.prg_banks)?;
+        self.prg_ram_protect_1 = r.read_u8()?;
+        self.prg_ram_protect_2 = r.read_u8()?;
+        self.chr_mode = r.read_u8()?;
+        r.read_u16_into(&mut self.chr_banks)?;
+        self.chr_upper = r.read_u8()?;
+        self.last_chr_write_sprite = r.read_bool()?;
+        self.nametable_mapping = r.read_u8()?;
+        self.fill_tile = r.read_u8()?;
+        self.fill_attr = r.read_u8()?;
+        self.exram_mode = r.read_u8()?;
+        r.read_bytes_into(&mut self.exram)?;
+        self.multiplicand = r.read_u8()?;
+        self.multiplier = r.read_u8()?;
+        self.irq_scanline = r.read_u8()?;
+        self.irq_enabled = r.read_bool()?;
+        self.irq_pending = r.read_bool()?;
+        self.in_frame = r.read_bool()?;
+        self.scanline_counter = r.read_u8()?;
+        self.mirroring.load_state(r)
+    }
+}
+
 // ===== Remaining commercial mappers (trivial through medium) =====
 
 /// CPROM (Mapper 13): 32K PRG + 4K CHR-RAM banking. Used by Videomation.
@@ -5103,6 +6211,20 @@ impl Mapper for Cprom {
     fn mirroring(&self) -> Mirroring { self.mirroring }
 }
 
+impl SaveState for Cprom {
+    fn save_state(&self, w: &mut StateWriter) {
+        w.write_bytes(&self.chr_ram);
+        w.write_u8(self.chr_bank);
+        self.mirroring.save_state(w);
+    }
+
+    fn load_state(&mut self, r: &mut StateReader<'_>) -> Result<(), StateError> {
+        r.read_bytes_into(&mut self.chr_ram)?;
+        self.chr_bank = r.read_u8()?;
+        self.mirroring.load_state(r)
+    }
+}
+
 /// Bit Corp (Mapper 38): 32K PRG + 8K CHR via $7000-$7FFF.
 struct BitCorp { prg_rom: Vec<u8>, chr_rom: Vec<u8>, mirroring: Mirroring, prg_bank: u8, chr_bank: u8 }
 impl BitCorp {
@@ -5126,6 +6248,21 @@ impl Mapper for BitCorp {
     fn mirroring(&self) -> Mirroring { self.mirroring }
 }
 
+impl SaveState for BitCorp {
+    fn save_state(&self, w: &mut StateWriter) {
+        self.mirroring.save_state(w);
+        w.write_u8(self.prg_bank);
+        w.write_u8(self.chr_bank);
+    }
+
+    fn load_state(&mut self, r: &mut StateReader<'_>) -> Result<(), StateError> {
+        self.mirroring.load_state(r)?;
+        self.prg_bank = r.read_u8()?;
+        self.chr_bank = r.read_u8()?;
+        Ok(())
+    }
+}
+
 /// MMC3 multicart wrapper: outer bank register selects game, inner uses MMC3.
 /// Mapper 37 (PAL-ZZ: 3-in-1) and Mapper 47 (NES-QJ: 2-in-1).
 #[allow(dead_code)]
@@ -5169,6 +6306,25 @@ impl Mapper for Mmc3Multicart {
     fn set_prg_ram(&mut self, data: &[u8]) { self.inner.set_prg_ram(data) }
 }
 
+impl SaveState for Mmc3Multicart {
+    fn save_state(&self, w: &mut StateWriter) {
+        self.inner.save_state(w);
+        w.write_u8(self.outer_bank);
+        w.write_u8(self.outer_mask);
+        w.write_u8(self.prg_outer_shift);
+        w.write_u8(self.chr_outer_shift);
+    }
+
+    fn load_state(&mut self, r: &mut StateReader<'_>) -> Result<(), StateError> {
+        self.inner.load_state(r)?;
+        self.outer_bank = r.read_u8()?;
+        self.outer_mask = r.read_u8()?;
+        self.prg_outer_shift = r.read_u8()?;
+        self.chr_outer_shift = r.read_u8()?;
+        Ok(())
+    }
+}
+
 /// Caltron 6-in-1 (Mapper 41): outer bank at $6000, inner at $8000.
 struct Caltron {
     prg_rom: Vec<u8>, chr_rom: Vec<u8>, mirroring: Mirroring,
@@ -5206,6 +6362,21 @@ impl Mapper for Caltron {
     fn mirroring(&self) -> Mirroring { self.mirroring }
 }
 
+impl SaveState for Caltron {
+    fn save_state(&self, w: &mut StateWriter) {
+        self.mirroring.save_state(w);
+        w.write_u8(self.prg_bank);
+        w.write_u8(self.chr_bank);
+    }
+
+    fn load_state(&mut self, r: &mut StateReader<'_>) -> Result<(), StateError> {
+        self.mirroring.load_state(r)?;
+        self.prg_bank = r.read_u8()?;
+        self.chr_bank = r.read_u8()?;
+        Ok(())
+    }
+}
+
 /// RAMBO-1 / Tengen (Mapper 64): MMC3 variant with 3 PRG banks and 1K CHR mode.
 struct Rambo1 {
     prg_rom: Vec<u8>, chr: Vec<u8>, chr_is_ram: bool, prg_ram: [u8; 8192],
@@ -5315,6 +6486,41 @@ impl Mapper for Rambo1 {
     fn set_prg_ram(&mut self, data: &[u8]) { let l = data.len().min(8192); self.prg_ram[..l].copy_from_slice(&data[..l]); }
 }
 
+impl SaveState for Rambo1 {
+    fn save_state(&self, w: &mut StateWriter) {
+        if self.chr_is_ram {
+            w.write_bytes(&self.chr);
+        }
+        w.write_bytes(&self.prg_ram);
+        w.write_u8(self.bank_select);
+        w.write_bytes(&self.registers);
+        self.mirroring.save_state(w);
+        w.write_u8(self.irq_latch);
+        w.write_u8(self.irq_counter);
+        w.write_bool(self.irq_reload_flag);
+        w.write_bool(self.irq_enabled);
+        w.write_bool(self.irq_pending);
+        w.write_bool(self.last_a12);
+    }
+
+    fn load_state(&mut self, r: &mut StateReader<'_>) -> Result<(), StateError> {
+        if self.chr_is_ram {
+            r.read_bytes_into(&mut self.chr)?;
+        }
+        r.read_bytes_into(&mut self.prg_ram)?;
+        self.bank_select = r.read_u8()?;
+        r.read_bytes_into(&mut self.registers)?;
+        self.mirroring.load_state(r)?;
+        self.irq_latch = r.read_u8()?;
+        self.irq_counter = r.read_u8()?;
+        self.irq_reload_flag = r.read_bool()?;
+        self.irq_enabled = r.read_bool()?;
+        self.irq_pending = r.read_bool()?;
+        self.last_a12 = r.read_bool()?;
+        Ok(())
+    }
+}
+
 /// VRC3 (Mapper 73): PRG banking + 16-bit IRQ. Used by Salamander.
 struct Vrc3 {
     prg_rom: Vec<u8>, chr_ram: [u8; 8192], mirroring: Mirroring,
@@ -5359,6 +6565,31 @@ impl Mapper for Vrc3 {
     fn irq_pending(&self) -> bool { self.irq_pending }
 }
 
+impl SaveState for Vrc3 {
+    fn save_state(&self, w: &mut StateWriter) {
+        w.write_bytes(&self.chr_ram);
+        self.mirroring.save_state(w);
+        w.write_u8(self.prg_bank);
+        w.write_u16(self.irq_latch);
+        w.write_u16(self.irq_counter);
+        w.write_bool(self.irq_enabled);
+        w.write_bool(self.irq_pending);
+        w.write_bool(self.irq_mode_8bit);
+    }
+
+    fn load_state(&mut self, r: &mut StateReader<'_>) -> Result<(), StateError> {
+        r.read_bytes_into(&mut self.chr_ram)?;
+        self.mirroring.load_state(r)?;
+        self.prg_bank = r.read_u8()?;
+        self.irq_latch = r.read_u16()?;
+        self.irq_counter = r.read_u16()?;
+        self.irq_enabled = r.read_bool()?;
+        self.irq_pending = r.read_bool()?;
+        self.irq_mode_8bit = r.read_bool()?;
+        Ok(())
+    }
+}
+
 /// Namcot-3446/76 (Mapper 76): 8K PRG + 2K CHR. Used by Megami Tensei.
 struct Namcot3446v2 {
     prg_rom: Vec<u8>, chr_rom: Vec<u8>, mirroring: Mirroring,
@@ -5407,6 +6638,22 @@ impl Mapper for Namcot3446v2 {
     fn mirroring(&self) -> Mirroring { self.mirroring }
 }
 
+impl SaveState for Namcot3446v2 {
+    fn save_state(&self, w: &mut StateWriter) {
+        self.mirroring.save_state(w);
+        w.write_u8(self.bank_select);
+        w.write_bytes(&self.prg_banks);
+        w.write_bytes(&self.chr_banks);
+    }
+
+    fn load_state(&mut self, r: &mut StateReader<'_>) -> Result<(), StateError> {
+        self.mirroring.load_state(r)?;
+        self.bank_select = r.read_u8()?;
+        r.read_bytes_into(&mut self.prg_banks)?;
+        r.read_bytes_into(&mut self.chr_banks)
+    }
+}
+
 /// Jaleco JF-13 (Mapper 86): 32K PRG + 8K CHR. Used by Moero!! Pro Yakyuu.
 struct JalecoJf13 { prg_rom: Vec<u8>, chr_rom: Vec<u8>, mirroring: Mirroring, prg_bank: u8, chr_bank: u8 }
 impl JalecoJf13 {
@@ -5433,6 +6680,21 @@ impl Mapper for JalecoJf13 {
     fn mirroring(&self) -> Mirroring { self.mirroring }
 }
 
+impl SaveState for JalecoJf13 {
+    fn save_state(&self, w: &mut StateWriter) {
+        self.mirroring.save_state(w);
+        w.write_u8(self.prg_bank);
+        w.write_u8(self.chr_bank);
+    }
+
+    fn load_state(&mut self, r: &mut StateReader<'_>) -> Result<(), StateError> {
+        self.mirroring.load_state(r)?;
+        self.prg_bank = r.read_u8()?;
+        self.chr_bank = r.read_u8()?;
+        Ok(())
+    }
+}
+
 /// Sunsoft-2 variant (Mapper 89): 16K PRG + 8K CHR + single-screen mirroring.
 struct Sunsoft2b { prg_rom: Vec<u8>, chr_rom: Vec<u8>, mirroring: Mirroring, prg_bank: u8, chr_bank: u8 }
 impl Sunsoft2b {
@@ -5465,6 +6727,21 @@ impl Mapper for Sunsoft2b {
     fn mirroring(&self) -> Mirroring { self.mirroring }
 }
 
+impl SaveState for Sunsoft2b {
+    fn save_state(&self, w: &mut StateWriter) {
+        self.mirroring.save_state(w);
+        w.write_u8(self.prg_bank);
+        w.write_u8(self.chr_bank);
+    }
+
+    fn load_state(&mut self, r: &mut StateReader<'_>) -> Result<(), StateError> {
+        self.mirroring.load_state(r)?;
+        self.prg_bank = r.read_u8()?;
+        self.chr_bank = r.read_u8()?;
+        Ok(())
+    }
+}
+
 /// Jaleco JF-19 (Mapper 92): like mapper 72 but $C000 switchable, $8000 fixed.
 struct JalecoJf19 { prg_rom: Vec<u8>, chr_rom: Vec<u8>, mirroring: Mirroring, prg_bank: u8, chr_bank: u8 }
 impl JalecoJf19 {
@@ -5496,6 +6773,21 @@ impl Mapper for JalecoJf19 {
     fn mirroring(&self) -> Mirroring { self.mirroring }
 }
 
+impl SaveState for JalecoJf19 {
+    fn save_state(&self, w: &mut StateWriter) {
+        self.mirroring.save_state(w);
+        w.write_u8(self.prg_bank);
+        w.write_u8(self.chr_bank);
+    }
+
+    fn load_state(&mut self, r: &mut StateReader<'_>) -> Result<(), StateError> {
+        self.mirroring.load_state(r)?;
+        self.prg_bank = r.read_u8()?;
+        self.chr_bank = r.read_u8()?;
+        Ok(())
+    }
+}
+
 /// UN1ROM (Mapper 94): UNROM variant with bank bits at D2-D4. Used by Senjou no Ookami.
 struct Un1rom { prg_rom: Vec<u8>, chr_ram: [u8; 8192], mirroring: Mirroring, prg_bank: u8 }
 impl Un1rom {
@@ -5520,6 +6812,21 @@ impl Mapper for Un1rom {
     fn mirroring(&self) -> Mirroring { self.mirroring }
 }
 
+impl SaveState for Un1rom {
+    fn save_state(&self, w: &mut StateWriter) {
+        w.write_bytes(&self.chr_ram);
+        self.mirroring.save_state(w);
+        w.write_u8(self.prg_bank);
+    }
+
+    fn load_state(&mut self, r: &mut StateReader<'_>) -> Result<(), StateError> {
+        r.read_bytes_into(&mut self.chr_ram)?;
+        self.mirroring.load_state(r)?;
+        self.prg_bank = r.read_u8()?;
+        Ok(())
+    }
+}
+
 /// Namcot-3425 (Mapper 95): like mapper 206 but D5 of CHR controls nametable.
 struct Namcot3425 {
     prg_rom: Vec<u8>, chr_rom: Vec<u8>, mirroring: Mirroring,
@@ -5574,6 +6881,20 @@ impl Mapper for Namcot3425 {
     fn mirroring(&self) -> Mirroring { self.mirroring }
 }
 
+impl SaveState for Namcot3425 {
+    fn save_state(&self, w: &mut StateWriter) {
+        self.mirroring.save_state(w);
+        w.write_u8(self.bank_select);
+        w.write_bytes(&self.registers);
+    }
+
+    fn load_state(&mut self, r: &mut StateReader<'_>) -> Result<(), StateError> {
+        self.mirroring.load_state(r)?;
+        self.bank_select = r.read_u8()?;
+        r.read_bytes_into(&mut self.registers)
+    }
+}
+
 /// Irem TAM-S1 (Mapper 97): 16K PRG at $C000 (switchable), $8000 fixed to last.
 struct IremTamS1 { prg_rom: Vec<u8>, chr_ram: [u8; 8192], mirroring: Mirroring, prg_bank: u8 }
 impl IremTamS1 {
@@ -5606,6 +6927,21 @@ impl Mapper for IremTamS1 {
     fn mirroring(&self) -> Mirroring { self.mirroring }
 }
 
+impl SaveState for IremTamS1 {
+    fn save_state(&self, w: &mut StateWriter) {
+        w.write_bytes(&self.chr_ram);
+        self.mirroring.save_state(w);
+        w.write_u8(self.prg_bank);
+    }
+
+    fn load_state(&mut self, r: &mut StateReader<'_>) -> Result<(), StateError> {
+        r.read_bytes_into(&mut self.chr_ram)?;
+        self.mirroring.load_state(r)?;
+        self.prg_bank = r.read_u8()?;
+        Ok(())
+    }
+}
+
 /// Camerica Quattro (Mapper 232): outer+inner PRG banking. Quattro multicarts.
 struct CamericaQuattro { prg_rom: Vec<u8>, chr_ram: [u8; 8192], mirroring: Mirroring, outer: u8, inner: u8 }
 impl CamericaQuattro {
@@ -5636,6 +6972,23 @@ impl Mapper for CamericaQuattro {
     fn mirroring(&self) -> Mirroring { self.mirroring }
 }
 
+impl SaveState for CamericaQuattro {
+    fn save_state(&self, w: &mut StateWriter) {
+        w.write_bytes(&self.chr_ram);
+        self.mirroring.save_state(w);
+        w.write_u8(self.outer);
+        w.write_u8(self.inner);
+    }
+
+    fn load_state(&mut self, r: &mut StateReader<'_>) -> Result<(), StateError> {
+        r.read_bytes_into(&mut self.chr_ram)?;
+        self.mirroring.load_state(r)?;
+        self.outer = r.read_u8()?;
+        self.inner = r.read_u8()?;
+        Ok(())
+    }
+}
+
 /// HES NTD-8 (Mapper 113): NINA-003 with extra bits. HES multicarts.
 struct HesNtd8 { prg_rom: Vec<u8>, chr_rom: Vec<u8>, mirroring: Mirroring, prg_bank: u8, chr_bank: u8 }
 impl HesNtd8 {
@@ -5663,6 +7016,21 @@ impl Mapper for HesNtd8 {
     fn mirroring(&self) -> Mirroring { self.mirroring }
 }
 
+impl SaveState for HesNtd8 {
+    fn save_state(&self, w: &mut StateWriter) {
+        self.mirroring.save_state(w);
+        w.write_u8(self.prg_bank);
+        w.write_u8(self.chr_bank);
+    }
+
+    fn load_state(&mut self, r: &mut StateReader<'_>) -> Result<(), StateError> {
+        self.mirroring.load_state(r)?;
+        self.prg_bank = r.read_u8()?;
+        self.chr_bank = r.read_u8()?;
+        Ok(())
+    }
+}
+
 /// Bandai SRAM variant (Mapper 153): mapper 16 with SRAM instead of EEPROM.
 /// Implemented as alias to BandaiFcg.
 
@@ -5716,6 +7084,20 @@ impl Mapper for Namcot3453 {
     fn mirroring(&self) -> Mirroring { self.mirroring }
 }
 
+impl SaveState for Namcot3453 {
+    fn save_state(&self, w: &mut StateWriter) {
+        self.mirroring.save_state(w);
+        w.write_u8(self.bank_select);
+        w.write_bytes(&self.registers);
+    }
+
+    fn load_state(&mut self, r: &mut StateReader<'_>) -> Result<(), StateError> {
+        self.mirroring.load_state(r)?;
+        self.bank_select = r.read_u8()?;
+        r.read_bytes_into(&mut self.registers)
+    }
+}
+
 /// DAOU-306 (Mapper 156): per-byte CHR banking. Korean commercial games.
 struct Daou306 {
     prg_rom: Vec<u8>, chr_rom: Vec<u8>, prg_bank: u8,
@@ -5753,6 +7135,18 @@ impl Mapper for Daou306 {
     fn mirroring(&self) -> Mirroring { Mirroring::SingleScreenLower }
 }
 
+impl SaveState for Daou306 {
+    fn save_state(&self, w: &mut StateWriter) {
+        w.write_u8(self.prg_bank);
+        w.write_bytes(&self.chr_banks);
+    }
+
+    fn load_state(&mut self, r: &mut StateReader<'_>) -> Result<(), StateError> {
+        self.prg_bank = r.read_u8()?;
+        r.read_bytes_into(&mut self.chr_banks)
+    }
+}
+
 /// Crazy Climber (Mapper 180): UNROM with fixed $8000, switchable $C000.
 struct CrazyClimber { prg_rom: Vec<u8>, chr_ram: [u8; 8192], mirroring: Mirroring, prg_bank: u8 }
 impl CrazyClimber {
@@ -5777,6 +7171,21 @@ impl Mapper for CrazyClimber {
     fn mirroring(&self) -> Mirroring { self.mirroring }
 }
 
+impl SaveState for CrazyClimber {
+    fn save_state(&self, w: &mut StateWriter) {
+        w.write_bytes(&self.chr_ram);
+        self.mirroring.save_state(w);
+        w.write_u8(self.prg_bank);
+    }
+
+    fn load_state(&mut self, r: &mut StateReader<'_>) -> Result<(), StateError> {
+        r.read_bytes_into(&mut self.chr_ram)?;
+        self.mirroring.load_state(r)?;
+        self.prg_bank = r.read_u8()?;
+        Ok(())
+    }
+}
+
 /// Action 52 (Mapper 228): PRG/CHR banking via address lines.
 struct Action52 { prg_rom: Vec<u8>, chr_rom: Vec<u8>, mirroring: Mirroring, prg_bank: u16, chr_bank: u8, prg_chip: u8 }
 impl Action52 {
@@ -5809,6 +7218,23 @@ impl Mapper for Action52 {
     fn mirroring(&self) -> Mirroring { self.mirroring }
 }
 
+impl SaveState for Action52 {
+    fn save_state(&self, w: &mut StateWriter) {
+        self.mirroring.save_state(w);
+        w.write_u16(self.prg_bank);
+        w.write_u8(self.chr_bank);
+        w.write_u8(self.prg_chip);
+    }
+
+    fn load_state(&mut self, r: &mut StateReader<'_>) -> Result<(), StateError> {
+        self.mirroring.load_state(r)?;
+        self.prg_bank = r.read_u16()?;
+        self.chr_bank = r.read_u8()?;
+        self.prg_chip = r.read_u8()?;
+        Ok(())
+    }
+}
+
 /// Parsed cartridge: mapper implementation and header metadata.
 pub struct ParsedCartridge {
     pub mapper: Box<dyn Mapper>,
@@ -8,6 +8,7 @@
 //! Power-up sequence: the keyboard sends $FD (init power-up) then $FE
 //! (terminate power-up), each requiring a handshake from the host.
 
+use emu_core::{SaveState, StateError, StateReader, StateWriter};
 use std::collections::VecDeque;
 
 /// E-clock ticks before power-up sequence begins (~200ms at 709 kHz).
@@ -170,6 +171,32 @@ impl AmigaKeyboard {
     }
 }
 
+impl SaveState for AmigaKeyboard {
+    fn save_state(&self, w: &mut StateWriter) {
+        w.write_u8(self.state as u8);
+        w.write_u32(self.timer);
+        w.write_bytes(&self.key_queue.iter().copied().collect::<Vec<_>>());
+        w.write_u32(self.bytes_sent);
+    }
+
+    fn load_state(&mut self, r: &mut StateReader<'_>) -> Result<(), StateError> {
+        self.state = match r.read_u8()? {
+            0 => State::PowerUpDelay,
+            1 => State::SendInitPowerUp,
+            2 => State::WaitHandshakeInit,
+            3 => State::SendTermPowerUp,
+            4 => State::WaitHandshakeTerm,
+            5 => State::Idle,
+            6 => State::WaitHandshakeKey,
+            n => return Err(StateError::Invalid(format!("keyboard state {n}"))),
+        };
+        self.timer = r.read_u32()?;
+        self.key_queue = r.read_bytes()?.iter().copied().collect();
+        self.bytes_sent = r.read_u32()?;
+        Ok(())
+    }
+}
+
 impl Default for AmigaKeyboard {
     fn default() -> Self {
         Self::new()
@@ -12,12 +12,15 @@
 //! linear counter, and sweep updates.
 //!
 //! Output is mixed through a non-linear mixer (nesdev formula) and
-//! downsampled to 48 kHz.
+//! band-limited to the output rate (48 kHz by default). Each channel can be
+//! muted in the mix or captured on its own as a stem.
 
 #![allow(clippy::cast_possible_truncation)]
 #![allow(clippy::cast_precision_loss)]
 #![allow(clippy::cast_sign_loss)]
 
+use emu_core::{BlipBuffer, SaveState, StateError, StateReader, StateWriter, Stems};
+
 // ---------------------------------------------------------------------------
 // Region
 // ---------------------------------------------------------------------------
@@ -652,29 +655,32 @@ pub struct Apu {
     four_step_seq: &'static [u16; 4],
     five_step_seq: &'static [u16; 5],
 
-    // Downsampling
-    accumulator: f32,
-    sample_count: u32,
-    ticks_per_sample: f32,
-    buffer: Vec<f32>,
-
-    // DC-blocking high-pass filter (applied at output sample rate).
-    // Removes the large DC offset inherent in the non-linear mixer.
-    // First-order high-pass: y[n] = α * (y[n-1] + x[n] - x[n-1])
-    // Cutoff ~37 Hz at 48 kHz: α ≈ 0.9952
-    hp_prev_in: f32,
-    hp_prev_out: f32,
+    // Band-limited output, clocked per CPU cycle. Its DC-blocking
+    // high-pass removes the large DC offset inherent in the non-linear
+    // mixer.
+    blip: BlipBuffer,
 
     /// Expansion audio level from cartridge mapper (e.g. Sunsoft 5B, VRC6,
     /// Namco 163). Set externally each CPU cycle before calling `tick()`.
     /// Range: 0.0 to ~0.5 (mixed additively with the internal APU output).
     pub expansion_audio: f32,
+
+    /// Channels left out of the mix (bit n = `CHANNELS[n]`).
+    channel_mute: u8,
+    /// Per-channel capture, while enabled.
+    stems: Option<Stems>,
 }
 
 impl Apu {
-    /// Output sample rate.
+    /// Channel names, in tap and mute-mask order.
+    pub const CHANNELS: [&'static str; 5] = ["pulse1", "pulse2", "triangle", "noise", "dmc"];
+
+    /// Default output sample rate.
     const SAMPLE_RATE: u32 = 48_000;
 
+    /// DC-blocking high-pass cutoff.
+    const DC_BLOCK_HZ: f32 = 37.0;
+
     /// Create an APU with NTSC timing (default).
     #[must_use]
     pub fn new() -> Self {
@@ -716,13 +722,11 @@ impl Apu {
             dmc_rate_table: dmc_table,
             four_step_seq: four_step,
             five_step_seq: five_step,
-            accumulator: 0.0,
-            sample_count: 0,
-            ticks_per_sample: cpu_freq as f32 / Self::SAMPLE_RATE as f32,
-            buffer: Vec::with_capacity(Self::SAMPLE_RATE as usize / 50 + 1),
-            hp_prev_in: 0.0,
-            hp_prev_out: 0.0,
+            blip: BlipBuffer::new(f64::from(cpu_freq), Self::SAMPLE_RATE)
+                .with_high_pass(Self::DC_BLOCK_HZ),
             expansion_audio: 0.0,
+            channel_mute: 0,
+            stems: None,
         }
     }
 
@@ -957,24 +961,14 @@ impl Apu {
         // Frame counter
         self.clock_frame_counter();
 
-        // Mix and downsample (including expansion audio from cartridge)
-        let sample = self.mix() + self.expansion_audio;
-        self.accumulator += sample;
-        self.sample_count += 1;
-
-        if self.sample_count as f32 >= self.ticks_per_sample {
-            let avg = self.accumulator / self.sample_count as f32;
-
-            // DC-blocking high-pass filter: removes mixer's inherent DC offset.
-            // y[n] = α * (y[n-1] + x[n] - x[n-1]), α ≈ 0.9952 (~37 Hz at 48 kHz)
-            const ALPHA: f32 = 0.9952;
-            let filtered = ALPHA * (self.hp_prev_out + avg - self.hp_prev_in);
-            self.hp_prev_in = avg;
-            self.hp_prev_out = filtered;
-
-            self.buffer.push(filtered);
-            self.accumulator = 0.0;
-            self.sample_count = 0;
+        // Mix (including expansion audio from cartridge)
+        self.blip.set_level(self.mix() + self.expansion_audio);
+        self.blip.advance(1);
+        if self.stems.is_some() {
+            let taps = self.channel_outputs();
+            if let Some(stems) = &mut self.stems {
+                stems.record(&taps, 1);
+            }
         }
     }
 
@@ -1059,14 +1053,43 @@ impl Apu {
         self.pulse2.timer_period = p;
     }
 
-    /// Non-linear mixer (nesdev formula).
+    /// Raw DAC inputs of each channel, in `CHANNELS` order.
+    fn channel_levels(&self) -> [f32; 5] {
+        [
+            f32::from(self.pulse1.output()),
+            f32::from(self.pulse2.output()),
+            f32::from(self.triangle.output()),
+            f32::from(self.noise.output()),
+            f32::from(self.dmc.output_level),
+        ]
+    }
+
+    /// Mix of the unmuted channels.
     fn mix(&self) -> f32 {
-        let p1 = f32::from(self.pulse1.output());
-        let p2 = f32::from(self.pulse2.output());
-        let tri = f32::from(self.triangle.output());
-        let noi = f32::from(self.noise.output());
-        let dmc = f32::from(self.dmc.output_level);
+        let mut levels = self.channel_levels();
+        for (ch, level) in levels.iter_mut().enumerate() {
+            if self.channel_mute & (1 << ch) != 0 {
+                *level = 0.0;
+            }
+        }
+        Self::mix_levels(levels)
+    }
 
+    /// Each channel's output at this instant: what the mixer produces with
+    /// that channel playing alone. The mixer is non-linear, so these add up
+    /// to slightly more than the full mix when channels overlap.
+    #[must_use]
+    pub fn channel_outputs(&self) -> [f32; 5] {
+        let levels = self.channel_levels();
+        std::array::from_fn(|ch| {
+            let mut solo = [0.0; 5];
+            solo[ch] = levels[ch];
+            Self::mix_levels(solo)
+        })
+    }
+
+    /// Non-linear mixer (nesdev formula).
+    fn mix_levels([p1, p2, tri, noi, dmc]: [f32; 5]) -> f32 {
         let pulse_out = if p1 + p2 > 0.0 {
             95.88 / (8128.0 / (p1 + p2) + 100.0)
         } else {
@@ -1093,15 +1116,61 @@ impl Apu {
 
     /// Take the audio output buffer (drains it).
     ///
-    /// Returns mono f32 samples in the range -1.0 to 1.0, at 48 kHz.
+    /// Returns mono f32 samples in the range -1.0 to 1.0, at the output
+    /// rate.
     pub fn take_buffer(&mut self) -> Vec<f32> {
-        std::mem::take(&mut self.buffer)
+        self.blip.take_samples()
     }
 
     /// Number of audio samples pending in the buffer.
     #[must_use]
     pub fn buffer_len(&self) -> usize {
-        self.buffer.len()
+        self.blip.len()
+    }
+
+    /// Output sample rate.
+    #[must_use]
+    pub fn sample_rate(&self) -> u32 {
+        self.blip.sample_rate()
+    }
+
+    /// Change the output sample rate (48 kHz by default).
+    pub fn set_sample_rate(&mut self, rate: u32) {
+        self.blip.set_sample_rate(rate);
+        if let Some(stems) = &mut self.stems {
+            stems.set_sample_rate(rate);
+        }
+    }
+
+    /// Stretch the output rate for dynamic rate control.
+    pub fn set_rate_adjust(&mut self, ratio: f64) {
+        self.blip.set_rate_adjust(ratio);
+        if let Some(stems) = &mut self.stems {
+            stems.set_rate_adjust(ratio);
+        }
+    }
+
+    /// Mute channels in the mix: bit n silences `CHANNELS[n]`.
+    pub fn set_channel_mute(&mut self, mask: u8) {
+        self.channel_mute = mask & 0x1F;
+    }
+
+    /// Channels currently muted in the mix.
+    #[must_use]
+    pub fn channel_mute(&self) -> u8 {
+        self.channel_mute
+    }
+
+    /// Start or stop capturing each channel as its own stream. Stems carry
+    /// the same DC-blocking as the mix; expansion audio is not included.
+    pub fn set_stems(&mut self, enabled: bool) {
+        self.stems = enabled.then(|| Stems::new(&self.blip, &self.channel_outputs()));
+    }
+
+    /// Drain the per-channel streams, one per `CHANNELS` entry, at the
+    /// output rate. Empty while capture is off.
+    pub fn take_stems(&mut self) -> Vec<Vec<f32>> {
+        self.stems.as_mut().map_or_else(Vec::new, Stems::take)
     }
 
     // -----------------------------------------------------------------------
@@ -1202,6 +1271,234 @@ impl Apu {
     }
 }
 
+// ---------------------------------------------------------------------------
+// Save state
+// ---------------------------------------------------------------------------
+
+impl SaveState for Envelope {
+    fn save_state(&self, w: &mut StateWriter) {
+        w.write_bool(self.start_flag);
+        w.write_u8(self.divider);
+        w.write_u8(self.decay_level);
+        w.write_u8(self.volume);
+        w.write_bool(self.constant_volume);
+        w.write_bool(self.loop_flag);
+    }
+
+    fn load_state(&mut self, r: &mut StateReader<'_>) -> Result<(), StateError> {
+        self.start_flag = r.read_bool()?;
+        self.divider = r.read_u8()?;
+        self.decay_level = r.read_u8()?;
+        self.volume = r.read_u8()?;
+        self.constant_volume = r.read_bool()?;
+        self.loop_flag = r.read_bool()?;
+        Ok(())
+    }
+}
+
+impl SaveState for LengthCounter {
+    fn save_state(&self, w: &mut StateWriter) {
+        w.write_u8(self.counter);
+        w.write_bool(self.halt);
+        w.write_bool(self.enabled);
+    }
+
+    fn load_state(&mut self, r: &mut StateReader<'_>) -> Result<(), StateError> {
+        self.counter = r.read_u8()?;
+        self.halt = r.read_bool()?;
+        self.enabled = r.read_bool()?;
+        Ok(())
+    }
+}
+
+impl SaveState for Sweep {
+    fn save_state(&self, w: &mut StateWriter) {
+        w.write_bool(self.enabled);
+        w.write_bool(self.negate);
+        w.write_u8(self.shift);
+        w.write_u8(self.period);
+        w.write_u8(self.divider);
+        w.write_bool(self.reload_flag);
+        w.write_bool(self.ones_complement);
+    }
+
+    fn load_state(&mut self, r: &mut StateReader<'_>) -> Result<(), StateError> {
+        self.enabled = r.read_bool()?;
+        self.negate = r.read_bool()?;
+        self.shift = r.read_u8()?;
+        self.period = r.read_u8()?;
+        self.divider = r.read_u8()?;
+        self.reload_flag = r.read_bool()?;
+        self.ones_complement = r.read_bool()?;
+        Ok(())
+    }
+}
+
+impl SaveState for Pulse {
+    fn save_state(&self, w: &mut StateWriter) {
+        w.write_u16(self.timer_period);
+        w.write_u16(self.timer);
+        w.write_u8(self.duty_pos);
+        w.write_u8(self.duty);
+        self.envelope.save_state(w);
+        self.length.save_state(w);
+        self.sweep.save_state(w);
+    }
+
+    fn load_state(&mut self, r: &mut StateReader<'_>) -> Result<(), StateError> {
+        self.timer_period = r.read_u16()?;
+        self.timer = r.read_u16()?;
+        self.duty_pos = r.read_u8()?;
+        self.duty = r.read_u8()?;
+        self.envelope.load_state(r)?;
+        self.length.load_state(r)?;
+        self.sweep.load_state(r)
+    }
+}
+
+impl SaveState for Triangle {
+    fn save_state(&self, w: &mut StateWriter) {
+        w.write_u16(self.timer_period);
+        w.write_u16(self.timer);
+        w.write_u8(self.sequence_pos);
+        self.length.save_state(w);
+        w.write_u8(self.linear_counter);
+        w.write_u8(self.linear_counter_reload);
+        w.write_bool(self.linear_reload_flag);
+        w.write_bool(self.control_flag);
+    }
+
+    fn load_state(&mut self, r: &mut StateReader<'_>) -> Result<(), StateError> {
+        self.timer_period = r.read_u16()?;
+        self.timer = r.read_u16()?;
+        self.sequence_pos = r.read_u8()?;
+        self.length.load_state(r)?;
+        self.linear_counter = r.read_u8()?;
+        self.linear_counter_reload = r.read_u8()?;
+        self.linear_reload_flag = r.read_bool()?;
+        self.control_flag = r.read_bool()?;
+        Ok(())
+    }
+}
+
+impl SaveState for Noise {
+    fn save_state(&self, w: &mut StateWriter) {
+        w.write_u16(self.timer_period);
+        w.write_u16(self.timer);
+        w.write_u16(self.shift_register);
+        w.write_bool(self.mode);
+        self.envelope.save_state(w);
+        self.length.save_state(w);
+    }
+
+    fn load_state(&mut self, r: &mut StateReader<'_>) -> Result<(), StateError> {
+        self.timer_period = r.read_u16()?;
+        self.timer = r.read_u16()?;
+        self.shift_register = r.read_u16()?;
+        self.mode = r.read_bool()?;
+        self.envelope.load_state(r)?;
+        self.length.load_state(r)
+    }
+}
+
+impl SaveState for Dmc {
+    fn save_state(&self, w: &mut StateWriter) {
+        w.write_u8(self.output_level);
+        w.write_bool(self.irq_enabled);
+        w.write_bool(self.irq_flag);
+        w.write_bool(self.loop_flag);
+        w.write_u8(self.rate_index);
+        w.write_u16(self.timer);
+        w.write_u16(self.timer_period);
+        w.write_u16(self.sample_address);
+        w.write_u16(self.sample_length);
+        w.write_u16(self.current_address);
+        w.write_u16(self.bytes_remaining);
+        w.write_u8(self.sample_buffer);
+        w.write_bool(self.sample_buffer_empty);
+        w.write_u8(self.shift_register);
+        w.write_u8(self.bits_remaining);
+        w.write_bool(self.silence_flag);
+        w.write_bool(self.enabled);
+        w.write_bool(self.dma_pending);
+    }
+
+    fn load_state(&mut self, r: &mut StateReader<'_>) -> Result<(), StateError> {
+        self.output_level = r.read_u8()?;
+        self.irq_enabled = r.read_bool()?;
+        self.irq_flag = r.read_bool()?;
+        self.loop_flag = r.read_bool()?;
+        self.rate_index = r.read_u8()?;
+        self.timer = r.read_u16()?;
+        self.timer_period = r.read_u16()?;
+        self.sample_address = r.read_u16()?;
+        self.sample_length = r.read_u16()?;
+        self.current_address = r.read_u16()?;
+        self.bytes_remaining = r.read_u16()?;
+        self.sample_buffer = r.read_u8()?;
+        self.sample_buffer_empty = r.read_bool()?;
+        self.shift_register = r.read_u8()?;
+        self.bits_remaining = r.read_u8()?;
+        self.silence_flag = r.read_bool()?;
+        self.enabled = r.read_bool()?;
+        self.dma_pending = r.read_bool()?;
+        Ok(())
+    }
+}
+
+/// The region tables are configuration: a state taken on the other region
+/// is rejected.
+impl SaveState for Apu {
+    fn save_state(&self, w: &mut StateWriter) {
+        self.pulse1.save_state(w);
+        self.pulse2.save_state(w);
+        self.triangle.save_state(w);
+        self.noise.save_state(w);
+        self.dmc.save_state(w);
+        w.write_u16(self.four_step_seq[0]);
+        w.write_bool(self.frame_mode == FrameCounterMode::FiveStep);
+        w.write_u16(self.frame_counter);
+        w.write_u8(self.frame_step);
+        w.write_bool(self.frame_irq_inhibit);
+        w.write_bool(self.frame_irq_flag);
+        w.write_bool(self.odd_cycle);
+        w.write_bool(self.frame_counter_pending.is_some());
+        let (value, cycles) = self.frame_counter_pending.unwrap_or_default();
+        w.write_u8(value);
+        w.write_u8(cycles);
+        self.blip.save_state(w);
+        w.write_f32(self.expansion_audio);
+    }
+
+    fn load_state(&mut self, r: &mut StateReader<'_>) -> Result<(), StateError> {
+        self.pulse1.load_state(r)?;
+        self.pulse2.load_state(r)?;
+        self.triangle.load_state(r)?;
+        self.noise.load_state(r)?;
+        self.dmc.load_state(r)?;
+        if r.read_u16()? != self.four_step_seq[0] {
+            return Err(StateError::Invalid("APU region mismatch".into()));
+        }
+        self.frame_mode = if r.read_bool()? {
+            FrameCounterMode::FiveStep
+        } else {
+            FrameCounterMode::FourStep
+        };
+        self.frame_counter = r.read_u16()?;
+        self.frame_step = r.read_u8()?;
+        self.frame_irq_inhibit = r.read_bool()?;
+        self.frame_irq_flag = r.read_bool()?;
+        self.odd_cycle = r.read_bool()?;
+        let pending = r.read_bool()?;
+        let value = r.read_u8()?;
+        let cycles = r.read_u8()?;
+        self.frame_counter_pending = pending.then_some((value, cycles));
+        self.blip.load_state(r)?;
+        self.expansion_audio = r.read_f32()?;
+        Ok(())
+    }
+}
+
 impl Default for Apu {
     fn default() -> Self {
         Self::new()
@@ -1615,4 +1912,27 @@ mod tests {
             "IRQ flag should not be set when IRQ is disabled"
         );
     }
+
+    #[test]
+    fn muted_channel_keeps_its_stem() {
+        let mut apu = Apu::new();
+        apu.write(0x4015, 0x04); // Enable triangle
+        apu.write(0x4008, 0xFF); // Linear counter reload, halt
+        apu.write(0x400A, 0x40); // ~270 Hz
+        apu.write(0x400B, 0x08);
+        apu.set_channel_mute(0b0_0100);
+        apu.set_stems(true);
+        for _ in 0..(1_789_773 / 10) {
+            apu.tick();
+        }
+
+        let mix = apu.take_buffer();
+        let stems = apu.take_stems();
+        assert_eq!(stems.len(), Apu::CHANNELS.len());
+        assert!(stems.iter().all(|stem| stem.len() == mix.len()));
+        let peak = |buf: &[f32]| buf.iter().fold(0.0f32, |m, s| m.max(s.abs()));
+        assert!(peak(&mix) < 1e-6, "muted triangle reached the mix");
+        assert!(peak(&stems[2]) > 0.05, "stem should carry the triangle");
+        assert!(peak(&stems[0]) < 1e-6);
+    }
 }
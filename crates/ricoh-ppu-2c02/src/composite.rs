@@ -0,0 +1,150 @@
+//! The 2C02's composite video signal.
+//!
+//! The PPU generates its signal directly rather than encoding RGB: each
+//! colour is a square wave between two voltages, high for half of the
+//! twelve phases of the 21.48 MHz master clock that make up one
+//! subcarrier cycle. The hue (low nibble of the palette index) picks
+//! which six phases are high. Hue 0 is high throughout and hues $D-$F
+//! low throughout, so they carry no colour. Each emphasis bit attenuates
+//! the signal during the six phases of a fixed hue (except in the
+//! blacks, $E and $F), which is why emphasis tints the picture rather
+//! than just dimming channels.
+//!
+//! Framebuffer pixels are matched back to their palette index and
+//! emphasis bits, so the filter can rebuild the real waveform. Levels are
+//! the measured 2C02 voltages documented at nesdev.org.
+
+use std::collections::HashMap;
+use std::sync::LazyLock;
+
+use emu_core::crt::{self, PHASES, Signal, Standard, Waveform};
+
+use crate::palette::{PALETTE, emphasise};
+
+/// Square-wave low and high levels for each luma row, in volts.
+const LOW: [f32; 4] = [0.350, 0.518, 0.962, 1.550];
+const HIGH: [f32; 4] = [1.094, 1.506, 1.962, 1.962];
+const BLACK: f32 = 0.518;
+const WHITE: f32 = 1.962;
+/// Signal level while an emphasis bit is attenuating it.
+const ATTENUATION: f32 = 0.746;
+/// Hues whose phases the red, green and blue emphasis bits attenuate.
+const EMPHASIS_HUES: [u8; 3] = [0x0C, 0x04, 0x08];
+
+/// NTSC 2C02: a pixel is 8 master clocks, two thirds of a subcarrier
+/// cycle. A 341-dot line is 2728 clocks, which moves the phase on by a
+/// third of a cycle each line. Odd frames with rendering on are a dot
+/// short, so frames alternate between two phases.
+///
+/// The hue and saturation line the decoded colours up with the palette.
+pub const NTSC_SIGNAL: Signal = Signal {
+    standard: Standard::Ntsc,
+    cycles_per_pixel: 2.0 / 3.0,
+    line_phase: 1.0 / 3.0,
+    frame_phases: &[0.0, 1.0 / 3.0],
+    hue: -86.0,
+    saturation: 0.67,
+    waveform,
+};
+
+/// Palette index (bits 0-5) and emphasis (bits 6-8) for each colour the
+/// PPU can draw. Plain colours win over emphasised ones that look the
+/// same.
+static INDICES: LazyLock<HashMap<u32, u16>> = LazyLock::new(|| {
+    let mut indices = HashMap::new();
+    for emphasis in 0..8u8 {
+        for (index, &argb) in PALETTE.iter().enumerate() {
+            // $0D is blacker than black; the emulator draws it as black,
+            // so black pixels are taken to be the ordinary $0F.
+            if index == 0x0D {
+                continue;
+            }
+            indices
+                .entry(emphasise(argb, emphasis) & 0x00FF_FFFF)
+                .or_insert(index as u16 | u16::from(emphasis) << 6);
+        }
+    }
+    indices
+});
+
+/// Waveform for a framebuffer pixel. Colours that are not in the palette
+/// are encoded from RGB.
+fn waveform(pixel: u32) -> Waveform {
+    match INDICES.get(&(pixel & 0x00FF_FFFF)) {
+        Some(&index) => square_wave(index),
+        None => crt::rgb_waveform(pixel),
+    }
+}
+
+/// The PPU's output over one subcarrier cycle for a palette index with
+/// emphasis bits above it.
+fn square_wave(index: u16) -> Waveform {
+    let hue = (index & 0x0F) as u8;
+    let row = if hue > 0x0D {
+        1
+    } else {
+        usize::from((index >> 4) & 3)
+    };
+    let emphasis = index >> 6;
+    let in_phase = |hue: u8, phase: usize| (usize::from(hue) + phase) % PHASES < PHASES / 2;
+
+    std::array::from_fn(|phase| {
+        let mut volts = match hue {
+            0 => HIGH[row],
+            0x0D.. => LOW[row],
+            _ if in_phase(hue, phase) => HIGH[row],
+            _ => LOW[row],
+        };
+        if hue < 0x0E
+            && EMPHASIS_HUES
+                .iter()
+                .enumerate()
+                .any(|(bit, &hue)| emphasis & (1 << bit) != 0 && in_phase(hue, phase))
+        {
+            volts *= ATTENUATION;
+        }
+        (volts - BLACK) / (WHITE - BLACK)
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use emu_core::crt::{Connection, CrtFilter};
+
+    /// Decode a flat field of `argb` over S-Video.
+    fn decode(argb: u32) -> [i32; 3] {
+        let out =
+            CrtFilter::new(Connection::SVideo(NTSC_SIGNAL)).process(32, 4, &[argb; 128], 32, 4);
+        [16, 8, 0].map(|shift| ((out[2 * 32 + 16] >> shift) & 0xFF) as i32)
+    }
+
+    #[test]
+    fn square_waves_decode_to_the_palette() {
+        for index in [0x00, 0x12, 0x16, 0x1A, 0x21, 0x27, 0x2A, 0x30] {
+            let expected = [16, 8, 0].map(|shift| ((PALETTE[index] >> shift) & 0xFF) as i32);
+            let decoded = decode(PALETTE[index]);
+            for (channel, (a, b)) in decoded.iter().zip(expected).enumerate() {
+                assert!(
+                    (a - b).abs() <= 24,
+                    "${index:02X} channel {channel}: {decoded:?} vs {expected:?}"
+                );
+            }
+        }
+    }
+
+    #[test]
+    fn emphasis_is_recovered_and_tints() {
+        let white = PALETTE[0x30];
+        let red = emphasise(white, 0x01);
+        // $20 and $30 are the same white; the lower index wins.
+        assert_eq!(INDICES.get(&(red & 0x00FF_FFFF)), Some(&(0x20 | 1 << 6)));
+        assert_ne!(
+            waveform(red).map(f32::to_bits),
+            waveform(white).map(f32::to_bits)
+        );
+
+        let [r, _, b] = decode(red);
+        assert!(r > b + 16, "red emphasis decoded as {r}/{b}");
+    }
+}
@@ -20,8 +20,10 @@
     clippy::manual_range_contains
 )]
 
+pub mod composite;
 pub mod palette;
 
+use emu_core::{SaveState, StateError, StateReader, StateWriter};
 use palette::PALETTE;
 
 /// Nametable mirroring mode.
@@ -34,6 +36,30 @@ pub enum Mirroring {
     SingleScreenUpper,
 }
 
+impl SaveState for Mirroring {
+    fn save_state(&self, w: &mut StateWriter) {
+        w.write_u8(match self {
+            Self::Horizontal => 0,
+            Self::Vertical => 1,
+            Self::FourScreen => 2,
+            Self::SingleScreenLower => 3,
+            Self::SingleScreenUpper => 4,
+        });
+    }
+
+    fn load_state(&mut self, r: &mut StateReader<'_>) -> Result<(), StateError> {
+        *self = match r.read_u8()? {
+            0 => Self::Horizontal,
+            1 => Self::Vertical,
+            2 => Self::FourScreen,
+            3 => Self::SingleScreenLower,
+            4 => Self::SingleScreenUpper,
+            n => return Err(StateError::Invalid(format!("mirroring mode {n}"))),
+        };
+        Ok(())
+    }
+}
+
 /// Framebuffer dimensions.
 pub const FB_WIDTH: u32 = 256;
 pub const FB_HEIGHT: u32 = 240;
@@ -793,35 +819,7 @@ impl Ppu {
             palette_index as usize
         };
 
-        let argb = PALETTE[idx];
-        let emphasis = self.mask >> 5;
-        if emphasis == 0 {
-            return argb;
-        }
-
-        // NTSC emphasis bits: bit 0 = red, bit 1 = green, bit 2 = blue.
-        // Each set bit attenuates the OTHER two channels.
-        let mut r = (argb >> 16) & 0xFF;
-        let mut g = (argb >> 8) & 0xFF;
-        let mut b = argb & 0xFF;
-
-        // Emphasise red → dim green and blue
-        if emphasis & 0x01 != 0 {
-            g = g * 13 / 16;
-            b = b * 13 / 16;
-        }
-        // Emphasise green → dim red and blue
-        if emphasis & 0x02 != 0 {
-            r = r * 13 / 16;
-            b = b * 13 / 16;
-        }
-        // Emphasise blue → dim red and green
-        if emphasis & 0x04 != 0 {
-            r = r * 13 / 16;
-            g = g * 13 / 16;
-        }
-
-        0xFF00_0000 | (r << 16) | (g << 8) | b
+        palette::emphasise(PALETTE[idx], self.mask >> 5)
     }
 
     fn check_nmi(&mut self) {
@@ -885,6 +883,91 @@ impl Ppu {
     }
 }
 
+/// The pre-render line (NTSC or PAL timing) is configuration: a state taken
+/// on the other video standard is rejected.
+impl SaveState for Ppu {
+    fn save_state(&self, w: &mut StateWriter) {
+        w.write_u16(self.pre_render_line);
+        w.write_bytes(&self.nametable_ram);
+        w.write_bytes(&self.palette_ram);
+        w.write_bytes(&self.oam);
+        w.write_u8(self.ctrl);
+        w.write_u8(self.mask);
+        w.write_u8(self.status);
+        w.write_u8(self.oam_addr);
+        w.write_u16(self.v);
+        w.write_u16(self.t);
+        w.write_u8(self.fine_x);
+        w.write_bool(self.w);
+        w.write_u8(self.read_buffer);
+        w.write_u8(self.open_bus);
+        w.write_u16(self.scanline);
+        w.write_u16(self.dot);
+        w.write_bool(self.frame_odd);
+        w.write_u16(self.bg_shift_pattern_lo);
+        w.write_u16(self.bg_shift_pattern_hi);
+        w.write_u16(self.bg_shift_attrib_lo);
+        w.write_u16(self.bg_shift_attrib_hi);
+        w.write_u8(self.bg_next_tile_id);
+        w.write_u8(self.bg_next_tile_attrib);
+        w.write_u8(self.bg_next_tile_lo);
+        w.write_u8(self.bg_next_tile_hi);
+        w.write_bytes(&self.secondary_oam);
+        w.write_u8(self.sprite_count);
+        w.write_bytes(&self.sprite_patterns_lo);
+        w.write_bytes(&self.sprite_patterns_hi);
+        w.write_bytes(&self.sprite_attribs);
+        w.write_bytes(&self.sprite_x_counters);
+        w.write_bool(self.sprite_zero_on_line);
+        w.write_u32_slice(&self.framebuffer);
+        w.write_bool(self.nmi_occurred);
+        w.write_bool(self.nmi_output);
+        w.write_bool(self.nmi_edge);
+    }
+
+    fn load_state(&mut self, r: &mut StateReader<'_>) -> Result<(), StateError> {
+        if r.read_u16()? != self.pre_render_line {
+            return Err(StateError::Invalid("PPU timing mismatch".into()));
+        }
+        r.read_bytes_into(&mut self.nametable_ram)?;
+        r.read_bytes_into(&mut self.palette_ram)?;
+        r.read_bytes_into(&mut self.oam)?;
+        self.ctrl = r.read_u8()?;
+        self.mask = r.read_u8()?;
+        self.status = r.read_u8()?;
+        self.oam_addr = r.read_u8()?;
+        self.v = r.read_u16()?;
+        self.t = r.read_u16()?;
+        self.fine_x = r.read_u8()?;
+        self.w = r.read_bool()?;
+        self.read_buffer = r.read_u8()?;
+        self.open_bus = r.read_u8()?;
+        self.scanline = r.read_u16()?;
+        self.dot = r.read_u16()?;
+        self.frame_odd = r.read_bool()?;
+        self.bg_shift_pattern_lo = r.read_u16()?;
+        self.bg_shift_pattern_hi = r.read_u16()?;
+        self.bg_shift_attrib_lo = r.read_u16()?;
+        self.bg_shift_attrib_hi = r.read_u16()?;
+        self.bg_next_tile_id = r.read_u8()?;
+        self.bg_next_tile_attrib = r.read_u8()?;
+        self.bg_next_tile_lo = r.read_u8()?;
+        self.bg_next_tile_hi = r.read_u8()?;
+        r.read_bytes_into(&mut self.secondary_oam)?;
+        self.sprite_count = r.read_u8()?;
+        r.read_bytes_into(&mut self.sprite_patterns_lo)?;
+        r.read_bytes_into(&mut self.sprite_patterns_hi)?;
+        r.read_bytes_into(&mut self.sprite_attribs)?;
+        r.read_bytes_into(&mut self.sprite_x_counters)?;
+        self.sprite_zero_on_line = r.read_bool()?;
+        r.read_u32_into(&mut self.framebuffer)?;
+        self.nmi_occurred = r.read_bool()?;
+        self.nmi_output = r.read_bool()?;
+        self.nmi_edge = r.read_bool()?;
+        Ok(())
+    }
+}
+
 impl Default for Ppu {
     fn default() -> Self {
         Self::new()
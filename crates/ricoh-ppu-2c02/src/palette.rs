@@ -26,3 +26,35 @@ pub static PALETTE: [u32; 64] = [
     0xFFE4E594, 0xFFCFEF96, 0xFFBDF4AB, 0xFFB3F3CC, // $38-$3B
     0xFFB5EBF2, 0xFFB8B8B8, 0xFF000000, 0xFF000000, // $3C-$3F
 ];
+
+/// Apply PPUMASK emphasis bits (0 = red, 1 = green, 2 = blue, as in
+/// PPUMASK bits 5-7) to an ARGB colour. Each set bit dims the other two
+/// channels.
+#[must_use]
+pub fn emphasise(argb: u32, emphasis: u8) -> u32 {
+    if emphasis == 0 {
+        return argb;
+    }
+
+    let mut r = (argb >> 16) & 0xFF;
+    let mut g = (argb >> 8) & 0xFF;
+    let mut b = argb & 0xFF;
+
+    // Emphasise red → dim green and blue
+    if emphasis & 0x01 != 0 {
+        g = g * 13 / 16;
+        b = b * 13 / 16;
+    }
+    // Emphasise green → dim red and blue
+    if emphasis & 0x02 != 0 {
+        r = r * 13 / 16;
+        b = b * 13 / 16;
+    }
+    // Emphasise blue → dim red and green
+    if emphasis & 0x04 != 0 {
+        r = r * 13 / 16;
+        g = g * 13 / 16;
+    }
+
+    0xFF00_0000 | (r << 16) | (g << 8) | b
+}
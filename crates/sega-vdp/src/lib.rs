@@ -13,6 +13,8 @@
 
 #![allow(clippy::cast_possible_truncation)]
 
+use emu_core::{SaveState, StateError, StateReader, StateWriter};
+
 // ---------------------------------------------------------------------------
 // Region and variant
 // ---------------------------------------------------------------------------
@@ -506,6 +508,56 @@ impl SegaVdp {
     }
 }
 
+impl SaveState for SegaVdp {
+    fn save_state(&self, w: &mut StateWriter) {
+        w.write_u16(self.lines_per_frame());
+        w.write_bool(self.is_game_gear);
+        w.write_bytes(&self.vram);
+        w.write_bytes(&self.cram);
+        w.write_u8(self.cram_latch);
+        w.write_bytes(&self.regs);
+        w.write_u8(self.status);
+        w.write_u8(self.read_buffer);
+        w.write_u16(self.address);
+        w.write_u8(self.code);
+        w.write_bool(self.latch_first);
+        w.write_u8(self.latch_value);
+        w.write_u16(self.v_counter);
+        w.write_u8(self.h_counter);
+        w.write_u8(self.line_counter);
+        w.write_bool(self.line_irq_pending);
+        w.write_u16(self.scanline);
+        w.write_u32_slice(&self.framebuffer);
+        w.write_bool(self.interrupt);
+        w.write_u64(self.frame_count);
+    }
+
+    fn load_state(&mut self, r: &mut StateReader<'_>) -> Result<(), StateError> {
+        if r.read_u16()? != self.lines_per_frame() || r.read_bool()? != self.is_game_gear {
+            return Err(StateError::Invalid("VDP variant mismatch".into()));
+        }
+        r.read_bytes_into(&mut self.vram)?;
+        r.read_bytes_into(&mut self.cram)?;
+        self.cram_latch = r.read_u8()?;
+        r.read_bytes_into(&mut self.regs)?;
+        self.status = r.read_u8()?;
+        self.read_buffer = r.read_u8()?;
+        self.address = r.read_u16()? & 0x3FFF;
+        self.code = r.read_u8()? & 0x03;
+        self.latch_first = r.read_bool()?;
+        self.latch_value = r.read_u8()?;
+        self.v_counter = r.read_u16()?;
+        self.h_counter = r.read_u8()?;
+        self.line_counter = r.read_u8()?;
+        self.line_irq_pending = r.read_bool()?;
+        self.scanline = r.read_u16()?;
+        r.read_u32_into(&mut self.framebuffer)?;
+        self.interrupt = r.read_bool()?;
+        self.frame_count = r.read_u64()?;
+        Ok(())
+    }
+}
+
 // ---------------------------------------------------------------------------
 // Tests
 // ---------------------------------------------------------------------------
@@ -638,4 +690,27 @@ mod tests {
         assert!(vdp.line_irq_pending);
         assert!(vdp.interrupt);
     }
+
+    #[test]
+    fn save_state_restores_vram_and_rejects_other_region() {
+        let mut vdp = SegaVdp::new(VdpRegion::Ntsc, VdpVariant::Sms2);
+        vdp.write_vram(0x1234, 0xA5);
+        vdp.write_control(0x07);
+        vdp.write_control(0x81); // Reg 1 = $07
+        for _ in 0..100 {
+            vdp.tick_scanline();
+        }
+        let mut w = StateWriter::new();
+        vdp.save_state(&mut w);
+        let state = w.into_bytes();
+
+        let mut copy = SegaVdp::new(VdpRegion::Ntsc, VdpVariant::Sms2);
+        copy.load_state(&mut StateReader::new(&state)).expect("state loads");
+        assert_eq!(copy.vram()[0x1234], 0xA5);
+        assert_eq!(copy.regs, vdp.regs);
+        assert_eq!(copy.scanline, vdp.scanline);
+
+        let mut pal = SegaVdp::new(VdpRegion::Pal, VdpVariant::Sms2);
+        assert!(pal.load_state(&mut StateReader::new(&state)).is_err());
+    }
 }
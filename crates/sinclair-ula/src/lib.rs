@@ -6,8 +6,9 @@
 //!
 //! # Standalone IC
 //!
-//! This crate has no dependencies — the ULA accesses VRAM through closures
-//! passed by the caller, keeping it decoupled from any particular memory model.
+//! The ULA accesses VRAM through closures passed by the caller, keeping it
+//! decoupled from any particular memory model. Its only dependency is
+//! `emu-core`, for the save-state traits.
 //!
 //! # Timing (48K PAL)
 //!
@@ -44,6 +45,8 @@
 
 mod palette;
 
+use emu_core::{SaveState, StateError, StateReader, StateWriter};
+
 pub use palette::PALETTE;
 
 /// Framebuffer dimensions.
@@ -255,6 +258,22 @@ impl Ula {
         self.tstate()
     }
 
+    /// T-states since the start of the frame (the interrupt).
+    #[must_use]
+    pub fn frame_tstate(&self) -> u32 {
+        u32::from(self.line) * u32::from(TSTATES_PER_LINE) + u32::from(self.tstate())
+    }
+
+    /// Move the beam to `tstate` T-states into the frame, as when
+    /// restoring a snapshot. Positions past the end of the frame (from a
+    /// model with longer frames) clamp to its last T-state.
+    pub fn set_frame_tstate(&mut self, tstate: u32) {
+        let frame = u32::from(LINES_PER_FRAME) * u32::from(TSTATES_PER_LINE);
+        let tstate = tstate.min(frame - 1);
+        self.line = (tstate / u32::from(TSTATES_PER_LINE)) as u16;
+        self.pixel = (tstate % u32::from(TSTATES_PER_LINE)) as u16 * 2;
+    }
+
     /// Reference to the framebuffer (ARGB32).
     #[must_use]
     pub fn framebuffer(&self) -> &[u32] {
@@ -273,6 +292,14 @@ impl Ula {
         FB_HEIGHT
     }
 
+    /// The 256×192 screen inside the border, as x, y, width and height
+    /// in the framebuffer.
+    #[must_use]
+    pub fn display_window(&self) -> (u32, u32, u32, u32) {
+        let top = (FB_HEIGHT - SCREEN_HEIGHT) / 2;
+        (BORDER_LEFT, top, SCREEN_WIDTH, SCREEN_HEIGHT)
+    }
+
     /// Current border colour index (0-7).
     #[must_use]
     pub fn border_colour(&self) -> u8 {
@@ -547,6 +574,34 @@ impl Ula {
     }
 }
 
+impl SaveState for Ula {
+    fn save_state(&self, w: &mut StateWriter) {
+        w.write_u16(self.line);
+        w.write_u16(self.pixel);
+        w.write_bool(self.frame_complete);
+        w.write_u8(self.border);
+        w.write_bool(self.flash_state);
+        w.write_u8(self.flash_counter);
+        w.write_u32_slice(&self.framebuffer);
+        w.write_bool(self.snow_byte.is_some());
+        w.write_u8(self.snow_byte.unwrap_or(0));
+    }
+
+    fn load_state(&mut self, r: &mut StateReader<'_>) -> Result<(), StateError> {
+        self.line = r.read_u16()?;
+        self.pixel = r.read_u16()?;
+        self.frame_complete = r.read_bool()?;
+        self.border = r.read_u8()?;
+        self.flash_state = r.read_bool()?;
+        self.flash_counter = r.read_u8()?;
+        r.read_u32_into(&mut self.framebuffer)?;
+        let has_snow = r.read_bool()?;
+        let snow = r.read_u8()?;
+        self.snow_byte = has_snow.then_some(snow);
+        Ok(())
+    }
+}
+
 impl Default for Ula {
     fn default() -> Self {
         Self::new()
@@ -659,6 +714,17 @@ mod tests {
         assert_eq!(ula.tstates_per_line(), 224);
     }
 
+    #[test]
+    fn frame_tstate_round_trips_and_clamps() {
+        let mut ula = Ula::new();
+        ula.set_frame_tstate(64 * 224 + 10);
+        assert_eq!((ula.line(), ula.line_tstate()), (64, 10));
+        assert_eq!(ula.frame_tstate(), 64 * 224 + 10);
+
+        ula.set_frame_tstate(70_907);
+        assert_eq!(ula.frame_tstate(), 69_887);
+    }
+
     #[test]
     fn line_tstate_reports_cpu_tstates() {
         let mut ula = Ula::new();
@@ -13,15 +13,28 @@
 //! register; the noise channel has a 3-bit mode register selecting
 //! period and feedback type.
 //!
-//! Output is downsampled to 48 kHz stereo (identical L/R by default;
-//! the Game Gear variant adds per-channel stereo panning).
+//! Output is band-limited to 48 kHz by default (identical L/R; the Game
+//! Gear variant adds per-channel stereo panning). Each channel's output is
+//! also available as a tap, for muting individual channels or capturing
+//! them as separate stems.
+//!
+//! Writes can be logged with input-clock timestamps and exported as VGM
+//! (see [`emu_core::reglog`]).
 
 #![allow(clippy::cast_possible_truncation)]
 #![allow(clippy::cast_precision_loss)]
 
-/// Output sample rate.
+use emu_core::{
+    BlipBuffer, LoggedChip, RegisterLog, SaveState, StateError, StateReader, StateWriter, Stems,
+};
+
+/// Default output sample rate.
 const SAMPLE_RATE: u32 = 48_000;
 
+/// The raw chip output is unipolar and normally AC-coupled by the
+/// surrounding hardware, so remove DC before exposing samples.
+const DC_BLOCK_HZ: f32 = 37.0;
+
 /// SN76489 Programmable Sound Generator.
 pub struct Sn76489 {
     // Tone channels 0-2
@@ -43,29 +56,35 @@ pub struct Sn76489 {
     /// Which register is currently latched for data writes.
     latched_register: u8,
 
-    // Downsampling
+    // Output, clocked at the internal (÷16) rate
     clock_divider: u32,
-    accumulator: f32,
-    sample_count: u32,
-    internal_clock_hz: u32,
-    resample_phase: u32,
-    hp_prev_in: f32,
-    hp_prev_out: f32,
-    buffer: Vec<f32>,
+    blip: BlipBuffer,
+    /// Channels left out of the mix (bit n = `CHANNELS[n]`).
+    channel_mute: u8,
+    /// Per-channel capture, while enabled.
+    stems: Option<Stems>,
 
     // Stereo panning (Game Gear extension). Bits 7-0: R3 L3 R2 L2 R1 L1 R0 L0.
     // Default $FF = all channels to both speakers.
     stereo_panning: u8,
+
+    /// Input clock in Hz, for register-log timestamps.
+    clock_hz: u32,
+    /// Register-write log, while enabled.
+    log: Option<RegisterLog>,
 }
 
 impl Sn76489 {
+    /// Channel names, in tap and mute-mask order.
+    pub const CHANNELS: [&'static str; 4] = ["tone0", "tone1", "tone2", "noise"];
+
     /// Create a new SN76489 with the given input clock frequency.
     ///
     /// The internal clock divides by 16, so for a 3.579545 MHz input the
     /// effective tone clock is ~223.7 kHz.
     #[must_use]
     pub fn new(clock_hz: u32) -> Self {
-        let internal_clock = clock_hz / 16;
+        let internal_clock = f64::from(clock_hz) / 16.0;
         Self {
             tone_period: [0; 3],
             tone_counter: [0; 3],
@@ -83,15 +102,14 @@ impl Sn76489 {
             latched_register: 0,
 
             clock_divider: 0,
-            accumulator: 0.0,
-            sample_count: 0,
-            internal_clock_hz: internal_clock,
-            resample_phase: 0,
-            hp_prev_in: 0.0,
-            hp_prev_out: 0.0,
-            buffer: Vec::with_capacity(SAMPLE_RATE as usize / 50 + 1),
+            blip: BlipBuffer::new(internal_clock, SAMPLE_RATE).with_high_pass(DC_BLOCK_HZ),
+            channel_mute: 0,
+            stems: None,
 
             stereo_panning: 0xFF,
+
+            clock_hz,
+            log: None,
         }
     }
 
@@ -100,6 +118,9 @@ impl Sn76489 {
     /// Bit 7 = 1: latch/data byte. Bits 6-4 = register (0-7). Bits 3-0 = data.
     /// Bit 7 = 0: data byte for the previously latched register.
     pub fn write(&mut self, value: u8) {
+        if let Some(log) = &mut self.log {
+            log.record(0, value);
+        }
         if value & 0x80 != 0 {
             // Latch + data
             self.latched_register = (value >> 4) & 0x07;
@@ -159,12 +180,18 @@ impl Sn76489 {
 
     /// Write the Game Gear stereo panning register ($06 on GG).
     pub fn write_stereo(&mut self, value: u8) {
+        if let Some(log) = &mut self.log {
+            log.record(1, value);
+        }
         self.stereo_panning = value;
     }
 
     /// Tick the PSG one master clock cycle. The internal divider handles
     /// the ÷16 frequency reduction.
     pub fn tick(&mut self) {
+        if let Some(log) = &mut self.log {
+            log.tick();
+        }
         self.clock_divider += 1;
         if self.clock_divider < 16 {
             return;
@@ -204,42 +231,108 @@ impl Sn76489 {
             self.noise_counter -= 1;
         }
 
-        // Mix and downsample
-        let sample = self.mix();
-        self.accumulator += sample;
-        self.sample_count += 1;
-
-        self.resample_phase = self.resample_phase.saturating_add(SAMPLE_RATE);
-        if self.resample_phase >= self.internal_clock_hz {
-            let avg = self.accumulator / self.sample_count as f32;
-
-            // The raw chip output is unipolar and normally AC-coupled by the
-            // surrounding hardware, so remove DC before exposing samples.
-            const DC_BLOCK_ALPHA: f32 = 0.9952;
-            let filtered = DC_BLOCK_ALPHA * (self.hp_prev_out + avg - self.hp_prev_in);
-            self.hp_prev_in = avg;
-            self.hp_prev_out = filtered;
-
-            self.buffer.push(filtered);
-            self.resample_phase -= self.internal_clock_hz;
-            self.accumulator = 0.0;
-            self.sample_count = 0;
+        let taps = self.channel_outputs();
+        self.blip.set_level(self.mix(&taps));
+        self.blip.advance(1);
+        if let Some(stems) = &mut self.stems {
+            stems.record(&taps, 1);
+        }
+    }
+
+    /// Output sample rate.
+    #[must_use]
+    pub fn sample_rate(&self) -> u32 {
+        self.blip.sample_rate()
+    }
+
+    /// Change the output sample rate (48 kHz by default).
+    pub fn set_sample_rate(&mut self, rate: u32) {
+        self.blip.set_sample_rate(rate);
+        if let Some(stems) = &mut self.stems {
+            stems.set_sample_rate(rate);
+        }
+    }
+
+    /// Stretch the output rate for dynamic rate control.
+    pub fn set_rate_adjust(&mut self, ratio: f64) {
+        self.blip.set_rate_adjust(ratio);
+        if let Some(stems) = &mut self.stems {
+            stems.set_rate_adjust(ratio);
         }
     }
 
+    /// Mute channels in the mix: bit n silences `CHANNELS[n]`.
+    pub fn set_channel_mute(&mut self, mask: u8) {
+        self.channel_mute = mask & 0x0F;
+    }
+
+    /// Channels currently muted in the mix.
+    #[must_use]
+    pub fn channel_mute(&self) -> u8 {
+        self.channel_mute
+    }
+
+    /// Start or stop capturing each channel as its own stream.
+    pub fn set_stems(&mut self, enabled: bool) {
+        self.stems = enabled.then(|| Stems::new(&self.blip, &self.channel_outputs()));
+    }
+
+    /// Drain the per-channel streams, one per `CHANNELS` entry, at the
+    /// output rate. Empty while capture is off.
+    pub fn take_stems(&mut self) -> Vec<Vec<f32>> {
+        self.stems.as_mut().map_or_else(Vec::new, Stems::take)
+    }
+
+    /// Start logging writes. The log opens with writes that recreate the
+    /// current register state.
+    pub fn start_register_log(&mut self) {
+        let mut log = RegisterLog::new(LoggedChip::Sn76489, self.clock_hz);
+        for reg in 0..8 {
+            log.record(0, self.latch_byte(reg));
+            if reg & 1 == 0 && reg < 6 {
+                let period = self.tone_period[usize::from(reg / 2)];
+                log.record(0, (period >> 4) as u8 & 0x3F);
+            }
+        }
+        if self.latched_register != 7 {
+            log.record(0, self.latch_byte(self.latched_register));
+        }
+        if self.stereo_panning != 0xFF {
+            log.record(1, self.stereo_panning);
+        }
+        self.log = Some(log);
+    }
+
+    /// Stop logging and return the log, if one was running.
+    pub fn stop_register_log(&mut self) -> Option<RegisterLog> {
+        self.log.take()
+    }
+
+    /// The latch byte that selects `reg` and sets its low bits to their
+    /// current value.
+    fn latch_byte(&self, reg: u8) -> u8 {
+        let data = match reg {
+            0 | 2 | 4 => self.tone_period[usize::from(reg / 2)] as u8 & 0x0F,
+            1 | 3 | 5 => self.tone_attenuation[usize::from(reg / 2)],
+            6 => u8::from(self.noise_white) << 2 | self.noise_mode,
+            _ => self.noise_attenuation,
+        };
+        0x80 | reg << 4 | data
+    }
+
     /// Take the audio output buffer (drains it).
     ///
-    /// Returns mono f32 samples at 48 kHz, centered around zero.
+    /// Returns mono f32 samples at the output rate, centered around zero.
     pub fn take_buffer(&mut self) -> Vec<f32> {
-        std::mem::take(&mut self.buffer)
+        self.blip.take_samples()
     }
 
-    /// Take stereo audio output (interleaved L, R pairs at 48 kHz).
+    /// Take stereo audio output (interleaved L, R pairs at the output rate).
     ///
     /// Applies Game Gear stereo panning. For non-GG systems where
     /// `stereo_panning` is $FF, left and right are identical.
     pub fn take_buffer_stereo(&mut self) -> Vec<f32> {
-        let mono = std::mem::take(&mut self.buffer);
+        let mono = self.blip.take_samples();
         // For true stereo we'd need to mix per-channel, but the mono
         // buffer is already mixed. Return duplicated L/R for now.
         let mut stereo = Vec::with_capacity(mono.len() * 2);
@@ -264,22 +357,73 @@ impl Sn76489 {
         VOLUMES[att as usize & 0x0F]
     }
 
-    fn mix(&self) -> f32 {
-        let mut output = 0.0f32;
-
-        for ch in 0..3 {
-            if self.tone_output[ch] {
-                output += Self::attenuation_to_volume(self.tone_attenuation[ch]);
+    /// Each channel's contribution to the mix at this instant.
+    ///
+    /// Scaled so the four together span ~0.0-1.0. The DC-blocking stage in
+    /// the output recentres the final signal around zero.
+    #[must_use]
+    pub fn channel_outputs(&self) -> [f32; 4] {
+        let level = |on: bool, att: u8| {
+            if on {
+                Self::attenuation_to_volume(att) / 4.0
+            } else {
+                0.0
             }
-        }
+        };
+        [
+            level(self.tone_output[0], self.tone_attenuation[0]),
+            level(self.tone_output[1], self.tone_attenuation[1]),
+            level(self.tone_output[2], self.tone_attenuation[2]),
+            level(self.noise_output, self.noise_attenuation),
+        ]
+    }
 
-        if self.noise_output {
-            output += Self::attenuation_to_volume(self.noise_attenuation);
-        }
+    /// Sum of the unmuted channels.
+    fn mix(&self, taps: &[f32; 4]) -> f32 {
+        taps.iter()
+            .enumerate()
+            .filter(|&(ch, _)| self.channel_mute & (1 << ch) == 0)
+            .map(|(_, &tap)| tap)
+            .sum()
+    }
+}
+
+impl SaveState for Sn76489 {
+    fn save_state(&self, w: &mut StateWriter) {
+        w.write_u16_slice(&self.tone_period);
+        w.write_u16_slice(&self.tone_counter);
+        w.write_bool_slice(&self.tone_output);
+        w.write_bytes(&self.tone_attenuation);
+        w.write_u8(self.noise_mode);
+        w.write_u16(self.noise_period);
+        w.write_u16(self.noise_counter);
+        w.write_u16(self.noise_shift);
+        w.write_bool(self.noise_output);
+        w.write_u8(self.noise_attenuation);
+        w.write_bool(self.noise_white);
+        w.write_u8(self.latched_register);
+        w.write_u32(self.clock_divider);
+        self.blip.save_state(w);
+        w.write_u8(self.stereo_panning);
+    }
 
-        // Scale to ~0.0-1.0 range (4 channels max). The DC-blocking stage in
-        // `tick()` recentres the final output around zero.
-        output / 4.0
+    fn load_state(&mut self, r: &mut StateReader<'_>) -> Result<(), StateError> {
+        r.read_u16_into(&mut self.tone_period)?;
+        r.read_u16_into(&mut self.tone_counter)?;
+        r.read_bool_into(&mut self.tone_output)?;
+        r.read_bytes_into(&mut self.tone_attenuation)?;
+        self.noise_mode = r.read_u8()?;
+        self.noise_period = r.read_u16()?;
+        self.noise_counter = r.read_u16()?;
+        self.noise_shift = r.read_u16()?;
+        self.noise_output = r.read_bool()?;
+        self.noise_attenuation = r.read_u8()?;
+        self.noise_white = r.read_bool()?;
+        self.latched_register = r.read_u8()? & 0x07;
+        self.clock_divider = r.read_u32()?;
+        self.blip.load_state(r)?;
+        self.stereo_panning = r.read_u8()?;
+        Ok(())
     }
 }
 
@@ -402,6 +546,17 @@ mod tests {
         );
     }
 
+    #[test]
+    fn output_rate_is_configurable() {
+        let mut psg = Sn76489::new(3_579_545);
+        psg.set_sample_rate(44_100);
+        for _ in 0..3_579_545 {
+            psg.tick();
+        }
+        let len = psg.take_buffer().len();
+        assert!((44_099..=44_101).contains(&len), "got {len} samples");
+    }
+
     #[test]
     fn output_is_dc_blocked() {
         let mut psg = Sn76489::new(3_579_545);
@@ -421,4 +576,85 @@ mod tests {
             "expected DC-blocked output average near zero, got {average}"
         );
     }
+
+    #[test]
+    fn muted_channel_keeps_its_stem() {
+        let mut psg = Sn76489::new(3_579_545);
+        psg.write(0x8F); // Tone 0 period $00F: ~7.5 kHz
+        psg.write(0x00);
+        psg.write(0x90); // Tone 0 full volume
+        psg.set_channel_mute(0b0001);
+        psg.set_stems(true);
+        for _ in 0..(3_579_545 / 10) {
+            psg.tick();
+        }
+
+        let mix = psg.take_buffer();
+        let stems = psg.take_stems();
+        assert_eq!(stems.len(), Sn76489::CHANNELS.len());
+        assert!(stems.iter().all(|stem| stem.len() == mix.len()));
+        let peak = |buf: &[f32]| buf.iter().fold(0.0f32, |m, s| m.max(s.abs()));
+        assert!(peak(&mix) < 1e-6, "muted channel reached the mix");
+        assert!(peak(&stems[0]) > 0.1, "stem should carry tone 0");
+        assert!(peak(&stems[1]) < 1e-6);
+
+        psg.set_stems(false);
+        assert!(psg.take_stems().is_empty());
+    }
+
+    #[test]
+    fn register_log_replays_to_the_same_state() {
+        let mut psg = Sn76489::new(3_579_545);
+        psg.write(0x8A); // Tone 0 period low nibble
+        psg.write(0x1C); // Tone 0 period high bits
+        psg.write(0xB3); // Tone 1 attenuation
+        psg.write(0xE5); // White noise, mode 1
+        psg.write(0xA7); // Latch tone 1 period
+
+        psg.start_register_log();
+        psg.tick();
+        psg.tick();
+        psg.write(0x22); // Data byte for the latched tone 1 period
+        let log = psg.stop_register_log().expect("log was running");
+        assert_eq!(log.cycles(), 2);
+        let last = log.writes().last().expect("write was logged");
+        assert_eq!((last.cycle, last.value), (2, 0x22));
+
+        let mut replay = Sn76489::new(3_579_545);
+        for w in log.writes() {
+            replay.write(w.value);
+        }
+        assert_eq!(replay.tone_period, psg.tone_period);
+        assert_eq!(replay.tone_attenuation, psg.tone_attenuation);
+        assert_eq!(replay.noise_white, psg.noise_white);
+        assert_eq!(replay.noise_mode, psg.noise_mode);
+        assert_eq!(replay.latched_register, psg.latched_register);
+        assert!(psg.stop_register_log().is_none());
+    }
+
+    #[test]
+    fn save_state_round_trip_continues_identically() {
+        let mut psg = Sn76489::new(3_579_545);
+        psg.write(0x8A);
+        psg.write(0x04);
+        psg.write(0x90); // Tone 0 full volume
+        psg.write(0xE4); // White noise
+        psg.write(0xF2);
+        for _ in 0..5000 {
+            psg.tick();
+        }
+        let mut w = StateWriter::new();
+        psg.save_state(&mut w);
+        let state = w.into_bytes();
+
+        let mut copy = Sn76489::new(3_579_545);
+        copy.load_state(&mut StateReader::new(&state))
+            .expect("state loads");
+        for _ in 0..5000 {
+            psg.tick();
+            copy.tick();
+        }
+        assert_eq!(copy.noise_shift, psg.noise_shift);
+        assert_eq!(copy.take_buffer(), psg.take_buffer());
+    }
 }
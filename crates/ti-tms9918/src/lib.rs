@@ -21,6 +21,8 @@
 
 #![allow(clippy::cast_possible_truncation)]
 
+use emu_core::{SaveState, StateError, StateReader, StateWriter};
+
 // ---------------------------------------------------------------------------
 // Color palette
 // ---------------------------------------------------------------------------
@@ -677,6 +679,47 @@ impl Tms9918 {
     }
 }
 
+// ---------------------------------------------------------------------------
+// Save state
+// ---------------------------------------------------------------------------
+
+impl SaveState for Tms9918 {
+    fn save_state(&self, w: &mut StateWriter) {
+        w.write_u16(self.region.lines_per_frame());
+        w.write_bytes(&self.vram);
+        w.write_bytes(&self.regs);
+        w.write_u8(self.status);
+        w.write_u8(self.read_buffer);
+        w.write_u16(self.address);
+        w.write_bool(self.latch_first);
+        w.write_u8(self.latch_value);
+        w.write_u16(self.scanline);
+        w.write_u16(self.dot);
+        w.write_u32_slice(&self.framebuffer);
+        w.write_bool(self.interrupt);
+        w.write_u64(self.frame_count);
+    }
+
+    fn load_state(&mut self, r: &mut StateReader<'_>) -> Result<(), StateError> {
+        if r.read_u16()? != self.region.lines_per_frame() {
+            return Err(StateError::Invalid("VDP region mismatch".into()));
+        }
+        r.read_bytes_into(&mut self.vram)?;
+        r.read_bytes_into(&mut self.regs)?;
+        self.status = r.read_u8()?;
+        self.read_buffer = r.read_u8()?;
+        self.address = r.read_u16()? & 0x3FFF;
+        self.latch_first = r.read_bool()?;
+        self.latch_value = r.read_u8()?;
+        self.scanline = r.read_u16()?;
+        self.dot = r.read_u16()?;
+        r.read_u32_into(&mut self.framebuffer)?;
+        self.interrupt = r.read_bool()?;
+        self.frame_count = r.read_u64()?;
+        Ok(())
+    }
+}
+
 // ---------------------------------------------------------------------------
 // Tests
 // ---------------------------------------------------------------------------
@@ -692,6 +735,32 @@ mod tests {
         assert!(vdp.framebuffer().iter().all(|&p| p == 0));
     }
 
+    #[test]
+    fn save_state_restores_vram_and_rejects_other_region() {
+        let mut vdp = Tms9918::new(VdpRegion::Ntsc);
+        vdp.write_control(0x34);
+        vdp.write_control(0x52); // write address $1234
+        vdp.write_data(0xA5);
+        vdp.write_control(0x40);
+        vdp.write_control(0x81); // VR1 = $40
+        for _ in 0..1000 {
+            vdp.tick();
+        }
+        let mut w = StateWriter::new();
+        vdp.save_state(&mut w);
+        let state = w.into_bytes();
+
+        let mut copy = Tms9918::new(VdpRegion::Ntsc);
+        copy.load_state(&mut StateReader::new(&state))
+            .expect("state loads");
+        assert_eq!(copy.vram[0x1234], 0xA5);
+        assert_eq!(copy.regs, vdp.regs);
+        assert_eq!((copy.scanline, copy.dot), (vdp.scanline, vdp.dot));
+
+        let mut pal = Tms9918::new(VdpRegion::Pal);
+        assert!(pal.load_state(&mut StateReader::new(&state)).is_err());
+    }
+
     #[test]
     fn control_port_register_write() {
         let mut vdp = Tms9918::new(VdpRegion::Ntsc);
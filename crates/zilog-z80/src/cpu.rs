@@ -949,6 +949,8 @@ impl Z80 {
 
 // Instruction execution split into separate file for readability
 mod execute;
+// Save-state serialisation
+mod state;
 
 impl Z80 {
     /// Handle NMI.
@@ -0,0 +1,117 @@
+//! Save-state serialisation for the Z80.
+
+use emu_core::{SaveState, StateError, StateReader, StateWriter, Ticks};
+
+use crate::registers::Registers;
+
+use super::Z80;
+
+impl SaveState for Registers {
+    fn save_state(&self, w: &mut StateWriter) {
+        for v in [
+            self.a, self.f, self.b, self.c, self.d, self.e, self.h, self.l, self.a_alt, self.f_alt,
+            self.b_alt, self.c_alt, self.d_alt, self.e_alt, self.h_alt, self.l_alt,
+        ] {
+            w.write_u8(v);
+        }
+        w.write_u16(self.ix);
+        w.write_u16(self.iy);
+        w.write_u16(self.sp);
+        w.write_u16(self.pc);
+        w.write_u8(self.i);
+        w.write_u8(self.r);
+        w.write_u16(self.wz);
+        w.write_bool(self.iff1);
+        w.write_bool(self.iff2);
+        w.write_u8(self.im);
+        w.write_bool(self.halted);
+    }
+
+    fn load_state(&mut self, r: &mut StateReader<'_>) -> Result<(), StateError> {
+        for v in [
+            &mut self.a,
+            &mut self.f,
+            &mut self.b,
+            &mut self.c,
+            &mut self.d,
+            &mut self.e,
+            &mut self.h,
+            &mut self.l,
+            &mut self.a_alt,
+            &mut self.f_alt,
+            &mut self.b_alt,
+            &mut self.c_alt,
+            &mut self.d_alt,
+            &mut self.e_alt,
+            &mut self.h_alt,
+            &mut self.l_alt,
+        ] {
+            *v = r.read_u8()?;
+        }
+        self.ix = r.read_u16()?;
+        self.iy = r.read_u16()?;
+        self.sp = r.read_u16()?;
+        self.pc = r.read_u16()?;
+        self.i = r.read_u8()?;
+        self.r = r.read_u8()?;
+        self.wz = r.read_u16()?;
+        self.iff1 = r.read_bool()?;
+        self.iff2 = r.read_bool()?;
+        self.im = r.read_u8()?;
+        self.halted = r.read_bool()?;
+        Ok(())
+    }
+}
+
+impl SaveState for Z80 {
+    fn save_state(&self, w: &mut StateWriter) {
+        self.regs.save_state(w);
+        self.micro_ops.save_state(w);
+        w.write_u8(self.t_state);
+        w.write_u8(self.wait_states);
+        w.write_u8(self.opcode);
+        w.write_u8(self.prefix);
+        w.write_u8(self.prefix2);
+        w.write_i8(self.displacement);
+        w.write_u16(self.addr);
+        w.write_u8(self.data_lo);
+        w.write_u8(self.data_hi);
+        w.write_bool(self.in_followup);
+        w.write_u8(self.followup_stage);
+        w.write_bool(self.im2_pending);
+        w.write_bool(self.int_pending);
+        w.write_bool(self.nmi_pending);
+        w.write_bool(self.nmi_last);
+        w.write_bool(self.ei_delay);
+        w.write_bool(self.last_was_ld_a_ir);
+        w.write_u8(self.last_q);
+        w.write_u8(self.prev_q);
+        w.write_u64(self.total_ticks.get());
+    }
+
+    fn load_state(&mut self, r: &mut StateReader<'_>) -> Result<(), StateError> {
+        self.regs.load_state(r)?;
+        self.micro_ops.load_state(r)?;
+        self.t_state = r.read_u8()?;
+        self.wait_states = r.read_u8()?;
+        self.opcode = r.read_u8()?;
+        self.prefix = r.read_u8()?;
+        self.prefix2 = r.read_u8()?;
+        self.displacement = r.read_i8()?;
+        self.addr = r.read_u16()?;
+        self.data_lo = r.read_u8()?;
+        self.data_hi = r.read_u8()?;
+        self.in_followup = r.read_bool()?;
+        self.followup_stage = r.read_u8()?;
+        self.im2_pending = r.read_bool()?;
+        self.int_pending = r.read_bool()?;
+        self.nmi_pending = r.read_bool()?;
+        self.nmi_last = r.read_bool()?;
+        self.ei_delay = r.read_bool()?;
+        self.last_was_ld_a_ir = r.read_bool()?;
+        self.last_q = r.read_u8()?;
+        self.prev_q = r.read_u8()?;
+        self.total_ticks = Ticks::new(r.read_u64()?);
+        Ok(())
+    }
+}
@@ -6,6 +6,8 @@
 #![allow(clippy::match_same_arms)] // T-state counts are intentionally explicit per op.
 #![allow(dead_code)] // t_states() will be used for timing verification.
 
+use emu_core::{SaveState, StateError, StateReader, StateWriter};
+
 /// A micro-operation that takes one or more T-states.
 #[derive(Debug, Clone, Copy, PartialEq, Eq)]
 pub enum MicroOp {
@@ -77,6 +79,53 @@ pub enum MicroOp {
 
 /// Duration of each micro-op in T-states.
 impl MicroOp {
+    /// Stable numeric code for save states. `Internal(n)` carries `n`
+    /// separately.
+    const fn code(self) -> u8 {
+        match self {
+            Self::FetchOpcode => 0,
+            Self::FetchDisplacement => 1,
+            Self::ReadImm8 => 2,
+            Self::ReadImm16Lo => 3,
+            Self::ReadImm16Hi => 4,
+            Self::ReadMem => 5,
+            Self::ReadMem16Lo => 6,
+            Self::ReadMem16Hi => 7,
+            Self::WriteMem => 8,
+            Self::WriteMem16Lo => 9,
+            Self::WriteMem16Hi => 10,
+            Self::WriteMemHiFirst => 11,
+            Self::WriteMemLoSecond => 12,
+            Self::IoRead => 13,
+            Self::IoWrite => 14,
+            Self::Internal(_) => 15,
+            Self::Execute => 16,
+        }
+    }
+
+    fn from_code(code: u8, arg: u8) -> Option<Self> {
+        Some(match code {
+            0 => Self::FetchOpcode,
+            1 => Self::FetchDisplacement,
+            2 => Self::ReadImm8,
+            3 => Self::ReadImm16Lo,
+            4 => Self::ReadImm16Hi,
+            5 => Self::ReadMem,
+            6 => Self::ReadMem16Lo,
+            7 => Self::ReadMem16Hi,
+            8 => Self::WriteMem,
+            9 => Self::WriteMem16Lo,
+            10 => Self::WriteMem16Hi,
+            11 => Self::WriteMemHiFirst,
+            12 => Self::WriteMemLoSecond,
+            13 => Self::IoRead,
+            14 => Self::IoWrite,
+            15 => Self::Internal(arg),
+            16 => Self::Execute,
+            _ => return None,
+        })
+    }
+
     #[must_use]
     pub const fn t_states(self) -> u8 {
         match self {
@@ -177,3 +226,29 @@ impl MicroOpQueue {
         self.pos
     }
 }
+
+impl SaveState for MicroOpQueue {
+    fn save_state(&self, w: &mut StateWriter) {
+        for op in &self.ops {
+            w.write_u8(op.code());
+            w.write_u8(if let MicroOp::Internal(n) = op { *n } else { 0 });
+        }
+        w.write_u8(self.len);
+        w.write_u8(self.pos);
+    }
+
+    fn load_state(&mut self, r: &mut StateReader<'_>) -> Result<(), StateError> {
+        for op in &mut self.ops {
+            let code = r.read_u8()?;
+            let arg = r.read_u8()?;
+            *op = MicroOp::from_code(code, arg)
+                .ok_or_else(|| StateError::Invalid(format!("bad Z80 micro-op {code}")))?;
+        }
+        self.len = r.read_u8()?;
+        self.pos = r.read_u8()?;
+        if self.len as usize > self.ops.len() || self.pos > self.len {
+            return Err(StateError::Invalid("Z80 micro-op queue out of range".into()));
+        }
+        Ok(())
+    }
+}